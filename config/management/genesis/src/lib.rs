@@ -10,6 +10,7 @@ mod genesis;
 mod key;
 pub mod layout;
 mod move_modules;
+pub mod network_genesis;
 pub mod validator_builder;
 mod validator_config;
 mod validator_operator;
@@ -0,0 +1,270 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Genesis of a custom network, built programmatically from the public keys and addresses of its
+//! validators, e.g. by tests or deployment automation. Unlike `GenesisBuilder`, which reads what
+//! the operators uploaded to a shared storage during a genesis ceremony, nothing is read from or
+//! written to a storage.
+
+use crate::waypoint::create_genesis_waypoint;
+use anyhow::{ensure, Result};
+use diem_config::config::HANDSHAKE_VERSION;
+use diem_crypto::{ed25519::Ed25519PublicKey, x25519};
+use diem_types::{
+    chain_id::ChainId,
+    network_address::{
+        encrypted::{Key as NetworkAddressEncryptionKey, KeyVersion},
+        NetworkAddress,
+    },
+    on_chain_config::VMPublishingOption,
+    transaction::{authenticator::AuthenticationKey, Transaction},
+    waypoint::Waypoint,
+};
+use std::{collections::HashSet, fs, path::Path};
+use vm_genesis::Validator;
+
+/// A validator of the network, as registered by its owner and operator at genesis.
+#[derive(Clone, Debug)]
+pub struct GenesisValidator {
+    /// Name of the owner, from which the address of its account is derived
+    pub owner_name: String,
+    /// The account of the owner can't sign transactions without a key
+    pub owner_key: Option<Ed25519PublicKey>,
+    pub operator_name: String,
+    pub operator_key: Ed25519PublicKey,
+    pub consensus_key: Ed25519PublicKey,
+    pub validator_network_key: x25519::PublicKey,
+    /// Base address of the validator network, e.g. `/dns/validator0/tcp/6180`
+    pub validator_address: NetworkAddress,
+    pub full_node_network_key: x25519::PublicKey,
+    /// Base address of the public full node network
+    pub full_node_address: NetworkAddress,
+}
+
+impl GenesisValidator {
+    fn encode(
+        &self,
+        encryption_key: &NetworkAddressEncryptionKey,
+        encryption_key_version: KeyVersion,
+    ) -> Result<Validator> {
+        let address =
+            diem_config::utils::validator_owner_account_from_name(self.owner_name.as_bytes());
+        let operator_auth_key = AuthenticationKey::ed25519(&self.operator_key);
+
+        // The addresses are registered as by `build_validator_config_transaction`: with the
+        // production protocols, and the validator address encrypted for the first transaction of
+        // the owner
+        let validator_address = self
+            .validator_address
+            .clone()
+            .append_prod_protos(self.validator_network_key, HANDSHAKE_VERSION)
+            .encrypt(encryption_key, encryption_key_version, &address, 0, 0)?;
+        let full_node_address = self
+            .full_node_address
+            .clone()
+            .append_prod_protos(self.full_node_network_key, HANDSHAKE_VERSION);
+
+        Ok(Validator {
+            address,
+            name: self.owner_name.as_bytes().to_vec(),
+            auth_key: self
+                .owner_key
+                .as_ref()
+                .map_or(AuthenticationKey::zero(), AuthenticationKey::ed25519),
+            consensus_pubkey: self.consensus_key.to_bytes().to_vec(),
+            operator_address: operator_auth_key.derived_address(),
+            operator_name: self.operator_name.as_bytes().to_vec(),
+            operator_auth_key,
+            network_address: bcs::to_bytes(&vec![validator_address])?,
+            full_node_network_address: bcs::to_bytes(&vec![full_node_address])?,
+        })
+    }
+}
+
+/// The genesis of a network, and the waypoint the nodes start from.
+#[derive(Clone, Debug)]
+pub struct NetworkGenesis {
+    pub genesis: Transaction,
+    pub waypoint: Waypoint,
+}
+
+impl NetworkGenesis {
+    /// The genesis transaction as read from `execution.genesis_file_location` by the nodes.
+    pub fn genesis_blob(&self) -> Result<Vec<u8>> {
+        bcs::to_bytes(&self.genesis).map_err(Into::into)
+    }
+
+    /// Writes the genesis blob to `genesis.blob` and the waypoint to `waypoint.txt` in `directory`.
+    pub fn save<P: AsRef<Path>>(&self, directory: P) -> Result<()> {
+        let directory = directory.as_ref();
+        fs::create_dir_all(directory)?;
+        fs::write(directory.join("genesis.blob"), self.genesis_blob()?)?;
+        fs::write(directory.join("waypoint.txt"), self.waypoint.to_string())?;
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct NetworkGenesisBuilder {
+    chain_id: ChainId,
+    /// Bytecodes of Move genesis modules
+    move_modules: Vec<Vec<u8>>,
+    root_key: Ed25519PublicKey,
+    treasury_compliance_key: Ed25519PublicKey,
+    publishing_option: Option<VMPublishingOption>,
+    encryption_key: Option<(KeyVersion, NetworkAddressEncryptionKey)>,
+    validators: Vec<GenesisValidator>,
+}
+
+impl NetworkGenesisBuilder {
+    pub fn new(
+        chain_id: ChainId,
+        move_modules: Vec<Vec<u8>>,
+        root_key: Ed25519PublicKey,
+        treasury_compliance_key: Ed25519PublicKey,
+    ) -> Self {
+        Self {
+            chain_id,
+            move_modules,
+            root_key,
+            treasury_compliance_key,
+            publishing_option: None,
+            encryption_key: None,
+            validators: vec![],
+        }
+    }
+
+    pub fn publishing_option(mut self, publishing_option: VMPublishingOption) -> Self {
+        self.publishing_option = Some(publishing_option);
+        self
+    }
+
+    /// The key the validator addresses are encrypted with, which the validators must have in
+    /// their storage to decrypt the addresses of their peers.
+    pub fn network_address_encryption_key(
+        mut self,
+        version: KeyVersion,
+        key: NetworkAddressEncryptionKey,
+    ) -> Self {
+        self.encryption_key = Some((version, key));
+        self
+    }
+
+    pub fn validator(mut self, validator: GenesisValidator) -> Self {
+        self.validators.push(validator);
+        self
+    }
+
+    pub fn validators(mut self, validators: impl IntoIterator<Item = GenesisValidator>) -> Self {
+        self.validators.extend(validators);
+        self
+    }
+
+    /// Builds the genesis transaction, and executes it in a temporary database to compute the
+    /// waypoint.
+    pub fn build(self) -> Result<NetworkGenesis> {
+        ensure!(!self.validators.is_empty(), "Genesis needs a validator");
+        ensure!(!self.move_modules.is_empty(), "Genesis needs Move modules");
+        let (encryption_key_version, encryption_key) = self
+            .encryption_key
+            .ok_or_else(|| anyhow::anyhow!("Missing the network address encryption key"))?;
+
+        let mut owners = HashSet::new();
+        let mut operators = HashSet::new();
+        for validator in &self.validators {
+            ensure!(
+                owners.insert(&validator.owner_name),
+                "Duplicate validator owner {}",
+                validator.owner_name
+            );
+            ensure!(
+                operators.insert(&validator.operator_name),
+                "Duplicate validator operator {}",
+                validator.operator_name
+            );
+        }
+        let validators = self
+            .validators
+            .iter()
+            .map(|validator| validator.encode(&encryption_key, encryption_key_version))
+            .collect::<Result<Vec<_>>>()?;
+
+        let genesis = vm_genesis::encode_genesis_transaction(
+            self.root_key,
+            self.treasury_compliance_key,
+            &validators,
+            &self.move_modules,
+            self.publishing_option,
+            self.chain_id,
+        );
+        let waypoint = create_genesis_waypoint(&genesis)?;
+        Ok(NetworkGenesis { genesis, waypoint })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::str::FromStr;
+
+    fn validator(rng: &mut StdRng, name: &str) -> GenesisValidator {
+        GenesisValidator {
+            owner_name: format!("{}_owner", name),
+            owner_key: Some(Ed25519PrivateKey::generate(rng).public_key()),
+            operator_name: format!("{}_operator", name),
+            operator_key: Ed25519PrivateKey::generate(rng).public_key(),
+            consensus_key: Ed25519PrivateKey::generate(rng).public_key(),
+            validator_network_key: x25519::PrivateKey::generate(rng).public_key(),
+            validator_address: NetworkAddress::from_str("/dns/validator/tcp/6180").unwrap(),
+            full_node_network_key: x25519::PrivateKey::generate(rng).public_key(),
+            full_node_address: NetworkAddress::from_str("/dns/fullnode/tcp/6182").unwrap(),
+        }
+    }
+
+    fn builder(rng: &mut StdRng) -> NetworkGenesisBuilder {
+        let root_key = Ed25519PrivateKey::generate(rng).public_key();
+        NetworkGenesisBuilder::new(
+            ChainId::test(),
+            diem_framework_releases::current_module_blobs().to_vec(),
+            root_key.clone(),
+            root_key,
+        )
+        .network_address_encryption_key(0, NetworkAddressEncryptionKey::default())
+    }
+
+    #[test]
+    fn test_build() {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let validators = vec![validator(&mut rng, "a"), validator(&mut rng, "b")];
+        let network_genesis = builder(&mut rng).validators(validators).build().unwrap();
+        assert_eq!(
+            network_genesis.waypoint,
+            create_genesis_waypoint(&network_genesis.genesis).unwrap()
+        );
+
+        let directory = diem_temppath::TempPath::new();
+        network_genesis.save(directory.path()).unwrap();
+        let genesis: Transaction =
+            bcs::from_bytes(&fs::read(directory.path().join("genesis.blob")).unwrap()).unwrap();
+        assert_eq!(genesis, network_genesis.genesis);
+        assert_eq!(
+            fs::read_to_string(directory.path().join("waypoint.txt")).unwrap(),
+            network_genesis.waypoint.to_string()
+        );
+    }
+
+    #[test]
+    fn test_invalid_network() {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        builder(&mut rng).build().unwrap_err();
+
+        let validator = validator(&mut rng, "a");
+        builder(&mut rng)
+            .validator(validator.clone())
+            .validator(validator)
+            .build()
+            .unwrap_err();
+    }
+}
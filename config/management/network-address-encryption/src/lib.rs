@@ -284,6 +284,7 @@ mod tests {
                     None,
                     None,
                     true,
+                    false,
                     None,
                     None,
                 ),
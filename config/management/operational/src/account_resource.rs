@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{auto_validate::AutoValidate, json_rpc::JsonRpcClientWrapper, TransactionContext};
+use diem_client::views::VMStatusView;
 use diem_crypto::ed25519::Ed25519PublicKey;
-use diem_global_constants::{OPERATOR_ACCOUNT, OPERATOR_KEY};
+use diem_global_constants::{OPERATOR_ACCOUNT, OPERATOR_KEY, OWNER_ACCOUNT, OWNER_KEY};
 use diem_management::{error::Error, transaction::build_raw_transaction};
 use diem_transaction_builder::stdlib as transaction_builder;
 use diem_types::{
@@ -52,7 +53,7 @@ pub struct SimplifiedAccountResource {
 }
 
 #[derive(Debug, StructOpt)]
-pub struct RotateOperatorKey {
+pub struct RotateAccountKey {
     /// JSON-RPC Endpoint (e.g. http://localhost:8080)
     #[structopt(long, required_unless = "config")]
     json_server: Option<String>,
@@ -62,8 +63,13 @@ pub struct RotateOperatorKey {
     auto_validate: AutoValidate,
 }
 
-impl RotateOperatorKey {
-    pub fn execute(self) -> Result<(TransactionContext, Ed25519PublicKey), Error> {
+impl RotateAccountKey {
+    pub fn execute(
+        self,
+        account_name: &'static str,
+        key_name: &'static str,
+        script_name: &'static str,
+    ) -> Result<KeyRotationResult, Error> {
         // Load the config, storage backend and create a json rpc client
         let config = self
             .validator_config
@@ -72,54 +78,43 @@ impl RotateOperatorKey {
         let mut storage = config.validator_backend();
         let client = JsonRpcClientWrapper::new(config.json_server.clone());
 
-        // Fetch the current on-chain auth key for the operator and the current key held in storage.
-        let operator_account = storage.account_address(OPERATOR_ACCOUNT)?;
-        let account_resource = client.account_resource(operator_account)?;
-        let on_chain_key = match AuthenticationKey::try_from(account_resource.authentication_key())
-        {
-            Ok(auth_key) => auth_key,
-            Err(e) => {
-                return Err(Error::UnexpectedError(format!(
-                    "Invalid authentication key found in account resource. Error: {}",
-                    e
-                )));
-            }
-        };
-        let mut current_storage_key = storage.ed25519_public_from_private(OPERATOR_KEY)?;
+        // Fetch the current on-chain auth key for the account and the current key held in storage.
+        let account = storage.account_address(account_name)?;
+        let on_chain_key = on_chain_authentication_key(&client, account)?;
+        let mut current_storage_key = storage.ed25519_public_from_private(key_name)?;
 
-        // Check that the key held in storage matches the key registered on-chain in the validator
-        // config. If so, rotate the key in storage. If not, fetch the previous key version from
-        // storage so that we can allow the next step to resubmit the key rotation transaction
-        // (to resynchronize storage with the blockchain).
+        // Check that the key held in storage matches the key registered on-chain for the account.
+        // If so, rotate the key in storage. If not, fetch the previous key version from storage so
+        // that we can allow the next step to resubmit the key rotation transaction (to
+        // resynchronize storage with the blockchain).
         let new_storage_key = if on_chain_key == AuthenticationKey::ed25519(&current_storage_key) {
-            storage.rotate_key(OPERATOR_KEY)?
+            storage.rotate_key(key_name)?
         } else {
             let new_storage_key = current_storage_key;
-            current_storage_key =
-                storage.ed25519_public_from_private_previous_version(OPERATOR_KEY)?;
+            current_storage_key = storage.ed25519_public_from_private_previous_version(key_name)?;
             new_storage_key
         };
 
         // Fetch the current sequence number
-        let sequence_number = client.sequence_number(operator_account)?;
+        let sequence_number = client.sequence_number(account)?;
 
-        // Build the operator rotation transaction
+        // Build the key rotation transaction
         let rotate_key_script =
             transaction_builder::encode_rotate_authentication_key_script_function(
                 AuthenticationKey::ed25519(&new_storage_key).to_vec(),
             );
         let rotate_key_txn = build_raw_transaction(
             config.chain_id,
-            operator_account,
+            account,
             sequence_number,
             rotate_key_script.into_script_function(),
         );
 
-        // Sign the operator rotation transaction
+        // Sign the key rotation transaction
         let rotate_key_txn = storage.sign_using_version(
-            OPERATOR_KEY,
+            key_name,
             current_storage_key,
-            "rotate-operator-key",
+            script_name,
             rotate_key_txn,
         )?;
         let rotate_key_txn = Transaction::UserTransaction(rotate_key_txn);
@@ -133,6 +128,84 @@ impl RotateOperatorKey {
             .auto_validate
             .execute(config.json_server, transaction_context)?;
 
-        Ok((transaction_context, new_storage_key))
+        // Once the transaction is executed, check that the rotation took effect on-chain and that
+        // the validator config still delegates to the operator held in storage.
+        let verification = if transaction_context.execution_result == Some(VMStatusView::Executed) {
+            let owner_account = storage.account_address(OWNER_ACCOUNT)?;
+            let operator_account = storage.account_address(OPERATOR_ACCOUNT)?;
+            let validator_config = client.validator_config(owner_account)?;
+            Some(KeyRotationVerification {
+                authentication_key_matches: on_chain_authentication_key(&client, account)?
+                    == AuthenticationKey::ed25519(&new_storage_key),
+                validator_config_matches: validator_config.delegated_account
+                    == Some(operator_account),
+            })
+        } else {
+            None
+        };
+
+        Ok(KeyRotationResult {
+            account,
+            new_key: new_storage_key,
+            transaction_context,
+            verification,
+        })
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct RotateOperatorKey {
+    #[structopt(flatten)]
+    rotate_key: RotateAccountKey,
+}
+
+impl RotateOperatorKey {
+    pub fn execute(self) -> Result<KeyRotationResult, Error> {
+        self.rotate_key
+            .execute(OPERATOR_ACCOUNT, OPERATOR_KEY, "rotate-operator-key")
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct RotateOwnerKey {
+    #[structopt(flatten)]
+    rotate_key: RotateAccountKey,
+}
+
+impl RotateOwnerKey {
+    pub fn execute(self) -> Result<KeyRotationResult, Error> {
+        self.rotate_key
+            .execute(OWNER_ACCOUNT, OWNER_KEY, "rotate-owner-key")
     }
 }
+
+/// The outcome of an account key rotation, displayed as JSON.
+#[derive(Debug, Serialize)]
+pub struct KeyRotationResult {
+    pub account: AccountAddress,
+    pub new_key: Ed25519PublicKey,
+    pub transaction_context: TransactionContext,
+    // The on-chain checks, only performed once the rotation transaction has been executed
+    pub verification: Option<KeyRotationVerification>,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct KeyRotationVerification {
+    // Whether the on-chain authentication key of the account is derived from the new key
+    pub authentication_key_matches: bool,
+    // Whether the owner's validator config delegates to the operator account held in storage
+    pub validator_config_matches: bool,
+}
+
+fn on_chain_authentication_key(
+    client: &JsonRpcClientWrapper,
+    account: AccountAddress,
+) -> Result<AuthenticationKey, Error> {
+    let account_resource = client.account_resource(account)?;
+    AuthenticationKey::try_from(account_resource.authentication_key()).map_err(|e| {
+        Error::UnexpectedError(format!(
+            "Invalid authentication key found in account resource. Error: {}",
+            e
+        ))
+    })
+}
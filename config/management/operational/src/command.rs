@@ -2,8 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    account_resource::SimplifiedAccountResource, validator_config::DecryptedValidatorConfig,
-    validator_set::DecryptedValidatorInfo, TransactionContext,
+    account_resource::{KeyRotationResult, SimplifiedAccountResource},
+    validator_config::DecryptedValidatorConfig,
+    validator_set::DecryptedValidatorInfo,
+    TransactionContext,
 };
 use diem_config::config::Peer;
 use diem_crypto::{ed25519::Ed25519PublicKey, x25519};
@@ -60,6 +62,8 @@ pub enum Command {
     RotateFullNodeNetworkKey(crate::validator_config::RotateFullNodeNetworkKey),
     #[structopt(about = "Rotates the operator key for the operator")]
     RotateOperatorKey(crate::account_resource::RotateOperatorKey),
+    #[structopt(about = "Rotates the owner key for the owner")]
+    RotateOwnerKey(crate::account_resource::RotateOwnerKey),
     #[structopt(about = "Rotates a validator network key")]
     RotateValidatorNetworkKey(crate::validator_config::RotateValidatorNetworkKey),
     #[structopt(about = "Sets the validator config")]
@@ -96,6 +100,7 @@ pub enum CommandName {
     RemoveValidator,
     RotateConsensusKey,
     RotateOperatorKey,
+    RotateOwnerKey,
     RotateFullNodeNetworkKey,
     RotateValidatorNetworkKey,
     SetValidatorConfig,
@@ -128,6 +133,7 @@ impl From<&Command> for CommandName {
             Command::RemoveValidator(_) => CommandName::RemoveValidator,
             Command::RotateConsensusKey(_) => CommandName::RotateConsensusKey,
             Command::RotateOperatorKey(_) => CommandName::RotateOperatorKey,
+            Command::RotateOwnerKey(_) => CommandName::RotateOwnerKey,
             Command::RotateFullNodeNetworkKey(_) => CommandName::RotateFullNodeNetworkKey,
             Command::RotateValidatorNetworkKey(_) => CommandName::RotateValidatorNetworkKey,
             Command::SetValidatorConfig(_) => CommandName::SetValidatorConfig,
@@ -162,6 +168,7 @@ impl std::fmt::Display for CommandName {
             CommandName::RemoveValidator => "remove-validator",
             CommandName::RotateConsensusKey => "rotate-consensus-key",
             CommandName::RotateOperatorKey => "rotate-operator-key",
+            CommandName::RotateOwnerKey => "rotate-owner-key",
             CommandName::RotateFullNodeNetworkKey => "rotate-full-node-network-key",
             CommandName::RotateValidatorNetworkKey => "rotate-validator-network-key",
             CommandName::SetValidatorConfig => "set-validator-config",
@@ -202,9 +209,8 @@ impl Command {
             Command::RotateConsensusKey(cmd) => {
                 Self::print_transaction_context(cmd.execute().map(|(txn_ctx, _)| txn_ctx))
             }
-            Command::RotateOperatorKey(cmd) => {
-                Self::print_transaction_context(cmd.execute().map(|(txn_ctx, _)| txn_ctx))
-            }
+            Command::RotateOperatorKey(cmd) => Self::pretty_print(cmd.execute()),
+            Command::RotateOwnerKey(cmd) => Self::pretty_print(cmd.execute()),
             Command::RotateFullNodeNetworkKey(cmd) => {
                 Self::print_transaction_context(cmd.execute().map(|(txn_ctx, _)| txn_ctx))
             }
@@ -361,7 +367,7 @@ impl Command {
         )
     }
 
-    pub fn rotate_operator_key(self) -> Result<(TransactionContext, Ed25519PublicKey), Error> {
+    pub fn rotate_operator_key(self) -> Result<KeyRotationResult, Error> {
         execute_command!(
             self,
             Command::RotateOperatorKey,
@@ -369,6 +375,10 @@ impl Command {
         )
     }
 
+    pub fn rotate_owner_key(self) -> Result<KeyRotationResult, Error> {
+        execute_command!(self, Command::RotateOwnerKey, CommandName::RotateOwnerKey)
+    }
+
     pub fn rotate_fullnode_network_key(
         self,
     ) -> Result<(TransactionContext, x25519::PublicKey), Error> {
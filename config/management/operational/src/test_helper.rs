@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    account_resource::SimplifiedAccountResource,
+    account_resource::{KeyRotationResult, SimplifiedAccountResource},
     command::{Command, CommandName},
     keys::{load_key, EncodingType, KeyType},
     validator_config::DecryptedValidatorConfig,
@@ -484,7 +484,23 @@ impl OperationalTool {
             backend,
             disable_validate,
             CommandName::RotateOperatorKey,
-            |cmd| cmd.rotate_operator_key(),
+            |cmd| {
+                cmd.rotate_operator_key()
+                    .map(|result| (result.transaction_context, result.new_key))
+            },
+        )
+    }
+
+    pub fn rotate_owner_key(
+        &self,
+        backend: &config::SecureBackend,
+        disable_validate: bool,
+    ) -> Result<KeyRotationResult, Error> {
+        self.rotate_key(
+            backend,
+            disable_validate,
+            CommandName::RotateOwnerKey,
+            |cmd| cmd.rotate_owner_key(),
         )
     }
 
@@ -514,7 +530,9 @@ impl OperationalTool {
             validate_timeout = optional_arg("validate-timeout", validate_timeout),
         );
         let command = Command::from_iter(args.split_whitespace());
-        command.rotate_operator_key()
+        command
+            .rotate_operator_key()
+            .map(|result| (result.transaction_context, result.new_key))
     }
 
     pub fn rotate_validator_network_key(
@@ -235,7 +235,7 @@ impl RotateFullNodeNetworkKey {
     }
 }
 
-/// Returns only the IP/DNS + Port portion of the NetworkAddress
+/// Returns only the IP/DNS + Port (+ QUIC) portion of the NetworkAddress
 pub fn strip_address(address: &NetworkAddress) -> NetworkAddress {
     let protocols = address
         .as_slice()
@@ -250,6 +250,8 @@ pub fn strip_address(address: &NetworkAddress) -> NetworkAddress {
                     | Protocol::Ip6(_)
                     | Protocol::Memory(_)
                     | Protocol::Tcp(_)
+                    | Protocol::Udp(_)
+                    | Protocol::Quic
             )
         })
         .cloned()
@@ -178,6 +178,7 @@ mod tests {
                 token: Token::FromConfig("test".to_string()),
                 renew_ttl_secs: None,
                 disable_cas: None,
+                disable_key_export: None,
                 connection_timeout_ms: None,
                 response_timeout_ms: None,
             }),
@@ -188,6 +189,7 @@ mod tests {
                 token: Token::FromConfig("test".to_string()),
                 renew_ttl_secs: None,
                 disable_cas: None,
+                disable_key_export: None,
                 connection_timeout_ms: None,
                 response_timeout_ms: None,
             }),
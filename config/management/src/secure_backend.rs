@@ -120,6 +120,7 @@ impl TryInto<config::SecureBackend> for SecureBackend {
                     token: Token::FromDisk(PathBuf::from(token)),
                     renew_ttl_secs: None,
                     disable_cas: Some(true),
+                    disable_key_export: None,
                     connection_timeout_ms: Some(CONNECTION_TIMEOUT_MS),
                     response_timeout_ms: Some(RESPONSE_TIMEOUT_MS),
                 })
@@ -0,0 +1,34 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::utils;
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, SocketAddr};
+
+/// Local HTTP service of the node for operators: config dump, log filters, consensus state,
+/// peers and mempool stats.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminServiceConfig {
+    pub enabled: bool,
+    pub address: SocketAddr,
+    // Every request must carry it in an `Authorization: Bearer <token>` header, the service
+    // doesn't start without it
+    pub auth_token: Option<String>,
+}
+
+impl Default for AdminServiceConfig {
+    fn default() -> AdminServiceConfig {
+        AdminServiceConfig {
+            enabled: false,
+            address: SocketAddr::from((Ipv4Addr::LOCALHOST, 9202)),
+            auth_token: None,
+        }
+    }
+}
+
+impl AdminServiceConfig {
+    pub fn randomize_ports(&mut self) {
+        self.address.set_port(utils::get_available_port());
+    }
+}
@@ -11,13 +11,30 @@ use std::{collections::HashMap, path::PathBuf};
 pub struct ConsensusConfig {
     pub contiguous_rounds: u32,
     pub max_block_size: u64,
+    // Max number of bytes of the transactions of a proposed block
+    pub max_block_bytes: u64,
+    // Max sum of the max gas amounts of the transactions of a proposed block, so that blocks
+    // remain executable within a round on slower hardware
+    pub max_block_gas: u64,
+    // Number of uncommitted blocks above which proposals shrink because the execution pipeline
+    // lags behind: the max block size halves for every additional block, down to empty blocks
+    pub proposal_back_pressure_limit: u64,
     pub max_pruned_blocks_in_mem: usize,
+    // Blocks and quorum certs more than this many rounds below the latest commit (or from previous
+    // epochs) are pruned from ConsensusDB, nothing is pruned beyond the block tree if not set
+    pub consensusdb_prune_window: Option<u64>,
     // Timeout for consensus to get an ack from mempool for executed transactions (in milliseconds)
     pub mempool_executed_txn_timeout_ms: u64,
     // Timeout for consensus to pull transactions from mempool and get a response (in milliseconds)
     pub mempool_txn_pull_timeout_ms: u64,
     pub round_initial_timeout_ms: u64,
+    pub round_timeout_backoff: RoundTimeoutBackoffConfig,
     pub proposer_type: ConsensusProposerType,
+    // Allows the experimental VRF proposer election as proposer_type: its proposers depend on the
+    // proposals each validator has seen, so the validators may disagree on them until a timeout
+    pub experimental_vrf_proposer: bool,
+    // Validators the votes for proposals are sent to, any of them can aggregate the QC
+    pub vote_recipients: VoteRecipientsType,
     pub safety_rules: SafetyRulesConfig,
     // Only sync committed transactions but not vote for any pending blocks. This is useful when
     // validators coordinate on the latest version to apply a manual transaction.
@@ -29,6 +46,22 @@ pub struct ConsensusConfig {
     pub decoupled_execution: bool,
     pub channel_size: usize,
     pub back_pressure_limit: u64,
+    pub observer: ConsensusObserverConfig,
+    // Compression of the messages sent to the peers that support it, mostly useful to reduce the
+    // bandwidth of proposals carrying large transaction payloads
+    pub payload_compression: PayloadCompressionType,
+    pub block_retrieval_stream: BlockRetrievalStreamConfig,
+    // Number of messages from the next epoch kept during reconfiguration, they are replayed once
+    // the new epoch starts instead of being dropped
+    pub max_buffered_next_epoch_messages: usize,
+    // Number of consensus messages whose signatures are verified concurrently, off the main
+    // event loop
+    pub num_verification_workers: usize,
+    pub reliable_broadcast: ReliableBroadcastConfig,
+    // The proposer of the next round proposes as soon as it aggregates the QC of the current round,
+    // before inserting the QC (which persists it and commits blocks)
+    pub optimistic_proposal: bool,
+    pub commit_notification_bus: CommitNotificationBusConfig,
 }
 
 impl Default for ConsensusConfig {
@@ -36,20 +69,34 @@ impl Default for ConsensusConfig {
         ConsensusConfig {
             contiguous_rounds: 2,
             max_block_size: 1000,
+            max_block_bytes: 5 * 1024 * 1024, // 5MB
+            max_block_gas: 1_000_000_000,
+            proposal_back_pressure_limit: 10,
             max_pruned_blocks_in_mem: 100,
+            consensusdb_prune_window: Some(1000),
             mempool_txn_pull_timeout_ms: 1000,
             mempool_executed_txn_timeout_ms: 1000,
             round_initial_timeout_ms: 1000,
-            proposer_type: ConsensusProposerType::LeaderReputation(LeaderReputationConfig {
-                active_weights: 99,
-                inactive_weights: 1,
-            }),
+            round_timeout_backoff: RoundTimeoutBackoffConfig::default(),
+            proposer_type: ConsensusProposerType::LeaderReputation(
+                LeaderReputationConfig::default(),
+            ),
+            experimental_vrf_proposer: false,
+            vote_recipients: VoteRecipientsType::NextProposer,
             safety_rules: SafetyRulesConfig::default(),
             sync_only: false,
             mempool_poll_count: 1,
             decoupled_execution: false, // by default, we turn of the decoupling execution feature
             channel_size: 30,           // hard-coded
             back_pressure_limit: 1,
+            observer: ConsensusObserverConfig::default(),
+            payload_compression: PayloadCompressionType::Disabled,
+            block_retrieval_stream: BlockRetrievalStreamConfig::default(),
+            max_buffered_next_epoch_messages: 100,
+            num_verification_workers: 16,
+            reliable_broadcast: ReliableBroadcastConfig::default(),
+            optimistic_proposal: false,
+            commit_notification_bus: CommitNotificationBusConfig::default(),
         }
     }
 }
@@ -60,6 +107,154 @@ impl ConsensusConfig {
     }
 }
 
+/// Round timeouts grow exponentially with the number of rounds since the last commit:
+/// round_initial_timeout_ms * exponent_base ^ min(rounds, max_exponent).
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RoundTimeoutBackoffConfig {
+    pub exponent_base: f64,
+    pub max_exponent: usize,
+    // Upper bound of a round timeout (jitter included), no bound if not set
+    pub max_timeout_ms: Option<u64>,
+    // Every validator extends its round timeouts by a pseudo-random fraction of up to this
+    // percentage, so that validators don't time out in lockstep during partitions
+    pub jitter_percent: u64,
+}
+
+impl Default for RoundTimeoutBackoffConfig {
+    fn default() -> RoundTimeoutBackoffConfig {
+        // 1.2^6 ~= 3
+        // Timeout goes from initial_timeout to initial_timeout*3 in 6 steps
+        RoundTimeoutBackoffConfig {
+            exponent_base: 1.2,
+            max_exponent: 6,
+            max_timeout_ms: None,
+            jitter_percent: 0,
+        }
+    }
+}
+
+/// The consensus observer lets fullnodes follow the consensus messages of the validators they are
+/// connected to without participating, and learn about commits before chunks are available.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConsensusObserverConfig {
+    // Fullnodes only: subscribe to proposals, votes and commit decisions of upstream validators
+    pub observer_enabled: bool,
+    // Validators only: publish proposals, votes and commit decisions to subscribed fullnodes
+    pub publisher_enabled: bool,
+    // Maximum number of pending observer messages per peer
+    pub max_network_channel_size: usize,
+}
+
+impl Default for ConsensusObserverConfig {
+    fn default() -> ConsensusObserverConfig {
+        ConsensusObserverConfig {
+            observer_enabled: false,
+            publisher_enabled: false,
+            max_network_channel_size: 1000,
+        }
+    }
+}
+
+/// Streamed block retrieval lets a node catching up request a long chain of blocks at once and
+/// receive it in chunks, instead of one round trip per MAX_BLOCKS_PER_REQUEST blocks.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlockRetrievalStreamConfig {
+    // Request blocks from peers with streams (peers are always served)
+    pub enabled: bool,
+    // Number of blocks in a chunk requested by this node
+    pub chunk_size: u64,
+    // Number of chunks a peer may send ahead of the acknowledgements of this node
+    pub window: u64,
+    // Limits enforced on the streams served to every peer
+    pub max_streams_per_peer: usize,
+    pub max_blocks_per_stream: u64,
+    pub max_chunk_size: u64,
+    pub max_window: u64,
+    // Served streams without any acknowledgement for this long are dropped (in milliseconds)
+    pub stream_timeout_ms: u64,
+}
+
+impl Default for BlockRetrievalStreamConfig {
+    fn default() -> BlockRetrievalStreamConfig {
+        BlockRetrievalStreamConfig {
+            enabled: false,
+            chunk_size: 20,
+            window: 4,
+            max_streams_per_peer: 2,
+            max_blocks_per_stream: 1000,
+            max_chunk_size: 50,
+            max_window: 8,
+            stream_timeout_ms: 10_000,
+        }
+    }
+}
+
+/// The timeout votes and the commit votes are sent again to the validators that didn't acknowledge
+/// them, so that a lost message doesn't delay the view change until the next round timeout.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReliableBroadcastConfig {
+    pub enabled: bool,
+    // Delay between two sends of a message to the validators that didn't acknowledge it
+    pub retry_interval_ms: u64,
+    // Number of times a message is sent again at most, after the initial broadcast
+    pub max_retries: usize,
+}
+
+impl Default for ReliableBroadcastConfig {
+    fn default() -> ReliableBroadcastConfig {
+        ReliableBroadcastConfig {
+            enabled: true,
+            retry_interval_ms: 300,
+            max_retries: 3,
+        }
+    }
+}
+
+/// Every commit is published on a local bus the components of the node can subscribe to, a late
+/// subscriber first receives the most recent commits.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommitNotificationBusConfig {
+    // Number of the most recent commits sent to a new subscriber
+    pub backfill_size: usize,
+    // Number of commits pending for a subscriber, the oldest ones are dropped for slow subscribers
+    pub channel_size: usize,
+}
+
+impl Default for CommitNotificationBusConfig {
+    fn default() -> CommitNotificationBusConfig {
+        CommitNotificationBusConfig {
+            backfill_size: 100,
+            channel_size: 1000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadCompressionType {
+    Disabled,
+    // Fast compression with a lower ratio
+    Lz4,
+    // Better ratio at a higher cpu cost
+    Zstd,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum VoteRecipientsType {
+    // Only the proposer of the next round aggregates the QC
+    NextProposer,
+    // The proposers of the next rounds, so that a slow proposer doesn't delay the QC
+    NextProposers { count: u64 },
+    // All the validators
+    Broadcast,
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum ConsensusProposerType {
@@ -73,11 +268,24 @@ pub enum ConsensusProposerType {
     // or default proposer if round proposer not
     // specified
     RoundProposer(HashMap<Round, AccountAddress>),
+    // Experimental: the proposer of a round is drawn from the VRF output carried by the proposal
+    // of the previous round, which requires experimental_vrf_proposer and the consensus key to be
+    // exported by safety rules. It can't be selected on-chain.
+    VrfProposer,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
-#[serde(deny_unknown_fields)]
+#[serde(default, deny_unknown_fields)]
 pub struct LeaderReputationConfig {
     pub active_weights: u64,
     pub inactive_weights: u64,
 }
+
+impl Default for LeaderReputationConfig {
+    fn default() -> LeaderReputationConfig {
+        LeaderReputationConfig {
+            active_weights: 99,
+            inactive_weights: 1,
+        }
+    }
+}
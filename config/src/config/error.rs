@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::config::{display_violations, ConfigViolation};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -15,6 +16,12 @@ pub enum Error {
     Yaml(String, #[source] serde_yaml::Error),
     #[error("Config is missing expected value: {0}")]
     Missing(&'static str),
+    #[error("Config fields can't be reloaded without a restart: {0:?}")]
+    Unreloadable(Vec<String>),
+    #[error("Config changes failed to apply, the config is left as it was: {0:?}")]
+    ReloadFailed(Vec<String>),
+    #[error("Inconsistent config:{}", display_violations(.0))]
+    Violations(Vec<ConfigViolation>),
 }
 
 pub fn invariant(cond: bool, msg: String) -> Result<(), Error> {
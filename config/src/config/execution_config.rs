@@ -23,6 +23,12 @@ pub struct ExecutionConfig {
     pub service: ExecutionCorrectnessService,
     pub backend: SecureBackend,
     pub network_timeout_ms: u64,
+    /// Number of threads executing the transactions of a block in parallel, 1 executes them
+    /// sequentially
+    pub concurrency_level: usize,
+    /// File the traces of the executed transactions are appended to, for debugging. The
+    /// transactions are executed sequentially while tracing, each with a VM of its own.
+    pub trace_path: Option<PathBuf>,
 }
 
 impl std::fmt::Debug for ExecutionConfig {
@@ -40,8 +46,13 @@ impl std::fmt::Debug for ExecutionConfig {
         )?;
         write!(
             f,
-            ", sign_vote_proposal: {:?}, service: {:?}, backend: {:?} }}",
-            self.sign_vote_proposal, self.service, self.backend
+            ", sign_vote_proposal: {:?}, service: {:?}, backend: {:?}, concurrency_level: {:?}, \
+             trace_path: {:?} }}",
+            self.sign_vote_proposal,
+            self.service,
+            self.backend,
+            self.concurrency_level,
+            self.trace_path
         )?;
         self.service.fmt(f)
     }
@@ -57,6 +68,8 @@ impl Default for ExecutionConfig {
             sign_vote_proposal: true,
             // Default value of 30 seconds for the network timeout.
             network_timeout_ms: 30_000,
+            concurrency_level: 1,
+            trace_path: None,
         }
     }
 }
@@ -3,7 +3,11 @@
 
 use crate::utils;
 use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -16,6 +20,12 @@ pub struct JsonRpcConfig {
     pub tls_key_path: Option<String>,
     #[serde(default)]
     pub stream_rpc: StreamConfig,
+    #[serde(default)]
+    pub rest: RestConfig,
+    #[serde(default)]
+    pub rate_limit: JsonRpcRateLimitConfig,
+    #[serde(default)]
+    pub api_key: ApiKeyConfig,
 }
 
 pub const DEFAULT_JSON_RPC_ADDRESS: &str = "127.0.0.1";
@@ -36,6 +46,9 @@ impl Default for JsonRpcConfig {
             tls_cert_path: None,
             tls_key_path: None,
             stream_rpc: StreamConfig::default(),
+            rest: RestConfig::default(),
+            rate_limit: JsonRpcRateLimitConfig::default(),
+            api_key: ApiKeyConfig::default(),
         }
     }
 }
@@ -74,3 +87,98 @@ impl Default for StreamConfig {
         }
     }
 }
+
+/// This API is experimental and subject to change
+/// Documentation is in /json-rpc/src/rest/mod.rs
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RestConfig {
+    pub enabled: bool,
+}
+
+/// Token bucket rate limiting of the JSON-RPC calls of every client IP. Every call, including each
+/// call of a batch, takes a token from the bucket of its IP, and from the bucket of its method and
+/// IP when the method has a quota. A `submit_batch` call takes a token per transaction, so a batch
+/// with more transactions than the size of a bucket is rejected as too large.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JsonRpcRateLimitConfig {
+    pub enabled: bool,
+    /// Maximum number of calls/s for an IP
+    pub ip_call_bucket_rate: usize,
+    /// Maximum burst of calls for an IP
+    pub ip_call_bucket_size: usize,
+    /// Quotas of the calls of a method for an IP, keyed by JSON-RPC method name
+    pub method_quotas: BTreeMap<String, CallQuota>,
+    /// Addresses of the load balancers and proxies in front of the node. The calls they forward
+    /// are limited by the client IP they add to the `X-Forwarded-For` header instead of their own.
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+pub const DEFAULT_IP_CALL_BUCKET_RATE: usize = 100;
+pub const DEFAULT_IP_CALL_BUCKET_SIZE: usize = 200;
+
+impl Default for JsonRpcRateLimitConfig {
+    fn default() -> JsonRpcRateLimitConfig {
+        let mut method_quotas = BTreeMap::new();
+        method_quotas.insert(
+            "get_transactions".to_string(),
+            CallQuota { rate: 10, size: 20 },
+        );
+        JsonRpcRateLimitConfig {
+            enabled: false,
+            ip_call_bucket_rate: DEFAULT_IP_CALL_BUCKET_RATE,
+            ip_call_bucket_size: DEFAULT_IP_CALL_BUCKET_SIZE,
+            method_quotas,
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+/// A token bucket quota of calls
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CallQuota {
+    /// Maximum number of calls/s
+    pub rate: usize,
+    /// Maximum burst of calls, must be >= `rate`
+    pub size: usize,
+}
+
+/// Optional authentication of the JSON-RPC calls by the API key of the `X-Diem-Api-Key` header.
+/// The calls without an API key are limited to `public_methods` and rate limited by IP, while the
+/// calls with a key are limited to the methods and quota of the key.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiKeyConfig {
+    pub enabled: bool,
+    /// YAML file mapping every API key to its `ApiKeyAccess`
+    pub keys_file: Option<PathBuf>,
+    /// Interval of reloading the keys file, so keys are changed without restarting the node
+    pub reload_interval_ms: u64,
+    /// The JSON-RPC methods the calls without an API key may call, all of them when None
+    pub public_methods: Option<Vec<String>>,
+}
+
+pub const DEFAULT_API_KEY_RELOAD_INTERVAL_MS: u64 = 10_000;
+
+impl Default for ApiKeyConfig {
+    fn default() -> ApiKeyConfig {
+        ApiKeyConfig {
+            enabled: false,
+            keys_file: None,
+            reload_interval_ms: DEFAULT_API_KEY_RELOAD_INTERVAL_MS,
+            public_methods: None,
+        }
+    }
+}
+
+/// The access granted to an API key
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiKeyAccess {
+    /// The JSON-RPC methods the key may call, all of them when None
+    pub allowed_methods: Option<Vec<String>>,
+    /// Quota of the calls of the key, unlimited when None
+    pub quota: Option<CallQuota>,
+}
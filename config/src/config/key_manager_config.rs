@@ -16,8 +16,12 @@ const DEFAULT_TXN_EXPIRATION_SECS: u64 = 3600; // 1 hour
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct KeyManagerConfig {
+    // Whether to only report the key operations instead of performing them
+    pub dry_run: bool,
     pub logger: LoggerConfig,
     pub json_rpc_endpoint: String,
+    // Whether to rotate the validator and fullnode network keys along with the consensus key
+    pub rotate_network_keys: bool,
     pub rotation_period_secs: u64,
     pub secure_backend: SecureBackend,
     pub sleep_period_secs: u64,
@@ -29,8 +33,10 @@ pub struct KeyManagerConfig {
 impl Default for KeyManagerConfig {
     fn default() -> KeyManagerConfig {
         KeyManagerConfig {
+            dry_run: false,
             json_rpc_endpoint: DEFAULT_JSON_RPC_ENDPOINT.into(),
             logger: LoggerConfig::default(),
+            rotate_network_keys: false,
             rotation_period_secs: DEFAULT_ROTATION_PERIOD_SECS,
             secure_backend: SecureBackend::InMemoryStorage,
             sleep_period_secs: DEFAULT_SLEEP_PERIOD_SECS,
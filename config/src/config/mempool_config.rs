@@ -2,12 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct MempoolConfig {
     pub capacity: usize,
+    // bounds on the estimated memory used by the transactions, in total and per sender
+    pub capacity_bytes: usize,
+    pub capacity_bytes_per_user: usize,
     pub capacity_per_user: usize,
+    // which transactions are evicted to make space for a new ready transaction once mempool is full
+    pub eviction_policy: EvictionPolicyType,
+    // reject the transactions whose sender can't cover the max gas of all its pending transactions
+    pub check_sender_balance: bool,
     // number of failovers to broadcast to when the primary network is alive
     pub default_failovers: usize,
     pub max_broadcasts_per_peer: usize,
@@ -15,10 +23,26 @@ pub struct MempoolConfig {
     pub shared_mempool_ack_timeout_ms: u64,
     pub shared_mempool_backoff_interval_ms: u64,
     pub shared_mempool_batch_size: usize,
+    pub shared_mempool_broadcast_batching: BroadcastBatchingConfig,
     pub shared_mempool_max_concurrent_inbound_syncs: usize,
+    pub shared_mempool_peer_scoring: PeerScoringConfig,
     pub shared_mempool_tick_interval_ms: u64,
+    // events buffered for each subscriber to the transaction status events, the oldest ones are
+    // dropped for a subscriber lagging further behind
+    pub status_subscription_channel_size: usize,
     pub system_transaction_timeout_secs: u64,
     pub system_transaction_gc_interval_ms: u64,
+    // Lower bounds of the gas price buckets used to fill blocks: transactions of a higher bucket go
+    // first, within a bucket the accounts with fewer transactions in the block go first
+    pub gas_price_buckets: Vec<u64>,
+    // A transaction replaces the one with the same sequence number if its gas price is higher by at
+    // least this percentage
+    pub replacement_gas_price_bump_percent: u64,
+    // Log of the pending transactions replayed on restart, relative to the data directory if not
+    // absolute. None disables the persistence of the transactions.
+    pub persistence_path: Option<PathBuf>,
+    #[serde(skip)]
+    data_dir: PathBuf,
 }
 
 impl Default for MempoolConfig {
@@ -27,15 +51,123 @@ impl Default for MempoolConfig {
             shared_mempool_tick_interval_ms: 50,
             shared_mempool_backoff_interval_ms: 30_000,
             shared_mempool_batch_size: 100,
+            shared_mempool_broadcast_batching: BroadcastBatchingConfig::default(),
             shared_mempool_ack_timeout_ms: 2_000,
             shared_mempool_max_concurrent_inbound_syncs: 2,
+            shared_mempool_peer_scoring: PeerScoringConfig::default(),
             max_broadcasts_per_peer: 1,
             mempool_snapshot_interval_secs: 180,
             capacity: 1_000_000,
+            capacity_bytes: 2 * 1024 * 1024 * 1024,
+            capacity_bytes_per_user: 4 * 1024 * 1024,
             capacity_per_user: 100,
+            eviction_policy: EvictionPolicyType::ParkingLot,
+            check_sender_balance: false,
             default_failovers: 3,
+            status_subscription_channel_size: 1_000,
             system_transaction_timeout_secs: 600,
             system_transaction_gc_interval_ms: 60_000,
+            gas_price_buckets: vec![0, 150, 300, 500, 1000, 3000, 5000, 10000, 100000, 1000000],
+            replacement_gas_price_bump_percent: 10,
+            persistence_path: None,
+            data_dir: PathBuf::from("/opt/diem/data"),
         }
     }
 }
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionPolicyType {
+    // A random transaction that isn't ready yet
+    ParkingLot,
+    // The transaction expiring first, if it expires before the new one
+    Ttl,
+    // The ready transaction with the lowest gas price, if it pays less than the new one
+    LowestGasFirst,
+    // A transaction that isn't ready, or the one that has been ready for the longest time once
+    // every transaction is ready
+    OldestReadyFirst,
+}
+
+/// How the transactions broadcast to each peer are grouped in batches, on top of the fixed batch
+/// size of shared mempool.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BroadcastBatchingConfig {
+    // Max time a fresh broadcast waits for a full batch of new transactions, 0 sends them on every
+    // tick
+    pub max_batch_delay_ms: u64,
+    // Compress the broadcasts with zstd for the peers that support it
+    pub compression: bool,
+    // Adapt the batch size of each peer to its ack latency, starting from the batch size of shared
+    // mempool: it grows while the acks are faster than the target, and it shrinks otherwise
+    pub adaptive_batch_size: bool,
+    pub min_batch_size: usize,
+    pub max_batch_size: usize,
+    pub target_ack_latency_ms: u64,
+}
+
+impl Default for BroadcastBatchingConfig {
+    fn default() -> BroadcastBatchingConfig {
+        BroadcastBatchingConfig {
+            max_batch_delay_ms: 0,
+            compression: false,
+            adaptive_batch_size: false,
+            min_batch_size: 10,
+            max_batch_size: 1000,
+            target_ack_latency_ms: 500,
+        }
+    }
+}
+
+/// How the upstream peers are ordered to pick the ones the transactions are broadcast to.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeerScoringConfig {
+    // Order of the upstream peers within each network
+    pub failover_ordering: FailoverOrdering,
+    // Max number of upstream peers broadcast to, the first ones in order. None broadcasts to all
+    // the peers on validators, and to the primary peer and `default_failovers` on the other nodes
+    pub fanout: Option<usize>,
+    // Weight of the latest sample in the moving averages of the ack latency and the duplicate rate
+    // of a peer, in percent
+    pub smoothing_percent: u64,
+    // Latency added to the score of a peer whose broadcasts all have to be sent again
+    pub duplicate_penalty_ms: u64,
+}
+
+impl Default for PeerScoringConfig {
+    fn default() -> PeerScoringConfig {
+        PeerScoringConfig {
+            failover_ordering: FailoverOrdering::Role,
+            fanout: None,
+            smoothing_percent: 20,
+            duplicate_penalty_ms: 2_000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailoverOrdering {
+    // By role of the peer (e.g. preferred upstreams first)
+    Role,
+    // By score of the peer, from its ack latency and the share of its broadcasts sent again
+    Score,
+}
+
+impl MempoolConfig {
+    pub fn persistence_path(&self) -> Option<PathBuf> {
+        self.persistence_path.as_ref().map(|path| {
+            if path.is_relative() {
+                self.data_dir.join(path)
+            } else {
+                path.clone()
+            }
+        })
+    }
+
+    pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        self.data_dir = data_dir;
+    }
+}
@@ -14,6 +14,8 @@ use std::{
 };
 use thiserror::Error;
 
+mod admin_service_config;
+pub use admin_service_config::*;
 mod consensus_config;
 pub use consensus_config::*;
 mod debug_interface_config;
@@ -40,6 +42,13 @@ mod storage_config;
 pub use storage_config::*;
 mod safety_rules_config;
 pub use safety_rules_config::*;
+mod sanitizer;
+pub(crate) use sanitizer::display_violations;
+pub use sanitizer::ConfigViolation;
+mod shutdown_config;
+pub use shutdown_config::*;
+mod telemetry_config;
+pub use telemetry_config::*;
 mod upstream_config;
 pub use upstream_config::*;
 mod test_config;
@@ -59,6 +68,8 @@ pub struct DeprecatedConfig {}
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct NodeConfig {
+    #[serde(default)]
+    pub admin_service: AdminServiceConfig,
     #[serde(default)]
     pub base: BaseConfig,
     #[serde(default)]
@@ -78,10 +89,14 @@ pub struct NodeConfig {
     #[serde(default)]
     pub json_rpc: JsonRpcConfig,
     #[serde(default)]
+    pub shutdown: ShutdownConfig,
+    #[serde(default)]
     pub state_sync: StateSyncConfig,
     #[serde(default)]
     pub storage: StorageConfig,
     #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    #[serde(default)]
     pub test: Option<TestConfig>,
     #[serde(default)]
     pub validator_network: Option<NetworkConfig>,
@@ -220,6 +235,7 @@ impl NodeConfig {
         self.base.data_dir = data_dir.clone();
         self.consensus.set_data_dir(data_dir.clone());
         self.execution.set_data_dir(data_dir.clone());
+        self.mempool.set_data_dir(data_dir.clone());
         self.storage.set_data_dir(data_dir);
     }
 
@@ -233,6 +249,7 @@ impl NodeConfig {
         config.execution.load(&input_dir)?;
 
         let mut config = config.validate_network_configs()?;
+        config.sanitize()?;
         config.set_data_dir(config.data_dir().to_path_buf());
         Ok(config)
     }
@@ -291,6 +308,7 @@ impl NodeConfig {
     }
 
     pub fn randomize_ports(&mut self) {
+        self.admin_service.randomize_ports();
         self.debug_interface.randomize_ports();
         self.json_rpc.randomize_ports();
         self.storage.randomize_ports();
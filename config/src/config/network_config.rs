@@ -20,7 +20,7 @@ use rand::{
 use serde::{Deserialize, Serialize};
 use short_hex_str::AsShortHexStr;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     convert::TryFrom,
     path::PathBuf,
     string::ToString,
@@ -45,6 +45,21 @@ pub const MAX_FRAME_SIZE: usize = 8 * 1024 * 1024; /* 8 MiB */
 pub const CONNECTION_BACKOFF_BASE: u64 = 2;
 pub const IP_BYTE_BUCKET_RATE: usize = 102400 /* 100 KiB */;
 pub const IP_BYTE_BUCKET_SIZE: usize = IP_BYTE_BUCKET_RATE;
+pub const PEER_BAN_SCORE: i64 = -100;
+pub const PEER_SCORE_HALF_LIFE_MS: u64 = 600_000; /* 10 minutes */
+pub const PEER_BAN_DURATION_MS: u64 = 3_600_000; /* 1 hour */
+pub const STATE_SYNC_BYTE_BUCKET_RATE: usize = 4 * 1024 * 1024; /* 4 MiB */
+pub const STATE_SYNC_BYTE_BUCKET_SIZE: usize = MAX_FRAME_SIZE;
+pub const MAX_RATE_LIMITED_QUEUE_SIZE: usize = 1024;
+pub const COMPRESSION_THRESHOLD_BYTES: usize = 16 * 1024; /* 16 KiB */
+pub const MAX_INBOUND_CONNECTIONS_PER_IP: usize = 8;
+pub const KEEP_ALIVE_INTERVAL_MS: u64 = 10_000;
+pub const KEEP_ALIVE_TIMEOUT_MS: u64 = 20_000;
+pub const KEEP_ALIVE_FAILURES_TOLERATED: u64 = 2;
+pub const SOCKS5_PROXY_ADDRESS: &str = "127.0.0.1:1080";
+pub const IP_HANDSHAKE_BUCKET_RATE: usize = 5;
+pub const IP_HANDSHAKE_BUCKET_SIZE: usize = 20;
+pub const ANTI_REPLAY_WINDOW_MS: u64 = 300_000; /* 5 minutes */
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -98,6 +113,22 @@ pub struct NetworkConfig {
     pub inbound_rate_limit_config: Option<RateLimitConfig>,
     // Outbound rate limiting configuration, if not specified, no rate limiting
     pub outbound_rate_limit_config: Option<RateLimitConfig>,
+    // Disconnects and bans the peers reported for misbehaving, if not specified, no peer is banned
+    pub peer_reputation_config: Option<PeerReputationConfig>,
+    // Per protocol rate limiting of the messages sent to each peer, if not specified, no rate limiting
+    pub protocol_rate_limit_config: Option<ProtocolRateLimitConfig>,
+    // Compression of large messages, negotiated with each peer, if not specified, no compression
+    pub wire_compression_config: Option<WireCompressionConfig>,
+    // Limits of the inbound connections of unknown peers from a single IP, if not specified, no
+    // per IP limit
+    pub inbound_connection_limit_config: Option<InboundConnectionLimitConfig>,
+    // Keep-alive pings of the connections, negotiated with each peer, if not specified, no pings
+    pub keep_alive_config: Option<KeepAliveConfig>,
+    // SOCKS5 proxy of the outbound connections, if not specified, peers are dialed directly
+    pub socks5_proxy_config: Option<Socks5ProxyConfig>,
+    // Per IP rate limit of the inbound noise handshakes and window of their anti-replay timestamps,
+    // if not specified, neither
+    pub handshake_limit_config: Option<HandshakeLimitConfig>,
 }
 
 impl Default for NetworkConfig {
@@ -132,6 +163,13 @@ impl NetworkConfig {
             max_inbound_connections: MAX_INBOUND_CONNECTIONS,
             inbound_rate_limit_config: None,
             outbound_rate_limit_config: None,
+            peer_reputation_config: None,
+            protocol_rate_limit_config: None,
+            wire_compression_config: None,
+            inbound_connection_limit_config: None,
+            keep_alive_config: None,
+            socks5_proxy_config: None,
+            handshake_limit_config: None,
         };
         config.prepare_identity();
         config
@@ -208,6 +246,15 @@ impl NetworkConfig {
             self.listen_address = utils::get_local_ip()
                 .ok_or_else(|| Error::InvariantViolation("No local IP".to_string()))?;
         }
+        if let Some(config) = &self.socks5_proxy_config {
+            crate::config::invariant(
+                config.username.is_some() == config.password.is_some(),
+                format!(
+                    "SOCKS5 proxy {} needs both a username and a password, or neither",
+                    config.address
+                ),
+            )?;
+        }
 
         self.prepare_identity();
         Ok(())
@@ -304,6 +351,9 @@ impl NetworkConfig {
 pub enum DiscoveryMethod {
     Onchain,
     File(PathBuf, Duration),
+    /// Seed peers behind DNS names, e.g. `/dns/<name>/tcp/<port>/noise-ik/<pubkey>/handshake/0`,
+    /// whose names are resolved again on every interval
+    Dns(Vec<NetworkAddress>, Duration),
     None,
 }
 
@@ -371,6 +421,209 @@ impl Default for RateLimitConfig {
     }
 }
 
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeerReputationConfig {
+    /// Peers start with a score of 0, which every reported misbehavior lowers. Peers whose score
+    /// falls to this score or below are disconnected and banned.
+    pub ban_score: i64,
+    /// Time for the score of a peer to recover half of the way back to 0
+    pub score_half_life_ms: u64,
+    /// How long the connections of a banned peer are refused
+    pub ban_duration_ms: u64,
+    /// Allow for disabling the bans
+    pub enabled: bool,
+}
+
+impl Default for PeerReputationConfig {
+    fn default() -> Self {
+        Self {
+            ban_score: PEER_BAN_SCORE,
+            score_half_life_ms: PEER_SCORE_HALF_LIFE_MS,
+            ban_duration_ms: PEER_BAN_DURATION_MS,
+            enabled: true,
+        }
+    }
+}
+
+/// Limits the bandwidth each protocol may use on the connection to a peer, so the messages of one
+/// protocol (e.g. a state sync catch-up) cannot starve the others (e.g. consensus).
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProtocolRateLimitConfig {
+    /// Limits of the messages sent to a peer, by protocol name (e.g. `StateSyncDirectSend`).
+    /// Protocols without a limit are never throttled.
+    pub protocols: BTreeMap<String, ProtocolByteLimit>,
+    /// Allow for disabling the throttles
+    pub enabled: bool,
+}
+
+impl Default for ProtocolRateLimitConfig {
+    fn default() -> Self {
+        let mut protocols = BTreeMap::new();
+        protocols.insert(
+            "StateSyncDirectSend".to_string(),
+            ProtocolByteLimit {
+                byte_bucket_rate: STATE_SYNC_BYTE_BUCKET_RATE,
+                byte_bucket_size: STATE_SYNC_BYTE_BUCKET_SIZE,
+                policy: RateLimitPolicy::Queue,
+                max_queue_size: MAX_RATE_LIMITED_QUEUE_SIZE,
+            },
+        );
+        Self {
+            protocols,
+            enabled: true,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProtocolByteLimit {
+    /// Maximum number of bytes/s sent to a peer
+    pub byte_bucket_rate: usize,
+    /// Maximum burst of bytes sent to a peer. Messages bigger than this are always dropped.
+    pub byte_bucket_size: usize,
+    /// What happens to the messages over the limit
+    pub policy: RateLimitPolicy,
+    /// Maximum number of messages queued by the `Queue` policy, the messages over it are dropped
+    pub max_queue_size: usize,
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitPolicy {
+    /// Drop the messages over the limit. The RPCs over the limit fail.
+    Drop,
+    /// Hold the messages over the limit until the bucket refills, keeping their order
+    Queue,
+}
+
+/// Compresses the large messages sent to the peers which also enabled compression. Mostly benefits
+/// state sync chunks and consensus proposals.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WireCompressionConfig {
+    /// Messages with a payload of at least this many bytes are compressed
+    pub threshold_bytes: usize,
+    /// Allow for disabling the compression
+    pub enabled: bool,
+}
+
+impl Default for WireCompressionConfig {
+    fn default() -> Self {
+        Self {
+            threshold_bytes: COMPRESSION_THRESHOLD_BYTES,
+            enabled: true,
+        }
+    }
+}
+
+/// Limits the inbound connections of unknown peers from a single IP, so a single host can't take
+/// all the inbound connection slots of a public network. A new connection over the limit evicts the
+/// oldest connection from the same IP. Every peer identity already has at most one connection, as
+/// a new connection with a connected peer replaces the existing one.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InboundConnectionLimitConfig {
+    /// Maximum number of inbound connections of unknown peers from a single IP
+    pub max_connections_per_ip: usize,
+    /// Allow for disabling the limit
+    pub enabled: bool,
+}
+
+impl Default for InboundConnectionLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_connections_per_ip: MAX_INBOUND_CONNECTIONS_PER_IP,
+            enabled: true,
+        }
+    }
+}
+
+/// Pings the peers which also enabled keep-alive over their connections, to measure the round trip
+/// time of the connections and to disconnect the peers which stopped answering, e.g. after a silent
+/// drop of their TCP connection.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeepAliveConfig {
+    /// Interval between the pings of a connection
+    pub interval_ms: u64,
+    /// Time until a ping without a pong fails
+    pub timeout_ms: u64,
+    /// Number of consecutive failed pings until the peer is disconnected
+    pub failures_tolerated: u64,
+    /// Allow for disabling the pings
+    pub enabled: bool,
+}
+
+impl Default for KeepAliveConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: KEEP_ALIVE_INTERVAL_MS,
+            timeout_ms: KEEP_ALIVE_TIMEOUT_MS,
+            failures_tolerated: KEEP_ALIVE_FAILURES_TOLERATED,
+            enabled: true,
+        }
+    }
+}
+
+/// Dials the peers through a SOCKS5 proxy, for the operators who must route their traffic through
+/// an egress proxy. Only TCP connections are proxied, and the DNS names of the peers are resolved
+/// by the proxy.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Socks5ProxyConfig {
+    /// `<host>:<port>` of the proxy
+    pub address: String,
+    /// Username of the proxy, if not specified, no authentication
+    pub username: Option<String>,
+    /// Password of the proxy, required with a username
+    pub password: Option<String>,
+    /// Allow for disabling the proxy
+    pub enabled: bool,
+}
+
+impl Default for Socks5ProxyConfig {
+    fn default() -> Self {
+        Self {
+            address: SOCKS5_PROXY_ADDRESS.to_string(),
+            username: None,
+            password: None,
+            enabled: true,
+        }
+    }
+}
+
+/// Limits the noise handshakes of the inbound connections. The responder of a handshake runs its
+/// Diffie-Hellman operations before it can authenticate the client or detect a replayed message, so
+/// the handshakes each IP may start are rate limited. The anti-replay timestamps of the clients are
+/// also checked against our clock, so that a captured handshake message can't be replayed once it's
+/// out of the window, even on the networks which don't keep the last timestamp of every peer.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HandshakeLimitConfig {
+    /// Maximum number of handshakes/s for an IP
+    pub ip_handshake_bucket_rate: usize,
+    /// Maximum burst of handshakes for an IP
+    pub ip_handshake_bucket_size: usize,
+    /// Maximum difference between the anti-replay timestamp of a client and our clock
+    pub anti_replay_window_ms: u64,
+    /// Allow for disabling the limits
+    pub enabled: bool,
+}
+
+impl Default for HandshakeLimitConfig {
+    fn default() -> Self {
+        Self {
+            ip_handshake_bucket_rate: IP_HANDSHAKE_BUCKET_RATE,
+            ip_handshake_bucket_size: IP_HANDSHAKE_BUCKET_SIZE,
+            anti_replay_window_ms: ANTI_REPLAY_WINDOW_MS,
+            enabled: true,
+        }
+    }
+}
+
 pub type PeerSet = HashMap<PeerId, Peer>;
 
 // TODO: Combine with RoleType?
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    config::{LoggerConfig, SecureBackend},
+    config::{Error, LoggerConfig, SecureBackend},
     keys::ConfigKey,
 };
 use diem_crypto::{ed25519::Ed25519PrivateKey, Uniform};
@@ -27,6 +27,8 @@ pub struct SafetyRulesConfig {
     pub network_timeout_ms: u64,
     pub enable_cached_safety_data: bool,
     pub decoupled_execution: bool,
+    // Controls how sensitive fields (authors, waypoints and rounds) appear in safety rules logs.
+    pub log_redaction: LogRedactionMode,
 }
 
 impl Default for SafetyRulesConfig {
@@ -42,6 +44,7 @@ impl Default for SafetyRulesConfig {
             network_timeout_ms: 30_000,
             enable_cached_safety_data: true,
             decoupled_execution: false,
+            log_redaction: LogRedactionMode::Disabled,
         }
     }
 }
@@ -52,6 +55,48 @@ impl SafetyRulesConfig {
             backend.set_data_dir(data_dir);
         }
     }
+
+    /// Checks the config is consistent: the consensus key can't be exported from a Vault backend
+    /// with key export disabled, so safety rules must then sign with Vault.
+    pub fn sanitize(&self) -> Result<(), Error> {
+        if self.key_export_conflict() {
+            return Err(Error::InvariantViolation(
+                "export_consensus_key must be false when the key export of the safety rules \
+                 backend is disabled"
+                    .into(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn key_export_conflict(&self) -> bool {
+        match &self.backend {
+            SecureBackend::Vault(vault_config) => {
+                vault_config.key_export_disabled() && self.export_consensus_key
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Defines how sensitive fields are emitted in safety rules logs. Redaction never introduces new
+/// log keys, so log pipelines can keep relying on the same schema.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRedactionMode {
+    /// Sensitive fields are logged in plaintext
+    Disabled,
+    /// Sensitive fields are replaced by the SHA3-256 hash of their value, keyed with a random salt
+    /// drawn at startup: hashes only correlate within the logs of the same process
+    Hash,
+    /// Sensitive fields are dropped from the log entry
+    Omit,
+}
+
+impl Default for LogRedactionMode {
+    fn default() -> Self {
+        LogRedactionMode::Disabled
+    }
 }
 
 /// Defines how safety rules should be executed
@@ -121,3 +166,32 @@ impl SafetyRulesTestConfig {
         self.execution_key = Some(ConfigKey::<Ed25519PrivateKey>::new(privkey));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Token, VaultConfig};
+
+    #[test]
+    fn test_sanitize_key_export() {
+        let mut config = SafetyRulesConfig {
+            backend: SecureBackend::Vault(VaultConfig {
+                namespace: None,
+                server: "127.0.0.1:8200".to_string(),
+                ca_certificate: None,
+                token: Token::FromConfig("test".to_string()),
+                renew_ttl_secs: None,
+                disable_cas: None,
+                disable_key_export: Some(true),
+                connection_timeout_ms: None,
+                response_timeout_ms: None,
+            }),
+            export_consensus_key: true,
+            ..Default::default()
+        };
+        config.sanitize().unwrap_err();
+
+        config.export_consensus_key = false;
+        config.sanitize().unwrap();
+    }
+}
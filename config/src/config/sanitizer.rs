@@ -0,0 +1,390 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks of the invariants spanning several components of the `NodeConfig`, which the
+//! deserialization of each section can't catch and which would otherwise only fail at runtime.
+
+use crate::config::{
+    ConsensusProposerType, Error, NodeConfig, VoteRecipientsType, TELEMETRY_FIELDS,
+};
+use serde::Serialize;
+use std::fmt;
+
+/// A combination of config fields the node can't run with.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ConfigViolation {
+    /// The fields involved, e.g. `consensus.safety_rules.export_consensus_key`
+    pub fields: Vec<&'static str>,
+    pub problem: String,
+    /// How to fix the config
+    pub hint: String,
+}
+
+impl ConfigViolation {
+    fn new(fields: &[&'static str], problem: &str, hint: &str) -> Self {
+        Self {
+            fields: fields.to_vec(),
+            problem: problem.to_string(),
+            hint: hint.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}. Hint: {}",
+            self.fields.join(", "),
+            self.problem,
+            self.hint
+        )
+    }
+}
+
+pub(crate) fn display_violations(violations: &[ConfigViolation]) -> String {
+    violations
+        .iter()
+        .map(|violation| format!("\n  {}", violation))
+        .collect()
+}
+
+impl NodeConfig {
+    /// Checks the invariants between the components of the config, and reports all their
+    /// violations at once.
+    pub fn sanitize(&self) -> Result<(), Error> {
+        let violations = self.violations();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Violations(violations))
+        }
+    }
+
+    /// Returns the violations of the invariants between the components of the config.
+    pub fn violations(&self) -> Vec<ConfigViolation> {
+        let mut violations = vec![];
+        let consensus = &self.consensus;
+        let safety_rules = &consensus.safety_rules;
+
+        if safety_rules.key_export_conflict() {
+            violations.push(ConfigViolation::new(
+                &[
+                    "consensus.safety_rules.export_consensus_key",
+                    "consensus.safety_rules.backend.disable_key_export",
+                ],
+                "the consensus key is exported from a Vault backend with key export disabled",
+                "set export_consensus_key to false, or enable the key export of the Vault backend",
+            ));
+        }
+        if consensus.decoupled_execution != safety_rules.decoupled_execution {
+            violations.push(ConfigViolation::new(
+                &[
+                    "consensus.decoupled_execution",
+                    "consensus.safety_rules.decoupled_execution",
+                ],
+                "consensus and safety rules disagree on decoupled execution",
+                "set both fields to the same value",
+            ));
+        }
+        if matches!(consensus.proposer_type, ConsensusProposerType::VrfProposer)
+            && !consensus.experimental_vrf_proposer
+        {
+            violations.push(ConfigViolation::new(
+                &[
+                    "consensus.proposer_type",
+                    "consensus.experimental_vrf_proposer",
+                ],
+                "the VRF proposer election is experimental, and isn't enabled",
+                "set experimental_vrf_proposer to true, or use another proposer_type",
+            ));
+        }
+        if matches!(consensus.proposer_type, ConsensusProposerType::VrfProposer)
+            && !safety_rules.export_consensus_key
+        {
+            violations.push(ConfigViolation::new(
+                &[
+                    "consensus.proposer_type",
+                    "consensus.safety_rules.export_consensus_key",
+                ],
+                "the VRF proposer election needs the consensus key, which safety rules don't export",
+                "set export_consensus_key to true, or use another proposer_type",
+            ));
+        }
+        if matches!(
+            consensus.vote_recipients,
+            VoteRecipientsType::NextProposers { count: 0 }
+        ) {
+            violations.push(ConfigViolation::new(
+                &["consensus.vote_recipients"],
+                "the votes are sent to the proposers of zero next rounds",
+                "set a positive count, or send the votes to the next proposer only",
+            ));
+        }
+        if self
+            .mempool
+            .gas_price_buckets
+            .windows(2)
+            .any(|buckets| buckets[0] >= buckets[1])
+        {
+            violations.push(ConfigViolation::new(
+                &["mempool.gas_price_buckets"],
+                "the gas price buckets aren't in increasing order, so the transactions would be \
+                 put in the wrong buckets",
+                "list the lower bounds of the buckets in increasing order, without duplicates",
+            ));
+        }
+        let backoff = &consensus.round_timeout_backoff;
+        let max_multiplier = backoff
+            .exponent_base
+            .powf(backoff.max_exponent as f64)
+            .ceil();
+        let backoff_fits = backoff.max_exponent < 32
+            && backoff.exponent_base >= 1.0
+            && max_multiplier < f64::from(u32::MAX);
+        if !backoff_fits {
+            violations.push(ConfigViolation::new(
+                &[
+                    "consensus.round_timeout_backoff.exponent_base",
+                    "consensus.round_timeout_backoff.max_exponent",
+                ],
+                "the round timeouts can't back off with this exponent base and max exponent",
+                "set an exponent_base of at least 1 and a max_exponent below 32, such that \
+                 exponent_base ^ max_exponent is below u32::MAX",
+            ));
+        }
+        if self.json_rpc.tls_cert_path.is_some() != self.json_rpc.tls_key_path.is_some() {
+            violations.push(ConfigViolation::new(
+                &["json_rpc.tls_cert_path", "json_rpc.tls_key_path"],
+                "JSON-RPC TLS is configured with only one of the certificate and the key",
+                "set both paths to serve over TLS, or neither to serve over plain HTTP",
+            ));
+        }
+        let admin_service = &self.admin_service;
+        if admin_service.enabled && admin_service.auth_token.is_none() {
+            if admin_service.address.ip().is_loopback() {
+                violations.push(ConfigViolation::new(
+                    &["admin_service.enabled", "admin_service.auth_token"],
+                    "the admin service is enabled without an auth token, so it wouldn't start",
+                    "set an auth_token, or disable the admin service",
+                ));
+            } else {
+                violations.push(ConfigViolation {
+                    fields: vec!["admin_service.address", "admin_service.auth_token"],
+                    problem: format!(
+                        "the admin service would be reachable from other hosts on {} without an \
+                         auth token",
+                        admin_service.address
+                    ),
+                    hint: "set an auth_token, or listen on a loopback address".to_string(),
+                });
+            }
+        }
+        if self.telemetry.enabled && self.telemetry.endpoint.is_none() {
+            violations.push(ConfigViolation::new(
+                &["telemetry.enabled", "telemetry.endpoint"],
+                "telemetry is enabled without an endpoint to report to",
+                "set the endpoint, or disable telemetry",
+            ));
+        }
+        if self.telemetry.report_interval_secs == 0 {
+            violations.push(ConfigViolation::new(
+                &["telemetry.report_interval_secs"],
+                "telemetry can't report every 0 seconds",
+                "set a report_interval_secs of at least 1",
+            ));
+        }
+        if let Some(field) = self
+            .telemetry
+            .fields
+            .iter()
+            .find(|field| !TELEMETRY_FIELDS.contains(&field.as_str()))
+        {
+            violations.push(ConfigViolation {
+                fields: vec!["telemetry.fields"],
+                problem: format!("{} can't be reported", field),
+                hint: format!("only report fields among {:?}", TELEMETRY_FIELDS),
+            });
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{SecureBackend, Token, VaultConfig};
+
+    fn violated_fields(config: &NodeConfig) -> Vec<Vec<&'static str>> {
+        config
+            .violations()
+            .into_iter()
+            .map(|violation| violation.fields)
+            .collect()
+    }
+
+    #[test]
+    fn test_default_configs() {
+        NodeConfig::default().sanitize().unwrap();
+        NodeConfig::default_for_public_full_node()
+            .sanitize()
+            .unwrap();
+        NodeConfig::default_for_validator().sanitize().unwrap();
+        NodeConfig::default_for_validator_full_node()
+            .sanitize()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_reports_all_violations() {
+        let mut config = NodeConfig::default();
+        config.consensus.safety_rules.backend = SecureBackend::Vault(VaultConfig {
+            namespace: None,
+            server: "127.0.0.1:8200".to_string(),
+            ca_certificate: None,
+            token: Token::FromConfig("test".to_string()),
+            renew_ttl_secs: None,
+            disable_cas: None,
+            disable_key_export: Some(true),
+            connection_timeout_ms: None,
+            response_timeout_ms: None,
+        });
+        config.consensus.safety_rules.export_consensus_key = true;
+        config.consensus.decoupled_execution = true;
+        config.json_rpc.tls_key_path = Some("key.pem".to_string());
+        config.admin_service.enabled = true;
+
+        assert_eq!(
+            violated_fields(&config),
+            vec![
+                vec![
+                    "consensus.safety_rules.export_consensus_key",
+                    "consensus.safety_rules.backend.disable_key_export",
+                ],
+                vec![
+                    "consensus.decoupled_execution",
+                    "consensus.safety_rules.decoupled_execution",
+                ],
+                vec!["json_rpc.tls_cert_path", "json_rpc.tls_key_path"],
+                vec!["admin_service.enabled", "admin_service.auth_token"],
+            ]
+        );
+        match config.sanitize() {
+            Err(error @ Error::Violations(_)) => {
+                assert!(error.to_string().contains("Hint: set both fields"))
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_admin_service_address() {
+        let mut config = NodeConfig::default();
+        config.admin_service.enabled = true;
+        config.admin_service.address = "0.0.0.0:9202".parse().unwrap();
+        assert_eq!(
+            violated_fields(&config),
+            vec![vec!["admin_service.address", "admin_service.auth_token"]]
+        );
+
+        config.admin_service.auth_token = Some("token".to_string());
+        config.sanitize().unwrap();
+    }
+
+    #[test]
+    fn test_vrf_proposer() {
+        let mut config = NodeConfig::default();
+        config.consensus.proposer_type = ConsensusProposerType::VrfProposer;
+        assert_eq!(
+            violated_fields(&config),
+            vec![
+                vec![
+                    "consensus.proposer_type",
+                    "consensus.experimental_vrf_proposer",
+                ],
+                vec![
+                    "consensus.proposer_type",
+                    "consensus.safety_rules.export_consensus_key",
+                ],
+            ]
+        );
+
+        config.consensus.experimental_vrf_proposer = true;
+        config.consensus.safety_rules.export_consensus_key = true;
+        config.sanitize().unwrap();
+    }
+
+    #[test]
+    fn test_vote_recipients() {
+        let mut config = NodeConfig::default();
+        config.consensus.vote_recipients = VoteRecipientsType::NextProposers { count: 0 };
+        assert_eq!(
+            violated_fields(&config),
+            vec![vec!["consensus.vote_recipients"]]
+        );
+
+        config.consensus.vote_recipients = VoteRecipientsType::NextProposers { count: 2 };
+        config.sanitize().unwrap();
+    }
+
+    #[test]
+    fn test_gas_price_buckets() {
+        let mut config = NodeConfig::default();
+        config.mempool.gas_price_buckets = vec![0, 300, 150];
+        assert_eq!(
+            violated_fields(&config),
+            vec![vec!["mempool.gas_price_buckets"]]
+        );
+        config.mempool.gas_price_buckets = vec![0, 150, 150];
+        assert_eq!(config.violations().len(), 1);
+
+        config.mempool.gas_price_buckets = vec![0, 150, 300];
+        config.sanitize().unwrap();
+    }
+
+    #[test]
+    fn test_round_timeout_backoff() {
+        let mut config = NodeConfig::default();
+        config.consensus.round_timeout_backoff.max_exponent = 32;
+        assert_eq!(config.violations().len(), 1);
+        config.consensus.round_timeout_backoff.max_exponent = 31;
+        config.consensus.round_timeout_backoff.exponent_base = 3.0;
+        assert_eq!(config.violations().len(), 1);
+        config.consensus.round_timeout_backoff.exponent_base = 0.5;
+        assert_eq!(config.violations().len(), 1);
+
+        config.consensus.round_timeout_backoff.exponent_base = 1.5;
+        config.sanitize().unwrap();
+    }
+
+    #[test]
+    fn test_admin_service() {
+        let mut config = NodeConfig::default();
+        config.admin_service.enabled = true;
+        assert_eq!(config.violations().len(), 1);
+
+        config.admin_service.auth_token = Some("token".to_string());
+        config.sanitize().unwrap();
+    }
+
+    #[test]
+    fn test_telemetry() {
+        let mut config = NodeConfig::default();
+        config.telemetry.enabled = true;
+        config.telemetry.report_interval_secs = 0;
+        config.telemetry.fields.push("peer_id".to_string());
+        assert_eq!(
+            violated_fields(&config),
+            vec![
+                vec!["telemetry.enabled", "telemetry.endpoint"],
+                vec!["telemetry.report_interval_secs"],
+                vec!["telemetry.fields"],
+            ]
+        );
+
+        config.telemetry.endpoint = Some("https://telemetry.example.com/report".to_string());
+        config.telemetry.report_interval_secs = 1;
+        config.telemetry.fields.pop();
+        config.sanitize().unwrap();
+    }
+}
@@ -81,6 +81,9 @@ pub struct VaultConfig {
     pub token: Token,
     /// Disable check-and-set when writing secrets to Vault
     pub disable_cas: Option<bool>,
+    /// Keep the keys in Vault's transit engine: the keys are created non-exportable and are
+    /// never exported, so every signature is produced by the transit sign API.
+    pub disable_key_export: Option<bool>,
     /// Timeout for new vault socket connections, in milliseconds.
     pub connection_timeout_ms: Option<u64>,
     /// Timeout for generic vault operations (e.g., reads and writes), in milliseconds.
@@ -95,6 +98,10 @@ impl VaultConfig {
             .ok_or(Error::Missing("ca_certificate"))?;
         read_file(path)
     }
+
+    pub fn key_export_disabled(&self) -> bool {
+        self.disable_key_export.unwrap_or(false)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
@@ -212,6 +219,7 @@ impl From<&SecureBackend> for Storage {
                         .map(|_| config.ca_certificate().unwrap()),
                     config.renew_ttl_secs,
                     config.disable_cas.map_or_else(|| true, |disable| !disable),
+                    config.key_export_disabled(),
                     config.connection_timeout_ms,
                     config.response_timeout_ms,
                 ));
@@ -244,6 +252,7 @@ mod tests {
                 token: Token::FromConfig("test".to_string()),
                 renew_ttl_secs: None,
                 disable_cas: None,
+                disable_key_export: None,
                 connection_timeout_ms: None,
                 response_timeout_ms: None,
             },
@@ -272,6 +281,7 @@ vault:
                 token: Token::FromConfig("test".to_string()),
                 renew_ttl_secs: None,
                 disable_cas: None,
+                disable_key_export: None,
                 connection_timeout_ms: Some(3000),
                 response_timeout_ms: Some(5000),
             },
@@ -302,6 +312,7 @@ vault:
                 token: Token::FromDisk(PathBuf::from("/token")),
                 renew_ttl_secs: None,
                 disable_cas: None,
+                disable_key_export: None,
                 connection_timeout_ms: None,
                 response_timeout_ms: None,
             },
@@ -8,8 +8,17 @@ use serde::{Deserialize, Serialize};
 pub struct StateSyncConfig {
     // Size of chunk to request for state synchronization
     pub chunk_limit: u64,
+    // Maximum number of chunks requested concurrently from different peers while catching up to
+    // a target in the current epoch. The chunks received ahead are buffered and applied in order.
+    // A value of 1 only requests the next chunk.
+    pub chunk_request_window: u64,
     // The timeout of the state sync client to process a commit notification (in milliseconds)
     pub client_commit_timeout_ms: u64,
+    // The config of the data streaming service of state sync v2
+    pub data_streaming_service: DataStreamingServiceConfig,
+    // Whether a full node with only the genesis transaction first syncs to a recent state snapshot
+    // downloaded from its peers, instead of executing all the transactions since genesis
+    pub fast_sync_enabled: bool,
     // default timeout used for long polling to remote peer
     pub long_poll_timeout_ms: u64,
     // valid maximum chunk limit for sanity check
@@ -22,6 +31,8 @@ pub struct StateSyncConfig {
     // if no progress is made by sending chunk requests to a number of networks,
     // the next sync request will be multicasted, i.e. sent to more networks
     pub multicast_timeout_ms: u64,
+    // Number of accounts to request per chunk of a state snapshot
+    pub snapshot_chunk_limit: u64,
     // The timeout for ensuring sync requests are making progress (i.e., the maximum time between
     // commits when processing a sync request).
     pub sync_request_timeout_ms: u64,
@@ -33,14 +44,49 @@ impl Default for StateSyncConfig {
     fn default() -> Self {
         Self {
             chunk_limit: 1000,
+            chunk_request_window: 1,
             client_commit_timeout_ms: 5_000,
+            data_streaming_service: DataStreamingServiceConfig::default(),
+            fast_sync_enabled: false,
             long_poll_timeout_ms: 10_000,
             max_chunk_limit: 1000,
             max_timeout_ms: 120_000,
             mempool_commit_timeout_ms: 5_000,
             multicast_timeout_ms: 30_000,
+            snapshot_chunk_limit: 1000,
             sync_request_timeout_ms: 60_000,
             tick_interval_ms: 100,
         }
     }
 }
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DataStreamingServiceConfig {
+    // Maximum number of chunks of a data stream fetched concurrently
+    pub max_concurrent_requests: u64,
+    // Maximum number of notifications a data stream buffers before its consumer reads them. The
+    // stream stops fetching while its buffer is full.
+    pub max_data_stream_channel_size: u64,
+    // Maximum number of epoch ending ledger infos per chunk
+    pub max_epoch_chunk_size: u64,
+    // Maximum number of times a chunk is requested again after a failed request
+    pub max_request_retry: u64,
+    // Maximum number of transactions per chunk
+    pub max_transaction_chunk_size: u64,
+    // Maximum number of transaction outputs per chunk
+    pub max_transaction_output_chunk_size: u64,
+}
+
+impl Default for DataStreamingServiceConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_requests: 3,
+            max_data_stream_channel_size: 50,
+            max_epoch_chunk_size: 100,
+            max_request_retry: 3,
+            max_transaction_chunk_size: 1000,
+            max_transaction_output_chunk_size: 1000,
+        }
+    }
+}
@@ -4,18 +4,68 @@
 use crate::utils;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     net::{IpAddr, Ipv4Addr, SocketAddr},
     path::PathBuf,
 };
 
+/// Compression of the blocks of the SST files of a column family.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RocksdbCompressionType {
+    None,
+    Lz4,
+    /// Smaller than Lz4 for the same decompression speed, but slower to compress
+    Lz4hc,
+}
+
+/// Compaction style of a column family, see https://github.com/facebook/rocksdb/wiki/Compaction.
+/// FIFO compaction isn't offered, as it deletes the oldest data of the ledger.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RocksdbCompactionStyle {
+    Level,
+    Universal,
+}
+
+/// Overrides for one column family of the column family options of `RocksdbConfig`, None keeps
+/// the value of `RocksdbConfig`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RocksdbColumnFamilyConfig {
+    pub compression: Option<RocksdbCompressionType>,
+    pub compaction_style: Option<RocksdbCompactionStyle>,
+    pub write_buffer_size: Option<usize>,
+}
+
 /// Port selected RocksDB options for tuning underlying rocksdb instance of DiemDB.
 /// see https://github.com/facebook/rocksdb/blob/master/include/rocksdb/options.h
 /// for detailed explanations.
-#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct RocksdbConfig {
     pub max_open_files: i32,
     pub max_total_wal_size: u64,
+    /// Archived WAL files older than this are deleted, 0 disables the TTL.
+    pub wal_ttl_seconds: u64,
+    /// Archived WAL files are deleted once they add up to more than this, 0 disables the limit.
+    pub wal_size_limit_mb: u64,
+    /// Size in bytes of the LRU block cache shared by all the column families. None keeps the
+    /// RocksDB default of a 8MB cache per column family.
+    pub block_cache_size: Option<usize>,
+    /// Options of all the column families, unless overridden in `column_families`
+    pub compression: RocksdbCompressionType,
+    pub compaction_style: RocksdbCompactionStyle,
+    /// None keeps the RocksDB default (64MB).
+    pub write_buffer_size: Option<usize>,
+    /// Overrides by column family name, e.g. to compress the rarely read column families of an
+    /// archival node harder. DiemDB refuses to open with an override of an unknown column family.
+    pub column_families: BTreeMap<String, RocksdbColumnFamilyConfig>,
+    /// Writes each commit in the background while the next commit is being built, the reads
+    /// waiting for it meanwhile, and a failed write failing the next commit. The latest commit can
+    /// be lost on a crash, which is fine on a node syncing from its peers, e.g. an archival one
+    /// catching up.
+    pub pipelined_commit: bool,
 }
 
 impl Default for RocksdbConfig {
@@ -28,10 +78,41 @@ impl Default for RocksdbConfig {
             // families are updated at non-uniform frequencies.
             #[allow(clippy::integer_arithmetic)] // TODO: remove once clippy lint fixed
             max_total_wal_size: 1u64 << 30,
+            wal_ttl_seconds: 0,
+            wal_size_limit_mb: 0,
+            block_cache_size: None,
+            compression: RocksdbCompressionType::Lz4,
+            compaction_style: RocksdbCompactionStyle::Level,
+            write_buffer_size: None,
+            column_families: BTreeMap::new(),
+            pipelined_commit: false,
         }
     }
 }
 
+impl RocksdbConfig {
+    pub fn compression(&self, cf_name: &str) -> RocksdbCompressionType {
+        self.column_families
+            .get(cf_name)
+            .and_then(|cf_config| cf_config.compression)
+            .unwrap_or(self.compression)
+    }
+
+    pub fn compaction_style(&self, cf_name: &str) -> RocksdbCompactionStyle {
+        self.column_families
+            .get(cf_name)
+            .and_then(|cf_config| cf_config.compaction_style)
+            .unwrap_or(self.compaction_style)
+    }
+
+    pub fn write_buffer_size(&self, cf_name: &str) -> Option<usize> {
+        self.column_families
+            .get(cf_name)
+            .and_then(|cf_config| cf_config.write_buffer_size)
+            .or(self.write_buffer_size)
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct StorageConfig {
@@ -42,6 +123,17 @@ pub struct StorageConfig {
     /// None disables pruning. The windows is in number of versions, consider system tps
     /// (transaction per second) when calculating proper window.
     pub prune_window: Option<u64>,
+    /// None disables pruning of the transactions, which is the default as peers syncing from
+    /// scratch fetch them. The window is in number of versions, like `prune_window`.
+    pub transaction_prune_window: Option<u64>,
+    /// None disables pruning of the events, which is the default. The window is in number of
+    /// versions, like `prune_window`.
+    pub event_prune_window: Option<u64>,
+    /// None disables the archiving of the transactions and events, which is the default. When set,
+    /// the ones older than this number of versions are moved out of RocksDB into compressed archive
+    /// files, still served but slower, keeping the RocksDB dir of an archival fullnode small.
+    /// Can't be combined with `transaction_prune_window` or `event_prune_window`.
+    pub archive_window: Option<u64>,
     #[serde(skip)]
     data_dir: PathBuf,
     /// Read, Write, Connect timeout for network operations in milliseconds
@@ -64,6 +156,9 @@ impl Default for StorageConfig {
             // conservatively safe minimal prune window. It'll take a few Gigabytes of disk space
             // depending on the size of an average account blob.
             prune_window: Some(1_000_000),
+            transaction_prune_window: None,
+            event_prune_window: None,
+            archive_window: None,
             data_dir: PathBuf::from("/opt/diem/data"),
             // Default read/write/connection timeout, in milliseconds
             timeout_ms: 30_000,
@@ -91,3 +186,54 @@ impl StorageConfig {
             .set_port(utils::get_available_port());
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rocksdb_column_family_overrides() {
+        let config: RocksdbConfig = serde_yaml::from_str(
+            r#"
+            compression: none
+            write_buffer_size: 1024
+            column_families:
+                transaction:
+                    compression: lz4hc
+                    compaction_style: universal
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.max_open_files,
+            RocksdbConfig::default().max_open_files
+        );
+        assert_eq!(
+            config.compression("transaction"),
+            RocksdbCompressionType::Lz4hc
+        );
+        assert_eq!(
+            config.compaction_style("transaction"),
+            RocksdbCompactionStyle::Universal
+        );
+        assert_eq!(config.write_buffer_size("transaction"), Some(1024));
+        assert_eq!(config.compression("event"), RocksdbCompressionType::None);
+        assert_eq!(
+            config.compaction_style("event"),
+            RocksdbCompactionStyle::Level
+        );
+    }
+    #[test]
+    fn test_rocksdb_fifo_compaction_is_refused() {
+        assert!(serde_yaml::from_str::<RocksdbConfig>("compaction_style: fifo").is_err());
+        assert!(serde_yaml::from_str::<RocksdbConfig>(
+            r#"
+            column_families:
+                transaction:
+                    compaction_style: fifo
+            "#
+        )
+        .is_err());
+    }
+}
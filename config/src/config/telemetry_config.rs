@@ -0,0 +1,48 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// The fields a telemetry report can carry. Nothing else is ever sent, whatever the config asks.
+pub const TELEMETRY_FIELDS: &[&str] = &[
+    // Revision the node was built from
+    "version",
+    "role",
+    // Latest version synced, and how far it's behind the highest version known
+    "synced_version",
+    "sync_lag",
+    // Number of connected peers per network
+    "peers",
+    // Errors logged per second since the previous report
+    "error_rate",
+];
+
+/// Opt-in reports of the anonymized health of the node to a telemetry service, for operators
+/// running many nodes.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    pub enabled: bool,
+    // URL the reports are posted to, as a JSON array
+    pub endpoint: Option<String>,
+    pub report_interval_secs: u64,
+    // Reports kept while the endpoint can't be reached, the oldest are dropped past it
+    pub buffer_size: usize,
+    // Fields of the reports, among `TELEMETRY_FIELDS`
+    pub fields: Vec<String>,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> TelemetryConfig {
+        TelemetryConfig {
+            enabled: false,
+            endpoint: None,
+            report_interval_secs: 60,
+            buffer_size: 60,
+            fields: TELEMETRY_FIELDS
+                .iter()
+                .map(|field| field.to_string())
+                .collect(),
+        }
+    }
+}
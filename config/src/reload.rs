@@ -0,0 +1,320 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reload of the config of a running node.
+//!
+//! The components subscribe with the fields of the config they can apply without a restart, e.g.
+//! `mempool.capacity`. A reload re-reads and validates the config file, then rejects it if it
+//! changes any field nobody subscribed to, or notifies every subscriber of the changes of its
+//! fields otherwise. If a subscriber fails to apply its changes, the reload fails and the config
+//! isn't recorded as applied, so that the next reload applies the changes again.
+
+use crate::config::{Error, NodeConfig};
+use diem_infallible::Mutex;
+use serde::Serialize;
+use serde_yaml::Value;
+use std::path::PathBuf;
+
+/// A field of the config changed by a reload.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ConfigChange {
+    /// Keys of the field and of its parents, joined with dots, e.g. `mempool.capacity`
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+impl ConfigChange {
+    /// Whether the field changed is `field` or one of its children. A `*` key of `field` matches
+    /// any key, e.g. `full_node_networks.*.max_inbound_connections` matches the field of every
+    /// full node network.
+    pub fn is_in(&self, field: &str) -> bool {
+        let mut keys = self.field.split('.');
+        field
+            .split('.')
+            .all(|pattern| matches!(keys.next(), Some(key) if pattern == "*" || pattern == key))
+    }
+}
+
+/// Called with the reloaded config and the changes of the fields of the subscriber, returns why
+/// they couldn't be applied on failure.
+type ReloadCallback = Box<dyn Fn(&NodeConfig, &[ConfigChange]) -> Result<(), String> + Send + Sync>;
+
+struct Subscriber {
+    name: &'static str,
+    fields: Vec<&'static str>,
+    callback: ReloadCallback,
+}
+
+pub struct ConfigReloader {
+    path: PathBuf,
+    config: Mutex<NodeConfig>,
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+impl ConfigReloader {
+    /// `config` is the one the node was started with, loaded from `path`.
+    pub fn new(path: PathBuf, config: NodeConfig) -> Self {
+        Self {
+            path,
+            config: Mutex::new(config),
+            subscribers: Mutex::new(vec![]),
+        }
+    }
+
+    /// Registers the fields `name` can reload, and the callback applying their changes. The
+    /// callbacks are called in turn on reloads, so they shouldn't block. As a failed reload is
+    /// applied again by the next one, applying the same changes twice must be harmless.
+    pub fn subscribe(
+        &self,
+        name: &'static str,
+        fields: &[&'static str],
+        callback: impl Fn(&NodeConfig, &[ConfigChange]) -> Result<(), String> + Send + Sync + 'static,
+    ) {
+        self.subscribers.lock().push(Subscriber {
+            name,
+            fields: fields.to_vec(),
+            callback: Box::new(callback),
+        });
+    }
+
+    /// Returns the config currently applied.
+    pub fn config(&self) -> NodeConfig {
+        self.config.lock().clone()
+    }
+
+    /// Re-reads the config file and applies its changes, which are returned.
+    pub fn reload(&self) -> Result<Vec<ConfigChange>, Error> {
+        let new_config = NodeConfig::load(&self.path)?;
+        self.apply(new_config)
+    }
+
+    /// Applies the changes of `new_config`, if the subscribers can reload all of them. The config
+    /// is recorded as applied only if every subscriber applied its changes.
+    pub fn apply(&self, new_config: NodeConfig) -> Result<Vec<ConfigChange>, Error> {
+        // Held until the subscribers are notified, for the reloads not to interleave
+        let mut config = self.config.lock();
+        let changes = diff(&config, &new_config)?;
+
+        let subscribers = self.subscribers.lock();
+        let unreloadable: Vec<_> = changes
+            .iter()
+            .filter(|change| {
+                !subscribers
+                    .iter()
+                    .flat_map(|subscriber| subscriber.fields.iter())
+                    .any(|field| change.is_in(field))
+            })
+            .map(|change| change.field.clone())
+            .collect();
+        if !unreloadable.is_empty() {
+            return Err(Error::Unreloadable(unreloadable));
+        }
+
+        let mut failures = vec![];
+        for subscriber in subscribers.iter() {
+            let subscriber_changes: Vec<_> = changes
+                .iter()
+                .filter(|change| subscriber.fields.iter().any(|field| change.is_in(field)))
+                .cloned()
+                .collect();
+            if !subscriber_changes.is_empty() {
+                diem_logger::info!(
+                    subscriber = subscriber.name,
+                    changes = subscriber_changes,
+                    "Reloading config"
+                );
+                if let Err(error) = (subscriber.callback)(&new_config, &subscriber_changes) {
+                    failures.push(format!("{}: {}", subscriber.name, error));
+                }
+            }
+        }
+        if !failures.is_empty() {
+            return Err(Error::ReloadFailed(failures));
+        }
+        *config = new_config;
+        Ok(changes)
+    }
+}
+
+/// Returns the fields changed from `old` to `new`.
+pub fn diff(old: &NodeConfig, new: &NodeConfig) -> Result<Vec<ConfigChange>, Error> {
+    let to_value = |config: &NodeConfig| {
+        serde_yaml::to_value(config).map_err(|e| Error::Yaml("NodeConfig".to_string(), e))
+    };
+    let mut changes = vec![];
+    diff_values("", &to_value(old)?, &to_value(new)?, &mut changes);
+    Ok(changes)
+}
+
+fn diff_values(field: &str, old: &Value, new: &Value, changes: &mut Vec<ConfigChange>) {
+    match (old, new) {
+        (Value::Mapping(old_fields), Value::Mapping(new_fields)) => {
+            let old_keys = old_fields.iter().map(|(key, _)| key);
+            let added_keys = new_fields
+                .iter()
+                .map(|(key, _)| key)
+                .filter(|key| !old_fields.contains_key(key));
+            for key in old_keys.chain(added_keys) {
+                let child = match field {
+                    "" => key_name(key),
+                    _ => format!("{}.{}", field, key_name(key)),
+                };
+                diff_values(
+                    &child,
+                    old_fields.get(key).unwrap_or(&Value::Null),
+                    new_fields.get(key).unwrap_or(&Value::Null),
+                    changes,
+                );
+            }
+        }
+        // The elements of lists of the same length are compared one by one, e.g. for a change of a
+        // single network to be reloadable
+        (Value::Sequence(old_elements), Value::Sequence(new_elements))
+            if old_elements.len() == new_elements.len() =>
+        {
+            for (index, (old, new)) in old_elements.iter().zip(new_elements).enumerate() {
+                diff_values(&format!("{}.{}", field, index), old, new, changes);
+            }
+        }
+        _ if old != new => changes.push(ConfigChange {
+            field: field.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => (),
+    }
+}
+
+fn key_name(key: &Value) -> String {
+    match key {
+        Value::String(key) => key.clone(),
+        key => serde_yaml::to_string(key)
+            .map(|key| key.trim_start_matches("---").trim().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+
+    fn changed_fields(changes: &[ConfigChange]) -> Vec<&str> {
+        changes.iter().map(|change| change.field.as_str()).collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = NodeConfig::default();
+        assert!(diff(&old, &old).unwrap().is_empty());
+
+        let mut new = old.clone();
+        new.mempool.capacity += 1;
+        new.logger.level = diem_logger::Level::Debug;
+        new.failpoints = Some(
+            vec![("point".to_string(), "off".to_string())]
+                .into_iter()
+                .collect(),
+        );
+        let changes = diff(&old, &new).unwrap();
+        assert_eq!(
+            changed_fields(&changes),
+            vec!["logger.level", "mempool.capacity", "failpoints"]
+        );
+        assert_eq!(changes[1].old, Value::from(old.mempool.capacity));
+        assert_eq!(changes[1].new, Value::from(new.mempool.capacity));
+    }
+
+    #[test]
+    fn test_change_is_in() {
+        let change = ConfigChange {
+            field: "mempool.capacity_per_user".to_string(),
+            old: Value::Null,
+            new: Value::Null,
+        };
+        assert!(change.is_in("mempool"));
+        assert!(change.is_in("mempool.capacity_per_user"));
+        assert!(!change.is_in("mempool.capacity"));
+        assert!(!change.is_in("mempool.capacity_per_user.value"));
+        assert!(change.is_in("*.capacity_per_user"));
+        assert!(!change.is_in("*.capacity"));
+    }
+
+    #[test]
+    fn test_diff_lists() {
+        let mut old = NodeConfig::default();
+        old.full_node_networks = vec![Default::default(), Default::default()];
+        let mut new = old.clone();
+        new.full_node_networks[1].max_inbound_connections += 1;
+        let changes = diff(&old, &new).unwrap();
+        assert_eq!(
+            changed_fields(&changes),
+            vec!["full_node_networks.1.max_inbound_connections"]
+        );
+        assert!(changes[0].is_in("full_node_networks.*.max_inbound_connections"));
+
+        // lists of different lengths change as a whole
+        new.full_node_networks.pop();
+        assert_eq!(
+            changed_fields(&diff(&old, &new).unwrap()),
+            vec!["full_node_networks"]
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let old = NodeConfig::default();
+        let reloader = ConfigReloader::new(PathBuf::new(), old.clone());
+        let notified = Arc::new(Mutex::new(vec![]));
+        let subscriber_notified = notified.clone();
+        reloader.subscribe(
+            "mempool",
+            &["mempool.capacity", "mempool.capacity_per_user"],
+            move |config, changes| {
+                assert_eq!(config.mempool.capacity, old.mempool.capacity * 2);
+                subscriber_notified.lock().extend(changes.iter().cloned());
+                Ok(())
+            },
+        );
+        reloader.subscribe("logger", &["logger.level"], |_, _| {
+            panic!("nothing to reload")
+        });
+        reloader.subscribe("state_sync", &["state_sync.max_chunk_limit"], |_, _| {
+            Err("can't apply".to_string())
+        });
+
+        let mut new = reloader.config();
+        new.mempool.capacity *= 2;
+        assert_eq!(
+            changed_fields(&reloader.apply(new.clone()).unwrap()),
+            vec!["mempool.capacity"]
+        );
+        assert_eq!(changed_fields(&notified.lock()), vec!["mempool.capacity"]);
+        assert_eq!(reloader.config(), new);
+
+        // rejected as a whole if a field can't be reloaded
+        let mut rejected = new.clone();
+        rejected.mempool.capacity_per_user += 1;
+        rejected.mempool.capacity_bytes += 1;
+        match reloader.apply(rejected) {
+            Err(Error::Unreloadable(fields)) => {
+                assert_eq!(fields, vec!["mempool.capacity_bytes".to_string()])
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert_eq!(notified.lock().len(), 1);
+        assert_eq!(reloader.config(), new);
+
+        // not recorded as applied if a subscriber fails to apply its changes
+        let mut failed = new.clone();
+        failed.state_sync.max_chunk_limit += 1;
+        match reloader.apply(failed) {
+            Err(Error::ReloadFailed(failures)) => {
+                assert_eq!(failures, vec!["state_sync: can't apply".to_string()])
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert_eq!(reloader.config(), new);
+    }
+}
@@ -0,0 +1,235 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Aggregation of the signatures of a quorum over the same message.
+//!
+//! Signers are identified by their index in the (address ordered) validator set, which keeps the
+//! certificates smaller than a map from author to signature. Ed25519 signatures cannot be combined
+//! into a single one, so the aggregate keeps one signature per signer and verifies them in batch;
+//! with a BLS or multi-signature scheme they fold into a single signature as votes come in.
+
+use crate::common::Author;
+use anyhow::{anyhow, ensure};
+use diem_bitvec::BitVec;
+use diem_crypto::{ed25519::Ed25519Signature, hash::CryptoHash};
+use diem_types::validator_verifier::{ValidatorVerifier, VerifyError};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, convert::TryFrom};
+
+/// The largest validator set whose signers fit the bitmask of an aggregated signature.
+pub const MAX_VALIDATOR_SET_SIZE: usize = u8::MAX as usize + 1;
+
+/// Signatures of a set of validators over the same message.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AggregateSignature {
+    /// Bit i is set if the i-th validator of the validator set signed.
+    validator_bitmask: BitVec,
+    /// The signatures, in the order of the validator set.
+    signatures: Vec<Ed25519Signature>,
+}
+
+impl AggregateSignature {
+    /// Aggregates the signatures of the given authors, all of them must be in the validator set.
+    pub fn aggregate(
+        signatures: &BTreeMap<Author, Ed25519Signature>,
+        validator: &ValidatorVerifier,
+    ) -> anyhow::Result<Self> {
+        let mut validator_bitmask = BitVec::default();
+        let mut aggregated = Vec::with_capacity(signatures.len());
+        for (index, author) in validator.get_ordered_account_addresses_iter().enumerate() {
+            if let Some(signature) = signatures.get(&author) {
+                let index = u8::try_from(index)
+                    .map_err(|_| anyhow!("Validator index {} doesn't fit the bitmask", index))?;
+                validator_bitmask.set(index);
+                aggregated.push(signature.clone());
+            }
+        }
+        ensure!(
+            aggregated.len() == signatures.len(),
+            "Signatures from authors outside of the validator set"
+        );
+        Ok(Self {
+            validator_bitmask,
+            signatures: aggregated,
+        })
+    }
+
+    pub fn validator_bitmask(&self) -> &BitVec {
+        &self.validator_bitmask
+    }
+
+    pub fn num_signers(&self) -> usize {
+        self.signatures.len()
+    }
+
+    /// Returns the authors of the signatures, in the order of the validator set.
+    pub fn get_signers(&self, validator: &ValidatorVerifier) -> Vec<Author> {
+        validator
+            .get_ordered_account_addresses_iter()
+            .take(MAX_VALIDATOR_SET_SIZE)
+            .enumerate()
+            .filter(|(index, _)| self.validator_bitmask.is_set(*index as u8))
+            .map(|(_, author)| author)
+            .collect()
+    }
+
+    /// Expands the aggregate into the signature of each signer.
+    pub fn to_signatures(
+        &self,
+        validator: &ValidatorVerifier,
+    ) -> Result<BTreeMap<Author, Ed25519Signature>, VerifyError> {
+        let signers = self.get_signers(validator);
+        // bits set beyond the validator set
+        if signers.len() != self.validator_bitmask.count_ones() as usize {
+            return Err(VerifyError::UnknownAuthor);
+        }
+        if signers.len() != self.signatures.len() {
+            return Err(VerifyError::InvalidSignature);
+        }
+        Ok(signers
+            .into_iter()
+            .zip(self.signatures.iter().cloned())
+            .collect())
+    }
+
+    /// Verifies that a quorum of the validator set signed the message.
+    pub fn verify<T: CryptoHash + Serialize>(
+        &self,
+        message: &T,
+        validator: &ValidatorVerifier,
+    ) -> Result<(), VerifyError> {
+        validator.batch_verify_aggregated_signatures(message, &self.to_signatures(validator)?)
+    }
+}
+
+/// Collects the signatures of the votes for the same message as they come in, keeping track of
+/// their voting power so that a quorum is detected without recounting all the signers.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PartialSignatures {
+    signatures: BTreeMap<Author, Ed25519Signature>,
+    voting_power: u64,
+}
+
+impl PartialSignatures {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn signatures(&self) -> &BTreeMap<Author, Ed25519Signature> {
+        &self.signatures
+    }
+
+    pub fn voting_power(&self) -> u64 {
+        self.voting_power
+    }
+
+    /// Adds the (already verified) signature of the author and returns the voting power of all
+    /// the signatures collected so far.
+    pub fn add_signature(
+        &mut self,
+        author: Author,
+        signature: Ed25519Signature,
+        validator: &ValidatorVerifier,
+    ) -> Result<u64, VerifyError> {
+        let voting_power = validator
+            .get_voting_power(&author)
+            .ok_or(VerifyError::UnknownAuthor)?;
+        if self.signatures.insert(author, signature).is_none() {
+            self.voting_power = self.voting_power.saturating_add(voting_power);
+        }
+        Ok(self.voting_power)
+    }
+
+    /// Whether the collected signatures reach the quorum voting power of the validator set.
+    pub fn has_quorum(&self, validator: &ValidatorVerifier) -> bool {
+        self.voting_power >= validator.quorum_voting_power()
+    }
+
+    pub fn aggregate(&self, validator: &ValidatorVerifier) -> anyhow::Result<AggregateSignature> {
+        AggregateSignature::aggregate(&self.signatures, validator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregated_quorum_cert() {
+        use crate::{quorum_cert::QuorumCert, vote_data::VoteData};
+        use diem_types::{
+            block_info::BlockInfo, ledger_info::LedgerInfo,
+            validator_verifier::random_validator_verifier,
+        };
+
+        let (signers, validators) = random_validator_verifier(4, None, false);
+        let vote_data = VoteData::new(BlockInfo::random(1), BlockInfo::random(0));
+        let ledger_info = LedgerInfo::new(BlockInfo::empty(), vote_data.hash());
+
+        let mut partial_sigs = PartialSignatures::new();
+        for signer in &signers[0..2] {
+            let voting_power = partial_sigs
+                .add_signature(signer.author(), signer.sign(&ledger_info), &validators)
+                .unwrap();
+            assert_eq!(voting_power, partial_sigs.voting_power());
+            assert!(!partial_sigs.has_quorum(&validators));
+        }
+        // duplicate signatures don't add voting power
+        partial_sigs
+            .add_signature(
+                signers[0].author(),
+                signers[0].sign(&ledger_info),
+                &validators,
+            )
+            .unwrap();
+        assert_eq!(partial_sigs.voting_power(), 2);
+        partial_sigs
+            .add_signature(
+                signers[2].author(),
+                signers[2].sign(&ledger_info),
+                &validators,
+            )
+            .unwrap();
+        assert!(partial_sigs.has_quorum(&validators));
+
+        let aggregated = partial_sigs.aggregate(&validators).unwrap();
+        assert_eq!(aggregated.num_signers(), 3);
+        assert_eq!(
+            aggregated.to_signatures(&validators).unwrap(),
+            *partial_sigs.signatures()
+        );
+        aggregated.verify(&ledger_info, &validators).unwrap();
+        let qc = QuorumCert::from_aggregate_signature(
+            vote_data.clone(),
+            ledger_info.clone(),
+            &aggregated,
+            &validators,
+        )
+        .unwrap();
+        assert_eq!(qc.aggregate_signature(&validators).unwrap(), aggregated);
+
+        // a signature from outside of the validator set can't be aggregated
+        let (other_signers, _) = random_validator_verifier(1, None, false);
+        partial_sigs
+            .add_signature(
+                other_signers[0].author(),
+                other_signers[0].sign(&ledger_info),
+                &validators,
+            )
+            .unwrap_err();
+        let mut signatures = partial_sigs.signatures().clone();
+        signatures.insert(
+            other_signers[0].author(),
+            other_signers[0].sign(&ledger_info),
+        );
+        AggregateSignature::aggregate(&signatures, &validators).unwrap_err();
+
+        // not enough voting power
+        let mut signatures = partial_sigs.signatures().clone();
+        signatures.remove(&signers[0].author());
+        let aggregated = AggregateSignature::aggregate(&signatures, &validators).unwrap();
+        aggregated.verify(&ledger_info, &validators).unwrap_err();
+        QuorumCert::from_aggregate_signature(vote_data, ledger_info, &aggregated, &validators)
+            .unwrap_err();
+    }
+}
@@ -2,12 +2,17 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    block_data::{BlockData, BlockType},
+    block_data::{BlockData, BlockType, ProposerVrfInput},
     common::{Author, Payload, Round},
     quorum_cert::QuorumCert,
 };
 use anyhow::{bail, ensure, format_err};
-use diem_crypto::{ed25519::Ed25519Signature, hash::CryptoHash, HashValue};
+use diem_crypto::{
+    ecvrf::{VRFProof, VRFPublicKey},
+    ed25519::Ed25519Signature,
+    hash::CryptoHash,
+    HashValue,
+};
 use diem_infallible::duration_since_epoch;
 use diem_types::{
     account_address::AccountAddress,
@@ -21,7 +26,10 @@ use diem_types::{
 };
 use mirai_annotations::debug_checked_verify_eq;
 use serde::{Deserialize, Deserializer, Serialize};
-use std::fmt::{self, Display, Formatter};
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display, Formatter},
+};
 
 #[path = "block_test_utils.rs"]
 #[cfg(any(test, feature = "fuzzing"))]
@@ -93,6 +101,10 @@ impl Block {
         self.block_data.payload()
     }
 
+    pub fn vrf_proof(&self) -> Option<&VRFProof> {
+        self.block_data.vrf_proof()
+    }
+
     pub fn quorum_cert(&self) -> &QuorumCert {
         self.block_data.quorum_cert()
     }
@@ -231,6 +243,23 @@ impl Block {
                 validator.verify(*author, &self.block_data, signature)?;
                 self.quorum_cert().verify(validator)
             }
+            BlockType::VrfProposal {
+                author, vrf_proof, ..
+            } => {
+                let signature = self
+                    .signature
+                    .as_ref()
+                    .ok_or_else(|| format_err!("Missing signature in Proposal"))?;
+                validator.verify(*author, &self.block_data, signature)?;
+                let public_key = validator
+                    .get_public_key(author)
+                    .ok_or_else(|| format_err!("Unknown author {}", author))?;
+                VRFPublicKey::try_from(&public_key)?.verify(
+                    vrf_proof,
+                    &ProposerVrfInput::new(self.epoch(), self.round()).to_bytes(),
+                )?;
+                self.quorum_cert().verify(validator)
+            }
         }
     }
 
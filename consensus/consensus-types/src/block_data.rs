@@ -6,7 +6,10 @@ use crate::{
     quorum_cert::QuorumCert,
     vote_data::VoteData,
 };
-use diem_crypto::hash::HashValue;
+use diem_crypto::{
+    ecvrf::VRFProof,
+    hash::{CryptoHash, HashValue},
+};
 use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
 use diem_types::{
     block_info::BlockInfo,
@@ -32,6 +35,35 @@ pub enum BlockType {
     /// from the previous epoch.  The genesis block is used as the the first root block of the
     /// BlockTree for all epochs.
     Genesis,
+    /// A proposal carrying the VRF proof of its author for the round, which VRF proposer elections
+    /// draw the proposer of the next round from.
+    VrfProposal {
+        /// T of the block (e.g. one or more transaction(s)
+        payload: Payload,
+        /// Author of the block that can be validated by the author's public key and the signature
+        author: Author,
+        /// Proof of the VRF output of the author's consensus key on the `ProposerVrfInput` of the
+        /// epoch and round of the block
+        vrf_proof: VRFProof,
+    },
+}
+
+/// The VRF input of the proposer of a round: the outputs of the proofs of different epochs and
+/// rounds are unrelated, and the hasher separates them from the VRF outputs of other protocols.
+#[derive(Deserialize, Serialize, CryptoHasher, BCSCryptoHash)]
+pub struct ProposerVrfInput {
+    epoch: u64,
+    round: Round,
+}
+
+impl ProposerVrfInput {
+    pub fn new(epoch: u64, round: Round) -> Self {
+        Self { epoch, round }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.hash().to_vec()
+    }
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, CryptoHasher, BCSCryptoHash)]
@@ -69,10 +101,11 @@ pub struct BlockData {
 
 impl BlockData {
     pub fn author(&self) -> Option<Author> {
-        if let BlockType::Proposal { author, .. } = self.block_type {
-            Some(author)
-        } else {
-            None
+        match self.block_type {
+            BlockType::Proposal { author, .. } | BlockType::VrfProposal { author, .. } => {
+                Some(author)
+            }
+            _ => None,
         }
     }
 
@@ -89,8 +122,17 @@ impl BlockData {
     }
 
     pub fn payload(&self) -> Option<&Payload> {
-        if let BlockType::Proposal { payload, .. } = &self.block_type {
-            Some(payload)
+        match &self.block_type {
+            BlockType::Proposal { payload, .. } | BlockType::VrfProposal { payload, .. } => {
+                Some(payload)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn vrf_proof(&self) -> Option<&VRFProof> {
+        if let BlockType::VrfProposal { vrf_proof, .. } = &self.block_type {
+            Some(vrf_proof)
         } else {
             None
         }
@@ -201,6 +243,20 @@ impl BlockData {
         }
     }
 
+    /// Turns a proposal into one carrying the VRF proof of its author for the round, other blocks
+    /// are left unchanged.
+    pub fn with_vrf_proof(mut self, vrf_proof: VRFProof) -> Self {
+        self.block_type = match self.block_type {
+            BlockType::Proposal { payload, author } => BlockType::VrfProposal {
+                payload,
+                author,
+                vrf_proof,
+            },
+            block_type => block_type,
+        };
+        self
+    }
+
     /// It's a reconfiguration suffix block if the parent block's executed state indicates next epoch.
     pub fn is_reconfiguration_suffix(&self) -> bool {
         self.quorum_cert.certified_block().has_reconfiguration()
@@ -43,6 +43,126 @@ impl fmt::Display for BlockRetrievalRequest {
     }
 }
 
+/// Request to stream a chain of blocks of the given length starting from the given block id.
+/// The blocks are sent back in chunks of at most chunk_size blocks, and at most window chunks
+/// are sent ahead of the acknowledgements of the requester.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockRetrievalStreamRequest {
+    stream_id: u64,
+    block_id: HashValue,
+    num_blocks: u64,
+    chunk_size: u64,
+    window: u64,
+}
+
+impl BlockRetrievalStreamRequest {
+    pub fn new(
+        stream_id: u64,
+        block_id: HashValue,
+        num_blocks: u64,
+        chunk_size: u64,
+        window: u64,
+    ) -> Self {
+        Self {
+            stream_id,
+            block_id,
+            num_blocks,
+            chunk_size,
+            window,
+        }
+    }
+    pub fn stream_id(&self) -> u64 {
+        self.stream_id
+    }
+    pub fn block_id(&self) -> HashValue {
+        self.block_id
+    }
+    pub fn num_blocks(&self) -> u64 {
+        self.num_blocks
+    }
+    pub fn chunk_size(&self) -> u64 {
+        self.chunk_size
+    }
+    pub fn window(&self) -> u64 {
+        self.window
+    }
+}
+
+impl fmt::Display for BlockRetrievalStreamRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[BlockRetrievalStreamRequest {} starting from id {} with {} blocks in chunks of {}]",
+            self.stream_id, self.block_id, self.num_blocks, self.chunk_size
+        )
+    }
+}
+
+/// A chunk of a block retrieval stream: the blocks of consecutive chunks form a single chain.
+/// The last chunk of the stream is marked as such, its response status telling whether all the
+/// requested blocks were found.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockRetrievalStreamChunk {
+    stream_id: u64,
+    chunk_index: u64,
+    is_last: bool,
+    response: BlockRetrievalResponse,
+}
+
+impl BlockRetrievalStreamChunk {
+    pub fn new(
+        stream_id: u64,
+        chunk_index: u64,
+        is_last: bool,
+        response: BlockRetrievalResponse,
+    ) -> Self {
+        Self {
+            stream_id,
+            chunk_index,
+            is_last,
+            response,
+        }
+    }
+    pub fn stream_id(&self) -> u64 {
+        self.stream_id
+    }
+    pub fn chunk_index(&self) -> u64 {
+        self.chunk_index
+    }
+    pub fn is_last(&self) -> bool {
+        self.is_last
+    }
+    pub fn response(&self) -> &BlockRetrievalResponse {
+        &self.response
+    }
+    pub fn into_response(self) -> BlockRetrievalResponse {
+        self.response
+    }
+}
+
+/// Acknowledges all the chunks of a stream up to (excluding) num_chunks. Acknowledgements are
+/// cumulative, so that a lost or superseded one is covered by the next.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockRetrievalStreamAck {
+    stream_id: u64,
+    num_chunks: u64,
+}
+
+impl BlockRetrievalStreamAck {
+    pub fn new(stream_id: u64, num_chunks: u64) -> Self {
+        Self {
+            stream_id,
+            num_chunks,
+        }
+    }
+    pub fn stream_id(&self) -> u64 {
+        self.stream_id
+    }
+    pub fn num_chunks(&self) -> u64 {
+        self.num_chunks
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum BlockRetrievalStatus {
     // Successfully fill in the request.
@@ -6,6 +6,7 @@ use crate::{
         block_test_utils::{certificate_for_genesis, *},
         Block,
     },
+    block_data::{BlockData, ProposerVrfInput},
     quorum_cert::QuorumCert,
 };
 use diem_crypto::hash::HashValue;
@@ -98,6 +99,30 @@ fn test_block_relation() {
     assert_eq!(cloned_block.round(), next_block.round());
 }
 
+#[test]
+fn test_vrf_proposal() {
+    let signer = ValidatorSigner::random(None);
+    let verifier = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    let block_data =
+        BlockData::new_proposal(vec![], signer.author(), 1, 1, certificate_for_genesis());
+
+    let input = ProposerVrfInput::new(block_data.epoch(), 1);
+    let vrf_block_data = block_data
+        .clone()
+        .with_vrf_proof(signer.prove_vrf(&input.to_bytes()));
+    let block = Block::new_proposal_from_block_data(vrf_block_data, &signer);
+    assert_eq!(block.author(), Some(signer.author()));
+    assert!(block.payload().is_some());
+    assert!(block.vrf_proof().is_some());
+    assert!(block.validate_signature(&verifier).is_ok());
+
+    // The proof of another round doesn't verify
+    let other_input = ProposerVrfInput::new(block_data.epoch(), 2);
+    let bad_block_data = block_data.with_vrf_proof(signer.prove_vrf(&other_input.to_bytes()));
+    let bad_block = Block::new_proposal_from_block_data(bad_block_data, &signer);
+    assert!(bad_block.validate_signature(&verifier).is_err());
+}
+
 // Ensure that blocks that extend from the same QuorumCertificate but with different signatures
 // have different block ids.
 #[test]
@@ -0,0 +1,49 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::Round;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The messages that are sent again to the validators that didn't acknowledge them, as losing
+/// them delays the progress of the protocol until the next round timeout.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BroadcastKind {
+    // The timeout vote of a round
+    Timeout,
+    // The batch of the commit votes of a round
+    CommitVote,
+}
+
+/// Acknowledges the reception of the message of the given kind for the given epoch and round.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BroadcastAck {
+    kind: BroadcastKind,
+    epoch: u64,
+    round: Round,
+}
+
+impl BroadcastAck {
+    pub fn new(kind: BroadcastKind, epoch: u64, round: Round) -> Self {
+        Self { kind, epoch, round }
+    }
+    pub fn kind(&self) -> BroadcastKind {
+        self.kind
+    }
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+    pub fn round(&self) -> Round {
+        self.round
+    }
+}
+
+impl fmt::Display for BroadcastAck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[BroadcastAck {:?} epoch {} round {}]",
+            self.kind, self.epoch, self.round
+        )
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::{Author, Round};
+use anyhow::{ensure, Context};
+use diem_crypto::ed25519::Ed25519Signature;
+use diem_types::{
+    block_info::BlockInfo, ledger_info::LedgerInfo, validator_verifier::ValidatorVerifier,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Display, Formatter},
+};
+
+/// The commit votes known to a validator for the same LedgerInfo, sent as a single message.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct CommitVoteBatch {
+    ledger_info: LedgerInfo,
+    signatures: BTreeMap<Author, Ed25519Signature>,
+}
+
+// this is required by structured log
+impl Debug for CommitVoteBatch {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Display for CommitVoteBatch {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "CommitVoteBatch: [{} votes, {}]",
+            self.signatures.len(),
+            self.ledger_info
+        )
+    }
+}
+
+impl CommitVoteBatch {
+    pub fn new(ledger_info: LedgerInfo, signatures: BTreeMap<Author, Ed25519Signature>) -> Self {
+        Self {
+            ledger_info,
+            signatures,
+        }
+    }
+
+    /// Return the LedgerInfo all the votes of the batch sign
+    pub fn ledger_info(&self) -> &LedgerInfo {
+        &self.ledger_info
+    }
+
+    pub fn signatures(&self) -> &BTreeMap<Author, Ed25519Signature> {
+        &self.signatures
+    }
+
+    pub fn round(&self) -> Round {
+        self.ledger_info.round()
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.ledger_info.epoch()
+    }
+
+    pub fn commit_info(&self) -> &BlockInfo {
+        self.ledger_info.commit_info()
+    }
+
+    /// Verifies every signature of the batch. Unlike a commit decision, the batch does not need
+    /// to carry a quorum of signatures.
+    pub fn verify(&self, validator: &ValidatorVerifier) -> anyhow::Result<()> {
+        ensure!(!self.signatures.is_empty(), "Empty Commit Vote Batch");
+        ensure!(
+            self.signatures.len() <= validator.len(),
+            "Commit Vote Batch has {} signatures, more than the {} validators",
+            self.signatures.len(),
+            validator.len()
+        );
+        for (author, signature) in &self.signatures {
+            validator
+                .verify(*author, &self.ledger_info, signature)
+                .context("Failed to verify Commit Vote Batch")?;
+        }
+        Ok(())
+    }
+}
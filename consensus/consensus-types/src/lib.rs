@@ -3,9 +3,11 @@
 
 #![forbid(unsafe_code)]
 
+pub mod aggregate_signature;
 pub mod block;
 pub mod block_data;
 pub mod block_retrieval;
+pub mod broadcast_ack;
 pub mod common;
 pub mod epoch_retrieval;
 pub mod executed_block;
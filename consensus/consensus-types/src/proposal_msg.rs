@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{block::Block, common::Author, sync_info::SyncInfo};
-use anyhow::{anyhow, ensure, format_err, Context, Result};
+use anyhow::{anyhow, ensure, Context, Result};
 use diem_types::validator_verifier::ValidatorVerifier;
 use serde::{Deserialize, Serialize};
 use short_hex_str::AsShortHexStr;
@@ -80,15 +80,18 @@ impl ProposalMsg {
     }
 
     pub fn verify(&self, validator: &ValidatorVerifier) -> Result<()> {
+        // the errors keep their causes, for the invalid signatures to be told apart
         self.proposal
             .validate_signature(validator)
-            .map_err(|e| format_err!("{:?}", e))?;
+            .context("Failed to verify the proposal")?;
         // if there is a timeout certificate, verify its signatures
         if let Some(tc) = self.sync_info.highest_timeout_certificate() {
-            tc.verify(validator).map_err(|e| format_err!("{:?}", e))?;
+            tc.verify(validator)
+                .context("Failed to verify the timeout certificate")?;
         }
         if let Some(tc) = self.sync_info.highest_2chain_timeout_cert() {
-            tc.verify(validator).map_err(|e| format_err!("{:?}", e))?;
+            tc.verify(validator)
+                .context("Failed to verify the 2-chain timeout certificate")?;
         }
         // Note that we postpone the verification of SyncInfo until it's being used.
         self.verify_well_formed()
@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::vote_data::VoteData;
+use crate::{aggregate_signature::AggregateSignature, vote_data::VoteData};
 use anyhow::{ensure, Context};
 use diem_crypto::{hash::CryptoHash, HashValue};
 use diem_types::{
@@ -96,6 +96,33 @@ impl QuorumCert {
         )
     }
 
+    /// Aggregated form of the signatures, the signers are identified by their index in the
+    /// validator set instead of their address.
+    pub fn aggregate_signature(
+        &self,
+        validator: &ValidatorVerifier,
+    ) -> anyhow::Result<AggregateSignature> {
+        AggregateSignature::aggregate(self.ledger_info().signatures(), validator)
+    }
+
+    /// Rebuilds a QuorumCert from the aggregated signatures of its LedgerInfo and verifies it.
+    pub fn from_aggregate_signature(
+        vote_data: VoteData,
+        ledger_info: LedgerInfo,
+        signature: &AggregateSignature,
+        validator: &ValidatorVerifier,
+    ) -> anyhow::Result<Self> {
+        let signatures = signature
+            .to_signatures(validator)
+            .context("Fail to expand aggregated QuorumCert signatures")?;
+        let qc = QuorumCert::new(
+            vote_data,
+            LedgerInfoWithSignatures::new(ledger_info, signatures),
+        );
+        qc.verify(validator)?;
+        Ok(qc)
+    }
+
     pub fn verify(&self, validator: &ValidatorVerifier) -> anyhow::Result<()> {
         let vote_hash = self.vote_data.hash();
         ensure!(
@@ -181,6 +181,7 @@ fn create_vault_storage() -> VaultStorage {
         None,
         None,
         true,
+        false,
         None,
         None,
     )
@@ -3,6 +3,7 @@
 
 use crate::{Error, PersistentSafetyStorage};
 use diem_crypto::{
+    ecvrf::VRFProof,
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
     hash::CryptoHash,
 };
@@ -59,6 +60,17 @@ impl ConfigurableValidatorSigner {
             ConfigurableValidatorSigner::Handle(handle) => handle.sign(message, storage),
         }
     }
+
+    /// Proves the VRF output of the consensus key on the given input. Only a signer holding the
+    /// private key can: secure storage backends don't compute VRF proofs.
+    pub fn prove_vrf(&self, alpha: &[u8]) -> Result<VRFProof, Error> {
+        match self {
+            ConfigurableValidatorSigner::Signer(signer) => Ok(signer.prove_vrf(alpha)),
+            ConfigurableValidatorSigner::Handle(_) => Err(Error::VrfProofUnavailable(
+                "the consensus key is only held by secure storage".into(),
+            )),
+        }
+    }
 }
 
 /// A ValidatorHandle associates a validator with a consensus key version held in storage.
@@ -4,12 +4,13 @@
 use crate::{ConsensusState, Error, SafetyRules, TSafetyRules};
 use consensus_types::{
     block_data::BlockData,
+    common::Round,
     timeout::Timeout,
     timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
     vote::Vote,
     vote_proposal::MaybeSignedVoteProposal,
 };
-use diem_crypto::ed25519::Ed25519Signature;
+use diem_crypto::{ecvrf::VRFProof, ed25519::Ed25519Signature};
 use diem_infallible::RwLock;
 use diem_types::{
     epoch_change::EpochChangeProof,
@@ -83,4 +84,8 @@ impl TSafetyRules for LocalClient {
             .write()
             .sign_commit_vote(ledger_info, new_ledger_info)
     }
+
+    fn prove_proposer_vrf(&mut self, round: Round) -> Result<VRFProof, Error> {
+        self.internal.write().prove_proposer_vrf(round)
+    }
 }
@@ -3,11 +3,29 @@
 
 use crate::Error;
 use consensus_types::common::{Author, Round};
-use diem_logger::Schema;
+use diem_config::config::LogRedactionMode;
+use diem_crypto::HashValue;
+use diem_infallible::RwLock;
+use diem_logger::{Key, Schema, Value, Visitor};
 use diem_types::waypoint::Waypoint;
+use once_cell::sync::Lazy;
 use serde::Serialize;
 
-#[derive(Schema)]
+/// The redaction mode applied to every `SafetyLogSchema`, set once safety rules is configured.
+static LOG_REDACTION_MODE: Lazy<RwLock<LogRedactionMode>> =
+    Lazy::new(|| RwLock::new(LogRedactionMode::Disabled));
+
+pub fn set_log_redaction_mode(mode: LogRedactionMode) {
+    *LOG_REDACTION_MODE.write() = mode;
+}
+
+pub fn log_redaction_mode() -> LogRedactionMode {
+    *LOG_REDACTION_MODE.read()
+}
+
+/// Safety rules log entry. Authors, waypoints and rounds are considered sensitive and are
+/// emitted according to the configured `LogRedactionMode`; all other fields are always logged
+/// as is. The key names are identical across modes.
 pub struct SafetyLogSchema<'a> {
     name: LogEntry,
     event: LogEvent,
@@ -15,7 +33,6 @@ pub struct SafetyLogSchema<'a> {
     preferred_round: Option<u64>,
     last_voted_round: Option<u64>,
     epoch: Option<u64>,
-    #[schema(display)]
     error: Option<&'a Error>,
     waypoint: Option<Waypoint>,
     author: Option<Author>,
@@ -35,6 +52,95 @@ impl<'a> SafetyLogSchema<'a> {
             author: None,
         }
     }
+
+    pub fn round(mut self, round: Round) -> Self {
+        self.round = Some(round);
+        self
+    }
+
+    pub fn preferred_round(mut self, preferred_round: u64) -> Self {
+        self.preferred_round = Some(preferred_round);
+        self
+    }
+
+    pub fn last_voted_round(mut self, last_voted_round: u64) -> Self {
+        self.last_voted_round = Some(last_voted_round);
+        self
+    }
+
+    pub fn epoch(mut self, epoch: u64) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    pub fn error(mut self, error: &'a Error) -> Self {
+        self.error = Some(error);
+        self
+    }
+
+    pub fn waypoint(mut self, waypoint: Waypoint) -> Self {
+        self.waypoint = Some(waypoint);
+        self
+    }
+
+    pub fn author(mut self, author: Author) -> Self {
+        self.author = Some(author);
+        self
+    }
+
+    fn visit_with_mode(&self, mode: LogRedactionMode, visitor: &mut dyn Visitor) {
+        visitor.visit_pair(Key::new("name"), Value::from_serde(&self.name));
+        visitor.visit_pair(Key::new("event"), Value::from_serde(&self.event));
+        visit_sensitive(visitor, mode, "round", &self.round);
+        visit_sensitive(visitor, mode, "preferred_round", &self.preferred_round);
+        visit_sensitive(visitor, mode, "last_voted_round", &self.last_voted_round);
+        if let Some(epoch) = &self.epoch {
+            visitor.visit_pair(Key::new("epoch"), Value::from_serde(epoch));
+        }
+        if let Some(error) = &self.error {
+            visitor.visit_pair(Key::new("error"), Value::from_display(error));
+        }
+        visit_sensitive(visitor, mode, "waypoint", &self.waypoint);
+        visit_sensitive(visitor, mode, "author", &self.author);
+    }
+}
+
+impl<'a> Schema for SafetyLogSchema<'a> {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        self.visit_with_mode(log_redaction_mode(), visitor)
+    }
+}
+
+fn visit_sensitive<T: Serialize>(
+    visitor: &mut dyn Visitor,
+    mode: LogRedactionMode,
+    key: &'static str,
+    value: &Option<T>,
+) {
+    let value = match value {
+        Some(value) => value,
+        None => return,
+    };
+    match mode {
+        LogRedactionMode::Disabled => visitor.visit_pair(Key::new(key), Value::from_serde(value)),
+        LogRedactionMode::Hash => {
+            let hash = redacted_hash(value).to_hex();
+            visitor.visit_pair(Key::new(key), Value::from_display(&hash))
+        }
+        LogRedactionMode::Omit => (),
+    }
+}
+
+/// The key of the redacted hashes, drawn at random once per process: without it, hashes of small
+/// values such as rounds could be reversed by hashing all the candidates.
+static REDACTION_SALT: Lazy<HashValue> = Lazy::new(HashValue::random);
+
+/// Hashes the JSON encoding of a sensitive value, keyed with the process salt, so that equal values
+/// still correlate across the log lines of a process without revealing the value itself.
+fn redacted_hash<T: Serialize>(value: &T) -> HashValue {
+    let mut bytes = REDACTION_SALT.to_vec();
+    serde_json::to_writer(&mut bytes, value).expect("Unable to serialize log value");
+    HashValue::sha3_256_of(&bytes)
 }
 
 #[derive(Clone, Copy, Serialize)]
@@ -49,6 +155,7 @@ pub enum LogEntry {
     LastVotedRound,
     OneChainRound,
     PreferredRound,
+    ProveProposerVrf,
     SignProposal,
     SignTimeout,
     SignTimeoutWithQC,
@@ -69,6 +176,7 @@ impl LogEntry {
             LogEntry::KeyReconciliation => "key_reconciliation",
             LogEntry::OneChainRound => "one_chain_round",
             LogEntry::PreferredRound => "preferred_round",
+            LogEntry::ProveProposerVrf => "prove_proposer_vrf",
             LogEntry::SignProposal => "sign_proposal",
             LogEntry::SignTimeout => "sign_timeout",
             LogEntry::SignTimeoutWithQC => "sign_timeout_with_qc",
@@ -87,3 +195,57 @@ pub enum LogEvent {
     Success,
     Update,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[derive(Default)]
+    struct TestVisitor(BTreeMap<&'static str, String>);
+
+    impl Visitor for TestVisitor {
+        fn visit_pair(&mut self, key: Key, value: Value<'_>) {
+            let value = match value {
+                Value::Debug(value) => format!("{:?}", value),
+                Value::Display(value) => format!("{}", value),
+                Value::Serde(value) => serde_json::to_string(value).unwrap(),
+            };
+            self.0.insert(key.as_str(), value);
+        }
+    }
+
+    fn visit(mode: LogRedactionMode) -> BTreeMap<&'static str, String> {
+        let mut visitor = TestVisitor::default();
+        SafetyLogSchema::new(LogEntry::State, LogEvent::Update)
+            .round(10)
+            .epoch(2)
+            .author(Author::random())
+            .visit_with_mode(mode, &mut visitor);
+        visitor.0
+    }
+
+    #[test]
+    fn test_redaction_modes() {
+        let plain = visit(LogRedactionMode::Disabled);
+        assert_eq!(plain["round"], "10");
+        assert_eq!(plain["epoch"], "2");
+        assert!(plain.contains_key("author"));
+
+        let hashed = visit(LogRedactionMode::Hash);
+        assert_eq!(hashed["round"], redacted_hash(&10u64).to_hex());
+        assert_ne!(hashed["round"], HashValue::sha3_256_of(b"10").to_hex());
+        assert_eq!(visit(LogRedactionMode::Hash)["round"], hashed["round"]);
+        assert_eq!(hashed["epoch"], "2");
+        assert_eq!(
+            hashed.keys().collect::<Vec<_>>(),
+            plain.keys().collect::<Vec<_>>()
+        );
+
+        let omitted = visit(LogRedactionMode::Omit);
+        assert!(!omitted.contains_key("round"));
+        assert!(!omitted.contains_key("author"));
+        assert_eq!(omitted["epoch"], "2");
+        assert_eq!(omitted["name"], "\"state\"");
+    }
+}
@@ -22,6 +22,10 @@ fn main() {
         eprintln!("Unable to read provided config: {}", e);
         process::exit(1);
     });
+    config.sanitize().unwrap_or_else(|e| {
+        eprintln!("Invalid config: {}", e);
+        process::exit(1);
+    });
 
     diem_logger::Logger::new()
         .channel_size(config.logger.chan_size)
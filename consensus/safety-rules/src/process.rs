@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    logging,
     persistent_safety_storage::PersistentSafetyStorage,
     remote_service::{self, RemoteService},
     safety_rules_manager,
@@ -16,6 +17,7 @@ pub struct Process {
 
 impl Process {
     pub fn new(config: SafetyRulesConfig) -> Self {
+        logging::set_log_redaction_mode(config.log_redaction);
         let storage = safety_rules_manager::storage(&config);
 
         let verify_vote_proposal_signature = config.verify_vote_proposal_signature;
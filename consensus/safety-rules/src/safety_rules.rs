@@ -12,7 +12,7 @@ use crate::{
 };
 use consensus_types::{
     block::Block,
-    block_data::BlockData,
+    block_data::{BlockData, ProposerVrfInput},
     common::{Author, Round},
     quorum_cert::QuorumCert,
     safety_data::SafetyData,
@@ -23,6 +23,7 @@ use consensus_types::{
     vote_proposal::{MaybeSignedVoteProposal, VoteProposal},
 };
 use diem_crypto::{
+    ecvrf::VRFProof,
     ed25519::{Ed25519PublicKey, Ed25519Signature},
     hash::{CryptoHash, HashValue},
     traits::Signature,
@@ -511,6 +512,14 @@ impl SafetyRules {
 
         Ok(signature)
     }
+
+    fn guarded_prove_proposer_vrf(&mut self, round: Round) -> Result<VRFProof, Error> {
+        self.signer()?;
+
+        let safety_data = self.persistent_storage.safety_data()?;
+        let input = ProposerVrfInput::new(safety_data.epoch, round);
+        self.signer()?.prove_vrf(&input.to_bytes())
+    }
 }
 
 impl TSafetyRules for SafetyRules {
@@ -581,6 +590,11 @@ impl TSafetyRules for SafetyRules {
         let cb = || self.guarded_sign_commit_vote(ledger_info, new_ledger_info);
         run_and_log(cb, |log| log, LogEntry::SignCommitVote)
     }
+
+    fn prove_proposer_vrf(&mut self, round: Round) -> Result<VRFProof, Error> {
+        let cb = || self.guarded_prove_proposer_vrf(round);
+        run_and_log(cb, |log| log.round(round), LogEntry::ProveProposerVrf)
+    }
 }
 
 fn run_and_log<F, L, R>(callback: F, log_cb: L, log_entry: LogEntry) -> Result<R, Error>
@@ -3,6 +3,7 @@
 
 use crate::{
     local_client::LocalClient,
+    logging,
     persistent_safety_storage::PersistentSafetyStorage,
     process::ProcessService,
     remote_service::RemoteService,
@@ -62,6 +63,7 @@ pub struct SafetyRulesManager {
 
 impl SafetyRulesManager {
     pub fn new(config: &SafetyRulesConfig) -> Self {
+        logging::set_log_redaction_mode(config.log_redaction);
         if let SafetyRulesService::Process(conf) = &config.service {
             return Self::new_process(conf.server_address(), config.network_timeout_ms);
         }
@@ -4,12 +4,13 @@
 use crate::{counters, logging::LogEntry, ConsensusState, Error, SafetyRules, TSafetyRules};
 use consensus_types::{
     block_data::BlockData,
+    common::Round,
     timeout::Timeout,
     timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
     vote::Vote,
     vote_proposal::MaybeSignedVoteProposal,
 };
-use diem_crypto::ed25519::Ed25519Signature;
+use diem_crypto::{ecvrf::VRFProof, ed25519::Ed25519Signature};
 use diem_infallible::RwLock;
 use diem_types::{
     epoch_change::EpochChangeProof,
@@ -34,6 +35,7 @@ pub enum SafetyRulesInput {
         Box<Option<TwoChainTimeoutCertificate>>,
     ),
     SignCommitVote(Box<LedgerInfoWithSignatures>, Box<LedgerInfo>),
+    ProveProposerVrf(Round),
 }
 
 pub struct SerializerService {
@@ -80,6 +82,9 @@ impl SerializerService {
                     .internal
                     .sign_commit_vote(*ledger_info, *new_ledger_info),
             ),
+            SafetyRulesInput::ProveProposerVrf(round) => {
+                serde_json::to_vec(&self.internal.prove_proposer_vrf(round))
+            }
         };
 
         Ok(output?)
@@ -181,6 +186,12 @@ impl TSafetyRules for SerializerClient {
         ))?;
         serde_json::from_slice(&response)?
     }
+
+    fn prove_proposer_vrf(&mut self, round: Round) -> Result<VRFProof, Error> {
+        let _timer = counters::start_timer("external", LogEntry::ProveProposerVrf.as_str());
+        let response = self.request(SafetyRulesInput::ProveProposerVrf(round))?;
+        serde_json::from_slice(&response)?
+    }
 }
 
 pub trait TSerializerClient: Send + Sync {
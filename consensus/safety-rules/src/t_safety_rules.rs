@@ -4,12 +4,13 @@
 use crate::{ConsensusState, Error};
 use consensus_types::{
     block_data::BlockData,
+    common::Round,
     timeout::Timeout,
     timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
     vote::Vote,
     vote_proposal::MaybeSignedVoteProposal,
 };
-use diem_crypto::ed25519::Ed25519Signature;
+use diem_crypto::{ecvrf::VRFProof, ed25519::Ed25519Signature};
 use diem_types::{
     epoch_change::EpochChangeProof,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
@@ -62,4 +63,9 @@ pub trait TSafetyRules {
         ledger_info: LedgerInfoWithSignatures,
         new_ledger_info: LedgerInfo,
     ) -> Result<Ed25519Signature, Error>;
+
+    /// As the holder of the private key, SafetyRules also proves the VRF output of the consensus
+    /// key for a round of the current epoch, which VRF proposer elections draw the proposer of the
+    /// next round from.
+    fn prove_proposer_vrf(&mut self, round: Round) -> Result<VRFProof, Error>;
 }
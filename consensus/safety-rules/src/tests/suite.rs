@@ -4,6 +4,7 @@
 use crate::{test_utils, test_utils::make_timeout_cert, Error, SafetyRules, TSafetyRules};
 use consensus_types::{
     block::block_test_utils::random_payload,
+    block_data::ProposerVrfInput,
     common::Round,
     quorum_cert::QuorumCert,
     timeout::Timeout,
@@ -11,6 +12,7 @@ use consensus_types::{
     vote_proposal::MaybeSignedVoteProposal,
 };
 use diem_crypto::{
+    ecvrf::VRFPublicKey,
     ed25519::{Ed25519PrivateKey, Ed25519Signature},
     hash::{CryptoHash, HashValue, ACCUMULATOR_PLACEHOLDER_HASH},
 };
@@ -24,7 +26,7 @@ use diem_types::{
     validator_signer::ValidatorSigner,
     validator_verifier::ValidatorVerifier,
 };
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, convert::TryFrom};
 
 type Proof = test_utils::Proof;
 
@@ -69,6 +71,7 @@ pub fn run_test_suite(safety_rules: &Callback, decoupled_execution: bool) {
     test_sign_proposal_with_bad_signer(safety_rules);
     test_sign_proposal_with_invalid_qc(safety_rules);
     test_sign_proposal_with_early_preferred_round(safety_rules);
+    test_prove_proposer_vrf(safety_rules);
     test_uninitialized_signer(safety_rules);
     test_reconcile_key(safety_rules);
     test_validator_not_in_set(safety_rules);
@@ -619,6 +622,31 @@ fn test_sign_proposal_with_early_preferred_round(safety_rules: &Callback) {
     assert_eq!(err, Error::IncorrectPreferredRound(0, 2));
 }
 
+fn test_prove_proposer_vrf(safety_rules: &Callback) {
+    // Test to prove the VRF output of the consensus key for a round of the current epoch
+
+    let (mut safety_rules, signer, _key) = safety_rules();
+
+    let (proof, genesis_qc) = test_utils::make_genesis(&signer);
+    let round = genesis_qc.certified_block().round() + 1;
+    let err = safety_rules.prove_proposer_vrf(round).unwrap_err();
+    assert_eq!(err, Error::NotInitialized("validator_signer".into()));
+
+    safety_rules.initialize(&proof).unwrap();
+    let epoch = safety_rules.consensus_state().unwrap().epoch();
+    match safety_rules.prove_proposer_vrf(round) {
+        Ok(vrf_proof) => {
+            let public_key = VRFPublicKey::try_from(&signer.public_key()).unwrap();
+            let input = ProposerVrfInput::new(epoch, round).to_bytes();
+            public_key.verify(&vrf_proof, &input).unwrap();
+            let other_input = ProposerVrfInput::new(epoch, round + 1).to_bytes();
+            public_key.verify(&vrf_proof, &other_input).unwrap_err();
+        }
+        // Secure storage holds the consensus key when it isn't exported
+        Err(err) => assert!(matches!(err, Error::VrfProofUnavailable(_))),
+    }
+}
+
 fn test_uninitialized_signer(safety_rules: &Callback) {
     // Testing for an uninitialized Option<ValidatorSigner>
 
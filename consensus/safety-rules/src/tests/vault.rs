@@ -46,6 +46,7 @@ fn safety_rules(
             None,
             None,
             true,
+            false,
             None,
             None,
         ));
@@ -0,0 +1,187 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{block_storage::BlockReader, counters};
+use anyhow::{bail, ensure};
+use consensus_types::{
+    block_retrieval::{
+        BlockRetrievalResponse, BlockRetrievalStatus, BlockRetrievalStreamAck,
+        BlockRetrievalStreamChunk, BlockRetrievalStreamRequest,
+    },
+    common::Author,
+};
+use diem_config::config::BlockRetrievalStreamConfig;
+use diem_crypto::HashValue;
+use std::{
+    cmp::{max, min},
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// The state of a stream served to a peer.
+struct ServedStream {
+    // Id of the next block to send
+    next_block_id: HashValue,
+    // Number of blocks left to send
+    remaining_blocks: u64,
+    chunk_size: u64,
+    window: u64,
+    // Index of the next chunk to send
+    next_chunk: u64,
+    // Number of chunks acknowledged by the peer
+    acked_chunks: u64,
+    last_active: Instant,
+}
+
+impl ServedStream {
+    /// Returns the chunks allowed by the window, as well as whether the last chunk was reached.
+    fn next_chunks(
+        &mut self,
+        stream_id: u64,
+        block_reader: &dyn BlockReader,
+    ) -> (Vec<BlockRetrievalStreamChunk>, bool) {
+        let mut chunks = vec![];
+        while self.next_chunk < self.acked_chunks + self.window {
+            let num_blocks = min(self.chunk_size, self.remaining_blocks);
+            let mut blocks = vec![];
+            while (blocks.len() as u64) < num_blocks {
+                match block_reader.get_block(self.next_block_id) {
+                    Some(executed_block) => {
+                        self.next_block_id = executed_block.parent_id();
+                        blocks.push(executed_block.block().clone());
+                    }
+                    None => break,
+                }
+            }
+            let status = if blocks.len() as u64 == num_blocks {
+                BlockRetrievalStatus::Succeeded
+            } else if blocks.is_empty() && self.next_chunk == 0 {
+                BlockRetrievalStatus::IdNotFound
+            } else {
+                BlockRetrievalStatus::NotEnoughBlocks
+            };
+            self.remaining_blocks -= blocks.len() as u64;
+            let is_last = status != BlockRetrievalStatus::Succeeded || self.remaining_blocks == 0;
+            chunks.push(BlockRetrievalStreamChunk::new(
+                stream_id,
+                self.next_chunk,
+                is_last,
+                BlockRetrievalResponse::new(status, blocks),
+            ));
+            self.next_chunk += 1;
+            if is_last {
+                return (chunks, true);
+            }
+        }
+        (chunks, false)
+    }
+}
+
+/// Serves the block retrieval streams requested by the peers: every stream sends chunks of
+/// consecutive blocks, at most a window of chunks ahead of the acknowledgements of the peer.
+/// The number of streams of a peer and the size of the streams are bounded by the config.
+pub struct BlockRetrievalStreamServer {
+    config: BlockRetrievalStreamConfig,
+    streams: HashMap<Author, HashMap<u64, ServedStream>>,
+}
+
+impl BlockRetrievalStreamServer {
+    pub fn new(config: BlockRetrievalStreamConfig) -> Self {
+        Self {
+            config,
+            streams: HashMap::new(),
+        }
+    }
+
+    /// Number of streams currently served to the given peer.
+    pub fn num_streams(&self, peer: Author) -> usize {
+        self.streams.get(&peer).map_or(0, |streams| streams.len())
+    }
+
+    /// Starts serving a stream to the given peer (or restarts it if the stream id is reused) and
+    /// returns its first chunks.
+    pub fn process_request(
+        &mut self,
+        peer: Author,
+        request: BlockRetrievalStreamRequest,
+        block_reader: &dyn BlockReader,
+    ) -> anyhow::Result<Vec<BlockRetrievalStreamChunk>> {
+        ensure!(
+            request.num_blocks() > 0 && request.chunk_size() > 0 && request.window() > 0,
+            "Empty block retrieval stream {}",
+            request
+        );
+        let stream_timeout = Duration::from_millis(self.config.stream_timeout_ms);
+        let peer_streams = self.streams.entry(peer).or_insert_with(HashMap::new);
+        peer_streams.retain(|_, stream| stream.last_active.elapsed() < stream_timeout);
+        if !peer_streams.contains_key(&request.stream_id())
+            && peer_streams.len() >= self.config.max_streams_per_peer
+        {
+            counters::BLOCK_RETRIEVAL_STREAMS_REJECTED.inc();
+            bail!(
+                "Too many block retrieval streams from {}, limit {}",
+                peer,
+                self.config.max_streams_per_peer
+            );
+        }
+
+        let mut stream = ServedStream {
+            next_block_id: request.block_id(),
+            remaining_blocks: min(request.num_blocks(), self.config.max_blocks_per_stream),
+            chunk_size: min(request.chunk_size(), self.config.max_chunk_size),
+            window: min(request.window(), self.config.max_window),
+            next_chunk: 0,
+            acked_chunks: 0,
+            last_active: Instant::now(),
+        };
+        let (chunks, finished) = stream.next_chunks(request.stream_id(), block_reader);
+        if finished {
+            peer_streams.remove(&request.stream_id());
+        } else {
+            peer_streams.insert(request.stream_id(), stream);
+        }
+        counters::BLOCK_RETRIEVAL_STREAM_CHUNKS_SENT.inc_by(chunks.len() as u64);
+        Ok(chunks)
+    }
+
+    /// Records the chunks acknowledged by the peer and returns the chunks that fit in the window.
+    pub fn process_ack(
+        &mut self,
+        peer: Author,
+        ack: BlockRetrievalStreamAck,
+        block_reader: &dyn BlockReader,
+    ) -> anyhow::Result<Vec<BlockRetrievalStreamChunk>> {
+        let peer_streams = match self.streams.get_mut(&peer) {
+            Some(peer_streams) => peer_streams,
+            None => bail!("No block retrieval stream from {}", peer),
+        };
+        let stream = match peer_streams.get_mut(&ack.stream_id()) {
+            Some(stream) => stream,
+            None => bail!(
+                "Unknown block retrieval stream {} from {}",
+                ack.stream_id(),
+                peer
+            ),
+        };
+        ensure!(
+            ack.num_chunks() <= stream.next_chunk,
+            "Acknowledged {} chunks of block retrieval stream {}, only {} sent",
+            ack.num_chunks(),
+            ack.stream_id(),
+            stream.next_chunk
+        );
+        stream.acked_chunks = max(stream.acked_chunks, ack.num_chunks());
+        stream.last_active = Instant::now();
+
+        let (chunks, finished) = stream.next_chunks(ack.stream_id(), block_reader);
+        if finished {
+            peer_streams.remove(&ack.stream_id());
+        }
+        counters::BLOCK_RETRIEVAL_STREAM_CHUNKS_SENT.inc_by(chunks.len() as u64);
+        Ok(chunks)
+    }
+}
+
+#[cfg(test)]
+#[path = "block_retrieval_stream_test.rs"]
+mod block_retrieval_stream_test;
@@ -0,0 +1,169 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{block_storage::BlockRetrievalStreamServer, test_utils::build_simple_tree};
+use consensus_types::{
+    block_retrieval::{
+        BlockRetrievalStatus, BlockRetrievalStreamAck, BlockRetrievalStreamChunk,
+        BlockRetrievalStreamRequest,
+    },
+    common::Author,
+};
+use diem_config::config::BlockRetrievalStreamConfig;
+use diem_crypto::HashValue;
+
+fn chunk_block_ids(chunk: &BlockRetrievalStreamChunk) -> Vec<HashValue> {
+    chunk.response().blocks().iter().map(|b| b.id()).collect()
+}
+
+#[test]
+fn test_stream_flow_control() {
+    //       ╭--> A1--> A2--> A3
+    // Genesis--> B1--> B2
+    //             ╰--> C1
+    let (blocks, block_store) = build_simple_tree();
+    let (genesis, a1, a2, a3) = (&blocks[0], &blocks[1], &blocks[2], &blocks[3]);
+    let peer = Author::random();
+    let mut server = BlockRetrievalStreamServer::new(BlockRetrievalStreamConfig::default());
+
+    // only the window of chunks is sent before any ack
+    let request = BlockRetrievalStreamRequest::new(1, a3.id(), 4, 1, 2);
+    let chunks = server
+        .process_request(peer, request, block_store.as_ref())
+        .unwrap();
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunk_block_ids(&chunks[0]), vec![a3.id()]);
+    assert_eq!(chunk_block_ids(&chunks[1]), vec![a2.id()]);
+    assert!(chunks.iter().all(|chunk| !chunk.is_last()));
+    assert_eq!(server.num_streams(peer), 1);
+
+    // every acked chunk lets one more chunk through
+    let chunks = server
+        .process_ack(
+            peer,
+            BlockRetrievalStreamAck::new(1, 1),
+            block_store.as_ref(),
+        )
+        .unwrap();
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].chunk_index(), 2);
+    assert_eq!(chunk_block_ids(&chunks[0]), vec![a1.id()]);
+
+    // stale acks don't grant anything
+    let chunks = server
+        .process_ack(
+            peer,
+            BlockRetrievalStreamAck::new(1, 1),
+            block_store.as_ref(),
+        )
+        .unwrap();
+    assert!(chunks.is_empty());
+
+    // the stream is over once the last block is sent
+    let chunks = server
+        .process_ack(
+            peer,
+            BlockRetrievalStreamAck::new(1, 3),
+            block_store.as_ref(),
+        )
+        .unwrap();
+    assert_eq!(chunks.len(), 1);
+    assert!(chunks[0].is_last());
+    assert_eq!(
+        chunks[0].response().status(),
+        BlockRetrievalStatus::Succeeded
+    );
+    assert_eq!(chunk_block_ids(&chunks[0]), vec![genesis.id()]);
+    assert_eq!(server.num_streams(peer), 0);
+    assert!(server
+        .process_ack(
+            peer,
+            BlockRetrievalStreamAck::new(1, 4),
+            block_store.as_ref()
+        )
+        .is_err());
+}
+
+#[test]
+fn test_stream_missing_blocks() {
+    let (blocks, block_store) = build_simple_tree();
+    let peer = Author::random();
+    let mut server = BlockRetrievalStreamServer::new(BlockRetrievalStreamConfig::default());
+
+    let request = BlockRetrievalStreamRequest::new(1, HashValue::random(), 10, 5, 2);
+    let chunks = server
+        .process_request(peer, request, block_store.as_ref())
+        .unwrap();
+    assert_eq!(chunks.len(), 1);
+    assert!(chunks[0].is_last());
+    assert_eq!(
+        chunks[0].response().status(),
+        BlockRetrievalStatus::IdNotFound
+    );
+
+    // A2 -> A1 -> Genesis, then nothing left for the rest of the second chunk
+    let request = BlockRetrievalStreamRequest::new(2, blocks[2].id(), 10, 2, 4);
+    let chunks = server
+        .process_request(peer, request, block_store.as_ref())
+        .unwrap();
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(
+        chunks[0].response().status(),
+        BlockRetrievalStatus::Succeeded
+    );
+    assert!(chunks[1].is_last());
+    assert_eq!(
+        chunks[1].response().status(),
+        BlockRetrievalStatus::NotEnoughBlocks
+    );
+    assert_eq!(chunk_block_ids(&chunks[1]), vec![blocks[0].id()]);
+    assert_eq!(server.num_streams(peer), 0);
+}
+
+#[test]
+fn test_stream_limits() {
+    let (blocks, block_store) = build_simple_tree();
+    let a3 = &blocks[3];
+    let (peer, other_peer) = (Author::random(), Author::random());
+    let mut server = BlockRetrievalStreamServer::new(BlockRetrievalStreamConfig {
+        max_streams_per_peer: 1,
+        max_blocks_per_stream: 3,
+        max_chunk_size: 2,
+        max_window: 1,
+        ..BlockRetrievalStreamConfig::default()
+    });
+
+    // chunk size and window are capped
+    let request = BlockRetrievalStreamRequest::new(1, a3.id(), 4, 10, 10);
+    let chunks = server
+        .process_request(peer, request, block_store.as_ref())
+        .unwrap();
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].response().blocks().len(), 2);
+
+    // a single stream per peer
+    let request = BlockRetrievalStreamRequest::new(2, a3.id(), 4, 1, 1);
+    assert!(server
+        .process_request(peer, request.clone(), block_store.as_ref())
+        .is_err());
+    server
+        .process_request(other_peer, request, block_store.as_ref())
+        .unwrap();
+
+    // the length of the stream is capped
+    let chunks = server
+        .process_ack(
+            peer,
+            BlockRetrievalStreamAck::new(1, 1),
+            block_store.as_ref(),
+        )
+        .unwrap();
+    assert_eq!(chunks.len(), 1);
+    assert!(chunks[0].is_last());
+    assert_eq!(chunks[0].response().blocks().len(), 1);
+    assert_eq!(
+        chunks[0].response().status(),
+        BlockRetrievalStatus::Succeeded
+    );
+    assert_eq!(server.num_streams(peer), 0);
+}
@@ -135,6 +135,10 @@ pub fn update_counters_and_prune_blocks(
         // executor.
         error!(error = ?e, "fail to delete block");
     }
+    if let Err(e) = storage.prune_stale(block_to_commit.epoch(), committed_round) {
+        // the stale data is pruned again with the next commit
+        error!(error = ?e, "fail to prune stale blocks");
+    }
     block_tree
         .write()
         .update_commit_id_and_process_pruned_blocks(block_to_commit.id(), id_to_remove);
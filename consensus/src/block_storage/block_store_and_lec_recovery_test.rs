@@ -3,6 +3,7 @@
 
 use crate::{
     block_storage::{block_store::BlockStore, BlockReader},
+    commit_notification_bus::CommitNotificationBus,
     persistent_liveness_storage::{LedgerRecoveryData, RecoveryData, RootMetadata},
     state_computer::ExecutionProxy,
     test_utils::{EmptyStorage, TreeInserter},
@@ -69,6 +70,7 @@ fn build_inserter(
     let state_computer = Arc::new(ExecutionProxy::new(
         lec_client,
         Box::new(consensus_notifier),
+        CommitNotificationBus::default(),
     ));
 
     TreeInserter::new_with_store(
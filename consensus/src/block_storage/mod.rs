@@ -7,10 +7,12 @@ use consensus_types::{
 use diem_crypto::HashValue;
 use std::sync::Arc;
 
+mod block_retrieval_stream;
 mod block_store;
 mod block_tree;
 pub mod tracing;
 
+pub use block_retrieval_stream::BlockRetrievalStreamServer;
 pub use block_store::{sync_manager::BlockRetriever, BlockStore};
 use consensus_types::{sync_info::SyncInfo, timeout_2chain::TwoChainTimeoutCertificate};
 use diem_types::ledger_info::LedgerInfoWithSignatures;
@@ -328,14 +328,27 @@ impl BlockRetriever {
                 retrieve_batch_size,
                 attempt
             );
-            let response = self
-                .network
-                .request_block(
-                    BlockRetrievalRequest::new(last_block_id, retrieve_batch_size),
-                    peer,
-                    retrieval_timeout(attempt),
-                )
-                .await;
+            // deep catch-ups stream the remaining blocks instead of requesting them in batches
+            let response = if self.network.supports_block_retrieval_streams()
+                && num_blocks - progress > MAX_BLOCKS_PER_REQUEST
+            {
+                self.network
+                    .request_block_stream(
+                        last_block_id,
+                        num_blocks - progress,
+                        peer,
+                        retrieval_timeout(attempt),
+                    )
+                    .await
+            } else {
+                self.network
+                    .request_block(
+                        BlockRetrievalRequest::new(last_block_id, retrieve_batch_size),
+                        peer,
+                        retrieval_timeout(attempt),
+                    )
+                    .await
+            };
             match response.and_then(|result| {
                 if result.status() == BlockRetrievalStatus::Succeeded {
                     Ok(result.blocks().clone())
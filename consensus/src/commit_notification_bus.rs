@@ -0,0 +1,105 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::counters;
+use channel::{diem_channel, message_queues::QueueStyle};
+use diem_config::config::CommitNotificationBusConfig;
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
+use diem_types::{
+    contract_event::ContractEvent, ledger_info::LedgerInfoWithSignatures, transaction::Transaction,
+};
+use std::{collections::VecDeque, sync::Arc};
+
+/// The blocks committed together by a ledger info.
+#[derive(Clone, Debug)]
+pub struct CommitNotification {
+    /// Ids of the committed blocks, in order
+    pub block_ids: Vec<HashValue>,
+    /// Ledger info of the last committed block
+    pub ledger_info: LedgerInfoWithSignatures,
+    /// Transactions of the committed blocks
+    pub transactions: Vec<Transaction>,
+    /// Reconfiguration events emitted by the committed blocks
+    pub reconfig_events: Vec<ContractEvent>,
+}
+
+struct BusState {
+    // The most recent commits, sent first to every new subscriber
+    backfill: VecDeque<Arc<CommitNotification>>,
+    subscribers: Vec<diem_channel::Sender<(), Arc<CommitNotification>>>,
+}
+
+/// Broadcasts the commits of consensus to any number of local subscribers (e.g., indexers), on top
+/// of the dedicated notifications of state sync and mempool. A subscriber first receives the most
+/// recent commits, then every new one, in commit order. A slow subscriber loses its oldest pending
+/// commits instead of slowing down consensus. The commits made while nobody is subscribed aren't
+/// published, not to copy their transactions for nothing, so the backfill only covers the commits
+/// made since the first subscription.
+#[derive(Clone)]
+pub struct CommitNotificationBus {
+    config: CommitNotificationBusConfig,
+    state: Arc<Mutex<BusState>>,
+}
+
+impl CommitNotificationBus {
+    /// Creates a bus without subscribers nor commits.
+    pub fn new(config: CommitNotificationBusConfig) -> Self {
+        Self {
+            config,
+            state: Arc::new(Mutex::new(BusState {
+                backfill: VecDeque::with_capacity(config.backfill_size),
+                subscribers: vec![],
+            })),
+        }
+    }
+
+    /// Returns the stream of the commits, starting with the most recent ones.
+    pub fn subscribe(&self) -> diem_channel::Receiver<(), Arc<CommitNotification>> {
+        let (mut tx, rx) = diem_channel::new(QueueStyle::KLAST, self.config.channel_size, None);
+        let mut state = self.state.lock();
+        for notification in &state.backfill {
+            // the receiver is still held here
+            let _ = tx.push((), notification.clone());
+        }
+        state.subscribers.push(tx);
+        counters::COMMIT_NOTIFICATION_SUBSCRIBERS.set(state.subscribers.len() as i64);
+        rx
+    }
+
+    /// Sends the commit to all the subscribers and forgets the ones that went away.
+    pub fn publish(&self, notification: CommitNotification) {
+        let notification = Arc::new(notification);
+        let mut state = self.state.lock();
+        if self.config.backfill_size > 0 {
+            if state.backfill.len() == self.config.backfill_size {
+                state.backfill.pop_front();
+            }
+            state.backfill.push_back(notification.clone());
+        }
+        state
+            .subscribers
+            .retain_mut(|tx| tx.push((), notification.clone()).is_ok());
+        counters::COMMIT_NOTIFICATION_SUBSCRIBERS.set(state.subscribers.len() as i64);
+    }
+
+    /// Whether anything subscribed to the commits and is still there.
+    pub fn has_subscribers(&self) -> bool {
+        !self.state.lock().subscribers.is_empty()
+    }
+
+    #[cfg(test)]
+    pub fn num_subscribers(&self) -> usize {
+        self.state.lock().subscribers.len()
+    }
+}
+
+impl Default for CommitNotificationBus {
+    fn default() -> Self {
+        Self::new(CommitNotificationBusConfig::default())
+    }
+}
+
+#[cfg(test)]
+#[path = "commit_notification_bus_test.rs"]
+mod commit_notification_bus_test;
@@ -0,0 +1,60 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::commit_notification_bus::{CommitNotification, CommitNotificationBus};
+use diem_config::config::CommitNotificationBusConfig;
+use diem_crypto::HashValue;
+use diem_types::{
+    block_info::BlockInfo,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+};
+use futures::StreamExt;
+use std::collections::BTreeMap;
+
+fn commit(round: u64) -> CommitNotification {
+    let block_info = BlockInfo::random(round);
+    CommitNotification {
+        block_ids: vec![block_info.id()],
+        ledger_info: LedgerInfoWithSignatures::new(
+            LedgerInfo::new(block_info, HashValue::zero()),
+            BTreeMap::new(),
+        ),
+        transactions: vec![],
+        reconfig_events: vec![],
+    }
+}
+
+#[tokio::test]
+async fn test_commit_notification_backfill() {
+    let bus = CommitNotificationBus::new(CommitNotificationBusConfig {
+        backfill_size: 2,
+        channel_size: 10,
+    });
+    // nothing listens yet
+    assert!(!bus.has_subscribers());
+    let mut early_subscriber = bus.subscribe();
+    assert!(bus.has_subscribers());
+    for round in 1..=3 {
+        bus.publish(commit(round));
+    }
+
+    // a late subscriber gets the most recent commits first
+    let mut late_subscriber = bus.subscribe();
+    bus.publish(commit(4));
+    for (subscriber, rounds) in vec![
+        (&mut early_subscriber, vec![1, 2, 3, 4]),
+        (&mut late_subscriber, vec![2, 3, 4]),
+    ] {
+        for round in rounds {
+            let notification = subscriber.next().await.unwrap();
+            assert_eq!(notification.ledger_info.ledger_info().round(), round);
+        }
+    }
+
+    // subscribers that went away are dropped
+    drop(early_subscriber);
+    bus.publish(commit(5));
+    assert_eq!(bus.num_subscribers(), 1);
+    let notification = late_subscriber.next().await.unwrap();
+    assert_eq!(notification.ledger_info.ledger_info().round(), 5);
+}
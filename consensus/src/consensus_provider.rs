@@ -2,12 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    commit_notification_bus::CommitNotificationBus,
     counters,
     epoch_manager::EpochManager,
     network::NetworkTask,
     network_interface::{ConsensusNetworkEvents, ConsensusNetworkSender},
+    observer::{
+        network::{ObserverNetworkEvents, ObserverNetworkSender},
+        observer_epoch_manager::ObserverEpochManager,
+        publisher::ConsensusPublisher,
+    },
     persistent_liveness_storage::StorageWriteProxy,
     state_computer::ExecutionProxy,
+    state_snapshot::SharedConsensusState,
     txn_manager::MempoolProxy,
     util::time_service::ClockTimeService,
 };
@@ -19,12 +26,13 @@ use diem_logger::prelude::*;
 use diem_mempool::ConsensusRequest;
 use diem_types::on_chain_config::OnChainConfigPayload;
 use execution_correctness::ExecutionCorrectnessManager;
-use futures::channel::mpsc;
+use futures::channel::{mpsc, oneshot};
 use std::{collections::HashMap, sync::Arc};
 use storage_interface::DbReader;
 use tokio::runtime::{self, Runtime};
 
-/// Helper function to start consensus based on configuration and return the runtime
+/// Helper function to start consensus based on configuration and return the runtime. Consensus
+/// stops on the first request of `shutdown_requests` and acknowledges it.
 pub fn start_consensus(
     node_config: &NodeConfig,
     mut network_sender: ConsensusNetworkSender,
@@ -33,6 +41,10 @@ pub fn start_consensus(
     consensus_to_mempool_sender: mpsc::Sender<ConsensusRequest>,
     diem_db: Arc<dyn DbReader>,
     reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
+    observer_network: Option<(ObserverNetworkSender, ObserverNetworkEvents)>,
+    commit_notification_bus: CommitNotificationBus,
+    shared_state: SharedConsensusState,
+    shutdown_requests: mpsc::Receiver<oneshot::Sender<()>>,
 ) -> Runtime {
     let runtime = runtime::Builder::new_multi_thread()
         .thread_name("consensus")
@@ -51,6 +63,7 @@ pub fn start_consensus(
     let state_computer = Arc::new(ExecutionProxy::new(
         execution_correctness_manager.client(),
         state_sync_notifier,
+        commit_notification_bus,
     ));
 
     let time_service = Arc::new(ClockTimeService::new(runtime.handle().clone()));
@@ -59,6 +72,14 @@ pub fn start_consensus(
     let (self_sender, self_receiver) = channel::new(1_024, &counters::PENDING_SELF_MESSAGES);
     let shared_connections = Arc::new(RwLock::new(HashMap::new()));
     network_sender.initialize(shared_connections.clone());
+    network_sender.set_compression(node_config.consensus.payload_compression);
+
+    let consensus_publisher =
+        observer_network.map(|(observer_network_sender, observer_network_events)| {
+            let publisher = ConsensusPublisher::new(observer_network_sender);
+            runtime.spawn(publisher.clone().start(observer_network_events));
+            publisher
+        });
 
     let epoch_mgr = EpochManager::new(
         node_config,
@@ -70,14 +91,44 @@ pub fn start_consensus(
         state_computer,
         storage,
         reconfig_events,
+        consensus_publisher,
+        shared_state,
     );
 
     let (network_task, network_receiver) =
         NetworkTask::new(network_events, self_receiver, shared_connections);
 
     runtime.spawn(network_task.start());
-    runtime.spawn(epoch_mgr.start(timeout_receiver, network_receiver));
+    runtime.spawn(epoch_mgr.start(timeout_receiver, network_receiver, shutdown_requests));
 
     debug!("Consensus started.");
     runtime
 }
+
+/// Helper function to start the consensus observer of a fullnode and return the runtime
+pub fn start_consensus_observer(
+    network_sender: ObserverNetworkSender,
+    network_events: ObserverNetworkEvents,
+    state_sync_notifier: Box<dyn ConsensusNotificationSender>,
+    diem_db: Arc<dyn DbReader>,
+    reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
+) -> Runtime {
+    let runtime = runtime::Builder::new_multi_thread()
+        .thread_name("consensus-observer")
+        .enable_all()
+        .build()
+        .expect("Failed to create Tokio runtime!");
+    let committed_version = diem_db
+        .get_latest_version()
+        .expect("Failed to read the latest version from storage");
+
+    let observer = ObserverEpochManager::new(
+        network_sender,
+        Arc::from(state_sync_notifier),
+        committed_version,
+    );
+    runtime.spawn(observer.start(network_events, reconfig_events));
+
+    debug!("Consensus observer started.");
+    runtime
+}
@@ -4,6 +4,8 @@
 use super::*;
 use consensus_types::block::block_test_utils::certificate_for_genesis;
 use diem_temppath::TempPath;
+use diem_types::ledger_info::{LedgerInfo, LedgerInfoWithSignatures};
+use std::{collections::BTreeMap, sync::Arc};
 
 #[test]
 fn test_put_get() {
@@ -73,3 +75,83 @@ fn test_delete_block_and_qc() {
     assert_eq!(db.get_blocks().unwrap().len(), 0);
     assert_eq!(db.get_quorum_certificates().unwrap().len(), 0);
 }
+
+#[test]
+fn test_ordered_blocks() {
+    let tmp_dir = TempPath::new();
+    let db = ConsensusDB::new(&tmp_dir);
+
+    assert!(db.get_ordered_blocks().unwrap().is_empty());
+
+    let ordered_blocks = PersistedOrderedBlocks::new(
+        vec![Block::make_genesis_block()],
+        LedgerInfoWithSignatures::new(LedgerInfo::mock_genesis(None), BTreeMap::new()),
+        BTreeMap::new(),
+    );
+    db.save_ordered_blocks(vec![ordered_blocks.clone()])
+        .unwrap();
+    assert_eq!(
+        db.get_ordered_blocks().unwrap(),
+        vec![ordered_blocks.clone()]
+    );
+
+    db.delete_ordered_blocks(vec![ordered_blocks.key()])
+        .unwrap();
+    assert!(db.get_ordered_blocks().unwrap().is_empty());
+}
+
+#[test]
+fn test_pruner() {
+    let tmp_dir = TempPath::new();
+    let db = Arc::new(ConsensusDB::new(&tmp_dir));
+    let pruner = ConsensusDBPruner::new(db.clone(), 3);
+
+    let genesis = Block::make_genesis_block();
+    let epoch = genesis.epoch();
+    let blocks = vec![
+        genesis,
+        Block::new_nil(5, certificate_for_genesis()),
+        Block::new_nil(10, certificate_for_genesis()),
+    ];
+    db.save_blocks_and_quorum_certificates(blocks.clone(), vec![certificate_for_genesis()])
+        .unwrap();
+
+    // the genesis round is still within the window
+    pruner.prune(epoch, 3).unwrap();
+    assert_eq!(db.get_blocks().unwrap().len(), 3);
+    assert_eq!(db.get_quorum_certificates().unwrap().len(), 1);
+
+    pruner.prune(epoch, 8).unwrap();
+    let remaining_blocks = db.get_blocks().unwrap();
+    assert_eq!(remaining_blocks.len(), 2);
+    assert!(!remaining_blocks.contains_key(&blocks[0].id()));
+    assert!(db.get_quorum_certificates().unwrap().is_empty());
+
+    // everything from the previous epochs
+    pruner.prune(epoch + 1, 0).unwrap();
+    assert!(db.get_blocks().unwrap().is_empty());
+}
+
+#[test]
+fn test_pruner_in_batches() {
+    let tmp_dir = TempPath::new();
+    let db = Arc::new(ConsensusDB::new(&tmp_dir));
+    let pruner = ConsensusDBPruner::new_with_batch_size(db.clone(), 0, 2);
+
+    let genesis = Block::make_genesis_block();
+    let epoch = genesis.epoch();
+    let blocks: Vec<_> = (1..=5)
+        .map(|round| Block::new_nil(round, certificate_for_genesis()))
+        .collect();
+    db.save_blocks_and_quorum_certificates(blocks, vec![certificate_for_genesis()])
+        .unwrap();
+
+    // every pruning scans at most 2 blocks, so the 5 stale blocks take 3 prunings
+    pruner.prune(epoch, 10).unwrap();
+    assert_eq!(db.get_blocks().unwrap().len(), 3);
+    pruner.prune(epoch, 10).unwrap();
+    assert_eq!(db.get_blocks().unwrap().len(), 1);
+    pruner.prune(epoch, 10).unwrap();
+    assert!(db.get_blocks().unwrap().is_empty());
+    assert!(db.get_quorum_certificates().unwrap().is_empty());
+}
@@ -3,22 +3,29 @@
 
 #[cfg(test)]
 mod consensusdb_test;
+mod pruner;
 mod schema;
 
+pub use pruner::ConsensusDBPruner;
+pub use schema::ordered_blocks::PersistedOrderedBlocks;
+
 use crate::{
     consensusdb::schema::{
         block::BlockSchema,
+        ordered_blocks::OrderedBlocksSchema,
         quorum_certificate::QCSchema,
         single_entry::{SingleEntryKey, SingleEntrySchema},
     },
     error::DbError,
 };
 use anyhow::Result;
-use consensus_types::{block::Block, quorum_cert::QuorumCert};
+use consensus_types::{block::Block, common::Round, quorum_cert::QuorumCert};
 use diem_crypto::HashValue;
 use diem_logger::prelude::*;
-use schema::{BLOCK_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
-use schemadb::{Options, ReadOptions, SchemaBatch, DB, DEFAULT_CF_NAME};
+use schema::{BLOCK_CF_NAME, ORDERED_BLOCKS_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
+use schemadb::{
+    schema::Schema, Options, ReadOptions, SchemaBatch, SchemaIterator, DB, DEFAULT_CF_NAME,
+};
 use std::{collections::HashMap, iter::Iterator, path::Path, time::Instant};
 
 pub struct ConsensusDB {
@@ -32,6 +39,7 @@ impl ConsensusDB {
             BLOCK_CF_NAME,
             QC_CF_NAME,
             SINGLE_ENTRY_CF_NAME,
+            ORDERED_BLOCKS_CF_NAME,
         ];
 
         let path = db_root_path.as_ref().join("consensusdb");
@@ -141,6 +149,37 @@ impl ConsensusDB {
         self.commit(batch)
     }
 
+    /// Persist (or overwrite) batches of ordered blocks buffered by the decoupled execution
+    /// pipeline, together with their collected commit votes.
+    pub fn save_ordered_blocks(
+        &self,
+        ordered_blocks: Vec<PersistedOrderedBlocks>,
+    ) -> Result<(), DbError> {
+        let mut batch = SchemaBatch::new();
+        ordered_blocks.iter().try_for_each(|ordered_blocks| {
+            batch.put::<OrderedBlocksSchema>(&ordered_blocks.key(), ordered_blocks)
+        })?;
+        self.commit(batch)
+    }
+
+    pub fn delete_ordered_blocks(&self, keys: Vec<(u64, Round)>) -> Result<(), DbError> {
+        let mut batch = SchemaBatch::new();
+        keys.iter()
+            .try_for_each(|key| batch.delete::<OrderedBlocksSchema>(key))?;
+        self.commit(batch)
+    }
+
+    /// Get all persisted ordered blocks, sorted by (epoch, round).
+    pub fn get_ordered_blocks(&self) -> Result<Vec<PersistedOrderedBlocks>, DbError> {
+        let mut iter = self
+            .db
+            .iter::<OrderedBlocksSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        Ok(iter
+            .map(|res| res.map(|(_key, ordered_blocks)| ordered_blocks))
+            .collect::<Result<Vec<_>>>()?)
+    }
+
     /// Write the whole schema batch including all data necessary to mutate the ledger
     /// state of some transaction by leveraging rocksdb atomicity support.
     fn commit(&self, batch: SchemaBatch) -> Result<(), DbError> {
@@ -201,4 +240,18 @@ impl ConsensusDB {
         iter.seek_to_first();
         Ok(iter.collect::<Result<HashMap<HashValue, QuorumCert>>>()?)
     }
+
+    /// Iterate over the entries of a schema keyed by hash, from the given key or from the first
+    /// one.
+    fn iter_from<S: Schema<Key = HashValue>>(
+        &self,
+        start: Option<&HashValue>,
+    ) -> Result<SchemaIterator<S>, DbError> {
+        let mut iter = self.db.iter::<S>(ReadOptions::default())?;
+        match start {
+            Some(key) => iter.seek(key)?,
+            None => iter.seek_to_first(),
+        }
+        Ok(iter)
+    }
 }
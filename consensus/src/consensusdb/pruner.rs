@@ -0,0 +1,124 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    consensusdb::{
+        schema::{block::BlockSchema, quorum_certificate::QCSchema},
+        ConsensusDB,
+    },
+    counters,
+    error::DbError,
+};
+use consensus_types::common::Round;
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use schemadb::{schema::Schema, SchemaBatch};
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Number of the blocks, and of the quorum certs, scanned by every pruning
+const DEFAULT_PRUNE_BATCH_SIZE: usize = 1000;
+
+/// The blocks and quorum certs are normally deleted from ConsensusDB when they get pruned from the
+/// block tree, but the ones left behind (e.g., by a failed deletion or a crash) would stay forever.
+/// The pruner deletes everything certifying rounds that are more than `window` rounds below the
+/// latest commit, as well as everything from previous epochs.
+///
+/// Pruning runs on every commit, so it doesn't scan the whole DB: every pruning scans the next
+/// `batch_size` blocks and quorum certs in key order, and the scan starts over once it reaches the
+/// end. Stale entries are thus deleted within a few commits.
+pub struct ConsensusDBPruner {
+    db: Arc<ConsensusDB>,
+    window: u64,
+    batch_size: usize,
+    // Keys the next scans of the blocks and of the quorum certs start from, None from the first
+    cursors: Mutex<(Option<HashValue>, Option<HashValue>)>,
+}
+
+impl ConsensusDBPruner {
+    pub fn new(db: Arc<ConsensusDB>, window: u64) -> Self {
+        Self::new_with_batch_size(db, window, DEFAULT_PRUNE_BATCH_SIZE)
+    }
+
+    pub fn new_with_batch_size(db: Arc<ConsensusDB>, window: u64, batch_size: usize) -> Self {
+        Self {
+            db,
+            window,
+            batch_size,
+            cursors: Mutex::new((None, None)),
+        }
+    }
+
+    /// Prunes the next batch of the data gone stale with the commit of the given round.
+    pub fn prune(&self, committed_epoch: u64, committed_round: Round) -> Result<(), DbError> {
+        let min_round = committed_round.saturating_sub(self.window);
+        let is_stale = |epoch: u64, round: Round| {
+            epoch < committed_epoch || (epoch == committed_epoch && round < min_round)
+        };
+
+        let mut cursors = self.cursors.lock();
+        let mut batch = SchemaBatch::new();
+        let (block_cursor, num_blocks, block_bytes) =
+            self.scan::<BlockSchema>(cursors.0.as_ref(), &mut batch, |block| {
+                is_stale(block.epoch(), block.round())
+            })?;
+        let (qc_cursor, num_qcs, qc_bytes) =
+            self.scan::<QCSchema>(cursors.1.as_ref(), &mut batch, |qc| {
+                let certified_block = qc.certified_block();
+                is_stale(certified_block.epoch(), certified_block.round())
+            })?;
+        if num_blocks > 0 || num_qcs > 0 {
+            self.db.commit(batch)?;
+        }
+        // only move on once the deletions of the scanned range are committed
+        *cursors = (block_cursor, qc_cursor);
+        if num_blocks == 0 && num_qcs == 0 {
+            return Ok(());
+        }
+
+        let num_bytes = block_bytes + qc_bytes;
+        counters::CONSENSUSDB_PRUNED_BLOCKS.inc_by(num_blocks);
+        counters::CONSENSUSDB_PRUNED_QCS.inc_by(num_qcs);
+        counters::CONSENSUSDB_PRUNED_BYTES.inc_by(num_bytes as u64);
+        debug!(
+            epoch = committed_epoch,
+            round = min_round,
+            "Pruned {} blocks and {} quorum certs ({} bytes) from ConsensusDB",
+            num_blocks,
+            num_qcs,
+            num_bytes,
+        );
+        Ok(())
+    }
+
+    /// Adds the deletions of the stale entries among the `batch_size` ones from `cursor` to the
+    /// batch. Returns the key the next scan starts from, and the number and size of the deleted
+    /// entries.
+    fn scan<S>(
+        &self,
+        cursor: Option<&HashValue>,
+        batch: &mut SchemaBatch,
+        is_stale: impl Fn(&S::Value) -> bool,
+    ) -> Result<(Option<HashValue>, u64, usize), DbError>
+    where
+        S: Schema<Key = HashValue>,
+        S::Value: Serialize,
+    {
+        let mut iter = self.db.iter_from::<S>(cursor)?;
+        let (mut num_entries, mut num_bytes) = (0, 0);
+        for _ in 0..self.batch_size {
+            let (key, value) = match iter.next().transpose()? {
+                Some(entry) => entry,
+                None => return Ok((None, num_entries, num_bytes)),
+            };
+            if is_stale(&value) {
+                batch.delete::<S>(&key)?;
+                num_entries += 1;
+                num_bytes += bcs::serialized_size(&value).map_err(anyhow::Error::from)?;
+            }
+        }
+        let next_cursor = iter.next().transpose()?.map(|(key, _)| key);
+        Ok((next_cursor, num_entries, num_bytes))
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the ordered but not yet committed blocks
+//! buffered by the decoupled execution pipeline.
+//!
+//! Serialized ordered blocks identified by the epoch and round of the ordered proof.
+//! ```text
+//! |<-----key----->|<------value------>|
+//! | epoch | round |  ordered blocks   |
+//! ```
+
+use super::{ensure_slice_len_eq, ORDERED_BLOCKS_CF_NAME};
+use anyhow::Result;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use consensus_types::{block::Block, common::Round};
+use diem_crypto::ed25519::Ed25519Signature;
+use diem_types::{account_address::AccountAddress, ledger_info::LedgerInfoWithSignatures};
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, mem::size_of};
+
+define_schema!(
+    OrderedBlocksSchema,
+    (u64, Round),
+    PersistedOrderedBlocks,
+    ORDERED_BLOCKS_CF_NAME
+);
+
+/// A batch of ordered blocks together with the commit votes collected for it so far.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PersistedOrderedBlocks {
+    pub ordered_blocks: Vec<Block>,
+    pub ordered_proof: LedgerInfoWithSignatures,
+    pub commit_votes: BTreeMap<AccountAddress, Ed25519Signature>,
+}
+
+impl PersistedOrderedBlocks {
+    pub fn new(
+        ordered_blocks: Vec<Block>,
+        ordered_proof: LedgerInfoWithSignatures,
+        commit_votes: BTreeMap<AccountAddress, Ed25519Signature>,
+    ) -> Self {
+        Self {
+            ordered_blocks,
+            ordered_proof,
+            commit_votes,
+        }
+    }
+
+    pub fn key(&self) -> (u64, Round) {
+        Self::key_of(&self.ordered_proof)
+    }
+
+    /// Ordered proofs are unique per (epoch, round), the key also sorts batches in commit order.
+    pub fn key_of(ordered_proof: &LedgerInfoWithSignatures) -> (u64, Round) {
+        let commit_info = ordered_proof.ledger_info().commit_info();
+        (commit_info.epoch(), commit_info.round())
+    }
+}
+
+impl KeyCodec<OrderedBlocksSchema> for (u64, Round) {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let mut encoded_key = Vec::with_capacity(2 * size_of::<u64>());
+        encoded_key.write_u64::<BigEndian>(self.0)?;
+        encoded_key.write_u64::<BigEndian>(self.1)?;
+        Ok(encoded_key)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, 2 * size_of::<u64>())?;
+        let mut reader = data;
+        let epoch = reader.read_u64::<BigEndian>()?;
+        let round = reader.read_u64::<BigEndian>()?;
+        Ok((epoch, round))
+    }
+}
+
+impl ValueCodec<OrderedBlocksSchema> for PersistedOrderedBlocks {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&self)?)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(bcs::from_bytes(data)?)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,17 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use diem_types::ledger_info::LedgerInfo;
+use schemadb::schema::assert_encode_decode;
+
+#[test]
+fn test_encode_decode() {
+    let block = Block::make_genesis_block();
+    let ordered_blocks = PersistedOrderedBlocks::new(
+        vec![block],
+        LedgerInfoWithSignatures::new(LedgerInfo::mock_genesis(None), BTreeMap::new()),
+        BTreeMap::new(),
+    );
+    assert_encode_decode::<OrderedBlocksSchema>(&ordered_blocks.key(), &ordered_blocks);
+}
@@ -71,6 +71,16 @@ pub static PROPOSALS_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!("diem_consensus_proposals_count", "Count of the block proposals sent by this validator since last restart (both primary and secondary)").unwrap()
 });
 
+/// Count of the block proposals sent by this validator as soon as it aggregated the QC of the
+/// previous round, before inserting it
+pub static OPTIMISTIC_PROPOSALS_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_optimistic_proposals_count",
+        "Count of the block proposals sent by this validator as soon as it aggregated the QC of the previous round, before inserting it"
+    )
+    .unwrap()
+});
+
 /// Count the number of times a validator voted for a nil block since last restart.
 pub static VOTE_NIL_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
@@ -145,6 +155,28 @@ pub static ROUND_TIMEOUT_MS: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Histogram of the scheduled round timeouts, jitter included.
+pub static ROUND_TIMEOUT_DURATION_S: Lazy<DurationHistogram> = Lazy::new(|| {
+    DurationHistogram::new(
+        register_histogram!(
+            "diem_consensus_round_timeout_duration_s",
+            "Histogram of the scheduled round timeouts, jitter included."
+        )
+        .unwrap(),
+    )
+});
+
+/// Histogram of the jitter added to the round timeouts.
+pub static ROUND_TIMEOUT_JITTER_S: Lazy<DurationHistogram> = Lazy::new(|| {
+    DurationHistogram::new(
+        register_histogram!(
+            "diem_consensus_round_timeout_jitter_s",
+            "Histogram of the jitter added to the round timeouts."
+        )
+        .unwrap(),
+    )
+});
+
 ////////////////////////
 // SYNC MANAGER COUNTERS
 ////////////////////////
@@ -173,6 +205,33 @@ pub static CURRENT_EPOCH_VALIDATORS: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of the messages from the next epoch buffered until the epoch starts.
+pub static BUFFERED_NEXT_EPOCH_MSGS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_buffered_next_epoch_msgs",
+        "Count of the messages from the next epoch buffered until the epoch starts."
+    )
+    .unwrap()
+});
+
+/// Count of the buffered messages from the next epoch replayed once the epoch started.
+pub static REPLAYED_NEXT_EPOCH_MSGS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_replayed_next_epoch_msgs",
+        "Count of the buffered messages from the next epoch replayed once the epoch started."
+    )
+    .unwrap()
+});
+
+/// Count of the buffered messages from the next epoch dropped without being replayed.
+pub static DROPPED_NEXT_EPOCH_MSGS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_dropped_next_epoch_msgs",
+        "Count of the buffered messages from the next epoch dropped without being replayed."
+    )
+    .unwrap()
+});
+
 //////////////////////
 // BLOCK STORE COUNTERS
 //////////////////////
@@ -198,6 +257,42 @@ pub static NUM_BLOCKS_IN_TREE: Lazy<IntGauge> = Lazy::new(|| {
 //     .unwrap()
 // });
 
+/// Number of uncommitted blocks on the branch extended by the last proposal.
+pub static PROPOSAL_PENDING_BLOCKS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_consensus_proposal_pending_blocks",
+        "Number of uncommitted blocks on the branch extended by the last proposal."
+    )
+    .unwrap()
+});
+
+/// Max number of txns of the last proposal, after back pressure.
+pub static PROPOSAL_MAX_BLOCK_SIZE: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_consensus_proposal_max_block_size",
+        "Max number of txns of the last proposal, after back pressure."
+    )
+    .unwrap()
+});
+
+/// Count of the proposals shrunk by back pressure from the execution pipeline.
+pub static PROPOSAL_BACK_PRESSURE_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_proposal_back_pressure_count",
+        "Count of the proposals shrunk by back pressure from the execution pipeline."
+    )
+    .unwrap()
+});
+
+/// Count of the txns left out of proposals by the byte size and gas limits.
+pub static PROPOSAL_TXNS_OVER_LIMITS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_proposal_txns_over_limits",
+        "Count of the txns left out of proposals by the byte size and gas limits."
+    )
+    .unwrap()
+});
+
 /// Histogram for the number of txns per (committed) blocks.
 pub static NUM_TXNS_PER_BLOCK: Lazy<Histogram> = Lazy::new(|| {
     register_histogram!(
@@ -234,6 +329,33 @@ pub static PENDING_SELF_MESSAGES: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of the consensus messages being verified or waiting to be processed once verified
+pub static PENDING_MESSAGE_VERIFICATIONS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_consensus_pending_message_verifications",
+        "Count of the consensus messages being verified or waiting to be processed once verified"
+    )
+    .unwrap()
+});
+
+/// Count of the duplicated proposals and votes dropped before their verification
+pub static DUPLICATE_CONSENSUS_MSGS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_duplicate_msgs_count",
+        "Count of the duplicated proposals and votes dropped before their verification"
+    )
+    .unwrap()
+});
+
+/// Number of the local subscribers of the commit notifications
+pub static COMMIT_NOTIFICATION_SUBSCRIBERS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_consensus_commit_notification_subscribers",
+        "Number of the local subscribers of the commit notifications"
+    )
+    .unwrap()
+});
+
 /// Count of the pending outbound round timeouts
 pub static PENDING_ROUND_TIMEOUTS: Lazy<IntGauge> = Lazy::new(|| {
     register_int_gauge!(
@@ -273,6 +395,69 @@ pub static BLOCK_RETRIEVAL_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter of the blocks deleted by the ConsensusDB pruner
+pub static CONSENSUSDB_PRUNED_BLOCKS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_consensusdb_pruned_blocks_count",
+        "Counter of the blocks deleted by the ConsensusDB pruner"
+    )
+    .unwrap()
+});
+
+/// Counter of the quorum certs deleted by the ConsensusDB pruner
+pub static CONSENSUSDB_PRUNED_QCS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_consensusdb_pruned_qcs_count",
+        "Counter of the quorum certs deleted by the ConsensusDB pruner"
+    )
+    .unwrap()
+});
+
+/// Counter of the serialized bytes of the data deleted by the ConsensusDB pruner
+pub static CONSENSUSDB_PRUNED_BYTES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_consensusdb_pruned_bytes",
+        "Counter of the serialized bytes of the data deleted by the ConsensusDB pruner"
+    )
+    .unwrap()
+});
+
+/// Counter of the chunks sent for the block retrieval streams served to the peers
+pub static BLOCK_RETRIEVAL_STREAM_CHUNKS_SENT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_block_retrieval_stream_chunks_sent_count",
+        "Counter of the chunks sent for the block retrieval streams served to the peers"
+    )
+    .unwrap()
+});
+
+/// Counter of the block retrieval streams rejected because of the per-peer limits
+pub static BLOCK_RETRIEVAL_STREAMS_REJECTED: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_block_retrieval_streams_rejected_count",
+        "Counter of the block retrieval streams rejected because of the per-peer limits"
+    )
+    .unwrap()
+});
+
+/// Counter of the reliably broadcast messages sent again to the validators that didn't ack them
+pub static RELIABLE_BROADCAST_RESENT_MSGS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_reliable_broadcast_resent_msgs_count",
+        "Counter of the reliably broadcast messages sent again to the validators that didn't ack them"
+    )
+    .unwrap()
+});
+
+/// Counter of the acknowledgements of reliably broadcast messages received from the validators
+pub static RELIABLE_BROADCAST_ACKS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_reliable_broadcast_acks_count",
+        "Counter of the acknowledgements of reliably broadcast messages received from the validators"
+    )
+    .unwrap()
+});
+
 ///////////////////
 // DECOUPLED EXECUTION CHANNEL COUNTERS
 ///////////////////
@@ -302,17 +487,16 @@ pub static DECOUPLED_EXECUTION__COMMIT_PHASE_CHANNEL: Lazy<IntGauge> = Lazy::new
 pub static DECOUPLED_EXECUTION__COMMIT_MESSAGE_CHANNEL: Lazy<IntGauge> = Lazy::new(|| {
     register_int_gauge!(
         "decoupled_execution__commit_message_channel",
-        "Number of pending commit phase messages (CommitVote/CommitDecision)"
+        "Number of pending commit phase messages (CommitVote/CommitVoteBatch/CommitDecision)"
     )
     .unwrap()
 });
 
-/// Counter for the decoupling execution channel of commit messages
-/// from commit phase to itself when a timeout triggers
-pub static DECOUPLED_EXECUTION__COMMIT_MESSAGE_TIMEOUT_CHANNEL: Lazy<IntGauge> = Lazy::new(|| {
-    register_int_gauge!(
-        "decoupled_execution__commit_message_timeout_channel",
-        "Number of pending commit phase message timeouts (CommitVote/CommitDecision)"
+/// Histogram of the number of commit votes in the batches broadcast by the commit phase
+pub static DECOUPLED_EXECUTION__COMMIT_VOTE_BATCH_SIZE: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "decoupled_execution__commit_vote_batch_size",
+        "Histogram of the number of commit votes in the broadcast commit vote batches"
     )
     .unwrap()
 });
@@ -336,3 +520,53 @@ pub static DECOUPLED_EXECUTION__EXECUTION_PHASE_RESET_CHANNEL: Lazy<IntGauge> =
     )
     .unwrap()
 });
+
+//////////////////////
+// CONSENSUS OBSERVER
+//////////////////////
+
+/// Counters(queued,dequeued,dropped) related to pending network notifications to the observer
+pub static PENDING_CONSENSUS_OBSERVER_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_consensus_observer_pending_network_events",
+        "Counters(queued,dequeued,dropped) related to pending network notifications to the consensus observer",
+        &["state"]
+    )
+    .unwrap()
+});
+
+/// Number of fullnodes subscribed to the consensus publisher of this validator
+pub static CONSENSUS_OBSERVER_SUBSCRIBERS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_consensus_observer_subscribers",
+        "Number of observers subscribed to the consensus publisher"
+    )
+    .unwrap()
+});
+
+/// Highest ordered round tracked by the consensus observer
+pub static CONSENSUS_OBSERVER_ORDERED_ROUND: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_consensus_observer_ordered_round",
+        "Highest ordered round tracked by the consensus observer"
+    )
+    .unwrap()
+});
+
+/// Highest committed version the consensus observer handed to state sync
+pub static CONSENSUS_OBSERVER_COMMITTED_VERSION: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_consensus_observer_committed_version",
+        "Highest committed version the consensus observer handed to state sync"
+    )
+    .unwrap()
+});
+
+/// Number of observer messages that failed verification
+pub static CONSENSUS_OBSERVER_INVALID_MESSAGES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_observer_invalid_messages",
+        "Number of observer messages that failed verification"
+    )
+    .unwrap()
+});
@@ -0,0 +1,123 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::round_manager::{UnverifiedEvent, VerifiedEvent};
+use consensus_types::{
+    common::{Author, Round},
+    proposal_msg::ProposalMsg,
+    vote_msg::VoteMsg,
+};
+use diem_crypto::HashValue;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// Bound on the number of messages remembered across all the rounds, so that messages for far
+/// future rounds can't grow the cache without limit.
+pub const MAX_SEEN_MESSAGES: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum MessageKind {
+    Proposal,
+    Vote,
+}
+
+type SeenMessage = (Author, MessageKind, HashValue);
+
+/// Remembers the proposals and votes verified for the current and future rounds, so that the
+/// duplicated ones (e.g., replayed during sync storms) are dropped before their signatures are
+/// verified again. Only verified messages are remembered, an invalid message doesn't get the
+/// valid one it copies dropped. The messages of a round are forgotten once the round is over.
+pub struct DedupCache {
+    seen: BTreeMap<Round, HashSet<SeenMessage>>,
+    num_seen: usize,
+    // Messages below this round are never cached, they're rejected by the round logic anyway
+    min_round: Round,
+}
+
+impl DedupCache {
+    pub fn new() -> Self {
+        Self {
+            seen: BTreeMap::new(),
+            num_seen: 0,
+            min_round: 0,
+        }
+    }
+
+    /// Returns true if the same event was already verified from the same author. Only proposals
+    /// and votes are tracked, every other event is considered new.
+    pub fn contains(&self, event: &UnverifiedEvent) -> bool {
+        let key = match event {
+            UnverifiedEvent::ProposalMsg(p) => proposal_key(p),
+            UnverifiedEvent::VoteMsg(v) => vote_key(v),
+            _ => None,
+        };
+        key.map_or(false, |(round, key)| {
+            self.seen
+                .get(&round)
+                .map_or(false, |messages| messages.contains(&key))
+        })
+    }
+
+    /// Records a verified event, for its duplicates to be dropped before their verification.
+    pub fn insert(&mut self, event: &VerifiedEvent) {
+        let key = match event {
+            VerifiedEvent::ProposalMsg(p) => proposal_key(p),
+            VerifiedEvent::VoteMsg(v) => vote_key(v),
+            _ => None,
+        };
+        let (round, key) = match key {
+            Some(key) => key,
+            None => return,
+        };
+        if round < self.min_round || self.num_seen >= MAX_SEEN_MESSAGES {
+            return;
+        }
+        if self.seen.entry(round).or_default().insert(key) {
+            self.num_seen += 1;
+        }
+    }
+
+    /// Forgets the messages of the rounds below the given one.
+    pub fn evict_below(&mut self, round: Round) {
+        if round <= self.min_round {
+            return;
+        }
+        self.min_round = round;
+        let kept = self.seen.split_off(&round);
+        self.num_seen -= self.seen.values().map(HashSet::len).sum::<usize>();
+        self.seen = kept;
+    }
+
+    #[cfg(test)]
+    pub fn num_messages(&self) -> usize {
+        self.num_seen
+    }
+}
+
+fn proposal_key(proposal: &ProposalMsg) -> Option<(Round, SeenMessage)> {
+    // an unverified proposal might lack an author
+    let author = proposal.proposal().author()?;
+    let key = (author, MessageKind::Proposal, message_hash(proposal)?);
+    Some((proposal.proposal().round(), key))
+}
+
+fn vote_key(vote: &VoteMsg) -> Option<(Round, SeenMessage)> {
+    let key = (vote.vote().author(), MessageKind::Vote, message_hash(vote)?);
+    Some((vote.vote().vote_data().proposed().round(), key))
+}
+
+fn message_hash(message: &impl Serialize) -> Option<HashValue> {
+    bcs::to_bytes(message)
+        .ok()
+        .map(|bytes| HashValue::sha3_256_of(&bytes))
+}
+
+impl Default for DedupCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+#[path = "dedup_cache_test.rs"]
+mod dedup_cache_test;
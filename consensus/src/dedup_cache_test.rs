@@ -0,0 +1,59 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    dedup_cache::DedupCache,
+    round_manager::{UnverifiedEvent, VerifiedEvent},
+    test_utils::{placeholder_ledger_info, placeholder_sync_info},
+};
+use consensus_types::{vote::Vote, vote_data::VoteData, vote_msg::VoteMsg};
+use diem_types::{block_info::BlockInfo, validator_signer::ValidatorSigner};
+
+fn vote_msg(signer: &ValidatorSigner, proposed: BlockInfo) -> Box<VoteMsg> {
+    let vote = Vote::new(
+        VoteData::new(proposed, BlockInfo::random(0)),
+        signer.author(),
+        placeholder_ledger_info(),
+        signer,
+    );
+    Box::new(VoteMsg::new(vote, placeholder_sync_info()))
+}
+
+fn seen(cache: &DedupCache, vote: &VoteMsg) -> bool {
+    cache.contains(&UnverifiedEvent::VoteMsg(Box::new(vote.clone())))
+}
+
+#[test]
+fn test_dedup_cache() {
+    let (first, second) = (
+        ValidatorSigner::random([0u8; 32]),
+        ValidatorSigner::random([1u8; 32]),
+    );
+    let mut cache = DedupCache::new();
+    let block_1 = BlockInfo::random(1);
+    let block_2 = BlockInfo::random(2);
+
+    // the same message of the same author is only new until it's verified
+    let vote = vote_msg(&first, block_1.clone());
+    assert!(!seen(&cache, &vote));
+    cache.insert(&VerifiedEvent::VoteMsg(vote.clone()));
+    assert!(seen(&cache, &vote_msg(&first, block_1.clone())));
+    assert!(!seen(&cache, &vote_msg(&second, block_1.clone())));
+    assert!(!seen(&cache, &vote_msg(&first, BlockInfo::random(1))));
+    cache.insert(&VerifiedEvent::VoteMsg(vote));
+    cache.insert(&VerifiedEvent::VoteMsg(vote_msg(&first, block_2.clone())));
+    assert_eq!(cache.num_messages(), 2);
+
+    // other events aren't tracked
+    let sync_info = Box::new(placeholder_sync_info());
+    cache.insert(&VerifiedEvent::SyncInfo(sync_info.clone()));
+    assert!(!cache.contains(&UnverifiedEvent::SyncInfo(sync_info)));
+    assert_eq!(cache.num_messages(), 2);
+
+    // the messages of the rounds over are forgotten and not cached anymore
+    cache.evict_below(2);
+    assert_eq!(cache.num_messages(), 1);
+    cache.insert(&VerifiedEvent::VoteMsg(vote_msg(&first, block_1.clone())));
+    assert!(!seen(&cache, &vote_msg(&first, block_1)));
+    assert!(seen(&cache, &vote_msg(&first, block_2)));
+}
@@ -6,29 +6,43 @@ use crate::{
     counters,
     error::{error_kind, DbError},
     liveness::{
-        leader_reputation::{ActiveInactiveHeuristic, DiemDBBackend, LeaderReputation},
+        leader_reputation::{
+            ActiveInactiveHeuristic, DiemDBBackend, LeaderReputation, ProposerAndVoterHeuristic,
+            ReputationHeuristic, DEFAULT_EXCLUDE_ROUND,
+        },
         proposal_generator::ProposalGenerator,
         proposer_election::ProposerElection,
         rotating_proposer_election::{choose_leader, RotatingProposer},
         round_proposer_election::RoundProposer,
-        round_state::{ExponentialTimeInterval, RoundState, RoundStateLogSchema},
+        round_state::{ExponentialTimeInterval, RoundState, RoundStateLogSchema, TimeoutJitter},
+        vrf_proposer_election::VrfProposer,
     },
     logging::{LogEvent, LogSchema},
     metrics_safety_rules::MetricsSafetyRules,
-    network::{IncomingBlockRetrievalRequest, NetworkReceivers, NetworkSender},
+    network::{
+        BlockRetrievalStreams, IncomingBlockRetrievalRequest, NetworkReceivers, NetworkSender,
+        ReliableBroadcasts,
+    },
     network_interface::{ConsensusMsg, ConsensusNetworkSender},
+    observer::publisher::ConsensusPublisher,
     persistent_liveness_storage::{LedgerRecoveryData, PersistentLivenessStorage, RecoveryData},
     round_manager::{RecoveryManager, RoundManager, UnverifiedEvent, VerifiedEvent},
     state_replication::{StateComputer, TxnManager},
+    state_snapshot::{ConsensusStateSnapshot, SharedConsensusState},
     util::time_service::TimeService,
+    verification_pool::VerificationPool,
 };
 use anyhow::{anyhow, bail, ensure, Context};
 use channel::{diem_channel, Sender};
 use consensus_types::{
+    aggregate_signature::MAX_VALIDATOR_SET_SIZE,
+    block_retrieval::{BlockRetrievalStreamAck, BlockRetrievalStreamRequest},
+    broadcast_ack::{BroadcastAck, BroadcastKind},
     common::{Author, Round},
     epoch_retrieval::EpochRetrievalRequest,
+    vote_msg::VoteMsg,
 };
-use diem_config::config::{ConsensusConfig, ConsensusProposerType, NodeConfig};
+use diem_config::config::{ConsensusConfig, ConsensusProposerType, NodeConfig, VoteRecipientsType};
 use diem_infallible::{duration_since_epoch, Mutex};
 use diem_logger::prelude::*;
 use diem_metrics::monitor;
@@ -36,16 +50,31 @@ use diem_types::{
     account_address::AccountAddress,
     epoch_change::EpochChangeProof,
     epoch_state::EpochState,
-    on_chain_config::{OnChainConfigPayload, OnChainConsensusConfig, ValidatorSet},
+    on_chain_config::{
+        LeaderReputationHeuristic, OnChainConfigPayload, OnChainConsensusConfig,
+        ProposerElectionType, ValidatorSet, VoteRecipients,
+    },
+};
+use futures::{
+    channel::{mpsc, oneshot},
+    select, SinkExt, StreamExt,
 };
-use futures::{select, SinkExt, StreamExt};
 use network::protocols::network::Event;
-use safety_rules::SafetyRulesManager;
+use safety_rules::{SafetyRulesManager, TSafetyRules};
 use std::{
     cmp::Ordering,
+    collections::VecDeque,
     sync::{atomic::AtomicU64, Arc},
     time::Duration,
 };
+use tokio::runtime::Handle;
+
+/// Wraps the safety rules client of every epoch, e.g., to inject faults in tests.
+pub type SafetyRulesWrapper = Arc<
+    dyn Fn(Box<dyn TSafetyRules + Send + Sync>) -> Box<dyn TSafetyRules + Send + Sync>
+        + Send
+        + Sync,
+>;
 
 /// RecoveryManager is used to process events in order to sync up with peer if we can't recover from local consensusdb
 /// RoundManager is used for normal event handling.
@@ -88,6 +117,17 @@ pub struct EpochManager {
     reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
     commit_msg_tx: Option<Sender<VerifiedEvent>>,
     back_pressure: Arc<AtomicU64>,
+    consensus_publisher: Option<ConsensusPublisher>,
+    // Set by the NetworkTask when starting
+    block_retrieval_streams: Option<BlockRetrievalStreams>,
+    reliable_broadcasts: Option<ReliableBroadcasts>,
+    // Messages from the next epoch received before the epoch starts, replayed once it does
+    next_epoch_msgs: VecDeque<(AccountAddress, UnverifiedEvent)>,
+    safety_rules_wrapper: Option<SafetyRulesWrapper>,
+    // Set when starting, verifies the messages of the current epoch off the event loop
+    verification_pool: Option<VerificationPool>,
+    // Refreshed after every event, for the operators of the node
+    shared_state: SharedConsensusState,
 }
 
 impl EpochManager {
@@ -101,6 +141,8 @@ impl EpochManager {
         commit_state_computer: Arc<dyn StateComputer>,
         storage: Arc<dyn PersistentLivenessStorage>,
         reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
+        consensus_publisher: Option<ConsensusPublisher>,
+        shared_state: SharedConsensusState,
     ) -> Self {
         let author = node_config.validator_network.as_ref().unwrap().peer_id();
         let config = node_config.consensus.clone();
@@ -125,9 +167,21 @@ impl EpochManager {
             reconfig_events,
             commit_msg_tx: None,
             back_pressure,
+            consensus_publisher,
+            block_retrieval_streams: None,
+            reliable_broadcasts: None,
+            next_epoch_msgs: VecDeque::new(),
+            safety_rules_wrapper: None,
+            verification_pool: None,
+            shared_state,
         }
     }
 
+    #[cfg(any(test, feature = "fuzzing"))]
+    pub(crate) fn set_safety_rules_wrapper(&mut self, safety_rules_wrapper: SafetyRulesWrapper) {
+        self.safety_rules_wrapper = Some(safety_rules_wrapper);
+    }
+
     fn epoch_state(&self) -> &EpochState {
         match self
             .processor
@@ -148,25 +202,54 @@ impl EpochManager {
         time_service: Arc<dyn TimeService>,
         timeout_sender: channel::Sender<Round>,
     ) -> RoundState {
-        // 1.5^6 ~= 11
-        // Timeout goes from initial_timeout to initial_timeout*11 in 6 steps
+        let backoff = &self.config.round_timeout_backoff;
         let time_interval = Box::new(ExponentialTimeInterval::new(
             Duration::from_millis(self.config.round_initial_timeout_ms),
-            1.2,
-            6,
+            backoff.exponent_base,
+            backoff.max_exponent,
         ));
-        RoundState::new(time_interval, time_service, timeout_sender)
+        let jitter = if backoff.jitter_percent > 0 {
+            Some(TimeoutJitter::new(self.author, backoff.jitter_percent))
+        } else {
+            None
+        };
+        let mut round_state = RoundState::new(time_interval, time_service, timeout_sender);
+        round_state.set_timeout_backoff(jitter, backoff.max_timeout_ms.map(Duration::from_millis));
+        round_state
     }
 
-    /// Create a proposer election handler based on proposers
+    /// Create a proposer election handler based on proposers, the on-chain policy takes
+    /// precedence over the local config if it is set. An invalid on-chain policy falls back to a
+    /// rotating proposer with a single round each, the same on every validator.
     fn create_proposer_election(
         &self,
         epoch_state: &EpochState,
+        onchain_config: &OnChainConsensusConfig,
     ) -> Box<dyn ProposerElection + Send + Sync> {
         let proposers = epoch_state
             .verifier
             .get_ordered_account_addresses_iter()
             .collect::<Vec<_>>();
+        if let Some(proposer_election_type) = onchain_config.proposer_election_type() {
+            // An invalid on-chain policy can't choose a proposer for every round. The local configs
+            // may differ between validators, so all of them fall back to the same rotation instead
+            return match proposer_election_type.validate() {
+                Ok(()) => self.create_onchain_proposer_election(
+                    epoch_state.epoch,
+                    proposers,
+                    proposer_election_type,
+                ),
+                Err(e) => {
+                    error!(
+                        epoch = epoch_state.epoch,
+                        "[EpochManager] Invalid on-chain proposer election {:?}, falling back to a rotating proposer: {}",
+                        proposer_election_type,
+                        e
+                    );
+                    Box::new(RotatingProposer::new(proposers, 1))
+                }
+            };
+        }
         match &self.config.proposer_type {
             ConsensusProposerType::RotatingProposer => Box::new(RotatingProposer::new(
                 proposers,
@@ -180,14 +263,15 @@ impl EpochManager {
                     self.config.contiguous_rounds,
                 ))
             }
+            // The heuristic, the excluded rounds and the epoch it's enabled from can only be set
+            // on-chain, so that all the validators elect the same proposers
             ConsensusProposerType::LeaderReputation(heuristic_config) => {
-                let backend = Box::new(DiemDBBackend::new(proposers.len(), self.storage.diem_db()));
                 let heuristic = Box::new(ActiveInactiveHeuristic::new(
                     self.author,
                     heuristic_config.active_weights,
                     heuristic_config.inactive_weights,
                 ));
-                Box::new(LeaderReputation::new(proposers, backend, heuristic))
+                self.create_leader_reputation(proposers, heuristic, DEFAULT_EXCLUDE_ROUND)
             }
             ConsensusProposerType::RoundProposer(round_proposers) => {
                 // Hardcoded to the first proposer
@@ -197,9 +281,127 @@ impl EpochManager {
                     *default_proposer,
                 ))
             }
+            ConsensusProposerType::VrfProposer if self.config.experimental_vrf_proposer => {
+                Box::new(VrfProposer::new(proposers))
+            }
+            ConsensusProposerType::VrfProposer => {
+                error!(
+                    epoch = epoch_state.epoch,
+                    "[EpochManager] The VRF proposer election isn't enabled, falling back to a rotating proposer"
+                );
+                Box::new(RotatingProposer::new(
+                    proposers,
+                    self.config.contiguous_rounds,
+                ))
+            }
+        }
+    }
+
+    /// The validators the votes are sent to, the on-chain policy takes precedence over the local
+    /// config if it is set.
+    fn vote_recipients(
+        &self,
+        epoch: u64,
+        onchain_config: &OnChainConsensusConfig,
+    ) -> VoteRecipients {
+        if let Some(vote_recipients) = onchain_config.vote_recipients() {
+            match vote_recipients.validate() {
+                Ok(()) => return *vote_recipients,
+                Err(e) => error!(
+                    epoch = epoch,
+                    "[EpochManager] Invalid on-chain vote recipients {:?}, falling back to the local config: {}",
+                    vote_recipients,
+                    e
+                ),
+            }
+        }
+        match self.config.vote_recipients {
+            VoteRecipientsType::NextProposer => VoteRecipients::NextProposer,
+            VoteRecipientsType::NextProposers { count } => VoteRecipients::NextProposers(count),
+            VoteRecipientsType::Broadcast => VoteRecipients::Broadcast,
+        }
+    }
+
+    /// The max size and estimated gas of the proposed blocks, from the on-chain config if set
+    /// there and valid, from the local config otherwise.
+    fn payload_limits(&self, epoch: u64, onchain_config: &OnChainConsensusConfig) -> (u64, u64) {
+        if let Err(e) = onchain_config.validate_payload_limits() {
+            error!(
+                epoch = epoch,
+                "[EpochManager] Invalid on-chain payload limits, falling back to the local config: {}",
+                e
+            );
+            return (self.config.max_block_bytes, self.config.max_block_gas);
+        }
+        (
+            onchain_config
+                .max_block_bytes()
+                .unwrap_or(self.config.max_block_bytes),
+            onchain_config
+                .max_block_gas()
+                .unwrap_or(self.config.max_block_gas),
+        )
+    }
+
+    fn create_onchain_proposer_election(
+        &self,
+        epoch: u64,
+        proposers: Vec<Author>,
+        proposer_election_type: &ProposerElectionType,
+    ) -> Box<dyn ProposerElection + Send + Sync> {
+        match proposer_election_type {
+            ProposerElectionType::RotatingProposer(contiguous_rounds) => {
+                Box::new(RotatingProposer::new(proposers, *contiguous_rounds))
+            }
+            ProposerElectionType::FixedProposer(contiguous_rounds) => {
+                let proposer = choose_leader(proposers);
+                Box::new(RotatingProposer::new(vec![proposer], *contiguous_rounds))
+            }
+            ProposerElectionType::LeaderReputation(leader_reputation_type) => {
+                if epoch < leader_reputation_type.enable_from_epoch {
+                    return Box::new(RotatingProposer::new(proposers, 1));
+                }
+                let heuristic: Box<dyn ReputationHeuristic> = match leader_reputation_type.heuristic
+                {
+                    LeaderReputationHeuristic::ActiveInactive => {
+                        Box::new(ActiveInactiveHeuristic::new(
+                            self.author,
+                            leader_reputation_type.active_weight,
+                            leader_reputation_type.inactive_weight,
+                        ))
+                    }
+                    LeaderReputationHeuristic::ProposerAndVoter => {
+                        Box::new(ProposerAndVoterHeuristic::new(
+                            self.author,
+                            leader_reputation_type.active_weight,
+                            leader_reputation_type.inactive_weight,
+                        ))
+                    }
+                };
+                self.create_leader_reputation(
+                    proposers,
+                    heuristic,
+                    leader_reputation_type.exclude_round,
+                )
+            }
         }
     }
 
+    fn create_leader_reputation(
+        &self,
+        proposers: Vec<Author>,
+        heuristic: Box<dyn ReputationHeuristic>,
+        exclude_round: u64,
+    ) -> Box<dyn ProposerElection + Send + Sync> {
+        let backend = Box::new(DiemDBBackend::new(proposers.len(), self.storage.diem_db()));
+        Box::new(LeaderReputation::new(
+            proposers,
+            backend,
+            heuristic,
+            exclude_round,
+        ))
+    }
+
     async fn process_epoch_retrieval(
         &mut self,
         request: EpochRetrievalRequest,
@@ -284,9 +486,39 @@ impl EpochManager {
             ))?;
 
         monitor!("reconfig", self.expect_new_epoch().await);
+        monitor!(
+            "replay_next_epoch_msgs",
+            self.replay_next_epoch_msgs().await
+        );
         Ok(())
     }
 
+    fn create_network_sender(&self, epoch_state: &EpochState) -> NetworkSender {
+        let mut network_sender = NetworkSender::new(
+            self.author,
+            self.network_sender.clone(),
+            self.self_sender.clone(),
+            epoch_state.verifier.clone(),
+        );
+        let stream_config = &self.config.block_retrieval_stream;
+        if let Some(streams) = &self.block_retrieval_streams {
+            if stream_config.enabled {
+                network_sender.set_block_retrieval_streams(
+                    streams.clone(),
+                    stream_config.chunk_size,
+                    stream_config.window,
+                );
+            }
+        }
+        if let Some(broadcasts) = &self.reliable_broadcasts {
+            if self.config.reliable_broadcast.enabled {
+                network_sender
+                    .set_reliable_broadcasts(broadcasts.clone(), self.config.reliable_broadcast);
+            }
+        }
+        network_sender
+    }
+
     // TODO: prepare_decoupled_execution
     async fn start_round_manager(
         &mut self,
@@ -309,8 +541,11 @@ impl EpochManager {
 
         info!(epoch = epoch, "Update SafetyRules");
 
-        let mut safety_rules =
-            MetricsSafetyRules::new(self.safety_rules_manager.client(), self.storage.clone());
+        let mut safety_rules_client = self.safety_rules_manager.client();
+        if let Some(safety_rules_wrapper) = &self.safety_rules_wrapper {
+            safety_rules_client = safety_rules_wrapper(safety_rules_client);
+        }
+        let mut safety_rules = MetricsSafetyRules::new(safety_rules_client, self.storage.clone());
         if let Err(error) = safety_rules.perform_initialize() {
             error!(
                 epoch = epoch,
@@ -324,13 +559,12 @@ impl EpochManager {
             self.create_round_state(self.time_service.clone(), self.timeout_sender.clone());
 
         info!(epoch = epoch, "Create ProposerElection");
-        let proposer_election = self.create_proposer_election(&epoch_state);
-        let network_sender = NetworkSender::new(
-            self.author,
-            self.network_sender.clone(),
-            self.self_sender.clone(),
-            epoch_state.verifier.clone(),
-        );
+        let proposer_election = self.create_proposer_election(&epoch_state, &onchain_config);
+        let vote_recipients = self.vote_recipients(epoch, &onchain_config);
+        let mut network_sender = self.create_network_sender(&epoch_state);
+        if let Some(publisher) = &self.consensus_publisher {
+            network_sender.set_publisher(publisher.clone());
+        }
 
         let safety_rules_container = Arc::new(Mutex::new(safety_rules));
 
@@ -348,13 +582,19 @@ impl EpochManager {
             info!(epoch = epoch, "Create ProposalGenerator");
             // txn manager is required both by proposal generator (to pull the proposers)
             // and by event processor (to update their status).
-            let proposal_generator = ProposalGenerator::new(
+            let mut proposal_generator = ProposalGenerator::new(
                 self.author,
                 block_store.clone(),
                 self.txn_manager.clone(),
                 self.time_service.clone(),
                 self.config.max_block_size,
+                self.config.proposal_back_pressure_limit,
             );
+            let (max_block_bytes, max_block_gas) = self.payload_limits(epoch, &onchain_config);
+            proposal_generator.set_payload_limits(max_block_bytes, max_block_gas);
+            if self.config.decoupled_execution {
+                proposal_generator.set_back_pressure(self.back_pressure.clone());
+            }
 
             RoundManager::new(
                 epoch_state,
@@ -370,6 +610,9 @@ impl EpochManager {
                 onchain_config,
             )
         };
+        processor.set_block_retrieval_stream_config(self.config.block_retrieval_stream);
+        processor.set_vote_recipients(vote_recipients);
+        processor.set_optimistic_proposal(self.config.optimistic_proposal);
 
         processor.start(last_vote).await;
         self.processor = Some(RoundProcessor::Normal(processor));
@@ -387,12 +630,7 @@ impl EpochManager {
         onchain_config: OnChainConsensusConfig,
     ) {
         let epoch = epoch_state.epoch;
-        let network_sender = NetworkSender::new(
-            self.author,
-            self.network_sender.clone(),
-            self.self_sender.clone(),
-            epoch_state.verifier.clone(),
-        );
+        let network_sender = self.create_network_sender(&epoch_state);
 
         // TODO: create a ordering only state computer
 
@@ -411,11 +649,22 @@ impl EpochManager {
         let validator_set: ValidatorSet = payload
             .get()
             .expect("failed to get ValidatorSet from payload");
+        // the signers of an aggregated signature are identified by their index in a bitmask
+        assert!(
+            validator_set.payload().len() <= MAX_VALIDATOR_SET_SIZE,
+            "Validator set of epoch {} has {} validators, more than the {} an aggregated signature supports",
+            payload.epoch(),
+            validator_set.payload().len(),
+            MAX_VALIDATOR_SET_SIZE,
+        );
         let epoch_state = EpochState {
             epoch: payload.epoch(),
             verifier: (&validator_set).into(),
         };
         let onchain_config: OnChainConsensusConfig = payload.get().unwrap_or_default();
+        if let Some(verification_pool) = self.verification_pool.as_mut() {
+            verification_pool.new_epoch(&epoch_state);
+        }
 
         match self.storage.start() {
             LivenessStorageData::RecoveryData(initial_data) => {
@@ -438,27 +687,109 @@ impl EpochManager {
         let maybe_unverified_event = self.process_epoch(peer_id, consensus_msg).await?;
 
         if let Some(unverified_event) = maybe_unverified_event {
-            // same epoch -> run well-formedness + signature check
-            let verified_event = unverified_event
-                .clone()
-                .verify(&self.epoch_state().verifier)
-                .context("[EpochManager] Verify event")
-                .map_err(|err| {
-                    error!(
-                        SecurityEvent::ConsensusInvalidMessage,
-                        remote_peer = peer_id,
-                        error = ?err,
-                        unverified_event = unverified_event
-                    );
-                    err
-                })?;
-
-            // process the verified event
-            self.process_event(peer_id, verified_event).await?;
+            self.process_unverified_event(peer_id, unverified_event)
+                .await;
         }
         Ok(())
     }
 
+    /// Acknowledges the messages the peers broadcast reliably once they're verified, so that they
+    /// stop sending them again. A peer sending invalid messages keeps sending them until it gives
+    /// up.
+    fn ack_reliable_broadcast(&mut self, peer_id: AccountAddress, ack: Option<BroadcastAck>) {
+        let ack = match ack {
+            Some(ack) if peer_id != self.author => ack,
+            _ => return,
+        };
+        if let Err(e) = self
+            .network_sender
+            .send_to(peer_id, ConsensusMsg::BroadcastAck(Box::new(ack)))
+        {
+            warn!(
+                remote_peer = peer_id,
+                error = ?e, "Failed to acknowledge a reliably broadcast message",
+            );
+        }
+    }
+
+    async fn process_unverified_event(
+        &mut self,
+        peer_id: AccountAddress,
+        unverified_event: UnverifiedEvent,
+    ) {
+        if let Some(RoundProcessor::Normal(p)) = self.processor.as_ref() {
+            if p.is_duplicate(&unverified_event) {
+                counters::DUPLICATE_CONSENSUS_MSGS.inc();
+                // the duplicate of a verified timeout is resent because the ack got lost
+                if let UnverifiedEvent::VoteMsg(vote_msg) = &unverified_event {
+                    self.ack_reliable_broadcast(peer_id, timeout_ack(vote_msg));
+                }
+                return;
+            }
+        }
+        // same epoch -> run well-formedness + signature check on the verification pool, the
+        // event comes back through the verified channel
+        self.verification_pool
+            .as_mut()
+            .expect("[EpochManager] VerificationPool not started yet")
+            .submit(peer_id, unverified_event)
+            .await;
+    }
+
+    async fn process_verified_event(
+        &mut self,
+        epoch: u64,
+        peer_id: AccountAddress,
+        verified_event: VerifiedEvent,
+    ) -> anyhow::Result<()> {
+        counters::PENDING_MESSAGE_VERIFICATIONS.dec();
+        // the epoch changed while the event was verified
+        if epoch != self.epoch() {
+            return Ok(());
+        }
+        if let Some(RoundProcessor::Normal(p)) = self.processor.as_mut() {
+            p.record_verified(&verified_event);
+        }
+        let ack = match &verified_event {
+            VerifiedEvent::VoteMsg(vote_msg) => timeout_ack(vote_msg),
+            VerifiedEvent::CommitVoteBatch(batch) => Some(BroadcastAck::new(
+                BroadcastKind::CommitVote,
+                batch.epoch(),
+                batch.round(),
+            )),
+            _ => None,
+        };
+        self.ack_reliable_broadcast(peer_id, ack);
+        self.process_event(peer_id, verified_event).await
+    }
+
+    /// Keeps a message from the next epoch until the epoch starts, dropping the oldest buffered
+    /// message once the buffer is full.
+    fn buffer_next_epoch_msg(&mut self, peer_id: AccountAddress, event: UnverifiedEvent) {
+        if self.config.max_buffered_next_epoch_messages == 0 {
+            return;
+        }
+        if self.next_epoch_msgs.len() >= self.config.max_buffered_next_epoch_messages {
+            self.next_epoch_msgs.pop_front();
+            counters::DROPPED_NEXT_EPOCH_MSGS.inc();
+        }
+        self.next_epoch_msgs.push_back((peer_id, event));
+        counters::BUFFERED_NEXT_EPOCH_MSGS.inc();
+    }
+
+    /// Replays the messages buffered for the epoch that just started, in their arrival order.
+    async fn replay_next_epoch_msgs(&mut self) {
+        let buffered_msgs = std::mem::take(&mut self.next_epoch_msgs);
+        for (peer_id, event) in buffered_msgs {
+            if event.epoch() != self.epoch() {
+                counters::DROPPED_NEXT_EPOCH_MSGS.inc();
+                continue;
+            }
+            counters::REPLAYED_NEXT_EPOCH_MSGS.inc();
+            self.process_unverified_event(peer_id, event).await;
+        }
+    }
+
     async fn process_epoch(
         &mut self,
         peer_id: AccountAddress,
@@ -469,11 +800,15 @@ impl EpochManager {
             | ConsensusMsg::SyncInfo(_)
             | ConsensusMsg::VoteMsg(_)
             | ConsensusMsg::CommitVoteMsg(_)
-            | ConsensusMsg::CommitDecisionMsg(_) => {
+            | ConsensusMsg::CommitDecisionMsg(_)
+            | ConsensusMsg::CommitVoteBatchMsg(_) => {
                 let event: UnverifiedEvent = msg.into();
                 if event.epoch() == self.epoch() {
                     return Ok(Some(event));
                 } else {
+                    if event.epoch() == self.epoch() + 1 {
+                        self.buffer_next_epoch_msg(peer_id, event.clone());
+                    }
                     monitor!(
                         "process_different_epoch_consensus_msg",
                         self.process_different_epoch(event.epoch(), peer_id).await?
@@ -498,6 +833,20 @@ impl EpochManager {
                     );
                 }
             }
+            ConsensusMsg::BlockRetrievalStreamRequest(request) => {
+                monitor!(
+                    "process_block_retrieval_stream_request",
+                    self.process_block_retrieval_stream_request(peer_id, *request)
+                        .await?
+                );
+            }
+            ConsensusMsg::BlockRetrievalStreamAck(ack) => {
+                monitor!(
+                    "process_block_retrieval_stream_ack",
+                    self.process_block_retrieval_stream_ack(peer_id, *ack)
+                        .await?
+                );
+            }
             ConsensusMsg::EpochRetrievalRequest(request) => {
                 ensure!(
                     request.end_epoch <= self.epoch(),
@@ -526,7 +875,9 @@ impl EpochManager {
                     VerifiedEvent::ProposalMsg(proposal) => p.process_proposal_msg(*proposal).await,
                     VerifiedEvent::VoteMsg(vote) => p.process_vote_msg(*vote).await,
                     VerifiedEvent::SyncInfo(sync_info) => p.sync_up(&sync_info, peer_id).await,
-                    VerifiedEvent::CommitVote(_) | VerifiedEvent::CommitDecision(_) => {
+                    VerifiedEvent::CommitVote(_)
+                    | VerifiedEvent::CommitDecision(_)
+                    | VerifiedEvent::CommitVoteBatch(_) => {
                         return Err(anyhow!(
                             "Ignoring commit vote/decision message during recovery"
                         ));
@@ -551,7 +902,8 @@ impl EpochManager {
                     p.process_sync_info_msg(*sync_info, peer_id).await
                 ),
                 verified_event @ VerifiedEvent::CommitVote(_)
-                | verified_event @ VerifiedEvent::CommitDecision(_) => {
+                | verified_event @ VerifiedEvent::CommitDecision(_)
+                | verified_event @ VerifiedEvent::CommitVoteBatch(_) => {
                     if let Some(sender) = &self.commit_msg_tx {
                         sender.clone().send(verified_event).await.map_err(|err| {
                             anyhow!(
@@ -583,6 +935,31 @@ impl EpochManager {
         }
     }
 
+    async fn process_block_retrieval_stream_request(
+        &mut self,
+        peer_id: AccountAddress,
+        request: BlockRetrievalStreamRequest,
+    ) -> anyhow::Result<()> {
+        match self.processor_mut() {
+            RoundProcessor::Normal(p) => {
+                p.process_block_retrieval_stream_request(peer_id, request)
+                    .await
+            }
+            _ => bail!("[EpochManager] RoundManager not started yet"),
+        }
+    }
+
+    async fn process_block_retrieval_stream_ack(
+        &mut self,
+        peer_id: AccountAddress,
+        ack: BlockRetrievalStreamAck,
+    ) -> anyhow::Result<()> {
+        match self.processor_mut() {
+            RoundProcessor::Normal(p) => p.process_block_retrieval_stream_ack(peer_id, ack).await,
+            _ => bail!("[EpochManager] RoundManager not started yet"),
+        }
+    }
+
     async fn process_local_timeout(&mut self, round: u64) -> anyhow::Result<()> {
         match self.processor_mut() {
             RoundProcessor::Normal(p) => p.process_local_timeout(round).await,
@@ -598,14 +975,27 @@ impl EpochManager {
         }
     }
 
+    /// Runs consensus until a shutdown is requested on `shutdown_requests`, the request is
+    /// acknowledged once the event being processed is done.
     pub async fn start(
         mut self,
         mut round_timeout_sender_rx: channel::Receiver<Round>,
         mut network_receivers: NetworkReceivers,
+        mut shutdown_requests: mpsc::Receiver<oneshot::Sender<()>>,
     ) {
+        self.block_retrieval_streams = Some(network_receivers.block_retrieval_streams.clone());
+        self.reliable_broadcasts = Some(network_receivers.reliable_broadcasts.clone());
+        let (verified_tx, mut verified_rx) = mpsc::unbounded();
+        self.verification_pool = Some(VerificationPool::new(
+            self.config.num_verification_workers,
+            Handle::current(),
+            verified_tx,
+            self.network_sender.clone(),
+        ));
         // initial start of the processor
         self.expect_new_epoch().await;
         loop {
+            let mut shutdown_ack = None;
             let result = monitor!(
                 "main_loop",
                 select! {
@@ -613,18 +1003,44 @@ impl EpochManager {
                         let (peer, msg) = (msg.0, msg.1);
                         monitor!("process_message", self.process_message(peer, msg).await.with_context(|| format!("from peer: {}", peer)))
                     }
+                    verified = verified_rx.select_next_some() => {
+                        let (epoch, peer, event) = verified;
+                        monitor!("process_verified_event", self.process_verified_event(epoch, peer, event).await.with_context(|| format!("from peer: {}", peer)))
+                    }
                     block_retrieval = network_receivers.block_retrieval.select_next_some() => {
                         monitor!("process_block_retrieval", self.process_block_retrieval(block_retrieval).await)
                     }
                     round = round_timeout_sender_rx.select_next_some() => {
                         monitor!("process_local_timeout", self.process_local_timeout(round).await)
                     }
+                    ack = shutdown_requests.select_next_some() => {
+                        shutdown_ack = Some(ack);
+                        Ok(())
+                    }
                 }
             );
-            let round_state = if let RoundProcessor::Normal(p) = self.processor_mut() {
-                Some(p.round_state())
-            } else {
-                None
+            if let Some(ack) = shutdown_ack {
+                // Every event is processed to the end before the next one is taken, so no
+                // commit nor safety data write is left half-done here.
+                info!(epoch = self.epoch(), "Consensus stopped for shutdown");
+                let _ = ack.send(());
+                return;
+            }
+            let round_state = match self
+                .processor
+                .as_ref()
+                .expect("[EpochManager] not started yet")
+            {
+                RoundProcessor::Normal(p) => {
+                    self.shared_state.update(p.state_snapshot());
+                    Some(p.round_state())
+                }
+                RoundProcessor::Recovery(p) => {
+                    self.shared_state.update(ConsensusStateSnapshot::Recovery {
+                        epoch: p.epoch_state().epoch,
+                    });
+                    None
+                }
             };
             match result {
                 Ok(_) => trace!(RoundStateLogSchema::new(round_state)),
@@ -642,3 +1058,16 @@ impl EpochManager {
         }
     }
 }
+
+/// The ack of a vote message, if it's a timeout the peer broadcast reliably.
+fn timeout_ack(vote_msg: &VoteMsg) -> Option<BroadcastAck> {
+    if vote_msg.vote().is_timeout() {
+        Some(BroadcastAck::new(
+            BroadcastKind::Timeout,
+            vote_msg.epoch(),
+            vote_msg.vote().vote_data().proposed().round(),
+        ))
+    } else {
+        None
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::experimental;
+use diem_types::validator_verifier::VerifyError as ValidatorVerifyError;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -44,6 +45,19 @@ pub struct VerifyError {
     inner: anyhow::Error,
 }
 
+impl VerifyError {
+    /// Whether the message carries a signature that doesn't match it, rather than e.g. an unknown
+    /// author or a malformed content.
+    pub fn is_invalid_signature(&self) -> bool {
+        self.inner.chain().any(|cause| {
+            matches!(
+                cause.downcast_ref::<ValidatorVerifyError>(),
+                Some(ValidatorVerifyError::InvalidSignature)
+            )
+        })
+    }
+}
+
 pub fn error_kind(e: &anyhow::Error) -> &'static str {
     if e.downcast_ref::<executor_types::Error>().is_some() {
         return "Execution";
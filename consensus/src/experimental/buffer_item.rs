@@ -1,8 +1,12 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::state_replication::StateComputerCommitCallBackType;
-use consensus_types::{common::Author, executed_block::ExecutedBlock};
+use crate::{
+    consensusdb::PersistedOrderedBlocks, state_replication::StateComputerCommitCallBackType,
+};
+use consensus_types::{
+    common::Author, executed_block::ExecutedBlock, experimental::commit_vote::CommitVote,
+};
 use diem_crypto::ed25519::Ed25519Signature;
 use diem_types::{
     account_address::AccountAddress,
@@ -10,6 +14,7 @@ use diem_types::{
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     validator_verifier::ValidatorVerifier,
 };
+use executor_types::StateComputeResult;
 #[allow(deprecated)]
 use itertools::zip;
 use std::collections::BTreeMap;
@@ -35,12 +40,14 @@ pub struct SignedBufferItem {
     pub executed_blocks: Vec<ExecutedBlock>,
     pub commit_proof: LedgerInfoWithSignatures,
     pub callback: StateComputerCommitCallBackType,
+    pub ordered_proof: LedgerInfoWithSignatures,
 }
 
 pub struct AggregatedBufferItem {
     pub executed_blocks: Vec<ExecutedBlock>,
     pub aggregated_proof: LedgerInfoWithSignatures,
     pub callback: StateComputerCommitCallBackType,
+    pub ordered_proof: LedgerInfoWithSignatures,
 }
 
 pub enum BufferItem {
@@ -63,6 +70,25 @@ impl BufferItem {
             ordered_proof,
         }))
     }
+
+    /// Rebuild an ordered buffer item from persisted ordered blocks, the blocks will go through
+    /// the execution phase again.
+    pub fn from_persisted(
+        persisted: PersistedOrderedBlocks,
+        callback: StateComputerCommitCallBackType,
+    ) -> Self {
+        Self::Ordered(Box::new(OrderedBufferItem {
+            pending_votes: persisted.commit_votes,
+            callback,
+            ordered_blocks: persisted
+                .ordered_blocks
+                .into_iter()
+                .map(|block| ExecutedBlock::new(block, StateComputeResult::new_dummy()))
+                .collect(),
+            ordered_proof: persisted.ordered_proof,
+        }))
+    }
+
     #[allow(deprecated)]
     // pipeline functions
     pub fn advance_to_executed(self, executed_blocks: Vec<ExecutedBlock>) -> Self {
@@ -121,6 +147,7 @@ impl BufferItem {
                     executed_blocks: executed_item.executed_blocks,
                     callback: executed_item.callback,
                     commit_proof: commit_ledger_info_with_sigs,
+                    ordered_proof: executed_item.ordered_proof,
                 }))
             }
             _ => {
@@ -142,6 +169,7 @@ impl BufferItem {
                         executed_blocks: signed_item.executed_blocks,
                         aggregated_proof: signed_item.commit_proof,
                         callback: signed_item.callback,
+                        ordered_proof: signed_item.ordered_proof,
                     }))
                 } else {
                     Self::Signed(Box::new(signed_item))
@@ -153,6 +181,36 @@ impl BufferItem {
         }
     }
 
+    /// Add a commit vote from another validator. Votes received before the item is signed are
+    /// kept pending and checked once the commit ledger info is known.
+    pub fn add_commit_vote(self, vote: &CommitVote, validator: &ValidatorVerifier) -> Self {
+        match self {
+            Self::Ordered(mut ordered) => {
+                ordered
+                    .pending_votes
+                    .insert(vote.author(), vote.signature().clone());
+                Self::Ordered(ordered)
+            }
+            Self::Executed(mut executed) => {
+                executed
+                    .pending_votes
+                    .insert(vote.author(), vote.signature().clone());
+                Self::Executed(executed)
+            }
+            Self::Signed(mut signed) => {
+                if vote.ledger_info() == signed.commit_proof.ledger_info()
+                    && vote.verify(validator).is_ok()
+                {
+                    signed
+                        .commit_proof
+                        .add_signature(vote.author(), vote.signature().clone());
+                }
+                Self::Signed(signed).try_advance_to_aggregated(validator)
+            }
+            aggregated @ Self::Aggregated(_) => aggregated,
+        }
+    }
+
     // generic functions
     pub fn get_blocks(&self) -> &Vec<ExecutedBlock> {
         match self {
@@ -163,6 +221,37 @@ impl BufferItem {
         }
     }
 
+    pub fn get_ordered_proof(&self) -> &LedgerInfoWithSignatures {
+        match self {
+            Self::Ordered(ordered) => &ordered.ordered_proof,
+            Self::Executed(executed) => &executed.ordered_proof,
+            Self::Signed(signed) => &signed.ordered_proof,
+            Self::Aggregated(aggregated) => &aggregated.ordered_proof,
+        }
+    }
+
+    /// The commit votes collected so far, for signed and aggregated items these are the
+    /// signatures of the commit proof.
+    pub fn get_commit_votes(&self) -> &BTreeMap<AccountAddress, Ed25519Signature> {
+        match self {
+            Self::Ordered(ordered) => &ordered.pending_votes,
+            Self::Executed(executed) => &executed.pending_votes,
+            Self::Signed(signed) => signed.commit_proof.signatures(),
+            Self::Aggregated(aggregated) => aggregated.aggregated_proof.signatures(),
+        }
+    }
+
+    pub fn to_persisted(&self) -> PersistedOrderedBlocks {
+        PersistedOrderedBlocks::new(
+            self.get_blocks()
+                .iter()
+                .map(|executed_block| executed_block.block().clone())
+                .collect(),
+            self.get_ordered_proof().clone(),
+            self.get_commit_votes().clone(),
+        )
+    }
+
     pub fn get_commit_info(&self) -> &BlockInfo {
         match self {
             Self::Ordered(_) => {
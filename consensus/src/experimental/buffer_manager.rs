@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    consensusdb::PersistedOrderedBlocks,
     experimental::{
         buffer_item::BufferItem,
         execution_phase::{ExecutionRequest, ExecutionResponse},
@@ -10,10 +11,15 @@ use crate::{
         signing_phase::{SigningRequest, SigningResponse},
     },
     network::NetworkSender,
+    persistent_liveness_storage::PersistentLivenessStorage,
     round_manager::VerifiedEvent,
-    state_replication::StateComputerCommitCallBackType,
+    state_replication::{empty_state_computer_call_back, StateComputerCommitCallBackType},
 };
-use consensus_types::{common::Author, executed_block::ExecutedBlock};
+use consensus_types::{
+    common::{Author, Round},
+    executed_block::ExecutedBlock,
+};
+use diem_logger::prelude::*;
 use diem_types::{
     account_address::AccountAddress,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
@@ -24,8 +30,10 @@ use futures::{
         mpsc::{UnboundedReceiver, UnboundedSender},
         oneshot,
     },
-    SinkExt,
+    stream::FusedStream,
+    SinkExt, StreamExt,
 };
+use std::{collections::VecDeque, sync::Arc};
 
 pub type SyncAck = ();
 pub fn sync_ack_new() -> SyncAck {}
@@ -49,6 +57,10 @@ pub type Receiver<T> = UnboundedReceiver<T>;
 /// StateManager handles the states of ordered blocks and
 /// interacts with the execution phase, the signing phase, and
 /// the persisting phase.
+/// The ordered blocks and their commit votes are persisted in consensusdb until they are
+/// committed, so that a restarted node does not need to re-order them.
+/// Each root points to the last item that went through its phase, None if no item of the buffer
+/// did: the next item to go through the phase is the one after the root, or the head.
 pub struct StateManager {
     author: Author,
 
@@ -68,12 +80,17 @@ pub struct StateManager {
 
     persisting_phase_tx: Sender<PersistingRequest>,
     persisting_phase_rx: Receiver<PersistingResponse>,
+    // the (epoch, round) keys of the items sent to the persisting phase, in the order of its
+    // responses, they are deleted from consensusdb once the items are committed
+    persisting_keys: VecDeque<(u64, Round)>,
 
     block_rx: UnboundedReceiver<OrderedBlocks>,
     sync_rx: UnboundedReceiver<SyncRequest>,
     end_epoch: bool,
 
     verifier: ValidatorVerifier,
+
+    storage: Arc<dyn PersistentLivenessStorage>,
 }
 
 impl StateManager {
@@ -90,42 +107,72 @@ impl StateManager {
         block_rx: UnboundedReceiver<OrderedBlocks>,
         sync_rx: UnboundedReceiver<SyncRequest>,
         verifier: ValidatorVerifier,
-    ) -> Self {
-        let buffer = List::<BufferItem>::new();
-
-        // point the roots to the head
-        let execution_root = buffer.head.as_ref().cloned();
-        let signing_root = buffer.head.as_ref().cloned();
-        let aggregation_root = buffer.head.as_ref().cloned();
+        storage: Arc<dyn PersistentLivenessStorage>,
+    ) -> anyhow::Result<Self> {
+        let mut buffer = List::<BufferItem>::new();
+
+        // restore the ordered blocks persisted before a restart and send them to the execution
+        // phase again, the block store of the restarted node has no commit callbacks for them
+        for persisted in storage.get_ordered_blocks()? {
+            let buffer_item =
+                BufferItem::from_persisted(persisted, empty_state_computer_call_back());
+            let ordered_blocks = buffer_item.get_blocks().clone();
+            buffer.push_back(buffer_item);
+            execution_phase_tx.unbounded_send(ExecutionRequest { ordered_blocks })?;
+        }
 
-        Self {
+        Ok(Self {
             author,
 
             buffer,
 
-            execution_root,
+            execution_root: None,
             execution_phase_tx,
             execution_phase_rx,
 
-            signing_root,
+            signing_root: None,
             signing_phase_tx,
             signing_phase_rx,
 
-            aggregation_root,
+            aggregation_root: None,
             commit_msg_tx,
             commit_msg_rx,
 
             persisting_phase_tx,
             persisting_phase_rx,
+            persisting_keys: VecDeque::new(),
 
             block_rx,
             sync_rx,
             end_epoch: false,
 
             verifier,
+
+            storage,
+        })
+    }
+
+    /// The item after the given root, the head if no item of the buffer went past the root.
+    fn next_item(&self, root: &BufferItemRootType) -> BufferItemRootType {
+        if root.is_none() {
+            self.buffer.head.as_ref().cloned()
+        } else {
+            get_next(root)
         }
     }
 
+    /// Reset the roots so that they don't point to items about to be popped from the buffer.
+    fn reset_roots(&mut self) {
+        self.execution_root = None;
+        self.signing_root = None;
+        self.aggregation_root = None;
+    }
+
+    /// Persist a buffer item, overwriting the previously persisted commit votes.
+    fn persist_buffer_item(&self, buffer_item: &BufferItem) -> anyhow::Result<()> {
+        self.storage.save_ordered_blocks(buffer_item.to_persisted())
+    }
+
     async fn process_ordered_blocks(
         &mut self,
         ordered_blocks: OrderedBlocks,
@@ -136,12 +183,10 @@ impl StateManager {
             callback,
         } = ordered_blocks;
 
-        // push blocks to buffer
-        self.buffer.push_back(BufferItem::new_ordered(
-            ordered_blocks.clone(),
-            ordered_proof,
-            callback,
-        ));
+        // persist and push blocks to buffer
+        let buffer_item = BufferItem::new_ordered(ordered_blocks.clone(), ordered_proof, callback);
+        self.persist_buffer_item(&buffer_item)?;
+        self.buffer.push_back(buffer_item);
 
         // send blocks to execution phase
         self.execution_phase_tx
@@ -161,8 +206,15 @@ impl StateManager {
             // buffer manager will stop
             self.end_epoch = true;
         } else {
+            // the remaining items go through the phases again from the head
+            self.reset_roots();
+
             // clear the buffer until (including) the ledger_info
+            let mut pruned_keys = vec![];
             while let Some(buffer_item) = self.buffer.pop_front() {
+                pruned_keys.push(PersistedOrderedBlocks::key_of(
+                    buffer_item.get_ordered_proof(),
+                ));
                 if buffer_item
                     .get_commit_info()
                     .match_ordered_only(ledger_info.commit_info())
@@ -170,11 +222,7 @@ impl StateManager {
                     break;
                 }
             }
-
-            // reset roots
-            self.execution_root = self.buffer.head.as_ref().cloned();
-            self.signing_root = self.buffer.head.as_ref().cloned();
-            self.aggregation_root = self.buffer.head.as_ref().cloned();
+            self.storage.prune_ordered_blocks(pruned_keys)?;
         }
 
         // ack reset
@@ -188,12 +236,7 @@ impl StateManager {
         &mut self,
         executed_blocks: Vec<ExecutedBlock>,
     ) -> anyhow::Result<()> {
-        if self.execution_root.is_none() {
-            // right after a sync
-            return Ok(());
-        }
-
-        let current_cursor = get_next(&self.execution_root);
+        let current_cursor = self.next_item(&self.execution_root);
 
         if current_cursor.is_some() {
             // update buffer
@@ -255,13 +298,13 @@ impl StateManager {
                 .process_successful_execution_response(executed_blocks)
                 .await;
             // try the next item (even if sending to signing phase failed)
-            let cursor = get_next(&self.execution_root);
-            let buffer_item = get_elem(&cursor);
-            self.execution_phase_tx
-                .send(ExecutionRequest {
-                    ordered_blocks: buffer_item.get_blocks().clone(),
-                })
-                .await?;
+            let cursor = self.next_item(&self.execution_root);
+            if cursor.is_some() {
+                let ordered_blocks = get_elem(&cursor).get_blocks().clone();
+                self.execution_phase_tx
+                    .send(ExecutionRequest { ordered_blocks })
+                    .await?;
+            }
             res
         } else {
             // it might be possible that the buffer is already reset
@@ -290,29 +333,136 @@ impl StateManager {
         }
     }
 
-    async fn start(self) {
-
-        // loop receving new blocks or reset
-        // while !self.end_epoch {
-
-        // select from all rx channels,
-        // if new from block_rx, push to buffer
-        // if new from reset_rx, make a mark that stops all the following ops
-        // if new from execution_phase_rx,
-        //   if execution failure, send all the blocks to execution_phase.
-        //   Otherwise,
-        //     update execution_root and send the blocks from execution_root to end to execution_phase
-        // if new from signing_phase_rx,
-        //   update sigining_root and send the blocks from signing_root to execution_root to signing_phase
-        // if new from commit_msg_rx,
-        //   collect sig and update the sigs
-        //   if aggregated,
-        //     update aggregation_root
-        // if new from persisting_phase_rx,
-        //   pop blocks from buffer, and continue to post-committing ops
-        //   send the blocks from aggregation_root to the end to persisting_phase
-
-        // if not reset, retry sending the commit_vote msg via commit_msg_tx
-        // }
+    /// this function adds our signature to the item right after the signing root and persists
+    /// the commit votes collected so far.
+    fn process_signing_response(&mut self, response: SigningResponse) -> anyhow::Result<()> {
+        let signature = response?;
+        let current_cursor = self.next_item(&self.signing_root);
+        if current_cursor.is_none() {
+            return Ok(());
+        }
+        let buffer_item = take_elem(&current_cursor);
+        if !matches!(buffer_item, BufferItem::Executed(_)) {
+            // a sync req happened before the response
+            set_elem(&current_cursor, buffer_item);
+            return Ok(());
+        }
+        let buffer_item = buffer_item
+            .advance_to_signed(self.author, signature, &self.verifier)
+            .try_advance_to_aggregated(&self.verifier);
+        let res = self.persist_buffer_item(&buffer_item);
+        set_elem(&current_cursor, buffer_item);
+        self.signing_root = current_cursor;
+        res
+    }
+
+    /// this function adds a commit vote to the buffer item it votes for and persists the commit
+    /// votes collected so far, so that a restarted node does not need to collect them again.
+    fn process_commit_msg(&mut self, commit_msg: VerifiedEvent) -> anyhow::Result<()> {
+        let vote = match commit_msg {
+            VerifiedEvent::CommitVote(vote) => vote,
+            _ => return Ok(()),
+        };
+        let target = (vote.epoch(), vote.round());
+        let mut cursor = self.buffer.head.clone();
+        while cursor.is_some() {
+            if PersistedOrderedBlocks::key_of(get_elem(&cursor).get_ordered_proof()) == target {
+                let buffer_item = take_elem(&cursor).add_commit_vote(&vote, &self.verifier);
+                let res = self.persist_buffer_item(&buffer_item);
+                set_elem(&cursor, buffer_item);
+                return res;
+            }
+            cursor = get_next(&cursor);
+        }
+        // the vote is for blocks we have not ordered yet or have already committed
+        Ok(())
+    }
+
+    /// this function pops the aggregated items at the front of the buffer and sends them to the
+    /// persisting phase, in order: an aggregated item waits for the items before it.
+    async fn advance_head(&mut self) -> anyhow::Result<()> {
+        while matches!(
+            self.buffer.peek_front().as_deref(),
+            Some(BufferItem::Aggregated(_))
+        ) {
+            // the roots point to the item at the head or after it, once the head is popped the
+            // next item goes through the phases from the new head
+            let head = self.buffer.head.as_ref().cloned();
+            for root in [
+                &mut self.execution_root,
+                &mut self.signing_root,
+                &mut self.aggregation_root,
+            ] {
+                if link_eq(root, &head) {
+                    *root = None;
+                }
+            }
+            drop(head);
+
+            let aggregated = match self.buffer.pop_front() {
+                Some(BufferItem::Aggregated(aggregated)) => *aggregated,
+                _ => unreachable!("the head of the buffer is an aggregated item"),
+            };
+            self.persisting_keys
+                .push_back(PersistedOrderedBlocks::key_of(&aggregated.ordered_proof));
+            self.persisting_phase_tx
+                .send(PersistingRequest {
+                    blocks: aggregated
+                        .executed_blocks
+                        .into_iter()
+                        .map(Arc::new)
+                        .collect(),
+                    commit_ledger_info: aggregated.aggregated_proof,
+                    callback: aggregated.callback,
+                })
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// this function deletes the ordered blocks of the oldest item sent to the persisting phase
+    /// from consensusdb once they are committed. If the commit fails, they are kept so that a
+    /// restarted node commits them again.
+    fn process_persisting_response(&mut self, response: PersistingResponse) -> anyhow::Result<()> {
+        let key = self
+            .persisting_keys
+            .pop_front()
+            .ok_or_else(|| anyhow::anyhow!("Persisting response without a request"))?;
+        response?;
+        self.storage.prune_ordered_blocks(vec![key])
+    }
+
+    /// Run the buffer manager until the epoch ends or all its channels are closed.
+    pub async fn start(mut self) {
+        while !self.end_epoch {
+            let res = tokio::select! {
+                ordered_blocks = self.block_rx.select_next_some(), if !self.block_rx.is_terminated() => {
+                    self.process_ordered_blocks(ordered_blocks).await
+                }
+                sync_event = self.sync_rx.select_next_some(), if !self.sync_rx.is_terminated() => {
+                    self.process_sync_req(sync_event).await
+                }
+                execution_resp = self.execution_phase_rx.select_next_some(), if !self.execution_phase_rx.is_terminated() => {
+                    self.process_execution_resp(execution_resp).await
+                }
+                signing_resp = self.signing_phase_rx.select_next_some(), if !self.signing_phase_rx.is_terminated() => {
+                    self.process_signing_response(signing_resp)
+                }
+                commit_msg = self.commit_msg_rx.select_next_some(), if !self.commit_msg_rx.is_terminated() => {
+                    self.process_commit_msg(commit_msg)
+                }
+                persisting_resp = self.persisting_phase_rx.select_next_some(), if !self.persisting_phase_rx.is_terminated() => {
+                    self.process_persisting_response(persisting_resp)
+                }
+                else => break,
+            };
+            if let Err(e) = res {
+                error!(error = e.to_string(), "Error in the buffer manager");
+            }
+            if let Err(e) = self.advance_head().await {
+                error!(error = e.to_string(), "Error in the buffer manager");
+            }
+            // TODO: retry sending the commit votes via commit_msg_tx
+        }
     }
 }
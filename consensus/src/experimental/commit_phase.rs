@@ -6,11 +6,16 @@ use crate::{
     network::NetworkSender, network_interface::ConsensusMsg, round_manager::VerifiedEvent,
     state_replication::StateComputer,
 };
-use channel::{Receiver, Sender};
+use channel::Receiver;
 use consensus_types::{
+    aggregate_signature::PartialSignatures,
+    broadcast_ack::BroadcastKind,
     common::Author,
     executed_block::ExecutedBlock,
-    experimental::{commit_decision::CommitDecision, commit_vote::CommitVote},
+    experimental::{
+        commit_decision::CommitDecision, commit_vote::CommitVote,
+        commit_vote_batch::CommitVoteBatch,
+    },
 };
 use core::sync::atomic::Ordering;
 use diem_crypto::ed25519::Ed25519Signature;
@@ -24,11 +29,12 @@ use diem_types::{
     validator_verifier::{ValidatorVerifier, VerifyError},
 };
 use executor_types::Error as ExecutionError;
-use futures::{FutureExt, SinkExt, StreamExt};
+use futures::{FutureExt, StreamExt};
 use safety_rules::TSafetyRules;
 use std::{
     collections::BTreeMap,
     sync::{atomic::AtomicU64, Arc},
+    time::{Duration, Instant},
 };
 use tokio::time;
 
@@ -48,9 +54,13 @@ the commit phase commits the blocks as well as broadcasts a commit
 decision message together with the quorum of signatures. The commit
 decision message helps the slower nodes to quickly catch up without
 having to collect the signatures.
+Instead of one message per vote, the commit votes are broadcast in
+batches per ledger info: the own vote goes out with the next periodic
+flush, and every retry carries all the votes collected so far.
 */
 
 const COMMIT_PHASE_TIMEOUT_SEC: u64 = 1; // retry timeout in seconds
+const COMMIT_VOTE_FLUSH_INTERVAL_MS: u64 = 50; // commit vote batch flush interval in milliseconds
 
 pub struct CommitChannelType(
     pub Vec<ExecutedBlock>,
@@ -74,8 +84,14 @@ impl std::fmt::Display for CommitChannelType {
 pub struct PendingBlocks {
     blocks: Vec<ExecutedBlock>,
     ledger_info_sig: LedgerInfoWithSignatures,
+    // the (verified) signatures of ledger_info_sig, with their voting power
+    partial_signatures: PartialSignatures,
     block_info: BlockInfo,
     callback: StateComputerCommitCallBackType,
+    // the votes to broadcast in the next commit vote batch
+    outgoing_votes: BTreeMap<Author, Ed25519Signature>,
+    // whether outgoing_votes changed since the last broadcast batch
+    has_new_outgoing_votes: bool,
 }
 
 impl PendingBlocks {
@@ -83,17 +99,36 @@ impl PendingBlocks {
         blocks: Vec<ExecutedBlock>,
         ledger_info_sig: LedgerInfoWithSignatures,
         callback: StateComputerCommitCallBackType,
+        verifier: &ValidatorVerifier,
     ) -> Self {
         assert!(!blocks.is_empty()); // the commit phase should not accept empty blocks.
         let block_info = blocks.last().unwrap().block_info();
+        let partial_signatures = Self::partial_signatures(&ledger_info_sig, verifier);
         Self {
             blocks,
             ledger_info_sig,
+            partial_signatures,
             block_info,
             callback,
+            outgoing_votes: BTreeMap::new(),
+            has_new_outgoing_votes: false,
         }
     }
 
+    fn partial_signatures(
+        ledger_info_sig: &LedgerInfoWithSignatures,
+        verifier: &ValidatorVerifier,
+    ) -> PartialSignatures {
+        let mut partial_signatures = PartialSignatures::new();
+        for (author, signature) in ledger_info_sig.signatures() {
+            // signatures of unknown authors don't count towards the quorum
+            partial_signatures
+                .add_signature(*author, signature.clone(), verifier)
+                .ok();
+        }
+        partial_signatures
+    }
+
     pub fn block_info(&self) -> &BlockInfo {
         &self.block_info
     }
@@ -114,20 +149,82 @@ impl PendingBlocks {
         &self.ledger_info_sig
     }
 
-    pub fn ledger_info_sig_mut(&mut self) -> &mut LedgerInfoWithSignatures {
-        &mut self.ledger_info_sig
+    /// Adds a verified signature of the ledger info and returns the voting power collected so
+    /// far. The signature is also sent along with the next commit vote batch.
+    pub fn add_signature(
+        &mut self,
+        author: Author,
+        signature: Ed25519Signature,
+        verifier: &ValidatorVerifier,
+    ) -> ::std::result::Result<u64, VerifyError> {
+        let voting_power =
+            self.partial_signatures
+                .add_signature(author, signature.clone(), verifier)?;
+        self.ledger_info_sig
+            .add_signature(author, signature.clone());
+        self.outgoing_votes.insert(author, signature);
+        Ok(voting_power)
+    }
+
+    /// Adds a vote to broadcast with the next flush of the commit votes.
+    pub fn add_outgoing_vote(&mut self, author: Author, signature: Ed25519Signature) {
+        self.outgoing_votes.insert(author, signature);
+        self.has_new_outgoing_votes = true;
     }
 
-    pub fn replace_ledger_info_sig(&mut self, new_ledger_info_sig: LedgerInfoWithSignatures) {
+    /// Returns the batch of outgoing votes if there are new votes to send since the last batch,
+    /// or if `retry` is set.
+    pub fn take_commit_vote_batch(&mut self, retry: bool) -> Option<CommitVoteBatch> {
+        if self.outgoing_votes.is_empty() || !(self.has_new_outgoing_votes || retry) {
+            return None;
+        }
+        self.has_new_outgoing_votes = false;
+        Some(CommitVoteBatch::new(
+            self.ledger_info_sig.ledger_info().clone(),
+            self.outgoing_votes.clone(),
+        ))
+    }
+
+    pub fn replace_ledger_info_sig(
+        &mut self,
+        new_ledger_info_sig: LedgerInfoWithSignatures,
+        verifier: &ValidatorVerifier,
+    ) {
+        self.partial_signatures = Self::partial_signatures(&new_ledger_info_sig, verifier);
         self.ledger_info_sig = new_ledger_info_sig
     }
 
+    /// Checks whether the collected signatures certify the blocks: they must reach a quorum, and
+    /// all sign the exact ledger info which is committed.
     pub fn verify(&self, verifier: &ValidatorVerifier) -> ::std::result::Result<(), VerifyError> {
-        if &self.block_info == self.ledger_info_sig.ledger_info().commit_info() {
-            self.ledger_info_sig.verify_signatures(verifier)
-        } else {
-            Err(VerifyError::InconsistentBlockInfo)
+        if &self.block_info != self.ledger_info_sig.ledger_info().commit_info() {
+            return Err(VerifyError::InconsistentBlockInfo);
+        }
+        if !self.partial_signatures.has_quorum(verifier) {
+            return Err(VerifyError::TooLittleVotingPower {
+                voting_power: self.partial_signatures.voting_power(),
+                quorum_voting_power: verifier.quorum_voting_power(),
+            });
         }
+        self.ledger_info_sig.verify_signatures(verifier)
+    }
+
+    /// Votes only count if they sign the ledger info of the pending blocks, not merely the same
+    /// commit info: their signatures are aggregated into it.
+    fn check_ledger_info(&self, ledger_info: &LedgerInfo) -> ::std::result::Result<(), Error> {
+        if ledger_info.commit_info() != self.block_info() {
+            return Err(Error::InconsistentBlockInfo(
+                ledger_info.commit_info().clone(),
+                self.block_info().clone(),
+            ));
+        }
+        if ledger_info != self.ledger_info_sig.ledger_info() {
+            return Err(Error::InconsistentLedgerInfo(
+                ledger_info.clone(),
+                self.ledger_info_sig.ledger_info().clone(),
+            ));
+        }
+        Ok(())
     }
 }
 
@@ -141,8 +238,8 @@ pub struct CommitPhase {
     author: Author,
     back_pressure: Arc<AtomicU64>,
     network_sender: NetworkSender,
-    timeout_event_tx: Sender<CommitVote>,
-    timeout_event_rx: Receiver<CommitVote>,
+    // when the last commit vote batch was broadcast
+    last_vote_broadcast: Instant,
     reset_event_rx: Receiver<oneshot::Sender<SyncAck>>,
 }
 
@@ -177,22 +274,6 @@ macro_rules! report_err {
     };
 }
 
-/// shortcut for sendng a message with a timeout retry event
-async fn broadcast_commit_vote_with_retry(
-    mut network_sender: NetworkSender,
-    cv: CommitVote,
-    mut notification: Sender<CommitVote>,
-) {
-    network_sender
-        .broadcast(ConsensusMsg::CommitVoteMsg(Box::new(cv.clone())))
-        .await;
-    time::sleep(time::Duration::from_secs(COMMIT_PHASE_TIMEOUT_SEC)).await;
-    report_err!(
-        notification.send(cv).await,
-        "Error in sending timeout events"
-    )
-}
-
 impl CommitPhase {
     pub fn new(
         commit_channel_recv: Receiver<CommitChannelType>,
@@ -205,10 +286,6 @@ impl CommitPhase {
         network_sender: NetworkSender,
         reset_event_rx: Receiver<oneshot::Sender<SyncAck>>,
     ) -> Self {
-        let (timeout_event_tx, timeout_event_rx) = channel::new::<CommitVote>(
-            2,
-            &counters::DECOUPLED_EXECUTION__COMMIT_MESSAGE_TIMEOUT_CHANNEL,
-        );
         Self {
             commit_channel_recv,
             execution_proxy,
@@ -219,8 +296,7 @@ impl CommitPhase {
             author,
             back_pressure,
             network_sender,
-            timeout_event_tx,
-            timeout_event_rx,
+            last_vote_broadcast: Instant::now(),
             reset_event_rx,
         }
     }
@@ -231,20 +307,17 @@ impl CommitPhase {
         commit_vote: &CommitVote,
     ) -> anyhow::Result<(), Error> {
         if let Some(pending_blocks) = self.blocks.as_mut() {
-            let commit_ledger_info = commit_vote.ledger_info();
-
-            // if the block infos do not match
-            if commit_ledger_info.commit_info() != pending_blocks.block_info() {
-                return Err(Error::InconsistentBlockInfo(
-                    commit_ledger_info.commit_info().clone(),
-                    pending_blocks.block_info().clone(),
-                )); // ignore the message
-            }
+            // if the ledger infos do not match, ignore the message
+            pending_blocks.check_ledger_info(commit_vote.ledger_info())?;
 
             // add the signature into the signature tree
             pending_blocks
-                .ledger_info_sig_mut()
-                .add_signature(commit_vote.author(), commit_vote.signature().clone());
+                .add_signature(
+                    commit_vote.author(),
+                    commit_vote.signature().clone(),
+                    &self.verifier,
+                )
+                .map_err(|_| Error::VerificationError)?;
         } else {
             info!("Ignore the commit vote message because the commit phase does not have a pending block.")
         }
@@ -252,6 +325,28 @@ impl CommitPhase {
         Ok(())
     }
 
+    /// Notified when receiving a commit vote batch message (assuming verified)
+    pub async fn process_commit_vote_batch(
+        &mut self,
+        commit_vote_batch: &CommitVoteBatch,
+    ) -> anyhow::Result<(), Error> {
+        if let Some(pending_blocks) = self.blocks.as_mut() {
+            // if the ledger infos do not match, ignore the message
+            pending_blocks.check_ledger_info(commit_vote_batch.ledger_info())?;
+
+            // add the signatures into the signature tree, one at a time
+            for (author, signature) in commit_vote_batch.signatures() {
+                pending_blocks
+                    .add_signature(*author, signature.clone(), &self.verifier)
+                    .map_err(|_| Error::VerificationError)?;
+            }
+        } else {
+            info!("Ignore the commit vote batch message because the commit phase does not have a pending block.")
+        }
+
+        Ok(())
+    }
+
     /// Notified when receiving a commit decision message (assuming verified)
     pub async fn process_commit_decision(
         &mut self,
@@ -269,7 +364,7 @@ impl CommitPhase {
             }
 
             // replace the signature tree
-            pending_blocks.replace_ledger_info_sig(commit_ledger_info.clone());
+            pending_blocks.replace_ledger_info_sig(commit_ledger_info.clone(), &self.verifier);
         } else {
             info!("Ignore the commit decision message because the commit phase does not have a pending block.")
         }
@@ -323,34 +418,53 @@ impl CommitPhase {
             .lock()
             .sign_commit_vote(ordered_ledger_info, commit_ledger_info.clone())?;
 
-        let commit_vote =
-            CommitVote::new_with_signature(self.author, commit_ledger_info.clone(), signature);
-
         let commit_ledger_info_with_sig = LedgerInfoWithSignatures::new(
             commit_ledger_info,
             BTreeMap::<AccountAddress, Ed25519Signature>::new(),
         );
 
         // we need to wait for the commit vote itself to collect the signature.
-        //commit_ledger_info_with_sig.add_signature(self.author, signature);
-        self.set_blocks(Some(PendingBlocks::new(
+        // the vote is broadcast with the next flush of the commit votes, and
+        // note that this message will also reach the node itself
+        let mut pending_blocks = PendingBlocks::new(
             blocks,
             commit_ledger_info_with_sig,
             callback,
-        )));
-
-        // asynchronously broadcast the message.
-        // note that this message will also reach the node itself
-        // if the message delivery fails, it needs to resend the message, or otherwise the liveness might compromise.
-        tokio::spawn(broadcast_commit_vote_with_retry(
-            self.network_sender.clone(),
-            commit_vote,
-            self.timeout_event_tx.clone(),
-        ));
+            &self.verifier,
+        );
+        pending_blocks.add_outgoing_vote(self.author, signature);
+        self.set_blocks(Some(pending_blocks));
 
         Ok(())
     }
 
+    /// Broadcasts the batch of commit votes of the pending blocks if it has new votes.
+    /// If the message delivery fails, it needs to resend the message, or otherwise the liveness
+    /// might compromise: the batch is sent again (with all the votes collected so far) when no
+    /// batch was sent for COMMIT_PHASE_TIMEOUT_SEC.
+    pub async fn flush_commit_votes(&mut self) {
+        let retry =
+            self.last_vote_broadcast.elapsed() >= Duration::from_secs(COMMIT_PHASE_TIMEOUT_SEC);
+        if let Some(batch) = self
+            .blocks
+            .as_mut()
+            .and_then(|pending_blocks| pending_blocks.take_commit_vote_batch(retry))
+        {
+            counters::DECOUPLED_EXECUTION__COMMIT_VOTE_BATCH_SIZE
+                .observe(batch.signatures().len() as f64);
+            self.last_vote_broadcast = Instant::now();
+            let (epoch, round) = (batch.epoch(), batch.round());
+            self.network_sender
+                .reliable_broadcast(
+                    ConsensusMsg::CommitVoteBatchMsg(Box::new(batch)),
+                    BroadcastKind::CommitVote,
+                    epoch,
+                    round,
+                )
+                .await;
+        }
+    }
+
     pub fn set_blocks(&mut self, blocks_or_none: Option<PendingBlocks>) {
         self.blocks = blocks_or_none;
     }
@@ -384,41 +498,66 @@ impl CommitPhase {
         Ok(())
     }
 
+    async fn process_commit_msg(&mut self, msg: VerifiedEvent) {
+        match msg {
+            VerifiedEvent::CommitVote(cv) => {
+                monitor!(
+                    "process_commit_vote",
+                    report_err!(
+                        self.process_commit_vote(&*cv).await,
+                        "Error in processing commit vote."
+                    )
+                );
+            }
+            VerifiedEvent::CommitVoteBatch(batch) => {
+                monitor!(
+                    "process_commit_vote_batch",
+                    report_err!(
+                        self.process_commit_vote_batch(&*batch).await,
+                        "Error in processing commit vote batch."
+                    )
+                );
+            }
+            VerifiedEvent::CommitDecision(cd) => {
+                monitor!(
+                    "process_commit_decision",
+                    report_err!(
+                        self.process_commit_decision(&*cd).await,
+                        "Error in processing commit decision."
+                    )
+                );
+            }
+            _ => {
+                unreachable!("Unexpected messages: something wrong with message dispatching.")
+            }
+        };
+    }
+
     pub async fn start(mut self) {
+        let mut flush_interval =
+            time::interval(Duration::from_millis(COMMIT_VOTE_FLUSH_INTERVAL_MS));
         loop {
             // if we are still collecting the signatures
             tokio::select! {
                 // process messages dispatched from epoch_manager
                 msg = self.commit_msg_rx.select_next_some(), if self.blocks.is_some() => {
-                        match msg {
-                            VerifiedEvent::CommitVote(cv) => {
-                                monitor!(
-                                    "process_commit_vote",
-                                    report_err!(self.process_commit_vote(&*cv).await, "Error in processing commit vote.")
-                                );
-                            }
-                            VerifiedEvent::CommitDecision(cd) => {
-                                monitor!(
-                                    "process_commit_decision",
-                                    report_err!(self.process_commit_decision(&*cd).await, "Error in processing commit decision.")
-                                );
-                            }
-                            _ => {
-                                unreachable!("Unexpected messages: something wrong with message dispatching.")
+                        self.process_commit_msg(msg).await;
+                        // process the messages that are already there before checking the quorum
+                        while self.blocks.is_some() {
+                            match self.commit_msg_rx.next().now_or_never() {
+                                Some(Some(msg)) => self.process_commit_msg(msg).await,
+                                _ => break,
                             }
-                        };
+                        }
                         report_err!(
                             // check if the blocks are ready to commit
                             self.check_commit().await,
                             "Error in checking whether self.block is ready to commit."
                         );
                 }
-                retry_cv = self.timeout_event_rx.select_next_some(), if self.blocks.is_some() && !self.commit_msg_rx.is_terminated()  => {
-                    let pending_blocks = self.blocks.as_ref().unwrap();
-                    if pending_blocks.block_info() == retry_cv.commit_info() {
-                        // retry broadcasting the message if the blocks are still pending
-                        tokio::spawn(broadcast_commit_vote_with_retry(self.network_sender.clone(), retry_cv, self.timeout_event_tx.clone()));
-                    }
+                _ = flush_interval.tick(), if self.blocks.is_some() && !self.commit_msg_rx.is_terminated() => {
+                    // broadcast the new commit votes, or retry if the blocks are still pending
+                    self.flush_commit_votes().await;
                 }
                 // callback event might come when self.blocks is not empty
                 reset_event_callback = self.reset_event_rx.select_next_some() => {
@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use diem_types::block_info::BlockInfo;
+use diem_types::{block_info::BlockInfo, ledger_info::LedgerInfo};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -11,6 +11,8 @@ use thiserror::Error;
 pub enum Error {
     #[error("The block in the message, {0}, does not match expected block, {1}")]
     InconsistentBlockInfo(BlockInfo, BlockInfo),
+    #[error("The ledger info in the message, {0}, does not match expected ledger info, {1}")]
+    InconsistentLedgerInfo(LedgerInfo, LedgerInfo),
     #[error("Verification Error")]
     VerificationError,
     #[error("Reset host dropped")]
@@ -0,0 +1,248 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    experimental::{
+        buffer_manager::{OrderedBlocks, StateManager, SyncRequest},
+        execution_phase::{ExecutionRequest, ExecutionResponse},
+        persisting_phase::{PersistingRequest, PersistingResponse},
+        signing_phase::{SigningRequest, SigningResponse},
+        tests::test_utils::prepare_executed_blocks_with_ordered_ledger_info,
+    },
+    network::NetworkSender,
+    network_interface::ConsensusNetworkSender,
+    persistent_liveness_storage::PersistentLivenessStorage,
+    round_manager::VerifiedEvent,
+    state_replication::empty_state_computer_call_back,
+    test_utils::{consensus_runtime, timed_block_on, MockStorage},
+};
+use channel::{diem_channel, message_queues::QueueStyle};
+use consensus_types::experimental::commit_vote::CommitVote;
+use diem_types::{
+    account_address::AccountAddress,
+    validator_signer::ValidatorSigner,
+    validator_verifier::{random_validator_verifier, ValidatorVerifier},
+};
+use futures::{
+    channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+    future::join,
+    StreamExt,
+};
+use network::peer_manager::{ConnectionRequestSender, PeerManagerRequestSender};
+use std::sync::Arc;
+
+/// The channels the test drives a state manager through, dropping them stops the manager.
+struct StateManagerChannels {
+    block_tx: UnboundedSender<OrderedBlocks>,
+    commit_msg_tx: diem_channel::Sender<AccountAddress, VerifiedEvent>,
+    execution_phase_rx: UnboundedReceiver<ExecutionRequest>,
+    execution_phase_tx: UnboundedSender<ExecutionResponse>,
+    signing_phase_rx: UnboundedReceiver<SigningRequest>,
+    signing_phase_tx: UnboundedSender<SigningResponse>,
+    persisting_phase_rx: UnboundedReceiver<PersistingRequest>,
+    persisting_phase_tx: UnboundedSender<PersistingResponse>,
+    _sync_tx: UnboundedSender<SyncRequest>,
+}
+
+fn prepare_state_manager(
+    signer: &ValidatorSigner,
+    verifier: ValidatorVerifier,
+    storage: Arc<dyn PersistentLivenessStorage>,
+) -> (StateManager, StateManagerChannels) {
+    let (network_reqs_tx, _network_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
+    let (connection_reqs_tx, _) = diem_channel::new(QueueStyle::FIFO, 8, None);
+    let network_sender = ConsensusNetworkSender::new(
+        PeerManagerRequestSender::new(network_reqs_tx),
+        ConnectionRequestSender::new(connection_reqs_tx),
+    );
+    let (self_loop_tx, _self_loop_rx) = channel::new_test(1000);
+    let network = NetworkSender::new(
+        signer.author(),
+        network_sender,
+        self_loop_tx,
+        verifier.clone(),
+    );
+
+    let (execution_request_tx, execution_request_rx) = unbounded();
+    let (execution_response_tx, execution_response_rx) = unbounded();
+    let (signing_request_tx, signing_request_rx) = unbounded();
+    let (signing_response_tx, signing_response_rx) = unbounded();
+    let (commit_msg_tx, commit_msg_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
+    let (persisting_request_tx, persisting_request_rx) = unbounded();
+    let (persisting_response_tx, persisting_response_rx) = unbounded();
+    let (block_tx, block_rx) = unbounded();
+    let (sync_tx, sync_rx) = unbounded();
+
+    let state_manager = StateManager::new(
+        signer.author(),
+        execution_request_tx,
+        execution_response_rx,
+        signing_request_tx,
+        signing_response_rx,
+        network,
+        commit_msg_rx,
+        persisting_request_tx,
+        persisting_response_rx,
+        block_rx,
+        sync_rx,
+        verifier,
+        storage,
+    )
+    .unwrap();
+
+    (
+        state_manager,
+        StateManagerChannels {
+            block_tx,
+            commit_msg_tx,
+            execution_phase_rx: execution_request_rx,
+            execution_phase_tx: execution_response_tx,
+            signing_phase_rx: signing_request_rx,
+            signing_phase_tx: signing_response_tx,
+            persisting_phase_rx: persisting_request_rx,
+            persisting_phase_tx: persisting_response_tx,
+            _sync_tx: sync_tx,
+        },
+    )
+}
+
+#[test]
+fn test_restore_ordered_blocks_after_restart() {
+    let mut runtime = consensus_runtime();
+    let (signers, verifier) = random_validator_verifier(2, None, false);
+    let (_, storage) = MockStorage::start_for_testing((&verifier).into());
+
+    let (ordered_blocks, ordered_proof) =
+        prepare_executed_blocks_with_ordered_ledger_info(&signers[0]);
+    let block_ids: Vec<_> = ordered_blocks.iter().map(|block| block.id()).collect();
+    let vote = CommitVote::new(
+        signers[1].author(),
+        ordered_proof.ledger_info().clone(),
+        &signers[1],
+    );
+
+    // order the blocks and receive a commit vote for them before the restart
+    let (state_manager, mut channels) =
+        prepare_state_manager(&signers[0], verifier.clone(), storage.clone());
+    let (ordered_proof_clone, block_ids_clone, vote_clone, signers_author) = (
+        ordered_proof.clone(),
+        block_ids.clone(),
+        vote.clone(),
+        signers[1].author(),
+    );
+    // the state manager holds the buffer in an Rc, so it runs on the test thread
+    let drive = async move {
+        channels
+            .block_tx
+            .unbounded_send(OrderedBlocks {
+                ordered_blocks,
+                ordered_proof: ordered_proof_clone,
+                callback: empty_state_computer_call_back(),
+            })
+            .unwrap();
+        let request = channels.execution_phase_rx.next().await.unwrap();
+        assert_eq!(
+            request
+                .ordered_blocks
+                .iter()
+                .map(|block| block.id())
+                .collect::<Vec<_>>(),
+            block_ids_clone
+        );
+        channels
+            .commit_msg_tx
+            .push(
+                signers_author,
+                VerifiedEvent::CommitVote(Box::new(vote_clone)),
+            )
+            .unwrap();
+    };
+    timed_block_on(&mut runtime, async {
+        join(state_manager.start(), drive).await;
+    });
+
+    let persisted = storage.get_ordered_blocks().unwrap();
+    assert_eq!(persisted.len(), 1);
+    assert_eq!(persisted[0].ordered_proof, ordered_proof);
+    assert_eq!(
+        persisted[0].commit_votes.get(&signers[1].author()),
+        Some(vote.signature())
+    );
+
+    // the restarted state manager sends the persisted blocks to the execution phase again
+    let (_state_manager, mut channels) = prepare_state_manager(&signers[0], verifier, storage);
+    let request = timed_block_on(&mut runtime, channels.execution_phase_rx.next()).unwrap();
+    assert_eq!(
+        request
+            .ordered_blocks
+            .iter()
+            .map(|block| block.id())
+            .collect::<Vec<_>>(),
+        block_ids
+    );
+}
+
+#[test]
+fn test_prune_ordered_blocks_once_persisted() {
+    let mut runtime = consensus_runtime();
+    let (signers, verifier) = random_validator_verifier(1, None, false);
+    let (_, storage) = MockStorage::start_for_testing((&verifier).into());
+
+    let (ordered_blocks, ordered_proof) =
+        prepare_executed_blocks_with_ordered_ledger_info(&signers[0]);
+    let block_ids: Vec<_> = ordered_blocks.iter().map(|block| block.id()).collect();
+
+    let (state_manager, mut channels) =
+        prepare_state_manager(&signers[0], verifier, storage.clone());
+    let signer = signers[0].clone();
+    let storage_clone = storage.clone();
+    // the state manager holds the buffer in an Rc, so it runs on the test thread
+    let drive = async move {
+        channels
+            .block_tx
+            .unbounded_send(OrderedBlocks {
+                ordered_blocks,
+                ordered_proof,
+                callback: empty_state_computer_call_back(),
+            })
+            .unwrap();
+
+        // the blocks go through the execution phase and the signing phase, the signature of the
+        // single validator aggregates them
+        let request = channels.execution_phase_rx.next().await.unwrap();
+        channels
+            .execution_phase_tx
+            .unbounded_send(ExecutionResponse {
+                inner: Ok(request.ordered_blocks),
+            })
+            .unwrap();
+        let request = channels.signing_phase_rx.next().await.unwrap();
+        channels
+            .signing_phase_tx
+            .unbounded_send(Ok(signer.sign(&request.commit_ledger_info)))
+            .unwrap();
+
+        // the aggregated blocks are sent to the persisting phase, but kept in consensusdb until
+        // they are committed
+        let request = channels.persisting_phase_rx.next().await.unwrap();
+        assert_eq!(
+            request
+                .blocks
+                .iter()
+                .map(|block| block.id())
+                .collect::<Vec<_>>(),
+            block_ids
+        );
+        assert!(request
+            .commit_ledger_info
+            .signatures()
+            .contains_key(&signer.author()));
+        assert_eq!(storage_clone.get_ordered_blocks().unwrap().len(), 1);
+        channels.persisting_phase_tx.unbounded_send(Ok(())).unwrap();
+    };
+    timed_block_on(&mut runtime, async {
+        join(state_manager.start(), drive).await;
+    });
+
+    assert!(storage.get_ordered_blocks().unwrap().is_empty());
+}
@@ -18,7 +18,7 @@ use crate::{
     experimental::ordering_state_computer::OrderingStateComputer, state_replication::StateComputer,
 };
 use consensus_types::block::{block_test_utils::certificate_for_genesis, Block};
-use diem_crypto::{ed25519::Ed25519Signature, hash::ACCUMULATOR_PLACEHOLDER_HASH};
+use diem_crypto::{ed25519::Ed25519Signature, hash::ACCUMULATOR_PLACEHOLDER_HASH, HashValue};
 
 use diem_types::{
     account_address::AccountAddress, validator_signer::ValidatorSigner,
@@ -30,7 +30,9 @@ use network::protocols::network::Event;
 use std::collections::BTreeMap;
 
 use crate::test_utils::{timed_block_on, EmptyStateComputer};
-use consensus_types::experimental::{commit_decision::CommitDecision, commit_vote::CommitVote};
+use consensus_types::experimental::{
+    commit_decision::CommitDecision, commit_vote::CommitVote, commit_vote_batch::CommitVoteBatch,
+};
 use diem_types::block_info::BlockInfo;
 
 use crate::{
@@ -194,16 +196,16 @@ mod commit_phase_e2e_tests {
 
             // check the next two messages from the self loop channel
             let commit_vote_msg = self_loop_rx.next().await.unwrap();
-            if let Event::Message(_, ConsensusMsg::CommitVoteMsg(request)) = commit_vote_msg {
+            if let Event::Message(_, ConsensusMsg::CommitVoteBatchMsg(request)) = commit_vote_msg {
                 let second_commit_vote_msg = self_loop_rx.next().await.unwrap();
-                if let Event::Message(_, ConsensusMsg::CommitVoteMsg(second_request)) =
+                if let Event::Message(_, ConsensusMsg::CommitVoteBatchMsg(second_request)) =
                     second_commit_vote_msg
                 {
                     assert_eq!(request, second_request);
                     return;
                 }
             }
-            panic!("We expect only commit vote messages from the self loop channel in this test.");
+            panic!("We expect only commit vote batch messages from the self loop channel in this test.");
         });
     }
 
@@ -390,7 +392,7 @@ mod commit_phase_function_tests {
             _safety_rules_container,
             signers,
             _state_computer,
-            _validator,
+            validator,
             mut commit_phase,
             _block_store,
         ) = prepare_commit_phase(&runtime);
@@ -404,6 +406,7 @@ mod commit_phase_function_tests {
                 vecblocks,
                 li_sig,
                 empty_state_computer_call_back(),
+                &validator,
             )));
 
             let random_commit_vote = generate_random_commit_vote(signer);
@@ -427,7 +430,7 @@ mod commit_phase_function_tests {
             _safety_rules_container,
             signers,
             _state_computer,
-            _validator,
+            validator,
             mut commit_phase,
             _block_store,
         ) = prepare_commit_phase(&runtime);
@@ -441,6 +444,7 @@ mod commit_phase_function_tests {
                 vecblocks,
                 li_sig,
                 empty_state_computer_call_back(),
+                &validator,
             )));
 
             let random_commit_decision = generate_random_commit_decision(signer);
@@ -454,6 +458,101 @@ mod commit_phase_function_tests {
         });
     }
 
+    #[test]
+    fn test_commit_phase_process_commit_vote_batch() {
+        let mut runtime = consensus_runtime();
+        let (
+            _commit_tx,
+            _msg_tx,
+            _commit_phase_reset_tx,
+            _commit_result_rx,
+            _self_loop_rx,
+            _safety_rules_container,
+            signers,
+            _state_computer,
+            validator,
+            mut commit_phase,
+            _block_store,
+        ) = prepare_commit_phase(&runtime);
+
+        timed_block_on(&mut runtime, async move {
+            let signer = &signers[0];
+
+            let (vecblocks, li_sig) = prepare_executed_blocks_with_executed_ledger_info(signer);
+            let ledger_info_with_no_sig = new_executed_ledger_info_with_empty_signature(
+                vecblocks.last().unwrap().block_info(),
+                li_sig.ledger_info(),
+            );
+            let commit_ledger_info = ledger_info_with_no_sig.ledger_info().clone();
+
+            let mut pending_blocks = PendingBlocks::new(
+                vecblocks,
+                ledger_info_with_no_sig,
+                empty_state_computer_call_back(),
+                &validator,
+            );
+
+            // the own vote goes out with the next batch only
+            assert!(pending_blocks.take_commit_vote_batch(false).is_none());
+            pending_blocks.add_outgoing_vote(signer.author(), signer.sign(&commit_ledger_info));
+            let batch = pending_blocks.take_commit_vote_batch(false).unwrap();
+            assert_eq!(batch.ledger_info(), &commit_ledger_info);
+            assert_eq!(batch.signatures().len(), 1);
+            assert!(pending_blocks.take_commit_vote_batch(false).is_none());
+            assert_eq!(
+                pending_blocks.take_commit_vote_batch(true),
+                Some(batch.clone())
+            );
+            commit_phase.set_blocks(Some(pending_blocks));
+
+            // inconsistent block info
+            let random_commit_vote = generate_random_commit_vote(signer);
+            let mut signatures = BTreeMap::new();
+            signatures.insert(
+                random_commit_vote.author(),
+                random_commit_vote.signature().clone(),
+            );
+            let random_batch =
+                CommitVoteBatch::new(random_commit_vote.ledger_info().clone(), signatures);
+            assert!(matches!(
+                commit_phase.process_commit_vote_batch(&random_batch).await,
+                Err(Error::InconsistentBlockInfo(_, _))
+            ));
+            commit_phase.check_commit().await.ok();
+            assert!(commit_phase.blocks().is_some());
+
+            // same block info, but another ledger info
+            let forged_ledger_info = LedgerInfo::new(
+                commit_ledger_info.commit_info().clone(),
+                HashValue::random(),
+            );
+            let mut signatures = BTreeMap::new();
+            signatures.insert(signer.author(), signer.sign(&forged_ledger_info));
+            let forged_batch = CommitVoteBatch::new(forged_ledger_info, signatures);
+            assert!(matches!(
+                commit_phase.process_commit_vote_batch(&forged_batch).await,
+                Err(Error::InconsistentLedgerInfo(_, _))
+            ));
+            commit_phase.check_commit().await.ok();
+            assert!(commit_phase.blocks().is_some());
+
+            // the signatures of the batch are aggregated
+            commit_phase
+                .process_commit_vote_batch(&batch)
+                .await
+                .unwrap();
+            assert!(commit_phase
+                .blocks()
+                .as_ref()
+                .unwrap()
+                .verify(&validator)
+                .is_ok());
+            commit_phase.check_commit().await.ok();
+            assert!(commit_phase.blocks().is_none());
+            assert_eq!(commit_phase.load_back_pressure(), 1);
+        });
+    }
+
     #[test]
     fn test_commit_phase_process_reset() {
         let mut runtime = consensus_runtime();
@@ -466,7 +565,7 @@ mod commit_phase_function_tests {
             _safety_rules_container,
             signers,
             _state_computer,
-            _validator,
+            validator,
             mut commit_phase,
             _block_store,
         ) = prepare_commit_phase(&runtime);
@@ -495,6 +594,7 @@ mod commit_phase_function_tests {
                 vecblocks.clone(),
                 li_sig.clone(),
                 empty_state_computer_call_back(),
+                &validator,
             )));
 
             // reset
@@ -528,7 +628,7 @@ mod commit_phase_function_tests {
             _safety_rules_container,
             signers,
             _state_computer,
-            _validator,
+            validator,
             mut commit_phase,
             _block_store,
         ) = prepare_commit_phase(&runtime);
@@ -549,6 +649,7 @@ mod commit_phase_function_tests {
                 vecblocks.clone(),
                 li_sig.clone(),
                 empty_state_computer_call_back(),
+                &validator,
             )));
 
             // when blocks is good
@@ -564,15 +665,39 @@ mod commit_phase_function_tests {
                 li_sig.ledger_info(),
             );
 
+            // a quorum of signatures, but over another ledger info
+            let mut ledger_info_with_forged_sig = ledger_info_with_no_sig.clone();
+            let forged_ledger_info = LedgerInfo::new(
+                ledger_info_with_no_sig.ledger_info().commit_info().clone(),
+                HashValue::random(),
+            );
+            ledger_info_with_forged_sig
+                .add_signature(signer.author(), signer.sign(&forged_ledger_info));
+
             commit_phase.set_blocks(Some(PendingBlocks::new(
-                vecblocks,
+                vecblocks.clone(),
                 ledger_info_with_no_sig,
                 empty_state_computer_call_back(),
+                &validator,
             )));
             commit_phase.check_commit().await.ok();
 
             // the block should be there
             assert!(commit_phase.blocks().is_some());
+
+            let pending_blocks = PendingBlocks::new(
+                vecblocks,
+                ledger_info_with_forged_sig,
+                empty_state_computer_call_back(),
+                &validator,
+            );
+            assert!(pending_blocks.verify(&validator).is_err());
+            commit_phase.set_blocks(Some(pending_blocks));
+            commit_phase.check_commit().await.ok();
+
+            // the block should not be committed
+            assert!(commit_phase.blocks().is_some());
+            assert_eq!(commit_phase.load_back_pressure(), 1);
         });
     }
 
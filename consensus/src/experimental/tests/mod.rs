@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+mod buffer_manager_tests;
 mod commit_phase_tests;
 mod execution_phase_tests;
 mod integration_tests;
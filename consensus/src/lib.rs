@@ -15,6 +15,7 @@
 mod block_storage;
 mod consensusdb;
 mod counters;
+mod dedup_cache;
 mod epoch_manager;
 mod error;
 mod experimental;
@@ -22,24 +23,32 @@ mod liveness;
 mod logging;
 mod metrics_safety_rules;
 mod network;
-#[cfg(test)]
+#[cfg(any(test, feature = "fuzzing"))]
 mod network_tests;
+mod observer;
 mod pending_votes;
 mod persistent_liveness_storage;
 mod round_manager;
 mod state_computer;
 mod state_replication;
+/// Test utilities, including the twins harness to run consensus against byzantine behaviors.
 #[cfg(any(test, feature = "fuzzing"))]
-mod test_utils;
-#[cfg(test)]
-mod twins;
+#[allow(missing_docs)]
+pub mod test_utils;
 mod txn_manager;
 mod util;
+mod verification_pool;
 
+/// Local subscriptions to the commits of consensus.
+pub mod commit_notification_bus;
 /// DiemBFT implementation
 pub mod consensus_provider;
 /// DiemNet interface.
 pub mod network_interface;
+/// Snapshots of the state of consensus for the operators of the node.
+pub mod state_snapshot;
+/// DiemNet interface of the consensus observer.
+pub use observer::network as observer_network_interface;
 
 #[cfg(feature = "fuzzing")]
 pub use round_manager::round_manager_fuzzing;
@@ -153,12 +153,71 @@ impl ReputationHeuristic for ActiveInactiveHeuristic {
     }
 }
 
+/// Candidates are weighted by the number of committed proposals and votes they have in the
+/// history, a candidate without any is assigned inactive_weight. Compared to
+/// ActiveInactiveHeuristic this keeps favoring the validators that participate the most.
+pub struct ProposerAndVoterHeuristic {
+    author: Author,
+    active_weight: u64,
+    inactive_weight: u64,
+}
+
+impl ProposerAndVoterHeuristic {
+    pub fn new(author: Author, active_weight: u64, inactive_weight: u64) -> Self {
+        Self {
+            author,
+            active_weight,
+            inactive_weight,
+        }
+    }
+}
+
+impl ReputationHeuristic for ProposerAndVoterHeuristic {
+    fn get_weights(&self, candidates: &[Author], history: &[NewBlockEvent]) -> Vec<u64> {
+        let mut proposals: HashMap<Author, u64> = HashMap::new();
+        let mut votes: HashMap<Author, u64> = HashMap::new();
+        for meta in history {
+            let count = proposals.entry(meta.proposer()).or_insert(0);
+            *count = count.saturating_add(1);
+            for vote in meta.votes() {
+                let count = votes.entry(vote).or_insert(0);
+                *count = count.saturating_add(1);
+            }
+        }
+
+        COMMITTED_PROPOSALS_IN_WINDOW.set(proposals.get(&self.author).copied().unwrap_or(0) as i64);
+        COMMITTED_VOTES_IN_WINDOW.set(votes.get(&self.author).copied().unwrap_or(0) as i64);
+
+        candidates
+            .iter()
+            .map(|author| {
+                let successes = proposals
+                    .get(author)
+                    .copied()
+                    .unwrap_or(0)
+                    .saturating_add(votes.get(author).copied().unwrap_or(0));
+                if successes == 0 {
+                    self.inactive_weight
+                } else {
+                    self.active_weight.saturating_mul(successes)
+                }
+            })
+            .collect()
+    }
+}
+
+/// Number of most recent rounds excluded from the history when the leader reputation isn't set
+/// on-chain, so that all honest validators read the same committed window.
+pub const DEFAULT_EXCLUDE_ROUND: u64 = 4;
+
 /// Committed history based proposer election implementation that could help bias towards
 /// successful leaders to help improve performance.
 pub struct LeaderReputation {
     proposers: Vec<Author>,
     backend: Box<dyn MetadataBackend>,
     heuristic: Box<dyn ReputationHeuristic>,
+    // Number of most recent rounds which are not taken into account by the election
+    exclude_round: u64,
     already_proposed: Mutex<(Round, HashMap<Author, HashValue>)>,
 }
 
@@ -167,11 +226,13 @@ impl LeaderReputation {
         proposers: Vec<Author>,
         backend: Box<dyn MetadataBackend>,
         heuristic: Box<dyn ReputationHeuristic>,
+        exclude_round: u64,
     ) -> Self {
         Self {
             proposers,
             backend,
             heuristic,
+            exclude_round,
             already_proposed: Mutex::new((0, HashMap::new())),
         }
     }
@@ -179,14 +240,14 @@ impl LeaderReputation {
 
 impl ProposerElection for LeaderReputation {
     fn get_valid_proposer(&self, round: Round) -> Author {
-        // TODO: configure the round gap
-        let target_round = if round >= 4 { round - 4 } else { 0 };
+        let target_round = round.saturating_sub(self.exclude_round);
         let sliding_window = self.backend.get_block_metadata(target_round);
         let mut weights = self.heuristic.get_weights(&self.proposers, &sliding_window);
         assert_eq!(weights.len(), self.proposers.len());
-        let mut total_weight = 0;
+        // the weights of ProposerAndVoterHeuristic grow with the history, so their sum saturates
+        let mut total_weight: u64 = 0;
         for w in &mut weights {
-            total_weight += *w;
+            total_weight = total_weight.saturating_add(*w);
             *w = total_weight;
         }
         let mut state = round.to_le_bytes().to_vec();
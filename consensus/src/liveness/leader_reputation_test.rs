@@ -3,7 +3,8 @@
 
 use crate::liveness::{
     leader_reputation::{
-        ActiveInactiveHeuristic, LeaderReputation, MetadataBackend, ReputationHeuristic,
+        ActiveInactiveHeuristic, LeaderReputation, MetadataBackend, ProposerAndVoterHeuristic,
+        ReputationHeuristic,
     },
     proposer_election::{next, ProposerElection},
 };
@@ -76,6 +77,50 @@ fn test_simple_heuristic() {
     }
 }
 
+#[test]
+fn test_proposer_and_voter_heuristic() {
+    let active_weight = 9;
+    let inactive_weight = 1;
+    let mut proposers = vec![];
+    let mut signers = vec![];
+    for i in 0..8 {
+        let signer = ValidatorSigner::random([i; 32]);
+        proposers.push(signer.author());
+        signers.push(signer);
+    }
+    let heuristic = ProposerAndVoterHeuristic::new(proposers[0], active_weight, inactive_weight);
+    // 1. Window size not enough
+    let weights = heuristic.get_weights(&proposers, &[]);
+    assert_eq!(weights, vec![inactive_weight; proposers.len()]);
+    // 2. Sliding window with [proposer 0, voters 1, 2], [proposer 0, voters 1]
+    let weights = heuristic.get_weights(
+        &proposers,
+        &[
+            create_block(proposers[0], vec![&signers[1], &signers[2]]),
+            create_block(proposers[0], vec![&signers[1]]),
+        ],
+    );
+    let mut expected = vec![inactive_weight; proposers.len()];
+    expected[0] = 2 * active_weight;
+    expected[1] = 2 * active_weight;
+    expected[2] = active_weight;
+    assert_eq!(weights, expected);
+
+    // 3. The weights and their sum saturate instead of overflowing
+    let history = vec![create_block(proposers[0], vec![&signers[1]]); 2];
+    let heuristic = ProposerAndVoterHeuristic::new(proposers[0], u64::MAX, inactive_weight);
+    let weights = heuristic.get_weights(&proposers, &history);
+    assert_eq!(weights[0], u64::MAX);
+    assert_eq!(weights[1], u64::MAX);
+    let leader_reputation = LeaderReputation::new(
+        proposers.clone(),
+        Box::new(MockHistory::new(2, history)),
+        Box::new(heuristic),
+        0,
+    );
+    assert!(proposers[..2].contains(&leader_reputation.get_valid_proposer(42)));
+}
+
 #[test]
 fn test_api() {
     let active_weight = 9;
@@ -99,6 +144,7 @@ fn test_api() {
             active_weight,
             inactive_weight,
         )),
+        4,
     );
     let round = 42u64;
     // first metadata is ignored because of window size 1
@@ -2,18 +2,25 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    block_storage::BlockReader, state_replication::TxnManager, util::time_service::TimeService,
+    block_storage::BlockReader, counters, state_replication::TxnManager,
+    util::time_service::TimeService,
 };
-use anyhow::{bail, ensure, format_err, Context};
+use anyhow::{ensure, format_err, Context};
 use consensus_types::{
     block::Block,
     block_data::BlockData,
-    common::{Author, Round},
+    common::{Author, Payload, Round},
     quorum_cert::QuorumCert,
 };
 
 use diem_infallible::Mutex;
-use std::sync::Arc;
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 #[cfg(test)]
 #[path = "proposal_generator_test.rs"]
@@ -40,6 +47,17 @@ pub struct ProposalGenerator {
     time_service: Arc<dyn TimeService>,
     // Max number of transactions to be added to a proposed block.
     max_block_size: u64,
+    // Number of uncommitted blocks on the extended branch above which the execution pipeline is
+    // considered lagging: the max block size is halved for every additional uncommitted block,
+    // down to empty blocks.
+    back_pressure_limit: u64,
+    // With decoupled execution, the last round committed by the commit phase: the blocks ordered
+    // above it are still uncommitted, even once they leave the block tree.
+    back_pressure: Option<Arc<AtomicU64>>,
+    // Max number of bytes of the transactions of a proposed block.
+    max_block_bytes: u64,
+    // Max sum of the max gas amounts of the transactions of a proposed block.
+    max_block_gas: u64,
     // Last round that a proposal was generated
     last_round_generated: Mutex<Round>,
 }
@@ -51,6 +69,7 @@ impl ProposalGenerator {
         txn_manager: Arc<dyn TxnManager>,
         time_service: Arc<dyn TimeService>,
         max_block_size: u64,
+        back_pressure_limit: u64,
     ) -> Self {
         Self {
             author,
@@ -58,10 +77,23 @@ impl ProposalGenerator {
             txn_manager,
             time_service,
             max_block_size,
+            back_pressure_limit,
+            back_pressure: None,
+            max_block_bytes: u64::MAX,
+            max_block_gas: u64::MAX,
             last_round_generated: Mutex::new(0),
         }
     }
 
+    pub fn set_payload_limits(&mut self, max_block_bytes: u64, max_block_gas: u64) {
+        self.max_block_bytes = max_block_bytes;
+        self.max_block_gas = max_block_gas;
+    }
+
+    pub fn set_back_pressure(&mut self, back_pressure: Arc<AtomicU64>) {
+        self.back_pressure = Some(back_pressure);
+    }
+
     pub fn author(&self) -> Author {
         self.author
     }
@@ -83,17 +115,39 @@ impl ProposalGenerator {
     /// 3. In case a given round is not greater than the calculated parent, return an OldRound
     /// error.
     pub async fn generate_proposal(&mut self, round: Round) -> anyhow::Result<BlockData> {
-        {
-            let mut last_round_generated = self.last_round_generated.lock();
-            if *last_round_generated < round {
-                *last_round_generated = round;
-            } else {
-                bail!("Already proposed in the round {}", round);
-            }
-        }
-
+        self.ensure_first_proposal(round)?;
         let hqc = self.ensure_highest_quorum_cert(round)?;
+        let block_data = self.generate_proposal_extending(round, hqc).await?;
+        self.mark_proposal_generated(round);
+        Ok(block_data)
+    }
 
+    /// Same as generate_proposal, but extends the given QC instead of the highest QC of the block
+    /// store: the QC might not be inserted yet, only the block it certifies needs to be.
+    pub async fn generate_optimistic_proposal(
+        &mut self,
+        round: Round,
+        qc: Arc<QuorumCert>,
+    ) -> anyhow::Result<BlockData> {
+        let hqc = self.block_store.highest_quorum_cert();
+        ensure!(
+            qc.certified_block().round() > hqc.certified_block().round(),
+            "QC round {} is not higher than hqc round {}",
+            qc.certified_block().round(),
+            hqc.certified_block().round()
+        );
+        Self::ensure_extendable(round, &qc)?;
+        self.ensure_first_proposal(round)?;
+        let block_data = self.generate_proposal_extending(round, qc).await?;
+        self.mark_proposal_generated(round);
+        Ok(block_data)
+    }
+
+    async fn generate_proposal_extending(
+        &mut self,
+        round: Round,
+        hqc: Arc<QuorumCert>,
+    ) -> anyhow::Result<BlockData> {
         let (payload, timestamp) = if hqc.certified_block().has_reconfiguration() {
             // Reconfiguration rule - we propose empty blocks with parents' timestamp
             // after reconfiguration until it's committed
@@ -105,6 +159,9 @@ impl ProposalGenerator {
                 .block_store
                 .path_from_commit_root(hqc.certified_block().id())
                 .ok_or_else(|| format_err!("HQC {} already pruned", hqc.certified_block().id()))?;
+            let max_block_size = self.max_block_size_with_back_pressure(
+                self.num_uncommitted_blocks(&hqc, pending_blocks.len()),
+            );
             // Avoid txn manager long poll it the root block has txns, so that the leader can
             // deliver the commit proof to others without delay.
             pending_blocks.push(self.block_store.commit_root());
@@ -121,11 +178,16 @@ impl ProposalGenerator {
             // the local time exceeds it.
             let timestamp = self.time_service.get_current_timestamp();
 
-            let payload = self
-                .txn_manager
-                .pull_txns(self.max_block_size, exclude_payload)
-                .await
-                .context("Fail to retrieve txn")?;
+            let payload = if max_block_size == 0 {
+                vec![]
+            } else {
+                let payload = self
+                    .txn_manager
+                    .pull_txns(max_block_size, exclude_payload)
+                    .await
+                    .context("Fail to retrieve txn")?;
+                self.apply_payload_limits(payload)
+            };
 
             (payload, timestamp.as_micros() as u64)
         };
@@ -140,19 +202,102 @@ impl ProposalGenerator {
         ))
     }
 
+    /// Number of uncommitted blocks up to the block certified by the QC, given the number of them
+    /// above the commit root of the block tree. With decoupled execution, the rounds above the one
+    /// the commit phase committed last are counted as well.
+    fn num_uncommitted_blocks(&self, qc: &QuorumCert, num_pending_blocks: usize) -> u64 {
+        let num_pending_blocks = num_pending_blocks as u64;
+        match &self.back_pressure {
+            Some(back_pressure) => num_pending_blocks.max(
+                qc.certified_block()
+                    .round()
+                    .saturating_sub(back_pressure.load(Ordering::SeqCst)),
+            ),
+            None => num_pending_blocks,
+        }
+    }
+
+    /// Shrink the proposed blocks while the execution pipeline lags behind ordering, so that the
+    /// backlog of uncommitted blocks (and the memory they hold) stops growing.
+    fn max_block_size_with_back_pressure(&self, num_pending_blocks: u64) -> u64 {
+        counters::PROPOSAL_PENDING_BLOCKS.set(num_pending_blocks as i64);
+        let excess = num_pending_blocks.saturating_sub(self.back_pressure_limit);
+        let max_block_size = self.max_block_size.checked_shr(excess as u32).unwrap_or(0);
+        if excess > 0 {
+            counters::PROPOSAL_BACK_PRESSURE_COUNT.inc();
+        }
+        counters::PROPOSAL_MAX_BLOCK_SIZE.set(max_block_size as i64);
+        max_block_size
+    }
+
+    /// Keeps the longest prefix of the payload within the byte size and gas limits: the left out
+    /// txns stay in mempool for the next proposals. A txn over the limits on its own would never
+    /// fit in any block, so it is skipped along with the next txns of its sender rather than
+    /// holding back the txns behind it, and stays in mempool until it expires.
+    fn apply_payload_limits(&self, payload: Payload) -> Payload {
+        let num_pulled = payload.len();
+        let (mut block_bytes, mut block_gas) = (0u64, 0u64);
+        let mut skipped_senders = HashSet::new();
+        let mut limited = Vec::with_capacity(num_pulled);
+        for txn in payload {
+            if skipped_senders.contains(&txn.sender()) {
+                continue;
+            }
+            let txn_bytes = bcs::serialized_size(&txn).map_or(u64::MAX, |size| size as u64);
+            let txn_gas = txn.max_gas_amount();
+            if txn_bytes > self.max_block_bytes || txn_gas > self.max_block_gas {
+                skipped_senders.insert(txn.sender());
+                continue;
+            }
+            let (next_bytes, next_gas) = (
+                block_bytes.saturating_add(txn_bytes),
+                block_gas.saturating_add(txn_gas),
+            );
+            if next_bytes > self.max_block_bytes || next_gas > self.max_block_gas {
+                break;
+            }
+            block_bytes = next_bytes;
+            block_gas = next_gas;
+            limited.push(txn);
+        }
+        if limited.len() < num_pulled {
+            counters::PROPOSAL_TXNS_OVER_LIMITS.inc_by((num_pulled - limited.len()) as u64);
+        }
+        limited
+    }
+
+    fn ensure_first_proposal(&self, round: Round) -> anyhow::Result<()> {
+        ensure!(
+            *self.last_round_generated.lock() < round,
+            "Already proposed in the round {}",
+            round
+        );
+        Ok(())
+    }
+
+    /// Only a generated proposal counts as the one of its round, a failed attempt can be retried.
+    fn mark_proposal_generated(&self, round: Round) {
+        let mut last_round_generated = self.last_round_generated.lock();
+        *last_round_generated = (*last_round_generated).max(round);
+    }
+
     fn ensure_highest_quorum_cert(&self, round: Round) -> anyhow::Result<Arc<QuorumCert>> {
         let hqc = self.block_store.highest_quorum_cert();
+        Self::ensure_extendable(round, &hqc)?;
+        Ok(hqc)
+    }
+
+    fn ensure_extendable(round: Round, qc: &QuorumCert) -> anyhow::Result<()> {
         ensure!(
-            hqc.certified_block().round() < round,
+            qc.certified_block().round() < round,
             "Given round {} is lower than hqc round {}",
             round,
-            hqc.certified_block().round()
+            qc.certified_block().round()
         );
         ensure!(
-            !hqc.ends_epoch(),
+            !qc.ends_epoch(),
             "The epoch has already ended,a proposal is not allowed to generated"
         );
-
-        Ok(hqc)
+        Ok(())
     }
 }
@@ -7,9 +7,22 @@ use crate::{
     test_utils::{build_empty_tree, MockTransactionManager, TreeInserter},
     util::mock_time_service::SimulatedTimeService,
 };
-use consensus_types::block::{block_test_utils::certificate_for_genesis, Block};
-use diem_types::validator_signer::ValidatorSigner;
-use std::sync::Arc;
+use consensus_types::{
+    block::{
+        block_test_utils::{certificate_for_genesis, random_payload},
+        Block,
+    },
+    executed_block::ExecutedBlock,
+};
+use diem_types::{
+    account_config::XUS_NAME, test_helpers::transaction_test_helpers::get_test_signed_transaction,
+    validator_signer::ValidatorSigner,
+};
+use executor_types::StateComputeResult;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
 
 #[tokio::test]
 async fn test_proposal_generation_empty_tree() {
@@ -21,6 +34,7 @@ async fn test_proposal_generation_empty_tree() {
         Arc::new(MockTransactionManager::new(None)),
         Arc::new(SimulatedTimeService::new()),
         1,
+        10,
     );
     let genesis = block_store.ordered_root();
 
@@ -46,6 +60,7 @@ async fn test_proposal_generation_parent() {
         Arc::new(MockTransactionManager::new(None)),
         Arc::new(SimulatedTimeService::new()),
         1,
+        10,
     );
     let genesis = block_store.ordered_root();
     let a1 = inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
@@ -77,6 +92,92 @@ async fn test_proposal_generation_parent() {
     assert_eq!(b1_child_res.quorum_cert().certified_block().id(), b1.id());
 }
 
+#[tokio::test]
+async fn test_optimistic_proposal_generation() {
+    let mut inserter = TreeInserter::default();
+    let block_store = inserter.block_store();
+    let mut proposal_generator = ProposalGenerator::new(
+        inserter.signer().author(),
+        block_store.clone(),
+        Arc::new(MockTransactionManager::new(None)),
+        Arc::new(SimulatedTimeService::new()),
+        1,
+        10,
+    );
+    let genesis = block_store.ordered_root();
+    let a1 = inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
+    let a1_qc = Arc::new(inserter.create_qc_for_block(a1.as_ref(), None));
+
+    // The QC doesn't need to be inserted
+    let proposal = proposal_generator
+        .generate_optimistic_proposal(2, a1_qc.clone())
+        .await
+        .unwrap();
+    assert_eq!(proposal.parent_id(), a1.id());
+    assert_eq!(proposal.round(), 2);
+    assert_eq!(
+        block_store.highest_quorum_cert().certified_block().id(),
+        genesis.id()
+    );
+
+    // At most one proposal per round, optimistic or not
+    assert!(proposal_generator.generate_proposal(2).await.is_err());
+
+    // The QC has to be higher than the highest QC of the block store
+    inserter.insert_qc_for_block(a1.as_ref(), None);
+    assert!(proposal_generator
+        .generate_optimistic_proposal(3, a1_qc)
+        .await
+        .is_err());
+    assert_eq!(
+        proposal_generator
+            .generate_proposal(3)
+            .await
+            .unwrap()
+            .parent_id(),
+        a1.id()
+    );
+}
+
+#[tokio::test]
+async fn test_failed_proposal_generation_can_be_retried() {
+    let mut inserter = TreeInserter::default();
+    let block_store = inserter.block_store();
+    let mut proposal_generator = ProposalGenerator::new(
+        inserter.signer().author(),
+        block_store.clone(),
+        Arc::new(MockTransactionManager::new(None)),
+        Arc::new(SimulatedTimeService::new()),
+        1,
+        10,
+    );
+    let genesis = block_store.ordered_root();
+    let unknown_block = ExecutedBlock::new(
+        inserter.create_block_with_qc(
+            certificate_for_genesis(),
+            genesis.timestamp_usecs() + 1,
+            1,
+            vec![],
+        ),
+        StateComputeResult::new_dummy(),
+    );
+    let unknown_qc = Arc::new(inserter.create_qc_for_block(&unknown_block, None));
+
+    // The certified block isn't in the block store, so no proposal is generated for the round
+    assert!(proposal_generator
+        .generate_optimistic_proposal(2, unknown_qc)
+        .await
+        .is_err());
+    assert_eq!(
+        proposal_generator
+            .generate_proposal(2)
+            .await
+            .unwrap()
+            .parent_id(),
+        genesis.id()
+    );
+}
+
 #[tokio::test]
 async fn test_old_proposal_generation() {
     let mut inserter = TreeInserter::default();
@@ -87,6 +188,7 @@ async fn test_old_proposal_generation() {
         Arc::new(MockTransactionManager::new(None)),
         Arc::new(SimulatedTimeService::new()),
         1,
+        10,
     );
     let genesis = block_store.ordered_root();
     let a1 = inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
@@ -95,3 +197,123 @@ async fn test_old_proposal_generation() {
     let proposal_err = proposal_generator.generate_proposal(1).await.err();
     assert!(proposal_err.is_some());
 }
+
+#[tokio::test]
+async fn test_proposal_generation_back_pressure() {
+    let mut inserter = TreeInserter::default();
+    let block_store = inserter.block_store();
+    let mut proposal_generator = ProposalGenerator::new(
+        inserter.signer().author(),
+        block_store.clone(),
+        Arc::new(MockTransactionManager::new(None)),
+        Arc::new(SimulatedTimeService::new()),
+        2,
+        1,
+    );
+    let genesis = block_store.ordered_root();
+    let a1 = inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
+    inserter.insert_qc_for_block(a1.as_ref(), None);
+
+    // A single uncommitted block is within the limit
+    let proposal = proposal_generator.generate_proposal(2).await.unwrap();
+    assert!(!proposal.payload().unwrap().is_empty());
+
+    // The block size is halved for every uncommitted block above the limit, down to empty blocks
+    let a2 = inserter.insert_block(a1.as_ref(), 2, None);
+    let a3 = inserter.insert_block(a2.as_ref(), 3, None);
+    inserter.insert_qc_for_block(a3.as_ref(), None);
+    let proposal = proposal_generator.generate_proposal(4).await.unwrap();
+    assert!(proposal.payload().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_proposal_generation_decoupled_back_pressure() {
+    let mut inserter = TreeInserter::default();
+    let block_store = inserter.block_store();
+    let mut proposal_generator = ProposalGenerator::new(
+        inserter.signer().author(),
+        block_store.clone(),
+        Arc::new(MockTransactionManager::new(None)),
+        Arc::new(SimulatedTimeService::new()),
+        2,
+        1,
+    );
+    let back_pressure = Arc::new(AtomicU64::new(0));
+    proposal_generator.set_back_pressure(back_pressure.clone());
+    let genesis = block_store.ordered_root();
+    let a1 = inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 3);
+    inserter.insert_qc_for_block(a1.as_ref(), None);
+
+    // A single block is above the commit root, but the commit phase lags 3 rounds behind
+    let proposal = proposal_generator.generate_proposal(4).await.unwrap();
+    assert!(proposal.payload().unwrap().is_empty());
+
+    // Once the commit phase catches up, the full block size is back
+    back_pressure.store(3, Ordering::SeqCst);
+    let proposal = proposal_generator.generate_proposal(5).await.unwrap();
+    assert!(!proposal.payload().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_proposal_generation_payload_limits() {
+    let signer = ValidatorSigner::random(None);
+    let block_store = build_empty_tree();
+    let mut proposal_generator = ProposalGenerator::new(
+        signer.author(),
+        block_store.clone(),
+        Arc::new(MockTransactionManager::new(None)),
+        Arc::new(SimulatedTimeService::new()),
+        10,
+        10,
+    );
+
+    // No limit
+    let proposal = proposal_generator.generate_proposal(1).await.unwrap();
+    assert_eq!(proposal.payload().unwrap().len(), 10);
+
+    // The mock txns have a max gas amount of 1M each
+    proposal_generator.set_payload_limits(u64::MAX, 3_500_000);
+    let proposal = proposal_generator.generate_proposal(2).await.unwrap();
+    assert_eq!(proposal.payload().unwrap().len(), 3);
+
+    // No txn fits in a single byte
+    proposal_generator.set_payload_limits(1, u64::MAX);
+    let proposal = proposal_generator.generate_proposal(3).await.unwrap();
+    assert!(proposal.payload().unwrap().is_empty());
+}
+
+#[test]
+fn test_payload_limits_skip_oversized_transaction() {
+    let signer = ValidatorSigner::random(None);
+    let mut proposal_generator = ProposalGenerator::new(
+        signer.author(),
+        build_empty_tree(),
+        Arc::new(MockTransactionManager::new(None)),
+        Arc::new(SimulatedTimeService::new()),
+        10,
+        10,
+    );
+    proposal_generator.set_payload_limits(u64::MAX, 2_500_000);
+
+    // the first transaction of the first sender is over the gas limit on its own
+    let mut payload = random_payload(3);
+    payload[0] = get_test_signed_transaction(
+        payload[0].sender(),
+        0,
+        signer.private_key(),
+        signer.public_key(),
+        None,
+        u64::MAX,
+        0,
+        XUS_NAME.to_owned(),
+        Some(5_000_000),
+    );
+    let other_sender_txns = random_payload(2);
+    payload.extend(other_sender_txns.clone());
+
+    // it doesn't hold back the transactions of the other senders
+    assert_eq!(
+        proposal_generator.apply_payload_limits(payload),
+        other_sender_txns
+    );
+}
@@ -28,6 +28,15 @@ pub trait ProposerElection {
             self.is_valid_proposer(author, block.round())
         })
     }
+
+    /// Return if the proposals should carry the VRF proof of their author for the round.
+    fn proposals_carry_vrf_proofs(&self) -> bool {
+        false
+    }
+
+    /// Called when the round times out locally, for the elections depending on the proposals
+    /// seen in the round to forget them.
+    fn process_local_timeout(&self, _round: Round) {}
 }
 
 // next continuously mutates a state and returns a u64-index
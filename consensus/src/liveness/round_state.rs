@@ -6,11 +6,16 @@ use crate::{
     pending_votes::{PendingVotes, VoteReceptionResult},
     util::time_service::{SendTask, TimeService},
 };
-use consensus_types::{common::Round, sync_info::SyncInfo, vote::Vote};
+use consensus_types::{
+    common::{Author, Round},
+    sync_info::SyncInfo,
+    vote::Vote,
+};
+use diem_crypto::HashValue;
 use diem_logger::{prelude::*, Schema};
 use diem_types::validator_verifier::ValidatorVerifier;
 use serde::Serialize;
-use std::{fmt, sync::Arc, time::Duration};
+use std::{convert::TryInto, fmt, sync::Arc, time::Duration};
 
 /// A reason for starting a new round: introduced for monitoring / debug purposes.
 #[derive(Serialize, Eq, Debug, PartialEq)]
@@ -113,6 +118,41 @@ impl RoundTimeInterval for ExponentialTimeInterval {
     }
 }
 
+/// Extends the round timeouts of a validator by a pseudo-random fraction, derived from its author
+/// and the round: validators don't time out in lockstep, while a given validator always computes
+/// the same timeout for a round.
+#[derive(Clone)]
+pub struct TimeoutJitter {
+    author: Author,
+    // The timeout is extended by at most max_percent percent
+    max_percent: u64,
+}
+
+impl TimeoutJitter {
+    pub fn new(author: Author, max_percent: u64) -> Self {
+        Self {
+            author,
+            max_percent,
+        }
+    }
+
+    /// Return the jitter added to the timeout of the round.
+    pub fn get_jitter(&self, timeout: Duration, round: Round) -> Duration {
+        let max_jitter_ms = (timeout.as_millis() as u64).saturating_mul(self.max_percent) / 100;
+        if max_jitter_ms == 0 {
+            return Duration::from_millis(0);
+        }
+        let seed =
+            HashValue::sha3_256_of(&[self.author.as_ref(), &round.to_le_bytes()[..]].concat());
+        let random = u64::from_le_bytes(
+            seed.as_ref()[..8]
+                .try_into()
+                .expect("HashValue is longer than 8 bytes"),
+        );
+        Duration::from_millis(random % (max_jitter_ms + 1))
+    }
+}
+
 /// `RoundState` contains information about a specific round and moves forward when
 /// receives new certificates.
 ///
@@ -131,6 +171,10 @@ pub struct RoundState {
     // Determines the time interval for a round given the number of non-committed rounds since
     // last commit.
     time_interval: Box<dyn RoundTimeInterval>,
+    // Optional per-validator jitter added on top of the time interval.
+    timeout_jitter: Option<TimeoutJitter>,
+    // Optional upper bound of the round timeouts, jitter included.
+    max_round_timeout: Option<Duration>,
     // Highest known committed round as reported by the caller. The caller might choose not to
     // inform the RoundState about certain committed rounds (e.g., NIL blocks): in this case the
     // committed round in RoundState might lag behind the committed round of a block tree.
@@ -189,6 +233,8 @@ impl RoundState {
 
         Self {
             time_interval,
+            timeout_jitter: None,
+            max_round_timeout: None,
             highest_committed_round: 0,
             current_round: 0,
             current_round_deadline: time_service.get_current_timestamp(),
@@ -199,6 +245,17 @@ impl RoundState {
         }
     }
 
+    /// Desynchronize the round timeouts of this validator from the others with a jitter, and cap
+    /// the round timeouts.
+    pub fn set_timeout_backoff(
+        &mut self,
+        timeout_jitter: Option<TimeoutJitter>,
+        max_round_timeout: Option<Duration>,
+    ) {
+        self.timeout_jitter = timeout_jitter;
+        self.max_round_timeout = max_round_timeout;
+    }
+
     /// Return the current round.
     pub fn current_round(&self) -> Round {
         self.current_round
@@ -304,9 +361,18 @@ impl RoundState {
                 self.current_round - self.highest_committed_round - 3
             }
         } as usize;
-        let timeout = self
+        let mut timeout = self
             .time_interval
             .get_round_duration(round_index_after_committed_round);
+        if let Some(jitter) = &self.timeout_jitter {
+            let jitter = jitter.get_jitter(timeout, self.current_round);
+            counters::ROUND_TIMEOUT_JITTER_S.observe_duration(jitter);
+            timeout += jitter;
+        }
+        if let Some(max_round_timeout) = self.max_round_timeout {
+            timeout = timeout.min(max_round_timeout);
+        }
+        counters::ROUND_TIMEOUT_DURATION_S.observe_duration(timeout);
         let now = self.time_service.get_current_timestamp();
         debug!(
             round = self.current_round,
@@ -4,6 +4,7 @@
 use crate::{
     liveness::round_state::{
         ExponentialTimeInterval, NewRoundEvent, NewRoundReason, RoundState, RoundTimeInterval,
+        TimeoutJitter,
     },
     util::mock_time_service::SimulatedTimeService,
 };
@@ -14,6 +15,7 @@ use consensus_types::{
 };
 use diem_crypto::HashValue;
 use diem_types::{
+    account_address::AccountAddress,
     block_info::BlockInfo,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
 };
@@ -33,6 +35,56 @@ fn test_round_time_interval() {
     assert_eq!(6750, interval.get_round_duration(1000).as_millis());
 }
 
+#[test]
+fn test_timeout_jitter() {
+    let timeout = Duration::from_millis(1000);
+    let jitter = TimeoutJitter::new(AccountAddress::random(), 10);
+    let other_jitter = TimeoutJitter::new(AccountAddress::random(), 10);
+    let mut desynchronized = false;
+    for round in 1..20 {
+        let round_jitter = jitter.get_jitter(timeout, round);
+        assert!(round_jitter <= Duration::from_millis(100));
+        // the jitter of a validator for a round is deterministic
+        assert_eq!(round_jitter, jitter.get_jitter(timeout, round));
+        desynchronized |= round_jitter != other_jitter.get_jitter(timeout, round);
+    }
+    assert!(desynchronized);
+    // no jitter
+    assert_eq!(
+        TimeoutJitter::new(AccountAddress::random(), 0).get_jitter(timeout, 1),
+        Duration::from_millis(0)
+    );
+}
+
+#[test]
+fn test_round_timeout_backoff() {
+    let time_interval = Box::new(ExponentialTimeInterval::new(
+        Duration::from_millis(1000),
+        2.0,
+        4,
+    ));
+    let simulated_time = SimulatedTimeService::new();
+    let (timeout_tx, _timeout_rx) = channel::new_test(1_024);
+    let mut round_state = RoundState::new(time_interval, Arc::new(simulated_time), timeout_tx);
+    round_state.set_timeout_backoff(
+        Some(TimeoutJitter::new(AccountAddress::random(), 50)),
+        Some(Duration::from_millis(5000)),
+    );
+
+    // jitter is added on top of the time interval
+    let event = round_state
+        .process_certificates(generate_sync_info(Some(0), None, None))
+        .unwrap();
+    assert!(event.timeout >= Duration::from_millis(1000));
+    assert!(event.timeout <= Duration::from_millis(1500));
+
+    // the timeouts are capped after a few rounds without commit
+    let event = round_state
+        .process_certificates(generate_sync_info(None, Some(9), None))
+        .unwrap();
+    assert_eq!(event.timeout, Duration::from_millis(5000));
+}
+
 #[tokio::test]
 /// Verify that RoundState properly outputs local timeout events upon timeout
 async fn test_basic_timeout() {
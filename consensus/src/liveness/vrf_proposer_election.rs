@@ -0,0 +1,96 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::liveness::{
+    proposer_election::{next, ProposerElection},
+    rotating_proposer_election::RotatingProposer,
+};
+use consensus_types::{
+    block::Block,
+    common::{Author, Round},
+};
+use diem_crypto::ecvrf::VRFOutput;
+use diem_infallible::Mutex;
+use std::collections::BTreeMap;
+
+/// Number of rounds whose VRF outputs are kept, older ones are dropped.
+const OUTPUTS_WINDOW: u64 = 10;
+
+/// Experimental: the VRF proposer maps a round to an author drawn from the VRF output of the
+/// proposal of the previous round. Only the author of that proposal knows the output beforehand,
+/// so the schedule isn't predictable more than a round in advance, and as the output of a key on
+/// a round is unique, the author can't choose it either.
+///
+/// Proposals carry the VRF proof of their author, verified along with the signature of the block,
+/// and the election learns the outputs from the valid proposals. The output of a round that times
+/// out locally is forgotten, so the proposer of a round without any known output (after a timeout,
+/// or when the previous proposer couldn't prove it) follows a round-robin rotation. This comes with
+/// known limitations, which is why it's only used with `experimental_vrf_proposer` and can't be
+/// selected on-chain:
+/// * a validator that missed the previous proposal may disagree with the others on the proposer,
+/// until a round times out for all of them;
+/// * the proposer of a round still chooses between two proposers for the next round, by attaching
+/// its proof or not.
+pub struct VrfProposer {
+    // Ordering of proposers to draw from (all honest replicas must agree on this)
+    proposers: Vec<Author>,
+    // Proposers of the rounds without a known VRF output
+    fallback: RotatingProposer,
+    // VRF outputs of the valid proposals of the recent rounds
+    outputs: Mutex<BTreeMap<Round, VRFOutput>>,
+}
+
+impl VrfProposer {
+    pub fn new(proposers: Vec<Author>) -> Self {
+        Self {
+            fallback: RotatingProposer::new(proposers.clone(), 1),
+            proposers,
+            outputs: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn record_output(&self, round: Round, output: VRFOutput) {
+        let mut outputs = self.outputs.lock();
+        outputs.insert(round, output);
+        *outputs = outputs.split_off(&round.saturating_sub(OUTPUTS_WINDOW));
+    }
+}
+
+impl ProposerElection for VrfProposer {
+    fn get_valid_proposer(&self, round: Round) -> Author {
+        let outputs = self.outputs.lock();
+        match round
+            .checked_sub(1)
+            .and_then(|previous_round| outputs.get(&previous_round))
+        {
+            Some(output) => {
+                let mut state = output.to_bytes().to_vec();
+                self.proposers[(next(&mut state) % self.proposers.len() as u64) as usize]
+            }
+            None => self.fallback.get_valid_proposer(round),
+        }
+    }
+
+    /// Records the VRF output of a valid proposal, which elects the proposer of the next round.
+    fn is_valid_proposal(&self, block: &Block) -> bool {
+        let valid = block.author().map_or(false, |author| {
+            self.is_valid_proposer(author, block.round())
+        });
+        if valid {
+            if let Some(vrf_proof) = block.vrf_proof() {
+                self.record_output(block.round(), VRFOutput::from(vrf_proof));
+            }
+        }
+        valid
+    }
+
+    fn proposals_carry_vrf_proofs(&self) -> bool {
+        true
+    }
+
+    /// Forgets the output of a round that timed out, the validators that didn't see its proposal
+    /// don't know it either.
+    fn process_local_timeout(&self, round: Round) {
+        self.outputs.lock().remove(&round);
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::liveness::{
+    proposer_election::{next, ProposerElection},
+    vrf_proposer_election::VrfProposer,
+};
+use consensus_types::{
+    block::{block_test_utils::certificate_for_genesis, Block},
+    block_data::{BlockData, ProposerVrfInput},
+    common::Round,
+};
+use diem_crypto::ecvrf::VRFOutput;
+use diem_types::validator_signer::ValidatorSigner;
+
+fn make_vrf_proposal(round: Round, signer: &ValidatorSigner) -> Block {
+    let block_data = BlockData::new_proposal(
+        vec![],
+        signer.author(),
+        round,
+        round,
+        certificate_for_genesis(),
+    );
+    let vrf_proof = signer.prove_vrf(&ProposerVrfInput::new(block_data.epoch(), round).to_bytes());
+    Block::new_proposal_from_block_data(block_data.with_vrf_proof(vrf_proof), signer)
+}
+
+fn make_signers(count: u8) -> Vec<ValidatorSigner> {
+    (0..count)
+        .map(|i| ValidatorSigner::random([i; 32]))
+        .collect()
+}
+
+#[test]
+fn test_vrf_proposer_without_outputs() {
+    let signers = make_signers(4);
+    let proposers: Vec<_> = signers.iter().map(|signer| signer.author()).collect();
+    let pe: Box<dyn ProposerElection> = Box::new(VrfProposer::new(proposers.clone()));
+
+    // Without any VRF output, the proposers follow the round-robin rotation
+    assert!(pe.proposals_carry_vrf_proofs());
+    for round in 1..10 {
+        assert_eq!(pe.get_valid_proposer(round), proposers[round as usize % 4]);
+    }
+
+    // A proposal without a VRF proof doesn't change the next proposer
+    let proposal = Block::new_proposal(vec![], 1, 1, certificate_for_genesis(), &signers[1]);
+    assert!(pe.is_valid_proposal(&proposal));
+    assert_eq!(pe.get_valid_proposer(2), proposers[2]);
+}
+
+#[test]
+fn test_vrf_proposer_draws_from_outputs() {
+    let signers = make_signers(4);
+    let proposers: Vec<_> = signers.iter().map(|signer| signer.author()).collect();
+    let pe: Box<dyn ProposerElection> = Box::new(VrfProposer::new(proposers.clone()));
+
+    // The proposal of round 1 elects the proposer of round 2
+    let proposal = make_vrf_proposal(1, &signers[1]);
+    assert!(pe.is_valid_proposal(&proposal));
+    let mut state = VRFOutput::from(proposal.vrf_proof().unwrap())
+        .to_bytes()
+        .to_vec();
+    let expected_proposer = proposers[(next(&mut state) % 4) as usize];
+    assert_eq!(pe.get_valid_proposer(2), expected_proposer);
+    assert!(pe.is_valid_proposer(expected_proposer, 2));
+
+    // The same author proves the same output again
+    let other_proposal = make_vrf_proposal(1, &signers[1]);
+    assert!(pe.is_valid_proposal(&other_proposal));
+    assert_eq!(pe.get_valid_proposer(2), expected_proposer);
+
+    // Round 3 has no known output yet
+    assert_eq!(pe.get_valid_proposer(3), proposers[3]);
+}
+
+#[test]
+fn test_vrf_proposer_ignores_invalid_proposals() {
+    let signers = make_signers(4);
+    let proposers: Vec<_> = signers.iter().map(|signer| signer.author()).collect();
+    let pe: Box<dyn ProposerElection> = Box::new(VrfProposer::new(proposers.clone()));
+
+    // Only the proposals of the valid proposer elect the next proposer
+    for signer in signers
+        .iter()
+        .filter(|signer| signer.author() != proposers[1])
+    {
+        let proposal = make_vrf_proposal(1, signer);
+        assert!(!pe.is_valid_proposal(&proposal));
+    }
+    assert_eq!(pe.get_valid_proposer(2), proposers[2]);
+}
+
+#[test]
+fn test_vrf_proposers_converge_after_timeout() {
+    let signers = make_signers(4);
+    let proposers: Vec<_> = signers.iter().map(|signer| signer.author()).collect();
+    let seen = VrfProposer::new(proposers.clone());
+    let missed = VrfProposer::new(proposers.clone());
+
+    // Only one validator sees the proposal of round 1, they may disagree on the proposer of round 2
+    let proposal = make_vrf_proposal(1, &signers[1]);
+    assert!(seen.is_valid_proposal(&proposal));
+    assert_eq!(missed.get_valid_proposer(2), proposers[2]);
+
+    // The validator that saw it accepts the proposal of the drawn proposer, which the other one
+    // rejects unless it's also the proposer it expects, and round 2 times out
+    let drawn_proposer = seen.get_valid_proposer(2);
+    let signer = signers
+        .iter()
+        .find(|signer| signer.author() == drawn_proposer)
+        .unwrap();
+    let proposal = make_vrf_proposal(2, signer);
+    assert!(seen.is_valid_proposal(&proposal));
+    assert_eq!(
+        missed.is_valid_proposal(&proposal),
+        drawn_proposer == proposers[2]
+    );
+    seen.process_local_timeout(2);
+    missed.process_local_timeout(2);
+
+    // Both forgot the outputs of round 2, and agree on the proposers from round 3
+    for round in 3..10 {
+        assert_eq!(
+            seen.get_valid_proposer(round),
+            proposers[round as usize % 4]
+        );
+        assert_eq!(
+            missed.get_valid_proposer(round),
+            proposers[round as usize % 4]
+        );
+    }
+}
@@ -4,12 +4,13 @@
 use crate::persistent_liveness_storage::PersistentLivenessStorage;
 use consensus_types::{
     block_data::BlockData,
+    common::Round,
     timeout::Timeout,
     timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
     vote::Vote,
     vote_proposal::MaybeSignedVoteProposal,
 };
-use diem_crypto::ed25519::Ed25519Signature;
+use diem_crypto::{ecvrf::VRFProof, ed25519::Ed25519Signature};
 use diem_metrics::monitor;
 use diem_types::{
     epoch_change::EpochChangeProof,
@@ -124,4 +125,8 @@ impl TSafetyRules for MetricsSafetyRules {
             )
         })
     }
+
+    fn prove_proposer_vrf(&mut self, round: Round) -> Result<VRFProof, Error> {
+        self.retry(|inner| monitor!("safety_rules", inner.prove_proposer_vrf(round)))
+    }
 }
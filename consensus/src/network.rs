@@ -5,31 +5,46 @@ use crate::{
     counters,
     logging::LogEvent,
     network_interface::{ConsensusMsg, ConsensusNetworkEvents, ConsensusNetworkSender},
+    observer::publisher::ConsensusPublisher,
 };
 use anyhow::{anyhow, ensure};
 use bytes::Bytes;
 use channel::{self, diem_channel, message_queues::QueueStyle};
 use consensus_types::{
-    block_retrieval::{BlockRetrievalRequest, BlockRetrievalResponse, MAX_BLOCKS_PER_REQUEST},
-    common::Author,
+    block_retrieval::{
+        BlockRetrievalRequest, BlockRetrievalResponse, BlockRetrievalStatus,
+        BlockRetrievalStreamAck, BlockRetrievalStreamChunk, BlockRetrievalStreamRequest,
+        MAX_BLOCKS_PER_REQUEST,
+    },
+    broadcast_ack::{BroadcastAck, BroadcastKind},
+    common::{Author, Round},
     sync_info::SyncInfo,
     vote_msg::VoteMsg,
 };
-use diem_infallible::RwLock;
+use diem_config::config::ReliableBroadcastConfig;
+use diem_crypto::HashValue;
+use diem_infallible::{Mutex, RwLock};
 use diem_logger::prelude::*;
 use diem_metrics::monitor;
 use diem_types::{
     account_address::AccountAddress, epoch_change::EpochChangeProof,
     validator_verifier::ValidatorVerifier, PeerId,
 };
-use futures::{channel::oneshot, stream::select, SinkExt, Stream, StreamExt};
+use futures::{
+    channel::{mpsc, oneshot},
+    stream::select,
+    SinkExt, Stream, StreamExt,
+};
 use network::protocols::{
     network::Event, rpc::error::RpcError, wire::handshake::v1::SupportedProtocols,
 };
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     mem::{discriminant, Discriminant},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     time::Duration,
 };
 
@@ -50,6 +65,106 @@ pub struct NetworkReceivers {
         (AccountAddress, ConsensusMsg),
     >,
     pub block_retrieval: diem_channel::Receiver<AccountAddress, IncomingBlockRetrievalRequest>,
+    pub block_retrieval_streams: BlockRetrievalStreams,
+    pub reliable_broadcasts: ReliableBroadcasts,
+}
+
+/// The chunks of the block retrieval streams are routed by the NetworkTask directly to the
+/// requesters, as the requesters wait for them from within the consensus main loop.
+#[derive(Clone, Default)]
+pub struct BlockRetrievalStreams {
+    next_stream_id: Arc<AtomicU64>,
+    senders: Arc<Mutex<HashMap<(Author, u64), mpsc::Sender<BlockRetrievalStreamChunk>>>>,
+}
+
+impl BlockRetrievalStreams {
+    /// Registers a new stream from the given peer, buffering up to size chunks.
+    fn register(
+        &self,
+        peer: Author,
+        size: usize,
+    ) -> (u64, mpsc::Receiver<BlockRetrievalStreamChunk>) {
+        let stream_id = self.next_stream_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = mpsc::channel(size);
+        self.senders.lock().insert((peer, stream_id), sender);
+        (stream_id, receiver)
+    }
+
+    fn unregister(&self, peer: Author, stream_id: u64) {
+        self.senders.lock().remove(&(peer, stream_id));
+    }
+
+    fn route(&self, peer: Author, chunk: BlockRetrievalStreamChunk) -> anyhow::Result<()> {
+        match self.senders.lock().get_mut(&(peer, chunk.stream_id())) {
+            Some(sender) => sender
+                .try_send(chunk)
+                .map_err(|e| anyhow!("Failed to route block retrieval chunk: {:?}", e)),
+            None => Err(anyhow!(
+                "Unknown block retrieval stream {}",
+                chunk.stream_id()
+            )),
+        }
+    }
+}
+
+/// A message broadcast reliably that some validators didn't acknowledge yet.
+struct PendingBroadcast {
+    // Distinguishes the successive broadcasts of the same message
+    id: u64,
+    epoch: u64,
+    round: Round,
+    unacked: HashSet<Author>,
+}
+
+/// The messages broadcast reliably that some validators didn't acknowledge yet, only the latest
+/// message of every kind is kept. The acknowledgements are routed by the NetworkTask directly.
+#[derive(Clone, Default)]
+pub struct ReliableBroadcasts {
+    next_id: Arc<AtomicU64>,
+    pending: Arc<Mutex<HashMap<BroadcastKind, PendingBroadcast>>>,
+}
+
+impl ReliableBroadcasts {
+    /// Waits for the acknowledgements of the given recipients, replacing the previous message of
+    /// the same kind. Returns the id of the broadcast.
+    pub fn register(
+        &self,
+        kind: BroadcastKind,
+        epoch: u64,
+        round: Round,
+        recipients: impl IntoIterator<Item = Author>,
+    ) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.pending.lock().insert(
+            kind,
+            PendingBroadcast {
+                id,
+                epoch,
+                round,
+                unacked: recipients.into_iter().collect(),
+            },
+        );
+        id
+    }
+
+    /// The recipients of the broadcast that didn't acknowledge it, none if the broadcast was
+    /// replaced or acknowledged by all of them.
+    pub fn unacked(&self, kind: BroadcastKind, id: u64) -> Option<Vec<Author>> {
+        match self.pending.lock().get(&kind) {
+            Some(pending) if pending.id == id && !pending.unacked.is_empty() => {
+                Some(pending.unacked.iter().copied().collect())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn process_ack(&self, peer: Author, ack: &BroadcastAck) {
+        if let Some(pending) = self.pending.lock().get_mut(&ack.kind()) {
+            if pending.epoch == ack.epoch() && pending.round == ack.round() {
+                pending.unacked.remove(&peer);
+            }
+        }
+    }
 }
 
 /// Implements the actual networking support for all consensus messaging.
@@ -62,6 +177,16 @@ pub struct NetworkSender {
     // Note that we do not support self rpc requests as it might cause infinite recursive calls.
     self_sender: channel::Sender<Event<ConsensusMsg>>,
     validators: ValidatorVerifier,
+    // Publishes the proposals and votes of this validator to subscribed observers (if enabled).
+    publisher: Option<ConsensusPublisher>,
+    // Long chains of blocks are requested with block retrieval streams (if enabled).
+    block_retrieval_streams: Option<BlockRetrievalStreams>,
+    stream_chunk_size: u64,
+    stream_window: u64,
+    // Timeout votes and commit votes are sent again to the validators that didn't acknowledge
+    // them (if enabled).
+    reliable_broadcasts: Option<ReliableBroadcasts>,
+    reliable_broadcast_config: ReliableBroadcastConfig,
 }
 
 impl NetworkSender {
@@ -76,9 +201,47 @@ impl NetworkSender {
             network_sender,
             self_sender,
             validators,
+            publisher: None,
+            block_retrieval_streams: None,
+            stream_chunk_size: MAX_BLOCKS_PER_REQUEST,
+            stream_window: 1,
+            reliable_broadcasts: None,
+            reliable_broadcast_config: ReliableBroadcastConfig::default(),
         }
     }
 
+    /// Also publish the proposals and votes sent by this node to the consensus observers.
+    pub fn set_publisher(&mut self, publisher: ConsensusPublisher) {
+        self.publisher = Some(publisher);
+    }
+
+    /// Request long chains of blocks with streams of chunks of chunk_size blocks, at most window
+    /// chunks being in flight.
+    pub fn set_block_retrieval_streams(
+        &mut self,
+        streams: BlockRetrievalStreams,
+        chunk_size: u64,
+        window: u64,
+    ) {
+        self.block_retrieval_streams = Some(streams);
+        self.stream_chunk_size = chunk_size;
+        self.stream_window = window;
+    }
+
+    /// Send the reliably broadcast messages again to the validators that don't acknowledge them.
+    pub fn set_reliable_broadcasts(
+        &mut self,
+        broadcasts: ReliableBroadcasts,
+        config: ReliableBroadcastConfig,
+    ) {
+        self.reliable_broadcasts = Some(broadcasts);
+        self.reliable_broadcast_config = config;
+    }
+
+    pub fn supports_block_retrieval_streams(&self) -> bool {
+        self.block_retrieval_streams.is_some()
+    }
+
     /// Tries to retrieve num of blocks backwards starting from id from the given peer: the function
     /// returns a future that is fulfilled with BlockRetrievalResponse.
     pub async fn request_block(
@@ -115,6 +278,127 @@ impl NetworkSender {
         Ok(response)
     }
 
+    /// Tries to retrieve num_blocks blocks backwards starting from block_id from the given peer
+    /// over a stream: the timeout applies to every chunk. The peer may end the stream before
+    /// num_blocks blocks are sent (the response is still successful if it had all the blocks it
+    /// sent), but the blocks received always form a chain starting from block_id.
+    pub async fn request_block_stream(
+        &mut self,
+        block_id: HashValue,
+        num_blocks: u64,
+        from: Author,
+        timeout: Duration,
+    ) -> anyhow::Result<BlockRetrievalResponse> {
+        ensure!(from != self.author, "Retrieve block from self");
+        let streams = self
+            .block_retrieval_streams
+            .clone()
+            .ok_or_else(|| anyhow!("Block retrieval streams are disabled"))?;
+        let (stream_id, mut chunks) = streams.register(from, self.stream_window as usize);
+        let response = monitor!(
+            "block_retrieval_stream",
+            self.receive_block_stream(stream_id, block_id, num_blocks, from, timeout, &mut chunks)
+                .await
+        );
+        streams.unregister(from, stream_id);
+        response
+    }
+
+    async fn receive_block_stream(
+        &mut self,
+        stream_id: u64,
+        block_id: HashValue,
+        num_blocks: u64,
+        from: Author,
+        timeout: Duration,
+        chunks: &mut mpsc::Receiver<BlockRetrievalStreamChunk>,
+    ) -> anyhow::Result<BlockRetrievalResponse> {
+        let request = BlockRetrievalStreamRequest::new(
+            stream_id,
+            block_id,
+            num_blocks,
+            self.stream_chunk_size,
+            self.stream_window,
+        );
+        self.network_sender.send_to(
+            from,
+            ConsensusMsg::BlockRetrievalStreamRequest(Box::new(request)),
+        )?;
+
+        let mut blocks = vec![];
+        let mut expected_id = block_id;
+        let mut num_chunks = 0;
+        loop {
+            let chunk = tokio::time::timeout(timeout, chunks.next())
+                .await
+                .map_err(|_| {
+                    anyhow!(
+                        "Timeout waiting for chunk {} of block retrieval stream {}",
+                        num_chunks,
+                        stream_id
+                    )
+                })?
+                .ok_or_else(|| anyhow!("Block retrieval stream {} closed", stream_id))?;
+            ensure!(
+                chunk.chunk_index() == num_chunks,
+                "Unexpected chunk {} of block retrieval stream {}, expect {}",
+                chunk.chunk_index(),
+                stream_id,
+                num_chunks
+            );
+            let response = chunk.response();
+            ensure!(
+                (blocks.len() + response.blocks().len()) as u64 <= num_blocks,
+                "Block retrieval stream {} returned more than {} blocks",
+                stream_id,
+                num_blocks
+            );
+            response
+                .verify(
+                    expected_id,
+                    response.blocks().len() as u64,
+                    &self.validators,
+                )
+                .map_err(|e| {
+                    error!(
+                        SecurityEvent::InvalidRetrievedBlock,
+                        request_block_response = response,
+                        error = ?e,
+                    );
+                    e
+                })?;
+            num_chunks += 1;
+            if let Some(block) = response.blocks().last() {
+                expected_id = block.parent_id();
+            }
+            blocks.extend(response.blocks().iter().cloned());
+
+            // a chunk without blocks ends the stream, so that the peer can't keep it open forever
+            if chunk.is_last() || response.blocks().is_empty() {
+                let status = if blocks.is_empty() {
+                    BlockRetrievalStatus::IdNotFound
+                } else if chunk.is_last() {
+                    response.status()
+                } else {
+                    BlockRetrievalStatus::NotEnoughBlocks
+                };
+                return Ok(BlockRetrievalResponse::new(status, blocks));
+            }
+            ensure!(
+                (blocks.len() as u64) < num_blocks,
+                "Block retrieval stream {} not over after {} blocks",
+                stream_id,
+                num_blocks
+            );
+            self.network_sender.send_to(
+                from,
+                ConsensusMsg::BlockRetrievalStreamAck(Box::new(BlockRetrievalStreamAck::new(
+                    stream_id, num_chunks,
+                ))),
+            )?;
+        }
+    }
+
     /// Tries to send the given msg to all the participants.
     ///
     /// The future is fulfilled as soon as the message put into the mpsc channel to network
@@ -122,6 +406,10 @@ impl NetworkSender {
     /// out. It does not give indication about when the message is delivered to the recipients,
     /// as well as there is no indication about the network failures.
     pub async fn broadcast(&mut self, msg: ConsensusMsg) {
+        if let Some(publisher) = &self.publisher {
+            publisher.publish_consensus_msg(&msg);
+        }
+
         // Directly send the message to ourself without going through network.
         let self_msg = Event::Message(self.author, msg.clone());
         if let Err(err) = self.self_sender.send(self_msg).await {
@@ -142,6 +430,48 @@ impl NetworkSender {
         }
     }
 
+    /// Broadcasts the message like broadcast(), then sends it again every retry interval to the
+    /// validators that didn't acknowledge it, up to max_retries times. The retries stop early
+    /// when a message of the same kind is broadcast reliably again.
+    pub async fn reliable_broadcast(
+        &mut self,
+        msg: ConsensusMsg,
+        kind: BroadcastKind,
+        epoch: u64,
+        round: Round,
+    ) {
+        let broadcasts = match &self.reliable_broadcasts {
+            Some(broadcasts) => broadcasts.clone(),
+            None => return self.broadcast(msg).await,
+        };
+        let self_author = self.author;
+        let id = broadcasts.register(
+            kind,
+            epoch,
+            round,
+            self.validators
+                .get_ordered_account_addresses_iter()
+                .filter(|author| author != &self_author),
+        );
+        self.broadcast(msg.clone()).await;
+
+        let mut network_sender = self.network_sender.clone();
+        let config = self.reliable_broadcast_config;
+        tokio::spawn(async move {
+            for _ in 0..config.max_retries {
+                tokio::time::sleep(Duration::from_millis(config.retry_interval_ms)).await;
+                let recipients = match broadcasts.unacked(kind, id) {
+                    Some(recipients) => recipients,
+                    None => return,
+                };
+                counters::RELIABLE_BROADCAST_RESENT_MSGS.inc_by(recipients.len() as u64);
+                if let Err(e) = network_sender.send_to_many(recipients.into_iter(), msg.clone()) {
+                    error!(error = ?e, "Error sending a reliably broadcast message again");
+                }
+            }
+        });
+    }
+
     /// Tries to send msg to given recipients.
     pub async fn send(&self, msg: ConsensusMsg, recipients: Vec<Author>) {
         let mut network_sender = self.network_sender.clone();
@@ -172,6 +502,9 @@ impl NetworkSender {
     /// out. It does not give indication about when the message is delivered to the recipients,
     /// as well as there is no indication about the network failures.
     pub async fn send_vote(&self, vote_msg: VoteMsg, recipients: Vec<Author>) {
+        if let Some(publisher) = &self.publisher {
+            publisher.publish_vote(&vote_msg);
+        }
         let msg = ConsensusMsg::VoteMsg(Box::new(vote_msg));
         self.send(msg, recipients).await
     }
@@ -196,6 +529,8 @@ pub struct NetworkTask {
         (AccountAddress, ConsensusMsg),
     >,
     block_retrieval_tx: diem_channel::Sender<AccountAddress, IncomingBlockRetrievalRequest>,
+    block_retrieval_streams: BlockRetrievalStreams,
+    reliable_broadcasts: ReliableBroadcasts,
     all_events: Box<dyn Stream<Item = Event<ConsensusMsg>> + Send + Unpin>,
     connections: Arc<RwLock<HashMap<PeerId, SupportedProtocols>>>,
 }
@@ -214,17 +549,23 @@ impl NetworkTask {
             1,
             Some(&counters::BLOCK_RETRIEVAL_CHANNEL_MSGS),
         );
+        let block_retrieval_streams = BlockRetrievalStreams::default();
+        let reliable_broadcasts = ReliableBroadcasts::default();
         let all_events = Box::new(select(network_events, self_receiver));
         (
             NetworkTask {
                 consensus_messages_tx,
                 block_retrieval_tx,
+                block_retrieval_streams: block_retrieval_streams.clone(),
+                reliable_broadcasts: reliable_broadcasts.clone(),
                 all_events,
                 connections,
             },
             NetworkReceivers {
                 consensus_messages,
                 block_retrieval,
+                block_retrieval_streams,
+                reliable_broadcasts,
             },
         )
     }
@@ -232,6 +573,15 @@ impl NetworkTask {
     pub async fn start(mut self) {
         while let Some(message) = self.all_events.next().await {
             match message {
+                Event::Message(peer_id, ConsensusMsg::BlockRetrievalStreamChunk(chunk)) => {
+                    if let Err(e) = self.block_retrieval_streams.route(peer_id, *chunk) {
+                        debug!(remote_peer = peer_id, error = ?e, "Dropping stream chunk");
+                    }
+                }
+                Event::Message(peer_id, ConsensusMsg::BroadcastAck(ack)) => {
+                    counters::RELIABLE_BROADCAST_ACKS.inc();
+                    self.reliable_broadcasts.process_ack(peer_id, &ack);
+                }
                 Event::Message(peer_id, msg) => {
                     if let Err(e) = self
                         .consensus_messages_tx
@@ -7,13 +7,21 @@ use crate::counters;
 use anyhow::anyhow;
 use channel::message_queues::QueueStyle;
 use consensus_types::{
-    block_retrieval::{BlockRetrievalRequest, BlockRetrievalResponse},
+    block_retrieval::{
+        BlockRetrievalRequest, BlockRetrievalResponse, BlockRetrievalStreamAck,
+        BlockRetrievalStreamChunk, BlockRetrievalStreamRequest,
+    },
+    broadcast_ack::BroadcastAck,
     epoch_retrieval::EpochRetrievalRequest,
-    experimental::{commit_decision::CommitDecision, commit_vote::CommitVote},
+    experimental::{
+        commit_decision::CommitDecision, commit_vote::CommitVote,
+        commit_vote_batch::CommitVoteBatch,
+    },
     proposal_msg::ProposalMsg,
     sync_info::SyncInfo,
     vote_msg::VoteMsg,
 };
+use diem_config::config::PayloadCompressionType;
 use diem_infallible::RwLock;
 use diem_logger::prelude::*;
 use diem_metrics::IntCounterVec;
@@ -21,7 +29,7 @@ use diem_types::{epoch_change::EpochChangeProof, PeerId};
 use network::{
     constants::NETWORK_CHANNEL_SIZE,
     error::NetworkError,
-    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    peer_manager::{ConnectionRequestSender, Misbehavior, PeerManagerRequestSender},
     protocols::{
         network::{NetworkEvents, NetworkSender, NewNetworkSender},
         rpc::error::RpcError,
@@ -59,6 +67,18 @@ pub enum ConsensusMsg {
     /// than 2f + 1 signatures on the commit proposal. This part is not on the critical path, but
     /// it can save slow machines to quickly confirm the execution result.
     CommitDecisionMsg(Box<CommitDecision>),
+    /// Request to stream a chain of blocks of the given length starting from the given block id.
+    BlockRetrievalStreamRequest(Box<BlockRetrievalStreamRequest>),
+    /// Carries a chunk of the blocks of a block retrieval stream.
+    BlockRetrievalStreamChunk(Box<BlockRetrievalStreamChunk>),
+    /// Acknowledges the received chunks of a block retrieval stream.
+    BlockRetrievalStreamAck(Box<BlockRetrievalStreamAck>),
+    /// The commit votes a validator knows for the same commit proposal, sent together instead of
+    /// one message per vote.
+    CommitVoteBatchMsg(Box<CommitVoteBatch>),
+    /// Acknowledges a timeout vote or a batch of commit votes, so that the sender stops sending it
+    /// again.
+    BroadcastAck(Box<BroadcastAck>),
 }
 
 /// The interface from Network to Consensus layer.
@@ -81,6 +101,8 @@ pub type ConsensusNetworkEvents = NetworkEvents<ConsensusMsg>;
 pub struct ConsensusNetworkSender {
     network_sender: NetworkSender<ConsensusMsg>,
     peers_protocols: Option<Arc<RwLock<HashMap<PeerId, SupportedProtocols>>>>,
+    // Direct send protocols in preferred order, compressed protocols come first if enabled
+    direct_send_protocols: &'static [ProtocolId],
 }
 
 /// Supported protocols in preferred order (from highest priority to lowest).
@@ -89,6 +111,20 @@ pub const RPC: &[ProtocolId] = &[ProtocolId::ConsensusRpc];
 pub const DIRECT_SEND: &[ProtocolId] = &[
     ProtocolId::ConsensusDirectSendJSON,
     ProtocolId::ConsensusDirectSend,
+    ProtocolId::ConsensusDirectSendLz4,
+    ProtocolId::ConsensusDirectSendZstd,
+];
+/// Supported protocols in preferred order when lz4 compression is enabled.
+pub const DIRECT_SEND_LZ4: &[ProtocolId] = &[
+    ProtocolId::ConsensusDirectSendLz4,
+    ProtocolId::ConsensusDirectSendJSON,
+    ProtocolId::ConsensusDirectSend,
+];
+/// Supported protocols in preferred order when zstd compression is enabled.
+pub const DIRECT_SEND_ZSTD: &[ProtocolId] = &[
+    ProtocolId::ConsensusDirectSendZstd,
+    ProtocolId::ConsensusDirectSendJSON,
+    ProtocolId::ConsensusDirectSend,
 ];
 
 /// Configuration for the network endpoints to support consensus.
@@ -119,18 +155,29 @@ impl NewNetworkSender for ConsensusNetworkSender {
         Self {
             network_sender: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
             peers_protocols: None,
+            direct_send_protocols: DIRECT_SEND,
         }
     }
 }
 
 impl ConsensusNetworkSender {
+    /// Compress the direct send messages to the peers that support the given compression, the
+    /// other peers keep receiving uncompressed messages.
+    pub fn set_compression(&mut self, compression: PayloadCompressionType) {
+        self.direct_send_protocols = match compression {
+            PayloadCompressionType::Disabled => DIRECT_SEND,
+            PayloadCompressionType::Lz4 => DIRECT_SEND_LZ4,
+            PayloadCompressionType::Zstd => DIRECT_SEND_ZSTD,
+        };
+    }
+
     /// Send a single message to the destination peer using available ProtocolId.
     pub fn send_to(
         &mut self,
         recipient: PeerId,
         message: ConsensusMsg,
     ) -> Result<(), NetworkError> {
-        let protocol = self.preferred_protocol_for_peer(recipient, DIRECT_SEND)?;
+        let protocol = self.preferred_protocol_for_peer(recipient, self.direct_send_protocols)?;
         self.network_sender.send_to(recipient, protocol, message)
     }
 
@@ -142,7 +189,7 @@ impl ConsensusNetworkSender {
     ) -> Result<(), NetworkError> {
         let mut peers_per_protocol = HashMap::new();
         for peer in recipients {
-            match self.preferred_protocol_for_peer(peer, DIRECT_SEND) {
+            match self.preferred_protocol_for_peer(peer, self.direct_send_protocols) {
                 Ok(protocol) => peers_per_protocol
                     .entry(protocol)
                     .or_insert_with(Vec::new)
@@ -170,6 +217,16 @@ impl ConsensusNetworkSender {
             .await
     }
 
+    /// Report a peer which sent an invalid message, so that the network bans it once it misbehaved
+    /// too often.
+    pub fn report_misbehavior(
+        &mut self,
+        peer: PeerId,
+        misbehavior: Misbehavior,
+    ) -> Result<(), NetworkError> {
+        self.network_sender.report_misbehavior(peer, misbehavior)
+    }
+
     /// Initialize a shared hashmap about connections metadata that is updated by the receiver.
     pub fn initialize(&mut self, connections: Arc<RwLock<HashMap<PeerId, SupportedProtocols>>>) {
         self.peers_protocols = Some(connections);
@@ -1,40 +1,20 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{
-    network::{NetworkReceivers, NetworkSender},
-    network_interface::{ConsensusMsg, ConsensusNetworkEvents, ConsensusNetworkSender},
-    test_utils::{self, consensus_runtime, placeholder_ledger_info, timed_block_on},
-};
-use channel::{self, diem_channel, message_queues::QueueStyle};
-use consensus_types::{
-    block::{block_test_utils::certificate_for_genesis, Block},
-    common::Author,
-    proposal_msg::ProposalMsg,
-    sync_info::SyncInfo,
-    vote::Vote,
-    vote_data::VoteData,
-    vote_msg::VoteMsg,
-};
+use crate::network_interface::ConsensusMsg;
+use channel::{self, diem_channel};
+use consensus_types::common::Author;
 use diem_infallible::{Mutex, RwLock};
-use diem_types::{block_info::BlockInfo, PeerId};
+use diem_types::PeerId;
 use futures::{channel::mpsc, SinkExt, StreamExt};
 use network::{
-    peer_manager::{
-        conn_notifs_channel, ConnectionRequestSender, PeerManagerNotification, PeerManagerRequest,
-        PeerManagerRequestSender,
-    },
-    protocols::{
-        network::{NewNetworkEvents, NewNetworkSender},
-        rpc::InboundRpcRequest,
-        wire::handshake::v1::SupportedProtocols,
-    },
+    peer_manager::{PeerManagerNotification, PeerManagerRequest},
+    protocols::{rpc::InboundRpcRequest, wire::handshake::v1::SupportedProtocols},
     ProtocolId,
 };
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
-    time::Duration,
 };
 use tokio::runtime::Handle;
 
@@ -473,15 +453,35 @@ impl DropConfigRound {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::network::NetworkTask;
+    use crate::{
+        network::{NetworkReceivers, NetworkSender, NetworkTask},
+        network_interface::{ConsensusNetworkEvents, ConsensusNetworkSender},
+        test_utils::{self, consensus_runtime, placeholder_ledger_info, timed_block_on},
+    };
     use bytes::Bytes;
-    use consensus_types::block_retrieval::{
-        BlockRetrievalRequest, BlockRetrievalResponse, BlockRetrievalStatus,
+    use channel::message_queues::QueueStyle;
+    use consensus_types::{
+        block::{block_test_utils::certificate_for_genesis, Block},
+        block_retrieval::{BlockRetrievalRequest, BlockRetrievalResponse, BlockRetrievalStatus},
+        broadcast_ack::{BroadcastAck, BroadcastKind},
+        proposal_msg::ProposalMsg,
+        sync_info::SyncInfo,
+        vote::Vote,
+        vote_data::VoteData,
+        vote_msg::VoteMsg,
     };
+    use diem_config::config::ReliableBroadcastConfig;
     use diem_crypto::HashValue;
-    use diem_types::validator_verifier::random_validator_verifier;
+    use diem_types::{block_info::BlockInfo, validator_verifier::random_validator_verifier};
     use futures::{channel::oneshot, future};
-    use network::protocols::direct_send::Message;
+    use network::{
+        peer_manager::{conn_notifs_channel, ConnectionRequestSender, PeerManagerRequestSender},
+        protocols::{
+            direct_send::Message,
+            network::{NewNetworkEvents, NewNetworkSender},
+        },
+    };
+    use std::time::Duration;
 
     #[test]
     fn test_split_network_round() {
@@ -629,6 +629,114 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_reliable_broadcast() {
+        let mut runtime = consensus_runtime();
+        let num_nodes = 3;
+        let mut receivers: Vec<NetworkReceivers> = Vec::new();
+        let mut playground = NetworkPlayground::new(runtime.handle().clone());
+        let mut nodes = Vec::new();
+        let (signers, validator_verifier) = random_validator_verifier(num_nodes, None, false);
+        let peers: Vec<_> = signers.iter().map(|signer| signer.author()).collect();
+        let shared_connections = Arc::new(RwLock::new(HashMap::new()));
+
+        for (peer_id, peer) in peers.iter().enumerate() {
+            let (network_reqs_tx, network_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
+            let (connection_reqs_tx, _) = diem_channel::new(QueueStyle::FIFO, 8, None);
+            let (consensus_tx, consensus_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
+            let (_conn_mgr_reqs_tx, conn_mgr_reqs_rx) = channel::new_test(8);
+            let (_, conn_status_rx) = conn_notifs_channel::new();
+            shared_connections.write().insert(
+                *peer,
+                vec![
+                    ProtocolId::ConsensusDirectSendJSON,
+                    ProtocolId::ConsensusDirectSend,
+                    ProtocolId::ConsensusRpc,
+                ]
+                .iter()
+                .into(),
+            );
+            let mut network_sender = ConsensusNetworkSender::new(
+                PeerManagerRequestSender::new(network_reqs_tx),
+                ConnectionRequestSender::new(connection_reqs_tx),
+            );
+            network_sender.initialize(shared_connections.clone());
+            let network_events = ConsensusNetworkEvents::new(consensus_rx, conn_status_rx);
+
+            let twin_id = TwinId {
+                id: peer_id,
+                author: *peer,
+            };
+
+            playground.add_node(twin_id, consensus_tx, network_reqs_rx, conn_mgr_reqs_rx);
+
+            let (self_sender, self_receiver) = channel::new_test(8);
+            let mut node = NetworkSender::new(
+                *peer,
+                network_sender,
+                self_sender,
+                validator_verifier.clone(),
+            );
+            let (task, receiver) =
+                NetworkTask::new(network_events, self_receiver, shared_connections.clone());
+            node.set_reliable_broadcasts(
+                receiver.reliable_broadcasts.clone(),
+                ReliableBroadcastConfig {
+                    enabled: true,
+                    retry_interval_ms: 1000,
+                    max_retries: 1,
+                },
+            );
+            receivers.push(receiver);
+            runtime.handle().spawn(task.start());
+            nodes.push(node);
+        }
+        let mut vote = Vote::new(
+            VoteData::new(BlockInfo::random(1), BlockInfo::random(0)),
+            peers[0],
+            placeholder_ledger_info(),
+            &signers[0],
+        );
+        vote.add_timeout_signature(signers[0].sign(&vote.generate_timeout()));
+        let timeout_msg = ConsensusMsg::VoteMsg(Box::new(VoteMsg::new(
+            vote,
+            test_utils::placeholder_sync_info(),
+        )));
+        let ack = BroadcastAck::new(BroadcastKind::Timeout, 1, 1);
+        let broadcasts = receivers[0].reliable_broadcasts.clone();
+
+        timed_block_on(&mut runtime, async {
+            nodes[0]
+                .reliable_broadcast(timeout_msg.clone(), BroadcastKind::Timeout, 1, 1)
+                .await;
+            // the ids of the broadcasts start from 0
+            let id = 0;
+            playground
+                .wait_for_messages(2, NetworkPlayground::take_all)
+                .await;
+            for r in receivers.iter_mut().skip(1) {
+                r.consensus_messages.next().await.unwrap();
+            }
+
+            // only the validator that didn't acknowledge the timeout receives it again
+            nodes[2]
+                .send(ConsensusMsg::BroadcastAck(Box::new(ack)), vec![peers[0]])
+                .await;
+            playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
+                .await;
+            while broadcasts.unacked(BroadcastKind::Timeout, id) != Some(vec![peers[1]]) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
+                .await;
+            let (author, msg) = receivers[1].consensus_messages.next().await.unwrap();
+            assert_eq!(author, peers[0]);
+            assert!(matches!(msg, ConsensusMsg::VoteMsg(_)));
+        });
+    }
+
     #[test]
     fn test_rpc() {
         let mut runtime = consensus_runtime();
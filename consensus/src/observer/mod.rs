@@ -0,0 +1,14 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Consensus observer: validators publish their proposals, votes and commit decisions to the
+//! fullnodes subscribed on their fullnode network, and the fullnodes track consensus in real time
+//! without signing anything. Verified commit decisions are handed to state sync as sync targets,
+//! so observers learn about new commits without waiting for chunk-based sync to discover them.
+
+pub mod network;
+pub mod observer_epoch_manager;
+pub mod publisher;
+
+#[cfg(test)]
+mod observer_test;
@@ -0,0 +1,58 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Interface between the consensus observer and the Network layer.
+
+use crate::counters;
+use channel::message_queues::QueueStyle;
+use consensus_types::{proposal_msg::ProposalMsg, vote_msg::VoteMsg};
+use diem_metrics::IntCounterVec;
+use diem_types::ledger_info::LedgerInfoWithSignatures;
+use network::{
+    protocols::network::{NetworkEvents, NetworkSender},
+    ProtocolId,
+};
+use serde::{Deserialize, Serialize};
+
+/// Network type for the consensus observer
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ObserverMsg {
+    /// Sent by an observer to start receiving consensus messages from a publisher.
+    Subscribe,
+    /// Sent by an observer to stop receiving consensus messages from a publisher.
+    Unsubscribe,
+    /// A proposal broadcast by the publishing validator.
+    ProposalMsg(Box<ProposalMsg>),
+    /// A vote sent by the publishing validator.
+    VoteMsg(Box<VoteMsg>),
+    /// A ledger info certified by a quorum of validators.
+    CommitDecision(Box<LedgerInfoWithSignatures>),
+}
+
+/// The interface from Network to the consensus observer (and publisher).
+pub type ObserverNetworkEvents = NetworkEvents<ObserverMsg>;
+
+/// The interface from the consensus observer (and publisher) to Network.
+pub type ObserverNetworkSender = NetworkSender<ObserverMsg>;
+
+/// Supported protocols in preferred order (from highest priority to lowest).
+pub const DIRECT_SEND: &[ProtocolId] = &[ProtocolId::ConsensusObserverDirectSend];
+
+/// Configuration for the network endpoints to support the consensus observer.
+pub fn network_endpoint_config(
+    max_network_channel_size: usize,
+) -> (
+    Vec<ProtocolId>,
+    Vec<ProtocolId>,
+    QueueStyle,
+    usize,
+    Option<&'static IntCounterVec>,
+) {
+    (
+        vec![],
+        DIRECT_SEND.to_vec(),
+        QueueStyle::FIFO,
+        max_network_channel_size,
+        Some(&counters::PENDING_CONSENSUS_OBSERVER_NETWORK_EVENTS),
+    )
+}
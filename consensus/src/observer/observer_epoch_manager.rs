@@ -0,0 +1,218 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters,
+    observer::network::{ObserverMsg, ObserverNetworkEvents, ObserverNetworkSender},
+};
+use anyhow::{anyhow, ensure};
+use channel::diem_channel;
+use consensus_notifications::ConsensusNotificationSender;
+use consensus_types::common::Round;
+use diem_logger::prelude::*;
+use diem_types::{
+    epoch_state::EpochState,
+    ledger_info::LedgerInfoWithSignatures,
+    on_chain_config::{OnChainConfigPayload, ValidatorSet},
+    transaction::Version,
+};
+use futures::{select, StreamExt};
+use network::{protocols::network::Event, ProtocolId};
+use std::{cmp::max, sync::Arc};
+
+/// ObserverEpochManager tracks the consensus state of the validators it subscribed to without
+/// participating: it verifies the published proposals, votes and commit decisions against the
+/// current validator set and hands new commit decisions to state sync.
+pub struct ObserverEpochManager {
+    network_sender: ObserverNetworkSender,
+    state_sync_notifier: Arc<dyn ConsensusNotificationSender>,
+    epoch_state: Option<EpochState>,
+    highest_ordered_round: Round,
+    highest_committed_version: Version,
+}
+
+impl ObserverEpochManager {
+    pub fn new(
+        network_sender: ObserverNetworkSender,
+        state_sync_notifier: Arc<dyn ConsensusNotificationSender>,
+        committed_version: Version,
+    ) -> Self {
+        Self {
+            network_sender,
+            state_sync_notifier,
+            epoch_state: None,
+            highest_ordered_round: 0,
+            highest_committed_version: committed_version,
+        }
+    }
+
+    pub fn epoch_state(&self) -> Option<&EpochState> {
+        self.epoch_state.as_ref()
+    }
+
+    pub fn highest_ordered_round(&self) -> Round {
+        self.highest_ordered_round
+    }
+
+    pub fn highest_committed_version(&self) -> Version {
+        self.highest_committed_version
+    }
+
+    pub async fn start(
+        mut self,
+        mut network_events: ObserverNetworkEvents,
+        mut reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
+    ) {
+        loop {
+            select! {
+                payload = reconfig_events.select_next_some() => {
+                    self.process_reconfiguration(payload);
+                }
+                event = network_events.select_next_some() => {
+                    self.process_network_event(event);
+                }
+                complete => break,
+            }
+        }
+    }
+
+    fn process_reconfiguration(&mut self, payload: OnChainConfigPayload) {
+        let validator_set: ValidatorSet = match payload.get() {
+            Ok(validator_set) => validator_set,
+            Err(e) => {
+                error!(error = ?e, "[ObserverEpochManager] failed to get ValidatorSet");
+                return;
+            }
+        };
+        let epoch = payload.epoch();
+        // the epoch might have been already updated by an epoch ending commit decision
+        if self
+            .epoch_state
+            .as_ref()
+            .map_or(false, |epoch_state| epoch_state.epoch >= epoch)
+        {
+            return;
+        }
+        self.start_new_epoch(EpochState {
+            epoch,
+            verifier: (&validator_set).into(),
+        });
+    }
+
+    pub(crate) fn start_new_epoch(&mut self, epoch_state: EpochState) {
+        info!(
+            epoch = epoch_state.epoch,
+            "[ObserverEpochManager] new epoch"
+        );
+        counters::EPOCH.set(epoch_state.epoch as i64);
+        self.epoch_state = Some(epoch_state);
+        self.highest_ordered_round = 0;
+    }
+
+    fn process_network_event(&mut self, event: Event<ObserverMsg>) {
+        match event {
+            Event::NewPeer(metadata) => {
+                // subscribe to every upstream peer, only validators with a publisher will answer
+                if let Err(e) = self.network_sender.send_to(
+                    metadata.remote_peer_id,
+                    ProtocolId::ConsensusObserverDirectSend,
+                    ObserverMsg::Subscribe,
+                ) {
+                    warn!(
+                        remote_peer = metadata.remote_peer_id,
+                        error = ?e,
+                        "[ObserverEpochManager] failed to subscribe"
+                    );
+                }
+            }
+            Event::Message(peer_id, msg) => {
+                if let Err(e) = self.process_message(msg) {
+                    counters::CONSENSUS_OBSERVER_INVALID_MESSAGES.inc();
+                    warn!(
+                        remote_peer = peer_id,
+                        error = ?e,
+                        "[ObserverEpochManager] failed to process message"
+                    );
+                }
+            }
+            Event::LostPeer(_) | Event::RpcRequest(..) => (),
+        }
+    }
+
+    pub(crate) fn process_message(&mut self, msg: ObserverMsg) -> anyhow::Result<()> {
+        let epoch_state = self
+            .epoch_state
+            .as_ref()
+            .ok_or_else(|| anyhow!("Epoch state is not initialized"))?;
+        match msg {
+            ObserverMsg::ProposalMsg(proposal) => {
+                ensure!(
+                    proposal.epoch() == epoch_state.epoch,
+                    "Proposal for epoch {}, current epoch {}",
+                    proposal.epoch(),
+                    epoch_state.epoch
+                );
+                proposal.verify(&epoch_state.verifier)?;
+                self.highest_ordered_round = max(
+                    self.highest_ordered_round,
+                    proposal.sync_info().highest_ordered_round(),
+                );
+                counters::CONSENSUS_OBSERVER_ORDERED_ROUND.set(self.highest_ordered_round as i64);
+                Ok(())
+            }
+            ObserverMsg::VoteMsg(vote_msg) => {
+                ensure!(
+                    vote_msg.epoch() == epoch_state.epoch,
+                    "Vote for epoch {}, current epoch {}",
+                    vote_msg.epoch(),
+                    epoch_state.epoch
+                );
+                vote_msg.verify(&epoch_state.verifier)
+            }
+            ObserverMsg::CommitDecision(commit_decision) => {
+                self.process_commit_decision(*commit_decision)
+            }
+            ObserverMsg::Subscribe | ObserverMsg::Unsubscribe => {
+                Err(anyhow!("Observers do not accept subscriptions"))
+            }
+        }
+    }
+
+    fn process_commit_decision(
+        &mut self,
+        commit_decision: LedgerInfoWithSignatures,
+    ) -> anyhow::Result<()> {
+        let epoch_state = self
+            .epoch_state
+            .as_ref()
+            .ok_or_else(|| anyhow!("Epoch state is not initialized"))?;
+        let ledger_info = commit_decision.ledger_info();
+        ensure!(
+            ledger_info.epoch() == epoch_state.epoch,
+            "Commit decision for epoch {}, current epoch {}",
+            ledger_info.epoch(),
+            epoch_state.epoch
+        );
+        let version = ledger_info.version();
+        if version <= self.highest_committed_version {
+            return Ok(());
+        }
+        commit_decision.verify_signatures(&epoch_state.verifier)?;
+
+        self.highest_committed_version = version;
+        counters::CONSENSUS_OBSERVER_COMMITTED_VERSION.set(version as i64);
+        if let Some(next_epoch_state) = ledger_info.next_epoch_state() {
+            self.start_new_epoch(next_epoch_state.clone());
+        }
+
+        // State sync keeps the latest target only, so the previous request does not need to
+        // complete before sending the next one.
+        let state_sync_notifier = self.state_sync_notifier.clone();
+        tokio::spawn(async move {
+            if let Err(e) = state_sync_notifier.sync_to_target(commit_decision).await {
+                warn!(error = ?e, "[ObserverEpochManager] failed to sync to commit decision");
+            }
+        });
+        Ok(())
+    }
+}
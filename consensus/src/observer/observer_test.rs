@@ -0,0 +1,97 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::observer::{
+    network::{ObserverMsg, ObserverNetworkSender},
+    observer_epoch_manager::ObserverEpochManager,
+};
+use channel::{diem_channel, message_queues::QueueStyle};
+use consensus_notifications::{new_consensus_notifier_listener_pair, ConsensusNotification};
+use diem_crypto::HashValue;
+use diem_types::{
+    block_info::BlockInfo,
+    epoch_state::EpochState,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    validator_signer::ValidatorSigner,
+    validator_verifier::random_validator_verifier,
+};
+use futures::StreamExt;
+use network::{
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    protocols::network::NewNetworkSender,
+};
+use std::{collections::BTreeMap, sync::Arc};
+
+fn commit_decision(
+    signers: &[ValidatorSigner],
+    epoch: u64,
+    version: u64,
+) -> LedgerInfoWithSignatures {
+    let ledger_info = LedgerInfo::new(
+        BlockInfo::new(
+            epoch,
+            version,
+            HashValue::random(),
+            HashValue::random(),
+            version,
+            0,
+            None,
+        ),
+        HashValue::zero(),
+    );
+    let signatures: BTreeMap<_, _> = signers
+        .iter()
+        .map(|signer| (signer.author(), signer.sign(&ledger_info)))
+        .collect();
+    LedgerInfoWithSignatures::new(ledger_info, signatures)
+}
+
+#[tokio::test]
+async fn test_commit_decision_triggers_sync() {
+    let (signers, verifier) = random_validator_verifier(4, None, false);
+    let (network_reqs_tx, _network_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
+    let (connection_reqs_tx, _) = diem_channel::new(QueueStyle::FIFO, 8, None);
+    let network_sender = ObserverNetworkSender::new(
+        PeerManagerRequestSender::new(network_reqs_tx),
+        ConnectionRequestSender::new(connection_reqs_tx),
+    );
+    let (notifier, mut listener) = new_consensus_notifier_listener_pair(1_000);
+    let mut observer = ObserverEpochManager::new(network_sender, Arc::new(notifier), 10);
+
+    // nothing can be verified before the first epoch starts
+    let decision = commit_decision(&signers, 1, 20);
+    assert!(observer
+        .process_message(ObserverMsg::CommitDecision(Box::new(decision.clone())))
+        .is_err());
+
+    observer.start_new_epoch(EpochState { epoch: 1, verifier });
+
+    // commit decisions from another epoch or without a quorum are rejected
+    let wrong_epoch = commit_decision(&signers, 2, 20);
+    assert!(observer
+        .process_message(ObserverMsg::CommitDecision(Box::new(wrong_epoch)))
+        .is_err());
+    let no_quorum = commit_decision(&signers[..1], 1, 20);
+    assert!(observer
+        .process_message(ObserverMsg::CommitDecision(Box::new(no_quorum)))
+        .is_err());
+    assert_eq!(observer.highest_committed_version(), 10);
+
+    // stale commit decisions are ignored
+    let stale = commit_decision(&signers, 1, 5);
+    observer
+        .process_message(ObserverMsg::CommitDecision(Box::new(stale)))
+        .unwrap();
+    assert_eq!(observer.highest_committed_version(), 10);
+
+    observer
+        .process_message(ObserverMsg::CommitDecision(Box::new(decision.clone())))
+        .unwrap();
+    assert_eq!(observer.highest_committed_version(), 20);
+    match listener.select_next_some().await {
+        ConsensusNotification::SyncToTarget(sync_notification) => {
+            assert_eq!(sync_notification.target, decision);
+        }
+        notification => panic!("Expected a sync notification, got {:?}", notification),
+    }
+}
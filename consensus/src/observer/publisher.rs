@@ -0,0 +1,103 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters,
+    network_interface::ConsensusMsg,
+    observer::network::{ObserverMsg, ObserverNetworkEvents, ObserverNetworkSender},
+};
+use consensus_types::vote_msg::VoteMsg;
+use diem_infallible::{Mutex, RwLock};
+use diem_logger::prelude::*;
+use diem_types::{transaction::Version, PeerId};
+use futures::StreamExt;
+use network::{protocols::network::Event, ProtocolId};
+use std::{collections::HashSet, sync::Arc};
+
+/// Publishes the consensus messages of a validator to the fullnodes that subscribed to them.
+/// Cloned handles share the same set of subscribers.
+#[derive(Clone)]
+pub struct ConsensusPublisher {
+    network_sender: ObserverNetworkSender,
+    subscribers: Arc<RwLock<HashSet<PeerId>>>,
+    // Version of the last published commit decision, to avoid sending the same one repeatedly
+    last_published_version: Arc<Mutex<Version>>,
+}
+
+impl ConsensusPublisher {
+    pub fn new(network_sender: ObserverNetworkSender) -> Self {
+        Self {
+            network_sender,
+            subscribers: Arc::new(RwLock::new(HashSet::new())),
+            last_published_version: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    /// Process the subscription requests of observers until the network events end.
+    pub async fn start(self, mut network_events: ObserverNetworkEvents) {
+        while let Some(event) = network_events.next().await {
+            match event {
+                Event::Message(peer_id, ObserverMsg::Subscribe) => {
+                    info!(remote_peer = peer_id, "[ConsensusPublisher] new subscriber");
+                    self.subscribers.write().insert(peer_id);
+                }
+                Event::Message(peer_id, ObserverMsg::Unsubscribe) => {
+                    self.subscribers.write().remove(&peer_id);
+                }
+                Event::LostPeer(metadata) => {
+                    self.subscribers.write().remove(&metadata.remote_peer_id);
+                }
+                Event::Message(peer_id, _) => {
+                    warn!(
+                        remote_peer = peer_id,
+                        "[ConsensusPublisher] unexpected message from observer"
+                    );
+                }
+                _ => (),
+            }
+            counters::CONSENSUS_OBSERVER_SUBSCRIBERS.set(self.subscribers.read().len() as i64);
+        }
+    }
+
+    /// Publish a consensus message broadcast by this validator, proposals also carry the latest
+    /// commit decision.
+    pub fn publish_consensus_msg(&self, msg: &ConsensusMsg) {
+        if let ConsensusMsg::ProposalMsg(proposal) = msg {
+            let commit_decision = proposal.sync_info().highest_ledger_info();
+            let version = commit_decision.ledger_info().version();
+            let is_new_commit = {
+                let mut last_published_version = self.last_published_version.lock();
+                if version > *last_published_version {
+                    *last_published_version = version;
+                    true
+                } else {
+                    false
+                }
+            };
+            self.publish(ObserverMsg::ProposalMsg(proposal.clone()));
+            if is_new_commit {
+                self.publish(ObserverMsg::CommitDecision(Box::new(
+                    commit_decision.clone(),
+                )));
+            }
+        }
+    }
+
+    pub fn publish_vote(&self, vote_msg: &VoteMsg) {
+        self.publish(ObserverMsg::VoteMsg(Box::new(vote_msg.clone())));
+    }
+
+    fn publish(&self, msg: ObserverMsg) {
+        let subscribers: Vec<_> = self.subscribers.read().iter().cloned().collect();
+        if subscribers.is_empty() {
+            return;
+        }
+        if let Err(e) = self.network_sender.clone().send_to_many(
+            subscribers.into_iter(),
+            ProtocolId::ConsensusObserverDirectSend,
+            msg,
+        ) {
+            warn!(error = ?e, "[ConsensusPublisher] failed to publish message");
+        }
+    }
+}
@@ -8,17 +8,18 @@
 //! Votes are automatically dropped when the structure goes out of scope.
 
 use consensus_types::{
-    common::Author, quorum_cert::QuorumCert, timeout_2chain::TwoChainTimeoutCertificate,
-    timeout_certificate::TimeoutCertificate, vote::Vote,
+    aggregate_signature::PartialSignatures, common::Author, quorum_cert::QuorumCert,
+    timeout_2chain::TwoChainTimeoutCertificate, timeout_certificate::TimeoutCertificate,
+    vote::Vote,
 };
 use diem_crypto::{hash::CryptoHash, HashValue};
 use diem_logger::prelude::*;
 use diem_types::{
-    ledger_info::LedgerInfoWithSignatures,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     validator_verifier::{ValidatorVerifier, VerifyError},
 };
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt,
     sync::Arc,
 };
@@ -27,7 +28,7 @@ use std::{
 /// as the Error part of the result.
 #[derive(Debug, PartialEq, Eq)]
 pub enum VoteReceptionResult {
-    /// The vote has been added but QC has not been formed yet. Return the amount of voting power
+    /// The vote has been added but no new QC has been formed. Return the amount of voting power
     /// the given (proposal, execution) pair.
     VoteAdded(u64),
     /// The very same vote message has been processed in past.
@@ -48,10 +49,11 @@ pub enum VoteReceptionResult {
 
 /// A PendingVotes structure keep track of votes
 pub struct PendingVotes {
-    /// Maps LedgerInfo digest to associated signatures (contained in a partial LedgerInfoWithSignatures).
+    /// Maps LedgerInfo digest to the LedgerInfo and its signatures aggregated so far.
     /// This might keep multiple LedgerInfos for the current round: either due to different proposals (byzantine behavior)
     /// or due to different NIL proposals (clients can have a different view of what block to extend).
-    li_digest_to_votes: HashMap<HashValue /* LedgerInfo digest */, LedgerInfoWithSignatures>,
+    li_digest_to_votes:
+        HashMap<HashValue /* LedgerInfo digest */, (LedgerInfo, PartialSignatures)>,
     /// Tracks all the signatures of the votes for the given round. In case we succeed to
     /// aggregate 2f+1 signatures a TimeoutCertificate is formed.
     maybe_partial_tc: Option<TimeoutCertificate>,
@@ -59,6 +61,9 @@ pub struct PendingVotes {
     maybe_partial_2chain_tc: Option<TwoChainTimeoutCertificate>,
     /// Map of Author to vote. This is useful to discard multiple votes.
     author_to_vote: HashMap<Author, Vote>,
+    /// LedgerInfo digests that already got a QC: the votes received after the quorum (e.g., when
+    /// votes are broadcast to all validators) don't form the same QC again.
+    certified_li_digests: HashSet<HashValue>,
 }
 
 impl PendingVotes {
@@ -69,6 +74,7 @@ impl PendingVotes {
             maybe_partial_tc: None,
             maybe_partial_2chain_tc: None,
             author_to_vote: HashMap::new(),
+            certified_li_digests: HashSet::new(),
         }
     }
 
@@ -118,38 +124,41 @@ impl PendingVotes {
         // 3. Let's check if we can create a QC
         //
 
-        // obtain the ledger info with signatures associated to the vote's ledger info
-        let li_with_sig = self.li_digest_to_votes.entry(li_digest).or_insert_with(|| {
-            // if the ledger info with signatures doesn't exist yet, create it
-            LedgerInfoWithSignatures::new(vote.ledger_info().clone(), BTreeMap::new())
-        });
-
-        // add this vote to the ledger info with signatures
-        li_with_sig.add_signature(vote.author(), vote.signature().clone());
-
-        // check if we have enough signatures to create a QC
-        let voting_power =
-            match validator_verifier.check_voting_power(li_with_sig.signatures().keys()) {
-                // a quorum of signature was reached, a new QC is formed
-                Ok(_) => {
-                    return VoteReceptionResult::NewQuorumCertificate(Arc::new(QuorumCert::new(
-                        vote.vote_data().clone(),
-                        li_with_sig.clone(),
-                    )));
-                }
-
-                // not enough votes
-                Err(VerifyError::TooLittleVotingPower { voting_power, .. }) => voting_power,
+        // obtain the signatures aggregated so far for the vote's ledger info
+        let (ledger_info, partial_sigs) =
+            self.li_digest_to_votes.entry(li_digest).or_insert_with(|| {
+                // if no vote was received for this ledger info yet, start aggregating
+                (vote.ledger_info().clone(), PartialSignatures::new())
+            });
+
+        // aggregate this vote, the voting power is accumulated incrementally
+        let voting_power = match partial_sigs.add_signature(
+            vote.author(),
+            vote.signature().clone(),
+            validator_verifier,
+        ) {
+            Ok(voting_power) => voting_power,
+            Err(error) => {
+                error!(
+                    "MUST_FIX: vote received could not be added: {}, vote: {}",
+                    error, vote
+                );
+                return VoteReceptionResult::ErrorAddingVote(error);
+            }
+        };
 
-                // error
-                Err(error) => {
-                    error!(
-                        "MUST_FIX: vote received could not be added: {}, vote: {}",
-                        error, vote
-                    );
-                    return VoteReceptionResult::ErrorAddingVote(error);
-                }
-            };
+        // check if we have enough signatures to create a QC (for the first time)
+        if partial_sigs.has_quorum(validator_verifier)
+            && self.certified_li_digests.insert(li_digest)
+        {
+            return VoteReceptionResult::NewQuorumCertificate(Arc::new(QuorumCert::new(
+                vote.vote_data().clone(),
+                LedgerInfoWithSignatures::new(
+                    ledger_info.clone(),
+                    partial_sigs.signatures().clone(),
+                ),
+            )));
+        }
 
         //
         // 4. We couldn't form a QC, let's check if we can create a TC
@@ -231,7 +240,12 @@ impl fmt::Display for PendingVotes {
         let votes = self
             .li_digest_to_votes
             .iter()
-            .map(|(li_digest, li)| (li_digest, li.signatures().keys().collect::<Vec<_>>()))
+            .map(|(li_digest, (_, partial_sigs))| {
+                (
+                    li_digest,
+                    partial_sigs.signatures().keys().collect::<Vec<_>>(),
+                )
+            })
             .collect::<BTreeMap<_, _>>();
 
         // collect timeout votes
@@ -338,7 +352,12 @@ mod tests {
         );
 
         // two votes for the ledger info -> NewQuorumCertificate
-        let vote_data_2_author_2 = Vote::new(vote_data_2, signers[2].author(), li2, &signers[2]);
+        let vote_data_2_author_2 = Vote::new(
+            vote_data_2.clone(),
+            signers[2].author(),
+            li2.clone(),
+            &signers[2],
+        );
         match pending_votes.insert_vote(&vote_data_2_author_2, &validator) {
             VoteReceptionResult::NewQuorumCertificate(qc) => {
                 assert!(validator
@@ -349,6 +368,13 @@ mod tests {
                 panic!("No QC formed.");
             }
         };
+
+        // votes after the quorum don't form the QC again
+        let vote_data_2_author_3 = Vote::new(vote_data_2, signers[3].author(), li2, &signers[3]);
+        assert_eq!(
+            pending_votes.insert_vote(&vote_data_2_author_3, &validator),
+            VoteReceptionResult::VoteAdded(3)
+        );
     }
 
     #[test]
@@ -1,7 +1,11 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{consensusdb::ConsensusDB, epoch_manager::LivenessStorageData, error::DbError};
+use crate::{
+    consensusdb::{ConsensusDB, ConsensusDBPruner, PersistedOrderedBlocks},
+    epoch_manager::LivenessStorageData,
+    error::DbError,
+};
 use anyhow::{format_err, Context, Result};
 use consensus_types::{
     block::Block, common::Author, quorum_cert::QuorumCert,
@@ -34,6 +38,10 @@ pub trait PersistentLivenessStorage: Send + Sync {
     /// Delete the corresponding blocks and quorum certs atomically.
     fn prune_tree(&self, block_ids: Vec<HashValue>) -> Result<()>;
 
+    /// Notified of every commit, deletes the blocks and quorum certs that are too old to be of any
+    /// use (whether they are still in the tree or not).
+    fn prune_stale(&self, committed_epoch: u64, committed_round: Round) -> Result<()>;
+
     /// Persist consensus' state
     fn save_vote(&self, vote: &Vote) -> Result<()>;
 
@@ -54,6 +62,17 @@ pub trait PersistentLivenessStorage: Send + Sync {
         highest_timeout_cert: &TwoChainTimeoutCertificate,
     ) -> Result<()>;
 
+    /// Persist ordered but not yet committed blocks of the decoupled execution pipeline, along
+    /// with the commit votes aggregated so far.
+    fn save_ordered_blocks(&self, ordered_blocks: PersistedOrderedBlocks) -> Result<()>;
+
+    /// Delete the ordered blocks identified by the (epoch, round) of their ordered proof.
+    fn prune_ordered_blocks(&self, keys: Vec<(u64, Round)>) -> Result<()>;
+
+    /// Retrieve the persisted ordered blocks in commit order, used to restore the buffer
+    /// manager after a restart.
+    fn get_ordered_blocks(&self) -> Result<Vec<PersistedOrderedBlocks>>;
+
     /// Retrieve a epoch change proof for SafetyRules so it can instantiate its
     /// ValidatorVerifier.
     fn retrieve_epoch_change_proof(&self, version: u64) -> Result<EpochChangeProof>;
@@ -313,12 +332,21 @@ impl RecoveryData {
 pub struct StorageWriteProxy {
     db: Arc<ConsensusDB>,
     diem_db: Arc<dyn DbReader>,
+    pruner: Option<ConsensusDBPruner>,
 }
 
 impl StorageWriteProxy {
     pub fn new(config: &NodeConfig, diem_db: Arc<dyn DbReader>) -> Self {
         let db = Arc::new(ConsensusDB::new(config.storage.dir()));
-        StorageWriteProxy { db, diem_db }
+        let pruner = config
+            .consensus
+            .consensusdb_prune_window
+            .map(|window| ConsensusDBPruner::new(db.clone(), window));
+        StorageWriteProxy {
+            db,
+            diem_db,
+            pruner,
+        }
     }
 }
 
@@ -337,6 +365,13 @@ impl PersistentLivenessStorage for StorageWriteProxy {
         Ok(())
     }
 
+    fn prune_stale(&self, committed_epoch: u64, committed_round: Round) -> Result<()> {
+        if let Some(pruner) = &self.pruner {
+            pruner.prune(committed_epoch, committed_round)?;
+        }
+        Ok(())
+    }
+
     fn save_vote(&self, vote: &Vote) -> Result<()> {
         Ok(self.db.save_vote(bcs::to_bytes(vote)?)?)
     }
@@ -484,6 +519,21 @@ impl PersistentLivenessStorage for StorageWriteProxy {
             .save_highest_2chain_timeout_certificate(bcs::to_bytes(highest_timeout_cert)?)?)
     }
 
+    fn save_ordered_blocks(&self, ordered_blocks: PersistedOrderedBlocks) -> Result<()> {
+        Ok(self.db.save_ordered_blocks(vec![ordered_blocks])?)
+    }
+
+    fn prune_ordered_blocks(&self, keys: Vec<(u64, Round)>) -> Result<()> {
+        if !keys.is_empty() {
+            self.db.delete_ordered_blocks(keys)?;
+        }
+        Ok(())
+    }
+
+    fn get_ordered_blocks(&self) -> Result<Vec<PersistedOrderedBlocks>> {
+        Ok(self.db.get_ordered_blocks()?)
+    }
+
     fn retrieve_epoch_change_proof(&self, version: u64) -> Result<EpochChangeProof> {
         let (_, proofs, _) = self
             .diem_db
@@ -4,9 +4,10 @@
 use crate::{
     block_storage::{
         tracing::{observe_block, BlockStage},
-        BlockReader, BlockRetriever, BlockStore,
+        BlockReader, BlockRetrievalStreamServer, BlockRetriever, BlockStore,
     },
     counters,
+    dedup_cache::DedupCache,
     error::VerifyError,
     liveness::{
         proposal_generator::ProposalGenerator,
@@ -20,13 +21,22 @@ use crate::{
     pending_votes::VoteReceptionResult,
     persistent_liveness_storage::{PersistentLivenessStorage, RecoveryData},
     state_replication::{StateComputer, TxnManager},
+    state_snapshot::ConsensusStateSnapshot,
 };
 use anyhow::{bail, ensure, Context, Result};
 use consensus_types::{
     block::Block,
-    block_retrieval::{BlockRetrievalResponse, BlockRetrievalStatus},
+    block_data::BlockData,
+    block_retrieval::{
+        BlockRetrievalResponse, BlockRetrievalStatus, BlockRetrievalStreamAck,
+        BlockRetrievalStreamChunk, BlockRetrievalStreamRequest,
+    },
+    broadcast_ack::BroadcastKind,
     common::{Author, Round},
-    experimental::{commit_decision::CommitDecision, commit_vote::CommitVote},
+    experimental::{
+        commit_decision::CommitDecision, commit_vote::CommitVote,
+        commit_vote_batch::CommitVoteBatch,
+    },
     proposal_msg::ProposalMsg,
     quorum_cert::QuorumCert,
     sync_info::SyncInfo,
@@ -36,10 +46,12 @@ use consensus_types::{
     vote_msg::VoteMsg,
 };
 use core::sync::atomic::Ordering;
+use diem_config::config::BlockRetrievalStreamConfig;
 use diem_infallible::{checked, Mutex};
 use diem_logger::prelude::*;
 use diem_types::{
-    epoch_state::EpochState, on_chain_config::OnChainConsensusConfig,
+    epoch_state::EpochState,
+    on_chain_config::{OnChainConsensusConfig, VoteRecipients},
     validator_verifier::ValidatorVerifier,
 };
 use fail::fail_point;
@@ -60,6 +72,7 @@ pub enum UnverifiedEvent {
     SyncInfo(Box<SyncInfo>),
     CommitVote(Box<CommitVote>),
     CommitDecision(Box<CommitDecision>),
+    CommitVoteBatch(Box<CommitVoteBatch>),
 }
 
 impl UnverifiedEvent {
@@ -85,6 +98,10 @@ impl UnverifiedEvent {
                 cd.verify(validator)?;
                 VerifiedEvent::CommitDecision(cd)
             }
+            UnverifiedEvent::CommitVoteBatch(batch) => {
+                batch.verify(validator)?;
+                VerifiedEvent::CommitVoteBatch(batch)
+            }
         })
     }
 
@@ -95,6 +112,7 @@ impl UnverifiedEvent {
             UnverifiedEvent::SyncInfo(s) => s.epoch(),
             UnverifiedEvent::CommitVote(cv) => cv.epoch(),
             UnverifiedEvent::CommitDecision(cd) => cd.epoch(),
+            UnverifiedEvent::CommitVoteBatch(batch) => batch.epoch(),
         }
     }
 }
@@ -107,6 +125,7 @@ impl From<ConsensusMsg> for UnverifiedEvent {
             ConsensusMsg::SyncInfo(m) => UnverifiedEvent::SyncInfo(m),
             ConsensusMsg::CommitVoteMsg(m) => UnverifiedEvent::CommitVote(m),
             ConsensusMsg::CommitDecisionMsg(m) => UnverifiedEvent::CommitDecision(m),
+            ConsensusMsg::CommitVoteBatchMsg(m) => UnverifiedEvent::CommitVoteBatch(m),
             _ => unreachable!("Unexpected conversion"),
         }
     }
@@ -119,6 +138,7 @@ pub enum VerifiedEvent {
     SyncInfo(Box<SyncInfo>),
     CommitVote(Box<CommitVote>),
     CommitDecision(Box<CommitDecision>),
+    CommitVoteBatch(Box<CommitVoteBatch>),
 }
 
 #[cfg(test)]
@@ -228,6 +248,14 @@ pub struct RoundManager {
     decoupled_execution: bool,
     back_pressure_limit: u64,
     onchain_config: OnChainConsensusConfig,
+    block_retrieval_streams: BlockRetrievalStreamServer,
+    vote_recipients: VoteRecipients,
+    // Propose for the next round as soon as its QC is aggregated, before inserting the QC
+    optimistic_proposal: bool,
+    // The new round event of the round proposed optimistically doesn't propose again
+    last_optimistic_proposal_round: Round,
+    // Proposals and votes received for the current and future rounds
+    seen_messages: DedupCache,
 }
 
 impl RoundManager {
@@ -267,6 +295,13 @@ impl RoundManager {
             decoupled_execution: false,
             back_pressure_limit: 1, // arbitrary dummy value
             onchain_config,
+            block_retrieval_streams: BlockRetrievalStreamServer::new(
+                BlockRetrievalStreamConfig::default(),
+            ),
+            vote_recipients: VoteRecipients::NextProposer,
+            optimistic_proposal: false,
+            last_optimistic_proposal_round: 0,
+            seen_messages: DedupCache::new(),
         }
     }
 
@@ -300,7 +335,66 @@ impl RoundManager {
             decoupled_execution: true,
             back_pressure_limit,
             onchain_config,
+            block_retrieval_streams: BlockRetrievalStreamServer::new(
+                BlockRetrievalStreamConfig::default(),
+            ),
+            vote_recipients: VoteRecipients::NextProposer,
+            optimistic_proposal: false,
+            last_optimistic_proposal_round: 0,
+            seen_messages: DedupCache::new(),
+        }
+    }
+
+    /// Limits of the block retrieval streams served to the peers.
+    pub fn set_block_retrieval_stream_config(&mut self, config: BlockRetrievalStreamConfig) {
+        self.block_retrieval_streams = BlockRetrievalStreamServer::new(config);
+    }
+
+    /// Validators the votes for proposals are sent to, all the validators of the epoch must
+    /// agree on them.
+    pub fn set_vote_recipients(&mut self, vote_recipients: VoteRecipients) {
+        self.vote_recipients = vote_recipients;
+    }
+
+    /// Propose as soon as the QC of the previous round is aggregated (if this validator is the
+    /// proposer), without waiting for the QC to be inserted in the block store.
+    pub fn set_optimistic_proposal(&mut self, optimistic_proposal: bool) {
+        self.optimistic_proposal = optimistic_proposal;
+    }
+
+    /// Returns true if the same proposal or vote was already verified for the current or a
+    /// future round, so that it can be dropped before verifying it again.
+    pub fn is_duplicate(&self, event: &UnverifiedEvent) -> bool {
+        self.seen_messages.contains(event)
+    }
+
+    /// Records a verified proposal or vote, for its duplicates to be dropped.
+    pub fn record_verified(&mut self, event: &VerifiedEvent) {
+        self.seen_messages.insert(event);
+    }
+
+    /// The validators that aggregate the votes for the proposal of the given round.
+    fn vote_recipients(&self, round: Round) -> Vec<Author> {
+        let num_next_proposers = match self.vote_recipients {
+            VoteRecipients::NextProposer => 1,
+            VoteRecipients::NextProposers(count) => count.max(1),
+            VoteRecipients::Broadcast => {
+                return self
+                    .epoch_state
+                    .verifier
+                    .get_ordered_account_addresses_iter()
+                    .collect()
+            }
+        };
+        let mut recipients = vec![];
+        // there are no rounds past the last one
+        for next_round in (1..=num_next_proposers).map_while(|offset| round.checked_add(offset)) {
+            let proposer = self.proposer_election.get_valid_proposer(next_round);
+            if !recipients.contains(&proposer) {
+                recipients.push(proposer);
+            }
         }
+        recipients
     }
 
     fn two_chain(&self) -> bool {
@@ -328,6 +422,7 @@ impl RoundManager {
         new_round_event: NewRoundEvent,
     ) -> anyhow::Result<()> {
         counters::CURRENT_ROUND.set(new_round_event.round as i64);
+        self.seen_messages.evict_below(new_round_event.round);
         counters::ROUND_TIMEOUT_MS.set(new_round_event.timeout.as_millis() as i64);
         match new_round_event.reason {
             NewRoundReason::QCReady => {
@@ -341,9 +436,10 @@ impl RoundManager {
             self.new_log(LogEvent::NewRound),
             reason = new_round_event.reason
         );
-        if self
-            .proposer_election
-            .is_valid_proposer(self.proposal_generator.author(), new_round_event.round)
+        if new_round_event.round != self.last_optimistic_proposal_round
+            && self
+                .proposer_election
+                .is_valid_proposer(self.proposal_generator.author(), new_round_event.round)
         {
             let proposal_msg = Box::new(self.generate_proposal(new_round_event).await?);
             let mut network = self.network.clone();
@@ -369,15 +465,83 @@ impl RoundManager {
             .proposal_generator
             .generate_proposal(new_round_event.round)
             .await?;
+        self.sign_proposal(proposal, self.block_store.sync_info())
+    }
+
+    fn sign_proposal(
+        &mut self,
+        proposal: BlockData,
+        sync_info: SyncInfo,
+    ) -> anyhow::Result<ProposalMsg> {
+        let proposal = if self.proposer_election.proposals_carry_vrf_proofs() {
+            // Without a proof, the proposer of the next round falls back to the rotation
+            let vrf_proof = self
+                .safety_rules
+                .lock()
+                .prove_proposer_vrf(proposal.round());
+            match vrf_proof {
+                Ok(vrf_proof) => proposal.with_vrf_proof(vrf_proof),
+                Err(e) => {
+                    warn!(
+                        self.new_log(LogEvent::Propose),
+                        error = ?e, "Proposing without a VRF proof",
+                    );
+                    proposal
+                }
+            }
+        } else {
+            proposal
+        };
         let signature = self.safety_rules.lock().sign_proposal(&proposal)?;
         let signed_proposal =
             Block::new_proposal_from_block_data_and_signature(proposal, signature);
         observe_block(signed_proposal.timestamp_usecs(), BlockStage::SIGNED);
         debug!(self.new_log(LogEvent::Propose), "{}", signed_proposal);
-        Ok(ProposalMsg::new(
-            signed_proposal,
-            self.block_store.sync_info(),
-        ))
+        Ok(ProposalMsg::new(signed_proposal, sync_info))
+    }
+
+    /// Leader of the next round:
+    ///
+    /// Proposes right away once the QC of the current round is aggregated, extending the QC before
+    /// it's inserted in the block store: inserting the QC persists it and might commit blocks,
+    /// which would otherwise delay the proposal. The certified block has to be in the block store
+    /// already, and the proposal carries a SyncInfo with the new QC as the highest QC.
+    /// SafetyRules signs the proposal under the usual rule (the QC is verified and the round has
+    /// to be higher than the last voted round), and the new round event of the round won't
+    /// propose again.
+    async fn process_optimistic_proposal(&mut self, qc: Arc<QuorumCert>) -> anyhow::Result<()> {
+        let round = qc.certified_block().round() + 1;
+        if qc.certified_block().round() != self.round_state.current_round()
+            || !self
+                .proposer_election
+                .is_valid_proposer(self.proposal_generator.author(), round)
+            || self
+                .block_store
+                .get_block(qc.certified_block().id())
+                .is_none()
+        {
+            return Ok(());
+        }
+        let proposal = self
+            .proposal_generator
+            .generate_optimistic_proposal(round, qc.clone())
+            .await?;
+        let sync_info = self.block_store.sync_info();
+        let sync_info = SyncInfo::new_decoupled(
+            qc.as_ref().clone(),
+            sync_info.highest_ordered_cert().clone(),
+            Some(sync_info.highest_ledger_info().clone()),
+            sync_info.highest_timeout_certificate().cloned(),
+            sync_info.highest_2chain_timeout_cert().cloned(),
+        );
+        let proposal_msg = self.sign_proposal(proposal, sync_info)?;
+        self.last_optimistic_proposal_round = round;
+        self.network
+            .broadcast(ConsensusMsg::ProposalMsg(Box::new(proposal_msg)))
+            .await;
+        counters::PROPOSALS_COUNT.inc();
+        counters::OPTIMISTIC_PROPOSALS_COUNT.inc();
+        Ok(())
     }
 
     /// Process the proposal message:
@@ -545,6 +709,7 @@ impl RoundManager {
         if !self.round_state.process_local_timeout(round) {
             return Ok(());
         }
+        self.proposer_election.process_local_timeout(round);
 
         if self.sync_only() {
             self.network
@@ -600,7 +765,14 @@ impl RoundManager {
             timeout_vote,
             self.block_store.sync_info(),
         )));
-        self.network.broadcast(timeout_vote_msg).await;
+        self.network
+            .reliable_broadcast(
+                timeout_vote_msg,
+                BroadcastKind::Timeout,
+                self.epoch_state.epoch,
+                round,
+            )
+            .await;
         error!(
             round = round,
             remote_peer = self.proposer_election.get_valid_proposer(round),
@@ -661,14 +833,12 @@ impl RoundManager {
             .await
             .context("[RoundManager] Process proposal")?;
 
-        let recipients = self
-            .proposer_election
-            .get_valid_proposer(proposal_round + 1);
+        let recipients = self.vote_recipients(proposal_round);
         debug!(self.new_log(LogEvent::Vote).remote_peer(author), "{}", vote);
 
         self.round_state.record_vote(vote.clone());
         let vote_msg = VoteMsg::new(vote, self.block_store.sync_info());
-        self.network.send_vote(vote_msg, vec![recipients]).await;
+        self.network.send_vote(vote_msg, recipients).await;
         Ok(())
     }
 
@@ -781,14 +951,13 @@ impl RoundManager {
         );
 
         if !vote.is_timeout() {
-            // Unlike timeout votes regular votes are sent to the leaders of the next round only.
-            let next_round = round + 1;
+            // Unlike timeout votes regular votes are sent to the vote recipients only.
             ensure!(
-                self.proposer_election
-                    .is_valid_proposer(self.proposal_generator.author(), next_round),
-                "[RoundManager] Received {}, but I am not a valid proposer for round {}, ignore.",
+                self.vote_recipients(round)
+                    .contains(&self.proposal_generator.author()),
+                "[RoundManager] Received {}, but I am not a vote recipient for round {}, ignore.",
                 vote,
-                next_round
+                round
             );
         }
         let block_id = vote.vote_data().proposed().id();
@@ -825,6 +994,14 @@ impl RoundManager {
             qc.certified_block().timestamp_usecs(),
             BlockStage::QC_AGGREGATED,
         );
+        if self.optimistic_proposal {
+            if let Err(e) = self.process_optimistic_proposal(qc.clone()).await {
+                warn!(
+                    self.new_log(LogEvent::Propose),
+                    error = ?e, "Failed to propose optimistically",
+                );
+            }
+        }
         let result = self
             .block_store
             .insert_quorum_cert(&qc, &mut self.create_block_retriever(preferred_peer))
@@ -896,6 +1073,49 @@ impl RoundManager {
             .context("[RoundManager] Failed to process block retrieval")
     }
 
+    /// Starts streaming the requested blocks to the peer.
+    pub async fn process_block_retrieval_stream_request(
+        &mut self,
+        peer: Author,
+        request: BlockRetrievalStreamRequest,
+    ) -> anyhow::Result<()> {
+        let chunks = self
+            .block_retrieval_streams
+            .process_request(peer, request, self.block_store.as_ref())
+            .context("[RoundManager] Failed to process block retrieval stream request")?;
+        self.send_block_retrieval_stream_chunks(peer, chunks).await;
+        Ok(())
+    }
+
+    /// Sends the next chunks allowed by the acknowledgement of the peer.
+    pub async fn process_block_retrieval_stream_ack(
+        &mut self,
+        peer: Author,
+        ack: BlockRetrievalStreamAck,
+    ) -> anyhow::Result<()> {
+        let chunks = self
+            .block_retrieval_streams
+            .process_ack(peer, ack, self.block_store.as_ref())
+            .context("[RoundManager] Failed to process block retrieval stream ack")?;
+        self.send_block_retrieval_stream_chunks(peer, chunks).await;
+        Ok(())
+    }
+
+    async fn send_block_retrieval_stream_chunks(
+        &self,
+        peer: Author,
+        chunks: Vec<BlockRetrievalStreamChunk>,
+    ) {
+        for chunk in chunks {
+            self.network
+                .send(
+                    ConsensusMsg::BlockRetrievalStreamChunk(Box::new(chunk)),
+                    vec![peer],
+                )
+                .await;
+        }
+    }
+
     /// To jump start new round with the current certificates we have.
     pub async fn start(&mut self, last_vote_sent: Option<Vote>) {
         let new_round_event = self
@@ -929,6 +1149,22 @@ impl RoundManager {
         &self.round_state
     }
 
+    pub fn state_snapshot(&self) -> ConsensusStateSnapshot {
+        ConsensusStateSnapshot::Normal {
+            epoch: self.epoch_state.epoch,
+            round: self.round_state.current_round(),
+            voted: self.round_state.vote_sent().is_some(),
+            highest_quorum_cert_round: self
+                .block_store
+                .highest_quorum_cert()
+                .certified_block()
+                .round(),
+            highest_ordered_round: self.block_store.ordered_root().round(),
+            highest_committed_round: self.block_store.commit_root().round(),
+            sync_only: self.sync_only,
+        }
+    }
+
     fn new_log(&self, event: LogEvent) -> LogSchema {
         LogSchema::new(event)
             .round(self.round_state.current_round())
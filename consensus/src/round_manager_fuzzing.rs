@@ -144,6 +144,7 @@ fn create_node_for_fuzzing() -> RoundManager {
         Arc::new(MockTransactionManager::new(None)),
         time_service,
         1,
+        10,
     );
 
     //
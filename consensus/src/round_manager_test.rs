@@ -41,7 +41,7 @@ use diem_secure_storage::Storage;
 use diem_types::{
     epoch_state::EpochState,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
-    on_chain_config::OnChainConsensusConfig,
+    on_chain_config::{OnChainConsensusConfig, VoteRecipients},
     validator_signer::ValidatorSigner,
     validator_verifier::random_validator_verifier,
     waypoint::Waypoint,
@@ -202,6 +202,7 @@ impl NodeSetup {
             Arc::new(MockTransactionManager::new(None)),
             time_service.clone(),
             1,
+            10,
         );
 
         let round_state = Self::create_round_state(time_service);
@@ -327,6 +328,49 @@ fn new_round_on_quorum_cert() {
     });
 }
 
+#[test]
+fn optimistic_proposal_on_quorum_cert() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 1);
+    let node = &mut nodes[0];
+    node.round_manager.set_optimistic_proposal(true);
+    timed_block_on(&mut runtime, async {
+        let proposal_msg = node.next_proposal().await;
+        let b1_id = proposal_msg.proposal().id();
+        node.round_manager
+            .process_proposal_msg(proposal_msg)
+            .await
+            .unwrap();
+        let vote_msg = node.next_vote().await;
+        // the new round event of round 2 doesn't propose a second time
+        node.round_manager.process_vote_msg(vote_msg).await.unwrap();
+        assert_eq!(node.round_manager.round_state().current_round(), 2);
+
+        let proposal_msg = node.next_proposal().await;
+        let proposal = proposal_msg.proposal();
+        assert_eq!(proposal.round(), 2);
+        assert_eq!(proposal.parent_id(), b1_id);
+        assert_eq!(
+            proposal_msg
+                .sync_info()
+                .highest_quorum_cert()
+                .certified_block()
+                .id(),
+            b1_id
+        );
+        proposal_msg.verify_well_formed().unwrap();
+        node.round_manager
+            .process_proposal_msg(proposal_msg)
+            .await
+            .unwrap();
+        assert_eq!(
+            node.next_vote().await.vote().vote_data().proposed().round(),
+            2
+        );
+    });
+}
+
 #[test]
 /// If the proposal is valid, a vote should be sent
 fn vote_on_successful_proposal() {
@@ -356,6 +400,58 @@ fn vote_on_successful_proposal() {
     });
 }
 
+#[test]
+/// The votes for the last rounds go to the proposers of the rounds that exist
+fn vote_recipients_near_last_round() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 1);
+    let node = &mut nodes[0];
+    node.round_manager
+        .set_vote_recipients(VoteRecipients::NextProposers(3));
+
+    assert_eq!(
+        node.round_manager.vote_recipients(1),
+        vec![node.signer.author()]
+    );
+    assert_eq!(node.round_manager.vote_recipients(u64::MAX - 1).len(), 1);
+    assert!(node.round_manager.vote_recipients(u64::MAX).is_empty());
+}
+
+#[test]
+/// With broadcast votes, every validator gets the vote and can aggregate the QC
+fn vote_broadcast_on_successful_proposal() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 2);
+    runtime.spawn(playground.start());
+    let mut voter = nodes.pop().unwrap();
+    let mut proposer = nodes.pop().unwrap();
+    voter
+        .round_manager
+        .set_vote_recipients(VoteRecipients::Broadcast);
+
+    timed_block_on(&mut runtime, async {
+        proposer.next_proposal().await;
+        let proposal_msg = voter.next_proposal().await;
+        voter
+            .round_manager
+            .process_proposal_msg(proposal_msg)
+            .await
+            .unwrap();
+
+        // the vote is sent to the voter itself as well as to the proposer
+        let vote_msg = voter.next_vote().await;
+        assert_eq!(vote_msg.vote().author(), voter.signer.author());
+        assert_eq!(proposer.next_vote().await, vote_msg);
+        voter
+            .round_manager
+            .process_vote_msg(vote_msg)
+            .await
+            .unwrap();
+    });
+}
+
 #[test]
 /// If the proposal does not pass voting rules,
 /// No votes are sent, but the block is still added to the block tree.
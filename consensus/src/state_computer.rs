@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    commit_notification_bus::{CommitNotification, CommitNotificationBus},
     error::StateSyncError,
     state_replication::{StateComputer, StateComputerCommitCallBackType},
 };
@@ -22,16 +23,19 @@ use std::{boxed::Box, sync::Arc};
 pub struct ExecutionProxy {
     execution_correctness_client: Box<dyn ExecutionCorrectness + Send + Sync>,
     state_sync_notifier: Box<dyn ConsensusNotificationSender>,
+    commit_notification_bus: CommitNotificationBus,
 }
 
 impl ExecutionProxy {
     pub fn new(
         execution_correctness_client: Box<dyn ExecutionCorrectness + Send + Sync>,
         state_sync_notifier: Box<dyn ConsensusNotificationSender>,
+        commit_notification_bus: CommitNotificationBus,
     ) -> Self {
         Self {
             execution_correctness_client,
             state_sync_notifier,
+            commit_notification_bus,
         }
     }
 }
@@ -84,9 +88,21 @@ impl StateComputer for ExecutionProxy {
         monitor!(
             "commit_block",
             self.execution_correctness_client
-                .commit_blocks(block_ids, finality_proof.clone())?
+                .commit_blocks(block_ids.clone(), finality_proof.clone())?
         );
 
+        // the transactions are only copied for the bus if anything listens to it
+        let notification = if self.commit_notification_bus.has_subscribers() {
+            Some(CommitNotification {
+                block_ids,
+                ledger_info: finality_proof.clone(),
+                transactions: txns.clone(),
+                reconfig_events: reconfig_events.clone(),
+            })
+        } else {
+            None
+        };
+
         if let Err(e) = monitor!(
             "notify_state_sync",
             self.state_sync_notifier
@@ -95,6 +111,9 @@ impl StateComputer for ExecutionProxy {
         ) {
             error!(error = ?e, "Failed to notify state synchronizer");
         }
+        if let Some(notification) = notification {
+            self.commit_notification_bus.publish(notification);
+        }
 
         callback(blocks, finality_proof);
 
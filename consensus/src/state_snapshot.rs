@@ -0,0 +1,58 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use consensus_types::common::Round;
+use diem_infallible::RwLock;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// What consensus is doing at one point in time, for the operators of the node.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ConsensusStateSnapshot {
+    /// Consensus fetches the blocks of the epoch from its peers before it can participate.
+    Recovery {
+        /// Current epoch
+        epoch: u64,
+    },
+    /// Consensus participates in the rounds of the epoch.
+    Normal {
+        /// Current epoch
+        epoch: u64,
+        /// Current round
+        round: Round,
+        /// Whether this validator has voted in the current round
+        voted: bool,
+        /// Round of the highest certified block
+        highest_quorum_cert_round: Round,
+        /// Round of the highest ordered block
+        highest_ordered_round: Round,
+        /// Round of the highest committed block
+        highest_committed_round: Round,
+        /// Whether this validator only syncs, without proposing nor voting
+        sync_only: bool,
+    },
+}
+
+/// The latest snapshot of the state of consensus, which consensus refreshes after every event it
+/// processes, for any number of readers (e.g., the admin service of the node).
+#[derive(Clone, Default)]
+pub struct SharedConsensusState {
+    snapshot: Arc<RwLock<Option<ConsensusStateSnapshot>>>,
+}
+
+impl SharedConsensusState {
+    /// Creates a shared state without any snapshot, until consensus starts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the latest snapshot, if consensus started.
+    pub fn get(&self) -> Option<ConsensusStateSnapshot> {
+        self.snapshot.read().clone()
+    }
+
+    pub(crate) fn update(&self, snapshot: ConsensusStateSnapshot) {
+        *self.snapshot.write() = Some(snapshot);
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    consensusdb::PersistedOrderedBlocks,
     epoch_manager::LivenessStorageData,
     persistent_liveness_storage::{
         LedgerRecoveryData, PersistentLivenessStorage, RecoveryData, RootMetadata,
@@ -9,8 +10,9 @@ use crate::{
 };
 use anyhow::Result;
 use consensus_types::{
-    block::Block, quorum_cert::QuorumCert, timeout_2chain::TwoChainTimeoutCertificate,
-    timeout_certificate::TimeoutCertificate, vote::Vote,
+    block::Block, common::Round, quorum_cert::QuorumCert,
+    timeout_2chain::TwoChainTimeoutCertificate, timeout_certificate::TimeoutCertificate,
+    vote::Vote,
 };
 use diem_crypto::HashValue;
 use diem_infallible::Mutex;
@@ -31,6 +33,7 @@ pub struct MockSharedStorage {
     pub qc: Mutex<HashMap<HashValue, QuorumCert>>,
     pub lis: Mutex<HashMap<u64, LedgerInfoWithSignatures>>,
     pub last_vote: Mutex<Option<Vote>>,
+    pub ordered_blocks: Mutex<BTreeMap<(u64, Round), PersistedOrderedBlocks>>,
 
     // Liveness state
     pub highest_timeout_certificate: Mutex<Option<TimeoutCertificate>>,
@@ -45,6 +48,7 @@ impl MockSharedStorage {
             qc: Mutex::new(HashMap::new()),
             lis: Mutex::new(HashMap::new()),
             last_vote: Mutex::new(None),
+            ordered_blocks: Mutex::new(BTreeMap::new()),
             highest_timeout_certificate: Mutex::new(None),
             highest_2chain_timeout_certificate: Mutex::new(None),
             validator_set,
@@ -192,6 +196,10 @@ impl PersistentLivenessStorage for MockStorage {
         Ok(())
     }
 
+    fn prune_stale(&self, _: u64, _: Round) -> Result<()> {
+        Ok(())
+    }
+
     fn save_vote(&self, last_vote: &Vote) -> Result<()> {
         self.shared_storage
             .last_vote
@@ -233,6 +241,32 @@ impl PersistentLivenessStorage for MockStorage {
         Ok(())
     }
 
+    fn save_ordered_blocks(&self, ordered_blocks: PersistedOrderedBlocks) -> Result<()> {
+        self.shared_storage
+            .ordered_blocks
+            .lock()
+            .insert(ordered_blocks.key(), ordered_blocks);
+        Ok(())
+    }
+
+    fn prune_ordered_blocks(&self, keys: Vec<(u64, Round)>) -> Result<()> {
+        let mut ordered_blocks = self.shared_storage.ordered_blocks.lock();
+        for key in keys {
+            ordered_blocks.remove(&key);
+        }
+        Ok(())
+    }
+
+    fn get_ordered_blocks(&self) -> Result<Vec<PersistedOrderedBlocks>> {
+        Ok(self
+            .shared_storage
+            .ordered_blocks
+            .lock()
+            .values()
+            .cloned()
+            .collect())
+    }
+
     fn retrieve_epoch_change_proof(&self, version: u64) -> Result<EpochChangeProof> {
         let lis = self
             .shared_storage
@@ -275,6 +309,10 @@ impl PersistentLivenessStorage for EmptyStorage {
         Ok(())
     }
 
+    fn prune_stale(&self, _: u64, _: Round) -> Result<()> {
+        Ok(())
+    }
+
     fn save_vote(&self, _: &Vote) -> Result<()> {
         Ok(())
     }
@@ -312,6 +350,18 @@ impl PersistentLivenessStorage for EmptyStorage {
         Ok(())
     }
 
+    fn save_ordered_blocks(&self, _: PersistedOrderedBlocks) -> Result<()> {
+        Ok(())
+    }
+
+    fn prune_ordered_blocks(&self, _: Vec<(u64, Round)>) -> Result<()> {
+        Ok(())
+    }
+
+    fn get_ordered_blocks(&self) -> Result<Vec<PersistedOrderedBlocks>> {
+        Ok(vec![])
+    }
+
     fn retrieve_epoch_change_proof(&self, _version: u64) -> Result<EpochChangeProof> {
         unimplemented!()
     }
@@ -19,6 +19,7 @@ mod mock_state_computer;
 mod mock_storage;
 #[cfg(any(test, feature = "fuzzing"))]
 mod mock_txn_manager;
+pub mod twins;
 
 use crate::util::mock_time_service::SimulatedTimeService;
 use consensus_types::{block::block_test_utils::gen_test_certificate, common::Payload};
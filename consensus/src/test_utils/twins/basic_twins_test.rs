@@ -3,12 +3,15 @@
 
 use crate::{
     network_interface::ConsensusMsg,
-    network_tests::{NetworkPlayground, TwinId},
-    test_utils::{consensus_runtime, timed_block_on},
-    twins::twins_node::SMRNode,
+    test_utils::{
+        consensus_runtime, timed_block_on,
+        twins::{NetworkPlayground, SMRNode, SafetyRulesFault, TwinId},
+    },
 };
 use consensus_types::{block::Block, common::Round};
-use diem_config::config::ConsensusProposerType::{FixedProposer, RotatingProposer, RoundProposer};
+use diem_config::config::ConsensusProposerType::{
+    FixedProposer, RotatingProposer, RoundProposer, VrfProposer,
+};
 use futures::StreamExt;
 use std::collections::HashMap;
 
@@ -55,6 +58,40 @@ fn basic_start_test() {
     });
 }
 
+#[test]
+/// This test checks that the proposals carry the VRF proofs
+/// of their authors with the VRF proposer election.
+///
+/// Setup:
+///
+/// 4 honest nodes, and 0 twins
+///
+/// Run the test:
+/// cargo xtest -p consensus vrf_proposer_test -- --nocapture
+fn vrf_proposer_test() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let num_nodes = 4;
+    let num_twins = 0;
+    let _nodes = SMRNode::start_num_nodes_with_twins(
+        num_nodes,
+        num_twins,
+        &mut playground,
+        VrfProposer,
+        None,
+    );
+    timed_block_on(&mut runtime, async {
+        let msg = playground
+            .wait_for_messages(1, NetworkPlayground::proposals_only)
+            .await;
+        let first_proposal = match &msg[0].1 {
+            ConsensusMsg::ProposalMsg(proposal) => proposal,
+            _ => panic!("Unexpected message found"),
+        };
+        assert!(first_proposal.proposal().vrf_proof().is_some());
+    });
+}
+
 #[test]
 /// This test checks that the split_network function works
 /// as expected, that is: nodes in a partition with less nodes
@@ -310,3 +347,58 @@ fn twins_commit_test() {
         }
     });
 }
+
+#[test]
+/// This test checks that the safety rules faults injected in a node
+/// apply to that node only.
+///
+/// Setup:
+///
+/// 4 honest nodes (n0, n1, n2, n3), and 0 twins.
+/// n3 rejects all the votes in its safety rules.
+///
+/// Test:
+///
+/// Check that n3 does not send any vote, and that the 3 other nodes
+/// still form a quorum and commit blocks.
+///
+/// Run the test:
+/// cargo xtest -p consensus safety_rules_faults_test -- --nocapture
+fn safety_rules_faults_test() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let num_nodes = 4;
+    let num_twins = 0;
+    let mut nodes = SMRNode::start_num_nodes_with_twins(
+        num_nodes,
+        num_twins,
+        &mut playground,
+        RotatingProposer,
+        None,
+    );
+
+    let n3_author = nodes[3].id.author;
+    nodes[3]
+        .safety_rules_faults
+        .inject(SafetyRulesFault::RejectVotes);
+    assert!(nodes[3]
+        .safety_rules_faults
+        .is_injected(SafetyRulesFault::RejectVotes));
+    assert!(!nodes[0]
+        .safety_rules_faults
+        .is_injected(SafetyRulesFault::RejectVotes));
+
+    timed_block_on(&mut runtime, async {
+        // votes of the first two rounds
+        let votes = playground
+            .wait_for_messages(6, NetworkPlayground::votes_only)
+            .await;
+        assert!(votes.iter().all(|(author, _)| *author != n3_author));
+    });
+
+    runtime.spawn(playground.start());
+    timed_block_on(&mut runtime, async {
+        let node0_commit = nodes[0].commit_cb_receiver.next().await;
+        assert!(node0_commit.is_some());
+    });
+}
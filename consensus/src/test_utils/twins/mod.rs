@@ -0,0 +1,18 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Twins harness to test consensus against byzantine behaviors: it starts a number of validators
+//! (some of them possibly sharing the identity of another validator, i.e., twins) connected by a
+//! `NetworkPlayground` which can split the network, globally or for given rounds. The safety
+//! rules of every node can be made to fail at runtime through its `SafetyRulesFaults`.
+//!
+//! Outside of the consensus crate, the harness is available with the `fuzzing` feature.
+
+#[cfg(test)]
+mod basic_twins_test;
+mod safety_rules_faults;
+mod twins_node;
+
+pub use crate::network_tests::{NetworkPlayground, TwinId};
+pub use safety_rules_faults::{FaultySafetyRules, SafetyRulesFault, SafetyRulesFaults};
+pub use twins_node::SMRNode;
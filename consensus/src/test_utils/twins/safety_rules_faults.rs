@@ -0,0 +1,139 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use consensus_types::{
+    block_data::BlockData,
+    common::Round,
+    timeout::Timeout,
+    timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
+    vote::Vote,
+    vote_proposal::MaybeSignedVoteProposal,
+};
+use diem_crypto::{ecvrf::VRFProof, ed25519::Ed25519Signature};
+use diem_infallible::RwLock;
+use diem_types::{
+    epoch_change::EpochChangeProof,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+};
+use safety_rules::{ConsensusState, Error, TSafetyRules};
+use std::{collections::HashSet, sync::Arc};
+
+/// The operations of the safety rules of a node that can be made to fail.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SafetyRulesFault {
+    /// The node refuses to sign proposals.
+    RejectProposals,
+    /// The node refuses to vote, with both the 3-chain and 2-chain rules.
+    RejectVotes,
+    /// The node refuses to sign timeouts.
+    RejectTimeouts,
+    /// The node refuses to sign commit votes (decoupled execution only).
+    RejectCommitVotes,
+}
+
+/// Handle to inject and clear the safety rules faults of a node while it runs.
+#[derive(Clone, Default)]
+pub struct SafetyRulesFaults(Arc<RwLock<HashSet<SafetyRulesFault>>>);
+
+impl SafetyRulesFaults {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn inject(&self, fault: SafetyRulesFault) {
+        self.0.write().insert(fault);
+    }
+
+    pub fn clear(&self, fault: SafetyRulesFault) {
+        self.0.write().remove(&fault);
+    }
+
+    pub fn clear_all(&self) {
+        self.0.write().clear();
+    }
+
+    pub fn is_injected(&self, fault: SafetyRulesFault) -> bool {
+        self.0.read().contains(&fault)
+    }
+
+    fn check(&self, fault: SafetyRulesFault) -> Result<(), Error> {
+        if self.is_injected(fault) {
+            Err(Error::InternalError(format!("Injected fault {:?}", fault)))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Safety rules failing the operations of the injected faults, and delegating everything else to
+/// the wrapped safety rules.
+pub struct FaultySafetyRules {
+    inner: Box<dyn TSafetyRules + Send + Sync>,
+    faults: SafetyRulesFaults,
+}
+
+impl FaultySafetyRules {
+    pub fn new(inner: Box<dyn TSafetyRules + Send + Sync>, faults: SafetyRulesFaults) -> Self {
+        Self { inner, faults }
+    }
+}
+
+impl TSafetyRules for FaultySafetyRules {
+    fn consensus_state(&mut self) -> Result<ConsensusState, Error> {
+        self.inner.consensus_state()
+    }
+
+    fn initialize(&mut self, proof: &EpochChangeProof) -> Result<(), Error> {
+        self.inner.initialize(proof)
+    }
+
+    fn construct_and_sign_vote(
+        &mut self,
+        vote_proposal: &MaybeSignedVoteProposal,
+    ) -> Result<Vote, Error> {
+        self.faults.check(SafetyRulesFault::RejectVotes)?;
+        self.inner.construct_and_sign_vote(vote_proposal)
+    }
+
+    fn sign_proposal(&mut self, block_data: &BlockData) -> Result<Ed25519Signature, Error> {
+        self.faults.check(SafetyRulesFault::RejectProposals)?;
+        self.inner.sign_proposal(block_data)
+    }
+
+    fn sign_timeout(&mut self, timeout: &Timeout) -> Result<Ed25519Signature, Error> {
+        self.faults.check(SafetyRulesFault::RejectTimeouts)?;
+        self.inner.sign_timeout(timeout)
+    }
+
+    fn sign_timeout_with_qc(
+        &mut self,
+        timeout: &TwoChainTimeout,
+        timeout_cert: Option<&TwoChainTimeoutCertificate>,
+    ) -> Result<Ed25519Signature, Error> {
+        self.faults.check(SafetyRulesFault::RejectTimeouts)?;
+        self.inner.sign_timeout_with_qc(timeout, timeout_cert)
+    }
+
+    fn construct_and_sign_vote_two_chain(
+        &mut self,
+        vote_proposal: &MaybeSignedVoteProposal,
+        timeout_cert: Option<&TwoChainTimeoutCertificate>,
+    ) -> Result<Vote, Error> {
+        self.faults.check(SafetyRulesFault::RejectVotes)?;
+        self.inner
+            .construct_and_sign_vote_two_chain(vote_proposal, timeout_cert)
+    }
+
+    fn sign_commit_vote(
+        &mut self,
+        ledger_info: LedgerInfoWithSignatures,
+        new_ledger_info: LedgerInfo,
+    ) -> Result<Ed25519Signature, Error> {
+        self.faults.check(SafetyRulesFault::RejectCommitVotes)?;
+        self.inner.sign_commit_vote(ledger_info, new_ledger_info)
+    }
+
+    fn prove_proposer_vrf(&mut self, round: Round) -> Result<VRFProof, Error> {
+        self.inner.prove_proposer_vrf(round)
+    }
+}
@@ -7,14 +7,18 @@ use crate::{
     network::NetworkTask,
     network_interface::{ConsensusNetworkEvents, ConsensusNetworkSender},
     network_tests::{NetworkPlayground, TwinId},
-    test_utils::{MockStateComputer, MockStorage, MockTransactionManager},
+    state_snapshot::SharedConsensusState,
+    test_utils::{
+        twins::{FaultySafetyRules, SafetyRulesFaults},
+        MockStateComputer, MockStorage, MockTransactionManager,
+    },
     util::time_service::ClockTimeService,
 };
 use channel::{self, diem_channel, message_queues::QueueStyle};
 use consensus_types::common::{Author, Payload, Round};
 use diem_config::{
     config::{
-        ConsensusProposerType::{self, RoundProposer},
+        ConsensusProposerType::{self, RoundProposer, VrfProposer},
         NodeConfig, WaypointConfig,
     },
     generator::{self, ValidatorSwarm},
@@ -40,6 +44,7 @@ pub struct SMRNode {
     pub id: TwinId,
     pub storage: Arc<MockStorage>,
     pub commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    pub safety_rules_faults: SafetyRulesFaults,
     _runtime: Runtime,
     _shared_mempool: MockSharedMempool,
     _state_sync: mpsc::UnboundedReceiver<Payload>,
@@ -107,7 +112,7 @@ impl SMRNode {
             channel::new(1_024, &counters::PENDING_ROUND_TIMEOUTS);
         let (self_sender, self_receiver) = channel::new(1_024, &counters::PENDING_SELF_MESSAGES);
 
-        let epoch_mgr = EpochManager::new(
+        let mut epoch_mgr = EpochManager::new(
             &config,
             time_service,
             self_sender,
@@ -117,16 +122,26 @@ impl SMRNode {
             state_computer,
             storage.clone(),
             reconfig_events,
+            None,
+            SharedConsensusState::new(),
         );
+        let safety_rules_faults = SafetyRulesFaults::new();
+        let faults = safety_rules_faults.clone();
+        epoch_mgr.set_safety_rules_wrapper(Arc::new(move |safety_rules| {
+            Box::new(FaultySafetyRules::new(safety_rules, faults.clone()))
+        }));
         let (network_task, network_receiver) =
             NetworkTask::new(network_events, self_receiver, playground.peer_protocols());
 
         runtime.spawn(network_task.start());
-        runtime.spawn(epoch_mgr.start(timeout_receiver, network_receiver));
+        // The twins aren't shut down, their runtime is dropped
+        let (_, shutdown_requests) = mpsc::channel(1);
+        runtime.spawn(epoch_mgr.start(timeout_receiver, network_receiver, shutdown_requests));
         Self {
             id: twin_id,
             _runtime: runtime,
             commit_cb_receiver,
+            safety_rules_faults,
             storage,
             _shared_mempool: shared_mempool,
             _state_sync: state_sync,
@@ -213,6 +228,10 @@ impl SMRNode {
                 .waypoint = Some(waypoint);
             config.base.waypoint = WaypointConfig::FromConfig(waypoint);
             config.consensus.proposer_type = proposer_type.clone();
+            // The VRF proofs of the proposals are produced with the consensus key
+            config.consensus.experimental_vrf_proposer = matches!(proposer_type, VrfProposer);
+            config.consensus.safety_rules.export_consensus_key =
+                matches!(proposer_type, VrfProposer);
             config.consensus.safety_rules.verify_vote_proposal_signature = false;
             // Disable timeout in twins test to avoid flakiness
             config.consensus.round_initial_timeout_ms = 2_000_000;
@@ -0,0 +1,119 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters,
+    network_interface::ConsensusNetworkSender,
+    round_manager::{UnverifiedEvent, VerifiedEvent},
+};
+use bounded_executor::BoundedExecutor;
+use consensus_types::common::Author;
+use diem_logger::prelude::*;
+use diem_types::{epoch_state::EpochState, validator_verifier::ValidatorVerifier};
+use futures::channel::{mpsc::UnboundedSender, oneshot};
+use network::peer_manager::Misbehavior;
+use std::{collections::HashMap, sync::Arc};
+use tokio::runtime::Handle;
+
+/// An event verified by the pool, along with the epoch of the verifier that checked it and its
+/// author.
+pub type VerifiedEventWithEpoch = (u64, Author, VerifiedEvent);
+
+/// Verifies the signatures of the consensus messages on a bounded number of concurrent tasks, so
+/// that the multi-signatures of the QCs aren't checked one at a time on the event loop of the
+/// EpochManager. The verified messages of an author are delivered in the order they were
+/// submitted, the messages of different authors might be delivered in any order.
+pub struct VerificationPool {
+    executor: BoundedExecutor,
+    verified_tx: UnboundedSender<VerifiedEventWithEpoch>,
+    // Reports the authors of the invalid events to the network
+    network_sender: ConsensusNetworkSender,
+    // Verifier of the current epoch, shared by the verification tasks
+    verifier: Option<(u64, Arc<ValidatorVerifier>)>,
+    // Completion of the last verification submitted for every author, the next verification of
+    // the author waits for it before delivering its event
+    last_verifications: HashMap<Author, oneshot::Receiver<()>>,
+}
+
+impl VerificationPool {
+    pub fn new(
+        num_workers: usize,
+        executor: Handle,
+        verified_tx: UnboundedSender<VerifiedEventWithEpoch>,
+        network_sender: ConsensusNetworkSender,
+    ) -> Self {
+        Self {
+            executor: BoundedExecutor::new(num_workers, executor),
+            verified_tx,
+            network_sender,
+            verifier: None,
+            last_verifications: HashMap::new(),
+        }
+    }
+
+    /// Verifies the events submitted from now on against the validators of the new epoch.
+    pub fn new_epoch(&mut self, epoch_state: &EpochState) {
+        self.verifier = Some((epoch_state.epoch, Arc::new(epoch_state.verifier.clone())));
+        self.last_verifications.clear();
+    }
+
+    /// Waits for a free worker and starts verifying the event against the validators of the
+    /// current epoch. Valid events are sent to the verified channel, invalid ones are logged and
+    /// dropped, and the authors of the ones with an invalid signature are reported to the network.
+    pub async fn submit(&mut self, peer_id: Author, event: UnverifiedEvent) {
+        let (epoch, verifier) = match &self.verifier {
+            Some((epoch, verifier)) => (*epoch, verifier.clone()),
+            None => panic!("[VerificationPool] No epoch started"),
+        };
+        let (done_tx, done_rx) = oneshot::channel();
+        let previous_verification = self.last_verifications.insert(peer_id, done_rx);
+        let verified_tx = self.verified_tx.clone();
+        let mut network_sender = self.network_sender.clone();
+
+        counters::PENDING_MESSAGE_VERIFICATIONS.inc();
+        self.executor
+            .spawn(async move {
+                let result = event.clone().verify(&verifier);
+                // the previous event of the author goes first, whether it turned out valid or not
+                if let Some(previous_verification) = previous_verification {
+                    let _ = previous_verification.await;
+                }
+                match result {
+                    Ok(verified_event) => {
+                        if verified_tx
+                            .unbounded_send((epoch, peer_id, verified_event))
+                            .is_err()
+                        {
+                            counters::PENDING_MESSAGE_VERIFICATIONS.dec();
+                        }
+                    }
+                    Err(e) => {
+                        counters::PENDING_MESSAGE_VERIFICATIONS.dec();
+                        error!(
+                            SecurityEvent::ConsensusInvalidMessage,
+                            remote_peer = peer_id,
+                            error = ?e,
+                            unverified_event = event
+                        );
+                        // the other failures may be on our side, e.g. an outdated validator set
+                        if e.is_invalid_signature() {
+                            if let Err(e) = network_sender
+                                .report_misbehavior(peer_id, Misbehavior::InvalidSignature)
+                            {
+                                warn!(
+                                    remote_peer = peer_id,
+                                    error = ?e, "Failed to report an invalid message",
+                                );
+                            }
+                        }
+                    }
+                }
+                let _ = done_tx.send(());
+            })
+            .await;
+    }
+}
+
+#[cfg(test)]
+#[path = "verification_pool_test.rs"]
+mod verification_pool_test;
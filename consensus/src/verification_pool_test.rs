@@ -0,0 +1,111 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    network_interface::ConsensusNetworkSender,
+    round_manager::{UnverifiedEvent, VerifiedEvent},
+    verification_pool::VerificationPool,
+};
+use channel::{diem_channel, message_queues::QueueStyle};
+use consensus_types::experimental::commit_vote::CommitVote;
+use diem_crypto::hash::ACCUMULATOR_PLACEHOLDER_HASH;
+use diem_types::{
+    block_info::BlockInfo, epoch_state::EpochState, ledger_info::LedgerInfo,
+    validator_signer::ValidatorSigner, validator_verifier::random_validator_verifier,
+};
+use futures::{channel::mpsc, FutureExt, StreamExt};
+use network::{
+    peer_manager::{
+        ConnectionRequest, ConnectionRequestSender, Misbehavior, PeerManagerRequestSender,
+    },
+    protocols::network::NewNetworkSender,
+};
+use tokio::runtime::Handle;
+
+fn commit_vote(author: &ValidatorSigner, signer: &ValidatorSigner, round: u64) -> UnverifiedEvent {
+    let ledger_info = LedgerInfo::new(BlockInfo::random(round), *ACCUMULATOR_PLACEHOLDER_HASH);
+    let signature = signer.sign(&ledger_info);
+    UnverifiedEvent::CommitVote(Box::new(CommitVote::new_with_signature(
+        author.author(),
+        ledger_info,
+        signature,
+    )))
+}
+
+fn verified_round(event: &VerifiedEvent) -> u64 {
+    match event {
+        VerifiedEvent::CommitVote(vote) => vote.round(),
+        _ => panic!("Unexpected event {:?}", event),
+    }
+}
+
+#[tokio::test]
+async fn test_verification_pool_order() {
+    let (signers, verifier) = random_validator_verifier(2, None, false);
+    let epoch_state = EpochState { epoch: 1, verifier };
+    let (verified_tx, mut verified_rx) = mpsc::unbounded();
+    let (network_reqs_tx, _network_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
+    let (connection_reqs_tx, mut connection_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
+    let network_sender = ConsensusNetworkSender::new(
+        PeerManagerRequestSender::new(network_reqs_tx),
+        ConnectionRequestSender::new(connection_reqs_tx),
+    );
+    let mut pool = VerificationPool::new(2, Handle::current(), verified_tx, network_sender);
+    pool.new_epoch(&epoch_state);
+
+    // the third vote of the first author carries the signature of the other one
+    for round in 1..=5 {
+        let signer = if round == 3 { &signers[1] } else { &signers[0] };
+        pool.submit(signers[0].author(), commit_vote(&signers[0], signer, round))
+            .await;
+        pool.submit(
+            signers[1].author(),
+            commit_vote(&signers[1], &signers[1], round + 10),
+        )
+        .await;
+    }
+
+    let (mut first_rounds, mut second_rounds) = (vec![], vec![]);
+    for _ in 0..9 {
+        let (epoch, author, event) = verified_rx.next().await.unwrap();
+        assert_eq!(epoch, 1);
+        if author == signers[0].author() {
+            first_rounds.push(verified_round(&event));
+        } else {
+            second_rounds.push(verified_round(&event));
+        }
+    }
+    assert_eq!(first_rounds, vec![1, 2, 4, 5]);
+    assert_eq!(second_rounds, vec![11, 12, 13, 14, 15]);
+    // the author of the invalid vote is reported
+    match connection_reqs_rx.next().await.unwrap() {
+        ConnectionRequest::ReportMisbehavior(peer_id, misbehavior) => {
+            assert_eq!(peer_id, signers[0].author());
+            assert_eq!(misbehavior, Misbehavior::InvalidSignature);
+        }
+        request => panic!("Unexpected ConnectionRequest: {:?}", request),
+    }
+
+    // the events are verified against the validators of the new epoch once it starts, the vote
+    // of an author unknown to it is dropped but not reported, its signature isn't invalid
+    let (new_signers, new_verifier) = random_validator_verifier(1, None, false);
+    pool.new_epoch(&EpochState {
+        epoch: 2,
+        verifier: new_verifier,
+    });
+    pool.submit(
+        new_signers[0].author(),
+        commit_vote(&signers[0], &signers[0], 6),
+    )
+    .await;
+    pool.submit(
+        new_signers[0].author(),
+        commit_vote(&new_signers[0], &new_signers[0], 7),
+    )
+    .await;
+    let (epoch, author, event) = verified_rx.next().await.unwrap();
+    assert_eq!(epoch, 2);
+    assert_eq!(author, new_signers[0].author());
+    assert_eq!(verified_round(&event), 7);
+    assert!(connection_reqs_rx.next().now_or_never().is_none());
+}
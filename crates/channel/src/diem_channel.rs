@@ -110,6 +110,11 @@ impl<K: Eq + Hash + Clone, M> Sender<K, M> {
         }
         Ok(())
     }
+
+    /// Whether the receiver was dropped, in which case every push fails.
+    pub fn is_closed(&self) -> bool {
+        self.shared_state.lock().receiver_dropped
+    }
 }
 
 impl<K: Eq + Hash + Clone, M> Clone for Sender<K, M> {
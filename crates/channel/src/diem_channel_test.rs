@@ -38,6 +38,15 @@ fn test_empty() {
     assert_eq!(receiver.select_next_some().now_or_never(), None);
 }
 
+#[test]
+fn test_is_closed() {
+    let (mut sender, receiver) = diem_channel::new::<u8, u8>(QueueStyle::FIFO, 10, None);
+    assert!(!sender.is_closed());
+    drop(receiver);
+    assert!(sender.is_closed());
+    assert!(sender.push(0, 0).is_err());
+}
+
 #[test]
 fn test_waker() {
     let (mut sender, mut receiver) = diem_channel::new(QueueStyle::FIFO, 10, None);
@@ -4,12 +4,15 @@
 use crate::{stream::websocket_transport::WebsocketTransport, StreamError, StreamResult};
 use diem_json_rpc_types::{
     stream::{
-        request::{StreamMethodRequest, SubscribeToEventsParams, SubscribeToTransactionsParams},
+        request::{
+            StreamMethodRequest, SubscribeToEventsParams, SubscribeToTransactionStatusParams,
+            SubscribeToTransactionsParams,
+        },
         response::StreamJsonRpcResponse,
     },
     Id,
 };
-use diem_types::event::EventKey;
+use diem_types::{account_address::AccountAddress, event::EventKey};
 use futures::Stream;
 use std::{
     collections::HashMap,
@@ -143,6 +146,23 @@ impl StreamingClient {
         let request = StreamMethodRequest::SubscribeToTransactions(SubscribeToTransactionsParams {
             starting_version,
             include_events,
+            sender: None,
+            user_transactions_only: None,
+        });
+        self.send_subscription(request).await
+    }
+
+    pub async fn subscribe_account_transactions(
+        &mut self,
+        sender: AccountAddress,
+        starting_version: u64,
+        include_events: Option<bool>,
+    ) -> StreamResult<SubscriptionStream> {
+        let request = StreamMethodRequest::SubscribeToTransactions(SubscribeToTransactionsParams {
+            starting_version,
+            include_events,
+            sender: Some(sender),
+            user_transactions_only: Some(true),
         });
         self.send_subscription(request).await
     }
@@ -159,6 +179,19 @@ impl StreamingClient {
         self.send_subscription(request).await
     }
 
+    /// Follows the transactions of `sender` in mempool, or all of them if None, from their
+    /// acceptance until they get committed or dropped.
+    pub async fn subscribe_transaction_status(
+        &mut self,
+        sender: Option<AccountAddress>,
+    ) -> StreamResult<SubscriptionStream> {
+        let request =
+            StreamMethodRequest::SubscribeToTransactionStatus(SubscribeToTransactionStatusParams {
+                sender,
+            });
+        self.send_subscription(request).await
+    }
+
     pub(crate) async fn send_unsubscribe(&mut self, id: &Id) -> StreamResult<()> {
         debug!("StreamingClient sending unsubscribe for: {:?}", id);
         self.client
@@ -0,0 +1,53 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compares the SHA3-256 backends on batches shaped like the internal nodes of a Merkle tree
+//! level: a 32-byte seed followed by the two 32-byte hashes of the children.
+//! Run with `--features simd-sha3` to include the AVX2 backend.
+
+#[macro_use]
+extern crate criterion;
+
+use criterion::{BenchmarkId, Criterion, Throughput};
+use diem_crypto::sha3_backend::Sha3Backend;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+const BATCH_SIZES: [usize; 3] = [4, 64, 1024];
+const NODE_SIZE: usize = 64;
+
+fn sha3_batch(c: &mut Criterion) {
+    let mut rng = StdRng::from_seed([0u8; 32]);
+    let mut seed = [0u8; 32];
+    rng.fill_bytes(&mut seed);
+
+    let mut group = c.benchmark_group("sha3_256_batch");
+    for batch_size in BATCH_SIZES.iter() {
+        let nodes: Vec<[u8; NODE_SIZE]> = (0..*batch_size)
+            .map(|_| {
+                let mut node = [0u8; NODE_SIZE];
+                rng.fill_bytes(&mut node);
+                node
+            })
+            .collect();
+        let messages: Vec<&[u8]> = nodes.iter().map(|node| &node[..]).collect();
+
+        // The backends must agree before comparing their speed
+        let expected = Sha3Backend::Portable.sha3_256_batch(&seed, &messages);
+        for backend in Sha3Backend::available() {
+            assert_eq!(backend.sha3_256_batch(&seed, &messages), expected);
+        }
+
+        group.throughput(Throughput::Elements(*batch_size as u64));
+        for backend in Sha3Backend::available() {
+            group.bench_with_input(
+                BenchmarkId::new(backend.name(), batch_size),
+                &messages,
+                |b, messages| b.iter(|| backend.sha3_256_batch(&seed, messages)),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(sha3_benches, sha3_batch);
+criterion_main!(sha3_benches);
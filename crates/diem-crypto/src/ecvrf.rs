@@ -0,0 +1,498 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides an API for the verifiable random function (VRF) ECVRF-EDWARDS25519-SHA512-TAI,
+//! as specified in [RFC9381](https://www.rfc-editor.org/rfc/rfc9381.html).
+//!
+//! The holder of a private key proves the output of the VRF on an input: the output looks random
+//! to anyone who doesn't know the private key, and anyone with the public key verifies that the
+//! output is the only one of the private key on that input. The output is computed from the proof
+//! with `VRFOutput::from`, and only means something once the proof is verified.
+//!
+//! VRF private keys are 32-byte secrets expanded like the Ed25519 ones, so that the VRF public key
+//! of an Ed25519 secret is its Ed25519 public key.
+//!
+//! # Examples
+//!
+//! ```
+//! use diem_crypto::{
+//!     ecvrf::*,
+//!     traits::Uniform,
+//! };
+//! use rand::{rngs::StdRng, SeedableRng};
+//!
+//! let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+//! let private_key = VRFPrivateKey::generate(&mut rng);
+//! let public_key: VRFPublicKey = (&private_key).into();
+//! let proof = private_key.prove(b"input");
+//! assert!(public_key.verify(&proof, b"input").is_ok());
+//! let output = VRFOutput::from(&proof);
+//! ```
+//! **Note**: The above example generates a private key using a private function intended only for
+//! testing purposes. Production code should find an alternate means for secure key generation.
+
+use crate::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    traits::*,
+};
+use anyhow::{anyhow, Result};
+use core::convert::TryFrom;
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_TABLE,
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar,
+};
+use diem_crypto_derive::{DeserializeKey, SerializeKey, SilentDebug, SilentDisplay};
+use sha2::{Digest, Sha512};
+use std::fmt;
+
+/// The length of the VRFPrivateKey
+pub const VRF_PRIVATE_KEY_LENGTH: usize = 32;
+/// The length of the VRFPublicKey
+pub const VRF_PUBLIC_KEY_LENGTH: usize = 32;
+/// The length of the VRFProof
+pub const VRF_PROOF_LENGTH: usize = 80;
+/// The length of the VRFOutput
+pub const VRF_OUTPUT_LENGTH: usize = 64;
+
+/// The suite string of ECVRF-EDWARDS25519-SHA512-TAI.
+const SUITE_STRING: u8 = 0x03;
+/// The domain separators of the hashes of the suite.
+const ENCODE_TO_CURVE_DOMAIN_SEPARATOR_FRONT: u8 = 0x01;
+const CHALLENGE_GENERATION_DOMAIN_SEPARATOR_FRONT: u8 = 0x02;
+const PROOF_TO_HASH_DOMAIN_SEPARATOR_FRONT: u8 = 0x03;
+const DOMAIN_SEPARATOR_BACK: u8 = 0x00;
+
+/// The length of the challenge of a proof, half the length of a scalar.
+const CHALLENGE_LENGTH: usize = 16;
+
+/// A VRF private key
+#[derive(DeserializeKey, SerializeKey, SilentDebug, SilentDisplay)]
+pub struct VRFPrivateKey([u8; VRF_PRIVATE_KEY_LENGTH]);
+
+#[cfg(feature = "assert-private-keys-not-cloneable")]
+static_assertions::assert_not_impl_any!(VRFPrivateKey: Clone);
+
+#[cfg(any(test, feature = "cloneable-private-keys"))]
+impl Clone for VRFPrivateKey {
+    fn clone(&self) -> Self {
+        VRFPrivateKey(self.0)
+    }
+}
+
+/// A VRF public key
+#[derive(DeserializeKey, Clone, SerializeKey)]
+pub struct VRFPublicKey(CompressedEdwardsY, EdwardsPoint);
+
+/// A VRF proof, made of the point Gamma, the challenge c and the response s of RFC9381
+#[derive(DeserializeKey, Clone, SerializeKey)]
+pub struct VRFProof {
+    gamma: EdwardsPoint,
+    c: Scalar,
+    s: Scalar,
+}
+
+/// A VRF output, which is the same for every proof of a private key on an input
+#[derive(Clone, PartialEq, Eq)]
+pub struct VRFOutput([u8; VRF_OUTPUT_LENGTH]);
+
+/// A private key expanded into the secret scalar and the nonce prefix, as in Ed25519.
+struct ExpandedPrivateKey {
+    scalar: Scalar,
+    nonce_prefix: [u8; 32],
+}
+
+impl VRFPrivateKey {
+    /// The length of the VRFPrivateKey
+    pub const LENGTH: usize = VRF_PRIVATE_KEY_LENGTH;
+
+    /// Serialize a VRFPrivateKey.
+    pub fn to_bytes(&self) -> [u8; VRF_PRIVATE_KEY_LENGTH] {
+        self.0
+    }
+
+    /// Proves the output of the VRF on the input `alpha`.
+    pub fn prove(&self, alpha: &[u8]) -> VRFProof {
+        let expanded_key = self.expand();
+        let public_key = expanded_key.public_key();
+        let h = encode_to_curve(&public_key, alpha);
+        let gamma = expanded_key.scalar * h;
+
+        let mut nonce_hash = Sha512::new();
+        nonce_hash.update(&expanded_key.nonce_prefix);
+        nonce_hash.update(h.compress().as_bytes());
+        let k = Scalar::from_hash(nonce_hash);
+
+        let c = generate_challenge(&[
+            &public_key.1,
+            &h,
+            &gamma,
+            &(&k * &ED25519_BASEPOINT_TABLE),
+            &(k * h),
+        ]);
+        let s = k + c * expanded_key.scalar;
+        VRFProof { gamma, c, s }
+    }
+
+    /// Derives the secret scalar and the nonce prefix from the hash of the private key.
+    fn expand(&self) -> ExpandedPrivateKey {
+        let hash = Sha512::digest(&self.0);
+        let mut scalar_bytes = [0u8; 32];
+        scalar_bytes.copy_from_slice(&hash[..32]);
+        scalar_bytes[0] &= 248;
+        scalar_bytes[31] &= 127;
+        scalar_bytes[31] |= 64;
+        let mut nonce_prefix = [0u8; 32];
+        nonce_prefix.copy_from_slice(&hash[32..]);
+        ExpandedPrivateKey {
+            scalar: Scalar::from_bits(scalar_bytes),
+            nonce_prefix,
+        }
+    }
+}
+
+impl ExpandedPrivateKey {
+    fn public_key(&self) -> VRFPublicKey {
+        let point = &self.scalar * &ED25519_BASEPOINT_TABLE;
+        VRFPublicKey(point.compress(), point)
+    }
+}
+
+impl VRFPublicKey {
+    /// Serialize a VRFPublicKey.
+    pub fn to_bytes(&self) -> [u8; VRF_PUBLIC_KEY_LENGTH] {
+        self.0.to_bytes()
+    }
+
+    /// Verifies the proof of the output of the VRF on the input `alpha`.
+    pub fn verify(&self, proof: &VRFProof, alpha: &[u8]) -> Result<()> {
+        let h = encode_to_curve(self, alpha);
+        let u = EdwardsPoint::vartime_double_scalar_mul_basepoint(&-proof.c, &self.1, &proof.s);
+        let v = proof.s * h - proof.c * proof.gamma;
+
+        let c = generate_challenge(&[&self.1, &h, &proof.gamma, &u, &v]);
+        if c == proof.c {
+            Ok(())
+        } else {
+            Err(anyhow!("VRF proof verification failed"))
+        }
+    }
+}
+
+impl VRFProof {
+    /// Serialize a VRFProof.
+    pub fn to_bytes(&self) -> [u8; VRF_PROOF_LENGTH] {
+        let mut bytes = [0u8; VRF_PROOF_LENGTH];
+        bytes[..32].copy_from_slice(self.gamma.compress().as_bytes());
+        bytes[32..48].copy_from_slice(&self.c.as_bytes()[..CHALLENGE_LENGTH]);
+        bytes[48..].copy_from_slice(self.s.as_bytes());
+        bytes
+    }
+}
+
+impl VRFOutput {
+    /// Serialize a VRFOutput.
+    pub fn to_bytes(&self) -> [u8; VRF_OUTPUT_LENGTH] {
+        self.0
+    }
+}
+
+/// Hashes the input to a point of the prime order subgroup, with the try-and-increment method of
+/// RFC9381: the first hash of the public key, the input and a counter that decodes to a point is
+/// multiplied by the cofactor.
+fn encode_to_curve(public_key: &VRFPublicKey, alpha: &[u8]) -> EdwardsPoint {
+    for counter in 0..=u8::max_value() {
+        let hash = Sha512::new()
+            .chain(&[SUITE_STRING, ENCODE_TO_CURVE_DOMAIN_SEPARATOR_FRONT])
+            .chain(public_key.0.as_bytes())
+            .chain(alpha)
+            .chain(&[counter, DOMAIN_SEPARATOR_BACK])
+            .finalize();
+        if let Some(point) = decode_point(&hash[..32]) {
+            return point.mul_by_cofactor();
+        }
+    }
+    // Each attempt succeeds with probability about 1/2
+    panic!("Failed to hash the VRF input to the curve in 256 attempts")
+}
+
+/// Hashes the points into the challenge of a proof.
+fn generate_challenge(points: &[&EdwardsPoint]) -> Scalar {
+    let mut hash = Sha512::new();
+    hash.update(&[SUITE_STRING, CHALLENGE_GENERATION_DOMAIN_SEPARATOR_FRONT]);
+    for point in points {
+        hash.update(point.compress().as_bytes());
+    }
+    hash.update(&[DOMAIN_SEPARATOR_BACK]);
+    let hash = hash.finalize();
+
+    let mut challenge_bytes = [0u8; 32];
+    challenge_bytes[..CHALLENGE_LENGTH].copy_from_slice(&hash[..CHALLENGE_LENGTH]);
+    Scalar::from_bits(challenge_bytes)
+}
+
+/// Decodes a point, rejecting the non-canonical encodings as RFC8032 does.
+fn decode_point(bytes: &[u8]) -> Option<EdwardsPoint> {
+    let compressed = CompressedEdwardsY::from_slice(bytes);
+    let point = compressed.decompress()?;
+    if point.compress() != compressed {
+        return None;
+    }
+    Some(point)
+}
+
+///////////////////////
+// PrivateKey Traits //
+///////////////////////
+
+impl PrivateKey for VRFPrivateKey {
+    type PublicKeyMaterial = VRFPublicKey;
+}
+
+impl Uniform for VRFPrivateKey {
+    fn generate<R>(rng: &mut R) -> Self
+    where
+        R: ::rand::RngCore + ::rand::CryptoRng,
+    {
+        let mut bytes = [0u8; VRF_PRIVATE_KEY_LENGTH];
+        rng.fill_bytes(&mut bytes);
+        VRFPrivateKey(bytes)
+    }
+}
+
+impl PartialEq<Self> for VRFPrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for VRFPrivateKey {}
+
+impl TryFrom<&[u8]> for VRFPrivateKey {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a VRFPrivateKey. Any 32 bytes are a valid private key.
+    fn try_from(bytes: &[u8]) -> std::result::Result<VRFPrivateKey, CryptoMaterialError> {
+        let bytes = <[u8; VRF_PRIVATE_KEY_LENGTH]>::try_from(bytes)
+            .map_err(|_| CryptoMaterialError::WrongLengthError)?;
+        Ok(VRFPrivateKey(bytes))
+    }
+}
+
+/// The VRF private key with the same secret as the Ed25519 private key, whose public key is the
+/// Ed25519 public key.
+impl From<&Ed25519PrivateKey> for VRFPrivateKey {
+    fn from(private_key: &Ed25519PrivateKey) -> Self {
+        VRFPrivateKey(private_key.to_bytes())
+    }
+}
+
+impl Length for VRFPrivateKey {
+    fn length(&self) -> usize {
+        Self::LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for VRFPrivateKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+//////////////////////
+// PublicKey Traits //
+//////////////////////
+
+impl From<&VRFPrivateKey> for VRFPublicKey {
+    fn from(private_key: &VRFPrivateKey) -> Self {
+        private_key.expand().public_key()
+    }
+}
+
+/// The VRF public key of the secret of an Ed25519 public key.
+impl TryFrom<&Ed25519PublicKey> for VRFPublicKey {
+    type Error = CryptoMaterialError;
+
+    fn try_from(public_key: &Ed25519PublicKey) -> std::result::Result<Self, CryptoMaterialError> {
+        VRFPublicKey::try_from(&public_key.to_bytes()[..])
+    }
+}
+
+impl PublicKey for VRFPublicKey {
+    type PrivateKeyMaterial = VRFPrivateKey;
+}
+
+impl std::hash::Hash for VRFPublicKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let encoded_pubkey = self.to_bytes();
+        state.write(&encoded_pubkey);
+    }
+}
+
+// Those are required by the implementation of hash above
+impl PartialEq for VRFPublicKey {
+    fn eq(&self, other: &VRFPublicKey) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for VRFPublicKey {}
+
+impl TryFrom<&[u8]> for VRFPublicKey {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a VRFPublicKey, rejecting the points of small order as RFC9381 requires.
+    fn try_from(bytes: &[u8]) -> std::result::Result<VRFPublicKey, CryptoMaterialError> {
+        if bytes.len() != VRF_PUBLIC_KEY_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+        let point = decode_point(bytes).ok_or(CryptoMaterialError::DeserializationError)?;
+        if point.is_small_order() {
+            return Err(CryptoMaterialError::SmallSubgroupError);
+        }
+        Ok(VRFPublicKey(point.compress(), point))
+    }
+}
+
+impl Length for VRFPublicKey {
+    fn length(&self) -> usize {
+        VRF_PUBLIC_KEY_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for VRFPublicKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl fmt::Display for VRFPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.to_bytes()))
+    }
+}
+
+impl fmt::Debug for VRFPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VRFPublicKey({})", self)
+    }
+}
+
+//////////////////
+// Proof Traits //
+//////////////////
+
+impl TryFrom<&[u8]> for VRFProof {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a VRFProof, rejecting the non-canonical encodings of Gamma and s.
+    fn try_from(bytes: &[u8]) -> std::result::Result<VRFProof, CryptoMaterialError> {
+        if bytes.len() != VRF_PROOF_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+        let gamma = decode_point(&bytes[..32]).ok_or(CryptoMaterialError::DeserializationError)?;
+
+        let mut c_bytes = [0u8; 32];
+        c_bytes[..CHALLENGE_LENGTH].copy_from_slice(&bytes[32..48]);
+        let c = Scalar::from_bits(c_bytes);
+
+        let mut s_bytes = [0u8; 32];
+        s_bytes.copy_from_slice(&bytes[48..]);
+        let s = Scalar::from_canonical_bytes(s_bytes)
+            .ok_or(CryptoMaterialError::CanonicalRepresentationError)?;
+        Ok(VRFProof { gamma, c, s })
+    }
+}
+
+impl Length for VRFProof {
+    fn length(&self) -> usize {
+        VRF_PROOF_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for VRFProof {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl PartialEq for VRFProof {
+    fn eq(&self, other: &VRFProof) -> bool {
+        self.to_bytes()[..] == other.to_bytes()[..]
+    }
+}
+
+impl Eq for VRFProof {}
+
+impl std::hash::Hash for VRFProof {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let encoded_proof = self.to_bytes();
+        state.write(&encoded_proof);
+    }
+}
+
+impl fmt::Display for VRFProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.to_bytes()[..]))
+    }
+}
+
+impl fmt::Debug for VRFProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VRFProof({})", self)
+    }
+}
+
+///////////////////
+// Output Traits //
+///////////////////
+
+/// The output of a proof, which is only the output of the VRF once the proof is verified.
+impl From<&VRFProof> for VRFOutput {
+    fn from(proof: &VRFProof) -> Self {
+        let hash = Sha512::new()
+            .chain(&[SUITE_STRING, PROOF_TO_HASH_DOMAIN_SEPARATOR_FRONT])
+            .chain(proof.gamma.mul_by_cofactor().compress().as_bytes())
+            .chain(&[DOMAIN_SEPARATOR_BACK])
+            .finalize();
+        let mut output = [0u8; VRF_OUTPUT_LENGTH];
+        output.copy_from_slice(&hash);
+        VRFOutput(output)
+    }
+}
+
+impl fmt::Display for VRFOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0[..]))
+    }
+}
+
+impl fmt::Debug for VRFOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VRFOutput({})", self)
+    }
+}
+
+#[cfg(any(test, feature = "fuzzing"))]
+use crate::test_utils::{self, KeyPair};
+
+/// Produces a uniformly random VRF keypair from a seed
+#[cfg(any(test, feature = "fuzzing"))]
+pub fn keypair_strategy() -> impl Strategy<Value = KeyPair<VRFPrivateKey, VRFPublicKey>> {
+    test_utils::uniform_keypair_strategy::<VRFPrivateKey, VRFPublicKey>()
+}
+
+#[cfg(any(test, feature = "fuzzing"))]
+use proptest::prelude::*;
+
+#[cfg(any(test, feature = "fuzzing"))]
+impl proptest::arbitrary::Arbitrary for VRFPublicKey {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        crate::test_utils::uniform_keypair_strategy::<VRFPrivateKey, VRFPublicKey>()
+            .prop_map(|v| v.public_key)
+            .boxed()
+    }
+}
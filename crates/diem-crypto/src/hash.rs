@@ -482,6 +482,13 @@ pub trait CryptoHasher: Default + std::io::Write {
         hasher.update(bytes);
         hasher.finish()
     }
+
+    /// Computes the hash of every complete byte slice, in order. The hashers of the internal nodes
+    /// of the Merkle trees hash them with the fastest SHA3 backend of the CPU, see
+    /// [`sha3_backend`](crate::sha3_backend).
+    fn hash_all_batch(inputs: &[&[u8]]) -> Vec<HashValue> {
+        inputs.iter().map(|bytes| Self::hash_all(bytes)).collect()
+    }
 }
 
 /// The default hasher underlying generated implementations of `CryptoHasher`.
@@ -572,6 +579,12 @@ macro_rules! define_hasher {
             fn finish(self) -> HashValue {
                 self.0.finish()
             }
+
+            fn hash_all_batch(inputs: &[&[u8]]) -> Vec<HashValue> {
+                // an empty salt isn't prefixed, see `DefaultHasher::new`
+                let prefix: &[u8] = if $salt.is_empty() { b"" } else { Self::seed() };
+                crate::sha3_backend::sha3_256_batch(prefix, inputs)
+            }
         }
 
         impl std::io::Write for $hasher_type {
@@ -1,19 +1,26 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-#![forbid(unsafe_code)]
+// The SIMD SHA3 backend is the only unsafe code of the crate, and it's opt-in
+#![cfg_attr(not(feature = "simd-sha3"), forbid(unsafe_code))]
+#![cfg_attr(feature = "simd-sha3", deny(unsafe_code))]
 #![deny(missing_docs)]
 //! This feature gets turned on only if diem-crypto is compiled via MIRAI in a nightly build.
 #![cfg_attr(mirai, allow(incomplete_features), feature(const_generics))]
 
 //! A library supplying various cryptographic primitives
+pub mod bls12381;
 pub mod compat;
+pub mod ecvrf;
 pub mod ed25519;
 pub mod error;
 pub mod hash;
 pub mod hkdf;
 pub mod multi_ed25519;
 pub mod noise;
+pub mod secp256k1;
+pub mod sha3_backend;
+pub mod slip10;
 pub mod test_utils;
 pub mod traits;
 pub mod validatable;
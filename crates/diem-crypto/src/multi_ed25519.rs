@@ -405,6 +405,41 @@ impl MultiEd25519Signature {
         bytes.extend(&self.bitmap[..]);
         bytes
     }
+
+    /// Pairs each signature with the public key it's from, according to the bitmap, after
+    /// checking the bitmap against the public keys and their threshold.
+    fn signed_public_keys<'a>(
+        &'a self,
+        public_key: &'a MultiEd25519PublicKey,
+    ) -> Result<Vec<(&'a Ed25519PublicKey, &'a Ed25519Signature)>> {
+        match bitmap_last_set_bit(self.bitmap) {
+            Some(last_bit) if last_bit as usize <= public_key.length() => (),
+            _ => {
+                return Err(anyhow!(
+                    "{}",
+                    CryptoMaterialError::BitVecError("Signature index is out of range".to_string())
+                ))
+            }
+        };
+        if bitmap_count_ones(self.bitmap) < public_key.threshold as u32 {
+            return Err(anyhow!(
+                "{}",
+                CryptoMaterialError::BitVecError(
+                    "Not enough signatures to meet the threshold".to_string()
+                )
+            ));
+        }
+        let mut signed_public_keys = Vec::with_capacity(self.signatures.len());
+        let mut bitmap_index = 0;
+        for sig in &self.signatures {
+            while !bitmap_get_bit(self.bitmap, bitmap_index) {
+                bitmap_index += 1;
+            }
+            signed_public_keys.push((&public_key.public_keys[bitmap_index as usize], sig));
+            bitmap_index += 1;
+        }
+        Ok(signed_public_keys)
+    }
 }
 
 //////////////////////
@@ -503,31 +538,9 @@ impl Signature for MultiEd25519Signature {
     ) -> Result<()> {
         // Public keys should be validated to be safe against small subgroup attacks, etc.
         precondition!(has_tag!(public_key, ValidatedPublicKeyTag));
-        match bitmap_last_set_bit(self.bitmap) {
-            Some(last_bit) if last_bit as usize <= public_key.length() => (),
-            _ => {
-                return Err(anyhow!(
-                    "{}",
-                    CryptoMaterialError::BitVecError("Signature index is out of range".to_string())
-                ))
-            }
-        };
-        if bitmap_count_ones(self.bitmap) < public_key.threshold as u32 {
-            return Err(anyhow!(
-                "{}",
-                CryptoMaterialError::BitVecError(
-                    "Not enough signatures to meet the threshold".to_string()
-                )
-            ));
-        }
-        let mut bitmap_index = 0;
         // TODO use deterministic batch verification when gets available.
-        for sig in &self.signatures {
-            while !bitmap_get_bit(self.bitmap, bitmap_index) {
-                bitmap_index += 1;
-            }
-            sig.verify_arbitrary_msg(message, &public_key.public_keys[bitmap_index as usize])?;
-            bitmap_index += 1;
+        for (key, sig) in self.signed_public_keys(public_key)? {
+            sig.verify_arbitrary_msg(message, key)?;
         }
         Ok(())
     }
@@ -535,6 +548,24 @@ impl Signature for MultiEd25519Signature {
     fn to_bytes(&self) -> Vec<u8> {
         self.to_bytes()
     }
+
+    /// Batch verification of multi-signatures on the same message: once their bitmaps and
+    /// thresholds are checked, all their Ed25519 signatures are verified together with
+    /// `Ed25519Signature::batch_verify`.
+    fn batch_verify<T: CryptoHash + Serialize>(
+        message: &T,
+        keys_and_signatures: Vec<(Self::VerifyingKeyMaterial, Self)>,
+    ) -> Result<()> {
+        let mut ed25519_keys_and_signatures = vec![];
+        for (public_key, signature) in &keys_and_signatures {
+            // Public keys should be validated to be safe against small subgroup attacks, etc.
+            precondition!(has_tag!(public_key, ValidatedPublicKeyTag));
+            for (key, sig) in signature.signed_public_keys(public_key)? {
+                ed25519_keys_and_signatures.push((key.clone(), sig.clone()));
+            }
+        }
+        Ed25519Signature::batch_verify(message, ed25519_keys_and_signatures)
+    }
 }
 
 impl From<Ed25519Signature> for MultiEd25519Signature {
@@ -0,0 +1,422 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides an API for the ECDSA signature scheme over the secp256k1 curve as
+//! defined in [SEC 1](https://www.secg.org/sec1-v2.pdf), the curve and the keys used by Bitcoin
+//! and Ethereum wallets.
+//!
+//! Private keys are the raw 32-byte scalars that these wallets export, and public keys are in
+//! the 65-byte uncompressed SEC 1 encoding. The signed digest is the SHA3-256 hash of the
+//! message, and signatures are the 64-byte concatenation of their r and s components. As in
+//! Ethereum, a signature with a high s is rejected, since its low-s twin is equally valid.
+//!
+//! # Examples
+//!
+//! ```
+//! use diem_crypto_derive::{CryptoHasher, BCSCryptoHash};
+//! use diem_crypto::{
+//!     secp256k1::*,
+//!     traits::{Signature, SigningKey, Uniform},
+//! };
+//! use rand::{rngs::StdRng, SeedableRng};
+//! use serde::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize, CryptoHasher, BCSCryptoHash)]
+//! pub struct TestCryptoDocTest(String);
+//! let message = TestCryptoDocTest("Test message".to_string());
+//!
+//! let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+//! let private_key = Secp256k1PrivateKey::generate(&mut rng);
+//! let public_key: Secp256k1PublicKey = (&private_key).into();
+//! let signature = private_key.sign(&message);
+//! assert!(signature.verify(&message, &public_key).is_ok());
+//! ```
+//! **Note**: The above example generates a private key using a private function intended only for
+//! testing purposes. Production code should find an alternate means for secure key generation.
+
+use crate::{
+    hash::{CryptoHash, CryptoHasher, HashValue},
+    traits::*,
+};
+use anyhow::{anyhow, Result};
+use core::convert::TryFrom;
+use diem_crypto_derive::{DeserializeKey, SerializeKey, SilentDebug, SilentDisplay};
+use mirai_annotations::*;
+use serde::Serialize;
+use std::fmt;
+
+pub use libsecp256k1;
+
+/// The length of the Secp256k1PrivateKey
+pub const SECP256K1_PRIVATE_KEY_LENGTH: usize = libsecp256k1::util::SECRET_KEY_SIZE;
+/// The length of the Secp256k1PublicKey, in the uncompressed encoding
+pub const SECP256K1_PUBLIC_KEY_LENGTH: usize = libsecp256k1::util::FULL_PUBLIC_KEY_SIZE;
+/// The length of the Secp256k1Signature
+pub const SECP256K1_SIGNATURE_LENGTH: usize = libsecp256k1::util::SIGNATURE_SIZE;
+
+/// A secp256k1 ECDSA private key
+#[derive(DeserializeKey, SerializeKey, SilentDebug, SilentDisplay)]
+pub struct Secp256k1PrivateKey(libsecp256k1::SecretKey);
+
+#[cfg(feature = "assert-private-keys-not-cloneable")]
+static_assertions::assert_not_impl_any!(Secp256k1PrivateKey: Clone);
+
+#[cfg(any(test, feature = "cloneable-private-keys"))]
+impl Clone for Secp256k1PrivateKey {
+    fn clone(&self) -> Self {
+        let serialized: &[u8] = &(self.to_bytes());
+        Secp256k1PrivateKey::try_from(serialized).unwrap()
+    }
+}
+
+/// A secp256k1 ECDSA public key
+#[derive(DeserializeKey, Clone, SerializeKey)]
+pub struct Secp256k1PublicKey(libsecp256k1::PublicKey);
+
+#[cfg(mirai)]
+use crate::tags::ValidatedPublicKeyTag;
+#[cfg(not(mirai))]
+struct ValidatedPublicKeyTag {}
+
+/// A secp256k1 ECDSA signature, with a low s
+#[derive(DeserializeKey, Clone, SerializeKey)]
+pub struct Secp256k1Signature(libsecp256k1::Signature);
+
+impl Secp256k1PrivateKey {
+    /// The length of the Secp256k1PrivateKey
+    pub const LENGTH: usize = SECP256K1_PRIVATE_KEY_LENGTH;
+
+    /// Serialize a Secp256k1PrivateKey.
+    pub fn to_bytes(&self) -> [u8; SECP256K1_PRIVATE_KEY_LENGTH] {
+        self.0.serialize()
+    }
+
+    /// Private function aimed at minimizing code duplication between sign
+    /// methods of the SigningKey implementation. This should remain private.
+    fn sign_arbitrary_message(&self, message: &[u8]) -> Secp256k1Signature {
+        let (mut signature, _recovery_id) = libsecp256k1::sign(&digest(message), &self.0);
+        // The signatures produced already have a low s, this only guarantees it
+        signature.normalize_s();
+        Secp256k1Signature(signature)
+    }
+}
+
+impl Secp256k1PublicKey {
+    /// Serialize a Secp256k1PublicKey, in the uncompressed encoding.
+    pub fn to_bytes(&self) -> [u8; SECP256K1_PUBLIC_KEY_LENGTH] {
+        self.0.serialize()
+    }
+}
+
+impl Secp256k1Signature {
+    /// Serialize a Secp256k1Signature.
+    pub fn to_bytes(&self) -> [u8; SECP256K1_SIGNATURE_LENGTH] {
+        self.0.serialize()
+    }
+
+    /// Check that the s component of the signature is in the lower half of the curve order.
+    ///
+    /// ECDSA signatures are malleable: if (r, s) is a valid signature, then so is (r, -s), which
+    /// a third-party could compute without knowing the private key. As in Bitcoin and Ethereum,
+    /// only the signature with the lowest s is accepted.
+    pub fn check_malleability(bytes: &[u8]) -> std::result::Result<(), CryptoMaterialError> {
+        let signature = Secp256k1Signature::from_bytes_unchecked(bytes)?;
+        if signature.0.s.is_high() {
+            return Err(CryptoMaterialError::CanonicalRepresentationError);
+        }
+        Ok(())
+    }
+
+    /// Deserialize a Secp256k1Signature without the malleability check, only checking that
+    /// both components are lower than the curve order.
+    fn from_bytes_unchecked(
+        bytes: &[u8],
+    ) -> std::result::Result<Secp256k1Signature, CryptoMaterialError> {
+        if bytes.len() != SECP256K1_SIGNATURE_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+        libsecp256k1::Signature::parse_standard_slice(bytes)
+            .map(Secp256k1Signature)
+            .map_err(|_| CryptoMaterialError::DeserializationError)
+    }
+}
+
+/// The digest of a message signed or verified, its SHA3-256 hash.
+fn digest(message: &[u8]) -> libsecp256k1::Message {
+    libsecp256k1::Message::parse(HashValue::sha3_256_of(message).as_ref())
+}
+
+///////////////////////
+// PrivateKey Traits //
+///////////////////////
+
+impl PrivateKey for Secp256k1PrivateKey {
+    type PublicKeyMaterial = Secp256k1PublicKey;
+}
+
+impl SigningKey for Secp256k1PrivateKey {
+    type VerifyingKeyMaterial = Secp256k1PublicKey;
+    type SignatureMaterial = Secp256k1Signature;
+
+    fn sign<T: CryptoHash + Serialize>(&self, message: &T) -> Secp256k1Signature {
+        let mut bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
+        bcs::serialize_into(&mut bytes, &message)
+            .map_err(|_| CryptoMaterialError::SerializationError)
+            .expect("Serialization of signable material should not fail.");
+        Secp256k1PrivateKey::sign_arbitrary_message(self, bytes.as_ref())
+    }
+
+    #[cfg(any(test, feature = "fuzzing"))]
+    fn sign_arbitrary_message(&self, message: &[u8]) -> Secp256k1Signature {
+        Secp256k1PrivateKey::sign_arbitrary_message(self, message)
+    }
+}
+
+impl Uniform for Secp256k1PrivateKey {
+    fn generate<R>(rng: &mut R) -> Self
+    where
+        R: ::rand::RngCore + ::rand::CryptoRng,
+    {
+        // Zero and the scalars above the curve order aren't valid keys, which happens with a
+        // negligible probability
+        let mut bytes = [0u8; SECP256K1_PRIVATE_KEY_LENGTH];
+        loop {
+            rng.fill_bytes(&mut bytes);
+            if let Ok(secret_key) = libsecp256k1::SecretKey::parse(&bytes) {
+                return Secp256k1PrivateKey(secret_key);
+            }
+        }
+    }
+}
+
+impl PartialEq<Self> for Secp256k1PrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for Secp256k1PrivateKey {}
+
+impl TryFrom<&[u8]> for Secp256k1PrivateKey {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a Secp256k1PrivateKey. This method will also check for key validity, i.e.
+    /// that the scalar is neither zero nor above the curve order.
+    fn try_from(bytes: &[u8]) -> std::result::Result<Secp256k1PrivateKey, CryptoMaterialError> {
+        if bytes.len() != SECP256K1_PRIVATE_KEY_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+        libsecp256k1::SecretKey::parse_slice(bytes)
+            .map(Secp256k1PrivateKey)
+            .map_err(|_| CryptoMaterialError::DeserializationError)
+    }
+}
+
+impl Length for Secp256k1PrivateKey {
+    fn length(&self) -> usize {
+        Self::LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for Secp256k1PrivateKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+//////////////////////
+// PublicKey Traits //
+//////////////////////
+
+// Implementing From<&PrivateKey<...>> allows to derive a public key in a more elegant fashion
+impl From<&Secp256k1PrivateKey> for Secp256k1PublicKey {
+    fn from(private_key: &Secp256k1PrivateKey) -> Self {
+        Secp256k1PublicKey(libsecp256k1::PublicKey::from_secret_key(&private_key.0))
+    }
+}
+
+// We deduce PublicKey from this
+impl PublicKey for Secp256k1PublicKey {
+    type PrivateKeyMaterial = Secp256k1PrivateKey;
+}
+
+impl std::hash::Hash for Secp256k1PublicKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let encoded_pubkey = self.to_bytes();
+        state.write(&encoded_pubkey);
+    }
+}
+
+// Those are required by the implementation of hash above
+impl PartialEq for Secp256k1PublicKey {
+    fn eq(&self, other: &Secp256k1PublicKey) -> bool {
+        self.to_bytes()[..] == other.to_bytes()[..]
+    }
+}
+
+impl Eq for Secp256k1PublicKey {}
+
+// We deduce VerifyingKey from pointing to the signature material
+// we get the ability to do `pubkey.validate(msg, signature)`
+impl VerifyingKey for Secp256k1PublicKey {
+    type SigningKeyMaterial = Secp256k1PrivateKey;
+    type SignatureMaterial = Secp256k1Signature;
+}
+
+impl fmt::Display for Secp256k1PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.to_bytes()[..]))
+    }
+}
+
+impl fmt::Debug for Secp256k1PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secp256k1PublicKey({})", self)
+    }
+}
+
+impl TryFrom<&[u8]> for Secp256k1PublicKey {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a Secp256k1PublicKey from its uncompressed encoding. This method will also
+    /// check that the point is on the curve. The compressed encoding is rejected, so that a key
+    /// has a single encoding and a single authentication key.
+    fn try_from(bytes: &[u8]) -> std::result::Result<Secp256k1PublicKey, CryptoMaterialError> {
+        if bytes.len() != SECP256K1_PUBLIC_KEY_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+        let public_key =
+            libsecp256k1::PublicKey::parse_slice(bytes, Some(libsecp256k1::PublicKeyFormat::Full))
+                .map(Secp256k1PublicKey)
+                .map_err(|_| CryptoMaterialError::PointNotOnCurveError)?;
+        add_tag!(&public_key, ValidatedPublicKeyTag); // This key has gone through validity checks.
+        Ok(public_key)
+    }
+}
+
+impl Length for Secp256k1PublicKey {
+    fn length(&self) -> usize {
+        SECP256K1_PUBLIC_KEY_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for Secp256k1PublicKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+//////////////////////
+// Signature Traits //
+//////////////////////
+
+impl Signature for Secp256k1Signature {
+    type VerifyingKeyMaterial = Secp256k1PublicKey;
+    type SigningKeyMaterial = Secp256k1PrivateKey;
+
+    /// Verifies that the provided signature is valid for the provided message, the SHA3-256 hash
+    /// of its domain separated serialization being the digest signed.
+    fn verify<T: CryptoHash + Serialize>(
+        &self,
+        message: &T,
+        public_key: &Secp256k1PublicKey,
+    ) -> Result<()> {
+        precondition!(has_tag!(public_key, ValidatedPublicKeyTag));
+        let mut bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
+        bcs::serialize_into(&mut bytes, &message)
+            .map_err(|_| CryptoMaterialError::SerializationError)?;
+        Self::verify_arbitrary_msg(self, &bytes, public_key)
+    }
+
+    /// Checks that `self` is valid for an arbitrary &[u8] `message` using `public_key`.
+    /// Outside of this crate, this particular function should only be used for native signature
+    /// verification in move
+    fn verify_arbitrary_msg(&self, message: &[u8], public_key: &Secp256k1PublicKey) -> Result<()> {
+        precondition!(has_tag!(public_key, ValidatedPublicKeyTag));
+        Secp256k1Signature::check_malleability(&self.to_bytes())?;
+
+        if libsecp256k1::verify(&digest(message), &self.0, &public_key.0) {
+            Ok(())
+        } else {
+            Err(anyhow!("Secp256k1 signature verification failed"))
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl Length for Secp256k1Signature {
+    fn length(&self) -> usize {
+        SECP256K1_SIGNATURE_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for Secp256k1Signature {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl std::hash::Hash for Secp256k1Signature {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let encoded_signature = self.to_bytes();
+        state.write(&encoded_signature);
+    }
+}
+
+impl TryFrom<&[u8]> for Secp256k1Signature {
+    type Error = CryptoMaterialError;
+
+    fn try_from(bytes: &[u8]) -> std::result::Result<Secp256k1Signature, CryptoMaterialError> {
+        Secp256k1Signature::check_malleability(bytes)?;
+        Secp256k1Signature::from_bytes_unchecked(bytes)
+    }
+}
+
+// Those are required by the implementation of hash above
+impl PartialEq for Secp256k1Signature {
+    fn eq(&self, other: &Secp256k1Signature) -> bool {
+        self.to_bytes()[..] == other.to_bytes()[..]
+    }
+}
+
+impl Eq for Secp256k1Signature {}
+
+impl fmt::Display for Secp256k1Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.to_bytes()[..]))
+    }
+}
+
+impl fmt::Debug for Secp256k1Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secp256k1Signature({})", self)
+    }
+}
+
+#[cfg(any(test, feature = "fuzzing"))]
+use crate::test_utils::{self, KeyPair};
+
+/// Produces a uniformly random secp256k1 keypair from a seed
+#[cfg(any(test, feature = "fuzzing"))]
+pub fn keypair_strategy() -> impl Strategy<Value = KeyPair<Secp256k1PrivateKey, Secp256k1PublicKey>>
+{
+    test_utils::uniform_keypair_strategy::<Secp256k1PrivateKey, Secp256k1PublicKey>()
+}
+
+#[cfg(any(test, feature = "fuzzing"))]
+use proptest::prelude::*;
+
+#[cfg(any(test, feature = "fuzzing"))]
+impl proptest::arbitrary::Arbitrary for Secp256k1PublicKey {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        crate::test_utils::uniform_keypair_strategy::<Secp256k1PrivateKey, Secp256k1PublicKey>()
+            .prop_map(|v| v.public_key)
+            .boxed()
+    }
+}
@@ -0,0 +1,510 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hierarchical deterministic derivation of Ed25519 keys, as specified by
+//! [SLIP-0010](https://github.com/satoshilabs/slips/blob/master/slip-0010.md), the adaptation of
+//! [BIP32](https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki) to Ed25519.
+//!
+//! A master key and its chain code are derived from a seed, and a child key from its parent key
+//! and chain code, so that a whole tree of keys is recovered from the seed alone. As Ed25519 has
+//! no public child key derivation, only hardened children are supported: a derivation path is
+//! written `m/44'/0'/0'`, every index being hardened.
+//!
+//! The seed is usually derived from a [BIP39](https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki)
+//! mnemonic with `mnemonic_to_seed`, which is how wallets recover the same keys from the same
+//! words.
+//!
+//! # Example
+//!
+//! ```
+//! use diem_crypto::slip10::{mnemonic_to_seed, DerivationPath, ExtendedPrivateKey};
+//! use std::str::FromStr;
+//!
+//! let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+//!                 abandon abandon about";
+//! let seed = mnemonic_to_seed(mnemonic, "").unwrap();
+//! let path = DerivationPath::from_str("m/44'/0'/0'").unwrap();
+//! let key = ExtendedPrivateKey::from_seed(&seed)
+//!     .unwrap()
+//!     .derive_path(&path)
+//!     .unwrap();
+//! assert_eq!(key.depth(), 3);
+//! ```
+
+use crate::ed25519::{Ed25519PrivateKey, Ed25519PublicKey, ED25519_PRIVATE_KEY_LENGTH};
+use diem_crypto_derive::SilentDebug;
+use hmac::{Hmac, Mac, NewMac};
+use sha2::{Digest, Sha256, Sha512};
+use std::{convert::TryFrom, fmt, str::FromStr};
+use thiserror::Error;
+
+/// The HMAC key deriving the master key from the seed, for Ed25519.
+const ED25519_SEED_KEY: &[u8] = b"ed25519 seed";
+
+/// The indices from this offset on are hardened.
+pub const HARDENED_OFFSET: u32 = 0x8000_0000;
+
+/// The length of a chain code.
+pub const CHAIN_CODE_LENGTH: usize = 32;
+
+/// BIP32 seeds are between 128 and 512 bits.
+const MINIMUM_SEED_LENGTH: usize = 16;
+const MAXIMUM_SEED_LENGTH: usize = 64;
+
+/// The number of PBKDF2 iterations deriving a seed from a BIP39 mnemonic.
+const MNEMONIC_PBKDF2_ITERATIONS: u32 = 2048;
+/// The length of the seed derived from a BIP39 mnemonic.
+pub const MNEMONIC_SEED_LENGTH: usize = 64;
+/// Every word of a BIP39 mnemonic encodes 11 bits.
+const MNEMONIC_WORD_BITS: usize = 11;
+
+/// An error that occurs during the derivation of a key.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum Slip10Error {
+    /// The seed isn't between 16 and 64 bytes.
+    #[error("Seed length should be between 16 and 64 bytes, found {0}")]
+    InvalidSeedLength(usize),
+    /// The derivation path can't be parsed.
+    #[error("Invalid derivation path {0}: {1}")]
+    InvalidPath(String, String),
+    /// The index of a child is too big for a hardened index.
+    #[error("Child index {0} is out of range")]
+    IndexOutOfRange(u32),
+    /// The key is the 255th descendant of the master key already.
+    #[error("Maximum derivation depth exceeded")]
+    MaxDepthExceeded,
+    /// The mnemonic isn't a valid BIP39 mnemonic in English.
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+}
+
+/// A path from the master key to one of its descendants, as the sequence of the hardened indices
+/// of the children, the hardening offset not included.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// Creates a path from the indices of the children, the hardening offset not included.
+    pub fn new(indices: Vec<u32>) -> Result<Self, Slip10Error> {
+        if let Some(index) = indices.iter().find(|index| **index >= HARDENED_OFFSET) {
+            return Err(Slip10Error::IndexOutOfRange(*index));
+        }
+        Ok(Self(indices))
+    }
+
+    /// The indices of the children, the hardening offset not included.
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = Slip10Error;
+
+    /// Parses a path such as `m/44'/0'/0'`. The indices must be hardened, with either `'` or `H`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid_path = |reason: &str| Slip10Error::InvalidPath(s.into(), reason.into());
+
+        let mut segments = s.split('/');
+        if segments.next() != Some("m") {
+            return Err(invalid_path("the path should start with m"));
+        }
+        let indices = segments
+            .map(|segment| {
+                let index = segment
+                    .strip_suffix('\'')
+                    .or_else(|| segment.strip_suffix('H'))
+                    .ok_or_else(|| invalid_path("only hardened indices are supported"))?;
+                // u32::from_str accepts a leading +, which isn't part of the notation
+                if !index.bytes().all(|byte| byte.is_ascii_digit()) {
+                    return Err(invalid_path("an index should be a decimal number"));
+                }
+                index
+                    .parse::<u32>()
+                    .map_err(|_| invalid_path("an index should be a decimal number"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(indices)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            write!(f, "/{}'", index)?;
+        }
+        Ok(())
+    }
+}
+
+/// An Ed25519 private key with the chain code deriving its children.
+#[derive(SilentDebug)]
+pub struct ExtendedPrivateKey {
+    depth: u8,
+    private_key: Ed25519PrivateKey,
+    chain_code: [u8; CHAIN_CODE_LENGTH],
+}
+
+impl ExtendedPrivateKey {
+    /// Derives the master key from a seed of 16 to 64 bytes.
+    pub fn from_seed(seed: &[u8]) -> Result<Self, Slip10Error> {
+        if seed.len() < MINIMUM_SEED_LENGTH || seed.len() > MAXIMUM_SEED_LENGTH {
+            return Err(Slip10Error::InvalidSeedLength(seed.len()));
+        }
+        Ok(Self::from_hmac(0, ED25519_SEED_KEY, &[seed]))
+    }
+
+    /// Derives the hardened child of the given index, the hardening offset not included.
+    pub fn derive_child(&self, index: u32) -> Result<Self, Slip10Error> {
+        if index >= HARDENED_OFFSET {
+            return Err(Slip10Error::IndexOutOfRange(index));
+        }
+        let depth = self
+            .depth
+            .checked_add(1)
+            .ok_or(Slip10Error::MaxDepthExceeded)?;
+        let hardened_index = (index | HARDENED_OFFSET).to_be_bytes();
+        Ok(Self::from_hmac(
+            depth,
+            &self.chain_code,
+            &[&[0u8], &self.private_key.to_bytes(), &hardened_index],
+        ))
+    }
+
+    /// Derives the descendant at the end of the path.
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, Slip10Error> {
+        let mut key = self.duplicate();
+        for index in path.indices() {
+            key = key.derive_child(*index)?;
+        }
+        Ok(key)
+    }
+
+    /// The number of derivations from the master key.
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// The private key.
+    pub fn private_key(&self) -> &Ed25519PrivateKey {
+        &self.private_key
+    }
+
+    /// The public key of the private key.
+    pub fn public_key(&self) -> Ed25519PublicKey {
+        Ed25519PublicKey::from(&self.private_key)
+    }
+
+    /// The chain code deriving the children of the key.
+    pub fn chain_code(&self) -> &[u8; CHAIN_CODE_LENGTH] {
+        &self.chain_code
+    }
+
+    /// Copies the key, as private keys are only cloneable in tests.
+    fn duplicate(&self) -> Self {
+        Self {
+            depth: self.depth,
+            private_key: Ed25519PrivateKey::try_from(&self.private_key.to_bytes()[..])
+                .expect("A valid private key should deserialize"),
+            chain_code: self.chain_code,
+        }
+    }
+
+    /// Splits the HMAC-SHA512 of the data into a private key and a chain code.
+    fn from_hmac(depth: u8, key: &[u8], data: &[&[u8]]) -> Self {
+        let mut mac = Hmac::<Sha512>::new_varkey(key).expect("HMAC should take keys of any length");
+        for bytes in data {
+            mac.update(bytes);
+        }
+        let output = mac.finalize().into_bytes();
+        let (private_key, chain_code) = output.split_at(ED25519_PRIVATE_KEY_LENGTH);
+
+        let mut chain_code_bytes = [0u8; CHAIN_CODE_LENGTH];
+        chain_code_bytes.copy_from_slice(chain_code);
+        Self {
+            depth,
+            // Any 32 bytes are an Ed25519 private key
+            private_key: Ed25519PrivateKey::try_from(private_key)
+                .expect("32 bytes should be a valid private key"),
+            chain_code: chain_code_bytes,
+        }
+    }
+}
+
+/// Derives the seed of a BIP39 mnemonic, protected by an optional passphrase (empty otherwise).
+///
+/// The mnemonic must be made of 12, 15, 18, 21 or 24 words of the English word list, with a
+/// valid checksum, so that a typo fails instead of deriving the keys of another wallet. The words
+/// are separated by single spaces before the derivation. Since the Unicode normalization of BIP39
+/// isn't implemented, the passphrase must be ASCII.
+pub fn mnemonic_to_seed(
+    mnemonic: &str,
+    passphrase: &str,
+) -> Result<[u8; MNEMONIC_SEED_LENGTH], Slip10Error> {
+    if !passphrase.is_ascii() {
+        return Err(Slip10Error::InvalidMnemonic(
+            "the passphrase should be ASCII".into(),
+        ));
+    }
+    let words: Vec<_> = mnemonic.split_whitespace().collect();
+    validate_mnemonic(&words)?;
+    let mnemonic = words.join(" ");
+    let salt = format!("mnemonic{}", passphrase);
+
+    let mut seed = [0u8; MNEMONIC_SEED_LENGTH];
+    pbkdf2::pbkdf2::<Hmac<Sha512>>(
+        mnemonic.as_bytes(),
+        salt.as_bytes(),
+        MNEMONIC_PBKDF2_ITERATIONS,
+        &mut seed,
+    );
+    Ok(seed)
+}
+
+/// Checks that the words are from the English word list, and that the checksum in the bits they
+/// encode matches the entropy before it.
+fn validate_mnemonic(words: &[&str]) -> Result<(), Slip10Error> {
+    if !(12..=24).contains(&words.len()) || words.len() % 3 != 0 {
+        return Err(Slip10Error::InvalidMnemonic(format!(
+            "the mnemonic should have 12, 15, 18, 21 or 24 words, found {}",
+            words.len()
+        )));
+    }
+
+    let mut bytes = vec![0u8; (words.len() * MNEMONIC_WORD_BITS + 7) / 8];
+    for (i, word) in words.iter().enumerate() {
+        let index = MNEMONIC_WORDS
+            .binary_search(word)
+            .map_err(|_| Slip10Error::InvalidMnemonic(format!("unknown word {}", word)))?;
+        for bit in 0..MNEMONIC_WORD_BITS {
+            if (index >> (MNEMONIC_WORD_BITS - 1 - bit)) & 1 == 1 {
+                let position = i * MNEMONIC_WORD_BITS + bit;
+                bytes[position / 8] |= 0x80 >> (position % 8);
+            }
+        }
+    }
+
+    // Every 32 bits of entropy come with 1 bit of checksum, which fits in the byte following them
+    let checksum_bits = words.len() / 3;
+    let (entropy, checksum) = bytes.split_at(checksum_bits * 4);
+    if checksum[0] >> (8 - checksum_bits) != Sha256::digest(entropy)[0] >> (8 - checksum_bits) {
+        return Err(Slip10Error::InvalidMnemonic("invalid checksum".into()));
+    }
+    Ok(())
+}
+
+/// The BIP39 English word list, sorted.
+pub const MNEMONIC_WORDS: [&str; 2048] = [
+    "abandon", "ability", "able", "about", "above", "absent", "absorb", "abstract", "absurd",
+    "abuse", "access", "accident", "account", "accuse", "achieve", "acid", "acoustic", "acquire",
+    "across", "act", "action", "actor", "actress", "actual", "adapt", "add", "addict", "address",
+    "adjust", "admit", "adult", "advance", "advice", "aerobic", "affair", "afford", "afraid",
+    "again", "age", "agent", "agree", "ahead", "aim", "air", "airport", "aisle", "alarm", "album",
+    "alcohol", "alert", "alien", "all", "alley", "allow", "almost", "alone", "alpha", "already",
+    "also", "alter", "always", "amateur", "amazing", "among", "amount", "amused", "analyst",
+    "anchor", "ancient", "anger", "angle", "angry", "animal", "ankle", "announce", "annual",
+    "another", "answer", "antenna", "antique", "anxiety", "any", "apart", "apology", "appear",
+    "apple", "approve", "april", "arch", "arctic", "area", "arena", "argue", "arm", "armed",
+    "armor", "army", "around", "arrange", "arrest", "arrive", "arrow", "art", "artefact", "artist",
+    "artwork", "ask", "aspect", "assault", "asset", "assist", "assume", "asthma", "athlete",
+    "atom", "attack", "attend", "attitude", "attract", "auction", "audit", "august", "aunt",
+    "author", "auto", "autumn", "average", "avocado", "avoid", "awake", "aware", "away", "awesome",
+    "awful", "awkward", "axis", "baby", "bachelor", "bacon", "badge", "bag", "balance", "balcony",
+    "ball", "bamboo", "banana", "banner", "bar", "barely", "bargain", "barrel", "base", "basic",
+    "basket", "battle", "beach", "bean", "beauty", "because", "become", "beef", "before", "begin",
+    "behave", "behind", "believe", "below", "belt", "bench", "benefit", "best", "betray", "better",
+    "between", "beyond", "bicycle", "bid", "bike", "bind", "biology", "bird", "birth", "bitter",
+    "black", "blade", "blame", "blanket", "blast", "bleak", "bless", "blind", "blood", "blossom",
+    "blouse", "blue", "blur", "blush", "board", "boat", "body", "boil", "bomb", "bone", "bonus",
+    "book", "boost", "border", "boring", "borrow", "boss", "bottom", "bounce", "box", "boy",
+    "bracket", "brain", "brand", "brass", "brave", "bread", "breeze", "brick", "bridge", "brief",
+    "bright", "bring", "brisk", "broccoli", "broken", "bronze", "broom", "brother", "brown",
+    "brush", "bubble", "buddy", "budget", "buffalo", "build", "bulb", "bulk", "bullet", "bundle",
+    "bunker", "burden", "burger", "burst", "bus", "business", "busy", "butter", "buyer", "buzz",
+    "cabbage", "cabin", "cable", "cactus", "cage", "cake", "call", "calm", "camera", "camp", "can",
+    "canal", "cancel", "candy", "cannon", "canoe", "canvas", "canyon", "capable", "capital",
+    "captain", "car", "carbon", "card", "cargo", "carpet", "carry", "cart", "case", "cash",
+    "casino", "castle", "casual", "cat", "catalog", "catch", "category", "cattle", "caught",
+    "cause", "caution", "cave", "ceiling", "celery", "cement", "census", "century", "cereal",
+    "certain", "chair", "chalk", "champion", "change", "chaos", "chapter", "charge", "chase",
+    "chat", "cheap", "check", "cheese", "chef", "cherry", "chest", "chicken", "chief", "child",
+    "chimney", "choice", "choose", "chronic", "chuckle", "chunk", "churn", "cigar", "cinnamon",
+    "circle", "citizen", "city", "civil", "claim", "clap", "clarify", "claw", "clay", "clean",
+    "clerk", "clever", "click", "client", "cliff", "climb", "clinic", "clip", "clock", "clog",
+    "close", "cloth", "cloud", "clown", "club", "clump", "cluster", "clutch", "coach", "coast",
+    "coconut", "code", "coffee", "coil", "coin", "collect", "color", "column", "combine", "come",
+    "comfort", "comic", "common", "company", "concert", "conduct", "confirm", "congress",
+    "connect", "consider", "control", "convince", "cook", "cool", "copper", "copy", "coral",
+    "core", "corn", "correct", "cost", "cotton", "couch", "country", "couple", "course", "cousin",
+    "cover", "coyote", "crack", "cradle", "craft", "cram", "crane", "crash", "crater", "crawl",
+    "crazy", "cream", "credit", "creek", "crew", "cricket", "crime", "crisp", "critic", "crop",
+    "cross", "crouch", "crowd", "crucial", "cruel", "cruise", "crumble", "crunch", "crush", "cry",
+    "crystal", "cube", "culture", "cup", "cupboard", "curious", "current", "curtain", "curve",
+    "cushion", "custom", "cute", "cycle", "dad", "damage", "damp", "dance", "danger", "daring",
+    "dash", "daughter", "dawn", "day", "deal", "debate", "debris", "decade", "december", "decide",
+    "decline", "decorate", "decrease", "deer", "defense", "define", "defy", "degree", "delay",
+    "deliver", "demand", "demise", "denial", "dentist", "deny", "depart", "depend", "deposit",
+    "depth", "deputy", "derive", "describe", "desert", "design", "desk", "despair", "destroy",
+    "detail", "detect", "develop", "device", "devote", "diagram", "dial", "diamond", "diary",
+    "dice", "diesel", "diet", "differ", "digital", "dignity", "dilemma", "dinner", "dinosaur",
+    "direct", "dirt", "disagree", "discover", "disease", "dish", "dismiss", "disorder", "display",
+    "distance", "divert", "divide", "divorce", "dizzy", "doctor", "document", "dog", "doll",
+    "dolphin", "domain", "donate", "donkey", "donor", "door", "dose", "double", "dove", "draft",
+    "dragon", "drama", "drastic", "draw", "dream", "dress", "drift", "drill", "drink", "drip",
+    "drive", "drop", "drum", "dry", "duck", "dumb", "dune", "during", "dust", "dutch", "duty",
+    "dwarf", "dynamic", "eager", "eagle", "early", "earn", "earth", "easily", "east", "easy",
+    "echo", "ecology", "economy", "edge", "edit", "educate", "effort", "egg", "eight", "either",
+    "elbow", "elder", "electric", "elegant", "element", "elephant", "elevator", "elite", "else",
+    "embark", "embody", "embrace", "emerge", "emotion", "employ", "empower", "empty", "enable",
+    "enact", "end", "endless", "endorse", "enemy", "energy", "enforce", "engage", "engine",
+    "enhance", "enjoy", "enlist", "enough", "enrich", "enroll", "ensure", "enter", "entire",
+    "entry", "envelope", "episode", "equal", "equip", "era", "erase", "erode", "erosion", "error",
+    "erupt", "escape", "essay", "essence", "estate", "eternal", "ethics", "evidence", "evil",
+    "evoke", "evolve", "exact", "example", "excess", "exchange", "excite", "exclude", "excuse",
+    "execute", "exercise", "exhaust", "exhibit", "exile", "exist", "exit", "exotic", "expand",
+    "expect", "expire", "explain", "expose", "express", "extend", "extra", "eye", "eyebrow",
+    "fabric", "face", "faculty", "fade", "faint", "faith", "fall", "false", "fame", "family",
+    "famous", "fan", "fancy", "fantasy", "farm", "fashion", "fat", "fatal", "father", "fatigue",
+    "fault", "favorite", "feature", "february", "federal", "fee", "feed", "feel", "female",
+    "fence", "festival", "fetch", "fever", "few", "fiber", "fiction", "field", "figure", "file",
+    "film", "filter", "final", "find", "fine", "finger", "finish", "fire", "firm", "first",
+    "fiscal", "fish", "fit", "fitness", "fix", "flag", "flame", "flash", "flat", "flavor", "flee",
+    "flight", "flip", "float", "flock", "floor", "flower", "fluid", "flush", "fly", "foam",
+    "focus", "fog", "foil", "fold", "follow", "food", "foot", "force", "forest", "forget", "fork",
+    "fortune", "forum", "forward", "fossil", "foster", "found", "fox", "fragile", "frame",
+    "frequent", "fresh", "friend", "fringe", "frog", "front", "frost", "frown", "frozen", "fruit",
+    "fuel", "fun", "funny", "furnace", "fury", "future", "gadget", "gain", "galaxy", "gallery",
+    "game", "gap", "garage", "garbage", "garden", "garlic", "garment", "gas", "gasp", "gate",
+    "gather", "gauge", "gaze", "general", "genius", "genre", "gentle", "genuine", "gesture",
+    "ghost", "giant", "gift", "giggle", "ginger", "giraffe", "girl", "give", "glad", "glance",
+    "glare", "glass", "glide", "glimpse", "globe", "gloom", "glory", "glove", "glow", "glue",
+    "goat", "goddess", "gold", "good", "goose", "gorilla", "gospel", "gossip", "govern", "gown",
+    "grab", "grace", "grain", "grant", "grape", "grass", "gravity", "great", "green", "grid",
+    "grief", "grit", "grocery", "group", "grow", "grunt", "guard", "guess", "guide", "guilt",
+    "guitar", "gun", "gym", "habit", "hair", "half", "hammer", "hamster", "hand", "happy",
+    "harbor", "hard", "harsh", "harvest", "hat", "have", "hawk", "hazard", "head", "health",
+    "heart", "heavy", "hedgehog", "height", "hello", "helmet", "help", "hen", "hero", "hidden",
+    "high", "hill", "hint", "hip", "hire", "history", "hobby", "hockey", "hold", "hole", "holiday",
+    "hollow", "home", "honey", "hood", "hope", "horn", "horror", "horse", "hospital", "host",
+    "hotel", "hour", "hover", "hub", "huge", "human", "humble", "humor", "hundred", "hungry",
+    "hunt", "hurdle", "hurry", "hurt", "husband", "hybrid", "ice", "icon", "idea", "identify",
+    "idle", "ignore", "ill", "illegal", "illness", "image", "imitate", "immense", "immune",
+    "impact", "impose", "improve", "impulse", "inch", "include", "income", "increase", "index",
+    "indicate", "indoor", "industry", "infant", "inflict", "inform", "inhale", "inherit",
+    "initial", "inject", "injury", "inmate", "inner", "innocent", "input", "inquiry", "insane",
+    "insect", "inside", "inspire", "install", "intact", "interest", "into", "invest", "invite",
+    "involve", "iron", "island", "isolate", "issue", "item", "ivory", "jacket", "jaguar", "jar",
+    "jazz", "jealous", "jeans", "jelly", "jewel", "job", "join", "joke", "journey", "joy", "judge",
+    "juice", "jump", "jungle", "junior", "junk", "just", "kangaroo", "keen", "keep", "ketchup",
+    "key", "kick", "kid", "kidney", "kind", "kingdom", "kiss", "kit", "kitchen", "kite", "kitten",
+    "kiwi", "knee", "knife", "knock", "know", "lab", "label", "labor", "ladder", "lady", "lake",
+    "lamp", "language", "laptop", "large", "later", "latin", "laugh", "laundry", "lava", "law",
+    "lawn", "lawsuit", "layer", "lazy", "leader", "leaf", "learn", "leave", "lecture", "left",
+    "leg", "legal", "legend", "leisure", "lemon", "lend", "length", "lens", "leopard", "lesson",
+    "letter", "level", "liar", "liberty", "library", "license", "life", "lift", "light", "like",
+    "limb", "limit", "link", "lion", "liquid", "list", "little", "live", "lizard", "load", "loan",
+    "lobster", "local", "lock", "logic", "lonely", "long", "loop", "lottery", "loud", "lounge",
+    "love", "loyal", "lucky", "luggage", "lumber", "lunar", "lunch", "luxury", "lyrics", "machine",
+    "mad", "magic", "magnet", "maid", "mail", "main", "major", "make", "mammal", "man", "manage",
+    "mandate", "mango", "mansion", "manual", "maple", "marble", "march", "margin", "marine",
+    "market", "marriage", "mask", "mass", "master", "match", "material", "math", "matrix",
+    "matter", "maximum", "maze", "meadow", "mean", "measure", "meat", "mechanic", "medal", "media",
+    "melody", "melt", "member", "memory", "mention", "menu", "mercy", "merge", "merit", "merry",
+    "mesh", "message", "metal", "method", "middle", "midnight", "milk", "million", "mimic", "mind",
+    "minimum", "minor", "minute", "miracle", "mirror", "misery", "miss", "mistake", "mix", "mixed",
+    "mixture", "mobile", "model", "modify", "mom", "moment", "monitor", "monkey", "monster",
+    "month", "moon", "moral", "more", "morning", "mosquito", "mother", "motion", "motor",
+    "mountain", "mouse", "move", "movie", "much", "muffin", "mule", "multiply", "muscle", "museum",
+    "mushroom", "music", "must", "mutual", "myself", "mystery", "myth", "naive", "name", "napkin",
+    "narrow", "nasty", "nation", "nature", "near", "neck", "need", "negative", "neglect",
+    "neither", "nephew", "nerve", "nest", "net", "network", "neutral", "never", "news", "next",
+    "nice", "night", "noble", "noise", "nominee", "noodle", "normal", "north", "nose", "notable",
+    "note", "nothing", "notice", "novel", "now", "nuclear", "number", "nurse", "nut", "oak",
+    "obey", "object", "oblige", "obscure", "observe", "obtain", "obvious", "occur", "ocean",
+    "october", "odor", "off", "offer", "office", "often", "oil", "okay", "old", "olive", "olympic",
+    "omit", "once", "one", "onion", "online", "only", "open", "opera", "opinion", "oppose",
+    "option", "orange", "orbit", "orchard", "order", "ordinary", "organ", "orient", "original",
+    "orphan", "ostrich", "other", "outdoor", "outer", "output", "outside", "oval", "oven", "over",
+    "own", "owner", "oxygen", "oyster", "ozone", "pact", "paddle", "page", "pair", "palace",
+    "palm", "panda", "panel", "panic", "panther", "paper", "parade", "parent", "park", "parrot",
+    "party", "pass", "patch", "path", "patient", "patrol", "pattern", "pause", "pave", "payment",
+    "peace", "peanut", "pear", "peasant", "pelican", "pen", "penalty", "pencil", "people",
+    "pepper", "perfect", "permit", "person", "pet", "phone", "photo", "phrase", "physical",
+    "piano", "picnic", "picture", "piece", "pig", "pigeon", "pill", "pilot", "pink", "pioneer",
+    "pipe", "pistol", "pitch", "pizza", "place", "planet", "plastic", "plate", "play", "please",
+    "pledge", "pluck", "plug", "plunge", "poem", "poet", "point", "polar", "pole", "police",
+    "pond", "pony", "pool", "popular", "portion", "position", "possible", "post", "potato",
+    "pottery", "poverty", "powder", "power", "practice", "praise", "predict", "prefer", "prepare",
+    "present", "pretty", "prevent", "price", "pride", "primary", "print", "priority", "prison",
+    "private", "prize", "problem", "process", "produce", "profit", "program", "project", "promote",
+    "proof", "property", "prosper", "protect", "proud", "provide", "public", "pudding", "pull",
+    "pulp", "pulse", "pumpkin", "punch", "pupil", "puppy", "purchase", "purity", "purpose",
+    "purse", "push", "put", "puzzle", "pyramid", "quality", "quantum", "quarter", "question",
+    "quick", "quit", "quiz", "quote", "rabbit", "raccoon", "race", "rack", "radar", "radio",
+    "rail", "rain", "raise", "rally", "ramp", "ranch", "random", "range", "rapid", "rare", "rate",
+    "rather", "raven", "raw", "razor", "ready", "real", "reason", "rebel", "rebuild", "recall",
+    "receive", "recipe", "record", "recycle", "reduce", "reflect", "reform", "refuse", "region",
+    "regret", "regular", "reject", "relax", "release", "relief", "rely", "remain", "remember",
+    "remind", "remove", "render", "renew", "rent", "reopen", "repair", "repeat", "replace",
+    "report", "require", "rescue", "resemble", "resist", "resource", "response", "result",
+    "retire", "retreat", "return", "reunion", "reveal", "review", "reward", "rhythm", "rib",
+    "ribbon", "rice", "rich", "ride", "ridge", "rifle", "right", "rigid", "ring", "riot", "ripple",
+    "risk", "ritual", "rival", "river", "road", "roast", "robot", "robust", "rocket", "romance",
+    "roof", "rookie", "room", "rose", "rotate", "rough", "round", "route", "royal", "rubber",
+    "rude", "rug", "rule", "run", "runway", "rural", "sad", "saddle", "sadness", "safe", "sail",
+    "salad", "salmon", "salon", "salt", "salute", "same", "sample", "sand", "satisfy", "satoshi",
+    "sauce", "sausage", "save", "say", "scale", "scan", "scare", "scatter", "scene", "scheme",
+    "school", "science", "scissors", "scorpion", "scout", "scrap", "screen", "script", "scrub",
+    "sea", "search", "season", "seat", "second", "secret", "section", "security", "seed", "seek",
+    "segment", "select", "sell", "seminar", "senior", "sense", "sentence", "series", "service",
+    "session", "settle", "setup", "seven", "shadow", "shaft", "shallow", "share", "shed", "shell",
+    "sheriff", "shield", "shift", "shine", "ship", "shiver", "shock", "shoe", "shoot", "shop",
+    "short", "shoulder", "shove", "shrimp", "shrug", "shuffle", "shy", "sibling", "sick", "side",
+    "siege", "sight", "sign", "silent", "silk", "silly", "silver", "similar", "simple", "since",
+    "sing", "siren", "sister", "situate", "six", "size", "skate", "sketch", "ski", "skill", "skin",
+    "skirt", "skull", "slab", "slam", "sleep", "slender", "slice", "slide", "slight", "slim",
+    "slogan", "slot", "slow", "slush", "small", "smart", "smile", "smoke", "smooth", "snack",
+    "snake", "snap", "sniff", "snow", "soap", "soccer", "social", "sock", "soda", "soft", "solar",
+    "soldier", "solid", "solution", "solve", "someone", "song", "soon", "sorry", "sort", "soul",
+    "sound", "soup", "source", "south", "space", "spare", "spatial", "spawn", "speak", "special",
+    "speed", "spell", "spend", "sphere", "spice", "spider", "spike", "spin", "spirit", "split",
+    "spoil", "sponsor", "spoon", "sport", "spot", "spray", "spread", "spring", "spy", "square",
+    "squeeze", "squirrel", "stable", "stadium", "staff", "stage", "stairs", "stamp", "stand",
+    "start", "state", "stay", "steak", "steel", "stem", "step", "stereo", "stick", "still",
+    "sting", "stock", "stomach", "stone", "stool", "story", "stove", "strategy", "street",
+    "strike", "strong", "struggle", "student", "stuff", "stumble", "style", "subject", "submit",
+    "subway", "success", "such", "sudden", "suffer", "sugar", "suggest", "suit", "summer", "sun",
+    "sunny", "sunset", "super", "supply", "supreme", "sure", "surface", "surge", "surprise",
+    "surround", "survey", "suspect", "sustain", "swallow", "swamp", "swap", "swarm", "swear",
+    "sweet", "swift", "swim", "swing", "switch", "sword", "symbol", "symptom", "syrup", "system",
+    "table", "tackle", "tag", "tail", "talent", "talk", "tank", "tape", "target", "task", "taste",
+    "tattoo", "taxi", "teach", "team", "tell", "ten", "tenant", "tennis", "tent", "term", "test",
+    "text", "thank", "that", "theme", "then", "theory", "there", "they", "thing", "this",
+    "thought", "three", "thrive", "throw", "thumb", "thunder", "ticket", "tide", "tiger", "tilt",
+    "timber", "time", "tiny", "tip", "tired", "tissue", "title", "toast", "tobacco", "today",
+    "toddler", "toe", "together", "toilet", "token", "tomato", "tomorrow", "tone", "tongue",
+    "tonight", "tool", "tooth", "top", "topic", "topple", "torch", "tornado", "tortoise", "toss",
+    "total", "tourist", "toward", "tower", "town", "toy", "track", "trade", "traffic", "tragic",
+    "train", "transfer", "trap", "trash", "travel", "tray", "treat", "tree", "trend", "trial",
+    "tribe", "trick", "trigger", "trim", "trip", "trophy", "trouble", "truck", "true", "truly",
+    "trumpet", "trust", "truth", "try", "tube", "tuition", "tumble", "tuna", "tunnel", "turkey",
+    "turn", "turtle", "twelve", "twenty", "twice", "twin", "twist", "two", "type", "typical",
+    "ugly", "umbrella", "unable", "unaware", "uncle", "uncover", "under", "undo", "unfair",
+    "unfold", "unhappy", "uniform", "unique", "unit", "universe", "unknown", "unlock", "until",
+    "unusual", "unveil", "update", "upgrade", "uphold", "upon", "upper", "upset", "urban", "urge",
+    "usage", "use", "used", "useful", "useless", "usual", "utility", "vacant", "vacuum", "vague",
+    "valid", "valley", "valve", "van", "vanish", "vapor", "various", "vast", "vault", "vehicle",
+    "velvet", "vendor", "venture", "venue", "verb", "verify", "version", "very", "vessel",
+    "veteran", "viable", "vibrant", "vicious", "victory", "video", "view", "village", "vintage",
+    "violin", "virtual", "virus", "visa", "visit", "visual", "vital", "vivid", "vocal", "voice",
+    "void", "volcano", "volume", "vote", "voyage", "wage", "wagon", "wait", "walk", "wall",
+    "walnut", "want", "warfare", "warm", "warrior", "wash", "wasp", "waste", "water", "wave",
+    "way", "wealth", "weapon", "wear", "weasel", "weather", "web", "wedding", "weekend", "weird",
+    "welcome", "west", "wet", "whale", "what", "wheat", "wheel", "when", "where", "whip",
+    "whisper", "wide", "width", "wife", "wild", "will", "win", "window", "wine", "wing", "wink",
+    "winner", "winter", "wire", "wisdom", "wise", "wish", "witness", "wolf", "woman", "wonder",
+    "wood", "wool", "word", "work", "world", "worry", "worth", "wrap", "wreck", "wrestle", "wrist",
+    "write", "wrong", "yard", "year", "yellow", "you", "young", "youth", "zebra", "zero", "zone",
+    "zoo",
+];
@@ -0,0 +1,144 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    bls12381::{
+        BLS12381PrivateKey, BLS12381ProofOfPossession, BLS12381PublicKey, BLS12381Signature,
+        BLS12381_PUBLIC_KEY_LENGTH, BLS12381_SIGNATURE_LENGTH,
+    },
+    test_utils::{random_serializable_struct, uniform_keypair_strategy, TestDiemCrypto, TEST_SEED},
+    traits::*,
+};
+
+use core::convert::TryFrom;
+use proptest::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+// Helper function to generate N BLS12-381 private keys.
+fn generate_keys(n: usize) -> Vec<BLS12381PrivateKey> {
+    let mut rng = StdRng::from_seed(TEST_SEED);
+    (0..n)
+        .map(|_| BLS12381PrivateKey::generate(&mut rng))
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn test_signature_verification_from_struct(
+        message in random_serializable_struct(),
+        keypair in uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>()
+    ) {
+        let signature = keypair.private_key.sign(&message);
+        prop_assert!(signature.verify(&message, &keypair.public_key).is_ok());
+
+        let other_message = TestDiemCrypto(format!("{}!", message.0));
+        prop_assert!(signature.verify(&other_message, &keypair.public_key).is_err());
+    }
+
+    #[test]
+    fn test_keys_and_signature_serialization(
+        message in random_serializable_struct(),
+        keypair in uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>()
+    ) {
+        let serialized_private_key = bcs::to_bytes(&keypair.private_key).unwrap();
+        let private_key: BLS12381PrivateKey = bcs::from_bytes(&serialized_private_key).unwrap();
+        prop_assert_eq!(&private_key, &keypair.private_key);
+
+        let serialized_public_key = bcs::to_bytes(&keypair.public_key).unwrap();
+        let public_key: BLS12381PublicKey = bcs::from_bytes(&serialized_public_key).unwrap();
+        prop_assert_eq!(&public_key, &keypair.public_key);
+        prop_assert_eq!(keypair.public_key.to_bytes().len(), BLS12381_PUBLIC_KEY_LENGTH);
+
+        let signature = private_key.sign(&message);
+        let serialized_signature = bcs::to_bytes(&signature).unwrap();
+        let deserialized_signature: BLS12381Signature =
+            bcs::from_bytes(&serialized_signature).unwrap();
+        prop_assert_eq!(&deserialized_signature, &signature);
+        prop_assert_eq!(signature.to_bytes().len(), BLS12381_SIGNATURE_LENGTH);
+
+        let encoded_public_key = keypair.public_key.to_encoded_string().unwrap();
+        prop_assert_eq!(
+            BLS12381PublicKey::from_encoded_string(&encoded_public_key).unwrap(),
+            keypair.public_key
+        );
+    }
+}
+
+#[test]
+fn test_proof_of_possession() {
+    let private_keys = generate_keys(2);
+    let public_keys: Vec<BLS12381PublicKey> =
+        private_keys.iter().map(BLS12381PublicKey::from).collect();
+
+    let pop = BLS12381ProofOfPossession::create(&private_keys[0]);
+    assert!(pop.verify(&public_keys[0]).is_ok());
+    assert!(pop.verify(&public_keys[1]).is_err());
+
+    // A proof of possession isn't a signature of the public key, and vice versa
+    let signature = private_keys[0].sign_arbitrary_message(&public_keys[0].to_bytes());
+    let signature_as_pop = BLS12381ProofOfPossession::try_from(&signature.to_bytes()[..]).unwrap();
+    assert!(signature_as_pop.verify(&public_keys[0]).is_err());
+    let pop_as_signature = BLS12381Signature::try_from(&pop.to_bytes()[..]).unwrap();
+    assert!(pop_as_signature
+        .verify_arbitrary_msg(&public_keys[0].to_bytes(), &public_keys[0])
+        .is_err());
+
+    let serialized_pop = bcs::to_bytes(&pop).unwrap();
+    let deserialized_pop: BLS12381ProofOfPossession = bcs::from_bytes(&serialized_pop).unwrap();
+    assert_eq!(deserialized_pop, pop);
+}
+
+#[test]
+fn test_aggregate_signature_verification() {
+    let message = TestDiemCrypto("Test Message".to_string());
+    let private_keys = generate_keys(5);
+    let public_keys: Vec<BLS12381PublicKey> =
+        private_keys.iter().map(BLS12381PublicKey::from).collect();
+    let public_key_refs: Vec<_> = public_keys.iter().collect();
+    let signatures: Vec<_> = private_keys.iter().map(|key| key.sign(&message)).collect();
+
+    let aggregate_signature =
+        BLS12381Signature::aggregate(&signatures.iter().collect::<Vec<_>>()).unwrap();
+    assert!(aggregate_signature
+        .verify_aggregate(&message, &public_key_refs)
+        .is_ok());
+
+    // The aggregate signature is also the signature of the aggregate public key
+    let aggregate_public_key = BLS12381PublicKey::aggregate(&public_key_refs).unwrap();
+    assert!(aggregate_signature
+        .verify(&message, &aggregate_public_key)
+        .is_ok());
+
+    // It fails with a signer missing, with a signer too many, or on another message
+    assert!(aggregate_signature
+        .verify_aggregate(&message, &public_key_refs[1..])
+        .is_err());
+    let partial_aggregate_signature =
+        BLS12381Signature::aggregate(&signatures[1..].iter().collect::<Vec<_>>()).unwrap();
+    assert!(partial_aggregate_signature
+        .verify_aggregate(&message, &public_key_refs)
+        .is_err());
+    let other_message = TestDiemCrypto("Other Message".to_string());
+    assert!(aggregate_signature
+        .verify_aggregate(&other_message, &public_key_refs)
+        .is_err());
+
+    // Nothing can be aggregated from nothing
+    assert!(BLS12381Signature::aggregate(&[]).is_err());
+    assert!(BLS12381PublicKey::aggregate(&[]).is_err());
+}
+
+#[test]
+fn test_public_key_validation() {
+    // The compressed point at infinity of G2 isn't a valid public key
+    let mut infinity = [0u8; BLS12381_PUBLIC_KEY_LENGTH];
+    infinity[0] = 0xc0;
+    assert!(BLS12381PublicKey::try_from(&infinity[..]).is_err());
+
+    // Nor are the encodings of the wrong length
+    let public_key = BLS12381PublicKey::from(&generate_keys(1)[0]);
+    assert_eq!(
+        BLS12381PublicKey::try_from(&public_key.to_bytes()[1..]),
+        Err(CryptoMaterialError::WrongLengthError)
+    );
+}
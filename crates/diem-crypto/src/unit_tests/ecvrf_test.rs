@@ -0,0 +1,167 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    ecvrf::{
+        VRFOutput, VRFPrivateKey, VRFProof, VRFPublicKey, VRF_PROOF_LENGTH, VRF_PUBLIC_KEY_LENGTH,
+    },
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    test_utils::uniform_keypair_strategy,
+    traits::*,
+};
+
+use core::convert::TryFrom;
+use proptest::prelude::*;
+
+/// The order of the prime order subgroup of ed25519, little-endian.
+const L: [u8; 32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+/// The test vectors of ECVRF-EDWARDS25519-SHA512-TAI in appendix B.3 of RFC9381, as
+/// (private key, public key, input, proof, output).
+const TEST_VECTORS: [(&str, &str, &str, &str, &str); 3] = [
+    (
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+        "",
+        "8657106690b5526245a92b003bb079ccd1a92130477671f6fc01ad16f26f723f26f8a57ccaed74ee1b190bed1f479d9727d2d0f9b005a6e456a35d4fb0daab1268a1b0db10836d9826a528ca76567805",
+        "90cf1df3b703cce59e2a35b925d411164068269d7b2d29f3301c03dd757876ff66b71dda49d2de59d03450451af026798e8f81cd2e333de5cdf4f3e140fdd8ae",
+    ),
+    (
+        "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+        "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+        "72",
+        "f3141cd382dc42909d19ec5110469e4feae18300e94f304590abdced48aed5933bf0864a62558b3ed7f2fea45c92a465301b3bbf5e3e54ddf2d935be3b67926da3ef39226bbc355bdc9850112c8f4b02",
+        "eb4440665d3891d668e7e0fcaf587f1b4bd7fbfe99d0eb2211ccec90496310eb5e33821bc613efb94db5e5b54c70a848a0bef4553a41befc57663b56373a5031",
+    ),
+    (
+        "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+        "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+        "af82",
+        "9bc0f79119cc5604bf02d23b4caede71393cedfbb191434dd016d30177ccbf8096bb474e53895c362d8628ee9f9ea3c0e52c7a5c691b6c18c9979866568add7a2d41b00b05081ed0f58ee5e31b3a970e",
+        "645427e5d00c62a23fb703732fa5d892940935942101e456ecca7bb217c61c452118fec1219202a0edcf038bb6373241578be7217ba85a2687f7a0310b2df19f",
+    ),
+];
+
+proptest! {
+    #[test]
+    fn test_proof_verification(
+        alpha in any::<Vec<u8>>(),
+        keypair in uniform_keypair_strategy::<VRFPrivateKey, VRFPublicKey>(),
+        other_keypair in uniform_keypair_strategy::<VRFPrivateKey, VRFPublicKey>()
+    ) {
+        let proof = keypair.private_key.prove(&alpha);
+        prop_assert!(keypair.public_key.verify(&proof, &alpha).is_ok());
+
+        let mut other_alpha = alpha.clone();
+        other_alpha.push(0);
+        prop_assert!(keypair.public_key.verify(&proof, &other_alpha).is_err());
+
+        prop_assume!(other_keypair.public_key != keypair.public_key);
+        prop_assert!(other_keypair.public_key.verify(&proof, &alpha).is_err());
+    }
+
+    #[test]
+    fn test_output_uniqueness(
+        alpha in any::<Vec<u8>>(),
+        keypair in uniform_keypair_strategy::<VRFPrivateKey, VRFPublicKey>()
+    ) {
+        let proof = keypair.private_key.prove(&alpha);
+        let other_proof = keypair.private_key.prove(&alpha);
+        prop_assert_eq!(VRFOutput::from(&proof), VRFOutput::from(&other_proof));
+
+        let mut other_alpha = alpha.clone();
+        other_alpha.push(0);
+        let other_input_proof = keypair.private_key.prove(&other_alpha);
+        prop_assert_ne!(VRFOutput::from(&proof), VRFOutput::from(&other_input_proof));
+    }
+
+    #[test]
+    fn test_keys_and_proof_serialization(
+        alpha in any::<Vec<u8>>(),
+        keypair in uniform_keypair_strategy::<VRFPrivateKey, VRFPublicKey>()
+    ) {
+        let serialized_private_key = bcs::to_bytes(&keypair.private_key).unwrap();
+        let private_key: VRFPrivateKey = bcs::from_bytes(&serialized_private_key).unwrap();
+        prop_assert_eq!(&private_key, &keypair.private_key);
+
+        let serialized_public_key = bcs::to_bytes(&keypair.public_key).unwrap();
+        let public_key: VRFPublicKey = bcs::from_bytes(&serialized_public_key).unwrap();
+        prop_assert_eq!(&public_key, &keypair.public_key);
+        prop_assert_eq!(keypair.public_key.to_bytes().len(), VRF_PUBLIC_KEY_LENGTH);
+
+        let proof = private_key.prove(&alpha);
+        let serialized_proof = bcs::to_bytes(&proof).unwrap();
+        let deserialized_proof: VRFProof = bcs::from_bytes(&serialized_proof).unwrap();
+        prop_assert_eq!(&deserialized_proof, &proof);
+        prop_assert_eq!(proof.to_bytes().len(), VRF_PROOF_LENGTH);
+
+        let json_proof = serde_json::to_string(&proof).unwrap();
+        let deserialized_proof: VRFProof = serde_json::from_str(&json_proof).unwrap();
+        prop_assert_eq!(&deserialized_proof, &proof);
+    }
+
+    #[test]
+    fn test_ed25519_key_compatibility(
+        keypair in uniform_keypair_strategy::<Ed25519PrivateKey, Ed25519PublicKey>()
+    ) {
+        let private_key = VRFPrivateKey::from(&keypair.private_key);
+        let public_key = VRFPublicKey::try_from(&keypair.public_key).unwrap();
+        prop_assert_eq!(&private_key.public_key(), &public_key);
+        prop_assert_eq!(public_key.to_bytes(), keypair.public_key.to_bytes());
+    }
+}
+
+#[test]
+fn test_vectors() {
+    for (private_key, public_key, alpha, proof, output) in TEST_VECTORS.iter() {
+        let private_key = VRFPrivateKey::try_from(&hex::decode(private_key).unwrap()[..]).unwrap();
+        let public_key = VRFPublicKey::try_from(&hex::decode(public_key).unwrap()[..]).unwrap();
+        let alpha = hex::decode(alpha).unwrap();
+        let proof = VRFProof::try_from(&hex::decode(proof).unwrap()[..]).unwrap();
+        let output = hex::decode(output).unwrap();
+
+        assert_eq!(private_key.public_key(), public_key);
+        assert_eq!(private_key.prove(&alpha), proof);
+        assert!(public_key.verify(&proof, &alpha).is_ok());
+        assert_eq!(&VRFOutput::from(&proof).to_bytes()[..], &output[..]);
+    }
+}
+
+#[test]
+fn test_non_canonical_proof() {
+    let private_key = VRFPrivateKey::generate_for_testing();
+    let mut bytes = private_key.prove(b"input").to_bytes();
+
+    // s isn't reduced modulo the order of the group
+    bytes[48..].copy_from_slice(&L);
+    assert_eq!(
+        VRFProof::try_from(&bytes[..]),
+        Err(CryptoMaterialError::CanonicalRepresentationError)
+    );
+
+    assert_eq!(
+        VRFProof::try_from(&bytes[1..]),
+        Err(CryptoMaterialError::WrongLengthError)
+    );
+}
+
+#[test]
+fn test_public_key_validation() {
+    // The identity point has a small order
+    let mut identity = [0u8; VRF_PUBLIC_KEY_LENGTH];
+    identity[0] = 1;
+    assert_eq!(
+        VRFPublicKey::try_from(&identity[..]),
+        Err(CryptoMaterialError::SmallSubgroupError)
+    );
+
+    // A y coordinate out of the field isn't a canonical encoding
+    let non_canonical = [0xffu8; VRF_PUBLIC_KEY_LENGTH];
+    assert_eq!(
+        VRFPublicKey::try_from(&non_canonical[..]),
+        Err(CryptoMaterialError::DeserializationError)
+    );
+}
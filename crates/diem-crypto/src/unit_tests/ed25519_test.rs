@@ -452,6 +452,92 @@ fn test_publickey_smallorder() {
     }
 }
 
+// Test that batch verification rejects signatures with a small order R, as strict verification does.
+#[test]
+fn test_batch_verify_small_order_r() {
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+    let public_key = private_key.public_key();
+    let message = CryptoHashable(0);
+    for torsion_point in &EIGHT_TORSION {
+        let mut signature_bytes = [0u8; ED25519_SIGNATURE_LENGTH];
+        signature_bytes[..32].copy_from_slice(torsion_point);
+        let signature = Ed25519Signature::try_from(&signature_bytes[..]).unwrap();
+        assert!(signature.verify(&message, &public_key).is_err());
+        assert!(Ed25519Signature::batch_verify(
+            &message,
+            vec![
+                (public_key.clone(), private_key.sign(&message)),
+                (public_key.clone(), signature)
+            ]
+        )
+        .is_err());
+    }
+}
+
+// Signs `message` with the secret scalars `a` and `r`, the public key and R being offset by the
+// torsion points `a_torsion` and `r_torsion`, and returns the public key and the signature.
+fn sign_with_torsion(
+    message: &CryptoHashable,
+    a: curve25519_dalek::scalar::Scalar,
+    a_torsion: curve25519_dalek::edwards::EdwardsPoint,
+    r: curve25519_dalek::scalar::Scalar,
+    r_torsion: curve25519_dalek::edwards::EdwardsPoint,
+) -> (
+    curve25519_dalek::scalar::Scalar,
+    Ed25519PublicKey,
+    Ed25519Signature,
+) {
+    let pub_point = curve25519_dalek::constants::ED25519_BASEPOINT_POINT.mul(a) + a_torsion;
+    let r_point = curve25519_dalek::constants::ED25519_BASEPOINT_POINT.mul(r) + r_torsion;
+
+    let mut message_bytes =
+        <CryptoHashableHasher as diem_crypto::hash::CryptoHasher>::seed().to_vec();
+    bcs::serialize_into(&mut message_bytes, message).unwrap();
+    let mut h: Sha512 = Sha512::default();
+    h.update(&r_point.compress().to_bytes());
+    h.update(&pub_point.compress().to_bytes());
+    h.update(&message_bytes);
+    let mut output = [0u8; 64];
+    output.copy_from_slice(h.finalize().as_slice());
+    let k = curve25519_dalek::scalar::Scalar::from_bytes_mod_order_wide(&output);
+
+    let s = k * a + r;
+    let public_key = Ed25519PublicKey::try_from(&pub_point.compress().to_bytes()[..]).unwrap();
+    let signature =
+        Ed25519Signature::try_from(&[r_point.compress().to_bytes(), s.to_bytes()].concat()[..])
+            .unwrap();
+    (k, public_key, signature)
+}
+
+proptest! {
+    // Batch verification never accepts a signature with a mixed order R, which strict
+    // verification rejects.
+    #[test]
+    fn test_batch_verify_mixed_order_r(
+        a in any::<[u8; 32]>(),
+        r in any::<[u8; 32]>(),
+        idx in 1usize..8usize,
+    ) {
+        let message = CryptoHashable(0);
+        let torsion_component = curve25519_dalek::edwards::CompressedEdwardsY(EIGHT_TORSION[idx]).decompress().unwrap();
+        let (_, public_key, signature) = sign_with_torsion(
+            &message,
+            curve25519_dalek::scalar::Scalar::from_bytes_mod_order(a),
+            curve25519_dalek::edwards::EdwardsPoint::default(),
+            curve25519_dalek::scalar::Scalar::from_bytes_mod_order(r),
+            torsion_component,
+        );
+        prop_assert!(signature.verify(&message, &public_key).is_err());
+        for _ in 0..8 {
+            prop_assert!(Ed25519Signature::batch_verify(
+                &message,
+                vec![(public_key.clone(), signature.clone())]
+            )
+            .is_err());
+        }
+    }
+}
+
 // The 8-torsion subgroup E[8].
 //
 // In the case of Curve25519, it is cyclic; the i-th element of
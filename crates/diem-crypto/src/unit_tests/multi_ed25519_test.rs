@@ -452,3 +452,30 @@ fn test_multi_ed25519_signature_verification() {
         .verify(message(), &multi_public_key_2of3)
         .is_err());
 }
+
+// Test batch verification of multi-sig Ed25519 signatures on the same message.
+#[test]
+fn test_multi_ed25519_signature_batch_verification() {
+    let priv_keys_10 = generate_keys(10);
+    let multi_private_key_a = MultiEd25519PrivateKey::new(priv_keys_10[..5].to_vec(), 3).unwrap();
+    let multi_public_key_a = MultiEd25519PublicKey::from(&multi_private_key_a);
+    let multi_private_key_b = MultiEd25519PrivateKey::new(priv_keys_10[5..].to_vec(), 3).unwrap();
+    let multi_public_key_b = MultiEd25519PublicKey::from(&multi_private_key_b);
+
+    let keys_and_signatures = vec![
+        (multi_public_key_a, multi_private_key_a.sign(message())),
+        (multi_public_key_b, multi_private_key_b.sign(message())),
+    ];
+    assert!(MultiEd25519Signature::batch_verify(message(), keys_and_signatures.clone()).is_ok());
+
+    // A signature below the threshold of its key fails the batch
+    let sig_with_6th_key = priv_keys_10[5].sign(message());
+    let mut below_threshold = keys_and_signatures.clone();
+    below_threshold[1].1 = MultiEd25519Signature::new(vec![(sig_with_6th_key, 0)]).unwrap();
+    assert!(MultiEd25519Signature::batch_verify(message(), below_threshold).is_err());
+
+    // So does a signature of other keys
+    let mut swapped_signatures = keys_and_signatures.clone();
+    swapped_signatures[1].1 = keys_and_signatures[0].1.clone();
+    assert!(MultiEd25519Signature::batch_verify(message(), swapped_signatures).is_err());
+}
@@ -0,0 +1,139 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    secp256k1::{
+        Secp256k1PrivateKey, Secp256k1PublicKey, Secp256k1Signature, SECP256K1_PUBLIC_KEY_LENGTH,
+        SECP256K1_SIGNATURE_LENGTH,
+    },
+    test_utils::{random_serializable_struct, uniform_keypair_strategy, TestDiemCrypto},
+    traits::*,
+};
+
+use core::convert::TryFrom;
+use proptest::prelude::*;
+
+/// The order of the secp256k1 curve, big-endian.
+const N: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+/// Computes N - s, big-endian.
+fn negate_scalar(s: &[u8]) -> [u8; 32] {
+    let mut result = [0u8; 32];
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let mut difference = N[i] as i16 - s[i] as i16 - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += 256;
+            borrow = 1;
+        }
+        result[i] = difference as u8;
+    }
+    result
+}
+
+proptest! {
+    #[test]
+    fn test_signature_verification_from_struct(
+        message in random_serializable_struct(),
+        keypair in uniform_keypair_strategy::<Secp256k1PrivateKey, Secp256k1PublicKey>()
+    ) {
+        let signature = keypair.private_key.sign(&message);
+        prop_assert!(signature.verify(&message, &keypair.public_key).is_ok());
+
+        let other_message = TestDiemCrypto(format!("{}!", message.0));
+        prop_assert!(signature.verify(&other_message, &keypair.public_key).is_err());
+    }
+
+    #[test]
+    fn test_keys_and_signature_serialization(
+        message in random_serializable_struct(),
+        keypair in uniform_keypair_strategy::<Secp256k1PrivateKey, Secp256k1PublicKey>()
+    ) {
+        let serialized_private_key = bcs::to_bytes(&keypair.private_key).unwrap();
+        let private_key: Secp256k1PrivateKey = bcs::from_bytes(&serialized_private_key).unwrap();
+        prop_assert_eq!(&private_key, &keypair.private_key);
+
+        let serialized_public_key = bcs::to_bytes(&keypair.public_key).unwrap();
+        let public_key: Secp256k1PublicKey = bcs::from_bytes(&serialized_public_key).unwrap();
+        prop_assert_eq!(&public_key, &keypair.public_key);
+        prop_assert_eq!(keypair.public_key.to_bytes().len(), SECP256K1_PUBLIC_KEY_LENGTH);
+
+        let signature = private_key.sign(&message);
+        let serialized_signature = bcs::to_bytes(&signature).unwrap();
+        let deserialized_signature: Secp256k1Signature =
+            bcs::from_bytes(&serialized_signature).unwrap();
+        prop_assert_eq!(&deserialized_signature, &signature);
+        prop_assert_eq!(signature.to_bytes().len(), SECP256K1_SIGNATURE_LENGTH);
+    }
+
+    #[test]
+    fn test_signature_malleability(
+        message in random_serializable_struct(),
+        keypair in uniform_keypair_strategy::<Secp256k1PrivateKey, Secp256k1PublicKey>()
+    ) {
+        let signature = keypair.private_key.sign(&message);
+        let mut bytes = signature.to_bytes();
+
+        // (r, N - s) is the other valid signature of the message, with a high s
+        let high_s = negate_scalar(&bytes[32..]);
+        bytes[32..].copy_from_slice(&high_s);
+        prop_assert_eq!(
+            Secp256k1Signature::try_from(&bytes[..]),
+            Err(CryptoMaterialError::CanonicalRepresentationError)
+        );
+        prop_assert_eq!(
+            Secp256k1Signature::check_malleability(&bytes),
+            Err(CryptoMaterialError::CanonicalRepresentationError)
+        );
+    }
+}
+
+#[test]
+fn test_ethereum_private_key() {
+    // The well-known private key 1, whose public key is the generator of the curve
+    let mut private_key_bytes = [0u8; 32];
+    private_key_bytes[31] = 1;
+    let private_key = Secp256k1PrivateKey::try_from(&private_key_bytes[..]).unwrap();
+    let public_key = Secp256k1PublicKey::from(&private_key);
+    assert_eq!(
+        hex::encode(public_key.to_bytes()),
+        "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+         483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"
+    );
+    assert_eq!(
+        Secp256k1PublicKey::try_from(&public_key.to_bytes()[..]).unwrap(),
+        public_key
+    );
+
+    // Zero and the curve order aren't private keys
+    assert!(Secp256k1PrivateKey::try_from(&[0u8; 32][..]).is_err());
+    assert!(Secp256k1PrivateKey::try_from(&N[..]).is_err());
+}
+
+#[test]
+fn test_public_key_validation() {
+    // The compressed encoding is rejected
+    let mut private_key_bytes = [0u8; 32];
+    private_key_bytes[31] = 1;
+    let private_key = Secp256k1PrivateKey::try_from(&private_key_bytes[..]).unwrap();
+    let public_key = Secp256k1PublicKey::from(&private_key);
+    let uncompressed = public_key.to_bytes();
+    let mut compressed = vec![0x02 | (uncompressed[64] & 1)];
+    compressed.extend_from_slice(&uncompressed[1..33]);
+    assert_eq!(
+        Secp256k1PublicKey::try_from(&compressed[..]),
+        Err(CryptoMaterialError::WrongLengthError)
+    );
+
+    // So are the points which aren't on the curve
+    let mut bytes = uncompressed;
+    bytes[64] ^= 1;
+    assert_eq!(
+        Secp256k1PublicKey::try_from(&bytes[..]),
+        Err(CryptoMaterialError::PointNotOnCurveError)
+    );
+}
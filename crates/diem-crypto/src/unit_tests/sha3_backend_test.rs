@@ -0,0 +1,85 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    hash::{CryptoHasher, TestOnlyHasher, TransactionAccumulatorHasher},
+    sha3_backend::{backend, Sha3Backend},
+    HashValue,
+};
+use proptest::{collection::vec, prelude::*};
+
+/// The SHA3-256 rate, in bytes.
+const RATE: usize = 136;
+
+fn sha3_256_of_prefixed(prefix: &[u8], message: &[u8]) -> HashValue {
+    HashValue::sha3_256_of(&[prefix, message].concat())
+}
+
+#[test]
+fn test_detected_backend_is_available() {
+    assert!(Sha3Backend::available().contains(&backend()));
+    assert!(Sha3Backend::available().contains(&Sha3Backend::Portable));
+}
+
+#[test]
+fn test_block_boundaries() {
+    // Messages whose padding falls at the end of a block, or spills over the next one
+    let messages: Vec<Vec<u8>> = (0..3 * RATE + 2)
+        .map(|len| (0..len).map(|i| i as u8).collect())
+        .collect();
+    let messages: Vec<&[u8]> = messages.iter().map(|message| &message[..]).collect();
+    for prefix in [&b""[..], &[0xab; 32][..], &[0xcd; RATE][..]].iter() {
+        for backend in Sha3Backend::available() {
+            let hashes = backend.sha3_256_batch(prefix, &messages);
+            assert_eq!(hashes.len(), messages.len());
+            for (hash, message) in hashes.iter().zip(messages.iter()) {
+                assert_eq!(
+                    *hash,
+                    sha3_256_of_prefixed(prefix, message),
+                    "{:?}",
+                    backend
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_empty_batch() {
+    for backend in Sha3Backend::available() {
+        assert!(backend.sha3_256_batch(b"prefix", &[]).is_empty());
+    }
+}
+
+proptest! {
+    #[test]
+    fn test_backends_match_sha3_256(
+        prefix in vec(any::<u8>(), 0..200),
+        messages in vec(vec(any::<u8>(), 0..400), 0..10)
+    ) {
+        let messages: Vec<&[u8]> = messages.iter().map(|message| &message[..]).collect();
+        for backend in Sha3Backend::available() {
+            let hashes = backend.sha3_256_batch(&prefix, &messages);
+            prop_assert_eq!(hashes.len(), messages.len());
+            for (hash, message) in hashes.iter().zip(messages.iter()) {
+                prop_assert_eq!(*hash, sha3_256_of_prefixed(&prefix, message));
+            }
+        }
+    }
+
+    #[test]
+    fn test_hash_all_batch(messages in vec(vec(any::<u8>(), 0..200), 0..10)) {
+        let messages: Vec<&[u8]> = messages.iter().map(|message| &message[..]).collect();
+
+        let hashes = TestOnlyHasher::hash_all_batch(&messages);
+        let expected: Vec<_> = messages.iter().map(|message| TestOnlyHasher::hash_all(message)).collect();
+        prop_assert_eq!(hashes, expected);
+
+        let hashes = TransactionAccumulatorHasher::hash_all_batch(&messages);
+        let expected: Vec<_> = messages
+            .iter()
+            .map(|message| TransactionAccumulatorHasher::hash_all(message))
+            .collect();
+        prop_assert_eq!(hashes, expected);
+    }
+}
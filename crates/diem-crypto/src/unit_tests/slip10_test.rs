@@ -0,0 +1,219 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::slip10::*;
+use std::str::FromStr;
+
+struct Slip10TestVector {
+    path: &'static str,
+    chain_code: &'static str,
+    private_key: &'static str,
+    public_key: &'static str,
+}
+
+// The Ed25519 test vectors of SLIP-0010, the public keys without their 00 prefix.
+const SEED_1: &str = "000102030405060708090a0b0c0d0e0f";
+const TEST_VECTORS_1: &[Slip10TestVector] = &[
+    Slip10TestVector {
+        path: "m",
+        chain_code: "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb",
+        private_key: "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+        public_key: "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed",
+    },
+    Slip10TestVector {
+        path: "m/0'",
+        chain_code: "8b59aa11380b624e81507a27fedda59fea6d0b779a778918a2fd3590e16e9c69",
+        private_key: "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+        public_key: "8c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c",
+    },
+    Slip10TestVector {
+        path: "m/0'/1'",
+        chain_code: "a320425f77d1b5c2505a6b1b27382b37368ee640e3557c315416801243552f14",
+        private_key: "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2",
+        public_key: "1932a5270f335bed617d5b935c80aedb1a35bd9fc1e31acafd5372c30f5c1187",
+    },
+    Slip10TestVector {
+        path: "m/0'/1'/2'",
+        chain_code: "2e69929e00b5ab250f49c3fb1c12f252de4fed2c1db88387094a0f8c4c9ccd6c",
+        private_key: "92a5b23c0b8a99e37d07df3fb9966917f5d06e02ddbd909c7e184371463e9fc9",
+        public_key: "ae98736566d30ed0e9d2f4486a64bc95740d89c7db33f52121f8ea8f76ff0fc1",
+    },
+    Slip10TestVector {
+        path: "m/0'/1'/2'/2'",
+        chain_code: "8f6d87f93d750e0efccda017d662a1b31a266e4a6f5993b15f5c1f07f74dd5cc",
+        private_key: "30d1dc7e5fc04c31219ab25a27ae00b50f6fd66622f6e9c913253d6511d1e662",
+        public_key: "8abae2d66361c879b900d204ad2cc4984fa2aa344dd7ddc46007329ac76c429c",
+    },
+    Slip10TestVector {
+        path: "m/0'/1'/2'/2'/1000000000'",
+        chain_code: "68789923a0cac2cd5a29172a475fe9e0fb14cd6adb5ad98a3fa70333e7afa230",
+        private_key: "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+        public_key: "3c24da049451555d51a7014a37337aa4e12d41e485abccfa46b47dfb2af54b7a",
+    },
+];
+
+const SEED_2: &str = "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542";
+const TEST_VECTORS_2: &[Slip10TestVector] = &[
+    Slip10TestVector {
+        path: "m",
+        chain_code: "ef70a74db9c3a5af931b5fe73ed8e1a53464133654fd55e7a66f8570b8e33c3b",
+        private_key: "171cb88b1b3c1db25add599712e36245d75bc65a1a5c9e18d76f9f2b1eab4012",
+        public_key: "8fe9693f8fa62a4305a140b9764c5ee01e455963744fe18204b4fb948249308a",
+    },
+    Slip10TestVector {
+        path: "m/0'",
+        chain_code: "0b78a3226f915c082bf118f83618a618ab6dec793752624cbeb622acb562862d",
+        private_key: "1559eb2bbec5790b0c65d8693e4d0875b1747f4970ae8b650486ed7470845635",
+        public_key: "86fab68dcb57aa196c77c5f264f215a112c22a912c10d123b0d03c3c28ef1037",
+    },
+    Slip10TestVector {
+        path: "m/0'/2147483647'",
+        chain_code: "138f0b2551bcafeca6ff2aa88ba8ed0ed8de070841f0c4ef0165df8181eaad7f",
+        private_key: "ea4f5bfe8694d8bb74b7b59404632fd5968b774ed545e810de9c32a4fb4192f4",
+        public_key: "5ba3b9ac6e90e83effcd25ac4e58a1365a9e35a3d3ae5eb07b9e4d90bcf7506d",
+    },
+    Slip10TestVector {
+        path: "m/0'/2147483647'/1'",
+        chain_code: "73bd9fff1cfbde33a1b846c27085f711c0fe2d66fd32e139d3ebc28e5a4a6b90",
+        private_key: "3757c7577170179c7868353ada796c839135b3d30554bbb74a4b1e4a5a58505c",
+        public_key: "2e66aa57069c86cc18249aecf5cb5a9cebbfd6fadeab056254763874a9352b45",
+    },
+    Slip10TestVector {
+        path: "m/0'/2147483647'/1'/2147483646'",
+        chain_code: "0902fe8a29f9140480a00ef244bd183e8a13288e4412d8389d140aac1794825a",
+        private_key: "5837736c89570de861ebc173b1086da4f505d4adb387c6a1b1342d5e4ac9ec72",
+        public_key: "e33c0f7d81d843c572275f287498e8d408654fdf0d1e065b84e2e6f157aab09b",
+    },
+    Slip10TestVector {
+        path: "m/0'/2147483647'/1'/2147483646'/2'",
+        chain_code: "5d70af781f3a37b829f0d060924d5e960bdc02e85423494afc0b1a41bbe196d4",
+        private_key: "551d333177df541ad876a60ea71f00447931c0a9da16f227c11ea080d7391b8d",
+        public_key: "47150c75db263559a70d5778bf36abbab30fb061ad69f69ece61a72b0cfa4fc0",
+    },
+];
+
+fn check_test_vectors(seed: &str, test_vectors: &[Slip10TestVector]) {
+    let master_key = ExtendedPrivateKey::from_seed(&hex::decode(seed).unwrap()).unwrap();
+    for t in test_vectors {
+        let path = DerivationPath::from_str(t.path).unwrap();
+        assert_eq!(path.to_string(), t.path);
+
+        let key = master_key.derive_path(&path).unwrap();
+        assert_eq!(key.depth() as usize, path.indices().len());
+        assert_eq!(hex::encode(key.chain_code()), t.chain_code);
+        assert_eq!(hex::encode(key.private_key().to_bytes()), t.private_key);
+        assert_eq!(hex::encode(key.public_key().to_bytes()), t.public_key);
+    }
+}
+
+#[test]
+fn test_slip10_test_vectors() {
+    check_test_vectors(SEED_1, TEST_VECTORS_1);
+    check_test_vectors(SEED_2, TEST_VECTORS_2);
+}
+
+#[test]
+fn test_derive_child() {
+    let master_key = ExtendedPrivateKey::from_seed(&hex::decode(SEED_1).unwrap()).unwrap();
+    let child = master_key.derive_child(0).unwrap().derive_child(1).unwrap();
+    let descendant = master_key
+        .derive_path(&DerivationPath::new(vec![0, 1]).unwrap())
+        .unwrap();
+    assert_eq!(child.private_key(), descendant.private_key());
+    assert_eq!(child.chain_code(), descendant.chain_code());
+
+    assert_eq!(
+        master_key.derive_child(HARDENED_OFFSET).unwrap_err(),
+        Slip10Error::IndexOutOfRange(HARDENED_OFFSET)
+    );
+}
+
+#[test]
+fn test_seed_length() {
+    assert_eq!(
+        ExtendedPrivateKey::from_seed(&[0u8; 15]).unwrap_err(),
+        Slip10Error::InvalidSeedLength(15)
+    );
+    assert_eq!(
+        ExtendedPrivateKey::from_seed(&[0u8; 65]).unwrap_err(),
+        Slip10Error::InvalidSeedLength(65)
+    );
+    assert!(ExtendedPrivateKey::from_seed(&[0u8; 16]).is_ok());
+    assert!(ExtendedPrivateKey::from_seed(&[0u8; 64]).is_ok());
+}
+
+#[test]
+fn test_parse_derivation_path() {
+    assert_eq!(
+        DerivationPath::from_str("m/44H/0'/7'").unwrap(),
+        DerivationPath::new(vec![44, 0, 7]).unwrap()
+    );
+    assert_eq!(
+        DerivationPath::from_str("m").unwrap(),
+        DerivationPath::default()
+    );
+
+    for invalid_path in &[
+        "",
+        "44'/0'",
+        "m/",
+        "m/44",
+        "m/44'/0",
+        "m/+44'",
+        "m/-1'",
+        "m/a'",
+        "m/2147483648'",
+        "m/4294967296'",
+    ] {
+        assert!(
+            DerivationPath::from_str(invalid_path).is_err(),
+            "{} should be invalid",
+            invalid_path
+        );
+    }
+}
+
+#[test]
+fn test_mnemonic_to_seed() {
+    // The test vector of BIP39 with the TREZOR passphrase
+    let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    assert_eq!(
+        hex::encode(&mnemonic_to_seed(mnemonic, "TREZOR").unwrap()[..]),
+        "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+    );
+
+    // Extra whitespace doesn't change the seed
+    let spaced_mnemonic = format!("  {}\n", mnemonic.replace(' ', "  "));
+    assert_eq!(
+        mnemonic_to_seed(&spaced_mnemonic, "TREZOR").unwrap()[..],
+        mnemonic_to_seed(mnemonic, "TREZOR").unwrap()[..]
+    );
+
+    // A typo, a wrong checksum or a missing word fails instead of deriving another seed
+    for invalid_mnemonic in &[
+        mnemonic.replace("about", "abuot"),
+        mnemonic.replace("about", "abandon"),
+        mnemonic.replace("abandon about", "about"),
+        mnemonic.replace("about", "abandonné"),
+    ] {
+        assert!(
+            matches!(
+                mnemonic_to_seed(invalid_mnemonic, "TREZOR"),
+                Err(Slip10Error::InvalidMnemonic(_))
+            ),
+            "{} should be invalid",
+            invalid_mnemonic
+        );
+    }
+    assert!(matches!(
+        mnemonic_to_seed(mnemonic, "TRÉZOR"),
+        Err(Slip10Error::InvalidMnemonic(_))
+    ));
+
+    // The last test vector of BIP39, with 24 words
+    assert!(mnemonic_to_seed(
+        "void come effort suffer camp survey warrior heavy shoot primary clutch crush open amazing screen patrol group space point ten exist slush involve unfold",
+        "TREZOR"
+    )
+    .is_ok());
+}
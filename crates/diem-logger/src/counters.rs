@@ -10,6 +10,11 @@ pub static STRUCT_LOG_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!("diem_struct_log_count", "Count of the struct logs.").unwrap()
 });
 
+/// Count of the struct logs submitted at the error level
+pub static ERROR_LOG_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("diem_error_log_count", "Count of the error logs.").unwrap()
+});
+
 /// Count of struct logs processed, but not necessarily sent
 pub static PROCESSED_STRUCT_LOG_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
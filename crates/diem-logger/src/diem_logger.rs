@@ -341,6 +341,28 @@ impl DiemLogger {
         self.filter.write().remote_filter = filter;
     }
 
+    /// Sets the level of a module in the local filter, or the level of all the modules if none
+    /// is given. Returns the level previously set for it, if any.
+    pub fn set_level(&self, module: Option<&str>, level: LevelFilter) -> Option<LevelFilter> {
+        self.filter.write().local_filter.set_level(module, level)
+    }
+
+    /// Removes the level of a module from the local filter, or the level of all the modules if
+    /// none is given. Returns the removed level, if any.
+    pub fn clear_level(&self, module: Option<&str>) -> Option<LevelFilter> {
+        self.filter.write().local_filter.clear_level(module)
+    }
+
+    /// Returns the directives of the local filter, e.g. `info,consensus=debug`.
+    pub fn local_filter(&self) -> String {
+        self.filter.read().local_filter.to_string()
+    }
+
+    /// Returns the directives of the remote filter.
+    pub fn remote_filter(&self) -> String {
+        self.filter.read().remote_filter.to_string()
+    }
+
     fn send_entry(&self, entry: LogEntry) {
         if let Some(printer) = &self.printer {
             let s = (self.formatter)(&entry).expect("Unable to format");
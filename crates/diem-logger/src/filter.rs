@@ -4,7 +4,7 @@
 //! Filtering definitions for controlling what modules and levels are logged
 
 use crate::{Level, Metadata};
-use std::{env, str::FromStr};
+use std::{env, fmt, str::FromStr};
 
 pub struct FilterParseError;
 
@@ -40,6 +40,20 @@ impl FromStr for LevelFilter {
     }
 }
 
+impl fmt::Display for LevelFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let level = match self {
+            LevelFilter::Off => "off",
+            LevelFilter::Error => "error",
+            LevelFilter::Warn => "warn",
+            LevelFilter::Info => "info",
+            LevelFilter::Debug => "debug",
+            LevelFilter::Trace => "trace",
+        };
+        f.write_str(level)
+    }
+}
+
 impl From<Level> for LevelFilter {
     fn from(level: Level) -> Self {
         match level {
@@ -106,19 +120,13 @@ impl Builder {
         if self.directives.is_empty() {
             // Add the default filter if none exist
             self.filter_level(LevelFilter::Error);
-        } else {
-            // Sort the directives by length of their name, this allows a
-            // little more efficient lookup at runtime.
-            self.directives.sort_by(|a, b| {
-                let alen = a.name.as_ref().map(|a| a.len()).unwrap_or(0);
-                let blen = b.name.as_ref().map(|b| b.len()).unwrap_or(0);
-                alen.cmp(&blen)
-            });
         }
 
-        Filter {
+        let mut filter = Filter {
             directives: ::std::mem::take(&mut self.directives),
-        }
+        };
+        filter.sort_directives();
+        filter
     }
 }
 
@@ -143,6 +151,61 @@ impl Filter {
         }
         false
     }
+
+    /// Sets the level of the given module, or of all the modules if none is given, replacing
+    /// the directive already set for it.
+    ///
+    /// Returns the level of the replaced directive, if any.
+    pub fn set_level(&mut self, module: Option<&str>, level: LevelFilter) -> Option<LevelFilter> {
+        let previous = self.clear_level(module);
+        self.directives.push(Directive::new(module, level));
+        self.sort_directives();
+        previous
+    }
+
+    /// Removes the directives of the given module, or of all the modules if none is given, so
+    /// that the module falls back to the directives of its parents.
+    ///
+    /// Returns the level of the removed directive in effect, if any.
+    pub fn clear_level(&mut self, module: Option<&str>) -> Option<LevelFilter> {
+        // The last of the directives of a module is the one in effect
+        let mut previous = None;
+        self.directives.retain(|directive| {
+            if directive.name.as_deref() == module {
+                previous = Some(directive.level);
+                false
+            } else {
+                true
+            }
+        });
+        previous
+    }
+
+    fn sort_directives(&mut self) {
+        // Sort the directives by length of their name, this allows a
+        // little more efficient lookup at runtime.
+        self.directives.sort_by(|a, b| {
+            let alen = a.name.as_ref().map(|a| a.len()).unwrap_or(0);
+            let blen = b.name.as_ref().map(|b| b.len()).unwrap_or(0);
+            alen.cmp(&blen)
+        });
+    }
+}
+
+/// Formats the filter as the directives string it can be parsed from, e.g. `info,consensus=debug`.
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, directive) in self.directives.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            match &directive.name {
+                Some(name) => write!(f, "{}={}", name, directive.level)?,
+                None => write!(f, "{}", directive.level)?,
+            }
+        }
+        Ok(())
+    }
 }
 
 /// A `Filter` directive for which logs to keep based on a module `name` based filter
@@ -351,4 +414,52 @@ mod tests {
         assert_eq!(dirs[1].name.as_deref(), Some("crate2"));
         assert_eq!(dirs[1].level, LevelFilter::Debug);
     }
+
+    #[test]
+    fn set_level() {
+        let mut logger = Builder::new().parse("info,crate1=warn").build();
+        assert_eq!(
+            logger.set_level(Some("crate1"), LevelFilter::Debug),
+            Some(LevelFilter::Warn)
+        );
+        assert_eq!(
+            logger.set_level(Some("crate2::mod1"), LevelFilter::Trace),
+            None
+        );
+        assert_eq!(
+            logger.set_level(None, LevelFilter::Error),
+            Some(LevelFilter::Info)
+        );
+        assert!(logger.enabled(&make_metadata(Level::Debug, "crate1::mod1")));
+        assert!(logger.enabled(&make_metadata(Level::Trace, "crate2::mod1")));
+        assert!(!logger.enabled(&make_metadata(Level::Trace, "crate2::mod2")));
+        assert!(!logger.enabled(&make_metadata(Level::Info, "crate3")));
+        assert_eq!(logger.to_string(), "error,crate1=debug,crate2::mod1=trace");
+    }
+
+    #[test]
+    fn clear_level() {
+        let mut logger = Builder::new()
+            .parse("info,crate1=warn,crate1=debug")
+            .build();
+        // the last directive of a module is the one in effect
+        assert_eq!(logger.clear_level(Some("crate1")), Some(LevelFilter::Debug));
+        assert_eq!(logger.clear_level(Some("crate1")), None);
+        assert!(logger.enabled(&make_metadata(Level::Info, "crate1")));
+        assert!(!logger.enabled(&make_metadata(Level::Debug, "crate1")));
+        assert_eq!(logger.to_string(), "info");
+    }
+
+    #[test]
+    fn display_parses_back() {
+        let logger = Builder::new()
+            .parse("crate1::mod1=error,crate1::mod2,warn,crate2=off")
+            .build();
+        let parsed = Builder::new().parse(&logger.to_string()).build();
+        assert_eq!(parsed.to_string(), logger.to_string());
+        assert_eq!(
+            logger.to_string(),
+            "warn,crate2=off,crate1::mod1=error,crate1::mod2=trace"
+        );
+    }
 }
@@ -3,7 +3,10 @@
 
 //! Global logger definition and functions
 
-use crate::{counters::STRUCT_LOG_COUNT, Event, Metadata};
+use crate::{
+    counters::{ERROR_LOG_COUNT, STRUCT_LOG_COUNT},
+    Event, Level, Metadata,
+};
 
 use once_cell::sync::OnceCell;
 use std::sync::Arc;
@@ -28,6 +31,9 @@ pub trait Logger: Sync + Send + 'static {
 pub(crate) fn dispatch(event: &Event) {
     if let Some(logger) = LOGGER.get() {
         STRUCT_LOG_COUNT.inc();
+        if event.metadata().level() == Level::Error {
+            ERROR_LOG_COUNT.inc();
+        }
         logger.record(event)
     }
 }
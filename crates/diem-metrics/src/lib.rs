@@ -49,6 +49,7 @@
 
 mod json_encoder;
 mod json_metrics;
+pub use json_metrics::get_json_metrics;
 pub mod metric_server;
 mod public_metrics;
 
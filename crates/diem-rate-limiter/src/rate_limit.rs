@@ -171,6 +171,22 @@ impl<Key: Eq + Hash + Clone + Debug> TokenBucketRateLimiter<Key> {
         }
         remove
     }
+
+    /// Garbage collects the buckets not in use that have refilled to their size, as a new bucket
+    /// for their key never starts with more tokens.  Returns the number of buckets removed.
+    pub fn garbage_collect_full_buckets(&self) -> usize {
+        let mut buckets = self.buckets.write();
+        let num_buckets = buckets.len();
+        buckets.retain(|_, bucket| {
+            if Arc::strong_count(bucket) > 1 {
+                return true;
+            }
+            let mut bucket = bucket.lock();
+            bucket.refill();
+            bucket.tokens < bucket.size
+        });
+        num_buckets - buckets.len()
+    }
 }
 
 /// A token bucket object that keeps track of everything related to a key
@@ -546,4 +562,26 @@ mod tests {
         assert!(!rate_limiter.try_garbage_collect_key(&key_to_keep));
         assert_num_keys(&rate_limiter, 1);
     }
+
+    #[test]
+    fn test_garbage_collect_full_buckets() {
+        let rate_limiter = TokenBucketRateLimiter::test(2, 2);
+        let _bucket_in_use = rate_limiter.bucket("in use");
+        rate_limiter.bucket("full");
+        assert!(rate_limiter
+            .bucket("drained")
+            .lock()
+            .acquire_tokens(1)
+            .is_ok());
+        assert_num_keys(&rate_limiter, 3);
+
+        // Only the full bucket nobody holds is removed
+        assert_eq!(1, rate_limiter.garbage_collect_full_buckets());
+        assert_num_keys(&rate_limiter, 2);
+
+        // Once refilled, the drained bucket goes too
+        sleep(Duration::from_secs(1));
+        assert_eq!(1, rate_limiter.garbage_collect_full_buckets());
+        assert_num_keys(&rate_limiter, 1);
+    }
 }
@@ -0,0 +1,594 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Local admin service of the node, for operators to inspect and tune it at runtime:
+//!   - GET /config dumps the current config of the node, without its secrets.
+//!   - POST /config/reload re-reads the config file and applies the changes, see
+//!     `ConfigReloader`.
+//!   - GET /log/filter returns the filters of the local and remote logs.
+//!   - POST /log/filter sets the filter of the local logs, e.g. `info,consensus=debug`.
+//!   - POST /log/remote-filter sets the filter of the logs sent to the remote log service.
+//!   - POST /log/level sets the level of the local logs of a module, or of all the modules,
+//!     e.g. `{"module": "consensus", "level": "debug"}`.
+//!   - POST /log/trace enables the local trace logs of a module for some time, e.g.
+//!     `{"module": "consensus", "seconds": 60}`.
+//!   - GET /consensus/state returns a snapshot of the state of consensus.
+//!   - GET /peers lists the peers connected on every network.
+//!   - GET /mempool/stats counts the transactions of mempool.
+//!   - GET /mempool/accounts/<address>/transactions lists the pending transactions of an account.
+//!   - GET /mempool/parking_lot lists the transactions of the parking lot.
+//!   - POST /mempool/evict/transaction/<hash> evicts a transaction and the later ones of its
+//!     sender.
+//!   - POST /mempool/evict/account/<address> evicts all the transactions of an account.
+//!   - GET /profile/cpu profiles the CPU for some time, e.g. `?seconds=30&format=flamegraph`,
+//!     see `Profiler`.
+//!   - GET /profile/heap profiles the heap for some time, e.g. `?seconds=30`.
+
+use crate::profiler::{
+    CpuProfileFormat, CpuProfileRequest, HeapProfileRequest, ProfileError, Profiler,
+};
+use consensus::state_snapshot::SharedConsensusState;
+use diem_config::{
+    config::{Error as ConfigError, NodeConfig},
+    network_id::NetworkId,
+    reload::ConfigReloader,
+};
+use diem_crypto::{hash::CryptoHash, HashValue};
+use diem_logger::{prelude::*, Filter, LevelFilter, Logger};
+use diem_mempool::{MempoolClientRequest, MempoolClientSender};
+use diem_types::{
+    account_address::AccountAddress,
+    transaction::{SignedTransaction, Transaction},
+    PeerId,
+};
+use futures::{channel::oneshot, SinkExt};
+use network::{application::storage::PeerMetadataStorage, transport::ConnectionMetadata};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{convert::Infallible, sync::Arc, time::Duration};
+use subtle::ConstantTimeEq;
+use tokio::runtime::Handle;
+use warp::{
+    filters::BoxedFilter,
+    http::StatusCode,
+    reject::{self, Reject},
+    reply::{self, Reply},
+    Filter as _, Rejection,
+};
+
+/// Replaces the values of the secrets in the config dump
+pub(crate) const REDACTED: &str = "<redacted>";
+/// Fields of the config holding secrets: the private keys, the tokens of the secure storages, the
+/// password of the SOCKS5 proxy and the auth token of the admin service
+pub(crate) const SECRET_FIELDS: &[&str] = &[
+    "auth_token",
+    "consensus_key",
+    "execution_key",
+    // private key of a `ConfigKey`, e.g. the identity of a network
+    "key",
+    "operator_key",
+    "owner_key",
+    "password",
+    "token",
+];
+/// Longest time the trace logs of a module can be enabled for
+const MAX_TRACE_SECS: u64 = 3600;
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl Reject for Unauthorized {}
+
+/// Handles on the components of the node the admin service reads from.
+#[derive(Clone)]
+pub struct AdminServiceContext {
+    pub node_config: NodeConfig,
+    pub logger: Option<Arc<Logger>>,
+    /// None if the node wasn't started from a config file
+    pub config_reloader: Option<Arc<ConfigReloader>>,
+    pub consensus_state: SharedConsensusState,
+    pub peers: Vec<(NetworkId, Arc<PeerMetadataStorage>)>,
+    pub mempool_client: MempoolClientSender,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct LogFilters {
+    pub local: String,
+    pub remote: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct LogLevelRequest {
+    /// All the modules if none
+    pub module: Option<String>,
+    pub level: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct LogTraceRequest {
+    pub module: String,
+    pub seconds: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PendingTransaction {
+    pub hash: HashValue,
+    pub sequence_number: u64,
+    pub gas_unit_price: u64,
+    pub max_gas_amount: u64,
+    pub expiration_timestamp_secs: u64,
+    pub is_parked: bool,
+}
+
+impl PendingTransaction {
+    fn new(txn: SignedTransaction, is_parked: bool) -> Self {
+        Self {
+            sequence_number: txn.sequence_number(),
+            gas_unit_price: txn.gas_unit_price(),
+            max_gas_amount: txn.max_gas_amount(),
+            expiration_timestamp_secs: txn.expiration_timestamp_secs(),
+            hash: Transaction::UserTransaction(txn).hash(),
+            is_parked,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct Evicted {
+    pub evicted: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ConnectedPeer {
+    pub network_id: NetworkId,
+    pub peer_id: PeerId,
+    pub connection: ConnectionMetadata,
+    pub rtt_ms: Option<u64>,
+}
+
+/// Starts the admin service on the given runtime, if it's enabled and has an auth token.
+pub fn start_admin_service(executor: &Handle, context: AdminServiceContext) {
+    let config = context.node_config.admin_service.clone();
+    if !config.enabled {
+        return;
+    }
+    let auth_token = match config.auth_token {
+        Some(auth_token) => auth_token,
+        None => {
+            error!(
+                "admin service is enabled on {} without an auth token, not starting it",
+                config.address
+            );
+            return;
+        }
+    };
+
+    // bind in the runtime context, tokio listeners can only be bound there
+    let _guard = executor.enter();
+    let server = warp::serve(get_routes(auth_token, context)).bind(config.address);
+    executor.spawn(server);
+    info!("admin service listening on {}", config.address);
+}
+
+pub(crate) fn get_routes(
+    auth_token: String,
+    context: AdminServiceContext,
+) -> BoxedFilter<(impl Reply,)> {
+    // GET config, the one currently applied if it can be reloaded
+    let node_config = context.node_config.clone();
+    let config_reloader = context.config_reloader.clone();
+    let config = warp::path!("config").and(warp::get()).map(move || {
+        let mut value = match &config_reloader {
+            Some(config_reloader) => serde_json::to_value(&config_reloader.config()),
+            None => serde_json::to_value(&node_config),
+        }
+        .expect("the node config should serialize to JSON");
+        redact_secrets(&mut value);
+        reply::json(&value)
+    });
+
+    // POST config/reload
+    let config_reloader = context.config_reloader.clone();
+    let reload = warp::path!("config" / "reload")
+        .and(warp::post())
+        .map(move || match &config_reloader {
+            Some(config_reloader) => match config_reloader.reload() {
+                Ok(changes) => reply::json(&changes).into_response(),
+                // the config is valid, the node failed to apply it
+                Err(error @ ConfigError::ReloadFailed(_)) => {
+                    reply::with_status(error.to_string(), StatusCode::INTERNAL_SERVER_ERROR)
+                        .into_response()
+                }
+                Err(error) => {
+                    reply::with_status(error.to_string(), StatusCode::BAD_REQUEST).into_response()
+                }
+            },
+            None => reply::with_status(
+                "the config of the node can't be reloaded",
+                StatusCode::SERVICE_UNAVAILABLE,
+            )
+            .into_response(),
+        });
+
+    // GET log/filter
+    let logger = context.logger.clone();
+    let filters = warp::path!("log" / "filter").and(warp::get()).map(move || {
+        with_logger(&logger, |logger| {
+            reply::json(&LogFilters {
+                local: logger.local_filter(),
+                remote: logger.remote_filter(),
+            })
+            .into_response()
+        })
+    });
+
+    // POST log/filter
+    let logger = context.logger.clone();
+    let local_filter = warp::path!("log" / "filter")
+        .and(warp::post())
+        // 16kb should be long enough for a filter
+        .and(warp::body::content_length_limit(1024 * 16))
+        .and(warp::body::bytes())
+        .map(move |bytes: bytes::Bytes| {
+            set_filter(&logger, &bytes, |logger, filter| {
+                info!(filter = filter, "Updating local logging filter");
+                logger.set_filter(Filter::builder().parse(filter).build());
+            })
+        });
+
+    // POST log/remote-filter
+    let logger = context.logger.clone();
+    let remote_filter = warp::path!("log" / "remote-filter")
+        .and(warp::post())
+        .and(warp::body::content_length_limit(1024 * 16))
+        .and(warp::body::bytes())
+        .map(move |bytes: bytes::Bytes| {
+            set_filter(&logger, &bytes, |logger, filter| {
+                info!(filter = filter, "Updating remote logging filter");
+                logger.set_remote_filter(Filter::builder().parse(filter).build());
+            })
+        });
+
+    // POST log/level
+    let logger = context.logger.clone();
+    let level = warp::path!("log" / "level")
+        .and(warp::post())
+        .and(warp::body::content_length_limit(1024 * 16))
+        .and(warp::body::json())
+        .map(move |request: LogLevelRequest| {
+            with_logger(&logger, |logger| set_level(logger, request))
+        });
+
+    // POST log/trace
+    let logger = context.logger.clone();
+    let trace = warp::path!("log" / "trace")
+        .and(warp::post())
+        .and(warp::body::content_length_limit(1024 * 16))
+        .and(warp::body::json())
+        .map(move |request: LogTraceRequest| {
+            with_logger(&logger, |logger| trace_temporarily(logger, request))
+        });
+
+    // GET consensus/state
+    let consensus_state = context.consensus_state.clone();
+    let consensus = warp::path!("consensus" / "state")
+        .and(warp::get())
+        .map(move || match consensus_state.get() {
+            Some(snapshot) => reply::json(&snapshot).into_response(),
+            None => reply::with_status(
+                "consensus isn't running on this node",
+                StatusCode::SERVICE_UNAVAILABLE,
+            )
+            .into_response(),
+        });
+
+    // GET peers
+    let networks = context.peers.clone();
+    let peers = warp::path!("peers").and(warp::get()).map(move || {
+        let peers: Vec<_> = networks
+            .iter()
+            .flat_map(|(network_id, peer_metadata_storage)| {
+                peer_metadata_storage
+                    .read_all()
+                    .into_iter()
+                    .map(move |(peer_id, peer_info)| ConnectedPeer {
+                        network_id: network_id.clone(),
+                        peer_id,
+                        connection: peer_info.active_connection,
+                        rtt_ms: peer_info.rtt.map(|rtt| rtt.as_millis() as u64),
+                    })
+            })
+            .collect();
+        reply::json(&peers)
+    });
+
+    // GET mempool/stats
+    let mempool_client = context.mempool_client.clone();
+    let mempool_stats = warp::path!("mempool" / "stats")
+        .and(warp::get())
+        .and_then(move || {
+            mempool_request(
+                mempool_client.clone(),
+                MempoolClientRequest::GetStats,
+                |stats| reply::json(&stats).into_response(),
+            )
+        });
+
+    // GET mempool/accounts/<address>/transactions
+    let mempool_client = context.mempool_client.clone();
+    let mempool_account = warp::path!("mempool" / "accounts" / AccountAddress / "transactions")
+        .and(warp::get())
+        .and_then(move |address| {
+            mempool_request(
+                mempool_client.clone(),
+                move |callback| {
+                    MempoolClientRequest::GetAccountPendingTransactions(address, callback)
+                },
+                |pending| match pending {
+                    Ok(pending) => {
+                        let transactions: Vec<_> = pending
+                            .transactions
+                            .into_iter()
+                            .map(|(txn, is_parked)| PendingTransaction::new(txn, is_parked))
+                            .collect();
+                        reply::json(&transactions).into_response()
+                    }
+                    Err(error) => {
+                        reply::with_status(error.to_string(), StatusCode::INTERNAL_SERVER_ERROR)
+                            .into_response()
+                    }
+                },
+            )
+        });
+
+    // GET mempool/parking_lot
+    let mempool_client = context.mempool_client.clone();
+    let mempool_parking_lot = warp::path!("mempool" / "parking_lot")
+        .and(warp::get())
+        .and_then(move || {
+            mempool_request(
+                mempool_client.clone(),
+                MempoolClientRequest::GetParkingLot,
+                |parking_lot| reply::json(&parking_lot).into_response(),
+            )
+        });
+
+    // POST mempool/evict/transaction/<hash>
+    let mempool_client = context.mempool_client.clone();
+    let mempool_evict_transaction = warp::path!("mempool" / "evict" / "transaction" / HashValue)
+        .and(warp::post())
+        .and_then(move |hash| {
+            mempool_request(
+                mempool_client.clone(),
+                move |callback| MempoolClientRequest::EvictTransaction(hash, callback),
+                |evicted| reply::json(&Evicted { evicted }).into_response(),
+            )
+        });
+
+    // POST mempool/evict/account/<address>
+    let mempool_client = context.mempool_client;
+    let mempool_evict_account = warp::path!("mempool" / "evict" / "account" / AccountAddress)
+        .and(warp::post())
+        .and_then(move |address| {
+            mempool_request(
+                mempool_client.clone(),
+                move |callback| MempoolClientRequest::EvictAccount(address, callback),
+                |evicted| reply::json(&Evicted { evicted }).into_response(),
+            )
+        });
+
+    // GET profile/cpu
+    let profiler = Arc::new(Profiler::default());
+    let cpu_profiler = profiler.clone();
+    let cpu_profile = warp::path!("profile" / "cpu")
+        .and(warp::get())
+        .and(warp::query::<CpuProfileRequest>())
+        .and_then(move |request: CpuProfileRequest| {
+            let content_type = match request.format {
+                CpuProfileFormat::Flamegraph => "image/svg+xml",
+                CpuProfileFormat::Pprof => "application/octet-stream",
+            };
+            profile(cpu_profiler.clone(), content_type, move |profiler| {
+                profiler.profile_cpu(&request)
+            })
+        });
+
+    // GET profile/heap
+    let heap_profile = warp::path!("profile" / "heap")
+        .and(warp::get())
+        .and(warp::query::<HeapProfileRequest>())
+        .and_then(move |request: HeapProfileRequest| {
+            profile(
+                profiler.clone(),
+                "application/octet-stream",
+                move |profiler| profiler.profile_heap(&request),
+            )
+        });
+
+    authorized(auth_token)
+        .and(
+            config
+                .or(reload)
+                .or(filters)
+                .or(local_filter)
+                .or(remote_filter)
+                .or(level)
+                .or(trace)
+                .or(consensus)
+                .or(peers)
+                .or(mempool_stats)
+                .or(mempool_account)
+                .or(mempool_parking_lot)
+                .or(mempool_evict_transaction)
+                .or(mempool_evict_account)
+                .or(cpu_profile)
+                .or(heap_profile),
+        )
+        .recover(handle_rejection)
+        .boxed()
+}
+
+fn with_logger(
+    logger: &Option<Arc<Logger>>,
+    f: impl FnOnce(&Arc<Logger>) -> reply::Response,
+) -> reply::Response {
+    match logger {
+        Some(logger) => f(logger),
+        None => reply::with_status(
+            "the node doesn't have a logger",
+            StatusCode::SERVICE_UNAVAILABLE,
+        )
+        .into_response(),
+    }
+}
+
+fn set_filter(
+    logger: &Option<Arc<Logger>>,
+    bytes: &[u8],
+    set: impl FnOnce(&Logger, &str),
+) -> reply::Response {
+    with_logger(logger, |logger| match std::str::from_utf8(bytes) {
+        Ok(filter) => {
+            set(logger, filter);
+            reply::reply().into_response()
+        }
+        Err(_) => {
+            reply::with_status("the filter isn't UTF-8", StatusCode::BAD_REQUEST).into_response()
+        }
+    })
+}
+
+fn set_level(logger: &Logger, request: LogLevelRequest) -> reply::Response {
+    let level = match request.level.parse::<LevelFilter>() {
+        Ok(level) => level,
+        Err(_) => {
+            return reply::with_status("invalid level", StatusCode::BAD_REQUEST).into_response()
+        }
+    };
+    info!(
+        module = request.module,
+        level = level.to_string(),
+        "Updating local logging level"
+    );
+    logger.set_level(request.module.as_deref(), level);
+    reply::reply().into_response()
+}
+
+/// Enables the trace logs of a module, then sets its level back once the time is up.
+fn trace_temporarily(logger: &Arc<Logger>, request: LogTraceRequest) -> reply::Response {
+    if request.seconds > MAX_TRACE_SECS {
+        return reply::with_status(
+            format!("can't trace for longer than {}s", MAX_TRACE_SECS),
+            StatusCode::BAD_REQUEST,
+        )
+        .into_response();
+    }
+    let LogTraceRequest { module, seconds } = request;
+    info!(module = module, seconds = seconds, "Tracing module");
+    let previous = logger.set_level(Some(&module), LevelFilter::Trace);
+
+    let logger = logger.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(seconds)).await;
+        // The changes of the level made in the meantime are overwritten
+        match previous {
+            Some(level) => logger.set_level(Some(&module), level),
+            None => logger.clear_level(Some(&module)),
+        };
+        info!(module = module, "Stopped tracing module");
+    });
+    reply::reply().into_response()
+}
+
+/// Sends a request to mempool, and replies with its response.
+async fn mempool_request<T>(
+    mut mempool_client: MempoolClientSender,
+    request: impl FnOnce(oneshot::Sender<T>) -> MempoolClientRequest,
+    to_reply: impl FnOnce(T) -> reply::Response,
+) -> Result<reply::Response, Infallible> {
+    let (callback, response) = oneshot::channel();
+    let response = match mempool_client.send(request(callback)).await {
+        Ok(()) => response.await.ok(),
+        Err(_) => None,
+    };
+    Ok(match response {
+        Some(response) => to_reply(response),
+        None => reply::with_status("mempool isn't running", StatusCode::SERVICE_UNAVAILABLE)
+            .into_response(),
+    })
+}
+
+/// Takes a profile off the runtime, as profiling blocks for its whole duration.
+async fn profile(
+    profiler: Arc<Profiler>,
+    content_type: &'static str,
+    take: impl FnOnce(&Profiler) -> Result<Vec<u8>, ProfileError> + Send + 'static,
+) -> Result<reply::Response, Infallible> {
+    let result = tokio::task::spawn_blocking(move || take(&profiler))
+        .await
+        .unwrap_or_else(|error| Err(ProfileError::Failed(error.to_string())));
+    Ok(match result {
+        Ok(profile) => reply::with_header(profile, "content-type", content_type).into_response(),
+        Err(error) => {
+            let status = match error {
+                ProfileError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+                ProfileError::Busy => StatusCode::CONFLICT,
+                ProfileError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+                ProfileError::Failed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            reply::with_status(error.to_string(), status).into_response()
+        }
+    })
+}
+
+/// Replaces the values of the `SECRET_FIELDS`.
+pub(crate) fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, value) in fields.iter_mut() {
+                if SECRET_FIELDS.contains(&name.as_str()) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_secrets(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_secrets),
+        _ => (),
+    }
+}
+
+/// Rejects the requests that don't carry the auth token.
+fn authorized(auth_token: String) -> impl warp::Filter<Extract = (), Error = Rejection> + Clone {
+    let expected = format!("Bearer {}", auth_token);
+    warp::header::optional::<String>("authorization")
+        .and_then(move |header: Option<String>| {
+            // compared in constant time, not to leak the token through the response time
+            let authorized = header.map_or(false, |header| {
+                bool::from(header.as_bytes().ct_eq(expected.as_bytes()))
+            });
+            async move {
+                if authorized {
+                    Ok(())
+                } else {
+                    Err(reject::custom(Unauthorized))
+                }
+            }
+        })
+        .untuple_one()
+}
+
+async fn handle_rejection(err: Rejection) -> Result<impl Reply, Rejection> {
+    if err.find::<Unauthorized>().is_some() {
+        Ok(reply::with_status(
+            "missing or invalid auth token",
+            StatusCode::UNAUTHORIZED,
+        ))
+    } else {
+        Err(err)
+    }
+}
+
+#[cfg(test)]
+#[path = "admin_service_test.rs"]
+mod admin_service_test;
@@ -0,0 +1,388 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::admin_service::{
+    get_routes, AdminServiceContext, LogFilters, LogLevelRequest, LogTraceRequest, REDACTED,
+};
+use consensus::state_snapshot::SharedConsensusState;
+use diem_config::{
+    config::{NodeConfig, SecureBackend, Socks5ProxyConfig, Token, VaultConfig},
+    network_id::NetworkId,
+    reload::ConfigReloader,
+};
+use diem_crypto::{
+    ed25519::Ed25519PrivateKey, hash::CryptoHash, PrivateKey, Uniform, ValidCryptoMaterialStringExt,
+};
+use diem_logger::{Level, Logger};
+use diem_mempool::{
+    AccountPendingTransactions, MempoolClientRequest, MempoolStats, TransactionSummary,
+};
+use diem_types::{
+    account_address::AccountAddress, test_helpers::transaction_test_helpers::get_test_signed_txn,
+    transaction::Transaction,
+};
+use futures::{channel::mpsc, StreamExt};
+use network::application::storage::PeerMetadataStorage;
+use rand::{rngs::StdRng, SeedableRng};
+use serde_json::Value;
+use std::{sync::Arc, time::Duration};
+use warp::http::StatusCode;
+
+const AUTH_TOKEN: &str = "secret";
+
+fn test_context() -> (AdminServiceContext, mpsc::Receiver<MempoolClientRequest>) {
+    let mut node_config = NodeConfig::default_for_validator();
+    node_config.admin_service.enabled = true;
+    node_config.admin_service.auth_token = Some(AUTH_TOKEN.to_string());
+    let (mempool_client, mempool_requests) = mpsc::channel(1);
+    let context = AdminServiceContext {
+        node_config,
+        logger: None,
+        config_reloader: None,
+        consensus_state: SharedConsensusState::new(),
+        peers: vec![(NetworkId::Validator, Arc::new(PeerMetadataStorage::new()))],
+        mempool_client,
+    };
+    (context, mempool_requests)
+}
+
+async fn request(
+    context: &AdminServiceContext,
+    method: &str,
+    path: &str,
+    auth_token: Option<&str>,
+) -> (StatusCode, Value) {
+    request_with_body(context, method, path, auth_token, Value::Null).await
+}
+
+async fn request_with_body(
+    context: &AdminServiceContext,
+    method: &str,
+    path: &str,
+    auth_token: Option<&str>,
+    body: Value,
+) -> (StatusCode, Value) {
+    let mut request = warp::test::request().method(method).path(path);
+    if !body.is_null() {
+        request = request.json(&body);
+    }
+    if let Some(auth_token) = auth_token {
+        request = request.header("authorization", format!("Bearer {}", auth_token));
+    }
+    let response = request
+        .reply(&get_routes(AUTH_TOKEN.to_string(), context.clone()))
+        .await;
+    let body = serde_json::from_slice(response.body()).unwrap_or(Value::Null);
+    (response.status(), body)
+}
+
+#[tokio::test]
+async fn test_unauthorized() {
+    let (context, _) = test_context();
+    for path in &["/config", "/consensus/state", "/peers", "/mempool/stats"] {
+        let (status, _) = request(&context, "GET", path, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = request(&context, "GET", path, Some("wrong")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
+
+#[tokio::test]
+async fn test_config_dump() {
+    let (context, _) = test_context();
+    let (status, config) = request(&context, "GET", "/config", Some(AUTH_TOKEN)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(config["admin_service"]["enabled"], true);
+    // the secrets don't leave the node
+    assert_eq!(config["admin_service"]["auth_token"], REDACTED);
+    assert!(!config.to_string().contains(AUTH_TOKEN));
+}
+
+#[tokio::test]
+async fn test_config_dump_redacts_every_secret() {
+    const PROXY_PASSWORD: &str = "proxy-password";
+    const VAULT_TOKEN: &str = "vault-token";
+
+    let (mut context, _) = test_context();
+    // sets the private keys of the test configs, and the identity of the validator network
+    let mut rng = StdRng::from_seed([0u8; 32]);
+    let mut node_config = NodeConfig::random_with_template(0, &context.node_config, &mut rng);
+    let network = node_config.validator_network.as_mut().unwrap();
+    network.socks5_proxy_config = Some(Socks5ProxyConfig {
+        address: "localhost:1080".to_string(),
+        username: Some("diem".to_string()),
+        password: Some(PROXY_PASSWORD.to_string()),
+        enabled: true,
+    });
+    node_config.consensus.safety_rules.backend = SecureBackend::Vault(VaultConfig {
+        ca_certificate: None,
+        namespace: None,
+        renew_ttl_secs: None,
+        server: "http://localhost:8200".to_string(),
+        token: Token::FromConfig(VAULT_TOKEN.to_string()),
+        disable_cas: None,
+        disable_key_export: None,
+        connection_timeout_ms: None,
+        response_timeout_ms: None,
+    });
+    node_config.json_rpc.api_key.enabled = true;
+
+    let test = node_config.test.as_ref().unwrap();
+    let safety_rules_test = node_config.consensus.safety_rules.test.as_ref().unwrap();
+    let mut secrets = vec![
+        AUTH_TOKEN.to_string(),
+        PROXY_PASSWORD.to_string(),
+        VAULT_TOKEN.to_string(),
+        network.identity_key().to_encoded_string().unwrap(),
+    ];
+    for key in &[
+        &test.operator_key,
+        &test.owner_key,
+        &test.execution_key,
+        &safety_rules_test.consensus_key,
+        &safety_rules_test.execution_key,
+    ] {
+        let key = key.as_ref().unwrap().private_key();
+        secrets.push(key.to_encoded_string().unwrap());
+    }
+    context.node_config = node_config;
+
+    let (status, config) = request(&context, "GET", "/config", Some(AUTH_TOKEN)).await;
+    assert_eq!(status, StatusCode::OK);
+    let dump = config.to_string();
+    for secret in &secrets {
+        assert!(!dump.contains(secret.as_str()), "{} leaked", secret);
+    }
+    let proxy = &config["validator_network"]["socks5_proxy_config"];
+    assert_eq!(proxy["password"], REDACTED);
+    assert_eq!(proxy["username"], "diem");
+    // the fields that only look like secrets are kept
+    assert_eq!(config["json_rpc"]["api_key"]["enabled"], true);
+    assert_ne!(config["test"]["auth_key"], REDACTED);
+    assert!(!config["test"]["auth_key"].is_null());
+}
+
+#[tokio::test]
+async fn test_mempool() {
+    let (context, mut mempool_requests) = test_context();
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+    let account = AccountAddress::random();
+    let txn = get_test_signed_txn(account, 0, &private_key, private_key.public_key(), None);
+    let hash = Transaction::UserTransaction(txn.clone()).hash();
+    tokio::spawn(async move {
+        while let Some(request) = mempool_requests.next().await {
+            match request {
+                MempoolClientRequest::GetAccountPendingTransactions(_, callback) => {
+                    let pending = AccountPendingTransactions {
+                        sequence_number: 0,
+                        transactions: vec![(txn.clone(), false)],
+                        sequence_gaps: vec![],
+                    };
+                    callback.send(Ok(pending)).unwrap();
+                }
+                MempoolClientRequest::GetParkingLot(callback) => {
+                    let parked = TransactionSummary {
+                        sender: account,
+                        sequence_number: 5,
+                    };
+                    callback.send(vec![parked]).unwrap();
+                }
+                MempoolClientRequest::EvictTransaction(_, callback) => callback.send(2).unwrap(),
+                MempoolClientRequest::EvictAccount(_, callback) => callback.send(1).unwrap(),
+                _ => panic!("unexpected mempool request"),
+            }
+        }
+    });
+
+    let path = format!("/mempool/accounts/{}/transactions", account);
+    let (status, transactions) = request(&context, "GET", &path, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(transactions, Value::Null);
+    let (status, transactions) = request(&context, "GET", &path, Some(AUTH_TOKEN)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(transactions.as_array().unwrap().len(), 1);
+    assert_eq!(transactions[0]["hash"], hash.to_hex());
+    assert_eq!(transactions[0]["is_parked"], false);
+
+    let (status, parking_lot) =
+        request(&context, "GET", "/mempool/parking_lot", Some(AUTH_TOKEN)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(parking_lot[0]["sequence_number"], 5);
+
+    let path = format!("/mempool/evict/transaction/{}", hash.to_hex());
+    let (status, evicted) = request(&context, "POST", &path, Some(AUTH_TOKEN)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(evicted["evicted"], 2);
+
+    let path = format!("/mempool/evict/account/{}", account);
+    let (status, evicted) = request(&context, "POST", &path, Some(AUTH_TOKEN)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(evicted["evicted"], 1);
+}
+
+#[tokio::test]
+async fn test_components() {
+    let (context, mut mempool_requests) = test_context();
+    let stats = MempoolStats {
+        ready: 3,
+        parked: 1,
+        size_bytes: 1024,
+    };
+    tokio::spawn(async move {
+        while let Some(MempoolClientRequest::GetStats(callback)) = mempool_requests.next().await {
+            callback.send(stats).unwrap();
+        }
+    });
+
+    let (status, mempool) = request(&context, "GET", "/mempool/stats", Some(AUTH_TOKEN)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(mempool["ready"], 3);
+    assert_eq!(mempool["parked"], 1);
+    assert_eq!(mempool["size_bytes"], 1024);
+
+    let (status, peers) = request(&context, "GET", "/peers", Some(AUTH_TOKEN)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(peers, Value::Array(vec![]));
+
+    // consensus never started
+    let (status, _) = request(&context, "GET", "/consensus/state", Some(AUTH_TOKEN)).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    // the node doesn't have a logger to filter
+    let (status, _) = request(&context, "POST", "/log/filter", Some(AUTH_TOKEN)).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+}
+
+async fn log_filters(context: &AdminServiceContext) -> LogFilters {
+    let (status, filters) = request(context, "GET", "/log/filter", Some(AUTH_TOKEN)).await;
+    assert_eq!(status, StatusCode::OK);
+    serde_json::from_value(filters).unwrap()
+}
+
+#[tokio::test]
+async fn test_log_level() {
+    let (mut context, _) = test_context();
+    context.logger = Some(Logger::builder().is_async(false).level(Level::Info).build());
+    assert_eq!(log_filters(&context).await.local, "info");
+
+    let set_level = |module: Option<&str>, level: &str| {
+        serde_json::to_value(LogLevelRequest {
+            module: module.map(str::to_string),
+            level: level.to_string(),
+        })
+        .unwrap()
+    };
+    for (module, level) in &[(Some("consensus"), "debug"), (None, "warn")] {
+        let (status, _) = request_with_body(
+            &context,
+            "POST",
+            "/log/level",
+            Some(AUTH_TOKEN),
+            set_level(*module, level),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    assert_eq!(log_filters(&context).await.local, "warn,consensus=debug");
+
+    let (status, _) = request_with_body(
+        &context,
+        "POST",
+        "/log/level",
+        Some(AUTH_TOKEN),
+        set_level(None, "loud"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_log_trace() {
+    let (mut context, _) = test_context();
+    context.logger = Some(Logger::builder().is_async(false).level(Level::Info).build());
+
+    let trace = |module: &str, seconds: u64| {
+        serde_json::to_value(LogTraceRequest {
+            module: module.to_string(),
+            seconds,
+        })
+        .unwrap()
+    };
+    let (status, _) = request_with_body(
+        &context,
+        "POST",
+        "/log/trace",
+        Some(AUTH_TOKEN),
+        trace("mempool", 1),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(log_filters(&context).await.local, "info,mempool=trace");
+
+    // the module is back to the level of its parents once the time is up
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert_eq!(log_filters(&context).await.local, "info");
+
+    let (status, _) = request_with_body(
+        &context,
+        "POST",
+        "/log/trace",
+        Some(AUTH_TOKEN),
+        trace("mempool", 24 * 3600),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_config_reload() {
+    let (mut context, _) = test_context();
+    let (status, _) = request(&context, "POST", "/config/reload", Some(AUTH_TOKEN)).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    // the config file is gone
+    context.config_reloader = Some(Arc::new(ConfigReloader::new(
+        diem_temppath::TempPath::new().path().to_path_buf(),
+        context.node_config.clone(),
+    )));
+    let (status, _) = request(&context, "POST", "/config/reload", Some(AUTH_TOKEN)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_config_dump_after_reload() {
+    let (mut context, _) = test_context();
+    let config_reloader = Arc::new(ConfigReloader::new(
+        diem_temppath::TempPath::new().path().to_path_buf(),
+        context.node_config.clone(),
+    ));
+    config_reloader.subscribe("logger", &["logger.level"], |_, _| Ok(()));
+    context.config_reloader = Some(config_reloader.clone());
+
+    let mut new_config = context.node_config.clone();
+    new_config.logger.level = Level::Debug;
+    config_reloader.apply(new_config).unwrap();
+
+    // the dump shows the config applied by the reload, not the one the node was started with
+    let (status, config) = request(&context, "GET", "/config", Some(AUTH_TOKEN)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(config["logger"]["level"], "DEBUG");
+    assert_eq!(config["admin_service"]["auth_token"], REDACTED);
+}
+
+#[tokio::test]
+async fn test_profile_duration() {
+    let (context, _) = test_context();
+    for path in &["/profile/cpu?seconds=1", "/profile/heap?seconds=1"] {
+        let (status, _) = request(&context, "GET", path, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    for path in &[
+        "/profile/cpu?seconds=0",
+        "/profile/cpu?seconds=100000&format=pprof",
+        "/profile/cpu?seconds=1&frequency=100000",
+    ] {
+        let (status, _) = request(&context, "GET", path, Some(AUTH_TOKEN)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
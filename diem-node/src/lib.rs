@@ -1,28 +1,49 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::{
+    admin_service::{start_admin_service, AdminServiceContext},
+    shutdown::{ShutdownCoordinator, ShutdownStage},
+};
 use backup_service::start_backup_service;
-use consensus::{consensus_provider::start_consensus, gen_consensus_reconfig_subscription};
+use consensus::{
+    commit_notification_bus::CommitNotificationBus,
+    consensus_provider::{start_consensus, start_consensus_observer},
+    gen_consensus_reconfig_subscription,
+    state_snapshot::SharedConsensusState,
+};
 use debug_interface::node_debug_service::NodeDebugService;
 use diem_config::{
     config::{NetworkConfig, NodeConfig, PersistableConfig},
-    network_id::NodeNetworkId,
+    network_id::{NetworkId, NodeNetworkId},
+    reload::ConfigReloader,
     utils::get_genesis_txn,
 };
-use diem_json_rpc::bootstrap_from_config as bootstrap_rpc;
+use diem_json_rpc::{bootstrap_from_config as bootstrap_rpc, RateLimiter};
 use diem_logger::{prelude::*, Logger};
-use diem_mempool::gen_mempool_reconfig_subscription;
+use diem_mempool::{gen_mempool_reconfig_subscription, MempoolClientRequest, MempoolClientSender};
 use diem_metrics::metric_server;
 use diem_time_service::TimeService;
 use diem_types::{
-    account_config::diem_root_address, account_state::AccountState, chain_id::ChainId,
-    move_resource::MoveStorage, on_chain_config::VMPublishingOption,
+    account_config::diem_root_address,
+    account_state::AccountState,
+    chain_id::ChainId,
+    move_resource::MoveStorage,
+    on_chain_config::{OnChainConfig, VMConfig, VMPublishingOption},
 };
-use diem_vm::DiemVM;
-use diemdb::DiemDB;
+use diem_vm::{execution_trace::ExecutionTracer, update_gas_schedule, DiemVM};
+use diemdb::{DiemDB, PruneWindows};
 use executor::{db_bootstrapper::maybe_bootstrap, Executor};
 use executor_types::ChunkExecutor;
-use futures::{channel::mpsc::channel, executor::block_on};
+use futures::{
+    channel::{
+        mpsc::{self, channel},
+        oneshot,
+    },
+    executor::block_on,
+    stream::StreamExt,
+    SinkExt,
+};
 use network_builder::builder::NetworkBuilder;
 use state_sync_v1::bootstrapper::StateSyncBootstrapper;
 use std::{
@@ -32,32 +53,110 @@ use std::{
     net::ToSocketAddrs,
     path::PathBuf,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicUsize, Ordering},
         Arc,
     },
     thread,
-    time::Instant,
+    time::{Duration, Instant},
 };
 use storage_interface::DbReaderWriter;
 use storage_service::start_storage_service_with_db;
-use tokio::runtime::{Builder, Runtime};
+use subscription_service::ReconfigSubscription;
+use tokio::{
+    runtime::{Builder, Runtime},
+    signal::unix::{signal, SignalKind},
+};
 use tokio_stream::wrappers::IntervalStream;
 
+mod admin_service;
+mod profiler;
+mod shutdown;
+mod telemetry;
+
 const AC_SMP_CHANNEL_BUFFER_SIZE: usize = 1_024;
 const INTRA_NODE_CHANNEL_BUFFER_SIZE: usize = 1;
 const MEMPOOL_NETWORK_CHANNEL_BUFFER_SIZE: usize = 1_024;
 
 pub struct DiemHandle {
-    _rpc: Runtime,
-    _mempool: Runtime,
-    _state_sync_bootstrapper: StateSyncBootstrapper,
-    _network_runtimes: Vec<Runtime>,
-    _consensus_runtime: Option<Runtime>,
+    rpc: Runtime,
+    mempool: Runtime,
+    state_sync_bootstrapper: StateSyncBootstrapper,
+    network_runtimes: Vec<Runtime>,
+    consensus_runtime: Option<Runtime>,
+    consensus_shutdown: Option<mpsc::Sender<oneshot::Sender<()>>>,
+    diem_db: Arc<DiemDB>,
+    commit_notification_bus: CommitNotificationBus,
+    shutdown_deadline: Duration,
     _debug: NodeDebugService,
     _backup: Runtime,
 }
 
-pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
+impl DiemHandle {
+    /// The bus the commits of consensus are published on, for the components embedding the node
+    /// (e.g., indexers) to subscribe to. Nothing is published on it if the node doesn't run
+    /// consensus.
+    pub fn commit_notification_bus(&self) -> &CommitNotificationBus {
+        &self.commit_notification_bus
+    }
+
+    /// Shuts the node down stage by stage, see `ShutdownStage`. Whatever is still running past
+    /// the deadline of the config is torn down abruptly, as on drop.
+    pub fn shutdown(self) {
+        let mut coordinator = ShutdownCoordinator::new();
+
+        let rpc = self.rpc;
+        coordinator.register(ShutdownStage::StopProposals, "json-rpc", move |time_left| {
+            rpc.shutdown_timeout(time_left)
+        });
+        if let Some(mut consensus_shutdown) = self.consensus_shutdown {
+            coordinator.register(ShutdownStage::StopProposals, "consensus", move |_| {
+                let (ack_sender, ack_receiver) = oneshot::channel();
+                // Consensus might be gone already
+                if block_on(consensus_shutdown.send(ack_sender)).is_ok() {
+                    let _ = block_on(ack_receiver);
+                }
+            });
+        }
+
+        if let Some(consensus_runtime) = self.consensus_runtime {
+            coordinator.register(
+                ShutdownStage::DrainCommits,
+                "consensus-runtime",
+                move |time_left| consensus_runtime.shutdown_timeout(time_left),
+            );
+        }
+        let state_sync_bootstrapper = self.state_sync_bootstrapper;
+        coordinator.register(
+            ShutdownStage::DrainCommits,
+            "state-sync",
+            move |time_left| state_sync_bootstrapper.shutdown(time_left),
+        );
+        let mempool = self.mempool;
+        coordinator.register(ShutdownStage::DrainCommits, "mempool", move |time_left| {
+            mempool.shutdown_timeout(time_left)
+        });
+
+        let diem_db = self.diem_db;
+        coordinator.register(ShutdownStage::FlushStorage, "storage", move |_| {
+            if let Err(error) = diem_db.wait_for_commit_sync() {
+                error!(error = ?error, "Failed to sync the last commit");
+            }
+        });
+
+        let network_runtimes = self.network_runtimes;
+        coordinator.register(ShutdownStage::CloseNetwork, "network", move |time_left| {
+            for runtime in network_runtimes {
+                runtime.shutdown_timeout(time_left);
+            }
+        });
+
+        coordinator.shutdown(self.shutdown_deadline);
+    }
+}
+
+/// Starts the node with the config loaded from `config_path`, if any, which is then re-read on
+/// SIGHUP to reload the config.
+pub fn start(config: &NodeConfig, config_path: Option<PathBuf>, log_file: Option<PathBuf>) {
     crash_handler::setup_panic_handler();
 
     let mut logger = diem_logger::Logger::new();
@@ -85,12 +184,19 @@ pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
         warn!("failpoints is set in config, but the binary doesn't compile with this feature");
     }
 
-    let _node_handle = setup_environment(config, logger);
-    let term = Arc::new(AtomicBool::new(false));
+    let node_handle = setup_environment(config, config_path, logger);
 
-    while !term.load(Ordering::Acquire) {
-        std::thread::park();
-    }
+    let (term_sender, term_receiver) = std::sync::mpsc::channel();
+    ctrlc::set_handler(move || {
+        let _ = term_sender.send(());
+    })
+    .expect("Failed to set the termination signal handler");
+    term_receiver
+        .recv()
+        .expect("The termination signal handler should not be dropped");
+
+    info!("Received a termination signal");
+    node_handle.shutdown();
 }
 
 pub fn load_test_environment<R>(
@@ -167,7 +273,8 @@ pub fn load_test_environment<R>(
     println!("Diem is running, press ctrl-c to exit");
     println!();
 
-    start(&config, Some(log_file))
+    // The config file isn't reloaded, the config differs from it
+    start(&config, None, Some(log_file))
 }
 
 // Fetch chain ID from on-chain resource
@@ -191,8 +298,15 @@ fn fetch_chain_id(db: &DbReaderWriter) -> ChainId {
         .chain_id()
 }
 
-fn setup_chunk_executor(db: DbReaderWriter) -> Box<dyn ChunkExecutor> {
-    Box::new(Executor::<DiemVM>::new(db))
+fn setup_chunk_executor(db: DbReaderWriter, node_config: &NodeConfig) -> Box<dyn ChunkExecutor> {
+    let tracer = node_config.execution.trace_path.as_ref().map(|trace_path| {
+        Arc::new(ExecutionTracer::new(trace_path).expect("Failed to open the execution trace"))
+    });
+    Box::new(
+        Executor::<DiemVM>::new(db)
+            .with_concurrency_level(node_config.execution.concurrency_level)
+            .with_execution_tracer(tracer),
+    )
 }
 
 fn setup_debug_interface(config: &NodeConfig, logger: Option<Arc<Logger>>) -> NodeDebugService {
@@ -209,8 +323,6 @@ fn setup_debug_interface(config: &NodeConfig, logger: Option<Arc<Logger>>) -> No
 }
 
 async fn periodic_state_dump(node_config: NodeConfig, db: DbReaderWriter) {
-    use futures::stream::StreamExt;
-
     let args: Vec<String> = ::std::env::args().collect();
 
     // Once an hour
@@ -252,8 +364,92 @@ async fn periodic_state_dump(node_config: NodeConfig, db: DbReaderWriter) {
     }
 }
 
-pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>) -> DiemHandle {
-    let debug_if = setup_debug_interface(node_config, logger);
+/// Subscribes the components to the reloads of the fields of the config they can apply at runtime.
+fn setup_config_reloader(
+    node_config: &NodeConfig,
+    config_path: PathBuf,
+    logger: Option<Arc<Logger>>,
+    mempool_client: MempoolClientSender,
+    rpc_rate_limiter: Arc<RateLimiter>,
+    inbound_connection_limits: Vec<(NetworkId, Arc<AtomicUsize>)>,
+) -> ConfigReloader {
+    let config_reloader = ConfigReloader::new(config_path, node_config.clone());
+    if let Some(logger) = logger {
+        config_reloader.subscribe("logger", &["logger.level"], move |config, _| {
+            logger.set_level(None, config.logger.level.into());
+            Ok(())
+        });
+    }
+    config_reloader.subscribe(
+        "mempool",
+        &[
+            "mempool.capacity",
+            "mempool.capacity_bytes",
+            "mempool.capacity_bytes_per_user",
+            "mempool.capacity_per_user",
+        ],
+        move |config, _| {
+            // Every sender has a slot in the channel, the new one can't find it full
+            let request = MempoolClientRequest::UpdateCapacities(config.mempool.clone());
+            mempool_client
+                .clone()
+                .try_send(request)
+                .map_err(|error| format!("failed to update the capacities: {}", error))
+        },
+    );
+    config_reloader.subscribe("json-rpc", &["json_rpc.rate_limit"], move |config, _| {
+        rpc_rate_limiter
+            .update(&config.json_rpc.rate_limit)
+            .map_err(|error| format!("failed to update the rate limits: {}", error))
+    });
+    config_reloader.subscribe(
+        "network",
+        &[
+            "full_node_networks.*.max_inbound_connections",
+            "validator_network.max_inbound_connections",
+        ],
+        move |config, _| {
+            let network_configs = config
+                .full_node_networks
+                .iter()
+                .chain(config.validator_network.as_ref());
+            for network_config in network_configs {
+                let (_, limit) = inbound_connection_limits
+                    .iter()
+                    .find(|(network_id, _)| *network_id == network_config.network_id)
+                    .ok_or_else(|| {
+                        format!("no {} network is running", network_config.network_id)
+                    })?;
+                limit.store(network_config.max_inbound_connections, Ordering::Relaxed);
+            }
+            Ok(())
+        },
+    );
+    config_reloader
+}
+
+async fn reload_config_on_hangup(config_reloader: Arc<ConfigReloader>) {
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(error) => {
+            error!(error = ?error, "Failed to listen to SIGHUP, the config won't be reloaded");
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        match config_reloader.reload() {
+            Ok(changes) => info!(changes = changes, "Reloaded the config"),
+            Err(error) => error!(error = ?error, "Failed to reload the config"),
+        }
+    }
+}
+
+pub fn setup_environment(
+    node_config: &NodeConfig,
+    config_path: Option<PathBuf>,
+    logger: Option<Arc<Logger>>,
+) -> DiemHandle {
+    let debug_if = setup_debug_interface(node_config, logger.clone());
 
     let metrics_port = node_config.debug_interface.metrics_server_port;
     let metric_host = node_config.debug_interface.address.clone();
@@ -266,11 +462,16 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
 
     let mut instant = Instant::now();
     let (diem_db, db_rw) = DbReaderWriter::wrap(
-        DiemDB::open(
+        DiemDB::open_with_prune_windows(
             &node_config.storage.dir(),
             false, /* readonly */
-            node_config.storage.prune_window,
-            node_config.storage.rocksdb_config,
+            PruneWindows {
+                state: node_config.storage.prune_window,
+                transactions: node_config.storage.transaction_prune_window,
+                events: node_config.storage.event_prune_window,
+                archive: node_config.storage.archive_window,
+            },
+            node_config.storage.rocksdb_config.clone(),
         )
         .expect("DB should open."),
     );
@@ -295,7 +496,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     );
 
     instant = Instant::now();
-    let chunk_executor = setup_chunk_executor(db_rw.clone());
+    let chunk_executor = setup_chunk_executor(db_rw.clone(), node_config);
     debug!(
         "ChunkExecutor setup in {} ms",
         instant.elapsed().as_millis()
@@ -305,7 +506,10 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     let mut state_sync_network_handles = vec![];
     let mut mempool_network_handles = vec![];
     let mut consensus_network_handles = None;
+    let mut observer_network_handles = None;
     let mut reconfig_subscriptions = vec![];
+    let mut peer_metadata_storages = vec![];
+    let mut inbound_connection_limits = vec![];
 
     let (mempool_reconfig_subscription, mempool_reconfig_events) =
         gen_mempool_reconfig_subscription();
@@ -313,9 +517,16 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     // consensus has to subscribe to ALL on-chain configs
     let (consensus_reconfig_subscription, consensus_reconfig_events) =
         gen_consensus_reconfig_subscription();
-    if node_config.base.role.is_validator() {
+    let observer_config = &node_config.consensus.observer;
+    let observer_enabled =
+        !node_config.base.role.is_validator() && observer_config.observer_enabled;
+    if node_config.base.role.is_validator() || observer_enabled {
         reconfig_subscriptions.push(consensus_reconfig_subscription);
     }
+    // the VM swaps in the gas schedule of every reconfiguration for the blocks of the new epoch
+    let (vm_reconfig_subscription, mut vm_reconfig_events) =
+        ReconfigSubscription::subscribe_all("vm", vec![VMConfig::CONFIG_ID], vec![]);
+    reconfig_subscriptions.push(vm_reconfig_subscription);
 
     // Gather all network configs into a single vector.
     let mut network_configs: Vec<&NetworkConfig> = node_config.full_node_networks.iter().collect();
@@ -377,7 +588,25 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             );
         }
 
+        // Validators publish and fullnodes observe consensus over the VFN network.
+        if network_id.is_vfn_network()
+            && ((node_config.base.role.is_validator() && observer_config.publisher_enabled)
+                || observer_enabled)
+            && observer_network_handles.is_none()
+        {
+            observer_network_handles = Some(network_builder.add_protocol_handler(
+                consensus::observer_network_interface::network_endpoint_config(
+                    observer_config.max_network_channel_size,
+                ),
+            ));
+        }
+
         reconfig_subscriptions.append(network_builder.reconfig_subscriptions());
+        inbound_connection_limits.push((
+            network_id.clone(),
+            network_builder.inbound_connection_limit(),
+        ));
+        peer_metadata_storages.push((network_id, network_builder.peer_metadata_storage()));
 
         let network_context = network_builder.network_context();
         network_builder.build(runtime.handle().clone());
@@ -402,7 +631,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         state_sync_network_handles,
         mempool_notifier,
         consensus_listener,
-        Arc::clone(&db_rw.reader),
+        db_rw.clone(),
         chunk_executor,
         node_config,
         genesis_waypoint,
@@ -410,9 +639,19 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     );
     let (mp_client_sender, mp_client_events) = channel(AC_SMP_CHANNEL_BUFFER_SIZE);
 
-    let rpc_runtime = bootstrap_rpc(node_config, chain_id, diem_db.clone(), mp_client_sender);
+    let mempool_client = mp_client_sender.clone();
+    let rpc_rate_limiter = Arc::new(RateLimiter::new(&node_config.json_rpc.rate_limit));
+    let rpc_runtime = bootstrap_rpc(
+        node_config,
+        chain_id,
+        diem_db.clone(),
+        mp_client_sender,
+        rpc_rate_limiter.clone(),
+    );
 
     let mut consensus_runtime = None;
+    let mut consensus_shutdown = None;
+    let consensus_state = SharedConsensusState::new();
     let (consensus_to_mempool_sender, consensus_requests) = channel(INTRA_NODE_CHANNEL_BUFFER_SIZE);
 
     instant = Instant::now();
@@ -427,6 +666,9 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     );
     debug!("Mempool started in {} ms", instant.elapsed().as_millis());
 
+    let commit_notification_bus =
+        CommitNotificationBus::new(node_config.consensus.commit_notification_bus);
+
     // StateSync should be instantiated and started before Consensus to avoid a cyclic dependency:
     // network provider -> consensus -> state synchronizer -> network provider.  This has resulted
     // in a deadlock as observed in GitHub issue #749.
@@ -444,30 +686,103 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
 
         // Initialize and start consensus.
         instant = Instant::now();
+        let (shutdown_sender, consensus_shutdown_requests) = channel(1);
+        consensus_shutdown = Some(shutdown_sender);
         consensus_runtime = Some(start_consensus(
             node_config,
             consensus_network_sender,
             consensus_network_events,
             Box::new(consensus_notifier),
             consensus_to_mempool_sender,
-            diem_db,
+            diem_db.clone(),
             consensus_reconfig_events,
+            observer_network_handles,
+            commit_notification_bus.clone(),
+            consensus_state.clone(),
+            consensus_shutdown_requests,
         ));
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
+    } else if let Some((observer_network_sender, observer_network_events)) =
+        observer_network_handles
+    {
+        // The observer relies on state sync to catch up to the observed commit decisions.
+        instant = Instant::now();
+        consensus_runtime = Some(start_consensus_observer(
+            observer_network_sender,
+            observer_network_events,
+            Box::new(consensus_notifier),
+            diem_db.clone(),
+            consensus_reconfig_events,
+        ));
+        debug!(
+            "Consensus observer started in {} ms",
+            instant.elapsed().as_millis()
+        );
     }
 
+    debug_if.runtime().handle().spawn(async move {
+        while let Some(payload) = vm_reconfig_events.next().await {
+            update_gas_schedule(&payload);
+        }
+    });
+
     // Spawn a task which will periodically dump some interesting state
     debug_if
         .runtime()
         .handle()
         .spawn(periodic_state_dump(node_config.to_owned(), db_rw));
 
+    let config_reloader = config_path.map(|config_path| {
+        Arc::new(setup_config_reloader(
+            node_config,
+            config_path,
+            logger.clone(),
+            mempool_client.clone(),
+            rpc_rate_limiter,
+            inbound_connection_limits,
+        ))
+    });
+    if let Some(config_reloader) = &config_reloader {
+        debug_if
+            .runtime()
+            .handle()
+            .spawn(reload_config_on_hangup(config_reloader.clone()));
+    }
+
+    if node_config.telemetry.enabled {
+        debug_if
+            .runtime()
+            .handle()
+            .spawn(telemetry::report_periodically(
+                node_config.telemetry.clone(),
+                node_config.data_dir().to_path_buf(),
+                node_config.base.role,
+                peer_metadata_storages.clone(),
+            ));
+    }
+
+    start_admin_service(
+        debug_if.runtime().handle(),
+        AdminServiceContext {
+            node_config: node_config.to_owned(),
+            logger,
+            config_reloader,
+            consensus_state,
+            peers: peer_metadata_storages,
+            mempool_client,
+        },
+    );
+
     DiemHandle {
-        _network_runtimes: network_runtimes,
-        _rpc: rpc_runtime,
-        _mempool: mempool,
-        _state_sync_bootstrapper: state_sync_bootstrapper,
-        _consensus_runtime: consensus_runtime,
+        network_runtimes,
+        rpc: rpc_runtime,
+        mempool,
+        state_sync_bootstrapper,
+        consensus_runtime,
+        consensus_shutdown,
+        diem_db,
+        commit_notification_bus,
+        shutdown_deadline: Duration::from_millis(node_config.shutdown.deadline_ms),
         _debug: debug_if,
         _backup: backup_service,
     }
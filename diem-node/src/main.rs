@@ -61,8 +61,9 @@ fn main() {
         };
         diem_node::load_test_environment(args.config, args.random_ports, publishing_option, rng);
     } else {
-        let config = NodeConfig::load(args.config.unwrap()).expect("Failed to load node config");
+        let config_path = args.config.unwrap();
+        let config = NodeConfig::load(&config_path).expect("Failed to load node config");
         println!("Using node config {:?}", &config);
-        diem_node::start(&config, None);
+        diem_node::start(&config, Some(config_path), None);
     };
 }
@@ -0,0 +1,191 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! On-demand profiles of the running node, served by the admin service:
+//!   - CPU profiles are sampled with `pprof`, and returned as an SVG flamegraph or as a protobuf
+//!     readable by `go tool pprof`.
+//!   - Heap profiles are sampled by jemalloc, and returned as a jemalloc heap dump readable by
+//!     `jeprof` or `go tool pprof`. jemalloc only samples if the node was started with
+//!     `MALLOC_CONF=prof:true,prof_active:false`; the sampling is then activated while profiling.
+//!
+//! One profile is taken at a time, for a bounded duration.
+
+use diem_logger::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    ffi::CString,
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+/// Longest time a profile can be taken for
+pub(crate) const MAX_PROFILE_SECS: u64 = 300;
+/// Samples per second of the CPU profiles, a prime number not to sample in lockstep with timers
+const DEFAULT_CPU_FREQUENCY: i32 = 99;
+const MAX_CPU_FREQUENCY: i32 = 1000;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CpuProfileFormat {
+    Flamegraph,
+    Pprof,
+}
+
+impl Default for CpuProfileFormat {
+    fn default() -> Self {
+        CpuProfileFormat::Flamegraph
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct CpuProfileRequest {
+    pub seconds: u64,
+    /// Samples per second
+    pub frequency: Option<i32>,
+    #[serde(default)]
+    pub format: CpuProfileFormat,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct HeapProfileRequest {
+    pub seconds: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum ProfileError {
+    /// The request asks for more than the profiler allows
+    InvalidRequest(String),
+    /// Another profile is being taken
+    Busy,
+    /// The node can't take this kind of profile
+    Unavailable(String),
+    Failed(String),
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProfileError::InvalidRequest(error) => write!(f, "invalid request: {}", error),
+            ProfileError::Busy => write!(f, "another profile is being taken"),
+            ProfileError::Unavailable(error) => write!(f, "profiling unavailable: {}", error),
+            ProfileError::Failed(error) => write!(f, "profiling failed: {}", error),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Profiler {
+    busy: AtomicBool,
+}
+
+/// Frees the profiler once the profile is taken, or given up.
+struct ProfilingGuard<'a>(&'a AtomicBool);
+
+impl Drop for ProfilingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+impl Profiler {
+    fn start(&self, seconds: u64) -> Result<ProfilingGuard, ProfileError> {
+        if seconds == 0 || seconds > MAX_PROFILE_SECS {
+            return Err(ProfileError::InvalidRequest(format!(
+                "can only profile for 1 to {}s",
+                MAX_PROFILE_SECS
+            )));
+        }
+        if self.busy.swap(true, Ordering::SeqCst) {
+            return Err(ProfileError::Busy);
+        }
+        Ok(ProfilingGuard(&self.busy))
+    }
+
+    /// Samples the stacks of all the threads of the node for the duration of the request. Blocks
+    /// until then.
+    pub fn profile_cpu(&self, request: &CpuProfileRequest) -> Result<Vec<u8>, ProfileError> {
+        let frequency = request.frequency.unwrap_or(DEFAULT_CPU_FREQUENCY);
+        if frequency <= 0 || frequency > MAX_CPU_FREQUENCY {
+            return Err(ProfileError::InvalidRequest(format!(
+                "can only sample 1 to {} times per second",
+                MAX_CPU_FREQUENCY
+            )));
+        }
+        let _guard = self.start(request.seconds)?;
+        info!(
+            seconds = request.seconds,
+            frequency = frequency,
+            "Profiling CPU"
+        );
+
+        let failed = |error: pprof::Error| ProfileError::Failed(error.to_string());
+        let profiler = pprof::ProfilerGuard::new(frequency).map_err(failed)?;
+        thread::sleep(Duration::from_secs(request.seconds));
+        let report = profiler.report().build().map_err(failed)?;
+        drop(profiler);
+
+        let mut profile = vec![];
+        match request.format {
+            CpuProfileFormat::Flamegraph => report.flamegraph(&mut profile).map_err(failed)?,
+            CpuProfileFormat::Pprof => {
+                use pprof::protos::Message;
+                report
+                    .pprof()
+                    .map_err(failed)?
+                    .encode(&mut profile)
+                    .map_err(|error| ProfileError::Failed(error.to_string()))?
+            }
+        }
+        info!("Profiled CPU");
+        Ok(profile)
+    }
+
+    /// Activates the sampling of the allocations of jemalloc for the duration of the request,
+    /// then dumps the live sampled allocations. Blocks until then.
+    pub fn profile_heap(&self, request: &HeapProfileRequest) -> Result<Vec<u8>, ProfileError> {
+        if !jemalloc_profiling_enabled() {
+            return Err(ProfileError::Unavailable(
+                "jemalloc doesn't sample the allocations, restart the node with \
+                 MALLOC_CONF=prof:true,prof_active:false"
+                    .into(),
+            ));
+        }
+        let _guard = self.start(request.seconds)?;
+        info!(seconds = request.seconds, "Profiling heap");
+
+        set_jemalloc_profiling_active(true)?;
+        thread::sleep(Duration::from_secs(request.seconds));
+        let dump = dump_jemalloc_profile();
+        set_jemalloc_profiling_active(false)?;
+        info!("Profiled heap");
+        dump
+    }
+}
+
+fn jemalloc_profiling_enabled() -> bool {
+    // SAFETY: `opt.prof` is a boolean
+    unsafe { jemalloc_ctl::raw::read::<bool>(b"opt.prof\0") }.unwrap_or(false)
+}
+
+fn set_jemalloc_profiling_active(active: bool) -> Result<(), ProfileError> {
+    // SAFETY: `prof.active` is a boolean
+    unsafe { jemalloc_ctl::raw::write(b"prof.active\0", active) }
+        .map_err(|error| ProfileError::Failed(error.to_string()))
+}
+
+fn dump_jemalloc_profile() -> Result<Vec<u8>, ProfileError> {
+    let path = diem_temppath::TempPath::new();
+    let failed = |error: &dyn fmt::Display| ProfileError::Failed(error.to_string());
+    let c_path =
+        CString::new(path.path().to_string_lossy().as_bytes()).map_err(|error| failed(&error))?;
+    // SAFETY: `prof.dump` takes the path of the dump as a C string, which outlives the call
+    unsafe { jemalloc_ctl::raw::write(b"prof.dump\0", c_path.as_ptr()) }
+        .map_err(|error| failed(&error))?;
+    std::fs::read(path.path()).map_err(|error| failed(&error))
+}
+
+#[cfg(test)]
+#[path = "profiler_test.rs"]
+mod profiler_test;
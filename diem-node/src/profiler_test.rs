@@ -0,0 +1,59 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::profiler::{
+    CpuProfileFormat, CpuProfileRequest, ProfileError, Profiler, MAX_PROFILE_SECS,
+};
+use std::{sync::Arc, thread, time::Duration};
+
+fn cpu_request(seconds: u64, format: CpuProfileFormat) -> CpuProfileRequest {
+    CpuProfileRequest {
+        seconds,
+        frequency: None,
+        format,
+    }
+}
+
+#[test]
+fn test_bounded_duration() {
+    let profiler = Profiler::default();
+    for seconds in &[0, MAX_PROFILE_SECS + 1] {
+        assert!(matches!(
+            profiler.profile_cpu(&cpu_request(*seconds, CpuProfileFormat::Flamegraph)),
+            Err(ProfileError::InvalidRequest(_))
+        ));
+    }
+    let request = CpuProfileRequest {
+        frequency: Some(0),
+        ..cpu_request(1, CpuProfileFormat::Flamegraph)
+    };
+    assert!(matches!(
+        profiler.profile_cpu(&request),
+        Err(ProfileError::InvalidRequest(_))
+    ));
+}
+
+#[test]
+fn test_cpu_profile() {
+    let profiler = Arc::new(Profiler::default());
+    let cpu_profiler = profiler.clone();
+    let flamegraph = thread::spawn(move || {
+        cpu_profiler.profile_cpu(&cpu_request(2, CpuProfileFormat::Flamegraph))
+    });
+
+    // One profile at a time
+    thread::sleep(Duration::from_millis(500));
+    assert_eq!(
+        profiler.profile_cpu(&cpu_request(1, CpuProfileFormat::Pprof)),
+        Err(ProfileError::Busy)
+    );
+
+    let flamegraph = String::from_utf8(flamegraph.join().unwrap().unwrap()).unwrap();
+    assert!(flamegraph.contains("<svg"));
+
+    // The profiler is free again once done
+    assert!(!profiler
+        .profile_cpu(&cpu_request(1, CpuProfileFormat::Pprof))
+        .unwrap()
+        .is_empty());
+}
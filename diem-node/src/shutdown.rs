@@ -0,0 +1,112 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Ordered shutdown of the node. The components register hooks to a stage, the stages are run
+//! one after the other and the hooks of a stage concurrently, each on its own thread. Dropping
+//! the components in an arbitrary order instead could have commits and safety data writes race
+//! the teardown of the storage and network.
+
+use diem_logger::prelude::*;
+use std::{
+    collections::BTreeMap,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum ShutdownStage {
+    /// Stop taking proposals and transactions, once the events being processed are done.
+    StopProposals,
+    /// Wait for the blocks and chunks being executed and committed.
+    DrainCommits,
+    /// Make the commits durable.
+    FlushStorage,
+    /// Close the connections to the peers.
+    CloseNetwork,
+}
+
+/// Takes the time left before the deadline.
+type ShutdownHook = Box<dyn FnOnce(Duration) + Send>;
+
+#[derive(Default)]
+pub struct ShutdownCoordinator {
+    hooks: BTreeMap<ShutdownStage, Vec<(&'static str, ShutdownHook)>>,
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(
+        &mut self,
+        stage: ShutdownStage,
+        name: &'static str,
+        hook: impl FnOnce(Duration) + Send + 'static,
+    ) {
+        self.hooks
+            .entry(stage)
+            .or_default()
+            .push((name, Box::new(hook)));
+    }
+
+    /// Runs the stages in order, each once all the hooks of the previous one returned. Past the
+    /// deadline, the hooks still running are left behind and the next stages are skipped.
+    ///
+    /// Returns whether all the hooks returned before the deadline.
+    pub fn shutdown(self, deadline: Duration) -> bool {
+        let deadline = Instant::now() + deadline;
+        for (stage, hooks) in self.hooks {
+            info!(stage = ?stage, "Shutting down");
+            let (done_sender, done_receiver) = mpsc::channel();
+            let mut pending: Vec<_> = hooks.iter().map(|(name, _)| *name).collect();
+            for (name, hook) in hooks {
+                let done_sender = done_sender.clone();
+                let time_left = deadline.saturating_duration_since(Instant::now());
+                thread::Builder::new()
+                    .name(format!("shutdown-{}", name))
+                    .spawn(move || {
+                        hook(time_left);
+                        // The coordinator might have given up on the hook
+                        let _ = done_sender.send(name);
+                    })
+                    .expect("Failed to spawn a shutdown thread");
+            }
+            drop(done_sender);
+
+            while !pending.is_empty() {
+                let time_left = deadline.saturating_duration_since(Instant::now());
+                match done_receiver.recv_timeout(time_left) {
+                    Ok(name) => {
+                        if let Some(index) = pending.iter().position(|pending| *pending == name) {
+                            pending.swap_remove(index);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {
+                        error!(
+                            stage = ?stage,
+                            pending = ?pending,
+                            "Shutdown deadline passed, giving up"
+                        );
+                        return false;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => {
+                        error!(
+                            stage = ?stage,
+                            pending = ?pending,
+                            "Shutdown hooks panicked, giving up"
+                        );
+                        return false;
+                    }
+                }
+            }
+        }
+        info!("Shutdown complete");
+        true
+    }
+}
+
+#[cfg(test)]
+#[path = "shutdown_test.rs"]
+mod shutdown_test;
@@ -0,0 +1,112 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::shutdown::{ShutdownCoordinator, ShutdownStage};
+use diem_infallible::Mutex;
+use std::{sync::Arc, thread, time::Duration};
+
+const DEADLINE: Duration = Duration::from_secs(10);
+
+fn record(
+    coordinator: &mut ShutdownCoordinator,
+    log: &Arc<Mutex<Vec<&'static str>>>,
+    stage: ShutdownStage,
+    name: &'static str,
+) {
+    let log = log.clone();
+    coordinator.register(stage, name, move |_| log.lock().push(name));
+}
+
+#[test]
+fn test_stages_in_order() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let mut coordinator = ShutdownCoordinator::new();
+    // registered out of order
+    record(
+        &mut coordinator,
+        &log,
+        ShutdownStage::CloseNetwork,
+        "network",
+    );
+    record(
+        &mut coordinator,
+        &log,
+        ShutdownStage::FlushStorage,
+        "storage",
+    );
+    record(
+        &mut coordinator,
+        &log,
+        ShutdownStage::DrainCommits,
+        "state-sync",
+    );
+    record(
+        &mut coordinator,
+        &log,
+        ShutdownStage::StopProposals,
+        "consensus",
+    );
+
+    assert!(coordinator.shutdown(DEADLINE));
+    assert_eq!(
+        *log.lock(),
+        vec!["consensus", "state-sync", "storage", "network"]
+    );
+}
+
+#[test]
+fn test_stage_waits_for_all_hooks() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let mut coordinator = ShutdownCoordinator::new();
+    let slow_log = log.clone();
+    coordinator.register(ShutdownStage::DrainCommits, "slow", move |_| {
+        thread::sleep(Duration::from_millis(100));
+        slow_log.lock().push("slow");
+    });
+    record(&mut coordinator, &log, ShutdownStage::DrainCommits, "fast");
+    record(
+        &mut coordinator,
+        &log,
+        ShutdownStage::FlushStorage,
+        "storage",
+    );
+
+    assert!(coordinator.shutdown(DEADLINE));
+    assert_eq!(*log.lock(), vec!["fast", "slow", "storage"]);
+}
+
+#[test]
+fn test_deadline() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let mut coordinator = ShutdownCoordinator::new();
+    coordinator.register(ShutdownStage::StopProposals, "stuck", |time_left| {
+        assert!(time_left <= Duration::from_millis(50));
+        thread::sleep(Duration::from_secs(60));
+    });
+    record(
+        &mut coordinator,
+        &log,
+        ShutdownStage::FlushStorage,
+        "storage",
+    );
+
+    // the stages after the stuck one are skipped
+    assert!(!coordinator.shutdown(Duration::from_millis(50)));
+    assert!(log.lock().is_empty());
+}
+
+#[test]
+fn test_panicking_hook() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let mut coordinator = ShutdownCoordinator::new();
+    coordinator.register(ShutdownStage::DrainCommits, "panicking", |_| panic!());
+    record(
+        &mut coordinator,
+        &log,
+        ShutdownStage::CloseNetwork,
+        "network",
+    );
+
+    assert!(!coordinator.shutdown(DEADLINE));
+    assert!(log.lock().is_empty());
+}
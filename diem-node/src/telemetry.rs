@@ -0,0 +1,277 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Opt-in telemetry: the node periodically posts reports of its health to the endpoint of the
+//! config. A report only carries the fields of `TELEMETRY_FIELDS` picked by the config, and a
+//! random id kept in the data dir, which tells the reports of a node apart without revealing it.
+//! The reports the endpoint doesn't accept are buffered, and sent again along with the next one.
+
+use diem_config::{
+    config::{RoleType, TelemetryConfig, TELEMETRY_FIELDS},
+    network_id::NetworkId,
+};
+use diem_crypto::HashValue;
+use diem_logger::prelude::*;
+use diem_metrics::{gather_metrics, get_json_metrics};
+use network::application::{storage::PeerMetadataStorage, types::PeerState};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Longest time a post of the reports can take
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// File of the data dir holding the id of the node in the reports
+const NODE_ID_FILE: &str = "telemetry_node_id";
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TelemetryReport {
+    /// Random id of the node, see `load_or_create_node_id`
+    pub node_id: String,
+    pub timestamp_secs: u64,
+    pub fields: BTreeMap<String, Value>,
+}
+
+/// Health of the node, of which the reports carry the fields allowed.
+#[derive(Clone, Debug)]
+pub(crate) struct NodeHealth {
+    pub version: String,
+    pub role: RoleType,
+    pub synced_version: u64,
+    pub highest_version: u64,
+    pub peers: BTreeMap<String, usize>,
+    pub error_rate: f64,
+}
+
+impl NodeHealth {
+    fn field(&self, field: &str) -> Option<Value> {
+        Some(match field {
+            "version" => json!(self.version),
+            "role" => json!(self.role.as_str()),
+            "synced_version" => json!(self.synced_version),
+            "sync_lag" => json!(self.highest_version.saturating_sub(self.synced_version)),
+            "peers" => json!(self.peers),
+            "error_rate" => json!(self.error_rate),
+            _ => return None,
+        })
+    }
+}
+
+/// Loads the id of the node in the reports from `data_dir`, or creates it there. The id is random,
+/// so that nothing links it to the node, and is kept across restarts. If it can't be read nor
+/// stored, the node reports under a new id until it restarts.
+pub(crate) fn load_or_create_node_id(data_dir: &Path) -> String {
+    let path = data_dir.join(NODE_ID_FILE);
+    match fs::read_to_string(&path) {
+        Ok(node_id) if !node_id.trim().is_empty() => return node_id.trim().to_string(),
+        Ok(_) => (),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => (),
+        Err(error) => {
+            warn!(error = ?error, "Failed to read the telemetry node id");
+            return HashValue::random().to_hex();
+        }
+    }
+    let node_id = HashValue::random().to_hex();
+    if let Err(error) = fs::write(&path, &node_id) {
+        warn!(error = ?error, "Failed to store the telemetry node id");
+    }
+    node_id
+}
+
+/// Builds a report of the `fields` of `health`, skipping those outside `TELEMETRY_FIELDS`.
+pub(crate) fn build_report(
+    node_id: &str,
+    timestamp_secs: u64,
+    health: &NodeHealth,
+    fields: &[String],
+) -> TelemetryReport {
+    let fields = fields
+        .iter()
+        .filter(|field| TELEMETRY_FIELDS.contains(&field.as_str()))
+        .filter_map(|field| Some((field.clone(), health.field(field)?)))
+        .collect();
+    TelemetryReport {
+        node_id: node_id.to_string(),
+        timestamp_secs,
+        fields,
+    }
+}
+
+/// The reports not sent yet, up to a capacity past which the oldest are dropped. The latest report
+/// is always kept.
+pub(crate) struct TelemetryBuffer {
+    reports: VecDeque<TelemetryReport>,
+    capacity: usize,
+}
+
+impl TelemetryBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            reports: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Buffers `report`, and returns the report dropped to make room for it, if any.
+    pub fn push(&mut self, report: TelemetryReport) -> Option<TelemetryReport> {
+        let dropped = if self.reports.len() == self.capacity {
+            self.reports.pop_front()
+        } else {
+            None
+        };
+        self.reports.push_back(report);
+        dropped
+    }
+
+    pub fn reports(&self) -> Vec<TelemetryReport> {
+        self.reports.iter().cloned().collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.reports.clear();
+    }
+}
+
+/// Reads the health of the node from its metrics and peers.
+struct HealthCollector {
+    role: RoleType,
+    peers: Vec<(NetworkId, Arc<PeerMetadataStorage>)>,
+    last_error_count: f64,
+    last_collect: Instant,
+}
+
+impl HealthCollector {
+    fn collect(&mut self) -> NodeHealth {
+        let metric_families = gather_metrics();
+        let metric = |name: &str, label: Option<(&str, &str)>| -> f64 {
+            metric_families
+                .iter()
+                .filter(|family| family.get_name() == name)
+                .flat_map(|family| family.get_metric())
+                .find(|metric| match label {
+                    Some((label_name, label_value)) => metric.get_label().iter().any(|pair| {
+                        pair.get_name() == label_name && pair.get_value() == label_value
+                    }),
+                    None => true,
+                })
+                .map(|metric| metric.get_gauge().get_value() + metric.get_counter().get_value())
+                .unwrap_or_default()
+        };
+
+        let error_count = metric("diem_error_log_count", None);
+        let elapsed = self.last_collect.elapsed().as_secs_f64();
+        let error_rate = if elapsed > 0.0 {
+            (error_count - self.last_error_count).max(0.0) / elapsed
+        } else {
+            0.0
+        };
+        self.last_error_count = error_count;
+        self.last_collect = Instant::now();
+
+        let peers = self
+            .peers
+            .iter()
+            .map(|(network_id, peer_metadata_storage)| {
+                let connected = peer_metadata_storage
+                    .read_all()
+                    .values()
+                    .filter(|peer_info| peer_info.status == PeerState::Connected)
+                    .count();
+                (network_id.to_string(), connected)
+            })
+            .collect();
+
+        NodeHealth {
+            version: get_json_metrics()
+                .remove("revision")
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+            role: self.role,
+            synced_version: metric("diem_state_sync_version", Some(("type", "synced"))) as u64,
+            highest_version: metric("diem_state_sync_version", Some(("type", "highest"))) as u64,
+            peers,
+            error_rate,
+        }
+    }
+}
+
+async fn send(
+    client: &reqwest::Client,
+    endpoint: &str,
+    reports: &[TelemetryReport],
+) -> Result<(), reqwest::Error> {
+    client
+        .post(endpoint)
+        .timeout(SEND_TIMEOUT)
+        .json(reports)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Reports the health of the node every `report_interval_secs` of the config, until the runtime
+/// is shut down.
+pub async fn report_periodically(
+    config: TelemetryConfig,
+    data_dir: PathBuf,
+    role: RoleType,
+    peers: Vec<(NetworkId, Arc<PeerMetadataStorage>)>,
+) {
+    let endpoint = match &config.endpoint {
+        Some(endpoint) => endpoint.clone(),
+        None => {
+            warn!("Telemetry is enabled without an endpoint, no report will be sent");
+            return;
+        }
+    };
+    let node_id = load_or_create_node_id(&data_dir);
+    let client = reqwest::Client::new();
+    let mut collector = HealthCollector {
+        role,
+        peers,
+        last_error_count: 0.0,
+        last_collect: Instant::now(),
+    };
+    let mut buffer = TelemetryBuffer::new(config.buffer_size);
+    // The first report comes after a full interval, for its error rate to cover one
+    let period = Duration::from_secs(config.report_interval_secs.max(1));
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    info!(
+        endpoint = endpoint,
+        fields = config.fields,
+        "Telemetry started"
+    );
+
+    loop {
+        interval.tick().await;
+        let timestamp_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let report = build_report(
+            &node_id,
+            timestamp_secs,
+            &collector.collect(),
+            &config.fields,
+        );
+        if buffer.push(report).is_some() {
+            debug!("Dropped the oldest telemetry report, the buffer is full");
+        }
+        match send(&client, &endpoint, &buffer.reports()).await {
+            Ok(()) => buffer.clear(),
+            Err(error) => warn!(error = ?error, "Failed to send the telemetry reports"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "telemetry_test.rs"]
+mod telemetry_test;
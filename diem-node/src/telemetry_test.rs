@@ -0,0 +1,73 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::telemetry::{build_report, load_or_create_node_id, NodeHealth, TelemetryBuffer};
+use diem_config::config::{RoleType, TelemetryConfig};
+use diem_temppath::TempPath;
+use serde_json::json;
+
+fn health() -> NodeHealth {
+    NodeHealth {
+        version: "abc1234".to_string(),
+        role: RoleType::FullNode,
+        synced_version: 90,
+        highest_version: 100,
+        peers: vec![("Public".to_string(), 3)].into_iter().collect(),
+        error_rate: 0.5,
+    }
+}
+
+#[test]
+fn test_report_fields() {
+    let report = build_report("node", 1, &health(), &TelemetryConfig::default().fields);
+    assert_eq!(
+        serde_json::to_value(&report.fields).unwrap(),
+        json!({
+            "version": "abc1234",
+            "role": "full_node",
+            "synced_version": 90,
+            "sync_lag": 10,
+            "peers": {"Public": 3},
+            "error_rate": 0.5,
+        })
+    );
+
+    // Only the allowed fields are reported, whatever the config asks
+    let fields = vec!["role".to_string(), "peer_id".to_string()];
+    let report = build_report("node", 1, &health(), &fields);
+    assert_eq!(
+        report.fields.keys().collect::<Vec<_>>(),
+        vec![&"role".to_string()]
+    );
+}
+
+#[test]
+fn test_node_id() {
+    let data_dir = TempPath::new();
+    data_dir.create_as_dir().unwrap();
+    let node_id = load_or_create_node_id(data_dir.path());
+    // The id is kept across restarts
+    assert_eq!(node_id, load_or_create_node_id(data_dir.path()));
+
+    let other_data_dir = TempPath::new();
+    other_data_dir.create_as_dir().unwrap();
+    assert_ne!(node_id, load_or_create_node_id(other_data_dir.path()));
+}
+
+#[test]
+fn test_buffer() {
+    let report = |timestamp_secs| build_report("node", timestamp_secs, &health(), &[]);
+    let mut buffer = TelemetryBuffer::new(2);
+    assert_eq!(buffer.push(report(1)), None);
+    assert_eq!(buffer.push(report(2)), None);
+    assert_eq!(buffer.push(report(3)), Some(report(1)));
+    assert_eq!(buffer.reports(), vec![report(2), report(3)]);
+
+    buffer.clear();
+    assert!(buffer.reports().is_empty());
+
+    // The latest report is kept even without room for any
+    let mut buffer = TelemetryBuffer::new(0);
+    assert_eq!(buffer.push(report(1)), None);
+    assert_eq!(buffer.push(report(2)), Some(report(1)));
+}
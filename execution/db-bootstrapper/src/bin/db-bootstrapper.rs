@@ -58,7 +58,7 @@ fn main() -> Result<()> {
         // When not committing, we open the DB as secondary so the tool is usable along side a
         // running node on the same DB. Using a TempPath since it won't run for long.
         tmpdir = TempPath::new();
-        DiemDB::open_as_secondary(
+        DiemDB::open_secondary(
             opt.db_dir.as_path(),
             tmpdir.path(),
             RocksdbConfig::default(),
@@ -13,7 +13,7 @@ use diem_config::config::{ExecutionCorrectnessService, NodeConfig};
 use diem_crypto::ed25519::Ed25519PrivateKey;
 use diem_global_constants::EXECUTION_KEY;
 use diem_secure_storage::{CryptoStorage, Storage};
-use diem_vm::DiemVM;
+use diem_vm::{execution_trace::ExecutionTracer, DiemVM};
 use executor::Executor;
 use std::{convert::TryInto, net::SocketAddr, sync::Arc};
 use storage_client::StorageClient;
@@ -43,6 +43,13 @@ pub fn extract_execution_prikey(config: &NodeConfig) -> Option<Ed25519PrivateKey
     }
 }
 
+/// The tracer of the executions of the transactions, if the config enables the trace
+pub fn extract_execution_tracer(config: &NodeConfig) -> Option<Arc<ExecutionTracer>> {
+    config.execution.trace_path.as_ref().map(|trace_path| {
+        Arc::new(ExecutionTracer::new(trace_path).expect("Unable to open the execution trace"))
+    })
+}
+
 enum ExecutionCorrectnessWrapper {
     Local(Arc<LocalService>),
     Process(ProcessService),
@@ -66,16 +73,30 @@ impl ExecutionCorrectnessManager {
         let execution_prikey = extract_execution_prikey(config);
         let storage_address = config.storage.address;
         let timeout_ms = config.storage.timeout_ms;
+        let concurrency_level = config.execution.concurrency_level;
+        let tracer = extract_execution_tracer(config);
         match &config.execution.service {
-            ExecutionCorrectnessService::Local => {
-                Self::new_local(storage_address, execution_prikey, timeout_ms)
-            }
-            ExecutionCorrectnessService::Serializer => {
-                Self::new_serializer(storage_address, execution_prikey, timeout_ms)
-            }
-            ExecutionCorrectnessService::Thread => {
-                Self::new_thread(storage_address, execution_prikey, timeout_ms)
-            }
+            ExecutionCorrectnessService::Local => Self::new_local(
+                storage_address,
+                execution_prikey,
+                timeout_ms,
+                concurrency_level,
+                tracer,
+            ),
+            ExecutionCorrectnessService::Serializer => Self::new_serializer(
+                storage_address,
+                execution_prikey,
+                timeout_ms,
+                concurrency_level,
+                tracer,
+            ),
+            ExecutionCorrectnessService::Thread => Self::new_thread(
+                storage_address,
+                execution_prikey,
+                timeout_ms,
+                concurrency_level,
+                tracer,
+            ),
             _ => unreachable!(
                 "Unimplemented ExecutionCorrectnessService: {:?}",
                 config.execution.service
@@ -87,10 +108,14 @@ impl ExecutionCorrectnessManager {
         storage_address: SocketAddr,
         execution_prikey: Option<Ed25519PrivateKey>,
         timeout: u64,
+        concurrency_level: usize,
+        tracer: Option<Arc<ExecutionTracer>>,
     ) -> Self {
-        let block_executor = Box::new(Executor::<DiemVM>::new(
-            StorageClient::new(&storage_address, timeout).into(),
-        ));
+        let block_executor = Box::new(
+            Executor::<DiemVM>::new(StorageClient::new(&storage_address, timeout).into())
+                .with_concurrency_level(concurrency_level)
+                .with_execution_tracer(tracer),
+        );
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Local(Arc::new(
                 LocalService::new(block_executor, execution_prikey),
@@ -109,10 +134,14 @@ impl ExecutionCorrectnessManager {
         storage_address: SocketAddr,
         execution_prikey: Option<Ed25519PrivateKey>,
         timeout: u64,
+        concurrency_level: usize,
+        tracer: Option<Arc<ExecutionTracer>>,
     ) -> Self {
-        let block_executor = Box::new(Executor::<DiemVM>::new(
-            StorageClient::new(&storage_address, timeout).into(),
-        ));
+        let block_executor = Box::new(
+            Executor::<DiemVM>::new(StorageClient::new(&storage_address, timeout).into())
+                .with_concurrency_level(concurrency_level)
+                .with_execution_tracer(tracer),
+        );
         let serializer_service = SerializerService::new(block_executor, execution_prikey);
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Serializer(Arc::new(
@@ -125,8 +154,16 @@ impl ExecutionCorrectnessManager {
         storage_address: SocketAddr,
         execution_prikey: Option<Ed25519PrivateKey>,
         network_timeout: u64,
+        concurrency_level: usize,
+        tracer: Option<Arc<ExecutionTracer>>,
     ) -> Self {
-        let thread = ThreadService::new(storage_address, execution_prikey, network_timeout);
+        let thread = ThreadService::new(
+            storage_address,
+            execution_prikey,
+            network_timeout,
+            concurrency_level,
+            tracer,
+        );
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Thread(thread),
         }
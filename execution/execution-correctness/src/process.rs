@@ -39,6 +39,8 @@ impl Process {
             server_addr,
             self.prikey,
             self.network_timeout_ms,
+            self.config.execution.concurrency_level,
+            execution_correctness_manager::extract_execution_tracer(&self.config),
         );
     }
 }
@@ -8,10 +8,10 @@ use diem_crypto::ed25519::Ed25519PrivateKey;
 use diem_infallible::Mutex;
 use diem_logger::warn;
 use diem_secure_net::{NetworkClient, NetworkServer};
-use diem_vm::DiemVM;
+use diem_vm::{execution_trace::ExecutionTracer, DiemVM};
 use executor::Executor;
 use executor_types::Error;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 use storage_client::StorageClient;
 
 pub trait RemoteService {
@@ -31,10 +31,14 @@ pub fn execute(
     listen_addr: SocketAddr,
     prikey: Option<Ed25519PrivateKey>,
     network_timeout: u64,
+    concurrency_level: usize,
+    tracer: Option<Arc<ExecutionTracer>>,
 ) {
-    let block_executor = Box::new(Executor::<DiemVM>::new(
-        StorageClient::new(&storage_addr, network_timeout).into(),
-    ));
+    let block_executor = Box::new(
+        Executor::<DiemVM>::new(StorageClient::new(&storage_addr, network_timeout).into())
+            .with_concurrency_level(concurrency_level)
+            .with_execution_tracer(tracer),
+    );
     let serializer_service = SerializerService::new(block_executor, prikey);
     let mut network_server = NetworkServer::new("execution", listen_addr, network_timeout);
 
@@ -8,11 +8,14 @@ use crate::{
         encode_mint_transaction, encode_reconfiguration_transaction, encode_transfer_transaction,
         MockVM, DISCARD_STATUS, KEEP_STATUS,
     },
+    prefetch::accounts_to_prefetch,
+    result_cache::BlockResultCache,
     BlockExecutor, Executor,
 };
 use diem_crypto::HashValue;
 use diem_types::{
     account_address::AccountAddress,
+    account_config,
     block_info::BlockInfo,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     transaction::{Transaction, TransactionListWithProof, Version},
@@ -20,7 +23,7 @@ use diem_types::{
 use diemdb::DiemDB;
 use proptest::prelude::*;
 use rand::Rng;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 
 fn execute_and_commit_block(
     executor: &TestExecutor,
@@ -243,6 +246,76 @@ fn test_executor_execute_same_block_multiple_times() {
     assert_eq!(responses.len(), 1);
 }
 
+#[test]
+fn test_executor_reuses_result_of_same_block() {
+    let executor = TestExecutor::new();
+    let parent_block_id = executor.committed_block_id();
+    let block1_id = gen_block_id(1);
+    let block1_txns: Vec<_> = (0..5)
+        .map(|i| encode_mint_transaction(gen_address(i), 100))
+        .collect();
+    let output1 = executor
+        .execute_block((block1_id, block1_txns), parent_block_id)
+        .unwrap();
+
+    // The id of a block commits to its transactions, so the block isn't executed again
+    let output = executor
+        .execute_block((block1_id, vec![]), parent_block_id)
+        .unwrap();
+    assert_eq!(output, output1);
+
+    // Unless its parent changed
+    let block2_id = gen_block_id(2);
+    let block2_txns = vec![encode_mint_transaction(gen_address(10), 100)];
+    executor
+        .execute_block((block2_id, block2_txns), parent_block_id)
+        .unwrap();
+    let block1_txns: Vec<_> = (0..5)
+        .map(|i| encode_mint_transaction(gen_address(i), 100))
+        .collect();
+    let output = executor
+        .execute_block((block1_id, block1_txns), block2_id)
+        .unwrap();
+    assert_eq!(output.version(), 6);
+}
+
+#[test]
+fn test_block_result_cache_eviction() {
+    let mut cache = BlockResultCache::new(2);
+    let parent_id = gen_block_id(0);
+    for i in 1..=3 {
+        cache.insert(
+            gen_block_id(i),
+            parent_id,
+            StateComputeResult::new_dummy_with_root_hash(gen_block_id(i)),
+        );
+    }
+    // The oldest block is evicted
+    assert!(cache.get(gen_block_id(1), parent_id).is_none());
+    assert_eq!(
+        cache.get(gen_block_id(3), parent_id).unwrap().root_hash(),
+        gen_block_id(3)
+    );
+    // A result for another parent is dropped
+    assert!(cache.get(gen_block_id(2), gen_block_id(3)).is_none());
+    assert!(cache.get(gen_block_id(2), parent_id).is_none());
+}
+
+#[test]
+fn test_accounts_to_prefetch() {
+    let txns = vec![
+        encode_mint_transaction(gen_address(10), 100),
+        encode_transfer_transaction(gen_address(10), gen_address(11), 10),
+        encode_transfer_transaction(gen_address(11), gen_address(10), 10),
+    ];
+    let addresses = accounts_to_prefetch(&txns);
+    assert!(addresses.contains(&account_config::diem_root_address()));
+    assert!(addresses.contains(&gen_address(10)));
+    assert!(addresses.contains(&gen_address(11)));
+    let unique: HashSet<_> = addresses.iter().collect();
+    assert_eq!(unique.len(), addresses.len());
+}
+
 /// Generates a list of `TransactionListWithProof`s according to the given ranges.
 fn create_transaction_chunks(
     chunk_ranges: Vec<std::ops::Range<Version>>,
@@ -468,6 +541,64 @@ fn test_noop_block_after_reconfiguration() {
     assert_eq!(output1.root_hash(), output2.root_hash());
 }
 
+#[test]
+fn test_epoch_checkpoints() {
+    let executor = TestExecutor::new();
+    let genesis_checkpoint = executor.db.reader.get_epoch_checkpoint(0).unwrap().unwrap();
+    assert_eq!(genesis_checkpoint.version, 0);
+
+    let parent_block_id = executor.committed_block_id();
+    let block_id = gen_block_id(1);
+    let output = executor
+        .execute_block(
+            (
+                block_id,
+                vec![encode_reconfiguration_transaction(gen_address(1))],
+            ),
+            parent_block_id,
+        )
+        .unwrap();
+    let ledger_info = LedgerInfo::new(
+        BlockInfo::new(
+            1,
+            0,
+            block_id,
+            output.root_hash(),
+            output.version(),
+            1,
+            output.epoch_state().clone(),
+        ),
+        HashValue::zero(),
+    );
+    executor
+        .commit_blocks(
+            vec![block_id],
+            LedgerInfoWithSignatures::new(ledger_info, BTreeMap::new()),
+        )
+        .unwrap();
+
+    let checkpoint = executor.db.reader.get_epoch_checkpoint(1).unwrap().unwrap();
+    assert_eq!(checkpoint.version, 1);
+    assert_eq!(
+        checkpoint.transaction_accumulator_root_hash,
+        output.root_hash()
+    );
+    assert_eq!(
+        checkpoint.state_root_hash,
+        executor.db.reader.get_latest_state_root().unwrap().1
+    );
+    assert_eq!(
+        Some(checkpoint.next_epoch_state),
+        output.epoch_state().clone()
+    );
+    assert!(executor
+        .db
+        .reader
+        .get_epoch_checkpoint(2)
+        .unwrap()
+        .is_none());
+}
+
 struct TestBlock {
     txns: Vec<Transaction>,
     id: HashValue,
@@ -11,6 +11,9 @@ mod logging;
 pub mod metrics;
 #[cfg(test)]
 mod mock_vm;
+mod parallel_execution;
+mod prefetch;
+mod result_cache;
 mod speculation_cache;
 mod types;
 
@@ -19,11 +22,12 @@ pub mod db_bootstrapper;
 use crate::{
     logging::{LogEntry, LogSchema},
     metrics::{
-        DIEM_EXECUTOR_COMMIT_BLOCKS_SECONDS, DIEM_EXECUTOR_ERRORS,
-        DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_SECONDS, DIEM_EXECUTOR_EXECUTE_BLOCK_SECONDS,
-        DIEM_EXECUTOR_SAVE_TRANSACTIONS_SECONDS, DIEM_EXECUTOR_TRANSACTIONS_SAVED,
-        DIEM_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS,
+        DIEM_EXECUTOR_BLOCK_RESULT_CACHE_HITS, DIEM_EXECUTOR_COMMIT_BLOCKS_SECONDS,
+        DIEM_EXECUTOR_ERRORS, DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_SECONDS,
+        DIEM_EXECUTOR_EXECUTE_BLOCK_SECONDS, DIEM_EXECUTOR_SAVE_TRANSACTIONS_SECONDS,
+        DIEM_EXECUTOR_TRANSACTIONS_SAVED, DIEM_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS,
     },
+    result_cache::{BlockResultCache, BLOCK_RESULT_CACHE_SIZE},
     speculation_cache::SpeculationCache,
     types::{ProcessedVMOutput, TransactionData},
 };
@@ -32,7 +36,7 @@ use diem_crypto::{
     hash::{CryptoHash, EventAccumulatorHasher, TransactionAccumulatorHasher},
     HashValue,
 };
-use diem_infallible::{RwLock, RwLockReadGuard};
+use diem_infallible::{Mutex, RwLock, RwLockReadGuard};
 use diem_logger::prelude::*;
 use diem_state_view::StateViewId;
 use diem_types::{
@@ -50,7 +54,7 @@ use diem_types::{
     },
     write_set::{WriteOp, WriteSet},
 };
-use diem_vm::VMExecutor;
+use diem_vm::{execution_trace::ExecutionTracer, VMExecutor};
 use executor_types::{
     BlockExecutor, ChunkExecutor, Error, ExecutedTrees, ProofReader, StateComputeResult,
     TransactionReplayer,
@@ -70,6 +74,12 @@ type SparseMerkleProof = diem_types::proof::SparseMerkleProof<AccountStateBlob>;
 pub struct Executor<V> {
     db: DbReaderWriter,
     cache: RwLock<SpeculationCache>,
+    /// The results of the blocks executed last, by block id
+    result_cache: Mutex<BlockResultCache>,
+    /// Number of threads executing the transactions of a block, 1 executes them sequentially
+    concurrency_level: usize,
+    /// Traces the execution of the transactions, for debugging
+    tracer: Option<Arc<ExecutionTracer>>,
     phantom: PhantomData<V>,
 }
 
@@ -92,10 +102,25 @@ where
         Self {
             db,
             cache: RwLock::new(SpeculationCache::new_with_startup_info(startup_info)),
+            result_cache: Mutex::new(BlockResultCache::new(BLOCK_RESULT_CACHE_SIZE)),
+            concurrency_level: 1,
+            tracer: None,
             phantom: PhantomData,
         }
     }
 
+    /// Executes the transactions of the blocks and chunks with `concurrency_level` threads
+    pub fn with_concurrency_level(mut self, concurrency_level: usize) -> Self {
+        self.concurrency_level = concurrency_level;
+        self
+    }
+
+    /// Appends the traces of the transactions of the blocks and chunks to `tracer`
+    pub fn with_execution_tracer(mut self, tracer: Option<Arc<ExecutionTracer>>) -> Self {
+        self.tracer = tracer;
+        self
+    }
+
     fn reset_cache(&self) -> Result<(), Error> {
         let startup_info = self
             .db
@@ -103,6 +128,7 @@ where
             .get_startup_info()?
             .ok_or_else(|| format_err!("DB not bootstrapped."))?;
         *self.cache.write() = SpeculationCache::new_with_startup_info(startup_info);
+        self.result_cache.lock().clear();
         Ok(())
     }
 
@@ -110,6 +136,9 @@ where
         Self {
             db,
             cache: RwLock::new(SpeculationCache::new_for_db_bootstrapping(tree_state)),
+            result_cache: Mutex::new(BlockResultCache::new(BLOCK_RESULT_CACHE_SIZE)),
+            concurrency_level: 1,
+            tracer: None,
             phantom: PhantomData,
         }
     }
@@ -489,7 +518,12 @@ where
         fail_point!("executor::vm_execute_chunk", |_| {
             Err(anyhow::anyhow!("Injected error in execute_chunk"))
         });
-        let vm_outputs = V::execute_block(transactions.clone(), &state_view)?;
+        let vm_outputs = parallel_execution::execute_block::<V>(
+            transactions.clone(),
+            &state_view,
+            self.concurrency_level,
+            self.tracer.as_deref(),
+        )?;
 
         // Since other validators have committed these transactions, their status should all be
         // TransactionStatus::Keep.
@@ -735,6 +769,15 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
         let (block_id, mut transactions) = block;
         let read_lock = self.cache.read();
 
+        // The result of a block executed again on the same parent is reused, as long as its output
+        // is still there to be committed.
+        if let Some(result) = self.result_cache.lock().get(block_id, parent_block_id) {
+            if read_lock.get_block(&block_id).is_ok() {
+                DIEM_EXECUTOR_BLOCK_RESULT_CACHE_HITS.inc();
+                return Ok(result);
+            }
+        }
+
         // Reconfiguration rule - if a block is a child of pending reconfiguration, it needs to be empty
         // So we roll over the executed state until it's committed and we start new epoch.
         let (output, state_compute_result) = if parent_block_id != read_lock.committed_block_id()
@@ -793,6 +836,9 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
             );
             drop(read_lock);
 
+            let prefetched_accounts = prefetch::accounts_to_prefetch(&transactions);
+            prefetch::prefetch_accounts(&state_view, &prefetched_accounts);
+
             let vm_outputs = {
                 let _timer = DIEM_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS.start_timer();
                 fail_point!("executor::vm_execute_block", |_| {
@@ -800,8 +846,15 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
                         "Injected error in vm_execute_block"
                     )))
                 });
-                V::execute_block(transactions.clone(), &state_view).map_err(anyhow::Error::from)?
+                parallel_execution::execute_block::<V>(
+                    transactions.clone(),
+                    &state_view,
+                    self.concurrency_level,
+                    self.tracer.as_deref(),
+                )
+                .map_err(anyhow::Error::from)?
             };
+            prefetch::record_prefetch_hits(&prefetched_accounts, &vm_outputs);
 
             let status: Vec<_> = vm_outputs
                 .iter()
@@ -835,6 +888,9 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
         self.cache
             .write()
             .add_block(parent_block_id, (block_id, transactions, output))?;
+        self.result_cache
+            .lock()
+            .insert(block_id, parent_block_id, state_compute_result.clone());
 
         Ok(state_compute_result)
     }
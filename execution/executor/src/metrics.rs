@@ -67,3 +67,53 @@ pub static DIEM_EXECUTOR_TRANSACTIONS_SAVED: Lazy<Histogram> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static DIEM_EXECUTOR_PARALLEL_EXECUTION_ABORTS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "diem_executor_parallel_execution_aborts_total",
+        // metric description
+        "Cumulative number of transaction executions aborted by the parallel execution of blocks"
+    )
+    .unwrap()
+});
+
+pub static DIEM_EXECUTOR_BLOCK_RESULT_CACHE_HITS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "diem_executor_block_result_cache_hits",
+        // metric description
+        "Cumulative number of blocks whose result was reused instead of executing them again"
+    )
+    .unwrap()
+});
+
+pub static DIEM_EXECUTOR_PREFETCH_SECONDS: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        // metric name
+        "diem_executor_prefetch_seconds",
+        // metric description
+        "The time spent in seconds of prefetching the accounts of a block before its execution"
+    )
+    .unwrap()
+});
+
+pub static DIEM_EXECUTOR_PREFETCHED_ACCOUNTS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "diem_executor_prefetched_accounts_total",
+        // metric description
+        "Cumulative number of accounts prefetched before the execution of their block"
+    )
+    .unwrap()
+});
+
+pub static DIEM_EXECUTOR_PREFETCH_HITS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "diem_executor_prefetch_hits_total",
+        // metric description
+        "Cumulative number of prefetched accounts written by their block"
+    )
+    .unwrap()
+});
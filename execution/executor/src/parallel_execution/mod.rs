@@ -0,0 +1,228 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Parallel execution of the transactions of a block, after Block-STM
+//! (https://arxiv.org/abs/2203.06871).
+//!
+//! The transactions are executed optimistically by several threads, each one on top of the values
+//! the lower transactions of the block wrote so far, kept by transaction in a multi-version memory.
+//! Every incarnation of a transaction is validated once executed, by checking the values it read
+//! are still the ones the lower transactions wrote. An incarnation failing its validation is
+//! aborted and the transaction executes again, until every transaction is validated, which yields
+//! the outputs of a sequential execution of the block. A transaction reading a value written by an
+//! aborted incarnation waits for the next incarnation, which likely writes that value again.
+//! The threads share the VM the block is set up with, see `VMExecutor::execute_block_by_transaction`.
+
+mod mv_memory;
+mod scheduler;
+#[cfg(test)]
+mod test;
+
+use crate::metrics::DIEM_EXECUTOR_PARALLEL_EXECUTION_ABORTS;
+use anyhow::Result;
+use diem_infallible::Mutex;
+use diem_state_view::{StateView, StateViewId};
+use diem_types::{
+    access_path::AccessPath,
+    on_chain_config,
+    transaction::{Transaction, TransactionOutput, TransactionPayload, TransactionStatus},
+    vm_status::{StatusCode, VMStatus},
+    write_set::WriteSet,
+};
+use diem_vm::{execution_trace::ExecutionTracer, TransactionExecutor, VMExecutor};
+use mv_memory::{MVMemory, ReadOrigin, ReadResult};
+use scheduler::{Scheduler, Task};
+use std::thread;
+
+type TxnIndex = usize;
+type Incarnation = usize;
+
+/// Executes the transactions of the block with the VM, with `concurrency_level` threads if it's
+/// above 1. The execution is sequential with a `tracer`, which would otherwise trace every
+/// incarnation of the transactions.
+pub fn execute_block<V: VMExecutor>(
+    transactions: Vec<Transaction>,
+    state_view: &dyn StateView,
+    concurrency_level: usize,
+    tracer: Option<&ExecutionTracer>,
+) -> Result<Vec<TransactionOutput>, VMStatus> {
+    if let Some(tracer) = tracer {
+        return V::execute_block_with_tracer(transactions, state_view, tracer);
+    }
+    if concurrency_level <= 1
+        || transactions.len() <= 1
+        || state_view.is_genesis()
+        || transactions.iter().any(writes_modules_or_write_sets)
+    {
+        return V::execute_block(transactions, state_view);
+    }
+    execute_block_parallel::<V>(transactions, state_view, concurrency_level)
+}
+
+/// The write sets of genesis and write set transactions may touch accounts they never read, whose
+/// state the executor would then load, unlike after a sequential execution. The VM shared by the
+/// transactions would keep the modules published by any incarnation of a transaction.
+fn writes_modules_or_write_sets(transaction: &Transaction) -> bool {
+    match transaction {
+        Transaction::GenesisTransaction(_) => true,
+        Transaction::UserTransaction(txn) => matches!(
+            txn.payload(),
+            TransactionPayload::WriteSet(_) | TransactionPayload::Module(_)
+        ),
+        Transaction::BlockMetadata(_) => false,
+    }
+}
+
+fn execute_block_parallel<V: VMExecutor>(
+    transactions: Vec<Transaction>,
+    state_view: &dyn StateView,
+    concurrency_level: usize,
+) -> Result<Vec<TransactionOutput>, VMStatus> {
+    let num_txns = transactions.len();
+    V::execute_block_by_transaction(transactions, state_view, |base_view, vm| {
+        let executor = ParallelExecutor {
+            vm,
+            base_view,
+            mv_memory: MVMemory::new(num_txns),
+            scheduler: Scheduler::new(num_txns),
+            outputs: (0..num_txns).map(|_| Mutex::new(None)).collect(),
+        };
+        thread::scope(|scope| {
+            for _ in 0..concurrency_level.min(num_txns) {
+                scope.spawn(|| executor.work());
+            }
+        });
+        executor.into_outputs()
+    })
+}
+
+struct ParallelExecutor<'a> {
+    vm: &'a TransactionExecutor<'a>,
+    base_view: &'a dyn StateView,
+    mv_memory: MVMemory,
+    scheduler: Scheduler,
+    /// The output of the last incarnation of every transaction
+    outputs: Vec<Mutex<Option<Result<TransactionOutput, VMStatus>>>>,
+}
+
+impl<'a> ParallelExecutor<'a> {
+    fn work(&self) {
+        let mut task = None;
+        while !self.scheduler.done() {
+            task = match task {
+                Some(Task::Execution(txn_idx, incarnation)) => self.execute(txn_idx, incarnation),
+                Some(Task::Validation(txn_idx, incarnation)) => self.validate(txn_idx, incarnation),
+                None => self.scheduler.next_task_or_wait(),
+            };
+        }
+    }
+
+    fn execute(&self, txn_idx: TxnIndex, incarnation: Incarnation) -> Option<Task> {
+        let view = SpeculativeStateView {
+            executor: self,
+            txn_idx,
+            read_set: Mutex::new(Vec::new()),
+        };
+        let output = (self.vm)(txn_idx, &view);
+        let read_set = std::mem::take(&mut *view.read_set.lock());
+        let write_set = match &output {
+            Ok(output) => output.write_set().iter().cloned().collect(),
+            Err(_) => Vec::new(),
+        };
+
+        *self.outputs[txn_idx].lock() = Some(output);
+        let wrote_new_path = self
+            .mv_memory
+            .record(txn_idx, incarnation, read_set, write_set);
+        self.scheduler
+            .finish_execution(txn_idx, incarnation, wrote_new_path)
+    }
+
+    fn validate(&self, txn_idx: TxnIndex, incarnation: Incarnation) -> Option<Task> {
+        let aborted = !self.mv_memory.validate_read_set(txn_idx)
+            && self.scheduler.try_validation_abort(txn_idx, incarnation);
+        if aborted {
+            DIEM_EXECUTOR_PARALLEL_EXECUTION_ABORTS.inc();
+            self.mv_memory.convert_writes_to_estimates(txn_idx);
+        }
+        self.scheduler.finish_validation(txn_idx, aborted)
+    }
+
+    /// The outputs of the last incarnations, once every transaction is executed and validated
+    fn into_outputs(self) -> Result<Vec<TransactionOutput>, VMStatus> {
+        let new_epoch_event_key = on_chain_config::new_epoch_event_key();
+        let mut outputs = Vec::with_capacity(self.outputs.len());
+        let mut reconfigured = false;
+        for output in &self.outputs {
+            // Like the VM, the transactions after a reconfiguration are left for the next epoch
+            if reconfigured {
+                outputs.push(TransactionOutput::new(
+                    WriteSet::default(),
+                    vec![],
+                    0,
+                    TransactionStatus::Retry,
+                ));
+                continue;
+            }
+            let output = output
+                .lock()
+                .take()
+                .expect("Every transaction is executed once the execution is done")?;
+            reconfigured = output
+                .events()
+                .iter()
+                .any(|event| *event.key() == new_epoch_event_key);
+            outputs.push(output);
+        }
+
+        // The executor expects the accounts of the writes to be read from the base state, which is
+        // only the case of the paths the transactions read from the base state instead of a lower
+        // transaction
+        for output in &outputs {
+            for (access_path, _) in output.write_set() {
+                self.base_view
+                    .get(access_path)
+                    .map_err(|_| VMStatus::Error(StatusCode::STORAGE_ERROR))?;
+            }
+        }
+        Ok(outputs)
+    }
+}
+
+/// The state an incarnation of a transaction executes on, the base state with the writes of the
+/// lower transactions of the block. Records the reads of the incarnation to validate it.
+struct SpeculativeStateView<'a> {
+    executor: &'a ParallelExecutor<'a>,
+    txn_idx: TxnIndex,
+    read_set: Mutex<Vec<(AccessPath, ReadOrigin)>>,
+}
+
+impl<'a> StateView for SpeculativeStateView<'a> {
+    fn id(&self) -> StateViewId {
+        self.executor.base_view.id()
+    }
+
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        loop {
+            match self.executor.mv_memory.read(access_path, self.txn_idx) {
+                ReadResult::Value(origin, value) => {
+                    self.read_set.lock().push((access_path.clone(), origin));
+                    return Ok(value.map(|value| value.as_ref().clone()));
+                }
+                ReadResult::NotFound => {
+                    self.read_set
+                        .lock()
+                        .push((access_path.clone(), ReadOrigin::Storage));
+                    return self.executor.base_view.get(access_path);
+                }
+                ReadResult::Dependency(blocking_txn_idx) => {
+                    self.executor.scheduler.wait_for_execution(blocking_txn_idx)
+                }
+            }
+        }
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.executor.base_view.is_genesis()
+    }
+}
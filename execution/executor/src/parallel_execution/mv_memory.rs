@@ -0,0 +1,144 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The multi-version memory of a block: the values the incarnations of its transactions wrote,
+//! by access path and transaction, and the read sets they executed with.
+
+use super::{Incarnation, TxnIndex};
+use diem_infallible::Mutex;
+use diem_types::{access_path::AccessPath, write_set::WriteOp};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// Number of shards of the values, so threads writing different paths rarely contend
+const NUM_SHARDS: usize = 64;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Entry {
+    /// A value written by an incarnation, None for a deletion
+    Value(Incarnation, Option<Arc<Vec<u8>>>),
+    /// The value written by an aborted incarnation, which the next incarnation likely writes again
+    Estimate,
+}
+
+/// Where a read got its value from
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ReadOrigin {
+    /// The write of an incarnation of a lower transaction
+    Version(TxnIndex, Incarnation),
+    /// No lower transaction wrote the path, the value is the one of the base state
+    Storage,
+}
+
+#[derive(Debug)]
+pub enum ReadResult {
+    Value(ReadOrigin, Option<Arc<Vec<u8>>>),
+    NotFound,
+    /// The path is an estimate of the given transaction, which has to execute again first
+    Dependency(TxnIndex),
+}
+
+pub struct MVMemory {
+    shards: Vec<Mutex<HashMap<AccessPath, BTreeMap<TxnIndex, Entry>>>>,
+    last_written_paths: Vec<Mutex<Vec<AccessPath>>>,
+    last_read_set: Vec<Mutex<Vec<(AccessPath, ReadOrigin)>>>,
+}
+
+impl MVMemory {
+    pub fn new(num_txns: usize) -> Self {
+        Self {
+            shards: (0..NUM_SHARDS)
+                .map(|_| Mutex::new(HashMap::new()))
+                .collect(),
+            last_written_paths: (0..num_txns).map(|_| Mutex::new(Vec::new())).collect(),
+            last_read_set: (0..num_txns).map(|_| Mutex::new(Vec::new())).collect(),
+        }
+    }
+
+    fn shard(&self, path: &AccessPath) -> &Mutex<HashMap<AccessPath, BTreeMap<TxnIndex, Entry>>> {
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % NUM_SHARDS]
+    }
+
+    /// Records the reads and writes of an incarnation, and drops the writes of the previous
+    /// incarnation it didn't write again. Returns whether it wrote a path the previous incarnation
+    /// didn't, in which case the higher transactions may have missed the new value.
+    pub fn record(
+        &self,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        read_set: Vec<(AccessPath, ReadOrigin)>,
+        write_set: Vec<(AccessPath, WriteOp)>,
+    ) -> bool {
+        let mut written_paths = Vec::with_capacity(write_set.len());
+        for (path, op) in write_set {
+            let value = match op {
+                WriteOp::Value(value) => Some(Arc::new(value)),
+                WriteOp::Deletion => None,
+            };
+            self.shard(&path)
+                .lock()
+                .entry(path.clone())
+                .or_default()
+                .insert(txn_idx, Entry::Value(incarnation, value));
+            written_paths.push(path);
+        }
+
+        let mut last_written_paths = self.last_written_paths[txn_idx].lock();
+        let mut wrote_new_path = false;
+        for path in &written_paths {
+            wrote_new_path |= !last_written_paths.contains(path);
+        }
+        for path in last_written_paths.iter() {
+            if !written_paths.contains(path) {
+                if let Some(entries) = self.shard(path).lock().get_mut(path) {
+                    entries.remove(&txn_idx);
+                }
+            }
+        }
+        *last_written_paths = written_paths;
+        *self.last_read_set[txn_idx].lock() = read_set;
+        wrote_new_path
+    }
+
+    /// Marks the writes of the aborted incarnation of the transaction as estimates
+    pub fn convert_writes_to_estimates(&self, txn_idx: TxnIndex) {
+        for path in self.last_written_paths[txn_idx].lock().iter() {
+            if let Some(entries) = self.shard(path).lock().get_mut(path) {
+                entries.insert(txn_idx, Entry::Estimate);
+            }
+        }
+    }
+
+    /// Reads the value of the path written by the highest transaction below `txn_idx`
+    pub fn read(&self, path: &AccessPath, txn_idx: TxnIndex) -> ReadResult {
+        let shard = self.shard(path).lock();
+        let entry = shard
+            .get(path)
+            .and_then(|entries| entries.range(..txn_idx).next_back());
+        match entry {
+            Some((idx, Entry::Value(incarnation, value))) => {
+                ReadResult::Value(ReadOrigin::Version(*idx, *incarnation), value.clone())
+            }
+            Some((idx, Entry::Estimate)) => ReadResult::Dependency(*idx),
+            None => ReadResult::NotFound,
+        }
+    }
+
+    /// Whether the reads of the last incarnation of the transaction would still read the same
+    /// values
+    pub fn validate_read_set(&self, txn_idx: TxnIndex) -> bool {
+        let read_set = self.last_read_set[txn_idx].lock().clone();
+        read_set
+            .iter()
+            .all(|(path, origin)| match (self.read(path, txn_idx), origin) {
+                (ReadResult::Value(current, _), origin) => current == *origin,
+                (ReadResult::NotFound, ReadOrigin::Storage) => true,
+                _ => false,
+            })
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The collaborative scheduler of Block-STM, handing out the execution and validation tasks of
+//! the transactions of a block to the threads, lowest transactions first.
+
+use super::{Incarnation, TxnIndex};
+use diem_infallible::Mutex;
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Condvar,
+};
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Task {
+    Execution(TxnIndex, Incarnation),
+    Validation(TxnIndex, Incarnation),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Status {
+    ReadyToExecute,
+    Executing,
+    Executed,
+    /// A validation failed, the incarnation is being aborted
+    Aborting,
+}
+
+pub struct Scheduler {
+    num_txns: usize,
+    /// The next transaction to execute, once it's ready
+    execution_idx: AtomicUsize,
+    /// The next transaction to validate, once it's executed
+    validation_idx: AtomicUsize,
+    /// Bumped whenever one of the indices decreases, so a thread checking whether the block is done
+    /// notices a concurrent decrease
+    decrease_cnt: AtomicUsize,
+    /// The tasks handed out which aren't finished yet
+    num_active_tasks: AtomicUsize,
+    done: AtomicBool,
+    /// The incarnation and status of every transaction, and the condition the transactions reading
+    /// its estimates wait on
+    statuses: Vec<(Mutex<(Incarnation, Status)>, Condvar)>,
+    /// Bumped whenever a task finishes, which may make new tasks available, and the condition the
+    /// threads without a task wait on
+    progress: (Mutex<usize>, Condvar),
+}
+
+impl Scheduler {
+    pub fn new(num_txns: usize) -> Self {
+        Self {
+            num_txns,
+            execution_idx: AtomicUsize::new(0),
+            validation_idx: AtomicUsize::new(0),
+            decrease_cnt: AtomicUsize::new(0),
+            num_active_tasks: AtomicUsize::new(0),
+            done: AtomicBool::new(false),
+            statuses: (0..num_txns)
+                .map(|_| (Mutex::new((0, Status::ReadyToExecute)), Condvar::new()))
+                .collect(),
+            progress: (Mutex::new(0), Condvar::new()),
+        }
+    }
+
+    /// Whether every transaction is executed and validated
+    pub fn done(&self) -> bool {
+        self.done.load(Ordering::SeqCst)
+    }
+
+    /// Validations go first, so conflicts are found before more transactions execute on top of
+    /// them
+    pub fn next_task(&self) -> Option<Task> {
+        if self.done() {
+            return None;
+        }
+        let validation_idx = self.validation_idx.load(Ordering::SeqCst);
+        let execution_idx = self.execution_idx.load(Ordering::SeqCst);
+        if validation_idx < execution_idx {
+            self.next_version_to_validate()
+                .map(|(txn_idx, incarnation)| Task::Validation(txn_idx, incarnation))
+        } else {
+            self.next_version_to_execute()
+                .map(|(txn_idx, incarnation)| Task::Execution(txn_idx, incarnation))
+        }
+    }
+
+    /// Like `next_task`, but parks the thread until another task finishes when no task is
+    /// available. The caller checks whether the block is done before asking again.
+    pub fn next_task_or_wait(&self) -> Option<Task> {
+        let observed_progress = *self.progress.0.lock();
+        let task = self.next_task();
+        if task.is_none() {
+            let (progress, progressed) = &self.progress;
+            let mut progress = progress.lock();
+            while *progress == observed_progress && !self.done() {
+                progress = progressed
+                    .wait(progress)
+                    .expect("diem cannot currently handle a poisoned lock");
+            }
+        }
+        task
+    }
+
+    /// Wakes up the threads waiting for a task
+    fn notify_progress(&self) {
+        let (progress, progressed) = &self.progress;
+        *progress.lock() += 1;
+        progressed.notify_all();
+    }
+
+    fn next_version_to_execute(&self) -> Option<(TxnIndex, Incarnation)> {
+        if self.execution_idx.load(Ordering::SeqCst) >= self.num_txns {
+            self.check_done();
+            return None;
+        }
+        self.num_active_tasks.fetch_add(1, Ordering::SeqCst);
+        let txn_idx = self.execution_idx.fetch_add(1, Ordering::SeqCst);
+        match self.try_incarnate(txn_idx) {
+            Some(incarnation) => Some((txn_idx, incarnation)),
+            None => {
+                self.num_active_tasks.fetch_sub(1, Ordering::SeqCst);
+                None
+            }
+        }
+    }
+
+    fn next_version_to_validate(&self) -> Option<(TxnIndex, Incarnation)> {
+        if self.validation_idx.load(Ordering::SeqCst) >= self.num_txns {
+            self.check_done();
+            return None;
+        }
+        self.num_active_tasks.fetch_add(1, Ordering::SeqCst);
+        let txn_idx = self.validation_idx.fetch_add(1, Ordering::SeqCst);
+        if txn_idx < self.num_txns {
+            let (incarnation, status) = *self.statuses[txn_idx].0.lock();
+            if status == Status::Executed {
+                return Some((txn_idx, incarnation));
+            }
+        }
+        self.num_active_tasks.fetch_sub(1, Ordering::SeqCst);
+        None
+    }
+
+    /// Starts the next incarnation of the transaction if it's ready to execute
+    fn try_incarnate(&self, txn_idx: TxnIndex) -> Option<Incarnation> {
+        if txn_idx >= self.num_txns {
+            return None;
+        }
+        let mut status = self.statuses[txn_idx].0.lock();
+        if status.1 == Status::ReadyToExecute {
+            status.1 = Status::Executing;
+            Some(status.0)
+        } else {
+            None
+        }
+    }
+
+    fn check_done(&self) {
+        let observed_cnt = self.decrease_cnt.load(Ordering::SeqCst);
+        let execution_idx = self.execution_idx.load(Ordering::SeqCst);
+        let validation_idx = self.validation_idx.load(Ordering::SeqCst);
+        if execution_idx.min(validation_idx) >= self.num_txns
+            && self.num_active_tasks.load(Ordering::SeqCst) == 0
+            && observed_cnt == self.decrease_cnt.load(Ordering::SeqCst)
+        {
+            self.done.store(true, Ordering::SeqCst);
+            self.notify_progress();
+        }
+    }
+
+    fn decrease_validation_idx(&self, target_idx: TxnIndex) {
+        self.validation_idx.fetch_min(target_idx, Ordering::SeqCst);
+        self.decrease_cnt.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns the validation of the incarnation when the thread can go on with it right away
+    pub fn finish_execution(
+        &self,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        wrote_new_path: bool,
+    ) -> Option<Task> {
+        {
+            let (status, executed) = &self.statuses[txn_idx];
+            status.lock().1 = Status::Executed;
+            executed.notify_all();
+        }
+        if self.validation_idx.load(Ordering::SeqCst) > txn_idx {
+            if wrote_new_path {
+                // The higher transactions validated so far may have missed the new path
+                self.decrease_validation_idx(txn_idx);
+            } else {
+                return Some(Task::Validation(txn_idx, incarnation));
+            }
+        }
+        self.num_active_tasks.fetch_sub(1, Ordering::SeqCst);
+        self.notify_progress();
+        None
+    }
+
+    /// Whether the thread whose validation of the incarnation failed gets to abort it, only one
+    /// of the failed validations of an incarnation does
+    pub fn try_validation_abort(&self, txn_idx: TxnIndex, incarnation: Incarnation) -> bool {
+        let mut status = self.statuses[txn_idx].0.lock();
+        if *status == (incarnation, Status::Executed) {
+            status.1 = Status::Aborting;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the next incarnation of an aborted transaction when the thread can go on with it
+    /// right away
+    pub fn finish_validation(&self, txn_idx: TxnIndex, aborted: bool) -> Option<Task> {
+        if aborted {
+            {
+                let mut status = self.statuses[txn_idx].0.lock();
+                *status = (status.0 + 1, Status::ReadyToExecute);
+            }
+            // The higher transactions have to be validated again against the next incarnation
+            self.decrease_validation_idx(txn_idx + 1);
+            if self.execution_idx.load(Ordering::SeqCst) > txn_idx {
+                if let Some(incarnation) = self.try_incarnate(txn_idx) {
+                    return Some(Task::Execution(txn_idx, incarnation));
+                }
+            }
+        }
+        self.num_active_tasks.fetch_sub(1, Ordering::SeqCst);
+        self.notify_progress();
+        None
+    }
+
+    /// Blocks until the aborted transaction executed again. The aborted transactions are always
+    /// incarnated again by the thread which aborted them, or by the next thread asking for a task,
+    /// so this never waits on a transaction nobody executes.
+    pub fn wait_for_execution(&self, txn_idx: TxnIndex) {
+        let (status, executed) = &self.statuses[txn_idx];
+        let mut status = status.lock();
+        while status.1 != Status::Executed {
+            status = executed
+                .wait(status)
+                .expect("diem cannot currently handle a poisoned lock");
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::execute_block_parallel;
+use crate::mock_vm::{
+    encode_mint_transaction, encode_reconfiguration_transaction, encode_transfer_transaction,
+    MockVM,
+};
+use anyhow::Result;
+use diem_state_view::StateView;
+use diem_types::{
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    transaction::{Transaction, TransactionStatus},
+};
+use diem_vm::VMExecutor;
+
+struct EmptyStateView;
+
+impl StateView for EmptyStateView {
+    fn get(&self, _access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    fn is_genesis(&self) -> bool {
+        false
+    }
+}
+
+fn gen_address(index: u8) -> AccountAddress {
+    AccountAddress::new([index; AccountAddress::LENGTH])
+}
+
+/// Transfers back and forth between a few accounts, so most of the transactions conflict
+fn gen_conflicting_block(num_accounts: u8, num_transfers: usize) -> Vec<Transaction> {
+    let mut txns: Vec<_> = (0..num_accounts)
+        .map(|i| encode_mint_transaction(gen_address(i), 1_000))
+        .collect();
+    for i in 0..num_transfers {
+        let sender = gen_address((i * 7 % num_accounts as usize) as u8);
+        let recipient = gen_address((i * 3 % num_accounts as usize) as u8);
+        // Some of the transfers are over the balance of their sender, and discarded
+        txns.push(encode_transfer_transaction(
+            sender,
+            recipient,
+            (i as u64 * 37) % 700,
+        ));
+    }
+    txns
+}
+
+#[test]
+fn test_parallel_execution_matches_sequential_execution() {
+    let txns = gen_conflicting_block(4, 200);
+    let expected = MockVM::execute_block(txns.clone(), &EmptyStateView).unwrap();
+    assert!(expected
+        .iter()
+        .any(|output| matches!(output.status(), TransactionStatus::Discard(_))));
+
+    for concurrency_level in &[2, 4, 8] {
+        // Every run interleaves the transactions differently
+        for _ in 0..5 {
+            let outputs =
+                execute_block_parallel::<MockVM>(txns.clone(), &EmptyStateView, *concurrency_level)
+                    .unwrap();
+            assert_eq!(outputs, expected);
+        }
+    }
+}
+
+#[test]
+fn test_parallel_execution_without_conflicts() {
+    let txns: Vec<_> = (0..50)
+        .map(|i| encode_mint_transaction(gen_address(i), 100))
+        .collect();
+    let expected = MockVM::execute_block(txns.clone(), &EmptyStateView).unwrap();
+    let outputs = execute_block_parallel::<MockVM>(txns, &EmptyStateView, 4).unwrap();
+    assert_eq!(outputs, expected);
+}
+
+#[test]
+fn test_parallel_execution_stops_at_reconfiguration() {
+    let txns = vec![
+        encode_mint_transaction(gen_address(0), 100),
+        encode_reconfiguration_transaction(gen_address(1)),
+        encode_mint_transaction(gen_address(0), 100),
+        encode_transfer_transaction(gen_address(0), gen_address(2), 50),
+    ];
+    let expected = MockVM::execute_block(txns.clone(), &EmptyStateView).unwrap();
+    let outputs = execute_block_parallel::<MockVM>(txns, &EmptyStateView, 4).unwrap();
+
+    assert_eq!(outputs[..2], expected[..2]);
+    // The transactions after the reconfiguration are retried in the next epoch
+    for output in &outputs[2..] {
+        assert_eq!(output.status(), &TransactionStatus::Retry);
+        assert!(output.write_set().is_empty());
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Prefetching of the account states a block likely touches before it executes: the senders of its
+//! transactions and the framework accounts every transaction reads. They are loaded from storage
+//! concurrently into the cache of the state view of the block, so the execution doesn't stall on
+//! reading them one by one.
+
+use crate::metrics::{
+    DIEM_EXECUTOR_PREFETCHED_ACCOUNTS, DIEM_EXECUTOR_PREFETCH_HITS, DIEM_EXECUTOR_PREFETCH_SECONDS,
+};
+use diem_state_view::StateView;
+use diem_types::{
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    account_config::{
+        diem_root_address, treasury_compliance_account_address, AccountResource, CORE_CODE_ADDRESS,
+    },
+    transaction::{Transaction, TransactionOutput},
+};
+use move_core_types::move_resource::MoveResource;
+use std::{collections::HashSet, thread};
+
+/// Number of threads reading the account states
+const PREFETCH_CONCURRENCY: usize = 8;
+
+/// The accounts the transactions likely touch, without duplicates
+pub(crate) fn accounts_to_prefetch(transactions: &[Transaction]) -> Vec<AccountAddress> {
+    let framework_accounts = vec![
+        CORE_CODE_ADDRESS,
+        diem_root_address(),
+        treasury_compliance_account_address(),
+    ];
+    let touched_accounts = transactions.iter().filter_map(|txn| match txn {
+        Transaction::UserTransaction(txn) => Some(txn.sender()),
+        Transaction::BlockMetadata(block_metadata) => Some(block_metadata.proposer()),
+        Transaction::GenesisTransaction(_) => None,
+    });
+
+    let mut seen = HashSet::new();
+    framework_accounts
+        .into_iter()
+        .chain(touched_accounts)
+        .filter(|address| seen.insert(*address))
+        .collect()
+}
+
+/// Loads the account states into the cache of the state view, which loads a whole account state
+/// the first time one of its paths is read. The errors are left for the execution to hit again.
+pub(crate) fn prefetch_accounts(state_view: &dyn StateView, addresses: &[AccountAddress]) {
+    if addresses.is_empty() || state_view.is_genesis() {
+        return;
+    }
+    let _timer = DIEM_EXECUTOR_PREFETCH_SECONDS.start_timer();
+    let chunk_size = (addresses.len() + PREFETCH_CONCURRENCY - 1) / PREFETCH_CONCURRENCY;
+    thread::scope(|scope| {
+        for chunk in addresses.chunks(chunk_size) {
+            scope.spawn(move || {
+                for address in chunk {
+                    let _ = state_view
+                        .get(&AccessPath::new(*address, AccountResource::resource_path()));
+                }
+            });
+        }
+    });
+    DIEM_EXECUTOR_PREFETCHED_ACCOUNTS.inc_by(addresses.len() as u64);
+}
+
+/// Counts the prefetched accounts the block wrote, as the accounts it only read aren't known
+pub(crate) fn record_prefetch_hits(addresses: &[AccountAddress], outputs: &[TransactionOutput]) {
+    let written: HashSet<_> = outputs
+        .iter()
+        .flat_map(|output| output.write_set().iter())
+        .map(|(access_path, _)| access_path.address)
+        .collect();
+    let hits = addresses
+        .iter()
+        .filter(|address| written.contains(address))
+        .count();
+    DIEM_EXECUTOR_PREFETCH_HITS.inc_by(hits as u64);
+}
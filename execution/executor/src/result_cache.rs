@@ -0,0 +1,77 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The results of the blocks executed last, so a block the decoupled execution pipeline sends
+//! again, e.g. after it was ordered again or its commit is retried, isn't executed again.
+
+use diem_crypto::HashValue;
+use executor_types::StateComputeResult;
+use std::collections::{HashMap, VecDeque};
+
+/// Number of blocks whose result is kept
+pub(crate) const BLOCK_RESULT_CACHE_SIZE: usize = 64;
+
+pub(crate) struct BlockResultCache {
+    capacity: usize,
+    /// The parent the block was executed on, and its result
+    results: HashMap<HashValue, (HashValue, StateComputeResult)>,
+    /// The blocks from the oldest to the last one added, the oldest is evicted first
+    order: VecDeque<HashValue>,
+}
+
+impl BlockResultCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            results: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// The result of the block if it was executed on the same parent. The result for another
+    /// parent is dropped, as the block is about to be executed on the new one.
+    pub fn get(
+        &mut self,
+        block_id: HashValue,
+        parent_block_id: HashValue,
+    ) -> Option<StateComputeResult> {
+        match self.results.get(&block_id) {
+            Some((parent, result)) if *parent == parent_block_id => Some(result.clone()),
+            Some(_) => {
+                self.remove(block_id);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(
+        &mut self,
+        block_id: HashValue,
+        parent_block_id: HashValue,
+        result: StateComputeResult,
+    ) {
+        if self
+            .results
+            .insert(block_id, (parent_block_id, result))
+            .is_none()
+        {
+            self.order.push_back(block_id);
+        }
+        while self.order.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.results.remove(&evicted);
+            }
+        }
+    }
+
+    fn remove(&mut self, block_id: HashValue) {
+        self.results.remove(&block_id);
+        self.order.retain(|id| *id != block_id);
+    }
+
+    pub fn clear(&mut self) {
+        self.results.clear();
+        self.order.clear();
+    }
+}
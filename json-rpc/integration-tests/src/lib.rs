@@ -17,6 +17,7 @@ use diem_sdk::{
         diem_id_identifier::DiemIdVaspDomainIdentifier,
         event::EventKey,
         ledger_info::LedgerInfoWithSignatures,
+        mempool_status::MempoolStatusCode,
         on_chain_config::DIEM_MAX_KNOWN_VERSION,
         proof::{AccumulatorConsistencyProof, TransactionAccumulatorSummary},
         transaction::{
@@ -786,11 +787,16 @@ impl PublicUsageTest for MempoolValidationError {
         env.submit(&txn1);
         env.allow_execution_failures(|env| {
             let resp = env.submit(&txn2);
+            let error = resp.error.expect("error");
             assert_eq!(
-                resp.error.expect("error").message,
+                error.message,
                 "Server error: Mempool submission error: \"Failed to update gas price to 0\""
                     .to_string(),
             );
+            assert_eq!(
+                error.as_mempool_status_code(),
+                Some(MempoolStatusCode::InvalidUpdate)
+            );
         });
         env.wait_for_txn(&txn1);
 
@@ -0,0 +1,268 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Optional API key authentication of the JSON-RPC calls, see `ApiKeyConfig` for the
+//! configuration. The keys file is reloaded periodically, so operators add, change and revoke keys
+//! without restarting the node.
+
+use crate::{
+    errors::{ErrorReason, JsonRpcError, ServerCode},
+    methods::JsonRpcService,
+    rate_limit::{rate_limited, X_FORWARDED_FOR},
+    util::parse_method,
+};
+use anyhow::{ensure, Result};
+use diem_config::config::{ApiKeyAccess, ApiKeyConfig, PersistableConfig};
+use diem_infallible::{Mutex, RwLock};
+use diem_json_rpc_types::Method;
+use diem_logger::warn;
+use diem_rate_limiter::rate_limit::{Bucket, SharedBucket};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+use warp::{
+    filters::BoxedFilter,
+    http::StatusCode,
+    reject::{self, Reject},
+    reply::Response,
+    Filter, Rejection, Reply,
+};
+
+/// Request header carrying the API key
+pub const X_DIEM_API_KEY: &str = "X-Diem-Api-Key";
+
+/// Who makes the calls of a request, resolved once per request from its API key
+pub(crate) enum Caller {
+    /// Calls without an API key, or with API key authentication disabled
+    Public(Option<IpAddr>),
+    Key(Arc<KeyAccess>),
+    /// Calls with an API key missing from the keys file
+    UnknownKey,
+}
+
+pub(crate) struct KeyAccess {
+    access: ApiKeyAccess,
+    allowed_methods: Option<HashSet<Method>>,
+    quota: Option<SharedBucket>,
+}
+
+impl KeyAccess {
+    fn new(access: ApiKeyAccess) -> Result<Self> {
+        let allowed_methods = access
+            .allowed_methods
+            .as_deref()
+            .map(parse_methods)
+            .transpose()?;
+        let quota = match access.quota {
+            Some(quota) => {
+                ensure!(
+                    quota.rate > 0 && quota.size >= quota.rate,
+                    "invalid quota {:?}, expected 0 < rate <= size",
+                    quota
+                );
+                Some(Arc::new(Mutex::new(Bucket::new(
+                    "json-rpc".to_string(),
+                    "api_key".to_string(),
+                    "api_key".to_string(),
+                    quota.size,
+                    quota.size,
+                    quota.rate,
+                    None,
+                ))))
+            }
+            None => None,
+        };
+        Ok(Self {
+            access,
+            allowed_methods,
+            quota,
+        })
+    }
+}
+
+pub(crate) struct Authenticator {
+    enabled: bool,
+    keys_file: Option<PathBuf>,
+    public_methods: Option<HashSet<Method>>,
+    keys: RwLock<HashMap<String, Arc<KeyAccess>>>,
+}
+
+impl Authenticator {
+    pub fn new(config: &ApiKeyConfig) -> Self {
+        if !config.enabled {
+            return Self::open();
+        }
+
+        let public_methods = config
+            .public_methods
+            .as_deref()
+            .map(parse_methods)
+            .transpose()
+            .unwrap_or_else(|e| panic!("[json-rpc] invalid public methods: {}", e));
+        let authenticator = Self {
+            enabled: true,
+            keys_file: config.keys_file.clone(),
+            public_methods,
+            keys: RwLock::new(HashMap::new()),
+        };
+        authenticator
+            .reload()
+            .unwrap_or_else(|e| panic!("[json-rpc] failed to load API keys: {}", e));
+        authenticator
+    }
+
+    /// Lets every call through
+    pub fn open() -> Self {
+        Self {
+            enabled: false,
+            keys_file: None,
+            public_methods: None,
+            keys: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Reloads the keys file. The keys whose access didn't change keep the state of their quota.
+    pub fn reload(&self) -> Result<()> {
+        let keys_file = match &self.keys_file {
+            Some(keys_file) => keys_file,
+            None => return Ok(()),
+        };
+        let accesses = BTreeMap::<String, ApiKeyAccess>::load_config(keys_file)?;
+
+        let old_keys = self.keys.read().clone();
+        let keys = accesses
+            .into_iter()
+            .map(|(key, access)| {
+                let key_access = match old_keys.get(&key) {
+                    Some(old_access) if old_access.access == access => old_access.clone(),
+                    _ => Arc::new(KeyAccess::new(access)?),
+                };
+                Ok((key, key_access))
+            })
+            .collect::<Result<_>>()?;
+        *self.keys.write() = keys;
+        Ok(())
+    }
+
+    pub async fn reload_periodically(self: Arc<Self>, interval: Duration) {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.reload() {
+                warn!("[json-rpc] failed to reload API keys: {}", e);
+            }
+        }
+    }
+
+    pub fn caller(&self, api_key: Option<&str>, ip: Option<IpAddr>) -> Caller {
+        match api_key {
+            Some(api_key) if self.enabled => self
+                .keys
+                .read()
+                .get(api_key)
+                .cloned()
+                .map_or(Caller::UnknownKey, Caller::Key),
+            _ => Caller::Public(ip),
+        }
+    }
+
+    /// Checks that the caller may call `method`, and takes the `cost` tokens of the call from the
+    /// quota of its API key
+    pub fn authorize(
+        &self,
+        caller: &Caller,
+        method: Method,
+        cost: usize,
+    ) -> Result<(), JsonRpcError> {
+        let allowed_methods = match caller {
+            Caller::Public(_) => self.public_methods.as_ref(),
+            Caller::Key(key) => key.allowed_methods.as_ref(),
+            Caller::UnknownKey => {
+                return Err(JsonRpcError::unauthorized(
+                    ErrorReason::UnknownApiKey,
+                    "unknown API key",
+                ))
+            }
+        };
+        if let Some(allowed_methods) = allowed_methods {
+            if !allowed_methods.contains(&method) {
+                return Err(JsonRpcError::unauthorized(
+                    ErrorReason::MethodNotAllowed,
+                    &format!("method {} is not allowed", method.as_str()),
+                ));
+            }
+        }
+
+        if let Caller::Key(key) = caller {
+            if let Some(quota) = &key.quota {
+                quota
+                    .lock()
+                    .acquire_all_tokens(cost)
+                    .map_err(|retry_at| rate_limited(method, "api_key", cost, retry_at))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rejection of a request whose caller may not call the methods of its route, or is rate limited
+#[derive(Debug)]
+pub(crate) struct AccessDenied(JsonRpcError);
+
+impl Reject for AccessDenied {}
+
+/// Filter giving the routes serving the data of JSON-RPC `methods` the same access control as the
+/// calls of these methods: it resolves the caller of a request from its API key and address, and
+/// rejects the request with `AccessDenied` unless the caller may call all the `methods`, taking
+/// their rate limit tokens. It must follow the filters matching the path of the route, so that
+/// the requests of other routes don't take tokens.
+pub(crate) fn authorize(service: JsonRpcService, methods: &'static [Method]) -> BoxedFilter<()> {
+    warp::addr::remote()
+        .and(warp::header::optional::<String>(X_DIEM_API_KEY))
+        .and(warp::header::optional::<String>(X_FORWARDED_FOR))
+        .and_then(
+            move |remote_addr: Option<SocketAddr>,
+                  api_key: Option<String>,
+                  forwarded_for: Option<String>| {
+                let service = service.clone();
+                async move {
+                    let caller = service.caller(
+                        api_key.as_deref(),
+                        remote_addr.map(|addr| addr.ip()),
+                        forwarded_for.as_deref(),
+                    );
+                    for method in methods {
+                        service
+                            .authorize(&caller, *method, 1)
+                            .map_err(|e| reject::custom(AccessDenied(e)))?;
+                    }
+                    Ok::<_, Rejection>(())
+                }
+            },
+        )
+        .untuple_one()
+        .boxed()
+}
+
+/// Replies to the `AccessDenied` rejections with their error, passing any other rejection on
+pub(crate) async fn recover_access_denied(rejection: Rejection) -> Result<Response, Rejection> {
+    match rejection.find::<AccessDenied>() {
+        Some(AccessDenied(error)) => {
+            let status = if error.code == ServerCode::RateLimited as i16 {
+                StatusCode::TOO_MANY_REQUESTS
+            } else {
+                StatusCode::UNAUTHORIZED
+            };
+            Ok(warp::reply::with_status(warp::reply::json(error), status).into_response())
+        }
+        None => Err(rejection),
+    }
+}
+
+fn parse_methods(names: &[String]) -> Result<HashSet<Method>> {
+    names.iter().map(|name| parse_method(name)).collect()
+}
@@ -82,3 +82,30 @@ pub static METHOD_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+/// Cumulative number of requests that the REST endpoint receives
+pub static REST_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_client_service_rest_requests_count",
+        "Cumulative number of requests that the REST endpoint receives",
+        &[
+            "method",   // the JSON RPC method the endpoint mirrors (e.g. "get_account")
+            "encoding", // encoding of the response: "json", "bcs"
+            "status",   // HTTP status code of the response
+        ]
+    )
+    .unwrap()
+});
+
+/// Cumulative number of calls throttled by the rate limiter
+pub static RATE_LIMITED_CALLS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_client_service_rate_limited_calls_count",
+        "Cumulative number of calls throttled by the rate limiter",
+        &[
+            "method", // method of the call, matches JSON RPC method name (e.g. "get_transactions")
+            "limit",  // the exhausted limit: "ip", "method" or "api_key"
+        ]
+    )
+    .unwrap()
+});
@@ -0,0 +1,72 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Cursors to page through the results of `get_transactions` and `get_events`.
+//!
+//! A cursor is returned as the `next_cursor` of a response whose page is full, and is opaque to
+//! clients: it is the hex-encoded BCS of `Cursor`. Besides the start of the next page, it pins the
+//! ledger version the first page was read at, so the pages of one query stay consistent with each
+//! other while the ledger grows.
+
+use crate::errors::{ErrorReason, JsonRpcError};
+use diem_types::event::EventKey;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum Cursor {
+    Transactions {
+        ledger_version: u64,
+        start_version: u64,
+    },
+    Events {
+        key: EventKey,
+        ledger_version: u64,
+        start: u64,
+    },
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        hex::encode(bcs::to_bytes(self).expect("cursor serialization should not fail"))
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, JsonRpcError> {
+        hex::decode(cursor)
+            .ok()
+            .and_then(|bytes| bcs::from_bytes(&bytes).ok())
+            .ok_or_else(|| {
+                JsonRpcError::invalid_param("cursor", ErrorReason::InvalidCursor, "is malformed")
+            })
+    }
+
+    /// Decodes a cursor of `get_transactions` into its ledger version and start version
+    pub fn decode_transactions(cursor: &str) -> Result<(u64, u64), JsonRpcError> {
+        match Self::decode(cursor)? {
+            Cursor::Transactions {
+                ledger_version,
+                start_version,
+            } => Ok((ledger_version, start_version)),
+            _ => Err(JsonRpcError::invalid_param(
+                "cursor",
+                ErrorReason::InvalidCursor,
+                "should be a cursor of get_transactions",
+            )),
+        }
+    }
+
+    /// Decodes a cursor of `get_events` for `key` into its ledger version and start sequence number
+    pub fn decode_events(cursor: &str, key: &EventKey) -> Result<(u64, u64), JsonRpcError> {
+        match Self::decode(cursor)? {
+            Cursor::Events {
+                key: cursor_key,
+                ledger_version,
+                start,
+            } if &cursor_key == key => Ok((ledger_version, start)),
+            _ => Err(JsonRpcError::invalid_param(
+                "cursor",
+                ErrorReason::InvalidCursor,
+                "should be a cursor of get_events for the same key",
+            )),
+        }
+    }
+}
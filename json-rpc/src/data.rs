@@ -2,19 +2,27 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    errors::JsonRpcError,
+    errors::{ErrorReason, JsonRpcError},
     views::{
         AccountStateWithProofView, AccountTransactionsWithProofView, AccountView,
-        AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionListView, TransactionView,
-        TransactionsWithProofsView,
+        AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView,
+        EventPageView, EventView, EventWithProofView, MetadataView, StateProofView,
+        TransactionListView, TransactionSimulationView, TransactionView,
+        TransactionsWithProofsView, VMStatusView, WriteSetChangeView,
     },
 };
 use anyhow::Result;
+use diem_json_rpc_types::request::EventOrder;
+use diem_state_view::StateView;
 use diem_types::{
-    account_address::AccountAddress, account_config::diem_root_address,
-    account_state::AccountState, chain_id::ChainId, event::EventKey,
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    account_config::diem_root_address,
+    account_state::AccountState,
+    chain_id::ChainId,
+    event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
+    transaction::{SignedTransaction, TransactionStatus},
 };
 use resource_viewer::{AnnotatedMoveStruct, MoveValueAnnotator};
 use std::{
@@ -22,6 +30,7 @@ use std::{
     convert::{TryFrom, TryInto},
 };
 use storage_interface::{MoveDbReader, Order};
+use vm_validator::vm_validator::{simulate_and_estimate_gas, MAX_SIMULATION_GAS_AMOUNT};
 
 pub fn get_account_state(
     db: &dyn MoveDbReader,
@@ -149,6 +158,26 @@ pub fn get_account_transactions(
     Ok(txs.0)
 }
 
+/// Returns the latest transactions sent by the account, newest first
+pub fn get_latest_account_transactions(
+    db: &dyn MoveDbReader,
+    account: AccountAddress,
+    start_seq_num: Option<u64>,
+    limit: u64,
+    include_events: bool,
+    ledger_version: u64,
+) -> Result<Vec<TransactionView>, JsonRpcError> {
+    let acct_txs = db.get_latest_account_transactions(
+        account,
+        start_seq_num,
+        limit,
+        include_events,
+        ledger_version,
+    )?;
+    let txs = TransactionListView::try_from(acct_txs)?;
+    Ok(txs.0)
+}
+
 /// Return a serialized list of an account's transactions along with a proof for
 /// each transaction.
 pub fn get_account_transactions_with_proofs(
@@ -185,6 +214,31 @@ pub fn get_events(
     Ok(events)
 }
 
+/// Returns a page of the events by given access path, in either order
+pub fn get_events_page(
+    db: &dyn MoveDbReader,
+    ledger_version: u64,
+    event_key: EventKey,
+    start: Option<u64>,
+    limit: u64,
+    order: EventOrder,
+) -> Result<EventPageView, JsonRpcError> {
+    let order = match order {
+        EventOrder::Ascending => Order::Ascending,
+        EventOrder::Descending => Order::Descending,
+    };
+    let page = db.get_events_page(&event_key, start, order, limit, ledger_version)?;
+
+    Ok(EventPageView {
+        events: page
+            .events
+            .into_iter()
+            .map(|event| event.try_into())
+            .collect::<Result<Vec<EventView>>>()?,
+        next_cursor: page.next_cursor,
+    })
+}
+
 /// Returns events by given access path along with their proofs
 pub fn get_events_with_proofs(
     db: &dyn MoveDbReader,
@@ -264,10 +318,13 @@ pub fn get_accumulator_consistency_proof(
 ) -> Result<AccumulatorConsistencyProofView, JsonRpcError> {
     if let Some(client_known_version) = client_known_version {
         if client_known_version > ledger_version {
-            return Err(JsonRpcError::invalid_request_with_msg(format!(
-                "client_known_version({}) should be <= ledger_version({})",
-                client_known_version, ledger_version,
-            )));
+            return Err(JsonRpcError::invalid_request_with_msg(
+                ErrorReason::VersionTooHigh,
+                format!(
+                    "client_known_version({}) should be <= ledger_version({})",
+                    client_known_version, ledger_version,
+                ),
+            ));
         }
     }
     let proof = db.get_accumulator_consistency_proof(client_known_version, ledger_version)?;
@@ -284,10 +341,13 @@ pub fn get_account_state_with_proof(
     version: u64,
 ) -> Result<AccountStateWithProofView, JsonRpcError> {
     if version > ledger_version {
-        return Err(JsonRpcError::invalid_request_with_msg(format!(
-            "version({}) should <= ledger version({})",
-            version, ledger_version
-        )));
+        return Err(JsonRpcError::invalid_request_with_msg(
+            ErrorReason::VersionTooHigh,
+            format!(
+                "version({}) should <= ledger version({})",
+                version, ledger_version
+            ),
+        ));
     }
     let account_state_with_proof =
         db.get_account_state_with_proof(account_address, version, ledger_version)?;
@@ -296,6 +356,31 @@ pub fn get_account_state_with_proof(
     )?)
 }
 
+/// Returns the states of a batch of accounts, each alongside a proof relative to the version and
+/// ledger_version specified by the client, in the order of `account_addresses`.
+pub fn get_account_states_with_proofs(
+    db: &dyn MoveDbReader,
+    ledger_version: u64,
+    account_addresses: &[AccountAddress],
+    version: u64,
+) -> Result<Vec<AccountStateWithProofView>, JsonRpcError> {
+    if version > ledger_version {
+        return Err(JsonRpcError::invalid_request_with_msg(
+            ErrorReason::VersionTooHigh,
+            format!(
+                "version({}) should <= ledger version({})",
+                version, ledger_version
+            ),
+        ));
+    }
+    db.get_state_values_with_proofs(account_addresses, version, ledger_version)?
+        .into_iter()
+        .map(|account_state_with_proof| {
+            AccountStateWithProofView::try_from(account_state_with_proof).map_err(Into::into)
+        })
+        .collect()
+}
+
 /// Get all resources stored under `account_address` at `version`
 pub fn get_resources(
     db: &dyn MoveDbReader,
@@ -317,3 +402,79 @@ pub fn get_resources(
     }
     Ok(resources)
 }
+
+/// Runs `txn` through the VM against the state at `ledger_version`, without submitting it.
+/// Transactions discarded by the VM return the VM status error, like submissions do. The VM runs
+/// synchronously, so async callers must run this on a blocking thread.
+pub fn simulate_transaction(
+    db: &dyn MoveDbReader,
+    ledger_version: u64,
+    txn: SignedTransaction,
+    skip_signature_check: bool,
+) -> Result<TransactionSimulationView, JsonRpcError> {
+    if txn.max_gas_amount() > MAX_SIMULATION_GAS_AMOUNT {
+        return Err(JsonRpcError::invalid_param(
+            "data",
+            ErrorReason::LimitExceeded,
+            &format!(
+                "max gas amount = {}, exceed limit {}",
+                txn.max_gas_amount(),
+                MAX_SIMULATION_GAS_AMOUNT
+            ),
+        ));
+    }
+    let state_view = DbStateView {
+        db,
+        version: ledger_version,
+    };
+    let (output, estimate) = simulate_and_estimate_gas(txn, skip_signature_check, &state_view);
+    let suggested_max_gas_amount = estimate.map(|estimate| estimate.suggested_max_gas_amount);
+    let vm_status = match output.status() {
+        TransactionStatus::Keep(status) => VMStatusView::from(status),
+        TransactionStatus::Discard(status_code) => {
+            return Err(JsonRpcError::vm_status(*status_code))
+        }
+        TransactionStatus::Retry => {
+            return Err(JsonRpcError::internal_error(
+                "unexpected retry status of a simulated transaction".to_string(),
+            ))
+        }
+    };
+
+    // the events are those the transaction would emit when committed at the next version
+    let events = output
+        .events()
+        .iter()
+        .map(|event| EventView::try_from((ledger_version + 1, event.clone())))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(TransactionSimulationView {
+        vm_status,
+        gas_used: output.gas_used(),
+        suggested_max_gas_amount,
+        write_set: output
+            .write_set()
+            .iter()
+            .map(WriteSetChangeView::from)
+            .collect(),
+        events,
+    })
+}
+
+/// The state at `version`, read from the DB without proofs
+struct DbStateView<'a> {
+    db: &'a dyn MoveDbReader,
+    version: u64,
+}
+
+impl<'a> StateView for DbStateView<'a> {
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        Ok(
+            get_account_state(self.db, access_path.address, self.version)?
+                .and_then(|account_state| account_state.get(&access_path.path).cloned()),
+        )
+    }
+
+    fn is_genesis(&self) -> bool {
+        false
+    }
+}
@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{methods, runtime, tests};
+use crate::{auth, methods, rate_limit, runtime, tests};
 use diem_config::config;
 use diem_proptest_helpers::ValueGenerator;
 use diem_types::account_state_blob::AccountStateWithProof;
@@ -79,6 +79,23 @@ fn test_method_fuzzer() {
         &gen_request_params!(["00000000000000000000000000000000000000000a550c18", 0]),
         "get_event_by_version_with_proof",
     );
+    method_fuzzer(
+        &gen_request_params!([
+            "00000000000000000000000000000000000000000a550c18",
+            0,
+            10,
+            "descending"
+        ]),
+        "get_events_page",
+    );
+    method_fuzzer(
+        &gen_request_params!(["000000000000000000000000000000dd", null, 1, true]),
+        "get_latest_account_transactions",
+    );
+    method_fuzzer(
+        &gen_request_params!([["000000000000000000000000000000dd"], 0, 1]),
+        "get_account_states_with_proofs",
+    );
 }
 
 pub fn method_fuzzer(params_data: &[u8], method: &str) {
@@ -146,6 +163,8 @@ pub fn request_fuzzer(json_request: serde_json::Value) {
         diem_types::chain_id::ChainId::test(),
         config::DEFAULT_BATCH_SIZE_LIMIT,
         config::DEFAULT_PAGE_SIZE_LIMIT,
+        Arc::new(rate_limit::RateLimiter::open()),
+        Arc::new(auth::Authenticator::open()),
     );
     let rt = tokio::runtime::Builder::new_current_thread()
         .enable_all()
@@ -153,7 +172,9 @@ pub fn request_fuzzer(json_request: serde_json::Value) {
         .unwrap();
 
     rt.spawn(async move {
-        if let Some((_, cb)) = mp_events.next().await {
+        if let Some(diem_mempool::MempoolClientRequest::SubmitTransaction(_, cb)) =
+            mp_events.next().await
+        {
             cb.send(Ok((
                 diem_types::mempool_status::MempoolStatus::new(
                     diem_types::mempool_status::MempoolStatusCode::Accepted,
@@ -164,7 +185,7 @@ pub fn request_fuzzer(json_request: serde_json::Value) {
         }
     });
     let body = rt.block_on(async {
-        let reply = runtime::rpc_endpoint(json_request, service, None)
+        let reply = runtime::rpc_endpoint(json_request, service, None, None, None, None, None)
             .await
             .unwrap();
 
@@ -0,0 +1,219 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! `/health` and `/sync_status` endpoints for load balancers and monitoring.
+//!
+//! Both report the latest ledger info of the DB, along with the progress state sync publishes and
+//! the connected peers of the node, which are read from the connection gauges of the network. Only
+//! these gauges are collected, the endpoints are unauthenticated so they don't gather every metric.
+
+use crate::runtime::check_latest_ledger_info_timestamp;
+use anyhow::Result;
+use diem_types::chain_id::ChainId;
+use network::counters::DIEM_CONNECTIONS;
+use prometheus::{core::Collector, proto::LabelPair};
+use serde::{Deserialize, Serialize};
+use state_sync_v1::sync_progress::get_sync_progress;
+use std::{collections::BTreeMap, sync::Arc, time::SystemTime};
+use storage_interface::MoveDbReader;
+use warp::{
+    filters::BoxedFilter,
+    http::StatusCode,
+    reply::{self, Response},
+    Filter, Rejection, Reply,
+};
+
+/// Query params of `/health`. Without params, the node is healthy when its DB serves the latest
+/// ledger info.
+#[derive(Debug, Deserialize)]
+struct HealthParams {
+    /// Unhealthy when the latest ledger info is older than this many seconds
+    max_ledger_age_secs: Option<u64>,
+    /// Unhealthy when the ledger is more than this many versions behind the highest version state
+    /// sync knows of. Not checked while that version is unknown.
+    max_sync_lag_versions: Option<u64>,
+    /// Unhealthy when state sync estimates it needs more than this many seconds to catch up with
+    /// the highest version it knows of. Not checked while the estimate is unknown.
+    max_sync_eta_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct SyncStatus {
+    pub chain_id: u8,
+    pub ledger_version: u64,
+    pub ledger_timestamp_usecs: u64,
+    /// The highest version of the chain state sync knows of, None until it knows one
+    pub highest_known_version: Option<u64>,
+    /// How many versions the ledger is behind `highest_known_version`
+    pub sync_lag_versions: Option<u64>,
+    /// Versions state sync syncs per second, None while state sync doesn't run
+    pub sync_versions_per_sec: Option<f64>,
+    /// Estimated seconds for state sync to sync to `highest_known_version`, None while unknown
+    pub sync_eta_secs: Option<u64>,
+    pub connected_peers: PeerCounts,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct PeerCounts {
+    pub total: u64,
+    pub inbound: u64,
+    pub outbound: u64,
+    /// Connected peers of every network, by network id
+    pub by_network: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct HealthStatus {
+    pub healthy: bool,
+    /// Why the node is unhealthy
+    pub errors: Vec<String>,
+    #[serde(flatten)]
+    pub sync_status: SyncStatus,
+}
+
+/// Gets the routes of `/health` and `/sync_status`
+pub(crate) fn get_health_routes(
+    db: Arc<dyn MoveDbReader>,
+    chain_id: ChainId,
+) -> BoxedFilter<(impl Reply,)> {
+    let db = warp::any().map(move || db.clone());
+    let chain_id = warp::any().map(move || chain_id);
+
+    let health = warp::path!("health")
+        .and(warp::get())
+        .and(warp::query::<HealthParams>())
+        .and(db.clone())
+        .and(chain_id.clone())
+        .and_then(health);
+    let sync_status = warp::path!("sync_status")
+        .and(warp::get())
+        .and(db)
+        .and(chain_id)
+        .and_then(sync_status);
+
+    health.or(sync_status).boxed()
+}
+
+async fn health(
+    params: HealthParams,
+    db: Arc<dyn MoveDbReader>,
+    chain_id: ChainId,
+) -> Result<Response, Rejection> {
+    let status = match get_sync_status(db.as_ref(), chain_id) {
+        Ok(status) => status,
+        Err(e) => return Ok(unavailable(e)),
+    };
+
+    let mut errors = vec![];
+    if let Some(max_ledger_age_secs) = params.max_ledger_age_secs {
+        if check_latest_ledger_info_timestamp(
+            max_ledger_age_secs,
+            status.ledger_timestamp_usecs,
+            SystemTime::now(),
+        )
+        .is_err()
+        {
+            errors.push(format!(
+                "latest ledger info is older than {}s",
+                max_ledger_age_secs
+            ));
+        }
+    }
+    if let (Some(max_sync_lag_versions), Some(lag)) =
+        (params.max_sync_lag_versions, status.sync_lag_versions)
+    {
+        if lag > max_sync_lag_versions {
+            errors.push(format!(
+                "ledger is {} versions behind the chain, more than {}",
+                lag, max_sync_lag_versions
+            ));
+        }
+    }
+    if let (Some(max_sync_eta_secs), Some(eta_secs)) =
+        (params.max_sync_eta_secs, status.sync_eta_secs)
+    {
+        if eta_secs > max_sync_eta_secs {
+            errors.push(format!(
+                "state sync needs {}s to catch up with the chain, more than {}s",
+                eta_secs, max_sync_eta_secs
+            ));
+        }
+    }
+
+    let code = if errors.is_empty() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let status = HealthStatus {
+        healthy: errors.is_empty(),
+        errors,
+        sync_status: status,
+    };
+    Ok(reply::with_status(reply::json(&status), code).into_response())
+}
+
+async fn sync_status(db: Arc<dyn MoveDbReader>, chain_id: ChainId) -> Result<Response, Rejection> {
+    Ok(match get_sync_status(db.as_ref(), chain_id) {
+        Ok(status) => reply::json(&status).into_response(),
+        Err(e) => unavailable(e),
+    })
+}
+
+fn unavailable(error: anyhow::Error) -> Response {
+    reply::with_status(
+        format!("failed to read the latest ledger info: {}", error),
+        StatusCode::SERVICE_UNAVAILABLE,
+    )
+    .into_response()
+}
+
+pub fn get_sync_status(db: &dyn MoveDbReader, chain_id: ChainId) -> Result<SyncStatus> {
+    let ledger_info = db.get_latest_ledger_info()?;
+    let ledger_version = ledger_info.ledger_info().version();
+
+    let sync_progress = get_sync_progress();
+    let highest_known_version = sync_progress
+        .as_ref()
+        .and_then(|progress| progress.highest_known_version);
+
+    let mut connected_peers = PeerCounts::default();
+    for family in &DIEM_CONNECTIONS.collect() {
+        for metric in family.get_metric() {
+            let count = metric.get_gauge().get_value() as u64;
+            connected_peers.total += count;
+            match label(metric.get_label(), "direction") {
+                Some("inbound") => connected_peers.inbound += count,
+                Some("outbound") => connected_peers.outbound += count,
+                _ => (),
+            }
+            if let Some(network_id) = label(metric.get_label(), "network_id") {
+                *connected_peers
+                    .by_network
+                    .entry(network_id.to_string())
+                    .or_default() += count;
+            }
+        }
+    }
+
+    Ok(SyncStatus {
+        chain_id: chain_id.id(),
+        ledger_version,
+        ledger_timestamp_usecs: ledger_info.ledger_info().timestamp_usecs(),
+        highest_known_version,
+        sync_lag_versions: highest_known_version
+            .map(|highest_known_version| highest_known_version.saturating_sub(ledger_version)),
+        sync_versions_per_sec: sync_progress
+            .as_ref()
+            .map(|progress| progress.versions_per_sec),
+        sync_eta_secs: sync_progress.and_then(|progress| progress.eta_secs),
+        connected_peers,
+    })
+}
+
+fn label<'a>(labels: &'a [LabelPair], name: &str) -> Option<&'a str> {
+    labels
+        .iter()
+        .find(|label| label.get_name() == name)
+        .map(|label| label.get_value())
+}
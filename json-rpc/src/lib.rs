@@ -9,22 +9,30 @@
 //! Protocol specification: https://www.jsonrpc.org/specification
 //!
 //! Module organization:
+//! ├── health.rs         # health and sync status endpoints
 //! ├── methods.rs        # contains all available JSON RPC method handlers
 //! ├── runtime.rs        # implementation of JSON RPC protocol over HTTP
+//! ├── rest              # REST counterpart to the JSON RPC methods
 //! ├── tests.rs          # tests
 
 #[macro_use]
 pub mod util;
 
+mod auth;
 mod counters;
+mod cursor;
 pub mod data;
+pub mod health;
 mod methods;
+mod rate_limit;
 pub mod runtime;
 
 pub use diem_json_rpc_types::{errors, response, views};
 
+pub mod rest;
 pub mod stream_rpc;
 
+pub use rate_limit::RateLimiter;
 pub use runtime::{bootstrap, bootstrap_from_config};
 
 #[cfg(any(feature = "fuzzing", test))]
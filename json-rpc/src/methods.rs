@@ -3,35 +3,53 @@
 
 //! Module contains RPC method handlers for Full Node JSON-RPC interface
 use crate::{
+    auth::{Authenticator, Caller},
+    cursor::Cursor,
     data,
-    errors::JsonRpcError,
+    errors::{ErrorReason, JsonRpcError},
+    rate_limit::RateLimiter,
     views::{
-        AccountStateWithProofView, AccountTransactionsWithProofView, AccountView,
-        AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionListView, TransactionView,
+        AccountStateWithProofView, AccountTransactionsPendingView,
+        AccountTransactionsWithProofView, AccountView, AccumulatorConsistencyProofView,
+        CurrencyInfoView, EventByVersionWithProofView, EventPageView, EventView,
+        EventWithProofView, MetadataView, PendingTransactionView, StateProofView,
+        SubmissionStatusView, TransactionListView, TransactionSimulationView, TransactionView,
         TransactionsWithProofsView,
     },
 };
 use anyhow::Result;
 use diem_config::config::RoleType;
-use diem_json_rpc_types::request::{
-    GetAccountParams, GetAccountStateWithProofParams, GetAccountTransactionParams,
-    GetAccountTransactionsParams, GetAccountTransactionsWithProofsParams,
-    GetAccumulatorConsistencyProofParams, GetCurrenciesParams, GetEventByVersionWithProof,
-    GetEventsParams, GetEventsWithProofsParams, GetMetadataParams, GetNetworkStatusParams,
-    GetResourcesParams, GetStateProofParams, GetTransactionsParams,
-    GetTransactionsWithProofsParams, MethodRequest, SubmitParams,
+use diem_crypto::hash::CryptoHash;
+use diem_json_rpc_types::{
+    request::{
+        GetAccountParams, GetAccountStateWithProofParams, GetAccountStatesWithProofsParams,
+        GetAccountTransactionParams, GetAccountTransactionsParams,
+        GetAccountTransactionsPendingParams, GetAccountTransactionsWithProofsParams,
+        GetAccumulatorConsistencyProofParams, GetCurrenciesParams, GetEventByVersionWithProof,
+        GetEventsPageParams, GetEventsParams, GetEventsWithProofsParams,
+        GetLatestAccountTransactionsParams, GetMetadataParams, GetNetworkStatusParams,
+        GetResourcesParams, GetStateProofParams, GetTransactionsParams,
+        GetTransactionsWithProofsParams, MethodRequest, SimulateTransactionParams,
+        SubmitBatchParams, SubmitParams,
+    },
+    Method,
+};
+use diem_mempool::{
+    AccountPendingTransactions, MempoolClientRequest, MempoolClientSender, SubmissionStatus,
 };
-use diem_mempool::{MempoolClientSender, SubmissionStatus};
 use diem_types::{
-    chain_id::ChainId, ledger_info::LedgerInfoWithSignatures, mempool_status::MempoolStatusCode,
-    transaction::SignedTransaction,
+    account_address::AccountAddress,
+    chain_id::ChainId,
+    ledger_info::LedgerInfoWithSignatures,
+    mempool_status::MempoolStatusCode,
+    transaction::{SignedTransaction, Transaction, TransactionListWithProof},
+    vm_status::StatusCode,
 };
 use fail::fail_point;
-use futures::{channel::oneshot, SinkExt};
+use futures::{channel::oneshot, future::join_all, SinkExt};
 use resource_viewer::AnnotatedMoveStruct;
 use serde_json::Value;
-use std::{borrow::Borrow, collections::BTreeMap, sync::Arc};
+use std::{borrow::Borrow, collections::BTreeMap, net::IpAddr, sync::Arc};
 use storage_interface::MoveDbReader;
 
 #[derive(Clone)]
@@ -42,6 +60,8 @@ pub(crate) struct JsonRpcService {
     chain_id: ChainId,
     batch_size_limit: u16,
     page_size_limit: u16,
+    rate_limiter: Arc<RateLimiter>,
+    authenticator: Arc<Authenticator>,
 }
 
 impl JsonRpcService {
@@ -52,6 +72,8 @@ impl JsonRpcService {
         chain_id: ChainId,
         batch_size_limit: u16,
         page_size_limit: u16,
+        rate_limiter: Arc<RateLimiter>,
+        authenticator: Arc<Authenticator>,
     ) -> Self {
         Self {
             db,
@@ -60,6 +82,8 @@ impl JsonRpcService {
             chain_id,
             batch_size_limit,
             page_size_limit,
+            rate_limiter,
+            authenticator,
         }
     }
 
@@ -71,7 +95,26 @@ impl JsonRpcService {
 
         self.mempool_sender
             .clone()
-            .send((transaction, req_sender))
+            .send(MempoolClientRequest::SubmitTransaction(
+                transaction,
+                req_sender,
+            ))
+            .await?;
+
+        callback.await?
+    }
+
+    pub async fn mempool_pending_transactions_request(
+        &self,
+        account: AccountAddress,
+    ) -> Result<AccountPendingTransactions> {
+        let (req_sender, callback) = oneshot::channel();
+
+        self.mempool_sender
+            .clone()
+            .send(MempoolClientRequest::GetAccountPendingTransactions(
+                account, req_sender,
+            ))
             .await?;
 
         callback.await?
@@ -91,6 +134,10 @@ impl JsonRpcService {
         self.chain_id
     }
 
+    pub fn db(&self) -> &dyn MoveDbReader {
+        self.db.borrow()
+    }
+
     pub fn validate_batch_size_limit(&self, size: usize) -> Result<(), JsonRpcError> {
         self.validate_size_limit("batch size", self.batch_size_limit, size)
     }
@@ -99,12 +146,40 @@ impl JsonRpcService {
         self.validate_size_limit("page size", self.page_size_limit, size)
     }
 
+    /// The caller of a request with `api_key` from `remote_ip`, see `RateLimiter::client_ip` for
+    /// the requests forwarded by a proxy
+    pub fn caller(
+        &self,
+        api_key: Option<&str>,
+        remote_ip: Option<IpAddr>,
+        forwarded_for: Option<&str>,
+    ) -> Caller {
+        let ip = self.rate_limiter.client_ip(remote_ip, forwarded_for);
+        self.authenticator.caller(api_key, ip)
+    }
+
+    /// Checks that the caller may call `method`, and takes the `cost` rate limit tokens of the
+    /// call: from the quota of its API key, or from the limits of its IP without a key. Calls from
+    /// an unknown address are not rate limited.
+    pub fn authorize(
+        &self,
+        caller: &Caller,
+        method: Method,
+        cost: usize,
+    ) -> Result<(), JsonRpcError> {
+        self.authenticator.authorize(caller, method, cost)?;
+        match caller {
+            Caller::Public(Some(ip)) => self.rate_limiter.acquire(*ip, method, cost),
+            _ => Ok(()),
+        }
+    }
+
     fn validate_size_limit(&self, name: &str, limit: u16, size: usize) -> Result<(), JsonRpcError> {
         if size > limit as usize {
-            Err(JsonRpcError::invalid_request_with_msg(format!(
-                "{} = {}, exceed limit {}",
-                name, size, limit
-            )))
+            Err(JsonRpcError::invalid_request_with_msg(
+                ErrorReason::LimitExceeded,
+                format!("{} = {}, exceed limit {}", name, size, limit),
+            ))
         } else {
             Ok(())
         }
@@ -124,19 +199,31 @@ impl<'a> Handler<'a> {
         }
     }
 
-    fn version(&self) -> u64 {
+    pub fn service(&self) -> &JsonRpcService {
+        self.service
+    }
+
+    pub fn ledger_info(&self) -> &LedgerInfoWithSignatures {
+        self.ledger_info
+    }
+
+    pub fn version(&self) -> u64 {
         self.ledger_info.ledger_info().version()
     }
 
-    fn version_param(&self, version: Option<u64>, name: &str) -> Result<u64, JsonRpcError> {
+    pub fn version_param(&self, version: Option<u64>, name: &str) -> Result<u64, JsonRpcError> {
         let latest_ledger_version = self.version();
         let version = version.unwrap_or(latest_ledger_version);
 
         if version > latest_ledger_version {
-            return Err(JsonRpcError::invalid_param(&format!(
-                "{} should be <= known latest version {}",
-                name, latest_ledger_version,
-            )));
+            return Err(JsonRpcError::invalid_param(
+                name,
+                ErrorReason::VersionTooHigh,
+                &format!(
+                    "should be <= known latest version {}",
+                    latest_ledger_version
+                ),
+            ));
         }
 
         Ok(version)
@@ -152,7 +239,7 @@ impl<'a> Handler<'a> {
                 serde_json::to_value(self.get_account(params).await?)?
             }
             MethodRequest::GetTransactions(params) => {
-                serde_json::to_value(self.get_transactions(params).await?)?
+                serde_json::to_value(self.get_transactions(params).await?.0)?
             }
             MethodRequest::GetAccountTransaction(params) => {
                 serde_json::to_value(self.get_account_transaction(params).await?)?
@@ -161,7 +248,7 @@ impl<'a> Handler<'a> {
                 serde_json::to_value(self.get_account_transactions(params).await?)?
             }
             MethodRequest::GetEvents(params) => {
-                serde_json::to_value(self.get_events(params).await?)?
+                serde_json::to_value(self.get_events(params).await?.0)?
             }
             MethodRequest::GetCurrencies(params) => {
                 serde_json::to_value(self.get_currencies(params).await?)?
@@ -169,6 +256,9 @@ impl<'a> Handler<'a> {
             MethodRequest::GetNetworkStatus(params) => {
                 serde_json::to_value(self.get_network_status(params).await?)?
             }
+            MethodRequest::GetAccountTransactionsPending(params) => {
+                serde_json::to_value(self.get_account_transactions_pending(params).await?)?
+            }
             MethodRequest::GetResources(params) => {
                 serde_json::to_value(self.get_resources(params).await?)?
             }
@@ -193,10 +283,104 @@ impl<'a> Handler<'a> {
             MethodRequest::GetEventByVersionWithProof(params) => {
                 serde_json::to_value(self.get_event_by_version_with_proof(params).await?)?
             }
+            MethodRequest::GetEventsPage(params) => {
+                serde_json::to_value(self.get_events_page(params).await?)?
+            }
+            MethodRequest::GetLatestAccountTransactions(params) => {
+                serde_json::to_value(self.get_latest_account_transactions(params).await?)?
+            }
+            MethodRequest::GetAccountStatesWithProofs(params) => {
+                serde_json::to_value(self.get_account_states_with_proofs(params).await?)?
+            }
+            MethodRequest::SimulateTransaction(params) => {
+                serde_json::to_value(self.simulate_transaction(params).await?)?
+            }
+            MethodRequest::SubmitBatch(params) => {
+                serde_json::to_value(self.submit_batch(params).await?)?
+            }
         };
         Ok(response)
     }
 
+    /// Handles the request like `handle`, and also returns the encoded cursor of the next page for
+    /// the paged methods
+    pub async fn handle_with_cursor(
+        &self,
+        method_request: MethodRequest,
+    ) -> Result<(Value, Option<String>), JsonRpcError> {
+        let (response, cursor) = match method_request {
+            MethodRequest::GetTransactions(params) => {
+                let (txns, cursor) = self.get_transactions(params).await?;
+                (serde_json::to_value(txns)?, cursor)
+            }
+            MethodRequest::GetEvents(params) => {
+                let (events, cursor) = self.get_events(params).await?;
+                (serde_json::to_value(events)?, cursor)
+            }
+            method_request => (self.handle(method_request).await?, None),
+        };
+        Ok((response, cursor.map(|cursor| cursor.encode())))
+    }
+
+    /// Handles the request of a method supporting BCS responses, returning the BCS bytes of the
+    /// on-chain types of its result along with their proofs instead of the JSON views
+    pub async fn handle_bcs(&self, method_request: MethodRequest) -> Result<Vec<u8>, JsonRpcError> {
+        let db = self.service.db.borrow();
+        let bytes = match method_request {
+            MethodRequest::GetAccountStateWithProof(params) => {
+                let version = self.version_param(params.version, "version")?;
+                let ledger_version = self.version_param(params.ledger_version, "ledger_version")?;
+                if version > ledger_version {
+                    return Err(JsonRpcError::invalid_request_with_msg(
+                        ErrorReason::VersionTooHigh,
+                        format!(
+                            "version({}) should <= ledger version({})",
+                            version, ledger_version
+                        ),
+                    ));
+                }
+                bcs::to_bytes(&db.get_account_state_with_proof(
+                    params.account,
+                    version,
+                    ledger_version,
+                )?)?
+            }
+            MethodRequest::GetTransactions(params) => {
+                self.service
+                    .validate_page_size_limit(params.limit as usize)?;
+                let (ledger_version, start_version) =
+                    self.transactions_range(params.start_version, params.cursor)?;
+                let txns = if start_version > ledger_version || params.limit == 0 {
+                    TransactionListWithProof::new_empty()
+                } else {
+                    db.get_transactions(
+                        start_version,
+                        params.limit,
+                        ledger_version,
+                        params.include_events,
+                    )?
+                };
+                bcs::to_bytes(&txns)?
+            }
+            MethodRequest::GetStateProof(params) => {
+                let version = self.version_param(Some(params.version), "version")?;
+                bcs::to_bytes(
+                    &db.get_state_proof_with_ledger_info(version, self.ledger_info.clone())?,
+                )?
+            }
+            method_request => {
+                return Err(JsonRpcError::invalid_request_with_msg(
+                    ErrorReason::BcsNotSupported,
+                    format!(
+                        "method {} doesn't support BCS responses",
+                        method_request.method().as_str()
+                    ),
+                ))
+            }
+        };
+        Ok(bytes)
+    }
+
     async fn submit(&self, params: SubmitParams) -> Result<(), JsonRpcError> {
         let (mempool_status, vm_status_opt) = self.service.mempool_request(params.data).await?;
 
@@ -228,25 +412,59 @@ impl<'a> Handler<'a> {
         data::get_account(self.service.db.borrow(), account_address, version)
     }
 
-    /// Returns transactions by range
+    /// Returns transactions by range, and the cursor of the next page if the page is full and
+    /// doesn't reach the ledger version the pages are read at
     async fn get_transactions(
         &self,
         params: GetTransactionsParams,
-    ) -> Result<TransactionListView, JsonRpcError> {
+    ) -> Result<(TransactionListView, Option<Cursor>), JsonRpcError> {
         let GetTransactionsParams {
             start_version,
             limit,
             include_events,
+            cursor,
         } = params;
 
         self.service.validate_page_size_limit(limit as usize)?;
-        data::get_transactions(
+        let (ledger_version, start_version) = self.transactions_range(start_version, cursor)?;
+        let txns = data::get_transactions(
             self.service.db.borrow(),
-            self.version(),
+            ledger_version,
             start_version,
             limit,
             include_events,
-        )
+        )?;
+
+        let next_start_version = start_version + txns.0.len() as u64;
+        let cursor =
+            if limit > 0 && txns.0.len() as u64 == limit && next_start_version <= ledger_version {
+                Some(Cursor::Transactions {
+                    ledger_version,
+                    start_version: next_start_version,
+                })
+            } else {
+                None
+            };
+        Ok((txns, cursor))
+    }
+
+    /// Returns the ledger version to read a page of transactions at and the start version of the
+    /// page, taken from the cursor if there is one
+    fn transactions_range(
+        &self,
+        start_version: u64,
+        cursor: Option<String>,
+    ) -> Result<(u64, u64), JsonRpcError> {
+        match cursor {
+            Some(cursor) => {
+                let (ledger_version, start_version) = Cursor::decode_transactions(&cursor)?;
+                Ok((
+                    self.version_param(Some(ledger_version), "cursor version")?,
+                    start_version,
+                ))
+            }
+            None => Ok((self.version(), start_version)),
+        }
     }
 
     /// Returns transactions by range with proofs
@@ -313,6 +531,54 @@ impl<'a> Handler<'a> {
         )
     }
 
+    /// Returns the latest transactions sent by the account, newest first
+    async fn get_latest_account_transactions(
+        &self,
+        params: GetLatestAccountTransactionsParams,
+    ) -> Result<Vec<TransactionView>, JsonRpcError> {
+        let GetLatestAccountTransactionsParams {
+            account,
+            start,
+            limit,
+            include_events,
+        } = params;
+
+        self.service.validate_page_size_limit(limit as usize)?;
+        data::get_latest_account_transactions(
+            self.service.db.borrow(),
+            account,
+            start,
+            limit,
+            include_events,
+            self.version(),
+        )
+    }
+
+    /// Returns the transactions of an account pending in mempool, and the missing sequence
+    /// numbers parking some of them
+    async fn get_account_transactions_pending(
+        &self,
+        params: GetAccountTransactionsPendingParams,
+    ) -> Result<AccountTransactionsPendingView, JsonRpcError> {
+        let AccountPendingTransactions {
+            sequence_number,
+            transactions,
+            sequence_gaps,
+        } = self
+            .service
+            .mempool_pending_transactions_request(params.account)
+            .await?;
+
+        Ok(AccountTransactionsPendingView {
+            sequence_number,
+            transactions: transactions
+                .into_iter()
+                .map(|(txn, is_parked)| PendingTransactionView::try_from_signed_txn(txn, is_parked))
+                .collect::<Result<_>>()?,
+            sequence_gaps: sequence_gaps.into_iter().map(Into::into).collect(),
+        })
+    }
+
     /// Return a serialized list of an account's transactions along with a proof for
     /// each transaction.
     async fn get_account_transactions_with_proofs(
@@ -339,12 +605,71 @@ impl<'a> Handler<'a> {
         )
     }
 
-    /// Returns events by given access path
-    async fn get_events(&self, params: GetEventsParams) -> Result<Vec<EventView>, JsonRpcError> {
-        let GetEventsParams { key, start, limit } = params;
+    /// Returns events by given access path, and the cursor of the next page if the page is full
+    async fn get_events(
+        &self,
+        params: GetEventsParams,
+    ) -> Result<(Vec<EventView>, Option<Cursor>), JsonRpcError> {
+        let GetEventsParams {
+            key,
+            start,
+            limit,
+            cursor,
+        } = params;
 
         self.service.validate_page_size_limit(limit as usize)?;
-        data::get_events(self.service.db.borrow(), self.version(), key, start, limit)
+        let (ledger_version, start) = match cursor {
+            Some(cursor) => {
+                let (ledger_version, start) = Cursor::decode_events(&cursor, &key)?;
+                (
+                    self.version_param(Some(ledger_version), "cursor version")?,
+                    start,
+                )
+            }
+            None => (self.version(), start),
+        };
+        let events = data::get_events(self.service.db.borrow(), ledger_version, key, start, limit)?;
+
+        let cursor = if limit > 0 && events.len() as u64 == limit {
+            Some(Cursor::Events {
+                key,
+                ledger_version,
+                start: start + limit,
+            })
+        } else {
+            None
+        };
+        Ok((events, cursor))
+    }
+
+    /// Returns a page of the events by given access path, in either order
+    async fn get_events_page(
+        &self,
+        params: GetEventsPageParams,
+    ) -> Result<EventPageView, JsonRpcError> {
+        let GetEventsPageParams {
+            key,
+            start,
+            limit,
+            order,
+        } = params;
+
+        if limit == 0 {
+            return Err(JsonRpcError::invalid_param(
+                "limit",
+                ErrorReason::InvalidValue,
+                "should be > 0",
+            ));
+        }
+        self.service.validate_page_size_limit(limit as usize)?;
+        data::get_events_page(
+            self.service.db.borrow(),
+            self.version(),
+            key,
+            start,
+            limit,
+            order,
+        )
     }
 
     /// Returns events by given access path along with their proofs
@@ -445,4 +770,72 @@ impl<'a> Handler<'a> {
             version,
         )
     }
+
+    /// Returns the states of a batch of accounts, each alongside a proof relative to the version
+    /// and ledger_version specified by the client, in the order of the requested accounts.
+    async fn get_account_states_with_proofs(
+        &self,
+        params: GetAccountStatesWithProofsParams,
+    ) -> Result<Vec<AccountStateWithProofView>, JsonRpcError> {
+        let version = self.version_param(params.version, "version")?;
+        let ledger_version = self.version_param(params.ledger_version, "ledger_version")?;
+
+        self.service
+            .validate_page_size_limit(params.accounts.len())?;
+        data::get_account_states_with_proofs(
+            self.service.db.borrow(),
+            ledger_version,
+            &params.accounts,
+            version,
+        )
+    }
+
+    /// Runs a transaction through the VM against the state at the latest ledger version without
+    /// submitting it, to estimate its gas usage and preview its effects
+    async fn simulate_transaction(
+        &self,
+        params: SimulateTransactionParams,
+    ) -> Result<TransactionSimulationView, JsonRpcError> {
+        let db = self.service.db.clone();
+        let ledger_version = self.version();
+        tokio::task::spawn_blocking(move || {
+            data::simulate_transaction(
+                db.borrow(),
+                ledger_version,
+                params.data,
+                params.skip_signature_check,
+            )
+        })
+        .await
+        .map_err(|e| JsonRpcError::internal_error(e.to_string()))?
+    }
+
+    /// Submits transactions to mempool concurrently, returning the status of every transaction in
+    /// their order. The transactions of another chain, or expired at the latest ledger timestamp,
+    /// are rejected without a round trip to mempool.
+    async fn submit_batch(
+        &self,
+        params: SubmitBatchParams,
+    ) -> Result<Vec<SubmissionStatusView>, JsonRpcError> {
+        self.service.validate_batch_size_limit(params.data.len())?;
+        let chain_id = self.service.chain_id();
+        let ledger_timestamp_secs = self.ledger_info.ledger_info().timestamp_usecs() / 1_000_000;
+
+        let submissions = params.data.into_iter().map(|txn| async move {
+            let hash = Transaction::UserTransaction(txn.clone()).hash();
+            let result = if txn.chain_id() != chain_id {
+                Err(JsonRpcError::vm_status(StatusCode::BAD_CHAIN_ID))
+            } else if txn.expiration_timestamp_secs() <= ledger_timestamp_secs {
+                Err(JsonRpcError::vm_status(StatusCode::TRANSACTION_EXPIRED))
+            } else {
+                self.submit(SubmitParams { data: txn }).await
+            };
+            SubmissionStatusView {
+                hash,
+                accepted: result.is_ok(),
+                error: result.err(),
+            }
+        });
+        Ok(join_all(submissions).await)
+    }
 }
@@ -0,0 +1,214 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Token bucket rate limiting of the JSON-RPC calls of every client IP, in total and per method.
+//! See `JsonRpcRateLimitConfig` for the configuration.
+
+use crate::{counters, errors::JsonRpcError, util::parse_method};
+use diem_config::config::JsonRpcRateLimitConfig;
+use diem_infallible::RwLock;
+use diem_json_rpc_types::{request::MethodRequest, Method};
+use diem_rate_limiter::rate_limit::TokenBucketRateLimiter;
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const LABEL: &str = "json-rpc";
+
+/// Request header of the proxies listing the addresses a request was forwarded from
+pub const X_FORWARDED_FOR: &str = "X-Forwarded-For";
+
+/// Interval of the removal of the buckets of the IPs that stopped calling
+pub const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Limits the calls of the clients, by limits which can be updated while the service runs.
+pub struct RateLimiter {
+    limits: RwLock<Limits>,
+}
+
+struct Limits {
+    enabled: bool,
+    ip_limiter: TokenBucketRateLimiter<IpAddr>,
+    method_limiters: HashMap<Method, TokenBucketRateLimiter<IpAddr>>,
+    trusted_proxies: HashSet<IpAddr>,
+}
+
+impl Limits {
+    fn new(config: &JsonRpcRateLimitConfig) -> anyhow::Result<Self> {
+        if !config.enabled {
+            return Ok(Self::open());
+        }
+
+        let check_quota = |name: &str, rate: usize, size: usize| {
+            anyhow::ensure!(
+                rate > 0 && size >= rate,
+                "the {} quota must have a rate above 0 and a size of at least its rate",
+                name
+            );
+            Ok(())
+        };
+        check_quota("ip", config.ip_call_bucket_rate, config.ip_call_bucket_size)?;
+        let method_limiters = config
+            .method_quotas
+            .iter()
+            .map(|(name, quota)| -> anyhow::Result<_> {
+                let method = parse_method(name)?;
+                check_quota(name, quota.rate, quota.size)?;
+                let limiter = TokenBucketRateLimiter::new(
+                    LABEL,
+                    method.as_str().to_string(),
+                    100,
+                    quota.size,
+                    quota.rate,
+                    None,
+                );
+                Ok((method, limiter))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self {
+            enabled: true,
+            ip_limiter: TokenBucketRateLimiter::new(
+                LABEL,
+                "ip".to_string(),
+                100,
+                config.ip_call_bucket_size,
+                config.ip_call_bucket_rate,
+                None,
+            ),
+            method_limiters,
+            trusted_proxies: config.trusted_proxies.iter().copied().collect(),
+        })
+    }
+
+    fn open() -> Self {
+        Self {
+            enabled: false,
+            ip_limiter: TokenBucketRateLimiter::open(LABEL),
+            method_limiters: HashMap::new(),
+            trusted_proxies: HashSet::new(),
+        }
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: &JsonRpcRateLimitConfig) -> Self {
+        let limits = Limits::new(config)
+            .unwrap_or_else(|e| panic!("[json-rpc] invalid rate limit quotas: {}", e));
+        Self {
+            limits: RwLock::new(limits),
+        }
+    }
+
+    /// Doesn't limit any call
+    pub fn open() -> Self {
+        Self {
+            limits: RwLock::new(Limits::open()),
+        }
+    }
+
+    /// Replaces the limits by the ones of `config`. The buckets of the new limits start full.
+    pub fn update(&self, config: &JsonRpcRateLimitConfig) -> anyhow::Result<()> {
+        *self.limits.write() = Limits::new(config)?;
+        Ok(())
+    }
+
+    /// The IP the calls of a request from `remote_ip` are limited by. A request from a trusted
+    /// proxy is limited by the last address of its `X-Forwarded-For` header that isn't a trusted
+    /// proxy: the one the client connected from, as the addresses before it are up to the client.
+    /// Without such an address, the request is limited by the IP of the proxy.
+    pub fn client_ip(
+        &self,
+        remote_ip: Option<IpAddr>,
+        forwarded_for: Option<&str>,
+    ) -> Option<IpAddr> {
+        let limits = self.limits.read();
+        let trusted_proxies = &limits.trusted_proxies;
+        match (remote_ip, forwarded_for) {
+            (Some(ip), Some(forwarded_for)) if trusted_proxies.contains(&ip) => Some(
+                forwarded_for
+                    .rsplit(',')
+                    .map(|addr| addr.trim().parse::<IpAddr>())
+                    .find(|addr| !matches!(addr, Ok(addr) if trusted_proxies.contains(addr)))
+                    .and_then(Result::ok)
+                    .unwrap_or(ip),
+            ),
+            _ => remote_ip,
+        }
+    }
+
+    /// Takes the `cost` tokens of a call of `method` from `ip`, or returns a rate limited error
+    /// carrying how long to wait before retrying, or that the call is too large to be served
+    pub fn acquire(&self, ip: IpAddr, method: Method, cost: usize) -> Result<(), JsonRpcError> {
+        let limits = self.limits.read();
+        if !limits.enabled {
+            return Ok(());
+        }
+
+        let ip_bucket = limits.ip_limiter.bucket(ip);
+        let mut ip_bucket = ip_bucket.lock();
+        if let Err(retry_at) = ip_bucket.acquire_all_tokens(cost) {
+            return Err(rate_limited(method, "ip", cost, retry_at));
+        }
+
+        if let Some(method_limiter) = limits.method_limiters.get(&method) {
+            if let Err(retry_at) = method_limiter.bucket(ip).lock().acquire_all_tokens(cost) {
+                // the call isn't served, so it doesn't count against the calls of the IP
+                ip_bucket.return_tokens(cost);
+                return Err(rate_limited(method, "method", cost, retry_at));
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the buckets of the IPs that haven't called for long enough to refill them
+    pub fn prune(&self) {
+        let limits = self.limits.read();
+        limits.ip_limiter.garbage_collect_full_buckets();
+        for method_limiter in limits.method_limiters.values() {
+            method_limiter.garbage_collect_full_buckets();
+        }
+    }
+
+    pub async fn prune_periodically(self: Arc<Self>, interval: Duration) {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            self.prune();
+        }
+    }
+}
+
+/// Number of tokens a call takes: one per transaction of a `submit_batch`, and one otherwise
+pub(crate) fn call_cost(method_request: &MethodRequest) -> usize {
+    match method_request {
+        MethodRequest::SubmitBatch(params) => params.data.len().max(1),
+        _ => 1,
+    }
+}
+
+/// The error of a call that didn't get its `cost` tokens from the `limit` bucket. Without a retry
+/// time, the call costs more than the bucket can ever hold, so retrying it is pointless.
+pub(crate) fn rate_limited(
+    method: Method,
+    limit: &str,
+    cost: usize,
+    retry_at: Option<Instant>,
+) -> JsonRpcError {
+    counters::RATE_LIMITED_CALLS
+        .with_label_values(&[method.as_str(), limit])
+        .inc();
+
+    match retry_at {
+        Some(retry_at) => {
+            JsonRpcError::rate_limited(retry_at.saturating_duration_since(Instant::now()))
+        }
+        None => JsonRpcError::invalid_params_size(format!(
+            "the call takes {} tokens, more than the {} quota holds",
+            cost, limit
+        )),
+    }
+}
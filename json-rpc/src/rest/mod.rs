@@ -0,0 +1,548 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! REST endpoint
+//!
+//! A REST counterpart to the JSON RPC methods reading accounts, resources, transactions and events,
+//! and submitting and simulating transactions. Every endpoint is translated into the `MethodRequest` of the JSON
+//! RPC method it mirrors and handled by the same `Handler`, so both APIs share their validation and
+//! data access code.
+//!
+//! Responses are the JSON views of the JSON RPC methods by default. Clients sending
+//! `Accept: application/x-bcs` get the BCS bytes of the underlying on-chain types instead, and may
+//! submit BCS signed transactions with `Content-Type: application/x-bcs`. The OpenAPI spec of the
+//! endpoints is served at `/v1/rest/openapi.json`.
+//!
+//! Module organization:
+//! ├── mod.rs            # routes, content negotiation and handlers
+//! ├── openapi.rs        # OpenAPI spec generated from the endpoint table
+//! ├── tests.rs          # tests
+
+mod openapi;
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    auth::{authorize, X_DIEM_API_KEY},
+    counters, data,
+    errors::{is_internal_error, JsonRpcError},
+    methods::{Handler, JsonRpcService},
+    response::{X_DIEM_CHAIN_ID, X_DIEM_TIMESTAMP_USEC_ID, X_DIEM_VERSION_ID},
+};
+use diem_config::config::RestConfig;
+use diem_json_rpc_types::{
+    request::{
+        GetAccountParams, GetAccountTransactionsParams, GetEventsParams, GetMetadataParams,
+        GetResourcesParams, GetTransactionsParams, MethodRequest, SimulateTransactionParams,
+        SubmitParams,
+    },
+    Method,
+};
+use diem_types::{
+    account_address::AccountAddress,
+    event::EventKey,
+    ledger_info::LedgerInfoWithSignatures,
+    transaction::{SignedTransaction, TransactionListWithProof},
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use storage_interface::Order;
+use warp::{
+    filters::BoxedFilter,
+    http::{header, HeaderValue, StatusCode},
+    hyper::body::Bytes,
+    reply::Response,
+    Filter, Rejection, Reply,
+};
+
+pub const BCS_CONTENT_TYPE: &str = "application/x-bcs";
+
+/// Number of items returned by the paginated endpoints when no `limit` is given
+const DEFAULT_LIMIT: u64 = 25;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Encoding {
+    Json,
+    Bcs,
+}
+
+impl Encoding {
+    /// BCS is only used when the client explicitly accepts it
+    fn from_accept(accept: Option<String>) -> Self {
+        if accepts_bcs(accept.as_deref()) {
+            Encoding::Bcs
+        } else {
+            Encoding::Json
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Json => "json",
+            Encoding::Bcs => "bcs",
+        }
+    }
+}
+
+/// Whether the `Accept` header of a request lists the BCS content type
+pub(crate) fn accepts_bcs(accept: Option<&str>) -> bool {
+    accept.map_or(false, |accept| {
+        accept
+            .split(',')
+            .any(|media_type| media_type.trim().starts_with(BCS_CONTENT_TYPE))
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionQuery {
+    version: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SimulateQuery {
+    skip_signature_check: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PageQuery {
+    start: Option<u64>,
+    limit: Option<u64>,
+    include_events: Option<bool>,
+}
+
+impl PageQuery {
+    fn start(&self) -> u64 {
+        self.start.unwrap_or(0)
+    }
+
+    fn limit(&self) -> u64 {
+        self.limit.unwrap_or(DEFAULT_LIMIT)
+    }
+
+    fn include_events(&self) -> bool {
+        self.include_events.unwrap_or(false)
+    }
+}
+
+/// Gets the routes of all the REST endpoints, returning a 404 if the REST API is not enabled. The
+/// endpoints have the access control of the JSON RPC methods they mirror.
+pub(crate) fn get_rest_routes(
+    config: &RestConfig,
+    content_length_limit: u64,
+    service: JsonRpcService,
+) -> BoxedFilter<(impl Reply,)> {
+    let access_service = service.clone();
+    let access = move |methods: &'static [Method]| authorize(access_service.clone(), methods);
+    let service = warp::any().map(move || service.clone());
+    let encoding = warp::header::optional::<String>("accept").map(Encoding::from_accept);
+
+    let openapi = warp::path!("openapi.json")
+        .and(warp::get())
+        .map(|| warp::reply::json(&openapi::spec()));
+    let ledger = warp::path!("ledger")
+        .and(warp::get())
+        .and(access(&[Method::GetMetadata]))
+        .and(encoding.clone())
+        .and(service.clone())
+        .and_then(get_ledger);
+    let account = warp::path!("accounts" / AccountAddress)
+        .and(warp::get())
+        .and(access(&[Method::GetAccount]))
+        .and(warp::query::<VersionQuery>())
+        .and(encoding.clone())
+        .and(service.clone())
+        .and_then(get_account);
+    let resources = warp::path!("accounts" / AccountAddress / "resources")
+        .and(warp::get())
+        .and(access(&[Method::GetResources]))
+        .and(warp::query::<VersionQuery>())
+        .and(encoding.clone())
+        .and(service.clone())
+        .and_then(get_resources);
+    let account_transactions = warp::path!("accounts" / AccountAddress / "transactions")
+        .and(warp::get())
+        .and(access(&[Method::GetAccountTransactions]))
+        .and(warp::query::<PageQuery>())
+        .and(encoding.clone())
+        .and(service.clone())
+        .and_then(get_account_transactions);
+    let transactions = warp::path!("transactions")
+        .and(warp::get())
+        .and(access(&[Method::GetTransactions]))
+        .and(warp::query::<PageQuery>())
+        .and(encoding.clone())
+        .and(service.clone())
+        .and_then(get_transactions);
+    let events = warp::path!("events" / EventKey)
+        .and(warp::get())
+        .and(access(&[Method::GetEvents]))
+        .and(warp::query::<PageQuery>())
+        .and(encoding)
+        .and(service.clone())
+        .and_then(get_events);
+    let submit = warp::path!("transactions")
+        .and(warp::post())
+        .and(access(&[Method::Submit]))
+        .and(warp::header::optional::<String>("content-type"))
+        .and(warp::body::content_length_limit(content_length_limit))
+        .and(warp::body::bytes())
+        .and(service.clone())
+        .and_then(submit);
+    let simulate = warp::path!("transactions" / "simulate")
+        .and(warp::post())
+        .and(access(&[Method::SimulateTransaction]))
+        .and(warp::query::<SimulateQuery>())
+        .and(warp::header::optional::<String>("content-type"))
+        .and(warp::body::content_length_limit(content_length_limit))
+        .and(warp::body::bytes())
+        .and(service)
+        .and_then(simulate);
+
+    // If the REST API isn't enabled, return a 404
+    // We do this here because we can't build routes conditionally as if/else types won't match
+    let is_enabled = config.enabled;
+    warp::path!("v1" / "rest" / ..)
+        .and_then(move || {
+            futures::future::ready(if is_enabled {
+                Ok(())
+            } else {
+                Err(warp::reject::not_found())
+            })
+        })
+        .untuple_one()
+        .and(
+            openapi
+                .or(ledger)
+                .or(account)
+                .or(resources)
+                .or(account_transactions)
+                .or(transactions)
+                .or(events)
+                .or(submit)
+                .or(simulate),
+        )
+        .with(
+            warp::cors()
+                .allow_any_origin()
+                .allow_methods(vec!["GET", "POST"])
+                .allow_headers(vec![
+                    header::CONTENT_TYPE.as_str(),
+                    header::ACCEPT.as_str(),
+                    X_DIEM_API_KEY,
+                ]),
+        )
+        .boxed()
+}
+
+async fn get_ledger(encoding: Encoding, service: JsonRpcService) -> Result<Response, Rejection> {
+    let request = MethodRequest::GetMetadata(GetMetadataParams { version: None });
+    handle(service, encoding, request, |handler| {
+        Ok(Some(bcs::to_bytes(handler.ledger_info())?))
+    })
+    .await
+}
+
+async fn get_account(
+    account: AccountAddress,
+    query: VersionQuery,
+    encoding: Encoding,
+    service: JsonRpcService,
+) -> Result<Response, Rejection> {
+    let request = MethodRequest::GetAccount(GetAccountParams {
+        account,
+        version: query.version,
+    });
+    handle(service, encoding, request, |handler| {
+        let version = handler.version_param(query.version, "version")?;
+        let (blob, _proof) = handler
+            .service()
+            .db()
+            .get_account_state_with_proof_by_version(account, version)?;
+        Ok(blob.map(|blob| bcs::to_bytes(&blob)).transpose()?)
+    })
+    .await
+}
+
+async fn get_resources(
+    account: AccountAddress,
+    query: VersionQuery,
+    encoding: Encoding,
+    service: JsonRpcService,
+) -> Result<Response, Rejection> {
+    let request = MethodRequest::GetResources(GetResourcesParams {
+        account,
+        version: query.version,
+    });
+    handle(service, encoding, request, |handler| {
+        let version = handler.version_param(query.version, "version")?;
+        // like the JSON view, an account that doesn't exist has no resources
+        let account_state =
+            data::get_account_state(handler.service().db(), account, version)?.unwrap_or_default();
+        Ok(Some(bcs::to_bytes(&account_state)?))
+    })
+    .await
+}
+
+async fn get_account_transactions(
+    account: AccountAddress,
+    query: PageQuery,
+    encoding: Encoding,
+    service: JsonRpcService,
+) -> Result<Response, Rejection> {
+    let request = MethodRequest::GetAccountTransactions(GetAccountTransactionsParams {
+        account,
+        start: query.start(),
+        limit: query.limit(),
+        include_events: query.include_events(),
+    });
+    handle(service, encoding, request, |handler| {
+        handler
+            .service()
+            .validate_page_size_limit(query.limit() as usize)?;
+        let txns = handler.service().db().get_account_transactions(
+            account,
+            query.start(),
+            query.limit(),
+            query.include_events(),
+            handler.version(),
+        )?;
+        Ok(Some(bcs::to_bytes(&txns)?))
+    })
+    .await
+}
+
+async fn get_transactions(
+    query: PageQuery,
+    encoding: Encoding,
+    service: JsonRpcService,
+) -> Result<Response, Rejection> {
+    let request = MethodRequest::GetTransactions(GetTransactionsParams {
+        start_version: query.start(),
+        limit: query.limit(),
+        include_events: query.include_events(),
+        cursor: None,
+    });
+    handle(service, encoding, request, |handler| {
+        handler
+            .service()
+            .validate_page_size_limit(query.limit() as usize)?;
+        let ledger_version = handler.version();
+        let txns = if query.start() > ledger_version || query.limit() == 0 {
+            TransactionListWithProof::new_empty()
+        } else {
+            handler.service().db().get_transactions(
+                query.start(),
+                query.limit(),
+                ledger_version,
+                query.include_events(),
+            )?
+        };
+        Ok(Some(bcs::to_bytes(&txns)?))
+    })
+    .await
+}
+
+async fn get_events(
+    key: EventKey,
+    query: PageQuery,
+    encoding: Encoding,
+    service: JsonRpcService,
+) -> Result<Response, Rejection> {
+    let request = MethodRequest::GetEvents(GetEventsParams {
+        key,
+        start: query.start(),
+        limit: query.limit(),
+        cursor: None,
+    });
+    handle(service, encoding, request, |handler| {
+        handler
+            .service()
+            .validate_page_size_limit(query.limit() as usize)?;
+        let ledger_version = handler.version();
+        let events: Vec<_> = handler
+            .service()
+            .db()
+            .get_events(&key, query.start(), Order::Ascending, query.limit())?
+            .into_iter()
+            .filter(|(version, _event)| *version <= ledger_version)
+            .collect();
+        Ok(Some(bcs::to_bytes(&events)?))
+    })
+    .await
+}
+
+/// Parses a request body carrying a signed transaction: its BCS bytes, turned into the params of
+/// `method` by `from_bcs`, or the JSON params of `method`
+fn transaction_body<P: DeserializeOwned>(
+    content_type: Option<String>,
+    body: &Bytes,
+    method: &str,
+    from_bcs: impl FnOnce(SignedTransaction) -> P,
+) -> (Result<P, JsonRpcError>, Encoding) {
+    let is_bcs = content_type.map_or(false, |content_type| {
+        content_type.starts_with(BCS_CONTENT_TYPE)
+    });
+    if is_bcs {
+        let params = bcs::from_bytes::<SignedTransaction>(body)
+            .map(from_bcs)
+            .map_err(|_| JsonRpcError::invalid_format());
+        (params, Encoding::Bcs)
+    } else {
+        let params =
+            serde_json::from_slice::<P>(body).map_err(|_| JsonRpcError::invalid_params(method));
+        (params, Encoding::Json)
+    }
+}
+
+async fn submit(
+    content_type: Option<String>,
+    body: Bytes,
+    service: JsonRpcService,
+) -> Result<Response, Rejection> {
+    let (params, encoding) =
+        transaction_body(content_type, &body, "submit", |data| SubmitParams { data });
+
+    let ledger_info = match service.get_latest_ledger_info() {
+        Ok(ledger_info) => ledger_info,
+        Err(e) => return Ok(error_response(&e.into())),
+    };
+    let result = match params {
+        Ok(params) => {
+            Handler::new(&service, &ledger_info)
+                .handle(MethodRequest::Submit(params))
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    let response = match result {
+        Ok(_) => StatusCode::ACCEPTED.into_response(),
+        Err(e) => error_response(&e),
+    };
+    Ok(finish_response(
+        response,
+        "submit",
+        encoding,
+        &service,
+        &ledger_info,
+    ))
+}
+
+/// Simulates a transaction of the body, which doesn't need to be signed with
+/// `skip_signature_check`. The response is always JSON.
+async fn simulate(
+    query: SimulateQuery,
+    content_type: Option<String>,
+    body: Bytes,
+    service: JsonRpcService,
+) -> Result<Response, Rejection> {
+    let skip_signature_check = query.skip_signature_check.unwrap_or(false);
+    let (params, encoding) =
+        transaction_body(content_type, &body, "simulate_transaction", |data| {
+            SimulateTransactionParams {
+                data,
+                skip_signature_check,
+            }
+        });
+
+    let ledger_info = match service.get_latest_ledger_info() {
+        Ok(ledger_info) => ledger_info,
+        Err(e) => return Ok(error_response(&e.into())),
+    };
+    let result = match params {
+        Ok(mut params) => {
+            params.skip_signature_check |= skip_signature_check;
+            Handler::new(&service, &ledger_info)
+                .handle(MethodRequest::SimulateTransaction(params))
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    let response = match result {
+        Ok(value) => warp::reply::json(&value).into_response(),
+        Err(e) => error_response(&e),
+    };
+    Ok(finish_response(
+        response,
+        "simulate_transaction",
+        encoding,
+        &service,
+        &ledger_info,
+    ))
+}
+
+/// Handles `request` with the JSON RPC `Handler` for JSON responses, or with `fetch_bcs` for BCS
+/// ones. A `null` JSON result, or no BCS bytes, is reported as a 404.
+async fn handle(
+    service: JsonRpcService,
+    encoding: Encoding,
+    request: MethodRequest,
+    fetch_bcs: impl FnOnce(&Handler) -> Result<Option<Vec<u8>>, JsonRpcError> + Send,
+) -> Result<Response, Rejection> {
+    let method = request.method();
+    let ledger_info = match service.get_latest_ledger_info() {
+        Ok(ledger_info) => ledger_info,
+        Err(e) => return Ok(error_response(&e.into())),
+    };
+    let handler = Handler::new(&service, &ledger_info);
+
+    let response = match encoding {
+        Encoding::Json => match handler.handle(request).await {
+            Ok(Value::Null) => StatusCode::NOT_FOUND.into_response(),
+            Ok(value) => warp::reply::json(&value).into_response(),
+            Err(e) => error_response(&e),
+        },
+        Encoding::Bcs => match fetch_bcs(&handler) {
+            Ok(Some(bytes)) => {
+                warp::reply::with_header(bytes, header::CONTENT_TYPE, BCS_CONTENT_TYPE)
+                    .into_response()
+            }
+            Ok(None) => StatusCode::NOT_FOUND.into_response(),
+            Err(e) => error_response(&e),
+        },
+    };
+    Ok(finish_response(
+        response,
+        method.as_str(),
+        encoding,
+        &service,
+        &ledger_info,
+    ))
+}
+
+/// Errors of the server are 500s, all the others are caused by the request
+fn error_response(error: &JsonRpcError) -> Response {
+    let status = if is_internal_error(&error.code) {
+        StatusCode::INTERNAL_SERVER_ERROR
+    } else {
+        StatusCode::BAD_REQUEST
+    };
+    warp::reply::with_status(warp::reply::json(error), status).into_response()
+}
+
+/// Adds the same ledger headers as the JSON RPC responses, and counts the response
+fn finish_response(
+    mut response: Response,
+    method: &str,
+    encoding: Encoding,
+    service: &JsonRpcService,
+    ledger_info: &LedgerInfoWithSignatures,
+) -> Response {
+    counters::REST_REQUESTS
+        .with_label_values(&[method, encoding.as_str(), response.status().as_str()])
+        .inc();
+
+    let headers = response.headers_mut();
+    headers.insert(
+        X_DIEM_CHAIN_ID,
+        HeaderValue::from_str(&service.chain_id().id().to_string()).unwrap(),
+    );
+    headers.insert(
+        X_DIEM_VERSION_ID,
+        HeaderValue::from_str(&ledger_info.ledger_info().version().to_string()).unwrap(),
+    );
+    headers.insert(
+        X_DIEM_TIMESTAMP_USEC_ID,
+        HeaderValue::from_str(&ledger_info.ledger_info().timestamp_usecs().to_string()).unwrap(),
+    );
+    response
+}
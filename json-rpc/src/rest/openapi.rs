@@ -0,0 +1,267 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The OpenAPI spec of the REST endpoints, generated from `ENDPOINTS`. Every endpoint documents the
+//! JSON RPC method it mirrors, whose JSON result is the JSON response of the endpoint.
+
+use crate::rest::BCS_CONTENT_TYPE;
+use diem_json_rpc_types::Method;
+use serde_json::{json, Map, Value};
+
+pub(crate) struct Endpoint {
+    pub http_method: &'static str,
+    pub path: &'static str,
+    pub summary: &'static str,
+    pub method: Method,
+    pub params: &'static [Param],
+    /// The on-chain type of the BCS response, or of the BCS request body for submissions
+    pub bcs_type: &'static str,
+}
+
+pub(crate) struct Param {
+    pub name: &'static str,
+    pub location: &'static str,
+    pub schema_type: &'static str,
+    pub description: &'static str,
+}
+
+const ACCOUNT: Param = Param {
+    name: "address",
+    location: "path",
+    schema_type: "string",
+    description: "Hex-encoded account address",
+};
+const VERSION: Param = Param {
+    name: "version",
+    location: "query",
+    schema_type: "integer",
+    description: "The version to read at, defaults to the latest version",
+};
+const START: Param = Param {
+    name: "start",
+    location: "query",
+    schema_type: "integer",
+    description:
+        "The first version, sequence number or event sequence number to return, defaults to 0",
+};
+const LIMIT: Param = Param {
+    name: "limit",
+    location: "query",
+    schema_type: "integer",
+    description: "The maximum number of items to return, defaults to 25",
+};
+const INCLUDE_EVENTS: Param = Param {
+    name: "include_events",
+    location: "query",
+    schema_type: "boolean",
+    description: "Set to true to also fetch the events of the transactions, defaults to false",
+};
+const SKIP_SIGNATURE_CHECK: Param = Param {
+    name: "skip_signature_check",
+    location: "query",
+    schema_type: "boolean",
+    description: "Set to true to simulate a transaction that is not signed yet, defaults to false",
+};
+const EVENT_KEY: Param = Param {
+    name: "key",
+    location: "path",
+    schema_type: "string",
+    description: "Hex-encoded event key",
+};
+
+pub(crate) const ENDPOINTS: &[Endpoint] = &[
+    Endpoint {
+        http_method: "get",
+        path: "/v1/rest/ledger",
+        summary: "Get the metadata of the latest ledger version",
+        method: Method::GetMetadata,
+        params: &[],
+        bcs_type: "LedgerInfoWithSignatures",
+    },
+    Endpoint {
+        http_method: "get",
+        path: "/v1/rest/accounts/{address}",
+        summary: "Get an account",
+        method: Method::GetAccount,
+        params: &[ACCOUNT, VERSION],
+        bcs_type: "AccountStateBlob",
+    },
+    Endpoint {
+        http_method: "get",
+        path: "/v1/rest/accounts/{address}/resources",
+        summary: "Get the resources of an account",
+        method: Method::GetResources,
+        params: &[ACCOUNT, VERSION],
+        bcs_type: "AccountState",
+    },
+    Endpoint {
+        http_method: "get",
+        path: "/v1/rest/accounts/{address}/transactions",
+        summary: "Get the transactions sent by an account, by sequence number",
+        method: Method::GetAccountTransactions,
+        params: &[ACCOUNT, START, LIMIT, INCLUDE_EVENTS],
+        bcs_type: "AccountTransactionsWithProof",
+    },
+    Endpoint {
+        http_method: "get",
+        path: "/v1/rest/transactions",
+        summary: "Get the transactions of a range of versions",
+        method: Method::GetTransactions,
+        params: &[START, LIMIT, INCLUDE_EVENTS],
+        bcs_type: "TransactionListWithProof",
+    },
+    Endpoint {
+        http_method: "get",
+        path: "/v1/rest/events/{key}",
+        summary: "Get the events of an event key, by sequence number",
+        method: Method::GetEvents,
+        params: &[EVENT_KEY, START, LIMIT],
+        bcs_type: "Vec<(Version, ContractEvent)>",
+    },
+    Endpoint {
+        http_method: "post",
+        path: "/v1/rest/transactions",
+        summary: "Submit a signed transaction",
+        method: Method::Submit,
+        params: &[],
+        bcs_type: "SignedTransaction",
+    },
+    Endpoint {
+        http_method: "post",
+        path: "/v1/rest/transactions/simulate",
+        summary: "Simulate a transaction against the latest state without submitting it",
+        method: Method::SimulateTransaction,
+        params: &[SKIP_SIGNATURE_CHECK],
+        bcs_type: "SignedTransaction",
+    },
+];
+
+/// Generates the OpenAPI spec of `ENDPOINTS`
+pub(crate) fn spec() -> Value {
+    let mut paths = Map::new();
+    for endpoint in ENDPOINTS {
+        let operation = match endpoint.method {
+            Method::Submit => submit_operation(endpoint),
+            Method::SimulateTransaction => simulate_operation(endpoint),
+            _ => read_operation(endpoint),
+        };
+        paths
+            .entry(endpoint.path)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .expect("path item is an object")
+            .insert(endpoint.http_method.to_string(), operation);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Diem REST API",
+            "description": "REST counterpart to the Diem JSON-RPC API. This API is experimental and subject to change.",
+            "version": "1.0.0",
+        },
+        "paths": paths,
+    })
+}
+
+fn parameters(endpoint: &Endpoint) -> Vec<Value> {
+    endpoint
+        .params
+        .iter()
+        .map(|param| {
+            json!({
+                "name": param.name,
+                "in": param.location,
+                "required": param.location == "path",
+                "description": param.description,
+                "schema": { "type": param.schema_type },
+            })
+        })
+        .collect()
+}
+
+fn read_operation(endpoint: &Endpoint) -> Value {
+    json!({
+        "operationId": endpoint.method.as_str(),
+        "summary": endpoint.summary,
+        "description": format!(
+            "The JSON response is the result of the JSON-RPC method `{}`. The BCS response is a BCS-encoded `{}`.",
+            endpoint.method.as_str(),
+            endpoint.bcs_type,
+        ),
+        "parameters": parameters(endpoint),
+        "responses": {
+            "200": {
+                "description": "Success",
+                "content": {
+                    "application/json": { "schema": { "type": "object" } },
+                    BCS_CONTENT_TYPE: { "schema": { "type": "string", "format": "binary" } },
+                },
+            },
+            "400": { "description": "Invalid request" },
+            "404": { "description": "Not found" },
+            "500": { "description": "Server error" },
+        },
+    })
+}
+
+/// The request body of the endpoints taking a signed transaction
+fn transaction_request_body() -> Value {
+    json!({
+        "required": true,
+        "content": {
+            "application/json": {
+                "schema": {
+                    "type": "object",
+                    "properties": { "data": { "type": "string" } },
+                    "required": ["data"],
+                },
+            },
+            BCS_CONTENT_TYPE: { "schema": { "type": "string", "format": "binary" } },
+        },
+    })
+}
+
+fn transaction_body_description(endpoint: &Endpoint) -> String {
+    format!(
+        "The JSON request body is the params of the JSON-RPC method `{}`. The BCS request body is a BCS-encoded `{}`.",
+        endpoint.method.as_str(),
+        endpoint.bcs_type,
+    )
+}
+
+fn simulate_operation(endpoint: &Endpoint) -> Value {
+    json!({
+        "operationId": endpoint.method.as_str(),
+        "summary": endpoint.summary,
+        "description": format!(
+            "{} The JSON response is the result of the JSON-RPC method `{}`.",
+            transaction_body_description(endpoint),
+            endpoint.method.as_str(),
+        ),
+        "parameters": parameters(endpoint),
+        "requestBody": transaction_request_body(),
+        "responses": {
+            "200": {
+                "description": "Success",
+                "content": { "application/json": { "schema": { "type": "object" } } },
+            },
+            "400": { "description": "Invalid or discarded transaction" },
+            "500": { "description": "Server error" },
+        },
+    })
+}
+
+fn submit_operation(endpoint: &Endpoint) -> Value {
+    json!({
+        "operationId": endpoint.method.as_str(),
+        "summary": endpoint.summary,
+        "description": transaction_body_description(endpoint),
+        "requestBody": transaction_request_body(),
+        "responses": {
+            "202": { "description": "The transaction is accepted by mempool" },
+            "400": { "description": "Invalid transaction" },
+            "500": { "description": "Server error" },
+        },
+    })
+}
@@ -0,0 +1,249 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    response::X_DIEM_VERSION_ID,
+    rest::{openapi::ENDPOINTS, BCS_CONTENT_TYPE},
+    tests::utils::{create_db_and_runtime, mock_db, test_bootstrap_with_access_control},
+    views::{AccountView, TransactionListView},
+};
+use diem_config::{
+    config::{ApiKeyAccess, ApiKeyConfig, CallQuota, JsonRpcRateLimitConfig, PersistableConfig},
+    utils,
+};
+use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+use diem_mempool::MempoolClientRequest;
+use diem_types::{
+    account_address::AccountAddress,
+    account_state_blob::AccountStateBlob,
+    mempool_status::{MempoolStatus, MempoolStatusCode},
+    test_helpers::transaction_test_helpers::get_test_signed_txn,
+    transaction::TransactionListWithProof,
+};
+use futures::{channel::mpsc::channel, StreamExt};
+use reqwest::{blocking::Client, header, StatusCode};
+use std::{collections::BTreeMap, sync::Arc};
+
+fn get(client: &Client, url: &str, bcs: bool) -> reqwest::blocking::Response {
+    let request = client.get(url);
+    let request = if bcs {
+        request.header(header::ACCEPT, BCS_CONTENT_TYPE)
+    } else {
+        request
+    };
+    request.send().unwrap()
+}
+
+#[test]
+fn test_get_account() {
+    let (mock_db, _runtime, url, _) = create_db_and_runtime();
+    let client = Client::new();
+
+    let (address, blob) = mock_db.all_accounts.iter().next().unwrap();
+    let account_url = format!("{}/v1/rest/accounts/{}", url, address);
+
+    let resp = get(&client, &account_url, false);
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers()[X_DIEM_VERSION_ID],
+        mock_db.version.to_string().as_str()
+    );
+    let account: AccountView = resp.json().unwrap();
+    assert_eq!(account.address, *address);
+
+    let resp = get(&client, &account_url, true);
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()[header::CONTENT_TYPE], BCS_CONTENT_TYPE);
+    let received_blob: AccountStateBlob = bcs::from_bytes(&resp.bytes().unwrap()).unwrap();
+    assert_eq!(received_blob, *blob);
+
+    // an account that doesn't exist
+    let missing_url = format!(
+        "{}/v1/rest/accounts/{}",
+        url,
+        AccountAddress::new([7; AccountAddress::LENGTH])
+    );
+    assert_eq!(
+        get(&client, &missing_url, false).status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        get(&client, &missing_url, true).status(),
+        StatusCode::NOT_FOUND
+    );
+
+    // a version beyond the ledger
+    let future_url = format!("{}?version={}", account_url, mock_db.version + 1);
+    assert_eq!(
+        get(&client, &future_url, false).status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        get(&client, &future_url, true).status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[test]
+fn test_get_transactions() {
+    let (mock_db, _runtime, url, _) = create_db_and_runtime();
+    let client = Client::new();
+
+    let transactions_url = format!("{}/v1/rest/transactions?start=1&limit=10", url);
+
+    let resp = get(&client, &transactions_url, false);
+    assert_eq!(resp.status(), StatusCode::OK);
+    let txns: TransactionListView = resp.json().unwrap();
+    assert_eq!(txns.0.len(), 10);
+    assert_eq!(txns.0[0].version, 1);
+
+    let resp = get(&client, &transactions_url, true);
+    assert_eq!(resp.status(), StatusCode::OK);
+    let txns: TransactionListWithProof = bcs::from_bytes(&resp.bytes().unwrap()).unwrap();
+    assert_eq!(txns.first_transaction_version, Some(1));
+    assert_eq!(txns.transactions.len(), 10);
+    for (txn, (expected_txn, _)) in txns.transactions.iter().zip(&mock_db.all_txns[1..]) {
+        assert_eq!(txn, expected_txn);
+    }
+
+    // the page size limit of the JSON-RPC service applies
+    let too_large_url = format!("{}/v1/rest/transactions?limit=1001", url);
+    assert_eq!(
+        get(&client, &too_large_url, false).status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        get(&client, &too_large_url, true).status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[test]
+fn test_submit() {
+    let (_mock_db, runtime, url, mut mp_events) = create_db_and_runtime();
+    let client = Client::new();
+
+    // future that mocks shared mempool, accepting every transaction
+    runtime.spawn(async move {
+        while let Some(MempoolClientRequest::SubmitTransaction(_txn, cb)) = mp_events.next().await {
+            cb.send(Ok((MempoolStatus::new(MempoolStatusCode::Accepted), None)))
+                .unwrap();
+        }
+    });
+
+    let sender = AccountAddress::new([9; AccountAddress::LENGTH]);
+    let privkey = Ed25519PrivateKey::generate_for_testing();
+    let txn = get_test_signed_txn(sender, 0, &privkey, privkey.public_key(), None);
+    let submit_url = format!("{}/v1/rest/transactions", url);
+
+    let resp = client
+        .post(&submit_url)
+        .header(header::CONTENT_TYPE, BCS_CONTENT_TYPE)
+        .body(bcs::to_bytes(&txn).unwrap())
+        .send()
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+
+    let resp = client
+        .post(&submit_url)
+        .json(&serde_json::json!({ "data": hex::encode(bcs::to_bytes(&txn).unwrap()) }))
+        .send()
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+
+    let resp = client
+        .post(&submit_url)
+        .header(header::CONTENT_TYPE, BCS_CONTENT_TYPE)
+        .body(vec![1, 2, 3])
+        .send()
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn test_openapi_spec() {
+    let (_mock_db, _runtime, url, _) = create_db_and_runtime();
+    let client = Client::new();
+
+    let resp = get(&client, &format!("{}/v1/rest/openapi.json", url), false);
+    assert_eq!(resp.status(), StatusCode::OK);
+    let spec: serde_json::Value = resp.json().unwrap();
+
+    for endpoint in ENDPOINTS {
+        let operation = &spec["paths"][endpoint.path][endpoint.http_method];
+        assert_eq!(operation["operationId"], endpoint.method.as_str());
+    }
+}
+
+#[test]
+fn test_api_key() {
+    let (mp_sender, _mp_events) = channel(1);
+    let port = utils::get_available_port();
+    let keys_file = diem_temppath::TempPath::new();
+    keys_file.create_as_file().unwrap();
+
+    let mut keys = BTreeMap::new();
+    keys.insert(
+        "partner".to_string(),
+        ApiKeyAccess {
+            allowed_methods: None,
+            quota: Some(CallQuota { rate: 2, size: 2 }),
+        },
+    );
+    keys.save_config(keys_file.path()).unwrap();
+
+    let api_key_config = ApiKeyConfig {
+        enabled: true,
+        keys_file: Some(keys_file.path().to_path_buf()),
+        reload_interval_ms: 0,
+        public_methods: Some(vec!["get_metadata".to_string()]),
+    };
+    let mock_db = mock_db();
+    let _runtime = test_bootstrap_with_access_control(
+        format!("127.0.0.1:{}", port).parse().unwrap(),
+        Arc::new(mock_db.clone()),
+        mp_sender,
+        &JsonRpcRateLimitConfig::default(),
+        &api_key_config,
+    );
+    let client = Client::new();
+    let url = format!("http://127.0.0.1:{}", port);
+    let (address, _blob) = mock_db.all_accounts.iter().next().unwrap();
+    let account_url = format!("{}/v1/rest/accounts/{}", url, address);
+    let send = |url: &str, api_key: Option<&str>| {
+        let request = client.get(url);
+        let request = match api_key {
+            Some(api_key) => request.header("X-Diem-Api-Key", api_key),
+            None => request,
+        };
+        request.send().unwrap().status()
+    };
+
+    // public calls are limited to the public methods
+    assert_eq!(
+        send(&format!("{}/v1/rest/ledger", url), None),
+        StatusCode::OK
+    );
+    assert_eq!(send(&account_url, None), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        send(&format!("{}/v1/rest/transactions", url), None),
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        send(&account_url, Some("unknown")),
+        StatusCode::UNAUTHORIZED
+    );
+    // and so are the streams
+    assert_eq!(
+        send(&format!("{}/v1/stream/ws", url), None),
+        StatusCode::UNAUTHORIZED
+    );
+
+    // the partner key may call any endpoint, within its quota
+    assert_eq!(send(&account_url, Some("partner")), StatusCode::OK);
+    assert_eq!(send(&account_url, Some("partner")), StatusCode::OK);
+    assert_eq!(
+        send(&account_url, Some("partner")),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
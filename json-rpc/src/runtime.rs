@@ -5,15 +5,18 @@
 #![allow(clippy::needless_borrow)]
 
 use crate::{
+    auth::{authorize, recover_access_denied, Authenticator, Caller, X_DIEM_API_KEY},
     counters,
     errors::is_internal_error,
+    health,
     methods::{Handler, JsonRpcService},
+    rate_limit::{call_cost, RateLimiter, PRUNE_INTERVAL, X_FORWARDED_FOR},
     response::{JsonRpcResponse, X_DIEM_CHAIN_ID, X_DIEM_TIMESTAMP_USEC_ID, X_DIEM_VERSION_ID},
-    stream_rpc,
+    rest, stream_rpc,
     util::{sdk_info_from_user_agent, SdkInfo},
 };
 use anyhow::{ensure, Result};
-use diem_config::config::{NodeConfig, RoleType, StreamConfig};
+use diem_config::config::{ApiKeyConfig, NodeConfig, RestConfig, RoleType, StreamConfig};
 use diem_json_rpc_types::Method;
 use diem_logger::{debug, Schema};
 use diem_mempool::MempoolClientSender;
@@ -22,7 +25,7 @@ use futures::future::{join_all, Either};
 use rand::{rngs::OsRng, RngCore};
 use serde_json::Value;
 use std::{
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
     ops::Sub,
     sync::Arc,
     time::{Duration, SystemTime, UNIX_EPOCH},
@@ -110,6 +113,9 @@ pub fn bootstrap(
     role: RoleType,
     chain_id: ChainId,
     stream_config: &StreamConfig,
+    rest_config: &RestConfig,
+    rate_limiter: Arc<RateLimiter>,
+    api_key_config: &ApiKeyConfig,
 ) -> Runtime {
     let runtime = Builder::new_multi_thread()
         .thread_name("json-rpc")
@@ -117,15 +123,30 @@ pub fn bootstrap(
         .build()
         .expect("[json-rpc] failed to create runtime");
 
+    let authenticator = Arc::new(Authenticator::new(api_key_config));
+    if api_key_config.enabled && api_key_config.reload_interval_ms > 0 {
+        runtime.spawn(
+            authenticator
+                .clone()
+                .reload_periodically(Duration::from_millis(api_key_config.reload_interval_ms)),
+        );
+    }
+
+    // the limits may be enabled by a reload of the config
+    runtime.spawn(rate_limiter.clone().prune_periodically(PRUNE_INTERVAL));
+
     let service = JsonRpcService::new(
         diem_db.clone(),
-        mp_sender,
+        mp_sender.clone(),
         role,
         chain_id,
         batch_size_limit,
         page_size_limit,
+        rate_limiter,
+        authenticator,
     );
 
+    let rest_service = service.clone();
     let base_route = warp::any()
         .and(warp::post())
         .and(warp::header::exact("content-type", "application/json"))
@@ -133,6 +154,10 @@ pub fn bootstrap(
         .and(warp::body::json())
         .and(warp::any().map(move || service.clone()))
         .and(warp::filters::header::optional::<String>("user-agent"))
+        .and(warp::addr::remote())
+        .and(warp::filters::header::optional::<String>(X_FORWARDED_FOR))
+        .and(warp::filters::header::optional::<String>(X_DIEM_API_KEY))
+        .and(warp::filters::header::optional::<String>("accept"))
         .and_then(rpc_endpoint)
         .with(warp::log::custom(|info| {
             debug!(HttpRequestLog {
@@ -157,7 +182,7 @@ pub fn bootstrap(
             warp::cors()
                 .allow_any_origin()
                 .allow_methods(vec!["POST"])
-                .allow_headers(vec![header::CONTENT_TYPE]),
+                .allow_headers(vec![header::CONTENT_TYPE.as_str(), X_DIEM_API_KEY]),
         );
 
     // For now we still allow user to use "/", but user should start to move to "/v1" soon
@@ -185,14 +210,26 @@ pub fn bootstrap(
 
     let _guard = runtime.enter();
 
-    let full_route =
-        health_route
-            .or(route_v1.or(route_root))
-            .or(stream_rpc::startup::get_stream_routes(
-                stream_config,
-                content_len_limit as u64,
-                diem_db,
-            ));
+    let full_route = health_route
+        .or(health::get_health_routes(diem_db.clone(), chain_id))
+        .or(route_v1.or(route_root))
+        .or(stream_rpc::startup::get_stream_routes(
+            stream_config,
+            content_len_limit as u64,
+            diem_db,
+            mp_sender,
+            // subscriptions stream the data of these methods
+            authorize(
+                rest_service.clone(),
+                &[Method::GetTransactions, Method::GetEvents],
+            ),
+        ))
+        .or(rest::get_rest_routes(
+            rest_config,
+            content_len_limit as u64,
+            rest_service,
+        ))
+        .recover(recover_access_denied);
 
     let server = match tls_cert_path {
         None => Either::Left(warp::serve(full_route).bind(address)),
@@ -208,12 +245,13 @@ pub fn bootstrap(
     runtime
 }
 
-/// Creates JSON RPC endpoint by given node config
+/// Creates JSON RPC endpoint by given node config, limiting the calls by `rate_limiter`
 pub fn bootstrap_from_config(
     config: &NodeConfig,
     chain_id: ChainId,
     diem_db: Arc<dyn MoveDbReader>,
     mp_sender: MempoolClientSender,
+    rate_limiter: Arc<RateLimiter>,
 ) -> Runtime {
     bootstrap(
         config.json_rpc.address,
@@ -227,6 +265,9 @@ pub fn bootstrap_from_config(
         config.base.role,
         chain_id,
         &config.json_rpc.stream_rpc,
+        &config.json_rpc.rest,
+        rate_limiter,
+        &config.json_rpc.api_key,
     )
 }
 
@@ -266,6 +307,10 @@ pub(crate) async fn rpc_endpoint(
     data: Value,
     service: JsonRpcService,
     user_agent: Option<String>,
+    remote_addr: Option<SocketAddr>,
+    forwarded_for: Option<String>,
+    api_key: Option<String>,
+    accept: Option<String>,
 ) -> Result<warp::reply::Response, warp::Rejection> {
     let label = match data {
         Value::Array(_) => LABEL_BATCH,
@@ -275,7 +320,16 @@ pub(crate) async fn rpc_endpoint(
     let timer = counters::RPC_REQUEST_LATENCY
         .with_label_values(&[label])
         .start_timer();
-    let ret = rpc_endpoint_without_metrics(data, service, user_agent.as_deref()).await;
+    let ret = rpc_endpoint_without_metrics(
+        data,
+        service,
+        user_agent.as_deref(),
+        remote_addr.map(|addr| addr.ip()),
+        forwarded_for.as_deref(),
+        api_key.as_deref(),
+        accept.as_deref(),
+    )
+    .await;
     timer.stop_and_record();
     ret
 }
@@ -284,6 +338,10 @@ async fn rpc_endpoint_without_metrics(
     data: Value,
     service: JsonRpcService,
     user_agent: Option<&str>,
+    remote_ip: Option<IpAddr>,
+    forwarded_for: Option<&str>,
+    api_key: Option<&str>,
+    accept: Option<&str>,
 ) -> Result<warp::reply::Response, warp::Rejection> {
     // take snapshot of latest version of DB to be used across all requests, especially for batched requests
     let ledger_info = service
@@ -300,19 +358,33 @@ async fn rpc_endpoint_without_metrics(
     let latest_ledger_version = ledger_info.ledger_info().version();
     let latest_ledger_timestamp_usecs = ledger_info.ledger_info().timestamp_usecs();
     let sdk_info = sdk_info_from_user_agent(user_agent);
+    let caller = service.caller(api_key, remote_ip, forwarded_for);
 
-    let resp = if let Value::Array(requests) = data {
+    let mut http_response = if let Value::Array(requests) = data {
         match service.validate_batch_size_limit(requests.len()) {
             Ok(_) => {
                 // batch API call
+                // batch responses are always JSON
                 let futures = requests.into_iter().map(|req| {
-                    rpc_request_handler(req, &service, &ledger_info, LABEL_BATCH, sdk_info)
+                    rpc_request_handler(
+                        req,
+                        &service,
+                        &ledger_info,
+                        LABEL_BATCH,
+                        sdk_info,
+                        &caller,
+                        false,
+                    )
                 });
-                let responses = join_all(futures).await;
+                let responses: Vec<_> = join_all(futures)
+                    .await
+                    .into_iter()
+                    .map(|(resp, _)| resp)
+                    .collect();
                 for resp in &responses {
                     log_response!(&trace_id, resp, true);
                 }
-                warp::reply::json(&responses)
+                warp::reply::json(&responses).into_response()
             }
             Err(err) => {
                 let mut response = JsonRpcResponse::new(
@@ -324,18 +396,31 @@ async fn rpc_endpoint_without_metrics(
                 bump_counters(&response, LABEL_BATCH, None, sdk_info);
                 log_response!(&trace_id, &response, true);
 
-                warp::reply::json(&response)
+                warp::reply::json(&response).into_response()
             }
         }
     } else {
         // single API call
-        let resp = rpc_request_handler(data, &service, &ledger_info, LABEL_SINGLE, sdk_info).await;
+        let (resp, bcs_result) = rpc_request_handler(
+            data,
+            &service,
+            &ledger_info,
+            LABEL_SINGLE,
+            sdk_info,
+            &caller,
+            rest::accepts_bcs(accept),
+        )
+        .await;
         log_response!(&trace_id, &resp, false);
 
-        warp::reply::json(&resp)
+        match bcs_result {
+            Some(bytes) => {
+                warp::reply::with_header(bytes, header::CONTENT_TYPE, rest::BCS_CONTENT_TYPE)
+                    .into_response()
+            }
+            None => warp::reply::json(&resp).into_response(),
+        }
     };
-
-    let mut http_response = resp.into_response();
     let headers = http_response.headers_mut();
 
     headers.insert(
@@ -354,13 +439,17 @@ async fn rpc_endpoint_without_metrics(
     Ok(http_response)
 }
 
+/// Handles a single call. When `bcs` is set and the call succeeds, its BCS result is returned
+/// alongside a response without result, see `Handler::handle_bcs`.
 async fn rpc_request_handler(
     request: Value,
     service: &JsonRpcService,
     ledger_info: &LedgerInfoWithSignatures,
     request_type_label: &str,
     sdk_info: SdkInfo,
-) -> JsonRpcResponse {
+    caller: &Caller,
+    bcs: bool,
+) -> (JsonRpcResponse, Option<Vec<u8>>) {
     let handler = Handler::new(service, ledger_info);
 
     let mut response = JsonRpcResponse::new(
@@ -369,6 +458,7 @@ async fn rpc_request_handler(
         ledger_info.ledger_info().timestamp_usecs(),
     );
     let method: Option<Method>;
+    let mut bcs_result = None;
 
     match diem_json_rpc_types::request::JsonRpcRequest::from_value(request) {
         Ok(request) => {
@@ -377,9 +467,23 @@ async fn rpc_request_handler(
                 .with_label_values(&[request_type_label, request.method_request.method().as_str()])
                 .start_timer();
             response.id = Some(serde_json::to_value(&request.id).unwrap());
-            match handler.handle(request.method_request).await {
-                Ok(ret) => response.result = Some(ret),
-                Err(e) => response.error = Some(e),
+            let cost = call_cost(&request.method_request);
+            let result = match service.authorize(caller, request.method_request.method(), cost) {
+                Ok(()) if bcs => handler
+                    .handle_bcs(request.method_request)
+                    .await
+                    .map(|bytes| bcs_result = Some(bytes)),
+                Ok(()) => handler
+                    .handle_with_cursor(request.method_request)
+                    .await
+                    .map(|(ret, next_cursor)| {
+                        response.result = Some(ret);
+                        response.next_cursor = next_cursor;
+                    }),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                response.error = Some(e);
             }
             timer.stop_and_record();
         }
@@ -392,7 +496,7 @@ async fn rpc_request_handler(
 
     bump_counters(&response, request_type_label, method, sdk_info);
 
-    response
+    (response, bcs_result)
 }
 
 fn bump_counters(
@@ -419,6 +523,8 @@ fn bump_counters(
                 -32601 => "method_not_found",
                 -32602 => "invalid_params",
                 -32604 => "invalid_format",
+                -32014 => "rate_limited",
+                -32015 => "unauthorized",
                 _ => "unexpected_code",
             };
             counters::INVALID_REQUESTS
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    errors::JsonRpcError,
+    errors::{ErrorReason, JsonRpcError},
     stream_rpc::{
         connection::{ConnectionContext, StreamSender},
         counters,
@@ -21,6 +21,7 @@ use diem_json_rpc_types::{
     Id,
 };
 use diem_logger::debug;
+use diem_mempool::MempoolClientSender;
 use std::{collections::HashMap, str::FromStr, sync::Arc};
 use storage_interface::MoveDbReader;
 
@@ -149,7 +150,12 @@ impl ClientConnection {
         }
     }
 
-    pub async fn received_message(&self, db: Arc<dyn MoveDbReader>, message: String) {
+    pub async fn received_message(
+        &self,
+        db: Arc<dyn MoveDbReader>,
+        mp_sender: MempoolClientSender,
+        message: String,
+    ) {
         match StreamJsonRpcRequest::from_str(&message) {
             Ok(mut request) => {
                 debug!(
@@ -167,7 +173,7 @@ impl ClientConnection {
                     },
                     "subscription request"
                 );
-                if let Err(err) = self.handle_rpc_request(db, &mut request) {
+                if let Err(err) = self.handle_rpc_request(db, mp_sender, &mut request) {
                     self.send_error(Some(request.method_request.method()), Some(request.id), err)
                         .await
                         .ok();
@@ -217,6 +223,7 @@ impl ClientConnection {
     fn handle_rpc_request(
         &self,
         db: Arc<dyn MoveDbReader>,
+        mp_sender: MempoolClientSender,
         request: &mut StreamJsonRpcRequest,
     ) -> Result<(), JsonRpcError> {
         // No task needs to spawn for an unsubscribe
@@ -231,16 +238,17 @@ impl ClientConnection {
                 "Client#{} already has a subscription for '{}'",
                 self.id, &request.id
             );
-            let err = JsonRpcError::invalid_request_with_msg(format!(
-                "Subscription for '{}' already exists",
-                &request.id
-            ));
+            let err = JsonRpcError::invalid_request_with_msg(
+                ErrorReason::SubscriptionExists,
+                format!("Subscription for '{}' already exists", &request.id),
+            );
 
             return Err(err);
         }
 
         match CallableStreamMethod(request.method_request).call_method(
-            db.clone(),
+            db,
+            mp_sender,
             self.clone(),
             request.id.clone(),
         ) {
@@ -285,7 +293,7 @@ fn metric_subscription_rpc_received(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::stream_rpc::tests::util::{create_client_connection, timeout};
+    use crate::stream_rpc::tests::util::{create_client_connection, mock_mempool, timeout};
 
     #[tokio::test]
     async fn test_send_raw() {
@@ -313,7 +321,7 @@ mod tests {
         })
         .to_string();
         client_connection
-            .received_message(Arc::new(mock_db.clone()), request)
+            .received_message(Arc::new(mock_db.clone()), mock_mempool(), request)
             .await;
 
         let result = timeout(50, receiver.recv(), "message 1")
@@ -361,7 +369,7 @@ mod tests {
         })
         .to_string();
         client_connection
-            .received_message(Arc::new(mock_db.clone()), request)
+            .received_message(Arc::new(mock_db.clone()), mock_mempool(), request)
             .await;
 
         let result = timeout(50, receiver.recv(), "message 1")
@@ -388,7 +396,7 @@ mod tests {
         .to_string();
 
         client_connection
-            .received_message(Arc::new(mock_db.clone()), request)
+            .received_message(Arc::new(mock_db.clone()), mock_mempool(), request)
             .await;
 
         let result = timeout(50, receiver.recv(), "message 1")
@@ -413,7 +421,7 @@ mod tests {
 
         let request = "{\"bad_json".to_string();
         client_connection
-            .received_message(Arc::new(mock_db.clone()), request)
+            .received_message(Arc::new(mock_db.clone()), mock_mempool(), request)
             .await;
 
         let result = timeout(50, receiver.recv(), "message 1")
@@ -9,9 +9,10 @@ use tokio::task::JoinHandle;
 use crate::stream_rpc::{
     connection::ClientConnection,
     subscription_types::{Subscription, SubscriptionHelper},
-    subscriptions::{EventsSubscription, TransactionsSubscription},
+    subscriptions::{EventsSubscription, TransactionStatusSubscription, TransactionsSubscription},
 };
 use diem_json_rpc_types::{stream::request::StreamMethodRequest, Id};
+use diem_mempool::MempoolClientSender;
 
 pub struct CallableStreamMethod(pub StreamMethodRequest);
 
@@ -19,6 +20,7 @@ impl CallableStreamMethod {
     pub fn call_method(
         self,
         db: Arc<dyn MoveDbReader>,
+        mp_sender: MempoolClientSender,
         client: ClientConnection,
         jsonrpc_id: Id,
     ) -> Result<JoinHandle<()>, JsonRpcError> {
@@ -31,6 +33,9 @@ impl CallableStreamMethod {
             StreamMethodRequest::SubscribeToEvents(params) => {
                 EventsSubscription::default().run(helper, params)
             }
+            StreamMethodRequest::SubscribeToTransactionStatus(params) => {
+                TransactionStatusSubscription::run(helper, mp_sender, params)
+            }
             // This is handled in the `handle_rpc_request` function, as we don't spawn a task
             StreamMethodRequest::Unsubscribe => unreachable!(),
         }
@@ -3,6 +3,7 @@
 
 use crate::stream_rpc::transport::websocket::get_websocket_routes;
 use diem_config::config::StreamConfig;
+use diem_mempool::MempoolClientSender;
 use std::sync::Arc;
 use storage_interface::MoveDbReader;
 use warp::{filters::BoxedFilter, Filter, Reply};
@@ -10,12 +11,23 @@ use warp::{filters::BoxedFilter, Filter, Reply};
 /// Gets all routes for all streaming endpoints
 /// Each transport is responsible for handling it's own versioning and endpoints
 /// This function also handles disabling/enabling streaming- returning a 404 if not enabled
+/// Only the requests passing `access` may open a stream
 pub fn get_stream_routes(
     config: &StreamConfig,
     content_length_limit: u64,
     diem_db: Arc<dyn MoveDbReader>,
+    mp_sender: MempoolClientSender,
+    access: BoxedFilter<()>,
 ) -> BoxedFilter<(impl Reply,)> {
-    let wss_routes = get_websocket_routes(config, content_length_limit, diem_db.clone(), None).0;
+    let wss_routes = get_websocket_routes(
+        config,
+        content_length_limit,
+        diem_db,
+        mp_sender,
+        None,
+        access,
+    )
+    .0;
 
     // If streaming rpc isn't enabled, return a 404
     // We do this here because we can't build routes conditionally as if/else types won't match
@@ -18,8 +18,9 @@
 //!       returned. Otherwise if the parameters are valid and a subscription could be created, simply
 //!       return `Ok(())`
 //!
-//! 2. `next(&self, helper: &SubscriptionHelper, params: &ParamType) -> Vec<ParamType>;`
+//! 2. `next(&mut self, helper: &SubscriptionHelper, params: &ParamType) -> Vec<ParamType>;`
 //!     Within this method, use existing state set up in the `init` function to fetch the next batch of data.
+//!     State may be updated here when some of the fetched data is not to be sent, e.g. filtered out.
 //!     The size of the batch should be determined by `helper.client.config.fetch_size`.
 //!     If the function returns an array of data, each item will be serialized one by one and sent to the client.
 //!     The `on_send` method (below) will be called for each item in the array.
@@ -142,7 +143,7 @@ where
 {
     fn init(&mut self, helper: &SubscriptionHelper, params: &ParamType)
         -> Result<(), JsonRpcError>;
-    fn next(&self, helper: &SubscriptionHelper, params: &ParamType) -> Vec<ReturnType>;
+    fn next(&mut self, helper: &SubscriptionHelper, params: &ParamType) -> Vec<ReturnType>;
     fn on_send(&mut self, item: Option<&ReturnType>);
 
     fn run(
@@ -211,6 +212,7 @@ where
 mod tests {
     use super::*;
     use crate::{
+        errors::ErrorReason,
         stream_rpc::{
             errors::StreamError,
             tests::util::{create_client_connection, timeout},
@@ -247,12 +249,16 @@ mod tests {
             if params.is_valid {
                 Ok(())
             } else {
-                Err(JsonRpcError::invalid_param("not is_valid"))
+                Err(JsonRpcError::invalid_param(
+                    "is_valid",
+                    ErrorReason::InvalidValue,
+                    "should be true",
+                ))
             }
         }
 
         fn next(
-            &self,
+            &mut self,
             _helper: &SubscriptionHelper,
             params: &SubscribeTestParams,
         ) -> Vec<TestView> {
@@ -376,7 +382,7 @@ mod tests {
         let handle = TestSubscription::default().run(subscription_helper, params);
 
         assert!(handle.is_err());
-        let expected = serde_json::json!({"code":-32602, "message": "Invalid param not is_valid", "data": null}).to_string();
+        let expected = serde_json::json!({"code":-32602, "message": "Invalid param is_valid should be true", "data": {"InvalidParam": {"param": "is_valid", "reason": "invalid_value"}}}).to_string();
         assert_eq!(
             expected,
             serde_json::to_string(&handle.err().unwrap()).unwrap()
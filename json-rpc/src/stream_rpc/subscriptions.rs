@@ -4,14 +4,20 @@
 use crate::{
     data::{get_events, get_transactions},
     errors::JsonRpcError,
-    stream_rpc::subscription_types::{Subscription, SubscriptionHelper},
-    views::{EventView, TransactionView},
+    stream_rpc::{
+        counters,
+        subscription_types::{Subscription, SubscriptionHelper},
+    },
+    views::{EventView, TransactionStageView, TransactionStatusView, TransactionView},
 };
 use diem_json_rpc_types::stream::request::{
-    SubscribeToEventsParams, SubscribeToTransactionsParams,
+    SubscribeToEventsParams, SubscribeToTransactionStatusParams, SubscribeToTransactionsParams,
 };
-use diem_logger::warn;
+use diem_logger::{debug, warn};
+use diem_mempool::{MempoolClientRequest, MempoolClientSender, TransactionStatus};
+use futures::{channel::oneshot, StreamExt};
 use std::borrow::Borrow;
+use tokio::task::JoinHandle;
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct TransactionsSubscription {
@@ -29,7 +35,7 @@ impl Subscription<SubscribeToTransactionsParams, TransactionView> for Transactio
     }
 
     fn next(
-        &self,
+        &mut self,
         helper: &SubscriptionHelper,
         params: &SubscribeToTransactionsParams,
     ) -> Vec<TransactionView> {
@@ -40,7 +46,18 @@ impl Subscription<SubscribeToTransactionsParams, TransactionView> for Transactio
             helper.client.config.fetch_size,
             params.include_events.unwrap_or(false),
         ) {
-            Ok(transactions) => transactions.0,
+            Ok(transactions) => {
+                // The filters may leave nothing of the batch to send, so move past all of it here
+                // rather than in `on_send`
+                if let Some(last) = transactions.0.last() {
+                    self.latest_version = last.version + 1;
+                }
+                transactions
+                    .0
+                    .into_iter()
+                    .filter(|tx| params.matches(&tx.transaction))
+                    .collect()
+            }
             Err(e) => {
                 warn!(
                     "Client#{} Could not fetch transactions: {}",
@@ -51,11 +68,7 @@ impl Subscription<SubscribeToTransactionsParams, TransactionView> for Transactio
         }
     }
 
-    fn on_send(&mut self, tx: Option<&TransactionView>) {
-        if tx.is_some() {
-            self.latest_version += 1;
-        }
-    }
+    fn on_send(&mut self, _tx: Option<&TransactionView>) {}
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -74,7 +87,7 @@ impl Subscription<SubscribeToEventsParams, EventView> for EventsSubscription {
     }
 
     fn next(
-        &self,
+        &mut self,
         helper: &SubscriptionHelper,
         params: &SubscribeToEventsParams,
     ) -> Vec<EventView> {
@@ -99,3 +112,81 @@ impl Subscription<SubscribeToEventsParams, EventView> for EventsSubscription {
         }
     }
 }
+
+/// Streams the lifecycle events of the transactions in mempool. Unlike the other subscriptions it
+/// doesn't poll: mempool pushes the events as they happen, and the oldest ones are dropped for a
+/// client lagging behind.
+pub struct TransactionStatusSubscription;
+
+impl TransactionStatusSubscription {
+    pub fn run(
+        helper: SubscriptionHelper,
+        mut mp_sender: MempoolClientSender,
+        params: SubscribeToTransactionStatusParams,
+    ) -> Result<JoinHandle<()>, JsonRpcError> {
+        let (callback, subscription) = oneshot::channel();
+        mp_sender
+            .try_send(MempoolClientRequest::SubscribeTransactionStatus(
+                params.sender,
+                callback,
+            ))
+            .map_err(|e| JsonRpcError::internal_error(e.to_string()))?;
+
+        Ok(tokio::spawn(async move {
+            let mut events = match subscription.await {
+                Ok(events) => events,
+                Err(e) => {
+                    warn!(
+                        "Client#{} Could not subscribe to the transaction status: {}",
+                        helper.client.id, e
+                    );
+                    return;
+                }
+            };
+            helper.send_ok().await;
+
+            while let Some(event) = events.next().await {
+                counters::SUBSCRIPTION_RPC_SENT
+                    .with_label_values(&[
+                        helper.client.connection_context.transport.as_str(),
+                        helper.method.as_str(),
+                        counters::RpcResult::Success.as_str(),
+                        helper.client.connection_context.sdk_info.language.as_str(),
+                        &helper
+                            .client
+                            .connection_context
+                            .sdk_info
+                            .version
+                            .to_string(),
+                    ])
+                    .inc();
+                let view = TransactionStatusView {
+                    sender: event.sender,
+                    sequence_number: event.sequence_number,
+                    hash: event.hash,
+                    stage: stage_view(event.status),
+                };
+                if let Err(e) = helper
+                    .client
+                    .send_success(helper.jsonrpc_id.clone(), &view)
+                    .await
+                {
+                    // client has disconnected
+                    debug!("Client#{}: Send error: {:?}", &helper.client.id, e);
+                    return;
+                }
+            }
+        }))
+    }
+}
+
+fn stage_view(status: TransactionStatus) -> TransactionStageView {
+    match status {
+        TransactionStatus::Accepted => TransactionStageView::Accepted,
+        TransactionStatus::Broadcast => TransactionStageView::Broadcast,
+        TransactionStatus::IncludedInBlock => TransactionStageView::IncludedInBlock,
+        TransactionStatus::Committed => TransactionStageView::Committed,
+        TransactionStatus::Expired => TransactionStageView::Expired,
+        TransactionStatus::Evicted => TransactionStageView::Evicted,
+    }
+}
@@ -4,9 +4,10 @@
 use std::{future::Future, sync::Arc};
 
 // use proptest::prelude::*;
-use warp::{test::WsClient, ws::Message};
+use warp::{test::WsClient, ws::Message, Filter};
 
 use diem_config::config::StreamConfig;
+use diem_mempool::MempoolClientSender;
 
 use crate::{
     stream_rpc::{
@@ -41,7 +42,17 @@ pub async fn connect_to_ws(
     config: &StreamConfig,
     cm: Option<ConnectionManager>,
 ) -> (WsClient, ConnectionManager) {
-    let (routes, cm) = get_websocket_routes(config, 1024 * 10, db.clone(), cm);
+    connect_to_ws_with_mempool(db, config, cm, mock_mempool()).await
+}
+
+pub async fn connect_to_ws_with_mempool(
+    db: Arc<MockDiemDB>,
+    config: &StreamConfig,
+    cm: Option<ConnectionManager>,
+    mp_sender: MempoolClientSender,
+) -> (WsClient, ConnectionManager) {
+    let (routes, cm) =
+        get_websocket_routes(config, 1024 * 10, db, mp_sender, cm, warp::any().boxed());
     let ws_client = warp::test::ws()
         .path("/v1/stream/ws")
         .header("user-agent", "diem-client-sdk-python / 0.1.22")
@@ -166,3 +177,8 @@ pub fn create_client_connection() -> (
 
     (mock_db, client_connection, receiver)
 }
+
+/// A sender to a mempool that is gone, the subscriptions to it fail
+pub fn mock_mempool() -> MempoolClientSender {
+    futures::channel::mpsc::channel(1).0
+}
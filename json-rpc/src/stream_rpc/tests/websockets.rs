@@ -3,12 +3,22 @@
 
 use crate::{
     stream_rpc::tests::util::{
-        close_ws, connect_to_ws, get_latest_client, next_message, num_clients, num_tasks, timeout,
-        verify_ok, ws_test_setup,
+        close_ws, connect_to_ws, connect_to_ws_with_mempool, get_latest_client, next_message,
+        num_clients, num_tasks, timeout, verify_ok, ws_test_setup,
     },
     tests::utils::create_db_and_runtime,
 };
-use diem_json_rpc_types::stream::response::StreamJsonRpcResponse;
+use channel::{diem_channel, message_queues::QueueStyle};
+use diem_crypto::HashValue;
+use diem_json_rpc_types::{
+    stream::{
+        request::StreamMethod,
+        response::{StreamJsonRpcResponse, StreamJsonRpcResponseView},
+    },
+    views::{TransactionDataView, TransactionStageView, TransactionStatusView, TransactionView},
+};
+use diem_mempool::{MempoolClientRequest, TransactionStatus, TransactionStatusEvent};
+use diem_types::account_address::AccountAddress;
 use futures::{SinkExt, StreamExt};
 use reqwest::Url;
 use serde_json::json;
@@ -49,7 +59,11 @@ async fn test_websocket_call_and_response() {
 async fn test_invalid_params() {
     let (mock_db, config) = ws_test_setup(5, 10, 100, 1000).await;
 
-    let endpoint_names = vec!["subscribe_to_transactions", "subscribe_to_events"];
+    let endpoint_names = vec![
+        "subscribe_to_transactions",
+        "subscribe_to_events",
+        "subscribe_to_transaction_status",
+    ];
 
     for endpoint_name in endpoint_names {
         let name = format!("{}: invalid param", &endpoint_name);
@@ -120,6 +134,106 @@ async fn test_websocket_fetching_data() {
     }
 }
 
+#[tokio::test]
+async fn test_websocket_filtering_transactions() {
+    let (mock_db, config) = ws_test_setup(5, 10, 100, 1000).await;
+
+    let user_txns: Vec<_> = mock_db
+        .all_txns
+        .iter()
+        .enumerate()
+        .filter_map(|(version, (tx, _))| Some((version as u64, tx.as_signed_user_txn().ok()?)))
+        .collect();
+    let sender = user_txns.first().unwrap().1.sender();
+    let expected_versions: Vec<_> = user_txns
+        .iter()
+        .filter(|(_, tx)| tx.sender() == sender)
+        .map(|(version, _)| *version)
+        .collect();
+
+    let name = "subscribe_to_transactions: filter by sender";
+    let request = json!({"id": "client-generated-id", "method": "subscribe_to_transactions", "params": {"starting_version": 0, "sender": sender.to_string()}, "jsonrpc": "2.0"});
+    let (mut ws_client, cm) = connect_to_ws(mock_db.clone(), &config, None).await;
+    ws_client.send_text(request.to_string()).await;
+    let sub_result = next_message(&mut ws_client, name).await;
+    let _transaction_version = verify_ok(sub_result, name);
+
+    for (i, expected_version) in expected_versions.iter().enumerate() {
+        let msg = next_message(&mut ws_client, &format!("{} get message {}", name, i)).await;
+        let resp: StreamJsonRpcResponse =
+            serde_json::from_str(msg.to_str().expect("response")).unwrap();
+        let tx: TransactionView = serde_json::from_value(resp.result.unwrap()).unwrap();
+        assert_eq!(tx.version, *expected_version);
+        match tx.transaction {
+            TransactionDataView::UserTransaction {
+                sender: tx_sender, ..
+            } => assert_eq!(tx_sender, sender),
+            _ => panic!("not a user transaction"),
+        }
+    }
+
+    close_ws(ws_client, name).await;
+    assert_eq!(num_clients(&cm), 0);
+}
+
+#[tokio::test]
+async fn test_websocket_transaction_status() {
+    let (mock_db, config) = ws_test_setup(5, 10, 100, 1000).await;
+    let (mp_sender, mut mp_requests) = futures::channel::mpsc::channel(1);
+    let (mut ws_client, cm) = connect_to_ws_with_mempool(mock_db, &config, None, mp_sender).await;
+
+    let name = "subscribe_to_transaction_status: get the status of the transactions of an account";
+    let sender = AccountAddress::random();
+    let request = json!({"id": "client-generated-id", "method": "subscribe_to_transaction_status", "params": {"sender": sender}, "jsonrpc": "2.0"});
+    ws_client.send_text(request.to_string()).await;
+
+    // mempool streams the status of the transactions of the account
+    let (mut status_sender, status_receiver) = diem_channel::new(QueueStyle::KLAST, 10, None);
+    match timeout(100, mp_requests.next(), name).await {
+        Some(MempoolClientRequest::SubscribeTransactionStatus(account, callback)) => {
+            assert_eq!(account, Some(sender));
+            assert!(callback.send(status_receiver).is_ok());
+        }
+        _ => panic!("{}: expected a subscription to mempool", name),
+    }
+    verify_ok(next_message(&mut ws_client, name).await, name);
+    assert_eq!(num_tasks(&get_latest_client(&cm)), 1);
+
+    let hash = HashValue::random();
+    status_sender
+        .push(
+            (),
+            TransactionStatusEvent {
+                sender,
+                sequence_number: 3,
+                hash,
+                status: TransactionStatus::IncludedInBlock,
+            },
+        )
+        .unwrap();
+    let msg = next_message(&mut ws_client, name).await;
+    let resp: StreamJsonRpcResponse = serde_json::from_str(msg.to_str().unwrap()).unwrap();
+    match resp
+        .parse_result(&StreamMethod::SubscribeToTransactionStatus)
+        .unwrap()
+        .unwrap()
+    {
+        StreamJsonRpcResponseView::TransactionStatus(view) => assert_eq!(
+            view,
+            TransactionStatusView {
+                sender,
+                sequence_number: 3,
+                hash,
+                stage: TransactionStageView::IncludedInBlock,
+            }
+        ),
+        view => panic!("{}: unexpected response {:?}", name, view),
+    }
+
+    close_ws(ws_client, name).await;
+    assert_eq!(num_clients(&cm), 0);
+}
+
 #[tokio::test]
 async fn test_multiple_subscriptions_and_response() {
     let (mock_db, config) = ws_test_setup(5, 10, 100, 1000).await;
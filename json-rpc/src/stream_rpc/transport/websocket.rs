@@ -10,6 +10,7 @@ use warp::{filters::BoxedFilter, ws::Message, Filter, Rejection, Reply};
 
 use diem_config::config::StreamConfig;
 use diem_logger::debug;
+use diem_mempool::MempoolClientSender;
 use storage_interface::MoveDbReader;
 
 use crate::stream_rpc::{
@@ -21,11 +22,14 @@ use crate::stream_rpc::{
     transport::util::{get_remote_addr, Transport},
 };
 
+/// Gets the websocket route, which upgrades the connections of the requests passing `access`
 pub fn get_websocket_routes(
     config: &StreamConfig,
     content_length_limit: u64,
     diem_db: Arc<dyn MoveDbReader>,
+    mp_sender: MempoolClientSender,
     connection_manager: Option<ConnectionManager>,
+    access: BoxedFilter<()>,
 ) -> (BoxedFilter<(impl Reply,)>, ConnectionManager) {
     let sub_config = Arc::new(SubscriptionConfig {
         fetch_size: config.subscription_fetch_size,
@@ -35,7 +39,7 @@ pub fn get_websocket_routes(
     });
 
     let connection_manager = match connection_manager {
-        None => ConnectionManager::new(diem_db.clone(), sub_config),
+        None => ConnectionManager::new(diem_db, mp_sender, sub_config),
         Some(cm) => cm,
     };
 
@@ -44,6 +48,7 @@ pub fn get_websocket_routes(
         .and(warp::path("stream"))
         .and(warp::path("ws"))
         .and(warp::path::end())
+        .and(access)
         .and(warp::body::content_length_limit(content_length_limit))
         .and(warp::filters::header::header::<String>("user-agent"))
         .and(warp::ws())
@@ -2,18 +2,32 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    errors::ServerCode,
+    errors::{ErrorReason, InvalidRequestCode, ServerCode},
+    health::{HealthStatus, PeerCounts, SyncStatus},
+    rate_limit::RateLimiter,
+    response::JsonRpcResponse,
     runtime::check_latest_ledger_info_timestamp,
     tests::utils::{
         create_database_client_and_runtime, create_db_and_runtime, mock_db, test_bootstrap,
-        MockDiemDB,
+        test_bootstrap_with_access_control, MockDiemDB,
     },
     util::{sdk_info_from_user_agent, SdkInfo, SdkLang, SdkVersion},
-    views::VMStatusView,
+    views::{
+        AccountStateWithProofView, EventPageView, SubmissionStatusView, TransactionView,
+        VMStatusView,
+    },
 };
 use diem_client::{views::TransactionDataView, BlockingClient, MethodRequest};
-use diem_config::{config::DEFAULT_CONTENT_LENGTH_LIMIT, utils};
+use diem_config::{
+    config::{
+        ApiKeyAccess, ApiKeyConfig, CallQuota, JsonRpcRateLimitConfig, PersistableConfig,
+        DEFAULT_CONTENT_LENGTH_LIMIT,
+    },
+    utils,
+};
 use diem_crypto::{ed25519::Ed25519PrivateKey, hash::CryptoHash, HashValue, PrivateKey, Uniform};
+use diem_json_rpc_types::Method;
+use diem_mempool::{AccountPendingTransactions, MempoolClientRequest};
 use diem_metrics::get_all_metrics;
 use diem_types::{
     account_address::AccountAddress,
@@ -25,14 +39,21 @@ use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
     mempool_status::{MempoolStatus, MempoolStatusCode},
     proof::{SparseMerkleProof, TransactionAccumulatorProof, TransactionInfoWithProof},
-    test_helpers::transaction_test_helpers::get_test_signed_txn,
-    transaction::{Transaction, TransactionInfo, TransactionPayload},
+    state_proof::StateProof,
+    test_helpers::transaction_test_helpers::{
+        get_test_signed_transaction, get_test_signed_txn, get_test_txn_with_chain_id,
+    },
+    transaction::{
+        SignedTransaction, Transaction, TransactionInfo, TransactionListWithProof,
+        TransactionPayload,
+    },
     vm_status::StatusCode,
 };
 use futures::{channel::mpsc::channel, StreamExt};
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use std::{
     cmp::{max, min},
+    collections::BTreeMap,
     convert::TryFrom,
     ops::Sub,
     sync::Arc,
@@ -40,7 +61,8 @@ use std::{
 };
 use storage_interface::DbReader;
 use vm_validator::{
-    mocks::mock_vm_validator::MockVMValidator, vm_validator::TransactionValidation,
+    mocks::mock_vm_validator::MockVMValidator,
+    vm_validator::{TransactionValidation, MAX_SIMULATION_GAS_AMOUNT},
 };
 
 use serde_json::json;
@@ -245,7 +267,7 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "error": {
                     "code": -32602,
                     "message": format!("Invalid param version should be <= known latest version {}", version),
-                    "data": null
+                    "data": {"InvalidParam": {"param": "version", "reason": "version_too_high"}}
                 },
                 "id": 1,
                 "jsonrpc": "2.0",
@@ -429,7 +451,7 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "error": {
                     "code": -32600,
                     "message": "Invalid Request: page size = 1001, exceed limit 1000",
-                    "data": null
+                    "data": {"Reason": "limit_exceeded"}
                 },
                 "id": 1,
                 "jsonrpc": "2.0",
@@ -585,7 +607,7 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "error": {
                     "code": -32602,
                     "message": format!("Invalid param version should be <= known latest version {}", version),
-                    "data": null
+                    "data": {"InvalidParam": {"param": "version", "reason": "version_too_high"}}
                 },
                 "id": 1,
                 "jsonrpc": "2.0",
@@ -767,7 +789,7 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "error": {
                     "code": -32600,
                     "message": "Invalid Request: page size = 1001, exceed limit 1000",
-                    "data": null
+                    "data": {"Reason": "limit_exceeded"}
                 },
                 "id": 1,
                 "jsonrpc": "2.0",
@@ -783,7 +805,7 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "error": {
                     "code": -32602,
                     "message": format!("Invalid param ledger_version should be <= known latest version {}", version),
-                    "data": null
+                    "data": {"InvalidParam": {"param": "ledger_version", "reason": "version_too_high"}}
                 },
                 "id": 1,
                 "jsonrpc": "2.0",
@@ -815,7 +837,7 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "error": {
                     "code": -32602,
                     "message": format!("Invalid param version should be <= known latest version {}", version),
-                    "data": null
+                    "data": {"InvalidParam": {"param": "version", "reason": "version_too_high"}}
                 },
                 "id": 1,
                 "jsonrpc": "2.0",
@@ -831,7 +853,7 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "error": {
                     "code": -32602,
                     "message": format!("Invalid param ledger_version should be <= known latest version {}", version),
-                    "data": null
+                    "data": {"InvalidParam": {"param": "ledger_version", "reason": "version_too_high"}}
                 },
                 "id": 1,
                 "jsonrpc": "2.0",
@@ -847,7 +869,7 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "error": {
                     "code": -32600,
                     "message": format!("Invalid Request: client_known_version({}) should be <= ledger_version({})", version+1, version),
-                    "data": null
+                    "data": {"Reason": "version_too_high"}
                 },
                 "id": 1,
                 "jsonrpc": "2.0",
@@ -895,7 +917,7 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "error": {
                     "code": -32600,
                     "message": format!("Invalid Request: version({}) should <= ledger version({})",version, version-1),
-                    "data": null
+                    "data": {"Reason": "version_too_high"}
                 },
                 "id": 1,
                 "jsonrpc": "2.0",
@@ -911,7 +933,7 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "error": {
                     "code": -32602,
                     "message": format!("Invalid param ledger_version should be <= known latest version {}", version),
-                    "data": null
+                    "data": {"InvalidParam": {"param": "ledger_version", "reason": "version_too_high"}}
                 },
                 "id": 1,
                 "jsonrpc": "2.0",
@@ -943,7 +965,7 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "error": {
                     "code": -32602,
                     "message": format!("Invalid param version should be <= known latest version {}", version),
-                    "data": null
+                    "data": {"InvalidParam": {"param": "version", "reason": "version_too_high"}}
                 },
                 "id": 1,
                 "jsonrpc": "2.0",
@@ -959,7 +981,7 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "error": {
                     "code": -32602,
                     "message": format!("Invalid param ledger_version should be <= known latest version {}", version),
-                    "data": null
+                    "data": {"InvalidParam": {"param": "ledger_version", "reason": "version_too_high"}}
                 },
                 "id": 1,
                 "jsonrpc": "2.0",
@@ -1066,6 +1088,269 @@ fn test_metrics() {
     }
 }
 
+#[test]
+fn test_rate_limit() {
+    let (mp_sender, _mp_events) = channel(1);
+    let port = utils::get_available_port();
+    let address = format!("0.0.0.0:{}", port);
+    let mut rate_limit_config = JsonRpcRateLimitConfig {
+        enabled: true,
+        ip_call_bucket_rate: 3,
+        ip_call_bucket_size: 3,
+        method_quotas: Default::default(),
+        trusted_proxies: vec![],
+    };
+    rate_limit_config.method_quotas.insert(
+        "get_transactions".to_string(),
+        CallQuota { rate: 1, size: 1 },
+    );
+    let _runtime = test_bootstrap_with_access_control(
+        address.parse().unwrap(),
+        Arc::new(mock_db()),
+        mp_sender,
+        &rate_limit_config,
+        &ApiKeyConfig::default(),
+    );
+    let client = reqwest::blocking::Client::new();
+
+    let batch = json!([
+        {"jsonrpc": "2.0", "method": "get_transactions", "params": [0, 1, false], "id": 1},
+        // throttled by the method quota, which doesn't take a token of the IP
+        {"jsonrpc": "2.0", "method": "get_transactions", "params": [0, 1, false], "id": 2},
+        {"jsonrpc": "2.0", "method": "get_metadata", "params": [], "id": 3},
+        {"jsonrpc": "2.0", "method": "get_metadata", "params": [], "id": 4},
+        // throttled by the IP limit
+        {"jsonrpc": "2.0", "method": "get_metadata", "params": [], "id": 5},
+    ]);
+    let resp = client
+        .post(&format!("http://127.0.0.1:{}/v1", port))
+        .json(&batch)
+        .send()
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let responses: Vec<JsonRpcResponse> = resp.json().unwrap();
+
+    let throttled: Vec<_> = responses
+        .iter()
+        .map(|resp| resp.error.as_ref().and_then(|err| err.as_retry_after()))
+        .collect();
+    assert!(throttled[0].is_none() && throttled[2].is_none() && throttled[3].is_none());
+    for retry_after in [throttled[1], throttled[4]].iter() {
+        assert!(retry_after.unwrap() <= Duration::from_secs(1));
+    }
+    assert_eq!(
+        responses[1].error.as_ref().unwrap().code,
+        ServerCode::RateLimited as i16
+    );
+}
+
+#[test]
+fn test_rate_limit_forwarded_calls_and_batch_submissions() {
+    let (mp_sender, _mp_events) = channel(1);
+    let port = utils::get_available_port();
+    let address = format!("0.0.0.0:{}", port);
+    let rate_limit_config = JsonRpcRateLimitConfig {
+        enabled: true,
+        ip_call_bucket_rate: 2,
+        ip_call_bucket_size: 2,
+        method_quotas: Default::default(),
+        trusted_proxies: vec!["127.0.0.1".parse().unwrap()],
+    };
+    let _runtime = test_bootstrap_with_access_control(
+        address.parse().unwrap(),
+        Arc::new(mock_db()),
+        mp_sender,
+        &rate_limit_config,
+        &ApiKeyConfig::default(),
+    );
+    let client = reqwest::blocking::Client::new();
+    let url = format!("http://127.0.0.1:{}/v1", port);
+    let call = |forwarded_for: &str, request: serde_json::Value| -> JsonRpcResponse {
+        client
+            .post(&url)
+            .header("X-Forwarded-For", forwarded_for)
+            .json(&request)
+            .send()
+            .unwrap()
+            .json()
+            .unwrap()
+    };
+    let get_metadata = json!({"jsonrpc": "2.0", "method": "get_metadata", "params": [], "id": 1});
+    let is_rate_limited = |resp: &JsonRpcResponse| {
+        resp.error
+            .as_ref()
+            .map_or(false, |err| err.code == ServerCode::RateLimited as i16)
+    };
+
+    // the calls forwarded by the proxy are limited by the IP of their client
+    assert!(!is_rate_limited(&call("10.0.0.1", get_metadata.clone())));
+    assert!(!is_rate_limited(&call("10.0.0.1", get_metadata.clone())));
+    assert!(is_rate_limited(&call("10.0.0.1", get_metadata.clone())));
+    assert!(!is_rate_limited(&call("10.0.0.2", get_metadata.clone())));
+    // the addresses a client adds before its own don't change its IP
+    assert!(is_rate_limited(&call("10.0.0.9, 10.0.0.1", get_metadata)));
+
+    // a batch submission takes a token per transaction, it's never served if it takes more tokens
+    // than the bucket holds
+    let sender = AccountAddress::new([9; AccountAddress::LENGTH]);
+    let privkey = Ed25519PrivateKey::generate_for_testing();
+    let txn = get_test_signed_txn(sender, 0, &privkey, privkey.public_key(), None);
+    let txns = vec![hex::encode(bcs::to_bytes(&txn).unwrap()); 3];
+    let submit_batch =
+        json!({"jsonrpc": "2.0", "method": "submit_batch", "params": [txns], "id": 1});
+    let resp = call("10.0.0.3", submit_batch);
+    let error = resp.error.unwrap();
+    assert_eq!(error.code, InvalidRequestCode::InvalidParams as i16);
+    assert_eq!(error.as_reason(), Some(ErrorReason::LimitExceeded));
+    assert_eq!(error.as_retry_after(), None);
+}
+
+#[test]
+fn test_update_rate_limits() {
+    let rate_limiter = RateLimiter::open();
+    let ip = "10.0.0.1".parse().unwrap();
+    for _ in 0..10 {
+        assert!(rate_limiter.acquire(ip, Method::GetMetadata, 1).is_ok());
+    }
+
+    let mut rate_limit_config = JsonRpcRateLimitConfig {
+        enabled: true,
+        ip_call_bucket_rate: 1,
+        ip_call_bucket_size: 1,
+        method_quotas: Default::default(),
+        trusted_proxies: vec![],
+    };
+    rate_limiter.update(&rate_limit_config).unwrap();
+    assert!(rate_limiter.acquire(ip, Method::GetMetadata, 1).is_ok());
+    assert!(rate_limiter.acquire(ip, Method::GetMetadata, 1).is_err());
+
+    // the limits are kept when the new ones are invalid
+    rate_limit_config
+        .method_quotas
+        .insert("unknown".to_string(), CallQuota { rate: 1, size: 1 });
+    assert!(rate_limiter.update(&rate_limit_config).is_err());
+    assert!(rate_limiter.acquire(ip, Method::GetMetadata, 1).is_err());
+}
+
+#[test]
+fn test_api_key() {
+    let (mp_sender, _mp_events) = channel(1);
+    let port = utils::get_available_port();
+    let address = format!("0.0.0.0:{}", port);
+    let keys_file = diem_temppath::TempPath::new();
+    keys_file.create_as_file().unwrap();
+
+    let mut keys = BTreeMap::new();
+    keys.insert(
+        "partner".to_string(),
+        ApiKeyAccess {
+            allowed_methods: None,
+            quota: Some(CallQuota { rate: 2, size: 2 }),
+        },
+    );
+    keys.save_config(keys_file.path()).unwrap();
+
+    let api_key_config = ApiKeyConfig {
+        enabled: true,
+        keys_file: Some(keys_file.path().to_path_buf()),
+        reload_interval_ms: 100,
+        public_methods: Some(vec!["get_metadata".to_string()]),
+    };
+    let _runtime = test_bootstrap_with_access_control(
+        address.parse().unwrap(),
+        Arc::new(mock_db()),
+        mp_sender,
+        &JsonRpcRateLimitConfig::default(),
+        &api_key_config,
+    );
+    let client = reqwest::blocking::Client::new();
+    let url = format!("http://127.0.0.1:{}/v1", port);
+    let batch = json!([
+        {"jsonrpc": "2.0", "method": "get_metadata", "params": [], "id": 1},
+        {"jsonrpc": "2.0", "method": "get_currencies", "params": [], "id": 2},
+        {"jsonrpc": "2.0", "method": "get_currencies", "params": [], "id": 3},
+    ]);
+    let send = |api_key: Option<&str>| {
+        let request = client.post(&url).json(&batch);
+        let request = match api_key {
+            Some(api_key) => request.header("X-Diem-Api-Key", api_key),
+            None => request,
+        };
+        let responses: Vec<JsonRpcResponse> = request.send().unwrap().json().unwrap();
+        responses
+            .into_iter()
+            .map(|resp| resp.error.map(|err| err.code))
+            .collect::<Vec<_>>()
+    };
+    let unauthorized = Some(ServerCode::Unauthorized as i16);
+
+    // public calls are limited to the public methods
+    assert_eq!(send(None), vec![None, unauthorized, unauthorized]);
+    assert_eq!(
+        send(Some("unknown")),
+        vec![unauthorized, unauthorized, unauthorized]
+    );
+    // the partner key may call any method, within its quota
+    assert_eq!(
+        send(Some("partner")),
+        vec![None, None, Some(ServerCode::RateLimited as i16)]
+    );
+
+    // revoking the key takes effect once the keys file is reloaded
+    BTreeMap::<String, ApiKeyAccess>::new()
+        .save_config(keys_file.path())
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(500));
+    assert_eq!(
+        send(Some("partner")),
+        vec![unauthorized, unauthorized, unauthorized]
+    );
+}
+
+#[test]
+fn test_bcs_responses() {
+    let (mock_db, _runtime, url, _) = create_db_and_runtime();
+    let client = reqwest::blocking::Client::new();
+    let send = |method: &str, params: serde_json::Value| {
+        client
+            .post(&url)
+            .header("accept", "application/x-bcs")
+            .json(&json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1}))
+            .send()
+            .unwrap()
+    };
+
+    let resp = send(
+        "get_account_state_with_proof",
+        json!([AccountAddress::ZERO, null, null]),
+    );
+    assert_eq!(resp.headers()["content-type"], "application/x-bcs");
+    let account_state: AccountStateWithProof = bcs::from_bytes(&resp.bytes().unwrap()).unwrap();
+    assert_eq!(account_state, mock_db.account_state_with_proof[0]);
+
+    let resp = send("get_transactions", json!([1, 10, false]));
+    assert_eq!(resp.headers()["content-type"], "application/x-bcs");
+    let txns: TransactionListWithProof = bcs::from_bytes(&resp.bytes().unwrap()).unwrap();
+    assert_eq!(txns.first_transaction_version, Some(1));
+    assert_eq!(txns.transactions.len(), 10);
+    for (txn, (expected_txn, _)) in txns.transactions.iter().zip(&mock_db.all_txns[1..]) {
+        assert_eq!(txn, expected_txn);
+    }
+
+    let resp = send("get_state_proof", json!([0]));
+    assert_eq!(resp.headers()["content-type"], "application/x-bcs");
+    let state_proof: StateProof = bcs::from_bytes(&resp.bytes().unwrap()).unwrap();
+    assert_eq!(state_proof.latest_ledger_info().version(), mock_db.version);
+
+    // errors and the methods without BCS responses are answered in JSON
+    let resp: JsonRpcResponse = send("get_currencies", json!([])).json().unwrap();
+    assert_eq!(resp.error.unwrap().code, -32600);
+    let resp: JsonRpcResponse = send("get_state_proof", json!([mock_db.version + 1]))
+        .json()
+        .unwrap();
+    assert!(resp.error.is_some());
+}
+
 #[test]
 fn test_transaction_submission() {
     let (mp_sender, mut mp_events) = channel(1);
@@ -1078,7 +1363,7 @@ fn test_transaction_submission() {
     // future that mocks shared mempool execution
     runtime.spawn(async move {
         let validator = MockVMValidator;
-        while let Some((txn, cb)) = mp_events.next().await {
+        while let Some(MempoolClientRequest::SubmitTransaction(txn, cb)) = mp_events.next().await {
             let vm_status = validator.validate_transaction(txn).unwrap().status();
             let result = if vm_status.is_some() {
                 (MempoolStatus::new(MempoolStatusCode::VmError), vm_status)
@@ -1111,6 +1396,187 @@ fn test_transaction_submission() {
     assert_eq!(status_code, StatusCode::SENDING_ACCOUNT_DOES_NOT_EXIST);
 }
 
+#[test]
+fn test_simulate_transaction() {
+    let (_mock_db, _runtime, url, _) = create_db_and_runtime();
+    let client = reqwest::blocking::Client::new();
+    let simulate_error = |txn: &SignedTransaction, skip_signature_check: bool| {
+        let resp: JsonRpcResponse = client
+            .post(&url)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "method": "simulate_transaction",
+                "params": [hex::encode(bcs::to_bytes(txn).unwrap()), skip_signature_check],
+                "id": 1,
+            }))
+            .send()
+            .unwrap()
+            .json()
+            .unwrap();
+        resp.error.unwrap()
+    };
+    let simulate = |txn: &SignedTransaction, skip_signature_check: bool| {
+        let error = simulate_error(txn, skip_signature_check);
+        assert_eq!(error.code, ServerCode::VmValidationError as i16);
+        error.as_status_code().unwrap()
+    };
+
+    // a transaction whose signature doesn't match its public key, from an account that doesn't
+    // exist
+    let sender = AccountAddress::new([0; AccountAddress::LENGTH]);
+    let privkey = Ed25519PrivateKey::generate_for_testing();
+    let other_privkey = Ed25519PrivateKey::generate(&mut rand::rngs::OsRng);
+    let txn = get_test_signed_txn(sender, 0, &privkey, other_privkey.public_key(), None);
+
+    assert_eq!(simulate(&txn, false), StatusCode::INVALID_SIGNATURE);
+    // without checking the signature, the prologue runs
+    assert_eq!(
+        simulate(&txn, true),
+        StatusCode::SENDING_ACCOUNT_DOES_NOT_EXIST
+    );
+
+    // a max gas amount over the limit isn't simulated
+    let txn = get_test_signed_transaction(
+        sender,
+        0,
+        &privkey,
+        privkey.public_key(),
+        None,
+        0,
+        0,
+        "XUS".to_string(),
+        Some(MAX_SIMULATION_GAS_AMOUNT + 1),
+    );
+    let error = simulate_error(&txn, true);
+    assert_eq!(error.code, InvalidRequestCode::InvalidParams as i16);
+    assert_eq!(error.as_invalid_param(), Some("data"));
+}
+
+#[test]
+fn test_submit_batch() {
+    let (_mock_db, runtime, url, mut mp_events) = create_db_and_runtime();
+    let client = reqwest::blocking::Client::new();
+    let submit_batch = |txns: &[SignedTransaction]| -> JsonRpcResponse {
+        let txns: Vec<_> = txns
+            .iter()
+            .map(|txn| hex::encode(bcs::to_bytes(txn).unwrap()))
+            .collect();
+        client
+            .post(&url)
+            .json(&json!({"jsonrpc": "2.0", "method": "submit_batch", "params": [txns], "id": 1}))
+            .send()
+            .unwrap()
+            .json()
+            .unwrap()
+    };
+
+    // future that mocks shared mempool, accepting the transactions of sequence number 0 only
+    runtime.spawn(async move {
+        while let Some(MempoolClientRequest::SubmitTransaction(txn, cb)) = mp_events.next().await {
+            let code = if txn.sequence_number() == 0 {
+                MempoolStatusCode::Accepted
+            } else {
+                MempoolStatusCode::InvalidSeqNumber
+            };
+            cb.send(Ok((MempoolStatus::new(code), None))).unwrap();
+        }
+    });
+
+    let sender = AccountAddress::new([9; AccountAddress::LENGTH]);
+    let privkey = Ed25519PrivateKey::generate_for_testing();
+    let txns = vec![
+        get_test_signed_txn(sender, 0, &privkey, privkey.public_key(), None),
+        get_test_signed_txn(sender, 5, &privkey, privkey.public_key(), None),
+        get_test_txn_with_chain_id(sender, 0, &privkey, privkey.public_key(), ChainId::new(100)),
+        get_test_signed_transaction(
+            sender,
+            0,
+            &privkey,
+            privkey.public_key(),
+            None,
+            0,
+            0,
+            "XUS".to_string(),
+            None,
+        ),
+    ];
+
+    let resp = submit_batch(&txns);
+    let statuses: Vec<SubmissionStatusView> = serde_json::from_value(resp.result.unwrap()).unwrap();
+    assert_eq!(statuses.len(), txns.len());
+    for (status, txn) in statuses.iter().zip(&txns) {
+        assert_eq!(
+            status.hash,
+            Transaction::UserTransaction(txn.clone()).hash()
+        );
+        assert_eq!(status.accepted, status.error.is_none());
+    }
+    assert!(statuses[0].accepted);
+    assert_eq!(
+        statuses[1].error.as_ref().unwrap().as_mempool_status_code(),
+        Some(MempoolStatusCode::InvalidSeqNumber)
+    );
+    assert_eq!(
+        statuses[2].error.as_ref().unwrap().as_status_code(),
+        Some(StatusCode::BAD_CHAIN_ID)
+    );
+    assert_eq!(
+        statuses[3].error.as_ref().unwrap().as_status_code(),
+        Some(StatusCode::TRANSACTION_EXPIRED)
+    );
+
+    // the batch size limit of the service applies to the transactions of a batch
+    let txns = vec![txns[0].clone(); 21];
+    let error = submit_batch(&txns).error.unwrap();
+    assert_eq!(error.code, -32600);
+    assert_eq!(error.as_reason(), Some(ErrorReason::LimitExceeded));
+}
+
+#[test]
+fn test_get_account_transactions_pending() {
+    let (_mock_db, runtime, url, mut mp_events) = create_db_and_runtime();
+    let sender = AccountAddress::new([9; AccountAddress::LENGTH]);
+    let privkey = Ed25519PrivateKey::generate_for_testing();
+    let parked_txn = get_test_signed_txn(sender, 3, &privkey, privkey.public_key(), None);
+
+    // future that mocks shared mempool, holding a txn waiting on sequence numbers 1 and 2
+    let mempool_txn = parked_txn.clone();
+    runtime.spawn(async move {
+        while let Some(MempoolClientRequest::GetAccountPendingTransactions(_, cb)) =
+            mp_events.next().await
+        {
+            let pending = AccountPendingTransactions {
+                sequence_number: 1,
+                transactions: vec![(mempool_txn.clone(), true)],
+                sequence_gaps: vec![1..3],
+            };
+            cb.send(Ok(pending)).unwrap();
+        }
+    });
+
+    let client = reqwest::blocking::Client::new();
+    let resp: serde_json::Value = client
+        .post(&url)
+        .json(&json!({
+            "jsonrpc": "2.0",
+            "method": "get_account_transactions_pending",
+            "params": [sender.to_string()],
+            "id": 1,
+        }))
+        .send()
+        .unwrap()
+        .json()
+        .unwrap();
+    let result = &resp["result"];
+    assert_eq!(result["sequence_number"], json!(1));
+    assert_eq!(result["sequence_gaps"], json!([{"start": 1, "end": 3}]));
+    assert_eq!(result["transactions"][0]["is_parked"], json!(true));
+    assert_eq!(
+        result["transactions"][0]["hash"],
+        json!(Transaction::UserTransaction(parked_txn).hash().to_hex())
+    );
+}
+
 #[test]
 fn test_get_account() {
     let (mock_db, client, _runtime) = create_database_client_and_runtime();
@@ -1210,7 +1676,7 @@ fn test_limit_batch_size() {
 
     let ret = client.batch(batch).unwrap_err();
     let error = ret.json_rpc_error().unwrap();
-    let expected = "JsonRpcError { code: -32600, message: \"Invalid Request: batch size = 21, exceed limit 20\", data: None }";
+    let expected = "JsonRpcError { code: -32600, message: \"Invalid Request: batch size = 21, exceed limit 20\", data: Some(Reason(LimitExceeded)) }";
     assert_eq!(format!("{:?}", error), expected)
 }
 
@@ -1227,7 +1693,7 @@ fn test_get_events_page_limit() {
         .unwrap_err();
 
     let error = ret.json_rpc_error().unwrap();
-    let expected = "JsonRpcError { code: -32600, message: \"Invalid Request: page size = 1001, exceed limit 1000\", data: None }";
+    let expected = "JsonRpcError { code: -32600, message: \"Invalid Request: page size = 1001, exceed limit 1000\", data: Some(Reason(LimitExceeded)) }";
     assert_eq!(format!("{:?}", error), expected)
 }
 
@@ -1237,7 +1703,7 @@ fn test_get_transactions_page_limit() {
 
     let ret = client.get_transactions(0, 1001, false).unwrap_err();
     let error = ret.json_rpc_error().unwrap();
-    let expected = "JsonRpcError { code: -32600, message: \"Invalid Request: page size = 1001, exceed limit 1000\", data: None }";
+    let expected = "JsonRpcError { code: -32600, message: \"Invalid Request: page size = 1001, exceed limit 1000\", data: Some(Reason(LimitExceeded)) }";
     assert_eq!(format!("{:?}", error), expected)
 }
 
@@ -1271,6 +1737,124 @@ fn test_get_events() {
     );
 }
 
+#[test]
+fn test_get_events_page() {
+    let (mock_db, _runtime, url, _) = create_db_and_runtime();
+    let client = reqwest::blocking::Client::new();
+
+    let (_, first_event) = mock_db.events[0].clone();
+    let event_key = hex::encode(first_event.key().as_bytes());
+    let mut expected: Vec<_> = mock_db
+        .events
+        .iter()
+        .filter(|(_, e)| e.key() == first_event.key())
+        .map(|(version, e)| (e.sequence_number(), *version))
+        .collect();
+    expected.sort_unstable();
+
+    let get_page = |params: serde_json::Value| {
+        let request =
+            json!({"jsonrpc": "2.0", "method": "get_events_page", "params": params, "id": 1});
+        let resp = client.post(&url).json(&request).send().unwrap();
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = resp.json().unwrap();
+        serde_json::from_value::<EventPageView>(resp_json["result"].clone()).unwrap()
+    };
+
+    let page = get_page(json!([event_key, null, 2]));
+    let fetched: Vec<_> = page
+        .events
+        .iter()
+        .map(|e| (e.sequence_number, e.transaction_version))
+        .collect();
+    assert_eq!(
+        fetched,
+        expected.iter().take(2).cloned().collect::<Vec<_>>()
+    );
+    assert_eq!(page.next_cursor, Some(fetched.last().unwrap().0 + 1));
+
+    let page = get_page(json!([event_key, null, 2, "descending"]));
+    let fetched: Vec<_> = page
+        .events
+        .iter()
+        .map(|e| (e.sequence_number, e.transaction_version))
+        .collect();
+    assert_eq!(
+        fetched,
+        expected.iter().rev().take(2).cloned().collect::<Vec<_>>()
+    );
+    assert_eq!(page.next_cursor, fetched.last().unwrap().0.checked_sub(1));
+}
+
+#[test]
+fn test_get_transactions_cursor() {
+    let (mock_db, _runtime, url, _) = create_db_and_runtime();
+    let client = reqwest::blocking::Client::new();
+
+    let send = |params: serde_json::Value| {
+        let request =
+            json!({"jsonrpc": "2.0", "method": "get_transactions", "params": params, "id": 1});
+        let resp = client.post(&url).json(&request).send().unwrap();
+        assert_eq!(resp.status(), 200);
+        resp.json::<serde_json::Value>().unwrap()
+    };
+
+    let mut versions = vec![];
+    let mut resp = send(json!([0, 10, false]));
+    loop {
+        let txns: Vec<TransactionView> = serde_json::from_value(resp["result"].clone()).unwrap();
+        versions.extend(txns.iter().map(|txn| txn.version));
+        match resp["next_cursor"].as_str() {
+            // the start version is ignored once a cursor is given
+            Some(cursor) => resp = send(json!([0, 10, false, cursor])),
+            None => break,
+        }
+    }
+    assert_eq!(
+        versions,
+        (0..mock_db.all_txns.len() as u64).collect::<Vec<_>>()
+    );
+
+    let resp = send(json!([0, 10, false, "invalid"]));
+    assert_eq!(resp["error"]["code"], -32602);
+}
+
+#[test]
+fn test_get_events_cursor() {
+    let (mock_db, _runtime, url, _) = create_db_and_runtime();
+    let client = reqwest::blocking::Client::new();
+
+    let send = |params: serde_json::Value| {
+        let request = json!({"jsonrpc": "2.0", "method": "get_events", "params": params, "id": 1});
+        let resp = client.post(&url).json(&request).send().unwrap();
+        assert_eq!(resp.status(), 200);
+        resp.json::<serde_json::Value>().unwrap()
+    };
+
+    let (_, first_event) = mock_db.events[0].clone();
+    let event_key = hex::encode(first_event.key().as_bytes());
+    let seq = first_event.sequence_number();
+
+    // a full page returns the cursor of the next one
+    let resp = send(json!([event_key, seq, 1]));
+    assert_eq!(resp["result"][0]["sequence_number"], seq);
+    let cursor = resp["next_cursor"].as_str().unwrap().to_string();
+
+    let resp = send(json!([event_key, 0, 1, cursor]));
+    let expected = mock_db
+        .events
+        .iter()
+        .filter(|(_, e)| e.key() == first_event.key() && e.sequence_number() == seq + 1)
+        .count();
+    assert_eq!(resp["result"].as_array().unwrap().len(), expected);
+    assert_eq!(resp["next_cursor"].is_string(), expected == 1);
+
+    // a cursor only pages through the events of its own key
+    let other_key = hex::encode(EventKey::random().as_bytes());
+    let resp = send(json!([other_key, 0, 1, cursor]));
+    assert_eq!(resp["error"]["code"], -32602);
+}
+
 #[test]
 fn test_get_transactions() {
     let (mock_db, client, _runtime) = create_database_client_and_runtime();
@@ -1431,6 +2015,42 @@ fn test_get_account_transactions() {
         assert_eq!(tx_views.len() as u64, total);
     }
 }
+
+#[test]
+fn test_get_latest_account_transactions() {
+    let (mock_db, _runtime, url, _) = create_db_and_runtime();
+    let client = reqwest::blocking::Client::new();
+
+    for (acc, blob) in mock_db.all_accounts.iter() {
+        let total = AccountResource::try_from(blob).unwrap().sequence_number();
+        let limit = 3;
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": "get_latest_account_transactions",
+            "params": [acc.to_hex(), null, limit, false],
+            "id": 1,
+        });
+        let resp = client.post(&url).json(&request).send().unwrap();
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = resp.json().unwrap();
+        let tx_views: Vec<TransactionView> =
+            serde_json::from_value(resp_json["result"].clone()).unwrap();
+
+        let expected_seq_nums: Vec<_> = (0..total).rev().take(limit).collect();
+        let seq_nums: Vec<_> = tx_views
+            .iter()
+            .map(|tx_view| match &tx_view.transaction {
+                TransactionDataView::UserTransaction {
+                    sequence_number, ..
+                } => *sequence_number,
+                _ => panic!("not a user transaction"),
+            })
+            .collect();
+        assert_eq!(seq_nums, expected_seq_nums);
+    }
+}
+
 #[test]
 // Check that if version and ledger_version parameters are None, then the server returns the latest
 // known state.
@@ -1482,6 +2102,32 @@ fn test_get_account_state_with_proof() {
     assert_eq!(txn_info_with_proof, *expected_txn_info_with_proof);
 }
 
+#[test]
+fn test_get_account_states_with_proofs() {
+    let (mock_db, _runtime, url, _) = create_db_and_runtime();
+    let client = reqwest::blocking::Client::new();
+
+    let accounts: Vec<_> = mock_db.all_accounts.keys().take(3).collect();
+    let request = json!({
+        "jsonrpc": "2.0",
+        "method": "get_account_states_with_proofs",
+        "params": [accounts, 0, 0],
+        "id": 1,
+    });
+    let resp = client.post(&url).json(&request).send().unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp_json: serde_json::Value = resp.json().unwrap();
+    let received_proofs: Vec<AccountStateWithProofView> =
+        serde_json::from_value(resp_json["result"].clone()).unwrap();
+    assert_eq!(received_proofs.len(), accounts.len());
+
+    let expected_proof = get_first_state_proof_from_mock_db(&mock_db);
+    for received_proof in received_proofs {
+        let received_proof = AccountStateWithProof::try_from(&received_proof).unwrap();
+        assert_eq!(received_proof, expected_proof);
+    }
+}
+
 #[test]
 fn test_get_state_proof() {
     let (mock_db, client, _runtime) = create_database_client_and_runtime();
@@ -1522,6 +2168,43 @@ fn test_health_check() {
     assert_eq!(resp.status(), 200);
 }
 
+#[test]
+fn test_health_and_sync_status() {
+    let (mock_db, _runtime, url, _) = create_db_and_runtime();
+    let client = reqwest::blocking::Client::new();
+
+    let resp = client.get(&format!("{}/sync_status", url)).send().unwrap();
+    assert_eq!(resp.status(), 200);
+    let status: SyncStatus = resp.json().unwrap();
+    assert_eq!(status.ledger_version, mock_db.version);
+    // no state sync and no network run in the test
+    assert_eq!(status.highest_known_version, None);
+    assert_eq!(status.sync_lag_versions, None);
+    assert_eq!(status.sync_versions_per_sec, None);
+    assert_eq!(status.sync_eta_secs, None);
+    assert_eq!(status.connected_peers, PeerCounts::default());
+
+    let resp = client.get(&format!("{}/health", url)).send().unwrap();
+    assert_eq!(resp.status(), 200);
+    let health: HealthStatus = resp.json().unwrap();
+    assert!(health.healthy);
+    assert_eq!(health.sync_status, status);
+
+    // the sync lag and ETA aren't checked while state sync doesn't know the chain
+    let resp = client
+        .get(&format!(
+            "{}/health?max_sync_lag_versions=0&max_sync_eta_secs=0&max_ledger_age_secs={}",
+            url,
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        ))
+        .send()
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
 #[test]
 fn test_sdk_info_from_user_agent() {
     // Invalid user agents:
@@ -4,15 +4,15 @@
 use anyhow::{format_err, Error, Result};
 use diem_config::{
     config::{
-        RoleType, StreamConfig, DEFAULT_BATCH_SIZE_LIMIT, DEFAULT_CONTENT_LENGTH_LIMIT,
-        DEFAULT_PAGE_SIZE_LIMIT, DEFAULT_STREAM_RPC_MAX_POLL_INTERVAL_MS,
-        DEFAULT_STREAM_RPC_POLL_INTERVAL_MS, DEFAULT_STREAM_RPC_SEND_QUEUE_SIZE,
-        DEFAULT_STREAM_RPC_SUBSCRIPTION_FETCH_SIZE,
+        ApiKeyConfig, JsonRpcRateLimitConfig, RestConfig, RoleType, StreamConfig,
+        DEFAULT_BATCH_SIZE_LIMIT, DEFAULT_CONTENT_LENGTH_LIMIT, DEFAULT_PAGE_SIZE_LIMIT,
+        DEFAULT_STREAM_RPC_MAX_POLL_INTERVAL_MS, DEFAULT_STREAM_RPC_POLL_INTERVAL_MS,
+        DEFAULT_STREAM_RPC_SEND_QUEUE_SIZE, DEFAULT_STREAM_RPC_SUBSCRIPTION_FETCH_SIZE,
     },
     utils,
 };
 use diem_crypto::HashValue;
-use diem_mempool::{MempoolClientSender, SubmissionStatus};
+use diem_mempool::{MempoolClientRequest, MempoolClientSender};
 use diem_types::{
     account_address::AccountAddress,
     account_state::AccountState,
@@ -29,21 +29,18 @@ use diem_types::{
     },
     state_proof::StateProof,
     transaction::{
-        AccountTransactionsWithProof, SignedTransaction, Transaction, TransactionInfo,
-        TransactionListWithProof, TransactionWithProof, Version,
+        AccountTransactionsWithProof, Transaction, TransactionInfo, TransactionListWithProof,
+        TransactionWithProof, Version,
     },
     vm_status::KeptVMStatus,
 };
 use diemdb::test_helper::arb_blocks_to_commit;
 
-use crate::tests::genesis::generate_genesis_state;
+use crate::{rate_limit::RateLimiter, tests::genesis::generate_genesis_state};
 use diem_client::BlockingClient;
 use diem_proptest_helpers::ValueGenerator;
 use diem_types::account_config::FreezingBit;
-use futures::channel::{
-    mpsc::{channel, Receiver},
-    oneshot,
-};
+use futures::channel::mpsc::{channel, Receiver};
 use move_core_types::{
     language_storage::{ModuleId, StructTag, TypeTag},
     move_resource::MoveResource,
@@ -58,7 +55,7 @@ use std::{
     net::SocketAddr,
     sync::Arc,
 };
-use storage_interface::{DbReader, MoveDbReader, Order, StartupInfo, TreeState};
+use storage_interface::{DbReader, EventPage, MoveDbReader, Order, StartupInfo, TreeState};
 use tokio::runtime::Runtime;
 
 /// Creates JSON RPC server for a Validator node
@@ -68,6 +65,26 @@ pub fn test_bootstrap(
     address: SocketAddr,
     diem_db: Arc<dyn MoveDbReader>,
     mp_sender: MempoolClientSender,
+) -> Runtime {
+    test_bootstrap_with_access_control(
+        address,
+        diem_db,
+        mp_sender,
+        &JsonRpcRateLimitConfig::default(),
+        &ApiKeyConfig::default(),
+    )
+}
+
+/// Creates JSON RPC server for a Validator node, which controls the access to the calls by
+/// `rate_limit_config` and `api_key_config`
+/// Should only be used for unit-tests
+#[allow(unused)]
+pub fn test_bootstrap_with_access_control(
+    address: SocketAddr,
+    diem_db: Arc<dyn MoveDbReader>,
+    mp_sender: MempoolClientSender,
+    rate_limit_config: &JsonRpcRateLimitConfig,
+    api_key_config: &ApiKeyConfig,
 ) -> Runtime {
     let mut stream_config: StreamConfig = StreamConfig {
         enabled: true,
@@ -88,6 +105,9 @@ pub fn test_bootstrap(
         RoleType::Validator,
         ChainId::test(),
         &stream_config,
+        &RestConfig { enabled: true },
+        Arc::new(RateLimiter::new(rate_limit_config)),
+        api_key_config,
     )
 }
 
@@ -205,6 +225,40 @@ impl DbReader for MockDiemDB {
         Ok(AccountTransactionsWithProof::new(txns_with_proofs))
     }
 
+    fn get_latest_account_transactions(
+        &self,
+        address: AccountAddress,
+        seq_num: Option<u64>,
+        limit: u64,
+        include_events: bool,
+        ledger_version: u64,
+    ) -> Result<AccountTransactionsWithProof> {
+        let txns_with_proofs = self
+            .get_account_transactions(
+                address,
+                0,
+                u32::max_value() as u64,
+                include_events,
+                ledger_version,
+            )?
+            .into_inner()
+            .into_iter()
+            .rev()
+            .filter(|txn| match seq_num {
+                Some(seq_num) => {
+                    txn.transaction
+                        .as_signed_user_txn()
+                        .unwrap()
+                        .sequence_number()
+                        <= seq_num
+                }
+                None => true,
+            })
+            .take(limit as usize)
+            .collect();
+        Ok(AccountTransactionsWithProof::new(txns_with_proofs))
+    }
+
     fn get_transactions(
         &self,
         start_version: u64,
@@ -284,6 +338,45 @@ impl DbReader for MockDiemDB {
         Ok(events)
     }
 
+    fn get_events_page(
+        &self,
+        key: &EventKey,
+        start: Option<u64>,
+        order: Order,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<EventPage> {
+        let mut events: Vec<_> = self
+            .events
+            .iter()
+            .filter(|(version, e)| e.key() == key && *version <= ledger_version)
+            .cloned()
+            .collect();
+        events.sort_by_key(|(_, e)| e.sequence_number());
+        if order == Order::Descending {
+            events.reverse();
+        }
+        let events: Vec<_> = events
+            .into_iter()
+            .filter(|(_, e)| match (order, start) {
+                (_, None) => true,
+                (Order::Ascending, Some(start)) => e.sequence_number() >= start,
+                (Order::Descending, Some(start)) => e.sequence_number() <= start,
+            })
+            .take(limit as usize)
+            .collect();
+        let next_cursor = match (order, events.last()) {
+            (Order::Ascending, Some((_, e))) => Some(e.sequence_number() + 1),
+            (Order::Ascending, None) => Some(start.unwrap_or(0)),
+            (Order::Descending, Some((_, e))) => e.sequence_number().checked_sub(1),
+            (Order::Descending, None) => None,
+        };
+        Ok(EventPage {
+            events,
+            next_cursor,
+        })
+    }
+
     fn get_events_with_proofs(
         &self,
         _key: &EventKey,
@@ -521,15 +614,7 @@ pub fn create_database_client_and_runtime() -> (MockDiemDB, BlockingClient, Runt
 }
 
 #[allow(unused)]
-pub fn create_db_and_runtime() -> (
-    MockDiemDB,
-    Runtime,
-    String,
-    Receiver<(
-        SignedTransaction,
-        oneshot::Sender<anyhow::Result<SubmissionStatus>>,
-    )>,
-) {
+pub fn create_db_and_runtime() -> (MockDiemDB, Runtime, String, Receiver<MempoolClientRequest>) {
     let mock_db = mock_db();
 
     let host = "127.0.0.1";
@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use diem_json_rpc_types::Method;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::{fmt, str::FromStr};
@@ -118,3 +119,9 @@ pub fn sdk_info_from_user_agent(user_agent: Option<&str>) -> SdkInfo {
         None => SdkInfo::default(),
     }
 }
+
+/// Parses a JSON RPC method name of the node config, e.g. "get_transactions"
+pub fn parse_method(name: &str) -> anyhow::Result<Method> {
+    serde_json::from_value(name.into())
+        .map_err(|_| anyhow::format_err!("unknown JSON RPC method {}", name))
+}
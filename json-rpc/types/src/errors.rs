@@ -10,6 +10,7 @@ use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::time::Duration;
 
 /// list of server internal errors
 pub static INTERNAL_ERRORS: &[i16; 7] = &[
@@ -51,6 +52,11 @@ pub enum ServerCode {
     MempoolInvalidUpdate = -32010,
     MempoolVmError = -32011,
     MempoolUnknownError = -32012,
+    MempoolUnderpricedReplacement = -32013,
+
+    // Access control errors
+    RateLimited = -32014,
+    Unauthorized = -32015,
 }
 
 /// JSON RPC server error codes for invalid request
@@ -77,9 +83,45 @@ impl InvalidRequestCode {
     }
 }
 
+/// Machine readable details of an error. Clients should branch on these rather than on messages,
+/// which may change between releases.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum ErrorData {
+    /// VM status of a transaction the VM rejected, see `StatusCode`
     StatusCode(StatusCode),
+    /// Milliseconds to wait before retrying a rate limited call
+    RetryAfterMs(u64),
+    /// Why mempool didn't admit a submitted transaction
+    MempoolStatusCode(MempoolStatusCode),
+    Reason(ErrorReason),
+    /// The name of an invalid param of a call, and why it's invalid
+    InvalidParam {
+        param: String,
+        reason: ErrorReason,
+    },
+}
+
+/// Registry of the reasons of the errors whose code has several causes. Reasons are never renamed
+/// nor reused; new ones may be added.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorReason {
+    /// A version beyond the latest ledger version known by the server
+    VersionTooHigh,
+    /// A param with a value out of its valid range
+    InvalidValue,
+    /// A cursor that is malformed or belongs to another query
+    InvalidCursor,
+    /// A batch or page larger than the limits of the server
+    LimitExceeded,
+    /// A BCS response requested from a method that only returns JSON
+    BcsNotSupported,
+    /// A subscription whose id is already used by another subscription of the connection
+    SubscriptionExists,
+    /// An API key that isn't known by the server
+    UnknownApiKey,
+    /// A method the API key, or the public, isn't allowed to call
+    MethodNotAllowed,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -132,11 +174,11 @@ impl JsonRpcError {
         }
     }
 
-    pub fn invalid_request_with_msg(msg: String) -> Self {
+    pub fn invalid_request_with_msg(reason: ErrorReason, msg: String) -> Self {
         Self {
             code: InvalidRequestCode::InvalidRequest as i16,
             message: format!("Invalid Request: {}", msg),
-            data: None,
+            data: Some(ErrorData::Reason(reason)),
         }
     }
 
@@ -160,15 +202,19 @@ impl JsonRpcError {
         Self {
             code: InvalidRequestCode::InvalidParams as i16,
             message: format!("Invalid params: {}", msg),
-            data: None,
+            data: Some(ErrorData::Reason(ErrorReason::LimitExceeded)),
         }
     }
 
-    pub fn invalid_param(msg: &str) -> Self {
+    /// An invalid param named `param`, `msg` describing why it's invalid
+    pub fn invalid_param(param: &str, reason: ErrorReason, msg: &str) -> Self {
         Self {
             code: InvalidRequestCode::InvalidParams as i16,
-            message: format!("Invalid param {}", msg),
-            data: None,
+            message: format!("Invalid param {} {}", param, msg),
+            data: Some(ErrorData::InvalidParam {
+                param: param.to_string(),
+                reason,
+            }),
         }
     }
 
@@ -203,6 +249,7 @@ impl JsonRpcError {
             MempoolStatusCode::InvalidUpdate => ServerCode::MempoolInvalidUpdate,
             MempoolStatusCode::VmError => ServerCode::MempoolVmError,
             MempoolStatusCode::UnknownStatus => ServerCode::MempoolUnknownError,
+            MempoolStatusCode::UnderpricedReplacement => ServerCode::MempoolUnderpricedReplacement,
             MempoolStatusCode::Accepted => {
                 return Err(anyhow::format_err!(
                     "[JSON RPC] cannot create mempool error for mempool accepted status"
@@ -216,7 +263,7 @@ impl JsonRpcError {
                 "Server error: Mempool submission error: {:?}",
                 error.message
             ),
-            data: None,
+            data: Some(ErrorData::MempoolStatusCode(error.code)),
         })
     }
 
@@ -239,21 +286,74 @@ impl JsonRpcError {
         }
     }
 
+    pub fn rate_limited(retry_after: Duration) -> Self {
+        Self {
+            code: ServerCode::RateLimited as i16,
+            message: format!(
+                "Server error: rate limited, retry after {}ms",
+                retry_after.as_millis()
+            ),
+            data: Some(ErrorData::RetryAfterMs(retry_after.as_millis() as u64)),
+        }
+    }
+
+    pub fn unauthorized(reason: ErrorReason, msg: &str) -> Self {
+        Self {
+            code: ServerCode::Unauthorized as i16,
+            message: format!("Server error: unauthorized, {}", msg),
+            data: Some(ErrorData::Reason(reason)),
+        }
+    }
+
+    pub fn as_retry_after(&self) -> Option<Duration> {
+        if let Some(ErrorData::RetryAfterMs(millis)) = &self.data {
+            return Some(Duration::from_millis(*millis));
+        }
+        None
+    }
+
     pub fn as_status_code(&self) -> Option<StatusCode> {
         if let Some(ErrorData::StatusCode(data)) = &self.data {
             return Some(*data);
         }
         None
     }
+
+    pub fn as_mempool_status_code(&self) -> Option<MempoolStatusCode> {
+        if let Some(ErrorData::MempoolStatusCode(code)) = &self.data {
+            return Some(*code);
+        }
+        None
+    }
+
+    pub fn as_reason(&self) -> Option<ErrorReason> {
+        match &self.data {
+            Some(ErrorData::Reason(reason)) | Some(ErrorData::InvalidParam { reason, .. }) => {
+                Some(*reason)
+            }
+            _ => None,
+        }
+    }
+
+    /// The name of the invalid param of an invalid param error
+    pub fn as_invalid_param(&self) -> Option<&str> {
+        if let Some(ErrorData::InvalidParam { param, .. }) = &self.data {
+            return Some(param);
+        }
+        None
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::errors::{is_internal_error, JsonRpcError, ServerCode, INTERNAL_ERRORS};
+    use crate::errors::{
+        is_internal_error, ErrorReason, JsonRpcError, ServerCode, INTERNAL_ERRORS,
+    };
     use diem_types::{
         mempool_status::{MempoolStatus, MempoolStatusCode},
         vm_status::StatusCode,
     };
+    use std::time::Duration;
 
     #[test]
     fn test_vm_status() {
@@ -316,6 +416,10 @@ mod tests {
             MempoolStatusCode::UnknownStatus,
             ServerCode::MempoolUnknownError,
         );
+        assert_map_code(
+            MempoolStatusCode::UnderpricedReplacement,
+            ServerCode::MempoolUnderpricedReplacement,
+        );
     }
 
     #[test]
@@ -335,6 +439,14 @@ mod tests {
         assert!(!is_internal_error(&(ServerCode::VmValidationError as i16)));
     }
 
+    #[test]
+    fn test_rate_limited() {
+        let err = JsonRpcError::rate_limited(Duration::from_millis(1500));
+        assert_eq!(err.code, ServerCode::RateLimited as i16);
+        assert_eq!(err.as_retry_after(), Some(Duration::from_millis(1500)));
+        assert!(!is_internal_error(&err.code));
+    }
+
     fn assert_map_code(from: MempoolStatusCode, to: ServerCode) {
         let err = JsonRpcError::mempool_error(MempoolStatus {
             code: from,
@@ -342,5 +454,45 @@ mod tests {
         })
         .unwrap();
         assert_eq!(err.code, to as i16);
+        assert_eq!(err.as_mempool_status_code(), Some(from));
+    }
+
+    #[test]
+    fn test_error_data_json() {
+        let err = JsonRpcError::invalid_param(
+            "version",
+            ErrorReason::VersionTooHigh,
+            "should be <= known latest version 1",
+        );
+        assert_eq!(err.as_invalid_param(), Some("version"));
+        assert_eq!(err.as_reason(), Some(ErrorReason::VersionTooHigh));
+        assert_eq!(
+            err.serialize()["data"],
+            serde_json::json!({"InvalidParam": {"param": "version", "reason": "version_too_high"}})
+        );
+
+        let err = JsonRpcError::invalid_request_with_msg(
+            ErrorReason::LimitExceeded,
+            "page size = 1001, exceed limit 1000".to_string(),
+        );
+        assert_eq!(err.as_invalid_param(), None);
+        assert_eq!(
+            err.serialize()["data"],
+            serde_json::json!({"Reason": "limit_exceeded"})
+        );
+
+        let err =
+            JsonRpcError::mempool_error(MempoolStatus::new(MempoolStatusCode::InvalidSeqNumber))
+                .unwrap();
+        assert_eq!(
+            err.serialize()["data"],
+            serde_json::json!({"MempoolStatusCode": "InvalidSeqNumber"})
+        );
+
+        let err = JsonRpcError::vm_status(StatusCode::SEQUENCE_NUMBER_TOO_OLD);
+        assert_eq!(
+            err.serialize()["data"],
+            serde_json::json!({ "StatusCode": StatusCode::SEQUENCE_NUMBER_TOO_OLD as u64 })
+        );
     }
 }
@@ -47,6 +47,7 @@ pub enum Method {
     GetEvents,
     GetCurrencies,
     GetNetworkStatus,
+    GetAccountTransactionsPending,
 
     //
     // Experimental APIs
@@ -59,6 +60,11 @@ pub enum Method {
     GetAccountTransactionsWithProofs,
     GetEventsWithProofs,
     GetEventByVersionWithProof,
+    GetEventsPage,
+    GetLatestAccountTransactions,
+    GetAccountStatesWithProofs,
+    SimulateTransaction,
+    SubmitBatch,
 }
 
 impl Method {
@@ -73,6 +79,7 @@ impl Method {
             Method::GetEvents => "get_events",
             Method::GetCurrencies => "get_currencies",
             Method::GetNetworkStatus => "get_network_status",
+            Method::GetAccountTransactionsPending => "get_account_transactions_pending",
             Method::GetResources => "get_resources",
             Method::GetStateProof => "get_state_proof",
             Method::GetAccumulatorConsistencyProof => "get_accumulator_consistency_proof",
@@ -81,6 +88,11 @@ impl Method {
             Method::GetAccountTransactionsWithProofs => "get_account_transactions_with_proofs",
             Method::GetEventsWithProofs => "get_events_with_proofs",
             Method::GetEventByVersionWithProof => "get_event_by_version_with_proof",
+            Method::GetEventsPage => "get_events_page",
+            Method::GetLatestAccountTransactions => "get_latest_account_transactions",
+            Method::GetAccountStatesWithProofs => "get_account_states_with_proofs",
+            Method::SimulateTransaction => "simulate_transaction",
+            Method::SubmitBatch => "submit_batch",
         }
     }
 }
@@ -73,6 +73,7 @@ pub enum MethodRequest {
     GetEvents(GetEventsParams),
     GetCurrencies(GetCurrenciesParams),
     GetNetworkStatus(GetNetworkStatusParams),
+    GetAccountTransactionsPending(GetAccountTransactionsPendingParams),
 
     //
     // Experimental APIs
@@ -85,6 +86,11 @@ pub enum MethodRequest {
     GetAccountTransactionsWithProofs(GetAccountTransactionsWithProofsParams),
     GetEventsWithProofs(GetEventsWithProofsParams),
     GetEventByVersionWithProof(GetEventByVersionWithProof),
+    GetEventsPage(GetEventsPageParams),
+    GetLatestAccountTransactions(GetLatestAccountTransactionsParams),
+    GetAccountStatesWithProofs(GetAccountStatesWithProofsParams),
+    SimulateTransaction(SimulateTransactionParams),
+    SubmitBatch(SubmitBatchParams),
 }
 
 impl MethodRequest {
@@ -107,6 +113,9 @@ impl MethodRequest {
             Method::GetNetworkStatus => {
                 MethodRequest::GetNetworkStatus(serde_json::from_value(value)?)
             }
+            Method::GetAccountTransactionsPending => {
+                MethodRequest::GetAccountTransactionsPending(serde_json::from_value(value)?)
+            }
             Method::GetResources => MethodRequest::GetResources(serde_json::from_value(value)?),
             Method::GetStateProof => MethodRequest::GetStateProof(serde_json::from_value(value)?),
             Method::GetAccumulatorConsistencyProof => {
@@ -127,6 +136,17 @@ impl MethodRequest {
             Method::GetEventByVersionWithProof => {
                 MethodRequest::GetEventByVersionWithProof(serde_json::from_value(value)?)
             }
+            Method::GetEventsPage => MethodRequest::GetEventsPage(serde_json::from_value(value)?),
+            Method::GetLatestAccountTransactions => {
+                MethodRequest::GetLatestAccountTransactions(serde_json::from_value(value)?)
+            }
+            Method::GetAccountStatesWithProofs => {
+                MethodRequest::GetAccountStatesWithProofs(serde_json::from_value(value)?)
+            }
+            Method::SimulateTransaction => {
+                MethodRequest::SimulateTransaction(serde_json::from_value(value)?)
+            }
+            Method::SubmitBatch => MethodRequest::SubmitBatch(serde_json::from_value(value)?),
         };
 
         Ok(method_request)
@@ -143,6 +163,9 @@ impl MethodRequest {
             MethodRequest::GetEvents(_) => Method::GetEvents,
             MethodRequest::GetCurrencies(_) => Method::GetCurrencies,
             MethodRequest::GetNetworkStatus(_) => Method::GetNetworkStatus,
+            MethodRequest::GetAccountTransactionsPending(_) => {
+                Method::GetAccountTransactionsPending
+            }
             MethodRequest::GetResources(_) => Method::GetResources,
             MethodRequest::GetStateProof(_) => Method::GetStateProof,
             MethodRequest::GetAccumulatorConsistencyProof(_) => {
@@ -155,6 +178,11 @@ impl MethodRequest {
             }
             MethodRequest::GetEventsWithProofs(_) => Method::GetEventsWithProofs,
             MethodRequest::GetEventByVersionWithProof(_) => Method::GetEventByVersionWithProof,
+            MethodRequest::GetEventsPage(_) => Method::GetEventsPage,
+            MethodRequest::GetLatestAccountTransactions(_) => Method::GetLatestAccountTransactions,
+            MethodRequest::GetAccountStatesWithProofs(_) => Method::GetAccountStatesWithProofs,
+            MethodRequest::SimulateTransaction(_) => Method::SimulateTransaction,
+            MethodRequest::SubmitBatch(_) => Method::SubmitBatch,
         }
     }
 }
@@ -166,6 +194,23 @@ pub struct SubmitParams {
     pub data: SignedTransaction,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SimulateTransactionParams {
+    #[serde(serialize_with = "serialize_signed_transaction")]
+    #[serde(deserialize_with = "deserialize_signed_transaction")]
+    pub data: SignedTransaction,
+    /// Set to true to simulate a transaction that is not signed yet
+    #[serde(default)]
+    pub skip_signature_check: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SubmitBatchParams {
+    #[serde(serialize_with = "serialize_signed_transactions")]
+    #[serde(deserialize_with = "deserialize_signed_transactions")]
+    pub data: Vec<SignedTransaction>,
+}
+
 fn serialize_signed_transaction<S>(
     txn: &SignedTransaction,
     serializer: S,
@@ -188,6 +233,36 @@ where
         .map_err(|_| D::Error::custom("expected hex-encoded SignedTransaction"))
 }
 
+fn serialize_signed_transactions<S>(
+    txns: &[SignedTransaction],
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    use serde::ser::Error;
+    txns.iter()
+        .map(|txn| bcs::to_bytes(txn).map(BytesView::new))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(S::Error::custom)?
+        .serialize(serializer)
+}
+
+fn deserialize_signed_transactions<'de, D>(
+    deserializer: D,
+) -> Result<Vec<SignedTransaction>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+    Vec::<BytesView>::deserialize(deserializer)
+        .map_err(|_| D::Error::custom("expected a list of hex-encoded SignedTransaction"))?
+        .iter()
+        .map(|bytes| bcs::from_bytes(bytes.inner()))
+        .collect::<Result<_, _>>()
+        .map_err(|_| D::Error::custom("expected a list of hex-encoded SignedTransaction"))
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct GetMetadataParams {
     #[serde(default)]
@@ -227,6 +302,9 @@ pub struct GetTransactionsParams {
     pub start_version: u64,
     pub limit: u64,
     pub include_events: bool,
+    /// The `next_cursor` of a previous response, takes precedence over `start_version`
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -249,6 +327,9 @@ pub struct GetEventsParams {
     pub key: EventKey,
     pub start: u64,
     pub limit: u64,
+    /// The `next_cursor` of a previous response, takes precedence over `start`
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -279,6 +360,11 @@ impl<'de> Deserialize<'de> for GetNetworkStatusParams {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetAccountTransactionsPendingParams {
+    pub account: AccountAddress,
+}
+
 /// A de::Visitor implementation for jsonrpc param structs without any parameters
 struct NoParamsVisitor(&'static str);
 impl<'de> de::Visitor<'de> for NoParamsVisitor {
@@ -384,6 +470,47 @@ pub struct GetEventByVersionWithProof {
     pub version: Option<u64>,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventOrder {
+    Ascending,
+    Descending,
+}
+
+impl Default for EventOrder {
+    fn default() -> Self {
+        EventOrder::Ascending
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetEventsPageParams {
+    pub key: EventKey,
+    /// None starts from the first event ascending, or from the latest one descending
+    pub start: Option<u64>,
+    pub limit: u64,
+    #[serde(default)]
+    pub order: EventOrder,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetLatestAccountTransactionsParams {
+    pub account: AccountAddress,
+    /// None starts from the latest transaction
+    pub start: Option<u64>,
+    pub limit: u64,
+    pub include_events: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetAccountStatesWithProofsParams {
+    pub accounts: Vec<AccountAddress>,
+    #[serde(default)]
+    pub version: Option<u64>,
+    #[serde(default)]
+    pub ledger_version: Option<u64>,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -746,6 +873,35 @@ mod test {
         serde_json::from_value::<JsonRpcRequest>(request).unwrap();
     }
 
+    #[test]
+    fn get_account_transactions_pending() {
+        let parse_ok =
+            |value| serde_json::from_value::<GetAccountTransactionsPendingParams>(value).unwrap();
+        let parse_err = |value| {
+            serde_json::from_value::<GetAccountTransactionsPendingParams>(value).unwrap_err()
+        };
+
+        let account = "1668f6be25668c1a17cd8caf6b8d2f25";
+
+        // Array with all params
+        parse_ok(json!([account]));
+
+        // Array with too many params
+        parse_err(json!([account, 11]));
+
+        // Array with wrong param
+        parse_err(json!(["foo"]));
+
+        // Empty array without required params should fail
+        parse_err(json!([]));
+
+        // Object params
+        parse_ok(json!({ "account": account }));
+
+        // Object without required params should fail
+        parse_err(json!({}));
+    }
+
     #[test]
     fn get_state_proof() {
         let parse_ok = |value| serde_json::from_value::<GetStateProofParams>(value).unwrap();
@@ -997,4 +1153,91 @@ mod test {
         // Object with more params
         parse_ok(json!({ "key": key, "version": 10, "foo": 99 }));
     }
+
+    #[test]
+    fn get_events_page() {
+        let parse = serde_json::from_value::<GetEventsPageParams>;
+        let parse_ok = |value| parse(value).unwrap();
+        let parse_err = |value| parse(value).unwrap_err();
+
+        let key = "13000000000000000000000000000000000000000a550c18";
+
+        // Correct arguments
+        let params = parse_ok(json!([key, 10, 11, "descending"]));
+        assert_eq!(params.start, Some(10));
+        assert_eq!(params.order, EventOrder::Descending);
+        let params = parse_ok(json!([key, null, 11]));
+        assert_eq!(params.start, None);
+        assert_eq!(params.order, EventOrder::Ascending);
+
+        // Incorrect arguments
+        parse_err(json!([key, 10, 11, "sideways"]));
+        parse_err(json!([key, 10, 11, "descending", false]));
+        parse_err(json!([key, 10]));
+        parse_err(json!(["foo", 10, 11]));
+        parse_err(json!([]));
+        parse_err(json!({}));
+
+        // Object params
+        parse_ok(json!({ "key": key, "start": 10, "limit": 11, "order": "ascending" }));
+        parse_ok(json!({ "key": key, "limit": 11 }));
+
+        // Object without all required params
+        parse_err(json!({ "key": key, "start": 10 }));
+    }
+
+    #[test]
+    fn get_latest_account_transactions() {
+        let parse = serde_json::from_value::<GetLatestAccountTransactionsParams>;
+        let parse_ok = |value| parse(value).unwrap();
+        let parse_err = |value| parse(value).unwrap_err();
+
+        let account = "000000000000000000000000000000dd";
+
+        // Correct arguments
+        let params = parse_ok(json!([account, 10, 11, true]));
+        assert_eq!(params.start, Some(10));
+        let params = parse_ok(json!([account, null, 11, false]));
+        assert_eq!(params.start, None);
+
+        // Incorrect arguments
+        parse_err(json!([account, 10, 11]));
+        parse_err(json!([account, 10, 11, true, 12]));
+        parse_err(json!(["foo", 10, 11, true]));
+        parse_err(json!([]));
+        parse_err(json!({}));
+
+        // Object params
+        parse_ok(json!({ "account": account, "start": 10, "limit": 11, "include_events": true }));
+        parse_ok(json!({ "account": account, "limit": 11, "include_events": true }));
+    }
+
+    #[test]
+    fn get_account_states_with_proofs() {
+        let parse = serde_json::from_value::<GetAccountStatesWithProofsParams>;
+        let parse_ok = |value| parse(value).unwrap();
+        let parse_err = |value| parse(value).unwrap_err();
+
+        let account1 = "1668f6be25668c1a17cd8caf6b8d2f25";
+        let account2 = "000000000000000000000000000000dd";
+
+        // Array with all params
+        let params = parse_ok(json!([[account1, account2], 11, 12]));
+        assert_eq!(params.accounts.len(), 2);
+
+        // Array without optional params
+        parse_ok(json!([[account1]]));
+        parse_ok(json!([[], 12]));
+
+        // Incorrect arguments
+        parse_err(json!([account1, 11, 12]));
+        parse_err(json!([[account1], 11, 12, "foo"]));
+        parse_err(json!([["foo"], 11, 12]));
+        parse_err(json!([]));
+        parse_err(json!({}));
+
+        // Object params
+        parse_ok(json!({ "accounts": [account1, account2], "version": 10, "ledger_version": 10 }));
+        parse_ok(json!({ "accounts": [account1], "ledger_version": 10 }));
+    }
 }
@@ -22,6 +22,9 @@ pub struct JsonRpcResponse {
     pub result: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<JsonRpcError>,
+    /// The cursor of the next page of a paged method, None once the last page is reached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 impl JsonRpcResponse {
@@ -38,6 +41,7 @@ impl JsonRpcResponse {
             id: None,
             result: None,
             error: None,
+            next_cursor: None,
         }
     }
 }
@@ -57,5 +61,6 @@ mod tests {
         assert!(resp.id.is_none());
         assert!(resp.result.is_none());
         assert!(resp.error.is_none());
+        assert!(resp.next_cursor.is_none());
     }
 }
@@ -1,8 +1,11 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{errors::JsonRpcError, request::RawJsonRpcRequest, Id, JsonRpcVersion};
-use diem_types::event::EventKey;
+use crate::{
+    errors::JsonRpcError, request::RawJsonRpcRequest, views::TransactionDataView, Id,
+    JsonRpcVersion,
+};
+use diem_types::{account_address::AccountAddress, event::EventKey};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
@@ -74,6 +77,7 @@ impl FromStr for StreamJsonRpcRequest {
 pub enum StreamMethodRequest {
     SubscribeToTransactions(SubscribeToTransactionsParams),
     SubscribeToEvents(SubscribeToEventsParams),
+    SubscribeToTransactionStatus(SubscribeToTransactionStatusParams),
     Unsubscribe,
 }
 
@@ -93,6 +97,9 @@ impl StreamMethodRequest {
             StreamMethod::SubscribeToEvents => {
                 StreamMethodRequest::SubscribeToEvents(serde_json::from_value(value)?)
             }
+            StreamMethod::SubscribeToTransactionStatus => {
+                StreamMethodRequest::SubscribeToTransactionStatus(serde_json::from_value(value)?)
+            }
             StreamMethod::Unsubscribe => StreamMethodRequest::Unsubscribe,
         };
 
@@ -105,6 +112,9 @@ impl StreamMethodRequest {
                 StreamMethod::SubscribeToTransactions
             }
             StreamMethodRequest::SubscribeToEvents(_) => StreamMethod::SubscribeToEvents,
+            StreamMethodRequest::SubscribeToTransactionStatus(_) => {
+                StreamMethod::SubscribeToTransactionStatus
+            }
             StreamMethodRequest::Unsubscribe => StreamMethod::Unsubscribe,
         }
     }
@@ -115,6 +125,7 @@ impl StreamMethodRequest {
 pub enum StreamMethod {
     SubscribeToTransactions,
     SubscribeToEvents,
+    SubscribeToTransactionStatus,
     Unsubscribe,
 }
 
@@ -123,6 +134,7 @@ impl StreamMethod {
         match self {
             StreamMethod::SubscribeToTransactions => "subscribe_to_transactions",
             StreamMethod::SubscribeToEvents => "subscribe_to_events",
+            StreamMethod::SubscribeToTransactionStatus => "subscribe_to_transaction_status",
             StreamMethod::Unsubscribe => "unsubscribe",
         }
     }
@@ -134,8 +146,34 @@ pub struct SubscribeToEventsParams {
     pub event_seq_num: u64,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubscribeToTransactionStatusParams {
+    /// Only sends the status of the transactions sent by this account, when set
+    #[serde(default)]
+    pub sender: Option<AccountAddress>,
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub struct SubscribeToTransactionsParams {
     pub starting_version: u64,
     pub include_events: Option<bool>,
+    /// Only sends the transactions sent by this account, when set
+    #[serde(default)]
+    pub sender: Option<AccountAddress>,
+    /// Leaves out the block metadata and writeset transactions, when set to true
+    #[serde(default)]
+    pub user_transactions_only: Option<bool>,
+}
+
+impl SubscribeToTransactionsParams {
+    /// Whether a transaction passes the filters of the subscription.
+    pub fn matches(&self, transaction: &TransactionDataView) -> bool {
+        match transaction {
+            TransactionDataView::UserTransaction { sender, .. } => {
+                self.sender.map_or(true, |expected| *sender == expected)
+            }
+            _ => self.sender.is_none() && !self.user_transactions_only.unwrap_or(false),
+        }
+    }
 }
@@ -4,7 +4,7 @@
 use crate::{
     errors::JsonRpcError,
     stream::request::StreamMethod,
-    views::{EventView, TransactionView},
+    views::{EventView, TransactionStatusView, TransactionView},
     Id, JsonRpcVersion,
 };
 use serde::{Deserialize, Serialize};
@@ -15,6 +15,7 @@ use std::str::FromStr;
 pub enum StreamJsonRpcResponseView {
     Transaction(TransactionView),
     Event(EventView),
+    TransactionStatus(TransactionStatusView),
     SubscribeResult(SubscribeResult),
     UnsubscribeResult(UnsubscribeResult),
 }
@@ -37,6 +38,9 @@ impl StreamJsonRpcResponseView {
                 Self::Transaction(serde_json::from_value(value)?)
             }
             StreamMethod::SubscribeToEvents => Self::Event(serde_json::from_value(value)?),
+            StreamMethod::SubscribeToTransactionStatus => {
+                Self::TransactionStatus(serde_json::from_value(value)?)
+            }
             StreamMethod::Unsubscribe => Self::UnsubscribeResult(serde_json::from_value(value)?),
         })
     }
@@ -1,10 +1,12 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::errors::JsonRpcError;
 use anyhow::{ensure, format_err, Error, Result};
 use diem_crypto::hash::{CryptoHash, HashValue};
 use diem_transaction_builder::{error_explain, stdlib::ScriptCall};
 use diem_types::{
+    access_path::{AccessPath, Path},
     account_config::{
         AccountResource, AccountRole, AdminTransactionEvent, BalanceResource, BaseUrlRotationEvent,
         BurnEvent, CancelBurnEvent, ComplianceKeyRotationEvent, CreateAccountEvent,
@@ -23,10 +25,11 @@ use diem_types::{
     },
     state_proof::StateProof,
     transaction::{
-        AccountTransactionsWithProof, Script, ScriptFunction, Transaction, TransactionArgument,
-        TransactionInfo, TransactionListWithProof, TransactionPayload,
+        AccountTransactionsWithProof, Script, ScriptFunction, SignedTransaction, Transaction,
+        TransactionArgument, TransactionInfo, TransactionListWithProof, TransactionPayload,
     },
     vm_status::KeptVMStatus,
+    write_set::WriteOp,
 };
 use hex::FromHex;
 use move_core_types::{
@@ -40,6 +43,7 @@ use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     collections::BTreeMap,
     convert::{TryFrom, TryInto},
+    ops::Range,
 };
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -275,6 +279,13 @@ impl TryFrom<(u64, ContractEvent)> for EventView {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct EventPageView {
+    pub events: Vec<EventView>,
+    /// The `start` of the next page, None once a descending read reached the first event
+    pub next_cursor: Option<u64>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct EventWithProofView {
     pub event_with_proof: BytesView,
@@ -815,6 +826,54 @@ impl From<&KeptVMStatus> for VMStatusView {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct TransactionSimulationView {
+    pub vm_status: VMStatusView,
+    pub gas_used: u64,
+    /// The max gas amount suggested for the transaction, from the gas it uses when its own max
+    /// gas amount doesn't limit it. None if it can't be measured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggested_max_gas_amount: Option<u64>,
+    pub write_set: Vec<WriteSetChangeView>,
+    pub events: Vec<EventView>,
+}
+
+/// Mempool admission status of a transaction of `submit_batch`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SubmissionStatusView {
+    pub hash: HashValue,
+    pub accepted: bool,
+    /// The error `submit` returns for the transaction when it's not accepted
+    pub error: Option<JsonRpcError>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct WriteSetChangeView {
+    pub address: AccountAddress,
+    /// `resource` or `module`, or `unknown` for paths of neither
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// The struct tag of the resource, the id of the module, or the hex-encoded unknown path
+    pub name: String,
+    pub deleted: bool,
+}
+
+impl From<&(AccessPath, WriteOp)> for WriteSetChangeView {
+    fn from((access_path, write_op): &(AccessPath, WriteOp)) -> Self {
+        let (type_, name) = match bcs::from_bytes::<Path>(&access_path.path) {
+            Ok(Path::Resource(tag)) => ("resource", tag.to_string()),
+            Ok(Path::Code(module_id)) => ("module", module_id.to_string()),
+            Err(_) => ("unknown", hex::encode(&access_path.path)),
+        };
+        WriteSetChangeView {
+            address: access_path.address,
+            type_: type_.to_string(),
+            name,
+            deleted: matches!(write_op, WriteOp::Deletion),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct TransactionView {
     pub version: u64,
@@ -859,6 +918,70 @@ impl TransactionListView {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct AccountTransactionsPendingView {
+    pub sequence_number: u64,
+    pub transactions: Vec<PendingTransactionView>,
+    pub sequence_gaps: Vec<SequenceGapView>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct PendingTransactionView {
+    pub transaction: TransactionDataView,
+    pub hash: HashValue,
+    pub bytes: BytesView,
+    // whether the transaction waits on the transactions of missing sequence numbers
+    pub is_parked: bool,
+}
+
+impl PendingTransactionView {
+    pub fn try_from_signed_txn(txn: SignedTransaction, is_parked: bool) -> Result<Self> {
+        let tx = Transaction::UserTransaction(txn);
+        Ok(PendingTransactionView {
+            hash: tx.hash(),
+            bytes: BytesView::new(bcs::to_bytes(&tx)?),
+            transaction: TransactionDataView::from(tx),
+            is_parked,
+        })
+    }
+}
+
+/// Lifecycle event of a transaction in mempool, see `subscribe_to_transaction_status`.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TransactionStatusView {
+    pub sender: AccountAddress,
+    pub sequence_number: u64,
+    pub hash: HashValue,
+    pub stage: TransactionStageView,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStageView {
+    Accepted,
+    Broadcast,
+    IncludedInBlock,
+    Committed,
+    Expired,
+    Evicted,
+}
+
+/// Missing sequence numbers from `start` up to `end`, excluded.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SequenceGapView {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl From<Range<u64>> for SequenceGapView {
+    fn from(range: Range<u64>) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
 impl TryFrom<TransactionListWithProof> for TransactionListView {
     type Error = Error;
 
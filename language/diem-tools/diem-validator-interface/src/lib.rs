@@ -7,17 +7,17 @@ mod storage_interface;
 pub use crate::storage_interface::DBDebuggerInterface;
 pub use json_rpc_interface::JsonRpcDebuggerInterface;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use diem_state_view::StateView;
 use diem_types::{
     access_path::AccessPath,
     account_address::AccountAddress,
     account_config,
     account_state::AccountState,
-    contract_event::EventWithProof,
+    contract_event::{ContractEvent, EventWithProof},
     event::EventKey,
     on_chain_config::ValidatorSet,
-    transaction::{Transaction, Version},
+    transaction::{Transaction, TransactionInfo, Version},
 };
 use move_binary_format::file_format::CompiledModule;
 
@@ -31,6 +31,15 @@ pub trait DiemValidatorInterface: Sync {
         -> Result<Vec<EventWithProof>>;
     fn get_committed_transactions(&self, start: Version, limit: u64) -> Result<Vec<Transaction>>;
     fn get_latest_version(&self) -> Result<Version>;
+    /// The results stored for the transactions from version `start`: their infos, and the events
+    /// they emitted.
+    fn get_committed_transaction_results(
+        &self,
+        _start: Version,
+        _limit: u64,
+    ) -> Result<Vec<(TransactionInfo, Vec<ContractEvent>)>> {
+        bail!("The results of the transactions can only be read from a DB")
+    }
     fn get_version_by_account_sequence(
         &self,
         account: AccountAddress,
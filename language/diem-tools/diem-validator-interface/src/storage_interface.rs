@@ -7,9 +7,9 @@ use diem_config::config::RocksdbConfig;
 use diem_types::{
     account_address::AccountAddress,
     account_state::AccountState,
-    contract_event::EventWithProof,
+    contract_event::{ContractEvent, EventWithProof},
     event::EventKey,
-    transaction::{Transaction, Version},
+    transaction::{Transaction, TransactionInfo, Version},
 };
 use diemdb::DiemDB;
 use std::{convert::TryFrom, path::Path, sync::Arc};
@@ -57,6 +57,25 @@ impl DiemValidatorInterface for DBDebuggerInterface {
             .transactions)
     }
 
+    fn get_committed_transaction_results(
+        &self,
+        start: Version,
+        limit: u64,
+    ) -> Result<Vec<(TransactionInfo, Vec<ContractEvent>)>> {
+        let txns = self
+            .0
+            .get_transactions(start, limit, self.get_latest_version()?, true)?;
+        let events = txns
+            .events
+            .ok_or_else(|| anyhow!("DB didn't return the events of the transactions"))?;
+        Ok(txns
+            .proof
+            .transaction_infos
+            .into_iter()
+            .zip(events)
+            .collect())
+    }
+
     fn get_latest_version(&self) -> Result<Version> {
         let (version, _) = self
             .0
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::{anyhow, bail, format_err, Result};
+use diem_crypto::HashValue;
 use diem_resource_viewer::{AnnotatedAccountStateBlob, AnnotatedMoveStruct, DiemValueAnnotator};
 use diem_state_view::StateView;
 use diem_types::{
@@ -11,15 +12,21 @@ use diem_types::{
     account_state::AccountState,
     contract_event::{ContractEvent, EventWithProof},
     event::EventKey,
-    transaction::{ChangeSet, Transaction, TransactionOutput, Version, WriteSetPayload},
-    write_set::WriteOp,
+    transaction::{
+        ChangeSet, Transaction, TransactionInfo, TransactionOutput, TransactionStatus, Version,
+        WriteSetPayload,
+    },
+    write_set::{WriteOp, WriteSet},
 };
 use diem_validator_interface::{
     DBDebuggerInterface, DebuggerStateView, DiemValidatorInterface, JsonRpcDebuggerInterface,
 };
 use diem_vm::{
-    convert_changeset_and_events, data_cache::RemoteStorage, logging::AdapterLogSchema, DiemVM,
-    VMExecutor,
+    convert_changeset_and_events,
+    data_cache::RemoteStorage,
+    execution_trace::{ExecutionTracer, TransactionTrace},
+    logging::AdapterLogSchema,
+    DiemVM, VMExecutor,
 };
 use move_binary_format::{errors::VMResult, file_format::CompiledModule};
 use move_cli::sandbox::utils::on_disk_state_view::OnDiskStateView;
@@ -28,15 +35,41 @@ use move_lang::{compiled_unit::AnnotatedCompiledUnit, Compiler, Flags};
 use move_vm_runtime::{move_vm::MoveVM, session::Session};
 use move_vm_test_utils::DeltaStorage;
 use move_vm_types::gas_schedule::GasStatus;
-use std::path::{Path, PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Debug},
+    path::{Path, PathBuf},
+};
 
 #[cfg(test)]
 mod unit_tests;
 
+/// The most transactions fetched at once, the DB and the JSON-RPC API both refuse more.
+const FETCH_PAGE_SIZE: u64 = 1000;
+
+/// The first transaction whose replay diverges from the result stored when it was committed.
+#[derive(Debug)]
+pub struct Divergence {
+    pub version: Version,
+    /// One line per difference between the stored result and the replayed output
+    pub differences: Vec<String>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Transaction {} diverges:", self.version)?;
+        for difference in &self.differences {
+            write!(f, "\n  {}", difference)?;
+        }
+        Ok(())
+    }
+}
+
 pub struct DiemDebugger {
     debugger: Box<dyn DiemValidatorInterface>,
     build_dir: PathBuf,
     storage_dir: PathBuf,
+    tracer: Option<ExecutionTracer>,
 }
 
 impl DiemDebugger {
@@ -45,9 +78,16 @@ impl DiemDebugger {
             debugger,
             build_dir: PathBuf::from(move_cli::DEFAULT_BUILD_DIR),
             storage_dir: PathBuf::from(move_cli::DEFAULT_STORAGE_DIR),
+            tracer: None,
         }
     }
 
+    /// Appends the traces of the replayed transactions to `tracer`
+    pub fn with_execution_tracer(mut self, tracer: ExecutionTracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
     pub fn json_rpc(url: &str) -> Result<Self> {
         Ok(Self::new(Box::new(JsonRpcDebuggerInterface::new(url)?)))
     }
@@ -64,8 +104,11 @@ impl DiemDebugger {
         txns: Vec<Transaction>,
     ) -> Result<Vec<TransactionOutput>> {
         let state_view = DebuggerStateView::new(&*self.debugger, version);
-        DiemVM::execute_block(txns, &state_view)
-            .map_err(|err| format_err!("Unexpected VM Error: {:?}", err))
+        match &self.tracer {
+            Some(tracer) => DiemVM::execute_block_with_tracer(txns, &state_view, tracer),
+            None => DiemVM::execute_block(txns, &state_view),
+        }
+        .map_err(|err| format_err!("Unexpected VM Error: {:?}", err))
     }
 
     pub fn execute_past_transactions(
@@ -91,6 +134,98 @@ impl DiemDebugger {
         Ok(ret)
     }
 
+    /// Replays the transactions from version `begin` to `begin + limit`, and compares each output
+    /// with the result stored when the transaction was committed: its status, gas used, events,
+    /// and the states of the accounts it writes to. Stops at the first transaction that diverges,
+    /// or at the latest version. The transactions are fetched `FETCH_PAGE_SIZE` at a time.
+    /// The write sets aren't stored, so the writes of the stored result to accounts the replay
+    /// doesn't write to go unnoticed.
+    pub fn verify_past_transactions(
+        &self,
+        mut begin: Version,
+        limit: u64,
+    ) -> Result<Option<Divergence>> {
+        let end = begin.saturating_add(limit);
+        while begin < end {
+            let page_size = std::cmp::min(end - begin, FETCH_PAGE_SIZE);
+            let mut txns = self.debugger.get_committed_transactions(begin, page_size)?;
+            let mut results = self
+                .debugger
+                .get_committed_transaction_results(begin, page_size)?;
+            if txns.len() != results.len() {
+                bail!(
+                    "Got {} transactions but {} results",
+                    txns.len(),
+                    results.len()
+                );
+            }
+            if txns.is_empty() {
+                // Past the latest version
+                break;
+            }
+            while !txns.is_empty() {
+                println!(
+                    "Starting epoch verification at {:?}, {:?} transactions remaining",
+                    begin,
+                    end - begin
+                );
+                let outputs = self.execute_transactions_by_epoch(begin, txns.clone(), false)?;
+                if outputs.is_empty() {
+                    bail!("The replay of version {} produced no output", begin);
+                }
+                for ((version, output), (info, events)) in (begin..).zip(&outputs).zip(&results) {
+                    let mut differences = diff_transaction_output(output, info, events);
+                    differences.append(&mut self.diff_write_set(version, output.write_set())?);
+                    if !differences.is_empty() {
+                        return Ok(Some(Divergence {
+                            version,
+                            differences,
+                        }));
+                    }
+                }
+                begin += outputs.len() as u64;
+                txns = txns.split_off(outputs.len());
+                results = results.split_off(outputs.len());
+            }
+        }
+        Ok(None)
+    }
+
+    /// Compares the states of the accounts `write_set` writes to, as stored after the transaction
+    /// at `version`, with their states before it updated by `write_set`.
+    fn diff_write_set(&self, version: Version, write_set: &WriteSet) -> Result<Vec<String>> {
+        let mut writes: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (access_path, op) in write_set {
+            writes
+                .entry(access_path.address)
+                .or_default()
+                .push((access_path.path.clone(), op));
+        }
+
+        let mut differences = vec![];
+        for (account, writes) in writes {
+            let mut replayed = match version {
+                0 => None,
+                _ => self
+                    .debugger
+                    .get_account_state_by_version(account, version - 1)?,
+            }
+            .unwrap_or_default();
+            for (path, op) in writes {
+                match op {
+                    WriteOp::Value(value) => replayed.insert(path, value.clone()),
+                    WriteOp::Deletion => replayed.remove(&path),
+                };
+            }
+            let stored = self
+                .debugger
+                .get_account_state_by_version(account, version)?
+                .unwrap_or_default();
+            diff_account_states(&mut differences, account, &stored, &replayed);
+        }
+        Ok(differences)
+    }
+
     pub fn execute_transactions_by_epoch(
         &self,
         begin: Version,
@@ -395,6 +530,164 @@ impl DiemDebugger {
     }
 }
 
+/// Describes where the execution traces of the same transactions by two binaries diverge, one line
+/// per divergence. The traces are compared in order, up to the first traces of different
+/// transactions.
+pub fn diff_execution_traces(
+    base: &[TransactionTrace],
+    revision: &[TransactionTrace],
+) -> Vec<String> {
+    let mut diffs = vec![];
+    for (base, revision) in base.iter().zip(revision) {
+        let txn = base.transaction_hash;
+        if txn != revision.transaction_hash {
+            diffs.push(format!(
+                "Traces of different transactions {} and {}",
+                txn, revision.transaction_hash
+            ));
+            return diffs;
+        }
+        if base.status != revision.status {
+            diffs.push(format!(
+                "{}: status {:?} != {:?}",
+                txn, base.status, revision.status
+            ));
+        }
+        if base.gas_used != revision.gas_used {
+            diffs.push(format!(
+                "{}: gas used {} != {}",
+                txn, base.gas_used, revision.gas_used
+            ));
+        }
+        diff_entries(
+            &mut diffs,
+            txn,
+            "gas of category",
+            base.gas_by_category.iter(),
+            revision.gas_by_category.iter(),
+        );
+        diff_entries(
+            &mut diffs,
+            txn,
+            "read of",
+            base.reads.iter().map(|(path, hash)| (path, hash)),
+            revision.reads.iter().map(|(path, hash)| (path, hash)),
+        );
+        diff_entries(
+            &mut diffs,
+            txn,
+            "write of",
+            base.writes.iter().map(|(path, hash)| (path, hash)),
+            revision.writes.iter().map(|(path, hash)| (path, hash)),
+        );
+        diff_entries(
+            &mut diffs,
+            txn,
+            "event",
+            base.events.iter().enumerate(),
+            revision.events.iter().enumerate(),
+        );
+    }
+    if base.len() != revision.len() {
+        diffs.push(format!(
+            "{} traces != {} traces",
+            base.len(),
+            revision.len()
+        ));
+    }
+    diffs
+}
+
+fn diff_entries<'a, K, V>(
+    diffs: &mut Vec<String>,
+    txn: HashValue,
+    what: &str,
+    base: impl Iterator<Item = (K, &'a V)>,
+    revision: impl Iterator<Item = (K, &'a V)>,
+) where
+    K: Debug + Ord,
+    V: Debug + PartialEq + 'a,
+{
+    let base: BTreeMap<_, _> = base.collect();
+    let revision: BTreeMap<_, _> = revision.collect();
+    let keys: BTreeSet<_> = base.keys().chain(revision.keys()).collect();
+    for key in keys {
+        let (base, revision) = (base.get(key), revision.get(key));
+        if base != revision {
+            diffs.push(format!(
+                "{}: {} {:?} {:?} != {:?}",
+                txn, what, key, base, revision
+            ));
+        }
+    }
+}
+
+/// Describes the differences between the replayed `output` of a transaction and the `info` and
+/// `events` stored when it was committed.
+fn diff_transaction_output(
+    output: &TransactionOutput,
+    info: &TransactionInfo,
+    events: &[ContractEvent],
+) -> Vec<String> {
+    let mut differences = vec![];
+    match output.status() {
+        TransactionStatus::Keep(status) if status == info.status() => (),
+        status => differences.push(format!(
+            "status: stored {:?}, replayed {:?}",
+            info.status(),
+            status
+        )),
+    }
+    if output.gas_used() != info.gas_used() {
+        differences.push(format!(
+            "gas used: stored {}, replayed {}",
+            info.gas_used(),
+            output.gas_used()
+        ));
+    }
+    if let Some(index) = (0..events.len().max(output.events().len()))
+        .find(|index| events.get(*index) != output.events().get(*index))
+    {
+        differences.push(format!(
+            "event {}: stored {:?}, replayed {:?}",
+            index,
+            events.get(index),
+            output.events().get(index)
+        ));
+    }
+    differences
+}
+
+/// Describes the paths of `account` whose values differ between its `stored` and `replayed`
+/// states. The values are hashed, for the differences to fit on a line.
+fn diff_account_states(
+    differences: &mut Vec<String>,
+    account: AccountAddress,
+    stored: &AccountState,
+    replayed: &AccountState,
+) {
+    let paths: BTreeSet<_> = stored
+        .iter()
+        .chain(replayed.iter())
+        .map(|(path, _)| path)
+        .collect();
+    for path in paths {
+        let (stored, replayed) = (stored.get(path), replayed.get(path));
+        if stored != replayed {
+            let path = bcs::from_bytes::<access_path::Path>(path)
+                .map_or_else(|_| hex::encode(path), |path| format!("{:?}", path));
+            let hash = |value: Option<&Vec<u8>>| value.map(|value| HashValue::sha3_256_of(value));
+            differences.push(format!(
+                "{} {}: stored {:?}, replayed {:?}",
+                account,
+                path,
+                hash(stored),
+                hash(replayed)
+            ));
+        }
+    }
+}
+
 fn is_reconfiguration(vm_output: &TransactionOutput) -> bool {
     let new_epoch_event_key = diem_types::on_chain_config::new_epoch_event_key();
     vm_output
@@ -2,12 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::{bail, Result};
-use diem_transaction_replay::DiemDebugger;
+use diem_transaction_replay::{diff_execution_traces, DiemDebugger};
 use diem_types::{
     account_address::AccountAddress,
     event::EventKey,
     transaction::{TransactionPayload, Version},
 };
+use diem_vm::execution_trace::{read_execution_trace, ExecutionTracer};
 use difference::Changeset;
 use move_core_types::effects::ChangeSet;
 use std::{fs, path::PathBuf};
@@ -24,6 +25,10 @@ struct Opt {
     /// If true, persist the effects of replaying transactions via `cmd` to disk in a format understood by the Move CLI
     #[structopt(short = "s", global = true)]
     save_write_sets: bool,
+    /// Append the execution traces of the transactions replayed via `cmd` to this file, to diff
+    /// them with the traces of another binary via `diff-traces`
+    #[structopt(long, parse(from_os_str), global = true)]
+    trace: Option<PathBuf>,
     #[structopt(subcommand)] // Note that we mark a field as a subcommand
     cmd: Command,
 }
@@ -33,6 +38,11 @@ enum Command {
     /// Replay transactions starting from version `start` to `start + limit`.
     #[structopt(name = "replay-transactions")]
     ReplayTransactions { start: Version, limit: u64 },
+    /// Replay transactions starting from version `start` to `start + limit`, and report the first
+    /// whose output diverges from the result stored in the DB. To verify the transactions of a
+    /// backup, restore it into a DB with `db-restore` first.
+    #[structopt(name = "verify-transactions")]
+    VerifyTransactions { start: Version, limit: u64 },
     /// Replay the last `txns` committed transactions.
     #[structopt(name = "replay-recent-transactions")]
     ReplayRecentTransactions { txns: u64 },
@@ -86,17 +96,45 @@ enum Command {
         #[structopt(long)]
         rebuild_stdlib: bool,
     },
+    /// Diff the execution traces of the same transactions replayed by two binaries.
+    #[structopt(name = "diff-traces")]
+    DiffTraces {
+        #[structopt(parse(from_os_str))]
+        base_trace: PathBuf,
+        #[structopt(parse(from_os_str))]
+        revision_trace: PathBuf,
+    },
 }
 
 fn main() -> Result<()> {
     let opt = Opt::from_args();
-    let debugger = if let Some(p) = opt.db {
+    if let Command::DiffTraces {
+        base_trace,
+        revision_trace,
+    } = &opt.cmd
+    {
+        let diffs = diff_execution_traces(
+            &read_execution_trace(base_trace)?,
+            &read_execution_trace(revision_trace)?,
+        );
+        if diffs.is_empty() {
+            println!("The traces are identical");
+        }
+        for diff in diffs {
+            println!("{}", diff);
+        }
+        return Ok(());
+    }
+    let mut debugger = if let Some(p) = opt.db {
         DiemDebugger::db(p)?
     } else if let Some(url) = opt.url {
         DiemDebugger::json_rpc(url.as_str())?
     } else {
         panic!("No debugger attached")
     };
+    if let Some(trace) = &opt.trace {
+        debugger = debugger.with_execution_tracer(ExecutionTracer::new(trace)?);
+    }
 
     println!("Connection Succeeded");
 
@@ -107,6 +145,15 @@ fn main() -> Result<()> {
                 debugger.execute_past_transactions(start, limit, opt.save_write_sets)
             );
         }
+        Command::VerifyTransactions { start, limit } => {
+            if let Some(divergence) = debugger.verify_past_transactions(start, limit)? {
+                bail!("{}", divergence);
+            }
+            println!(
+                "The {} transactions from version {} replay as stored",
+                limit, start
+            );
+        }
         Command::ReplayRecentTransactions { txns } => {
             let latest_version = debugger
                 .get_latest_version()
@@ -232,6 +279,7 @@ fn main() -> Result<()> {
                 },
             )
         ),
+        Command::DiffTraces { .. } => unreachable!("The traces are diffed without a debugger"),
     }
     Ok(())
 }
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod bisection_tests;
+mod trace_diff_tests;
+mod verify_tests;
 
 use crate::DiemValidatorInterface;
 use anyhow::{bail, Result};
@@ -9,9 +11,9 @@ use diem_types::{
     account_address::AccountAddress,
     account_state::AccountState,
     account_state_blob::AccountStateBlob,
-    contract_event::EventWithProof,
+    contract_event::{ContractEvent, EventWithProof},
     event::EventKey,
-    transaction::{Transaction, Version, WriteSetPayload},
+    transaction::{Transaction, TransactionInfo, Version, WriteSetPayload},
     write_set::WriteOp,
 };
 use std::{collections::HashMap, convert::TryFrom};
@@ -83,6 +85,10 @@ impl DiemValidatorInterface for TestInterface {
     }
 
     fn get_committed_transactions(&self, start: Version, limit: u64) -> Result<Vec<Transaction>> {
+        // Like the DB, nothing is returned past the latest version
+        if start > self.latest_version {
+            return Ok(vec![]);
+        }
         if start + limit >= self.transaction_store.len() as u64 {
             bail!("Unexpected length")
         }
@@ -93,6 +99,18 @@ impl DiemValidatorInterface for TestInterface {
         Ok(result)
     }
 
+    fn get_committed_transaction_results(
+        &self,
+        start: Version,
+        limit: u64,
+    ) -> Result<Vec<(TransactionInfo, Vec<ContractEvent>)>> {
+        // Like the DB, nothing is returned past the latest version
+        if start > self.latest_version {
+            return Ok(vec![]);
+        }
+        bail!("No results for versions {} to {}", start, start + limit)
+    }
+
     fn get_latest_version(&self) -> Result<Version> {
         Ok(self.latest_version)
     }
@@ -0,0 +1,62 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::diff_execution_traces;
+use diem_crypto::HashValue;
+use diem_types::{
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    transaction::TransactionStatus,
+    vm_status::{KeptVMStatus, StatusCode},
+};
+use diem_vm::execution_trace::TransactionTrace;
+use move_vm_types::gas_schedule::GasCategory;
+use std::collections::BTreeMap;
+
+fn gen_trace(seed: u8) -> TransactionTrace {
+    let access_path = AccessPath::new(AccountAddress::new([seed; AccountAddress::LENGTH]), vec![]);
+    let mut gas_by_category = BTreeMap::new();
+    gas_by_category.insert(GasCategory::Intrinsic, 600);
+    gas_by_category.insert(GasCategory::Calls, 40);
+    TransactionTrace {
+        transaction_hash: HashValue::sha3_256_of(&[seed]),
+        status: TransactionStatus::Keep(KeptVMStatus::Executed),
+        gas_used: 10,
+        gas_by_category,
+        reads: vec![(access_path.clone(), Some(HashValue::sha3_256_of(b"read")))],
+        writes: vec![(access_path, Some(HashValue::sha3_256_of(b"written")))],
+        events: vec![],
+    }
+}
+
+#[test]
+fn test_diff_identical_traces() {
+    let traces = vec![gen_trace(1), gen_trace(2)];
+    assert!(diff_execution_traces(&traces, &traces).is_empty());
+}
+
+#[test]
+fn test_diff_diverging_traces() {
+    let base = vec![gen_trace(1), gen_trace(2)];
+    let mut revision = base.clone();
+    revision[1].status = TransactionStatus::Discard(StatusCode::OUT_OF_GAS);
+    revision[1].gas_by_category.insert(GasCategory::Calls, 50);
+    revision[1].writes.clear();
+
+    let diffs = diff_execution_traces(&base, &revision);
+    assert_eq!(diffs.len(), 3);
+    assert!(diffs[0].contains("status"));
+    assert!(diffs[1].contains("Calls"));
+    assert!(diffs[2].contains("write of"));
+}
+
+#[test]
+fn test_diff_traces_of_different_transactions() {
+    let base = vec![gen_trace(1), gen_trace(2), gen_trace(3)];
+    let revision = vec![gen_trace(1), gen_trace(3)];
+
+    let diffs = diff_execution_traces(&base, &revision);
+    // The traces after the first different transactions aren't compared
+    assert_eq!(diffs.len(), 1);
+    assert!(diffs[0].contains("different transactions"));
+}
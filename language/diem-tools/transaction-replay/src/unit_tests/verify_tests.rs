@@ -0,0 +1,115 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{diff_transaction_output, unit_tests::TestInterface, DiemDebugger};
+use diem_crypto::HashValue;
+use diem_types::{
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    account_state::AccountState,
+    account_state_blob::AccountStateBlob,
+    contract_event::ContractEvent,
+    event::EventKey,
+    transaction::{TransactionInfo, TransactionOutput, TransactionStatus},
+    vm_status::KeptVMStatus,
+    write_set::{WriteOp, WriteSet, WriteSetMut},
+};
+use move_core_types::language_storage::TypeTag;
+use std::{collections::HashMap, convert::TryFrom};
+
+fn gen_event(seq: u64) -> ContractEvent {
+    ContractEvent::new(
+        EventKey::new([1; EventKey::LENGTH]),
+        seq,
+        TypeTag::Bool,
+        vec![],
+    )
+}
+
+fn gen_info(gas_used: u64, status: KeptVMStatus) -> TransactionInfo {
+    TransactionInfo::new(
+        HashValue::zero(),
+        HashValue::zero(),
+        HashValue::zero(),
+        gas_used,
+        status,
+    )
+}
+
+fn gen_output(gas_used: u64, events: Vec<ContractEvent>) -> TransactionOutput {
+    TransactionOutput::new(
+        WriteSet::default(),
+        events,
+        gas_used,
+        TransactionStatus::Keep(KeptVMStatus::Executed),
+    )
+}
+
+#[test]
+fn test_diff_identical_output() {
+    let events = vec![gen_event(0), gen_event(1)];
+    let output = gen_output(10, events.clone());
+    let info = gen_info(10, KeptVMStatus::Executed);
+    assert!(diff_transaction_output(&output, &info, &events).is_empty());
+}
+
+#[test]
+fn test_diff_diverging_output() {
+    let output = gen_output(12, vec![gen_event(0), gen_event(2)]);
+    let info = gen_info(10, KeptVMStatus::OutOfGas);
+
+    let differences = diff_transaction_output(&output, &info, &[gen_event(0), gen_event(1)]);
+    assert_eq!(differences.len(), 3);
+    assert!(differences[0].starts_with("status"));
+    assert!(differences[1].starts_with("gas used"));
+    // Only the first diverging event is reported
+    assert!(differences[2].starts_with("event 1"));
+}
+
+#[test]
+fn test_diff_write_set() {
+    let account = AccountAddress::random();
+    let path = |seed: u8| AccessPath::new(account, vec![seed]);
+    let state = |values: &[(u8, u8)]| {
+        let mut state = AccountState::default();
+        for (path, value) in values {
+            state.insert(vec![*path], vec![*value]);
+        }
+        AccountStateBlob::try_from(&state).unwrap()
+    };
+    let mut state_db = HashMap::new();
+    state_db.insert((4, account), state(&[(0, 0), (1, 1)]));
+    state_db.insert((5, account), state(&[(0, 2), (2, 2)]));
+    let debugger = DiemDebugger::new(Box::new(TestInterface::new(state_db, vec![], 5)));
+
+    let write_set = |writes: Vec<(AccessPath, WriteOp)>| WriteSetMut::new(writes).freeze().unwrap();
+    let stored_writes = vec![
+        (path(0), WriteOp::Value(vec![2])),
+        (path(1), WriteOp::Deletion),
+        (path(2), WriteOp::Value(vec![2])),
+    ];
+    assert!(debugger
+        .diff_write_set(5, &write_set(stored_writes))
+        .unwrap()
+        .is_empty());
+
+    // The replay misses the deletion, and writes another value
+    let replayed_writes = vec![
+        (path(0), WriteOp::Value(vec![3])),
+        (path(2), WriteOp::Value(vec![2])),
+    ];
+    let differences = debugger
+        .diff_write_set(5, &write_set(replayed_writes))
+        .unwrap();
+    assert_eq!(differences.len(), 2);
+}
+
+#[test]
+fn test_verify_past_latest_version() {
+    let debugger = DiemDebugger::new(Box::new(TestInterface::empty(5)));
+    // The range is larger than a page, and starts past the latest version
+    assert!(debugger
+        .verify_past_transactions(10, 5000)
+        .unwrap()
+        .is_none());
+}
@@ -1,8 +1,14 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{counters::*, create_access_path, data_cache::StateViewCache};
+use crate::{
+    counters::*,
+    create_access_path,
+    data_cache::StateViewCache,
+    execution_trace::{ExecutionTracer, TracingView},
+};
 use anyhow::Result;
+use diem_crypto::{hash::CryptoHash, HashValue};
 use diem_state_view::StateView;
 use diem_types::{
     account_address::AccountAddress,
@@ -12,8 +18,11 @@ use diem_types::{
     },
     vm_status::{StatusCode, VMStatus},
 };
-use move_core_types::{move_resource::MoveStructType, resolver::MoveResolver};
+use move_core_types::{
+    gas_schedule::GasCarrier, move_resource::MoveStructType, resolver::MoveResolver,
+};
 use move_vm_runtime::session::Session;
+use move_vm_types::gas_schedule::GasCategory;
 
 use crate::logging::AdapterLogSchema;
 use diem_logger::prelude::*;
@@ -27,7 +36,7 @@ use diem_types::{
     write_set::WriteSet,
 };
 use rayon::prelude::*;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
 /// This trait describes the VM adapter's interface.
 /// TODO: bring more of the execution logic in diem_vm into this file.
@@ -54,11 +63,12 @@ pub trait VMAdapter {
         remote_cache: &S,
     ) -> Result<u64, VMStatus>;
 
-    /// Runs the prologue for the given transaction.
+    /// Runs the prologue for the given transaction, whose signature has been checked unless it is
+    /// simulated.
     fn run_prologue<S: MoveResolver>(
         &self,
         session: &mut Session<S>,
-        transaction: &SignatureCheckedTransaction,
+        transaction: &SignedTransaction,
         log_context: &AdapterLogSchema,
     ) -> Result<(), VMStatus>;
 
@@ -72,6 +82,15 @@ pub trait VMAdapter {
         data_cache: &S,
         log_context: &AdapterLogSchema,
     ) -> Result<(VMStatus, TransactionOutput, Option<String>), VMStatus>;
+
+    /// A VM with the same configuration loading the modules anew, to trace the execution of a
+    /// transaction, see `execution_trace`.
+    fn new_for_tracing(&self) -> Self
+    where
+        Self: Sized;
+
+    /// Takes the gas charged by category to the user transaction a VM for tracing executed last
+    fn take_gas_by_category(&self) -> BTreeMap<GasCategory, GasCarrier>;
 }
 
 /// Validate a signed transaction by performing the following:
@@ -139,7 +158,7 @@ fn get_account_role(sender: AccountAddress, remote_cache: &StateViewCache) -> Go
 pub(crate) fn validate_signature_checked_transaction<S: MoveResolver, A: VMAdapter>(
     adapter: &A,
     mut session: &mut Session<S>,
-    transaction: &SignatureCheckedTransaction,
+    transaction: &SignedTransaction,
     allow_too_new: bool,
     log_context: &AdapterLogSchema,
 ) -> Result<(), VMStatus> {
@@ -183,6 +202,7 @@ pub(crate) fn execute_block_impl<A: VMAdapter>(
     adapter: &A,
     transactions: Vec<Transaction>,
     data_cache: &mut StateViewCache,
+    tracer: Option<&ExecutionTracer>,
 ) -> Result<Vec<(VMStatus, TransactionOutput)>, VMStatus> {
     let mut result = vec![];
     let mut should_restart = false;
@@ -193,6 +213,13 @@ pub(crate) fn execute_block_impl<A: VMAdapter>(
         transactions.len()
     );
 
+    // The transactions are only hashed for the execution trace
+    let transaction_hashes: Vec<HashValue> = if tracer.is_some() {
+        transactions.iter().map(CryptoHash::hash).collect()
+    } else {
+        vec![]
+    };
+
     let signature_verified_block: Vec<PreprocessedTransaction>;
     {
         // Verify the signatures of all the transactions in parallel.
@@ -219,8 +246,18 @@ pub(crate) fn execute_block_impl<A: VMAdapter>(
             debug!(log_context, "Retry after reconfiguration");
             continue;
         };
-        let (vm_status, output, sender) =
-            adapter.execute_single_transaction(&txn, data_cache, &log_context)?;
+        let (vm_status, output, sender) = match (tracer, transaction_hashes.get(idx)) {
+            (Some(tracer), Some(transaction_hash)) => {
+                let traced_adapter = adapter.new_for_tracing();
+                let view = TracingView::new(data_cache);
+                let result =
+                    traced_adapter.execute_single_transaction(&txn, &view, &log_context)?;
+                let gas_by_category = traced_adapter.take_gas_by_category();
+                tracer.trace_transaction(*transaction_hash, view, &result.1, gas_by_category);
+                result
+            }
+            _ => adapter.execute_single_transaction(&txn, data_cache, &log_context)?,
+        };
         if !output.status().is_discarded() {
             data_cache.push_write_set(output.write_set());
         } else {
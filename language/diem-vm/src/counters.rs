@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diem_metrics::{
-    register_histogram, register_int_counter, register_int_counter_vec, Histogram, IntCounter,
-    IntCounterVec,
+    register_histogram, register_int_counter, register_int_counter_vec, register_int_gauge,
+    Histogram, IntCounter, IntCounterVec, IntGauge,
 };
 use once_cell::sync::Lazy;
 
@@ -70,3 +70,32 @@ pub static TXN_GAS_USAGE: Lazy<Histogram> =
 pub static CRITICAL_ERRORS: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!("diem_vm_critical_errors", "Number of critical errors").unwrap()
 });
+
+/// Count the lookups of the Move VM cache by the blocks, with a "result" label to distinguish the
+/// hits, the misses of an empty cache, and the cached VMs whose modules changed.
+pub static MOVE_VM_CACHE_LOOKUPS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_vm_move_vm_cache_lookups",
+        "Number of lookups of the Move VM cached across blocks",
+        &["result"]
+    )
+    .unwrap()
+});
+
+pub static MOVE_VM_CACHE_MODULES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_vm_move_vm_cache_modules",
+        "Number of modules of the Move VM cached across blocks"
+    )
+    .unwrap()
+});
+
+/// Count the changes of the gas schedule swapped in for the VMs, at startup and on the
+/// reconfigurations updating it.
+pub static GAS_SCHEDULE_SWAPS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_vm_gas_schedule_swaps",
+        "Number of times a new gas schedule was swapped in"
+    )
+    .unwrap()
+});
@@ -10,7 +10,9 @@ use diem_state_view::StateView;
 use diem_types::{
     account_address::AccountAddress,
     account_config::{self, CurrencyInfoResource, RoleId},
-    on_chain_config::{DiemVersion, VMConfig, VMPublishingOption, DIEM_VERSION_2, DIEM_VERSION_3},
+    on_chain_config::{
+        DiemVersion, VMConfig, VMPublishingOption, DIEM_VERSION_2, DIEM_VERSION_3, DIEM_VERSION_5,
+    },
     transaction::{
         GovernanceRole, SignatureCheckedTransaction, SignedTransaction, TransactionPayload,
         VMValidatorResult,
@@ -132,6 +134,11 @@ pub(crate) fn validate_signature_checked_transaction<S: MoveResolver>(
         return Err(VMStatus::Error(StatusCode::FEATURE_UNDER_GATING));
     }
 
+    if transaction.uses_secp256k1_ecdsa() && vm.get_diem_version()? < DIEM_VERSION_5 {
+        // secp256k1 ECDSA signatures are not allowed under this version
+        return Err(VMStatus::Error(StatusCode::FEATURE_UNDER_GATING));
+    }
+
     if transaction.contains_duplicate_signers() {
         return Err(VMStatus::Error(StatusCode::SIGNERS_CONTAIN_DUPLICATES));
     }
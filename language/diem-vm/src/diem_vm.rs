@@ -11,14 +11,16 @@ use crate::{
     data_cache::StateViewCache,
     diem_vm_impl::{
         charge_global_write_gas_usage, convert_changeset_and_events, get_currency_info,
-        get_gas_currency_code, get_transaction_output, DiemVMImpl, DiemVMInternals,
+        get_gas_currency_code, get_transaction_output, new_move_vm, DiemVMImpl, DiemVMInternals,
     },
     errors::expect_only_successful_execution,
+    execution_trace::ExecutionTracer,
     logging::AdapterLogSchema,
+    move_vm_cache::{writes_modules, ModuleRecordingView, MOVE_VM_CACHE},
     script_to_script_function,
     system_module_names::*,
     transaction_metadata::TransactionMetadata,
-    VMExecutor, VMValidator,
+    TransactionExecutor, VMExecutor, VMValidator,
 };
 use anyhow::Result;
 use diem_logger::prelude::*;
@@ -26,7 +28,9 @@ use diem_state_view::StateView;
 use diem_types::{
     account_config,
     block_metadata::BlockMetadata,
-    on_chain_config::{DiemVersion, VMConfig, VMPublishingOption, DIEM_VERSION_2, DIEM_VERSION_3},
+    on_chain_config::{
+        DiemVersion, VMConfig, VMPublishingOption, DIEM_VERSION_2, DIEM_VERSION_3, DIEM_VERSION_5,
+    },
     transaction::{
         ChangeSet, Module, SignatureCheckedTransaction, SignedTransaction, Transaction,
         TransactionOutput, TransactionPayload, TransactionStatus, VMValidatorResult,
@@ -38,17 +42,19 @@ use diem_types::{
 use fail::fail_point;
 use move_core_types::{
     account_address::AccountAddress,
-    gas_schedule::GasAlgebra,
+    gas_schedule::{GasAlgebra, GasCarrier},
     identifier::IdentStr,
     resolver::MoveResolver,
     transaction_argument::convert_txn_args,
     value::{serialize_values, MoveValue},
 };
-use move_vm_runtime::session::Session;
-use move_vm_types::gas_schedule::GasStatus;
+use move_vm_runtime::{move_vm::MoveVM, session::Session};
+use move_vm_types::gas_schedule::{GasCategory, GasStatus};
+use rayon::prelude::*;
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     convert::{AsMut, AsRef},
+    sync::Arc,
 };
 
 #[derive(Clone)]
@@ -307,10 +313,12 @@ impl DiemVM {
         )
     }
 
+    /// Executes a user transaction. Its signature must have been checked, unless the transaction is
+    /// simulated, whose output is never committed.
     pub(crate) fn execute_user_transaction<S: MoveResolver>(
         &self,
         storage: &S,
-        txn: &SignatureCheckedTransaction,
+        txn: &SignedTransaction,
         log_context: &AdapterLogSchema,
     ) -> (VMStatus, TransactionOutput) {
         macro_rules! unwrap_or_discard {
@@ -348,6 +356,9 @@ impl DiemVM {
         let gas_schedule = unwrap_or_discard!(self.0.get_gas_schedule(log_context));
         let txn_data = TransactionMetadata::new(txn);
         let mut gas_status = GasStatus::new(gas_schedule, txn_data.max_gas_amount());
+        if self.0.gas_by_category().is_some() {
+            gas_status.record_gas_by_category();
+        }
 
         let result = match txn.payload() {
             payload @ TransactionPayload::Script(_)
@@ -379,7 +390,7 @@ impl DiemVM {
             .get();
         TXN_GAS_USAGE.observe(gas_usage as f64);
 
-        match result {
+        let output = match result {
             Ok(output) => output,
             Err(err) => {
                 let txn_status = TransactionStatus::from(err.clone());
@@ -396,7 +407,15 @@ impl DiemVM {
                     )
                 }
             }
+        };
+        if let (Some(recorded), Some(gas_by_category)) =
+            (self.0.gas_by_category(), gas_status.gas_by_category())
+        {
+            *recorded
+                .lock()
+                .expect("diem cannot currently handle a poisoned lock") = gas_by_category.clone();
         }
+        output
     }
 
     fn execute_writeset<S: MoveResolver>(
@@ -690,14 +709,83 @@ impl DiemVM {
         transactions: Vec<Transaction>,
         state_view: &dyn StateView,
     ) -> Result<Vec<(VMStatus, TransactionOutput)>, VMStatus> {
-        let mut state_view_cache = StateViewCache::new(state_view);
+        Self::execute_block_and_keep_vm_status_impl(transactions, state_view, None)
+    }
+
+    /// The traced transactions each execute with a VM of their own, so the block neither uses nor
+    /// changes the cached VM.
+    fn execute_block_and_keep_vm_status_impl(
+        transactions: Vec<Transaction>,
+        state_view: &dyn StateView,
+        tracer: Option<&ExecutionTracer>,
+    ) -> Result<Vec<(VMStatus, TransactionOutput)>, VMStatus> {
         let count = transactions.len();
-        let vm = DiemVM::new(&state_view_cache);
-        let res = adapter_common::execute_block_impl(&vm, transactions, &mut state_view_cache)?;
+        let res = if tracer.is_some() {
+            Self::execute_block_with_move_vm(
+                Arc::new(new_move_vm()),
+                transactions,
+                state_view,
+                tracer,
+            )?
+        } else if state_view.is_genesis() || transactions.iter().any(writes_modules) {
+            MOVE_VM_CACHE.flush();
+            Self::execute_block_with_move_vm(
+                Arc::new(new_move_vm()),
+                transactions,
+                state_view,
+                None,
+            )?
+        } else {
+            let cached = MOVE_VM_CACHE.checkout(state_view);
+            let recording_view = ModuleRecordingView::new(state_view);
+            let res = Self::execute_block_with_move_vm(
+                cached.move_vm(),
+                transactions,
+                &recording_view,
+                None,
+            )?;
+            MOVE_VM_CACHE.checkin(cached, recording_view);
+            res
+        };
         // Record the histogram count for transactions per block.
         BLOCK_TRANSACTION_COUNT.observe(count as f64);
         Ok(res)
     }
+
+    fn execute_block_with_move_vm(
+        move_vm: Arc<MoveVM>,
+        transactions: Vec<Transaction>,
+        state_view: &dyn StateView,
+        tracer: Option<&ExecutionTracer>,
+    ) -> Result<Vec<(VMStatus, TransactionOutput)>, VMStatus> {
+        let mut state_view_cache = StateViewCache::new(state_view);
+        let vm = DiemVM(DiemVMImpl::new_with_move_vm(move_vm, &state_view_cache));
+        adapter_common::execute_block_impl(&vm, transactions, &mut state_view_cache, tracer)
+    }
+
+    /// Executes a user transaction against `state_view` to preview its output, without committing
+    /// anything. With `skip_signature_check`, transactions that are not signed yet are simulated
+    /// too; their authenticator must still carry the public key of the sender.
+    pub fn simulate_signed_transaction(
+        txn: SignedTransaction,
+        skip_signature_check: bool,
+        state_view: &dyn StateView,
+    ) -> (VMStatus, TransactionOutput) {
+        let txn = if skip_signature_check {
+            txn
+        } else {
+            match txn.check_signature() {
+                Ok(txn) => txn.into_inner(),
+                Err(_) => {
+                    return discard_error_vm_status(VMStatus::Error(StatusCode::INVALID_SIGNATURE))
+                }
+            }
+        };
+        let state_view_cache = StateViewCache::new(state_view);
+        let vm = DiemVM::new(&state_view_cache);
+        let log_context = AdapterLogSchema::new(state_view.id(), 0);
+        vm.execute_user_transaction(&state_view_cache, &txn, &log_context)
+    }
 }
 
 // Executor external API
@@ -723,6 +811,64 @@ impl VMExecutor for DiemVM {
             .map(|(_vm_status, txn_output)| txn_output)
             .collect())
     }
+
+    fn execute_block_with_tracer(
+        transactions: Vec<Transaction>,
+        state_view: &dyn StateView,
+        tracer: &ExecutionTracer,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        let output =
+            Self::execute_block_and_keep_vm_status_impl(transactions, state_view, Some(tracer))?;
+        Ok(output
+            .into_iter()
+            .map(|(_vm_status, txn_output)| txn_output)
+            .collect())
+    }
+
+    /// The VM is the cached one, like for sequential executions, and is shared by all the
+    /// transactions. The blocks writing modules must be executed sequentially: the loader would
+    /// keep the modules published by any incarnation of a transaction.
+    fn execute_block_by_transaction<R>(
+        transactions: Vec<Transaction>,
+        state_view: &dyn StateView,
+        execute: impl FnOnce(&dyn StateView, &TransactionExecutor<'_>) -> R,
+    ) -> R {
+        assert!(
+            !state_view.is_genesis() && !transactions.iter().any(writes_modules),
+            "The blocks writing modules can't share a VM across their transactions"
+        );
+        info!(
+            AdapterLogSchema::new(state_view.id(), 0),
+            "Executing block by transaction, transaction count: {}",
+            transactions.len()
+        );
+        BLOCK_TRANSACTION_COUNT.observe(transactions.len() as f64);
+
+        let cached = MOVE_VM_CACHE.checkout(state_view);
+        let recording_view = ModuleRecordingView::new(state_view);
+        let vm = DiemVM(DiemVMImpl::new_with_move_vm(
+            cached.move_vm(),
+            &StateViewCache::new(&recording_view),
+        ));
+        // The signatures are checked once, instead of on every execution of the transactions
+        let signature_verified_block: Vec<PreprocessedTransaction> = transactions
+            .into_par_iter()
+            .map(adapter_common::preprocess_transaction::<Self>)
+            .collect();
+
+        let result = execute(&recording_view, &|txn_idx, view| {
+            let state_view_cache = StateViewCache::new(view);
+            let log_context = AdapterLogSchema::new(view.id(), txn_idx);
+            vm.execute_single_transaction(
+                &signature_verified_block[txn_idx],
+                &state_view_cache,
+                &log_context,
+            )
+            .map(|(_vm_status, output, _sender)| output)
+        });
+        MOVE_VM_CACHE.checkin(cached, recording_view);
+        result
+    }
 }
 
 // VMValidator external API
@@ -761,6 +907,10 @@ impl VMAdapter for DiemVM {
             // Multi agent is not allowed
             return Err(VMStatus::Error(StatusCode::FEATURE_UNDER_GATING));
         }
+        if txn.uses_secp256k1_ecdsa() && self.0.get_diem_version()? < DIEM_VERSION_5 {
+            // secp256k1 ECDSA signatures are not allowed
+            return Err(VMStatus::Error(StatusCode::FEATURE_UNDER_GATING));
+        }
         if txn.contains_duplicate_signers() {
             return Err(VMStatus::Error(StatusCode::SIGNERS_CONTAIN_DUPLICATES));
         }
@@ -789,7 +939,7 @@ impl VMAdapter for DiemVM {
     fn run_prologue<S: MoveResolver>(
         &self,
         session: &mut Session<S>,
-        transaction: &SignatureCheckedTransaction,
+        transaction: &SignedTransaction,
         log_context: &AdapterLogSchema,
     ) -> Result<(), VMStatus> {
         let currency_code = get_gas_currency_code(transaction)?;
@@ -876,6 +1026,23 @@ impl VMAdapter for DiemVM {
             }
         })
     }
+
+    fn new_for_tracing(&self) -> Self {
+        Self(self.0.new_for_tracing())
+    }
+
+    fn take_gas_by_category(&self) -> BTreeMap<GasCategory, GasCarrier> {
+        self.0
+            .gas_by_category()
+            .map(|gas_by_category| {
+                std::mem::take(
+                    &mut *gas_by_category
+                        .lock()
+                        .expect("diem cannot currently handle a poisoned lock"),
+                )
+            })
+            .unwrap_or_default()
+    }
 }
 
 impl AsRef<DiemVMImpl> for DiemVM {
@@ -6,6 +6,8 @@ use crate::{
     counters::*,
     data_cache::RemoteStorage,
     errors::{convert_epilogue_error, convert_prologue_error, expect_only_successful_execution},
+    execution_trace::GasByCategory,
+    gas_schedule_cache::GAS_SCHEDULE_CACHE,
     logging::AdapterLogSchema,
     natives::diem_natives,
     system_module_names::*,
@@ -41,42 +43,70 @@ use move_vm_runtime::{logging::expect_no_verification_errors, move_vm::MoveVM, s
 use move_vm_types::gas_schedule::{calculate_intrinsic_gas, GasStatus};
 use std::{convert::TryFrom, sync::Arc};
 
+pub(crate) fn new_move_vm() -> MoveVM {
+    MoveVM::new(diem_natives())
+        .expect("should be able to create Move VM; check if there are duplicated natives")
+}
+
 #[derive(Clone)]
 /// A wrapper to make VMRuntime standalone and thread safe.
 pub struct DiemVMImpl {
     move_vm: Arc<MoveVM>,
-    on_chain_config: Option<VMConfig>,
+    on_chain_config: Option<Arc<VMConfig>>,
     version: Option<DiemVersion>,
     publishing_option: Option<VMPublishingOption>,
+    /// Where the gas charged by category to the user transactions is recorded, when traced
+    gas_by_category: Option<Arc<GasByCategory>>,
 }
 
 impl DiemVMImpl {
     #[allow(clippy::new_without_default)]
     pub fn new<S: StateView>(state: &S) -> Self {
-        let inner = MoveVM::new(diem_natives())
-            .expect("should be able to create Move VM; check if there are duplicated natives");
+        Self::new_with_move_vm(Arc::new(new_move_vm()), state)
+    }
+
+    /// Wraps a Move VM which may already have loaded modules, e.g. from the cache of the VM across
+    /// blocks
+    pub(crate) fn new_with_move_vm<S: StateView>(move_vm: Arc<MoveVM>, state: &S) -> Self {
         let mut vm = Self {
-            move_vm: Arc::new(inner),
+            move_vm,
             on_chain_config: None,
             version: None,
             publishing_option: None,
+            gas_by_category: None,
         };
         vm.load_configs_impl(&RemoteStorage::new(state));
         vm
     }
 
+    /// A VM with the same configs loading the modules anew, which records the gas charged by
+    /// category to the user transactions it executes
+    pub(crate) fn new_for_tracing(&self) -> Self {
+        Self {
+            move_vm: Arc::new(new_move_vm()),
+            on_chain_config: self.on_chain_config.clone(),
+            version: self.version.clone(),
+            publishing_option: self.publishing_option.clone(),
+            gas_by_category: Some(Arc::new(GasByCategory::default())),
+        }
+    }
+
+    /// Where the gas charged by category to the user transactions is recorded, if it is
+    pub(crate) fn gas_by_category(&self) -> Option<&GasByCategory> {
+        self.gas_by_category.as_deref()
+    }
+
     pub fn init_with_config(
         version: DiemVersion,
         on_chain_config: VMConfig,
         publishing_option: VMPublishingOption,
     ) -> Self {
-        let inner = MoveVM::new(diem_natives())
-            .expect("should be able to create Move VM; check if there are duplicated natives");
         Self {
-            move_vm: Arc::new(inner),
-            on_chain_config: Some(on_chain_config),
+            move_vm: Arc::new(new_move_vm()),
+            on_chain_config: Some(Arc::new(on_chain_config)),
             version: Some(version),
             publishing_option: Some(publishing_option),
+            gas_by_category: None,
         }
     }
 
@@ -100,7 +130,7 @@ impl DiemVMImpl {
     }
 
     fn load_configs_impl<S: ConfigStorage>(&mut self, data_cache: &S) {
-        self.on_chain_config = VMConfig::fetch_config(data_cache);
+        self.on_chain_config = GAS_SCHEDULE_CACHE.get(data_cache);
         self.version = DiemVersion::fetch_config(data_cache);
         self.publishing_option = VMPublishingOption::fetch_config(data_cache);
     }
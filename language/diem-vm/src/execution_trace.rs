@@ -0,0 +1,192 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Opt-in tracing of the execution of transactions, to debug the divergence of the outputs of
+//! different binaries executing the same transactions.
+//!
+//! An `ExecutionTracer` given to `VMExecutor::execute_block_with_tracer` appends the trace of every
+//! transaction of the block to a file as a line of JSON: the paths it read and the hashes of the
+//! values it read, the hashes of the values it wrote, its events, and the gas it used by category.
+//! The paths are sorted, so the traces of a transaction executed by two binaries only differ where
+//! the executions diverged. Every traced transaction executes with a VM of its own, so the modules
+//! it reads don't depend on the ones loaded by the transactions and blocks executed before it.
+
+use crate::data_cache::RemoteStorage;
+use anyhow::Result;
+use diem_crypto::HashValue;
+use diem_state_view::{StateView, StateViewId};
+use diem_types::{
+    access_path::AccessPath,
+    contract_event::ContractEvent,
+    transaction::{TransactionOutput, TransactionStatus},
+    write_set::WriteOp,
+};
+use move_binary_format::errors::VMError;
+use move_core_types::{
+    account_address::AccountAddress,
+    gas_schedule::GasCarrier,
+    language_storage::{ModuleId, StructTag},
+    resolver::{ModuleResolver, ResourceResolver},
+};
+use move_vm_types::gas_schedule::GasCategory;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+/// The gas charged by category to the user transaction a VM executed last
+pub(crate) type GasByCategory = Mutex<BTreeMap<GasCategory, GasCarrier>>;
+
+/// Appends the traces of the transactions to a file. Every trace is appended with a single write,
+/// so several tracers may append to the same file.
+pub struct ExecutionTracer {
+    file: Mutex<File>,
+}
+
+impl ExecutionTracer {
+    pub fn new(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Appends the trace of the transaction executed on `view` to the trace file
+    pub(crate) fn trace_transaction(
+        &self,
+        transaction_hash: HashValue,
+        view: TracingView,
+        output: &TransactionOutput,
+        gas_by_category: BTreeMap<GasCategory, GasCarrier>,
+    ) {
+        let trace = TransactionTrace::new(transaction_hash, view, output, gas_by_category);
+        // The trace is best effort, the execution doesn't depend on it
+        if let Ok(mut line) = serde_json::to_string(&trace) {
+            line.push('\n');
+            let _ = self
+                .file
+                .lock()
+                .expect("diem cannot currently handle a poisoned lock")
+                .write_all(line.as_bytes());
+        }
+    }
+}
+
+/// The execution of a transaction
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TransactionTrace {
+    pub transaction_hash: HashValue,
+    pub status: TransactionStatus,
+    pub gas_used: u64,
+    /// The gas charged by category, in internal gas units. Empty for the transactions the VM
+    /// executes without charging gas.
+    pub gas_by_category: BTreeMap<GasCategory, GasCarrier>,
+    /// The paths read with the hashes of their values, None for the paths without value
+    pub reads: Vec<(AccessPath, Option<HashValue>)>,
+    /// The paths written with the hashes of their values, None for deletions
+    pub writes: Vec<(AccessPath, Option<HashValue>)>,
+    pub events: Vec<ContractEvent>,
+}
+
+impl TransactionTrace {
+    pub(crate) fn new(
+        transaction_hash: HashValue,
+        view: TracingView,
+        output: &TransactionOutput,
+        gas_by_category: BTreeMap<GasCategory, GasCarrier>,
+    ) -> Self {
+        Self {
+            transaction_hash,
+            status: output.status().clone(),
+            gas_used: output.gas_used(),
+            gas_by_category,
+            reads: view
+                .reads
+                .into_inner()
+                .expect("diem cannot currently handle a poisoned lock")
+                .into_iter()
+                .collect(),
+            writes: output
+                .write_set()
+                .iter()
+                .map(|(access_path, op)| {
+                    let hash = match op {
+                        WriteOp::Value(value) => Some(HashValue::sha3_256_of(value)),
+                        WriteOp::Deletion => None,
+                    };
+                    (access_path.clone(), hash)
+                })
+                .collect::<BTreeMap<_, _>>()
+                .into_iter()
+                .collect(),
+            events: output.events().to_vec(),
+        }
+    }
+}
+
+/// Reads the traces of a trace file
+pub fn read_execution_trace(path: &Path) -> Result<Vec<TransactionTrace>> {
+    BufReader::new(File::open(path)?)
+        .lines()
+        .map(|line| Ok(serde_json::from_str(&line?)?))
+        .collect()
+}
+
+/// A view over the state of a transaction recording what it reads
+pub(crate) struct TracingView<'a> {
+    state_view: &'a dyn StateView,
+    reads: Mutex<BTreeMap<AccessPath, Option<HashValue>>>,
+}
+
+impl<'a> TracingView<'a> {
+    pub fn new(state_view: &'a dyn StateView) -> Self {
+        Self {
+            state_view,
+            reads: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl<'a> StateView for TracingView<'a> {
+    fn id(&self) -> StateViewId {
+        self.state_view.id()
+    }
+
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        let value = self.state_view.get(access_path)?;
+        self.reads
+            .lock()
+            .expect("diem cannot currently handle a poisoned lock")
+            .entry(access_path.clone())
+            .or_insert_with(|| value.as_ref().map(|value| HashValue::sha3_256_of(value)));
+        Ok(value)
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.state_view.is_genesis()
+    }
+}
+
+impl<'a> ModuleResolver for TracingView<'a> {
+    type Error = VMError;
+
+    fn get_module(&self, module_id: &ModuleId) -> Result<Option<Vec<u8>>, Self::Error> {
+        RemoteStorage::new(self).get_module(module_id)
+    }
+}
+
+impl<'a> ResourceResolver for TracingView<'a> {
+    type Error = VMError;
+
+    fn get_resource(
+        &self,
+        address: &AccountAddress,
+        tag: &StructTag,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        RemoteStorage::new(self).get_resource(address, tag)
+    }
+}
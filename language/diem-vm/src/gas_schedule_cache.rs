@@ -0,0 +1,82 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The on-chain VM config with the gas schedule, parsed once per change instead of by every VM.
+//!
+//! The config only changes through a reconfiguration: the node swaps in the config of every
+//! reconfiguration it's notified of, so the blocks of the new epoch find it parsed. The VMs still
+//! check the config is the one stored in the state they execute on, and swap in the one of their
+//! state otherwise, so the blocks executed before the notification, or on another fork, never use
+//! the gas schedule of another state.
+
+use crate::counters::GAS_SCHEDULE_SWAPS;
+use diem_logger::prelude::*;
+use diem_types::on_chain_config::{ConfigStorage, OnChainConfig, OnChainConfigPayload, VMConfig};
+use once_cell::sync::Lazy;
+use std::sync::{Arc, RwLock};
+
+pub(crate) static GAS_SCHEDULE_CACHE: Lazy<GasScheduleCache> = Lazy::new(GasScheduleCache::new);
+
+pub(crate) struct GasScheduleCache {
+    /// The serialized config, and the config parsed from it
+    current: RwLock<Option<(Vec<u8>, Arc<VMConfig>)>>,
+}
+
+impl GasScheduleCache {
+    pub fn new() -> Self {
+        Self {
+            current: RwLock::new(None),
+        }
+    }
+
+    /// The VM config stored in `storage`, parsed only if it differs from the current one, which it
+    /// then replaces
+    pub fn get<S: ConfigStorage>(&self, storage: &S) -> Option<Arc<VMConfig>> {
+        let bytes = storage.fetch_config(VMConfig::CONFIG_ID.access_path())?;
+        if let Some((current_bytes, config)) = &*self
+            .current
+            .read()
+            .expect("diem cannot currently handle a poisoned lock")
+        {
+            if *current_bytes == bytes {
+                return Some(config.clone());
+            }
+        }
+        let config = Arc::new(VMConfig::deserialize_into_config(&bytes).ok()?);
+        self.swap(bytes, config.clone());
+        Some(config)
+    }
+
+    /// Swaps in the VM config of a reconfiguration
+    pub fn update(&self, payload: &OnChainConfigPayload) {
+        let bytes = match payload.configs().get(&VMConfig::CONFIG_ID) {
+            Some(bytes) => bytes,
+            None => return,
+        };
+        match VMConfig::deserialize_into_config(bytes) {
+            Ok(config) => self.swap(bytes.clone(), Arc::new(config)),
+            Err(err) => error!(
+                epoch = payload.epoch(),
+                "Failed to parse the VM config of the reconfiguration: {}", err
+            ),
+        }
+    }
+
+    fn swap(&self, bytes: Vec<u8>, config: Arc<VMConfig>) {
+        let mut current = self
+            .current
+            .write()
+            .expect("diem cannot currently handle a poisoned lock");
+        if matches!(&*current, Some((current_bytes, _)) if *current_bytes == bytes) {
+            return;
+        }
+        *current = Some((bytes, config));
+        GAS_SCHEDULE_SWAPS.inc();
+        info!("Swapped in a new gas schedule");
+    }
+}
+
+/// Swaps in the gas schedule of the reconfiguration, for the blocks of the new epoch
+pub fn update_gas_schedule(payload: &OnChainConfigPayload) {
+    GAS_SCHEDULE_CACHE.update(payload)
+}
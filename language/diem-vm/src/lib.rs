@@ -113,6 +113,9 @@ pub mod foreign_contracts;
 mod adapter_common;
 mod diem_vm_impl;
 mod errors;
+pub mod execution_trace;
+mod gas_schedule_cache;
+mod move_vm_cache;
 pub mod natives;
 pub mod transaction_metadata;
 
@@ -126,8 +129,12 @@ pub mod system_module_names;
 #[cfg(test)]
 mod unit_tests;
 
-pub use crate::{diem_vm::DiemVM, diem_vm_impl::convert_changeset_and_events};
+pub use crate::{
+    diem_vm::DiemVM, diem_vm_impl::convert_changeset_and_events,
+    gas_schedule_cache::update_gas_schedule,
+};
 
+use crate::execution_trace::ExecutionTracer;
 use diem_state_view::StateView;
 use diem_types::{
     access_path::AccessPath,
@@ -151,18 +158,50 @@ pub trait VMValidator {
 
 /// This trait describes the VM's execution interface.
 pub trait VMExecutor: Send + Sync {
-    // NOTE: At the moment there are no persistent caches that live past the end of a block (that's
-    // why execute_block doesn't take &self.)
-    // There are some cache invalidation issues around transactions publishing code that need to be
-    // sorted out before that's possible.
+    // NOTE: The only cache that lives past the end of a block is the Move VM cached by the adapter
+    // itself, see `move_vm_cache` (that's why execute_block doesn't take &self.)
 
     /// Executes a block of transactions and returns output for each one of them.
     fn execute_block(
         transactions: Vec<Transaction>,
         state_view: &dyn StateView,
     ) -> Result<Vec<TransactionOutput>, VMStatus>;
+
+    /// Executes a block of transactions like `execute_block`, appending the trace of every one of
+    /// them to `tracer`. By default, the transactions aren't traced.
+    fn execute_block_with_tracer(
+        transactions: Vec<Transaction>,
+        state_view: &dyn StateView,
+        _tracer: &ExecutionTracer,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        Self::execute_block(transactions, state_view)
+    }
+
+    /// Runs `execute` with a VM set up once for the block on `state_view`, which `execute` may
+    /// call from several threads to execute the transactions of the block one at a time, by index,
+    /// each time on its own view of the state. Those views must read the state through the one
+    /// `execute` is given instead of `state_view`. The transactions aren't executed on top of each
+    /// other: that's up to the views. By default, every call sets up a VM for the transaction.
+    fn execute_block_by_transaction<R>(
+        transactions: Vec<Transaction>,
+        state_view: &dyn StateView,
+        execute: impl FnOnce(&dyn StateView, &TransactionExecutor<'_>) -> R,
+    ) -> R {
+        execute(state_view, &|txn_idx, view| {
+            Self::execute_block(vec![transactions[txn_idx].clone()], view).map(|mut outputs| {
+                outputs
+                    .pop()
+                    .expect("The VM yields an output per transaction")
+            })
+        })
+    }
 }
 
+/// Executes the transaction of a block at an index on a view of the state, see
+/// `VMExecutor::execute_block_by_transaction`
+pub type TransactionExecutor<'a> =
+    dyn Fn(usize, &dyn StateView) -> Result<TransactionOutput, VMStatus> + Sync + 'a;
+
 /// Get the AccessPath to a resource stored under `address` with type name `tag`
 fn create_access_path(address: AccountAddress, tag: StructTag) -> AccessPath {
     let resource_tag = ResourceKey::new(address, tag);
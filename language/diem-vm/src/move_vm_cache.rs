@@ -0,0 +1,156 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A cache of the Move VM across blocks, so the modules and scripts its loader loaded, verified
+//! and linked for a block are reused by the next ones instead of being loaded again.
+//!
+//! The loader never reloads a module it cached, so the VM is only reused for a block if every
+//! module it read from the state is still the same in the state the block executes on, which also
+//! covers the blocks of other forks, and the parents of the blocks of a new epoch. The blocks with
+//! transactions writing modules run on a fresh VM, and drop the cached one: the loader would
+//! otherwise keep the modules the block published, even the ones of a failed transaction.
+
+use crate::{
+    counters::{MOVE_VM_CACHE_LOOKUPS, MOVE_VM_CACHE_MODULES},
+    diem_vm_impl::new_move_vm,
+};
+use anyhow::Result;
+use diem_state_view::{StateView, StateViewId};
+use diem_types::{
+    access_path::AccessPath,
+    transaction::{Transaction, TransactionPayload},
+};
+use move_core_types::language_storage::CODE_TAG;
+use move_vm_runtime::move_vm::MoveVM;
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// The cache shared by the blocks executed by `DiemVM`
+pub(crate) static MOVE_VM_CACHE: Lazy<MoveVMCache> = Lazy::new(MoveVMCache::new);
+
+pub(crate) struct CachedMoveVM {
+    move_vm: Arc<MoveVM>,
+    /// The modules the VM read from the state, by access path
+    modules: HashMap<AccessPath, Vec<u8>>,
+}
+
+impl CachedMoveVM {
+    fn new() -> Self {
+        Self {
+            move_vm: Arc::new(new_move_vm()),
+            modules: HashMap::new(),
+        }
+    }
+
+    pub fn move_vm(&self) -> Arc<MoveVM> {
+        self.move_vm.clone()
+    }
+}
+
+pub(crate) struct MoveVMCache {
+    cached: Mutex<Option<CachedMoveVM>>,
+}
+
+impl MoveVMCache {
+    pub fn new() -> Self {
+        Self {
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Takes the cached VM if it's valid for `state_view`, or creates a new one. Blocks executing
+    /// concurrently never share a VM, since the modules one of them loads may not be valid for the
+    /// other.
+    pub fn checkout(&self, state_view: &dyn StateView) -> CachedMoveVM {
+        let cached = match lock(&self.cached).take() {
+            Some(cached) => cached,
+            None => {
+                MOVE_VM_CACHE_LOOKUPS.with_label_values(&["miss"]).inc();
+                return CachedMoveVM::new();
+            }
+        };
+        let valid = cached.modules.iter().all(|(access_path, bytes)| {
+            matches!(state_view.get(access_path), Ok(Some(current)) if current == *bytes)
+        });
+        if valid {
+            MOVE_VM_CACHE_LOOKUPS.with_label_values(&["hit"]).inc();
+            cached
+        } else {
+            MOVE_VM_CACHE_LOOKUPS
+                .with_label_values(&["invalidated"])
+                .inc();
+            CachedMoveVM::new()
+        }
+    }
+
+    /// Puts back the VM which executed a block, with the modules it read from the state through
+    /// `view`
+    pub fn checkin(&self, mut cached: CachedMoveVM, view: ModuleRecordingView) {
+        cached.modules.extend(
+            view.modules
+                .into_inner()
+                .expect("diem cannot currently handle a poisoned lock"),
+        );
+        MOVE_VM_CACHE_MODULES.set(cached.modules.len() as i64);
+        *lock(&self.cached) = Some(cached);
+    }
+
+    pub fn flush(&self) {
+        MOVE_VM_CACHE_MODULES.set(0);
+        *lock(&self.cached) = None;
+    }
+}
+
+/// Whether the transaction may write modules
+pub(crate) fn writes_modules(transaction: &Transaction) -> bool {
+    match transaction {
+        Transaction::GenesisTransaction(_) => true,
+        Transaction::UserTransaction(txn) => matches!(
+            txn.payload(),
+            TransactionPayload::Module(_) | TransactionPayload::WriteSet(_)
+        ),
+        Transaction::BlockMetadata(_) => false,
+    }
+}
+
+/// A view over the state recording the modules read from it
+pub(crate) struct ModuleRecordingView<'a> {
+    state_view: &'a dyn StateView,
+    modules: Mutex<HashMap<AccessPath, Vec<u8>>>,
+}
+
+impl<'a> ModuleRecordingView<'a> {
+    pub fn new(state_view: &'a dyn StateView) -> Self {
+        Self {
+            state_view,
+            modules: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<'a> StateView for ModuleRecordingView<'a> {
+    fn id(&self) -> StateViewId {
+        self.state_view.id()
+    }
+
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        let value = self.state_view.get(access_path)?;
+        if let (Some(CODE_TAG), Some(bytes)) = (access_path.path.first().copied(), &value) {
+            lock(&self.modules).insert(access_path.clone(), bytes.clone());
+        }
+        Ok(value)
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.state_view.is_genesis()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .expect("diem cannot currently handle a poisoned lock")
+}
@@ -0,0 +1,125 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::execution_trace::{
+    read_execution_trace, ExecutionTracer, TracingView, TransactionTrace,
+};
+use anyhow::Result;
+use diem_crypto::HashValue;
+use diem_state_view::StateView;
+use diem_types::{
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    transaction::{TransactionOutput, TransactionStatus},
+    vm_status::KeptVMStatus,
+    write_set::{WriteOp, WriteSetMut},
+};
+use std::collections::{BTreeMap, HashMap};
+
+struct FakeStateView(HashMap<AccessPath, Vec<u8>>);
+
+impl StateView for FakeStateView {
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        Ok(self.0.get(access_path).cloned())
+    }
+
+    fn is_genesis(&self) -> bool {
+        false
+    }
+}
+
+fn gen_access_path(index: u8) -> AccessPath {
+    AccessPath::new(
+        AccountAddress::new([index; AccountAddress::LENGTH]),
+        vec![index],
+    )
+}
+
+#[test]
+fn test_transaction_trace() {
+    let mut state = HashMap::new();
+    state.insert(gen_access_path(1), vec![1]);
+    state.insert(gen_access_path(3), vec![3]);
+    let state_view = FakeStateView(state);
+
+    let view = TracingView::new(&state_view);
+    for index in &[3, 2, 1, 3] {
+        view.get(&gen_access_path(*index)).unwrap();
+    }
+    let write_set = WriteSetMut::new(vec![
+        (gen_access_path(3), WriteOp::Deletion),
+        (gen_access_path(2), WriteOp::Value(vec![2])),
+    ])
+    .freeze()
+    .unwrap();
+    let output = TransactionOutput::new(
+        write_set,
+        vec![],
+        7,
+        TransactionStatus::Keep(KeptVMStatus::Executed),
+    );
+    let trace = TransactionTrace::new(HashValue::zero(), view, &output, BTreeMap::new());
+
+    // The reads and writes are sorted by path, and every path read is recorded once
+    assert_eq!(
+        trace.reads,
+        vec![
+            (gen_access_path(1), Some(HashValue::sha3_256_of(&[1]))),
+            (gen_access_path(2), None),
+            (gen_access_path(3), Some(HashValue::sha3_256_of(&[3]))),
+        ]
+    );
+    assert_eq!(
+        trace.writes,
+        vec![
+            (gen_access_path(2), Some(HashValue::sha3_256_of(&[2]))),
+            (gen_access_path(3), None),
+        ]
+    );
+    assert_eq!(trace.gas_used, 7);
+
+    let line = serde_json::to_string(&trace).unwrap();
+    assert_eq!(
+        serde_json::from_str::<TransactionTrace>(&line).unwrap(),
+        trace
+    );
+}
+
+#[test]
+fn test_execution_tracer() {
+    let state_view = FakeStateView(HashMap::new());
+    let output = TransactionOutput::new(
+        WriteSetMut::new(vec![]).freeze().unwrap(),
+        vec![],
+        1,
+        TransactionStatus::Keep(KeptVMStatus::Executed),
+    );
+    let path = std::env::temp_dir().join(format!("execution_trace_{}", std::process::id()));
+
+    // Two tracers appending to the same file, like the executors of a node
+    let tracers = [
+        ExecutionTracer::new(&path).unwrap(),
+        ExecutionTracer::new(&path).unwrap(),
+    ];
+    for (index, tracer) in tracers.iter().enumerate() {
+        let view = TracingView::new(&state_view);
+        view.get(&gen_access_path(index as u8)).unwrap();
+        tracer.trace_transaction(
+            HashValue::sha3_256_of(&[index as u8]),
+            view,
+            &output,
+            BTreeMap::new(),
+        );
+    }
+
+    let traces = read_execution_trace(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        traces
+            .iter()
+            .map(|trace| trace.transaction_hash)
+            .collect::<Vec<_>>(),
+        vec![HashValue::sha3_256_of(&[0]), HashValue::sha3_256_of(&[1])]
+    );
+    assert_eq!(traces[1].reads, vec![(gen_access_path(1), None)]);
+}
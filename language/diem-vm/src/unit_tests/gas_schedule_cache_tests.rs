@@ -0,0 +1,69 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::gas_schedule_cache::GasScheduleCache;
+use diem_types::{
+    access_path::AccessPath,
+    on_chain_config::{ConfigStorage, OnChainConfig, OnChainConfigPayload, VMConfig},
+};
+use move_core_types::gas_schedule::{GasConstants, GasCost};
+use move_vm_types::gas_schedule::zero_cost_schedule;
+use std::{collections::HashMap, sync::Arc};
+
+struct FakeConfigStorage(Vec<u8>);
+
+impl ConfigStorage for FakeConfigStorage {
+    fn fetch_config(&self, access_path: AccessPath) -> Option<Vec<u8>> {
+        if access_path == VMConfig::CONFIG_ID.access_path() {
+            Some(self.0.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// Serializes a VM config the way it's stored on chain, with the given cost of its instructions
+fn serialize_vm_config(instruction_cost: u64) -> Vec<u8> {
+    let cost_table = zero_cost_schedule();
+    let instruction_table: Vec<_> = cost_table
+        .instruction_table
+        .iter()
+        .map(|_| GasCost::new(instruction_cost, 1))
+        .collect();
+    bcs::to_bytes(&(
+        bcs::to_bytes(&instruction_table).unwrap(),
+        bcs::to_bytes(&cost_table.native_table).unwrap(),
+        GasConstants::default(),
+    ))
+    .unwrap()
+}
+
+#[test]
+fn test_gas_schedule_parsed_once() {
+    let cache = GasScheduleCache::new();
+    let storage = FakeConfigStorage(serialize_vm_config(1));
+
+    let config = cache.get(&storage).unwrap();
+    assert!(Arc::ptr_eq(&config, &cache.get(&storage).unwrap()));
+
+    // A state with another config swaps it in
+    let new_config = cache
+        .get(&FakeConfigStorage(serialize_vm_config(2)))
+        .unwrap();
+    assert_ne!(config, new_config);
+    assert_eq!(config, cache.get(&storage).unwrap());
+}
+
+#[test]
+fn test_gas_schedule_swapped_on_reconfiguration() {
+    let cache = GasScheduleCache::new();
+    let bytes = serialize_vm_config(3);
+    let mut configs = HashMap::new();
+    configs.insert(VMConfig::CONFIG_ID, bytes.clone());
+    cache.update(&OnChainConfigPayload::new(1, Arc::new(configs)));
+
+    let storage = FakeConfigStorage(bytes.clone());
+    let config = cache.get(&storage).unwrap();
+    assert_eq!(*config, VMConfig::deserialize_into_config(&bytes).unwrap());
+    assert!(Arc::ptr_eq(&config, &cache.get(&storage).unwrap()));
+}
@@ -1,4 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+mod execution_trace_tests;
+mod gas_schedule_cache_tests;
+mod move_vm_cache_tests;
 mod script_to_script_function_tests;
@@ -0,0 +1,94 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::move_vm_cache::{ModuleRecordingView, MoveVMCache};
+use anyhow::Result;
+use diem_state_view::StateView;
+use diem_types::access_path::AccessPath;
+use move_core_types::{
+    identifier::Identifier,
+    language_storage::{ModuleId, CORE_CODE_ADDRESS},
+};
+use move_vm_runtime::move_vm::MoveVM;
+use std::{collections::HashMap, sync::Arc};
+
+struct FakeStateView(HashMap<AccessPath, Vec<u8>>);
+
+impl StateView for FakeStateView {
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        Ok(self.0.get(access_path).cloned())
+    }
+
+    fn is_genesis(&self) -> bool {
+        false
+    }
+}
+
+fn module_path(name: &str) -> AccessPath {
+    AccessPath::code_access_path(ModuleId::new(
+        CORE_CODE_ADDRESS,
+        Identifier::new(name).unwrap(),
+    ))
+}
+
+fn state_with_module(bytes: &[u8]) -> FakeStateView {
+    let mut state = HashMap::new();
+    state.insert(module_path("M"), bytes.to_vec());
+    FakeStateView(state)
+}
+
+/// Executes a block which reads the module M, and puts the VM back in the cache
+fn execute_block(cache: &MoveVMCache, state: &FakeStateView) -> Arc<MoveVM> {
+    let cached = cache.checkout(state);
+    let move_vm = cached.move_vm();
+    let view = ModuleRecordingView::new(state);
+    view.get(&module_path("M")).unwrap();
+    cache.checkin(cached, view);
+    move_vm
+}
+
+#[test]
+fn test_move_vm_reused_across_blocks() {
+    let cache = MoveVMCache::new();
+    let state = state_with_module(b"M");
+    let move_vm = execute_block(&cache, &state);
+    assert!(Arc::ptr_eq(&execute_block(&cache, &state), &move_vm));
+    // Changes to the resources don't invalidate the modules
+    let mut state = state;
+    state
+        .0
+        .insert(AccessPath::new(CORE_CODE_ADDRESS, vec![1, 2, 3]), vec![4]);
+    assert!(Arc::ptr_eq(&execute_block(&cache, &state), &move_vm));
+}
+
+#[test]
+fn test_move_vm_invalidated_by_changed_module() {
+    let cache = MoveVMCache::new();
+    let move_vm = execute_block(&cache, &state_with_module(b"M"));
+    assert!(!Arc::ptr_eq(
+        &execute_block(&cache, &state_with_module(b"M upgraded")),
+        &move_vm
+    ));
+    // Nor is a VM reused for a state without the module
+    let move_vm = execute_block(&cache, &state_with_module(b"M"));
+    assert!(!Arc::ptr_eq(
+        &execute_block(&cache, &FakeStateView(HashMap::new())),
+        &move_vm
+    ));
+}
+
+#[test]
+fn test_move_vm_not_shared_by_concurrent_blocks() {
+    let cache = MoveVMCache::new();
+    let state = state_with_module(b"M");
+    execute_block(&cache, &state);
+    let first = cache.checkout(&state);
+    let second = cache.checkout(&state);
+    assert!(!Arc::ptr_eq(&first.move_vm(), &second.move_vm()));
+
+    cache.flush();
+    assert!(!Arc::ptr_eq(
+        &execute_block(&cache, &state),
+        &first.move_vm()
+    ));
+}
@@ -4,19 +4,22 @@
 use diem_crypto::{
     ed25519::Ed25519PrivateKey,
     multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature},
+    secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey},
     PrivateKey, SigningKey, Uniform,
 };
 use diem_keygen::KeyGen;
 use diem_types::{
+    on_chain_config::DIEM_VERSION_5,
     transaction::{authenticator::AuthenticationKey, SignedTransaction, TransactionStatus},
     vm_status::{KeptVMStatus, StatusCode},
 };
 use language_e2e_tests::{
     account,
     common_transactions::{raw_rotate_key_txn, rotate_key_txn},
-    test_with_different_versions,
+    test_with_different_versions, utils,
     versioning::CURRENT_RELEASE_VERSIONS,
 };
+use std::convert::TryFrom;
 
 #[test]
 fn rotate_ed25519_key() {
@@ -135,6 +138,82 @@ fn rotate_ed25519_multisig_key() {
     }
 }
 
+#[test]
+fn rotate_secp256k1_ecdsa_key() {
+    test_with_different_versions! {CURRENT_RELEASE_VERSIONS, |test_env| {
+        let mut executor = test_env.executor;
+
+        let mut seq_number = 10;
+        // create and publish sender
+        let sender = executor.create_raw_account_data(1_000_000, seq_number);
+        executor.add_account_data(&sender);
+
+        let privkey = Secp256k1PrivateKey::generate_for_testing();
+        let pubkey = Secp256k1PublicKey::from(&privkey);
+        let new_auth_key = AuthenticationKey::secp256k1_ecdsa(&pubkey);
+
+        // (1) rotate key to the secp256k1 key
+        let output = &executor.execute_transaction(rotate_key_txn(
+            sender.account(),
+            new_auth_key.to_vec(),
+            seq_number,
+        ));
+        assert_eq!(
+            output.status(),
+            &TransactionStatus::Keep(KeptVMStatus::Executed),
+        );
+        executor.apply_write_set(output.write_set());
+        seq_number += 1;
+
+        // (2) a tx signed by the secp256k1 key is gated by the Diem version
+        let txn = raw_rotate_key_txn(sender.account(), new_auth_key.to_vec(), seq_number);
+        let signed_txn = txn.sign_secp256k1_ecdsa(&privkey).unwrap().into_inner();
+        let output = &executor.execute_transaction(signed_txn.clone());
+        assert_eq!(
+            output.status(),
+            &TransactionStatus::Discard(StatusCode::FEATURE_UNDER_GATING),
+        );
+        let mut dr_sequence_number = test_env.dr_sequence_number;
+        utils::upgrade_df(
+            &mut executor,
+            &test_env.dr_account,
+            &mut dr_sequence_number,
+            Some(DIEM_VERSION_5.major),
+        );
+
+        // (3) which accepts it once upgraded
+        let output = &executor.execute_transaction(signed_txn);
+        assert_eq!(
+            output.status(),
+            &TransactionStatus::Keep(KeptVMStatus::Executed),
+        );
+        executor.apply_write_set(output.write_set());
+        seq_number += 1;
+
+        // (4) a tx signed by another secp256k1 key doesn't match the authentication key
+        let other_privkey = Secp256k1PrivateKey::try_from(&[7u8; 32][..]).unwrap();
+        let txn = raw_rotate_key_txn(sender.account(), new_auth_key.to_vec(), seq_number);
+        let signed_txn = txn.sign_secp256k1_ecdsa(&other_privkey).unwrap().into_inner();
+        let output = &executor.execute_transaction(signed_txn);
+        assert_eq!(
+            output.status(),
+            &TransactionStatus::Discard(StatusCode::INVALID_AUTH_KEY),
+        );
+
+        // (5) nor does a tx whose signature is for another tx
+        let txn = raw_rotate_key_txn(sender.account(), new_auth_key.to_vec(), seq_number);
+        let other_txn = raw_rotate_key_txn(sender.account(), vec![], seq_number);
+        let signed_txn =
+            SignedTransaction::new_secp256k1_ecdsa(txn, pubkey, privkey.sign(&other_txn));
+        let output = &executor.execute_transaction(signed_txn);
+        assert_eq!(
+            output.status(),
+            &TransactionStatus::Discard(StatusCode::INVALID_SIGNATURE),
+        );
+    }
+    }
+}
+
 #[test]
 
 fn rotate_shared_ed25519_public_key() {}
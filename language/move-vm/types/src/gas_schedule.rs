@@ -24,6 +24,8 @@ use move_core_types::{
     vm_status::StatusCode,
 };
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 static ZERO_COST_SCHEDULE: Lazy<CostTable> = Lazy::new(zero_cost_schedule);
 
@@ -37,6 +39,76 @@ pub struct GasStatus<'a> {
     cost_table: &'a CostTable,
     gas_left: InternalGasUnits<GasCarrier>,
     charge: bool,
+    /// The gas charged by category, in internal gas units, when recorded
+    gas_by_category: Option<BTreeMap<GasCategory, GasCarrier>>,
+}
+
+/// What gas is charged for, to break down the gas used by a transaction
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GasCategory {
+    /// The size of the transaction
+    Intrinsic,
+    /// Branches, returns and aborts
+    ControlFlow,
+    /// Loading constants and casts
+    Constants,
+    /// Copying, moving and storing locals
+    Locals,
+    /// Arithmetic, bitwise, logical and comparison operations
+    Arithmetic,
+    /// Borrowing, reading and writing references
+    References,
+    /// Packing and unpacking structs
+    Structs,
+    /// Accessing resources in global storage
+    GlobalStorage,
+    /// Vector operations
+    Vectors,
+    /// Function calls
+    Calls,
+    /// The gas the native functions charge
+    Natives,
+    /// Any other gas the client charges, like for the writes of a transaction
+    Other,
+}
+
+impl GasCategory {
+    /// The category of the instruction
+    pub fn of_instruction(opcode: Opcodes) -> Self {
+        use Opcodes::*;
+        match opcode {
+            POP | RET | BR_TRUE | BR_FALSE | BRANCH | ABORT | NOP => GasCategory::ControlFlow,
+            LD_U8 | LD_U64 | LD_U128 | LD_CONST | LD_TRUE | LD_FALSE | CAST_U8 | CAST_U64
+            | CAST_U128 => GasCategory::Constants,
+            COPY_LOC | MOVE_LOC | ST_LOC => GasCategory::Locals,
+            ADD | SUB | MUL | MOD | DIV | BIT_OR | BIT_AND | XOR | OR | AND | NOT | EQ | NEQ
+            | LT | GT | LE | GE | SHL | SHR => GasCategory::Arithmetic,
+            MUT_BORROW_LOC
+            | IMM_BORROW_LOC
+            | MUT_BORROW_FIELD
+            | IMM_BORROW_FIELD
+            | MUT_BORROW_FIELD_GENERIC
+            | IMM_BORROW_FIELD_GENERIC
+            | READ_REF
+            | WRITE_REF
+            | FREEZE_REF => GasCategory::References,
+            PACK | UNPACK | PACK_GENERIC | UNPACK_GENERIC => GasCategory::Structs,
+            EXISTS
+            | EXISTS_GENERIC
+            | MUT_BORROW_GLOBAL
+            | MUT_BORROW_GLOBAL_GENERIC
+            | IMM_BORROW_GLOBAL
+            | IMM_BORROW_GLOBAL_GENERIC
+            | MOVE_FROM
+            | MOVE_FROM_GENERIC
+            | MOVE_TO
+            | MOVE_TO_GENERIC => GasCategory::GlobalStorage,
+            VEC_PACK | VEC_LEN | VEC_IMM_BORROW | VEC_MUT_BORROW | VEC_PUSH_BACK | VEC_POP_BACK
+            | VEC_UNPACK | VEC_SWAP => GasCategory::Vectors,
+            CALL | CALL_GENERIC => GasCategory::Calls,
+        }
+    }
 }
 
 impl<'a> GasStatus<'a> {
@@ -49,6 +121,7 @@ impl<'a> GasStatus<'a> {
             gas_left: cost_table.gas_constants.to_internal_units(gas_left),
             cost_table,
             charge: true,
+            gas_by_category: None,
         }
     }
 
@@ -61,6 +134,7 @@ impl<'a> GasStatus<'a> {
             gas_left: InternalGasUnits::new(0),
             cost_table: &ZERO_COST_SCHEDULE,
             charge: false,
+            gas_by_category: None,
         }
     }
 
@@ -76,11 +150,43 @@ impl<'a> GasStatus<'a> {
             .to_external_units(self.gas_left)
     }
 
+    /// Start recording the gas charged by category, see `gas_by_category`.
+    pub fn record_gas_by_category(&mut self) {
+        self.gas_by_category.get_or_insert_with(BTreeMap::new);
+    }
+
+    /// Return the gas charged by category since the recording started, in internal gas units.
+    /// The gas of an operation running out of gas is the gas that was left.
+    pub fn gas_by_category(&self) -> Option<&BTreeMap<GasCategory, GasCarrier>> {
+        self.gas_by_category.as_ref()
+    }
+
     /// Charge a given amount of gas and fail if not enough gas units are left.
     pub fn deduct_gas(&mut self, amount: InternalGasUnits<GasCarrier>) -> PartialVMResult<()> {
+        self.deduct_gas_for(GasCategory::Other, amount)
+    }
+
+    /// Charge a given amount of gas for the native function which computed it and fail if not
+    /// enough gas units are left.
+    pub fn charge_native_function(
+        &mut self,
+        amount: InternalGasUnits<GasCarrier>,
+    ) -> PartialVMResult<()> {
+        self.deduct_gas_for(GasCategory::Natives, amount)
+    }
+
+    fn deduct_gas_for(
+        &mut self,
+        category: GasCategory,
+        amount: InternalGasUnits<GasCarrier>,
+    ) -> PartialVMResult<()> {
         if !self.charge {
             return Ok(());
         }
+        if let Some(gas_by_category) = &mut self.gas_by_category {
+            let charged = std::cmp::min(amount.get(), self.gas_left.get());
+            *gas_by_category.entry(category).or_insert(0) += charged;
+        }
         if self
             .gas_left
             .app(&amount, |curr_gas, gas_amt| curr_gas >= gas_amt)
@@ -103,7 +209,8 @@ impl<'a> GasStatus<'a> {
         // Make sure that the size is always non-zero
         let size = size.map(|x| std::cmp::max(1, x));
         debug_assert!(size.get() > 0);
-        self.deduct_gas(
+        self.deduct_gas_for(
+            GasCategory::of_instruction(opcode),
             self.cost_table
                 .instruction_cost(opcode as u8)
                 .total()
@@ -113,7 +220,10 @@ impl<'a> GasStatus<'a> {
 
     /// Charge an instruction and fail if not enough gas units are left.
     pub fn charge_instr(&mut self, opcode: Opcodes) -> PartialVMResult<()> {
-        self.deduct_gas(self.cost_table.instruction_cost(opcode as u8).total())
+        self.deduct_gas_for(
+            GasCategory::of_instruction(opcode),
+            self.cost_table.instruction_cost(opcode as u8).total(),
+        )
     }
 
     /// Charge gas related to the overall size of a transaction and fail if not enough
@@ -123,7 +233,7 @@ impl<'a> GasStatus<'a> {
         intrinsic_cost: AbstractMemorySize<GasCarrier>,
     ) -> VMResult<()> {
         let cost = calculate_intrinsic_gas(intrinsic_cost, &self.cost_table.gas_constants);
-        self.deduct_gas(cost)
+        self.deduct_gas_for(GasCategory::Intrinsic, cost)
             .map_err(|e| e.finish(Location::Undefined))
     }
 
@@ -0,0 +1,146 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Policies picking the transactions evicted when Mempool is full.
+use crate::core_mempool::{
+    index::{ParkingLotIndex, PriorityIndex, TTLIndex, TimelineIndex, TxnPointer},
+    transaction::MempoolTransaction,
+};
+use diem_config::config::EvictionPolicyType;
+use diem_types::account_address::AccountAddress;
+
+/// The indexes of Mempool the policies pick the evicted transactions from.
+pub(crate) struct EvictionCandidates<'a> {
+    pub system_ttl_index: &'a TTLIndex,
+    pub priority_index: &'a PriorityIndex,
+    pub timeline_index: &'a TimelineIndex,
+    pub parking_lot_index: &'a ParkingLotIndex,
+}
+
+/// Picks the transaction to evict to make space for a new ready transaction once Mempool is full.
+/// The evicted transaction leaves along with the later transactions of its account, which can't be
+/// executed without it, so the transactions the new transaction depends on must never be picked.
+pub(crate) trait EvictionPolicy: Send + Sync {
+    /// Name reported in the metrics of the evictions.
+    fn name(&self) -> &'static str;
+
+    /// Returns the transaction to evict for `txn`, or None if no transaction is worth evicting.
+    fn select(
+        &self,
+        candidates: &EvictionCandidates,
+        txn: &MempoolTransaction,
+    ) -> Option<TxnPointer>;
+}
+
+/// Whether the transaction can be evicted for the new transaction `txn`: it mustn't be one of the
+/// transactions of the same account `txn` depends on.
+fn is_evictable(address: &AccountAddress, sequence_number: u64, txn: &MempoolTransaction) -> bool {
+    *address != txn.get_sender() || sequence_number > txn.sequence_info.transaction_sequence_number
+}
+
+pub(crate) fn eviction_policy(policy_type: EvictionPolicyType) -> Box<dyn EvictionPolicy> {
+    match policy_type {
+        EvictionPolicyType::ParkingLot => Box::new(ParkingLotPolicy),
+        EvictionPolicyType::Ttl => Box::new(TtlPolicy),
+        EvictionPolicyType::LowestGasFirst => Box::new(LowestGasFirstPolicy),
+        EvictionPolicyType::OldestReadyFirst => Box::new(OldestReadyFirstPolicy),
+    }
+}
+
+/// Evicts a random transaction that isn't ready, the one with the highest sequence number of its
+/// account.
+struct ParkingLotPolicy;
+
+impl EvictionPolicy for ParkingLotPolicy {
+    fn name(&self) -> &'static str {
+        "parking_lot"
+    }
+
+    fn select(
+        &self,
+        candidates: &EvictionCandidates,
+        txn: &MempoolTransaction,
+    ) -> Option<TxnPointer> {
+        candidates
+            .parking_lot_index
+            .get_poppable()
+            .filter(|(address, sequence_number)| is_evictable(address, *sequence_number, txn))
+    }
+}
+
+/// Evicts the transaction expiring first, if it expires before the new transaction.
+struct TtlPolicy;
+
+impl EvictionPolicy for TtlPolicy {
+    fn name(&self) -> &'static str {
+        "ttl"
+    }
+
+    fn select(
+        &self,
+        candidates: &EvictionCandidates,
+        txn: &MempoolTransaction,
+    ) -> Option<TxnPointer> {
+        let new_key = candidates.system_ttl_index.make_key(txn);
+        candidates
+            .system_ttl_index
+            .iter()
+            .take_while(|key| key.expiration_time < new_key.expiration_time)
+            .find(|key| is_evictable(&key.address, key.sequence_number, txn))
+            .map(|key| (key.address, key.sequence_number))
+    }
+}
+
+/// Evicts the ready transaction with the lowest priority, if the new transaction has a higher one.
+struct LowestGasFirstPolicy;
+
+impl EvictionPolicy for LowestGasFirstPolicy {
+    fn name(&self) -> &'static str {
+        "lowest_gas_first"
+    }
+
+    fn select(
+        &self,
+        candidates: &EvictionCandidates,
+        txn: &MempoolTransaction,
+    ) -> Option<TxnPointer> {
+        let new_key = candidates.priority_index.make_key(txn);
+        candidates
+            .priority_index
+            .iter_lowest()
+            .take_while(|key| **key < new_key)
+            .find(|key| {
+                key.gas_ranking_score < new_key.gas_ranking_score
+                    && is_evictable(
+                        &key.address,
+                        key.sequence_number.transaction_sequence_number,
+                        txn,
+                    )
+            })
+            .map(|key| (key.address, key.sequence_number.transaction_sequence_number))
+    }
+}
+
+/// Evicts a transaction that isn't ready as the parking lot policy does, or the transaction that
+/// has been ready for the longest time once every transaction is ready.
+struct OldestReadyFirstPolicy;
+
+impl EvictionPolicy for OldestReadyFirstPolicy {
+    fn name(&self) -> &'static str {
+        "oldest_ready_first"
+    }
+
+    fn select(
+        &self,
+        candidates: &EvictionCandidates,
+        txn: &MempoolTransaction,
+    ) -> Option<TxnPointer> {
+        ParkingLotPolicy.select(candidates, txn).or_else(|| {
+            candidates
+                .timeline_index
+                .iter()
+                .find(|(address, sequence_number)| is_evictable(address, *sequence_number, txn))
+                .cloned()
+        })
+    }
+}
@@ -51,7 +51,7 @@ impl PriorityIndex {
         self.data.contains(&self.make_key(txn))
     }
 
-    fn make_key(&self, txn: &MempoolTransaction) -> OrderedQueueKey {
+    pub(crate) fn make_key(&self, txn: &MempoolTransaction) -> OrderedQueueKey {
         OrderedQueueKey {
             gas_ranking_score: txn.ranking_score,
             expiration_time: txn.expiration_time,
@@ -65,6 +65,11 @@ impl PriorityIndex {
         self.data.iter().rev()
     }
 
+    /// Iterates from the lowest priority transaction.
+    pub(crate) fn iter_lowest(&self) -> Iter<OrderedQueueKey> {
+        self.data.iter()
+    }
+
     pub(crate) fn size(&self) -> usize {
         self.data.len()
     }
@@ -114,6 +119,61 @@ impl Ord for OrderedQueueKey {
     }
 }
 
+/// BlockQueueKey orders the ready transactions competing for a block.
+/// Transactions are ordered by governance role first, then by bucket of gas price. Within a bucket,
+/// the accounts with fewer transactions already picked for the block go first, so that a single
+/// account can't crowd out the other accounts paying a similar price. Remaining ties are broken
+/// the same way as in the PriorityIndex.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct BlockQueueKey {
+    pub gas_bucket: usize,
+    pub num_picked: usize,
+    pub key: OrderedQueueKey,
+}
+
+impl BlockQueueKey {
+    /// `gas_buckets` are the sorted lower bounds of the gas price buckets.
+    pub fn new(key: &OrderedQueueKey, gas_buckets: &[u64], num_picked: usize) -> Self {
+        Self {
+            gas_bucket: gas_buckets
+                .iter()
+                .take_while(|bucket| **bucket <= key.gas_ranking_score)
+                .count(),
+            num_picked,
+            key: key.clone(),
+        }
+    }
+}
+
+impl PartialOrd for BlockQueueKey {
+    fn partial_cmp(&self, other: &BlockQueueKey) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BlockQueueKey {
+    fn cmp(&self, other: &BlockQueueKey) -> Ordering {
+        match self
+            .key
+            .governance_role
+            .priority()
+            .cmp(&other.key.governance_role.priority())
+        {
+            Ordering::Equal => {}
+            ordering => return ordering,
+        }
+        match self.gas_bucket.cmp(&other.gas_bucket) {
+            Ordering::Equal => {}
+            ordering => return ordering,
+        }
+        match self.num_picked.cmp(&other.num_picked).reverse() {
+            Ordering::Equal => {}
+            ordering => return ordering,
+        }
+        self.key.cmp(&other.key)
+    }
+}
+
 /// TTLIndex is used to perform garbage collection of old transactions in Mempool.
 /// Periodically separate GC-like job queries this index to find out transactions that have to be
 /// removed. Index is represented as `BTreeSet<TTLOrderingKey>`, where `TTLOrderingKey`
@@ -158,7 +218,12 @@ impl TTLIndex {
         ttl_transactions
     }
 
-    fn make_key(&self, txn: &MempoolTransaction) -> TTLOrderingKey {
+    /// Iterates from the transaction expiring first.
+    pub(crate) fn iter(&self) -> Iter<TTLOrderingKey> {
+        self.data.iter()
+    }
+
+    pub(crate) fn make_key(&self, txn: &MempoolTransaction) -> TTLOrderingKey {
         TTLOrderingKey {
             expiration_time: (self.get_expiration_time)(txn),
             address: txn.get_sender(),
@@ -263,6 +328,11 @@ impl TimelineIndex {
         }
     }
 
+    /// Iterates from the transaction that became ready first.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &TxnPointer> {
+        self.timeline.values()
+    }
+
     pub(crate) fn size(&self) -> usize {
         self.timeline.len()
     }
@@ -349,7 +419,7 @@ impl ParkingLotIndex {
     }
 
     /// Returns a random "non-ready" transaction (with highest sequence number for that account).
-    pub(crate) fn get_poppable(&mut self) -> Option<TxnPointer> {
+    pub(crate) fn get_poppable(&self) -> Option<TxnPointer> {
         let mut rng = rand::thread_rng();
         self.data
             .choose(&mut rng)
@@ -359,6 +429,13 @@ impl ParkingLotIndex {
     pub(crate) fn size(&self) -> usize {
         self.size
     }
+
+    /// Iterates over the "non-ready" transactions, grouped by account.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = TxnPointer> + '_ {
+        self.data
+            .iter()
+            .flat_map(|(sender, seq_nums)| seq_nums.iter().map(move |seq_num| (*sender, *seq_num)))
+    }
 }
 
 /// Logical pointer to `MempoolTransaction`.
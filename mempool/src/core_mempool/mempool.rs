@@ -5,15 +5,18 @@
 //! agreed upon.
 use crate::{
     core_mempool::{
-        index::TxnPointer,
+        index::{BlockQueueKey, OrderedQueueKey, TxnPointer},
+        persistence::TransactionLog,
         transaction::{MempoolTransaction, TimelineState},
+        transaction_status::{TransactionStatus, TransactionStatusBus, TransactionStatusReceiver},
         transaction_store::TransactionStore,
         ttl_cache::TtlCache,
     },
     counters,
     logging::{LogEntry, LogSchema, TxnsLog},
 };
-use diem_config::config::NodeConfig;
+use diem_config::config::{MempoolConfig, NodeConfig};
+use diem_crypto::HashValue;
 use diem_logger::prelude::*;
 use diem_types::{
     account_address::AccountAddress,
@@ -23,7 +26,9 @@ use diem_types::{
 };
 use std::{
     cmp::max,
-    collections::HashSet,
+    collections::{BinaryHeap, HashMap, HashSet},
+    io,
+    path::Path,
     time::{Duration, SystemTime},
 };
 
@@ -37,6 +42,10 @@ pub struct Mempool {
     // takes to pick it up by consensus.
     pub(crate) metrics_cache: TtlCache<(AccountAddress, u64), SystemTime>,
     pub system_transaction_timeout: Duration,
+    // Lower bounds of the gas price buckets transactions compete in for a block
+    gas_price_buckets: Vec<u64>,
+    // Log of the inserted and removed transactions, if they're persisted across restarts
+    transaction_log: Option<TransactionLog>,
 }
 
 impl Mempool {
@@ -48,9 +57,26 @@ impl Mempool {
             system_transaction_timeout: Duration::from_secs(
                 config.mempool.system_transaction_timeout_secs,
             ),
+            gas_price_buckets: config.mempool.gas_price_buckets.clone(),
+            transaction_log: None,
         }
     }
 
+    /// Starts persisting the transactions of Mempool to a new log at `path`, which replaces any
+    /// existing one.
+    pub(crate) fn persist_to(&mut self, path: &Path) -> io::Result<()> {
+        self.transaction_log = Some(TransactionLog::create(
+            path,
+            self.transactions.iter_transactions(),
+        )?);
+        Ok(())
+    }
+
+    fn log_persistence_error(e: io::Error) {
+        let e = anyhow::Error::from(e);
+        error!(LogSchema::new(LogEntry::Persistence).error(&e));
+    }
+
     /// This function will be called once the transaction has been stored.
     pub(crate) fn remove_transaction(
         &mut self,
@@ -69,6 +95,11 @@ impl Mempool {
         };
         self.log_latency(*sender, sequence_number, metric_label);
         self.metrics_cache.remove(&(*sender, sequence_number));
+        if let Some(log) = self.transaction_log.as_mut() {
+            if let Err(e) = log.remove(*sender, sequence_number) {
+                Self::log_persistence_error(e);
+            }
+        }
 
         let current_seq_number = self
             .sequence_number_cache
@@ -118,6 +149,12 @@ impl Mempool {
         }
     }
 
+    /// Checks that the balance of the sender covers the max gas cost of the transaction, on top of
+    /// the transactions of the sender already in Mempool.
+    pub(crate) fn covers_max_gas_cost(&self, txn: &SignedTransaction, balance: u64) -> bool {
+        self.transactions.covers_max_gas_cost(txn, balance)
+    }
+
     /// Used to add a transaction to the Mempool.
     /// Performs basic validation: checks account's sequence number.
     pub(crate) fn add_txn(
@@ -161,6 +198,7 @@ impl Mempool {
                 .insert((txn.sender(), txn.sequence_number()), SystemTime::now());
         }
 
+        let persisted_txn = self.transaction_log.as_ref().map(|_| txn.clone());
         let txn_info = MempoolTransaction::new(
             txn,
             expiration_time,
@@ -171,66 +209,100 @@ impl Mempool {
             sequence_number,
         );
 
-        self.transactions.insert(txn_info)
+        let status = self.transactions.insert(txn_info);
+        if let (Some(log), Some(txn)) = (self.transaction_log.as_mut(), persisted_txn) {
+            if status.code == MempoolStatusCode::Accepted {
+                if let Err(e) = log.insert(&txn) {
+                    Self::log_persistence_error(e);
+                }
+            }
+        }
+        status
     }
 
     /// Fetches next block of transactions for consensus.
     /// `batch_size` - size of requested block.
     /// `seen_txns` - transactions that were sent to Consensus but were not committed yet,
     ///  mempool should filter out such transactions.
-    #[allow(clippy::explicit_counter_loop)]
     pub(crate) fn get_block(
         &mut self,
         batch_size: u64,
         mut seen: HashSet<TxnPointer>,
     ) -> Vec<SignedTransaction> {
         let mut result = vec![];
-        // Helper DS. Helps to mitigate scenarios where account submits several transactions
-        // with increasing gas price (e.g. user submits transactions with sequence number 1, 2
-        // and gas_price 1, 10 respectively)
-        // Later txn has higher gas price and will be observed first in priority index iterator,
-        // but can't be executed before first txn. Once observed, such txn will be saved in
-        // `skipped` DS and rechecked once it's ancestor becomes available
-        let mut skipped = HashSet::new();
+        // A single ready transaction per account competes for the block at any time, the next
+        // one of the account replaces it once it's picked. This keeps the transactions of an
+        // account in sequence number order (by gas price for CRSNs) while the accounts compete
+        // with each other by gas price.
+        let mut candidates = BinaryHeap::new();
+        let mut accounts_with_candidate = HashSet::new();
+        // Helper DS. Transactions that can't be included yet: either they're waiting for the
+        // candidate of their account to be picked, or for an ancestor not observed yet (e.g. user
+        // submits transactions with sequence number 1, 2 and gas_price 1, 10 respectively)
+        let mut pending: HashMap<AccountAddress, Vec<&OrderedQueueKey>> = HashMap::new();
+        let mut num_picked: HashMap<AccountAddress, usize> = HashMap::new();
         let seen_size = seen.len();
         let mut txn_walked = 0usize;
-        // iterate over the queue of transactions based on gas price
-        'main: for txn in self.transactions.iter_queue() {
-            txn_walked += 1;
-            if seen.contains(&TxnPointer::from(txn)) {
-                continue;
-            }
-            let account_seqtype = txn.sequence_number.account_sequence_number_type;
+
+        // include transaction if it's "next" for given account or
+        // we've already sent its ancestor to Consensus. In the case of CRSNs, we can safely
+        // assume that it can be included.
+        let is_ready = |txn: &OrderedQueueKey, seen: &HashSet<TxnPointer>| {
             let tx_seq = txn.sequence_number.transaction_sequence_number;
-            let account_sequence_number = self.sequence_number_cache.get(&txn.address);
-            let seen_previous = tx_seq > 0 && seen.contains(&(txn.address, tx_seq - 1));
-            // include transaction if it's "next" for given account or
-            // we've already sent its ancestor to Consensus. In the case of CRSNs, we can safely
-            // assume that it can be included.
-            if seen_previous
-                || account_sequence_number == Some(&tx_seq)
-                || matches!(account_seqtype, AccountSequenceInfo::CRSN { .. })
-            {
-                let ptr = TxnPointer::from(txn);
+            (tx_seq > 0 && seen.contains(&(txn.address, tx_seq - 1)))
+                || self.sequence_number_cache.get(&txn.address) == Some(&tx_seq)
+                || matches!(
+                    txn.sequence_number.account_sequence_number_type,
+                    AccountSequenceInfo::CRSN { .. }
+                )
+        };
+
+        // iterate over the queue of transactions based on gas price
+        let mut queue = self.transactions.iter_queue().peekable();
+        'main: loop {
+            // pick the best candidates as long as no transaction left in the queue can beat them
+            while let Some(best) = candidates.peek() {
+                if let Some(next) = queue.peek() {
+                    if BlockQueueKey::new(next, &self.gas_price_buckets, 0) > *best {
+                        break;
+                    }
+                }
+                let BlockQueueKey { key, .. } = candidates.pop().expect("candidate must exist");
+                accounts_with_candidate.remove(&key.address);
+                let ptr = TxnPointer::from(&key);
                 seen.insert(ptr);
                 result.push(ptr);
                 if (result.len() as u64) == batch_size {
-                    break;
+                    break 'main;
                 }
 
-                // check if we can now include some transactions
-                // that were skipped before for given account
-                let mut skipped_txn = (txn.address, tx_seq + 1);
-                while skipped.contains(&skipped_txn) {
-                    seen.insert(skipped_txn);
-                    result.push(skipped_txn);
-                    if (result.len() as u64) == batch_size {
-                        break 'main;
+                // check if we can now include some transaction
+                // that was pending before for given account
+                let picked = num_picked.entry(key.address).or_default();
+                *picked += 1;
+                if let Some(txns) = pending.get_mut(&key.address) {
+                    if let Some(index) = txns.iter().position(|txn| is_ready(txn, &seen)) {
+                        let txn = txns.remove(index);
+                        candidates.push(BlockQueueKey::new(txn, &self.gas_price_buckets, *picked));
+                        accounts_with_candidate.insert(key.address);
                     }
-                    skipped_txn = (txn.address, skipped_txn.1 + 1);
                 }
+            }
+
+            let txn = match queue.next() {
+                Some(txn) => txn,
+                None => break,
+            };
+            txn_walked += 1;
+            if seen.contains(&TxnPointer::from(txn)) {
+                continue;
+            }
+            if !accounts_with_candidate.contains(&txn.address) && is_ready(txn, &seen) {
+                let picked = num_picked.get(&txn.address).copied().unwrap_or_default();
+                candidates.push(BlockQueueKey::new(txn, &self.gas_price_buckets, picked));
+                accounts_with_candidate.insert(txn.address);
             } else {
-                skipped.insert(TxnPointer::from(txn));
+                pending.entry(txn.address).or_default().push(txn);
             }
         }
         let result_size = result.len();
@@ -258,6 +330,9 @@ impl Mempool {
                 transaction.sequence_number(),
                 counters::GET_BLOCK_STAGE_LABEL,
             );
+            self.transactions
+                .status_bus()
+                .record(transaction, TransactionStatus::IncludedInBlock);
         }
         block
     }
@@ -270,6 +345,14 @@ impl Mempool {
         self.transactions.gc_by_system_ttl(&self.metrics_cache);
         self.metrics_cache.gc(now);
         self.sequence_number_cache.gc(now);
+        if let Some(log) = self.transaction_log.as_mut() {
+            if let Err(e) = log.maybe_compact(
+                self.transactions.size(),
+                self.transactions.iter_transactions(),
+            ) {
+                Self::log_persistence_error(e);
+            }
+        }
     }
 
     /// Garbage collection based on client-specified expiration time.
@@ -297,8 +380,94 @@ impl Mempool {
         self.transactions.gen_snapshot(&self.metrics_cache)
     }
 
-    #[cfg(test)]
     pub fn get_parking_lot_size(&self) -> usize {
         self.transactions.get_parking_lot_size()
     }
+
+    /// Number of transactions in Mempool, ready or not.
+    pub(crate) fn size(&self) -> usize {
+        self.transactions.size()
+    }
+
+    /// Lists the "non-ready" transactions of the parking lot.
+    pub(crate) fn parking_lot_transactions(&self) -> Vec<TxnPointer> {
+        self.transactions.iter_parking_lot().collect()
+    }
+
+    /// Lists the transactions of the account, with whether they're in the parking lot.
+    pub(crate) fn account_transactions(
+        &self,
+        address: &AccountAddress,
+    ) -> Vec<(SignedTransaction, bool)> {
+        self.transactions.account_transactions(address)
+    }
+
+    /// Evicts the transaction with the given hash and the later transactions of its sender.
+    /// Returns the number of evicted transactions.
+    pub(crate) fn evict_by_hash(&mut self, hash: &HashValue) -> usize {
+        match self.transactions.find_by_hash(hash) {
+            Some((address, sequence_number)) => {
+                self.evict_transactions_from(&address, sequence_number)
+            }
+            None => 0,
+        }
+    }
+
+    /// Evicts all the transactions of the account.
+    /// Returns the number of evicted transactions.
+    pub(crate) fn evict_account(&mut self, address: &AccountAddress) -> usize {
+        self.evict_transactions_from(address, 0)
+    }
+
+    fn evict_transactions_from(&mut self, address: &AccountAddress, sequence_number: u64) -> usize {
+        let evicted = self
+            .transactions
+            .evict_transactions_from(address, sequence_number);
+        let mut txns_log = TxnsLog::new();
+        for sequence_number in &evicted {
+            txns_log.add(*address, *sequence_number);
+            self.metrics_cache.remove(&(*address, *sequence_number));
+            if let Some(log) = self.transaction_log.as_mut() {
+                if let Err(e) = log.remove(*address, *sequence_number) {
+                    Self::log_persistence_error(e);
+                }
+            }
+        }
+        info!(LogSchema::new(LogEntry::AdminEvictTxns).txns(txns_log));
+        evicted.len()
+    }
+
+    /// Returns the stream of the lifecycle events of the transactions of `account`, or of all the
+    /// transactions if None.
+    pub(crate) fn subscribe_status(
+        &self,
+        account: Option<AccountAddress>,
+    ) -> TransactionStatusReceiver {
+        self.transactions.status_bus().subscribe(account)
+    }
+
+    /// The bus sending the lifecycle events of the transactions, once out of the Mempool lock.
+    pub(crate) fn status_bus(&self) -> TransactionStatusBus {
+        self.transactions.status_bus().clone()
+    }
+
+    /// Reports the broadcast of the transactions from timeline from `start_id` (exclusive) to
+    /// `end_id` (inclusive) to the subscribers.
+    pub(crate) fn notify_broadcast(&mut self, start_id: u64, end_id: u64) {
+        let status_bus = self.status_bus();
+        if !status_bus.has_subscribers() {
+            return;
+        }
+        for txn in self.transactions.timeline_range(start_id, end_id) {
+            status_bus.record(&txn, TransactionStatus::Broadcast);
+        }
+    }
+
+    pub fn get_size_bytes(&self) -> usize {
+        self.transactions.get_size_bytes()
+    }
+
+    pub(crate) fn update_capacities(&mut self, config: &MempoolConfig) {
+        self.transactions.update_capacities(config);
+    }
 }
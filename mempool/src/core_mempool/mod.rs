@@ -1,12 +1,21 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+mod eviction;
 mod index;
 mod mempool;
+mod persistence;
 mod transaction;
+mod transaction_status;
 mod transaction_store;
 mod ttl_cache;
 
 #[cfg(test)]
 pub use self::ttl_cache::TtlCache;
-pub use self::{index::TxnPointer, mempool::Mempool as CoreMempool, transaction::TimelineState};
+pub use self::{
+    index::TxnPointer,
+    mempool::Mempool as CoreMempool,
+    persistence::TransactionLog,
+    transaction::TimelineState,
+    transaction_status::{TransactionStatus, TransactionStatusEvent, TransactionStatusReceiver},
+};
@@ -0,0 +1,141 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+/// This module persists the pending transactions of Mempool across restarts.
+use diem_types::{account_address::AccountAddress, transaction::SignedTransaction};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+// The log is compacted once it holds this many records more than twice the pending transactions
+pub const COMPACTION_THRESHOLD: usize = 10_000;
+
+#[derive(Deserialize, Serialize)]
+enum LogRecord {
+    Insert(SignedTransaction),
+    Remove(AccountAddress, u64),
+}
+
+/// TransactionLog is an append-only log of the transactions inserted in and removed from Mempool.
+/// Every record is written as Mempool applies the change, but the log isn't synced: a crash of the
+/// machine (not only of the process) may lose the last records.
+///
+/// On startup the transactions still pending according to the log are validated again against the
+/// current state, and the log is rewritten with the ones that are accepted again.
+pub struct TransactionLog {
+    path: PathBuf,
+    file: File,
+    num_records: usize,
+}
+
+impl TransactionLog {
+    /// Reads the transactions pending according to the log at `path`, if it exists.
+    /// A truncated last record (e.g. the process died while writing it) is ignored.
+    pub(crate) fn read(path: &Path) -> io::Result<Vec<SignedTransaction>> {
+        let mut bytes = vec![];
+        match File::open(path) {
+            Ok(mut file) => file.read_to_end(&mut bytes)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        let mut transactions = BTreeMap::new();
+        let mut remaining = bytes.as_slice();
+        while remaining.len() >= 4 {
+            let mut len = [0u8; 4];
+            len.copy_from_slice(&remaining[..4]);
+            let len = u32::from_le_bytes(len) as usize;
+            if remaining.len() < 4 + len {
+                break;
+            }
+            let record = match bcs::from_bytes(&remaining[4..4 + len]) {
+                Ok(record) => record,
+                Err(_) => break,
+            };
+            remaining = &remaining[4 + len..];
+            match record {
+                LogRecord::Insert(txn) => {
+                    transactions.insert((txn.sender(), txn.sequence_number()), txn);
+                }
+                LogRecord::Remove(address, sequence_number) => {
+                    transactions.remove(&(address, sequence_number));
+                }
+            }
+        }
+        Ok(transactions.into_iter().map(|(_, txn)| txn).collect())
+    }
+
+    /// Creates a log at `path` holding the given transactions, replacing any existing log.
+    pub(crate) fn create<'a>(
+        path: &Path,
+        transactions: impl Iterator<Item = &'a SignedTransaction>,
+    ) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp_path = path.with_extension("tmp");
+        let mut bytes = vec![];
+        let mut num_records = 0;
+        for txn in transactions {
+            encode_record(&LogRecord::Insert(txn.clone()), &mut bytes)?;
+            num_records += 1;
+        }
+        let mut tmp_file = File::create(&tmp_path)?;
+        tmp_file.write_all(&bytes)?;
+        tmp_file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: OpenOptions::new().append(true).open(path)?,
+            num_records,
+        })
+    }
+
+    /// Records a transaction inserted in Mempool.
+    pub(crate) fn insert(&mut self, txn: &SignedTransaction) -> io::Result<()> {
+        self.append(&LogRecord::Insert(txn.clone()))
+    }
+
+    /// Records a transaction removed from Mempool.
+    pub(crate) fn remove(
+        &mut self,
+        address: AccountAddress,
+        sequence_number: u64,
+    ) -> io::Result<()> {
+        self.append(&LogRecord::Remove(address, sequence_number))
+    }
+
+    /// Rewrites the log with the given transactions once most records are stale.
+    pub(crate) fn maybe_compact<'a>(
+        &mut self,
+        num_transactions: usize,
+        transactions: impl Iterator<Item = &'a SignedTransaction>,
+    ) -> io::Result<()> {
+        if self.num_records <= 2 * num_transactions + COMPACTION_THRESHOLD {
+            return Ok(());
+        }
+        *self = Self::create(&self.path, transactions)?;
+        Ok(())
+    }
+
+    fn append(&mut self, record: &LogRecord) -> io::Result<()> {
+        let mut bytes = vec![];
+        encode_record(record, &mut bytes)?;
+        self.file.write_all(&bytes)?;
+        self.num_records += 1;
+        Ok(())
+    }
+}
+
+fn encode_record(record: &LogRecord, bytes: &mut Vec<u8>) -> io::Result<()> {
+    let record =
+        bcs::to_bytes(record).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    bytes.extend_from_slice(&(record.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&record);
+    Ok(())
+}
@@ -7,7 +7,7 @@ use diem_types::{
     transaction::{GovernanceRole, SignedTransaction},
 };
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::{mem::size_of, time::Duration};
 
 #[derive(Clone, Debug)]
 pub struct MempoolTransaction {
@@ -19,6 +19,8 @@ pub struct MempoolTransaction {
     pub timeline_state: TimelineState,
     pub governance_role: GovernanceRole,
     pub sequence_info: SequenceInfo,
+    // Estimated memory used by the transaction, counted against the capacity of mempool
+    pub estimated_bytes: usize,
 }
 
 impl MempoolTransaction {
@@ -31,6 +33,8 @@ impl MempoolTransaction {
         governance_role: GovernanceRole,
         seqno_type: AccountSequenceInfo,
     ) -> Self {
+        let estimated_bytes =
+            size_of::<Self>() + bcs::to_bytes(&txn).map_or(0, |bytes| bytes.len());
         Self {
             sequence_info: SequenceInfo {
                 transaction_sequence_number: txn.sequence_number(),
@@ -42,6 +46,7 @@ impl MempoolTransaction {
             ranking_score,
             timeline_state,
             governance_role,
+            estimated_bytes,
         }
     }
     pub(crate) fn get_sender(&self) -> AccountAddress {
@@ -0,0 +1,177 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Streams of the lifecycle events of the transactions of Mempool, for clients (e.g., JSON-RPC) to
+//! follow their transactions without polling.
+use crate::counters;
+use channel::{diem_channel, message_queues::QueueStyle};
+use diem_crypto::{hash::CryptoHash, HashValue};
+use diem_infallible::Mutex;
+use diem_types::{
+    account_address::AccountAddress,
+    transaction::{SignedTransaction, Transaction},
+};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::Notify;
+
+/// Step of the lifecycle of a transaction in Mempool.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TransactionStatus {
+    /// Accepted in Mempool
+    Accepted,
+    /// Broadcast to a peer, once per broadcast
+    Broadcast,
+    /// Pulled by consensus in a proposed block, which may still fail to commit
+    IncludedInBlock,
+    /// Removed as its sequence number was committed (by this transaction or by another one with
+    /// the same sequence number submitted elsewhere)
+    Committed,
+    /// Removed as its expiration time or the system TTL of Mempool passed
+    Expired,
+    /// Removed without being committed: replaced by a higher gas price, evicted to make space or
+    /// by an operator, or discarded with the other transactions of its account after execution
+    /// rejected one of them
+    Evicted,
+}
+
+/// Lifecycle event of a transaction of Mempool.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransactionStatusEvent {
+    pub sender: AccountAddress,
+    pub sequence_number: u64,
+    pub hash: HashValue,
+    pub status: TransactionStatus,
+}
+
+pub type TransactionStatusReceiver = diem_channel::Receiver<(), TransactionStatusEvent>;
+
+/// Events recorded and not sent yet beyond which the oldest ones are dropped, in case sending them
+/// falls behind
+const MAX_PENDING_EVENTS: usize = 10_000;
+
+struct Subscriber {
+    // Only the transactions of this account are sent if set
+    account: Option<AccountAddress>,
+    sender: diem_channel::Sender<(), TransactionStatusEvent>,
+}
+
+impl Subscriber {
+    fn follows(&self, address: &AccountAddress) -> bool {
+        self.account.map_or(true, |account| account == *address)
+    }
+}
+
+struct BusState {
+    channel_size: usize,
+    subscribers: Mutex<Vec<Subscriber>>,
+    // Read while holding the Mempool lock, not to wait for a send holding `subscribers`
+    num_subscribers: AtomicUsize,
+    // Events recorded by Mempool and not sent yet
+    pending: Mutex<VecDeque<(SignedTransaction, TransactionStatus)>>,
+    new_events: Notify,
+}
+
+/// Sends the lifecycle events of the transactions to the subscribers. Mempool only records the
+/// events, while holding its lock, and `run` hashes and sends them out of it. A slow subscriber
+/// loses its oldest pending events instead of slowing down Mempool.
+#[derive(Clone)]
+pub(crate) struct TransactionStatusBus {
+    state: Arc<BusState>,
+}
+
+impl TransactionStatusBus {
+    pub(crate) fn new(channel_size: usize) -> Self {
+        Self {
+            state: Arc::new(BusState {
+                channel_size,
+                subscribers: Mutex::new(vec![]),
+                num_subscribers: AtomicUsize::new(0),
+                pending: Mutex::new(VecDeque::new()),
+                new_events: Notify::new(),
+            }),
+        }
+    }
+
+    /// Returns the stream of the events of the transactions of `account`, or of all the
+    /// transactions if None.
+    pub(crate) fn subscribe(&self, account: Option<AccountAddress>) -> TransactionStatusReceiver {
+        let (sender, receiver) =
+            diem_channel::new(QueueStyle::KLAST, self.state.channel_size, None);
+        let mut subscribers = self.state.subscribers.lock();
+        subscribers.push(Subscriber { account, sender });
+        self.forget_closed(&mut subscribers);
+        receiver
+    }
+
+    pub(crate) fn has_subscribers(&self) -> bool {
+        self.state.num_subscribers.load(Ordering::Relaxed) > 0
+    }
+
+    /// Records the event of the transaction for `run` to send it, unless nobody listens.
+    pub(crate) fn record(&self, txn: &SignedTransaction, status: TransactionStatus) {
+        if !self.has_subscribers() {
+            return;
+        }
+        {
+            let mut pending = self.state.pending.lock();
+            if pending.len() >= MAX_PENDING_EVENTS {
+                pending.pop_front();
+                counters::CORE_MEMPOOL_STATUS_EVENTS_DROPPED.inc();
+            }
+            pending.push_back((txn.clone(), status));
+        }
+        self.state.new_events.notify_one();
+    }
+
+    /// Sends the recorded events to the subscribers following them, and forgets the subscribers
+    /// that went away.
+    pub(crate) fn flush(&self) {
+        let events = std::mem::take(&mut *self.state.pending.lock());
+        let mut subscribers = self.state.subscribers.lock();
+        self.forget_closed(&mut subscribers);
+        for (txn, status) in events {
+            let sender = txn.sender();
+            // hashing is the expensive part, skip it if nobody follows the account
+            if !subscribers
+                .iter()
+                .any(|subscriber| subscriber.follows(&sender))
+            {
+                continue;
+            }
+            let event = TransactionStatusEvent {
+                sender,
+                sequence_number: txn.sequence_number(),
+                hash: Transaction::UserTransaction(txn).hash(),
+                status,
+            };
+            for subscriber in subscribers.iter_mut() {
+                if subscriber.follows(&sender) {
+                    // a subscriber going away meanwhile is forgotten by the next flush
+                    let _ = subscriber.sender.push((), event.clone());
+                }
+            }
+        }
+    }
+
+    /// Sends the recorded events as they come.
+    pub(crate) async fn run(self) {
+        loop {
+            self.state.new_events.notified().await;
+            self.flush();
+        }
+    }
+
+    fn forget_closed(&self, subscribers: &mut Vec<Subscriber>) {
+        subscribers.retain(|subscriber| !subscriber.sender.is_closed());
+        self.state
+            .num_subscribers
+            .store(subscribers.len(), Ordering::Relaxed);
+        counters::CORE_MEMPOOL_STATUS_SUBSCRIBERS.set(subscribers.len() as i64);
+    }
+}
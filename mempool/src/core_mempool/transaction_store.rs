@@ -3,23 +3,26 @@
 
 use crate::{
     core_mempool::{
+        eviction::{eviction_policy, EvictionCandidates, EvictionPolicy},
         index::{
             AccountTransactions, ParkingLotIndex, PriorityIndex, PriorityQueueIter, TTLIndex,
-            TimelineIndex,
+            TimelineIndex, TxnPointer,
         },
         transaction::{MempoolTransaction, TimelineState},
+        transaction_status::{TransactionStatus, TransactionStatusBus},
         ttl_cache::TtlCache,
     },
     counters,
     logging::{LogEntry, LogEvent, LogSchema, TxnsLog},
 };
 use diem_config::config::MempoolConfig;
+use diem_crypto::{hash::CryptoHash, HashValue};
 use diem_logger::prelude::*;
 use diem_types::{
     account_address::AccountAddress,
     account_config::AccountSequenceInfo,
     mempool_status::{MempoolStatus, MempoolStatusCode},
-    transaction::SignedTransaction,
+    transaction::{SignedTransaction, Transaction},
 };
 use std::{
     collections::HashMap,
@@ -43,10 +46,18 @@ pub struct TransactionStore {
     timeline_index: TimelineIndex,
     // keeps track of "non-ready" txns (transactions that can't be included in next block)
     parking_lot_index: ParkingLotIndex,
+    // estimated memory used by all the transactions
+    size_bytes: usize,
+    // subscribers to the lifecycle events of the transactions
+    status_bus: TransactionStatusBus,
 
     // configuration
     capacity: usize,
+    capacity_bytes: usize,
+    capacity_bytes_per_user: usize,
     capacity_per_user: usize,
+    replacement_gas_price_bump_percent: u64,
+    eviction_policy: Box<dyn EvictionPolicy>,
 }
 
 impl TransactionStore {
@@ -63,13 +74,28 @@ impl TransactionStore {
             priority_index: PriorityIndex::new(),
             timeline_index: TimelineIndex::new(),
             parking_lot_index: ParkingLotIndex::new(),
+            size_bytes: 0,
+            status_bus: TransactionStatusBus::new(config.status_subscription_channel_size),
 
             // configuration
             capacity: config.capacity,
+            capacity_bytes: config.capacity_bytes,
+            capacity_bytes_per_user: config.capacity_bytes_per_user,
             capacity_per_user: config.capacity_per_user,
+            replacement_gas_price_bump_percent: config.replacement_gas_price_bump_percent,
+            eviction_policy: eviction_policy(config.eviction_policy),
         }
     }
 
+    /// Replaces the capacities with the ones of `config`, the transactions already beyond them
+    /// aren't evicted for it.
+    pub(crate) fn update_capacities(&mut self, config: &MempoolConfig) {
+        self.capacity = config.capacity;
+        self.capacity_bytes = config.capacity_bytes;
+        self.capacity_bytes_per_user = config.capacity_bytes_per_user;
+        self.capacity_per_user = config.capacity_per_user;
+    }
+
     /// Fetch transaction by account address + sequence_number.
     pub(crate) fn get(
         &self,
@@ -105,7 +131,9 @@ impl TransactionStore {
 
         // check if transaction is already present in Mempool
         // e.g. given request is update
-        // we allow increase in gas price to speed up process.
+        // we allow replacing the transaction, e.g. to speed it up or to cancel it, as long as the gas
+        // price is increased by at least the configured percentage. The replaced transaction is
+        // evicted.
         // ignores the case transaction hash is same for retrying submit transaction.
         if let Some(txns) = self.transactions.get_mut(&address) {
             if let Some(current_version) =
@@ -114,20 +142,40 @@ impl TransactionStore {
                 if current_version.txn == txn.txn {
                     return MempoolStatus::new(MempoolStatusCode::Accepted);
                 }
-                if current_version.txn.max_gas_amount() == txn.txn.max_gas_amount()
-                    && current_version.txn.payload() == txn.txn.payload()
-                    && current_version.txn.expiration_timestamp_secs()
-                        == txn.txn.expiration_timestamp_secs()
-                    && current_version.get_gas_price() < txn.get_gas_price()
-                {
-                    if let Some(txn) = txns.remove(&txn.sequence_info.transaction_sequence_number) {
-                        self.index_remove(&txn);
-                    }
-                } else {
+                let current_gas_price = current_version.get_gas_price();
+                if current_gas_price >= txn.get_gas_price() {
                     return MempoolStatus::new(MempoolStatusCode::InvalidUpdate).with_message(
                         format!("Failed to update gas price to {}", txn.get_gas_price()),
                     );
                 }
+                let min_gas_price = current_gas_price.saturating_add(
+                    current_gas_price.saturating_mul(self.replacement_gas_price_bump_percent) / 100,
+                );
+                if txn.get_gas_price() < min_gas_price {
+                    return MempoolStatus::new(MempoolStatusCode::UnderpricedReplacement)
+                        .with_message(format!(
+                            "Failed to replace gas price {} with {}, at least {} is required",
+                            current_gas_price,
+                            txn.get_gas_price(),
+                            min_gas_price,
+                        ));
+                }
+                if let Some(replaced_txn) =
+                    txns.remove(&txn.sequence_info.transaction_sequence_number)
+                {
+                    debug!(
+                        LogSchema::new(LogEntry::ReplaceTxn).txns(TxnsLog::new_txn(
+                            address,
+                            replaced_txn.sequence_info.transaction_sequence_number
+                        )),
+                        old_gas_price = current_gas_price,
+                        new_gas_price = txn.get_gas_price(),
+                    );
+                    counters::CORE_MEMPOOL_REPLACED_TXNS.inc();
+                    self.index_remove(&replaced_txn);
+                    self.status_bus
+                        .record(&replaced_txn.txn, TransactionStatus::Evicted);
+                }
             }
         }
 
@@ -136,9 +184,11 @@ impl TransactionStore {
             sequence_number.account_sequence_number_type.min_seq(),
         ) {
             return MempoolStatus::new(MempoolStatusCode::MempoolIsFull).with_message(format!(
-                "mempool size: {}, capacity: {}",
+                "mempool size: {}, capacity: {}, size in bytes: {}, capacity in bytes: {}",
                 self.system_ttl_index.size(),
                 self.capacity,
+                self.size_bytes,
+                self.capacity_bytes,
             ));
         }
 
@@ -162,10 +212,22 @@ impl TransactionStore {
                     ),
                 );
             }
+            let user_bytes: usize = txns.values().map(|txn| txn.estimated_bytes).sum();
+            if user_bytes + txn.estimated_bytes > self.capacity_bytes_per_user {
+                return MempoolStatus::new(MempoolStatusCode::TooManyTransactions).with_message(
+                    format!(
+                        "txns size in bytes: {} capacity in bytes per user: {}",
+                        user_bytes, self.capacity_bytes_per_user,
+                    ),
+                );
+            }
 
             // insert into storage and other indexes
+            self.size_bytes += txn.estimated_bytes;
             self.system_ttl_index.insert(&txn);
             self.expiration_time_index.insert(&txn);
+            self.status_bus
+                .record(&txn.txn, TransactionStatus::Accepted);
             txns.insert(sequence_number.transaction_sequence_number, txn);
             self.track_indices();
         }
@@ -194,37 +256,55 @@ impl TransactionStore {
             counters::TIMELINE_INDEX_LABEL,
             self.timeline_index.size(),
         );
+        counters::CORE_MEMPOOL_SIZE_BYTES.set(self.size_bytes as i64);
+    }
+
+    fn is_full(&self, txn: &MempoolTransaction) -> bool {
+        self.system_ttl_index.size() >= self.capacity
+            || self.size_bytes + txn.estimated_bytes > self.capacity_bytes
     }
 
-    /// Checks if Mempool is full.
-    /// If it's full, tries to free some space by evicting transactions from the ParkingLot.
-    /// We only evict on attempt to insert a transaction that would be ready for broadcast upon insertion.
+    /// Checks if Mempool is full, by transaction count or by memory.
+    /// If it's full, tries to free some space by evicting the transactions picked by the eviction
+    /// policy. We only evict on attempt to insert a transaction that would be ready for broadcast
+    /// upon insertion.
     fn check_is_full_after_eviction(
         &mut self,
         txn: &MempoolTransaction,
         curr_sequence_number: u64,
     ) -> bool {
-        if self.system_ttl_index.size() >= self.capacity
-            && self.check_txn_ready(txn, curr_sequence_number)
-        {
-            // try to free some space in Mempool from ParkingLot by evicting a non-ready txn
-            if let Some((address, sequence_number)) = self.parking_lot_index.get_poppable() {
-                if let Some(txn) = self
-                    .transactions
-                    .get_mut(&address)
-                    .and_then(|txns| txns.remove(&sequence_number))
+        if self.is_full(txn) && self.check_txn_ready(txn, curr_sequence_number) {
+            // several small txns may have to go to make space for a big one
+            while self.is_full(txn) {
+                let candidates = EvictionCandidates {
+                    system_ttl_index: &self.system_ttl_index,
+                    priority_index: &self.priority_index,
+                    timeline_index: &self.timeline_index,
+                    parking_lot_index: &self.parking_lot_index,
+                };
+                let (address, sequence_number) = match self.eviction_policy.select(&candidates, txn)
                 {
-                    debug!(
-                        LogSchema::new(LogEntry::MempoolFullEvictedTxn).txns(TxnsLog::new_txn(
-                            txn.get_sender(),
-                            txn.sequence_info.transaction_sequence_number
-                        ))
-                    );
-                    self.index_remove(&txn);
+                    Some(pointer) => pointer,
+                    None => break,
+                };
+                let evicted = self.evict_transactions_from(&address, sequence_number);
+                if evicted.is_empty() {
+                    break;
                 }
+                let mut txns_log = TxnsLog::new();
+                for sequence_number in &evicted {
+                    txns_log.add(address, *sequence_number);
+                }
+                debug!(
+                    LogSchema::new(LogEntry::MempoolFullEvictedTxn).txns(txns_log),
+                    policy = self.eviction_policy.name(),
+                );
+                counters::CORE_MEMPOOL_EVICTED_TXNS
+                    .with_label_values(&[self.eviction_policy.name()])
+                    .inc_by(evicted.len() as u64);
             }
         }
-        self.system_ttl_index.size() >= self.capacity
+        self.is_full(txn)
     }
 
     /// Check if a transaction would be ready for broadcast in mempool upon insertion (without inserting it).
@@ -339,6 +419,8 @@ impl TransactionStore {
                     transaction.sequence_info.transaction_sequence_number,
                 );
                 self.index_remove(transaction);
+                self.status_bus
+                    .record(&transaction.txn, TransactionStatus::Committed);
             }
             trace!(
                 LogSchema::new(LogEntry::CleanCommittedTxn).txns(rm_txns),
@@ -370,6 +452,8 @@ impl TransactionStore {
                     transaction.sequence_info.transaction_sequence_number,
                 );
                 self.index_remove(transaction);
+                self.status_bus
+                    .record(&transaction.txn, TransactionStatus::Evicted);
             }
             debug!(LogSchema::new(LogEntry::CleanRejectedTxn).txns(txns_log));
         }
@@ -378,6 +462,7 @@ impl TransactionStore {
     /// Removes transaction from all indexes.
     fn index_remove(&mut self, txn: &MempoolTransaction) {
         counters::CORE_MEMPOOL_REMOVED_TXNS.inc();
+        self.size_bytes -= txn.estimated_bytes;
         self.system_ttl_index.remove(txn);
         self.expiration_time_index.remove(txn);
         self.priority_index.remove(txn);
@@ -507,6 +592,7 @@ impl TransactionStore {
 
                     // remove txn
                     self.index_remove(&txn);
+                    self.status_bus.record(&txn.txn, TransactionStatus::Expired);
                 }
             }
         }
@@ -515,6 +601,35 @@ impl TransactionStore {
         self.track_indices();
     }
 
+    /// Iterates over all the transactions of Mempool, ready or not.
+    pub(crate) fn iter_transactions(&self) -> impl Iterator<Item = &SignedTransaction> {
+        self.transactions
+            .values()
+            .flat_map(|txns| txns.values().map(|txn| &txn.txn))
+    }
+
+    /// Checks that the balance of the sender covers the max gas cost of the transaction on top of
+    /// its other transactions paying gas in the same currency. The transaction with the same
+    /// sequence number isn't counted, it would be replaced.
+    pub(crate) fn covers_max_gas_cost(&self, txn: &SignedTransaction, balance: u64) -> bool {
+        let pending_cost = self.transactions.get(&txn.sender()).map_or(0, |txns| {
+            txns.iter()
+                .filter(|(sequence_number, pending_txn)| {
+                    **sequence_number != txn.sequence_number()
+                        && pending_txn.txn.gas_currency_code() == txn.gas_currency_code()
+                })
+                .fold(0, |cost: u64, (_, pending_txn)| {
+                    cost.saturating_add(max_gas_cost(&pending_txn.txn))
+                })
+        });
+        pending_cost.saturating_add(max_gas_cost(txn)) <= balance
+    }
+
+    /// Number of transactions in Mempool, ready or not.
+    pub(crate) fn size(&self) -> usize {
+        self.system_ttl_index.size()
+    }
+
     pub(crate) fn iter_queue(&self) -> PriorityQueueIter {
         self.priority_index.iter()
     }
@@ -538,8 +653,78 @@ impl TransactionStore {
         txns_log
     }
 
-    #[cfg(test)]
     pub(crate) fn get_parking_lot_size(&self) -> usize {
         self.parking_lot_index.size()
     }
+
+    /// Iterates over the "non-ready" transactions of the parking lot.
+    pub(crate) fn iter_parking_lot(&self) -> impl Iterator<Item = TxnPointer> + '_ {
+        self.parking_lot_index.iter()
+    }
+
+    /// Transactions of the account in sequence number order, with whether they're in the parking
+    /// lot.
+    pub(crate) fn account_transactions(
+        &self,
+        address: &AccountAddress,
+    ) -> Vec<(SignedTransaction, bool)> {
+        self.transactions.get(address).map_or(vec![], |txns| {
+            txns.iter()
+                .map(|(seq_num, txn)| {
+                    let is_parked = self.parking_lot_index.contains(address, seq_num);
+                    (txn.txn.clone(), is_parked)
+                })
+                .collect()
+        })
+    }
+
+    /// Finds a transaction by its hash, by going through all the transactions.
+    pub(crate) fn find_by_hash(&self, hash: &HashValue) -> Option<TxnPointer> {
+        self.transactions.iter().find_map(|(address, txns)| {
+            txns.iter()
+                .find(|(_, txn)| Transaction::UserTransaction(txn.txn.clone()).hash() == *hash)
+                .map(|(seq_num, _)| (*address, *seq_num))
+        })
+    }
+
+    /// Evicts the transaction of the account with the given sequence number, along with the later
+    /// ones which can't be executed without it.
+    /// Returns the sequence numbers of the evicted transactions.
+    pub(crate) fn evict_transactions_from(
+        &mut self,
+        address: &AccountAddress,
+        sequence_number: u64,
+    ) -> Vec<u64> {
+        let evicted_txns = match self.transactions.get_mut(address) {
+            Some(txns) => {
+                let evicted_txns = txns.split_off(&sequence_number);
+                if txns.is_empty() {
+                    self.transactions.remove(address);
+                }
+                evicted_txns
+            }
+            None => return vec![],
+        };
+        for txn in evicted_txns.values() {
+            self.index_remove(txn);
+            self.status_bus.record(&txn.txn, TransactionStatus::Evicted);
+        }
+        evicted_txns
+            .into_iter()
+            .map(|(seq_num, _)| seq_num)
+            .collect()
+    }
+
+    pub(crate) fn status_bus(&self) -> &TransactionStatusBus {
+        &self.status_bus
+    }
+
+    pub(crate) fn get_size_bytes(&self) -> usize {
+        self.size_bytes
+    }
+}
+
+/// Max amount of gas currency the transaction may spend on its fees.
+fn max_gas_cost(txn: &SignedTransaction) -> u64 {
+    txn.max_gas_amount().saturating_mul(txn.gas_unit_price())
 }
@@ -100,6 +100,53 @@ pub static CORE_MEMPOOL_REMOVED_TXNS: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter tracking number of txns evicted to make space in a full core mempool, by policy
+pub static CORE_MEMPOOL_EVICTED_TXNS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_core_mempool_evicted_txns_count",
+        "Number of txns evicted to make space in a full core mempool, by policy",
+        &["policy"]
+    )
+    .unwrap()
+});
+
+/// Gauge tracking the estimated memory used by the txns in core mempool
+pub static CORE_MEMPOOL_SIZE_BYTES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_core_mempool_size_bytes",
+        "Estimated memory used by the txns in core mempool"
+    )
+    .unwrap()
+});
+
+/// Gauge tracking the number of subscribers to the status events of the txns in core mempool
+pub static CORE_MEMPOOL_STATUS_SUBSCRIBERS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_core_mempool_status_subscribers",
+        "Number of subscribers to the status events of the txns in core mempool"
+    )
+    .unwrap()
+});
+
+/// Counter tracking the number of txn status events dropped as sending them fell behind
+pub static CORE_MEMPOOL_STATUS_EVENTS_DROPPED: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_core_mempool_status_events_dropped",
+        "Number of txn status events dropped as sending them fell behind"
+    )
+    .unwrap()
+});
+
+/// Counter tracking number of txns replaced by a txn with the same sequence number and a higher
+/// gas price
+pub static CORE_MEMPOOL_REPLACED_TXNS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_core_mempool_replaced_txns_count",
+        "Number of txns replaced by a txn with the same sequence number and a higher gas price"
+    )
+    .unwrap()
+});
+
 /// Counter tracking latency of txns reaching various stages in committing
 /// (e.g. time from txn entering core mempool to being pulled in consensus block)
 pub static CORE_MEMPOOL_TXN_COMMIT_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
@@ -252,6 +299,23 @@ pub fn shared_mempool_pending_broadcasts(peer: &PeerNetworkId) -> IntGauge {
     ])
 }
 
+/// Gauge tracking the score of each upstream peer, the lower ones are broadcast to first
+static SHARED_MEMPOOL_PEER_SCORE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "diem_shared_mempool_peer_score",
+        "Score of an upstream peer from its ack latency and duplicate broadcasts",
+        &["network", "recipient"]
+    )
+    .unwrap()
+});
+
+pub fn shared_mempool_peer_score(peer: &PeerNetworkId) -> IntGauge {
+    SHARED_MEMPOOL_PEER_SCORE.with_label_values(&[
+        peer.raw_network_id().as_str(),
+        peer.peer_id().short_str().as_str(),
+    ])
+}
+
 static SHARED_MEMPOOL_TRANSACTIONS_PROCESSED: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_shared_mempool_transactions_processed",
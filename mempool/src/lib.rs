@@ -57,11 +57,13 @@
 
 #[cfg(any(test, feature = "fuzzing"))]
 mod tests;
+pub use core_mempool::{TransactionStatus, TransactionStatusEvent, TransactionStatusReceiver};
 pub use shared_mempool::{
     bootstrap, network,
     types::{
-        gen_mempool_reconfig_subscription, ConsensusRequest, ConsensusResponse,
-        MempoolClientSender, SubmissionStatus, TransactionSummary,
+        gen_mempool_reconfig_subscription, AccountPendingTransactions, ConsensusRequest,
+        ConsensusResponse, MempoolClientRequest, MempoolClientSender, MempoolStats,
+        SubmissionStatus, TransactionSummary,
     },
 };
 #[cfg(any(test, feature = "fuzzing"))]
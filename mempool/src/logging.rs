@@ -139,6 +139,8 @@ pub enum LogEntry {
     InvariantViolated,
     AddTxn,
     RemoveTxn,
+    ReplaceTxn,
+    AdminEvictTxns,
     MempoolFullEvictedTxn,
     GCRemoveTxns,
     CleanCommittedTxn,
@@ -148,6 +150,7 @@ pub enum LogEntry {
     UpstreamNetwork,
     UnexpectedNetworkMsg,
     MempoolSnapshot,
+    Persistence,
 }
 
 #[derive(Clone, Copy, Serialize)]
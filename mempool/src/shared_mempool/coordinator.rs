@@ -11,23 +11,22 @@ use crate::{
     shared_mempool::{
         tasks,
         tasks::commit_txns,
-        types::{notify_subscribers, ScheduledBroadcast, SharedMempool, SharedMempoolNotification},
+        types::{
+            notify_subscribers, MempoolStats, ScheduledBroadcast, SharedMempool,
+            SharedMempoolNotification,
+        },
     },
-    ConsensusRequest, SubmissionStatus, TransactionSummary,
+    ConsensusRequest, MempoolClientRequest, TransactionSummary,
 };
 use ::network::protocols::network::Event;
-use anyhow::Result;
 use bounded_executor::BoundedExecutor;
 use channel::diem_channel;
 use diem_config::{config::PeerNetworkId, network_id::NodeNetworkId};
 use diem_infallible::Mutex;
 use diem_logger::prelude::*;
-use diem_types::{
-    mempool_status::MempoolStatus, on_chain_config::OnChainConfigPayload,
-    transaction::SignedTransaction, vm_status::DiscardedVMStatus,
-};
+use diem_types::on_chain_config::OnChainConfigPayload;
 use futures::{
-    channel::{mpsc, oneshot},
+    channel::mpsc,
     stream::{select_all, FuturesUnordered},
     StreamExt,
 };
@@ -45,10 +44,7 @@ pub(crate) async fn coordinator<V>(
     mut smp: SharedMempool<V>,
     executor: Handle,
     network_events: Vec<(NodeNetworkId, MempoolNetworkEvents)>,
-    mut client_events: mpsc::Receiver<(
-        SignedTransaction,
-        oneshot::Sender<Result<SubmissionStatus>>,
-    )>,
+    mut client_events: mpsc::Receiver<MempoolClientRequest>,
     mut consensus_requests: mpsc::Receiver<ConsensusRequest>,
     mut mempool_listener: MempoolNotificationListener,
     mut mempool_reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
@@ -71,11 +67,16 @@ pub(crate) async fn coordinator<V>(
     let workers_available = smp.config.shared_mempool_max_concurrent_inbound_syncs;
     let bounded_executor = BoundedExecutor::new(workers_available, executor.clone());
 
+    // the persisted transactions go first, so that the log doesn't miss any new transaction
+    if let Some(path) = smp.config.persistence_path() {
+        tasks::replay_persisted_transactions(&smp, &path).await;
+    }
+
     loop {
         let _timer = counters::MAIN_LOOP.start_timer();
         ::futures::select! {
-            (msg, callback) = client_events.select_next_some() => {
-                handle_client_event(&mut smp, &bounded_executor, msg, callback).await;
+            request = client_events.select_next_some() => {
+                handle_client_request(&mut smp, &bounded_executor, request).await;
             },
             msg = consensus_requests.select_next_some() => {
                 tasks::process_consensus_request(&smp.mempool, msg).await;
@@ -101,11 +102,10 @@ pub(crate) async fn coordinator<V>(
     ));
 }
 
-async fn handle_client_event<V>(
+async fn handle_client_request<V>(
     smp: &mut SharedMempool<V>,
     bounded_executor: &BoundedExecutor,
-    msg: SignedTransaction,
-    callback: oneshot::Sender<anyhow::Result<(MempoolStatus, Option<DiscardedVMStatus>)>>,
+    request: MempoolClientRequest,
 ) where
     V: TransactionValidation,
 {
@@ -116,14 +116,80 @@ async fn handle_client_event<V>(
     // This timer measures how long it took for the task to go from scheduled to started.
     let task_start_timer =
         counters::task_spawn_latency_timer(counters::CLIENT_EVENT_LABEL, counters::START_LABEL);
-    bounded_executor
-        .spawn(tasks::process_client_transaction_submission(
-            smp.clone(),
-            msg,
-            callback,
-            task_start_timer,
-        ))
-        .await;
+    match request {
+        MempoolClientRequest::SubmitTransaction(txn, callback) => {
+            bounded_executor
+                .spawn(tasks::process_client_transaction_submission(
+                    smp.clone(),
+                    txn,
+                    callback,
+                    task_start_timer,
+                ))
+                .await;
+        }
+        MempoolClientRequest::GetAccountPendingTransactions(address, callback) => {
+            bounded_executor
+                .spawn(tasks::process_account_pending_transactions_request(
+                    smp.clone(),
+                    address,
+                    callback,
+                    task_start_timer,
+                ))
+                .await;
+        }
+        MempoolClientRequest::SubscribeTransactionStatus(account, callback) => {
+            // cheap enough to not go through the bounded executor
+            let receiver = smp.mempool.lock().subscribe_status(account);
+            if callback.send(receiver).is_err() {
+                counters::CLIENT_CALLBACK_FAIL.inc();
+            }
+        }
+        MempoolClientRequest::GetStats(callback) => {
+            // cheap enough to not go through the bounded executor
+            let stats = {
+                let mempool = smp.mempool.lock();
+                let parked = mempool.get_parking_lot_size();
+                MempoolStats {
+                    ready: mempool.size() - parked,
+                    parked,
+                    size_bytes: mempool.get_size_bytes(),
+                }
+            };
+            if callback.send(stats).is_err() {
+                counters::CLIENT_CALLBACK_FAIL.inc();
+            }
+        }
+        MempoolClientRequest::GetParkingLot(callback) => {
+            let parking_lot = smp
+                .mempool
+                .lock()
+                .parking_lot_transactions()
+                .into_iter()
+                .map(|(sender, sequence_number)| TransactionSummary {
+                    sender,
+                    sequence_number,
+                })
+                .collect();
+            if callback.send(parking_lot).is_err() {
+                counters::CLIENT_CALLBACK_FAIL.inc();
+            }
+        }
+        MempoolClientRequest::EvictTransaction(hash, callback) => {
+            let evicted = smp.mempool.lock().evict_by_hash(&hash);
+            if callback.send(evicted).is_err() {
+                counters::CLIENT_CALLBACK_FAIL.inc();
+            }
+        }
+        MempoolClientRequest::EvictAccount(address, callback) => {
+            let evicted = smp.mempool.lock().evict_account(&address);
+            if callback.send(evicted).is_err() {
+                counters::CLIENT_CALLBACK_FAIL.inc();
+            }
+        }
+        MempoolClientRequest::UpdateCapacities(config) => {
+            smp.mempool.lock().update_capacities(&config);
+        }
+    }
 }
 
 async fn handle_state_sync_request<V>(
@@ -10,7 +10,7 @@ use diem_types::{transaction::SignedTransaction, PeerId};
 use fail::fail_point;
 use network::{
     error::NetworkError,
-    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    peer_manager::{ConnectionRequestSender, Misbehavior, PeerManagerRequestSender},
     protocols::network::{NetworkEvents, NetworkSender, NewNetworkSender},
     ProtocolId,
 };
@@ -61,7 +61,7 @@ pub struct MempoolNetworkSender {
 }
 
 /// Create a new Sender that only sends for the `MEMPOOL_DIRECT_SEND_PROTOCOL` ProtocolId and a
-/// Receiver (Events) that explicitly returns only said ProtocolId..
+/// Receiver (Events) that explicitly returns only said ProtocolId and its compressed variant.
 pub fn network_endpoint_config(
     max_broadcasts_per_peer: usize,
 ) -> (
@@ -73,7 +73,10 @@ pub fn network_endpoint_config(
 ) {
     (
         vec![],
-        vec![ProtocolId::MempoolDirectSend],
+        vec![
+            ProtocolId::MempoolDirectSend,
+            ProtocolId::MempoolDirectSendZstd,
+        ],
         QueueStyle::KLAST,
         max_broadcasts_per_peer,
         Some(&counters::PENDING_MEMPOOL_NETWORK_EVENTS),
@@ -103,4 +106,28 @@ impl MempoolNetworkSender {
         let protocol = ProtocolId::MempoolDirectSend;
         self.inner.send_to(recipient, protocol, message)
     }
+
+    /// Same as `send_to`, but the message is compressed with zstd: the recipient must support
+    /// `ProtocolId::MempoolDirectSendZstd`.
+    pub fn send_compressed_to(
+        &mut self,
+        recipient: PeerId,
+        message: MempoolSyncMsg,
+    ) -> Result<(), NetworkError> {
+        fail_point!("mempool::send_to", |_| {
+            Err(anyhow::anyhow!("Injected error in mempool::send_to").into())
+        });
+        let protocol = ProtocolId::MempoolDirectSendZstd;
+        self.inner.send_to(recipient, protocol, message)
+    }
+
+    /// Report a peer which broadcast invalid transactions, so that the network bans it once it
+    /// misbehaved too often.
+    pub fn report_misbehavior(
+        &mut self,
+        peer: PeerId,
+        misbehavior: Misbehavior,
+    ) -> Result<(), NetworkError> {
+        self.inner.report_misbehavior(peer, misbehavior)
+    }
 }
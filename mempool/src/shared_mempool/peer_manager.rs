@@ -10,18 +10,22 @@ use crate::{
         types::{notify_subscribers, SharedMempool, SharedMempoolNotification},
     },
 };
-use diem_config::config::{MempoolConfig, PeerNetworkId, PeerRole, RoleType};
+use diem_config::config::{
+    BroadcastBatchingConfig, FailoverOrdering, MempoolConfig, PeerNetworkId, PeerRole,
+    PeerScoringConfig, RoleType,
+};
 use diem_infallible::Mutex;
 use diem_logger::prelude::*;
 use diem_types::transaction::SignedTransaction;
 use itertools::Itertools;
 use netcore::transport::ConnectionOrigin;
-use network::transport::ConnectionMetadata;
+use network::{transport::ConnectionMetadata, ProtocolId};
 use serde::{Deserialize, Serialize};
 use short_hex_str::AsShortHexStr;
 use std::{
     cmp::Ordering,
     collections::{BTreeMap, BTreeSet, HashMap},
+    convert::TryFrom,
     ops::Add,
     time::{Duration, Instant, SystemTime},
 };
@@ -44,11 +48,11 @@ pub(crate) struct PeerSyncState {
 }
 
 impl PeerSyncState {
-    pub fn new(metadata: ConnectionMetadata) -> Self {
+    pub fn new(metadata: ConnectionMetadata, batch_size: usize) -> Self {
         PeerSyncState {
             timeline_id: 0,
             is_alive: true,
-            broadcast_info: BroadcastInfo::new(),
+            broadcast_info: BroadcastInfo::new(batch_size),
             metadata,
         }
     }
@@ -88,16 +92,74 @@ pub struct BroadcastInfo {
     pub retry_batches: BTreeSet<BatchId>,
     // Whether broadcasting to this peer is in backoff mode, e.g. broadcasting at longer intervals.
     pub backoff_mode: bool,
+    // Max number of txns in a fresh broadcast, adapted to the ack latency if configured.
+    pub batch_size: usize,
+    // Since when the fresh broadcast has been waiting for a full batch of txns.
+    pub pending_since: Option<Instant>,
+    // Responsiveness of the peer, to order the failovers by score if configured.
+    pub score: PeerScore,
 }
 
 impl BroadcastInfo {
-    fn new() -> Self {
+    pub(crate) fn new(batch_size: usize) -> Self {
         Self {
             sent_batches: BTreeMap::new(),
             retry_batches: BTreeSet::new(),
             backoff_mode: false,
+            batch_size,
+            pending_since: None,
+            score: PeerScore::default(),
         }
     }
+
+    /// Grows the batch size by a quarter while the acks come back within the target latency, and
+    /// halves it otherwise.
+    pub(crate) fn adapt_batch_size(&mut self, rtt: Duration, config: &BroadcastBatchingConfig) {
+        let batch_size = if rtt <= Duration::from_millis(config.target_ack_latency_ms) {
+            self.batch_size + std::cmp::max(self.batch_size / 4, 1)
+        } else {
+            self.batch_size / 2
+        };
+        self.batch_size = batch_size
+            .min(config.max_batch_size)
+            .max(config.min_batch_size);
+    }
+}
+
+/// Moving averages of the responsiveness of a peer. A new peer starts with the best score, so that it
+/// gets broadcasts to measure it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PeerScore {
+    // Latency of the acks, in ms.
+    pub ack_latency_ms: u64,
+    // Share of the broadcasts sent again because of a missing or a retry ack, in percent.
+    pub duplicate_percent: u64,
+}
+
+impl PeerScore {
+    pub(crate) fn record_ack(&mut self, rtt: Duration, config: &PeerScoringConfig) {
+        let rtt_ms = u64::try_from(rtt.as_millis()).unwrap_or(u64::MAX);
+        self.ack_latency_ms = moving_average(self.ack_latency_ms, rtt_ms, config);
+    }
+
+    pub(crate) fn record_broadcast(&mut self, is_duplicate: bool, config: &PeerScoringConfig) {
+        let sample = if is_duplicate { 100 } else { 0 };
+        self.duplicate_percent = moving_average(self.duplicate_percent, sample, config);
+    }
+
+    /// The lower the score, the earlier the peer is broadcast to.
+    pub(crate) fn score(&self, config: &PeerScoringConfig) -> u64 {
+        self.ack_latency_ms.saturating_add(
+            self.duplicate_percent
+                .saturating_mul(config.duplicate_penalty_ms)
+                / 100,
+        )
+    }
+}
+
+fn moving_average(average: u64, sample: u64, config: &PeerScoringConfig) -> u64 {
+    let weight = std::cmp::min(config.smoothing_percent, 100) as u128;
+    ((average as u128 * (100 - weight) + sample as u128 * weight) / 100) as u64
 }
 
 impl PeerManager {
@@ -121,7 +183,10 @@ impl PeerManager {
             // If we have a new peer, let's insert new data, otherwise, let's just update the current state
             if is_new_peer {
                 counters::active_upstream_peers(&peer.raw_network_id()).inc();
-                peer_states.insert(peer, PeerSyncState::new(metadata));
+                peer_states.insert(
+                    peer,
+                    PeerSyncState::new(metadata, self.mempool_config.shared_mempool_batch_size),
+                );
             } else if let Some(peer_state) = peer_states.get_mut(&peer) {
                 if !peer_state.is_alive {
                     counters::active_upstream_peers(&peer.raw_network_id()).inc();
@@ -192,15 +257,16 @@ impl PeerManager {
             return;
         }
 
-        // When not a validator, only broadcast to `default_failovers`
-        if !self.role.is_validator() {
+        // Only broadcast to the first `fanout` prioritized peers: by default all of them on a
+        // validator, the primary peer and `default_failovers` otherwise
+        if let Some(fanout) = self.fanout() {
             let priority = self
                 .prioritized_peers
                 .lock()
                 .iter()
                 .find_position(|peer_network_id| *peer_network_id == &peer)
                 .map_or(usize::MAX, |(pos, _)| pos);
-            if priority > self.mempool_config.default_failovers {
+            if priority >= fanout {
                 return;
             }
         }
@@ -271,10 +337,8 @@ impl PeerManager {
                 }
                 None => {
                     // Fresh broadcast
-                    let (txns, new_timeline_id) = mempool.read_timeline(
-                        state.timeline_id,
-                        self.mempool_config.shared_mempool_batch_size,
-                    );
+                    let (txns, new_timeline_id) =
+                        mempool.read_timeline(state.timeline_id, state.broadcast_info.batch_size);
                     (BatchId(state.timeline_id, new_timeline_id), txns)
                 }
             };
@@ -287,6 +351,18 @@ impl PeerManager {
             return;
         }
 
+        // A fresh broadcast waits for a full batch, until its first txns waited long enough.
+        let batching = &self.mempool_config.shared_mempool_broadcast_batching;
+        if metric_label.is_none() && transactions.len() < state.broadcast_info.batch_size {
+            let pending_since = *state
+                .broadcast_info
+                .pending_since
+                .get_or_insert_with(Instant::now);
+            if pending_since.elapsed() < Duration::from_millis(batching.max_batch_delay_ms) {
+                return;
+            }
+        }
+
         let mut network_sender = smp
             .network_senders
             .get_mut(&peer.network_id())
@@ -294,13 +370,21 @@ impl PeerManager {
             .clone();
 
         let num_txns = transactions.len();
-        if let Err(e) = network_sender.send_to(
-            peer.peer_id(),
-            MempoolSyncMsg::BroadcastTransactionsRequest {
-                request_id: bcs::to_bytes(&batch_id).expect("failed BCS serialization of batch ID"),
-                transactions,
-            },
-        ) {
+        let request = MempoolSyncMsg::BroadcastTransactionsRequest {
+            request_id: bcs::to_bytes(&batch_id).expect("failed BCS serialization of batch ID"),
+            transactions,
+        };
+        let result = if batching.compression
+            && state
+                .metadata
+                .application_protocols
+                .contains(ProtocolId::MempoolDirectSendZstd)
+        {
+            network_sender.send_compressed_to(peer.peer_id(), request)
+        } else {
+            network_sender.send_to(peer.peer_id(), request)
+        };
+        if let Err(e) = result {
             counters::network_send_fail_inc(counters::BROADCAST_TXNS);
             error!(
                 LogSchema::event_log(LogEntry::BroadcastTransaction, LogEvent::NetworkSendFail)
@@ -313,11 +397,22 @@ impl PeerManager {
         state.timeline_id = std::cmp::max(state.timeline_id, batch_id.1);
         // Turn off backoff mode after every broadcast.
         state.broadcast_info.backoff_mode = false;
+        if metric_label.is_none() {
+            state.broadcast_info.pending_since = None;
+        }
+        let scoring = &self.mempool_config.shared_mempool_peer_scoring;
+        state
+            .broadcast_info
+            .score
+            .record_broadcast(metric_label.is_some(), scoring);
+        counters::shared_mempool_peer_score(&peer)
+            .set(state.broadcast_info.score.score(scoring) as i64);
         state
             .broadcast_info
             .sent_batches
             .insert(batch_id, SystemTime::now());
         state.broadcast_info.retry_batches.remove(&batch_id);
+        smp.mempool.lock().notify_broadcast(batch_id.0, batch_id.1);
         notify_subscribers(SharedMempoolNotification::Broadcast, &smp.subscribers);
 
         let latency = start_time.elapsed();
@@ -353,11 +448,21 @@ impl PeerManager {
         }
     }
 
+    /// Max number of prioritized peers broadcast to, None for all of them.
+    fn fanout(&self) -> Option<usize> {
+        match self.mempool_config.shared_mempool_peer_scoring.fanout {
+            Some(fanout) => Some(fanout),
+            None if !self.role.is_validator() => Some(self.mempool_config.default_failovers + 1),
+            None => None,
+        }
+    }
+
     fn update_prioritized_peers(&self) {
-        // Only do this if it's not a validator
-        if self.role.is_validator() {
+        // Only do this if broadcasts don't go to all the peers
+        if self.fanout().is_none() {
             return;
         }
+        let scoring = &self.mempool_config.shared_mempool_peer_scoring;
 
         // Retrieve just what's needed for the peer ordering
         let peers: Vec<_> = {
@@ -365,19 +470,34 @@ impl PeerManager {
             peer_states
                 .iter()
                 .filter(|(_, state)| state.is_alive)
-                .map(|(peer, state)| (peer.clone(), state.metadata.role))
+                .map(|(peer, state)| {
+                    (
+                        peer.clone(),
+                        state.metadata.role,
+                        state.broadcast_info.score.score(scoring),
+                    )
+                })
                 .collect()
         };
 
-        // Order peers by network and by type
+        // Order peers by network and by type or score
         // Origin doesn't matter at this point, only inserted ones into peer_states are upstream
         // Validators will always have the full set
         let mut prioritized_peers = self.prioritized_peers.lock();
-        let peers: Vec<_> = peers
-            .iter()
-            .sorted_by(|peer_a, peer_b| compare_prioritized_peers(peer_a, peer_b))
-            .map(|(peer, _)| peer.clone())
-            .collect();
+        let peers: Vec<_> = match scoring.failover_ordering {
+            FailoverOrdering::Role => peers
+                .into_iter()
+                .map(|(peer, role, _)| (peer, role))
+                .sorted_by(compare_prioritized_peers)
+                .map(|(peer, _)| peer)
+                .collect(),
+            FailoverOrdering::Score => peers
+                .into_iter()
+                .map(|(peer, _, score)| (peer, score))
+                .sorted_by(compare_scored_peers)
+                .map(|(peer, _)| peer)
+                .collect(),
+        };
         let _ = std::mem::replace(&mut *prioritized_peers, peers);
     }
 
@@ -415,6 +535,14 @@ impl PeerManager {
             counters::SHARED_MEMPOOL_BROADCAST_RTT
                 .with_label_values(&[network_id.as_str(), peer_id.as_str()])
                 .observe(rtt.as_secs_f64());
+            let batching = &self.mempool_config.shared_mempool_broadcast_batching;
+            if batching.adaptive_batch_size {
+                sync_state.broadcast_info.adapt_batch_size(rtt, batching);
+            }
+            let scoring = &self.mempool_config.shared_mempool_peer_scoring;
+            sync_state.broadcast_info.score.record_ack(rtt, scoring);
+            counters::shared_mempool_peer_score(&peer)
+                .set(sync_state.broadcast_info.score.score(scoring) as i64);
 
             counters::shared_mempool_pending_broadcasts(&peer).dec();
         } else {
@@ -446,6 +574,17 @@ impl PeerManager {
         if backoff {
             sync_state.broadcast_info.backoff_mode = true;
         }
+        drop(peer_states);
+
+        // The latency of the peer changed its score
+        if self
+            .mempool_config
+            .shared_mempool_peer_scoring
+            .failover_ordering
+            == FailoverOrdering::Score
+        {
+            self.update_prioritized_peers();
+        }
     }
 
     // If the origin is provided, checks whether this peer is an upstream peer based on configured preferences and
@@ -500,6 +639,17 @@ fn compare_prioritized_peers(
     }
 }
 
+/// Provides ordering for prioritized peers by score
+fn compare_scored_peers(peer_a: &(PeerNetworkId, u64), peer_b: &(PeerNetworkId, u64)) -> Ordering {
+    // Sort by NetworkId, then by score, then tiebreak by PeerId for stability
+    peer_a
+        .0
+        .raw_network_id()
+        .cmp(&peer_b.0.raw_network_id())
+        .then(peer_a.1.cmp(&peer_b.1))
+        .then_with(|| peer_a.0.peer_id().cmp(&peer_b.0.peer_id()))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -552,4 +702,47 @@ mod test {
         // Same the only equal case
         assert_eq!(Ordering::Equal, compare_prioritized_peers(&val_1, &val_1));
     }
+
+    #[test]
+    fn check_scored_peer_prioritization() {
+        let peer_id_1 = PeerId::from_hex_literal("0x1").unwrap();
+        let peer_id_2 = PeerId::from_hex_literal("0x2").unwrap();
+        let fast = (peer_network_id(peer_id_2, NetworkId::vfn_network()), 10);
+        let slow = (peer_network_id(peer_id_1, NetworkId::vfn_network()), 500);
+        let public = (peer_network_id(peer_id_1, NetworkId::Public), 0);
+
+        // NetworkId ordering first
+        assert_eq!(Ordering::Less, compare_scored_peers(&slow, &public));
+
+        // Then score ordering
+        assert_eq!(Ordering::Less, compare_scored_peers(&fast, &slow));
+        assert_eq!(Ordering::Greater, compare_scored_peers(&slow, &fast));
+
+        // Tiebreaker on peer_id
+        let tied = (peer_network_id(peer_id_1, NetworkId::vfn_network()), 10);
+        assert_eq!(Ordering::Greater, compare_scored_peers(&fast, &tied));
+    }
+
+    #[test]
+    fn check_peer_score() {
+        let config = PeerScoringConfig {
+            smoothing_percent: 50,
+            duplicate_penalty_ms: 1_000,
+            ..PeerScoringConfig::default()
+        };
+        let mut score = PeerScore::default();
+        assert_eq!(score.score(&config), 0);
+
+        score.record_ack(Duration::from_millis(200), &config);
+        assert_eq!(score.ack_latency_ms, 100);
+        score.record_ack(Duration::from_millis(200), &config);
+        assert_eq!(score.ack_latency_ms, 150);
+
+        // half the broadcasts had to be sent again
+        score.record_broadcast(true, &config);
+        assert_eq!(score.duplicate_percent, 50);
+        assert_eq!(score.score(&config), 650);
+        score.record_broadcast(false, &config);
+        assert_eq!(score.score(&config), 400);
+    }
 }
@@ -4,7 +4,7 @@
 //! Tasks that are executed by coordinators (short-lived compared to coordinators)
 
 use crate::{
-    core_mempool::{CoreMempool, TimelineState, TxnPointer},
+    core_mempool::{CoreMempool, TimelineState, TransactionLog, TxnPointer},
     counters,
     logging::{LogEntry, LogEvent, LogSchema},
     network::MempoolSyncMsg,
@@ -12,7 +12,7 @@ use crate::{
         notify_subscribers, ScheduledBroadcast, SharedMempool, SharedMempoolNotification,
         SubmissionStatusBundle, TransactionSummary,
     },
-    ConsensusRequest, ConsensusResponse, SubmissionStatus,
+    AccountPendingTransactions, ConsensusRequest, ConsensusResponse, SubmissionStatus,
 };
 use anyhow::Result;
 use diem_config::config::PeerNetworkId;
@@ -20,22 +20,27 @@ use diem_infallible::{Mutex, RwLock};
 use diem_logger::prelude::*;
 use diem_metrics::HistogramTimer;
 use diem_types::{
+    account_address::AccountAddress,
     mempool_status::{MempoolStatus, MempoolStatusCode},
     on_chain_config::OnChainConfigPayload,
     transaction::SignedTransaction,
     vm_status::DiscardedVMStatus,
 };
 use futures::{channel::oneshot, stream::FuturesUnordered};
+use network::peer_manager::Misbehavior;
 use rayon::prelude::*;
 use short_hex_str::AsShortHexStr;
 use std::{
     cmp,
     collections::HashSet,
+    path::Path,
     sync::Arc,
     time::{Duration, Instant},
 };
 use tokio::runtime::Handle;
-use vm_validator::vm_validator::{get_account_sequence_number, TransactionValidation};
+use vm_validator::vm_validator::{
+    get_account_balance, get_account_sequence_number, TransactionValidation,
+};
 
 // ============================== //
 //  broadcast_coordinator tasks  //
@@ -100,6 +105,29 @@ pub(crate) async fn process_client_transaction_submission<V>(
     }
 }
 
+/// Processes a request of a client for the transactions of an account pending in mempool.
+pub(crate) async fn process_account_pending_transactions_request<V>(
+    smp: SharedMempool<V>,
+    address: AccountAddress,
+    callback: oneshot::Sender<Result<AccountPendingTransactions>>,
+    timer: HistogramTimer,
+) where
+    V: TransactionValidation,
+{
+    timer.stop_and_record();
+    let result = get_account_sequence_number(smp.db.as_ref(), address).map(|sequence_info| {
+        let transactions = smp.mempool.lock().account_transactions(&address);
+        AccountPendingTransactions::new(sequence_info, transactions)
+    });
+    if callback.send(result).is_err() {
+        error!(LogSchema::event_log(
+            LogEntry::JsonRpc,
+            LogEvent::CallbackFail
+        ));
+        counters::CLIENT_CALLBACK_FAIL.inc();
+    }
+}
+
 /// Processes transactions from other nodes.
 pub(crate) async fn process_transaction_broadcast<V>(
     mut smp: SharedMempool<V>,
@@ -118,12 +146,25 @@ pub(crate) async fn process_transaction_broadcast<V>(
     );
     let results = process_incoming_transactions(&smp, transactions.clone(), timeline_state).await;
     log_txn_process_results(&results, Some(peer.clone()));
+    // peers only broadcast the transactions their own mempool accepted
+    let has_invalid_signature = results
+        .iter()
+        .any(|(_, (_, vm_status))| *vm_status == Some(DiscardedVMStatus::INVALID_SIGNATURE));
 
     let ack_response = gen_ack_response(request_id, results, &peer);
     let network_sender = smp
         .network_senders
         .get_mut(&peer.network_id())
         .expect("[shared mempool] missing network sender");
+    if has_invalid_signature {
+        if let Err(e) =
+            network_sender.report_misbehavior(peer.peer_id(), Misbehavior::InvalidSignature)
+        {
+            error!(LogSchema::new(LogEntry::BroadcastTransaction)
+                .peer(&peer)
+                .error(&e.into()));
+        }
+    }
     if let Err(e) = network_sender.send_to(peer.peer_id(), ack_response) {
         counters::network_send_fail_inc(counters::ACK_TXNS);
         error!(
@@ -257,12 +298,45 @@ where
         .collect::<Vec<_>>();
     vm_validation_timer.stop_and_record();
 
+    let balances = smp.config.check_sender_balance.then(|| {
+        transactions
+            .par_iter()
+            .map(|t| {
+                get_account_balance(smp.db.as_ref(), t.0.sender(), t.0.gas_currency_code()).map_err(
+                    |e| {
+                        error!(LogSchema::new(LogEntry::DBError).error(&e));
+                        counters::DB_ERROR.inc();
+                        e
+                    },
+                )
+            })
+            .collect::<Vec<_>>()
+    });
+
     {
         let mut mempool = smp.mempool.lock();
         for (idx, (transaction, crsn_or_seqno)) in transactions.into_iter().enumerate() {
             if let Ok(validation_result) = &validation_results[idx] {
                 match validation_result.status() {
                     None => {
+                        // the sender must be able to pay for all its pending transactions
+                        if let Some(balances) = &balances {
+                            let covered = balances[idx].as_ref().map_or(false, |balance| {
+                                mempool.covers_max_gas_cost(&transaction, *balance)
+                            });
+                            if !covered {
+                                statuses.push((
+                                    transaction,
+                                    (
+                                        MempoolStatus::new(MempoolStatusCode::VmError),
+                                        Some(
+                                            DiscardedVMStatus::INSUFFICIENT_BALANCE_FOR_TRANSACTION_FEE,
+                                        ),
+                                    ),
+                                ));
+                                continue;
+                            }
+                        }
                         let gas_amount = transaction.max_gas_amount();
                         let ranking_score = validation_result.score();
                         let governance_role = validation_result.governance_role();
@@ -293,6 +367,36 @@ where
     statuses
 }
 
+/// Validates again the transactions persisted before the restart, then starts persisting the
+/// transactions of mempool to a new log holding the ones accepted again.
+pub(crate) async fn replay_persisted_transactions<V>(smp: &SharedMempool<V>, path: &Path)
+where
+    V: TransactionValidation,
+{
+    let transactions = TransactionLog::read(path).unwrap_or_else(|e| {
+        let e = anyhow::Error::from(e);
+        error!(LogSchema::new(LogEntry::Persistence).error(&e));
+        vec![]
+    });
+    let num_persisted = transactions.len();
+    if !transactions.is_empty() {
+        let results =
+            process_incoming_transactions(smp, transactions, TimelineState::NotReady).await;
+        let num_accepted = results
+            .iter()
+            .filter(|(_, (status, _))| status.code == MempoolStatusCode::Accepted)
+            .count();
+        info!(
+            LogSchema::event_log(LogEntry::Persistence, LogEvent::Success),
+            "Replayed {} of the {} persisted transactions", num_accepted, num_persisted,
+        );
+    }
+    if let Err(e) = smp.mempool.lock().persist_to(path) {
+        let e = anyhow::Error::from(e);
+        error!(LogSchema::new(LogEntry::Persistence).error(&e));
+    }
+}
+
 fn log_txn_process_results(results: &[SubmissionStatusBundle], sender: Option<PeerNetworkId>) {
     let (network, sender) = match sender {
         Some(peer) => (
@@ -4,7 +4,7 @@
 //! Objects used by/related to shared mempool
 
 use crate::{
-    core_mempool::CoreMempool,
+    core_mempool::{CoreMempool, TransactionStatusReceiver},
     shared_mempool::{network::MempoolNetworkSender, peer_manager::PeerManager},
 };
 use anyhow::Result;
@@ -13,9 +13,11 @@ use diem_config::{
     config::{MempoolConfig, PeerNetworkId},
     network_id::NodeNetworkId,
 };
+use diem_crypto::HashValue;
 use diem_infallible::{Mutex, RwLock};
 use diem_types::{
     account_address::AccountAddress,
+    account_config::AccountSequenceInfo,
     mempool_status::MempoolStatus,
     on_chain_config::{ConfigID, DiemVersion, OnChainConfig, OnChainConfigPayload, VMConfig},
     transaction::SignedTransaction,
@@ -26,7 +28,10 @@ use futures::{
     future::Future,
     task::{Context, Poll},
 };
-use std::{collections::HashMap, fmt, pin::Pin, sync::Arc, task::Waker, time::Instant};
+use serde::Serialize;
+use std::{
+    cmp, collections::HashMap, fmt, ops::Range, pin::Pin, sync::Arc, task::Waker, time::Instant,
+};
 use storage_interface::DbReader;
 use subscription_service::ReconfigSubscription;
 use tokio::runtime::Handle;
@@ -168,7 +173,7 @@ pub enum ConsensusResponse {
     CommitResponse(),
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 pub struct TransactionSummary {
     pub sender: AccountAddress,
     pub sequence_number: u64,
@@ -184,8 +189,84 @@ pub type SubmissionStatus = (MempoolStatus, Option<DiscardedVMStatus>);
 
 pub type SubmissionStatusBundle = (SignedTransaction, SubmissionStatus);
 
-pub type MempoolClientSender =
-    mpsc::Sender<(SignedTransaction, oneshot::Sender<Result<SubmissionStatus>>)>;
+/// Message sent from the clients of mempool (e.g., JSON-RPC) to mempool.
+pub enum MempoolClientRequest {
+    /// Submission of a transaction.
+    SubmitTransaction(SignedTransaction, oneshot::Sender<Result<SubmissionStatus>>),
+    /// Request for the transactions of an account pending in mempool.
+    GetAccountPendingTransactions(
+        AccountAddress,
+        oneshot::Sender<Result<AccountPendingTransactions>>,
+    ),
+    /// Subscription to the lifecycle events of the transactions of an account, or of all the
+    /// transactions if None.
+    SubscribeTransactionStatus(
+        Option<AccountAddress>,
+        oneshot::Sender<TransactionStatusReceiver>,
+    ),
+    /// Request for the number and size of the transactions in mempool.
+    GetStats(oneshot::Sender<MempoolStats>),
+    /// Request for the transactions of the parking lot.
+    GetParkingLot(oneshot::Sender<Vec<TransactionSummary>>),
+    /// Eviction of a transaction and of the later transactions of its sender, for operators to
+    /// unblock an account. The callback gets the number of evicted transactions.
+    EvictTransaction(HashValue, oneshot::Sender<usize>),
+    /// Eviction of all the transactions of an account. The callback gets the number of evicted
+    /// transactions.
+    EvictAccount(AccountAddress, oneshot::Sender<usize>),
+    /// Update of the capacities of mempool to the ones of the config, on reloads. The
+    /// transactions beyond the new capacities are kept, the next ones are rejected.
+    UpdateCapacities(MempoolConfig),
+}
+
+pub type MempoolClientSender = mpsc::Sender<MempoolClientRequest>;
+
+/// Number and size of the transactions in mempool.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub struct MempoolStats {
+    /// Transactions consensus can pull
+    pub ready: usize,
+    /// Transactions of the parking lot, waiting on the missing sequence numbers before them
+    pub parked: usize,
+    /// Total size of the transactions, in bytes
+    pub size_bytes: usize,
+}
+
+/// Transactions of an account pending in mempool.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AccountPendingTransactions {
+    /// Sequence number of the account in the latest ledger state
+    pub sequence_number: u64,
+    /// Transactions in sequence number order, with whether they're parked: not ready until the
+    /// transactions of the missing sequence numbers before them arrive
+    pub transactions: Vec<(SignedTransaction, bool)>,
+    /// Ranges of the missing sequence numbers the parked transactions wait on
+    pub sequence_gaps: Vec<Range<u64>>,
+}
+
+impl AccountPendingTransactions {
+    pub(crate) fn new(
+        sequence_info: AccountSequenceInfo,
+        transactions: Vec<(SignedTransaction, bool)>,
+    ) -> Self {
+        let mut sequence_gaps = vec![];
+        // the transactions of a CRSN account don't wait on each other
+        if let AccountSequenceInfo::Sequential(sequence_number) = sequence_info {
+            let mut next_sequence_number = sequence_number;
+            for (txn, _) in &transactions {
+                if txn.sequence_number() > next_sequence_number {
+                    sequence_gaps.push(next_sequence_number..txn.sequence_number());
+                }
+                next_sequence_number = cmp::max(next_sequence_number, txn.sequence_number() + 1);
+            }
+        }
+        Self {
+            sequence_number: sequence_info.min_seq(),
+            transactions,
+            sequence_gaps,
+        }
+    }
+}
 
 const MEMPOOL_SUBSCRIBED_CONFIGS: &[ConfigID] = &[DiemVersion::CONFIG_ID, VMConfig::CONFIG_ID];
 
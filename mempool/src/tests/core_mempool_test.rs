@@ -2,19 +2,28 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    core_mempool::{CoreMempool, TimelineState, TtlCache},
+    core_mempool::{
+        CoreMempool, TimelineState, TransactionLog, TransactionStatus, TransactionStatusReceiver,
+        TtlCache,
+    },
     tests::common::{
         add_signed_txn, add_txn, add_txns_to_mempool, exist_in_metrics_cache, setup_mempool,
         TestTransaction,
     },
 };
-use diem_config::config::NodeConfig;
+use diem_config::config::{EvictionPolicyType, NodeConfig};
+use diem_crypto::hash::CryptoHash;
+use diem_temppath::TempPath;
 use diem_types::{
     account_config::AccountSequenceInfo,
-    transaction::{GovernanceRole, SignedTransaction},
+    mempool_status::MempoolStatusCode,
+    transaction::{GovernanceRole, SignedTransaction, Transaction},
 };
+use futures::{FutureExt, StreamExt};
 use std::{
     collections::HashSet,
+    fs::OpenOptions,
+    io::Write,
     time::{Duration, SystemTime},
 };
 
@@ -68,6 +77,52 @@ fn test_transaction_ordering_only_seqnos() {
     }
 }
 
+#[test]
+fn test_transaction_ordering_fairness() {
+    let (mut mempool, mut consensus) = setup_mempool();
+
+    // The highest bucket of gas price goes first, then the accounts of the same bucket take turns
+    let transactions = add_txns_to_mempool(
+        &mut mempool,
+        vec![
+            TestTransaction::new(0, 0, 200),
+            TestTransaction::new(0, 1, 200),
+            TestTransaction::new(0, 2, 200),
+            TestTransaction::new(1, 0, 160),
+            TestTransaction::new(2, 0, 500),
+        ],
+    );
+    assert_eq!(
+        consensus.get_block(&mut mempool, 5),
+        vec![
+            transactions[4].clone(),
+            transactions[0].clone(),
+            transactions[3].clone(),
+            transactions[1].clone(),
+            transactions[2].clone(),
+        ]
+    );
+
+    // A transaction paying more waits for the previous transactions of its account
+    let (mut mempool, mut consensus) = setup_mempool();
+    let transactions = add_txns_to_mempool(
+        &mut mempool,
+        vec![
+            TestTransaction::new(0, 0, 1),
+            TestTransaction::new(0, 1, 2000),
+            TestTransaction::new(1, 0, 200),
+        ],
+    );
+    assert_eq!(
+        consensus.get_block(&mut mempool, 3),
+        vec![
+            transactions[2].clone(),
+            transactions[0].clone(),
+            transactions[1].clone(),
+        ]
+    );
+}
+
 #[test]
 fn test_transaction_ordering_only_crsns() {
     let (mut mempool, mut consensus) = setup_mempool();
@@ -284,6 +339,37 @@ fn test_update_transaction_in_mempool_crsn() {
     assert_eq!(consensus.get_block(&mut mempool, 1), vec![txns[1].clone()]);
 }
 
+#[test]
+fn test_replace_transaction_gas_price_bump() {
+    let (mut mempool, mut consensus) = setup_mempool();
+    let _ = add_txns_to_mempool(&mut mempool, vec![TestTransaction::new(0, 0, 100)]);
+    let add = |mempool: &mut CoreMempool, gas_price| {
+        mempool
+            .add_txn(
+                TestTransaction::new(0, 0, gas_price).make_signed_transaction(),
+                0,
+                gas_price,
+                AccountSequenceInfo::Sequential(0),
+                TimelineState::NotReady,
+                GovernanceRole::NonGovernanceRole,
+            )
+            .code
+    };
+
+    // the gas price must be increased by at least 10% by default
+    assert_eq!(add(&mut mempool, 90), MempoolStatusCode::InvalidUpdate);
+    assert_eq!(
+        add(&mut mempool, 109),
+        MempoolStatusCode::UnderpricedReplacement
+    );
+    assert_eq!(add(&mut mempool, 110), MempoolStatusCode::Accepted);
+
+    // the replaced transaction is evicted
+    let block = consensus.get_block(&mut mempool, 10);
+    assert_eq!(block.len(), 1);
+    assert_eq!(block[0].gas_unit_price(), 110);
+}
+
 #[test]
 fn test_ignore_same_transaction_submitted_to_mempool() {
     let (mut mempool, _) = setup_mempool();
@@ -322,7 +408,7 @@ fn test_fail_for_same_gas_amount_and_not_same_expiration_time_crsn() {
 }
 
 #[test]
-fn test_update_invalid_transaction_in_mempool() {
+fn test_replace_transaction_with_different_max_gas_amount() {
     let (mut mempool, mut consensus) = setup_mempool();
     let txns = add_txns_to_mempool(
         &mut mempool,
@@ -332,18 +418,16 @@ fn test_update_invalid_transaction_in_mempool() {
         &TestTransaction::new(0, 0, 5),
         200,
     );
-    let _added_tnx = add_signed_txn(&mut mempool, updated_txn);
+    assert!(add_signed_txn(&mut mempool, updated_txn.clone()).is_ok());
 
-    // Since both gas price and mas gas amount were updated, the ordering should not have changed.
-    // The second transaction with gas price 2 should come first.
+    // The gas price was increased, so the transaction is replaced even though its max gas amount
+    // changed. The replacement with gas price 5 should come first.
+    assert_eq!(consensus.get_block(&mut mempool, 1), vec![updated_txn]);
     assert_eq!(consensus.get_block(&mut mempool, 1), vec![txns[1].clone()]);
-    let next_tnx = consensus.get_block(&mut mempool, 1);
-    assert_eq!(next_tnx, vec![txns[0].clone()]);
-    assert_eq!(next_tnx[0].gas_unit_price(), 1);
 }
 
 #[test]
-fn test_update_invalid_transaction_in_mempool_crsn() {
+fn test_replace_transaction_with_different_max_gas_amount_crsn() {
     let (mut mempool, mut consensus) = setup_mempool();
     let txns = add_txns_to_mempool(
         &mut mempool,
@@ -356,14 +440,12 @@ fn test_update_invalid_transaction_in_mempool_crsn() {
         &TestTransaction::new(0, 0, 5).crsn(0),
         200,
     );
-    let _added_tnx = add_signed_txn(&mut mempool, updated_txn);
+    assert!(add_signed_txn(&mut mempool, updated_txn.clone()).is_ok());
 
-    // Since both gas price and mas gas amount were updated, the ordering should not have changed.
-    // The second transaction with gas price 2 should come first.
+    // The gas price was increased, so the transaction is replaced even though its max gas amount
+    // changed. The replacement with gas price 5 should come first.
+    assert_eq!(consensus.get_block(&mut mempool, 1), vec![updated_txn]);
     assert_eq!(consensus.get_block(&mut mempool, 1), vec![txns[1].clone()]);
-    let next_tnx = consensus.get_block(&mut mempool, 1);
-    assert_eq!(next_tnx, vec![txns[0].clone()]);
-    assert_eq!(next_tnx[0].gas_unit_price(), 1);
 }
 
 #[test]
@@ -544,6 +626,69 @@ fn test_capacity() {
     assert!(add_txn(&mut pool, TestTransaction::new(1, 2, 1)).is_ok());
 }
 
+#[test]
+fn test_update_capacities() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 1;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap_err();
+
+    config.mempool.capacity = 3;
+    config.mempool.capacity_per_user = 1;
+    pool.update_capacities(&config.mempool);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    // Error on exceeding the new quota of the sender.
+    add_txn(&mut pool, TestTransaction::new(0, 1, 1)).unwrap_err();
+
+    // The transactions beyond a lower capacity are kept.
+    config.mempool.capacity = 1;
+    pool.update_capacities(&config.mempool);
+    assert_eq!(pool.size(), 2);
+    add_txn(&mut pool, TestTransaction::new(2, 0, 1)).unwrap_err();
+}
+
+#[test]
+fn test_capacity_bytes() {
+    let mut config = NodeConfig::random();
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+    let txn_bytes = pool.get_size_bytes();
+    config.mempool.capacity_bytes = 2 * txn_bytes;
+    config.mempool.capacity_bytes_per_user = txn_bytes;
+    let mut pool = CoreMempool::new(&config);
+
+    // Error on exceeding the quota of the sender.
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+    assert!(add_txn(&mut pool, TestTransaction::new(1, 1, 1)).is_err());
+
+    // Error on exceeding the memory of mempool.
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    assert!(add_txn(&mut pool, TestTransaction::new(2, 0, 1)).is_err());
+    assert_eq!(pool.get_size_bytes(), 2 * txn_bytes);
+
+    // Commit transaction and free memory.
+    pool.remove_transaction(&TestTransaction::get_address(0), 0, false);
+    assert!(add_txn(&mut pool, TestTransaction::new(2, 0, 1)).is_ok());
+}
+
+#[test]
+fn test_covers_max_gas_cost() {
+    let (mut pool, _) = setup_mempool();
+    // max gas amount of 100 at gas price 1
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+    let covers = |pool: &CoreMempool, txn: TestTransaction, balance| {
+        pool.covers_max_gas_cost(&txn.make_signed_transaction(), balance)
+    };
+
+    // the cost of the pending transactions of the sender is counted
+    assert!(covers(&pool, TestTransaction::new(1, 1, 1), 200));
+    assert!(!covers(&pool, TestTransaction::new(1, 1, 2), 200));
+    // but not the cost of the transaction replaced
+    assert!(covers(&pool, TestTransaction::new(1, 0, 2), 200));
+    assert!(covers(&pool, TestTransaction::new(2, 0, 2), 200));
+}
+
 #[test]
 fn test_parking_lot_eviction() {
     let mut config = NodeConfig::random();
@@ -602,6 +747,166 @@ fn test_parking_lot_evict_only_for_ready_txn_insertion() {
     }
 }
 
+#[test]
+fn test_lowest_gas_first_eviction() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 2;
+    config.mempool.eviction_policy = EvictionPolicyType::LowestGasFirst;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 0, 5)).unwrap();
+
+    // Mempool is full, the ready txn paying the least makes space for one paying more.
+    add_txn(&mut pool, TestTransaction::new(2, 0, 3)).unwrap();
+    let mut senders: Vec<_> = pool
+        .get_block(5, HashSet::new())
+        .iter()
+        .map(SignedTransaction::sender)
+        .collect();
+    senders.sort_unstable();
+    let mut expected = vec![
+        TestTransaction::get_address(1),
+        TestTransaction::get_address(2),
+    ];
+    expected.sort_unstable();
+    assert_eq!(senders, expected);
+
+    // A txn paying less than all the ready ones is rejected.
+    assert!(add_txn(&mut pool, TestTransaction::new(3, 0, 2)).is_err());
+}
+
+#[test]
+fn test_oldest_ready_first_eviction() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 3;
+    config.mempool.eviction_policy = EvictionPolicyType::OldestReadyFirst;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 5)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 1, 5)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 1, 1)).unwrap();
+
+    // Mempool is full, the txn that isn't ready leaves first.
+    add_txn(&mut pool, TestTransaction::new(2, 0, 1)).unwrap();
+    let txns = pool.get_block(5, HashSet::new());
+    assert_eq!(txns.len(), 3);
+    assert!(txns
+        .iter()
+        .all(|txn| txn.sender() != TestTransaction::get_address(1)));
+
+    // Once every txn is ready, the oldest one leaves along with the later txns of its account.
+    add_txn(&mut pool, TestTransaction::new(3, 0, 1)).unwrap();
+    let txns = pool.get_block(5, HashSet::new());
+    assert_eq!(txns.len(), 2);
+    assert!(txns
+        .iter()
+        .all(|txn| txn.sender() != TestTransaction::get_address(0)));
+}
+
+fn received_statuses(
+    pool: &CoreMempool,
+    receiver: &mut TransactionStatusReceiver,
+) -> Vec<(u64, TransactionStatus)> {
+    pool.status_bus().flush();
+    let mut statuses = vec![];
+    while let Some(event) = receiver.select_next_some().now_or_never() {
+        statuses.push((event.sequence_number, event.status));
+    }
+    statuses
+}
+
+#[test]
+fn test_transaction_status_events() {
+    let mut pool = setup_mempool().0;
+    let mut receiver = pool.subscribe_status(Some(TestTransaction::get_address(0)));
+    let txns = add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(0, 0, 1),
+            TestTransaction::new(0, 1, 1),
+            TestTransaction::new(1, 0, 1),
+        ],
+    );
+    // the events are only sent once out of the mempool lock
+    assert!(receiver.select_next_some().now_or_never().is_none());
+    pool.status_bus().flush();
+    let event = receiver.select_next_some().now_or_never().unwrap();
+    assert_eq!(event.sender, txns[0].sender());
+    assert_eq!(
+        event.hash,
+        Transaction::UserTransaction(txns[0].clone()).hash()
+    );
+    assert_eq!(event.status, TransactionStatus::Accepted);
+
+    // only the transactions of the account are followed
+    pool.get_block(10, HashSet::new());
+    pool.notify_broadcast(0, 10);
+    assert_eq!(
+        received_statuses(&pool, &mut receiver),
+        vec![
+            (1, TransactionStatus::Accepted),
+            (0, TransactionStatus::IncludedInBlock),
+            (1, TransactionStatus::IncludedInBlock),
+            (0, TransactionStatus::Broadcast),
+            (1, TransactionStatus::Broadcast),
+        ]
+    );
+
+    pool.remove_transaction(&txns[0].sender(), 0, false);
+    pool.gc_by_expiration_time(Duration::from_secs(u64::MAX));
+    assert_eq!(
+        received_statuses(&pool, &mut receiver),
+        vec![
+            (0, TransactionStatus::Committed),
+            (1, TransactionStatus::Expired),
+        ]
+    );
+
+    // the subscribers that went away are forgotten
+    drop(receiver);
+    let receiver = pool.subscribe_status(Some(TestTransaction::get_address(1)));
+    assert!(pool.status_bus().has_subscribers());
+    drop(receiver);
+    pool.status_bus().flush();
+    assert!(!pool.status_bus().has_subscribers());
+}
+
+#[test]
+fn test_transaction_log() {
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let path = dir.path().join("transactions.log");
+    let mut pool = setup_mempool().0;
+    pool.persist_to(&path).unwrap();
+    let txns = add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(0, 0, 1),
+            TestTransaction::new(0, 1, 1),
+            TestTransaction::new(1, 0, 1),
+        ],
+    );
+    pool.remove_transaction(&txns[2].sender(), txns[2].sequence_number(), false);
+    assert_eq!(
+        TransactionLog::read(&path).unwrap(),
+        vec![txns[0].clone(), txns[1].clone()]
+    );
+
+    // a record cut short by a crash is ignored
+    OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap()
+        .write_all(&[100, 0, 0, 0, 1, 2])
+        .unwrap();
+    assert_eq!(TransactionLog::read(&path).unwrap().len(), 2);
+
+    // a new log only holds the transactions of the mempool
+    let mut pool = setup_mempool().0;
+    add_txns_to_mempool(&mut pool, vec![TestTransaction::new(0, 0, 1)]);
+    pool.persist_to(&path).unwrap();
+    assert_eq!(TransactionLog::read(&path).unwrap(), vec![txns[0].clone()]);
+}
+
 #[test]
 fn test_gc_ready_transaction() {
     let mut pool = setup_mempool().0;
@@ -5,7 +5,7 @@ use crate::{
     core_mempool::{CoreMempool, TimelineState},
     network::{MempoolNetworkEvents, MempoolNetworkSender},
     shared_mempool::start_shared_mempool,
-    ConsensusRequest, SubmissionStatus,
+    ConsensusRequest, MempoolClientSender,
 };
 use anyhow::{format_err, Result};
 use channel::{self, diem_channel, message_queues::QueueStyle};
@@ -19,7 +19,7 @@ use diem_types::{
     mempool_status::MempoolStatusCode,
     transaction::{GovernanceRole, SignedTransaction},
 };
-use futures::channel::{mpsc, oneshot};
+use futures::channel::mpsc;
 use mempool_notifications::{self, MempoolNotificationListener, MempoolNotifier};
 use network::{
     peer_manager::{conn_notifs_channel, ConnectionRequestSender, PeerManagerRequestSender},
@@ -33,7 +33,7 @@ use vm_validator::mocks::mock_vm_validator::MockVMValidator;
 /// Mock of a running instance of shared mempool.
 pub struct MockSharedMempool {
     _runtime: Runtime,
-    pub ac_client: mpsc::Sender<(SignedTransaction, oneshot::Sender<Result<SubmissionStatus>>)>,
+    pub ac_client: MempoolClientSender,
     pub mempool: Arc<Mutex<CoreMempool>>,
     pub consensus_sender: mpsc::Sender<ConsensusRequest>,
     pub mempool_notifier: Option<MempoolNotifier>,
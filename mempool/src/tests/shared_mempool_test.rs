@@ -3,13 +3,15 @@
 
 use crate::{
     mocks::MockSharedMempool,
-    shared_mempool::types::TransactionSummary,
+    shared_mempool::{peer_manager::BroadcastInfo, types::TransactionSummary},
     tests::common::{batch_add_signed_txn, TestTransaction},
-    ConsensusRequest,
+    AccountPendingTransactions, ConsensusRequest,
 };
-use diem_types::transaction::Transaction;
+use diem_config::config::BroadcastBatchingConfig;
+use diem_types::{account_config::AccountSequenceInfo, transaction::Transaction};
 use futures::{channel::oneshot, executor::block_on, sink::SinkExt};
 use mempool_notifications::MempoolNotificationSender;
+use std::time::Duration;
 use tokio::runtime::Builder;
 
 #[test]
@@ -94,3 +96,58 @@ fn test_mempool_notify_committed_txns() {
     assert_eq!(timeline.len(), 1);
     assert_eq!(timeline.get(0).unwrap(), &kept_txn);
 }
+
+#[test]
+fn test_adaptive_broadcast_batch_size() {
+    let config = BroadcastBatchingConfig {
+        adaptive_batch_size: true,
+        min_batch_size: 10,
+        max_batch_size: 150,
+        target_ack_latency_ms: 500,
+        ..BroadcastBatchingConfig::default()
+    };
+    let mut broadcast_info = BroadcastInfo::new(100);
+
+    // fast acks grow the batch size up to the max
+    broadcast_info.adapt_batch_size(Duration::from_millis(100), &config);
+    assert_eq!(broadcast_info.batch_size, 125);
+    broadcast_info.adapt_batch_size(Duration::from_millis(500), &config);
+    assert_eq!(broadcast_info.batch_size, 150);
+
+    // slow acks shrink it down to the min
+    for expected in &[75, 37, 18, 10, 10] {
+        broadcast_info.adapt_batch_size(Duration::from_secs(1), &config);
+        assert_eq!(broadcast_info.batch_size, *expected);
+    }
+}
+
+#[test]
+fn test_account_pending_transactions_sequence_gaps() {
+    let transactions: Vec<_> = [1, 2, 4, 7, 8]
+        .iter()
+        .map(|seq| {
+            let txn = TestTransaction::new(0, *seq, 1).make_signed_transaction();
+            (txn, *seq > 2)
+        })
+        .collect();
+
+    let pending =
+        AccountPendingTransactions::new(AccountSequenceInfo::Sequential(1), transactions.clone());
+    assert_eq!(pending.sequence_number, 1);
+    assert_eq!(pending.sequence_gaps, vec![3..4, 5..7]);
+
+    // the first txn waits on the sequence number of the account
+    let pending =
+        AccountPendingTransactions::new(AccountSequenceInfo::Sequential(0), transactions.clone());
+    assert_eq!(pending.sequence_gaps, vec![0..1, 3..4, 5..7]);
+
+    // the txns of a CRSN account don't wait on each other
+    let pending = AccountPendingTransactions::new(
+        AccountSequenceInfo::CRSN {
+            min_nonce: 0,
+            size: 4,
+        },
+        transactions,
+    );
+    assert!(pending.sequence_gaps.is_empty());
+}
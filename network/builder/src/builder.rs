@@ -12,14 +12,15 @@
 use channel::{self, message_queues::QueueStyle};
 use diem_config::{
     config::{
-        DiscoveryMethod, NetworkConfig, Peer, PeerRole, PeerSet, RateLimitConfig, RoleType,
-        CONNECTION_BACKOFF_BASE, CONNECTIVITY_CHECK_INTERVAL_MS, MAX_CONCURRENT_NETWORK_REQS,
-        MAX_CONNECTION_DELAY_MS, MAX_FRAME_SIZE, MAX_FULLNODE_OUTBOUND_CONNECTIONS,
-        MAX_INBOUND_CONNECTIONS, NETWORK_CHANNEL_SIZE,
+        DiscoveryMethod, HandshakeLimitConfig, Identity, IdentityFromStorage,
+        InboundConnectionLimitConfig, KeepAliveConfig, NetworkConfig, Peer, PeerReputationConfig,
+        PeerRole, PeerSet, ProtocolRateLimitConfig, RateLimitConfig, RoleType, Socks5ProxyConfig,
+        WireCompressionConfig, CONNECTION_BACKOFF_BASE, CONNECTIVITY_CHECK_INTERVAL_MS,
+        MAX_CONCURRENT_NETWORK_REQS, MAX_CONNECTION_DELAY_MS, MAX_FRAME_SIZE,
+        MAX_FULLNODE_OUTBOUND_CONNECTIONS, MAX_INBOUND_CONNECTIONS, NETWORK_CHANNEL_SIZE,
     },
     network_id::NetworkContext,
 };
-use diem_crypto::x25519::PublicKey;
 use diem_infallible::RwLock;
 use diem_logger::prelude::*;
 use diem_metrics::IntCounterVec;
@@ -31,6 +32,7 @@ use network::{
     application::storage::PeerMetadataStorage,
     connectivity_manager::{builder::ConnectivityManagerBuilder, ConnectivityRequest},
     logging::NetworkSchema,
+    noise::IdentityKey,
     peer_manager::{
         builder::{AuthenticationMode, PeerManagerBuilder},
         ConnectionRequestSender,
@@ -39,13 +41,14 @@ use network::{
         health_checker::{self, builder::HealthCheckerBuilder},
         network::{NewNetworkEvents, NewNetworkSender},
     },
+    transport::DiemTransport,
     ProtocolId,
 };
 use network_discovery::{gen_simple_discovery_reconfig_subscription, DiscoveryChangeListener};
 use std::{
     clone::Clone,
     collections::{HashMap, HashSet},
-    sync::Arc,
+    sync::{atomic::AtomicUsize, Arc},
 };
 use subscription_service::ReconfigSubscription;
 use tokio::runtime::Handle;
@@ -80,6 +83,7 @@ pub struct NetworkBuilder {
 impl NetworkBuilder {
     /// Return a new NetworkBuilder initialized with default configuration values.
     // TODO:  Remove `pub`.  NetworkBuilder should only be created thorugh `::create()`
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         chain_id: ChainId,
         trusted_peers: Arc<RwLock<PeerSet>>,
@@ -94,6 +98,13 @@ impl NetworkBuilder {
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        peer_reputation_config: Option<PeerReputationConfig>,
+        protocol_rate_limit_config: Option<ProtocolRateLimitConfig>,
+        wire_compression_config: Option<WireCompressionConfig>,
+        inbound_connection_limit_config: Option<InboundConnectionLimitConfig>,
+        keep_alive_config: Option<KeepAliveConfig>,
+        socks5_proxy_config: Option<Socks5ProxyConfig>,
+        handshake_limit_config: Option<HandshakeLimitConfig>,
     ) -> Self {
         let peer_metadata_storage = Arc::new(PeerMetadataStorage::new());
         // A network cannot exist without a PeerManager
@@ -113,6 +124,13 @@ impl NetworkBuilder {
             inbound_connection_limit,
             inbound_rate_limit_config,
             outbound_rate_limit_config,
+            peer_reputation_config,
+            protocol_rate_limit_config,
+            wire_compression_config,
+            inbound_connection_limit_config,
+            keep_alive_config,
+            socks5_proxy_config,
+            handshake_limit_config,
         );
 
         NetworkBuilder {
@@ -154,6 +172,13 @@ impl NetworkBuilder {
             MAX_INBOUND_CONNECTIONS,
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
 
         builder.add_connectivity_manager(
@@ -179,7 +204,6 @@ impl NetworkBuilder {
     ) -> NetworkBuilder {
         let peer_id = config.peer_id();
         let identity_key = config.identity_key();
-        let pubkey = identity_key.public_key();
 
         let authentication_mode = if config.mutual_authentication {
             AuthenticationMode::Mutual(identity_key)
@@ -209,6 +233,13 @@ impl NetworkBuilder {
             config.max_inbound_connections,
             config.inbound_rate_limit_config,
             config.outbound_rate_limit_config,
+            config.peer_reputation_config,
+            config.protocol_rate_limit_config.clone(),
+            config.wire_compression_config,
+            config.inbound_connection_limit_config,
+            config.keep_alive_config,
+            config.socks5_proxy_config.clone(),
+            config.handshake_limit_config,
         );
 
         network_builder.add_connection_monitoring(
@@ -231,11 +262,18 @@ impl NetworkBuilder {
             config.mutual_authentication,
         );
 
+        // The on-chain discovery rotates the identity key once the key in storage is registered
+        let identity_key = network_builder.peer_manager_builder.identity_key();
+        let identity_storage = match &config.identity {
+            Identity::FromStorage(identity) => Some(identity.clone()),
+            _ => None,
+        };
         network_builder.discovery_listeners = Some(Vec::new());
         for discovery_method in config.discovery_methods() {
             network_builder.add_discovery_change_listener(
                 discovery_method,
-                pubkey,
+                identity_key.clone(),
+                identity_storage.clone(),
                 config.encryptor(),
             );
         }
@@ -256,6 +294,14 @@ impl NetworkBuilder {
         network_builder
     }
 
+    /// Runs the connections of the network over `transport`, e.g. a custom tunnel, in place of the
+    /// transport picked from the listen address. See [`DiemTransport`].
+    pub fn set_transport(&mut self, transport: impl DiemTransport) -> &mut Self {
+        assert_eq!(self.state, State::CREATED);
+        self.peer_manager_builder.set_transport(transport);
+        self
+    }
+
     /// Create the configured Networking components.
     pub fn build(&mut self, executor: Handle) -> &mut Self {
         assert_eq!(self.state, State::CREATED);
@@ -310,6 +356,16 @@ impl NetworkBuilder {
         self.network_context.clone()
     }
 
+    pub fn peer_metadata_storage(&self) -> Arc<PeerMetadataStorage> {
+        self.peer_metadata_storage.clone()
+    }
+
+    /// The limit of the inbound connections of unknown peers, `max_inbound_connections` of the
+    /// config, which can be updated while the network runs
+    pub fn inbound_connection_limit(&self) -> Arc<AtomicUsize> {
+        self.peer_manager_builder.inbound_connection_limit()
+    }
+
     pub fn conn_mgr_reqs_tx(&self) -> Option<channel::Sender<ConnectivityRequest>> {
         self.connectivity_manager_builder
             .as_ref()
@@ -368,7 +424,8 @@ impl NetworkBuilder {
     fn add_discovery_change_listener(
         &mut self,
         discovery_method: &DiscoveryMethod,
-        pubkey: PublicKey,
+        identity_key: IdentityKey,
+        identity_storage: Option<IdentityFromStorage>,
         encryptor: Encryptor<Storage>,
     ) {
         let conn_mgr_reqs_tx = self
@@ -384,7 +441,8 @@ impl NetworkBuilder {
                 DiscoveryChangeListener::validator_set(
                     self.network_context.clone(),
                     conn_mgr_reqs_tx,
-                    pubkey,
+                    identity_key,
+                    identity_storage,
                     encryptor,
                     simple_discovery_reconfig_rx,
                 )
@@ -396,6 +454,14 @@ impl NetworkBuilder {
                 *interval_duration,
                 self.time_service.clone(),
             ),
+            DiscoveryMethod::Dns(seed_addrs, interval_duration) => DiscoveryChangeListener::dns(
+                self.network_context.clone(),
+                conn_mgr_reqs_tx,
+                seed_addrs,
+                *interval_duration,
+                self.time_service.clone(),
+            )
+            .expect("DNS seed addresses must be well formed"),
             DiscoveryMethod::None => return,
         };
 
@@ -109,6 +109,14 @@ pub struct DummyNetwork {
 
 /// The following sets up a 2 peer network and verifies connectivity.
 pub fn setup_network() -> DummyNetwork {
+    setup_network_with("/ip4/127.0.0.1/tcp/0", |_| ())
+}
+
+/// Sets up a 2 peer network listening on `listen_addr`, with the transport set by `set_transport`
+pub fn setup_network_with(
+    listen_addr: &str,
+    set_transport: fn(&mut NetworkBuilder),
+) -> DummyNetwork {
     let runtime = Runtime::new().unwrap();
     let role = RoleType::Validator;
     let network_id = NetworkId::Validator;
@@ -126,8 +134,8 @@ pub fn setup_network() -> DummyNetwork {
     let listener_identity_private_key = x25519::PrivateKey::generate(&mut rng);
 
     // Setup listen addresses
-    let dialer_addr: NetworkAddress = "/ip4/127.0.0.1/tcp/0".parse().unwrap();
-    let listener_addr: NetworkAddress = "/ip4/127.0.0.1/tcp/0".parse().unwrap();
+    let dialer_addr: NetworkAddress = listen_addr.parse().unwrap();
+    let listener_addr: NetworkAddress = listen_addr.parse().unwrap();
 
     // Setup seed peers
     let mut seeds = PeerSet::new();
@@ -155,6 +163,7 @@ pub fn setup_network() -> DummyNetwork {
         authentication_mode,
     );
 
+    set_transport(&mut network_builder);
     let (listener_sender, mut listener_events) = network_builder
         .add_protocol_handler::<DummyNetworkSender, DummyNetworkEvents>(network_endpoint_config());
     network_builder.build(runtime.handle().clone()).start();
@@ -183,6 +192,7 @@ pub fn setup_network() -> DummyNetwork {
         authentication_mode,
     );
 
+    set_transport(&mut network_builder);
     let (dialer_sender, mut dialer_events) = network_builder
         .add_protocol_handler::<DummyNetworkSender, DummyNetworkEvents>(network_endpoint_config());
     network_builder.build(runtime.handle().clone()).start();
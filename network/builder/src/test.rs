@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! Integration tests for validator_network.
-use crate::dummy::{setup_network, DummyMsg};
+use crate::dummy::{setup_network, setup_network_with, DummyMsg};
 use futures::{future::join, StreamExt};
+use netcore::transport::memory::MemoryTransport;
 use network::protocols::network::Event;
 use std::time::Duration;
 
@@ -12,6 +13,13 @@ fn test_network_builder() {
     setup_network();
 }
 
+#[test]
+fn test_network_builder_custom_transport() {
+    setup_network_with("/memory/0", |network_builder| {
+        network_builder.set_transport(MemoryTransport);
+    });
+}
+
 #[test]
 fn test_direct_send() {
     ::diem_logger::Logger::init_for_testing();
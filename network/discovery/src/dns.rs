@@ -0,0 +1,207 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Discovery of the seed peers behind DNS names, so operators can rotate the hosts of their seeds
+//! without any change to the config of the nodes using them.
+//!
+//! Every seed is a DNS address with the identity of the seed, e.g.
+//! `/dns/seed.example.com/tcp/6182/noise-ik/<pubkey>/handshake/0`, and all the hosts its name
+//! resolves to share that identity. The names are resolved again on every interval. A seed whose
+//! name fails to resolve keeps the addresses of its last resolution.
+
+use crate::{counters::DISCOVERY_COUNTS, DiscoveryError};
+use diem_config::{
+    config::{Peer, PeerRole, PeerSet},
+    network_id::NetworkContext,
+};
+use diem_logger::prelude::*;
+use diem_time_service::{Interval, TimeService, TimeServiceTrait};
+use diem_types::{
+    account_address::from_identity_public_key,
+    network_address::{parse_dns_tcp, NetworkAddress},
+    PeerId,
+};
+use futures::{stream, Stream, StreamExt};
+use network::{counters::inc_by_with_context, logging::NetworkSchema};
+use std::{
+    collections::HashSet,
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::net::lookup_host;
+
+struct DnsSeed {
+    peer_id: PeerId,
+    addr: NetworkAddress,
+    /// The addresses of the hosts of the last successful resolution
+    resolved_addrs: Vec<NetworkAddress>,
+}
+
+impl DnsSeed {
+    fn new(addr: NetworkAddress) -> Result<Self, DiscoveryError> {
+        if !addr.is_diemnet_addr() || parse_dns_tcp(addr.as_slice()).is_none() {
+            return Err(DiscoveryError::Parsing(format!(
+                "invalid DNS seed address: {}, expected /dns/<name>/tcp/<port>/noise-ik/<pubkey>/handshake/<version>",
+                addr
+            )));
+        }
+        let pubkey = addr
+            .find_noise_proto()
+            .expect("diemnet addresses have a noise pubkey");
+        Ok(Self {
+            peer_id: from_identity_public_key(pubkey),
+            addr,
+            resolved_addrs: Vec::new(),
+        })
+    }
+
+    async fn resolve(&mut self) -> io::Result<()> {
+        let ((ip_filter, dns_name, port), suffix) =
+            parse_dns_tcp(self.addr.as_slice()).expect("checked on creation");
+        let mut socket_addrs: Vec<SocketAddr> = lookup_host((dns_name.as_ref(), port))
+            .await?
+            .filter(|socket_addr| ip_filter.matches(socket_addr.ip()))
+            .collect();
+        // Names may resolve to their hosts in any order, which must not look like a change
+        socket_addrs.sort_unstable();
+        socket_addrs.dedup();
+        self.resolved_addrs = socket_addrs
+            .into_iter()
+            .map(|socket_addr| NetworkAddress::from(socket_addr).extend_from_slice(suffix))
+            .collect();
+        Ok(())
+    }
+
+    fn peer(&self) -> Option<Peer> {
+        if self.resolved_addrs.is_empty() {
+            return None;
+        }
+        // The keys are pulled out of the addresses
+        Some(Peer::new(
+            self.resolved_addrs.clone(),
+            HashSet::new(),
+            PeerRole::Upstream,
+        ))
+    }
+}
+
+pub struct DnsStream {
+    stream: Pin<Box<dyn Stream<Item = Result<PeerSet, DiscoveryError>> + Send>>,
+}
+
+impl DnsStream {
+    pub(crate) fn new(
+        network_context: Arc<NetworkContext>,
+        seed_addrs: &[NetworkAddress],
+        interval_duration: Duration,
+        time_service: TimeService,
+    ) -> Result<Self, DiscoveryError> {
+        let seeds = seed_addrs
+            .iter()
+            .cloned()
+            .map(DnsSeed::new)
+            .collect::<Result<Vec<_>, _>>()?;
+        let interval: Pin<Box<Interval>> = Box::pin(time_service.interval(interval_duration));
+        let stream = stream::unfold(
+            (network_context, interval, seeds),
+            |(network_context, mut interval, mut seeds)| async move {
+                interval.next().await?;
+                let peers = resolve_seeds(&network_context, &mut seeds).await;
+                Some((Ok(peers), (network_context, interval, seeds)))
+            },
+        );
+        Ok(DnsStream {
+            stream: Box::pin(stream),
+        })
+    }
+}
+
+impl Stream for DnsStream {
+    type Item = Result<PeerSet, DiscoveryError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.as_mut().poll_next(cx)
+    }
+}
+
+/// Resolves the names of all the seeds, falling back to the last resolution of the seeds whose
+/// name failed to resolve
+async fn resolve_seeds(network_context: &NetworkContext, seeds: &mut [DnsSeed]) -> PeerSet {
+    let mut peers = PeerSet::new();
+    for seed in seeds.iter_mut() {
+        if let Err(error) = seed.resolve().await {
+            inc_by_with_context(&DISCOVERY_COUNTS, network_context, "dns_failure", 1);
+            warn!(
+                NetworkSchema::new(network_context),
+                "{} Failed to resolve DNS seed {}: {}", network_context, seed.addr, error
+            );
+        }
+        if let Some(peer) = seed.peer() {
+            peers.insert(seed.peer_id, peer);
+        }
+    }
+    peers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DiscoveryChangeListener;
+    use network::connectivity_manager::{ConnectivityRequest, DiscoverySource};
+    use std::str::FromStr;
+
+    const PUBKEY: &str = "080e287879c918794170e258bfaddd75acac5b3e350419044655e4983a487120";
+
+    fn seed_addr(host: &str) -> NetworkAddress {
+        NetworkAddress::from_str(&format!(
+            "/{}/tcp/6182/ln-noise-ik/{}/ln-handshake/0",
+            host, PUBKEY
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_invalid_seed_addrs() {
+        // Not a DNS address
+        DnsSeed::new(seed_addr("ip4/1.2.3.4")).unwrap_err();
+        // Without the identity of the seed
+        DnsSeed::new(NetworkAddress::from_str("/dns/localhost/tcp/6182").unwrap()).unwrap_err();
+        DnsSeed::new(seed_addr("dns/localhost")).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dns_listener() {
+        let (conn_mgr_reqs_tx, mut conn_mgr_reqs_rx) =
+            channel::new(1, &network::counters::PENDING_CONNECTIVITY_MANAGER_REQUESTS);
+        let listener = DiscoveryChangeListener::dns(
+            NetworkContext::mock(),
+            conn_mgr_reqs_tx,
+            &[seed_addr("dns4/localhost"), seed_addr("dns4/seed.invalid")],
+            Duration::from_millis(5),
+            TimeService::real(),
+        )
+        .unwrap();
+        tokio::task::spawn(Box::pin(listener).run());
+
+        if let Some(ConnectivityRequest::UpdateDiscoveredPeers(DiscoverySource::Dns, peers)) =
+            conn_mgr_reqs_rx.next().await
+        {
+            // The seed failing to resolve is left out
+            assert_eq!(peers.len(), 1);
+            let seed = DnsSeed::new(seed_addr("dns4/localhost")).unwrap();
+            let peer = &peers[&seed.peer_id];
+            assert_eq!(
+                peer.addresses,
+                vec![seed_addr("ip4/127.0.0.1")],
+                "localhost should resolve to the loopback address"
+            );
+            assert_eq!(peer.role, PeerRole::Upstream);
+        } else {
+            panic!("No message sent by discovery")
+        }
+    }
+}
@@ -1,20 +1,28 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{counters::DISCOVERY_COUNTS, file::FileStream, validator_set::ValidatorSetStream};
+use crate::{
+    counters::DISCOVERY_COUNTS, dns::DnsStream, file::FileStream, validator_set::ValidatorSetStream,
+};
 use channel::{diem_channel, diem_channel::Receiver};
-use diem_config::{config::PeerSet, network_id::NetworkContext};
-use diem_crypto::x25519;
+use diem_config::{
+    config::{IdentityFromStorage, PeerSet},
+    network_id::NetworkContext,
+};
 use diem_logger::prelude::*;
 use diem_network_address_encryption::Encryptor;
 use diem_secure_storage::Storage;
 use diem_time_service::TimeService;
-use diem_types::on_chain_config::{OnChainConfigPayload, ON_CHAIN_CONFIG_REGISTRY};
+use diem_types::{
+    network_address::NetworkAddress,
+    on_chain_config::{OnChainConfigPayload, ON_CHAIN_CONFIG_REGISTRY},
+};
 use futures::{Stream, StreamExt};
 use network::{
     connectivity_manager::{ConnectivityRequest, DiscoverySource},
     counters::inc_by_with_context,
     logging::NetworkSchema,
+    noise::IdentityKey,
 };
 use std::{
     path::Path,
@@ -27,6 +35,7 @@ use subscription_service::ReconfigSubscription;
 use tokio::runtime::Handle;
 
 mod counters;
+mod dns;
 mod file;
 mod validator_set;
 
@@ -47,6 +56,7 @@ pub struct DiscoveryChangeListener {
 enum DiscoveryChangeStream {
     ValidatorSet(ValidatorSetStream),
     File(FileStream),
+    Dns(DnsStream),
 }
 
 impl Stream for DiscoveryChangeStream {
@@ -56,6 +66,7 @@ impl Stream for DiscoveryChangeStream {
         match self.get_mut() {
             Self::ValidatorSet(stream) => Pin::new(stream).poll_next(cx),
             Self::File(stream) => Pin::new(stream).poll_next(cx),
+            Self::Dns(stream) => Pin::new(stream).poll_next(cx),
         }
     }
 }
@@ -64,13 +75,15 @@ impl DiscoveryChangeListener {
     pub fn validator_set(
         network_context: Arc<NetworkContext>,
         update_channel: channel::Sender<ConnectivityRequest>,
-        expected_pubkey: x25519::PublicKey,
+        identity_key: IdentityKey,
+        identity_storage: Option<IdentityFromStorage>,
         encryptor: Encryptor<Storage>,
         reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
     ) -> Self {
         let source_stream = DiscoveryChangeStream::ValidatorSet(ValidatorSetStream::new(
             network_context.clone(),
-            expected_pubkey,
+            identity_key,
+            identity_storage,
             encryptor,
             reconfig_events,
         ));
@@ -102,6 +115,27 @@ impl DiscoveryChangeListener {
         }
     }
 
+    pub fn dns(
+        network_context: Arc<NetworkContext>,
+        update_channel: channel::Sender<ConnectivityRequest>,
+        seed_addrs: &[NetworkAddress],
+        interval_duration: Duration,
+        time_service: TimeService,
+    ) -> Result<Self, DiscoveryError> {
+        let source_stream = DiscoveryChangeStream::Dns(DnsStream::new(
+            network_context.clone(),
+            seed_addrs,
+            interval_duration,
+            time_service,
+        )?);
+        Ok(DiscoveryChangeListener {
+            discovery_source: DiscoverySource::Dns,
+            network_context,
+            update_channel,
+            source_stream,
+        })
+    }
+
     pub fn start(self, executor: &Handle) {
         executor.spawn(Box::pin(self).run());
     }
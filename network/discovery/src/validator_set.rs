@@ -7,16 +7,16 @@ use crate::{
 };
 use channel::diem_channel;
 use diem_config::{
-    config::{Peer, PeerRole, PeerSet},
+    config::{IdentityFromStorage, Peer, PeerRole, PeerSet},
     network_id::NetworkContext,
 };
 use diem_crypto::x25519;
 use diem_logger::prelude::*;
 use diem_network_address_encryption::{Encryptor, Error as EncryptorError};
-use diem_secure_storage::Storage;
+use diem_secure_storage::{CryptoStorage, Storage};
 use diem_types::on_chain_config::{OnChainConfigPayload, ValidatorSet};
 use futures::Stream;
-use network::{counters::inc_by_with_context, logging::NetworkSchema};
+use network::{counters::inc_by_with_context, logging::NetworkSchema, noise::IdentityKey};
 use short_hex_str::AsShortHexStr;
 use std::{
     collections::HashSet,
@@ -27,7 +27,9 @@ use std::{
 
 pub struct ValidatorSetStream {
     pub(crate) network_context: Arc<NetworkContext>,
-    expected_pubkey: x25519::PublicKey,
+    identity_key: IdentityKey,
+    // The storage of the identity key, from which a rotated key is loaded
+    identity_storage: Option<IdentityFromStorage>,
     encryptor: Encryptor<Storage>,
     reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
 }
@@ -35,26 +37,72 @@ pub struct ValidatorSetStream {
 impl ValidatorSetStream {
     pub(crate) fn new(
         network_context: Arc<NetworkContext>,
-        expected_pubkey: x25519::PublicKey,
+        identity_key: IdentityKey,
+        identity_storage: Option<IdentityFromStorage>,
         encryptor: Encryptor<Storage>,
         reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
     ) -> Self {
         Self {
             network_context,
-            expected_pubkey,
+            identity_key,
+            identity_storage,
             encryptor,
             reconfig_events,
         }
     }
 
+    /// Rotates the identity key of the network to the key held in storage once that key is
+    /// registered on-chain, i.e., once the peers expect it. The connections are kept, and the
+    /// peers that still expect the previous key are served with it.
+    fn rotate_identity_key(&self, onchain_keys: Option<&HashSet<x25519::PublicKey>>) {
+        let (identity_storage, onchain_keys) = match (&self.identity_storage, onchain_keys) {
+            (Some(identity_storage), Some(onchain_keys)) => (identity_storage, onchain_keys),
+            _ => return,
+        };
+        if onchain_keys.contains(&self.identity_key.public_key()) {
+            return;
+        }
+
+        let storage: Storage = (&identity_storage.backend).into();
+        let key = match storage
+            .export_private_key(&identity_storage.key_name)
+            .map_err(|error| error.to_string())
+            .and_then(|key| {
+                x25519::PrivateKey::from_ed25519_private_bytes(&key.to_bytes())
+                    .map_err(|error| error.to_string())
+            }) {
+            Ok(key) => key,
+            Err(error) => {
+                warn!(
+                    NetworkSchema::new(&self.network_context),
+                    "Unable to read the identity key from storage: {}", error
+                );
+                return;
+            }
+        };
+
+        let pubkey = key.public_key();
+        if onchain_keys.contains(&pubkey) && self.identity_key.rotate(key) {
+            info!(
+                NetworkSchema::new(&self.network_context),
+                "Rotated the identity key to the onchain pubkey {}", pubkey
+            );
+            inc_by_with_context(
+                &DISCOVERY_COUNTS,
+                &self.network_context,
+                "identity_key_rotated",
+                1,
+            );
+        }
+    }
+
     fn find_key_mismatches(&self, onchain_keys: Option<&HashSet<x25519::PublicKey>>) {
+        let expected_pubkey = self.identity_key.public_key();
         let mismatch = onchain_keys.map_or(0, |pubkeys| {
-            if !pubkeys.contains(&self.expected_pubkey) {
+            if !pubkeys.contains(&expected_pubkey) {
                 error!(
                     NetworkSchema::new(&self.network_context),
-                    "Onchain pubkey {:?} differs from local pubkey {}",
-                    pubkeys,
-                    self.expected_pubkey
+                    "Onchain pubkey {:?} differs from local pubkey {}", pubkeys, expected_pubkey
                 );
                 1
             } else {
@@ -80,12 +128,12 @@ impl ValidatorSetStream {
 
         let peer_set =
             extract_validator_set_updates(self.network_context.clone(), &self.encryptor, node_set);
-        // Ensure that the public key matches what's onchain for this peer
-        self.find_key_mismatches(
-            peer_set
-                .get(&self.network_context.peer_id())
-                .map(|peer| &peer.keys),
-        );
+        // Ensure that the public key matches what's onchain for this peer, rotating it if needed
+        let onchain_keys = peer_set
+            .get(&self.network_context.peer_id())
+            .map(|peer| &peer.keys);
+        self.rotate_identity_key(onchain_keys);
+        self.find_key_mismatches(onchain_keys);
 
         inc_by_with_context(
             &DISCOVERY_COUNTS,
@@ -163,12 +211,13 @@ fn extract_validator_set_updates(
 mod tests {
     use super::*;
     use crate::{gen_simple_discovery_reconfig_subscription, DiscoveryChangeListener};
-    use diem_config::config::HANDSHAKE_VERSION;
+    use diem_config::config::{OnDiskStorageConfig, SecureBackend, HANDSHAKE_VERSION};
     use diem_crypto::{
         ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
         x25519::PrivateKey,
         PrivateKey as PK, Uniform,
     };
+    use diem_temppath::TempPath;
     use diem_types::{
         network_address::NetworkAddress, on_chain_config::OnChainConfig,
         validator_config::ValidatorConfig, validator_info::ValidatorInfo, PeerId,
@@ -188,8 +237,9 @@ mod tests {
         let runtime = Runtime::new().unwrap();
         let consensus_private_key = Ed25519PrivateKey::generate_for_testing();
         let consensus_pubkey = consensus_private_key.public_key();
-        let pubkey = test_pubkey([0u8; 32]);
-        let different_pubkey = test_pubkey([1u8; 32]);
+        let private_key = test_private_key([0u8; 32]);
+        let pubkey = private_key.public_key();
+        let different_pubkey = test_private_key([1u8; 32]).public_key();
         let peer_id = diem_types::account_address::from_identity_public_key(pubkey);
 
         // Build up the Reconfig Listener
@@ -199,7 +249,8 @@ mod tests {
         let listener = DiscoveryChangeListener::validator_set(
             network_context.clone(),
             conn_mgr_reqs_tx,
-            pubkey,
+            IdentityKey::new(private_key),
+            None,
             Encryptor::for_testing(),
             reconfig_rx,
         );
@@ -228,6 +279,48 @@ mod tests {
         check_network_key_mismatch_metric(1, &network_context);
     }
 
+    #[test]
+    fn rotate_identity_key_once_onchain() {
+        let private_key = test_private_key([0u8; 32]);
+        let pubkey = private_key.public_key();
+        let peer_id = diem_types::account_address::from_identity_public_key(pubkey);
+
+        // Store a new identity key, as the key manager rotates it
+        let temp_path = TempPath::new();
+        let mut storage_config = OnDiskStorageConfig::default();
+        storage_config.path = temp_path.path().to_path_buf();
+        let backend = SecureBackend::OnDiskStorage(storage_config);
+        let mut storage: Storage = (&backend).into();
+        let key_name = "validator_network".to_string();
+        let new_ed25519_key = Ed25519PrivateKey::generate_for_testing();
+        let new_pubkey =
+            x25519::PublicKey::from_ed25519_public_bytes(&new_ed25519_key.public_key().to_bytes())
+                .unwrap();
+        storage
+            .import_private_key(&key_name, new_ed25519_key)
+            .unwrap();
+
+        let identity_key = IdentityKey::new(private_key);
+        let (_reconfig_tx, reconfig_rx) = gen_simple_discovery_reconfig_subscription();
+        let stream = ValidatorSetStream::new(
+            NetworkContext::mock_with_peer_id(peer_id),
+            identity_key.clone(),
+            Some(IdentityFromStorage {
+                backend,
+                key_name,
+                peer_id_name: "owner_account".into(),
+            }),
+            Encryptor::for_testing(),
+            reconfig_rx,
+        );
+
+        // The key isn't rotated until the new key is onchain
+        stream.rotate_identity_key(Some(&vec![pubkey].into_iter().collect()));
+        assert_eq!(identity_key.public_key(), pubkey);
+        stream.rotate_identity_key(Some(&vec![new_pubkey].into_iter().collect()));
+        assert_eq!(identity_key.public_key(), new_pubkey);
+    }
+
     fn check_network_key_mismatch_metric(expected: i64, network_context: &NetworkContext) {
         assert_eq!(
             expected,
@@ -269,9 +362,8 @@ mod tests {
         reconfig_tx.publish(payload).unwrap();
     }
 
-    fn test_pubkey(seed: [u8; 32]) -> x25519::PublicKey {
+    fn test_private_key(seed: [u8; 32]) -> PrivateKey {
         let mut rng: StdRng = SeedableRng::from_seed(seed);
-        let private_key = PrivateKey::generate(&mut rng);
-        private_key.public_key()
+        PrivateKey::generate(&mut rng)
     }
 }
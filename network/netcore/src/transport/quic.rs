@@ -0,0 +1,443 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! QUIC Transport
+//!
+//! A QUIC connection carries a single bidirectional stream, which is used like a TCP connection:
+//! the stream is still upgraded with Noise IK and the DiemNet handshake. The TLS certificates of
+//! QUIC are self-signed and not verified, as peers are authenticated by the Noise IK handshake.
+//! QUIC still brings faster connection establishment and better loss recovery than TCP on lossy
+//! links.
+use crate::transport::{
+    tcp::{resolve_with_filter, TcpSocket, TcpTransport},
+    Transport,
+};
+use diem_types::{
+    network_address::{parse_dns_udp_quic, parse_ip_udp_quic, NetworkAddress, Protocol},
+    PeerId,
+};
+use futures::{
+    future::{BoxFuture, Either, FutureExt, TryFutureExt},
+    io::{AsyncRead, AsyncWrite},
+    stream::{BoxStream, Stream, StreamExt, TryStreamExt},
+};
+use quinn::{Endpoint, Incoming, NewConnection};
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+use tokio::io::ReadBuf;
+use tokio_util::compat::Compat;
+
+/// The ALPN protocol of DiemNet over QUIC
+const ALPN_DIEMNET: &[u8] = b"diemnet";
+/// The server name of the self-signed certificates, which is not verified
+const SERVER_NAME: &str = "diemnet";
+
+/// Transport to build QUIC connections
+#[derive(Debug, Clone, Default)]
+pub struct QuicTransport {
+    /// Interval of the keep-alive packets, which also keep NAT mappings open, or `None` to not
+    /// send any.
+    pub keep_alive_interval: Option<Duration>,
+}
+
+impl QuicTransport {
+    fn transport_config(&self) -> quinn::TransportConfig {
+        let mut transport_config = quinn::TransportConfig::default();
+        transport_config.keep_alive_interval(self.keep_alive_interval);
+        transport_config
+    }
+
+    fn server_config(&self) -> io::Result<quinn::ServerConfig> {
+        let cert = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()])
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let cert_der = cert
+            .serialize_der()
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let key_der = cert.serialize_private_key_der();
+
+        let mut crypto = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![rustls::Certificate(cert_der)],
+                rustls::PrivateKey(key_der),
+            )
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        crypto.alpn_protocols = vec![ALPN_DIEMNET.to_vec()];
+
+        let mut server_config = quinn::ServerConfig::with_crypto(Arc::new(crypto));
+        server_config.transport = Arc::new(self.transport_config());
+        Ok(server_config)
+    }
+
+    fn client_config(&self) -> quinn::ClientConfig {
+        let mut crypto = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(Arc::new(SkipServerVerification))
+            .with_no_client_auth();
+        crypto.alpn_protocols = vec![ALPN_DIEMNET.to_vec()];
+
+        let mut client_config = quinn::ClientConfig::new(Arc::new(crypto));
+        client_config.transport = Arc::new(self.transport_config());
+        client_config
+    }
+}
+
+impl Transport for QuicTransport {
+    type Output = QuicSocket;
+    type Error = io::Error;
+    type Listener = QuicListenerStream;
+    type Inbound = BoxFuture<'static, io::Result<QuicSocket>>;
+    type Outbound = BoxFuture<'static, io::Result<QuicSocket>>;
+
+    fn listen_on(
+        &self,
+        addr: NetworkAddress,
+    ) -> Result<(Self::Listener, NetworkAddress), Self::Error> {
+        let ((ipaddr, port), addr_suffix) =
+            parse_ip_udp_quic(addr.as_slice()).ok_or_else(|| invalid_addr_error(&addr))?;
+        if !addr_suffix.is_empty() {
+            return Err(invalid_addr_error(&addr));
+        }
+
+        let (endpoint, incoming) =
+            Endpoint::server(self.server_config()?, SocketAddr::new(ipaddr, port))?;
+        let listen_addr = quic_addr(endpoint.local_addr()?);
+
+        Ok((QuicListenerStream { endpoint, incoming }, listen_addr))
+    }
+
+    fn dial(&self, _peer_id: PeerId, addr: NetworkAddress) -> Result<Self::Outbound, Self::Error> {
+        // ensure addr is well formed to save some work before potentially
+        // spawning a dial task that will fail anyway.
+        let protos = addr.as_slice();
+        parse_ip_udp_quic(protos)
+            .map(|_| ())
+            .or_else(|| parse_dns_udp_quic(protos).map(|_| ()))
+            .ok_or_else(|| invalid_addr_error(&addr))?;
+
+        Ok(resolve_and_connect(self.client_config(), addr).boxed())
+    }
+}
+
+/// Returns true if `addr` starts with `"/<ip or dns>/<addr>/udp/<port>/quic"`
+pub fn is_quic_addr(addr: &NetworkAddress) -> bool {
+    let protos = addr.as_slice();
+    parse_ip_udp_quic(protos).is_some() || parse_dns_udp_quic(protos).is_some()
+}
+
+fn quic_addr(sockaddr: SocketAddr) -> NetworkAddress {
+    NetworkAddress::from(Protocol::from(sockaddr.ip()))
+        .push(Protocol::Udp(sockaddr.port()))
+        .push(Protocol::Quic)
+}
+
+async fn resolve_and_connect(
+    client_config: quinn::ClientConfig,
+    addr: NetworkAddress,
+) -> io::Result<QuicSocket> {
+    let protos = addr.as_slice();
+
+    if let Some(((ipaddr, port), _addr_suffix)) = parse_ip_udp_quic(protos) {
+        connect(client_config, SocketAddr::new(ipaddr, port)).await
+    } else if let Some(((ip_filter, dns_name, port), _addr_suffix)) = parse_dns_udp_quic(protos) {
+        // resolve dns name and filter
+        let socketaddr_iter = resolve_with_filter(ip_filter, dns_name.as_ref(), port).await?;
+        let mut last_err = None;
+
+        // try to connect until the first succeeds
+        for socketaddr in socketaddr_iter {
+            match connect(client_config.clone(), socketaddr).await {
+                Ok(socket) => return Ok(socket),
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "could not resolve dns name to any address: name: {}, ip filter: {:?}",
+                    dns_name.as_ref(),
+                    ip_filter,
+                ),
+            )
+        }))
+    } else {
+        Err(invalid_addr_error(&addr))
+    }
+}
+
+async fn connect(
+    client_config: quinn::ClientConfig,
+    socketaddr: SocketAddr,
+) -> io::Result<QuicSocket> {
+    let bind_addr = match socketaddr.ip() {
+        IpAddr::V4(_) => SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0),
+        IpAddr::V6(_) => SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0),
+    };
+    let endpoint = Endpoint::client(bind_addr)?;
+    let NewConnection { connection, .. } = endpoint
+        .connect_with(client_config, socketaddr, SERVER_NAME)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+        .await?;
+    let (send, recv) = connection.open_bi().await?;
+    Ok(QuicSocket::new(endpoint, connection, send, recv))
+}
+
+/// Accepts the stream of an inbound connection, which the dialer opens with its first write
+async fn accept(connecting: quinn::Connecting, endpoint: Endpoint) -> io::Result<QuicSocket> {
+    let NewConnection {
+        connection,
+        mut bi_streams,
+        ..
+    } = connecting.await?;
+    let (send, recv) = bi_streams.next().await.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "QUIC connection closed before opening a stream",
+        )
+    })??;
+    Ok(QuicSocket::new(endpoint, connection, send, recv))
+}
+
+fn invalid_addr_error(addr: &NetworkAddress) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Invalid NetworkAddress: '{}'", addr),
+    )
+}
+
+#[must_use = "streams do nothing unless polled"]
+pub struct QuicListenerStream {
+    endpoint: Endpoint,
+    incoming: Incoming,
+}
+
+impl Stream for QuicListenerStream {
+    type Item = io::Result<(BoxFuture<'static, io::Result<QuicSocket>>, NetworkAddress)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        match self.incoming.poll_next_unpin(context) {
+            Poll::Ready(Some(connecting)) => {
+                let dialer_addr = quic_addr(connecting.remote_address());
+                let inbound = accept(connecting, self.endpoint.clone()).boxed();
+                Poll::Ready(Some(Ok((inbound, dialer_addr))))
+            }
+            // the endpoint is closed
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Doesn't verify the certificate of the server, see the module documentation
+struct SkipServerVerification;
+
+impl rustls::client::ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
+
+/// The bidirectional stream of a QUIC connection
+///
+/// The connection and its endpoint are kept open as long as the stream.
+#[derive(Debug)]
+pub struct QuicSocket {
+    inner: Compat<QuicStream>,
+}
+
+impl QuicSocket {
+    fn new(
+        endpoint: Endpoint,
+        connection: quinn::Connection,
+        send: quinn::SendStream,
+        recv: quinn::RecvStream,
+    ) -> Self {
+        use tokio_util::compat::TokioAsyncReadCompatExt;
+
+        Self {
+            inner: QuicStream {
+                _endpoint: endpoint,
+                _connection: connection,
+                send,
+                recv,
+            }
+            .compat(),
+        }
+    }
+}
+
+impl AsyncRead for QuicSocket {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_read(context, buf)
+    }
+}
+
+impl AsyncWrite for QuicSocket {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(context, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(context)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(context)
+    }
+}
+
+#[derive(Debug)]
+struct QuicStream {
+    _endpoint: Endpoint,
+    _connection: quinn::Connection,
+    send: quinn::SendStream,
+    recv: quinn::RecvStream,
+}
+
+impl tokio::io::AsyncRead for QuicStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.recv).poll_read(context, buf)
+    }
+}
+
+impl tokio::io::AsyncWrite for QuicStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.send).poll_write(context, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_flush(context)
+    }
+
+    /// Finishes the stream, like closing the write half of a TCP connection
+    fn poll_shutdown(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_shutdown(context)
+    }
+}
+
+/// Listens on a QUIC address. Dials QUIC addresses over QUIC, and the other addresses over TCP,
+/// so a node listening on QUIC still reaches the peers only listening on TCP.
+#[derive(Debug, Clone, Default)]
+pub struct QuicOrTcpTransport {
+    pub quic: QuicTransport,
+    pub tcp: TcpTransport,
+}
+
+impl Transport for QuicOrTcpTransport {
+    type Output = Either<QuicSocket, TcpSocket>;
+    type Error = io::Error;
+    type Listener = BoxStream<'static, io::Result<(Self::Inbound, NetworkAddress)>>;
+    type Inbound = BoxFuture<'static, io::Result<Self::Output>>;
+    type Outbound = BoxFuture<'static, io::Result<Self::Output>>;
+
+    fn listen_on(
+        &self,
+        addr: NetworkAddress,
+    ) -> Result<(Self::Listener, NetworkAddress), Self::Error> {
+        let (listener, listen_addr) = self.quic.listen_on(addr)?;
+        let listener = listener
+            .map_ok(|(inbound, dialer_addr)| (inbound.map_ok(Either::Left).boxed(), dialer_addr))
+            .boxed();
+        Ok((listener, listen_addr))
+    }
+
+    fn dial(&self, peer_id: PeerId, addr: NetworkAddress) -> Result<Self::Outbound, Self::Error> {
+        if is_quic_addr(&addr) {
+            Ok(self.quic.dial(peer_id, addr)?.map_ok(Either::Left).boxed())
+        } else {
+            Ok(self.tcp.dial(peer_id, addr)?.map_ok(Either::Right).boxed())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transport::{ConnectionOrigin, TransportExt};
+    use futures::{
+        future::join,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    #[tokio::test]
+    async fn simple_listen_and_dial() -> Result<(), ::std::io::Error> {
+        let t = QuicTransport::default().and_then(|mut out, _addr, origin| async move {
+            match origin {
+                ConnectionOrigin::Inbound => {
+                    let mut buf = [0; 5];
+                    out.read_exact(&mut buf).await?;
+                    assert_eq!(&buf, b"Earth");
+                    out.write_all(b"Air").await?;
+                    out.flush().await?;
+                }
+                ConnectionOrigin::Outbound => {
+                    // the dialer writes first, which opens the stream
+                    out.write_all(b"Earth").await?;
+                    out.flush().await?;
+                    let mut buf = [0; 3];
+                    out.read_exact(&mut buf).await?;
+                    assert_eq!(&buf, b"Air");
+                }
+            }
+            Ok(())
+        });
+
+        let (listener, addr) = t.listen_on("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())?;
+        assert!(is_quic_addr(&addr));
+        let peer_id = PeerId::random();
+        let dial = t.dial(peer_id, addr)?;
+        let listener = listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming.map(Result::unwrap)
+        });
+
+        let (outgoing, _incoming) = join(dial, listener).await;
+        assert!(outgoing.is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_multiaddrs() {
+        let t = QuicTransport::default();
+
+        let result = t.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap());
+        assert!(result.is_err());
+
+        let peer_id = PeerId::random();
+        let result = t.dial(peer_id, "/ip4/127.0.0.1/udp/22".parse().unwrap());
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,322 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! # A client of SOCKS5 proxies for the outbound connections of the TCP transport
+//! https://datatracker.ietf.org/doc/html/rfc1928
+//!
+//! ## Limitations
+//! - Only supports the CONNECT command
+//! - Only supports no authentication, and username/password authentication (RFC 1929)
+//! - DNS names are resolved by the proxy, so the IP filter of `/dns4` and `/dns6` addresses is not
+//!   applied
+
+use diem_types::network_address::{parse_dns_tcp, parse_ip_tcp, NetworkAddress};
+use std::{fmt, io, net::IpAddr};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const VERSION: u8 = 0x05;
+
+// Authentication methods
+const NO_AUTHENTICATION: u8 = 0x00;
+const USERNAME_PASSWORD: u8 = 0x02;
+const NO_ACCEPTABLE_METHODS: u8 = 0xFF;
+/// Version of the username/password authentication subnegotiation
+const USERNAME_PASSWORD_VERSION: u8 = 0x01;
+
+// Commands
+const CONNECT: u8 = 0x01;
+
+// Address types
+const IPV4: u8 = 0x01;
+const DOMAIN_NAME: u8 = 0x03;
+const IPV6: u8 = 0x04;
+
+const SUCCEEDED: u8 = 0x00;
+
+/// A SOCKS5 proxy, and the credentials to authenticate with it
+#[derive(Clone, Eq, PartialEq)]
+pub struct Socks5Proxy {
+    /// `<host>:<port>` of the proxy
+    pub address: String,
+    pub credentials: Option<Socks5Credentials>,
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Socks5Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+// Keep the password out of the logs
+impl fmt::Debug for Socks5Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Socks5Proxy")
+            .field("address", &self.address)
+            .field(
+                "username",
+                &self.credentials.as_ref().map(|creds| &creds.username),
+            )
+            .finish()
+    }
+}
+
+/// Asks the proxy behind `stream` to connect to `addr`. Once this returns, `stream` carries the
+/// traffic of the connection.
+pub async fn connect<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    proxy: &Socks5Proxy,
+    addr: &NetworkAddress,
+) -> io::Result<()> {
+    let destination = destination(addr)?;
+    authenticate(stream, proxy).await?;
+
+    let mut request = vec![VERSION, CONNECT, 0x00];
+    request.extend_from_slice(&destination);
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    check_version(reply[0])?;
+    if reply[1] != SUCCEEDED {
+        return Err(socks5_error(format!(
+            "proxy failed to connect to {}: {}",
+            addr,
+            reply_error(reply[1])
+        )));
+    }
+    // The address the proxy bound for the connection is of no use
+    let bound_addr_len = match reply[3] {
+        IPV4 => 4,
+        IPV6 => 16,
+        DOMAIN_NAME => stream.read_u8().await? as usize,
+        address_type => {
+            return Err(socks5_error(format!(
+                "unknown address type: {}",
+                address_type
+            )))
+        }
+    };
+    let mut bound_addr = vec![0u8; bound_addr_len + 2];
+    stream.read_exact(&mut bound_addr).await?;
+    Ok(())
+}
+
+async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    proxy: &Socks5Proxy,
+) -> io::Result<()> {
+    let method = if proxy.credentials.is_some() {
+        USERNAME_PASSWORD
+    } else {
+        NO_AUTHENTICATION
+    };
+    stream.write_all(&[VERSION, 1, method]).await?;
+
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    check_version(reply[0])?;
+    match (reply[1], &proxy.credentials) {
+        (NO_AUTHENTICATION, None) => Ok(()),
+        (USERNAME_PASSWORD, Some(credentials)) => {
+            let mut request = vec![USERNAME_PASSWORD_VERSION];
+            push_with_len(&mut request, credentials.username.as_bytes(), "username")?;
+            push_with_len(&mut request, credentials.password.as_bytes(), "password")?;
+            stream.write_all(&request).await?;
+
+            let mut reply = [0u8; 2];
+            stream.read_exact(&mut reply).await?;
+            if reply[1] != SUCCEEDED {
+                return Err(socks5_error("proxy rejected the credentials".to_string()));
+            }
+            Ok(())
+        }
+        (NO_ACCEPTABLE_METHODS, _) => Err(socks5_error(
+            "proxy accepts none of the offered authentication methods".to_string(),
+        )),
+        (method, _) => Err(socks5_error(format!(
+            "proxy picked an authentication method which wasn't offered: {}",
+            method
+        ))),
+    }
+}
+
+/// The address and port of `addr`, as they are encoded in a request
+fn destination(addr: &NetworkAddress) -> io::Result<Vec<u8>> {
+    let protos = addr.as_slice();
+    let mut destination = Vec::new();
+    let port = if let Some(((ipaddr, port), _addr_suffix)) = parse_ip_tcp(protos) {
+        match ipaddr {
+            IpAddr::V4(ip) => {
+                destination.push(IPV4);
+                destination.extend_from_slice(&ip.octets());
+            }
+            IpAddr::V6(ip) => {
+                destination.push(IPV6);
+                destination.extend_from_slice(&ip.octets());
+            }
+        }
+        port
+    } else if let Some(((_ip_filter, dns_name, port), _addr_suffix)) = parse_dns_tcp(protos) {
+        destination.push(DOMAIN_NAME);
+        push_with_len(&mut destination, dns_name.as_ref().as_bytes(), "DNS name")?;
+        port
+    } else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid NetworkAddress: '{}'", addr),
+        ));
+    };
+    destination.extend_from_slice(&port.to_be_bytes());
+    Ok(destination)
+}
+
+/// Pushes `field` prefixed by its length, which the protocol limits to 255 bytes
+fn push_with_len(buf: &mut Vec<u8>, field: &[u8], name: &str) -> io::Result<()> {
+    if field.len() > u8::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("SOCKS5 {} is longer than 255 bytes", name),
+        ));
+    }
+    buf.push(field.len() as u8);
+    buf.extend_from_slice(field);
+    Ok(())
+}
+
+fn check_version(version: u8) -> io::Result<()> {
+    if version != VERSION {
+        return Err(socks5_error(format!(
+            "unsupported SOCKS version: {}",
+            version
+        )));
+    }
+    Ok(())
+}
+
+fn reply_error(reply: u8) -> &'static str {
+    match reply {
+        0x01 => "general SOCKS server failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+fn socks5_error(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("SOCKS5 {}", message))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio::io::duplex;
+
+    fn proxy(credentials: bool) -> Socks5Proxy {
+        Socks5Proxy {
+            address: "127.0.0.1:1080".to_string(),
+            credentials: if credentials {
+                Some(Socks5Credentials {
+                    username: "user".to_string(),
+                    password: "pass".to_string(),
+                })
+            } else {
+                None
+            },
+        }
+    }
+
+    async fn expect_read<S: AsyncRead + Unpin>(stream: &mut S, expected: &[u8]) {
+        let mut buf = vec![0u8; expected.len()];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, expected);
+    }
+
+    #[tokio::test]
+    async fn test_connect_dns_with_credentials() {
+        let (mut client, mut server) = duplex(1024);
+        let addr: NetworkAddress = "/dns/example.com/tcp/6180".parse().unwrap();
+        let server = async move {
+            expect_read(&mut server, &[VERSION, 1, USERNAME_PASSWORD]).await;
+            server
+                .write_all(&[VERSION, USERNAME_PASSWORD])
+                .await
+                .unwrap();
+            expect_read(&mut server, b"\x01\x04user\x04pass").await;
+            server
+                .write_all(&[USERNAME_PASSWORD_VERSION, SUCCEEDED])
+                .await
+                .unwrap();
+            expect_read(&mut server, b"\x05\x01\x00\x03\x0bexample.com\x18\x24").await;
+            server
+                .write_all(&[VERSION, SUCCEEDED, 0x00, IPV4, 10, 0, 0, 1, 0x04, 0x38])
+                .await
+                .unwrap();
+            server.write_all(b"hello").await.unwrap();
+        };
+        let client = async move {
+            connect(&mut client, &proxy(true), &addr).await.unwrap();
+            // The stream is left at the start of the traffic of the connection
+            expect_read(&mut client, b"hello").await;
+        };
+        futures::future::join(server, client).await;
+    }
+
+    #[tokio::test]
+    async fn test_connect_ip_without_credentials() {
+        let (mut client, mut server) = duplex(1024);
+        let addr: NetworkAddress = "/ip4/1.2.3.4/tcp/6180".parse().unwrap();
+        let server = async move {
+            expect_read(&mut server, &[VERSION, 1, NO_AUTHENTICATION]).await;
+            server
+                .write_all(&[VERSION, NO_AUTHENTICATION])
+                .await
+                .unwrap();
+            expect_read(
+                &mut server,
+                &[VERSION, CONNECT, 0x00, IPV4, 1, 2, 3, 4, 0x18, 0x24],
+            )
+            .await;
+            // Connection refused
+            server
+                .write_all(&[VERSION, 0x05, 0x00, IPV4, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+        };
+        let client = async move {
+            let err = connect(&mut client, &proxy(false), &addr)
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("connection refused"), "{}", err);
+        };
+        futures::future::join(server, client).await;
+    }
+
+    #[tokio::test]
+    async fn test_rejected_credentials() {
+        let (mut client, mut server) = duplex(1024);
+        let addr: NetworkAddress = "/ip4/1.2.3.4/tcp/6180".parse().unwrap();
+        let server = async move {
+            expect_read(&mut server, &[VERSION, 1, USERNAME_PASSWORD]).await;
+            server
+                .write_all(&[VERSION, NO_ACCEPTABLE_METHODS])
+                .await
+                .unwrap();
+        };
+        let client = async move {
+            connect(&mut client, &proxy(true), &addr).await.unwrap_err();
+        };
+        futures::future::join(server, client).await;
+    }
+
+    #[test]
+    fn test_debug_hides_password() {
+        let debug = format!("{:?}", proxy(true));
+        assert!(debug.contains("user"));
+        assert!(!debug.contains("pass"), "{}", debug);
+    }
+}
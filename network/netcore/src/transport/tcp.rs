@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! TCP Transport
-use crate::transport::Transport;
+use crate::transport::{
+    socks5::{self, Socks5Proxy},
+    Transport,
+};
 use diem_types::{
     network_address::{parse_dns_tcp, parse_ip_tcp, parse_tcp, IpFilter, NetworkAddress},
     PeerId,
@@ -36,6 +39,9 @@ pub struct TcpTransport {
     pub ttl: Option<u32>,
     /// `TCP_NODELAY` to set for opened sockets, or `None` to keep default.
     pub nodelay: Option<bool>,
+    /// SOCKS5 proxy to dial through, or `None` to dial directly. Takes precedence over the HTTP
+    /// proxy of the environment.
+    pub socks5_proxy: Option<Socks5Proxy>,
 }
 
 impl TcpTransport {
@@ -94,6 +100,13 @@ impl Transport for TcpTransport {
             .or_else(|| parse_dns_tcp(protos).map(|_| ()))
             .ok_or_else(|| invalid_addr_error(&addr))?;
 
+        if let Some(socks5_proxy) = self.socks5_proxy.clone() {
+            return Ok(TcpOutbound {
+                inner: Box::pin(connect_via_socks5(socks5_proxy, addr)),
+                config: self.clone(),
+            });
+        }
+
         let proxy = Proxy::new();
 
         let proxy_addr = {
@@ -134,7 +147,7 @@ impl Transport for TcpTransport {
 }
 
 /// Try to lookup the dns name, then filter addrs according to the `IpFilter`.
-async fn resolve_with_filter(
+pub(crate) async fn resolve_with_filter(
     ip_filter: IpFilter,
     dns_name: &str,
     port: u16,
@@ -218,6 +231,12 @@ async fn connect_via_proxy(proxy_addr: String, addr: NetworkAddress) -> io::Resu
     }
 }
 
+async fn connect_via_socks5(proxy: Socks5Proxy, addr: NetworkAddress) -> io::Result<TcpStream> {
+    let mut stream = TcpStream::connect(&proxy.address).await?;
+    socks5::connect(&mut stream, &proxy, &addr).await?;
+    Ok(stream)
+}
+
 fn invalid_addr_error(addr: &NetworkAddress) -> io::Error {
     io::Error::new(
         io::ErrorKind::InvalidInput,
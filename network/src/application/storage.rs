@@ -10,6 +10,7 @@ use std::{
     collections::{hash_map::Entry, HashMap},
     fmt::Debug,
     hash::Hash,
+    time::Duration,
 };
 
 pub type PeerMetadataStorage = LockingHashMap<PeerId, PeerInfo>;
@@ -89,10 +90,23 @@ impl PeerMetadataStorage {
         self.map
             .write()
             .entry(peer_id)
-            .and_modify(|entry| entry.active_connection = connection_metadata.clone())
+            .and_modify(|entry| {
+                entry.active_connection = connection_metadata.clone();
+                entry.rtt = None;
+            })
             .or_insert_with(|| PeerInfo::new(connection_metadata));
     }
 
+    /// Records the round trip time of a connection, if it's still the active connection of its peer
+    pub fn update_rtt(&self, connection_metadata: &ConnectionMetadata, rtt: Duration) {
+        let peer_id = connection_metadata.remote_peer_id;
+        if let Some(entry) = self.map.write().get_mut(&peer_id) {
+            if entry.active_connection.connection_id == connection_metadata.connection_id {
+                entry.rtt = Some(rtt);
+            }
+        }
+    }
+
     pub fn remove_connection(&self, connection_metadata: &ConnectionMetadata) {
         let peer_id = connection_metadata.remote_peer_id;
         let mut map = self.map.write();
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::transport::ConnectionMetadata;
+use std::time::Duration;
 
 /// Errors related to the peer layer in the `NetworkInterface`
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -14,6 +15,8 @@ pub enum PeerError {
 pub struct PeerInfo {
     pub status: PeerState,
     pub active_connection: ConnectionMetadata,
+    /// Last round trip time of the active connection, if measured by keep-alive pings
+    pub rtt: Option<Duration>,
 }
 
 impl PeerInfo {
@@ -21,6 +24,7 @@ impl PeerInfo {
         PeerInfo {
             status: PeerState::Connected,
             active_connection: connection_metadata,
+            rtt: None,
         }
     }
 }
@@ -122,6 +122,7 @@ pub struct ConnectivityManager<TBackoff> {
 pub enum DiscoverySource {
     OnChainValidatorSet,
     File,
+    Dns,
     Config,
 }
 
@@ -139,6 +140,7 @@ impl fmt::Display for DiscoverySource {
             match self {
                 DiscoverySource::OnChainValidatorSet => "OnChainValidatorSet",
                 DiscoverySource::File => "File",
+                DiscoverySource::Dns => "Dns",
                 DiscoverySource::Config => "Config",
             }
         )
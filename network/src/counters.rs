@@ -11,6 +11,7 @@ use diem_types::PeerId;
 use netcore::transport::ConnectionOrigin;
 use once_cell::sync::Lazy;
 use short_hex_str::AsShortHexStr;
+use std::time::Duration;
 
 // some type labels
 pub const REQUEST_LABEL: &str = "request";
@@ -23,6 +24,12 @@ pub const RECEIVED_LABEL: &str = "received";
 pub const SENT_LABEL: &str = "sent";
 pub const SUCCEEDED_LABEL: &str = "succeeded";
 pub const FAILED_LABEL: &str = "failed";
+pub const QUEUED_LABEL: &str = "queued";
+pub const DROPPED_LABEL: &str = "dropped";
+
+// some compression labels
+pub const RAW_LABEL: &str = "raw";
+pub const COMPRESSED_LABEL: &str = "compressed";
 
 pub static DIEM_CONNECTIONS: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
@@ -63,6 +70,105 @@ pub fn connections_rejected(
     ])
 }
 
+pub static DIEM_NETWORK_PEER_MISBEHAVIORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_peer_misbehaviors",
+        "Number of misbehaviors reported for peers, by kind",
+        &["role_type", "network_id", "peer_id", "misbehavior"]
+    )
+    .unwrap()
+});
+
+pub fn peer_misbehaviors(network_context: &NetworkContext, misbehavior: &str) -> IntCounter {
+    DIEM_NETWORK_PEER_MISBEHAVIORS.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        misbehavior,
+    ])
+}
+
+pub static DIEM_NETWORK_PEER_BANS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_peer_bans",
+        "Number of peers banned for misbehaving",
+        &["role_type", "network_id", "peer_id"]
+    )
+    .unwrap()
+});
+
+pub fn peer_bans(network_context: &NetworkContext) -> IntCounter {
+    DIEM_NETWORK_PEER_BANS.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+    ])
+}
+
+pub static DIEM_CONNECTIONS_EVICTED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_connections_evicted",
+        "Number of inbound connections evicted over the connection limit of their IP",
+        &["role_type", "network_id", "peer_id"]
+    )
+    .unwrap()
+});
+
+pub fn connections_evicted(network_context: &NetworkContext) -> IntCounter {
+    DIEM_CONNECTIONS_EVICTED.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+    ])
+}
+
+pub static DIEM_NETWORK_NOISE_HANDSHAKES_REJECTED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_noise_handshakes_rejected",
+        "Number of inbound noise handshakes rejected, by reason",
+        &["role_type", "network_id", "peer_id", "reason"]
+    )
+    .unwrap()
+});
+
+pub fn noise_handshakes_rejected(network_context: &NetworkContext, reason: &str) -> IntCounter {
+    DIEM_NETWORK_NOISE_HANDSHAKES_REJECTED.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        reason,
+    ])
+}
+
+pub static DIEM_NETWORK_RATE_LIMITED_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_rate_limited_messages",
+        "Number of outbound messages over the rate limit of their protocol, by action taken",
+        &[
+            "role_type",
+            "network_id",
+            "peer_id",
+            "protocol_id",
+            "action"
+        ]
+    )
+    .unwrap()
+});
+
+pub fn rate_limited_messages(
+    network_context: &NetworkContext,
+    protocol_id: ProtocolId,
+    action: &str,
+) -> IntCounter {
+    DIEM_NETWORK_RATE_LIMITED_MESSAGES.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        protocol_id.as_str(),
+        action,
+    ])
+}
+
 pub static DIEM_NETWORK_PEER_CONNECTED: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
         "diem_network_peer_connected",
@@ -85,6 +191,62 @@ pub fn peer_connected(network_context: &NetworkContext, remote_peer_id: &PeerId,
     }
 }
 
+pub static DIEM_NETWORK_PEER_RTT: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "diem_network_peer_rtt_seconds",
+        "Round trip time of the connections measured by keep-alive pings, in seconds",
+        &["role_type", "network_id", "peer_id"]
+    )
+    .unwrap()
+});
+
+pub static DIEM_NETWORK_PEER_RTT_MS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "diem_network_peer_rtt_ms",
+        "Last round trip time of the connection with a particular peer, in milliseconds",
+        &["role_type", "network_id", "peer_id", "remote_peer_id"]
+    )
+    .unwrap()
+});
+
+pub fn peer_rtt(network_context: &NetworkContext, remote_peer_id: &PeerId, rtt: Duration) {
+    DIEM_NETWORK_PEER_RTT
+        .with_label_values(&[
+            network_context.role().as_str(),
+            network_context.network_id().as_str(),
+            network_context.peer_id().short_str().as_str(),
+        ])
+        .observe(rtt.as_secs_f64());
+    // Only the validator network has a bounded set of peers
+    if network_context.network_id().is_validator_network() {
+        DIEM_NETWORK_PEER_RTT_MS
+            .with_label_values(&[
+                network_context.role().as_str(),
+                network_context.network_id().as_str(),
+                network_context.peer_id().short_str().as_str(),
+                remote_peer_id.short_str().as_str(),
+            ])
+            .set(rtt.as_millis() as i64)
+    }
+}
+
+pub static DIEM_NETWORK_KEEP_ALIVE_DISCONNECTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_keep_alive_disconnects",
+        "Number of connections closed after too many unanswered keep-alive pings",
+        &["role_type", "network_id", "peer_id"]
+    )
+    .unwrap()
+});
+
+pub fn keep_alive_disconnects(network_context: &NetworkContext) -> IntCounter {
+    DIEM_NETWORK_KEEP_ALIVE_DISCONNECTS.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+    ])
+}
+
 /// Increments the counter based on `NetworkContext`
 pub fn inc_by_with_context(
     counter: &IntCounterVec,
@@ -474,3 +636,29 @@ pub static NETWORK_RATE_LIMIT_METRICS: Lazy<HistogramVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static DIEM_NETWORK_COMPRESSION_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_compression_bytes",
+        "Number of bytes of outbound messages before (raw) and after compression",
+        &["protocol_id", "state"]
+    )
+    .unwrap()
+});
+
+pub fn compression_bytes(protocol_id: ProtocolId, state: &'static str) -> IntCounter {
+    DIEM_NETWORK_COMPRESSION_BYTES.with_label_values(&[protocol_id.as_str(), state])
+}
+
+pub static DIEM_NETWORK_WIRE_COMPRESSION_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_wire_compression_bytes",
+        "Number of bytes of the network messages compressed on the wire, uncompressed (raw) and compressed",
+        &["direction", "state"]
+    )
+    .unwrap()
+});
+
+pub fn wire_compression_bytes(direction: &'static str, state: &'static str) -> IntCounter {
+    DIEM_NETWORK_WIRE_COMPRESSION_BYTES.with_label_values(&[direction, state])
+}
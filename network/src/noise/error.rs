@@ -4,7 +4,7 @@
 use diem_crypto::noise::NoiseError;
 use diem_types::PeerId;
 use short_hex_str::ShortHexStr;
-use std::io;
+use std::{io, net::IpAddr};
 use thiserror::Error;
 
 /// Different errors than can be raised when negotiating a Noise handshake.
@@ -31,6 +31,9 @@ pub enum NoiseHandshakeError {
     #[error("noise client: error finalizing secure connection: {0}")]
     ClientFinalizeFailed(NoiseError),
 
+    #[error("noise server: {0} is over its handshake rate limit")]
+    HandshakeRateLimited(IpAddr),
+
     #[error("noise server: error reading client handshake init message: {0}")]
     ServerReadFailed(io::Error),
 
@@ -73,6 +76,12 @@ pub enum NoiseHandshakeError {
     )]
     ServerReplayDetected(ShortHexStr, u64),
 
+    #[error(
+        "noise server: client {0}: the anti-replay timestamp is out of the window \
+         around our clock: {1}"
+    )]
+    StaleAntiReplayTimestamp(ShortHexStr, u64),
+
     #[error("noise server: client {0}: error building handshake response message: {1}")]
     BuildServerHandshakeMessageFailed(ShortHexStr, NoiseError),
 
@@ -87,4 +96,23 @@ impl NoiseHandshakeError {
         use NoiseHandshakeError::*;
         matches!(self, ServerReplayDetected(_, _))
     }
+
+    /// The reason the server rejected the handshake, as a metrics label, or None if the handshake
+    /// failed for another reason, e.g. the client closed the connection.
+    pub fn rejection_reason(&self) -> Option<&'static str> {
+        use NoiseHandshakeError::*;
+        match self {
+            HandshakeRateLimited(_) => Some("rate_limited"),
+            InvalidClientPeerId(_) | ServerParseClient(_, _) => Some("malformed"),
+            SelfDialDetected => Some("self_dial"),
+            ClientExpectingDifferentPubkey(_, _) => Some("unexpected_pubkey"),
+            UnauthenticatedClientPubkey(_, _)
+            | UnauthenticatedClient(_, _)
+            | ClientPeerIdMismatch(_, _, _) => Some("unauthenticated"),
+            MissingAntiReplayTimestamp(_) => Some("missing_timestamp"),
+            ServerReplayDetected(_, _) => Some("replay"),
+            StaleAntiReplayTimestamp(_, _) => Some("stale_timestamp"),
+            _ => None,
+        }
+    }
 }
@@ -12,17 +12,25 @@
 
 use crate::noise::{error::NoiseHandshakeError, stream::NoiseStream};
 use diem_config::{
-    config::{Peer, PeerRole, PeerSet},
+    config::{HandshakeLimitConfig, Peer, PeerRole, PeerSet},
     network_id::NetworkContext,
 };
 use diem_crypto::{noise, x25519};
-use diem_infallible::{duration_since_epoch, RwLock};
+use diem_infallible::{duration_since_epoch, Mutex, RwLock};
 use diem_logger::trace;
+use diem_rate_limiter::rate_limit::Bucket;
 use diem_types::PeerId;
 use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use netcore::transport::ConnectionOrigin;
 use short_hex_str::{AsShortHexStr, ShortHexStr};
-use std::{collections::HashMap, convert::TryFrom as _, fmt::Debug, sync::Arc};
+use std::{
+    collections::HashMap,
+    convert::TryFrom as _,
+    fmt::Debug,
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 /// In a mutually authenticated network, a client message is accompanied with a timestamp.
 /// This is in order to prevent replay attacks, where the attacker does not know the client's static key,
@@ -70,6 +78,78 @@ impl AntiReplayTimestamps {
     }
 }
 
+/// Limits the handshakes each IP may start, as the responder of a handshake runs its Diffie-Hellman
+/// operations before it can authenticate the client or detect a replay. Every IP has a token bucket
+/// of handshakes, which is dropped once it's full again, so the buckets of the IPs which stopped
+/// connecting don't pile up.
+pub struct HandshakeRateLimiter {
+    network_context: Arc<NetworkContext>,
+    bucket_size: usize,
+    bucket_rate: usize,
+    buckets: Mutex<HandshakeBuckets>,
+}
+
+struct HandshakeBuckets {
+    buckets: HashMap<IpAddr, Bucket>,
+    last_pruning: Instant,
+}
+
+impl HandshakeRateLimiter {
+    /// How often the full buckets are dropped
+    const PRUNING_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new(
+        network_context: Arc<NetworkContext>,
+        bucket_size: usize,
+        bucket_rate: usize,
+    ) -> Self {
+        Self {
+            network_context,
+            // a bucket can't be smaller than its fill rate
+            bucket_size: std::cmp::max(bucket_size, bucket_rate),
+            bucket_rate,
+            buckets: Mutex::new(HandshakeBuckets {
+                buckets: HashMap::new(),
+                last_pruning: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes a handshake from the bucket of the IP, returns false if the bucket is empty
+    pub fn allow(&self, remote_ip: IpAddr) -> bool {
+        let mut buckets = self.buckets.lock();
+        let now = Instant::now();
+        if now.duration_since(buckets.last_pruning) >= Self::PRUNING_INTERVAL {
+            let bucket_size = self.bucket_size;
+            buckets.buckets.retain(|_, bucket| {
+                bucket
+                    .time_of_tokens_needed(bucket_size)
+                    .map_or(false, |full| full > now)
+            });
+            buckets.last_pruning = now;
+        }
+
+        let (network_context, bucket_size, bucket_rate) =
+            (&self.network_context, self.bucket_size, self.bucket_rate);
+        buckets
+            .buckets
+            .entry(remote_ip)
+            .or_insert_with(|| {
+                Bucket::new(
+                    "noise_handshake".to_string(),
+                    network_context.to_string(),
+                    remote_ip.to_string(),
+                    bucket_size,
+                    bucket_size,
+                    bucket_rate,
+                    None,
+                )
+            })
+            .acquire_all_tokens(1)
+            .is_ok()
+    }
+}
+
 /// Noise handshake authentication mode.
 pub enum HandshakeAuthMode {
     /// In `Mutual` mode, both sides will authenticate each other with their
@@ -122,6 +202,68 @@ impl HandshakeAuthMode {
     }
 }
 
+/// The static key of a peer, shared by the noise upgraders of its network. It can be rotated while
+/// the network runs, e.g., once the new key is registered on-chain: the established connections are
+/// kept, and the inbound clients that still expect the previous key are served with it until the
+/// next rotation.
+#[derive(Clone)]
+pub struct IdentityKey(Arc<RwLock<IdentityKeys>>);
+
+struct IdentityKeys {
+    current: Arc<noise::NoiseConfig>,
+    previous: Option<Arc<noise::NoiseConfig>>,
+}
+
+impl IdentityKey {
+    pub fn new(key: x25519::PrivateKey) -> Self {
+        IdentityKey(Arc::new(RwLock::new(IdentityKeys {
+            current: Arc::new(noise::NoiseConfig::new(key)),
+            previous: None,
+        })))
+    }
+
+    pub fn public_key(&self) -> x25519::PublicKey {
+        self.0.read().current.public_key()
+    }
+
+    /// Rotates to the key, the current key becoming the previous one. Returns false if the key is
+    /// already the current key.
+    pub fn rotate(&self, key: x25519::PrivateKey) -> bool {
+        let mut keys = self.0.write();
+        if keys.current.public_key() == key.public_key() {
+            return false;
+        }
+        let previous = std::mem::replace(&mut keys.current, Arc::new(noise::NoiseConfig::new(key)));
+        keys.previous = Some(previous);
+        true
+    }
+
+    fn current(&self) -> Arc<noise::NoiseConfig> {
+        self.0.read().current.clone()
+    }
+
+    /// Returns the current or the previous key, whichever has the public key a client expects
+    fn matching(&self, public_key: &[u8]) -> Option<Arc<noise::NoiseConfig>> {
+        let keys = self.0.read();
+        std::iter::once(&keys.current)
+            .chain(&keys.previous)
+            .find(|noise_config| noise_config.public_key().as_slice() == public_key)
+            .cloned()
+    }
+}
+
+impl From<x25519::PrivateKey> for IdentityKey {
+    fn from(key: x25519::PrivateKey) -> Self {
+        IdentityKey::new(key)
+    }
+}
+
+impl Debug for IdentityKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "IdentityKey({})", self.public_key())
+    }
+}
+
 // Noise Upgrader
 // --------------
 // Noise by default is not aware of the above or lower protocol layers,
@@ -136,10 +278,14 @@ impl HandshakeAuthMode {
 pub struct NoiseUpgrader {
     /// The validator's network context
     pub network_context: Arc<NetworkContext>,
-    /// Config for executing Noise handshakes. Includes our static private key.
-    noise_config: noise::NoiseConfig,
+    /// Our static key, with which the Noise handshakes are executed.
+    identity_key: IdentityKey,
     /// Handshake authentication can be either mutual or server-only authentication.
     auth_mode: HandshakeAuthMode,
+    /// Limits the inbound handshakes of every IP, if set.
+    handshake_rate_limiter: Option<HandshakeRateLimiter>,
+    /// Maximum difference between the anti-replay timestamp of a client and our clock, if set.
+    anti_replay_window_ms: Option<u64>,
 }
 
 impl NoiseUpgrader {
@@ -148,14 +294,46 @@ impl NoiseUpgrader {
         network_context: Arc<NetworkContext>,
         key: x25519::PrivateKey,
         auth_mode: HandshakeAuthMode,
+    ) -> Self {
+        Self::with_identity_key(network_context, IdentityKey::new(key), auth_mode)
+    }
+
+    /// Create a new NoiseConfig with the provided identity key, which may be rotated later, and
+    /// authentication mode.
+    pub fn with_identity_key(
+        network_context: Arc<NetworkContext>,
+        identity_key: IdentityKey,
+        auth_mode: HandshakeAuthMode,
     ) -> Self {
         Self {
             network_context,
-            noise_config: noise::NoiseConfig::new(key),
+            identity_key,
             auth_mode,
+            handshake_rate_limiter: None,
+            anti_replay_window_ms: None,
         }
     }
 
+    /// Rate limits the inbound handshakes of every IP and checks the anti-replay timestamps of the
+    /// clients against our clock, in any authentication mode.
+    pub fn with_handshake_limits(mut self, config: &HandshakeLimitConfig) -> Self {
+        self.handshake_rate_limiter = Some(HandshakeRateLimiter::new(
+            self.network_context.clone(),
+            config.ip_handshake_bucket_size,
+            config.ip_handshake_bucket_rate,
+        ));
+        self.anti_replay_window_ms = Some(config.anti_replay_window_ms);
+        self
+    }
+
+    /// Returns false if the IP is over its handshake rate limit, in which case the inbound
+    /// connection should be dropped before its handshake.
+    pub fn allow_inbound_handshake(&self, remote_ip: IpAddr) -> bool {
+        self.handshake_rate_limiter
+            .as_ref()
+            .map_or(true, |rate_limiter| rate_limiter.allow(remote_ip))
+    }
+
     /// Perform a protocol upgrade on an underlying connection. In addition perform the noise IK
     /// handshake to establish a noise stream and exchange static public keys. Upon success,
     /// returns the static public key of the remote as well as a NoiseStream.
@@ -232,10 +410,12 @@ impl NoiseUpgrader {
         // craft 8-byte payload as current timestamp (in milliseconds)
         let payload = time_provider();
 
+        // the whole handshake uses the current key, even if it's rotated meanwhile
+        let noise_config = self.identity_key.current();
+
         // craft first handshake message  (-> e, es, s, ss)
         let mut rng = rand::rngs::OsRng;
-        let initiator_state = self
-            .noise_config
+        let initiator_state = noise_config
             .initiate_connection(
                 &mut rng,
                 prologue_msg,
@@ -278,8 +458,7 @@ impl NoiseUpgrader {
             self.network_context,
             remote_public_key,
         );
-        let (_, session) = self
-            .noise_config
+        let (_, session) = noise_config
             .finalize_connection(initiator_state, &server_response)
             .map_err(NoiseHandshakeError::ClientFinalizeFailed)?;
 
@@ -331,18 +510,21 @@ impl NoiseUpgrader {
             return Err(NoiseHandshakeError::SelfDialDetected);
         }
 
-        // verify that this is indeed our public key
-        if self_expected_public_key != self.noise_config.public_key().as_slice() {
-            return Err(NoiseHandshakeError::ClientExpectingDifferentPubkey(
-                remote_peer_short,
-                hex::encode(self_expected_public_key),
-            ));
-        }
+        // verify that this is indeed our public key, or our previous key if the client doesn't
+        // know of its rotation yet
+        let noise_config = self
+            .identity_key
+            .matching(self_expected_public_key)
+            .ok_or_else(|| {
+                NoiseHandshakeError::ClientExpectingDifferentPubkey(
+                    remote_peer_short,
+                    hex::encode(self_expected_public_key),
+                )
+            })?;
 
         // parse it
         let (prologue, client_init_message) = client_message.split_at(Self::PROLOGUE_SIZE);
-        let (remote_public_key, handshake_state, payload) = self
-            .noise_config
+        let (remote_public_key, handshake_state, payload) = noise_config
             .parse_client_init_message(prologue, client_init_message)
             .map_err(|err| NoiseHandshakeError::ServerParseClient(remote_peer_short, err))?;
 
@@ -384,10 +566,11 @@ impl NoiseUpgrader {
             }
         }?;
 
-        // if on a mutually authenticated network,
+        // if on a mutually authenticated network, or with an anti-replay window,
         // the payload should contain a u64 client timestamp
-        if let Some(anti_replay_timestamps) = self.auth_mode.anti_replay_timestamps() {
-            // check that the payload received as the client timestamp (in seconds)
+        let anti_replay_timestamps = self.auth_mode.anti_replay_timestamps();
+        if anti_replay_timestamps.is_some() || self.anti_replay_window_ms.is_some() {
+            // check that the payload received as the client timestamp (in milliseconds)
             if payload.len() != AntiReplayTimestamps::TIMESTAMP_SIZE {
                 return Err(NoiseHandshakeError::MissingAntiReplayTimestamp(
                     remote_peer_short,
@@ -398,24 +581,39 @@ impl NoiseUpgrader {
             client_timestamp.copy_from_slice(&payload);
             let client_timestamp = u64::from_le_bytes(client_timestamp);
 
-            // check the timestamp is not a replay
-            let mut anti_replay_timestamps = anti_replay_timestamps.write();
-            if anti_replay_timestamps.is_replay(remote_public_key, client_timestamp) {
-                return Err(NoiseHandshakeError::ServerReplayDetected(
-                    remote_peer_short,
-                    client_timestamp,
-                ));
+            // check the timestamp is close enough to our clock, so that an old message can't be
+            // replayed even if we don't know the last timestamp of the client
+            if let Some(window_ms) = self.anti_replay_window_ms {
+                let now = duration_since_epoch().as_millis() as u64;
+                if client_timestamp.saturating_add(window_ms) < now
+                    || client_timestamp > now.saturating_add(window_ms)
+                {
+                    return Err(NoiseHandshakeError::StaleAntiReplayTimestamp(
+                        remote_peer_short,
+                        client_timestamp,
+                    ));
+                }
             }
 
-            // store the timestamp
-            anti_replay_timestamps.store_timestamp(remote_public_key, client_timestamp);
+            // check the timestamp is not a replay
+            if let Some(anti_replay_timestamps) = anti_replay_timestamps {
+                let mut anti_replay_timestamps = anti_replay_timestamps.write();
+                if anti_replay_timestamps.is_replay(remote_public_key, client_timestamp) {
+                    return Err(NoiseHandshakeError::ServerReplayDetected(
+                        remote_peer_short,
+                        client_timestamp,
+                    ));
+                }
+
+                // store the timestamp
+                anti_replay_timestamps.store_timestamp(remote_public_key, client_timestamp);
+            }
         }
 
         // construct the response
         let mut rng = rand::rngs::OsRng;
         let mut server_response = [0u8; Self::SERVER_MESSAGE_SIZE];
-        let session = self
-            .noise_config
+        let session = noise_config
             .respond_to_client(&mut rng, handshake_state, None, &mut server_response)
             .map_err(|err| {
                 NoiseHandshakeError::BuildServerHandshakeMessageFailed(remote_peer_short, err)
@@ -608,6 +806,60 @@ mod test {
         server_session.unwrap();
     }
 
+    #[test]
+    fn test_timestamp_window() {
+        let ((client, _), (server, server_public_key)) =
+            build_peers(false /* is_mutual_auth */);
+        let server = server.with_handshake_limits(&HandshakeLimitConfig::default());
+        let window_ms = HandshakeLimitConfig::default().anti_replay_window_ms;
+        let now = duration_since_epoch().as_millis() as u64;
+
+        // timestamps too far in the past or in the future are rejected, even though the server
+        // doesn't keep the timestamps of its clients
+        for timestamp in &[1, now - 2 * window_ms, now + 2 * window_ms] {
+            let (dialer_socket, listener_socket) = MemorySocket::new_pair();
+            let (client_session, server_session) = block_on(join(
+                client.upgrade_outbound(
+                    dialer_socket,
+                    server_public_key,
+                    bad_timestamp(*timestamp),
+                ),
+                server.upgrade_inbound(listener_socket),
+            ));
+
+            client_session.unwrap_err();
+            assert!(matches!(
+                server_session.unwrap_err(),
+                NoiseHandshakeError::StaleAntiReplayTimestamp(_, _)
+            ));
+        }
+
+        // a timestamp within the window is accepted
+        let (client_res, server_res) = perform_handshake(&client, &server, server_public_key);
+        client_res.unwrap();
+        server_res.unwrap();
+    }
+
+    #[test]
+    fn test_handshake_rate_limiter() {
+        let ip = "1.2.3.4".parse().unwrap();
+        let other_ip = "5.6.7.8".parse().unwrap();
+        let rate_limiter =
+            HandshakeRateLimiter::new(NetworkContext::mock_with_peer_id(PeerId::random()), 2, 1);
+
+        // an IP may start as many handshakes as its bucket holds, without affecting other IPs
+        assert!(rate_limiter.allow(ip));
+        assert!(rate_limiter.allow(ip));
+        assert!(!rate_limiter.allow(ip));
+        assert!(rate_limiter.allow(other_ip));
+
+        // without limits, every handshake is allowed
+        let (_, (server, _)) = build_peers(false /* is_mutual_auth */);
+        assert!((0..100).all(|_| server.allow_inbound_handshake(ip)));
+        let server = server.with_handshake_limits(&HandshakeLimitConfig::default());
+        assert!(!(0..100).all(|_| server.allow_inbound_handshake(ip)));
+    }
+
     fn test_handshake_success(is_mutual_auth: bool) {
         // perform handshake with two testing peers
         let ((client, client_public_key), (server, server_public_key)) =
@@ -658,13 +910,50 @@ mod test {
             build_peers(true /* is_mutual_auth */);
 
         // swap in a different keypair, so the connection will be unauthenticated
-        client.noise_config = noise::NoiseConfig::new(client_private_key);
+        client.identity_key = IdentityKey::new(client_private_key);
         let (client_res, server_res) = perform_handshake(&client, &server, server_public_key);
 
         client_res.unwrap_err();
         server_res.unwrap_err();
     }
 
+    #[test]
+    fn test_handshake_after_key_rotation() {
+        let mut rng = ::rand::rngs::StdRng::from_seed([7; 32]);
+        let ((client, _), (server, server_public_key)) =
+            build_peers(false /* is_mutual_auth */);
+
+        // rotate the server key, rotating to the same key again is a no-op
+        let new_private_key =
+            || x25519::PrivateKey::generate(&mut ::rand::rngs::StdRng::from_seed(TEST_SEED_2));
+        let new_public_key = new_private_key().public_key();
+        assert!(server.identity_key.rotate(new_private_key()));
+        assert!(!server.identity_key.rotate(new_private_key()));
+        assert_eq!(server.identity_key.public_key(), new_public_key);
+
+        // clients expecting either the new or the previous key are served
+        for expected_public_key in &[new_public_key, server_public_key] {
+            let (client_res, server_res) =
+                perform_handshake(&client, &server, *expected_public_key);
+            assert_eq!(
+                client_res.unwrap().get_remote_static(),
+                *expected_public_key
+            );
+            server_res.unwrap();
+        }
+
+        // after another rotation, the first key isn't served anymore
+        server
+            .identity_key
+            .rotate(x25519::PrivateKey::generate(&mut rng));
+        let (client_res, server_res) = perform_handshake(&client, &server, server_public_key);
+        client_res.unwrap_err();
+        server_res.unwrap_err();
+        let (client_res, server_res) = perform_handshake(&client, &server, new_public_key);
+        client_res.unwrap();
+        server_res.unwrap();
+    }
+
     #[test]
     fn test_handshake_unauthed_peerid_fails_mutual_auth() {
         let mut rng = ::rand::rngs::StdRng::from_seed(TEST_SEED_2);
@@ -105,4 +105,4 @@ pub mod stream;
 pub mod fuzzing;
 
 pub use error::NoiseHandshakeError;
-pub use handshake::{AntiReplayTimestamps, HandshakeAuthMode, NoiseUpgrader};
+pub use handshake::{AntiReplayTimestamps, HandshakeAuthMode, IdentityKey, NoiseUpgrader};
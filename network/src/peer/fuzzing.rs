@@ -3,7 +3,7 @@
 
 use crate::{
     constants,
-    peer::Peer,
+    peer::{Peer, ProtocolRateLimiters},
     protocols::wire::{
         handshake::v1::{MessagingProtocolVersion, SupportedProtocols},
         messaging::v1::{NetworkMessage, NetworkMessageSink},
@@ -109,6 +109,9 @@ pub fn fuzz(data: &[u8]) {
         constants::MAX_FRAME_SIZE,
         None,
         None,
+        ProtocolRateLimiters::open(),
+        None,
+        None,
     );
     executor.spawn(peer.start());
 
@@ -0,0 +1,219 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Keep-alive pings of the connection of a [`Peer`](crate::peer::Peer), see `KeepAliveConfig`.
+//!
+//! The peer pings its remote peer every interval, and measures the round trip time of the
+//! connection from the pongs. A ping without a pong before the timeout fails, and the connection is
+//! dead once too many pings in a row failed, e.g. after a silent drop of the TCP connection which
+//! would otherwise go unnoticed until an application timeout.
+
+use crate::{
+    application::storage::PeerMetadataStorage, protocols::wire::messaging::v1::Nonce,
+    transport::ConnectionMetadata,
+};
+use diem_config::config::KeepAliveConfig;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// What to do once the deadline of the keep-alive is reached
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum KeepAliveAction {
+    Ping(Nonce),
+    /// Too many pings in a row failed
+    Dead,
+}
+
+pub struct KeepAlive {
+    interval: Duration,
+    timeout: Duration,
+    failures_tolerated: u64,
+    /// Where the round trip times are recorded for the other components
+    peer_metadata_storage: Arc<PeerMetadataStorage>,
+    next_nonce: Nonce,
+    /// The ping waiting for its pong, with the time it was sent
+    in_flight: Option<(Nonce, Instant)>,
+    next_ping_at: Instant,
+    failures: u64,
+}
+
+impl KeepAlive {
+    pub fn new(
+        config: KeepAliveConfig,
+        now: Instant,
+        peer_metadata_storage: Arc<PeerMetadataStorage>,
+    ) -> Self {
+        let interval = Duration::from_millis(config.interval_ms);
+        Self {
+            interval,
+            timeout: Duration::from_millis(config.timeout_ms),
+            failures_tolerated: config.failures_tolerated,
+            peer_metadata_storage,
+            next_nonce: 0,
+            in_flight: None,
+            next_ping_at: now + interval,
+            failures: 0,
+        }
+    }
+
+    /// When the ping in flight fails, or the next ping is due
+    pub(crate) fn deadline(&self) -> Instant {
+        match self.in_flight {
+            Some((_, sent_at)) => sent_at + self.timeout,
+            None => self.next_ping_at,
+        }
+    }
+
+    pub(crate) fn on_deadline(&mut self, now: Instant) -> Option<KeepAliveAction> {
+        if now < self.deadline() {
+            return None;
+        }
+        if self.in_flight.take().is_some() {
+            self.failures += 1;
+            if self.failures > self.failures_tolerated {
+                return Some(KeepAliveAction::Dead);
+            }
+        }
+        // Failed pings are retried right away
+        let nonce = self.next_nonce;
+        self.next_nonce = self.next_nonce.wrapping_add(1);
+        self.in_flight = Some((nonce, now));
+        self.next_ping_at = now + self.interval;
+        Some(KeepAliveAction::Ping(nonce))
+    }
+
+    /// Returns the round trip time of the connection if `nonce` is the one of the ping in flight,
+    /// and records it in the metadata of the connection
+    pub(crate) fn on_pong(
+        &mut self,
+        connection_metadata: &ConnectionMetadata,
+        nonce: Nonce,
+        now: Instant,
+    ) -> Option<Duration> {
+        match self.in_flight {
+            Some((in_flight_nonce, sent_at)) if in_flight_nonce == nonce => {
+                self.in_flight = None;
+                self.failures = 0;
+                let rtt = now.saturating_duration_since(sent_at);
+                self.peer_metadata_storage
+                    .update_rtt(connection_metadata, rtt);
+                Some(rtt)
+            }
+            // Pongs of failed pings are ignored
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        protocols::wire::handshake::v1::MessagingProtocolVersion, transport::ConnectionId,
+    };
+    use diem_config::config::PeerRole;
+    use diem_types::{network_address::NetworkAddress, PeerId};
+    use netcore::transport::ConnectionOrigin;
+
+    fn config() -> KeepAliveConfig {
+        KeepAliveConfig {
+            interval_ms: 1_000,
+            timeout_ms: 500,
+            failures_tolerated: 1,
+            enabled: true,
+        }
+    }
+
+    fn connection_metadata() -> ConnectionMetadata {
+        ConnectionMetadata::new(
+            PeerId::random(),
+            ConnectionId::default(),
+            NetworkAddress::mock(),
+            ConnectionOrigin::Outbound,
+            MessagingProtocolVersion::V3,
+            [].iter().into(),
+            PeerRole::Unknown,
+        )
+    }
+
+    #[test]
+    fn test_ping_pong() {
+        let storage = Arc::new(PeerMetadataStorage::new());
+        let metadata = connection_metadata();
+        storage.insert_connection(metadata.clone());
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new(config(), start, storage.clone());
+
+        assert_eq!(keep_alive.deadline(), start + Duration::from_millis(1_000));
+        assert_eq!(keep_alive.on_deadline(start), None);
+        let sent_at = keep_alive.deadline();
+        assert_eq!(
+            keep_alive.on_deadline(sent_at),
+            Some(KeepAliveAction::Ping(0))
+        );
+        assert_eq!(keep_alive.deadline(), sent_at + Duration::from_millis(500));
+
+        let rtt = Duration::from_millis(20);
+        // A pong for another ping is ignored
+        assert_eq!(keep_alive.on_pong(&metadata, 1, sent_at + rtt), None);
+        assert_eq!(keep_alive.on_pong(&metadata, 0, sent_at + rtt), Some(rtt));
+        assert_eq!(
+            storage.read(&metadata.remote_peer_id).unwrap().rtt,
+            Some(rtt)
+        );
+        // The next ping is an interval after the previous one
+        assert_eq!(
+            keep_alive.deadline(),
+            sent_at + Duration::from_millis(1_000)
+        );
+    }
+
+    #[test]
+    fn test_dead_peer() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new(config(), start, Arc::new(PeerMetadataStorage::new()));
+
+        let deadline = keep_alive.deadline();
+        assert_eq!(
+            keep_alive.on_deadline(deadline),
+            Some(KeepAliveAction::Ping(0))
+        );
+        // The first failure is tolerated, and retried right away
+        let deadline = keep_alive.deadline();
+        assert_eq!(
+            keep_alive.on_deadline(deadline),
+            Some(KeepAliveAction::Ping(1))
+        );
+        let deadline = keep_alive.deadline();
+        assert_eq!(
+            keep_alive.on_deadline(deadline),
+            Some(KeepAliveAction::Dead)
+        );
+    }
+
+    #[test]
+    fn test_pong_resets_failures() {
+        let start = Instant::now();
+        let metadata = connection_metadata();
+        let mut keep_alive = KeepAlive::new(config(), start, Arc::new(PeerMetadataStorage::new()));
+
+        for nonce in 0..3 {
+            let deadline = keep_alive.deadline();
+            assert_eq!(
+                keep_alive.on_deadline(deadline),
+                Some(KeepAliveAction::Ping(2 * nonce))
+            );
+            // The ping fails
+            let deadline = keep_alive.deadline();
+            assert_eq!(
+                keep_alive.on_deadline(deadline),
+                Some(KeepAliveAction::Ping(2 * nonce + 1))
+            );
+            assert!(keep_alive
+                .on_pong(&metadata, 2 * nonce + 1, deadline)
+                .is_some());
+        }
+    }
+}
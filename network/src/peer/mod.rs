@@ -15,15 +15,15 @@
 //! [`PeerManager`]: crate::peer_manager::PeerManager
 
 use crate::{
-    counters::{self, RECEIVED_LABEL, SENT_LABEL},
+    counters::{self, DROPPED_LABEL, QUEUED_LABEL, RECEIVED_LABEL, SENT_LABEL},
     logging::NetworkSchema,
     peer_manager::{PeerManagerError, TransportNotification},
     protocols::{
         direct_send::Message,
-        rpc::{InboundRpcRequest, InboundRpcs, OutboundRpcRequest, OutboundRpcs},
+        rpc::{error::RpcError, InboundRpcRequest, InboundRpcs, OutboundRpcRequest, OutboundRpcs},
         wire::messaging::v1::{
             DirectSendMsg, ErrorCode, NetworkMessage, NetworkMessageSink, NetworkMessageStream,
-            Priority, ReadError, WriteError,
+            Nonce, Priority, ReadError, WriteError,
         },
     },
     transport::{self, Connection, ConnectionMetadata},
@@ -39,24 +39,46 @@ use diem_types::PeerId;
 use futures::{
     self,
     channel::oneshot,
+    future,
     io::{AsyncRead, AsyncWrite},
     stream::StreamExt,
     FutureExt, SinkExt, TryFutureExt,
 };
 use serde::Serialize;
 use short_hex_str::AsShortHexStr;
-use std::{fmt, panic, sync::Arc, time::Duration};
+use std::{
+    fmt, panic,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::runtime::Handle;
 use tokio_util::compat::{
     FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt,
 };
 
+mod keep_alive;
+mod priority;
+mod rate_limit;
 #[cfg(test)]
 mod test;
 
+pub use self::{
+    keep_alive::KeepAlive,
+    rate_limit::{protocol_byte_limits, ProtocolRateLimiters},
+};
+use self::{
+    keep_alive::KeepAliveAction,
+    priority::{PriorityClass, WeightedScheduler},
+    rate_limit::Throttle,
+};
+
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod fuzzing;
 
+/// Maximum number of outbound messages the writer holds for scheduling, the other ones wait in its
+/// channel
+const MAX_SCHEDULED_WIRE_MESSAGES: usize = 1024;
+
 /// Requests [`Peer`] receives from the [`PeerManager`](crate::peer_manager::PeerManager).
 #[derive(Debug)]
 pub enum PeerRequest {
@@ -134,12 +156,20 @@ pub struct Peer<TSocket> {
     inbound_rate_limiter: Option<SharedBucket>,
     /// Optional outbound rate limiter
     outbound_rate_limiter: Option<SharedBucket>,
+    /// Rate limiters of the outbound requests of each protocol
+    protocol_rate_limiters: ProtocolRateLimiters,
+    /// Outbound messages with a payload of at least this many bytes are compressed, if the
+    /// connection supports compression
+    compression_threshold: Option<usize>,
+    /// Keep-alive pings of the connection, if the connection supports them
+    keep_alive: Option<KeepAlive>,
 }
 
 impl<TSocket> Peer<TSocket>
 where
     TSocket: AsyncRead + AsyncWrite + Send + 'static,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         network_context: Arc<NetworkContext>,
         executor: Handle,
@@ -154,6 +184,9 @@ where
         max_frame_size: usize,
         inbound_rate_limiter: Option<SharedBucket>,
         outbound_rate_limiter: Option<SharedBucket>,
+        protocol_rate_limiters: ProtocolRateLimiters,
+        compression_threshold: Option<usize>,
+        keep_alive: Option<KeepAlive>,
     ) -> Self {
         let Connection {
             metadata: connection_metadata,
@@ -186,6 +219,9 @@ where
             max_frame_size,
             inbound_rate_limiter,
             outbound_rate_limiter,
+            protocol_rate_limiters,
+            compression_threshold,
+            keep_alive,
         }
     }
 
@@ -223,13 +259,26 @@ where
         // communicate with the task:
         //   1. `write_reqs_tx`: Queue of pending NetworkMessages to write.
         //   2. `close_tx`: Handle to close the task and underlying connection.
+        let compression_threshold = self.compression_threshold.filter(|_| {
+            self.connection_metadata
+                .messaging_protocol
+                .supports_compression()
+        });
         let (mut write_reqs_tx, writer_close_tx) = Self::start_writer_task(
             &self.executor,
             self.time_service.clone(),
             self.connection_metadata.clone(),
             self.network_context.clone(),
             writer,
+            compression_threshold,
         );
+        if !self
+            .connection_metadata
+            .messaging_protocol
+            .supports_keep_alive()
+        {
+            self.keep_alive = None;
+        }
 
         // Start main Peer event loop.
         let reason = loop {
@@ -241,7 +290,7 @@ where
                 // Handle a new outbound request from the PeerManager.
                 maybe_request = self.peer_reqs_rx.next() => {
                     match maybe_request {
-                        Some(request) => self.handle_outbound_request(request, &mut write_reqs_tx).await,
+                        Some(request) => self.throttle_outbound_request(request, &mut write_reqs_tx).await,
                         // The PeerManager is requesting this connection to close
                         // by dropping the corresponding peer_reqs_tx handle.
                         None => self.shutdown(DisconnectReason::Requested),
@@ -283,6 +332,17 @@ where
                 // successfully or unsuccessfully completed request.
                 (request_id, maybe_completed_request) = self.outbound_rpcs.next_completed_request() => {
                     self.outbound_rpcs.handle_completed_request(request_id, maybe_completed_request);
+                },
+                // Send the requests queued by the rate limits of their protocols once the buckets
+                // refill.
+                _ = sleep_until(self.time_service.clone(), self.protocol_rate_limiters.next_release_time()).fuse() => {
+                    for request in self.protocol_rate_limiters.release() {
+                        self.handle_outbound_request(request, &mut write_reqs_tx).await;
+                    }
+                },
+                // Ping the remote peer, or give up on it once too many pings failed.
+                _ = sleep_until(self.time_service.clone(), self.keep_alive.as_ref().map(KeepAlive::deadline)).fuse() => {
+                    self.handle_keep_alive_deadline(&mut write_reqs_tx).await;
                 }
             }
         };
@@ -299,12 +359,16 @@ where
     // 2. The second channel is used to instruct the task to close the connection and terminate.
     // If outbound messages are queued when the task receives a close instruction, it discards
     // them and immediately closes the connection.
+    // Queued messages are written in the order of a weighted scheduler over their priority
+    // classes, so consensus messages are not stuck behind bulk state sync messages.
+    // Messages with a payload of at least `compression_threshold` bytes are compressed.
     fn start_writer_task(
         executor: &Handle,
         time_service: TimeService,
         connection_metadata: ConnectionMetadata,
         network_context: Arc<NetworkContext>,
         mut writer: NetworkMessageSink<impl AsyncWrite + Unpin + Send + 'static>,
+        compression_threshold: Option<usize>,
     ) -> (
         channel::Sender<(
             NetworkMessage,
@@ -323,30 +387,44 @@ where
         let (close_tx, close_rx) = oneshot::channel();
         let writer_task = async move {
             let mut close_rx = close_rx.into_stream();
+            let mut scheduler = WeightedScheduler::new();
             loop {
-                futures::select! {
-                    (message, ack_ch) = write_reqs_rx.select_next_some() => {
-                        if let Err(err) = writer
-                            .send(&message)
-                            .map_ok(|_| ack_ch.send(Ok(())))
-                            .await
-                        {
-                            warn!(
-                                NetworkSchema::new(&network_context)
-                                    .connection_metadata(&connection_metadata),
-                                error = %err,
-                                "{} Error in sending message to peer: {}, error: {}",
-                                network_context,
-                                remote_peer_id.short_str(),
-                                err
-                            );
-                            break;
+                // Let the scheduler pick among all the messages ready to be written.
+                while scheduler.len() < MAX_SCHEDULED_WIRE_MESSAGES {
+                    match write_reqs_rx.next().now_or_never() {
+                        Some(Some((message, ack_ch))) => {
+                            scheduler.push(PriorityClass::from_message(&message), (message, ack_ch))
                         }
-                    },
-                    _ = close_rx.select_next_some() => {
-                        break;
+                        _ => break,
                     }
                 }
+                let (message, ack_ch) = match scheduler.pop() {
+                    Some(request) => request,
+                    None => futures::select! {
+                        request = write_reqs_rx.select_next_some() => request,
+                        _ = close_rx.select_next_some() => break,
+                    },
+                };
+                // Discard the queued messages once asked to close.
+                if close_rx.next().now_or_never().flatten().is_some() {
+                    break;
+                }
+                let message = match compression_threshold {
+                    Some(threshold) => message.compress(threshold),
+                    None => message,
+                };
+                if let Err(err) = writer.send(&message).map_ok(|_| ack_ch.send(Ok(()))).await {
+                    warn!(
+                        NetworkSchema::new(&network_context)
+                            .connection_metadata(&connection_metadata),
+                        error = %err,
+                        "{} Error in sending message to peer: {}, error: {}",
+                        network_context,
+                        remote_peer_id.short_str(),
+                        err
+                    );
+                    break;
+                }
             }
             info!(
                 NetworkSchema::new(&network_context).connection_metadata(&connection_metadata),
@@ -438,6 +516,11 @@ where
             },
         };
 
+        let message = match message {
+            NetworkMessage::CompressedMsg(message) => message.decompress(self.max_frame_size)?,
+            message => message,
+        };
+
         match message {
             NetworkMessage::DirectSendMsg(message) => self.handle_inbound_direct_send(message),
             NetworkMessage::Error(error_msg) => {
@@ -469,10 +552,75 @@ where
             NetworkMessage::RpcResponse(response) => {
                 self.outbound_rpcs.handle_inbound_response(response)
             }
+            NetworkMessage::Ping(nonce) => {
+                let (ack_tx, _) = oneshot::channel();
+                write_reqs_tx
+                    .send((NetworkMessage::Pong(nonce), ack_tx))
+                    .await?;
+            }
+            NetworkMessage::Pong(nonce) => self.handle_pong(nonce),
+            // `decompress` never returns a compressed message
+            NetworkMessage::CompressedMsg(_) => unreachable!(),
         };
         Ok(())
     }
 
+    async fn handle_keep_alive_deadline(
+        &mut self,
+        write_reqs_tx: &mut channel::Sender<(
+            NetworkMessage,
+            oneshot::Sender<Result<(), PeerManagerError>>,
+        )>,
+    ) {
+        let now = self.time_service.now();
+        let action = match self.keep_alive.as_mut() {
+            Some(keep_alive) => keep_alive.on_deadline(now),
+            None => return,
+        };
+        match action {
+            Some(KeepAliveAction::Ping(nonce)) => {
+                let (ack_tx, _) = oneshot::channel();
+                if let Err(e) = write_reqs_tx
+                    .send((NetworkMessage::Ping(nonce), ack_tx))
+                    .await
+                {
+                    warn!(
+                        NetworkSchema::new(&self.network_context)
+                            .connection_metadata(&self.connection_metadata),
+                        error = ?e,
+                        "{} Failed to send keep-alive ping to peer: {}. Error: {:?}",
+                        self.network_context,
+                        self.remote_peer_id().short_str(),
+                        e,
+                    );
+                }
+            }
+            Some(KeepAliveAction::Dead) => {
+                warn!(
+                    NetworkSchema::new(&self.network_context)
+                        .connection_metadata(&self.connection_metadata),
+                    "{} Disconnecting peer: {} after too many unanswered keep-alive pings",
+                    self.network_context,
+                    self.remote_peer_id().short_str(),
+                );
+                counters::keep_alive_disconnects(&self.network_context).inc();
+                self.shutdown(DisconnectReason::ConnectionLost);
+            }
+            None => (),
+        }
+    }
+
+    fn handle_pong(&mut self, nonce: Nonce) {
+        let now = self.time_service.now();
+        let rtt = match self.keep_alive.as_mut() {
+            Some(keep_alive) => keep_alive.on_pong(&self.connection_metadata, nonce, now),
+            None => None,
+        };
+        if let Some(rtt) = rtt {
+            counters::peer_rtt(&self.network_context, &self.remote_peer_id(), rtt);
+        }
+    }
+
     /// Handle an inbound DirectSendMsg from the remote peer. There's not much to
     /// do here other than bump some counters and forward the message up to the
     /// PeerManager.
@@ -510,6 +658,42 @@ where
         }
     }
 
+    /// Sends, queues or drops an outbound request according to the rate limit of its protocol
+    async fn throttle_outbound_request(
+        &mut self,
+        request: PeerRequest,
+        write_reqs_tx: &mut channel::Sender<(
+            NetworkMessage,
+            oneshot::Sender<Result<(), PeerManagerError>>,
+        )>,
+    ) {
+        match self.protocol_rate_limiters.throttle(request) {
+            Throttle::Send(request) => self.handle_outbound_request(request, write_reqs_tx).await,
+            Throttle::Queued(protocol_id) => {
+                counters::rate_limited_messages(&self.network_context, protocol_id, QUEUED_LABEL)
+                    .inc();
+            }
+            Throttle::Dropped(request) => {
+                let protocol_id = match request {
+                    PeerRequest::SendRpc(request) => {
+                        let _ = request.res_tx.send(Err(RpcError::RateLimited));
+                        request.protocol_id
+                    }
+                    PeerRequest::SendDirectSend(message) => message.protocol_id,
+                };
+                counters::rate_limited_messages(&self.network_context, protocol_id, DROPPED_LABEL)
+                    .inc();
+                debug!(
+                    NetworkSchema::new(&self.network_context)
+                        .connection_metadata(&self.connection_metadata),
+                    "Dropped outbound request for protocol {} to peer: {} over its rate limit",
+                    protocol_id,
+                    self.remote_peer_id().short_str(),
+                );
+            }
+        }
+    }
+
     async fn handle_outbound_request(
         &mut self,
         request: PeerRequest,
@@ -628,3 +812,11 @@ where
         );
     }
 }
+
+/// Waits until `deadline`, forever if there is none
+async fn sleep_until(time_service: TimeService, deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => time_service.sleep_until(deadline).await,
+        None => future::pending().await,
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Priority classes of the outbound messages of a [`Peer`](crate::peer::Peer), and the weighted
+//! scheduler picking the next message its writer puts on the wire.
+//!
+//! Every class gets a share of the writes proportional to its weight while it has queued messages,
+//! so round critical consensus messages overtake bulk state sync chunks, without starving the lower
+//! classes.
+
+use crate::{protocols::wire::messaging::v1::NetworkMessage, ProtocolId};
+use std::collections::VecDeque;
+
+/// Priority classes of the outbound messages, from the highest to the lowest priority
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PriorityClass {
+    Consensus,
+    Mempool,
+    StateSync,
+    Discovery,
+}
+
+impl PriorityClass {
+    const ALL: [PriorityClass; 4] = [
+        PriorityClass::Consensus,
+        PriorityClass::Mempool,
+        PriorityClass::StateSync,
+        PriorityClass::Discovery,
+    ];
+
+    /// Share of the writes of the class, relative to the other classes with queued messages
+    fn weight(self) -> i64 {
+        match self {
+            PriorityClass::Consensus => 8,
+            PriorityClass::Mempool => 4,
+            PriorityClass::StateSync => 2,
+            PriorityClass::Discovery => 1,
+        }
+    }
+
+    pub fn from_protocol(protocol_id: ProtocolId) -> Self {
+        use ProtocolId::*;
+        match protocol_id {
+            ConsensusRpc
+            | ConsensusDirectSend
+            | ConsensusDirectSendJSON
+            | ConsensusObserverDirectSend
+            | ConsensusDirectSendLz4
+            | ConsensusDirectSendZstd
+            // Peers failing their health checks are disconnected, so they can't wait behind bulk
+            // messages either
+            | HealthCheckerRpc => PriorityClass::Consensus,
+            MempoolDirectSend | MempoolDirectSendZstd => PriorityClass::Mempool,
+            StateSyncDirectSend => PriorityClass::StateSync,
+            DiscoveryDirectSend => PriorityClass::Discovery,
+        }
+    }
+
+    /// The messages without a protocol are errors, keep-alive pings, and responses to the only rpc
+    /// protocols, consensus and health checker. Messages are only compressed once scheduled.
+    pub fn from_message(message: &NetworkMessage) -> Self {
+        match message {
+            NetworkMessage::DirectSendMsg(message) => Self::from_protocol(message.protocol_id),
+            NetworkMessage::RpcRequest(request) => Self::from_protocol(request.protocol_id),
+            NetworkMessage::RpcResponse(_)
+            | NetworkMessage::Error(_)
+            | NetworkMessage::CompressedMsg(_)
+            | NetworkMessage::Ping(_)
+            | NetworkMessage::Pong(_) => PriorityClass::Consensus,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Smooth weighted round robin over the queues of the priority classes
+pub struct WeightedScheduler<T> {
+    queues: Vec<VecDeque<T>>,
+    /// Running credits of the classes, the class with the most credits is picked next
+    credits: Vec<i64>,
+    len: usize,
+}
+
+impl<T> Default for WeightedScheduler<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> WeightedScheduler<T> {
+    pub fn new() -> Self {
+        Self {
+            queues: PriorityClass::ALL.iter().map(|_| VecDeque::new()).collect(),
+            credits: vec![0; PriorityClass::ALL.len()],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, class: PriorityClass, item: T) {
+        self.queues[class.index()].push_back(item);
+        self.len += 1;
+    }
+
+    /// Pops the next item, from the first queue of the classes with queued items when their
+    /// credits tie
+    pub fn pop(&mut self) -> Option<T> {
+        let mut total_weight = 0;
+        let mut picked: Option<PriorityClass> = None;
+        for class in PriorityClass::ALL.iter().copied() {
+            if self.queues[class.index()].is_empty() {
+                continue;
+            }
+            self.credits[class.index()] += class.weight();
+            total_weight += class.weight();
+            if picked.map_or(true, |picked| {
+                self.credits[class.index()] > self.credits[picked.index()]
+            }) {
+                picked = Some(class);
+            }
+        }
+        let picked = picked?;
+        self.credits[picked.index()] -= total_weight;
+        // Classes start over once they run out of queued items, so an idle class doesn't
+        // accumulate credits
+        if self.queues[picked.index()].len() == 1 {
+            self.credits[picked.index()] = 0;
+        }
+        self.len -= 1;
+        self.queues[picked.index()].pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_strict_order_within_class() {
+        let mut scheduler = WeightedScheduler::new();
+        for i in 0..3 {
+            scheduler.push(PriorityClass::StateSync, i);
+        }
+        assert_eq!(scheduler.len(), 3);
+        assert_eq!(scheduler.pop(), Some(0));
+        assert_eq!(scheduler.pop(), Some(1));
+        assert_eq!(scheduler.pop(), Some(2));
+        assert_eq!(scheduler.pop(), None);
+        assert_eq!(scheduler.len(), 0);
+    }
+
+    #[test]
+    fn test_consensus_overtakes_state_sync() {
+        let mut scheduler = WeightedScheduler::new();
+        for _ in 0..100 {
+            scheduler.push(PriorityClass::StateSync, PriorityClass::StateSync);
+        }
+        scheduler.push(PriorityClass::Consensus, PriorityClass::Consensus);
+        // The consensus message is written right away, ahead of the state sync backlog
+        assert_eq!(scheduler.pop(), Some(PriorityClass::Consensus));
+    }
+
+    #[test]
+    fn test_weighted_shares() {
+        let mut scheduler = WeightedScheduler::new();
+        for class in PriorityClass::ALL.iter().copied() {
+            for _ in 0..100 {
+                scheduler.push(class, class);
+            }
+        }
+
+        let popped: Vec<_> = (0..15).map(|_| scheduler.pop().unwrap()).collect();
+        let count = |class| popped.iter().filter(|popped| **popped == class).count();
+        assert_eq!(count(PriorityClass::Consensus), 8);
+        assert_eq!(count(PriorityClass::Mempool), 4);
+        assert_eq!(count(PriorityClass::StateSync), 2);
+        // The lowest class is not starved
+        assert_eq!(count(PriorityClass::Discovery), 1);
+        assert_eq!(popped[0], PriorityClass::Consensus);
+    }
+}
@@ -0,0 +1,294 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per protocol rate limits of the requests a [`Peer`](crate::peer::Peer) sends to its remote
+//! peer, see `ProtocolRateLimitConfig`.
+//!
+//! Every limited protocol has its own token bucket of bytes on every connection. The requests over
+//! the limit of a protocol with the `Drop` policy are dropped, while the ones of a protocol with the
+//! `Queue` policy wait in order for the bucket to refill. The requests of the other protocols are
+//! never held back, so they keep flowing while a limited protocol is throttled.
+
+use crate::{peer::PeerRequest, ProtocolId};
+use anyhow::{ensure, format_err, Result};
+use diem_config::{
+    config::{ProtocolByteLimit, ProtocolRateLimitConfig, RateLimitPolicy},
+    network_id::NetworkContext,
+};
+use diem_rate_limiter::rate_limit::Bucket;
+use diem_types::PeerId;
+use short_hex_str::AsShortHexStr;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Instant,
+};
+
+/// What to do with an outbound request
+#[derive(Debug)]
+pub(crate) enum Throttle {
+    Send(PeerRequest),
+    /// The request is queued until its protocol's bucket refills
+    Queued(ProtocolId),
+    /// The request is over the limit of its protocol
+    Dropped(PeerRequest),
+}
+
+struct ProtocolRateLimiter {
+    bucket: Bucket,
+    limit: ProtocolByteLimit,
+    queue: VecDeque<PeerRequest>,
+}
+
+/// The rate limiters of the protocols of a single connection
+pub struct ProtocolRateLimiters {
+    limiters: HashMap<ProtocolId, ProtocolRateLimiter>,
+}
+
+impl ProtocolRateLimiters {
+    pub fn new(
+        network_context: &NetworkContext,
+        remote_peer_id: PeerId,
+        limits: &HashMap<ProtocolId, ProtocolByteLimit>,
+    ) -> Self {
+        let limiters = limits
+            .iter()
+            .map(|(protocol_id, limit)| {
+                let bucket = Bucket::new(
+                    "network_protocol".to_string(),
+                    network_context.to_string(),
+                    format!("{}-{}", remote_peer_id.short_str(), protocol_id),
+                    limit.byte_bucket_size,
+                    limit.byte_bucket_size,
+                    limit.byte_bucket_rate,
+                    None,
+                );
+                let limiter = ProtocolRateLimiter {
+                    bucket,
+                    limit: *limit,
+                    queue: VecDeque::new(),
+                };
+                (*protocol_id, limiter)
+            })
+            .collect();
+        Self { limiters }
+    }
+
+    /// Never throttles any request
+    pub fn open() -> Self {
+        Self {
+            limiters: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn throttle(&mut self, request: PeerRequest) -> Throttle {
+        let protocol_id = request_protocol_id(&request);
+        let limiter = match self.limiters.get_mut(&protocol_id) {
+            Some(limiter) => limiter,
+            None => return Throttle::Send(request),
+        };
+
+        let size = request_size(&request);
+        // The bucket can never hold enough tokens for this request
+        if limiter.bucket.time_of_tokens_needed(size).is_none() {
+            return Throttle::Dropped(request);
+        }
+        // Requests can't overtake the ones already queued for their protocol
+        if limiter.queue.is_empty() && limiter.bucket.acquire_all_tokens(size).is_ok() {
+            return Throttle::Send(request);
+        }
+        match limiter.limit.policy {
+            RateLimitPolicy::Queue if limiter.queue.len() < limiter.limit.max_queue_size => {
+                limiter.queue.push_back(request);
+                Throttle::Queued(protocol_id)
+            }
+            _ => Throttle::Dropped(request),
+        }
+    }
+
+    /// When the next queued request may be sent, None if no request is queued
+    pub(crate) fn next_release_time(&self) -> Option<Instant> {
+        self.limiters
+            .values()
+            .filter_map(|limiter| {
+                let request = limiter.queue.front()?;
+                limiter.bucket.time_of_tokens_needed(request_size(request))
+            })
+            .min()
+    }
+
+    /// Removes the queued requests which may be sent now
+    pub(crate) fn release(&mut self) -> Vec<PeerRequest> {
+        let mut released = Vec::new();
+        for limiter in self.limiters.values_mut() {
+            while let Some(request) = limiter.queue.front() {
+                if limiter
+                    .bucket
+                    .acquire_all_tokens(request_size(request))
+                    .is_err()
+                {
+                    break;
+                }
+                released.extend(limiter.queue.pop_front());
+            }
+        }
+        released
+    }
+}
+
+/// Parses the byte limits of the protocols of `config`, by protocol
+pub fn protocol_byte_limits(
+    config: Option<&ProtocolRateLimitConfig>,
+) -> Result<HashMap<ProtocolId, ProtocolByteLimit>> {
+    let config = match config {
+        Some(config) if config.enabled => config,
+        _ => return Ok(HashMap::new()),
+    };
+    config
+        .protocols
+        .iter()
+        .map(|(name, limit)| {
+            let protocol_id = ProtocolId::all()
+                .iter()
+                .find(|protocol_id| protocol_id.as_str() == name)
+                .ok_or_else(|| format_err!("unknown protocol {}", name))?;
+            ensure!(
+                limit.byte_bucket_rate > 0 && limit.byte_bucket_size >= limit.byte_bucket_rate,
+                "invalid limit {:?} of protocol {}, expected 0 < byte_bucket_rate <= byte_bucket_size",
+                limit,
+                name
+            );
+            Ok((*protocol_id, *limit))
+        })
+        .collect()
+}
+
+fn request_protocol_id(request: &PeerRequest) -> ProtocolId {
+    match request {
+        PeerRequest::SendRpc(request) => request.protocol_id,
+        PeerRequest::SendDirectSend(message) => message.protocol_id,
+    }
+}
+
+fn request_size(request: &PeerRequest) -> usize {
+    match request {
+        PeerRequest::SendRpc(request) => request.data.len(),
+        PeerRequest::SendDirectSend(message) => message.mdata.len(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::protocols::direct_send::Message;
+    use bytes::Bytes;
+
+    fn limits(policy: RateLimitPolicy) -> HashMap<ProtocolId, ProtocolByteLimit> {
+        let mut limits = HashMap::new();
+        limits.insert(
+            ProtocolId::StateSyncDirectSend,
+            ProtocolByteLimit {
+                byte_bucket_rate: 10,
+                byte_bucket_size: 10,
+                policy,
+                max_queue_size: 2,
+            },
+        );
+        limits
+    }
+
+    fn direct_send(protocol_id: ProtocolId, size: usize) -> PeerRequest {
+        PeerRequest::SendDirectSend(Message {
+            protocol_id,
+            mdata: Bytes::from(vec![0; size]),
+        })
+    }
+
+    #[test]
+    fn test_drop_policy() {
+        let mut rate_limiters = ProtocolRateLimiters::new(
+            &NetworkContext::mock(),
+            PeerId::random(),
+            &limits(RateLimitPolicy::Drop),
+        );
+
+        let state_sync = ProtocolId::StateSyncDirectSend;
+        assert!(matches!(
+            rate_limiters.throttle(direct_send(state_sync, 6)),
+            Throttle::Send(_)
+        ));
+        assert!(matches!(
+            rate_limiters.throttle(direct_send(state_sync, 6)),
+            Throttle::Dropped(_)
+        ));
+        // Bigger than the bucket
+        assert!(matches!(
+            rate_limiters.throttle(direct_send(state_sync, 11)),
+            Throttle::Dropped(_)
+        ));
+        // The other protocols are not limited
+        assert!(matches!(
+            rate_limiters.throttle(direct_send(ProtocolId::ConsensusDirectSend, 100)),
+            Throttle::Send(_)
+        ));
+        assert_eq!(rate_limiters.next_release_time(), None);
+    }
+
+    #[test]
+    fn test_queue_policy() {
+        let mut rate_limiters = ProtocolRateLimiters::new(
+            &NetworkContext::mock(),
+            PeerId::random(),
+            &limits(RateLimitPolicy::Queue),
+        );
+
+        let state_sync = ProtocolId::StateSyncDirectSend;
+        assert!(matches!(
+            rate_limiters.throttle(direct_send(state_sync, 8)),
+            Throttle::Send(_)
+        ));
+        assert!(matches!(
+            rate_limiters.throttle(direct_send(state_sync, 6)),
+            Throttle::Queued(_)
+        ));
+        // Fits in the bucket, but queued behind the previous request
+        assert!(matches!(
+            rate_limiters.throttle(direct_send(state_sync, 1)),
+            Throttle::Queued(_)
+        ));
+        // The queue is full
+        assert!(matches!(
+            rate_limiters.throttle(direct_send(state_sync, 1)),
+            Throttle::Dropped(_)
+        ));
+        assert!(matches!(
+            rate_limiters.throttle(direct_send(ProtocolId::ConsensusDirectSend, 100)),
+            Throttle::Send(_)
+        ));
+
+        assert!(rate_limiters.release().is_empty());
+        let release_time = rate_limiters.next_release_time().unwrap();
+        std::thread::sleep(release_time.saturating_duration_since(Instant::now()));
+        let released: Vec<_> = rate_limiters.release().iter().map(request_size).collect();
+        assert_eq!(released, vec![6, 1]);
+        assert_eq!(rate_limiters.next_release_time(), None);
+    }
+
+    #[test]
+    fn test_protocol_byte_limits() {
+        assert!(protocol_byte_limits(None).unwrap().is_empty());
+
+        let mut config = ProtocolRateLimitConfig::default();
+        let limits = protocol_byte_limits(Some(&config)).unwrap();
+        assert!(limits.contains_key(&ProtocolId::StateSyncDirectSend));
+
+        config.enabled = false;
+        assert!(protocol_byte_limits(Some(&config)).unwrap().is_empty());
+
+        config.enabled = true;
+        config.protocols.insert(
+            "UnknownDirectSend".to_string(),
+            config.protocols["StateSyncDirectSend"],
+        );
+        protocol_byte_limits(Some(&config)).unwrap_err();
+    }
+}
@@ -2,11 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    application::storage::PeerMetadataStorage,
     constants::{
         INBOUND_RPC_TIMEOUT_MS, MAX_CONCURRENT_INBOUND_RPCS, MAX_CONCURRENT_OUTBOUND_RPCS,
         MAX_FRAME_SIZE, NETWORK_CHANNEL_SIZE,
     },
-    peer::{DisconnectReason, Peer, PeerNotification, PeerRequest},
+    peer::{
+        DisconnectReason, KeepAlive, Peer, PeerNotification, PeerRequest, ProtocolRateLimiters,
+    },
     peer_manager::TransportNotification,
     protocols::{
         direct_send::Message,
@@ -24,8 +27,11 @@ use crate::{
 };
 use bytes::Bytes;
 use channel::{self, diem_channel, message_queues::QueueStyle};
-use diem_config::{config::PeerRole, network_id::NetworkContext};
-use diem_time_service::{MockTimeService, TimeService};
+use diem_config::{
+    config::{KeepAliveConfig, PeerRole, ProtocolByteLimit, RateLimitPolicy},
+    network_id::NetworkContext,
+};
+use diem_time_service::{MockTimeService, TimeService, TimeServiceTrait};
 use diem_types::{network_address::NetworkAddress, PeerId};
 use futures::{
     channel::oneshot,
@@ -36,7 +42,7 @@ use futures::{
 };
 use memsocket::MemorySocket;
 use netcore::transport::ConnectionOrigin;
-use std::{collections::HashSet, str::FromStr, time::Duration};
+use std::{collections::HashSet, str::FromStr, sync::Arc, time::Duration};
 use tokio::runtime::{Handle, Runtime};
 use tokio_util::compat::{
     FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt,
@@ -90,6 +96,9 @@ fn build_test_peer(
         MAX_FRAME_SIZE,
         None,
         None,
+        ProtocolRateLimiters::open(),
+        None,
+        None,
     );
     let peer_handle = PeerHandle(peer_reqs_tx);
 
@@ -280,6 +289,46 @@ fn peer_recv_message() {
     rt.block_on(future::join3(peer.start(), server, client));
 }
 
+// Reading an inbound compressed DirectSendMsg off the wire should notify the
+// PeerManager of the decompressed DirectSend.
+#[test]
+fn peer_recv_compressed_message() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (peer, _peer_handle, connection, _connection_notifs_rx, mut peer_notifs_rx) =
+        build_test_peer(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+        );
+
+    let data = vec![7; 1024];
+    let send_msg = NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: PROTOCOL,
+        priority: 0,
+        raw_msg: data.clone(),
+    })
+    .compress(0);
+    assert!(matches!(send_msg, NetworkMessage::CompressedMsg(_)));
+    let recv_msg = PeerNotification::RecvMessage(Message {
+        protocol_id: PROTOCOL,
+        mdata: Bytes::from(data),
+    });
+
+    let client = async move {
+        let mut connection = NetworkMessageSink::new(connection, MAX_FRAME_SIZE, None);
+        connection.send(&send_msg).await.unwrap();
+        // Client then closes connection.
+        connection.close().await.unwrap();
+    };
+
+    let server = async move {
+        let received = peer_notifs_rx.next().await.unwrap();
+        assert_eq!(recv_msg, received);
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
 // Two connected Peer actors should be able to send/recv a DirectSend from each
 // other and then shutdown gracefully.
 #[test]
@@ -633,6 +682,63 @@ fn peer_send_rpc() {
     rt.block_on(future::join3(peer.start(), server, client));
 }
 
+// Outbound rpcs over the rate limit of their protocol should fail without reaching the wire.
+#[test]
+fn peer_send_rpc_over_rate_limit() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (mut peer, mut peer_handle, mut connection, _connection_notifs_rx, _peer_notifs_rx) =
+        build_test_peer(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+        );
+    let limit = ProtocolByteLimit {
+        byte_bucket_rate: 11,
+        byte_bucket_size: 11,
+        policy: RateLimitPolicy::Drop,
+        max_queue_size: 0,
+    };
+    peer.protocol_rate_limiters = ProtocolRateLimiters::new(
+        &NetworkContext::mock(),
+        PeerId::random(),
+        &[(PROTOCOL, limit)].iter().cloned().collect(),
+    );
+    let (mut server_sink, mut server_stream) = build_network_sink_stream(&mut connection);
+    let timeout = Duration::from_millis(10_000);
+
+    let client = async move {
+        // The first request fits in the bucket.
+        let response = peer_handle
+            .send_rpc_request(PROTOCOL, Bytes::from(&b"hello world"[..]), timeout)
+            .await
+            .unwrap();
+        assert_eq!(response, Bytes::from(&b"goodbye world"[..]));
+        // The second one is over the limit.
+        let result = peer_handle
+            .send_rpc_request(PROTOCOL, Bytes::from(&b"hello world"[..]), timeout)
+            .await;
+        assert!(matches!(result, Err(RpcError::RateLimited)));
+        // Client then closes connection.
+    };
+    let server = async move {
+        let received = match server_stream.next().await.unwrap().unwrap() {
+            NetworkMessage::RpcRequest(request) => request,
+            received => panic!("Expected RpcRequest; unexpected: {:?}", received),
+        };
+        assert_eq!(received.raw_request, b"hello world");
+        let response = NetworkMessage::RpcResponse(RpcResponse {
+            request_id: received.request_id,
+            priority: 0,
+            raw_response: Vec::from(&b"goodbye world"[..]),
+        });
+        server_sink.send(&response).await.unwrap();
+        // No other request should reach the wire.
+        assert!(matches!(server_stream.next().await, None));
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
 #[test]
 fn peer_send_rpc_concurrent() {
     ::diem_logger::Logger::init_for_testing();
@@ -826,6 +932,71 @@ fn peer_send_rpc_timeout() {
     rt.block_on(future::join(peer.start(), test));
 }
 
+// Peer pings its remote peer to measure the round trip time of the connection, and disconnects
+// once too many pings in a row are left unanswered.
+#[test]
+fn peer_keep_alive() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let mock_time = MockTimeService::new();
+    let (mut peer, _peer_handle, mut connection, mut connection_notifs_rx, _peer_notifs_rx) =
+        build_test_peer(
+            rt.handle().clone(),
+            mock_time.clone().into(),
+            ConnectionOrigin::Inbound,
+        );
+    peer.connection_metadata.messaging_protocol = MessagingProtocolVersion::V3;
+    let storage = Arc::new(PeerMetadataStorage::new());
+    storage.insert_connection(peer.connection_metadata.clone());
+    let config = KeepAliveConfig {
+        interval_ms: 1_000,
+        timeout_ms: 500,
+        failures_tolerated: 1,
+        enabled: true,
+    };
+    peer.keep_alive = Some(KeepAlive::new(config, mock_time.now(), storage.clone()));
+    let remote_peer_id = peer.remote_peer_id();
+
+    let test = async move {
+        let (mut server_sink, mut server_stream) = build_network_sink_stream(&mut connection);
+
+        mock_time.advance_ms_async(1_000).await;
+        assert_eq!(
+            server_stream.next().await.unwrap().unwrap(),
+            NetworkMessage::Ping(0)
+        );
+        mock_time.advance_ms_async(5).await;
+        server_sink.send(&NetworkMessage::Pong(0)).await.unwrap();
+        while storage.read(&remote_peer_id).unwrap().rtt.is_none() {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(
+            storage.read(&remote_peer_id).unwrap().rtt,
+            Some(Duration::from_millis(5))
+        );
+
+        // The server stops answering, the first failed ping is tolerated and retried right away.
+        mock_time.advance_ms_async(995).await;
+        assert_eq!(
+            server_stream.next().await.unwrap().unwrap(),
+            NetworkMessage::Ping(1)
+        );
+        mock_time.advance_ms_async(500).await;
+        assert_eq!(
+            server_stream.next().await.unwrap().unwrap(),
+            NetworkMessage::Ping(2)
+        );
+        mock_time.advance_ms_async(500).await;
+        assert_disconnected_event(
+            remote_peer_id,
+            DisconnectReason::ConnectionLost,
+            &mut connection_notifs_rx,
+        )
+        .await;
+    };
+    rt.block_on(future::join(peer.start(), test));
+}
+
 // PeerManager can request a Peer to shutdown.
 #[test]
 fn peer_disconnect_request() {
@@ -5,18 +5,26 @@ use crate::{
     application::storage::PeerMetadataStorage,
     counters,
     counters::NETWORK_RATE_LIMIT_METRICS,
-    noise::{stream::NoiseStream, HandshakeAuthMode},
+    noise::{stream::NoiseStream, HandshakeAuthMode, IdentityKey},
+    peer::protocol_byte_limits,
     peer_manager::{
         conn_notifs_channel, ConnectionRequest, ConnectionRequestSender, PeerManager,
-        PeerManagerNotification, PeerManagerRequest, PeerManagerRequestSender,
+        PeerManagerNotification, PeerManagerRequest, PeerManagerRequestSender, PeerReputation,
     },
     protocols::wire::handshake::v1::SupportedProtocols,
-    transport::{self, Connection, DiemNetTransport, DIEM_TCP_TRANSPORT},
+    transport::{
+        self, BoxedSocket, Connection, DiemNetTransport, DiemTransport, DIEM_QUIC_TRANSPORT,
+        DIEM_TCP_TRANSPORT,
+    },
     ProtocolId,
 };
 use channel::{self, diem_channel, message_queues::QueueStyle};
 use diem_config::{
-    config::{PeerSet, RateLimitConfig, HANDSHAKE_VERSION},
+    config::{
+        HandshakeLimitConfig, InboundConnectionLimitConfig, KeepAliveConfig, PeerReputationConfig,
+        PeerSet, ProtocolRateLimitConfig, RateLimitConfig, Socks5ProxyConfig,
+        WireCompressionConfig, HANDSHAKE_VERSION,
+    },
     network_id::NetworkContext,
 };
 use diem_crypto::x25519;
@@ -26,13 +34,24 @@ use diem_metrics::IntCounterVec;
 use diem_rate_limiter::rate_limit::TokenBucketRateLimiter;
 use diem_time_service::TimeService;
 use diem_types::{chain_id::ChainId, network_address::NetworkAddress, PeerId};
+use futures::future::Either;
 #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
 use netcore::transport::memory::MemoryTransport;
 use netcore::transport::{
+    boxed::BoxedTransport,
+    quic::{QuicOrTcpTransport, QuicSocket},
+    socks5::{Socks5Credentials, Socks5Proxy},
     tcp::{TcpSocket, TcpTransport},
     Transport,
 };
-use std::{clone::Clone, collections::HashMap, fmt::Debug, net::IpAddr, sync::Arc};
+use std::{
+    clone::Clone,
+    collections::HashMap,
+    fmt::Debug,
+    io,
+    net::IpAddr,
+    sync::{atomic::AtomicUsize, Arc},
+};
 use tokio::runtime::Handle;
 
 /// Inbound and Outbound connections are always secured with NoiseIK.  The dialer
@@ -53,9 +72,16 @@ struct TransportContext {
     chain_id: ChainId,
     direct_send_protocols: Vec<ProtocolId>,
     rpc_protocols: Vec<ProtocolId>,
-    authentication_mode: AuthenticationMode,
+    identity_key: IdentityKey,
+    mutual_authentication: bool,
     trusted_peers: Arc<RwLock<PeerSet>>,
     enable_proxy_protocol: bool,
+    enable_compression: bool,
+    enable_keep_alive: bool,
+    socks5_proxy: Option<Socks5Proxy>,
+    handshake_limit_config: Option<HandshakeLimitConfig>,
+    /// Base transport in place of the one picked from the listen address
+    base_transport: Option<BoxedTransport<BoxedSocket, io::Error>>,
 }
 
 impl TransportContext {
@@ -63,17 +89,28 @@ impl TransportContext {
         chain_id: ChainId,
         direct_send_protocols: Vec<ProtocolId>,
         rpc_protocols: Vec<ProtocolId>,
-        authentication_mode: AuthenticationMode,
+        identity_key: IdentityKey,
+        mutual_authentication: bool,
         trusted_peers: Arc<RwLock<PeerSet>>,
         enable_proxy_protocol: bool,
+        enable_compression: bool,
+        enable_keep_alive: bool,
+        socks5_proxy: Option<Socks5Proxy>,
+        handshake_limit_config: Option<HandshakeLimitConfig>,
     ) -> Self {
         Self {
             chain_id,
             direct_send_protocols,
             rpc_protocols,
-            authentication_mode,
+            identity_key,
+            mutual_authentication,
             trusted_peers,
             enable_proxy_protocol,
+            enable_compression,
+            enable_keep_alive,
+            socks5_proxy,
+            handshake_limit_config,
+            base_transport: None,
         }
     }
 
@@ -114,9 +151,14 @@ struct PeerManagerContext {
     max_concurrent_network_reqs: usize,
     channel_size: usize,
     max_frame_size: usize,
-    inbound_connection_limit: usize,
+    inbound_connection_limit: Arc<AtomicUsize>,
     inbound_rate_limit_config: Option<RateLimitConfig>,
     outbound_rate_limit_config: Option<RateLimitConfig>,
+    peer_reputation_config: Option<PeerReputationConfig>,
+    protocol_rate_limit_config: Option<ProtocolRateLimitConfig>,
+    compression_threshold: Option<usize>,
+    inbound_connection_limit_per_ip: Option<usize>,
+    keep_alive_config: Option<KeepAliveConfig>,
 }
 
 impl PeerManagerContext {
@@ -138,9 +180,14 @@ impl PeerManagerContext {
         max_concurrent_network_reqs: usize,
         channel_size: usize,
         max_frame_size: usize,
-        inbound_connection_limit: usize,
+        inbound_connection_limit: Arc<AtomicUsize>,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        peer_reputation_config: Option<PeerReputationConfig>,
+        protocol_rate_limit_config: Option<ProtocolRateLimitConfig>,
+        compression_threshold: Option<usize>,
+        inbound_connection_limit_per_ip: Option<usize>,
+        keep_alive_config: Option<KeepAliveConfig>,
     ) -> Self {
         Self {
             pm_reqs_tx,
@@ -159,6 +206,11 @@ impl PeerManagerContext {
             inbound_connection_limit,
             inbound_rate_limit_config,
             outbound_rate_limit_config,
+            peer_reputation_config,
+            protocol_rate_limit_config,
+            compression_threshold,
+            inbound_connection_limit_per_ip,
+            keep_alive_config,
         }
     }
 
@@ -182,22 +234,30 @@ impl PeerManagerContext {
 type MemoryPeerManager =
     PeerManager<DiemNetTransport<MemoryTransport>, NoiseStream<memsocket::MemorySocket>>;
 type TcpPeerManager = PeerManager<DiemNetTransport<TcpTransport>, NoiseStream<TcpSocket>>;
+type QuicPeerManager =
+    PeerManager<DiemNetTransport<QuicOrTcpTransport>, NoiseStream<Either<QuicSocket, TcpSocket>>>;
+type CustomPeerManager =
+    PeerManager<DiemNetTransport<BoxedTransport<BoxedSocket, io::Error>>, NoiseStream<BoxedSocket>>;
 
 enum TransportPeerManager {
     #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
     Memory(MemoryPeerManager),
     Tcp(TcpPeerManager),
+    Quic(QuicPeerManager),
+    Custom(CustomPeerManager),
 }
 
 pub struct PeerManagerBuilder {
     network_context: Arc<NetworkContext>,
     time_service: TimeService,
+    identity_key: IdentityKey,
     transport_context: Option<TransportContext>,
     peer_manager_context: Option<PeerManagerContext>,
     // TODO(philiphayes): better support multiple listening addrs
     peer_manager: Option<TransportPeerManager>,
     // ListenAddress will be updated when the PeerManager is built
     listen_address: NetworkAddress,
+    inbound_connection_limit: Arc<AtomicUsize>,
 }
 
 impl PeerManagerBuilder {
@@ -218,7 +278,32 @@ impl PeerManagerBuilder {
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        peer_reputation_config: Option<PeerReputationConfig>,
+        protocol_rate_limit_config: Option<ProtocolRateLimitConfig>,
+        wire_compression_config: Option<WireCompressionConfig>,
+        inbound_connection_limit_config: Option<InboundConnectionLimitConfig>,
+        keep_alive_config: Option<KeepAliveConfig>,
+        socks5_proxy_config: Option<Socks5ProxyConfig>,
+        handshake_limit_config: Option<HandshakeLimitConfig>,
     ) -> Self {
+        let compression_threshold = wire_compression_config
+            .filter(|config| config.enabled)
+            .map(|config| config.threshold_bytes);
+        let inbound_connection_limit_per_ip = inbound_connection_limit_config
+            .filter(|config| config.enabled)
+            .map(|config| config.max_connections_per_ip);
+        let keep_alive_config = keep_alive_config.filter(|config| config.enabled);
+        let inbound_connection_limit = Arc::new(AtomicUsize::new(inbound_connection_limit));
+        let handshake_limit_config = handshake_limit_config.filter(|config| config.enabled);
+        let socks5_proxy = socks5_proxy_config
+            .filter(|config| config.enabled)
+            .map(|config| Socks5Proxy {
+                address: config.address,
+                credentials: config
+                    .username
+                    .zip(config.password)
+                    .map(|(username, password)| Socks5Credentials { username, password }),
+            });
         // Setup channel to send requests to peer manager.
         let (pm_reqs_tx, pm_reqs_rx) = diem_channel::new(
             QueueStyle::FIFO,
@@ -228,17 +313,27 @@ impl PeerManagerBuilder {
         // Setup channel to send connection requests to peer manager.
         let (connection_reqs_tx, connection_reqs_rx) =
             diem_channel::new(QueueStyle::FIFO, channel_size, None);
+        let (identity_key, mutual_authentication) = match authentication_mode {
+            AuthenticationMode::MaybeMutual(key) => (IdentityKey::new(key), false),
+            AuthenticationMode::Mutual(key) => (IdentityKey::new(key), true),
+        };
 
         Self {
             network_context,
             time_service,
+            identity_key: identity_key.clone(),
             transport_context: Some(TransportContext::new(
                 chain_id,
                 Vec::new(),
                 Vec::new(),
-                authentication_mode,
+                identity_key,
+                mutual_authentication,
                 trusted_peers.clone(),
                 enable_proxy_protocol,
+                compression_threshold.is_some(),
+                keep_alive_config.is_some(),
+                socks5_proxy,
+                handshake_limit_config,
             )),
             peer_manager_context: Some(PeerManagerContext::new(
                 pm_reqs_tx,
@@ -252,12 +347,18 @@ impl PeerManagerBuilder {
                 max_concurrent_network_reqs,
                 channel_size,
                 max_frame_size,
-                inbound_connection_limit,
+                inbound_connection_limit.clone(),
                 inbound_rate_limit_config,
                 outbound_rate_limit_config,
+                peer_reputation_config,
+                protocol_rate_limit_config,
+                compression_threshold,
+                inbound_connection_limit_per_ip,
+                keep_alive_config,
             )),
             peer_manager: None,
             listen_address,
+            inbound_connection_limit,
         }
     }
 
@@ -265,6 +366,17 @@ impl PeerManagerBuilder {
         self.listen_address.clone()
     }
 
+    /// The identity key of the network, through which it can be rotated while the network runs
+    pub fn identity_key(&self) -> IdentityKey {
+        self.identity_key.clone()
+    }
+
+    /// The limit of the inbound connections of unknown peers, through which it can be updated
+    /// while the network runs
+    pub fn inbound_connection_limit(&self) -> Arc<AtomicUsize> {
+        self.inbound_connection_limit.clone()
+    }
+
     pub fn connection_reqs_tx(&self) -> diem_channel::Sender<PeerId, ConnectionRequest> {
         self.peer_manager_context
             .as_ref()
@@ -273,6 +385,16 @@ impl PeerManagerBuilder {
             .clone()
     }
 
+    /// Runs the connections over `transport` in place of the transport picked from the listen
+    /// address, which must then be an address `transport` listens on.
+    pub fn set_transport(&mut self, transport: impl DiemTransport) -> &mut Self {
+        self.transport_context
+            .as_mut()
+            .expect("Cannot set the transport once PeerManager has been built")
+            .base_transport = Some(transport.into_boxed());
+        self
+    }
+
     /// Create the configured transport and start PeerManager.
     /// Return the actual NetworkAddress over which this peer is listening.
     pub fn build(&mut self, executor: &Handle) -> &mut Self {
@@ -286,23 +408,69 @@ impl PeerManagerBuilder {
         let protos = transport_context.supported_protocols();
         let chain_id = transport_context.chain_id;
         let enable_proxy_protocol = transport_context.enable_proxy_protocol;
+        let enable_compression = transport_context.enable_compression;
+        let enable_keep_alive = transport_context.enable_keep_alive;
+        let handshake_limit_config = transport_context.handshake_limit_config;
+        let tcp_transport = TcpTransport {
+            socks5_proxy: transport_context.socks5_proxy.clone(),
+            ..DIEM_TCP_TRANSPORT
+        };
 
-        let (key, auth_mode) = match transport_context.authentication_mode {
-            AuthenticationMode::MaybeMutual(key) => (
-                key,
-                HandshakeAuthMode::maybe_mutual(transport_context.trusted_peers),
-            ),
-            AuthenticationMode::Mutual(key) => (
-                key,
-                HandshakeAuthMode::mutual(transport_context.trusted_peers),
-            ),
+        let key = transport_context.identity_key;
+        let auth_mode = if transport_context.mutual_authentication {
+            HandshakeAuthMode::mutual(transport_context.trusted_peers)
+        } else {
+            HandshakeAuthMode::maybe_mutual(transport_context.trusted_peers)
         };
 
+        if let Some(base_transport) = transport_context.base_transport {
+            self.peer_manager = Some(TransportPeerManager::Custom(self.build_with_transport(
+                DiemNetTransport::new(
+                    base_transport,
+                    self.network_context.clone(),
+                    self.time_service.clone(),
+                    key,
+                    auth_mode,
+                    HANDSHAKE_VERSION,
+                    chain_id,
+                    protos,
+                    enable_proxy_protocol,
+                    enable_compression,
+                    enable_keep_alive,
+                    handshake_limit_config,
+                ),
+                executor,
+            )));
+            return self;
+        }
+
         self.peer_manager = match self.listen_address.as_slice() {
             [Ip4(_), Tcp(_)] | [Ip6(_), Tcp(_)] => {
                 Some(TransportPeerManager::Tcp(self.build_with_transport(
                     DiemNetTransport::new(
-                        DIEM_TCP_TRANSPORT.clone(),
+                        tcp_transport,
+                        self.network_context.clone(),
+                        self.time_service.clone(),
+                        key,
+                        auth_mode,
+                        HANDSHAKE_VERSION,
+                        chain_id,
+                        protos,
+                        enable_proxy_protocol,
+                        enable_compression,
+                        enable_keep_alive,
+                        handshake_limit_config,
+                    ),
+                    executor,
+                )))
+            }
+            [Ip4(_), Udp(_), Quic] | [Ip6(_), Udp(_), Quic] => {
+                Some(TransportPeerManager::Quic(self.build_with_transport(
+                    DiemNetTransport::new(
+                        QuicOrTcpTransport {
+                            quic: DIEM_QUIC_TRANSPORT.clone(),
+                            tcp: tcp_transport,
+                        },
                         self.network_context.clone(),
                         self.time_service.clone(),
                         key,
@@ -311,6 +479,9 @@ impl PeerManagerBuilder {
                         chain_id,
                         protos,
                         enable_proxy_protocol,
+                        enable_compression,
+                        enable_keep_alive,
+                        handshake_limit_config,
                     ),
                     executor,
                 )))
@@ -327,12 +498,16 @@ impl PeerManagerBuilder {
                     chain_id,
                     protos,
                     enable_proxy_protocol,
+                    enable_compression,
+                    enable_keep_alive,
+                    handshake_limit_config,
                 ),
                 executor,
             ))),
             _ => panic!(
                 "{} Unsupported listen_address: '{}', expected '/memory/<port>', \
-                 '/ip4/<addr>/tcp/<port>', or '/ip6/<addr>/tcp/<port>'.",
+                 '/ip4/<addr>/tcp/<port>', '/ip6/<addr>/tcp/<port>', \
+                 '/ip4/<addr>/udp/<port>/quic', or '/ip6/<addr>/udp/<port>/quic'.",
                 self.network_context, self.listen_address
             ),
         };
@@ -365,6 +540,9 @@ impl PeerManagerBuilder {
             "outbound",
             pm_context.outbound_rate_limit_config,
         );
+        let protocol_byte_limits =
+            protocol_byte_limits(pm_context.protocol_rate_limit_config.as_ref())
+                .unwrap_or_else(|e| panic!("Invalid protocol rate limit config: {}", e));
         let peer_mgr = PeerManager::new(
             executor.clone(),
             self.time_service.clone(),
@@ -385,6 +563,11 @@ impl PeerManagerBuilder {
             pm_context.inbound_connection_limit,
             inbound_rate_limiters,
             outbound_rate_limiters,
+            PeerReputation::new(pm_context.peer_reputation_config, self.time_service.clone()),
+            protocol_byte_limits,
+            pm_context.compression_threshold,
+            pm_context.inbound_connection_limit_per_ip,
+            pm_context.keep_alive_config,
         );
 
         // PeerManager constructor appends a public key to the listen_address.
@@ -415,6 +598,8 @@ impl PeerManagerBuilder {
             #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
             TransportPeerManager::Memory(pm) => self.start_peer_manager(pm, executor),
             TransportPeerManager::Tcp(pm) => self.start_peer_manager(pm, executor),
+            TransportPeerManager::Quic(pm) => self.start_peer_manager(pm, executor),
+            TransportPeerManager::Custom(pm) => self.start_peer_manager(pm, executor),
         }
     }
 
@@ -14,7 +14,7 @@ use crate::{
     constants,
     counters::{self},
     logging::*,
-    peer::{Peer, PeerNotification, PeerRequest},
+    peer::{KeepAlive, Peer, PeerNotification, PeerRequest, ProtocolRateLimiters},
     transport::{
         Connection, ConnectionId, ConnectionMetadata, TSocket as TransportTSocket,
         TRANSPORT_TIMEOUT,
@@ -39,7 +39,10 @@ use std::{
     collections::{hash_map::Entry, HashMap},
     marker::PhantomData,
     net::{IpAddr, Ipv4Addr},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     time::Duration,
 };
 use tokio::runtime::Handle;
@@ -47,18 +50,19 @@ use tokio::runtime::Handle;
 pub mod builder;
 pub mod conn_notifs_channel;
 mod error;
+mod reputation;
 mod senders;
 #[cfg(test)]
 mod tests;
 mod transport;
 mod types;
 
-pub use self::error::PeerManagerError;
+pub use self::{error::PeerManagerError, reputation::PeerReputation};
 use crate::{
     application::storage::PeerMetadataStorage,
     peer_manager::transport::{TransportHandler, TransportRequest},
 };
-use diem_config::config::{PeerRole, PeerSet};
+use diem_config::config::{KeepAliveConfig, PeerRole, PeerSet, ProtocolByteLimit};
 use diem_infallible::RwLock;
 pub use senders::*;
 pub use types::*;
@@ -119,12 +123,23 @@ where
     channel_size: usize,
     /// Max network frame size
     max_frame_size: usize,
-    /// Inbound connection limit separate of outbound connections
-    inbound_connection_limit: usize,
+    /// Inbound connection limit separate of outbound connections, shared with the builder for the
+    /// limit to be updated while the network runs
+    inbound_connection_limit: Arc<AtomicUsize>,
     /// Keyed storage of all inbound rate limiters
     inbound_rate_limiters: IpAddrTokenBucketLimiter,
     /// Keyed storage of all outbound rate limiters
     outbound_rate_limiters: IpAddrTokenBucketLimiter,
+    /// Scores of the peers reported for misbehaving, and the banned peers
+    peer_reputation: PeerReputation,
+    /// Limits of the bytes sent to each peer by protocol
+    protocol_byte_limits: HashMap<ProtocolId, ProtocolByteLimit>,
+    /// Messages with a payload of at least this many bytes are compressed
+    compression_threshold: Option<usize>,
+    /// Inbound connection limit of unknown peers from a single IP
+    inbound_connection_limit_per_ip: Option<usize>,
+    /// Keep-alive pings of the connections which support them
+    keep_alive_config: Option<KeepAliveConfig>,
 }
 
 impl<TTransport, TSocket> PeerManager<TTransport, TSocket>
@@ -152,9 +167,14 @@ where
         channel_size: usize,
         max_concurrent_network_reqs: usize,
         max_frame_size: usize,
-        inbound_connection_limit: usize,
+        inbound_connection_limit: Arc<AtomicUsize>,
         inbound_rate_limiters: IpAddrTokenBucketLimiter,
         outbound_rate_limiters: IpAddrTokenBucketLimiter,
+        peer_reputation: PeerReputation,
+        protocol_byte_limits: HashMap<ProtocolId, ProtocolByteLimit>,
+        compression_threshold: Option<usize>,
+        inbound_connection_limit_per_ip: Option<usize>,
+        keep_alive_config: Option<KeepAliveConfig>,
     ) -> Self {
         let (transport_notifs_tx, transport_notifs_rx) = channel::new(
             channel_size,
@@ -199,6 +219,11 @@ where
             inbound_connection_limit,
             inbound_rate_limiters,
             outbound_rate_limiters,
+            peer_reputation,
+            protocol_byte_limits,
+            compression_threshold,
+            inbound_connection_limit_per_ip,
+            keep_alive_config,
         }
     }
 
@@ -285,6 +310,23 @@ where
         self.sample_connected_peers();
         match event {
             TransportNotification::NewConnection(mut conn) => {
+                if self
+                    .peer_reputation
+                    .is_banned(&conn.metadata.remote_peer_id)
+                {
+                    info!(
+                        NetworkSchema::new(&self.network_context)
+                            .connection_metadata_with_address(&conn.metadata),
+                        "{} Connection rejected since the peer is banned: {}",
+                        self.network_context,
+                        conn.metadata
+                    );
+                    counters::connections_rejected(&self.network_context, conn.metadata.origin)
+                        .inc();
+                    self.disconnect(conn);
+                    return;
+                }
+
                 match conn.metadata.origin {
                     ConnectionOrigin::Outbound => {
                         // TODO: This is right now a hack around having to feed trusted peers deeper in the outbound path.  Inbound ones are assigned at Noise handshake time.
@@ -308,6 +350,8 @@ where
                         // Everything below here is meant for unknown peers only, role comes from
                         // Noise handshake and if it's not `Unknown` it is trusted
                         if conn.metadata.role == PeerRole::Unknown {
+                            self.evict_inbound_connections_from_ip(&conn.metadata);
+
                             // TODO: Keep track of somewhere else to not take this hit in case of DDoS
                             // Count unknown inbound connections
                             let unknown_inbound_conns = self
@@ -329,7 +373,8 @@ where
                             if !self
                                 .active_peers
                                 .contains_key(&conn.metadata.remote_peer_id)
-                                && unknown_inbound_conns + 1 > self.inbound_connection_limit
+                                && unknown_inbound_conns + 1
+                                    > self.inbound_connection_limit.load(Ordering::Relaxed)
                             {
                                 info!(
                                     NetworkSchema::new(&self.network_context)
@@ -437,8 +482,30 @@ where
         self.sample_connected_peers();
         match request {
             ConnectionRequest::DialPeer(requested_peer_id, addr, response_tx) => {
+                // Don't dial banned peers
+                if self.peer_reputation.is_banned(&requested_peer_id) {
+                    debug!(
+                        NetworkSchema::new(&self.network_context).remote_peer(&requested_peer_id),
+                        "{} Peer {} is banned. Not dialing address {}",
+                        self.network_context,
+                        requested_peer_id.short_str(),
+                        addr
+                    );
+                    if let Err(send_err) =
+                        response_tx.send(Err(PeerManagerError::Banned(requested_peer_id)))
+                    {
+                        info!(
+                            NetworkSchema::new(&self.network_context)
+                                .remote_peer(&requested_peer_id),
+                            "{} Failed to notify that peer is banned for Peer {}: {:?}",
+                            self.network_context,
+                            requested_peer_id.short_str(),
+                            send_err
+                        );
+                    }
                 // Only dial peers which we aren't already connected with
-                if let Some((curr_connection, _)) = self.active_peers.get(&requested_peer_id) {
+                } else if let Some((curr_connection, _)) = self.active_peers.get(&requested_peer_id)
+                {
                     let error = PeerManagerError::AlreadyConnected(curr_connection.addr.clone());
                     debug!(
                         NetworkSchema::new(&self.network_context)
@@ -495,6 +562,87 @@ where
                     }
                 }
             }
+            ConnectionRequest::ReportMisbehavior(peer_id, misbehavior) => {
+                self.report_misbehavior(peer_id, misbehavior);
+            }
+        }
+    }
+
+    /// Lowers the score of the peer, and disconnects the peer if it got banned
+    fn report_misbehavior(&mut self, peer_id: PeerId, misbehavior: Misbehavior) {
+        counters::peer_misbehaviors(&self.network_context, misbehavior.as_str()).inc();
+        if !self.peer_reputation.report(peer_id, misbehavior) {
+            debug!(
+                NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                "{} Peer {} misbehaved: {}, score: {}",
+                self.network_context,
+                peer_id.short_str(),
+                misbehavior,
+                self.peer_reputation.score(&peer_id)
+            );
+            return;
+        }
+
+        warn!(
+            NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+            "{} Banning Peer {} after misbehaving: {}",
+            self.network_context,
+            peer_id.short_str(),
+            misbehavior
+        );
+        counters::peer_bans(&self.network_context).inc();
+        if let Some((conn_metadata, sender)) = self.active_peers.remove(&peer_id) {
+            self.peer_metadata_storage.remove_connection(&conn_metadata);
+            // This triggers a disconnect.
+            drop(sender);
+        }
+    }
+
+    /// Evicts the oldest inbound connections of unknown peers from the IP of a new inbound
+    /// connection, so the new connection fits in the per IP limit
+    fn evict_inbound_connections_from_ip(&mut self, new_conn_metadata: &ConnectionMetadata) {
+        let limit = match self.inbound_connection_limit_per_ip {
+            Some(limit) => limit,
+            None => return,
+        };
+        let ip_addr = match new_conn_metadata.addr.find_ip_addr() {
+            Some(ip_addr) => ip_addr,
+            None => return,
+        };
+
+        let mut conns_from_ip: Vec<_> = {
+            let trusted_peers = self.trusted_peers.read();
+            self.active_peers
+                .iter()
+                .filter(|(peer_id, (metadata, _))| {
+                    // A new connection with a connected peer replaces the existing one anyways
+                    **peer_id != new_conn_metadata.remote_peer_id
+                        && metadata.origin == ConnectionOrigin::Inbound
+                        && metadata.addr.find_ip_addr() == Some(ip_addr)
+                        && trusted_peers
+                            .get(peer_id)
+                            .map_or(true, |peer| peer.role == PeerRole::Unknown)
+                })
+                .map(|(peer_id, (metadata, _))| (metadata.connection_id, *peer_id))
+                .collect()
+        };
+        // Connection ids increase over time, so the oldest connections come first
+        conns_from_ip.sort_unstable();
+        let num_evicted = (conns_from_ip.len() + 1).saturating_sub(limit);
+        for (_, peer_id) in conns_from_ip.into_iter().take(num_evicted) {
+            info!(
+                NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                "{} Evicting connection with Peer {} over the connection limit of IP {}",
+                self.network_context,
+                peer_id.short_str(),
+                ip_addr
+            );
+            counters::connections_evicted(&self.network_context).inc();
+            if let Some((conn_metadata, sender)) = self.active_peers.remove(&peer_id) {
+                self.peer_metadata_storage.remove_connection(&conn_metadata);
+                // This triggers a disconnect.
+                drop(sender);
+            }
         }
     }
 
@@ -702,6 +850,15 @@ where
             self.max_frame_size,
             Some(inbound_rate_limiter),
             Some(outbound_rate_limiter),
+            ProtocolRateLimiters::new(&self.network_context, peer_id, &self.protocol_byte_limits),
+            self.compression_threshold,
+            self.keep_alive_config.map(|config| {
+                KeepAlive::new(
+                    config,
+                    self.time_service.now(),
+                    self.peer_metadata_storage.clone(),
+                )
+            }),
         );
         self.executor.spawn(peer.start());
 
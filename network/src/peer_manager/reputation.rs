@@ -0,0 +1,132 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Scores of the peers reported for misbehaving by the applications (e.g. consensus, mempool and
+//! state sync), used by the PeerManager to disconnect and ban the peers which misbehave too often.
+//!
+//! Every peer starts with a score of 0. Each reported misbehavior lowers the score of the peer by
+//! its penalty, and the score recovers exponentially back to 0 over time. A peer whose score falls
+//! to the ban score is banned for the configured duration, after which it starts over from 0.
+
+use crate::peer_manager::types::Misbehavior;
+use diem_config::config::PeerReputationConfig;
+use diem_time_service::{TimeService, TimeServiceTrait};
+use diem_types::PeerId;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Scores whose distance to 0 decayed below this are forgotten
+const NEGLIGIBLE_SCORE: f64 = 1.0;
+
+struct Score {
+    value: f64,
+    updated_at: Instant,
+}
+
+pub struct PeerReputation {
+    enabled: bool,
+    ban_score: f64,
+    score_half_life: Duration,
+    ban_duration: Duration,
+    time_service: TimeService,
+    scores: HashMap<PeerId, Score>,
+    /// Banned peers, with the end of their ban
+    bans: HashMap<PeerId, Instant>,
+}
+
+impl PeerReputation {
+    pub fn new(config: Option<PeerReputationConfig>, time_service: TimeService) -> Self {
+        let config = match config {
+            Some(config) if config.enabled => config,
+            _ => return Self::open(time_service),
+        };
+        Self {
+            enabled: true,
+            ban_score: config.ban_score as f64,
+            score_half_life: Duration::from_millis(config.score_half_life_ms.max(1)),
+            ban_duration: Duration::from_millis(config.ban_duration_ms),
+            time_service,
+            scores: HashMap::new(),
+            bans: HashMap::new(),
+        }
+    }
+
+    /// Never bans any peer
+    pub fn open(time_service: TimeService) -> Self {
+        Self {
+            enabled: false,
+            ban_score: f64::NEG_INFINITY,
+            score_half_life: Duration::from_secs(1),
+            ban_duration: Duration::from_secs(0),
+            time_service,
+            scores: HashMap::new(),
+            bans: HashMap::new(),
+        }
+    }
+
+    /// Lowers the score of `peer_id` by the penalty of `misbehavior`. Returns true if the peer just
+    /// got banned.
+    pub fn report(&mut self, peer_id: PeerId, misbehavior: Misbehavior) -> bool {
+        if !self.enabled || self.is_banned(&peer_id) {
+            return false;
+        }
+
+        let now = self.time_service.now();
+        self.forget_negligible_scores(now);
+        let score = self.score_at(&peer_id, now) - misbehavior.penalty();
+        if score <= self.ban_score {
+            self.scores.remove(&peer_id);
+            self.bans.insert(peer_id, now + self.ban_duration);
+            return true;
+        }
+        self.scores.insert(
+            peer_id,
+            Score {
+                value: score,
+                updated_at: now,
+            },
+        );
+        false
+    }
+
+    /// Returns true if `peer_id` is banned, forgetting its ban once it's over
+    pub fn is_banned(&mut self, peer_id: &PeerId) -> bool {
+        match self.bans.get(peer_id) {
+            Some(banned_until) if *banned_until > self.time_service.now() => true,
+            Some(_) => {
+                self.bans.remove(peer_id);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// The current score of `peer_id`
+    pub fn score(&self, peer_id: &PeerId) -> f64 {
+        self.score_at(peer_id, self.time_service.now())
+    }
+
+    fn score_at(&self, peer_id: &PeerId, now: Instant) -> f64 {
+        self.scores.get(peer_id).map_or(0.0, |score| {
+            let half_lives = now
+                .saturating_duration_since(score.updated_at)
+                .as_secs_f64()
+                / self.score_half_life.as_secs_f64();
+            score.value * 0.5f64.powf(half_lives)
+        })
+    }
+
+    fn forget_negligible_scores(&mut self, now: Instant) {
+        let negligible_peers: Vec<_> = self
+            .scores
+            .keys()
+            .filter(|peer_id| self.score_at(peer_id, now).abs() < NEGLIGIBLE_SCORE)
+            .cloned()
+            .collect();
+        for peer_id in negligible_peers {
+            self.scores.remove(&peer_id);
+        }
+    }
+}
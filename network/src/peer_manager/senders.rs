@@ -14,7 +14,10 @@ use diem_types::{network_address::NetworkAddress, PeerId};
 use futures::channel::oneshot;
 use std::time::Duration;
 
-use crate::peer_manager::{types::PeerManagerRequest, ConnectionRequest, PeerManagerError};
+use crate::peer_manager::{
+    types::{Misbehavior, PeerManagerRequest},
+    ConnectionRequest, PeerManagerError,
+};
 
 /// Convenience wrapper which makes it easy to issue communication requests and await the responses
 /// from PeerManager.
@@ -131,4 +134,19 @@ impl ConnectionRequestSender {
             .push(peer, ConnectionRequest::DisconnectPeer(peer, oneshot_tx))?;
         oneshot_rx.await?
     }
+
+    /// Reports a misbehavior of a peer, which is disconnected and banned once it misbehaved too
+    /// often. The function returns once the report has been enqueued on the network actor's event
+    /// queue.
+    pub fn report_misbehavior(
+        &mut self,
+        peer: PeerId,
+        misbehavior: Misbehavior,
+    ) -> Result<(), PeerManagerError> {
+        self.inner.push(
+            peer,
+            ConnectionRequest::ReportMisbehavior(peer, misbehavior),
+        )?;
+        Ok(())
+    }
 }
@@ -7,7 +7,8 @@ use crate::{
     peer::DisconnectReason,
     peer_manager::{
         conn_notifs_channel, error::PeerManagerError, ConnectionNotification, ConnectionRequest,
-        PeerManager, PeerManagerNotification, PeerManagerRequest, TransportNotification,
+        Misbehavior, PeerManager, PeerManagerNotification, PeerManagerRequest, PeerReputation,
+        TransportNotification,
     },
     protocols::wire::{
         handshake::v1::MessagingProtocolVersion,
@@ -21,7 +22,7 @@ use anyhow::anyhow;
 use bytes::Bytes;
 use channel::{diem_channel, message_queues::QueueStyle};
 use diem_config::{
-    config::{PeerRole, MAX_INBOUND_CONNECTIONS},
+    config::{PeerReputationConfig, PeerRole, MAX_INBOUND_CONNECTIONS},
     network_id::NetworkContext,
 };
 use diem_infallible::RwLock;
@@ -33,7 +34,10 @@ use memsocket::MemorySocket;
 use netcore::transport::{
     boxed::BoxedTransport, memory::MemoryTransport, ConnectionOrigin, TransportExt,
 };
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{atomic::AtomicUsize, Arc},
+};
 use tokio::runtime::Handle;
 use tokio_util::compat::{
     FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt,
@@ -113,9 +117,14 @@ fn build_test_peer_manager(
         constants::NETWORK_CHANNEL_SIZE,
         constants::MAX_CONCURRENT_NETWORK_REQS,
         constants::MAX_FRAME_SIZE,
-        MAX_INBOUND_CONNECTIONS,
+        Arc::new(AtomicUsize::new(MAX_INBOUND_CONNECTIONS)),
         TokenBucketRateLimiter::open("inbound"),
         TokenBucketRateLimiter::open("outbound"),
+        PeerReputation::new(Some(PeerReputationConfig::default()), TimeService::mock()),
+        HashMap::new(),
+        None,
+        None,
+        None,
     );
 
     (
@@ -640,3 +649,164 @@ fn test_dial_disconnect() {
 
     runtime.block_on(test);
 }
+
+#[test]
+fn test_peer_reputation() {
+    let time_service = TimeService::mock();
+    let config = PeerReputationConfig {
+        ban_score: -100,
+        score_half_life_ms: 60_000,
+        ban_duration_ms: 600_000,
+        enabled: true,
+    };
+    let mut reputation = PeerReputation::new(Some(config), time_service.clone());
+    let peer_id = PeerId::random();
+
+    assert!(!reputation.report(peer_id, Misbehavior::InvalidSignature));
+    assert_eq!(reputation.score(&peer_id), -50.0);
+
+    // the score recovers half of the way back to 0 every half-life
+    time_service.clone().into_mock().advance_ms(60_000);
+    assert_eq!(reputation.score(&peer_id), -25.0);
+    assert!(!reputation.report(peer_id, Misbehavior::InvalidSignature));
+    assert_eq!(reputation.score(&peer_id), -75.0);
+    assert!(!reputation.is_banned(&peer_id));
+
+    // falling to the ban score bans the peer, and restarts its score from 0
+    assert!(!reputation.report(peer_id, Misbehavior::MalformedMessage));
+    assert!(reputation.report(peer_id, Misbehavior::InvalidSignature));
+    assert!(reputation.is_banned(&peer_id));
+    assert_eq!(reputation.score(&peer_id), 0.0);
+    // banned peers aren't reported again
+    assert!(!reputation.report(peer_id, Misbehavior::InvalidSignature));
+
+    time_service.into_mock().advance_ms(600_000);
+    assert!(!reputation.is_banned(&peer_id));
+
+    // without config, peers are never banned
+    let mut reputation = PeerReputation::new(None, TimeService::mock());
+    for _ in 0..10 {
+        assert!(!reputation.report(peer_id, Misbehavior::InvalidSignature));
+    }
+    assert!(!reputation.is_banned(&peer_id));
+}
+
+#[test]
+fn test_ban_misbehaving_peer() {
+    ::diem_logger::Logger::init_for_testing();
+    let runtime = ::tokio::runtime::Runtime::new().unwrap();
+
+    let ids = ordered_peer_ids(2);
+    let (mut peer_manager, _request_tx, _connection_reqs_tx, _hello_rx, mut conn_status_rx) =
+        build_test_peer_manager(runtime.handle().clone(), ids[1]);
+
+    let test = async move {
+        let (outbound, _inbound) = build_test_connection();
+        peer_manager.add_peer(create_connection(
+            outbound,
+            ids[0],
+            NetworkAddress::mock(),
+            ConnectionOrigin::Outbound,
+            ConnectionId::from(0),
+        ));
+        let conn_notif = conn_status_rx.next().await.unwrap();
+        assert!(matches!(conn_notif, ConnectionNotification::NewPeer(_, _)));
+
+        // Two invalid signatures reach the default ban score, which disconnects the peer
+        for _ in 0..2 {
+            peer_manager
+                .handle_outbound_connection_request(ConnectionRequest::ReportMisbehavior(
+                    ids[0],
+                    Misbehavior::InvalidSignature,
+                ))
+                .await;
+        }
+        assert_peer_disconnected_event(
+            ids[0],
+            ConnectionOrigin::Outbound,
+            DisconnectReason::Requested,
+            &mut peer_manager,
+        )
+        .await;
+        let conn_notif = conn_status_rx.next().await.unwrap();
+        assert!(matches!(
+            conn_notif,
+            ConnectionNotification::LostPeer(_, _, _)
+        ));
+
+        // The banned peer isn't dialed
+        let (dial_resp_tx, dial_resp_rx) = oneshot::channel();
+        peer_manager
+            .handle_outbound_connection_request(ConnectionRequest::DialPeer(
+                ids[0],
+                NetworkAddress::mock(),
+                dial_resp_tx,
+            ))
+            .await;
+        assert!(matches!(
+            dial_resp_rx.await.unwrap(),
+            Err(PeerManagerError::Banned(_))
+        ));
+
+        // And its connections are rejected
+        let (outbound, _inbound) = build_test_connection();
+        peer_manager.handle_connection_event(TransportNotification::NewConnection(
+            create_connection(
+                outbound,
+                ids[0],
+                NetworkAddress::mock(),
+                ConnectionOrigin::Inbound,
+                ConnectionId::from(1),
+            ),
+        ));
+        assert!(!peer_manager.active_peers.contains_key(&ids[0]));
+    };
+
+    runtime.block_on(test);
+}
+
+#[test]
+fn test_evict_inbound_connections_over_ip_limit() {
+    ::diem_logger::Logger::init_for_testing();
+    let runtime = ::tokio::runtime::Runtime::new().unwrap();
+
+    let ids = ordered_peer_ids(5);
+    let (mut peer_manager, _request_tx, _connection_reqs_tx, _hello_rx, _conn_status_rx) =
+        build_test_peer_manager(runtime.handle().clone(), ids[4]);
+    peer_manager.inbound_connection_limit_per_ip = Some(2);
+
+    let test = async move {
+        let addr: NetworkAddress = "/ip4/1.2.3.4/tcp/6180".parse().unwrap();
+        let other_addr: NetworkAddress = "/ip4/5.6.7.8/tcp/6180".parse().unwrap();
+        let mut sockets = Vec::new();
+        for (i, addr) in [&addr, &addr, &other_addr, &addr].iter().enumerate() {
+            let (outbound, inbound) = build_test_connection();
+            sockets.push(inbound);
+            peer_manager.handle_connection_event(TransportNotification::NewConnection(
+                create_connection(
+                    outbound,
+                    ids[i],
+                    (*addr).clone(),
+                    ConnectionOrigin::Inbound,
+                    ConnectionId::from(i as u32),
+                ),
+            ));
+        }
+
+        // The third connection from the same IP evicts the oldest one, leaving the connection
+        // from the other IP alone
+        assert_peer_disconnected_event(
+            ids[0],
+            ConnectionOrigin::Inbound,
+            DisconnectReason::Requested,
+            &mut peer_manager,
+        )
+        .await;
+        assert!(!peer_manager.active_peers.contains_key(&ids[0]));
+        for peer_id in &ids[1..4] {
+            assert!(peer_manager.active_peers.contains_key(peer_id));
+        }
+    };
+
+    runtime.block_on(test);
+}
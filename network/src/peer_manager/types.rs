@@ -44,6 +44,45 @@ pub enum ConnectionRequest {
         PeerId,
         #[serde(skip)] oneshot::Sender<Result<(), PeerManagerError>>,
     ),
+    /// Lowers the score of a peer, which is disconnected and banned once its score is too low.
+    /// Ignored unless a `PeerReputationConfig` is set.
+    ReportMisbehavior(PeerId, Misbehavior),
+}
+
+/// Protocol violations of peers, reported by the applications
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize)]
+pub enum Misbehavior {
+    /// A message carrying an invalid signature or proof
+    InvalidSignature,
+    /// A message that can't be deserialized or breaks the rules of its protocol
+    MalformedMessage,
+    /// Unsolicited, duplicate or excessive messages
+    Spam,
+}
+
+impl Misbehavior {
+    /// How much the misbehavior lowers the score of the peer
+    pub fn penalty(self) -> f64 {
+        match self {
+            Misbehavior::InvalidSignature => 50.0,
+            Misbehavior::MalformedMessage => 20.0,
+            Misbehavior::Spam => 5.0,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Misbehavior::InvalidSignature => "invalid_signature",
+            Misbehavior::MalformedMessage => "malformed_message",
+            Misbehavior::Spam => "spam",
+        }
+    }
+}
+
+impl fmt::Display for Misbehavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 #[derive(Clone, PartialEq, Serialize)]
@@ -7,7 +7,7 @@ pub use crate::protocols::rpc::error::RpcError;
 use crate::{
     error::NetworkError,
     peer_manager::{
-        ConnectionNotification, ConnectionRequestSender, PeerManagerNotification,
+        ConnectionNotification, ConnectionRequestSender, Misbehavior, PeerManagerNotification,
         PeerManagerRequestSender,
     },
     transport::ConnectionMetadata,
@@ -246,6 +246,18 @@ impl<TMessage> NetworkSender<TMessage> {
         self.connection_reqs_tx.disconnect_peer(peer).await?;
         Ok(())
     }
+
+    /// Report a misbehavior of a given Peer, which is disconnected and banned once it misbehaved
+    /// too often.
+    pub fn report_misbehavior(
+        &mut self,
+        peer: PeerId,
+        misbehavior: Misbehavior,
+    ) -> Result<(), NetworkError> {
+        self.connection_reqs_tx
+            .report_misbehavior(peer, misbehavior)?;
+        Ok(())
+    }
 }
 
 impl<TMessage: Message> NetworkSender<TMessage> {
@@ -47,6 +47,9 @@ pub enum RpcError {
 
     #[error("Rpc timed out")]
     TimedOut,
+
+    #[error("Rpc dropped over the rate limit of its protocol")]
+    RateLimited,
 }
 
 impl From<PeerManagerError> for RpcError {
@@ -12,11 +12,17 @@
 //!
 //! [DiemNet Handshake v1 Specification]: https://github.com/diem/diem/blob/main/specifications/network/handshake-v1.md
 
-use anyhow::anyhow;
-use diem_config::network_id::NetworkId;
+use crate::counters;
+use anyhow::{anyhow, ensure};
+use diem_config::{config::MAX_FRAME_SIZE, network_id::NetworkId};
 use diem_types::chain_id::ChainId;
-use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, convert::TryInto, fmt, iter::Iterator};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    convert::{TryFrom, TryInto},
+    fmt,
+    iter::Iterator,
+};
 use thiserror::Error;
 
 #[cfg(any(test, feature = "fuzzing"))]
@@ -42,6 +48,12 @@ pub enum ProtocolId {
     HealthCheckerRpc = 5,
     // json provides flexibility for backwards compatible upgrade
     ConsensusDirectSendJSON = 6,
+    ConsensusObserverDirectSend = 7,
+    // bcs, compressed with lz4 or zstd to reduce the bandwidth of large block payloads
+    ConsensusDirectSendLz4 = 8,
+    ConsensusDirectSendZstd = 9,
+    // bcs compressed with zstd, to reduce the bandwidth of transaction broadcasts
+    MempoolDirectSendZstd = 10,
 }
 
 impl ProtocolId {
@@ -55,6 +67,10 @@ impl ProtocolId {
             DiscoveryDirectSend => "DiscoveryDirectSend",
             HealthCheckerRpc => "HealthCheckerRpc",
             ConsensusDirectSendJSON => "ConsensusDirectSendJson",
+            ConsensusObserverDirectSend => "ConsensusObserverDirectSend",
+            ConsensusDirectSendLz4 => "ConsensusDirectSendLz4",
+            ConsensusDirectSendZstd => "ConsensusDirectSendZstd",
+            MempoolDirectSendZstd => "MempoolDirectSendZstd",
         }
     }
 
@@ -67,6 +83,10 @@ impl ProtocolId {
             ProtocolId::DiscoveryDirectSend,
             ProtocolId::HealthCheckerRpc,
             ProtocolId::ConsensusDirectSendJSON,
+            ProtocolId::ConsensusObserverDirectSend,
+            ProtocolId::ConsensusDirectSendLz4,
+            ProtocolId::ConsensusDirectSendZstd,
+            ProtocolId::MempoolDirectSendZstd,
         ]
     }
 
@@ -75,20 +95,82 @@ impl ProtocolId {
             ProtocolId::ConsensusDirectSendJSON => {
                 serde_json::to_vec(value).map_err(|e| anyhow!("{:?}", e))
             }
+            ProtocolId::ConsensusDirectSendLz4
+            | ProtocolId::ConsensusDirectSendZstd
+            | ProtocolId::MempoolDirectSendZstd => {
+                let raw = bcs::to_bytes(value).map_err(|e| anyhow! {"{:?}", e})?;
+                let compressed = self.compress(&raw)?;
+                counters::compression_bytes(*self, counters::RAW_LABEL).inc_by(raw.len() as u64);
+                counters::compression_bytes(*self, counters::COMPRESSED_LABEL)
+                    .inc_by(compressed.len() as u64);
+                Ok(compressed)
+            }
             _ => bcs::to_bytes(value).map_err(|e| anyhow! {"{:?}", e}),
         }
     }
 
-    pub fn from_bytes<'a, T: Deserialize<'a>>(&self, bytes: &'a [u8]) -> anyhow::Result<T> {
+    pub fn from_bytes<T: DeserializeOwned>(&self, bytes: &[u8]) -> anyhow::Result<T> {
         match self {
             ProtocolId::ConsensusDirectSendJSON => {
                 serde_json::from_slice(bytes).map_err(|e| anyhow!("{:?}", e))
             }
+            ProtocolId::ConsensusDirectSendLz4
+            | ProtocolId::ConsensusDirectSendZstd
+            | ProtocolId::MempoolDirectSendZstd => {
+                let raw = self.decompress(bytes)?;
+                bcs::from_bytes(&raw).map_err(|e| anyhow! {"{:?}", e})
+            }
             _ => bcs::from_bytes(bytes).map_err(|e| anyhow! {"{:?}", e}),
         }
     }
+
+    fn compress(&self, raw: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            // the uncompressed size is prepended, so that it can be checked before decompressing
+            ProtocolId::ConsensusDirectSendLz4 => lz4::block::compress(raw, None, true),
+            ProtocolId::ConsensusDirectSendZstd | ProtocolId::MempoolDirectSendZstd => {
+                zstd::bulk::compress(raw, ZSTD_LEVEL)
+            }
+            _ => return Err(anyhow!("{} does not support compression", self)),
+        }
+        .map_err(|e| anyhow!("{:?}", e))
+    }
+
+    /// Decompressed messages are bounded by the max frame size, so a small compressed message
+    /// from a malicious peer cannot make us allocate an arbitrary large buffer.
+    fn decompress(&self, compressed: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            ProtocolId::ConsensusDirectSendLz4 => {
+                ensure!(
+                    compressed.len() >= 4,
+                    "Lz4 message is missing its size prefix"
+                );
+                let size = i32::from_le_bytes([
+                    compressed[0],
+                    compressed[1],
+                    compressed[2],
+                    compressed[3],
+                ]);
+                ensure!(
+                    usize::try_from(size).map_or(false, |size| size <= MAX_FRAME_SIZE),
+                    "Invalid lz4 uncompressed size {}",
+                    size
+                );
+                lz4::block::decompress(compressed, None)
+            }
+            ProtocolId::ConsensusDirectSendZstd | ProtocolId::MempoolDirectSendZstd => {
+                zstd::bulk::decompress(compressed, MAX_FRAME_SIZE)
+            }
+            _ => return Err(anyhow!("{} does not support compression", self)),
+        }
+        .map_err(|e| anyhow!("{:?}", e))
+    }
 }
 
+/// Favor speed over ratio, consensus messages are on the critical path and transaction broadcasts
+/// are frequent.
+const ZSTD_LEVEL: i32 = 1;
+
 impl fmt::Debug for ProtocolId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self)
@@ -158,14 +240,30 @@ impl SupportedProtocols {
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
 pub enum MessagingProtocolVersion {
     V1 = 0,
+    /// V1, plus zstd compressed messages (see `CompressedMsg`)
+    V2 = 1,
+    /// V2, plus keep-alive pings (see `NetworkMessage::Ping`)
+    V3 = 2,
 }
 
 impl MessagingProtocolVersion {
     fn as_str(&self) -> &str {
         match self {
             Self::V1 => "V1",
+            Self::V2 => "V2",
+            Self::V3 => "V3",
         }
     }
+
+    /// Returns true if compressed messages may be sent over this version
+    pub fn supports_compression(self) -> bool {
+        self >= Self::V2
+    }
+
+    /// Returns true if keep-alive pings may be sent over this version
+    pub fn supports_keep_alive(self) -> bool {
+        self >= Self::V3
+    }
 }
 
 impl fmt::Debug for MessagingProtocolVersion {
@@ -115,3 +115,53 @@ fn common_protocols() {
         h1.perform_handshake(&h2).unwrap()
     );
 }
+
+#[test]
+fn compression_negotiation() {
+    let protocols: SupportedProtocols = [ProtocolId::StateSyncDirectSend].iter().into();
+    let handshake_msg = |versions: &[MessagingProtocolVersion]| HandshakeMsg {
+        chain_id: ChainId::default(),
+        network_id: NetworkId::default(),
+        supported_protocols: versions
+            .iter()
+            .map(|version| (*version, protocols.clone()))
+            .collect(),
+    };
+    let compressing = handshake_msg(&[MessagingProtocolVersion::V1, MessagingProtocolVersion::V2]);
+    let not_compressing = handshake_msg(&[MessagingProtocolVersion::V1]);
+
+    let (version, _) = compressing.perform_handshake(&compressing).unwrap();
+    assert_eq!(version, MessagingProtocolVersion::V2);
+    assert!(version.supports_compression());
+
+    // Both ends have to enable compression
+    let (version, _) = compressing.perform_handshake(&not_compressing).unwrap();
+    assert_eq!(version, MessagingProtocolVersion::V1);
+    assert!(!version.supports_compression());
+    let (version, _) = not_compressing.perform_handshake(&compressing).unwrap();
+    assert_eq!(version, MessagingProtocolVersion::V1);
+}
+
+#[test]
+fn compressed_protocols_round_trip() {
+    let message: Vec<u64> = (0..10_000).map(|i| i % 7).collect();
+    for protocol in &[
+        ProtocolId::ConsensusDirectSendLz4,
+        ProtocolId::ConsensusDirectSendZstd,
+        ProtocolId::MempoolDirectSendZstd,
+    ] {
+        let bytes = protocol.to_bytes(&message).unwrap();
+        assert!(bytes.len() < bcs::to_bytes(&message).unwrap().len());
+        let decoded: Vec<u64> = protocol.from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, message);
+    }
+
+    // lz4 messages claiming an uncompressed size above the max frame size are rejected
+    let mut bytes = ProtocolId::ConsensusDirectSendLz4
+        .to_bytes(&message)
+        .unwrap();
+    bytes[..4].copy_from_slice(&(MAX_FRAME_SIZE as i32 + 1).to_le_bytes());
+    ProtocolId::ConsensusDirectSendLz4
+        .from_bytes::<Vec<u64>>(&bytes)
+        .unwrap_err();
+}
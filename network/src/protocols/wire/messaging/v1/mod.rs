@@ -9,7 +9,11 @@
 //! describes in greater detail how these messages are sent and received
 //! over-the-wire.
 
-use crate::protocols::wire::handshake::v1::ProtocolId;
+use crate::{
+    counters::{self, COMPRESSED_LABEL, RAW_LABEL, RECEIVED_LABEL, SENT_LABEL},
+    protocols::wire::handshake::v1::ProtocolId,
+};
+use anyhow::{anyhow, ensure};
 use bytes::Bytes;
 use diem_rate_limiter::{async_lib::AsyncRateLimiter, rate_limit::SharedBucket};
 use futures::{
@@ -43,6 +47,83 @@ pub enum NetworkMessage {
     RpcRequest(RpcRequest),
     RpcResponse(RpcResponse),
     DirectSendMsg(DirectSendMsg),
+    CompressedMsg(CompressedMsg),
+    /// Keep-alive ping, answered with a `Pong` carrying the same nonce. Only sent over connections
+    /// which negotiated
+    /// [`MessagingProtocolVersion::V3`](crate::protocols::wire::handshake::v1::MessagingProtocolVersion)
+    /// or above.
+    Ping(Nonce),
+    Pong(Nonce),
+}
+
+impl NetworkMessage {
+    /// Length of the application payload of the message
+    fn payload_len(&self) -> usize {
+        match self {
+            NetworkMessage::Error(_) | NetworkMessage::Ping(_) | NetworkMessage::Pong(_) => 0,
+            NetworkMessage::RpcRequest(request) => request.raw_request.len(),
+            NetworkMessage::RpcResponse(response) => response.raw_response.len(),
+            NetworkMessage::DirectSendMsg(message) => message.raw_msg.len(),
+            NetworkMessage::CompressedMsg(message) => message.raw_msg.len(),
+        }
+    }
+
+    /// Compresses the message if its payload is at least `threshold` bytes, and compression makes
+    /// it smaller. Messages which are already compressed, e.g. by their application protocol,
+    /// are sent as is.
+    pub fn compress(self, threshold: usize) -> Self {
+        if matches!(self, NetworkMessage::CompressedMsg(_)) || self.payload_len() < threshold {
+            return self;
+        }
+        let raw = match bcs::to_bytes(&self) {
+            Ok(raw) => raw,
+            Err(_) => return self,
+        };
+        match zstd::bulk::compress(&raw, WIRE_ZSTD_LEVEL) {
+            Ok(compressed) if compressed.len() < raw.len() => {
+                counters::wire_compression_bytes(SENT_LABEL, RAW_LABEL).inc_by(raw.len() as u64);
+                counters::wire_compression_bytes(SENT_LABEL, COMPRESSED_LABEL)
+                    .inc_by(compressed.len() as u64);
+                NetworkMessage::CompressedMsg(CompressedMsg {
+                    raw_msg: compressed,
+                })
+            }
+            _ => self,
+        }
+    }
+}
+
+/// Favor speed over ratio, large messages are mostly state sync chunks and consensus proposals.
+const WIRE_ZSTD_LEVEL: i32 = 1;
+
+/// A [`NetworkMessage`] compressed with zstd. Only sent over connections which negotiated
+/// [`MessagingProtocolVersion::V2`](crate::protocols::wire::handshake::v1::MessagingProtocolVersion)
+/// or above.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct CompressedMsg {
+    /// Compressed bcs serialization of a NetworkMessage, which is not a CompressedMsg itself.
+    #[serde(with = "serde_bytes")]
+    pub raw_msg: Vec<u8>,
+}
+
+impl CompressedMsg {
+    /// Decompresses the inner message. Decompressed messages are bounded by `max_frame_size`, so
+    /// a small compressed message from a malicious peer cannot make us allocate an arbitrary large
+    /// buffer.
+    pub fn decompress(self, max_frame_size: usize) -> anyhow::Result<NetworkMessage> {
+        let raw = zstd::bulk::decompress(&self.raw_msg, max_frame_size)
+            .map_err(|e| anyhow!("Failed to decompress network message: {:?}", e))?;
+        counters::wire_compression_bytes(RECEIVED_LABEL, RAW_LABEL).inc_by(raw.len() as u64);
+        counters::wire_compression_bytes(RECEIVED_LABEL, COMPRESSED_LABEL)
+            .inc_by(self.raw_msg.len() as u64);
+        let message: NetworkMessage = bcs::from_bytes(&raw)?;
+        ensure!(
+            !matches!(message, NetworkMessage::CompressedMsg(_)),
+            "Compressed network messages can't be nested"
+        );
+        Ok(message)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
@@ -84,6 +165,9 @@ pub type RequestId = u32;
 /// Create alias Priority for u8.
 pub type Priority = u8;
 
+/// Create alias Nonce for u32.
+pub type Nonce = u32;
+
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
 pub struct RpcRequest {
@@ -46,6 +46,47 @@ fn rpc_request() -> bcs::Result<()> {
     Ok(())
 }
 
+#[test]
+fn compress_round_trip() {
+    let message = NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: ProtocolId::StateSyncDirectSend,
+        priority: 0,
+        raw_msg: vec![7; 1024],
+    });
+
+    // Messages under the threshold are not compressed
+    assert_eq!(message.clone().compress(1025), message);
+
+    let compressed = match message.clone().compress(1024) {
+        NetworkMessage::CompressedMsg(compressed) => compressed,
+        message => panic!("Expected CompressedMsg, received: {:?}", message),
+    };
+    assert!(compressed.raw_msg.len() < 1024);
+    assert_eq!(compressed.clone().decompress(1024 * 1024).unwrap(), message);
+
+    // Decompressed messages are bounded by the max frame size
+    compressed.decompress(512).unwrap_err();
+
+    // Incompressible messages are sent as is
+    let message = NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: ProtocolId::StateSyncDirectSend,
+        priority: 0,
+        raw_msg: (0..=255).collect(),
+    });
+    assert_eq!(message.clone().compress(0), message);
+}
+
+#[test]
+fn nested_compressed_messages_are_rejected() {
+    let inner = NetworkMessage::CompressedMsg(CompressedMsg {
+        raw_msg: vec![7; 1024],
+    });
+    let nested = CompressedMsg {
+        raw_msg: zstd::bulk::compress(&bcs::to_bytes(&inner).unwrap(), 1).unwrap(),
+    };
+    nested.decompress(1024 * 1024).unwrap_err();
+}
+
 #[test]
 fn libranet_wire_test_vectors() {
     let message = NetworkMessage::DirectSendMsg(DirectSendMsg {
@@ -171,6 +212,8 @@ fn arb_network_message(max_frame_size: usize) -> impl Strategy<Value = NetworkMe
         arb_rpc_request(max_frame_size).prop_map(NetworkMessage::RpcRequest),
         arb_rpc_response(max_frame_size).prop_map(NetworkMessage::RpcResponse),
         arb_direct_send_msg(max_frame_size).prop_map(NetworkMessage::DirectSendMsg),
+        any::<Nonce>().prop_map(NetworkMessage::Ping),
+        any::<Nonce>().prop_map(NetworkMessage::Pong),
     ]
     .prop_filter("larger than max frame size", move |msg| {
         bcs::serialized_size(&msg).unwrap() <= max_frame_size
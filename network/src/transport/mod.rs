@@ -2,15 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    counters,
     logging::NetworkSchema,
-    noise::{stream::NoiseStream, AntiReplayTimestamps, HandshakeAuthMode, NoiseUpgrader},
+    noise::{
+        stream::NoiseStream, AntiReplayTimestamps, HandshakeAuthMode, IdentityKey,
+        NoiseHandshakeError, NoiseUpgrader,
+    },
     protocols::{
         identity::exchange_handshake,
         wire::handshake::v1::{HandshakeMsg, MessagingProtocolVersion, SupportedProtocols},
     },
 };
 use diem_config::{
-    config::{PeerRole, HANDSHAKE_VERSION},
+    config::{HandshakeLimitConfig, PeerRole, HANDSHAKE_VERSION},
     network_id::{NetworkContext, NetworkId},
 };
 use diem_crypto::x25519;
@@ -18,15 +22,20 @@ use diem_logger::prelude::*;
 use diem_time_service::{timeout, TimeService, TimeServiceTrait};
 use diem_types::{
     chain_id::ChainId,
-    network_address::{parse_dns_tcp, parse_ip_tcp, parse_memory, NetworkAddress},
+    network_address::{
+        parse_dns_tcp, parse_dns_udp_quic, parse_ip_tcp, parse_ip_udp_quic, parse_memory,
+        NetworkAddress,
+    },
     PeerId,
 };
 use futures::{
-    future::{Future, FutureExt},
+    future::{self, Future, FutureExt},
     io::{AsyncRead, AsyncWrite},
     stream::{Stream, StreamExt, TryStreamExt},
 };
-use netcore::transport::{proxy_protocol, tcp, ConnectionOrigin, Transport};
+use netcore::transport::{
+    boxed::BoxedTransport, proxy_protocol, quic, tcp, ConnectionOrigin, Transport, TransportExt,
+};
 use serde::Serialize;
 use short_hex_str::AsShortHexStr;
 use std::{
@@ -51,6 +60,15 @@ pub const TRANSPORT_TIMEOUT: Duration = Duration::from_secs(30);
 /// TODO: Add ability to support more than one messaging protocol.
 pub const SUPPORTED_MESSAGING_PROTOCOL: MessagingProtocolVersion = MessagingProtocolVersion::V1;
 
+/// Messaging protocol version additionally supported when compression is enabled, negotiated with
+/// the peers which also enabled it.
+pub const COMPRESSION_MESSAGING_PROTOCOL: MessagingProtocolVersion = MessagingProtocolVersion::V2;
+
+/// Messaging protocol version additionally supported when keep-alive is enabled, negotiated with
+/// the peers which also enabled it. Versions are cumulative, so its connections also accept
+/// compressed messages.
+pub const KEEP_ALIVE_MESSAGING_PROTOCOL: MessagingProtocolVersion = MessagingProtocolVersion::V3;
+
 /// Global connection-id generator.
 static CONNECTION_ID_GENERATOR: ConnectionIdGenerator = ConnectionIdGenerator::new();
 
@@ -60,6 +78,14 @@ pub const DIEM_TCP_TRANSPORT: tcp::TcpTransport = tcp::TcpTransport {
     ttl: None,
     // Use TCP_NODELAY for diem tcp connections.
     nodelay: Some(true),
+    // Dial directly, unless a proxy is configured.
+    socks5_proxy: None,
+};
+
+/// quic::Transport with Diem-specific configuration applied.
+pub const DIEM_QUIC_TRANSPORT: quic::QuicTransport = quic::QuicTransport {
+    // Keep idle connections (and the NAT mappings of their UDP flows) open.
+    keep_alive_interval: Some(Duration::from_secs(5)),
 };
 
 /// A trait alias for "socket-like" things.
@@ -67,8 +93,45 @@ pub trait TSocket: AsyncRead + AsyncWrite + Send + fmt::Debug + Unpin + 'static
 
 impl<T> TSocket for T where T: AsyncRead + AsyncWrite + Send + fmt::Debug + Unpin + 'static {}
 
-/// Unique local identifier for a connection.
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize)]
+/// Socket of the connections of a [`DiemTransport`]
+pub type BoxedSocket = Box<dyn TSocket>;
+
+/// A base transport which downstream deployments can run the connections of a network over, in
+/// place of the one picked from its listen address, e.g. a WireGuard or TOR tunnel, or an in-memory
+/// network in tests. Its connections are secured and upgraded by a [`DiemNetTransport`] like any
+/// other, so it only listens on and dials the base addresses, e.g. `/ip4/<addr>/tcp/<port>` or
+/// `/memory/<port>`.
+///
+/// Every [`Transport`] of [`TSocket`]s is a `DiemTransport`, with
+/// `netcore::transport::memory::MemoryTransport` as the reference implementation.
+pub trait DiemTransport: Send + 'static {
+    fn into_boxed(self) -> BoxedTransport<BoxedSocket, io::Error>;
+}
+
+impl<T> DiemTransport for T
+where
+    T: Transport<Error = io::Error> + Send + 'static,
+    T::Output: TSocket,
+    T::Listener: Send + 'static,
+    T::Inbound: Send + 'static,
+    T::Outbound: Send + 'static,
+{
+    fn into_boxed(self) -> BoxedTransport<BoxedSocket, io::Error> {
+        self.and_then(box_socket).boxed()
+    }
+}
+
+fn box_socket<T: TSocket>(
+    socket: T,
+    _addr: NetworkAddress,
+    _origin: ConnectionOrigin,
+) -> future::Ready<io::Result<BoxedSocket>> {
+    future::ready(Ok(Box::new(socket)))
+}
+
+/// Unique local identifier for a connection. Identifiers are generated in increasing order, so
+/// newer connections have greater identifiers.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize)]
 pub struct ConnectionId(u32);
 
 impl From<u32> for ConnectionId {
@@ -267,24 +330,33 @@ async fn upgrade_inbound<T: TSocket>(
         addr
     };
 
-    // try authenticating via noise handshake
-    let (mut socket, remote_peer_id, peer_role) =
-        ctxt.noise.upgrade_inbound(socket).await.map_err(|err| {
-            if err.should_security_log() {
-                sample!(
-                    SampleRate::Duration(Duration::from_secs(15)),
-                    error!(
-                        SecurityEvent::NoiseHandshake,
-                        NetworkSchema::new(&ctxt.noise.network_context)
-                            .network_address(&addr)
-                            .connection_origin(&origin),
-                        error = %err,
-                    )
-                );
-            }
-            let err = io::Error::new(io::ErrorKind::Other, err);
-            add_pp_addr(proxy_protocol_enabled, err, &addr)
-        })?;
+    // try authenticating via noise handshake, unless the IP is over its handshake rate limit, which
+    // is checked before any Diffie-Hellman operation
+    let noise_result = match addr.find_ip_addr() {
+        Some(remote_ip) if !ctxt.noise.allow_inbound_handshake(remote_ip) => {
+            Err(NoiseHandshakeError::HandshakeRateLimited(remote_ip))
+        }
+        _ => ctxt.noise.upgrade_inbound(socket).await,
+    };
+    let (mut socket, remote_peer_id, peer_role) = noise_result.map_err(|err| {
+        if let Some(reason) = err.rejection_reason() {
+            counters::noise_handshakes_rejected(&ctxt.noise.network_context, reason).inc();
+        }
+        if err.should_security_log() {
+            sample!(
+                SampleRate::Duration(Duration::from_secs(15)),
+                error!(
+                    SecurityEvent::NoiseHandshake,
+                    NetworkSchema::new(&ctxt.noise.network_context)
+                        .network_address(&addr)
+                        .connection_origin(&origin),
+                    error = %err,
+                )
+            );
+        }
+        let err = io::Error::new(io::ErrorKind::Other, err);
+        add_pp_addr(proxy_protocol_enabled, err, &addr)
+    })?;
     let remote_pubkey = socket.get_remote_static();
     let addr = addr.append_prod_protos(remote_pubkey, HANDSHAKE_VERSION);
 
@@ -403,7 +475,7 @@ pub async fn upgrade_outbound<T: TSocket>(
 ///
 /// The base transport layer is pluggable, so long as it provides a reliable,
 /// ordered, connection-oriented, byte-stream abstraction (e.g., TCP). We currently
-/// use either `MemoryTransport` or `TcpTransport` as this base layer.
+/// use either `MemoryTransport`, `TcpTransport` or `QuicOrTcpTransport` as this base layer.
 ///
 /// Inbound and outbound connections are first established with the `base_transport`
 /// and then negotiate a secure, authenticated transport layer (currently Noise
@@ -431,22 +503,40 @@ where
         base_transport: TTransport,
         network_context: Arc<NetworkContext>,
         time_service: TimeService,
-        identity_key: x25519::PrivateKey,
+        identity_key: IdentityKey,
         auth_mode: HandshakeAuthMode,
         handshake_version: u8,
         chain_id: ChainId,
         application_protocols: SupportedProtocols,
         enable_proxy_protocol: bool,
+        enable_compression: bool,
+        enable_keep_alive: bool,
+        handshake_limit_config: Option<HandshakeLimitConfig>,
     ) -> Self {
         // build supported protocols
         let mut supported_protocols = BTreeMap::new();
+        if enable_keep_alive {
+            supported_protocols
+                .insert(KEEP_ALIVE_MESSAGING_PROTOCOL, application_protocols.clone());
+        }
+        if enable_compression {
+            supported_protocols.insert(
+                COMPRESSION_MESSAGING_PROTOCOL,
+                application_protocols.clone(),
+            );
+        }
         supported_protocols.insert(SUPPORTED_MESSAGING_PROTOCOL, application_protocols);
 
         let identity_pubkey = identity_key.public_key();
         let network_id = network_context.network_id().clone();
 
+        let mut noise = NoiseUpgrader::with_identity_key(network_context, identity_key, auth_mode);
+        if let Some(config) = handshake_limit_config {
+            noise = noise.with_handshake_limits(&config);
+        }
+
         let upgrade_context = UpgradeContext::new(
-            NoiseUpgrader::new(network_context, identity_key, auth_mode),
+            noise,
             handshake_version,
             supported_protocols,
             chain_id,
@@ -476,13 +566,15 @@ where
         let (base_transport_protos, base_transport_suffix) = parse_ip_tcp(protos)
             .map(|x| (&protos[..2], x.1))
             .or_else(|| parse_dns_tcp(protos).map(|x| (&protos[..2], x.1)))
+            .or_else(|| parse_ip_udp_quic(protos).map(|x| (&protos[..3], x.1)))
+            .or_else(|| parse_dns_udp_quic(protos).map(|x| (&protos[..3], x.1)))
             .or_else(|| parse_memory(protos).map(|x| (&protos[..1], x.1)))
             .ok_or_else(|| {
                 io::Error::new(
                     io::ErrorKind::InvalidInput,
                     format!(
                         "Unexpected dialing network address: '{}', expected: \
-                         memory, ip+tcp, or dns+tcp",
+                         memory, ip+tcp, dns+tcp, ip+udp+quic, or dns+udp+quic",
                         addr
                     ),
                 )
@@ -528,6 +620,15 @@ where
     /// `/dns/<ipaddr>/tcp/<port>` or
     /// `/dns4/<ipaddr>/tcp/<port>` or
     /// `/dns6/<ipaddr>/tcp/<port>`
+    ///
+    /// If the base transport is `QuicOrTcpTransport`, then `/<base_transport>` is
+    /// also any of the above `TcpTransport` formats, or:
+    ///
+    /// `/ip4/<ipaddr>/udp/<port>/quic` or
+    /// `/ip6/<ipaddr>/udp/<port>/quic` or
+    /// `/dns/<ipaddr>/udp/<port>/quic` or
+    /// `/dns4/<ipaddr>/udp/<port>/quic` or
+    /// `/dns6/<ipaddr>/udp/<port>/quic`
     pub fn dial(
         &self,
         peer_id: PeerId,
@@ -574,6 +675,11 @@ where
     ///
     /// `/ip4/<ipaddr>/tcp/<port>` or
     /// `/ip6/<ipaddr>/tcp/<port>`
+    ///
+    /// If the base transport is `QuicOrTcpTransport`, then we expect:
+    ///
+    /// `/ip4/<ipaddr>/udp/<port>/quic` or
+    /// `/ip6/<ipaddr>/udp/<port>/quic`
     pub fn listen_on(
         &self,
         addr: NetworkAddress,
@@ -151,24 +151,30 @@ where
         base_transport.clone(),
         NetworkContext::mock_with_peer_id(listener_peer_id),
         time_service.clone(),
-        listener_key,
+        IdentityKey::new(listener_key),
         listener_auth_mode,
         HANDSHAKE_VERSION,
         chain_id,
         supported_protocols.clone(),
         false, /* Disable proxy protocol */
+        false, /* Disable compression */
+        false, /* Disable keep-alive */
+        None,  /* No handshake limits */
     );
 
     let dialer_transport = DiemNetTransport::new(
         base_transport,
         NetworkContext::mock_with_peer_id(dialer_peer_id),
         time_service.clone(),
-        dialer_key,
+        IdentityKey::new(dialer_key),
         dialer_auth_mode,
         HANDSHAKE_VERSION,
         chain_id,
         supported_protocols.clone(),
         false, /* Disable proxy protocol */
+        false, /* Disable compression */
+        false, /* Disable keep-alive */
+        None,  /* No handshake limits */
     );
 
     (
@@ -8,7 +8,10 @@ use crate::{
         traits::Uniform,
     },
     transaction_builder::TransactionBuilder,
-    types::transaction::{authenticator::AuthenticationKey, RawTransaction, SignedTransaction},
+    types::transaction::{
+        authenticator::{AccountAuthenticator, AuthenticationKey},
+        RawTransaction, RawTransactionWithData, SignedTransaction,
+    },
 };
 
 pub use diem_types::*;
@@ -88,6 +91,17 @@ impl LocalAccount {
             .into_inner()
     }
 
+    /// Signs a multi-agent transaction on behalf of this account, either as its sender or as one of
+    /// its secondary signers, for the signatures of all the signers to be put together with
+    /// `RawTransactionWithData::into_signed_transaction`. Unlike
+    /// `sign_multi_agent_with_transaction_builder`, this doesn't need the keys of the other signers.
+    pub fn sign_multi_agent_transaction(
+        &self,
+        txn: &RawTransactionWithData,
+    ) -> AccountAuthenticator {
+        txn.sign(self.private_key())
+    }
+
     pub fn address(&self) -> AccountAddress {
         self.address
     }
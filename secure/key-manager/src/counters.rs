@@ -17,10 +17,16 @@ static COUNTER: Lazy<IntCounterVec> = Lazy::new(|| {
 /// Metric counter keys.
 const CHECK_KEYS: &str = "check_keys";
 const CONSENSUS_KEY: &str = "consensus_key";
+const NETWORK_KEYS: &str = "network_keys";
 
 /// Metric counter states.
+pub const DRY_RUN_ACTION_SKIPPED: &[&str] = &[CHECK_KEYS, "dry_run_action_skipped"];
 pub const KEYS_STILL_FRESH: &[&str] = &[CHECK_KEYS, "keys_still_fresh"];
 pub const LIVENESS_ERROR_ENCOUNTERED: &[&str] = &[CHECK_KEYS, "liveness_error_encountered"];
+pub const NETWORK_KEYS_RETIRED_PREVIOUS_VERSION: &[&str] =
+    &[NETWORK_KEYS, "retired_previous_version"];
+pub const NETWORK_KEYS_ROTATED_IN_STORAGE: &[&str] = &[NETWORK_KEYS, "rotated_in_storage"];
+pub const RETIRED_PREVIOUS_VERSION: &[&str] = &[CONSENSUS_KEY, "retired_previous_version"];
 pub const ROTATED_IN_STORAGE: &[&str] = &[CONSENSUS_KEY, "rotated_in_storage"];
 pub const SUBMITTED_ROTATION_TRANSACTION: &[&str] =
     &[CONSENSUS_KEY, "submitted_rotation_transaction"];
@@ -32,8 +38,12 @@ pub const UNEXPECTED_ERROR_ENCOUNTERED: &[&str] = &[CHECK_KEYS, "unexpected_erro
 /// Initializes all metric counter states.
 pub fn initialize_all_metric_counters() {
     let metric_counter_states = &[
+        DRY_RUN_ACTION_SKIPPED,
         KEYS_STILL_FRESH,
         LIVENESS_ERROR_ENCOUNTERED,
+        NETWORK_KEYS_RETIRED_PREVIOUS_VERSION,
+        NETWORK_KEYS_ROTATED_IN_STORAGE,
+        RETIRED_PREVIOUS_VERSION,
         ROTATED_IN_STORAGE,
         SUBMITTED_ROTATION_TRANSACTION,
         WAITING_ON_RECONFIGURATION,
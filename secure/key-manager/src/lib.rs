@@ -1,8 +1,9 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-//! The purpose of KeyManager is to rotate consensus key (and eventually the network key). It is not
-//! responsible for generating the first key and fails if the stores have not been properly setup.
+//! The purpose of KeyManager is to rotate the consensus key and, once enabled, the validator and
+//! fullnode network keys. It is not responsible for generating the first keys and fails if the
+//! stores have not been properly setup.
 //! During rotation, it first updates the local store, then submits a transaction to rotate to the
 //! new key. After some period of time and upon restarts of the process, it will evaluate the
 //! current status of the system including:
@@ -13,6 +14,17 @@
 //! * if the current key in the ValidatorConfig matches the ValidatorSet, if it does not it
 //! evaluates the current time from the last reconfiguration and logs that delta with greater
 //! levels of severity depending on the delta.
+//! * if the previous version of the key is still in the store once the current key is in the
+//! ValidatorSet, it retires the previous version, as safety rules (which shares the store) no
+//! longer needs it to sign.
+//!
+//! The network keys are rotated in the store along with the consensus key, and the rotation
+//! transaction registers them in the (encrypted) validator and the fullnode network addresses. The
+//! nodes keep serving with their previous network keys until the new keys are in the ValidatorSet,
+//! and then rotate to them without dropping their connections.
+//!
+//! In dry-run mode, KeyManager evaluates the status and reports the actions it would perform, but
+//! never modifies the store or submits a transaction.
 //!
 //! KeyManager talks to Diem via the DiemInterface that may either be a direct link into
 //! `DiemDB`/`Executor`, JSON-RPC, or some other concoction.
@@ -21,23 +33,30 @@
 
 use crate::{
     counters::{
-        KEYS_STILL_FRESH, LIVENESS_ERROR_ENCOUNTERED, ROTATED_IN_STORAGE,
-        SUBMITTED_ROTATION_TRANSACTION, UNEXPECTED_ERROR_ENCOUNTERED, WAITING_ON_RECONFIGURATION,
-        WAITING_ON_TRANSACTION_EXECUTION,
+        DRY_RUN_ACTION_SKIPPED, KEYS_STILL_FRESH, LIVENESS_ERROR_ENCOUNTERED,
+        NETWORK_KEYS_RETIRED_PREVIOUS_VERSION, NETWORK_KEYS_ROTATED_IN_STORAGE,
+        RETIRED_PREVIOUS_VERSION, ROTATED_IN_STORAGE, SUBMITTED_ROTATION_TRANSACTION,
+        UNEXPECTED_ERROR_ENCOUNTERED, WAITING_ON_RECONFIGURATION, WAITING_ON_TRANSACTION_EXECUTION,
     },
     diem_interface::DiemInterface,
     logging::{LogEntry, LogEvent, LogSchema},
 };
-use diem_crypto::ed25519::Ed25519PublicKey;
-use diem_global_constants::{CONSENSUS_KEY, OPERATOR_ACCOUNT, OPERATOR_KEY, OWNER_ACCOUNT};
+use diem_crypto::{ed25519::Ed25519PublicKey, x25519};
+use diem_global_constants::{
+    CONSENSUS_KEY, FULLNODE_NETWORK_KEY, OPERATOR_ACCOUNT, OPERATOR_KEY, OWNER_ACCOUNT,
+    VALIDATOR_NETWORK_KEY,
+};
 use diem_logger::prelude::*;
+use diem_network_address_encryption::Encryptor;
 use diem_secure_storage::{CryptoStorage, KVStorage};
 use diem_time_service::{TimeService, TimeServiceTrait};
 use diem_types::{
     account_address::AccountAddress,
     account_config::XUS_NAME,
     chain_id::ChainId,
+    network_address::NetworkAddress,
     transaction::{RawTransaction, SignedTransaction, Transaction},
+    validator_config::ValidatorConfig,
 };
 use std::time::Duration;
 use thiserror::Error;
@@ -52,6 +71,9 @@ mod tests;
 const GAS_UNIT_PRICE: u64 = 0;
 const MAX_GAS_AMOUNT: u64 = 400_000;
 
+/// The network keys, rotated along with the consensus key when network key rotation is enabled.
+const NETWORK_KEYS: &[&str] = &[VALIDATOR_NETWORK_KEY, FULLNODE_NETWORK_KEY];
+
 /// Defines actions that KeyManager should perform after a check of all associated state.
 #[derive(Debug, PartialEq, Eq)]
 pub enum Action {
@@ -59,6 +81,8 @@ pub enum Action {
     NoAction,
     /// Sufficient time has passed for another key rotation (keys are stale).
     FullKeyRotation,
+    /// The current key is in the validator set, retire the previous version of the key in storage.
+    RetirePreviousKeyVersion,
     /// Storage and the blockchain are inconsistent, submit a new rotation transaction.
     SubmitKeyRotationTransaction,
     /// The validator config and the validator set are inconsistent, wait for reconfiguration.
@@ -72,8 +96,12 @@ pub enum Action {
 pub enum Error {
     #[error("Key mismatch, config: {0}, info: {1}")]
     ConfigInfoKeyMismatch(Ed25519PublicKey, Ed25519PublicKey),
+    #[error("Network addresses mismatch between the config and the info of: {0}")]
+    ConfigInfoNetworkAddressesMismatch(AccountAddress),
     #[error("Key mismatch, config: {0}, storage: {1}")]
     ConfigStorageKeyMismatch(Ed25519PublicKey, Ed25519PublicKey),
+    #[error("Network key mismatch for {0}, config: {1:?}, storage: {2}")]
+    ConfigStorageNetworkKeyMismatch(String, Option<x25519::PublicKey>, x25519::PublicKey),
     #[error("Data does not exist: {0}")]
     DataDoesNotExist(String),
     #[error(
@@ -108,6 +136,12 @@ impl From<bcs::Error> for Error {
     }
 }
 
+impl From<diem_network_address_encryption::Error> for Error {
+    fn from(error: diem_network_address_encryption::Error) -> Self {
+        Error::UnknownError(format!("Network address encryption error: {}", error))
+    }
+}
+
 impl From<diem_secure_storage::Error> for Error {
     fn from(error: diem_secure_storage::Error) -> Self {
         Error::StorageError(error.to_string())
@@ -123,6 +157,8 @@ pub struct KeyManager<LI, S> {
     sleep_period_secs: u64,    // The amount of time to sleep between key management checks
     txn_expiration_secs: u64,  // The time after which a rotation transaction expires
     chain_id: ChainId,
+    dry_run: bool, // Whether to only report the actions instead of performing them
+    rotate_network_keys: bool, // Whether to rotate the network keys with the consensus key
 }
 
 impl<LI, S> KeyManager<LI, S>
@@ -138,6 +174,8 @@ where
         sleep_period_secs: u64,
         txn_expiration_secs: u64,
         chain_id: ChainId,
+        dry_run: bool,
+        rotate_network_keys: bool,
     ) -> Self {
         Self {
             diem,
@@ -148,6 +186,8 @@ where
             sleep_period_secs,
             txn_expiration_secs,
             chain_id,
+            dry_run,
+            rotate_network_keys,
         }
     }
 
@@ -201,16 +241,31 @@ where
         self.perform_action(action)
     }
 
-    pub fn compare_storage_to_config(&self) -> Result<(), Error> {
+    pub fn compare_storage_to_config(&mut self) -> Result<(), Error> {
         let owner_account = self.get_account_from_storage(OWNER_ACCOUNT)?;
         let validator_config = self.diem.retrieve_validator_config(owner_account)?;
 
         let storage_key = self.storage.get_public_key(CONSENSUS_KEY)?.public_key;
-        let config_key = validator_config.consensus_public_key;
+        let config_key = validator_config.consensus_public_key.clone();
         if storage_key != config_key {
             return Err(Error::ConfigStorageKeyMismatch(config_key, storage_key));
         }
 
+        if self.rotate_network_keys {
+            let (validator_key, fullnode_key) =
+                self.network_keys_from_config(owner_account, &validator_config)?;
+            for (key_name, config_key) in NETWORK_KEYS.iter().zip(&[validator_key, fullnode_key]) {
+                let storage_key = self.network_key_from_storage(key_name)?;
+                if config_key.as_ref() != Some(&storage_key) {
+                    return Err(Error::ConfigStorageNetworkKeyMismatch(
+                        key_name.to_string(),
+                        *config_key,
+                        storage_key,
+                    ));
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -225,9 +280,48 @@ where
             return Err(Error::ConfigInfoKeyMismatch(config_key, info_key.clone()));
         }
 
+        let info_config = validator_info.config();
+        if self.rotate_network_keys
+            && (info_config.validator_network_addresses
+                != validator_config.validator_network_addresses
+                || info_config.fullnode_network_addresses
+                    != validator_config.fullnode_network_addresses)
+        {
+            return Err(Error::ConfigInfoNetworkAddressesMismatch(owner_account));
+        }
+
         Ok(())
     }
 
+    /// Returns the x25519 public key of the network key in storage, as found in the network
+    /// addresses.
+    fn network_key_from_storage(&self, key_name: &str) -> Result<x25519::PublicKey, Error> {
+        let public_key = self.storage.get_public_key(key_name)?.public_key;
+        x25519::PublicKey::from_ed25519_public_bytes(&public_key.to_bytes()).map_err(|e| {
+            Error::UnknownError(format!("Unable to convert {} to x25519: {}", key_name, e))
+        })
+    }
+
+    /// Returns the validator and the fullnode network keys registered in the validator config,
+    /// i.e., the keys of their first network addresses.
+    fn network_keys_from_config(
+        &mut self,
+        owner_account: AccountAddress,
+        validator_config: &ValidatorConfig,
+    ) -> Result<(Option<x25519::PublicKey>, Option<x25519::PublicKey>), Error> {
+        let validator_addresses = Encryptor::new(&mut self.storage)
+            .decrypt(&validator_config.validator_network_addresses, owner_account)?;
+        let fullnode_addresses = validator_config.fullnode_network_addresses()?;
+        Ok((
+            validator_addresses
+                .get(0)
+                .and_then(NetworkAddress::find_noise_proto),
+            fullnode_addresses
+                .get(0)
+                .and_then(NetworkAddress::find_noise_proto),
+        ))
+    }
+
     pub fn last_reconfiguration(&self) -> Result<u64, Error> {
         // Convert the time to seconds
         Ok(self.diem.last_reconfiguration()? / 1_000_000)
@@ -237,6 +331,29 @@ where
         Ok(self.storage.get_public_key(CONSENSUS_KEY)?.last_update)
     }
 
+    /// Returns true iff storage still holds a previous version of the consensus key (or of the
+    /// network keys, when they're rotated).
+    pub fn has_previous_key_version(&self) -> Result<bool, Error> {
+        for key_name in self.rotated_keys() {
+            let current_key = self.storage.get_public_key(key_name)?.public_key;
+            match self.storage.get_public_key_previous_version(key_name) {
+                Ok(previous_key) if previous_key != current_key => return Ok(true),
+                Ok(_) | Err(diem_secure_storage::Error::KeyVersionNotFound(..)) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(false)
+    }
+
+    /// The keys rotated by the key manager
+    fn rotated_keys(&self) -> Vec<&'static str> {
+        let mut keys = vec![CONSENSUS_KEY];
+        if self.rotate_network_keys {
+            keys.extend_from_slice(NETWORK_KEYS);
+        }
+        keys
+    }
+
     pub fn diem_timestamp(&self) -> Result<u64, Error> {
         // Convert the time to seconds
         Ok(self.diem.diem_timestamp()? / 1_000_000)
@@ -248,6 +365,24 @@ where
             .map(|_| ())
     }
 
+    /// Rotates the validator and the fullnode network keys in storage. The nodes keep using their
+    /// previous network keys until the rotation transaction is executed.
+    pub fn rotate_network_keys_in_storage(&mut self) -> Result<(), Error> {
+        for key_name in NETWORK_KEYS {
+            info!(LogSchema::new(LogEntry::NetworkKeyRotatedInStorage)
+                .event(LogEvent::Pending)
+                .key_name(key_name));
+            self.storage.rotate_key(key_name)?;
+            let network_key = self.network_key_from_storage(key_name)?;
+            info!(LogSchema::new(LogEntry::NetworkKeyRotatedInStorage)
+                .event(LogEvent::Success)
+                .key_name(key_name)
+                .network_key(&network_key));
+            counters::increment_metric_counter(NETWORK_KEYS_ROTATED_IN_STORAGE);
+        }
+        Ok(())
+    }
+
     pub fn rotate_consensus_key(&mut self) -> Result<Ed25519PublicKey, Error> {
         info!(LogSchema::new(LogEntry::KeyRotatedInStorage).event(LogEvent::Pending));
         let consensus_key = self.storage.rotate_key(CONSENSUS_KEY)?;
@@ -259,6 +394,27 @@ where
         self.submit_key_rotation_transaction(consensus_key)
     }
 
+    pub fn retire_previous_key_version(&mut self) -> Result<(), Error> {
+        info!(LogSchema::new(LogEntry::PreviousKeyVersionRetired).event(LogEvent::Pending));
+        self.storage.retire_previous_key_versions(CONSENSUS_KEY)?;
+        info!(LogSchema::new(LogEntry::PreviousKeyVersionRetired).event(LogEvent::Success));
+        counters::increment_metric_counter(RETIRED_PREVIOUS_VERSION);
+
+        if self.rotate_network_keys {
+            for key_name in NETWORK_KEYS {
+                info!(LogSchema::new(LogEntry::PreviousKeyVersionRetired)
+                    .event(LogEvent::Pending)
+                    .key_name(key_name));
+                self.storage.retire_previous_key_versions(key_name)?;
+                info!(LogSchema::new(LogEntry::PreviousKeyVersionRetired)
+                    .event(LogEvent::Success)
+                    .key_name(key_name));
+                counters::increment_metric_counter(NETWORK_KEYS_RETIRED_PREVIOUS_VERSION);
+            }
+        }
+        Ok(())
+    }
+
     pub fn submit_key_rotation_transaction(
         &mut self,
         consensus_key: Ed25519PublicKey,
@@ -272,14 +428,23 @@ where
         // Retrieve existing network information as registered on-chain
         let owner_account = self.get_account_from_storage(OWNER_ACCOUNT)?;
         let validator_config = self.diem.retrieve_validator_config(owner_account)?;
+        let (validator_network_addresses, fullnode_network_addresses) = if self.rotate_network_keys
+        {
+            self.rotated_network_addresses(owner_account, seq_id, &validator_config)?
+        } else {
+            (
+                validator_config.validator_network_addresses,
+                validator_config.fullnode_network_addresses,
+            )
+        };
 
         let txn = build_rotation_transaction(
             owner_account,
             operator_account,
             seq_id,
             &consensus_key,
-            validator_config.validator_network_addresses,
-            validator_config.fullnode_network_addresses,
+            validator_network_addresses,
+            fullnode_network_addresses,
             expiration,
             self.chain_id,
         );
@@ -297,6 +462,34 @@ where
         Ok(consensus_key)
     }
 
+    /// Returns the (encrypted) validator and the fullnode network addresses of the validator
+    /// config, with their noise keys replaced by the network keys in storage.
+    fn rotated_network_addresses(
+        &mut self,
+        owner_account: AccountAddress,
+        seq_id: u64,
+        validator_config: &ValidatorConfig,
+    ) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let validator_key = self.network_key_from_storage(VALIDATOR_NETWORK_KEY)?;
+        let fullnode_key = self.network_key_from_storage(FULLNODE_NETWORK_KEY)?;
+
+        let encryptor = Encryptor::new(&mut self.storage);
+        let validator_addresses = replace_noise_key(
+            encryptor.decrypt(&validator_config.validator_network_addresses, owner_account)?,
+            &validator_key,
+        );
+        let validator_network_addresses =
+            encryptor.encrypt(&validator_addresses, owner_account, seq_id)?;
+
+        let fullnode_addresses = replace_noise_key(
+            validator_config.fullnode_network_addresses()?,
+            &fullnode_key,
+        );
+        let fullnode_network_addresses = bcs::to_bytes(&fullnode_addresses)?;
+
+        Ok((validator_network_addresses, fullnode_network_addresses))
+    }
+
     /// Ensures that the diem_timestamp() value registered on-chain is strictly monotonically
     /// increasing.
     fn ensure_timestamp_progress(&mut self) -> Result<(), Error> {
@@ -325,7 +518,10 @@ where
         // Compare the validator config to the validator set
         match self.compare_info_to_config() {
             Ok(()) => { /* Expected */ }
-            Err(Error::ConfigInfoKeyMismatch(..)) => return Ok(Action::WaitForReconfiguration),
+            Err(Error::ConfigInfoKeyMismatch(..))
+            | Err(Error::ConfigInfoNetworkAddressesMismatch(..)) => {
+                return Ok(Action::WaitForReconfiguration)
+            }
             Err(e) => return Err(e),
         }
 
@@ -334,7 +530,8 @@ where
         // Compare the validator config to secure storage
         match self.compare_storage_to_config() {
             Ok(()) => { /* Expected */ }
-            Err(Error::ConfigStorageKeyMismatch(..)) => {
+            Err(Error::ConfigStorageKeyMismatch(..))
+            | Err(Error::ConfigStorageNetworkKeyMismatch(..)) => {
                 return if last_rotation + self.txn_expiration_secs <= self.time_service.now_secs() {
                     Ok(Action::SubmitKeyRotationTransaction)
                 } else {
@@ -346,18 +543,41 @@ where
 
         if last_rotation + self.rotation_period_secs <= self.time_service.now_secs() {
             Ok(Action::FullKeyRotation)
+        } else if self.has_previous_key_version()? {
+            Ok(Action::RetirePreviousKeyVersion)
         } else {
             Ok(Action::NoAction)
         }
     }
 
     pub fn perform_action(&mut self, action: Action) -> Result<(), Error> {
+        let modifies_state = matches!(
+            action,
+            Action::FullKeyRotation
+                | Action::RetirePreviousKeyVersion
+                | Action::SubmitKeyRotationTransaction
+        );
+        if self.dry_run && modifies_state {
+            info!(
+                LogSchema::new(LogEntry::DryRun).event(LogEvent::Success),
+                action = format!("{:?}", action)
+            );
+            counters::increment_metric_counter(DRY_RUN_ACTION_SKIPPED);
+            return Ok(());
+        }
+
         match action {
             Action::FullKeyRotation => {
                 info!(LogSchema::new(LogEntry::FullKeyRotation).event(LogEvent::Pending));
+                if self.rotate_network_keys {
+                    self.rotate_network_keys_in_storage()?;
+                }
                 self.rotate_consensus_key().map(|_| ())?;
                 info!(LogSchema::new(LogEntry::FullKeyRotation).event(LogEvent::Success));
             }
+            Action::RetirePreviousKeyVersion => {
+                self.retire_previous_key_version()?;
+            }
             Action::SubmitKeyRotationTransaction => {
                 info!(LogSchema::new(LogEntry::TransactionResubmission).event(LogEvent::Pending));
                 self.resubmit_consensus_key_transaction()?;
@@ -388,6 +608,22 @@ where
     }
 }
 
+/// Replaces the noise keys of the network addresses with the given key
+fn replace_noise_key(
+    network_addresses: Vec<NetworkAddress>,
+    network_key: &x25519::PublicKey,
+) -> Vec<NetworkAddress> {
+    network_addresses
+        .into_iter()
+        .map(|mut network_address| {
+            if let Some(previous_key) = network_address.find_noise_proto() {
+                network_address.rotate_noise_public_key(&previous_key, network_key);
+            }
+            network_address
+        })
+        .collect()
+}
+
 pub fn build_rotation_transaction(
     owner_address: AccountAddress,
     operator_address: AccountAddress,
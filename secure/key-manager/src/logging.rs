@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::Error;
-use diem_crypto::ed25519::Ed25519PublicKey;
+use diem_crypto::{ed25519::Ed25519PublicKey, x25519};
 use diem_logger::Schema;
 use serde::Serialize;
 
@@ -13,8 +13,11 @@ pub struct LogSchema<'a> {
     #[schema(display)]
     consensus_key: Option<&'a Ed25519PublicKey>,
     json_rpc_endpoint: Option<&'a str>,
+    key_name: Option<&'a str>,
     #[schema(display)]
     liveness_error: Option<&'a Error>,
+    #[schema(display)]
+    network_key: Option<&'a x25519::PublicKey>,
     sleep_duration: Option<u64>,
     #[schema(display)]
     unexpected_error: Option<&'a Error>,
@@ -27,7 +30,9 @@ impl<'a> LogSchema<'a> {
             event: None,
             consensus_key: None,
             json_rpc_endpoint: None,
+            key_name: None,
             liveness_error: None,
+            network_key: None,
             sleep_duration: None,
             unexpected_error: None,
         }
@@ -38,10 +43,13 @@ impl<'a> LogSchema<'a> {
 #[serde(rename_all = "snake_case")]
 pub enum LogEntry {
     CheckKeyStatus,
+    DryRun,
     FullKeyRotation,
     Initialized,
     KeyRotatedInStorage,
     KeyStillFresh,
+    NetworkKeyRotatedInStorage,
+    PreviousKeyVersionRetired,
     Sleep,
     TransactionResubmission,
     TransactionSubmitted,
@@ -71,6 +71,8 @@ fn create_and_execute_key_manager(key_manager_config: KeyManagerConfig) -> Resul
         key_manager_config.sleep_period_secs,
         key_manager_config.txn_expiration_secs,
         key_manager_config.chain_id,
+        key_manager_config.dry_run,
+        key_manager_config.rotate_network_keys,
     );
 
     info!(LogSchema::new(LogEntry::Initialized)
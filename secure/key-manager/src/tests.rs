@@ -13,9 +13,12 @@ use diem_config::{
 };
 use diem_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, Uniform};
 use diem_global_constants::{
-    CONSENSUS_KEY, OPERATOR_ACCOUNT, OPERATOR_KEY, OWNER_ACCOUNT, OWNER_KEY,
+    CONSENSUS_KEY, FULLNODE_NETWORK_KEY, OPERATOR_ACCOUNT, OPERATOR_KEY, OWNER_ACCOUNT, OWNER_KEY,
+    VALIDATOR_NETWORK_ADDRESS_KEYS, VALIDATOR_NETWORK_KEY,
 };
-use diem_secure_storage::{InMemoryStorage, KVStorage};
+use diem_mempool::MempoolClientRequest;
+use diem_network_address_encryption::ValidatorKeys;
+use diem_secure_storage::{CryptoStorage, InMemoryStorage, KVStorage, Storage};
 use diem_time_service::{MockTimeService, TimeService, TimeServiceTrait};
 use diem_types::{
     account_address::AccountAddress,
@@ -363,13 +366,15 @@ fn setup_node<T: DiemInterface + Clone>(
         key_manager_config.sleep_period_secs,
         key_manager_config.txn_expiration_secs,
         diem_types::chain_id::ChainId::test(),
+        key_manager_config.dry_run,
+        key_manager_config.rotate_network_keys,
     );
 
     Node::new(executor, diem_test_harness, key_manager, time.into_mock())
 }
 
 // Creates and returns a secure storage implementation (based on an in memory storage engine) for
-// testing. As part of the initialization, the consensus key is created.
+// testing. As part of the initialization, the consensus key and the network keys are created.
 fn setup_secure_storage(config: &NodeConfig, time: TimeService) -> InMemoryStorage {
     let mut sec_storage = InMemoryStorage::new_with_time_service(time);
     let test_config = config.clone().test.unwrap();
@@ -397,6 +402,26 @@ fn setup_secure_storage(config: &NodeConfig, time: TimeService) -> InMemoryStora
         .set(crate::CONSENSUS_KEY, consensus_prikey)
         .unwrap();
 
+    // Initialize the network keys and the network address encryption keys from genesis
+    let backend = &config
+        .validator_network
+        .as_ref()
+        .unwrap()
+        .identity_from_storage()
+        .backend;
+    let genesis_storage: Storage = backend.into();
+    for key_name in &[VALIDATOR_NETWORK_KEY, FULLNODE_NETWORK_KEY] {
+        let network_prikey = genesis_storage.export_private_key(key_name).unwrap();
+        sec_storage.set(key_name, network_prikey).unwrap();
+    }
+    let address_keys = genesis_storage
+        .get::<ValidatorKeys>(VALIDATOR_NETWORK_ADDRESS_KEYS)
+        .unwrap()
+        .value;
+    sec_storage
+        .set(VALIDATOR_NETWORK_ADDRESS_KEYS, address_keys)
+        .unwrap();
+
     sec_storage
 }
 
@@ -416,7 +441,7 @@ fn setup_diem_interface_and_json_server(
 
     // Provide a VMValidator to the runtime.
     server.spawn(async move {
-        while let Some((txn, cb)) = mp_events.next().await {
+        while let Some(MempoolClientRequest::SubmitTransaction(txn, cb)) = mp_events.next().await {
             let vm_status = MockVMValidator.validate_transaction(txn).unwrap().status();
             let result = if vm_status.is_some() {
                 (MempoolStatus::new(MempoolStatusCode::VmError), vm_status)
@@ -639,10 +664,103 @@ fn verify_execute<T: DiemInterface>(mut node: Node<T>) {
     node.key_manager.execute_once().unwrap();
     node.execute_and_commit(node.diem.take_all_transactions());
     assert_eq!(
-        Action::NoAction,
+        Action::RetirePreviousKeyVersion,
         node.key_manager.evaluate_status().unwrap()
     );
     assert_ne!(0, node.diem.last_reconfiguration().unwrap());
+
+    // Verify that a single execution iteration retires the previous key version, now that the
+    // new key is on-chain
+    node.update_diem_timestamp();
+    node.key_manager.execute_once().unwrap();
+    assert!(!node.key_manager.has_previous_key_version().unwrap());
+    node.update_diem_timestamp();
+    assert_eq!(
+        Action::NoAction,
+        node.key_manager.evaluate_status().unwrap()
+    );
+}
+
+#[test]
+// This tests that the key manager doesn't modify storage or submit transactions in dry-run mode.
+fn test_dry_run() {
+    let (node_config, mut key_manager_config) = get_test_configs();
+    key_manager_config.dry_run = true;
+    let (storage, db_rw) = setup_diem_db(&node_config);
+    let diem = MockDiemInterface { storage };
+    let executor = Executor::new(db_rw);
+    let mut node = setup_node(&node_config, &key_manager_config, executor, diem);
+
+    // Verify the rotation is evaluated but not performed
+    let last_rotation = node.key_manager.last_rotation().unwrap();
+    node.time
+        .advance_secs(key_manager_config.rotation_period_secs);
+    node.update_diem_timestamp();
+    node.key_manager.execute_once().unwrap();
+    assert_eq!(last_rotation, node.key_manager.last_rotation().unwrap());
+    assert!(node.diem.take_all_transactions().is_empty());
+    node.key_manager.compare_storage_to_config().unwrap();
+
+    node.update_diem_timestamp();
+    assert_eq!(
+        Action::FullKeyRotation,
+        node.key_manager.evaluate_status().unwrap()
+    );
+}
+
+#[test]
+// This tests that the network keys are rotated with the consensus key, once enabled.
+fn test_network_key_rotation() {
+    let (node_config, mut key_manager_config) = get_test_configs();
+    key_manager_config.rotate_network_keys = true;
+    let (storage, db_rw) = setup_diem_db(&node_config);
+    let diem = MockDiemInterface { storage };
+    let executor = Executor::new(db_rw);
+    let mut node = setup_node(&node_config, &key_manager_config, executor, diem);
+
+    // Verify the network keys in storage match the genesis validator config
+    node.key_manager.compare_storage_to_config().unwrap();
+    let owner_account = node.get_account_from_storage(OWNER_ACCOUNT);
+    let genesis_config = node.diem.retrieve_validator_config(owner_account).unwrap();
+
+    // Verify the network keys are rotated in storage along with the consensus key
+    node.time
+        .advance_secs(key_manager_config.rotation_period_secs);
+    node.update_diem_timestamp();
+    node.key_manager.execute_once().unwrap();
+    let fullnode_key = node
+        .key_manager
+        .network_key_from_storage(FULLNODE_NETWORK_KEY)
+        .unwrap();
+    assert_ne!(
+        Some(fullnode_key),
+        genesis_config.fullnode_network_addresses().unwrap()[0].find_noise_proto()
+    );
+    node.update_diem_timestamp();
+    assert_eq!(
+        Action::WaitForTransactionExecution,
+        node.key_manager.evaluate_status().unwrap()
+    );
+
+    // Verify the rotation transaction registers the new network keys on-chain
+    node.execute_and_commit(node.diem.take_all_transactions());
+    node.key_manager.compare_storage_to_config().unwrap();
+    node.key_manager.compare_info_to_config().unwrap();
+    let rotated_info = node.diem.retrieve_validator_info(owner_account).unwrap();
+    assert_eq!(
+        Some(fullnode_key),
+        rotated_info.config().fullnode_network_addresses().unwrap()[0].find_noise_proto()
+    );
+
+    // Verify the previous versions of all the keys are retired
+    node.update_diem_timestamp();
+    assert_eq!(
+        Action::RetirePreviousKeyVersion,
+        node.key_manager.evaluate_status().unwrap()
+    );
+    node.update_diem_timestamp();
+    node.key_manager.execute_once().unwrap();
+    assert!(!node.key_manager.has_previous_key_version().unwrap());
 }
 
 #[test]
@@ -0,0 +1,20 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_secure_push_metrics::{register_histogram_vec, HistogramTimer, HistogramVec};
+use once_cell::sync::Lazy;
+
+static VAULT_TRANSIT_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "diem_secure_storage_vault_transit_latency",
+        "Time to perform an operation in the transit engine of Vault",
+        &["operation"]
+    )
+    .unwrap()
+});
+
+pub fn start_vault_transit_timer(operation: &str) -> HistogramTimer {
+    VAULT_TRANSIT_LATENCY
+        .with_label_values(&[operation])
+        .start_timer()
+}
@@ -56,6 +56,22 @@ impl<T: CryptoKVStorage> CryptoStorage for T {
         self.set(name, key)
     }
 
+    fn import_rotated_private_key(
+        &mut self,
+        name: &str,
+        previous_key: Ed25519PrivateKey,
+        key: Ed25519PrivateKey,
+    ) -> Result<(), Error> {
+        match self.get::<Ed25519PrivateKey>(name) {
+            Ok(_) => return Err(Error::KeyAlreadyExists(name.into())),
+            Err(Error::KeyNotSet(_)) => (/* Expected this for new keys! */),
+            Err(e) => return Err(e),
+        }
+
+        self.set(&get_previous_version_name(name), previous_key)?;
+        self.set(name, key)
+    }
+
     fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
         let response = self.get(name)?;
         let key: Ed25519PrivateKey = response.value;
@@ -85,6 +101,12 @@ impl<T: CryptoKVStorage> CryptoStorage for T {
         Ok(new_public_key)
     }
 
+    fn retire_previous_key_versions(&mut self, name: &str) -> Result<(), Error> {
+        // The previous version is overwritten with the current one, as keys can't be deleted
+        let private_key: Ed25519PrivateKey = self.get(name)?.value;
+        self.set(&get_previous_version_name(name), private_key)
+    }
+
     fn sign<U: CryptoHash + Serialize>(
         &self,
         name: &str,
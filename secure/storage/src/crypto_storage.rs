@@ -23,6 +23,17 @@ pub trait CryptoStorage {
     /// Diem to be run in test environments where a set of deterministic keys must be generated.
     fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error>;
 
+    /// Imports a rotated private key at 'name': 'key' is stored as the current version and
+    /// 'previous_key' as the previous one, as if 'previous_key' had been rotated into 'key'. This
+    /// allows moving keys between storages without losing their previous version. Fails if the
+    /// 'named' key already exists.
+    fn import_rotated_private_key(
+        &mut self,
+        name: &str,
+        previous_key: Ed25519PrivateKey,
+        key: Ed25519PrivateKey,
+    ) -> Result<(), Error>;
+
     /// Returns the Ed25519 private key stored at 'name' and identified by 'version', which is the
     /// corresponding public key. This may fail even if the 'named' key exists but the version is
     /// not present.
@@ -44,6 +55,10 @@ pub trait CryptoStorage {
     /// the version. At most two versions are expected to be retained.
     fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error>;
 
+    /// Retires the previous versions of an Ed25519 private key, so that only its current version
+    /// can be exported or used to sign. This cannot be undone.
+    fn retire_previous_key_versions(&mut self, name: &str) -> Result<(), Error>;
+
     /// Signs the provided securely-hashable struct, using the 'named' private
     /// key.
     // The FQDNs on the next line help macros don't remove them
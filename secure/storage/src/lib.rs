@@ -3,6 +3,7 @@
 
 #![forbid(unsafe_code)]
 
+mod counters;
 mod crypto_kv_storage;
 mod crypto_storage;
 mod error;
@@ -91,6 +91,16 @@ impl<S: CryptoStorage> CryptoStorage for Namespaced<S> {
         self.inner.import_private_key(&self.namespaced(name), key)
     }
 
+    fn import_rotated_private_key(
+        &mut self,
+        name: &str,
+        previous_key: Ed25519PrivateKey,
+        key: Ed25519PrivateKey,
+    ) -> Result<(), Error> {
+        self.inner
+            .import_rotated_private_key(&self.namespaced(name), previous_key, key)
+    }
+
     fn export_private_key_for_version(
         &self,
         name: &str,
@@ -113,6 +123,11 @@ impl<S: CryptoStorage> CryptoStorage for Namespaced<S> {
         self.inner.rotate_key(&self.namespaced(name))
     }
 
+    fn retire_previous_key_versions(&mut self, name: &str) -> Result<(), Error> {
+        self.inner
+            .retire_previous_key_versions(&self.namespaced(name))
+    }
+
     fn sign<T: CryptoHash + Serialize>(
         &self,
         name: &str,
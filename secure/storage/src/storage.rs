@@ -55,6 +55,15 @@ impl CryptoStorage for Box<Storage> {
         Storage::import_private_key(self, name, key)
     }
 
+    fn import_rotated_private_key(
+        &mut self,
+        name: &str,
+        previous_key: Ed25519PrivateKey,
+        key: Ed25519PrivateKey,
+    ) -> Result<(), Error> {
+        Storage::import_rotated_private_key(self, name, previous_key, key)
+    }
+
     fn export_private_key_for_version(
         &self,
         name: &str,
@@ -75,6 +84,10 @@ impl CryptoStorage for Box<Storage> {
         Storage::rotate_key(self, name)
     }
 
+    fn retire_previous_key_versions(&mut self, name: &str) -> Result<(), Error> {
+        Storage::retire_previous_key_versions(self, name)
+    }
+
     fn sign<T: diem_crypto::hash::CryptoHash + Serialize>(
         &self,
         name: &str,
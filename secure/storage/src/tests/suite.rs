@@ -31,6 +31,8 @@ const STORAGE_TESTS: &[fn(&mut Storage)] = &[
     test_hash_value,
     test_incremental_timestamp,
     test_import_key,
+    test_import_rotated_key,
+    test_retire_previous_key_versions,
     test_verify_incorrect_value_types,
 ];
 
@@ -189,6 +191,55 @@ fn test_import_key(storage: &mut Storage) {
     assert_ne!(message_signature, rotated_message_signature);
 }
 
+/// This test ensures that a rotated key can be imported along with its previous version.
+fn test_import_rotated_key(storage: &mut Storage) {
+    let key_name = "key";
+    let imported_key_name = "imported_key";
+
+    // Prepare a rotated key
+
+    storage.create_key(key_name).unwrap();
+    let previous_public_key = storage.get_public_key(key_name).unwrap().public_key;
+    let public_key = storage.rotate_key(key_name).unwrap();
+    let previous_key = storage
+        .export_private_key_for_version(key_name, previous_public_key.clone())
+        .unwrap();
+    let key = storage.export_private_key(key_name).unwrap();
+
+    // Restore and verify both versions
+
+    storage
+        .import_rotated_private_key(imported_key_name, previous_key.clone(), key.clone())
+        .unwrap();
+    assert_eq!(
+        storage
+            .get_public_key(imported_key_name)
+            .unwrap()
+            .public_key,
+        public_key
+    );
+    assert_eq!(
+        storage
+            .get_public_key_previous_version(imported_key_name)
+            .unwrap(),
+        previous_public_key
+    );
+    assert_eq!(storage.export_private_key(imported_key_name).unwrap(), key);
+    assert_eq!(
+        storage
+            .export_private_key_for_version(imported_key_name, previous_public_key)
+            .unwrap(),
+        previous_key
+    );
+
+    // Existing keys can't be overwritten
+
+    assert!(matches!(
+        storage.import_rotated_private_key(imported_key_name, previous_key, key),
+        Err(Error::KeyAlreadyExists(_))
+    ));
+}
+
 /// This test stores different types of values into storage, retrieves them, and asserts
 /// that the value unwrap functions return an unexpected type error on an incorrect unwrap.
 fn test_verify_incorrect_value_types(storage: &mut Storage) {
@@ -294,6 +345,32 @@ fn test_create_sign_rotate_sign(storage: &mut Storage) {
     assert_eq!(message_signature, message_signature_previous);
 }
 
+/// This test rotates a key pair, retires its previous version, and asserts the previous version
+/// can no longer be used while the current version still can.
+fn test_retire_previous_key_versions(storage: &mut Storage) {
+    let previous_public_key = storage.create_key(CRYPTO_NAME).unwrap();
+    let public_key = storage.rotate_key(CRYPTO_NAME).unwrap();
+    storage.retire_previous_key_versions(CRYPTO_NAME).unwrap();
+
+    // Verify the previous version is gone
+    assert!(storage
+        .export_private_key_for_version(CRYPTO_NAME, previous_public_key.clone())
+        .is_err());
+    assert_ne!(
+        storage.get_public_key_previous_version(CRYPTO_NAME).ok(),
+        Some(previous_public_key)
+    );
+
+    // Verify the current version is still usable
+    let message = TestDiemCrypto("Hello, World".to_string());
+    let message_signature = storage.sign(CRYPTO_NAME, &message).unwrap();
+    assert!(message_signature.verify(&message, &public_key).is_ok());
+    assert_eq!(
+        public_key,
+        storage.get_public_key(CRYPTO_NAME).unwrap().public_key
+    );
+}
+
 /// This test verifies that timestamps increase with successive writes
 fn test_incremental_timestamp(storage: &mut Storage) {
     let key = "timestamp_u64";
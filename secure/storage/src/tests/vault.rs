@@ -43,6 +43,7 @@ const VAULT_TESTS: &[fn()] = &[
     test_suite_no_namespaces,
     test_vault_cas,
     test_vault_crypto_policies,
+    test_vault_key_export_disabled,
     test_vault_key_trimming,
     test_vault_key_value_policies,
     test_vault_tokens,
@@ -100,6 +101,7 @@ fn create_vault_storage(token: String, renew_ttl_secs: Option<u32>, use_cas: boo
         None,
         renew_ttl_secs,
         use_cas,
+        false,
         None,
         None,
     )
@@ -412,6 +414,38 @@ fn test_vault_cas() {
     assert_eq!(with_cas.get::<u64>("test").unwrap().value, 6);
 }
 
+fn test_vault_key_export_disabled() {
+    let mut storage = VaultStorage::new(
+        dev::test_host(),
+        ROOT_TOKEN.into(),
+        None,
+        None,
+        true,
+        true,
+        None,
+        None,
+    );
+
+    // Verify the key signs through the transit engine
+    let public_key = storage.create_key(CRYPTO_KEY).unwrap();
+    let message = TestDiemCrypto("Hello, World".to_string());
+    let signature = storage.sign(CRYPTO_KEY, &message).unwrap();
+    signature.verify(&message, &public_key).unwrap();
+
+    // Verify the key can't be exported, even by a storage allowing exports
+    assert_eq!(
+        storage.export_private_key(CRYPTO_KEY).unwrap_err(),
+        Error::PermissionDenied
+    );
+    assert_eq!(
+        storage
+            .export_private_key_for_version(CRYPTO_KEY, public_key)
+            .unwrap_err(),
+        Error::PermissionDenied
+    );
+    create_vault().export_private_key(CRYPTO_KEY).unwrap_err();
+}
+
 fn test_vault_key_trimming() {
     let mut storage = create_vault();
 
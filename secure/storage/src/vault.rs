@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    namespaced::NAMESPACE_SEPARATOR, CryptoStorage, Error, GetResponse, KVStorage,
+    counters, namespaced::NAMESPACE_SEPARATOR, CryptoStorage, Error, GetResponse, KVStorage,
     PublicKeyResponse,
 };
 use chrono::DateTime;
@@ -31,12 +31,17 @@ const TRANSIT_NAMESPACE_SEPARATOR: &str = "__";
 /// Version 2 (https://www.vaultproject.io/api/secret/kv/kv-v2.html). So while Diem Secure Storage
 /// calls pointers to data keys, Vault has actually a secret that contains multiple key value
 /// pairs.
+///
+/// The keys are held by the Transit Secrets Engine, which signs with them. When key export is
+/// disabled, the keys are created non-exportable and the export APIs are refused, so the private
+/// keys never leave Vault.
 pub struct VaultStorage {
     client: Client,
     time_service: TimeService,
     renew_ttl_secs: Option<u32>,
     next_renewal: AtomicU64,
     use_cas: bool,
+    export_disabled: bool,
     secret_versions: RwLock<HashMap<String, u32>>,
 }
 
@@ -47,6 +52,7 @@ impl VaultStorage {
         certificate: Option<String>,
         renew_ttl_secs: Option<u32>,
         use_cas: bool,
+        export_disabled: bool,
         connection_timeout_ms: Option<u64>,
         response_timeout_ms: Option<u64>,
     ) -> Self {
@@ -62,6 +68,7 @@ impl VaultStorage {
             renew_ttl_secs,
             next_renewal: AtomicU64::new(0),
             use_cas,
+            export_disabled,
             secret_versions: RwLock::new(HashMap::new()),
         }
     }
@@ -133,6 +140,24 @@ impl VaultStorage {
             .version)
     }
 
+    fn ensure_export_enabled(&self, name: &str) -> Result<(), Error> {
+        if self.export_disabled {
+            diem_logger::error!("Refused to export {}: key export is disabled", name);
+            return Err(Error::PermissionDenied);
+        }
+        Ok(())
+    }
+
+    fn sign_bytes(
+        &self,
+        name: &str,
+        bytes: &[u8],
+        version: Option<u32>,
+    ) -> Result<Ed25519Signature, Error> {
+        let _timer = counters::start_vault_transit_timer("sign");
+        Ok(self.client().sign_ed25519(name, bytes, version)?)
+    }
+
     fn crypto_name(&self, name: &str) -> String {
         name.replace(NAMESPACE_SEPARATOR, TRANSIT_NAMESPACE_SEPARATOR)
     }
@@ -200,12 +225,14 @@ impl CryptoStorage for VaultStorage {
             Err(e) => return Err(e),
         }
 
-        self.client().create_ed25519_key(&ns_name, true)?;
+        self.client()
+            .create_ed25519_key(&ns_name, !self.export_disabled)?;
         self.get_public_key(name).map(|v| v.public_key)
     }
 
     fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
         let name = self.crypto_name(name);
+        self.ensure_export_enabled(&name)?;
         Ok(self.client().export_ed25519_key(&name, None)?)
     }
 
@@ -215,6 +242,7 @@ impl CryptoStorage for VaultStorage {
         version: Ed25519PublicKey,
     ) -> Result<Ed25519PrivateKey, Error> {
         let name = self.crypto_name(name);
+        self.ensure_export_enabled(&name)?;
         let vers = self.key_version(&name, &version)?;
         Ok(self.client().export_ed25519_key(&name, Some(vers))?)
     }
@@ -228,7 +256,25 @@ impl CryptoStorage for VaultStorage {
         }
 
         self.client()
-            .import_ed25519_key(&ns_name, &key)
+            .import_ed25519_key(&ns_name, &key, !self.export_disabled)
+            .map_err(|e| e.into())
+    }
+
+    fn import_rotated_private_key(
+        &mut self,
+        name: &str,
+        previous_key: Ed25519PrivateKey,
+        key: Ed25519PrivateKey,
+    ) -> Result<(), Error> {
+        let ns_name = self.crypto_name(name);
+        match self.get_public_key(name) {
+            Ok(_) => return Err(Error::KeyAlreadyExists(ns_name)),
+            Err(Error::KeyNotSet(_)) => (/* Expected this for new keys! */),
+            Err(e) => return Err(e),
+        }
+
+        self.client()
+            .import_ed25519_key_versions(&ns_name, &[&previous_key, &key], !self.export_disabled)
             .map_err(|e| e.into())
     }
 
@@ -272,6 +318,11 @@ impl CryptoStorage for VaultStorage {
         Ok(self.client().trim_key_versions(&ns_name)?)
     }
 
+    fn retire_previous_key_versions(&mut self, name: &str) -> Result<(), Error> {
+        let ns_name = self.crypto_name(name);
+        Ok(self.client().retire_previous_key_versions(&ns_name)?)
+    }
+
     fn sign<T: CryptoHash + Serialize>(
         &self,
         name: &str,
@@ -285,7 +336,7 @@ impl CryptoStorage for VaultStorage {
                 e
             ))
         })?;
-        Ok(self.client().sign_ed25519(&name, &bytes, None)?)
+        self.sign_bytes(&name, &bytes, None)
     }
 
     fn sign_using_version<T: CryptoHash + Serialize>(
@@ -303,7 +354,7 @@ impl CryptoStorage for VaultStorage {
                 e
             ))
         })?;
-        Ok(self.client().sign_ed25519(&name, &bytes, Some(vers))?)
+        self.sign_bytes(&name, &bytes, Some(vers))
     }
 }
 
@@ -491,6 +542,17 @@ pub mod policy {
             self.vault.import_private_key(&ns_name, key)
         }
 
+        fn import_rotated_private_key(
+            &mut self,
+            name: &str,
+            previous_key: Ed25519PrivateKey,
+            key: Ed25519PrivateKey,
+        ) -> Result<(), Error> {
+            let ns_name = self.crypto_name(name);
+            self.vault
+                .import_rotated_private_key(&ns_name, previous_key, key)
+        }
+
         fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
             let name = self.crypto_name(name);
             self.vault.get_public_key(&name)
@@ -506,6 +568,11 @@ pub mod policy {
             self.vault.rotate_key(&ns_name)
         }
 
+        fn retire_previous_key_versions(&mut self, name: &str) -> Result<(), Error> {
+            let ns_name = self.crypto_name(name);
+            self.vault.retire_previous_key_versions(&ns_name)
+        }
+
         fn sign<T: CryptoHash + Serialize>(
             &self,
             name: &str,
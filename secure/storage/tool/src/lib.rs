@@ -0,0 +1,26 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+pub mod migrate;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Tool for managing the secrets held in Diem secure storage")]
+pub enum Command {
+    #[structopt(about = "Copies the keys and values of a node between two secure backends")]
+    Migrate(migrate::Migrate),
+}
+
+impl Command {
+    pub fn execute(self) -> anyhow::Result<String> {
+        match self {
+            Command::Migrate(migrate) => {
+                let summary = migrate.execute()?;
+                Ok(serde_json::to_string_pretty(&summary)?)
+            }
+        }
+    }
+}
@@ -0,0 +1,18 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use diem_secure_storage_tool::Command;
+use std::process::exit;
+use structopt::StructOpt;
+
+fn main() {
+    match Command::from_args().execute() {
+        Ok(output) => println!("{}", output),
+        Err(err) => {
+            eprintln!("{:#}", err);
+            exit(1);
+        }
+    }
+}
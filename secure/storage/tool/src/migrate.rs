@@ -0,0 +1,375 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Copies the secrets of a node from one secure backend to another, e.g., from a file to Vault or
+//! between two namespaces, and verifies the copy before reporting success.
+//!
+//! The keys are copied with their previous version, if any, so that a key pending its rotation
+//! on-chain keeps working after the migration. The policies of a Vault backend are only copied on
+//! request, as they live outside of the namespaces of the secrets.
+
+use anyhow::{anyhow, bail, ensure, Result};
+use diem_config::config::{self, VaultConfig};
+use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey};
+use diem_global_constants::{
+    CONSENSUS_KEY, DIEM_ROOT_KEY, EXECUTION_KEY, FULLNODE_NETWORK_KEY, GENESIS_WAYPOINT,
+    MOVE_MODULES, OPERATOR_ACCOUNT, OPERATOR_KEY, OWNER_ACCOUNT, OWNER_KEY, SAFETY_DATA,
+    TREASURY_COMPLIANCE_KEY, VALIDATOR_NETWORK_ADDRESS_KEYS, VALIDATOR_NETWORK_KEY, WAYPOINT,
+};
+use diem_management::secure_backend::SecureBackend;
+use diem_secure_storage::{CryptoStorage, Error, KVStorage, Storage};
+use diem_vault_client::Client;
+use serde::Serialize;
+use std::convert::TryInto;
+use structopt::StructOpt;
+
+/// The keys a node may hold in secure storage
+pub const KEYS: &[&str] = &[
+    CONSENSUS_KEY,
+    EXECUTION_KEY,
+    FULLNODE_NETWORK_KEY,
+    DIEM_ROOT_KEY,
+    TREASURY_COMPLIANCE_KEY,
+    OPERATOR_KEY,
+    OWNER_KEY,
+    VALIDATOR_NETWORK_KEY,
+];
+
+/// The values a node may hold in secure storage
+pub const VALUES: &[&str] = &[
+    GENESIS_WAYPOINT,
+    MOVE_MODULES,
+    OPERATOR_ACCOUNT,
+    OWNER_ACCOUNT,
+    SAFETY_DATA,
+    VALIDATOR_NETWORK_ADDRESS_KEYS,
+    WAYPOINT,
+];
+
+// The policies every Vault has, which aren't managed by the operators
+const BUILTIN_VAULT_POLICIES: &[&str] = &["default", "root"];
+
+// The separator of a namespace in the names of Vault transit keys
+const VAULT_TRANSIT_NAMESPACE_SEPARATOR: &str = "__";
+
+#[derive(Debug, StructOpt)]
+pub struct Migrate {
+    /// Backend holding the secrets, e.g., "backend=disk;path=PATH", in the format of the
+    /// management tools
+    #[structopt(long)]
+    source_backend: SecureBackend,
+    /// Backend to copy the secrets to, which must not hold any of them yet
+    #[structopt(long)]
+    target_backend: SecureBackend,
+    /// Also copies the policies of the source Vault to the target Vault, with their paths moved
+    /// to the target namespace. Policies of the same name are replaced in the target Vault.
+    #[structopt(long)]
+    copy_policies: bool,
+}
+
+/// What the migration copied, all of it verified in the target backend
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct MigrationSummary {
+    pub keys: Vec<String>,
+    /// The keys copied along with their previous version
+    pub rotated_keys: Vec<String>,
+    pub values: Vec<String>,
+    pub policies: Vec<String>,
+}
+
+impl Migrate {
+    pub fn execute(self) -> Result<MigrationSummary> {
+        let source_config: config::SecureBackend = self.source_backend.try_into()?;
+        let target_config: config::SecureBackend = self.target_backend.try_into()?;
+        let source = Storage::from(&source_config);
+        let mut target = Storage::from(&target_config);
+        source.available()?;
+        target.available()?;
+
+        let mut summary = migrate(&source, &mut target)?;
+        if self.copy_policies {
+            summary.policies = copy_vault_policies(&source_config, &target_config)?;
+        }
+        Ok(summary)
+    }
+}
+
+/// Copies the keys and values found in the source to the target, then verifies that the target
+/// holds the same keys, with the same versions, and the same values. Nothing is copied if the
+/// target already holds any of them.
+pub fn migrate(source: &Storage, target: &mut Storage) -> Result<MigrationSummary> {
+    for name in KEYS {
+        ensure!(
+            key_versions(target, name)?.is_none(),
+            "The key {} already exists in the target backend",
+            name
+        );
+    }
+    for name in VALUES {
+        ensure!(
+            value(target, name)?.is_none(),
+            "The value {} already exists in the target backend",
+            name
+        );
+    }
+
+    let mut summary = MigrationSummary::default();
+    for name in KEYS {
+        let (key, previous_key) = match export_key_versions(source, name)? {
+            Some(versions) => versions,
+            None => continue,
+        };
+        match previous_key {
+            Some(previous_key) => {
+                target.import_rotated_private_key(name, previous_key, key)?;
+                summary.rotated_keys.push(name.to_string());
+            }
+            None => target.import_private_key(name, key)?,
+        }
+        summary.keys.push(name.to_string());
+    }
+    for name in VALUES {
+        if let Some(value) = value(source, name)? {
+            target.set(name, value)?;
+            summary.values.push(name.to_string());
+        }
+    }
+
+    verify(source, target, &summary)?;
+    Ok(summary)
+}
+
+/// Checks that the target holds the copied keys and values as the source does
+fn verify(source: &Storage, target: &Storage, summary: &MigrationSummary) -> Result<()> {
+    for name in &summary.keys {
+        ensure!(
+            key_versions(source, name)? == key_versions(target, name)?,
+            "The versions of the key {} differ in the target backend",
+            name
+        );
+    }
+    for name in &summary.values {
+        ensure!(
+            value(source, name)? == value(target, name)?,
+            "The value {} differs in the target backend",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Returns the current and previous versions of the 'named' key, identified by their public keys,
+/// or None if the key isn't set. A previous version equal to the current one, e.g., once retired,
+/// counts as no previous version.
+fn key_versions(storage: &Storage, name: &str) -> Result<Option<(String, Option<String>)>> {
+    let public_key = match storage.get_public_key(name) {
+        Ok(response) => response.public_key,
+        Err(Error::KeyNotSet(_)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let previous_public_key = match storage.get_public_key_previous_version(name) {
+        Ok(previous_public_key) if previous_public_key != public_key => {
+            Some(previous_public_key.to_string())
+        }
+        Ok(_) | Err(Error::KeyVersionNotFound(_, _)) => None,
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Some((public_key.to_string(), previous_public_key)))
+}
+
+/// Exports the current and previous versions of the 'named' private key, or None if the key isn't
+/// set
+fn export_key_versions(
+    storage: &Storage,
+    name: &str,
+) -> Result<Option<(Ed25519PrivateKey, Option<Ed25519PrivateKey>)>> {
+    let key = match storage.export_private_key(name) {
+        Ok(key) => key,
+        Err(Error::KeyNotSet(_)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let previous_key = match storage.get_public_key_previous_version(name) {
+        Ok(previous_public_key) if previous_public_key != key.public_key() => {
+            Some(storage.export_private_key_for_version(name, previous_public_key)?)
+        }
+        Ok(_) | Err(Error::KeyVersionNotFound(_, _)) => None,
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Some((key, previous_key)))
+}
+
+/// Returns the 'named' value, whatever its type, or None if it isn't set
+fn value(storage: &Storage, name: &str) -> Result<Option<serde_json::Value>> {
+    match storage.get::<serde_json::Value>(name) {
+        Ok(response) => Ok(Some(response.value)),
+        Err(Error::KeyNotSet(_)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Copies the policies managed by the operators from the source Vault to the target Vault, with
+/// the paths under the source namespace moved to the target namespace
+fn copy_vault_policies(
+    source: &config::SecureBackend,
+    target: &config::SecureBackend,
+) -> Result<Vec<String>> {
+    let (source, target) = match (source, target) {
+        (config::SecureBackend::Vault(source), config::SecureBackend::Vault(target)) => {
+            (source, target)
+        }
+        _ => bail!("Policies can only be copied between two Vault backends"),
+    };
+    ensure!(
+        source.namespace.is_some() == target.namespace.is_some(),
+        "Policies can't be moved between a namespaced and an unnamespaced Vault backend"
+    );
+    let rename_path = |path: &str| match (&source.namespace, &target.namespace) {
+        (Some(source_namespace), Some(target_namespace)) => {
+            move_path_namespace(path, source_namespace, target_namespace)
+        }
+        _ => path.to_string(),
+    };
+
+    let source_client = vault_client(source)?;
+    let target_client = vault_client(target)?;
+    let mut policies = Vec::new();
+    for name in source_client.list_policies()? {
+        if BUILTIN_VAULT_POLICIES.contains(&name.as_str()) {
+            continue;
+        }
+        let policy = source_client.read_policy(&name)?.map_paths(rename_path);
+        target_client.set_policy(&name, &policy)?;
+        policies.push(name);
+    }
+    Ok(policies)
+}
+
+/// Replaces the namespace in the path of a Vault secret (e.g., secret/data/N/S) or transit key
+/// (e.g., transit/keys/N__S)
+fn move_path_namespace(path: &str, source_namespace: &str, target_namespace: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if segment == source_namespace {
+                target_namespace.to_string()
+            } else if let Some(key) = segment.strip_prefix(&format!(
+                "{}{}",
+                source_namespace, VAULT_TRANSIT_NAMESPACE_SEPARATOR
+            )) {
+                format!(
+                    "{}{}{}",
+                    target_namespace, VAULT_TRANSIT_NAMESPACE_SEPARATOR, key
+                )
+            } else {
+                segment.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn vault_client(config: &VaultConfig) -> Result<Client> {
+    let token = config
+        .token
+        .read_token()
+        .map_err(|e| anyhow!("Unable to read the Vault token: {}", e))?;
+    let ca_certificate = match &config.ca_certificate {
+        Some(_) => Some(
+            config
+                .ca_certificate()
+                .map_err(|e| anyhow!("Unable to read the Vault certificate: {}", e))?,
+        ),
+        None => None,
+    };
+    Ok(Client::new(
+        config.server.clone(),
+        token,
+        ca_certificate,
+        config.connection_timeout_ms,
+        config.response_timeout_ms,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_config::config::OnDiskStorageConfig;
+    use diem_secure_storage::{InMemoryStorage, Namespaced, OnDiskStorage};
+    use diem_temppath::TempPath;
+
+    #[test]
+    fn test_migrate_keeps_key_versions() {
+        let mut source = Storage::from(InMemoryStorage::new());
+        source.create_key(CONSENSUS_KEY).unwrap();
+        source.rotate_key(CONSENSUS_KEY).unwrap();
+        source.create_key(OWNER_KEY).unwrap();
+        source.set(OWNER_ACCOUNT, "owner").unwrap();
+        source.set(SAFETY_DATA, vec![1u64, 2, 3]).unwrap();
+
+        let path = TempPath::new();
+        path.create_as_file().unwrap();
+        let mut target = Storage::from(Namespaced::new(
+            "validator",
+            Box::new(Storage::from(OnDiskStorage::new(path.path().to_path_buf()))),
+        ));
+        let summary = migrate(&source, &mut target).unwrap();
+        assert_eq!(summary.keys, vec![CONSENSUS_KEY, OWNER_KEY]);
+        assert_eq!(summary.rotated_keys, vec![CONSENSUS_KEY]);
+        assert_eq!(summary.values, vec![OWNER_ACCOUNT, SAFETY_DATA]);
+
+        // The previous version of the rotated key still signs
+        let previous_public_key = source
+            .get_public_key_previous_version(CONSENSUS_KEY)
+            .unwrap();
+        assert_eq!(
+            target
+                .export_private_key_for_version(CONSENSUS_KEY, previous_public_key.clone())
+                .unwrap()
+                .public_key(),
+            previous_public_key
+        );
+        assert_eq!(
+            target.get::<Vec<u64>>(SAFETY_DATA).unwrap().value,
+            vec![1, 2, 3]
+        );
+
+        // Nothing is overwritten by a second migration
+        assert!(migrate(&source, &mut target).is_err());
+    }
+
+    #[test]
+    fn test_migrate_from_disk_backend() {
+        let path = TempPath::new();
+        path.create_as_file().unwrap();
+        let mut config = OnDiskStorageConfig::default();
+        config.path = path.path().to_path_buf();
+        let mut source = Storage::from(&config::SecureBackend::OnDiskStorage(config));
+        source.create_key(EXECUTION_KEY).unwrap();
+        source.set(WAYPOINT, "waypoint").unwrap();
+
+        let mut target = Storage::from(InMemoryStorage::new());
+        let summary = migrate(&source, &mut target).unwrap();
+        assert_eq!(summary.keys, vec![EXECUTION_KEY]);
+        assert!(summary.rotated_keys.is_empty());
+        assert_eq!(summary.values, vec![WAYPOINT]);
+        assert_eq!(
+            target.get_public_key(EXECUTION_KEY).unwrap().public_key,
+            source.get_public_key(EXECUTION_KEY).unwrap().public_key
+        );
+    }
+
+    #[test]
+    fn test_move_path_namespace() {
+        assert_eq!(
+            move_path_namespace("secret/data/old/safety_data", "old", "new"),
+            "secret/data/new/safety_data"
+        );
+        assert_eq!(
+            move_path_namespace("transit/sign/old__consensus", "old", "new"),
+            "transit/sign/new__consensus"
+        );
+        assert_eq!(
+            move_path_namespace("transit/keys/older__consensus", "old", "new"),
+            "transit/keys/older__consensus"
+        );
+    }
+}
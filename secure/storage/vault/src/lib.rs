@@ -304,8 +304,26 @@ impl Client {
         process_transit_export_response(name, version, resp)
     }
 
-    pub fn import_ed25519_key(&self, name: &str, key: &Ed25519PrivateKey) -> Result<(), Error> {
-        let backup = base64::encode(serde_json::to_string(&KeyBackup::new(key))?);
+    pub fn import_ed25519_key(
+        &self,
+        name: &str,
+        key: &Ed25519PrivateKey,
+        exportable: bool,
+    ) -> Result<(), Error> {
+        self.import_ed25519_key_versions(name, &[key], exportable)
+    }
+
+    /// Imports the keys as the versions of a single transit key, the oldest first, so that the
+    /// last one is the latest version.
+    pub fn import_ed25519_key_versions(
+        &self,
+        name: &str,
+        keys: &[&Ed25519PrivateKey],
+        exportable: bool,
+    ) -> Result<(), Error> {
+        let backup = base64::encode(serde_json::to_string(&KeyBackup::with_versions(
+            keys, exportable,
+        ))?);
         let request = self
             .agent
             .post(&format!("{}/v1/transit/restore/{}", self.host, name));
@@ -389,6 +407,20 @@ impl Client {
         Ok(newest_pub_key.value.clone())
     }
 
+    /// Deletes all the versions of the named key but the most recent one, e.g., once the most
+    /// recent version replaced the previous ones wherever they were used. This operation cannot be
+    /// undone.
+    pub fn retire_previous_key_versions(&self, name: &str) -> Result<(), Error> {
+        let max_version = self
+            .read_ed25519_key(name)?
+            .iter()
+            .map(|resp| resp.version)
+            .max()
+            .ok_or_else(|| Error::NotFound("transit/".into(), name.into()))?;
+        self.set_minimum_encrypt_decrypt_version(name, max_version)?;
+        self.set_minimum_available_version(name, max_version)
+    }
+
     /// Trims the key versions according to the minimum available version specified.
     /// This operation deletes any older keys and cannot be undone.
     fn set_minimum_available_version(
@@ -767,45 +799,48 @@ pub fn process_unsealed_response(resp: Response) -> Result<bool, Error> {
 /// }
 ///
 /// This is intended to be a very simple application of it only for the purpose of introducing a
-/// single key with no versioning history into Vault. This is /only/ for test purposes and not
-/// intended for production use cases.
+/// key and its recent versions into Vault, e.g., when migrating keys from another storage. It
+/// doesn't carry any other history of the key.
 #[derive(Debug, Deserialize, PartialEq, Eq, Serialize)]
 pub struct KeyBackup {
     policy: KeyBackupPolicy,
 }
 
 impl KeyBackup {
-    pub fn new(key: &Ed25519PrivateKey) -> Self {
-        let mut key_bytes = key.to_bytes().to_vec();
-        let pub_key_bytes = key.public_key().to_bytes();
-        key_bytes.extend(&pub_key_bytes);
-
+    /// Backs up the keys as consecutive versions of the same key, starting from version 1.
+    pub fn with_versions(keys: &[&Ed25519PrivateKey], exportable: bool) -> Self {
         let now = chrono::Utc::now();
         let time_as_str = now.to_rfc3339();
-
-        let info = KeyBackupInfo {
-            key: Some(base64::encode(key_bytes)),
-            public_key: Some(base64::encode(pub_key_bytes)),
-            creation_time: now.timestamp_subsec_millis(),
-            time: time_as_str.clone(),
-            ..Default::default()
-        };
+        let latest_version = keys.len() as u32;
 
         let mut key_backup = Self {
             policy: KeyBackupPolicy {
-                exportable: true,
+                exportable,
                 min_decryption_version: 1,
-                latest_version: 1,
-                archive_version: 1,
+                latest_version,
+                archive_version: latest_version,
                 backup_type: 2,
                 backup_info: BackupInfo {
-                    time: time_as_str,
-                    version: 1,
+                    time: time_as_str.clone(),
+                    version: latest_version,
                 },
                 ..Default::default()
             },
         };
-        key_backup.policy.keys.insert(1, info);
+        for (version, key) in (1..).zip(keys) {
+            let mut key_bytes = key.to_bytes().to_vec();
+            let pub_key_bytes = key.public_key().to_bytes();
+            key_bytes.extend(&pub_key_bytes);
+
+            let info = KeyBackupInfo {
+                key: Some(base64::encode(key_bytes)),
+                public_key: Some(base64::encode(pub_key_bytes)),
+                creation_time: now.timestamp_subsec_millis(),
+                time: time_as_str.clone(),
+                ..Default::default()
+            };
+            key_backup.policy.keys.insert(version, info);
+        }
         key_backup
     }
 }
@@ -1117,6 +1152,19 @@ impl Policy {
             .path
             .insert(path.to_string(), path_policy);
     }
+
+    /// Returns a copy of the policy with its paths renamed by 'rename', e.g., to grant the same
+    /// capabilities over secrets moved to another namespace.
+    pub fn map_paths<F: Fn(&str) -> String>(&self, rename: F) -> Self {
+        let mut policy = Self::new();
+        for (path, path_policy) in &self.internal_rules.path {
+            policy
+                .internal_rules
+                .path
+                .insert(rename(path), path_policy.clone());
+        }
+        policy
+    }
 }
 
 impl TryFrom<serde_json::Value> for Policy {
@@ -35,6 +35,6 @@ fn main() -> Result<()> {
     )?;
     let node_config = NodeConfig::load(validator_config.config_path())?;
     println!("Running a Diem node with custom modules ...");
-    diem_node::start(&node_config, None);
+    diem_node::start(&node_config, Some(validator_config.config_path()), None);
     Ok(())
 }
@@ -12,15 +12,15 @@ use diem_types::waypoint::Waypoint;
 use executor_types::ChunkExecutor;
 use futures::channel::mpsc;
 use mempool_notifications::MempoolNotificationSender;
-use std::{boxed::Box, collections::HashMap, sync::Arc};
-use storage_interface::DbReader;
+use std::{boxed::Box, collections::HashMap, time::Duration};
+use storage_interface::DbReaderWriter;
 use subscription_service::ReconfigSubscription;
 use tokio::runtime::{Builder, Runtime};
 
 /// Creates and bootstraps new state syncs and creates clients for
 /// communicating with those state syncs.
 pub struct StateSyncBootstrapper {
-    _runtime: Runtime,
+    runtime: Runtime,
     coordinator_sender: mpsc::UnboundedSender<CoordinatorMessage>,
 }
 
@@ -29,7 +29,7 @@ impl StateSyncBootstrapper {
         network: Vec<(NodeNetworkId, StateSyncSender, StateSyncEvents)>,
         mempool_notifier: M,
         consensus_listener: ConsensusNotificationListener,
-        storage: Arc<dyn DbReader>,
+        storage: DbReaderWriter,
         executor: Box<dyn ChunkExecutor>,
         node_config: &NodeConfig,
         waypoint: Waypoint,
@@ -88,7 +88,7 @@ impl StateSyncBootstrapper {
         runtime.spawn(coordinator.start(network));
 
         Self {
-            _runtime: runtime,
+            runtime,
             coordinator_sender,
         }
     }
@@ -96,4 +96,9 @@ impl StateSyncBootstrapper {
     pub fn create_client(&self) -> StateSyncClient {
         StateSyncClient::new(self.coordinator_sender.clone())
     }
+
+    /// Stops state sync, waiting up to `timeout` for the chunk being committed.
+    pub fn shutdown(self, timeout: Duration) {
+        self.runtime.shutdown_timeout(timeout);
+    }
 }
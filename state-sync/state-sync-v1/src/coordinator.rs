@@ -12,6 +12,11 @@ use crate::{
     network::{StateSyncEvents, StateSyncMessage, StateSyncSender},
     request_manager::RequestManager,
     shared_components::SyncState,
+    snapshot_sync::{
+        GetStateSnapshotChunkRequest, GetStateSnapshotChunkResponse, StateSnapshotLedger,
+        StateSnapshotSync,
+    },
+    sync_progress::SyncProgressTracker,
 };
 use consensus_notifications::{
     ConsensusCommitNotification, ConsensusNotification, ConsensusNotificationListener,
@@ -24,6 +29,7 @@ use diem_config::{
 use diem_logger::prelude::*;
 use diem_types::{
     contract_event::ContractEvent,
+    epoch_change::EpochChangeProof,
     ledger_info::LedgerInfoWithSignatures,
     transaction::{Transaction, TransactionListWithProof, Version},
     waypoint::Waypoint,
@@ -40,7 +46,7 @@ use mempool_notifications::MempoolNotificationSender;
 use network::{protocols::network::Event, transport::ConnectionMetadata};
 use std::{
     cmp,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     time::{Duration, SystemTime},
 };
 use tokio::time::interval;
@@ -84,6 +90,8 @@ pub(crate) struct StateSyncCoordinator<T, M> {
     config: StateSyncConfig,
     // role of node
     role: RoleType,
+    // whether the consensus observer is enabled (full nodes then accept sync requests from it)
+    observer_enabled: bool,
     // An initial waypoint: for as long as the local version is less than a version determined by
     // waypoint a node is not going to be abl
     waypoint: Waypoint,
@@ -102,6 +110,14 @@ pub(crate) struct StateSyncCoordinator<T, M> {
     // queue of incoming long polling requests
     // peer will be notified about new chunk of transactions if it's available before expiry time
     subscriptions: HashMap<PeerNetworkId, PendingRequestInfo>,
+    // The chunks received ahead of the synced version (keyed by the known version of their
+    // requests) with their senders, applied once the chunks before them are.
+    buffered_chunks: BTreeMap<u64, (PeerNetworkId, GetChunkResponse)>,
+    // If we're a full node fast syncing, the download of the state snapshot we sync to before
+    // syncing the transactions after it.
+    snapshot_sync: Option<StateSnapshotSync>,
+    // Tracks the sync rate and publishes the sync progress
+    sync_progress: SyncProgressTracker,
     executor_proxy: T,
 }
 
@@ -137,6 +153,22 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             network_senders,
         );
 
+        // Only a full node that hasn't synced past genesis fast syncs.
+        let snapshot_sync = if node_config.state_sync.fast_sync_enabled
+            && role == RoleType::FullNode
+            && initial_state.synced_version() == 0
+        {
+            info!(LogSchema::event_log(
+                LogEntry::StateSnapshotSync,
+                LogEvent::Initialize
+            ));
+            Some(StateSnapshotSync::new(Duration::from_millis(
+                retry_timeout_val,
+            )))
+        } else {
+            None
+        };
+
         Ok(Self {
             client_events,
             mempool_notifier,
@@ -144,12 +176,16 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             local_state: initial_state,
             config: node_config.state_sync.clone(),
             role,
+            observer_enabled: node_config.consensus.observer.observer_enabled,
             waypoint,
             request_manager,
             subscriptions: HashMap::new(),
             sync_request: None,
             target_ledger_info: None,
             initialization_listener: None,
+            buffered_chunks: BTreeMap::new(),
+            snapshot_sync,
+            sync_progress: SyncProgressTracker::new(),
             executor_proxy,
         })
     }
@@ -320,6 +356,43 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 // Process chunk response
                 self.process_chunk_response(&peer, *response).await
             }
+            StateSyncMessage::GetStateSnapshotChunkRequest(request) => {
+                // Time request handling
+                let _timer = counters::PROCESS_MSG_LATENCY
+                    .with_label_values(&[
+                        &peer.raw_network_id().to_string(),
+                        &peer.peer_id().to_string(),
+                        counters::SNAPSHOT_CHUNK_REQUEST_MSG_LABEL,
+                    ])
+                    .start_timer();
+
+                // Process state snapshot chunk request
+                let process_result =
+                    self.process_state_snapshot_chunk_request(peer.clone(), *request);
+                if let Err(ref error) = process_result {
+                    error!(
+                        LogSchema::event_log(LogEntry::StateSnapshotSync, LogEvent::Fail)
+                            .peer(&peer)
+                            .error(error)
+                            .local_li_version(self.local_state.committed_version())
+                    );
+                }
+                process_result
+            }
+            StateSyncMessage::GetStateSnapshotChunkResponse(response) => {
+                // Time response handling
+                let _timer = counters::PROCESS_MSG_LATENCY
+                    .with_label_values(&[
+                        &peer.raw_network_id().to_string(),
+                        &peer.peer_id().to_string(),
+                        counters::SNAPSHOT_CHUNK_RESPONSE_MSG_LABEL,
+                    ])
+                    .start_timer();
+
+                // Process state snapshot chunk response
+                self.process_state_snapshot_chunk_response(&peer, *response)
+                    .await
+            }
         }
     }
 
@@ -377,8 +450,8 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             consensus_sync_notification: sync_notification,
         };
 
-        // Full nodes don't support sync requests
-        if self.role == RoleType::FullNode {
+        // Full nodes don't support sync requests (unless they run the consensus observer)
+        if self.role == RoleType::FullNode && !self.observer_enabled {
             return Err(Error::FullNodeSyncRequest);
         }
 
@@ -610,6 +683,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         counters::set_version(counters::VersionType::Synced, synced_version);
         counters::set_version(counters::VersionType::Committed, committed_version);
         counters::EPOCH.set(local_epoch as i64);
+        self.sync_progress.update(synced_version, committed_version);
 
         // Update timestamps
         counters::set_timestamp(
@@ -978,7 +1052,10 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             ),
         }
         .map_err(|error| {
-            self.request_manager.process_invalid_chunk(peer);
+            match error {
+                Error::InvalidSignature(_) => self.request_manager.process_invalid_signature(peer),
+                _ => self.request_manager.process_invalid_chunk(peer),
+            }
             Error::ProcessInvalidChunk(error.to_string())
         })?;
 
@@ -1037,6 +1114,93 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             return Err(error);
         }
 
+        // Ensure we're not saving a state snapshot, the chunk would then be applied to its state.
+        if self.snapshot_sync.is_some() {
+            return Err(Error::UnexpectedError(
+                "Received a chunk response while syncing a state snapshot".into(),
+            ));
+        }
+
+        // Sample the latency of the peer from the request of the chunk.
+        if let Some(known_version) = response
+            .txn_list_with_proof
+            .first_transaction_version
+            .and_then(|first_version| first_version.checked_sub(1))
+        {
+            self.request_manager
+                .process_response_latency(peer, known_version);
+        }
+
+        // Buffer the chunk if it was requested ahead of the synced version.
+        if self.buffer_chunk_requested_ahead(peer, &response) {
+            return Ok(());
+        }
+
+        // Apply the chunk, then the buffered chunks following it.
+        self.apply_chunk_response(peer, response).await?;
+        self.apply_buffered_chunks().await
+    }
+
+    /// Buffers the chunk if it starts after the synced version and was requested from the peer
+    /// ahead of the chunks before it. Such a chunk is dropped, without penalizing the peer, if the
+    /// buffer is full. Returns true iff the chunk was buffered or dropped.
+    fn buffer_chunk_requested_ahead(
+        &mut self,
+        peer: &PeerNetworkId,
+        response: &GetChunkResponse,
+    ) -> bool {
+        let known_version = match response.txn_list_with_proof.first_transaction_version {
+            Some(first_chunk_version) => first_chunk_version.saturating_sub(1),
+            None => return false,
+        };
+        if known_version <= self.local_state.synced_version()
+            || response.txn_list_with_proof.transactions.is_empty()
+            || !self.request_manager.is_request_sent_to(known_version, peer)
+        {
+            return false;
+        }
+        if !self.buffered_chunks.contains_key(&known_version)
+            && self.buffered_chunks.len() as u64 >= self.config.chunk_request_window
+        {
+            debug!(
+                LogSchema::event_log(LogEntry::ProcessChunkResponse, LogEvent::ChunkBufferFull)
+                    .peer(peer)
+                    .version(known_version)
+            );
+            return true;
+        }
+
+        // A chunk already buffered for the same request (e.g., from a multicast) is kept.
+        self.buffered_chunks
+            .entry(known_version)
+            .or_insert_with(|| (peer.clone(), response.clone()));
+        counters::BUFFERED_CHUNKS.set(self.buffered_chunks.len() as i64);
+        true
+    }
+
+    /// Applies the buffered chunks starting at the synced version, in order, and drops the ones
+    /// the applied chunks have overtaken.
+    async fn apply_buffered_chunks(&mut self) -> Result<(), Error> {
+        loop {
+            let synced_version = self.local_state.synced_version();
+            self.buffered_chunks = self.buffered_chunks.split_off(&synced_version);
+            let next_chunk = self.buffered_chunks.remove(&synced_version);
+            counters::BUFFERED_CHUNKS.set(self.buffered_chunks.len() as i64);
+
+            match next_chunk {
+                Some((peer, response)) => self.apply_chunk_response(&peer, response).await?,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Verifies, processes and stores the chunk in the given response, which must start at the
+    /// synced version.
+    async fn apply_chunk_response(
+        &mut self,
+        peer: &PeerNetworkId,
+        response: GetChunkResponse,
+    ) -> Result<(), Error> {
         // Verify the chunk response is well formed before trying to process it.
         self.verify_chunk_response_is_valid(peer, &response)?;
 
@@ -1088,6 +1252,224 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         })
     }
 
+    /// Sends the requested chunk of accounts of a state snapshot. The first chunk comes with the
+    /// ledger of the snapshot, at the version of our latest ledger info verifiable by the peer.
+    fn process_state_snapshot_chunk_request(
+        &mut self,
+        peer: PeerNetworkId,
+        request: GetStateSnapshotChunkRequest,
+    ) -> Result<(), Error> {
+        if request.limit == 0 || request.limit > self.config.max_chunk_limit {
+            self.request_manager.process_invalid_chunk_request(&peer);
+            return Err(Error::InvalidChunkRequest(format!(
+                "State snapshot chunk limit {} is not in (0, {}]",
+                request.limit, self.config.max_chunk_limit
+            )));
+        }
+        self.sync_state_with_local_storage()?;
+
+        let (version, ledger) = match request.version {
+            Some(version) => (version, None),
+            None => {
+                let ledger = self.create_state_snapshot_ledger(&peer, request.known_epoch)?;
+                (ledger.version(), Some(ledger))
+            }
+        };
+        let chunk =
+            self.executor_proxy
+                .get_account_chunk(version, request.last_key, request.limit)?;
+        let response = GetStateSnapshotChunkResponse {
+            version,
+            ledger,
+            chunk,
+        };
+        self.request_manager.send_chunk_response(
+            &peer,
+            StateSyncMessage::GetStateSnapshotChunkResponse(Box::new(response)),
+        )
+    }
+
+    /// Creates the ledger of a state snapshot at the version of our latest ledger info, or at the
+    /// version of the last epoch ending ledger info the storage proves from `known_epoch`.
+    fn create_state_snapshot_ledger(
+        &mut self,
+        peer: &PeerNetworkId,
+        known_epoch: u64,
+    ) -> Result<StateSnapshotLedger, Error> {
+        let latest_li = self.local_state.committed_ledger_info();
+        let latest_epoch = latest_li.ledger_info().epoch();
+        if known_epoch > latest_epoch {
+            self.request_manager.process_invalid_chunk_request(peer);
+            return Err(Error::InvalidChunkRequest(format!(
+                "Known epoch {} is higher than our latest epoch {}",
+                known_epoch, latest_epoch
+            )));
+        }
+
+        let mut epoch_change_proof = if known_epoch < latest_epoch {
+            self.executor_proxy
+                .get_epoch_change_proof(known_epoch, latest_epoch)?
+        } else {
+            EpochChangeProof::new(vec![], /* more = */ false)
+        };
+        let version = if epoch_change_proof.more {
+            // The peer can't verify our latest ledger info from the proof, the snapshot is at the
+            // end of the last epoch it proves instead.
+            epoch_change_proof.more = false;
+            epoch_change_proof
+                .ledger_info_with_sigs
+                .pop()
+                .ok_or_else(|| {
+                    Error::UnexpectedError("Empty epoch change proof with more epochs".into())
+                })?
+                .ledger_info()
+                .version()
+        } else {
+            latest_li.ledger_info().version()
+        };
+
+        let (ledger_info, txn_info, frozen_subtrees) =
+            self.executor_proxy.get_bootstrap_ledger(version)?;
+        Ok(StateSnapshotLedger {
+            epoch_change_proof,
+            ledger_info,
+            txn_info,
+            frozen_subtrees,
+        })
+    }
+
+    /// Saves the next chunk of the state snapshot we sync to, and requests the chunk after it.
+    /// Once the whole snapshot is saved, it becomes the latest state and the transactions after it
+    /// are synced as usual.
+    async fn process_state_snapshot_chunk_response(
+        &mut self,
+        peer: &PeerNetworkId,
+        response: GetStateSnapshotChunkResponse,
+    ) -> Result<(), Error> {
+        // Verify response comes from known peer
+        if !self.request_manager.is_known_state_sync_peer(peer) {
+            self.request_manager.process_chunk_from_downstream(peer);
+            return Err(Error::ReceivedChunkFromDownstream(peer.to_string()));
+        }
+
+        let mut snapshot_sync = self.snapshot_sync.take().ok_or_else(|| {
+            Error::UnexpectedError(
+                "Received a state snapshot chunk without syncing a state snapshot".into(),
+            )
+        })?;
+        if !snapshot_sync.is_next_chunk(&response) {
+            // The chunk was already received from another peer, or was requested before a timeout.
+            self.snapshot_sync = Some(snapshot_sync);
+            return Ok(());
+        }
+        if snapshot_sync.version().is_none()
+            && response.version <= self.local_state.synced_version()
+        {
+            // Our peers have nothing to fast sync to, the transactions are synced instead.
+            info!(
+                LogSchema::event_log(LogEntry::StateSnapshotSync, LogEvent::Complete)
+                    .peer(peer)
+                    .version(response.version)
+                    .local_synced_version(self.local_state.synced_version())
+            );
+            return Ok(());
+        }
+
+        let ledger = match self.add_state_snapshot_chunk(&mut snapshot_sync, response) {
+            Ok(Some(ledger)) => ledger,
+            Ok(None) => {
+                self.request_manager.process_success_response(peer);
+                counters::STATE_SNAPSHOT_ACCOUNTS.set(snapshot_sync.num_accounts() as i64);
+                let request = snapshot_sync.next_request(
+                    self.local_state.trusted_epoch(),
+                    self.config.snapshot_chunk_limit,
+                );
+                self.snapshot_sync = Some(snapshot_sync);
+                return self
+                    .request_manager
+                    .send_state_snapshot_chunk_request(request, None);
+            }
+            Err(error) => {
+                // The chunk is requested again from another peer, after the accounts saved so far
+                // unless the whole snapshot turned out invalid and was dropped.
+                self.request_manager.process_invalid_chunk(peer);
+                let request = snapshot_sync.next_request(
+                    self.local_state.trusted_epoch(),
+                    self.config.snapshot_chunk_limit,
+                );
+                self.snapshot_sync = Some(snapshot_sync);
+                if let Err(error) = self
+                    .request_manager
+                    .send_state_snapshot_chunk_request(request, Some(peer))
+                {
+                    warn!(LogSchema::event_log(
+                        LogEntry::StateSnapshotSync,
+                        LogEvent::SendChunkRequestFail
+                    )
+                    .error(&error));
+                }
+                return Err(error);
+            }
+        };
+        self.request_manager.process_success_response(peer);
+        counters::STATE_SNAPSHOT_ACCOUNTS.set(snapshot_sync.num_accounts() as i64);
+
+        if let Err(error) = self.executor_proxy.finalize_state_snapshot(
+            ledger.epoch_endings(),
+            &ledger.ledger_info,
+            &ledger.txn_info,
+            &ledger.frozen_subtrees,
+        ) {
+            snapshot_sync.reset();
+            self.snapshot_sync = Some(snapshot_sync);
+            return Err(error);
+        }
+        self.sync_state_with_local_storage()?;
+        let synced_version = self.local_state.synced_version();
+        info!(
+            LogSchema::event_log(LogEntry::StateSnapshotSync, LogEvent::Complete)
+                .peer(peer)
+                .version(ledger.version())
+                .count(snapshot_sync.num_accounts() as usize)
+                .local_synced_version(synced_version)
+                .local_epoch(self.local_state.trusted_epoch())
+        );
+        self.check_initialized_or_sync_request_completed(synced_version)
+            .await
+    }
+
+    /// Adds the chunk to the state snapshot, starting with the ledger of its first chunk once
+    /// verified. Returns the ledger of the snapshot once it's saved.
+    fn add_state_snapshot_chunk(
+        &self,
+        snapshot_sync: &mut StateSnapshotSync,
+        response: GetStateSnapshotChunkResponse,
+    ) -> Result<Option<StateSnapshotLedger>, Error> {
+        if snapshot_sync.version().is_none() {
+            let ledger = response.ledger.ok_or_else(|| {
+                Error::InvalidStateSnapshotChunk("Missing the ledger of the snapshot".into())
+            })?;
+            if ledger.version() != response.version {
+                return Err(Error::InvalidStateSnapshotChunk(format!(
+                    "Ledger version {} doesn't match the snapshot version {}",
+                    ledger.version(),
+                    response.version
+                )));
+            }
+            // Until we're initialized, the waypoint is also checked if the snapshot reaches it.
+            let waypoint = Some(&self.waypoint).filter(|waypoint| {
+                !self.is_initialized() && waypoint.version() <= ledger.version()
+            });
+            ledger.verify(self.local_state.trusted_epoch_state(), waypoint)?;
+
+            let receiver = self
+                .executor_proxy
+                .get_state_snapshot_receiver(ledger.version(), ledger.txn_info.state_root_hash())?;
+            snapshot_sync.start(ledger, receiver);
+        }
+        snapshot_sync.add_chunk(response.chunk)
+    }
+
     fn verify_chunk_response_is_valid(
         &mut self,
         peer: &PeerNetworkId,
@@ -1211,7 +1593,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
     }
 
     /// Logs the highest seen ledger info version based on the current syncing mode.
-    fn log_highest_seen_version(&self, new_highest_li: Option<LedgerInfoWithSignatures>) {
+    fn log_highest_seen_version(&mut self, new_highest_li: Option<LedgerInfoWithSignatures>) {
         let current_highest_version = if !self.is_initialized() {
             self.waypoint.version()
         } else if let Some(sync_request) = self.sync_request.as_ref() {
@@ -1231,6 +1613,8 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         let highest_seen_version = counters::get_version(counters::VersionType::Highest);
         let highest_version = cmp::max(current_highest_version, highest_seen_version);
         counters::set_version(counters::VersionType::Highest, highest_version);
+        self.sync_progress
+            .update_highest_known_version(highest_version);
     }
 
     /// Calculates the next version and epoch to request (assuming the given transaction list
@@ -1487,7 +1871,13 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
     /// Ensures that state sync is making progress:
     /// * Kick starts the initial sync process (e.g., syncing to a waypoint or target).
     /// * Issues a new request if too much time has passed since the last request was sent.
+    /// * Refreshes the sync progress, so its rate also drops when no progress is made.
     fn check_progress(&mut self) -> Result<(), Error> {
+        self.sync_progress.update(
+            self.local_state.synced_version(),
+            self.local_state.committed_version(),
+        );
+
         if self.is_consensus_executing() {
             return Ok(()); // No need to check progress or issue any requests (consensus is running).
         }
@@ -1528,6 +1918,21 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             }
         }
 
+        // If we're syncing a state snapshot, request its next chunk if it wasn't requested yet
+        // or its request timed out. The transactions are only synced once it's saved.
+        if let Some(snapshot_sync) = self.snapshot_sync.as_mut() {
+            if !snapshot_sync.has_request_timed_out() {
+                return Ok(());
+            }
+            let request = snapshot_sync.next_request(
+                self.local_state.trusted_epoch(),
+                self.config.snapshot_chunk_limit,
+            );
+            return self
+                .request_manager
+                .send_state_snapshot_chunk_request(request, None);
+        }
+
         // If the coordinator didn't make progress by the expected time or did not
         // send a request for the current local synced version, issue a new request.
         let known_version = self.local_state.synced_version();
@@ -1600,8 +2005,91 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             .unwrap_or_else(|| known_version.wrapping_add(1));
         counters::set_version(counters::VersionType::Target, target_version);
 
-        let req = GetChunkRequest::new(known_version, known_epoch, self.config.chunk_limit, target);
-        self.request_manager.send_chunk_request(req)
+        // The chunk at the known version may have been received ahead already, it's then applied
+        // once the chunk before it is.
+        let is_buffered_ahead = known_version > self.local_state.synced_version()
+            && self.buffered_chunks.contains_key(&known_version);
+        if !is_buffered_ahead {
+            let req = GetChunkRequest::new(
+                known_version,
+                known_epoch,
+                self.chunk_limit_at(known_version),
+                target.clone(),
+            );
+            self.request_manager.send_chunk_request(req)?;
+        }
+        self.send_chunk_requests_ahead(known_version, known_epoch, target);
+        Ok(())
+    }
+
+    /// Returns the number of transactions to request after `known_version`: the chunk limit,
+    /// unless a chunk buffered ahead starts before, in which case the request stops there.
+    fn chunk_limit_at(&self, known_version: u64) -> u64 {
+        match known_version
+            .checked_add(1)
+            .and_then(|version| self.buffered_chunks.range(version..).next())
+        {
+            Some((next_known_version, _)) => {
+                cmp::min(self.config.chunk_limit, next_known_version - known_version)
+            }
+            None => self.config.chunk_limit,
+        }
+    }
+
+    /// Requests the chunks following the one at `known_version` from other peers, up to the
+    /// chunk request window, unless they're buffered or their requests are still pending.
+    /// The requests follow the ends of the buffered chunks, which may be shorter than the chunk
+    /// limit, and stop at the start of the next buffered chunk.
+    /// Only the chunks proven by a target in the known epoch are requested ahead, as the
+    /// responding peers build the chunks of later epochs relative to other ledger infos.
+    fn send_chunk_requests_ahead(
+        &mut self,
+        known_version: u64,
+        known_epoch: u64,
+        target: TargetType,
+    ) {
+        let target_version = match (target.epoch(), target.version()) {
+            (Some(target_epoch), Some(target_version)) if target_epoch == known_epoch => {
+                target_version
+            }
+            _ => return,
+        };
+
+        let mut version = known_version;
+        for _ in 1..self.config.chunk_request_window {
+            let chunk_length = match self.buffered_chunks.get(&version) {
+                Some((_, response)) => response.txn_list_with_proof.transactions.len() as u64,
+                None => self.chunk_limit_at(version),
+            };
+            version = match version.checked_add(chunk_length) {
+                Some(version) if version < target_version => version,
+                _ => return,
+            };
+            if self.buffered_chunks.contains_key(&version) {
+                continue;
+            }
+            match self.request_manager.has_request_timed_out(version) {
+                Ok(true) => {}
+                _ => continue,
+            }
+
+            let req = GetChunkRequest::new(
+                version,
+                known_epoch,
+                self.chunk_limit_at(version),
+                target.clone(),
+            );
+            if let Err(error) = self.request_manager.send_chunk_request(req) {
+                error!(LogSchema::event_log(
+                    LogEntry::SendChunkRequest,
+                    LogEvent::SendChunkRequestFail
+                )
+                .version(version)
+                .local_epoch(known_epoch)
+                .error(&error));
+                return;
+            }
+        }
     }
 
     fn deliver_subscription(
@@ -1725,7 +2213,9 @@ mod tests {
         executor_proxy::ExecutorProxy,
         network::StateSyncMessage,
         shared_components::{test_utils, test_utils::create_coordinator_with_config_and_waypoint},
+        snapshot_sync::{GetStateSnapshotChunkRequest, StateSnapshotLedger, StateSnapshotSync},
     };
+    use anyhow::format_err;
     use consensus_notifications::{
         ConsensusCommitNotification, ConsensusNotificationResponse, ConsensusSyncNotification,
     };
@@ -1735,19 +2225,23 @@ mod tests {
     };
     use diem_crypto::{
         ed25519::{Ed25519PrivateKey, Ed25519Signature},
+        hash::SPARSE_MERKLE_PLACEHOLDER_HASH,
         HashValue, PrivateKey, Uniform,
     };
     use diem_types::{
         account_address::AccountAddress,
+        account_state_blob::AccountStateBlob,
         block_info::BlockInfo,
         chain_id::ChainId,
         contract_event::ContractEvent,
+        epoch_change::EpochChangeProof,
         ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
-        proof::TransactionListProof,
+        proof::{SparseMerkleRangeProof, TransactionListProof},
         transaction::{
-            RawTransaction, Script, SignedTransaction, Transaction, TransactionListWithProof,
-            TransactionPayload, Version,
+            RawTransaction, Script, SignedTransaction, Transaction, TransactionInfo,
+            TransactionListWithProof, TransactionPayload, Version,
         },
+        vm_status::KeptVMStatus,
         waypoint::Waypoint,
         PeerId,
     };
@@ -1755,7 +2249,8 @@ mod tests {
     use mempool_notifications::MempoolNotifier;
     use netcore::transport::ConnectionOrigin;
     use network::transport::ConnectionMetadata;
-    use std::collections::BTreeMap;
+    use std::{collections::BTreeMap, time::Duration};
+    use storage_interface::{AccountStateChunkWithProof, StateSnapshotReceiver};
 
     #[test]
     fn test_process_sync_request() {
@@ -2308,6 +2803,169 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_process_chunk_responses_requested_ahead() {
+        // Create a coordinator for a full node requesting two chunks at a time
+        let mut node_config = NodeConfig::default();
+        node_config.base.role = RoleType::FullNode;
+        node_config.state_sync.chunk_request_window = 2;
+        let mut full_node_coordinator =
+            create_coordinator_with_config_and_waypoint(node_config, Waypoint::default());
+
+        // Create a peer for the node and add the peer as a known peer
+        let peer_network_id = PeerNetworkId::random_validator();
+        process_new_peer_event(&mut full_node_coordinator, &peer_network_id);
+
+        // Request chunks ahead of the synced version from the peer
+        for known_version in &[10, 20, 30] {
+            full_node_coordinator
+                .request_manager
+                .add_request(*known_version, vec![peer_network_id.clone()]);
+        }
+
+        // Verify the chunks requested ahead are buffered, up to the window
+        for known_version in &[10, 20] {
+            let chunk_responses = create_non_empty_chunk_responses(known_version + 1);
+            let result = block_on(full_node_coordinator.process_chunk_message(
+                peer_network_id.network_id(),
+                peer_network_id.peer_id(),
+                chunk_responses[2].clone(), // Only use the highest chunk response
+            ));
+            assert!(result.is_ok());
+            assert!(full_node_coordinator
+                .buffered_chunks
+                .contains_key(known_version));
+        }
+        let chunk_responses = create_non_empty_chunk_responses(31);
+        let result = block_on(full_node_coordinator.process_chunk_message(
+            peer_network_id.network_id(),
+            peer_network_id.peer_id(),
+            chunk_responses[2].clone(),
+        ));
+
+        // Verify the chunk beyond the window is dropped without an error, the peer being honest
+        assert!(result.is_ok());
+        assert_eq!(full_node_coordinator.buffered_chunks.len(), 2);
+        assert!(!full_node_coordinator.buffered_chunks.contains_key(&30));
+
+        // Verify the requests stop at the buffered chunks, and follow their ends
+        assert_eq!(full_node_coordinator.chunk_limit_at(0), 10);
+        assert_eq!(full_node_coordinator.chunk_limit_at(15), 5);
+        assert_eq!(
+            full_node_coordinator.chunk_limit_at(20),
+            full_node_coordinator.config.chunk_limit
+        );
+    }
+
+    #[test]
+    fn test_state_snapshot_chunk_messages() {
+        // Create a coordinator for a validator node
+        let mut validator_coordinator = test_utils::create_validator_coordinator();
+
+        // Create state snapshot chunk requests with a chunk limit of 0 and with a known epoch
+        // higher than the local epoch
+        let peer_network_id = PeerNetworkId::random();
+        let snapshot_chunk_requests: Vec<_> = [(0, 0), (100, 250)]
+            .iter()
+            .map(|(known_epoch, limit)| {
+                StateSyncMessage::GetStateSnapshotChunkRequest(Box::new(
+                    GetStateSnapshotChunkRequest {
+                        version: None,
+                        known_epoch: *known_epoch,
+                        last_key: None,
+                        limit: *limit,
+                    },
+                ))
+            })
+            .collect();
+
+        // Verify invalid request errors are thrown
+        verify_all_chunk_requests_are_invalid(
+            &mut validator_coordinator,
+            &peer_network_id,
+            &snapshot_chunk_requests,
+        );
+
+        // Create a coordinator for a full node fast syncing
+        let mut node_config = NodeConfig::default();
+        node_config.base.role = RoleType::FullNode;
+        node_config.state_sync.fast_sync_enabled = true;
+        let mut full_node_coordinator =
+            create_coordinator_with_config_and_waypoint(node_config, Waypoint::default());
+
+        // Verify chunk responses are rejected while the state snapshot is synced
+        let peer_network_id = PeerNetworkId::random();
+        for chunk_response in &create_non_empty_chunk_responses(1) {
+            let result = block_on(full_node_coordinator.process_chunk_message(
+                peer_network_id.network_id(),
+                peer_network_id.peer_id(),
+                chunk_response.clone(),
+            ));
+            if !matches!(result, Err(Error::UnexpectedError(..))) {
+                panic!("Expected unexpected error, got: {:?}", result);
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncated_state_snapshot() {
+        let ledger = StateSnapshotLedger {
+            epoch_change_proof: EpochChangeProof::new(vec![], false),
+            ledger_info: create_ledger_info_at_version(10),
+            txn_info: TransactionInfo::new(
+                HashValue::zero(),
+                HashValue::zero(),
+                HashValue::zero(),
+                0,
+                KeptVMStatus::Executed,
+            ),
+            frozen_subtrees: vec![],
+        };
+        let mut snapshot_sync = StateSnapshotSync::new(Duration::from_secs(10));
+        snapshot_sync.start(ledger, Box::new(FailingFinishReceiver));
+
+        // Verify a chunk claimed to be the last one, with accounts on its right in its proof, is
+        // rejected without dropping the snapshot
+        let create_chunk = |right_sibling| AccountStateChunkWithProof {
+            account_blobs: vec![(HashValue::random(), AccountStateBlob::from(vec![1]))],
+            proof: SparseMerkleRangeProof::new(vec![right_sibling]),
+            is_last_chunk: true,
+        };
+        let result = snapshot_sync.add_chunk(create_chunk(HashValue::random()));
+        if !matches!(result, Err(Error::InvalidStateSnapshotChunk(..))) {
+            panic!("Expected an invalid chunk error, got: {:?}", result);
+        }
+        assert_eq!(snapshot_sync.version(), Some(10));
+
+        // Verify the snapshot is dropped if it can't be finished, the sync starting over
+        let result = snapshot_sync.add_chunk(create_chunk(*SPARSE_MERKLE_PLACEHOLDER_HASH));
+        if !matches!(result, Err(Error::InvalidStateSnapshotChunk(..))) {
+            panic!("Expected an invalid chunk error, got: {:?}", result);
+        }
+        assert_eq!(snapshot_sync.version(), None);
+        assert_eq!(snapshot_sync.num_accounts(), 0);
+        let request = snapshot_sync.next_request(0, 10);
+        assert_eq!(request.version, None);
+        assert_eq!(request.last_key, None);
+    }
+
+    /// Accepts all the chunks of a state snapshot, but fails to finish it.
+    struct FailingFinishReceiver;
+
+    impl StateSnapshotReceiver for FailingFinishReceiver {
+        fn add_chunk(
+            &mut self,
+            _chunk: Vec<(HashValue, AccountStateBlob)>,
+            _proof: SparseMerkleRangeProof,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn finish(self: Box<Self>) -> anyhow::Result<()> {
+            Err(format_err!("Root hashes do not match"))
+        }
+    }
+
     fn create_test_transaction() -> Transaction {
         let private_key = Ed25519PrivateKey::generate_for_testing();
         let public_key = private_key.public_key();
@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::sync_progress::SyncProgress;
 use diem_metrics::{
     register_histogram, register_histogram_vec, register_int_counter, register_int_counter_vec,
     register_int_gauge, register_int_gauge_vec, DurationHistogram, Histogram, HistogramVec,
@@ -17,6 +18,8 @@ pub const SYNC_MSG_LABEL: &str = "sync";
 pub const COMMIT_MSG_LABEL: &str = "commit";
 pub const CHUNK_REQUEST_MSG_LABEL: &str = "chunk_request";
 pub const CHUNK_RESPONSE_MSG_LABEL: &str = "chunk_response";
+pub const SNAPSHOT_CHUNK_REQUEST_MSG_LABEL: &str = "snapshot_chunk_request";
+pub const SNAPSHOT_CHUNK_RESPONSE_MSG_LABEL: &str = "snapshot_chunk_response";
 
 pub fn set_timestamp(timestamp_type: TimestampType, time_as_usecs: u64) {
     TIMESTAMP
@@ -162,6 +165,24 @@ pub static STATE_SYNC_CHUNK_SIZE: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Number of chunks received ahead of the synced version, waiting for the chunks before them
+pub static BUFFERED_CHUNKS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_state_sync_buffered_chunks",
+        "Number of chunks received ahead of the synced version waiting to be applied"
+    )
+    .unwrap()
+});
+
+/// Number of accounts of the state snapshot being synced that were received and verified
+pub static STATE_SNAPSHOT_ACCOUNTS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_state_sync_state_snapshot_accounts",
+        "Number of accounts of the state snapshot being synced received so far"
+    )
+    .unwrap()
+});
+
 /// Number of peers that are currently active and upstream.
 /// They are the set of nodes a node can make sync requests to
 pub static ACTIVE_UPSTREAM_PEERS: Lazy<IntGaugeVec> = Lazy::new(|| {
@@ -173,6 +194,17 @@ pub static ACTIVE_UPSTREAM_PEERS: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// What is known of each upstream peer to rank it: its score, the moving average of its response
+/// latency (-1 until it responds) and its rate of timed out requests
+pub static PEER_STATS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "diem_state_sync_peer_stats",
+        "Ranking stats of the upstream peers of state sync",
+        &["network", "peer", "type"] // score, latency_ms or timeout_rate_percent
+    )
+    .unwrap()
+});
+
 /// Highest preference of the networks this node is sending chunk requests to.
 /// It is usually 0 if the node's primary network is healthy, but can be >0 if the node's primary
 /// network is unhealthy/all peers in that network are dead
@@ -206,6 +238,33 @@ pub static VERSION: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// The progress of state sync, see `SyncProgress`
+pub static SYNC_STATUS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "diem_state_sync_sync_status",
+        "Progress of state sync towards the highest known version",
+        &["type"] // synced_version, highest_known_version, versions_per_sec or eta_secs
+    )
+    .unwrap()
+});
+
+/// Sets the sync status gauges. The unknown values are set to -1.
+pub fn set_sync_status(progress: &SyncProgress) {
+    let unknown_or = |value: Option<u64>| value.map_or(-1, |value| value as i64);
+    SYNC_STATUS
+        .with_label_values(&["synced_version"])
+        .set(progress.synced_version as i64);
+    SYNC_STATUS
+        .with_label_values(&["highest_known_version"])
+        .set(unknown_or(progress.highest_known_version));
+    SYNC_STATUS
+        .with_label_values(&["versions_per_sec"])
+        .set(progress.versions_per_sec.round() as i64);
+    SYNC_STATUS
+        .with_label_values(&["eta_secs"])
+        .set(unknown_or(progress.eta_secs));
+}
+
 pub static EPOCH: Lazy<IntGauge> = Lazy::new(|| {
     register_int_gauge!("diem_state_sync_epoch", "Current epoch in local state").unwrap()
 });
@@ -20,6 +20,10 @@ pub enum Error {
     IntegerOverflow(String),
     #[error("Received an invalid chunk request: {0}")]
     InvalidChunkRequest(String),
+    #[error("Received a ledger info with an invalid signature: {0}")]
+    InvalidSignature(String),
+    #[error("Received an invalid state snapshot chunk: {0}")]
+    InvalidStateSnapshotChunk(String),
     #[error(
         "Unable to add peer as they are not a valid state sync peer: {0}. Connection origin: {1}"
     )]
@@ -7,15 +7,17 @@ use crate::{
     logging::{LogEntry, LogEvent, LogSchema},
     shared_components::SyncState,
 };
+use diem_crypto::HashValue;
 use diem_logger::prelude::*;
 use diem_types::{
     account_state::AccountState,
     contract_event::ContractEvent,
+    epoch_change::EpochChangeProof,
     ledger_info::LedgerInfoWithSignatures,
     move_resource::MoveStorage,
     on_chain_config,
     on_chain_config::{config_address, ConfigID, OnChainConfigPayload, ON_CHAIN_CONFIG_REGISTRY},
-    transaction::TransactionListWithProof,
+    transaction::{TransactionInfo, TransactionListWithProof, Version},
 };
 use executor_types::{ChunkExecutor, ExecutedTrees};
 use std::{
@@ -23,7 +25,9 @@ use std::{
     convert::TryFrom,
     sync::Arc,
 };
-use storage_interface::DbReader;
+use storage_interface::{
+    AccountStateChunkWithProof, DbReader, DbReaderWriter, DbWriter, StateSnapshotReceiver,
+};
 use subscription_service::ReconfigSubscription;
 
 /// Proxies interactions with execution and storage for state synchronization
@@ -59,10 +63,50 @@ pub trait ExecutorProxyTrait: Send {
 
     /// publishes on-chain config updates to subscribed components
     fn publish_on_chain_config_updates(&mut self, events: Vec<ContractEvent>) -> Result<(), Error>;
+
+    /// Gets the ledger infos ending the epochs from `start_epoch` to the one before `end_epoch`.
+    fn get_epoch_change_proof(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<EpochChangeProof, Error>;
+
+    /// Gets the latest ledger info of the epoch of `version`, which must be at `version`, with the
+    /// transaction info at `version` and the frozen subtree roots of the accumulator before it.
+    fn get_bootstrap_ledger(
+        &self,
+        version: Version,
+    ) -> Result<(LedgerInfoWithSignatures, TransactionInfo, Vec<HashValue>), Error>;
+
+    /// Gets a chunk of at most `limit` accounts of the state at `version`, after `last_key`.
+    fn get_account_chunk(
+        &self,
+        version: Version,
+        last_key: Option<HashValue>,
+        limit: u64,
+    ) -> Result<AccountStateChunkWithProof, Error>;
+
+    /// Gets the receiver saving the state snapshot at `version` into the local storage.
+    fn get_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>, Error>;
+
+    /// Makes the saved state snapshot at the version of `ledger_info` the latest state, and
+    /// publishes its on-chain configs to all the subscribed components.
+    fn finalize_state_snapshot(
+        &mut self,
+        epoch_endings: &[LedgerInfoWithSignatures],
+        ledger_info: &LedgerInfoWithSignatures,
+        txn_info: &TransactionInfo,
+        frozen_subtrees: &[HashValue],
+    ) -> Result<(), Error>;
 }
 
 pub(crate) struct ExecutorProxy {
     storage: Arc<dyn DbReader>,
+    storage_writer: Arc<dyn DbWriter>,
     executor: Box<dyn ChunkExecutor>,
     reconfig_subscriptions: Vec<ReconfigSubscription>,
     on_chain_configs: OnChainConfigPayload,
@@ -70,17 +114,18 @@ pub(crate) struct ExecutorProxy {
 
 impl ExecutorProxy {
     pub(crate) fn new(
-        storage: Arc<dyn DbReader>,
+        storage: DbReaderWriter,
         executor: Box<dyn ChunkExecutor>,
         mut reconfig_subscriptions: Vec<ReconfigSubscription>,
     ) -> Self {
         let on_chain_configs = Self::publish_initial_on_chain_configs(
             ON_CHAIN_CONFIG_REGISTRY,
-            &*storage,
+            &*storage.reader,
             &mut reconfig_subscriptions,
         );
         Self {
-            storage,
+            storage: storage.reader,
+            storage_writer: storage.writer,
             executor,
             reconfig_subscriptions,
             on_chain_configs,
@@ -89,18 +134,19 @@ impl ExecutorProxy {
 
     #[cfg(test)]
     pub(crate) fn new_for_test(
-        storage: Arc<dyn DbReader>,
+        storage: DbReaderWriter,
         executor: Box<dyn ChunkExecutor>,
         mut reconfig_subscriptions: Vec<ReconfigSubscription>,
         config_registry: &[ConfigID],
     ) -> Self {
         let on_chain_configs = Self::publish_initial_on_chain_configs(
             config_registry,
-            &*storage,
+            &*storage.reader,
             &mut reconfig_subscriptions,
         );
         Self {
-            storage,
+            storage: storage.reader,
+            storage_writer: storage.writer,
             executor,
             reconfig_subscriptions,
             on_chain_configs,
@@ -269,6 +315,19 @@ impl ExecutorProxyTrait for ExecutorProxy {
         let starting_version = known_version
             .checked_add(1)
             .ok_or_else(|| Error::IntegerOverflow("Starting version has overflown!".into()))?;
+
+        // A node synced from a state snapshot doesn't have the transactions before it.
+        let first_version = self
+            .storage
+            .get_first_txn_version()
+            .map_err(|error| Error::UnexpectedError(error.to_string()))?;
+        if first_version.map_or(true, |first_version| starting_version < first_version) {
+            return Err(Error::UnexpectedError(format!(
+                "Transactions from version {} are not stored, the first stored one is {:?}",
+                starting_version, first_version
+            )));
+        }
+
         self.storage
             .get_transactions(starting_version, limit, target_version, false)
             .map_err(|error| {
@@ -384,6 +443,76 @@ impl ExecutorProxyTrait for ExecutorProxy {
             ))
         }
     }
+
+    fn get_epoch_change_proof(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<EpochChangeProof, Error> {
+        self.storage
+            .get_epoch_ending_ledger_infos(start_epoch, end_epoch)
+            .map_err(|error| Error::UnexpectedError(error.to_string()))
+    }
+
+    fn get_bootstrap_ledger(
+        &self,
+        version: Version,
+    ) -> Result<(LedgerInfoWithSignatures, TransactionInfo, Vec<HashValue>), Error> {
+        self.storage
+            .get_bootstrap_ledger(version)
+            .map_err(|error| Error::UnexpectedError(error.to_string()))
+    }
+
+    fn get_account_chunk(
+        &self,
+        version: Version,
+        last_key: Option<HashValue>,
+        limit: u64,
+    ) -> Result<AccountStateChunkWithProof, Error> {
+        self.storage
+            .get_account_chunk_with_proof(version, last_key, limit as usize)
+            .map_err(|error| {
+                Error::UnexpectedError(format!("Failed to get accounts from storage {}", error))
+            })
+    }
+
+    fn get_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>, Error> {
+        self.storage_writer
+            .get_state_snapshot_receiver(version, expected_root_hash)
+            .map_err(|error| Error::UnexpectedError(error.to_string()))
+    }
+
+    fn finalize_state_snapshot(
+        &mut self,
+        epoch_endings: &[LedgerInfoWithSignatures],
+        ledger_info: &LedgerInfoWithSignatures,
+        txn_info: &TransactionInfo,
+        frozen_subtrees: &[HashValue],
+    ) -> Result<(), Error> {
+        self.storage_writer
+            .finalize_state_snapshot(epoch_endings, ledger_info, txn_info, frozen_subtrees)
+            .map_err(|error| {
+                Error::UnexpectedError(format!("Failed to finalize state snapshot {}", error))
+            })?;
+
+        // All the on-chain configs may have changed with the state.
+        self.on_chain_configs = Self::fetch_all_configs(ON_CHAIN_CONFIG_REGISTRY, &*self.storage)?;
+        for subscription in self.reconfig_subscriptions.iter_mut() {
+            subscription
+                .publish(self.on_chain_configs.clone())
+                .map_err(|error| {
+                    Error::UnexpectedError(format!(
+                        "Failed to publish on-chain configs to subscription {}: {}",
+                        subscription.name, error
+                    ))
+                })?;
+        }
+        Ok(())
+    }
 }
 
 fn extract_reconfig_events(events: Vec<ContractEvent>) -> Vec<ContractEvent> {
@@ -755,7 +884,7 @@ mod tests {
         // Create a test diem database
         let db_path = diem_temppath::TempPath::new();
         db_path.create_as_dir().unwrap();
-        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
+        let (_db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
 
         // Bootstrap the database with regular genesis
         let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
@@ -767,8 +896,12 @@ mod tests {
         let chunk_executor = Box::new(Executor::<DiemVM>::new(db_rw.clone()));
         let mut config_registry = ON_CHAIN_CONFIG_REGISTRY.to_owned();
         config_registry.push(TestOnChainConfig::CONFIG_ID);
-        let mut executor_proxy =
-            ExecutorProxy::new_for_test(db, chunk_executor, vec![subscription], &config_registry);
+        let mut executor_proxy = ExecutorProxy::new_for_test(
+            db_rw.clone(),
+            chunk_executor,
+            vec![subscription],
+            &config_registry,
+        );
 
         // Verify that the initial configs returned to the subscriber don't contain the unknown on-chain config
         let payload = reconfig_receiver.select_next_some().now_or_never().unwrap();
@@ -810,7 +943,7 @@ mod tests {
         // Create test diem database
         let db_path = diem_temppath::TempPath::new();
         db_path.create_as_dir().unwrap();
-        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
+        let (_db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
 
         // Boostrap the genesis transaction
         let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
@@ -818,8 +951,8 @@ mod tests {
 
         // Create executor proxy with given subscription
         let block_executor = Box::new(Executor::<DiemVM>::new(db_rw.clone()));
-        let chunk_executor = Box::new(Executor::<DiemVM>::new(db_rw));
-        let executor_proxy = ExecutorProxy::new(db, chunk_executor, vec![subscription]);
+        let chunk_executor = Box::new(Executor::<DiemVM>::new(db_rw.clone()));
+        let executor_proxy = ExecutorProxy::new(db_rw, chunk_executor, vec![subscription]);
 
         // Verify initial reconfiguration notification is sent
         assert!(
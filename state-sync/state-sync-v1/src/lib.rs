@@ -20,6 +20,8 @@ mod logging;
 pub mod network;
 mod request_manager;
 pub mod shared_components;
+pub mod snapshot_sync;
+pub mod sync_progress;
 
 #[cfg(any(feature = "fuzzing", test))]
 pub mod fuzzing;
@@ -108,6 +108,7 @@ pub enum LogEntry {
     Multicast,
     SubscriptionDeliveryFail,
     ProgressCheck,
+    StateSnapshotSync,
 }
 
 #[derive(Clone, Copy, Serialize)]
@@ -135,6 +136,7 @@ pub enum LogEvent {
     ApplyChunkFail,
     PostCommitFail,
     OldResponseLI,
+    ChunkBufferFull,
 
     // ProcessChunkRequest events
     PastEpochRequested,
@@ -4,13 +4,17 @@
 //! Interface between State Sync and Network layers.
 
 use crate::{
-    chunk_request::GetChunkRequest, chunk_response::GetChunkResponse, counters, error::Error,
+    chunk_request::GetChunkRequest,
+    chunk_response::GetChunkResponse,
+    counters,
+    error::Error,
+    snapshot_sync::{GetStateSnapshotChunkRequest, GetStateSnapshotChunkResponse},
 };
 use channel::message_queues::QueueStyle;
 use diem_metrics::IntCounterVec;
 use diem_types::PeerId;
 use network::{
-    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    peer_manager::{ConnectionRequestSender, Misbehavior, PeerManagerRequestSender},
     protocols::network::{NetworkEvents, NetworkSender, NewNetworkSender},
     ProtocolId,
 };
@@ -23,6 +27,8 @@ const STATE_SYNC_MAX_BUFFER_SIZE: usize = 1;
 pub enum StateSyncMessage {
     GetChunkRequest(Box<GetChunkRequest>),
     GetChunkResponse(Box<GetChunkResponse>),
+    GetStateSnapshotChunkRequest(Box<GetStateSnapshotChunkRequest>),
+    GetStateSnapshotChunkResponse(Box<GetStateSnapshotChunkResponse>),
 }
 
 /// The interface from Network to StateSync layer.
@@ -62,6 +68,16 @@ impl StateSyncSender {
         let protocol = ProtocolId::StateSyncDirectSend;
         Ok(self.inner.send_to(recipient, protocol, message)?)
     }
+
+    /// Reports a peer which sent an invalid message, so that the network bans it once it
+    /// misbehaved too often.
+    pub fn report_misbehavior(
+        &mut self,
+        peer: PeerId,
+        misbehavior: Misbehavior,
+    ) -> Result<(), Error> {
+        Ok(self.inner.report_misbehavior(peer, misbehavior)?)
+    }
 }
 
 /// Configuration for the network endpoints to support state sync.
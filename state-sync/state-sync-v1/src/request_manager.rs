@@ -7,6 +7,7 @@ use crate::{
     error::Error,
     logging::{LogEntry, LogEvent, LogSchema},
     network::{StateSyncMessage, StateSyncSender},
+    snapshot_sync::GetStateSnapshotChunkRequest,
 };
 use diem_config::{
     config::{PeerNetworkId, PeerRole},
@@ -15,7 +16,7 @@ use diem_config::{
 use diem_logger::prelude::*;
 use itertools::Itertools;
 use netcore::transport::ConnectionOrigin;
-use network::transport::ConnectionMetadata;
+use network::{peer_manager::Misbehavior, transport::ConnectionMetadata};
 use rand::{
     distributions::{Distribution, WeightedIndex},
     thread_rng,
@@ -35,6 +36,12 @@ const MIN_SCORE: f64 = 1.0;
 const STARTING_SCORE: f64 = 50.0;
 const STARTING_SCORE_PREFERRED: f64 = 100.0;
 
+/// Weight of the latest sample in the moving averages of the latency and timeout rate of a peer.
+const PEER_STATS_SMOOTHING_FACTOR: f64 = 0.2;
+/// Every this many picks, the peers are picked uniformly at random instead of by their ranking,
+/// so that the peers ranked low get the chance to show they improved.
+const EXPLORATION_INTERVAL: u64 = 10;
+
 /// Basic metadata about the chunk request.
 #[derive(Clone, Debug)]
 pub struct ChunkRequestInfo {
@@ -72,9 +79,44 @@ enum PeerScoreUpdateType {
     TimeOut,
 }
 
+/// What is known of a peer to rank it against the other peers.
+#[derive(Clone, Debug)]
+struct PeerStats {
+    // The score of the peer based on preferences and behavior
+    score: f64,
+    // The moving average of the latency of the peer's responses (in milliseconds), None until
+    // the peer responds to a request
+    latency_ms: Option<f64>,
+    // The moving average of the rate of the requests sent to the peer that timed out
+    timeout_rate: f64,
+}
+
+impl PeerStats {
+    fn new(score: f64) -> Self {
+        Self {
+            score,
+            latency_ms: None,
+            timeout_rate: 0.0,
+        }
+    }
+
+    fn update_latency(&mut self, latency: Duration) {
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        self.latency_ms = Some(match self.latency_ms {
+            Some(average_ms) => moving_average(average_ms, latency_ms),
+            None => latency_ms,
+        });
+        self.timeout_rate = moving_average(self.timeout_rate, 0.0);
+    }
+
+    fn update_timeout(&mut self) {
+        self.timeout_rate = moving_average(self.timeout_rate, 1.0);
+    }
+}
+
 pub struct RequestManager {
-    // Maps each peer to their peer score
-    peer_scores: HashMap<PeerNetworkId, f64>,
+    // Maps each peer to what is known of it
+    peer_stats: HashMap<PeerNetworkId, PeerStats>,
     requests: BTreeMap<u64, ChunkRequestInfo>,
     // duration with the same version before the next attempt to get the next chunk
     request_timeout: Duration,
@@ -85,6 +127,8 @@ pub struct RequestManager {
     // NetworkId.
     multicast_network_level: NetworkId,
     network_senders: HashMap<NodeNetworkId, StateSyncSender>,
+    // The number of times peers were picked to send chunk requests to
+    num_picks: u64,
 }
 
 impl RequestManager {
@@ -97,12 +141,13 @@ impl RequestManager {
         update_multicast_network_counter(multicast_network_level.clone());
 
         Self {
-            peer_scores: HashMap::new(),
+            peer_stats: HashMap::new(),
             requests: BTreeMap::new(),
             request_timeout,
             multicast_timeout,
             multicast_network_level,
             network_senders,
+            num_picks: 0,
         }
     }
 
@@ -125,7 +170,7 @@ impl RequestManager {
             .with_label_values(&[&peer.raw_network_id().to_string()])
             .inc();
 
-        match self.peer_scores.entry(peer) {
+        match self.peer_stats.entry(peer) {
             Occupied(occupied_entry) => {
                 warn!(LogSchema::new(LogEntry::NewPeerAlreadyExists).peer(occupied_entry.key()));
             }
@@ -135,7 +180,9 @@ impl RequestManager {
                 } else {
                     STARTING_SCORE
                 };
-                vacant_entry.insert(peer_score);
+                let peer_stats = PeerStats::new(peer_score);
+                update_peer_counters(vacant_entry.key(), &peer_stats);
+                vacant_entry.insert(peer_stats);
             }
         }
 
@@ -145,11 +192,12 @@ impl RequestManager {
     pub fn disable_peer(&mut self, peer: &PeerNetworkId) -> Result<(), Error> {
         info!(LogSchema::new(LogEntry::LostPeer).peer(peer));
 
-        if self.peer_scores.contains_key(peer) {
+        if self.peer_stats.contains_key(peer) {
             counters::ACTIVE_UPSTREAM_PEERS
                 .with_label_values(&[&peer.raw_network_id().to_string()])
                 .dec();
-            self.peer_scores.remove(peer);
+            remove_peer_counters(peer);
+            self.peer_stats.remove(peer);
         } else {
             warn!(LogSchema::new(LogEntry::LostPeerNotKnown).peer(peer));
         }
@@ -158,12 +206,12 @@ impl RequestManager {
     }
 
     pub fn no_available_peers(&self) -> bool {
-        self.peer_scores.is_empty()
+        self.peer_stats.is_empty()
     }
 
     fn update_score(&mut self, peer: &PeerNetworkId, update_type: PeerScoreUpdateType) {
-        if let Some(score) = self.peer_scores.get_mut(peer) {
-            let old_score = *score;
+        if let Some(stats) = self.peer_stats.get_mut(peer) {
+            let old_score = stats.score;
             let new_score = match update_type {
                 PeerScoreUpdateType::Success => {
                     let new_score = old_score + 1.0;
@@ -181,32 +229,91 @@ impl RequestManager {
                     new_score.max(MIN_SCORE)
                 }
             };
-            *score = new_score;
+            stats.score = new_score;
+            update_peer_counters(peer, stats);
         }
     }
 
+    /// Samples the latency of the peer with the time since the request with known_version =
+    /// `version` was sent to it, if it was.
+    pub fn process_response_latency(&mut self, peer: &PeerNetworkId, version: u64) {
+        let last_request_time = match self.requests.get(&version) {
+            Some(request) if request.last_request_peers.contains(peer) => request.last_request_time,
+            _ => return,
+        };
+        if let Ok(latency) = SystemTime::now().duration_since(last_request_time) {
+            self.update_latency(peer, latency);
+        }
+    }
+
+    fn update_latency(&mut self, peer: &PeerNetworkId, latency: Duration) {
+        if let Some(stats) = self.peer_stats.get_mut(peer) {
+            stats.update_latency(latency);
+            update_peer_counters(peer, stats);
+        }
+    }
+
+    // Counts the requests sent to each peer that haven't timed out yet.
+    fn count_pending_requests(&self) -> HashMap<PeerNetworkId, u64> {
+        let mut pending_requests = HashMap::new();
+        for request in self.requests.values() {
+            if is_timeout(request.last_request_time, self.request_timeout) {
+                continue;
+            }
+            for peer in &request.last_request_peers {
+                *pending_requests.entry(peer.clone()).or_insert(0) += 1;
+            }
+        }
+        pending_requests
+    }
+
     // Calculates a weighted index for each peer per network. This is used to probabilistically
-    // select a peer (per network) to send a chunk request to.
+    // select a peer (per network) to send a chunk request to. The score of each peer is scaled
+    // down by its latency relative to the fastest peer of its network (peers without a known
+    // latency aren't scaled down), and is shared by the requests pending on it, so the concurrent
+    // requests are spread across the peers. If `explore` is true, all peers are weighted equally.
+    // The excluded peer, if any, is never picked.
     fn calculate_weighted_peers_per_network(
         &mut self,
+        explore: bool,
+        excluded_peer: Option<&PeerNetworkId>,
     ) -> BTreeMap<NetworkId, (Vec<PeerNetworkId>, Option<WeightedIndex<f64>>)> {
+        let pending_requests = self.count_pending_requests();
+
         // Group peers by network level
         let peers_by_network_level = self
-            .peer_scores
+            .peer_stats
             .iter()
-            .map(|(peer, peer_score)| (peer.raw_network_id(), (peer, peer_score)))
+            .map(|(peer, peer_stats)| (peer.raw_network_id(), (peer, peer_stats)))
             .into_group_map();
 
         // For each network, compute the weighted index
         peers_by_network_level
             .into_iter()
             .map(|(network_level, peers)| {
+                let fastest_latency_ms = peers
+                    .iter()
+                    .filter_map(|(_, peer_stats)| peer_stats.latency_ms)
+                    .fold(f64::INFINITY, f64::min);
                 let mut eligible_peers = vec![];
                 let weights: Vec<_> = peers
                     .iter()
-                    .map(|(peer, peer_score)| {
+                    .map(|(peer, peer_stats)| {
                         eligible_peers.push((*peer).clone());
-                        *peer_score
+                        if Some(*peer) == excluded_peer {
+                            return 0.0;
+                        }
+                        if explore {
+                            return 1.0;
+                        }
+                        let latency_factor = match peer_stats.latency_ms {
+                            Some(latency_ms) if latency_ms > 0.0 => {
+                                (fastest_latency_ms / latency_ms).min(1.0)
+                            }
+                            _ => 1.0,
+                        };
+                        let num_pending_requests = pending_requests.get(*peer).unwrap_or(&0);
+                        peer_stats.score * latency_factor / (1 + num_pending_requests) as f64
                     })
                     .collect();
                 let weighted_index = WeightedIndex::new(weights)
@@ -228,9 +335,21 @@ impl RequestManager {
     /// determined by the multicast network level. All networks with preference
     /// level <= multicast level are sampled. If there are no live peers in these networks,
     /// the multicast level is updated to the preference level of the first chosen network.
+    /// Every `EXPLORATION_INTERVAL` picks, the peers of each network are picked uniformly.
     fn pick_peers(&mut self) -> Vec<PeerNetworkId> {
+        self.pick_peers_excluding(None)
+    }
+
+    /// Picks a set of peers like `pick_peers`, without the excluded peer.
+    fn pick_peers_excluding(
+        &mut self,
+        excluded_peer: Option<&PeerNetworkId>,
+    ) -> Vec<PeerNetworkId> {
         // Calculate a weighted peer selection map per network level
-        let weighted_peers_per_network = self.calculate_weighted_peers_per_network();
+        self.num_picks += 1;
+        let explore = self.num_picks % EXPLORATION_INTERVAL == 0;
+        let weighted_peers_per_network =
+            self.calculate_weighted_peers_per_network(explore, excluded_peer);
 
         let mut chosen_peers = vec![];
         let mut new_multicast_network_level = None;
@@ -307,6 +426,49 @@ impl RequestManager {
         }
     }
 
+    /// Sends the request of a chunk of a state snapshot to the peers picked for chunk requests.
+    /// The timeouts of these requests are tracked by the state snapshot sync itself.
+    pub fn send_state_snapshot_chunk_request(
+        &mut self,
+        req: GetStateSnapshotChunkRequest,
+        excluded_peer: Option<&PeerNetworkId>,
+    ) -> Result<(), Error> {
+        let peers = self.pick_peers_excluding(excluded_peer);
+        if peers.is_empty() {
+            warn!(LogSchema::event_log(
+                LogEntry::StateSnapshotSync,
+                LogEvent::MissingPeers
+            ));
+            return Err(Error::NoAvailablePeers(
+                "No peers to send state snapshot chunk request to".into(),
+            ));
+        }
+
+        let msg = StateSyncMessage::GetStateSnapshotChunkRequest(Box::new(req));
+        let mut failed_peer_sends = vec![];
+        for peer in peers {
+            let mut sender = self.get_network_sender(&peer);
+            if let Err(e) = sender.send_to(peer.peer_id(), msg.clone()) {
+                error!(LogSchema::event_log(
+                    LogEntry::StateSnapshotSync,
+                    LogEvent::NetworkSendError
+                )
+                .peer(&peer)
+                .error(&e));
+                failed_peer_sends.push(peer);
+            }
+        }
+
+        if failed_peer_sends.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::UnexpectedError(format!(
+                "Failed to send state snapshot chunk request to: {:?}",
+                failed_peer_sends
+            )))
+        }
+    }
+
     fn get_network_sender(&mut self, peer: &PeerNetworkId) -> StateSyncSender {
         self.network_senders
             .get_mut(&peer.network_id())
@@ -359,6 +521,22 @@ impl RequestManager {
         self.update_score(peer, PeerScoreUpdateType::InvalidChunk);
     }
 
+    /// A chunk carrying a ledger info with an invalid signature is also reported to the network,
+    /// which bans the peers sending too many of them. The other failures to apply a chunk may be
+    /// local, so they only lower the score of the peer.
+    pub fn process_invalid_signature(&mut self, peer: &PeerNetworkId) {
+        self.process_invalid_chunk(peer);
+        if let Some(sender) = self.network_senders.get_mut(&peer.network_id()) {
+            if let Err(error) =
+                sender.report_misbehavior(peer.peer_id(), Misbehavior::InvalidSignature)
+            {
+                error!(LogSchema::new(LogEntry::NetworkError)
+                    .peer(peer)
+                    .error(&error));
+            }
+        }
+    }
+
     pub fn process_invalid_chunk_request(&mut self, peer: &PeerNetworkId) {
         self.update_score(peer, PeerScoreUpdateType::InvalidChunkRequest);
     }
@@ -402,6 +580,13 @@ impl RequestManager {
         }
     }
 
+    /// Returns true iff the last request sent with known_version = `version` was sent to `peer`.
+    pub fn is_request_sent_to(&self, version: u64, peer: &PeerNetworkId) -> bool {
+        self.requests
+            .get(&version)
+            .map_or(false, |req| req.last_request_peers.contains(peer))
+    }
+
     fn is_multicast_response(&self, version: u64, peer: &PeerNetworkId) -> bool {
         self.requests.get(&version).map_or(false, |req| {
             req.last_request_peers.contains(peer) && req.last_request_peers.len() > 1
@@ -469,6 +654,10 @@ impl RequestManager {
         };
         for peer in peers_to_penalize.iter() {
             self.update_score(peer, PeerScoreUpdateType::TimeOut);
+            if let Some(stats) = self.peer_stats.get_mut(peer) {
+                stats.update_timeout();
+                update_peer_counters(peer, stats);
+            }
         }
 
         // Increase the multicast network level if this request has also hit a multicast timeout
@@ -495,7 +684,7 @@ impl RequestManager {
     }
 
     pub fn is_known_state_sync_peer(&self, peer: &PeerNetworkId) -> bool {
-        self.peer_scores.contains_key(peer)
+        self.peer_stats.contains_key(peer)
     }
 
     fn update_multicast_network_level(
@@ -534,6 +723,36 @@ fn is_timeout(timeout_start: SystemTime, timeout_duration: Duration) -> bool {
         })
 }
 
+fn moving_average(average: f64, sample: f64) -> f64 {
+    PEER_STATS_SMOOTHING_FACTOR * sample + (1.0 - PEER_STATS_SMOOTHING_FACTOR) * average
+}
+
+fn update_peer_counters(peer: &PeerNetworkId, stats: &PeerStats) {
+    let network = peer.raw_network_id().to_string();
+    let peer_id = peer.peer_id().to_string();
+    counters::PEER_STATS
+        .with_label_values(&[&network, &peer_id, "score"])
+        .set(stats.score.round() as i64);
+    counters::PEER_STATS
+        .with_label_values(&[&network, &peer_id, "latency_ms"])
+        .set(
+            stats
+                .latency_ms
+                .map_or(-1, |latency_ms| latency_ms.round() as i64),
+        );
+    counters::PEER_STATS
+        .with_label_values(&[&network, &peer_id, "timeout_rate_percent"])
+        .set((stats.timeout_rate * 100.0).round() as i64);
+}
+
+fn remove_peer_counters(peer: &PeerNetworkId) {
+    let network = peer.raw_network_id().to_string();
+    let peer_id = peer.peer_id().to_string();
+    for stat in &["score", "latency_ms", "timeout_rate_percent"] {
+        let _ = counters::PEER_STATS.remove_label_values(&[&network, &peer_id, stat]);
+    }
+}
+
 fn pick_peer(
     peers: &[PeerNetworkId],
     weighted_index: &Option<WeightedIndex<f64>>,
@@ -753,6 +972,85 @@ mod tests {
         verify_validator_picked_most_often(&mut request_manager, &validators, 0);
     }
 
+    #[test]
+    fn test_score_pending_requests() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(30, 4);
+
+        // Send multiple requests to validator 0 that are still pending
+        for version in 0..NUM_CHUNKS_TO_PROCESS {
+            request_manager.add_request(version, vec![validators[0].clone()]);
+        }
+
+        // Verify validator 0 is chosen less often than the other validators
+        verify_validator_picked_least_often(&mut request_manager, &validators, 0);
+
+        // Verify the requests are only known to be sent to validator 0
+        assert!(request_manager.is_request_sent_to(1, &validators[0]));
+        assert!(!request_manager.is_request_sent_to(1, &validators[1]));
+        assert!(!request_manager.is_request_sent_to(NUM_CHUNKS_TO_PROCESS, &validators[0]));
+    }
+
+    #[test]
+    fn test_score_latency() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(0, 4);
+
+        // Validator 0 responds much slower than the other validators
+        for _ in 0..NUM_CHUNKS_TO_PROCESS {
+            request_manager.update_latency(&validators[0], Duration::from_millis(1000));
+            for validator in &validators[1..] {
+                request_manager.update_latency(validator, Duration::from_millis(100));
+            }
+        }
+        verify_validator_picked_least_often(&mut request_manager, &validators, 0);
+
+        // Validator 0 gets faster than the other validators
+        for _ in 0..NUM_CHUNKS_TO_PROCESS {
+            request_manager.update_latency(&validators[0], Duration::from_millis(10));
+        }
+        verify_validator_picked_most_often(&mut request_manager, &validators, 0);
+    }
+
+    #[test]
+    fn test_response_latency() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(0, 2);
+
+        // Only the responses to the requests sent to the peer are sampled
+        request_manager.add_request(10, vec![validators[0].clone()]);
+        request_manager.process_response_latency(&validators[0], 10);
+        request_manager.process_response_latency(&validators[1], 10);
+        request_manager.process_response_latency(&validators[0], 11);
+        assert!(request_manager.peer_stats[&validators[0]]
+            .latency_ms
+            .is_some());
+        assert!(request_manager.peer_stats[&validators[1]]
+            .latency_ms
+            .is_none());
+
+        // A timeout raises the timeout rate of the peers the request was sent to
+        assert!(request_manager.has_request_timed_out(10).unwrap());
+        assert!(request_manager.peer_stats[&validators[0]].timeout_rate > 0.0);
+        assert!(request_manager.peer_stats[&validators[1]].timeout_rate < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_pick_exploration() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(0, 4);
+
+        // Validator 0 is very slow and sends invalid chunks
+        for _ in 0..NUM_CHUNKS_TO_PROCESS {
+            request_manager.process_invalid_chunk(&validators[0]);
+            request_manager.update_latency(&validators[0], Duration::from_secs(100));
+            for validator in &validators[1..] {
+                request_manager.update_latency(validator, Duration::from_millis(1));
+            }
+        }
+
+        // Validator 0 is still picked by the exploration picks
+        let pick_counts =
+            calculate_pick_counts_for_validators(&mut request_manager, NUM_PICKS_TO_MAKE);
+        assert!(pick_counts.get(&validators[0]).unwrap_or(&0) > &0);
+    }
+
     #[test]
     fn test_remove_requests() {
         let (mut request_manager, validators) = generate_request_manager_and_validators(0, 2);
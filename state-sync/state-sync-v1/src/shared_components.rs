@@ -4,6 +4,7 @@
 use crate::error::Error;
 use diem_types::{
     epoch_change::Verifier, epoch_state::EpochState, ledger_info::LedgerInfoWithSignatures,
+    validator_verifier::VerifyError,
 };
 use executor_types::ExecutedTrees;
 
@@ -68,10 +69,17 @@ impl SyncState {
         self.trusted_epoch_state.epoch
     }
 
+    pub fn trusted_epoch_state(&self) -> &EpochState {
+        &self.trusted_epoch_state
+    }
+
     pub fn verify_ledger_info(&self, ledger_info: &LedgerInfoWithSignatures) -> Result<(), Error> {
         self.trusted_epoch_state
             .verify(ledger_info)
-            .map_err(|error| Error::UnexpectedError(error.to_string()))
+            .map_err(|error| match error.downcast_ref::<VerifyError>() {
+                Some(VerifyError::InvalidSignature) => Error::InvalidSignature(error.to_string()),
+                _ => Error::UnexpectedError(error.to_string()),
+            })
     }
 }
 
@@ -138,15 +146,15 @@ pub(crate) mod test_utils {
         // Create test diem database
         let db_path = diem_temppath::TempPath::new();
         db_path.create_as_dir().unwrap();
-        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
+        let (_db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
 
         // Bootstrap the genesis transaction
         let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
         bootstrap_genesis::<DiemVM>(&db_rw, &genesis_txn).unwrap();
 
         // Create executor proxy
-        let chunk_executor = Box::new(Executor::<DiemVM>::new(db_rw));
-        let executor_proxy = ExecutorProxy::new(db, chunk_executor, vec![]);
+        let chunk_executor = Box::new(Executor::<DiemVM>::new(db_rw.clone()));
+        let executor_proxy = ExecutorProxy::new(db_rw, chunk_executor, vec![]);
 
         // Get initial state
         let initial_state = executor_proxy.get_local_storage_state().unwrap();
@@ -0,0 +1,266 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fast sync from a state snapshot: instead of executing all the transactions since genesis, a
+//! full node downloads the account states at a recent version from its peers chunk by chunk, each
+//! chunk proven against the state root of a ledger info it verifies from its trusted epoch (and
+//! its waypoint). The node then syncs the transactions after that version as usual.
+
+use crate::error::Error;
+use diem_crypto::{
+    hash::{CryptoHash, TransactionAccumulatorHasher, SPARSE_MERKLE_PLACEHOLDER_HASH},
+    HashValue,
+};
+use diem_types::{
+    epoch_change::{EpochChangeProof, Verifier},
+    epoch_state::EpochState,
+    ledger_info::LedgerInfoWithSignatures,
+    proof::accumulator::InMemoryAccumulator,
+    transaction::{TransactionInfo, Version},
+    waypoint::Waypoint,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    iter::once,
+    time::{Duration, SystemTime},
+};
+use storage_interface::{AccountStateChunkWithProof, StateSnapshotReceiver};
+
+/// Requests a chunk of the accounts of a state snapshot.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GetStateSnapshotChunkRequest {
+    /// The version of the snapshot, `None` for its first chunk: the responder then picks a recent
+    /// version and sends the ledger of the snapshot with the chunk.
+    pub version: Option<Version>,
+    /// The epoch trusted by the requester, which the ledger of the snapshot is proven from.
+    pub known_epoch: u64,
+    /// The chunk starts after the account with this key, or with the first account if `None`.
+    pub last_key: Option<HashValue>,
+    /// The maximum number of accounts in the chunk.
+    pub limit: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GetStateSnapshotChunkResponse {
+    pub version: Version,
+    /// The ledger of the snapshot, sent with its first chunk only.
+    pub ledger: Option<StateSnapshotLedger>,
+    pub chunk: AccountStateChunkWithProof,
+}
+
+/// What the requester needs on top of the accounts of a snapshot to continue from it.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StateSnapshotLedger {
+    /// The ledger infos ending the epochs from the known epoch of the requester to the epoch of
+    /// `ledger_info`.
+    pub epoch_change_proof: EpochChangeProof,
+    /// The ledger info at the version of the snapshot.
+    pub ledger_info: LedgerInfoWithSignatures,
+    pub txn_info: TransactionInfo,
+    /// The frozen subtree roots of the transaction accumulator before the version of the snapshot.
+    pub frozen_subtrees: Vec<HashValue>,
+}
+
+impl StateSnapshotLedger {
+    pub fn version(&self) -> Version {
+        self.ledger_info.ledger_info().version()
+    }
+
+    pub fn epoch_endings(&self) -> &[LedgerInfoWithSignatures] {
+        &self.epoch_change_proof.ledger_info_with_sigs
+    }
+
+    /// Verifies the ledger info from `trusted_epoch_state` through the epoch change proof, then
+    /// the TransactionInfo against it. The waypoint, if given, must be one of the ledger infos.
+    pub fn verify(
+        &self,
+        trusted_epoch_state: &EpochState,
+        waypoint: Option<&Waypoint>,
+    ) -> Result<(), Error> {
+        let mut epoch_state = trusted_epoch_state.clone();
+        for ledger_info in self.epoch_endings() {
+            epoch_state.verify(ledger_info).map_err(invalid_chunk)?;
+            epoch_state = ledger_info
+                .ledger_info()
+                .next_epoch_state()
+                .cloned()
+                .ok_or_else(|| {
+                    Error::InvalidStateSnapshotChunk(format!(
+                        "Ledger info of the epoch change proof doesn't end an epoch: {}",
+                        ledger_info
+                    ))
+                })?;
+        }
+        epoch_state
+            .verify(&self.ledger_info)
+            .map_err(invalid_chunk)?;
+
+        if let Some(waypoint) = waypoint {
+            let waypoint_li = self
+                .epoch_endings()
+                .iter()
+                .chain(once(&self.ledger_info))
+                .find(|ledger_info| ledger_info.ledger_info().version() == waypoint.version())
+                .ok_or_else(|| {
+                    Error::InvalidStateSnapshotChunk(format!(
+                        "No ledger info at the version of the waypoint {}",
+                        waypoint
+                    ))
+                })?;
+            waypoint
+                .verify(waypoint_li.ledger_info())
+                .map_err(invalid_chunk)?;
+        }
+
+        let accumulator = InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+            self.frozen_subtrees.clone(),
+            self.version(),
+        )
+        .map_err(invalid_chunk)?
+        .append(&[self.txn_info.hash()]);
+        let expected_root_hash = self
+            .ledger_info
+            .ledger_info()
+            .transaction_accumulator_hash();
+        if accumulator.root_hash() != expected_root_hash {
+            return Err(Error::InvalidStateSnapshotChunk(format!(
+                "Transaction accumulator root hash doesn't match. Computed: {}, in ledger info: {}",
+                accumulator.root_hash(),
+                expected_root_hash
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The download of the state snapshot a full node fast syncs from.
+pub(crate) struct StateSnapshotSync {
+    /// The verified ledger of the snapshot with the receiver saving its accounts, from its first
+    /// chunk on.
+    snapshot: Option<(StateSnapshotLedger, Box<dyn StateSnapshotReceiver>)>,
+    /// The key of the last account received.
+    last_key: Option<HashValue>,
+    num_accounts: u64,
+    last_request_time: Option<SystemTime>,
+    request_timeout: Duration,
+}
+
+impl StateSnapshotSync {
+    pub fn new(request_timeout: Duration) -> Self {
+        Self {
+            snapshot: None,
+            last_key: None,
+            num_accounts: 0,
+            last_request_time: None,
+            request_timeout,
+        }
+    }
+
+    pub fn version(&self) -> Option<Version> {
+        self.snapshot.as_ref().map(|(ledger, _)| ledger.version())
+    }
+
+    pub fn num_accounts(&self) -> u64 {
+        self.num_accounts
+    }
+
+    /// Whether the next chunk should be requested, as it wasn't yet or its request timed out.
+    pub fn has_request_timed_out(&self) -> bool {
+        match self.last_request_time {
+            Some(last_request_time) => SystemTime::now()
+                .duration_since(last_request_time)
+                .map_or(false, |elapsed| elapsed >= self.request_timeout),
+            None => true,
+        }
+    }
+
+    /// The request of the next chunk, which is then considered sent.
+    pub fn next_request(&mut self, known_epoch: u64, limit: u64) -> GetStateSnapshotChunkRequest {
+        self.last_request_time = Some(SystemTime::now());
+        GetStateSnapshotChunkRequest {
+            version: self.version(),
+            known_epoch,
+            last_key: self.last_key,
+            limit,
+        }
+    }
+
+    /// Whether the response holds the next chunk. The responses to duplicate or timed out
+    /// requests, and the first chunks of other snapshots, don't.
+    pub fn is_next_chunk(&self, response: &GetStateSnapshotChunkResponse) -> bool {
+        let first_key = match response.chunk.account_blobs.first() {
+            Some((key, _blob)) => *key,
+            None => return false,
+        };
+        let version_matches = match self.version() {
+            Some(version) => version == response.version,
+            None => response.ledger.is_some(),
+        };
+        version_matches && self.last_key.map_or(true, |last_key| first_key > last_key)
+    }
+
+    /// Starts saving the snapshot of the verified ledger with the receiver.
+    pub fn start(&mut self, ledger: StateSnapshotLedger, receiver: Box<dyn StateSnapshotReceiver>) {
+        self.snapshot = Some((ledger, receiver));
+        self.last_key = None;
+        self.num_accounts = 0;
+    }
+
+    /// Adds the next chunk, checking it against the state root of the snapshot. Returns the ledger
+    /// of the snapshot once its last chunk is added and the whole state is saved.
+    ///
+    /// If the whole state doesn't match the state root once the last chunk is added, the snapshot
+    /// is dropped so that the sync starts over from a new one.
+    pub fn add_chunk(
+        &mut self,
+        chunk: AccountStateChunkWithProof,
+    ) -> Result<Option<StateSnapshotLedger>, Error> {
+        let (_ledger, receiver) = self.snapshot.as_mut().ok_or_else(|| {
+            Error::UnexpectedError("The state snapshot sync is not started".into())
+        })?;
+        // The proof of the last chunk can't have any account on its right.
+        if chunk.is_last_chunk
+            && chunk
+                .proof
+                .right_siblings()
+                .iter()
+                .any(|sibling| *sibling != *SPARSE_MERKLE_PLACEHOLDER_HASH)
+        {
+            return Err(Error::InvalidStateSnapshotChunk(
+                "The last chunk is followed by more accounts in its proof".into(),
+            ));
+        }
+        let last_key = chunk.last_key();
+        let num_accounts = chunk.account_blobs.len() as u64;
+        receiver
+            .add_chunk(chunk.account_blobs, chunk.proof)
+            .map_err(invalid_chunk)?;
+        self.last_key = last_key;
+        self.num_accounts += num_accounts;
+        // The next chunk can be requested right away.
+        self.last_request_time = None;
+
+        if !chunk.is_last_chunk {
+            return Ok(None);
+        }
+        let (ledger, receiver) = self.snapshot.take().expect("The snapshot was just checked");
+        if let Err(error) = receiver.finish() {
+            self.reset();
+            return Err(invalid_chunk(error));
+        }
+        Ok(Some(ledger))
+    }
+
+    /// Drops the snapshot being saved, so the sync starts over from a new one.
+    pub fn reset(&mut self) {
+        self.snapshot = None;
+        self.last_key = None;
+        self.num_accounts = 0;
+        self.last_request_time = None;
+    }
+}
+
+fn invalid_chunk<E: Display>(error: E) -> Error {
+    Error::InvalidStateSnapshotChunk(error.to_string())
+}
@@ -0,0 +1,155 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The progress of state sync towards the highest version it knows of: the synced version, the
+//! rate it syncs at and the estimated time to catch up.
+//!
+//! The coordinator publishes the progress from its local state whenever it changes, and on every
+//! progress check, so reading it (e.g., by the health endpoints) never goes through storage.
+
+use crate::counters;
+use diem_infallible::RwLock;
+use diem_types::transaction::Version;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// The progress last published by the coordinator of the process, None until it starts
+static SYNC_PROGRESS: Lazy<RwLock<Option<SyncProgress>>> = Lazy::new(|| RwLock::new(None));
+
+/// Minimum time between two samples of the sync rate, so that the bursts of commits don't skew it
+const RATE_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+/// Weight of the latest sample in the moving average of the sync rate
+const RATE_SMOOTHING_FACTOR: f64 = 0.3;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SyncProgress {
+    pub synced_version: Version,
+    pub committed_version: Version,
+    /// The highest version of the chain state sync knows of, None until it knows one
+    pub highest_known_version: Option<Version>,
+    /// Versions synced per second, as a moving average over the recent seconds
+    pub versions_per_sec: f64,
+    /// Estimated seconds to sync to `highest_known_version`, None while it's unknown or while
+    /// nothing is synced
+    pub eta_secs: Option<u64>,
+}
+
+/// Returns the progress last published by state sync, None if it isn't running
+pub fn get_sync_progress() -> Option<SyncProgress> {
+    SYNC_PROGRESS.read().clone()
+}
+
+/// Tracks the sync rate from the synced versions it's updated with, and publishes the progress
+pub(crate) struct SyncProgressTracker {
+    highest_known_version: Option<Version>,
+    // The synced version at the start of the current rate sample
+    sample_start: Option<(Instant, Version)>,
+    versions_per_sec: f64,
+}
+
+impl SyncProgressTracker {
+    pub fn new() -> Self {
+        Self {
+            highest_known_version: None,
+            sample_start: None,
+            versions_per_sec: 0.0,
+        }
+    }
+
+    pub fn update_highest_known_version(&mut self, version: Version) {
+        self.highest_known_version = Some(
+            self.highest_known_version
+                .map_or(version, |highest| highest.max(version)),
+        );
+    }
+
+    /// Samples the sync rate with the synced version if the sample interval elapsed, then
+    /// publishes the progress.
+    pub fn update(&mut self, synced_version: Version, committed_version: Version) {
+        self.sample_rate(Instant::now(), synced_version);
+
+        let progress = self.progress(synced_version, committed_version);
+        counters::set_sync_status(&progress);
+        *SYNC_PROGRESS.write() = Some(progress);
+    }
+
+    fn sample_rate(&mut self, now: Instant, synced_version: Version) {
+        let (start_time, start_version) = match self.sample_start {
+            Some(sample_start) => sample_start,
+            None => {
+                self.sample_start = Some((now, synced_version));
+                return;
+            }
+        };
+        let elapsed = now.duration_since(start_time);
+        if elapsed < RATE_SAMPLE_INTERVAL {
+            return;
+        }
+
+        let rate = synced_version.saturating_sub(start_version) as f64 / elapsed.as_secs_f64();
+        self.versions_per_sec =
+            RATE_SMOOTHING_FACTOR * rate + (1.0 - RATE_SMOOTHING_FACTOR) * self.versions_per_sec;
+        self.sample_start = Some((now, synced_version));
+    }
+
+    fn progress(&self, synced_version: Version, committed_version: Version) -> SyncProgress {
+        // The local commits (e.g., of consensus) may get ahead of the versions known from peers
+        let highest_known_version = self
+            .highest_known_version
+            .map(|highest_known_version| highest_known_version.max(synced_version));
+        let eta_secs = highest_known_version.and_then(|highest_known_version| {
+            let remaining_versions = highest_known_version.saturating_sub(synced_version);
+            if remaining_versions == 0 {
+                Some(0)
+            } else if self.versions_per_sec > 0.0 {
+                Some((remaining_versions as f64 / self.versions_per_sec).ceil() as u64)
+            } else {
+                None
+            }
+        });
+
+        SyncProgress {
+            synced_version,
+            committed_version,
+            highest_known_version,
+            versions_per_sec: self.versions_per_sec,
+            eta_secs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_rate_and_eta() {
+        let mut tracker = SyncProgressTracker::new();
+        let start = Instant::now();
+
+        // Nothing is known of the rate before the first sample interval
+        tracker.sample_rate(start, 100);
+        tracker.update_highest_known_version(1100);
+        let progress = tracker.progress(100, 100);
+        assert_eq!(progress.highest_known_version, Some(1100));
+        assert!(progress.versions_per_sec < f64::EPSILON);
+        assert_eq!(progress.eta_secs, None);
+
+        // Samples within the interval are ignored
+        tracker.sample_rate(start + Duration::from_millis(500), 200);
+        assert!(tracker.versions_per_sec < f64::EPSILON);
+
+        // 100 versions per second, smoothed from 0
+        tracker.sample_rate(start + Duration::from_secs(2), 300);
+        let progress = tracker.progress(300, 300);
+        assert!((progress.versions_per_sec - 30.0).abs() < 1e-9);
+        assert_eq!(progress.eta_secs, Some(27)); // 800 versions at 30 versions per second
+
+        // The highest known version never decreases, and the ETA is 0 once reached
+        tracker.update_highest_known_version(500);
+        let progress = tracker.progress(1100, 1100);
+        assert_eq!(progress.highest_known_version, Some(1100));
+        assert_eq!(progress.eta_secs, Some(0));
+    }
+}
@@ -20,6 +20,7 @@ use diem_types::{
     block_info::BlockInfo,
     chain_id::ChainId,
     contract_event::ContractEvent,
+    epoch_change::EpochChangeProof,
     epoch_state::EpochState,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     network_address::{
@@ -30,8 +31,8 @@ use diem_types::{
     proof::TransactionListProof,
     test_helpers::transaction_test_helpers::get_test_signed_txn,
     transaction::{
-        authenticator::AuthenticationKey, SignedTransaction, Transaction, TransactionListWithProof,
-        TransactionPayload,
+        authenticator::AuthenticationKey, SignedTransaction, Transaction, TransactionInfo,
+        TransactionListWithProof, TransactionPayload, Version,
     },
     validator_config::ValidatorConfig,
     validator_info::ValidatorInfo,
@@ -74,6 +75,7 @@ use std::{
     ops::DerefMut,
     sync::Arc,
 };
+use storage_interface::{AccountStateChunkWithProof, StateSnapshotReceiver};
 use tokio::runtime::Runtime;
 use vm_genesis::GENESIS_KEYPAIR;
 
@@ -905,4 +907,51 @@ impl ExecutorProxyTrait for MockExecutorProxy {
     ) -> Result<(), Error> {
         Ok(())
     }
+
+    fn get_epoch_change_proof(
+        &self,
+        _start_epoch: u64,
+        _end_epoch: u64,
+    ) -> Result<EpochChangeProof, Error> {
+        Err(state_snapshot_unsupported())
+    }
+
+    fn get_bootstrap_ledger(
+        &self,
+        _version: Version,
+    ) -> Result<(LedgerInfoWithSignatures, TransactionInfo, Vec<HashValue>), Error> {
+        Err(state_snapshot_unsupported())
+    }
+
+    fn get_account_chunk(
+        &self,
+        _version: Version,
+        _last_key: Option<HashValue>,
+        _limit: u64,
+    ) -> Result<AccountStateChunkWithProof, Error> {
+        Err(state_snapshot_unsupported())
+    }
+
+    fn get_state_snapshot_receiver(
+        &self,
+        _version: Version,
+        _expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>, Error> {
+        Err(state_snapshot_unsupported())
+    }
+
+    fn finalize_state_snapshot(
+        &mut self,
+        _epoch_endings: &[LedgerInfoWithSignatures],
+        _ledger_info: &LedgerInfoWithSignatures,
+        _txn_info: &TransactionInfo,
+        _frozen_subtrees: &[HashValue],
+    ) -> Result<(), Error> {
+        Err(state_snapshot_unsupported())
+    }
+}
+
+// The mock storage holds no account states, so the nodes of the tests can't fast sync
+fn state_snapshot_unsupported() -> Error {
+    Error::UnexpectedError("State snapshots are not supported by the mock storage".into())
 }
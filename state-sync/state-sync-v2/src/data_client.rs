@@ -0,0 +1,40 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::Error;
+use async_trait::async_trait;
+use diem_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    transaction::{TransactionListWithProof, TransactionOutputListWithProof, Version},
+};
+
+/// The source of the data streamed by the data streaming service, e.g., the peers of the node.
+/// The ranges are inclusive, and are never larger than the chunk sizes of the service config.
+#[async_trait]
+pub trait DiemDataClient: Send + Sync + 'static {
+    /// Fetches the ledger infos ending the epochs `start_epoch` to `end_epoch`.
+    async fn get_epoch_ending_ledger_infos(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<Vec<LedgerInfoWithSignatures>, Error>;
+
+    /// Fetches the transactions at versions `start_version` to `end_version`, proven against the
+    /// ledger info at `proof_version`.
+    async fn get_transactions_with_proof(
+        &self,
+        proof_version: Version,
+        start_version: Version,
+        end_version: Version,
+        include_events: bool,
+    ) -> Result<TransactionListWithProof, Error>;
+
+    /// Fetches the transaction outputs at versions `start_version` to `end_version`, proven
+    /// against the ledger info at `proof_version`.
+    async fn get_transaction_outputs_with_proof(
+        &self,
+        proof_version: Version,
+        start_version: Version,
+        end_version: Version,
+    ) -> Result<TransactionOutputListWithProof, Error>;
+}
@@ -0,0 +1,288 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{data_client::DiemDataClient, error::Error, streaming_client::StreamRequest};
+use diem_config::config::DataStreamingServiceConfig;
+use diem_logger::prelude::*;
+use diem_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    transaction::{TransactionListWithProof, TransactionOutputListWithProof},
+};
+use futures::{
+    channel::mpsc,
+    stream::{self, FusedStream, Stream},
+    SinkExt, StreamExt,
+};
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+pub type NotificationId = u64;
+
+/// A chunk of a data stream. The notifications of a stream are numbered from 0, in the order of
+/// their chunks.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DataNotification {
+    pub notification_id: NotificationId,
+    pub data_payload: DataPayload,
+}
+
+/// The data of a chunk. It's checked to hold the requested range, but its proofs are left for the
+/// consumer to verify against the ledger infos it trusts.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataPayload {
+    EpochEndingLedgerInfos(Vec<LedgerInfoWithSignatures>),
+    TransactionsWithProof(TransactionListWithProof),
+    TransactionOutputsWithProof(TransactionOutputListWithProof),
+}
+
+/// The consumer end of a data stream: the chunks of the stream in order, until its last chunk or
+/// the chunk that failed to be fetched, whose error then ends the stream. Dropping the listener
+/// stops the stream.
+#[derive(Debug)]
+pub struct DataStreamListener {
+    pub stream_id: u64,
+    notification_receiver: mpsc::Receiver<Result<DataNotification, Error>>,
+}
+
+impl DataStreamListener {
+    pub(crate) fn new(
+        stream_id: u64,
+        notification_receiver: mpsc::Receiver<Result<DataNotification, Error>>,
+    ) -> Self {
+        Self {
+            stream_id,
+            notification_receiver,
+        }
+    }
+}
+
+impl Stream for DataStreamListener {
+    type Item = Result<DataNotification, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().notification_receiver).poll_next(cx)
+    }
+}
+
+impl FusedStream for DataStreamListener {
+    fn is_terminated(&self) -> bool {
+        self.notification_receiver.is_terminated()
+    }
+}
+
+impl StreamRequest {
+    /// Checks the range of the request isn't empty
+    pub(crate) fn verify(&self) -> Result<(), Error> {
+        let (start, end) = self.range();
+        if start > end {
+            return Err(Error::InvalidRequest(format!(
+                "The start of the range ({}) is after its end ({}): {:?}",
+                start, end, self
+            )));
+        }
+        Ok(())
+    }
+
+    fn range(&self) -> (u64, u64) {
+        match *self {
+            StreamRequest::GetAllEpochEndingLedgerInfos {
+                start_epoch,
+                end_epoch,
+            } => (start_epoch, end_epoch),
+            StreamRequest::GetAllTransactions {
+                start_version,
+                end_version,
+                ..
+            }
+            | StreamRequest::GetAllTransactionOutputs {
+                start_version,
+                end_version,
+                ..
+            } => (start_version, end_version),
+        }
+    }
+
+    fn chunk_size(&self, config: &DataStreamingServiceConfig) -> u64 {
+        let chunk_size = match self {
+            StreamRequest::GetAllEpochEndingLedgerInfos { .. } => config.max_epoch_chunk_size,
+            StreamRequest::GetAllTransactions { .. } => config.max_transaction_chunk_size,
+            StreamRequest::GetAllTransactionOutputs { .. } => {
+                config.max_transaction_output_chunk_size
+            }
+        };
+        chunk_size.max(1)
+    }
+}
+
+/// Fetches the chunks of the stream, up to `max_concurrent_requests` at once, and sends them to the
+/// listener in order. The chunks are only fetched as fast as the listener reads them: while the
+/// channel to the listener is full, no new chunk is requested.
+pub(crate) async fn stream_data<T: DiemDataClient>(
+    stream_id: u64,
+    stream_request: StreamRequest,
+    config: DataStreamingServiceConfig,
+    data_client: Arc<T>,
+    mut notification_sender: mpsc::Sender<Result<DataNotification, Error>>,
+) {
+    let (start, end) = stream_request.range();
+    let max_request_retry = config.max_request_retry;
+    let mut chunks = stream::iter(chunk_ranges(start, end, stream_request.chunk_size(&config)))
+        .map(|(chunk_start, chunk_end)| {
+            fetch_chunk(
+                stream_id,
+                data_client.clone(),
+                stream_request,
+                chunk_start,
+                chunk_end,
+                max_request_retry,
+            )
+        })
+        .buffered(config.max_concurrent_requests.max(1) as usize);
+
+    let mut notification_id = 0;
+    while let Some(result) = chunks.next().await {
+        let is_error = result.is_err();
+        let notification = result.map(|data_payload| DataNotification {
+            notification_id,
+            data_payload,
+        });
+        if notification_sender.send(notification).await.is_err() {
+            debug!(
+                stream_id = stream_id,
+                "The data stream listener was dropped"
+            );
+            return;
+        }
+        if is_error {
+            return;
+        }
+        notification_id += 1;
+    }
+}
+
+/// Splits the inclusive range into consecutive chunks of at most `chunk_size` items
+fn chunk_ranges(start: u64, end: u64, chunk_size: u64) -> impl Iterator<Item = (u64, u64)> {
+    (start..=end)
+        .step_by(chunk_size as usize)
+        .map(move |chunk_start| {
+            (
+                chunk_start,
+                chunk_start.saturating_add(chunk_size - 1).min(end),
+            )
+        })
+}
+
+/// Fetches the chunk, requesting it again up to `max_request_retry` times if it fails
+async fn fetch_chunk<T: DiemDataClient>(
+    stream_id: u64,
+    data_client: Arc<T>,
+    stream_request: StreamRequest,
+    start: u64,
+    end: u64,
+    max_request_retry: u64,
+) -> Result<DataPayload, Error> {
+    let mut num_retries = 0;
+    loop {
+        match fetch_and_check_chunk(&*data_client, stream_request, start, end).await {
+            Ok(data_payload) => return Ok(data_payload),
+            Err(error) if num_retries < max_request_retry => {
+                warn!(
+                    stream_id = stream_id,
+                    "Failed to fetch the chunk from {} to {}, retrying: {}", start, end, error
+                );
+                num_retries += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+async fn fetch_and_check_chunk<T: DiemDataClient>(
+    data_client: &T,
+    stream_request: StreamRequest,
+    start: u64,
+    end: u64,
+) -> Result<DataPayload, Error> {
+    let expected_len = (end - start).saturating_add(1) as usize;
+    match stream_request {
+        StreamRequest::GetAllEpochEndingLedgerInfos { .. } => {
+            let ledger_infos = data_client
+                .get_epoch_ending_ledger_infos(start, end)
+                .await?;
+            let epochs_match = ledger_infos.len() == expected_len
+                && ledger_infos
+                    .iter()
+                    .zip(start..)
+                    .all(|(ledger_info, epoch)| {
+                        ledger_info.ledger_info().epoch() == epoch
+                            && ledger_info.ledger_info().ends_epoch()
+                    });
+            if !epochs_match {
+                return Err(Error::InvalidData(format!(
+                    "Expected the ledger infos ending the epochs {} to {}",
+                    start, end
+                )));
+            }
+            Ok(DataPayload::EpochEndingLedgerInfos(ledger_infos))
+        }
+        StreamRequest::GetAllTransactions {
+            proof_version,
+            include_events,
+            ..
+        } => {
+            let transactions = data_client
+                .get_transactions_with_proof(proof_version, start, end, include_events)
+                .await?;
+            if transactions.first_transaction_version != Some(start)
+                || transactions.len() != expected_len
+            {
+                return Err(Error::InvalidData(format!(
+                    "Expected the transactions at versions {} to {}, got {} from {:?}",
+                    start,
+                    end,
+                    transactions.len(),
+                    transactions.first_transaction_version
+                )));
+            }
+            Ok(DataPayload::TransactionsWithProof(transactions))
+        }
+        StreamRequest::GetAllTransactionOutputs { proof_version, .. } => {
+            let outputs = data_client
+                .get_transaction_outputs_with_proof(proof_version, start, end)
+                .await?;
+            if outputs.first_transaction_output_version != Some(start)
+                || outputs.len() != expected_len
+            {
+                return Err(Error::InvalidData(format!(
+                    "Expected the transaction outputs at versions {} to {}, got {} from {:?}",
+                    start,
+                    end,
+                    outputs.len(),
+                    outputs.first_transaction_output_version
+                )));
+            }
+            Ok(DataPayload::TransactionOutputsWithProof(outputs))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_ranges() {
+        assert_eq!(
+            chunk_ranges(5, 29, 10).collect::<Vec<_>>(),
+            vec![(5, 14), (15, 24), (25, 29)]
+        );
+        assert_eq!(chunk_ranges(7, 7, 10).collect::<Vec<_>>(), vec![(7, 7)]);
+        assert_eq!(
+            chunk_ranges(u64::MAX - 1, u64::MAX, 1).collect::<Vec<_>>(),
+            vec![(u64::MAX - 1, u64::MAX - 1), (u64::MAX, u64::MAX)]
+        );
+    }
+}
@@ -0,0 +1,25 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use futures::channel::oneshot::Canceled;
+use thiserror::Error;
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    #[error("The data client failed to fetch the data: {0}")]
+    DataClientError(String),
+    #[error("Received invalid data from the data client: {0}")]
+    InvalidData(String),
+    #[error("Received an invalid stream request: {0}")]
+    InvalidRequest(String),
+    #[error("Failed to send a stream request to the data streaming service: {0}")]
+    StreamingServiceUnavailable(String),
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<Canceled> for Error {
+    fn from(canceled: Canceled) -> Self {
+        Error::StreamingServiceUnavailable(canceled.to_string())
+    }
+}
@@ -1,7 +1,16 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-//! This is an empty skeleton library for state sync v2.
-//!
-//! TODO(joshlind): update this description when we start implementing state sync v2.
+//! State sync v2. The data the node syncs is fetched through the data streaming service: its
+//! consumers request a range of data (e.g., transactions, transaction outputs or epoch ending
+//! ledger infos) and receive it as an ordered stream of chunks, which the service fetches from a
+//! data client (`DiemDataClient`). For now, the only data client serves the data of the local
+//! storage (`StorageDataClient`); state sync v1 doesn't fetch its data through the service yet.
 #![forbid(unsafe_code)]
+
+pub mod data_client;
+pub mod data_stream;
+pub mod error;
+pub mod storage_data_client;
+pub mod streaming_client;
+pub mod streaming_service;
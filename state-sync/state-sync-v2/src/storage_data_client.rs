@@ -0,0 +1,69 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{data_client::DiemDataClient, error::Error};
+use async_trait::async_trait;
+use diem_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    transaction::{TransactionListWithProof, TransactionOutputListWithProof, Version},
+};
+use std::sync::Arc;
+use storage_interface::DbReader;
+
+/// Serves the data from the local storage, for the consumers on the node itself (e.g., an
+/// indexer) to stream the data the node has synced already. The storage doesn't keep the
+/// transaction outputs, so they can't be served.
+pub struct StorageDataClient {
+    storage: Arc<dyn DbReader>,
+}
+
+impl StorageDataClient {
+    pub fn new(storage: Arc<dyn DbReader>) -> Self {
+        Self { storage }
+    }
+}
+
+#[async_trait]
+impl DiemDataClient for StorageDataClient {
+    async fn get_epoch_ending_ledger_infos(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<Vec<LedgerInfoWithSignatures>, Error> {
+        let epoch_change_proof = self
+            .storage
+            .get_epoch_ending_ledger_infos(start_epoch, end_epoch.saturating_add(1))
+            .map_err(|error| Error::DataClientError(error.to_string()))?;
+        Ok(epoch_change_proof.ledger_info_with_sigs)
+    }
+
+    async fn get_transactions_with_proof(
+        &self,
+        proof_version: Version,
+        start_version: Version,
+        end_version: Version,
+        include_events: bool,
+    ) -> Result<TransactionListWithProof, Error> {
+        let num_transactions = (end_version - start_version).saturating_add(1);
+        self.storage
+            .get_transactions(
+                start_version,
+                num_transactions,
+                proof_version,
+                include_events,
+            )
+            .map_err(|error| Error::DataClientError(error.to_string()))
+    }
+
+    async fn get_transaction_outputs_with_proof(
+        &self,
+        _proof_version: Version,
+        start_version: Version,
+        end_version: Version,
+    ) -> Result<TransactionOutputListWithProof, Error> {
+        Err(Error::DataClientError(format!(
+            "The storage doesn't keep the transaction outputs at versions {} to {}",
+            start_version, end_version
+        )))
+    }
+}
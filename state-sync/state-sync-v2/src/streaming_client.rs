@@ -0,0 +1,142 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{data_stream::DataStreamListener, error::Error};
+use async_trait::async_trait;
+use diem_types::transaction::Version;
+use futures::channel::{mpsc, oneshot};
+
+/// The interface of the data streaming service to its consumers. Every request opens a new data
+/// stream with the requested range, which ends after the last item of the range. The ranges are
+/// inclusive.
+#[async_trait]
+pub trait DataStreamingClient {
+    /// Streams the ledger infos ending the epochs `start_epoch` to `end_epoch`.
+    async fn get_all_epoch_ending_ledger_infos(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<DataStreamListener, Error>;
+
+    /// Streams the transactions at versions `start_version` to `end_version`, proven against the
+    /// ledger info at `proof_version`.
+    async fn get_all_transactions(
+        &self,
+        start_version: Version,
+        end_version: Version,
+        proof_version: Version,
+        include_events: bool,
+    ) -> Result<DataStreamListener, Error>;
+
+    /// Streams the transaction outputs at versions `start_version` to `end_version`, proven
+    /// against the ledger info at `proof_version`.
+    async fn get_all_transaction_outputs(
+        &self,
+        start_version: Version,
+        end_version: Version,
+        proof_version: Version,
+    ) -> Result<DataStreamListener, Error>;
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StreamRequest {
+    GetAllEpochEndingLedgerInfos {
+        start_epoch: u64,
+        end_epoch: u64,
+    },
+    GetAllTransactions {
+        start_version: Version,
+        end_version: Version,
+        proof_version: Version,
+        include_events: bool,
+    },
+    GetAllTransactionOutputs {
+        start_version: Version,
+        end_version: Version,
+        proof_version: Version,
+    },
+}
+
+/// A stream request with the channel the data streaming service responds on
+#[derive(Debug)]
+pub struct StreamRequestMessage {
+    pub stream_request: StreamRequest,
+    pub response_sender: oneshot::Sender<Result<DataStreamListener, Error>>,
+}
+
+/// The end of the stream requests the data streaming service listens on
+pub type StreamingServiceListener = mpsc::UnboundedReceiver<StreamRequestMessage>;
+
+/// The `DataStreamingClient` sending its requests to a data streaming service
+#[derive(Clone, Debug)]
+pub struct StreamingServiceClient {
+    request_sender: mpsc::UnboundedSender<StreamRequestMessage>,
+}
+
+impl StreamingServiceClient {
+    async fn send_request(
+        &self,
+        stream_request: StreamRequest,
+    ) -> Result<DataStreamListener, Error> {
+        let (response_sender, response_receiver) = oneshot::channel();
+        self.request_sender
+            .unbounded_send(StreamRequestMessage {
+                stream_request,
+                response_sender,
+            })
+            .map_err(|error| Error::StreamingServiceUnavailable(error.to_string()))?;
+        response_receiver.await?
+    }
+}
+
+#[async_trait]
+impl DataStreamingClient for StreamingServiceClient {
+    async fn get_all_epoch_ending_ledger_infos(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<DataStreamListener, Error> {
+        self.send_request(StreamRequest::GetAllEpochEndingLedgerInfos {
+            start_epoch,
+            end_epoch,
+        })
+        .await
+    }
+
+    async fn get_all_transactions(
+        &self,
+        start_version: Version,
+        end_version: Version,
+        proof_version: Version,
+        include_events: bool,
+    ) -> Result<DataStreamListener, Error> {
+        self.send_request(StreamRequest::GetAllTransactions {
+            start_version,
+            end_version,
+            proof_version,
+            include_events,
+        })
+        .await
+    }
+
+    async fn get_all_transaction_outputs(
+        &self,
+        start_version: Version,
+        end_version: Version,
+        proof_version: Version,
+    ) -> Result<DataStreamListener, Error> {
+        self.send_request(StreamRequest::GetAllTransactionOutputs {
+            start_version,
+            end_version,
+            proof_version,
+        })
+        .await
+    }
+}
+
+/// Creates a client of a data streaming service, and the listener the service is created with
+pub fn new_streaming_service_client_listener_pair(
+) -> (StreamingServiceClient, StreamingServiceListener) {
+    let (request_sender, request_listener) = mpsc::unbounded();
+    (StreamingServiceClient { request_sender }, request_listener)
+}
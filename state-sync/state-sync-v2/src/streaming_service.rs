@@ -0,0 +1,324 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    data_client::DiemDataClient,
+    data_stream::{self, DataStreamListener},
+    error::Error,
+    streaming_client::{StreamRequest, StreamingServiceListener},
+};
+use diem_config::config::DataStreamingServiceConfig;
+use diem_logger::prelude::*;
+use futures::{channel::mpsc, StreamExt};
+use std::sync::Arc;
+
+/// The data streaming service: it opens a data stream for every request of its clients, and
+/// fetches the data of each stream from the data client in a task of its own.
+pub struct DataStreamingService<T> {
+    config: DataStreamingServiceConfig,
+    data_client: Arc<T>,
+    stream_requests: StreamingServiceListener,
+    next_stream_id: u64,
+}
+
+impl<T: DiemDataClient> DataStreamingService<T> {
+    pub fn new(
+        config: DataStreamingServiceConfig,
+        data_client: T,
+        stream_requests: StreamingServiceListener,
+    ) -> Self {
+        Self {
+            config,
+            data_client: Arc::new(data_client),
+            stream_requests,
+            next_stream_id: 0,
+        }
+    }
+
+    /// Serves the stream requests until all the clients are dropped. Must run in a tokio runtime,
+    /// which the data streams are spawned on.
+    pub async fn start_service(mut self) {
+        while let Some(request_message) = self.stream_requests.next().await {
+            let response = self.create_data_stream(request_message.stream_request);
+            if request_message.response_sender.send(response).is_err() {
+                debug!("The requester of a data stream dropped the response channel");
+            }
+        }
+    }
+
+    fn create_data_stream(
+        &mut self,
+        stream_request: StreamRequest,
+    ) -> Result<DataStreamListener, Error> {
+        stream_request.verify()?;
+
+        let stream_id = self.next_stream_id;
+        self.next_stream_id += 1;
+        let (notification_sender, notification_receiver) =
+            mpsc::channel(self.config.max_data_stream_channel_size as usize);
+        tokio::spawn(data_stream::stream_data(
+            stream_id,
+            stream_request,
+            self.config.clone(),
+            self.data_client.clone(),
+            notification_sender,
+        ));
+        debug!(
+            stream_id = stream_id,
+            "Opened a data stream: {:?}", stream_request
+        );
+        Ok(DataStreamListener::new(stream_id, notification_receiver))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data_stream::{DataNotification, DataPayload},
+        streaming_client::{new_streaming_service_client_listener_pair, DataStreamingClient},
+    };
+    use async_trait::async_trait;
+    use diem_crypto::HashValue;
+    use diem_types::{
+        account_address::AccountAddress,
+        block_info::BlockInfo,
+        block_metadata::BlockMetadata,
+        epoch_state::EpochState,
+        ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+        proof::TransactionListProof,
+        transaction::{
+            Transaction, TransactionListWithProof, TransactionOutputListWithProof, Version,
+        },
+    };
+    use std::{
+        collections::BTreeMap,
+        sync::atomic::{AtomicU64, Ordering},
+        time::Duration,
+    };
+
+    /// Serves any range, with the later chunks served faster than the earlier ones, after
+    /// `num_failures` failed requests
+    struct MockDataClient {
+        num_failures: u64,
+        num_requests: AtomicU64,
+    }
+
+    impl MockDataClient {
+        fn new(num_failures: u64) -> Self {
+            Self {
+                num_failures,
+                num_requests: AtomicU64::new(0),
+            }
+        }
+
+        async fn serve_request(&self, start: u64) -> Result<(), Error> {
+            let num_requests = self.num_requests.fetch_add(1, Ordering::SeqCst);
+            if num_requests < self.num_failures {
+                return Err(Error::DataClientError("Mock failure".into()));
+            }
+            tokio::time::sleep(Duration::from_millis(50u64.saturating_sub(start))).await;
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl DiemDataClient for MockDataClient {
+        async fn get_epoch_ending_ledger_infos(
+            &self,
+            start_epoch: u64,
+            end_epoch: u64,
+        ) -> Result<Vec<LedgerInfoWithSignatures>, Error> {
+            self.serve_request(start_epoch).await?;
+            Ok((start_epoch..=end_epoch)
+                .map(|epoch| {
+                    let block_info = BlockInfo::new(
+                        epoch,
+                        0,
+                        HashValue::zero(),
+                        HashValue::zero(),
+                        0,
+                        0,
+                        Some(EpochState::empty()),
+                    );
+                    LedgerInfoWithSignatures::new(
+                        LedgerInfo::new(block_info, HashValue::zero()),
+                        BTreeMap::new(),
+                    )
+                })
+                .collect())
+        }
+
+        async fn get_transactions_with_proof(
+            &self,
+            _proof_version: Version,
+            start_version: Version,
+            end_version: Version,
+            _include_events: bool,
+        ) -> Result<TransactionListWithProof, Error> {
+            self.serve_request(start_version).await?;
+            Ok(TransactionListWithProof::new(
+                (start_version..=end_version)
+                    .map(create_transaction)
+                    .collect(),
+                None,
+                Some(start_version),
+                TransactionListProof::new_empty(),
+            ))
+        }
+
+        async fn get_transaction_outputs_with_proof(
+            &self,
+            _proof_version: Version,
+            start_version: Version,
+            _end_version: Version,
+        ) -> Result<TransactionOutputListWithProof, Error> {
+            self.serve_request(start_version).await?;
+            // Always serves an empty list, which doesn't hold the requested range
+            Ok(TransactionOutputListWithProof::new_empty())
+        }
+    }
+
+    fn create_transaction(version: Version) -> Transaction {
+        Transaction::BlockMetadata(BlockMetadata::new(
+            HashValue::zero(),
+            version,
+            0,
+            vec![],
+            AccountAddress::ZERO,
+        ))
+    }
+
+    fn create_streaming_client(
+        config: DataStreamingServiceConfig,
+        data_client: MockDataClient,
+    ) -> impl DataStreamingClient {
+        let (streaming_client, streaming_service_listener) =
+            new_streaming_service_client_listener_pair();
+        let streaming_service =
+            DataStreamingService::new(config, data_client, streaming_service_listener);
+        tokio::spawn(streaming_service.start_service());
+        streaming_client
+    }
+
+    #[tokio::test]
+    async fn test_stream_transactions_in_order() {
+        let config = DataStreamingServiceConfig {
+            max_concurrent_requests: 4,
+            max_transaction_chunk_size: 10,
+            ..Default::default()
+        };
+        let streaming_client = create_streaming_client(config, MockDataClient::new(0));
+
+        let mut stream = streaming_client
+            .get_all_transactions(5, 44, 100, false)
+            .await
+            .unwrap();
+        let mut next_version = 5;
+        let mut next_notification_id = 0;
+        while let Some(notification) = stream.next().await {
+            let DataNotification {
+                notification_id,
+                data_payload,
+            } = notification.unwrap();
+            assert_eq!(notification_id, next_notification_id);
+            match data_payload {
+                DataPayload::TransactionsWithProof(transactions) => {
+                    assert_eq!(transactions.first_transaction_version, Some(next_version));
+                    next_version += transactions.len() as u64;
+                }
+                data_payload => panic!("Unexpected data payload: {:?}", data_payload),
+            }
+            next_notification_id += 1;
+        }
+        assert_eq!(next_version, 45);
+        assert_eq!(next_notification_id, 4);
+    }
+
+    #[tokio::test]
+    async fn test_stream_epoch_ending_ledger_infos_with_retries() {
+        let config = DataStreamingServiceConfig {
+            max_concurrent_requests: 1,
+            max_epoch_chunk_size: 2,
+            max_request_retry: 2,
+            ..Default::default()
+        };
+        let streaming_client = create_streaming_client(config, MockDataClient::new(2));
+
+        let stream = streaming_client
+            .get_all_epoch_ending_ledger_infos(1, 5)
+            .await
+            .unwrap();
+        let epochs: Vec<_> = stream
+            .map(|notification| match notification.unwrap().data_payload {
+                DataPayload::EpochEndingLedgerInfos(ledger_infos) => ledger_infos
+                    .iter()
+                    .map(|ledger_info| ledger_info.ledger_info().epoch())
+                    .collect::<Vec<_>>(),
+                data_payload => panic!("Unexpected data payload: {:?}", data_payload),
+            })
+            .concat()
+            .await;
+        assert_eq!(epochs, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_ends_with_error() {
+        let config = DataStreamingServiceConfig {
+            max_request_retry: 1,
+            ..Default::default()
+        };
+        let streaming_client = create_streaming_client(config, MockDataClient::new(0));
+
+        // The outputs served never match the requested range
+        let mut stream = streaming_client
+            .get_all_transaction_outputs(0, 10, 10)
+            .await
+            .unwrap();
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(Error::InvalidData(_)))
+        ));
+        assert!(stream.next().await.is_none());
+
+        // Empty ranges are rejected
+        assert!(matches!(
+            streaming_client
+                .get_all_transactions(10, 9, 10, false)
+                .await,
+            Err(Error::InvalidRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stream_backpressure() {
+        let config = DataStreamingServiceConfig {
+            max_concurrent_requests: 2,
+            max_data_stream_channel_size: 3,
+            max_transaction_chunk_size: 1,
+            ..Default::default()
+        };
+        let (streaming_client, streaming_service_listener) =
+            new_streaming_service_client_listener_pair();
+        let streaming_service =
+            DataStreamingService::new(config, MockDataClient::new(0), streaming_service_listener);
+        let data_client = streaming_service.data_client.clone();
+        tokio::spawn(streaming_service.start_service());
+
+        // Without a read, the stream stops fetching once the channel and the requests in flight
+        // are full
+        let mut stream = streaming_client
+            .get_all_transactions(0, 999, 999, false)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let num_requests = data_client.num_requests.load(Ordering::SeqCst);
+        assert!(num_requests < 10, "Too many requests: {}", num_requests);
+
+        // Reading the stream resumes the fetching
+        for _ in 0..20 {
+            stream.next().await.unwrap().unwrap();
+        }
+        assert!(data_client.num_requests.load(Ordering::SeqCst) > num_requests);
+    }
+}
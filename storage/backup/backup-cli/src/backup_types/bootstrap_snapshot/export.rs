@@ -0,0 +1,156 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        bootstrap_snapshot::manifest::{BootstrapLedger, BootstrapSnapshot},
+        state_snapshot::backup::backup_local_state_snapshot,
+    },
+    storage::{BackupStorage, FileHandle, ShellSafeName},
+    utils::{GlobalBackupOpt, RocksdbOpt},
+};
+use anyhow::{anyhow, Result};
+use diem_logger::prelude::*;
+use diem_types::transaction::Version;
+use diemdb::DiemDB;
+use once_cell::sync::Lazy;
+use std::{path::PathBuf, str::FromStr, sync::Arc};
+use storage_interface::DbReader;
+use structopt::StructOpt;
+use tokio::io::AsyncWriteExt;
+
+#[derive(StructOpt)]
+pub struct BootstrapSnapshotExportOpt {
+    #[structopt(
+        long = "db-dir",
+        parse(from_os_str),
+        help = "DB to export from. It's opened read only, so it can be the DB of a running node."
+    )]
+    pub db_dir: PathBuf,
+
+    #[structopt(
+        long = "state-version",
+        help = "Version to export the snapshot at, which must be an epoch ending version or the \
+        latest committed version, defaulting to the latter."
+    )]
+    pub version: Option<Version>,
+
+    #[structopt(flatten)]
+    pub rocksdb_opt: RocksdbOpt,
+}
+
+/// Exports the state snapshot at a version of a local DB, along with the ledger needed to
+/// bootstrap a fullnode from it.
+pub struct BootstrapSnapshotExportController {
+    db_dir: PathBuf,
+    version: Option<Version>,
+    rocksdb_opt: RocksdbOpt,
+    max_chunk_size: usize,
+    storage: Arc<dyn BackupStorage>,
+}
+
+impl BootstrapSnapshotExportController {
+    pub fn new(
+        opt: BootstrapSnapshotExportOpt,
+        global_opt: GlobalBackupOpt,
+        storage: Arc<dyn BackupStorage>,
+    ) -> Self {
+        Self {
+            db_dir: opt.db_dir,
+            version: opt.version,
+            rocksdb_opt: opt.rocksdb_opt,
+            max_chunk_size: global_opt.max_chunk_size,
+            storage,
+        }
+    }
+
+    pub async fn run(self) -> Result<FileHandle> {
+        info!(
+            "Bootstrap snapshot export started, from DB {:?}.",
+            self.db_dir
+        );
+        let ret = self
+            .run_impl()
+            .await
+            .map_err(|e| anyhow!("Bootstrap snapshot export failed: {}", e))?;
+        info!("Bootstrap snapshot export succeeded. Manifest: {}", ret);
+        Ok(ret)
+    }
+
+    async fn run_impl(self) -> Result<FileHandle> {
+        let db = DiemDB::open(
+            &self.db_dir,
+            true, /* read_only */
+            None, /* pruner */
+            self.rocksdb_opt.into(),
+        )?;
+        let version = match self.version {
+            Some(version) => version,
+            None => db.get_latest_ledger_info()?.ledger_info().version(),
+        };
+        let backup_handler = db.get_backup_handler();
+        let (ledger_info, txn_info, frozen_subtrees) =
+            backup_handler.get_bootstrap_ledger(version)?;
+        let epoch_endings = backup_handler
+            .get_epoch_ending_ledger_info_iter(0, ledger_info.ledger_info().next_block_epoch())?
+            .collect::<Result<Vec<_>>>()?;
+        let proof_bytes = bcs::to_bytes(&backup_handler.get_state_root_proof(version)?)?;
+
+        let state_snapshot = backup_local_state_snapshot(
+            &self.storage,
+            &backup_handler,
+            version,
+            txn_info.state_root_hash(),
+            &proof_bytes,
+            self.max_chunk_size,
+        )
+        .await?;
+
+        let backup_handle = self
+            .storage
+            .create_backup_with_random_suffix(&format!("bootstrap_ver_{}", version))
+            .await?;
+        let ledger = BootstrapLedger {
+            ledger_info,
+            txn_info,
+            frozen_subtrees,
+            epoch_endings,
+        };
+        let (ledger_handle, mut ledger_file) = self
+            .storage
+            .create_for_write(&backup_handle, Self::ledger_name())
+            .await?;
+        ledger_file.write_all(&bcs::to_bytes(&ledger)?).await?;
+        ledger_file.shutdown().await?;
+
+        let manifest = BootstrapSnapshot {
+            version,
+            state_snapshot,
+            ledger: ledger_handle,
+        };
+        let (manifest_handle, mut manifest_file) = self
+            .storage
+            .create_for_write(&backup_handle, Self::manifest_name())
+            .await?;
+        manifest_file
+            .write_all(&serde_json::to_vec(&manifest)?)
+            .await?;
+        manifest_file.shutdown().await?;
+
+        Ok(manifest_handle)
+    }
+}
+
+impl BootstrapSnapshotExportController {
+    fn manifest_name() -> &'static ShellSafeName {
+        static NAME: Lazy<ShellSafeName> =
+            Lazy::new(|| ShellSafeName::from_str("bootstrap.manifest").unwrap());
+        &NAME
+    }
+
+    fn ledger_name() -> &'static ShellSafeName {
+        static NAME: Lazy<ShellSafeName> =
+            Lazy::new(|| ShellSafeName::from_str("bootstrap.ledger").unwrap());
+        &NAME
+    }
+}
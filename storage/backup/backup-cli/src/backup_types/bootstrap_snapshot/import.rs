@@ -0,0 +1,186 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        bootstrap_snapshot::manifest::{BootstrapLedger, BootstrapSnapshot},
+        state_snapshot::{
+            manifest::StateSnapshotBackup,
+            restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        },
+    },
+    storage::{BackupStorage, FileHandle},
+    utils::{storage_ext::BackupStorageExt, GlobalRestoreOptions, RestoreRunMode},
+};
+use anyhow::{anyhow, ensure, Result};
+use diem_crypto::hash::{CryptoHash, TransactionAccumulatorHasher};
+use diem_logger::prelude::*;
+use diem_types::{
+    epoch_change::{EpochChangeProof, Verifier},
+    ledger_info::LedgerInfoWithSignatures,
+    proof::accumulator::InMemoryAccumulator,
+    waypoint::Waypoint,
+};
+use std::sync::Arc;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+pub struct BootstrapSnapshotImportOpt {
+    #[structopt(long = "bootstrap-manifest")]
+    pub manifest_handle: FileHandle,
+
+    #[structopt(
+        long,
+        help = "Waypoint the snapshot is verified against, e.g. the genesis waypoint or any later \
+        epoch ending waypoint not newer than the snapshot."
+    )]
+    pub waypoint: Waypoint,
+}
+
+/// Bootstraps an empty DB from a bootstrap snapshot, so that the node starts at its version
+/// without replaying any transaction. Everything in the snapshot is verified against the
+/// waypoint before the ledger is saved.
+pub struct BootstrapSnapshotImportController {
+    storage: Arc<dyn BackupStorage>,
+    global_opt: GlobalRestoreOptions,
+    manifest_handle: FileHandle,
+    waypoint: Waypoint,
+}
+
+impl BootstrapSnapshotImportController {
+    pub fn new(
+        opt: BootstrapSnapshotImportOpt,
+        global_opt: GlobalRestoreOptions,
+        storage: Arc<dyn BackupStorage>,
+    ) -> Self {
+        Self {
+            storage,
+            global_opt,
+            manifest_handle: opt.manifest_handle,
+            waypoint: opt.waypoint,
+        }
+    }
+
+    pub async fn run(self) -> Result<()> {
+        let name = self.name();
+        info!("{} started. Manifest: {}", name, self.manifest_handle);
+        self.run_impl()
+            .await
+            .map_err(|e| anyhow!("{} failed: {}", name, e))?;
+        info!("{} succeeded.", name);
+        Ok(())
+    }
+}
+
+impl BootstrapSnapshotImportController {
+    fn name(&self) -> String {
+        format!("bootstrap snapshot {}", self.global_opt.run_mode.name())
+    }
+
+    async fn run_impl(self) -> Result<()> {
+        let manifest: BootstrapSnapshot =
+            self.storage.load_json_file(&self.manifest_handle).await?;
+        let version = manifest.version;
+        ensure!(
+            version <= self.global_opt.target_version,
+            "Bootstrap snapshot at version {} is newer than the target version {}.",
+            version,
+            self.global_opt.target_version,
+        );
+        let ledger: BootstrapLedger = self.storage.load_bcs_file(&manifest.ledger).await?;
+        ensure!(
+            ledger.ledger_info.ledger_info().version() == version,
+            "LedgerInfo is at version {}, expecting {}.",
+            ledger.ledger_info.ledger_info().version(),
+            version,
+        );
+        verify_ledger_info(&self.waypoint, &ledger.epoch_endings, &ledger.ledger_info)?;
+
+        let accumulator = InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+            ledger.frozen_subtrees.clone(),
+            version,
+        )?
+        .append(&[ledger.txn_info.hash()]);
+        ensure!(
+            accumulator.root_hash()
+                == ledger
+                    .ledger_info
+                    .ledger_info()
+                    .transaction_accumulator_hash(),
+            "Transaction accumulator root hash doesn't match. Computed: {}, in LedgerInfo: {}",
+            accumulator.root_hash(),
+            ledger
+                .ledger_info
+                .ledger_info()
+                .transaction_accumulator_hash(),
+        );
+
+        let state_snapshot: StateSnapshotBackup = self
+            .storage
+            .load_json_file(&manifest.state_snapshot)
+            .await?;
+        ensure!(
+            state_snapshot.version == version
+                && state_snapshot.root_hash == ledger.txn_info.state_root_hash(),
+            "State snapshot at version {} with root hash {} doesn't match the TransactionInfo: \
+            version {}, state root hash {}.",
+            state_snapshot.version,
+            state_snapshot.root_hash,
+            version,
+            ledger.txn_info.state_root_hash(),
+        );
+        StateSnapshotRestoreController::new(
+            StateSnapshotRestoreOpt {
+                manifest_handle: manifest.state_snapshot,
+                version,
+            },
+            self.global_opt.clone(),
+            Arc::clone(&self.storage),
+            None, /* epoch_history */
+        )
+        .run()
+        .await?;
+
+        if let RestoreRunMode::Restore { restore_handler } = self.global_opt.run_mode.as_ref() {
+            if !ledger.epoch_endings.is_empty() {
+                restore_handler.save_ledger_infos(&ledger.epoch_endings)?;
+            }
+            restore_handler.save_bootstrap_ledger(
+                &ledger.ledger_info,
+                &ledger.txn_info,
+                &ledger.frozen_subtrees,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Verifies `ledger_info` with the epoch endings, walking from the one at the waypoint.
+fn verify_ledger_info(
+    waypoint: &Waypoint,
+    epoch_endings: &[LedgerInfoWithSignatures],
+    ledger_info: &LedgerInfoWithSignatures,
+) -> Result<()> {
+    let version = ledger_info.ledger_info().version();
+    if waypoint.version() == version {
+        return waypoint.verify(ledger_info.ledger_info());
+    }
+    ensure!(
+        waypoint.version() < version,
+        "Waypoint {} is newer than the snapshot at version {}.",
+        waypoint,
+        version,
+    );
+
+    let epoch_change_li =
+        EpochChangeProof::new(epoch_endings.to_vec(), false /* more */).verify(waypoint)?;
+    if epoch_change_li != ledger_info {
+        epoch_change_li
+            .ledger_info()
+            .next_epoch_state()
+            .ok_or_else(|| anyhow!("Epoch ending LedgerInfo carries no next epoch state."))?
+            .verify(ledger_info)?;
+    }
+    Ok(())
+}
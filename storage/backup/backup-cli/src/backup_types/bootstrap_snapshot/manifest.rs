@@ -0,0 +1,39 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::storage::FileHandle;
+use diem_crypto::HashValue;
+use diem_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    transaction::{TransactionInfo, Version},
+};
+use serde::{Deserialize, Serialize};
+
+/// What a DB needs on top of the state to start at the version of a state snapshot, without any
+/// transaction before it.
+#[derive(Deserialize, Serialize)]
+pub struct BootstrapLedger {
+    /// LedgerInfo at the version of the snapshot.
+    pub ledger_info: LedgerInfoWithSignatures,
+    /// TransactionInfo at the version of the snapshot, carrying the state root hash.
+    pub txn_info: TransactionInfo,
+    /// Roots of the frozen subtrees of the transaction accumulator before the version of the
+    /// snapshot, from left to right. Appending `txn_info` to them adds up to the accumulator root
+    /// hash in `ledger_info`.
+    pub frozen_subtrees: Vec<HashValue>,
+    /// Epoch ending LedgerInfos from epoch 0 up to `ledger_info`, which prove `ledger_info` from
+    /// any epoch ending waypoint.
+    pub epoch_endings: Vec<LedgerInfoWithSignatures>,
+}
+
+/// Bootstrap snapshot manifest, from which a fullnode can be bootstrapped at `version` without
+/// replaying any transaction.
+#[derive(Deserialize, Serialize)]
+pub struct BootstrapSnapshot {
+    /// Version at which the snapshot is taken.
+    pub version: Version,
+    /// Manifest of the state snapshot at `version`, a `StateSnapshotBackup`.
+    pub state_snapshot: FileHandle,
+    /// BCS serialized `BootstrapLedger`.
+    pub ledger: FileHandle,
+}
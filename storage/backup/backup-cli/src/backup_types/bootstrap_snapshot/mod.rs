@@ -1,5 +1,9 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-mod basic_twins_test;
-mod twins_node;
+pub mod export;
+pub mod import;
+pub mod manifest;
+
+#[cfg(test)]
+pub mod tests;
@@ -0,0 +1,101 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::bootstrap_snapshot::{
+        export::{BootstrapSnapshotExportController, BootstrapSnapshotExportOpt},
+        import::{BootstrapSnapshotImportController, BootstrapSnapshotImportOpt},
+    },
+    storage::{local_fs::LocalFs, BackupStorage},
+    utils::{
+        test_utils::tmp_db_with_random_content, ConcurrentDownloadsOpt, GlobalBackupOpt,
+        GlobalRestoreOpt, GlobalRestoreOptions, RocksdbOpt, TrustedWaypointOpt,
+    },
+};
+use diem_config::config::RocksdbConfig;
+use diem_crypto::HashValue;
+use diem_temppath::TempPath;
+use diem_types::waypoint::Waypoint;
+use diemdb::DiemDB;
+use std::{convert::TryInto, str::FromStr, sync::Arc};
+use storage_interface::DbReader;
+use tokio::runtime::Runtime;
+
+#[test]
+fn end_to_end() {
+    let (src_db_dir, src_db, _blocks) = tmp_db_with_random_content();
+    let tgt_db_dir = TempPath::new();
+    tgt_db_dir.create_as_dir().unwrap();
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+    let latest_li = src_db.get_latest_ledger_info().unwrap();
+    let waypoint = Waypoint::new_any(latest_li.ledger_info());
+    let rt = Runtime::new().unwrap();
+
+    let manifest_handle = rt
+        .block_on(
+            BootstrapSnapshotExportController::new(
+                BootstrapSnapshotExportOpt {
+                    db_dir: src_db_dir.path().to_path_buf(),
+                    version: None, // latest
+                    rocksdb_opt: RocksdbOpt::default(),
+                },
+                GlobalBackupOpt {
+                    max_chunk_size: 500,
+                },
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap();
+
+    let restore_opt = |db_dir: Option<&TempPath>| -> GlobalRestoreOptions {
+        GlobalRestoreOpt {
+            dry_run: db_dir.is_none(),
+            db_dir: db_dir.map(|d| d.path().to_path_buf()),
+            target_version: None, // max
+            trusted_waypoints: TrustedWaypointOpt::default(),
+            rocksdb_opt: RocksdbOpt::default(),
+            concurernt_downloads: ConcurrentDownloadsOpt::default(),
+        }
+        .try_into()
+        .unwrap()
+    };
+    let import = |waypoint: Waypoint, db_dir: Option<&TempPath>| {
+        rt.block_on(
+            BootstrapSnapshotImportController::new(
+                BootstrapSnapshotImportOpt {
+                    manifest_handle: manifest_handle.clone(),
+                    waypoint,
+                },
+                restore_opt(db_dir),
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+    };
+
+    // A wrong waypoint is rejected.
+    let wrong_waypoint = Waypoint::from_str(&format!(
+        "{}:{}",
+        waypoint.version(),
+        HashValue::zero().to_hex()
+    ))
+    .unwrap();
+    assert!(import(wrong_waypoint, None).is_err());
+    import(waypoint, None).unwrap();
+    import(waypoint, Some(&tgt_db_dir)).unwrap();
+
+    let tgt_db = DiemDB::open(
+        &tgt_db_dir,
+        true, /* read_only */
+        None, /* pruner */
+        RocksdbConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        tgt_db.get_startup_info().unwrap(),
+        src_db.get_startup_info().unwrap()
+    );
+}
@@ -12,9 +12,11 @@ use crate::{
 };
 use anyhow::{anyhow, ensure, Result};
 use diem_logger::prelude::*;
-use diem_types::{ledger_info::LedgerInfoWithSignatures, waypoint::Waypoint};
+use diem_types::{
+    epoch_checkpoint::EpochCheckpoint, ledger_info::LedgerInfoWithSignatures, waypoint::Waypoint,
+};
 use once_cell::sync::Lazy;
-use std::{convert::TryInto, str::FromStr, sync::Arc};
+use std::{collections::HashMap, convert::TryInto, str::FromStr, sync::Arc};
 use structopt::StructOpt;
 use tokio::io::AsyncWriteExt;
 
@@ -79,6 +81,7 @@ impl EpochEndingBackupController {
         let mut chunks = Vec::new();
         let mut waypoints = Vec::new();
         let mut chunk_bytes = Vec::new();
+        let checkpoints = self.get_epoch_checkpoints().await?;
 
         let mut ledger_infos_file = self
             .client
@@ -102,7 +105,11 @@ impl EpochEndingBackupController {
                 chunk_first_epoch = current_epoch;
             }
 
-            waypoints.push(Self::get_waypoint(&record_bytes, current_epoch)?);
+            waypoints.push(Self::get_waypoint(
+                &record_bytes,
+                current_epoch,
+                checkpoints.get(&current_epoch),
+            )?);
             chunk_bytes.extend(&(record_bytes.len() as u32).to_be_bytes());
             chunk_bytes.extend(&record_bytes);
             current_epoch += 1;
@@ -137,7 +144,28 @@ impl EpochEndingBackupController {
         format!("{}-.chunk", first_epoch).try_into().unwrap()
     }
 
-    fn get_waypoint(record: &[u8], epoch: u64) -> Result<Waypoint> {
+    /// Gets the checkpoints the DB saved at the end of the epochs to back up. The epochs ended
+    /// before the checkpoints were introduced have none.
+    async fn get_epoch_checkpoints(&self) -> Result<HashMap<u64, EpochCheckpoint>> {
+        let mut checkpoints_file = self
+            .client
+            .get_epoch_checkpoints(self.start_epoch, self.end_epoch)
+            .await?;
+        let mut checkpoints = HashMap::new();
+        while let Some(record_bytes) = checkpoints_file.read_record_bytes().await? {
+            let checkpoint: EpochCheckpoint = bcs::from_bytes(&record_bytes)?;
+            checkpoints.insert(checkpoint.epoch, checkpoint);
+        }
+        Ok(checkpoints)
+    }
+
+    /// Gets the waypoint of the ledger info in `record`, after checking it against the checkpoint
+    /// of its epoch, if there is one.
+    fn get_waypoint(
+        record: &[u8],
+        epoch: u64,
+        checkpoint: Option<&EpochCheckpoint>,
+    ) -> Result<Waypoint> {
         let li: LedgerInfoWithSignatures = bcs::from_bytes(record)?;
         ensure!(
             li.ledger_info().epoch() == epoch,
@@ -145,6 +173,9 @@ impl EpochEndingBackupController {
             li.ledger_info().epoch(),
             epoch,
         );
+        if let Some(checkpoint) = checkpoint {
+            checkpoint.verify(li.ledger_info())?;
+        }
         Waypoint::new_epoch_boundary(li.ledger_info())
     }
 
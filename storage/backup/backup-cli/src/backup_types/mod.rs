@@ -1,7 +1,9 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod bootstrap_snapshot;
 pub mod epoch_ending;
+pub mod state_delta;
 pub mod state_snapshot;
 pub mod transaction;
 
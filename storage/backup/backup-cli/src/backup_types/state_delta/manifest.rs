@@ -0,0 +1,42 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::storage::FileHandle;
+use diem_crypto::HashValue;
+use diem_types::transaction::Version;
+use serde::{Deserialize, Serialize};
+
+/// A chunk of a state delta manifest, representing the changed accounts in the key range
+/// [`first_key`, `last_key`] (right side inclusive).
+#[derive(Deserialize, Serialize)]
+pub struct StateDeltaChunk {
+    /// key of the first account in this chunk.
+    pub first_key: HashValue,
+    /// key of the last account in this chunk.
+    pub last_key: HashValue,
+    /// Repeated `len(record) + record` where `record` is BCS serialized tuple
+    /// `(key, account_state_blob)`
+    pub blobs: FileHandle,
+}
+
+/// State delta backup manifest, representing the accounts changed after `base_version` up to
+/// `version`, with their states at `version`. Applied on top of the state at `base_version`,
+/// restored from a state snapshot or from another state delta, it makes the complete state at
+/// `version`. The transactions and events in between are in the transaction backups, which are
+/// incremental already.
+#[derive(Deserialize, Serialize)]
+pub struct StateDeltaBackup {
+    /// Version of the state this delta applies to.
+    pub base_version: Version,
+    /// Version of the state this delta makes.
+    pub version: Version,
+    /// Hash of the state tree root at `version`.
+    pub root_hash: HashValue,
+    /// All changed account blobs in chunks, sorted by key.
+    pub chunks: Vec<StateDeltaChunk>,
+    /// BCS serialized `Tuple(TransactionInfoWithProof, LedgerInfoWithSignatures)`, in the same
+    /// format as `StateSnapshotBackup::proof`, proving the `root_hash` above at `version`.
+    /// Since the delta itself isn't covered by range proofs, it's verified by checking that the
+    /// state it makes adds up to this root hash.
+    pub proof: FileHandle,
+}
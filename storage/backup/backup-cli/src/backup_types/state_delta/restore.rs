@@ -0,0 +1,142 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{epoch_ending::restore::EpochHistory, state_delta::manifest::StateDeltaBackup},
+    metrics::{restore::STATE_DELTA_VERSION, verify::VERIFY_STATE_DELTA_VERSION},
+    storage::{BackupStorage, FileHandle},
+    utils::{
+        read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, GlobalRestoreOptions,
+        RestoreRunMode,
+    },
+};
+use anyhow::{anyhow, ensure, Result};
+use diem_crypto::HashValue;
+use diem_logger::prelude::*;
+use diem_types::{
+    account_state_blob::AccountStateBlob, ledger_info::LedgerInfoWithSignatures,
+    proof::TransactionInfoWithProof, transaction::Version,
+};
+use std::sync::Arc;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+pub struct StateDeltaRestoreOpt {
+    #[structopt(long = "state-delta-manifest")]
+    pub manifest_handle: FileHandle,
+}
+
+pub struct StateDeltaRestoreController {
+    storage: Arc<dyn BackupStorage>,
+    run_mode: Arc<RestoreRunMode>,
+    manifest_handle: FileHandle,
+    /// Global "target_version" for the entire restore process, if the version of the delta is
+    /// newer than this, nothing will be done, otherwise, this has no effect.
+    target_version: Version,
+    epoch_history: Option<Arc<EpochHistory>>,
+}
+
+impl StateDeltaRestoreController {
+    pub fn new(
+        opt: StateDeltaRestoreOpt,
+        global_opt: GlobalRestoreOptions,
+        storage: Arc<dyn BackupStorage>,
+        epoch_history: Option<Arc<EpochHistory>>,
+    ) -> Self {
+        Self {
+            storage,
+            run_mode: global_opt.run_mode,
+            manifest_handle: opt.manifest_handle,
+            target_version: global_opt.target_version,
+            epoch_history,
+        }
+    }
+
+    pub async fn run(self) -> Result<()> {
+        let name = self.name();
+        info!("{} started. Manifest: {}", name, self.manifest_handle);
+        self.run_impl()
+            .await
+            .map_err(|e| anyhow!("{} failed: {}", name, e))?;
+        info!("{} succeeded.", name);
+        Ok(())
+    }
+}
+
+impl StateDeltaRestoreController {
+    fn name(&self) -> String {
+        format!("state delta {}", self.run_mode.name())
+    }
+
+    async fn run_impl(self) -> Result<()> {
+        let manifest: StateDeltaBackup = self.storage.load_json_file(&self.manifest_handle).await?;
+        if manifest.version > self.target_version {
+            warn!(
+                "Trying to restore state delta to version {}, which is newer than the target version {}, skipping.",
+                manifest.version,
+                self.target_version,
+            );
+            return Ok(());
+        }
+
+        let (txn_info_with_proof, li): (TransactionInfoWithProof, LedgerInfoWithSignatures) =
+            self.storage.load_bcs_file(&manifest.proof).await?;
+        txn_info_with_proof.verify(li.ledger_info(), manifest.version)?;
+        ensure!(
+            txn_info_with_proof.transaction_info().state_root_hash() == manifest.root_hash,
+            "Root hash mismatch with that in proof. root hash: {}, expected: {}",
+            manifest.root_hash,
+            txn_info_with_proof.transaction_info().state_root_hash(),
+        );
+        if let Some(epoch_history) = self.epoch_history.as_ref() {
+            epoch_history.verify_ledger_info(&li)?;
+        }
+
+        // The delta only adds up to the root hash on top of the base state, so it's applied at
+        // once after reading all the chunks.
+        let mut delta = vec![];
+        for chunk in manifest.chunks {
+            let blobs = self.read_account_state_chunk(chunk.blobs).await?;
+            ensure!(
+                blobs.first().map(|(key, _)| *key) == Some(chunk.first_key)
+                    && blobs.last().map(|(key, _)| *key) == Some(chunk.last_key),
+                "Keys of chunk not matching the manifest: [{}, {}]",
+                chunk.first_key,
+                chunk.last_key,
+            );
+            delta.extend(blobs);
+        }
+
+        match self.run_mode.as_ref() {
+            RestoreRunMode::Restore { restore_handler } => {
+                restore_handler.save_state_delta(
+                    manifest.base_version,
+                    manifest.version,
+                    manifest.root_hash,
+                    delta,
+                )?;
+                STATE_DELTA_VERSION.set(manifest.version as i64);
+            }
+            RestoreRunMode::Verify => {
+                // Without the base state the root hash can't be checked, only the proof of it is.
+                VERIFY_STATE_DELTA_VERSION.set(manifest.version as i64);
+            }
+        }
+        Ok(())
+    }
+
+    async fn read_account_state_chunk(
+        &self,
+        file_handle: FileHandle,
+    ) -> Result<Vec<(HashValue, AccountStateBlob)>> {
+        let mut file = self.storage.open_for_read(&file_handle).await?;
+
+        let mut chunk = vec![];
+
+        while let Some(record_bytes) = file.read_record_bytes().await? {
+            chunk.push(bcs::from_bytes(&record_bytes)?);
+        }
+
+        Ok(chunk)
+    }
+}
@@ -0,0 +1,224 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        epoch_ending::backup::{EpochEndingBackupController, EpochEndingBackupOpt},
+        state_delta::{
+            backup::{StateDeltaBackupController, StateDeltaBackupOpt},
+            restore::{StateDeltaRestoreController, StateDeltaRestoreOpt},
+        },
+        state_snapshot::{
+            backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
+            restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        },
+    },
+    coordinators::compact::{CompactionCoordinator, CompactionCoordinatorOpt},
+    metadata::cache::MetadataCacheOpt,
+    storage::{local_fs::LocalFs, BackupStorage},
+    utils::{
+        backup_service_client::BackupServiceClient, test_utils::start_local_backup_service,
+        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, GlobalRestoreOptions,
+        RocksdbOpt, TrustedWaypointOpt,
+    },
+};
+use diem_config::config::RocksdbConfig;
+use diem_temppath::TempPath;
+use diem_types::{
+    account_config::{diem_root_address, treasury_compliance_account_address},
+    transaction::PRE_GENESIS_VERSION,
+};
+use diemdb::DiemDB;
+use executor_test_helpers::integration_test_impl::test_execution_with_storage_impl;
+use std::{convert::TryInto, sync::Arc};
+use storage_interface::DbReader;
+use structopt::StructOpt;
+use tokio::time::Duration;
+
+fn restore_opt(db_dir: &TempPath) -> GlobalRestoreOptions {
+    GlobalRestoreOpt {
+        dry_run: false,
+        db_dir: Some(db_dir.path().to_path_buf()),
+        target_version: None, // max
+        trusted_waypoints: TrustedWaypointOpt::default(),
+        rocksdb_opt: RocksdbOpt::default(),
+        concurernt_downloads: ConcurrentDownloadsOpt::default(),
+    }
+    .try_into()
+    .unwrap()
+}
+
+#[test]
+fn end_to_end() {
+    let src_db = test_execution_with_storage_impl();
+    let latest_tree_state = src_db.get_latest_tree_state().unwrap();
+    let version = latest_tree_state.num_transactions - 1;
+    let base_version = version / 2;
+    let state_root_hash = latest_tree_state.account_state_root_hash;
+    let next_epoch = src_db
+        .get_latest_ledger_info()
+        .unwrap()
+        .ledger_info()
+        .next_block_epoch();
+
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+    let (rt, port) = start_local_backup_service(Arc::clone(&src_db));
+    let client = Arc::new(BackupServiceClient::new(format!(
+        "http://localhost:{}",
+        port
+    )));
+    let global_backup_opt = GlobalBackupOpt {
+        max_chunk_size: 2048,
+    };
+
+    // Backup
+    rt.block_on(
+        EpochEndingBackupController::new(
+            EpochEndingBackupOpt {
+                start_epoch: 0,
+                end_epoch: next_epoch,
+            },
+            global_backup_opt.clone(),
+            Arc::clone(&client),
+            Arc::clone(&store),
+        )
+        .run(),
+    )
+    .unwrap();
+    let state_snapshot_manifest = rt
+        .block_on(
+            StateSnapshotBackupController::new(
+                StateSnapshotBackupOpt {
+                    version: base_version,
+                },
+                global_backup_opt.clone(),
+                Arc::clone(&client),
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap();
+    let state_delta_manifest = rt
+        .block_on(
+            StateDeltaBackupController::new(
+                StateDeltaBackupOpt {
+                    base_version,
+                    version,
+                },
+                global_backup_opt.clone(),
+                client,
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap();
+
+    // Restore the snapshot and the delta on top of it
+    let tgt_db_dir = TempPath::new();
+    tgt_db_dir.create_as_dir().unwrap();
+    rt.block_on(
+        StateSnapshotRestoreController::new(
+            StateSnapshotRestoreOpt {
+                manifest_handle: state_snapshot_manifest,
+                version: base_version,
+            },
+            restore_opt(&tgt_db_dir),
+            Arc::clone(&store),
+            None, /* epoch_history */
+        )
+        .run(),
+    )
+    .unwrap();
+    rt.block_on(
+        StateDeltaRestoreController::new(
+            StateDeltaRestoreOpt {
+                manifest_handle: state_delta_manifest,
+            },
+            restore_opt(&tgt_db_dir),
+            Arc::clone(&store),
+            None, /* epoch_history */
+        )
+        .run(),
+    )
+    .unwrap();
+
+    let tgt_db = DiemDB::open(
+        &tgt_db_dir,
+        true, /* read_only */
+        None, /* pruner */
+        RocksdbConfig::default(),
+    )
+    .unwrap();
+    for address in &[diem_root_address(), treasury_compliance_account_address()] {
+        assert_eq!(
+            tgt_db
+                .get_account_state_with_proof_by_version(*address, version)
+                .unwrap()
+                .0,
+            src_db
+                .get_account_state_with_proof_by_version(*address, version)
+                .unwrap()
+                .0,
+        );
+    }
+
+    // Merge the snapshot and the delta into a new snapshot, and restore it
+    let metadata_cache_dir = TempPath::new();
+    let scratch_db_dir = TempPath::new();
+    let compacted_manifest = rt
+        .block_on(
+            CompactionCoordinator::new(
+                CompactionCoordinatorOpt {
+                    metadata_cache_opt: MetadataCacheOpt::from_iter(vec![
+                        "exe",
+                        "--metadata-cache-dir",
+                        metadata_cache_dir.path().to_str().unwrap(),
+                    ]),
+                    scratch_db_dir: scratch_db_dir.path().to_path_buf(),
+                    target_version: None,
+                    trusted_waypoints: TrustedWaypointOpt::default(),
+                    rocksdb_opt: RocksdbOpt::default(),
+                    concurernt_downloads: ConcurrentDownloadsOpt::default(),
+                },
+                global_backup_opt,
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap()
+        .unwrap();
+
+    let compacted_db_dir = TempPath::new();
+    compacted_db_dir.create_as_dir().unwrap();
+    rt.block_on(
+        StateSnapshotRestoreController::new(
+            StateSnapshotRestoreOpt {
+                manifest_handle: compacted_manifest,
+                version: PRE_GENESIS_VERSION,
+            },
+            restore_opt(&compacted_db_dir),
+            store,
+            None, /* epoch_history */
+        )
+        .run(),
+    )
+    .unwrap();
+    let compacted_db = DiemDB::open(
+        &compacted_db_dir,
+        true, /* read_only */
+        None, /* pruner */
+        RocksdbConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        compacted_db
+            .get_latest_tree_state()
+            .unwrap()
+            .account_state_root_hash,
+        state_root_hash,
+    );
+
+    rt.shutdown_timeout(Duration::from_secs(1));
+}
@@ -18,6 +18,7 @@ use diem_types::{
     account_state_blob::AccountStateBlob, ledger_info::LedgerInfoWithSignatures,
     proof::TransactionInfoWithProof, transaction::Version,
 };
+use diemdb::backup::backup_handler::BackupHandler;
 use once_cell::sync::Lazy;
 use std::{convert::TryInto, str::FromStr, sync::Arc};
 use structopt::StructOpt;
@@ -132,23 +133,23 @@ impl StateSnapshotBackupController {
         format!("state_ver_{}", self.version)
     }
 
-    fn manifest_name() -> &'static ShellSafeName {
+    pub(crate) fn manifest_name() -> &'static ShellSafeName {
         static NAME: Lazy<ShellSafeName> =
             Lazy::new(|| ShellSafeName::from_str("state.manifest").unwrap());
         &NAME
     }
 
-    fn proof_name() -> &'static ShellSafeName {
+    pub(crate) fn proof_name() -> &'static ShellSafeName {
         static NAME: Lazy<ShellSafeName> =
             Lazy::new(|| ShellSafeName::from_str("state.proof").unwrap());
         &NAME
     }
 
-    fn chunk_name(first_idx: usize) -> ShellSafeName {
+    pub(crate) fn chunk_name(first_idx: usize) -> ShellSafeName {
         format!("{}-.chunk", first_idx).try_into().unwrap()
     }
 
-    fn chunk_proof_name(first_idx: usize, last_idx: usize) -> ShellSafeName {
+    pub(crate) fn chunk_proof_name(first_idx: usize, last_idx: usize) -> ShellSafeName {
         format!("{}-{}.proof", first_idx, last_idx)
             .try_into()
             .unwrap()
@@ -238,3 +239,137 @@ impl StateSnapshotBackupController {
         Ok(manifest_handle)
     }
 }
+
+/// Writes the state snapshot at `version` straight from a local DB, for when there's no backup
+/// service to go through (e.g. the DB is a scratch one), and returns its manifest. `proof_bytes`
+/// is the BCS encoded proof of `root_hash`.
+pub(crate) async fn backup_local_state_snapshot(
+    storage: &Arc<dyn BackupStorage>,
+    backup_handler: &BackupHandler,
+    version: Version,
+    root_hash: HashValue,
+    proof_bytes: &[u8],
+    max_chunk_size: usize,
+) -> Result<FileHandle> {
+    let backup_handle = storage
+        .create_backup_with_random_suffix(&format!("state_ver_{}", version))
+        .await?;
+
+    let mut chunks = vec![];
+    let mut chunk_bytes = vec![];
+    let mut chunk_first_idx = 0;
+    let mut chunk_first_key = HashValue::zero();
+    // (idx, key) of the last account seen
+    let mut prev = None;
+    for (idx, res) in backup_handler.get_account_iter(version)?.enumerate() {
+        let (key, blob) = res?;
+        let record_bytes = bcs::to_bytes(&(key, blob))?;
+        if should_cut_chunk(&chunk_bytes, &record_bytes, max_chunk_size) {
+            let (last_idx, last_key) = prev.expect("Chunk is not empty.");
+            chunks.push(
+                write_local_chunk(
+                    storage,
+                    &backup_handle,
+                    backup_handler,
+                    version,
+                    &chunk_bytes,
+                    (chunk_first_idx, last_idx),
+                    (chunk_first_key, last_key),
+                )
+                .await?,
+            );
+            chunk_bytes = vec![];
+        }
+        if chunk_bytes.is_empty() {
+            chunk_first_idx = idx;
+            chunk_first_key = key;
+        }
+
+        chunk_bytes.extend(&(record_bytes.len() as u32).to_be_bytes());
+        chunk_bytes.extend(&record_bytes);
+        prev = Some((idx, key));
+    }
+    let (last_idx, last_key) = prev.ok_or_else(|| anyhow!("State is empty."))?;
+    chunks.push(
+        write_local_chunk(
+            storage,
+            &backup_handle,
+            backup_handler,
+            version,
+            &chunk_bytes,
+            (chunk_first_idx, last_idx),
+            (chunk_first_key, last_key),
+        )
+        .await?,
+    );
+
+    let (proof_handle, mut proof_file) = storage
+        .create_for_write(&backup_handle, StateSnapshotBackupController::proof_name())
+        .await?;
+    proof_file.write_all(proof_bytes).await?;
+    proof_file.shutdown().await?;
+
+    let manifest = StateSnapshotBackup {
+        version,
+        root_hash,
+        chunks,
+        proof: proof_handle,
+    };
+    let (manifest_handle, mut manifest_file) = storage
+        .create_for_write(
+            &backup_handle,
+            StateSnapshotBackupController::manifest_name(),
+        )
+        .await?;
+    manifest_file
+        .write_all(&serde_json::to_vec(&manifest)?)
+        .await?;
+    manifest_file.shutdown().await?;
+
+    let metadata = Metadata::new_state_snapshot_backup(version, manifest_handle.clone());
+    storage
+        .save_metadata_line(&metadata.name(), &metadata.to_text_line()?)
+        .await?;
+
+    Ok(manifest_handle)
+}
+
+async fn write_local_chunk(
+    storage: &Arc<dyn BackupStorage>,
+    backup_handle: &BackupHandleRef,
+    backup_handler: &BackupHandler,
+    version: Version,
+    chunk_bytes: &[u8],
+    (first_idx, last_idx): (usize, usize),
+    (first_key, last_key): (HashValue, HashValue),
+) -> Result<StateSnapshotChunk> {
+    let (chunk_handle, mut chunk_file) = storage
+        .create_for_write(
+            backup_handle,
+            &StateSnapshotBackupController::chunk_name(first_idx),
+        )
+        .await?;
+    chunk_file.write_all(chunk_bytes).await?;
+    chunk_file.shutdown().await?;
+    let (proof_handle, mut proof_file) = storage
+        .create_for_write(
+            backup_handle,
+            &StateSnapshotBackupController::chunk_proof_name(first_idx, last_idx),
+        )
+        .await?;
+    proof_file
+        .write_all(&bcs::to_bytes(
+            &backup_handler.get_account_state_range_proof(last_key, version)?,
+        )?)
+        .await?;
+    proof_file.shutdown().await?;
+
+    Ok(StateSnapshotChunk {
+        first_idx,
+        last_idx,
+        first_key,
+        last_key,
+        blobs: chunk_handle,
+        proof: proof_handle,
+    })
+}
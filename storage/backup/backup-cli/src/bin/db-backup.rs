@@ -8,11 +8,18 @@ use structopt::StructOpt;
 
 use backup_cli::{
     backup_types::{
+        bootstrap_snapshot::export::{
+            BootstrapSnapshotExportController, BootstrapSnapshotExportOpt,
+        },
         epoch_ending::backup::{EpochEndingBackupController, EpochEndingBackupOpt},
+        state_delta::backup::{StateDeltaBackupController, StateDeltaBackupOpt},
         state_snapshot::backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
         transaction::backup::{TransactionBackupController, TransactionBackupOpt},
     },
-    coordinators::backup::{BackupCoordinator, BackupCoordinatorOpt},
+    coordinators::{
+        backup::{BackupCoordinator, BackupCoordinatorOpt},
+        compact::{CompactionCoordinator, CompactionCoordinatorOpt},
+    },
     metadata::{cache, cache::MetadataCacheOpt},
     storage::StorageOpt,
     utils::{
@@ -38,6 +45,11 @@ enum OneShotCommand {
     Query(OneShotQueryType),
     #[structopt(about = "Do a one shot backup.")]
     Backup(OneShotBackupOpt),
+    #[structopt(
+        about = "Export the state snapshot at a version of a local DB, along with what's needed \
+        to bootstrap a fullnode from it without replaying transactions."
+    )]
+    ExportBootstrapSnapshot(OneShotExportBootstrapSnapshotOpt),
 }
 
 #[derive(StructOpt)]
@@ -81,6 +93,18 @@ struct OneShotBackupOpt {
     backup_type: BackupType,
 }
 
+#[derive(StructOpt)]
+struct OneShotExportBootstrapSnapshotOpt {
+    #[structopt(flatten)]
+    global: GlobalBackupOpt,
+
+    #[structopt(flatten)]
+    opt: BootstrapSnapshotExportOpt,
+
+    #[structopt(subcommand)]
+    storage: StorageOpt,
+}
+
 #[derive(StructOpt)]
 enum BackupType {
     EpochEnding {
@@ -95,6 +119,12 @@ enum BackupType {
         #[structopt(subcommand)]
         storage: StorageOpt,
     },
+    StateDelta {
+        #[structopt(flatten)]
+        opt: StateDeltaBackupOpt,
+        #[structopt(subcommand)]
+        storage: StorageOpt,
+    },
     Transaction {
         #[structopt(flatten)]
         opt: TransactionBackupOpt,
@@ -107,6 +137,11 @@ enum BackupType {
 enum CoordinatorCommand {
     #[structopt(about = "Run the coordinator.")]
     Run(CoordinatorRunOpt),
+    #[structopt(
+        about = "Merge the latest state snapshot and the state deltas on top of it into a new \
+        state snapshot."
+    )]
+    Compact(CoordinatorCompactOpt),
 }
 
 #[derive(StructOpt)]
@@ -124,6 +159,18 @@ struct CoordinatorRunOpt {
     storage: StorageOpt,
 }
 
+#[derive(StructOpt)]
+struct CoordinatorCompactOpt {
+    #[structopt(flatten)]
+    global: GlobalBackupOpt,
+
+    #[structopt(flatten)]
+    coordinator: CompactionCoordinatorOpt,
+
+    #[structopt(subcommand)]
+    storage: StorageOpt,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     main_impl().await.map_err(|e| {
@@ -183,6 +230,16 @@ async fn main_impl() -> Result<()> {
                         .run()
                         .await?;
                     }
+                    BackupType::StateDelta { opt, storage } => {
+                        StateDeltaBackupController::new(
+                            opt,
+                            global_opt,
+                            client,
+                            storage.init_storage().await?,
+                        )
+                        .run()
+                        .await?;
+                    }
                     BackupType::Transaction { opt, storage } => {
                         TransactionBackupController::new(
                             opt,
@@ -195,6 +252,15 @@ async fn main_impl() -> Result<()> {
                     }
                 }
             }
+            OneShotCommand::ExportBootstrapSnapshot(opt) => {
+                BootstrapSnapshotExportController::new(
+                    opt.opt,
+                    opt.global,
+                    opt.storage.init_storage().await?,
+                )
+                .run()
+                .await?;
+            }
         },
         Command::Coordinator(coordinator_cmd) => match coordinator_cmd {
             CoordinatorCommand::Run(opt) => {
@@ -207,6 +273,15 @@ async fn main_impl() -> Result<()> {
                 .run()
                 .await?;
             }
+            CoordinatorCommand::Compact(opt) => {
+                CompactionCoordinator::new(
+                    opt.coordinator,
+                    opt.global,
+                    opt.storage.init_storage().await?,
+                )
+                .run()
+                .await?;
+            }
         },
     }
     Ok(())
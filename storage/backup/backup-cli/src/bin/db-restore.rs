@@ -1,14 +1,21 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use backup_cli::{
     backup_types::{
+        bootstrap_snapshot::import::{
+            BootstrapSnapshotImportController, BootstrapSnapshotImportOpt,
+        },
         epoch_ending::restore::{EpochEndingRestoreController, EpochEndingRestoreOpt},
+        state_delta::restore::{StateDeltaRestoreController, StateDeltaRestoreOpt},
         state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
         transaction::restore::{TransactionRestoreController, TransactionRestoreOpt},
     },
-    coordinators::restore::{RestoreCoordinator, RestoreCoordinatorOpt},
+    coordinators::{
+        restore::{RestoreCoordinator, RestoreCoordinatorOpt},
+        restore_verify::{RestoreVerifyCoordinator, RestoreVerifyCoordinatorOpt},
+    },
     storage::StorageOpt,
     utils::{GlobalRestoreOpt, GlobalRestoreOptions},
 };
@@ -40,18 +47,39 @@ enum RestoreType {
         #[structopt(subcommand)]
         storage: StorageOpt,
     },
+    StateDelta {
+        #[structopt(flatten)]
+        opt: StateDeltaRestoreOpt,
+        #[structopt(subcommand)]
+        storage: StorageOpt,
+    },
     Transaction {
         #[structopt(flatten)]
         opt: TransactionRestoreOpt,
         #[structopt(subcommand)]
         storage: StorageOpt,
     },
+    /// Bootstraps an empty DB from an exported bootstrap snapshot without replaying transactions.
+    BootstrapSnapshot {
+        #[structopt(flatten)]
+        opt: BootstrapSnapshotImportOpt,
+        #[structopt(subcommand)]
+        storage: StorageOpt,
+    },
     Auto {
         #[structopt(flatten)]
         opt: RestoreCoordinatorOpt,
         #[structopt(subcommand)]
         storage: StorageOpt,
     },
+    /// Verifies the backups against the trusted waypoints by replaying them into a throwaway DB,
+    /// or by verifying their proofs only with --dry-run. The target DB is never touched.
+    Verify {
+        #[structopt(flatten)]
+        opt: RestoreVerifyCoordinatorOpt,
+        #[structopt(subcommand)]
+        storage: StorageOpt,
+    },
 }
 
 #[tokio::main]
@@ -67,6 +95,24 @@ async fn main_impl() -> Result<()> {
     let _mp = MetricsPusher::start();
 
     let opt = Opt::from_args();
+    // Verify takes no target DB, so the global options are checked by it instead.
+    if let RestoreType::Verify {
+        opt: verify_opt,
+        storage,
+    } = opt.restore_type
+    {
+        return RestoreVerifyCoordinator::new(
+            verify_opt,
+            opt.global,
+            storage.init_storage().await?,
+        )?
+        .run()
+        .await;
+    }
+    ensure!(
+        opt.global.dry_run || opt.global.db_dir.is_some(),
+        "--target-db-dir is required unless --dry-run.",
+    );
     let global_opt: GlobalRestoreOptions = opt.global.clone().try_into()?;
 
     match opt.restore_type {
@@ -85,6 +131,16 @@ async fn main_impl() -> Result<()> {
             .run()
             .await?;
         }
+        RestoreType::StateDelta { opt, storage } => {
+            StateDeltaRestoreController::new(
+                opt,
+                global_opt,
+                storage.init_storage().await?,
+                None, /* epoch_history */
+            )
+            .run()
+            .await?;
+        }
         RestoreType::Transaction { opt, storage } => {
             TransactionRestoreController::new(
                 opt,
@@ -95,11 +151,17 @@ async fn main_impl() -> Result<()> {
             .run()
             .await?;
         }
+        RestoreType::BootstrapSnapshot { opt, storage } => {
+            BootstrapSnapshotImportController::new(opt, global_opt, storage.init_storage().await?)
+                .run()
+                .await?;
+        }
         RestoreType::Auto { opt, storage } => {
             RestoreCoordinator::new(opt, global_opt, storage.init_storage().await?)
                 .run()
                 .await?;
         }
+        RestoreType::Verify { .. } => unreachable!("Handled above."),
     }
 
     Ok(())
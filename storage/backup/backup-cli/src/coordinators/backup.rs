@@ -4,13 +4,15 @@
 use crate::{
     backup_types::{
         epoch_ending::backup::{EpochEndingBackupController, EpochEndingBackupOpt},
+        state_delta::backup::{StateDeltaBackupController, StateDeltaBackupOpt},
         state_snapshot::backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
         transaction::backup::{TransactionBackupController, TransactionBackupOpt},
     },
     metadata,
     metadata::cache::MetadataCacheOpt,
     metrics::backup::{
-        EPOCH_ENDING_EPOCH, HEARTBEAT_TS, STATE_SNAPSHOT_VERSION, TRANSACTION_VERSION,
+        EPOCH_ENDING_EPOCH, HEARTBEAT_TS, STATE_DELTA_VERSION, STATE_SNAPSHOT_VERSION,
+        TRANSACTION_VERSION,
     },
     storage::BackupStorage,
     utils::{
@@ -41,6 +43,11 @@ pub struct BackupCoordinatorOpt {
     // here to make it less than two, and easier for eyes.
     #[structopt(long, default_value = "10000000")]
     pub state_snapshot_interval: usize,
+    // A state delta only holds the accounts changed since the previous state snapshot or delta,
+    // taking them in between the state snapshots cuts the transactions to replay at restore time
+    // without uploading the whole state more often. Not taken if not set.
+    #[structopt(long)]
+    pub state_delta_interval: Option<usize>,
     // Assuming the network runs at 100 tps, it's 100 * 3600 = 360k transactions per hour, we don't
     // want the backups to lag behind too much. Defaulting to 100k here in case the network is way
     // slower than expected.
@@ -63,6 +70,15 @@ impl BackupCoordinatorOpt {
              that's not yet in a transaction backup, resulting in replaying all transactions \
              at restore time."
         );
+        if let Some(state_delta_interval) = self.state_delta_interval {
+            ensure!(
+                state_delta_interval > 0
+                    && self.state_snapshot_interval % state_delta_interval == 0
+                    && state_delta_interval % self.transaction_batch_size == 0,
+                "State snapshot interval should be N x state_delta_interval, and state delta \
+                 interval should be M x transaction_batch_size, N, M >= 1."
+            );
+        }
         Ok(())
     }
 }
@@ -73,6 +89,7 @@ pub struct BackupCoordinator {
     global_opt: GlobalBackupOpt,
     metadata_cache_opt: MetadataCacheOpt,
     state_snapshot_interval: usize,
+    state_delta_interval: Option<usize>,
     transaction_batch_size: usize,
     concurrent_downloads: usize,
 }
@@ -91,6 +108,7 @@ impl BackupCoordinator {
             global_opt,
             metadata_cache_opt: opt.metadata_cache_opt,
             state_snapshot_interval: opt.state_snapshot_interval,
+            state_delta_interval: opt.state_delta_interval,
             transaction_batch_size: opt.transaction_batch_size,
             concurrent_downloads: opt.concurernt_downloads.get(),
         }
@@ -142,13 +160,25 @@ impl BackupCoordinator {
             )
             .boxed_local();
 
-        info!("Backup coordinator started.");
-        let mut all_work = stream::select_all(vec![
+        let mut work_streams = vec![
             watch_db_state,
             backup_epoch_endings,
             backup_state_snapshots,
             backup_transactions,
-        ]);
+        ];
+        if self.state_delta_interval.is_some() {
+            work_streams.push(
+                self.backup_work_stream(
+                    backup_state.latest_state_delta_version,
+                    &rx2,
+                    Self::backup_state_delta,
+                )
+                .boxed_local(),
+            );
+        }
+
+        info!("Backup coordinator started.");
+        let mut all_work = stream::select_all(work_streams);
 
         loop {
             all_work
@@ -251,6 +281,50 @@ impl BackupCoordinator {
         Ok(Some(next_snapshot_version))
     }
 
+    async fn backup_state_delta(
+        &self,
+        last_delta_version_in_backup: Option<Version>,
+        db_state: DbState,
+    ) -> Result<Option<Version>> {
+        let state_delta_interval = self
+            .state_delta_interval
+            .ok_or_else(|| anyhow!("Must be a bug: state deltas are not enabled."))?;
+        if let Some(version) = last_delta_version_in_backup {
+            STATE_DELTA_VERSION.set(version as i64);
+        }
+        let next_delta_version =
+            get_next_snapshot(last_delta_version_in_backup, db_state, state_delta_interval);
+
+        if db_state.committed_version < next_delta_version {
+            // wait for the next db_state update
+            return Ok(last_delta_version_in_backup);
+        }
+
+        let base_version = match get_state_delta_base(
+            last_delta_version_in_backup,
+            next_delta_version,
+            self.state_snapshot_interval,
+        ) {
+            Some(base_version) => base_version,
+            // the state snapshot backup takes this version.
+            None => return Ok(Some(next_delta_version)),
+        };
+
+        StateDeltaBackupController::new(
+            StateDeltaBackupOpt {
+                base_version,
+                version: next_delta_version,
+            },
+            self.global_opt.clone(),
+            Arc::clone(&self.client),
+            Arc::clone(&self.storage),
+        )
+        .run()
+        .await?;
+
+        Ok(Some(next_delta_version))
+    }
+
     async fn backup_transactions(
         &self,
         mut last_transaction_version_in_backup: Option<Version>,
@@ -357,9 +431,27 @@ fn get_next_snapshot(last_in_backup: Option<u64>, db_state: DbState, interval: u
     std::cmp::max(next_for_storage, last_for_db)
 }
 
+fn get_state_delta_base(
+    last_in_backup: Option<u64>,
+    next_delta: u64,
+    snapshot_interval: usize,
+) -> Option<u64> {
+    // A state delta applies on top of the state snapshot at the previous snapshot interval, or of
+    // the last state delta taken after it, so restoring a state takes a snapshot and a chain of
+    // deltas. No delta is taken at the version of a snapshot.
+    // For example, with snapshot interval 100 and delta interval 20, the delta at 140 applies to the
+    // delta at 120 and the delta at 220 applies to the snapshot at 200.
+    let last_snapshot = next_delta / snapshot_interval as u64 * snapshot_interval as u64;
+    if next_delta == last_snapshot {
+        return None;
+    }
+
+    Some(last_in_backup.map_or(last_snapshot, |last| std::cmp::max(last, last_snapshot)))
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::coordinators::backup::{get_batch_range, get_next_snapshot};
+    use crate::coordinators::backup::{get_batch_range, get_next_snapshot, get_state_delta_base};
     use diemdb::backup::backup_handler::DbState;
 
     #[test]
@@ -386,4 +478,14 @@ mod tests {
         assert_eq!(get_next_snapshot(Some(0), _state(250), 100), 200);
         assert_eq!(get_next_snapshot(Some(200), _state(250), 100), 300);
     }
+
+    #[test]
+    fn test_get_state_delta_base() {
+        assert_eq!(get_state_delta_base(None, 0, 100), None);
+        assert_eq!(get_state_delta_base(None, 20, 100), Some(0));
+        assert_eq!(get_state_delta_base(Some(20), 40, 100), Some(20));
+        assert_eq!(get_state_delta_base(Some(80), 100, 100), None);
+        assert_eq!(get_state_delta_base(Some(100), 120, 100), Some(100));
+        assert_eq!(get_state_delta_base(Some(40), 260, 100), Some(200));
+    }
 }
@@ -0,0 +1,213 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        epoch_ending::restore::EpochHistoryRestoreController,
+        state_delta::{
+            manifest::StateDeltaBackup,
+            restore::{StateDeltaRestoreController, StateDeltaRestoreOpt},
+        },
+        state_snapshot::{
+            backup::backup_local_state_snapshot,
+            restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        },
+    },
+    metadata,
+    metadata::cache::MetadataCacheOpt,
+    storage::{BackupStorage, FileHandle},
+    utils::{
+        path_exists, storage_ext::BackupStorageExt, ConcurrentDownloadsOpt, GlobalBackupOpt,
+        GlobalRestoreOptions, RestoreRunMode, RocksdbOpt, TrustedWaypointOpt,
+    },
+};
+use anyhow::{anyhow, ensure, Result};
+use diem_logger::prelude::*;
+use diem_types::transaction::Version;
+use diemdb::{DiemDB, GetRestoreHandler};
+use std::{path::PathBuf, sync::Arc};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+pub struct CompactionCoordinatorOpt {
+    #[structopt(flatten)]
+    pub metadata_cache_opt: MetadataCacheOpt,
+
+    #[structopt(
+        long = "scratch-db-dir",
+        parse(from_os_str),
+        help = "Dir to rebuild the state in, which must not exist. It can be removed afterwards."
+    )]
+    pub scratch_db_dir: PathBuf,
+
+    #[structopt(
+        long,
+        help = "State deltas newer than this version are not merged, defaulting to the largest \
+        version possible, meaning merge all the state deltas after the latest state snapshot."
+    )]
+    pub target_version: Option<Version>,
+
+    #[structopt(flatten)]
+    pub trusted_waypoints: TrustedWaypointOpt,
+
+    #[structopt(flatten)]
+    pub rocksdb_opt: RocksdbOpt,
+
+    #[structopt(flatten)]
+    pub concurernt_downloads: ConcurrentDownloadsOpt,
+}
+
+/// Merges the latest state snapshot and the chain of state deltas on top of it into a new state
+/// snapshot, at the version of the last delta, without going through the node. The state is
+/// rebuilt and verified in a scratch DB, from which the new snapshot is taken. The merged backups
+/// are left in the storage.
+pub struct CompactionCoordinator {
+    storage: Arc<dyn BackupStorage>,
+    metadata_cache_opt: MetadataCacheOpt,
+    scratch_db_dir: PathBuf,
+    target_version: Version,
+    trusted_waypoints_opt: TrustedWaypointOpt,
+    rocksdb_opt: RocksdbOpt,
+    concurrent_downloads: usize,
+    max_chunk_size: usize,
+}
+
+impl CompactionCoordinator {
+    pub fn new(
+        opt: CompactionCoordinatorOpt,
+        global_opt: GlobalBackupOpt,
+        storage: Arc<dyn BackupStorage>,
+    ) -> Self {
+        Self {
+            storage,
+            metadata_cache_opt: opt.metadata_cache_opt,
+            scratch_db_dir: opt.scratch_db_dir,
+            target_version: opt.target_version.unwrap_or(Version::max_value()),
+            trusted_waypoints_opt: opt.trusted_waypoints,
+            rocksdb_opt: opt.rocksdb_opt,
+            concurrent_downloads: opt.concurernt_downloads.get(),
+            max_chunk_size: global_opt.max_chunk_size,
+        }
+    }
+
+    /// Returns the manifest of the new state snapshot, or None if there's no state delta to merge.
+    pub async fn run(self) -> Result<Option<FileHandle>> {
+        info!("Compaction coordinator started.");
+        let ret = self
+            .run_impl()
+            .await
+            .map_err(|e| anyhow!("Compaction coordinator failed: {}", e))?;
+        info!("Compaction coordinator exiting with success.");
+        Ok(ret)
+    }
+
+    async fn run_impl(self) -> Result<Option<FileHandle>> {
+        let metadata_view = metadata::cache::sync_and_load(
+            &self.metadata_cache_opt,
+            Arc::clone(&self.storage),
+            self.concurrent_downloads,
+        )
+        .await?;
+        let state_snapshot = metadata_view
+            .select_state_snapshot(self.target_version)?
+            .ok_or_else(|| anyhow!("No state snapshot found."))?;
+        let state_deltas =
+            metadata_view.select_state_deltas(state_snapshot.version, self.target_version)?;
+        let last_delta = match state_deltas.last() {
+            Some(backup) => backup.clone(),
+            None => {
+                info!(
+                    "No state delta on top of the state snapshot at version {}, nothing to compact.",
+                    state_snapshot.version,
+                );
+                return Ok(None);
+            }
+        };
+        let version = last_delta.version;
+        let epoch_endings = metadata_view.select_epoch_ending_backups(version)?;
+        info!(
+            "Planned to merge the state snapshot at version {} and {} state deltas up to version {}.",
+            state_snapshot.version,
+            state_deltas.len(),
+            version,
+        );
+
+        ensure!(
+            !path_exists(&self.scratch_db_dir).await,
+            "Scratch DB dir {:?} exists already.",
+            self.scratch_db_dir,
+        );
+        let db = Arc::new(DiemDB::open(
+            &self.scratch_db_dir,
+            false, /* read_only */
+            None,  /* pruner */
+            self.rocksdb_opt.into(),
+        )?);
+        let global_opt = GlobalRestoreOptions {
+            target_version: version,
+            trusted_waypoints: Arc::new(self.trusted_waypoints_opt.verify()?),
+            run_mode: Arc::new(RestoreRunMode::Restore {
+                restore_handler: db.get_restore_handler(),
+            }),
+            concurrent_downloads: self.concurrent_downloads,
+        };
+
+        let epoch_history = Arc::new(
+            EpochHistoryRestoreController::new(
+                epoch_endings
+                    .into_iter()
+                    .map(|backup| backup.manifest)
+                    .collect(),
+                global_opt.clone(),
+                Arc::clone(&self.storage),
+            )
+            .run()
+            .await?,
+        );
+
+        StateSnapshotRestoreController::new(
+            StateSnapshotRestoreOpt {
+                manifest_handle: state_snapshot.manifest,
+                version: state_snapshot.version,
+            },
+            global_opt.clone(),
+            Arc::clone(&self.storage),
+            Some(Arc::clone(&epoch_history)),
+        )
+        .run()
+        .await?;
+        for backup in state_deltas {
+            StateDeltaRestoreController::new(
+                StateDeltaRestoreOpt {
+                    manifest_handle: backup.manifest,
+                },
+                global_opt.clone(),
+                Arc::clone(&self.storage),
+                Some(Arc::clone(&epoch_history)),
+            )
+            .run()
+            .await?;
+        }
+
+        // The state in the scratch DB adds up to the root hash proven in the last delta, which
+        // proves the new snapshot as well.
+        let last_delta: StateDeltaBackup =
+            self.storage.load_json_file(&last_delta.manifest).await?;
+        let proof_bytes = self.storage.read_all(&last_delta.proof).await?;
+        let manifest_handle = backup_local_state_snapshot(
+            &self.storage,
+            &db.get_backup_handler(),
+            version,
+            last_delta.root_hash,
+            &proof_bytes,
+            self.max_chunk_size,
+        )
+        .await?;
+        info!(
+            "New state snapshot at version {}: {}",
+            version, manifest_handle
+        );
+
+        Ok(Some(manifest_handle))
+    }
+}
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod backup;
+pub mod compact;
 pub mod replay_verify;
 pub mod restore;
+pub mod restore_verify;
 pub mod verify;
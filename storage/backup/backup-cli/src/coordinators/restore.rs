@@ -4,6 +4,7 @@
 use crate::{
     backup_types::{
         epoch_ending::restore::EpochHistoryRestoreController,
+        state_delta::restore::{StateDeltaRestoreController, StateDeltaRestoreOpt},
         state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
         transaction::restore::TransactionRestoreBatchController,
     },
@@ -89,9 +90,14 @@ impl RestoreCoordinator {
         } else {
             metadata_view.select_state_snapshot(actual_target_version)?
         };
-        let replay_transactions_from_version = match &state_snapshot {
-            Some(b) => b.version + 1,
-            None => 0,
+        let state_deltas = match &state_snapshot {
+            Some(b) => metadata_view.select_state_deltas(b.version, actual_target_version)?,
+            None => Vec::new(),
+        };
+        let replay_transactions_from_version = match (state_deltas.last(), &state_snapshot) {
+            (Some(d), _) => d.version + 1,
+            (None, Some(b)) => b.version + 1,
+            (None, None) => 0,
         };
         COORDINATOR_TARGET_VERSION.set(actual_target_version as i64);
         info!("Planned to restore to version {}.", actual_target_version);
@@ -138,6 +144,19 @@ impl RestoreCoordinator {
             .await?;
         }
 
+        for backup in state_deltas {
+            StateDeltaRestoreController::new(
+                StateDeltaRestoreOpt {
+                    manifest_handle: backup.manifest,
+                },
+                self.global_opt.clone(),
+                Arc::clone(&self.storage),
+                Some(Arc::clone(&epoch_history)),
+            )
+            .run()
+            .await?;
+        }
+
         let txn_manifests = transactions
             .into_iter()
             .skip_while(|b| b.last_version < txn_resume_point)
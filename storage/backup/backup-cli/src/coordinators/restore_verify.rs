@@ -0,0 +1,331 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::epoch_ending::restore::{EpochHistory, EpochHistoryRestoreController},
+    coordinators::restore::{RestoreCoordinator, RestoreCoordinatorOpt},
+    metadata,
+    storage::BackupStorage,
+    utils::{path_exists, GlobalRestoreOpt, GlobalRestoreOptions, RestoreRunMode, RocksdbOpt},
+};
+use anyhow::{anyhow, bail, ensure, Result};
+use diem_logger::prelude::*;
+use diem_temppath::TempPath;
+use diem_types::{
+    account_address::HashAccountAddress, account_config::diem_root_address, transaction::Version,
+    waypoint::Waypoint,
+};
+use diemdb::{DiemDB, GetRestoreHandler};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use storage_interface::DbReader;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+pub struct RestoreVerifyCoordinatorOpt {
+    #[structopt(flatten)]
+    pub restore_coordinator_opt: RestoreCoordinatorOpt,
+
+    #[structopt(
+        long = "scratch-db-dir",
+        parse(from_os_str),
+        help = "Dir of the throwaway DB the backup is replayed into, which must not exist. \
+        Defaults to a temporary dir removed on exit."
+    )]
+    pub scratch_db_dir: Option<PathBuf>,
+}
+
+/// Verifies the backups against the trusted waypoints without touching any live DB: the backups
+/// are replayed into a throwaway DB, or only their proofs are verified on a dry run. Either way,
+/// every trusted waypoint covered by the backups must be one of the epoch endings in them, and
+/// at least one must be covered.
+pub struct RestoreVerifyCoordinator {
+    storage: Arc<dyn BackupStorage>,
+    restore_coordinator_opt: RestoreCoordinatorOpt,
+    scratch_db_dir: Option<PathBuf>,
+    dry_run: bool,
+    target_version: Version,
+    trusted_waypoints: HashMap<Version, Waypoint>,
+    rocksdb_opt: RocksdbOpt,
+    concurrent_downloads: usize,
+}
+
+impl RestoreVerifyCoordinator {
+    pub fn new(
+        opt: RestoreVerifyCoordinatorOpt,
+        global_opt: GlobalRestoreOpt,
+        storage: Arc<dyn BackupStorage>,
+    ) -> Result<Self> {
+        ensure!(
+            global_opt.db_dir.is_none(),
+            "Verification never writes to --target-db-dir, use --scratch-db-dir instead.",
+        );
+        ensure!(
+            !(global_opt.dry_run && opt.scratch_db_dir.is_some()),
+            "A dry run doesn't replay the backups, --scratch-db-dir can't be used with it.",
+        );
+        Ok(Self {
+            storage,
+            restore_coordinator_opt: opt.restore_coordinator_opt,
+            scratch_db_dir: opt.scratch_db_dir,
+            dry_run: global_opt.dry_run,
+            target_version: global_opt.target_version.unwrap_or(Version::max_value()),
+            trusted_waypoints: global_opt.trusted_waypoints.verify()?,
+            rocksdb_opt: global_opt.rocksdb_opt,
+            concurrent_downloads: global_opt.concurernt_downloads.get(),
+        })
+    }
+
+    pub async fn run(self) -> Result<()> {
+        info!("Restore verify coordinator started.");
+        let ret = self.run_impl().await;
+
+        if let Err(e) = &ret {
+            error!(
+                error = ?e,
+                "Restore verify coordinator failed."
+            );
+        } else {
+            info!("Restore verify coordinator exiting with success.");
+        }
+
+        ret
+    }
+
+    async fn run_impl(self) -> Result<()> {
+        ensure!(
+            !self.trusted_waypoints.is_empty(),
+            "At least one trusted waypoint is needed to verify the backups against.",
+        );
+        let trusted_waypoints = Arc::new(self.trusted_waypoints);
+
+        let metadata_view = metadata::cache::sync_and_load(
+            &self.restore_coordinator_opt.metadata_cache_opt,
+            Arc::clone(&self.storage),
+            self.concurrent_downloads,
+        )
+        .await?;
+        let epoch_endings = metadata_view.select_epoch_ending_backups(self.target_version)?;
+        let epoch_history = EpochHistoryRestoreController::new(
+            epoch_endings
+                .into_iter()
+                .map(|backup| backup.manifest)
+                .collect(),
+            GlobalRestoreOptions {
+                target_version: self.target_version,
+                trusted_waypoints: Arc::clone(&trusted_waypoints),
+                run_mode: Arc::new(RestoreRunMode::Verify),
+                concurrent_downloads: self.concurrent_downloads,
+            },
+            Arc::clone(&self.storage),
+        )
+        .run()
+        .await?;
+        let waypoints = covered_waypoints(&trusted_waypoints, &epoch_history)?;
+
+        if self.dry_run {
+            info!("This is a dry run, verifying the proofs only.");
+            return RestoreCoordinator::new(
+                self.restore_coordinator_opt,
+                GlobalRestoreOptions {
+                    target_version: self.target_version,
+                    trusted_waypoints,
+                    run_mode: Arc::new(RestoreRunMode::Verify),
+                    concurrent_downloads: self.concurrent_downloads,
+                },
+                self.storage,
+            )
+            .run()
+            .await;
+        }
+
+        // Removes the temporary DB when dropped.
+        let _tmp_dir;
+        let scratch_db_dir = match self.scratch_db_dir {
+            Some(dir) => {
+                ensure!(
+                    !path_exists(&dir).await,
+                    "Scratch DB dir {:?} exists already.",
+                    dir,
+                );
+                dir
+            }
+            None => {
+                let tmp_dir = TempPath::new();
+                let dir = tmp_dir.path().to_path_buf();
+                _tmp_dir = tmp_dir;
+                dir
+            }
+        };
+        info!(
+            "Replaying the backups into scratch DB at {:?}.",
+            scratch_db_dir
+        );
+        let db = Arc::new(DiemDB::open(
+            &scratch_db_dir,
+            false, /* read_only */
+            None,  /* pruner */
+            self.rocksdb_opt.into(),
+        )?);
+        RestoreCoordinator::new(
+            self.restore_coordinator_opt,
+            GlobalRestoreOptions {
+                target_version: self.target_version,
+                trusted_waypoints,
+                run_mode: Arc::new(RestoreRunMode::Restore {
+                    restore_handler: db.get_restore_handler(),
+                }),
+                concurrent_downloads: self.concurrent_downloads,
+            },
+            self.storage,
+        )
+        .run()
+        .await?;
+
+        verify_replayed_db(&db, &waypoints)
+    }
+}
+
+/// Returns the trusted waypoints the epoch history covers, which have been checked against the
+/// epoch endings at their versions while restoring the history.
+fn covered_waypoints(
+    trusted_waypoints: &HashMap<Version, Waypoint>,
+    epoch_history: &EpochHistory,
+) -> Result<Vec<Waypoint>> {
+    let last_epoch_ending_version = epoch_history.epoch_endings.last().map(|li| li.version());
+    let mut waypoints: Vec<Waypoint> = trusted_waypoints.values().cloned().collect();
+    waypoints.sort_by_key(|w| w.version());
+
+    let mut covered = Vec::new();
+    for waypoint in waypoints {
+        match last_epoch_ending_version {
+            Some(last_version) if waypoint.version() <= last_version => {
+                if !epoch_history
+                    .epoch_endings
+                    .iter()
+                    .any(|li| li.version() == waypoint.version())
+                {
+                    bail!(
+                        "Trusted waypoint {} isn't at an epoch ending in the backups.",
+                        waypoint,
+                    );
+                }
+                covered.push(waypoint);
+            }
+            _ => warn!(
+                "Trusted waypoint {} is beyond the epoch endings in the backups, not checked.",
+                waypoint,
+            ),
+        }
+    }
+    ensure!(
+        !covered.is_empty(),
+        "None of the trusted waypoints is covered by the epoch endings in the backups.",
+    );
+    Ok(covered)
+}
+
+/// Checks the replayed transaction accumulator against the ledger infos of the waypoints, and the
+/// replayed state against the root hash of the last transaction.
+fn verify_replayed_db(db: &DiemDB, waypoints: &[Waypoint]) -> Result<()> {
+    let (latest_version, latest_txn_info) = db
+        .get_latest_transaction_info_option()?
+        .ok_or_else(|| anyhow!("Nothing was replayed."))?;
+
+    let mut num_verified = 0;
+    for waypoint in waypoints {
+        if waypoint.version() > latest_version {
+            warn!(
+                "Trusted waypoint {} is beyond the replayed version {}, not checked.",
+                waypoint, latest_version,
+            );
+            continue;
+        }
+        let li = db.get_epoch_ending_ledger_info(waypoint.version())?;
+        waypoint.verify(li.ledger_info())?;
+        db.get_transactions(
+            waypoint.version(),
+            1,
+            waypoint.version(),
+            false, /* fetch_events */
+        )?
+        .verify(li.ledger_info(), Some(waypoint.version()))?;
+        num_verified += 1;
+    }
+    ensure!(
+        num_verified > 0,
+        "None of the trusted waypoints is within the replayed versions (until {}).",
+        latest_version,
+    );
+
+    let address = diem_root_address();
+    let (blob, proof) = db.get_account_state_with_proof_by_version(address, latest_version)?;
+    proof.verify(
+        latest_txn_info.state_root_hash(),
+        address.hash(),
+        blob.as_ref(),
+    )?;
+    info!(
+        "Replayed DB verified until version {} against {} trusted waypoints.",
+        latest_version, num_verified,
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        backup_types::epoch_ending::restore::EpochHistory,
+        coordinators::restore_verify::covered_waypoints,
+    };
+    use diem_crypto::HashValue;
+    use diem_types::{
+        block_info::BlockInfo, epoch_state::EpochState, ledger_info::LedgerInfo,
+        transaction::Version, waypoint::Waypoint,
+    };
+    use std::collections::HashMap;
+
+    fn epoch_ending(epoch: u64, version: Version) -> LedgerInfo {
+        LedgerInfo::new(
+            BlockInfo::new(
+                epoch,
+                0, /* round */
+                HashValue::zero(),
+                HashValue::zero(),
+                version,
+                0, /* timestamp_usecs */
+                Some(EpochState::empty()),
+            ),
+            HashValue::zero(),
+        )
+    }
+
+    fn trusted(waypoints: &[Waypoint]) -> HashMap<Version, Waypoint> {
+        waypoints.iter().map(|w| (w.version(), *w)).collect()
+    }
+
+    #[test]
+    fn test_covered_waypoints() {
+        let epoch_history = EpochHistory {
+            epoch_endings: vec![epoch_ending(0, 0), epoch_ending(1, 10)],
+        };
+        let genesis = Waypoint::new_epoch_boundary(&epoch_history.epoch_endings[0]).unwrap();
+        let epoch_1 = Waypoint::new_epoch_boundary(&epoch_history.epoch_endings[1]).unwrap();
+        let beyond = Waypoint::new_epoch_boundary(&epoch_ending(2, 20)).unwrap();
+        let not_epoch_ending = Waypoint::new_any(&epoch_ending(1, 5));
+
+        assert_eq!(
+            covered_waypoints(&trusted(&[epoch_1, genesis, beyond]), &epoch_history).unwrap(),
+            vec![genesis, epoch_1],
+        );
+        assert!(covered_waypoints(&trusted(&[genesis, not_epoch_ending]), &epoch_history).is_err());
+        assert!(covered_waypoints(&trusted(&[beyond]), &epoch_history).is_err());
+        assert!(covered_waypoints(
+            &trusted(&[genesis]),
+            &EpochHistory {
+                epoch_endings: vec![]
+            }
+        )
+        .is_err());
+    }
+}
@@ -4,6 +4,7 @@
 use crate::{
     backup_types::{
         epoch_ending::restore::EpochHistoryRestoreController,
+        state_delta::restore::{StateDeltaRestoreController, StateDeltaRestoreOpt},
         state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
         transaction::restore::TransactionRestoreBatchController,
     },
@@ -71,6 +72,10 @@ impl VerifyCoordinator {
         .await?;
         let ver_max = Version::max_value();
         let state_snapshot = metadata_view.select_state_snapshot(ver_max)?;
+        let state_deltas = match &state_snapshot {
+            Some(b) => metadata_view.select_state_deltas(b.version, ver_max)?,
+            None => Vec::new(),
+        };
         let transactions = metadata_view.select_transaction_backups(0, ver_max)?;
         let epoch_endings = metadata_view.select_epoch_ending_backups(ver_max)?;
 
@@ -108,6 +113,19 @@ impl VerifyCoordinator {
             .await?;
         }
 
+        for backup in state_deltas {
+            StateDeltaRestoreController::new(
+                StateDeltaRestoreOpt {
+                    manifest_handle: backup.manifest,
+                },
+                global_opt.clone(),
+                Arc::clone(&self.storage),
+                Some(Arc::clone(&epoch_history)),
+            )
+            .run()
+            .await?;
+        }
+
         let txn_manifests = transactions.into_iter().map(|b| b.manifest).collect();
         TransactionRestoreBatchController::new(
             global_opt,
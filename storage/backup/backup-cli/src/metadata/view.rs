@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::metadata::{
-    EpochEndingBackupMeta, Metadata, StateSnapshotBackupMeta, TransactionBackupMeta,
+    EpochEndingBackupMeta, Metadata, StateDeltaBackupMeta, StateSnapshotBackupMeta,
+    TransactionBackupMeta,
 };
 use anyhow::{anyhow, ensure, Result};
 use diem_types::transaction::Version;
@@ -12,6 +13,7 @@ use std::{fmt, str::FromStr};
 pub struct MetadataView {
     epoch_ending_backups: Vec<EpochEndingBackupMeta>,
     state_snapshot_backups: Vec<StateSnapshotBackupMeta>,
+    state_delta_backups: Vec<StateDeltaBackupMeta>,
     transaction_backups: Vec<TransactionBackupMeta>,
 }
 
@@ -21,6 +23,7 @@ impl MetadataView {
             self.epoch_ending_backups.iter().map(|e| e.last_epoch).max();
         let latest_state_snapshot_version =
             self.state_snapshot_backups.iter().map(|s| s.version).max();
+        let latest_state_delta_version = self.state_delta_backups.iter().map(|d| d.version).max();
         let latest_transaction_version = self
             .transaction_backups
             .iter()
@@ -30,6 +33,7 @@ impl MetadataView {
         BackupStorageState {
             latest_epoch_ending_epoch,
             latest_state_snapshot_version,
+            latest_state_delta_version,
             latest_transaction_version,
         }
     }
@@ -47,6 +51,29 @@ impl MetadataView {
             .map(Clone::clone))
     }
 
+    /// Selects the chain of state deltas building the state of the latest version possible, no
+    /// newer than `target_version`, on top of the state of `base_version`.
+    pub fn select_state_deltas(
+        &self,
+        base_version: Version,
+        target_version: Version,
+    ) -> Result<Vec<StateDeltaBackupMeta>> {
+        let mut version = base_version;
+        let mut res = Vec::new();
+        while let Some(backup) = self
+            .state_delta_backups
+            .iter()
+            .filter(|d| d.base_version == version && d.version > version)
+            .filter(|d| d.version <= target_version)
+            .max()
+        {
+            res.push(backup.clone());
+            version = backup.version;
+        }
+
+        Ok(res)
+    }
+
     pub fn select_transaction_backups(
         &self,
         start_version: Version,
@@ -109,12 +136,14 @@ impl From<Vec<Metadata>> for MetadataView {
     fn from(metadata_vec: Vec<Metadata>) -> Self {
         let mut epoch_ending_backups = Vec::new();
         let mut state_snapshot_backups = Vec::new();
+        let mut state_delta_backups = Vec::new();
         let mut transaction_backups = Vec::new();
 
         for meta in metadata_vec {
             match meta {
                 Metadata::EpochEndingBackup(e) => epoch_ending_backups.push(e),
                 Metadata::StateSnapshotBackup(s) => state_snapshot_backups.push(s),
+                Metadata::StateDeltaBackup(d) => state_delta_backups.push(d),
                 Metadata::TransactionBackup(t) => transaction_backups.push(t),
             }
         }
@@ -122,6 +151,7 @@ impl From<Vec<Metadata>> for MetadataView {
         Self {
             epoch_ending_backups,
             state_snapshot_backups,
+            state_delta_backups,
             transaction_backups,
         }
     }
@@ -130,6 +160,7 @@ impl From<Vec<Metadata>> for MetadataView {
 pub struct BackupStorageState {
     pub latest_epoch_ending_epoch: Option<u64>,
     pub latest_state_snapshot_version: Option<Version>,
+    pub latest_state_delta_version: Option<Version>,
     pub latest_transaction_version: Option<Version>,
 }
 
@@ -137,9 +168,10 @@ impl fmt::Display for BackupStorageState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "latest_epoch_ending_epoch: {}, latest_state_snapshot_version: {}, latest_transaction_version: {}",
+            "latest_epoch_ending_epoch: {}, latest_state_snapshot_version: {}, latest_state_delta_version: {}, latest_transaction_version: {}",
             self.latest_epoch_ending_epoch.as_ref().map_or("none".to_string(), u64::to_string),
             self.latest_state_snapshot_version.as_ref().map_or("none".to_string(), Version::to_string),
+            self.latest_state_delta_version.as_ref().map_or("none".to_string(), Version::to_string),
             self.latest_transaction_version.as_ref().map_or("none".to_string(), Version::to_string),
         )
     }
@@ -165,13 +197,14 @@ impl FromStr for BackupStorageState {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let captures = regex::Regex::new(
-            r"latest_epoch_ending_epoch: (none|\d+), latest_state_snapshot_version: (none|\d+), latest_transaction_version: (none|\d+)",
+            r"latest_epoch_ending_epoch: (none|\d+), latest_state_snapshot_version: (none|\d+), latest_state_delta_version: (none|\d+), latest_transaction_version: (none|\d+)",
         )?.captures(s).ok_or_else(|| anyhow!("Not in BackupStorageState display format: {}", s))?;
 
         Ok(Self {
             latest_epoch_ending_epoch: captures.get(1).parse_option_u64()?,
             latest_state_snapshot_version: captures.get(2).parse_option_u64()?,
-            latest_transaction_version: captures.get(3).parse_option_u64()?,
+            latest_state_delta_version: captures.get(3).parse_option_u64()?,
+            latest_transaction_version: captures.get(4).parse_option_u64()?,
         })
     }
 }
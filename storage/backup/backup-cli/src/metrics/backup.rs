@@ -28,6 +28,14 @@ pub static STATE_SNAPSHOT_VERSION: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static STATE_DELTA_VERSION: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_backup_coordinator_state_delta_version",
+        "The version of the latest state delta taken."
+    )
+    .unwrap()
+});
+
 pub static TRANSACTION_VERSION: Lazy<IntGauge> = Lazy::new(|| {
     register_int_gauge!(
         "diem_db_backup_coordinator_transaction_version",
@@ -52,6 +52,14 @@ pub static STATE_SNAPSHOT_LEAF_INDEX: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static STATE_DELTA_VERSION: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_restore_state_delta_version",
+        "The version that a state delta restores to."
+    )
+    .unwrap()
+});
+
 pub static TRANSACTION_SAVE_VERSION: Lazy<IntGauge> = Lazy::new(|| {
     register_int_gauge!(
         "diem_db_restore_transaction_save_version",
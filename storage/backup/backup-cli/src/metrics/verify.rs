@@ -44,6 +44,14 @@ pub static VERIFY_STATE_SNAPSHOT_LEAF_INDEX: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static VERIFY_STATE_DELTA_VERSION: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_backup_verify_state_delta_version",
+        "The version of the verified state delta."
+    )
+    .unwrap()
+});
+
 pub static VERIFY_TRANSACTION_VERSION: Lazy<IntGauge> = Lazy::new(|| {
     register_int_gauge!(
         "diem_db_backup_verify_transaction_version",
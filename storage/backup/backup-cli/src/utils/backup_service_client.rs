@@ -76,6 +76,15 @@ impl BackupServiceClient {
         self.get(&format!("state_snapshot/{}", version)).await
     }
 
+    pub async fn get_state_delta(
+        &self,
+        base_version: Version,
+        version: Version,
+    ) -> Result<impl AsyncRead> {
+        self.get(&format!("state_delta/{}/{}", base_version, version))
+            .await
+    }
+
     pub async fn get_state_root_proof(&self, version: Version) -> Result<Vec<u8>> {
         let mut buf = Vec::new();
         self.get(&format!("state_root_proof/{}", version))
@@ -97,6 +106,15 @@ impl BackupServiceClient {
         .await
     }
 
+    pub async fn get_epoch_checkpoints(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<impl AsyncRead> {
+        self.get(&format!("epoch_checkpoints/{}/{}", start_epoch, end_epoch))
+            .await
+    }
+
     pub async fn get_transactions(
         &self,
         start_version: Version,
@@ -54,6 +54,7 @@ impl From<RocksdbOpt> for RocksdbConfig {
         Self {
             max_open_files: opt.max_open_files,
             max_total_wal_size: opt.max_total_wal_size,
+            ..Default::default()
         }
     }
 }
@@ -73,7 +74,7 @@ pub struct GlobalRestoreOpt {
         long = "target-db-dir",
         parse(from_os_str),
         conflicts_with = "dry-run",
-        required_unless = "dry-run"
+        help = "DB to restore into, required unless --dry-run."
     )]
     pub db_dir: Option<PathBuf>,
 
@@ -15,8 +15,10 @@ use warp::{filters::BoxedFilter, reply::Reply, Filter};
 static DB_STATE: &str = "db_state";
 static STATE_RANGE_PROOF: &str = "state_range_proof";
 static STATE_SNAPSHOT: &str = "state_snapshot";
+static STATE_DELTA: &str = "state_delta";
 static STATE_ROOT_PROOF: &str = "state_root_proof";
 static EPOCH_ENDING_LEDGER_INFOS: &str = "epoch_ending_ledger_infos";
+static EPOCH_CHECKPOINTS: &str = "epoch_checkpoints";
 static TRANSACTIONS: &str = "transactions";
 static TRANSACTION_RANGE_PROOF: &str = "transaction_range_proof";
 
@@ -50,6 +52,19 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
         })
         .recover(handle_rejection);
 
+    // GET state_delta/<base_version>/<version>
+    let bh = backup_handler.clone();
+    let state_delta = warp::path!(Version / Version)
+        .map(move |base_version, version| {
+            reply_with_async_channel_writer(&bh, STATE_DELTA, |bh, sender| {
+                send_size_prefixed_bcs_bytes(
+                    bh.get_account_delta_iter(base_version, version),
+                    sender,
+                )
+            })
+        })
+        .recover(handle_rejection);
+
     // GET state_root_proof/<version>
     let bh = backup_handler.clone();
     let state_root_proof = warp::path!(Version)
@@ -79,6 +94,22 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
         })
         .recover(handle_rejection);
 
+    // GET epoch_checkpoints/<start_epoch>/<end_epoch>/
+    let bh = backup_handler.clone();
+    let epoch_checkpoints = warp::path!(u64 / u64)
+        .map(move |start_epoch, end_epoch| {
+            // use async move block to group `bh` and the iterator into the same lifetime, since the
+            // latter references the former.
+            reply_with_async_channel_writer(&bh, EPOCH_CHECKPOINTS, |bh, sender| async move {
+                send_size_prefixed_bcs_bytes(
+                    bh.get_epoch_checkpoint_iter(start_epoch, end_epoch),
+                    sender,
+                )
+                .await
+            })
+        })
+        .recover(handle_rejection);
+
     // GET transactions/<start_version>/<num_transactions>
     let bh = backup_handler.clone();
     let transactions = warp::path!(Version / usize)
@@ -112,8 +143,10 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
         .and(warp::path(DB_STATE).and(db_state))
         .or(warp::path(STATE_RANGE_PROOF).and(state_range_proof))
         .or(warp::path(STATE_SNAPSHOT).and(state_snapshot))
+        .or(warp::path(STATE_DELTA).and(state_delta))
         .or(warp::path(STATE_ROOT_PROOF).and(state_root_proof))
         .or(warp::path(EPOCH_ENDING_LEDGER_INFOS).and(epoch_ending_ledger_infos))
+        .or(warp::path(EPOCH_CHECKPOINTS).and(epoch_checkpoints))
         .or(warp::path(TRANSACTIONS).and(transactions))
         .or(warp::path(TRANSACTION_RANGE_PROOF).and(transaction_range_proof));
 
@@ -0,0 +1,52 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use anyhow::Result;
+use diemdb::db_checker::DbChecker;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "db-checker",
+    about = "Checks the internal consistency of a DiemDB instance up to its latest ledger info, \
+    e.g. after an unclean shutdown or a disk error"
+)]
+struct Opt {
+    /// The parent dir of diemdb
+    #[structopt(long = "db", parse(from_os_str))]
+    db_dir: PathBuf,
+
+    /// The number of versions, or of accounts of the state tree, checked at a time
+    #[structopt(long, default_value = "10000")]
+    chunk_size: usize,
+}
+
+fn main() {
+    ::diem_logger::DiemLogger::builder().build();
+
+    match run() {
+        Ok(true) => println!("No corrupt data found."),
+        Ok(false) => std::process::exit(1),
+        Err(err) => {
+            println!("Error: {}", err);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Returns whether the DB is consistent.
+fn run() -> Result<bool> {
+    let opt = Opt::from_args();
+    let report = DbChecker::new(opt.db_dir, opt.chunk_size)?.check()?;
+    println!(
+        "Checked versions [{}, {}].",
+        report.first_version, report.ledger_version
+    );
+    for range in &report.corrupt_ranges {
+        println!("{}", range);
+    }
+    Ok(report.is_ok())
+}
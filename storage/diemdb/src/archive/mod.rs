@@ -0,0 +1,458 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides `Archive`, the cold storage of the old transactions and events, and
+//! `Archiver` which manages a thread moving them there from RocksDB in the background.
+//!
+//! The archive is a directory of chunk files, each holding the transactions and the events of a
+//! range of versions, BCS serialized then LZ4 compressed. The chunks are contiguous and only ever
+//! appended. A chunk file is durable before its data is deleted from RocksDB, so the readers fall
+//! back on the archive when an archived version is missing from RocksDB. Reading from the archive
+//! decompresses a whole chunk, which is cached as the reads tend to be of consecutive versions.
+
+use crate::{
+    metrics::{DIEM_STORAGE_ARCHIVED_VERSION, DIEM_STORAGE_OTHER_TIMERS_SECONDS},
+    schema::{event::EventSchema, transaction::TransactionSchema},
+};
+use anyhow::{ensure, Result};
+use diem_infallible::{Mutex, RwLock};
+use diem_logger::prelude::*;
+use diem_types::{
+    contract_event::ContractEvent,
+    transaction::{Transaction, Version},
+};
+use schemadb::{ReadOptions, DB};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::max,
+    collections::BTreeMap,
+    fs,
+    io::Write,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+const CHUNK_FILE_EXTENSION: &str = "chunk";
+
+/// The transactions and the events of consecutive versions, as stored in a chunk file.
+#[derive(Debug, Deserialize, Serialize)]
+struct ArchiveChunk {
+    first_version: Version,
+    transactions: Vec<Transaction>,
+    /// The events of each transaction.
+    events: Vec<Vec<ContractEvent>>,
+}
+
+impl ArchiveChunk {
+    fn end_version(&self) -> Version {
+        self.first_version + self.transactions.len() as u64
+    }
+
+    fn index(&self, version: Version) -> Option<usize> {
+        if version >= self.first_version && version < self.end_version() {
+            Some((version - self.first_version) as usize)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Archive {
+    dir: PathBuf,
+    /// The end (exclusive) of the versions of each chunk, by the first version of the chunk.
+    chunks: RwLock<BTreeMap<Version, Version>>,
+    /// The chunk read last.
+    cache: Mutex<Option<Arc<ArchiveChunk>>>,
+}
+
+impl Archive {
+    /// Opens the archive in `dir`, which is empty until the first chunk is written if `dir`
+    /// doesn't exist.
+    pub fn open(dir: PathBuf) -> Result<Self> {
+        let archive = Self {
+            dir,
+            chunks: RwLock::new(BTreeMap::new()),
+            cache: Mutex::new(None),
+        };
+        archive.refresh()?;
+        Ok(archive)
+    }
+
+    /// Rescans the directory for the chunks written since the opening by another process, e.g.
+    /// the primary of a secondary instance.
+    pub fn refresh(&self) -> Result<()> {
+        let mut chunks = BTreeMap::new();
+        if self.dir.exists() {
+            for entry in fs::read_dir(&self.dir)? {
+                if let Some((first_version, end_version)) = Self::parse_chunk_path(&entry?.path()) {
+                    chunks.insert(first_version, end_version);
+                }
+            }
+        }
+        let mut expected_first_version = None;
+        for (first_version, end_version) in &chunks {
+            if let Some(expected) = expected_first_version {
+                ensure!(
+                    *first_version == expected,
+                    "Archive chunks are not contiguous, versions [{}, {}) are missing.",
+                    expected,
+                    first_version,
+                );
+            }
+            expected_first_version = Some(*end_version);
+        }
+        *self.chunks.write() = chunks;
+        Ok(())
+    }
+
+    /// The versions archived, if any.
+    pub fn version_range(&self) -> Option<Range<Version>> {
+        let chunks = self.chunks.read();
+        let first_version = *chunks.keys().next()?;
+        let end_version = *chunks.values().next_back()?;
+        Some(first_version..end_version)
+    }
+
+    /// The version following the last archived one, 0 if nothing is archived.
+    pub fn end_version(&self) -> Version {
+        self.version_range().map_or(0, |range| range.end)
+    }
+
+    /// Gets the transaction of `version` if it's archived.
+    pub fn get_transaction(&self, version: Version) -> Result<Option<Transaction>> {
+        Ok(self.get_chunk(version)?.and_then(|chunk| {
+            chunk
+                .index(version)
+                .map(|index| chunk.transactions[index].clone())
+        }))
+    }
+
+    /// Gets the events of the transaction of `version` if it's archived.
+    pub fn get_events(&self, version: Version) -> Result<Option<Vec<ContractEvent>>> {
+        Ok(self.get_chunk(version)?.and_then(|chunk| {
+            chunk
+                .index(version)
+                .map(|index| chunk.events[index].clone())
+        }))
+    }
+
+    fn get_chunk(&self, version: Version) -> Result<Option<Arc<ArchiveChunk>>> {
+        let (first_version, end_version) = match self.chunks.read().range(..=version).next_back() {
+            Some((first_version, end_version)) if version < *end_version => {
+                (*first_version, *end_version)
+            }
+            _ => return Ok(None),
+        };
+
+        let mut cache = self.cache.lock();
+        if let Some(chunk) = cache.as_ref() {
+            if chunk.first_version == first_version {
+                return Ok(Some(Arc::clone(chunk)));
+            }
+        }
+
+        let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
+            .with_label_values(&["archive_read_chunk"])
+            .start_timer();
+        let compressed = fs::read(self.chunk_path(first_version, end_version))?;
+        let chunk: ArchiveChunk = bcs::from_bytes(&lz4::block::decompress(&compressed, None)?)?;
+        ensure!(
+            chunk.first_version == first_version
+                && chunk.end_version() == end_version
+                && chunk.events.len() == chunk.transactions.len(),
+            "Archive chunk of versions [{}, {}) is corrupt.",
+            first_version,
+            end_version,
+        );
+        let chunk = Arc::new(chunk);
+        *cache = Some(Arc::clone(&chunk));
+        Ok(Some(chunk))
+    }
+
+    /// Appends a chunk to the archive, readable once it's durable.
+    fn write_chunk(&self, chunk: &ArchiveChunk) -> Result<()> {
+        ensure!(!chunk.transactions.is_empty(), "Archive chunk is empty.");
+        if let Some(range) = self.version_range() {
+            ensure!(
+                chunk.first_version == range.end,
+                "Archive chunk starting at version {} doesn't follow the archived versions {:?}.",
+                chunk.first_version,
+                range,
+            );
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        let path = self.chunk_path(chunk.first_version, chunk.end_version());
+        let tmp_path = path.with_extension("tmp");
+        {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(&lz4::block::compress(
+                &bcs::to_bytes(chunk)?,
+                None, /* mode */
+                true, /* prepend_size */
+            )?)?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, &path)?;
+        fs::File::open(&self.dir)?.sync_all()?;
+
+        self.chunks
+            .write()
+            .insert(chunk.first_version, chunk.end_version());
+        DIEM_STORAGE_ARCHIVED_VERSION.set(chunk.end_version() as i64);
+        Ok(())
+    }
+
+    fn chunk_path(&self, first_version: Version, end_version: Version) -> PathBuf {
+        self.dir.join(format!(
+            "{:020}-{:020}.{}",
+            first_version, end_version, CHUNK_FILE_EXTENSION,
+        ))
+    }
+
+    fn parse_chunk_path(path: &Path) -> Option<(Version, Version)> {
+        if path.extension()? != CHUNK_FILE_EXTENSION {
+            return None;
+        }
+        let (first_version, end_version) = path.file_stem()?.to_str()?.split_once('-')?;
+        Some((first_version.parse().ok()?, end_version.parse().ok()?))
+    }
+}
+
+/// Moves the transactions and the events of versions `[first_version, end_version)` from RocksDB
+/// into a new chunk of the archive.
+pub(crate) fn archive_versions(
+    db: &DB,
+    archive: &Archive,
+    first_version: Version,
+    end_version: Version,
+) -> Result<()> {
+    let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
+        .with_label_values(&["archive_versions"])
+        .start_timer();
+
+    let mut transactions = Vec::new();
+    let mut iter = db.iter::<TransactionSchema>(ReadOptions::default())?;
+    iter.seek(&first_version)?;
+    for res in iter {
+        let (version, txn) = res?;
+        if version >= end_version {
+            break;
+        }
+        ensure!(
+            version == first_version + transactions.len() as u64,
+            "Transaction of version {} is missing.",
+            first_version + transactions.len() as u64,
+        );
+        transactions.push(txn);
+    }
+    ensure!(
+        transactions.len() as u64 == end_version - first_version,
+        "Transactions of versions [{}, {}) are missing.",
+        first_version + transactions.len() as u64,
+        end_version,
+    );
+
+    let mut events = vec![Vec::new(); transactions.len()];
+    let mut iter = db.iter::<EventSchema>(ReadOptions::default())?;
+    iter.seek(&first_version)?;
+    for res in iter {
+        let ((version, _index), event) = res?;
+        if version >= end_version {
+            break;
+        }
+        events[(version - first_version) as usize].push(event);
+    }
+
+    archive.write_chunk(&ArchiveChunk {
+        first_version,
+        transactions,
+        events,
+    })?;
+    delete_archived(db, first_version, end_version)
+}
+
+/// Deletes the transactions and the events of the archived versions `[first_version, end_version)`
+/// from RocksDB.
+fn delete_archived(db: &DB, first_version: Version, end_version: Version) -> Result<()> {
+    db.range_delete::<TransactionSchema, Version>(&first_version, &end_version)?;
+    db.range_delete::<EventSchema, Version>(&first_version, &end_version)
+}
+
+/// The `Archiver` is meant to be part of a `DiemDB` instance and runs in the background to move
+/// the transactions and the events older than `horizon` versions into the archive.
+///
+/// It creates a worker thread on construction and joins it on destruction. When destructed, it
+/// quits the worker thread once the chunk being archived is done.
+#[derive(Debug)]
+pub(crate) struct Archiver {
+    /// The worker thread handle, created upon Archiver instance construction and joined upon its
+    /// destruction. It only becomes `None` after joined in `drop()`.
+    worker_thread: Option<JoinHandle<()>>,
+    /// The sender side of the channel talking to the worker thread.
+    command_sender: Mutex<Sender<Command>>,
+}
+
+impl Archiver {
+    /// The number of versions archived into each chunk.
+    pub const VERSIONS_PER_CHUNK: u64 = 10_000;
+
+    /// Creates a worker thread that waits on a channel for archiving commands.
+    pub fn new(db: Arc<DB>, archive: Arc<Archive>, horizon: u64, versions_per_chunk: u64) -> Self {
+        let (command_sender, command_receiver) = channel();
+
+        let worker_thread = std::thread::Builder::new()
+            .name("diemdb_archiver".into())
+            .spawn(move || {
+                Worker {
+                    db,
+                    archive,
+                    horizon,
+                    versions_per_chunk,
+                    command_receiver,
+                }
+                .work()
+            })
+            .expect("Creating archiver thread should succeed.");
+
+        Self {
+            worker_thread: Some(worker_thread),
+            command_sender: Mutex::new(command_sender),
+        }
+    }
+
+    /// Informs the worker thread of the latest version committed.
+    pub fn wake(&self, latest_version: Version) {
+        self.command_sender
+            .lock()
+            .send(Command::Archive { latest_version })
+            .expect("Receiver should not destruct prematurely.");
+    }
+}
+
+impl Drop for Archiver {
+    fn drop(&mut self) {
+        self.command_sender
+            .lock()
+            .send(Command::Quit)
+            .expect("Receiver should not destruct.");
+        self.worker_thread
+            .take()
+            .expect("Worker thread must exist.")
+            .join()
+            .expect("Worker thread should join peacefully.");
+    }
+}
+
+enum Command {
+    Quit,
+    Archive { latest_version: Version },
+}
+
+struct Worker {
+    db: Arc<DB>,
+    archive: Arc<Archive>,
+    horizon: u64,
+    versions_per_chunk: u64,
+    command_receiver: Receiver<Command>,
+}
+
+impl Worker {
+    fn work(self) {
+        // The process might have crashed after the last chunk was written but before its data was
+        // deleted from RocksDB.
+        if let Some(range) = self.archive.version_range() {
+            if let Err(e) = delete_archived(&self.db, range.start, range.end) {
+                warn!(
+                    error = ?e,
+                    "[archiver worker] Failed deleting archived data, ignored.",
+                );
+            }
+        }
+
+        let mut latest_version = 0;
+        while self.receive_commands(&mut latest_version, true /* blocking */) {
+            loop {
+                match self.archive_next_chunk(latest_version) {
+                    Ok(true) => (),
+                    Ok(false) => break,
+                    Err(e) => {
+                        error!(
+                            error = ?e,
+                            "[archiver worker] Error archiving.",
+                        );
+                        break;
+                    }
+                }
+                // Quit between the chunks if `Command::Quit` is received.
+                if !self.receive_commands(&mut latest_version, false /* blocking */) {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Receives the pending commands into `latest_version`, blocking waits for the first one if
+    /// `blocking`.
+    ///
+    /// Returns `false` if `Command::Quit` is received.
+    fn receive_commands(&self, latest_version: &mut Version, mut blocking: bool) -> bool {
+        loop {
+            let command = if blocking {
+                blocking = false;
+                self.command_receiver
+                    .recv()
+                    .expect("Sender should not destruct prematurely.")
+            } else {
+                match self.command_receiver.try_recv() {
+                    Ok(command) => command,
+                    Err(_) => return true,
+                }
+            };
+            match command {
+                Command::Quit => return false,
+                Command::Archive {
+                    latest_version: version,
+                } => *latest_version = max(*latest_version, version),
+            }
+        }
+    }
+
+    /// Archives the chunk following the archived versions if it's entirely beyond the horizon.
+    ///
+    /// Returns whether a chunk was archived.
+    fn archive_next_chunk(&self, latest_version: Version) -> Result<bool> {
+        let first_version = match self.archive.version_range() {
+            Some(range) => range.end,
+            None => {
+                let mut iter = self.db.iter::<TransactionSchema>(ReadOptions::default())?;
+                iter.seek_to_first();
+                match iter.next().transpose()? {
+                    Some((version, _)) => version,
+                    None => return Ok(false),
+                }
+            }
+        };
+        let end_version = first_version.saturating_add(self.versions_per_chunk);
+        if end_version > latest_version.saturating_sub(self.horizon) {
+            return Ok(false);
+        }
+
+        archive_versions(&self.db, &self.archive, first_version, end_version)?;
+        info!(
+            first_version = first_version,
+            end_version = end_version,
+            "[archiver worker] Archived versions.",
+        );
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test;
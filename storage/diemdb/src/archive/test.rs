@@ -0,0 +1,180 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{
+    change_set::ChangeSet, event_store::EventStore, transaction_store::TransactionStore, DiemDB,
+};
+use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+use diem_temppath::TempPath;
+use diem_types::{
+    account_address::AccountAddress, event::EventKey,
+    test_helpers::transaction_test_helpers::get_test_signed_txn,
+};
+use move_core_types::language_storage::TypeTag;
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+/// Puts a transaction of `sender` emitting `version % 3` events for each of the versions.
+fn put_ledger(db: &Arc<DB>, sender: AccountAddress, event_key: EventKey, num_versions: u64) {
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+    let transaction_store = TransactionStore::new(Arc::clone(db));
+    let event_store = EventStore::new(Arc::clone(db));
+    let mut cs = ChangeSet::new();
+    let mut seq_num = 0;
+    for version in 0..num_versions {
+        let txn = get_test_signed_txn(
+            sender,
+            version,
+            &private_key,
+            private_key.public_key(),
+            None,
+        );
+        transaction_store
+            .put_transaction(version, &Transaction::UserTransaction(txn), &mut cs)
+            .unwrap();
+        let events: Vec<_> = (0..version % 3)
+            .map(|_| {
+                seq_num += 1;
+                ContractEvent::new(event_key, seq_num - 1, TypeTag::Bool, vec![])
+            })
+            .collect();
+        event_store.put_events(version, &events, &mut cs).unwrap();
+    }
+    db.write_schemas(cs.batch).unwrap();
+}
+
+fn wait_until(condition: impl Fn() -> bool) {
+    let end = Instant::now() + Duration::from_secs(10);
+    while !condition() {
+        assert!(Instant::now() < end, "Timeout waiting for archiver worker.");
+        sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn test_archive_versions() {
+    let sender = AccountAddress::new([1u8; AccountAddress::LENGTH]);
+    let event_key = EventKey::new_from_address(&sender, 0);
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir).db;
+    put_ledger(&db, sender, event_key, 10);
+    let archive_dir = tmp_dir.path().join("test_archive");
+    let archive = Arc::new(Archive::open(archive_dir.clone()).unwrap());
+    let transaction_store =
+        TransactionStore::new_with_archive(Arc::clone(&db), Arc::clone(&archive));
+    let event_store = EventStore::new_with_archive(Arc::clone(&db), Arc::clone(&archive));
+
+    let transactions = transaction_store
+        .get_transaction_iter(0, 10)
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    let events = event_store
+        .get_events_by_version_iter(0, 10)
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+
+    archive_versions(&db, &archive, 0, 4).unwrap();
+    archive_versions(&db, &archive, 4, 8).unwrap();
+    assert_eq!(archive.version_range(), Some(0..8));
+    // the chunks must be contiguous
+    assert!(archive_versions(&db, &archive, 9, 10).is_err());
+
+    // the data is gone from RocksDB
+    for version in 0..8 {
+        assert!(db.get::<TransactionSchema>(&version).unwrap().is_none());
+        assert!(db.get::<EventSchema>(&(version, 0)).unwrap().is_none());
+    }
+
+    // but still readable, from the archive
+    assert_eq!(
+        transaction_store
+            .get_transaction_iter(0, 10)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap(),
+        transactions
+    );
+    assert_eq!(
+        event_store
+            .get_events_by_version_iter(2, 8)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap(),
+        events[2..]
+    );
+    for version in 0..10 {
+        assert_eq!(
+            transaction_store.get_transaction(version).unwrap(),
+            transactions[version as usize]
+        );
+        assert_eq!(
+            event_store.get_events_by_version(version).unwrap(),
+            events[version as usize]
+        );
+    }
+    assert_eq!(
+        event_store.get_event_by_version_and_index(5, 1).unwrap(),
+        events[5][1]
+    );
+    assert!(event_store.get_event_by_version_and_index(5, 2).is_err());
+    event_store
+        .get_event_with_proof_by_version_and_index(5, 1)
+        .unwrap();
+    // the indexes stay in RocksDB
+    assert_eq!(
+        event_store
+            .lookup_events_by_key(&event_key, 0, 2, 9)
+            .unwrap(),
+        vec![(0, 1, 0), (1, 2, 0)]
+    );
+    assert_eq!(
+        transaction_store
+            .get_account_transaction_version(sender, 3, 9)
+            .unwrap(),
+        Some(3)
+    );
+    // the search for the block metadata goes on in the archive, down to the genesis here
+    assert_eq!(transaction_store.get_block_metadata(9).unwrap(), None);
+
+    // the archive is found on reopening
+    let archive = Archive::open(archive_dir).unwrap();
+    assert_eq!(archive.version_range(), Some(0..8));
+    assert_eq!(
+        archive.get_transaction(7).unwrap().as_ref(),
+        Some(&transactions[7])
+    );
+    assert_eq!(archive.get_transaction(8).unwrap(), None);
+}
+
+#[test]
+fn test_archiver() {
+    let sender = AccountAddress::new([1u8; AccountAddress::LENGTH]);
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir).db;
+    put_ledger(&db, sender, EventKey::new_from_address(&sender, 0), 10);
+    let archive = Arc::new(Archive::open(tmp_dir.path().join("test_archive")).unwrap());
+    let transaction_store =
+        TransactionStore::new_with_archive(Arc::clone(&db), Arc::clone(&archive));
+
+    let archiver = Archiver::new(
+        Arc::clone(&db),
+        Arc::clone(&archive),
+        2, /* horizon */
+        3, /* versions_per_chunk */
+    );
+    // versions [6, 9) aren't entirely beyond the horizon
+    archiver.wake(9 /* latest_version */);
+    wait_until(|| archive.end_version() == 6);
+    assert!(db.get::<TransactionSchema>(&5).unwrap().is_none());
+    assert!(db.get::<TransactionSchema>(&6).unwrap().is_some());
+    for version in 0..10 {
+        assert!(transaction_store.get_transaction(version).is_ok());
+    }
+    drop(archiver);
+    assert_eq!(archive.version_range(), Some(0..6));
+}
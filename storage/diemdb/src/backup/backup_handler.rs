@@ -8,15 +8,17 @@ use crate::{
         BACKUP_EPOCH_ENDING_EPOCH, BACKUP_STATE_SNAPSHOT_LEAF_IDX, BACKUP_STATE_SNAPSHOT_VERSION,
         BACKUP_TXN_VERSION,
     },
+    pruner::{PrunedData, PrunerProgress},
     state_store::StateStore,
     transaction_store::TransactionStore,
 };
 use anyhow::{anyhow, ensure, Result};
 use diem_crypto::hash::HashValue;
-use diem_jellyfish_merkle::iterator::JellyfishMerkleIterator;
+use diem_jellyfish_merkle::iterator::{JellyfishMerkleDeltaIterator, JellyfishMerkleIterator};
 use diem_types::{
     account_state_blob::AccountStateBlob,
     contract_event::ContractEvent,
+    epoch_checkpoint::EpochCheckpoint,
     ledger_info::LedgerInfoWithSignatures,
     proof::{SparseMerkleRangeProof, TransactionAccumulatorRangeProof, TransactionInfoWithProof},
     transaction::{Transaction, TransactionInfo, Version},
@@ -32,6 +34,7 @@ pub struct BackupHandler {
     transaction_store: Arc<TransactionStore>,
     state_store: Arc<StateStore>,
     event_store: Arc<EventStore>,
+    pruner_progress: Arc<PrunerProgress>,
 }
 
 impl BackupHandler {
@@ -40,22 +43,29 @@ impl BackupHandler {
         transaction_store: Arc<TransactionStore>,
         state_store: Arc<StateStore>,
         event_store: Arc<EventStore>,
+        pruner_progress: Arc<PrunerProgress>,
     ) -> Self {
         Self {
             ledger_store,
             transaction_store,
             state_store,
             event_store,
+            pruner_progress,
         }
     }
 
     /// Gets an iterator that yields a range of transactions.
+    /// The pruner leaves the range alone until the iterator is dropped.
     pub fn get_transaction_iter(
         &self,
         start_version: Version,
         num_transactions: usize,
     ) -> Result<impl Iterator<Item = Result<(Transaction, TransactionInfo, Vec<ContractEvent>)>> + '_>
     {
+        let pin = self.pruner_progress.pin(
+            start_version,
+            &[PrunedData::Transactions, PrunedData::Events],
+        )?;
         let txn_iter = self
             .transaction_store
             .get_transaction_iter(start_version, num_transactions)?;
@@ -69,6 +79,7 @@ impl BackupHandler {
         let zipped = zip_eq(zip_eq(txn_iter, txn_info_iter), events_iter)
             .enumerate()
             .map(move |(idx, ((txn_res, txn_info_res), events_res))| {
+                let _pin = &pin;
                 BACKUP_TXN_VERSION.set((start_version.wrapping_add(idx as u64)) as i64);
                 Ok((txn_res?, txn_info_res?, events_res?))
             });
@@ -100,10 +111,12 @@ impl BackupHandler {
     }
 
     /// Gets an iterator which can yield all accounts in the state tree.
+    /// The pruner leaves the state of the version alone until the iterator is dropped.
     pub fn get_account_iter(
         &self,
         version: Version,
     ) -> Result<Box<dyn Iterator<Item = Result<(HashValue, AccountStateBlob)>> + Send + Sync>> {
+        let pin = self.pruner_progress.pin(version, &[PrunedData::State])?;
         let iterator = JellyfishMerkleIterator::new(
             Arc::clone(&self.state_store),
             version,
@@ -111,6 +124,7 @@ impl BackupHandler {
         )?
         .enumerate()
         .map(move |(idx, res)| {
+            let _pin = &pin;
             BACKUP_STATE_SNAPSHOT_VERSION.set(version as i64);
             BACKUP_STATE_SNAPSHOT_LEAF_IDX.set(idx as i64);
             res
@@ -118,6 +132,33 @@ impl BackupHandler {
         Ok(Box::new(iterator))
     }
 
+    /// Gets an iterator which yields the accounts changed after `base_version` up to `version`,
+    /// with their states at `version`, sorted by key. It may yield some accounts that were only
+    /// moved in the tree without being changed.
+    /// The pruner leaves the state of the version alone until the iterator is dropped.
+    pub fn get_account_delta_iter(
+        &self,
+        base_version: Version,
+        version: Version,
+    ) -> Result<Box<dyn Iterator<Item = Result<(HashValue, AccountStateBlob)>> + Send + Sync>> {
+        ensure!(
+            base_version < version,
+            "Bad state delta range: ({}, {}]",
+            base_version,
+            version,
+        );
+        let pin = self.pruner_progress.pin(version, &[PrunedData::State])?;
+        // make sure the tree of `version` is still there
+        self.state_store.get_root_hash(version)?;
+        let iterator =
+            JellyfishMerkleDeltaIterator::new(Arc::clone(&self.state_store), base_version, version)
+                .map(move |res| {
+                    let _pin = &pin;
+                    res
+                });
+        Ok(Box::new(iterator))
+    }
+
     /// Gets the proof that proves a range of accounts.
     pub fn get_account_state_range_proof(
         &self,
@@ -167,6 +208,18 @@ impl BackupHandler {
         Ok((txn_info, ledger_info))
     }
 
+    /// Gets what a DB bootstrapped from the state snapshot at `version` needs on top of the state:
+    /// the LedgerInfo and the TransactionInfo at `version`, and the frozen subtree roots of the
+    /// transaction accumulator before `version`, from left to right.
+    /// `version` must be that of a LedgerInfo kept in the DB, i.e. an epoch ending version or the
+    /// latest committed version.
+    pub fn get_bootstrap_ledger(
+        &self,
+        version: Version,
+    ) -> Result<(LedgerInfoWithSignatures, TransactionInfo, Vec<HashValue>)> {
+        self.ledger_store.get_bootstrap_ledger(version)
+    }
+
     pub fn get_epoch_ending_ledger_info_iter(
         &self,
         start_epoch: u64,
@@ -181,6 +234,18 @@ impl BackupHandler {
                 li
             }))
     }
+
+    /// Gets an iterator that yields the checkpoints saved at the end of the epochs from
+    /// `start_epoch` to the one before `end_epoch`, skipping the epochs ended before the checkpoints
+    /// were written.
+    pub fn get_epoch_checkpoint_iter(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<impl Iterator<Item = Result<EpochCheckpoint>> + '_> {
+        self.ledger_store
+            .get_epoch_checkpoint_iter(start_epoch, end_epoch)
+    }
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -3,8 +3,7 @@
 
 use crate::{
     change_set::ChangeSet, event_store::EventStore, ledger_store::LedgerStore,
-    schema::transaction_accumulator::TransactionAccumulatorSchema, state_store::StateStore,
-    transaction_store::TransactionStore, DiemDB,
+    state_store::StateStore, transaction_store::TransactionStore, DiemDB,
 };
 use anyhow::{ensure, Result};
 use diem_crypto::{hash::SPARSE_MERKLE_PLACEHOLDER_HASH, HashValue};
@@ -12,8 +11,9 @@ use diem_jellyfish_merkle::restore::JellyfishMerkleRestore;
 use diem_types::{
     account_state_blob::AccountStateBlob,
     contract_event::ContractEvent,
+    epoch_checkpoint::EpochCheckpoint,
     ledger_info::LedgerInfoWithSignatures,
-    proof::{definition::LeafCount, position::FrozenSubTreeIterator},
+    proof::definition::LeafCount,
     transaction::{Transaction, TransactionInfo, Version, PRE_GENESIS_VERSION},
 };
 use schemadb::DB;
@@ -62,6 +62,19 @@ impl RestoreHandler {
         )
     }
 
+    /// Builds the state of `version` from the state of `base_version`, which must be in the DB
+    /// already, and the accounts changed in between.
+    pub fn save_state_delta(
+        &self,
+        base_version: Version,
+        version: Version,
+        expected_root_hash: HashValue,
+        delta: Vec<(HashValue, AccountStateBlob)>,
+    ) -> Result<()> {
+        self.state_store
+            .put_account_state_delta(base_version, version, expected_root_hash, delta)
+    }
+
     pub fn save_ledger_infos(&self, ledger_infos: &[LedgerInfoWithSignatures]) -> Result<()> {
         ensure!(!ledger_infos.is_empty(), "No LedgerInfos to save.");
 
@@ -89,36 +102,30 @@ impl RestoreHandler {
         num_leaves: LeafCount,
         frozen_subtrees: &[HashValue],
     ) -> Result<()> {
-        let mut cs = ChangeSet::new();
-        let positions: Vec<_> = FrozenSubTreeIterator::new(num_leaves).collect();
+        self.diemdb
+            .confirm_or_save_frozen_subtrees(num_leaves, frozen_subtrees)
+    }
 
+    /// Makes the state at the version of `ledger_info`, which must be in the DB already, the
+    /// starting point of a DB without any transaction, given the TransactionInfo at that version
+    /// and the frozen subtree roots of the transaction accumulator before it, from left to right.
+    /// Both are checked against `ledger_info`, which is trusted.
+    pub fn save_bootstrap_ledger(
+        &self,
+        ledger_info: &LedgerInfoWithSignatures,
+        txn_info: &TransactionInfo,
+        frozen_subtrees: &[HashValue],
+    ) -> Result<()> {
         ensure!(
-            positions.len() == frozen_subtrees.len(),
-            "Number of frozen subtree roots not expected. Expected: {}, actual: {}",
-            positions.len(),
-            frozen_subtrees.len(),
+            self.get_next_expected_transaction_version()? == 0,
+            "DB has existing transactions.",
         );
-
-        positions
-            .iter()
-            .zip(frozen_subtrees.iter().rev())
-            .map(|(p, h)| {
-                if let Some(_h) = self.db.get::<TransactionAccumulatorSchema>(p)? {
-                    ensure!(
-                        h == &_h,
-                        "Frozen subtree root does not match that already in DB. Provided: {}, in db: {}.",
-                        h,
-                        _h,
-                    );
-                } else {
-                    cs.batch.put::<TransactionAccumulatorSchema>(p, h)?;
-                }
-                Ok(())
-            })
-            .collect::<Result<Vec<_>>>()?;
-        self.db.write_schemas(cs.batch)
+        self.diemdb
+            .save_ledger_at_state(ledger_info, txn_info, frozen_subtrees)
     }
 
+    /// The epoch ending ledger infos are restored before the transactions, so the checkpoints of
+    /// the epochs ending at these transactions are saved along with them.
     pub fn save_transactions(
         &self,
         first_version: Version,
@@ -136,6 +143,18 @@ impl RestoreHandler {
         self.event_store
             .put_events_multiple_versions(first_version, events, &mut cs)?;
 
+        let end_version = first_version + txn_infos.len() as Version;
+        for li in self
+            .ledger_store
+            .get_epoch_ending_ledger_infos_in_versions(first_version, end_version)?
+        {
+            let version = li.ledger_info().version();
+            let txn_info = &txn_infos[(version - first_version) as usize];
+            let checkpoint = EpochCheckpoint::new(li.ledger_info(), txn_info.state_root_hash())?;
+            self.ledger_store
+                .put_epoch_checkpoint(&checkpoint, &mut cs)?;
+        }
+
         self.db.write_schemas(cs.batch)
     }
 
@@ -1,11 +1,14 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{test_helper::arb_blocks_to_commit, DiemDB};
+use crate::{test_helper::arb_blocks_to_commit, DiemDB, GetRestoreHandler};
 use anyhow::Result;
+use diem_crypto::HashValue;
 use diem_temppath::TempPath;
+use diem_types::account_address::HashAccountAddress;
 use proptest::prelude::*;
-use storage_interface::DbWriter;
+use std::{collections::BTreeMap, sync::Arc};
+use storage_interface::{DbReader, DbWriter};
 
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10))]
@@ -41,4 +44,204 @@ proptest! {
             .unwrap();
         prop_assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_state_delta(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::new_for_test(&tmp_dir);
+
+        let mut cur_ver = 0;
+        let mut base_version = None;
+        let mut expected = BTreeMap::new();
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+            if base_version.is_none() {
+                base_version = Some(cur_ver - 1);
+                continue;
+            }
+            for txn_to_commit in txns_to_commit {
+                for (address, blob) in txn_to_commit.account_states() {
+                    expected.insert(address.hash(), blob.clone());
+                }
+            }
+        }
+        let base_version = base_version.unwrap();
+        let version = cur_ver - 1;
+        prop_assume!(base_version < version);
+
+        let backup_handler = db.get_backup_handler();
+        let delta = backup_handler
+            .get_account_delta_iter(base_version, version)
+            .unwrap()
+            .collect::<Result<BTreeMap<_, _>>>()
+            .unwrap();
+        let state = backup_handler
+            .get_account_iter(version)
+            .unwrap()
+            .collect::<Result<BTreeMap<_, _>>>()
+            .unwrap();
+        // every changed account is in the delta, and the delta holds the states at `version`
+        for (key, blob) in &expected {
+            prop_assert_eq!(delta.get(key), Some(blob));
+        }
+        for (key, blob) in &delta {
+            prop_assert_eq!(state.get(key), Some(blob));
+        }
+
+        // rebuild the state of `version` from the state of `base_version` and the delta
+        let tgt_tmp_dir = TempPath::new();
+        let tgt_db = Arc::new(DiemDB::new_for_test(&tgt_tmp_dir));
+        let restore_handler = tgt_db.get_restore_handler();
+        let base_state = backup_handler
+            .get_account_iter(base_version)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let mut receiver = restore_handler
+            .get_state_restore_receiver(
+                base_version,
+                db.state_store.get_root_hash(base_version).unwrap(),
+            )
+            .unwrap();
+        let rightmost_key = base_state.last().unwrap().0;
+        receiver
+            .add_chunk(
+                base_state,
+                backup_handler
+                    .get_account_state_range_proof(rightmost_key, base_version)
+                    .unwrap(),
+            )
+            .unwrap();
+        receiver.finish().unwrap();
+        restore_handler
+            .save_state_delta(
+                base_version,
+                version,
+                db.state_store.get_root_hash(version).unwrap(),
+                delta.into_iter().collect(),
+            )
+            .unwrap();
+        prop_assert_eq!(
+            tgt_db.state_store.get_root_hash(version).unwrap(),
+            db.state_store.get_root_hash(version).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_bootstrap_ledger(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::new_for_test(&tmp_dir);
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        let version = cur_ver - 1;
+
+        let backup_handler = db.get_backup_handler();
+        let (ledger_info, txn_info, frozen_subtrees) =
+            backup_handler.get_bootstrap_ledger(version).unwrap();
+        prop_assert_eq!(ledger_info.ledger_info().version(), version);
+        let epoch_endings = backup_handler
+            .get_epoch_ending_ledger_info_iter(0, ledger_info.ledger_info().next_block_epoch())
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        // bootstrap a new DB from the state at `version` only
+        let tgt_tmp_dir = TempPath::new();
+        let tgt_db = Arc::new(DiemDB::new_for_test(&tgt_tmp_dir));
+        let restore_handler = tgt_db.get_restore_handler();
+        let state = backup_handler
+            .get_account_iter(version)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let rightmost_key = state.last().unwrap().0;
+        let mut receiver = restore_handler
+            .get_state_restore_receiver(version, txn_info.state_root_hash())
+            .unwrap();
+        receiver
+            .add_chunk(
+                state,
+                backup_handler
+                    .get_account_state_range_proof(rightmost_key, version)
+                    .unwrap(),
+            )
+            .unwrap();
+        receiver.finish().unwrap();
+        if !epoch_endings.is_empty() {
+            restore_handler.save_ledger_infos(&epoch_endings).unwrap();
+        }
+
+        // malformed frozen subtrees are rejected before anything is written
+        let mut bad_frozen_subtrees = frozen_subtrees.clone();
+        bad_frozen_subtrees.push(HashValue::zero());
+        prop_assert!(restore_handler
+            .save_bootstrap_ledger(&ledger_info, &txn_info, &bad_frozen_subtrees)
+            .is_err());
+
+        restore_handler
+            .save_bootstrap_ledger(&ledger_info, &txn_info, &frozen_subtrees)
+            .unwrap();
+        prop_assert_eq!(
+            tgt_db.get_startup_info().unwrap(),
+            db.get_startup_info().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_restore_epoch_checkpoints(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::new_for_test(&tmp_dir);
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        let next_epoch = input.last().unwrap().1.ledger_info().next_block_epoch();
+
+        let backup_handler = db.get_backup_handler();
+        let epoch_endings = backup_handler
+            .get_epoch_ending_ledger_info_iter(0, next_epoch)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let (mut txns, mut txn_infos, mut events) = (vec![], vec![], vec![]);
+        for res in backup_handler.get_transaction_iter(0, cur_ver as usize).unwrap() {
+            let (txn, txn_info, txn_events) = res.unwrap();
+            txns.push(txn);
+            txn_infos.push(txn_info);
+            events.push(txn_events);
+        }
+
+        // the checkpoints are rebuilt when restoring the transactions after the epoch endings
+        let tgt_tmp_dir = TempPath::new();
+        let tgt_db = Arc::new(DiemDB::new_for_test(&tgt_tmp_dir));
+        let restore_handler = tgt_db.get_restore_handler();
+        restore_handler.save_ledger_infos(&epoch_endings).unwrap();
+        restore_handler
+            .save_transactions(0, &txns, &txn_infos, &events)
+            .unwrap();
+
+        let expected = backup_handler
+            .get_epoch_checkpoint_iter(0, next_epoch)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        prop_assert_eq!(expected.len(), epoch_endings.len());
+        let actual = tgt_db
+            .get_backup_handler()
+            .get_epoch_checkpoint_iter(0, next_epoch)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        prop_assert_eq!(actual, expected);
+    }
 }
@@ -0,0 +1,156 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides `CommitWriter` which manages a thread writing the commits in the
+//! background, so that a commit can be built while the previous one is written and synced.
+//!
+//! At most one commit is being written at any time: before the write of a commit is handed to the
+//! worker thread, the one of the previous commit is waited for. The commit handed over is
+//! announced to the DB as a deferred write, so it looks written right away: the reads wait for
+//! it. Only the latest commit can be lost on a crash.
+
+use crate::metrics::{DIEM_STORAGE_OTHER_TIMERS_SECONDS, DIEM_STORAGE_SYNCED_VERSION};
+use anyhow::{format_err, Result};
+use diem_infallible::Mutex;
+use diem_types::transaction::Version;
+use schemadb::{SchemaBatch, DB};
+use std::{
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+#[derive(Debug)]
+pub(crate) struct CommitWriter {
+    db: Arc<DB>,
+    /// The worker thread handle, created upon CommitWriter instance construction and joined upon
+    /// its destruction. It only becomes `None` after joined in `drop()`.
+    worker_thread: Option<JoinHandle<()>>,
+    channels: Mutex<Channels>,
+}
+
+#[derive(Debug)]
+struct Channels {
+    command_sender: Sender<Command>,
+    result_receiver: Receiver<Result<()>>,
+    /// The last version of the commit being written, whose result isn't received yet.
+    pending_version: Option<Version>,
+}
+
+impl CommitWriter {
+    /// Creates a worker thread that waits on a channel for write commands.
+    pub fn new(db: Arc<DB>) -> Self {
+        let (command_sender, command_receiver) = channel();
+        let (result_sender, result_receiver) = channel();
+
+        let worker_thread = std::thread::Builder::new()
+            .name("diemdb_commit_writer".into())
+            .spawn({
+                let db = Arc::clone(&db);
+                move || Worker::new(db, command_receiver, result_sender).work()
+            })
+            .expect("Creating commit writer thread should succeed.");
+
+        Self {
+            db,
+            worker_thread: Some(worker_thread),
+            channels: Mutex::new(Channels {
+                command_sender,
+                result_receiver,
+                pending_version: None,
+            }),
+        }
+    }
+
+    /// Waits for the write of the previous commit, then has the worker thread write the batch of
+    /// the commit ending at `last_version`.
+    ///
+    /// Fails if the write of the previous commit failed.
+    pub fn write_in_background(&self, batch: SchemaBatch, last_version: Version) -> Result<()> {
+        let mut channels = self.channels.lock();
+        channels.wait_for_pending()?;
+        self.db.defer_write();
+        channels
+            .command_sender
+            .send(Command::Write(batch))
+            .expect("Receiver should not destruct prematurely.");
+        channels.pending_version = Some(last_version);
+        Ok(())
+    }
+
+    /// Waits for the write of the latest commit, after which all the commits are durable.
+    pub fn wait_for_write(&self) -> Result<()> {
+        self.channels.lock().wait_for_pending()
+    }
+}
+
+impl Channels {
+    fn wait_for_pending(&mut self) -> Result<()> {
+        if let Some(version) = self.pending_version.take() {
+            self.result_receiver
+                .recv()
+                .expect("Sender should not destruct prematurely.")
+                .map_err(|e| {
+                    format_err!("Failed to write the commit of version {}: {}", version, e)
+                })?;
+            DIEM_STORAGE_SYNCED_VERSION.set(version as i64);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for CommitWriter {
+    fn drop(&mut self) {
+        // The pending write is done before quitting, the commands are served in order.
+        self.channels
+            .lock()
+            .command_sender
+            .send(Command::Quit)
+            .expect("Receiver should not destruct.");
+        self.worker_thread
+            .take()
+            .expect("Worker thread must exist.")
+            .join()
+            .expect("Worker thread should join peacefully.");
+    }
+}
+
+enum Command {
+    Quit,
+    Write(SchemaBatch),
+}
+
+struct Worker {
+    db: Arc<DB>,
+    command_receiver: Receiver<Command>,
+    result_sender: Sender<Result<()>>,
+}
+
+impl Worker {
+    fn new(
+        db: Arc<DB>,
+        command_receiver: Receiver<Command>,
+        result_sender: Sender<Result<()>>,
+    ) -> Self {
+        Self {
+            db,
+            command_receiver,
+            result_sender,
+        }
+    }
+
+    fn work(self) {
+        while let Ok(Command::Write(batch)) = self.command_receiver.recv() {
+            let res = {
+                let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
+                    .with_label_values(&["commit_write_in_background"])
+                    .start_timer();
+                self.db.write_deferred(batch)
+            };
+            // The CommitWriter might be dropping without waiting for the result.
+            let _ = self.result_sender.send(res);
+        }
+    }
+}
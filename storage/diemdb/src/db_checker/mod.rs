@@ -0,0 +1,541 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks of the internal consistency of a DiemDB instance, e.g. after an unclean shutdown or a
+//! disk error.
+//!
+//! Everything is checked against the latest ledger info, itself verified through the chain of the
+//! epoch ending ledger infos: the transaction accumulator, the transactions, the event
+//! accumulators and the state tree of the latest version. The check goes on after finding
+//! corrupt data, so that all the corrupt ranges are reported at once.
+
+#[cfg(test)]
+mod test;
+
+use crate::{
+    schema::{event::EventSchema, transaction::TransactionSchema},
+    DiemDB,
+};
+use anyhow::{ensure, format_err, Result};
+use diem_config::config::RocksdbConfig;
+use diem_crypto::{
+    hash::{CryptoHash, EventAccumulatorHasher, SPARSE_MERKLE_PLACEHOLDER_HASH},
+    HashValue,
+};
+use diem_jellyfish_merkle::{
+    iterator::JellyfishMerkleIterator, restore::JellyfishMerkleRestore, NodeBatch, TreeWriter,
+};
+use diem_logger::prelude::*;
+use diem_types::{
+    account_state_blob::AccountStateBlob,
+    contract_event::ContractEvent,
+    epoch_change::Verifier,
+    epoch_state::EpochState,
+    ledger_info::LedgerInfoWithSignatures,
+    proof::accumulator::InMemoryAccumulator,
+    transaction::{Transaction, TransactionInfo, Version},
+};
+use schemadb::ReadOptions;
+use std::{
+    cmp::{max, min},
+    fmt,
+    path::Path,
+    sync::Arc,
+};
+
+/// The kinds of data checked.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CheckedData {
+    LedgerInfos,
+    TransactionAccumulator,
+    Transactions,
+    TransactionInfos,
+    Events,
+    EventAccumulators,
+    StateTree,
+}
+
+impl CheckedData {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CheckedData::LedgerInfos => "ledger_infos",
+            CheckedData::TransactionAccumulator => "transaction_accumulator",
+            CheckedData::Transactions => "transactions",
+            CheckedData::TransactionInfos => "transaction_infos",
+            CheckedData::Events => "events",
+            CheckedData::EventAccumulators => "event_accumulators",
+            CheckedData::StateTree => "state_tree",
+        }
+    }
+}
+
+/// Consecutive versions whose data is found corrupt, with the first error found in them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CorruptRange {
+    pub data: CheckedData,
+    pub first_version: Version,
+    pub last_version: Version,
+    pub error: String,
+}
+
+impl fmt::Display for CorruptRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} corrupt in versions [{}, {}]: {}",
+            self.data.as_str(),
+            self.first_version,
+            self.last_version,
+            self.error,
+        )
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CheckReport {
+    /// The version of the latest ledger info, the last checked version.
+    pub ledger_version: Version,
+    /// The first checked version, the versions before it are pruned.
+    pub first_version: Version,
+    /// The first version whose events are checked. The events of the versions before it might be
+    /// pruned, they can't be told apart from no events.
+    pub first_event_version: Version,
+    pub corrupt_ranges: Vec<CorruptRange>,
+}
+
+impl CheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.corrupt_ranges.is_empty()
+    }
+
+    /// Records corrupt data in `[first_version, last_version]`, merging it into the previous
+    /// corrupt range if it's of the same data and they are adjacent.
+    fn record(
+        &mut self,
+        data: CheckedData,
+        first_version: Version,
+        last_version: Version,
+        error: impl fmt::Display,
+    ) {
+        warn!(
+            data = data.as_str(),
+            first_version = first_version,
+            last_version = last_version,
+            error = %error,
+            "Corrupt data found."
+        );
+        if let Some(range) = self.corrupt_ranges.last_mut() {
+            if range.data == data && range.last_version.checked_add(1) == Some(first_version) {
+                range.last_version = last_version;
+                return;
+            }
+        }
+        self.corrupt_ranges.push(CorruptRange {
+            data,
+            first_version,
+            last_version,
+            error: error.to_string(),
+        });
+    }
+}
+
+/// Only the root hash matters when checking the state tree, the restored nodes are thrown away.
+struct NodeDiscarder;
+
+impl TreeWriter<AccountStateBlob> for NodeDiscarder {
+    fn write_node_batch(&self, _node_batch: &NodeBatch<AccountStateBlob>) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub struct DbChecker {
+    db: DiemDB,
+    chunk_size: usize,
+}
+
+impl DbChecker {
+    /// Opens the DB read only, checking `chunk_size` versions or accounts at a time.
+    pub fn new<P: AsRef<Path> + Clone>(db_root_path: P, chunk_size: usize) -> Result<Self> {
+        ensure!(chunk_size > 0, "Chunk size must be positive.");
+        let db = DiemDB::open(
+            db_root_path,
+            true, /* read only */
+            None, /* no prune_window */
+            RocksdbConfig::default(),
+        )?;
+        Ok(Self { db, chunk_size })
+    }
+
+    /// Checks the DB up to the latest ledger info. Fails only if the latest ledger info can't be
+    /// read, any corrupt data found is in the report.
+    pub fn check(&self) -> Result<CheckReport> {
+        let ledger_info = self.db.ledger_store.get_latest_ledger_info()?;
+        let (first_version, first_event_version) = self.first_readable_versions()?;
+        let mut report = CheckReport {
+            ledger_version: ledger_info.ledger_info().version(),
+            first_version,
+            first_event_version,
+            corrupt_ranges: vec![],
+        };
+        info!(
+            ledger_version = report.ledger_version,
+            first_version = first_version,
+            first_event_version = first_event_version,
+            "Checking DB."
+        );
+
+        self.check_ledger_infos(&ledger_info, &mut report);
+        self.check_transactions(&ledger_info, &mut report);
+        self.check_state_tree(&mut report);
+
+        info!(
+            num_corrupt_ranges = report.corrupt_ranges.len(),
+            "DB check finished."
+        );
+        Ok(report)
+    }
+
+    /// The least versions with a transaction and with events.
+    fn first_readable_versions(&self) -> Result<(Version, Version)> {
+        // The archive has the transactions and the events before the ones in RocksDB.
+        if let Some(range) = self.db.archive.version_range() {
+            return Ok((range.start, range.start));
+        }
+
+        let mut iter = self
+            .db
+            .db
+            .iter::<TransactionSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        let first_txn_version = iter.next().transpose()?.map_or(0, |(version, _)| version);
+
+        let mut iter = self.db.db.iter::<EventSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        let first_event_version = iter
+            .next()
+            .transpose()?
+            .map_or(0, |((version, _index), _)| version);
+
+        Ok((
+            first_txn_version,
+            max(first_txn_version, first_event_version),
+        ))
+    }
+
+    /// Verifies the signatures of the epoch ending ledger infos one epoch after another, then the
+    /// ones of the latest ledger info.
+    fn check_ledger_infos(&self, ledger_info: &LedgerInfoWithSignatures, report: &mut CheckReport) {
+        let mut version = 0;
+        if let Err(e) = self.verify_ledger_infos(ledger_info, &mut version) {
+            report.record(CheckedData::LedgerInfos, version, version, e);
+        }
+    }
+
+    /// Sets `version` to the one of each ledger info before verifying it.
+    fn verify_ledger_infos(
+        &self,
+        ledger_info: &LedgerInfoWithSignatures,
+        version: &mut Version,
+    ) -> Result<()> {
+        let latest_epoch = ledger_info.ledger_info().epoch();
+        let mut epoch_state: Option<EpochState> = None;
+        for res in self
+            .db
+            .ledger_store
+            .get_epoch_ending_ledger_info_iter(0, latest_epoch)?
+        {
+            let li = res?;
+            *version = li.ledger_info().version();
+            // The genesis ledger info isn't signed.
+            if let Some(epoch_state) = &epoch_state {
+                epoch_state.verify(&li)?;
+            }
+            epoch_state = li.ledger_info().next_epoch_state().cloned();
+        }
+
+        *version = ledger_info.ledger_info().version();
+        match &epoch_state {
+            Some(epoch_state) => epoch_state.verify(ledger_info),
+            None => {
+                ensure!(
+                    latest_epoch == 0,
+                    "Epoch ending ledger infos before epoch {} are missing.",
+                    latest_epoch,
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks the transactions, the transaction infos and the events chunk by chunk against the
+    /// transaction accumulator, along with the state root hashes of each version.
+    fn check_transactions(&self, ledger_info: &LedgerInfoWithSignatures, report: &mut CheckReport) {
+        let ledger_version = report.ledger_version;
+        let expected_root_hash = ledger_info.ledger_info().transaction_accumulator_hash();
+        let res = self
+            .db
+            .ledger_store
+            .get_root_hash(ledger_version)
+            .and_then(|root_hash| {
+                ensure!(
+                    root_hash == expected_root_hash,
+                    "Root hash {} doesn't match the one of the ledger info {}.",
+                    root_hash,
+                    expected_root_hash,
+                );
+                Ok(())
+            });
+        if let Err(e) = res {
+            report.record(
+                CheckedData::TransactionAccumulator,
+                report.first_version,
+                ledger_version,
+                e,
+            );
+        }
+
+        let mut first_version = report.first_version;
+        while first_version <= ledger_version {
+            let last_version = min(
+                ledger_version,
+                first_version.saturating_add(self.chunk_size as u64 - 1),
+            );
+            self.check_transaction_chunk(first_version, last_version, expected_root_hash, report);
+            info!(
+                last_version = last_version,
+                ledger_version = ledger_version,
+                "Transactions checked."
+            );
+            first_version = match last_version.checked_add(1) {
+                Some(version) => version,
+                None => break,
+            };
+        }
+    }
+
+    fn check_transaction_chunk(
+        &self,
+        first_version: Version,
+        last_version: Version,
+        expected_root_hash: HashValue,
+        report: &mut CheckReport,
+    ) {
+        let ledger_version = report.ledger_version;
+        let num_versions = (last_version - first_version + 1) as usize;
+
+        let txns = self
+            .db
+            .transaction_store
+            .get_transaction_iter(first_version, num_versions)
+            .and_then(|iter| iter.collect::<Result<Vec<Transaction>>>());
+        let txn_infos = self
+            .db
+            .ledger_store
+            .get_transaction_info_iter(first_version, num_versions)
+            .and_then(|iter| iter.collect::<Result<Vec<TransactionInfo>>>());
+        let events = self
+            .db
+            .event_store
+            .get_events_by_version_iter(first_version, num_versions)
+            .and_then(|iter| iter.collect::<Result<Vec<Vec<ContractEvent>>>>());
+
+        let txn_infos = match txn_infos {
+            Ok(txn_infos) => txn_infos,
+            Err(e) => {
+                report.record(
+                    CheckedData::TransactionInfos,
+                    first_version,
+                    last_version,
+                    e,
+                );
+                return;
+            }
+        };
+        let txn_info_hashes: Vec<_> = txn_infos.iter().map(CryptoHash::hash).collect();
+        let res = self
+            .db
+            .ledger_store
+            .get_transaction_range_proof(Some(first_version), num_versions as u64, ledger_version)
+            .and_then(|proof| {
+                proof.verify(expected_root_hash, Some(first_version), &txn_info_hashes)
+            });
+        if let Err(e) = res {
+            report.record(
+                CheckedData::TransactionAccumulator,
+                first_version,
+                last_version,
+                e,
+            );
+        }
+
+        match txns {
+            Ok(txns) => {
+                for ((version, txn), txn_info) in (first_version..).zip(txns).zip(&txn_infos) {
+                    let txn_hash = txn.hash();
+                    if txn_hash != txn_info.transaction_hash() {
+                        report.record(
+                            CheckedData::Transactions,
+                            version,
+                            version,
+                            format_err!(
+                                "Transaction hash {} doesn't match the transaction info {}.",
+                                txn_hash,
+                                txn_info.transaction_hash(),
+                            ),
+                        );
+                    }
+                }
+            }
+            Err(e) => report.record(CheckedData::Transactions, first_version, last_version, e),
+        }
+
+        match events {
+            Ok(events) => {
+                for ((version, events), txn_info) in (first_version..).zip(events).zip(&txn_infos) {
+                    if version >= report.first_event_version {
+                        self.check_events(version, &events, txn_info, report);
+                    }
+                }
+            }
+            Err(e) => report.record(CheckedData::Events, first_version, last_version, e),
+        }
+
+        for (version, txn_info) in (first_version..).zip(&txn_infos) {
+            // The trees of the versions before a state snapshot or pruned are gone.
+            match self.db.state_store.get_root_hash_option(version) {
+                Ok(Some(root_hash)) if root_hash != txn_info.state_root_hash() => report.record(
+                    CheckedData::StateTree,
+                    version,
+                    version,
+                    format_err!(
+                        "State root hash {} doesn't match the transaction info {}.",
+                        root_hash,
+                        txn_info.state_root_hash(),
+                    ),
+                ),
+                Ok(_) => (),
+                Err(e) => report.record(CheckedData::StateTree, version, version, e),
+            }
+        }
+    }
+
+    /// Checks the events of a transaction and their stored accumulator against the event root
+    /// hash of the transaction info.
+    fn check_events(
+        &self,
+        version: Version,
+        events: &[ContractEvent],
+        txn_info: &TransactionInfo,
+        report: &mut CheckReport,
+    ) {
+        let expected_root_hash = txn_info.event_root_hash();
+        let event_hashes: Vec<_> = events.iter().map(CryptoHash::hash).collect();
+        let root_hash =
+            InMemoryAccumulator::<EventAccumulatorHasher>::from_leaves(&event_hashes).root_hash();
+        if root_hash != expected_root_hash {
+            report.record(
+                CheckedData::Events,
+                version,
+                version,
+                format_err!(
+                    "Event root hash {} doesn't match the transaction info {}.",
+                    root_hash,
+                    expected_root_hash,
+                ),
+            );
+        }
+
+        let res = self
+            .db
+            .event_store
+            .get_event_root_hash(version, events.len() as u64)
+            .and_then(|root_hash| {
+                ensure!(
+                    root_hash == expected_root_hash,
+                    "Stored event root hash {} doesn't match the transaction info {}.",
+                    root_hash,
+                    expected_root_hash,
+                );
+                Ok(())
+            });
+        if let Err(e) = res {
+            report.record(CheckedData::EventAccumulators, version, version, e);
+        }
+    }
+
+    /// Rebuilds the state tree of the latest version from all its accounts, chunk by chunk,
+    /// verifying each chunk against the state root hash of the latest transaction info.
+    ///
+    /// A corrupt chunk fails the following ones, so the check stops at the first one.
+    fn check_state_tree(&self, report: &mut CheckReport) {
+        let version = report.ledger_version;
+        if let Err(e) = self.check_state_tree_impl(version) {
+            report.record(CheckedData::StateTree, version, version, e);
+        }
+    }
+
+    fn check_state_tree_impl(&self, version: Version) -> Result<()> {
+        let expected_root_hash = self
+            .db
+            .ledger_store
+            .get_transaction_info(version)?
+            .state_root_hash();
+        let mut restore = JellyfishMerkleRestore::new_overwrite(
+            Arc::new(NodeDiscarder),
+            version,
+            expected_root_hash,
+        )?;
+        let mut iter = JellyfishMerkleIterator::new(
+            Arc::clone(&self.db.state_store),
+            version,
+            HashValue::zero(),
+        )?
+        .peekable();
+
+        if iter.peek().is_none() {
+            ensure!(
+                expected_root_hash == *SPARSE_MERKLE_PLACEHOLDER_HASH,
+                "No accounts found, while the state root hash is {}.",
+                expected_root_hash,
+            );
+            return Ok(());
+        }
+
+        let mut num_accounts = 0;
+        while iter.peek().is_some() {
+            let chunk = iter
+                .by_ref()
+                .take(self.chunk_size)
+                .collect::<Result<Vec<(HashValue, AccountStateBlob)>>>()?;
+            let first_key = chunk.first().expect("Chunk is not empty.").0;
+            let last_key = chunk.last().expect("Chunk is not empty.").0;
+            num_accounts += chunk.len();
+
+            let proof = self
+                .db
+                .state_store
+                .get_account_state_range_proof(last_key, version)?;
+            // Nothing is on the right of the last account.
+            if iter.peek().is_none() {
+                ensure!(
+                    proof
+                        .right_siblings()
+                        .iter()
+                        .all(|hash| *hash == *SPARSE_MERKLE_PLACEHOLDER_HASH),
+                    "Accounts are missing after {}.",
+                    last_key,
+                );
+            }
+            restore.add_chunk(chunk, proof).map_err(|e| {
+                format_err!(
+                    "Accounts from {} to {} are corrupt: {}",
+                    first_key,
+                    last_key,
+                    e
+                )
+            })?;
+            info!(num_accounts = num_accounts, "Accounts checked.");
+        }
+        restore.finish()
+    }
+}
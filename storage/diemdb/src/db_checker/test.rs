@@ -0,0 +1,67 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    db_checker::{CheckedData, CorruptRange, DbChecker},
+    schema::transaction::TransactionSchema,
+    test_helper::arb_blocks_to_commit,
+    DiemDB,
+};
+use diem_crypto::HashValue;
+use diem_temppath::TempPath;
+use diem_types::{
+    account_address::AccountAddress, block_metadata::BlockMetadata, transaction::Transaction,
+};
+use proptest::prelude::*;
+use storage_interface::DbWriter;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_check(input in arb_blocks_to_commit(), chunk_size in 1usize..4) {
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::new_for_test(&tmp_dir);
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        let ledger_version = cur_ver - 1;
+
+        let report = DbChecker::new(tmp_dir.path(), chunk_size)
+            .unwrap()
+            .check()
+            .unwrap();
+        prop_assert_eq!(report.first_version, 0);
+        prop_assert_eq!(report.ledger_version, ledger_version);
+        prop_assert!(report.is_ok(), "{:?}", report.corrupt_ranges);
+
+        // Overwrite the transaction of the last version.
+        let txn = Transaction::BlockMetadata(BlockMetadata::new(
+            HashValue::random(),
+            0, /* round */
+            0, /* timestamp_usecs */
+            vec![],
+            AccountAddress::random(),
+        ));
+        db.db.put::<TransactionSchema>(&ledger_version, &txn).unwrap();
+        drop(db);
+
+        let report = DbChecker::new(tmp_dir.path(), chunk_size)
+            .unwrap()
+            .check()
+            .unwrap();
+        prop_assert!(!report.is_ok());
+        prop_assert_eq!(report.corrupt_ranges.len(), 1);
+        let CorruptRange {
+            data,
+            first_version,
+            last_version,
+            ..
+        } = report.corrupt_ranges[0].clone();
+        prop_assert_eq!(data, CheckedData::Transactions);
+        prop_assert_eq!((first_version, last_version), (ledger_version, ledger_version));
+    }
+}
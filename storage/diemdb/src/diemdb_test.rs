@@ -175,6 +175,268 @@ fn test_sync_transactions_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoW
     }
 }
 
+fn test_open_secondary_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let tmp_dir_sec = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+
+    let num_batches_before_open = input.len() / 2;
+    let mut cur_ver = 0;
+    let mut db_sec = None;
+    for (batch_idx, (txns_to_commit, ledger_info_with_sigs)) in input.iter().enumerate() {
+        if batch_idx == num_batches_before_open {
+            db_sec = Some(
+                DiemDB::open_secondary(
+                    tmp_dir.path(),
+                    tmp_dir_sec.path(),
+                    RocksdbConfig::default(),
+                )
+                .unwrap(),
+            );
+        }
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let db_sec = db_sec.unwrap();
+    if num_batches_before_open > 0 {
+        assert_eq!(
+            db_sec.get_latest_ledger_info().unwrap(),
+            input[num_batches_before_open - 1].1,
+        );
+    }
+
+    db_sec.try_catch_up_with_primary().unwrap();
+    let (txns_to_commit, ledger_info_with_sigs) = input.last().unwrap();
+    assert_eq!(
+        db_sec.get_latest_ledger_info().unwrap(),
+        *ledger_info_with_sigs,
+    );
+    verify_committed_transactions(
+        &db_sec,
+        txns_to_commit,
+        cur_ver - txns_to_commit.len() as u64,
+        ledger_info_with_sigs,
+        true, /* is_latest */
+    );
+}
+
+fn test_pipelined_commit_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let rocksdb_config = RocksdbConfig {
+        pipelined_commit: true,
+        ..Default::default()
+    };
+    {
+        let db = DiemDB::open(&tmp_dir, false, None, rocksdb_config.clone()).unwrap();
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            // The commits are readable while being written.
+            verify_committed_transactions(
+                &db,
+                txns_to_commit,
+                cur_ver,
+                ledger_info_with_sigs,
+                true, /* is_latest */
+            );
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        db.wait_for_commit_sync().unwrap();
+    }
+
+    let db = DiemDB::open(&tmp_dir, false, None, rocksdb_config).unwrap();
+    let (txns_to_commit, ledger_info_with_sigs) = input.last().unwrap();
+    assert_eq!(db.get_latest_ledger_info().unwrap(), *ledger_info_with_sigs);
+    let num_txns: usize = input.iter().map(|(txns, _)| txns.len()).sum();
+    verify_committed_transactions(
+        &db,
+        txns_to_commit,
+        (num_txns - txns_to_commit.len()) as u64,
+        ledger_info_with_sigs,
+        true, /* is_latest */
+    );
+}
+
+fn test_save_epoch_checkpoints_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    let mut expected_checkpoints = vec![];
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+
+        let ledger_info = ledger_info_with_sigs.ledger_info();
+        let state_root_hash = db
+            .ledger_store
+            .get_transaction_info(ledger_info.version())
+            .unwrap()
+            .state_root_hash();
+        if !ledger_info.ends_epoch() {
+            assert!(EpochCheckpoint::new(ledger_info, state_root_hash).is_err());
+            continue;
+        }
+
+        // The checkpoint is saved with the ledger info ending the epoch.
+        let checkpoint = EpochCheckpoint::new(ledger_info, state_root_hash).unwrap();
+        checkpoint.verify(ledger_info).unwrap();
+        expected_checkpoints.push(checkpoint);
+    }
+
+    for checkpoint in &expected_checkpoints {
+        assert_eq!(
+            db.get_epoch_checkpoint(checkpoint.epoch).unwrap().as_ref(),
+            Some(checkpoint)
+        );
+    }
+    let latest_epoch = input.last().unwrap().1.ledger_info().next_block_epoch();
+    let actual_checkpoints = db
+        .ledger_store
+        .get_epoch_checkpoint_iter(0, latest_epoch)
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(actual_checkpoints, expected_checkpoints);
+}
+
+fn test_state_snapshot_sync_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let version = cur_ver - 1;
+    let (ledger_info, txn_info, frozen_subtrees) = db.get_bootstrap_ledger(version).unwrap();
+    assert_eq!(ledger_info, db.get_latest_ledger_info().unwrap());
+    let (epoch_endings, _more) = db
+        .get_epoch_ending_ledger_infos(0, ledger_info.ledger_info().epoch())
+        .unwrap();
+
+    // Copy the state over in small chunks, then the ledger on top of it.
+    let tmp_dir2 = TempPath::new();
+    let db2 = DiemDB::new_for_test(&tmp_dir2);
+    let mut receiver = db2
+        .get_state_snapshot_receiver(version, txn_info.state_root_hash())
+        .unwrap();
+    let mut last_key = None;
+    loop {
+        let chunk = db
+            .get_account_chunk_with_proof(version, last_key, 2)
+            .unwrap();
+        last_key = chunk.last_key();
+        let is_last_chunk = chunk.is_last_chunk;
+        receiver
+            .add_chunk(chunk.account_blobs, chunk.proof)
+            .unwrap();
+        if is_last_chunk {
+            break;
+        }
+    }
+    receiver.finish().unwrap();
+    db2.finalize_state_snapshot(&epoch_endings, &ledger_info, &txn_info, &frozen_subtrees)
+        .unwrap();
+
+    assert_eq!(
+        db2.get_startup_info().unwrap(),
+        db.get_startup_info().unwrap()
+    );
+    assert_eq!(
+        db2.get_latest_state_root().unwrap(),
+        (version, txn_info.state_root_hash())
+    );
+    // The checkpoint of the epoch the snapshot ends, if it ends one, is synced too.
+    let epoch = ledger_info.ledger_info().epoch();
+    assert_eq!(
+        db2.get_epoch_checkpoint(epoch).unwrap(),
+        db.get_epoch_checkpoint(epoch).unwrap()
+    );
+    // The snapshot isn't ahead of the DB anymore.
+    assert!(db2
+        .finalize_state_snapshot(&epoch_endings, &ledger_info, &txn_info, &frozen_subtrees)
+        .is_err());
+
+    // A snapshot truncated after its first chunk is neither saved nor finalized.
+    let chunk = db.get_account_chunk_with_proof(version, None, 1).unwrap();
+    if !chunk.is_last_chunk {
+        let tmp_dir3 = TempPath::new();
+        let db3 = DiemDB::new_for_test(&tmp_dir3);
+        let mut receiver = db3
+            .get_state_snapshot_receiver(version, txn_info.state_root_hash())
+            .unwrap();
+        receiver
+            .add_chunk(chunk.account_blobs, chunk.proof)
+            .unwrap();
+        assert!(receiver.finish().is_err());
+        assert!(db3
+            .finalize_state_snapshot(&epoch_endings, &ledger_info, &txn_info, &frozen_subtrees)
+            .is_err());
+    }
+}
+
+fn test_get_events_page_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+
+    let mut all_committed_txns = vec![];
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(
+            txns_to_commit,
+            all_committed_txns.len() as u64, /* first_version */
+            Some(ledger_info_with_sigs),
+        )
+        .unwrap();
+        all_committed_txns.extend_from_slice(txns_to_commit);
+    }
+    let ledger_version = all_committed_txns.len() as u64 - 1;
+
+    let read_all_pages = |event_key: &EventKey, order: Order| {
+        let mut events = vec![];
+        let mut cursor = None;
+        loop {
+            let page = db
+                .get_events_page(event_key, cursor, order, 2, ledger_version)
+                .unwrap();
+            if page.events.is_empty() {
+                break;
+            }
+            events.extend(page.events);
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        events
+    };
+
+    for (event_key, mut events) in group_events_by_event_key(0, &all_committed_txns) {
+        assert_eq!(read_all_pages(&event_key, Order::Ascending), events);
+        events.reverse();
+        assert_eq!(read_all_pages(&event_key, Order::Descending), events);
+
+        // A descending read from beyond the latest event starts from the latest one.
+        let page = db
+            .get_events_page(
+                &event_key,
+                Some(events[0].1.sequence_number() + 10),
+                Order::Descending,
+                1,
+                ledger_version,
+            )
+            .unwrap();
+        assert_eq!(page.events, events[..1].to_vec());
+    }
+}
+
 fn get_events_by_event_key(
     db: &DiemDB,
     ledger_info: &LedgerInfo,
@@ -530,6 +792,27 @@ fn verify_committed_transactions(
                 .unwrap();
         }
 
+        // Fetch and verify the same account states in a batch.
+        let (addrs, expected_blobs): (Vec<_>, Vec<_>) = txn_to_commit
+            .account_states()
+            .iter()
+            .map(|(addr, blob)| (*addr, Some(blob.clone())))
+            .unzip();
+        let account_states_with_proofs = db
+            .get_state_values_with_proofs(&addrs, cur_ver, ledger_version)
+            .unwrap();
+        assert_eq!(account_states_with_proofs.len(), addrs.len());
+        for ((addr, expected_blob), account_state_with_proof) in addrs
+            .iter()
+            .zip(expected_blobs)
+            .zip(account_states_with_proofs)
+        {
+            assert_eq!(account_state_with_proof.blob, expected_blob);
+            account_state_with_proof
+                .verify(ledger_info, cur_ver, *addr)
+                .unwrap();
+        }
+
         cur_ver += 1;
     }
 
@@ -557,6 +840,31 @@ proptest! {
     fn test_sync_transactions(input in arb_blocks_to_commit()) {
         test_sync_transactions_impl(input);
     }
+
+    #[test]
+    fn test_open_secondary(input in arb_blocks_to_commit()) {
+        test_open_secondary_impl(input);
+    }
+
+    #[test]
+    fn test_pipelined_commit(input in arb_blocks_to_commit()) {
+        test_pipelined_commit_impl(input);
+    }
+
+    #[test]
+    fn test_get_events_page(input in arb_blocks_to_commit()) {
+        test_get_events_page_impl(input);
+    }
+
+    #[test]
+    fn test_save_epoch_checkpoints(input in arb_blocks_to_commit()) {
+        test_save_epoch_checkpoints_impl(input);
+    }
+
+    #[test]
+    fn test_state_snapshot_sync(input in arb_blocks_to_commit()) {
+        test_state_snapshot_sync_impl(input);
+    }
 }
 
 #[test]
@@ -641,6 +949,22 @@ fn test_get_latest_tree_state() {
     );
 }
 
+#[test]
+fn test_open_with_unknown_column_family_config() {
+    let tmp_dir = TempPath::new();
+    let mut rocksdb_config = RocksdbConfig::default();
+    rocksdb_config
+        .column_families
+        .insert("transactions".to_string(), Default::default());
+    assert!(DiemDB::open(&tmp_dir, false, None, rocksdb_config.clone()).is_err());
+
+    rocksdb_config.column_families.clear();
+    rocksdb_config
+        .column_families
+        .insert("transaction".to_string(), Default::default());
+    assert!(DiemDB::open(&tmp_dir, false, None, rocksdb_config).is_ok());
+}
+
 fn put_transaction_info(db: &DiemDB, version: Version, txn_info: &TransactionInfo) {
     let mut cs = ChangeSet::new();
     db.ledger_store
@@ -7,6 +7,7 @@
 
 use super::DiemDB;
 use crate::{
+    archive::Archive,
     change_set::ChangeSet,
     errors::DiemDbError,
     ledger_counters::{LedgerCounter, LedgerCounterBumps},
@@ -18,7 +19,7 @@ use crate::{
 use accumulator::{HashReader, MerkleAccumulator};
 use anyhow::{ensure, format_err, Result};
 use diem_crypto::{
-    hash::{CryptoHash, EventAccumulatorHasher},
+    hash::{CryptoHash, EventAccumulatorHasher, ACCUMULATOR_PLACEHOLDER_HASH},
     HashValue,
 };
 use diem_types::{
@@ -32,19 +33,37 @@ use diem_types::{
 };
 use schemadb::{schema::ValueCodec, ReadOptions, SchemaIterator, DB};
 use std::{
+    cmp::min,
     convert::{TryFrom, TryInto},
     iter::Peekable,
     sync::Arc,
 };
+use storage_interface::Order;
 
 #[derive(Debug)]
 pub(crate) struct EventStore {
     db: Arc<DB>,
+    /// The events moved out of `db` once old enough, if archiving.
+    archive: Option<Arc<Archive>>,
 }
 
 impl EventStore {
     pub fn new(db: Arc<DB>) -> Self {
-        Self { db }
+        Self { db, archive: None }
+    }
+
+    /// Like `new`, but falls back on `archive` to read the events missing from `db`.
+    pub fn new_with_archive(db: Arc<DB>, archive: Arc<Archive>) -> Self {
+        Self {
+            db,
+            archive: Some(archive),
+        }
+    }
+
+    fn get_archived_events(&self, version: Version) -> Result<Option<Vec<ContractEvent>>> {
+        self.archive
+            .as_ref()
+            .map_or(Ok(None), |archive| archive.get_events(version))
     }
 
     /// Get all of the events given a transaction version.
@@ -62,6 +81,11 @@ impl EventStore {
             }
             events.push(event);
         }
+        if events.is_empty() {
+            if let Some(archived_events) = self.get_archived_events(version)? {
+                return Ok(archived_events);
+            }
+        }
 
         Ok(events)
     }
@@ -76,6 +100,7 @@ impl EventStore {
 
         Ok(EventsByVersionIter {
             inner: iter.peekable(),
+            archive: self.archive.as_deref(),
             expected_next_version: start_version,
             end_version: start_version
                 .checked_add(num_versions as u64)
@@ -83,13 +108,22 @@ impl EventStore {
         })
     }
 
-    fn get_event_by_version_and_index(
+    pub fn get_event_by_version_and_index(
         &self,
         version: Version,
         index: u64,
     ) -> Result<ContractEvent> {
-        self.db
-            .get::<EventSchema>(&(version, index))?
+        if let Some(event) = self.db.get::<EventSchema>(&(version, index))? {
+            return Ok(event);
+        }
+        self.get_archived_events(version)?
+            .and_then(|mut events| {
+                if (index as usize) < events.len() {
+                    Some(events.swap_remove(index as usize))
+                } else {
+                    None
+                }
+            })
             .ok_or_else(|| {
                 DiemDbError::NotFound(format!("Event {} of Txn {}", index, version)).into()
             })
@@ -109,7 +143,10 @@ impl EventStore {
         iter.seek_for_prev(&(version + 1))?;
         let num_events = match iter.next().transpose()? {
             Some(((ver, index), _)) if ver == version => (index + 1),
-            _ => unreachable!(), // since we've already got at least one event above
+            // Since we've already got at least one event above, they're archived.
+            _ => self
+                .get_archived_events(version)?
+                .map_or(0, |events| events.len() as u64),
         };
 
         // Get proof.
@@ -119,6 +156,15 @@ impl EventStore {
         Ok((event, proof))
     }
 
+    /// Gets the root hash of the accumulator of the `num_events` events of the transaction at
+    /// `version`, from the stored accumulator nodes.
+    pub fn get_event_root_hash(&self, version: Version, num_events: u64) -> Result<HashValue> {
+        if num_events == 0 {
+            return Ok(*ACCUMULATOR_PLACEHOLDER_HASH);
+        }
+        Accumulator::get_root_hash(&EventHashReader::new(self, version), num_events)
+    }
+
     fn get_txn_ver_by_seq_num(&self, event_key: &EventKey, seq_num: u64) -> Result<u64> {
         let (ver, _) = self
             .db
@@ -207,6 +253,71 @@ impl EventStore {
         Ok(result)
     }
 
+    /// Given `event_key`, returns at most `limit` events from `start_seq_num` on in `order`,
+    /// identified like in `lookup_events_by_key`. A descending read starts from the latest event if
+    /// `start_seq_num` is beyond it, and an ascending one from the first event kept if the ones
+    /// before are pruned. Result won't contain records with a transaction version >
+    /// `ledger_version`.
+    pub fn lookup_events_by_key_range(
+        &self,
+        event_key: &EventKey,
+        start_seq_num: u64,
+        order: Order,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<
+        Vec<(
+            u64,     // sequence number
+            Version, // transaction version it belongs to
+            u64,     // index among events for the same transaction
+        )>,
+    > {
+        let iter = match order {
+            Order::Ascending => {
+                let mut iter = self.db.iter::<EventByKeySchema>(ReadOptions::default())?;
+                iter.seek(&(*event_key, start_seq_num))?;
+                iter
+            }
+            Order::Descending => {
+                // Skips the events later than `ledger_version`, which have the largest sequence
+                // numbers.
+                let latest_seq_num =
+                    match self.get_latest_sequence_number(ledger_version, event_key)? {
+                        Some(seq_num) => seq_num,
+                        None => return Ok(Vec::new()),
+                    };
+                let mut iter = self
+                    .db
+                    .rev_iter::<EventByKeySchema>(ReadOptions::default())?;
+                iter.seek_for_prev(&(*event_key, min(start_seq_num, latest_seq_num)))?;
+                iter
+            }
+        };
+
+        let mut result: Vec<(u64, Version, u64)> = Vec::new();
+        for res in iter.take(limit as usize) {
+            let ((path, seq), (ver, idx)) = res?;
+            if path != *event_key || ver > ledger_version {
+                break;
+            }
+            if let Some((prev_seq, _, _)) = result.last() {
+                let expected_seq = match order {
+                    Order::Ascending => prev_seq + 1,
+                    Order::Descending => prev_seq - 1,
+                };
+                ensure!(
+                    seq == expected_seq,
+                    "DB corrupt: Sequence number not continuous, expected: {}, actual: {}.",
+                    expected_seq,
+                    seq
+                );
+            }
+            result.push((seq, ver, idx));
+        }
+
+        Ok(result)
+    }
+
     fn lookup_event_by_key(
         &self,
         event_key: &EventKey,
@@ -397,6 +508,7 @@ impl HashReader for EmptyReader {
 
 pub struct EventsByVersionIter<'a> {
     inner: Peekable<SchemaIterator<'a, EventSchema>>,
+    archive: Option<&'a Archive>,
     expected_next_version: Version,
     end_version: Version,
 }
@@ -407,11 +519,22 @@ impl<'a> EventsByVersionIter<'a> {
             return Ok(None);
         }
 
+        let archived_events = match self.archive {
+            Some(archive) => archive.get_events(self.expected_next_version)?,
+            None => None,
+        };
         let mut ret = Vec::new();
         while let Some(res) = self.inner.peek() {
             let ((version, _index), _event) = res
                 .as_ref()
                 .map_err(|e| format_err!("Hit error iterating events: {}", e))?;
+            // Skips the events read from the archive, if the iterator still sees them.
+            if *version < self.expected_next_version
+                || (*version == self.expected_next_version && archived_events.is_some())
+            {
+                self.inner.next();
+                continue;
+            }
             if *version != self.expected_next_version {
                 break;
             }
@@ -423,7 +546,7 @@ impl<'a> EventsByVersionIter<'a> {
             .expected_next_version
             .checked_add(1)
             .ok_or_else(|| format_err!("expected version overflowed."))?;
-        Ok(Some(ret))
+        Ok(Some(archived_events.unwrap_or(ret)))
     }
 }
 
@@ -92,6 +92,52 @@ proptest! {
         prop_assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_epoch_checkpoint_iter(
+        (ledger_infos_with_sigs, start_epoch, end_epoch) in arb_ledger_infos_with_sigs()
+            .prop_flat_map(|ledger_infos_with_sigs| {
+                let last_epoch = get_last_epoch(&ledger_infos_with_sigs);
+                (
+                    Just(ledger_infos_with_sigs),
+                    0..=last_epoch,
+                    0..=last_epoch + 1,
+                )
+            })
+    ) {
+        let tmp_dir = TempPath::new();
+        let db = set_up(&tmp_dir, &ledger_infos_with_sigs);
+
+        let checkpoints: Vec<_> = ledger_infos_with_sigs
+            .iter()
+            .filter_map(|li| EpochCheckpoint::new(li.ledger_info(), HashValue::random()).ok())
+            .collect();
+        let mut cs = ChangeSet::new();
+        for checkpoint in &checkpoints {
+            db.ledger_store.put_epoch_checkpoint(checkpoint, &mut cs).unwrap();
+        }
+        db.db.write_schemas(cs.batch).unwrap();
+
+        let actual = db
+            .ledger_store
+            .get_epoch_checkpoint_iter(start_epoch, end_epoch)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let expected: Vec<_> = checkpoints
+            .iter()
+            .filter(|checkpoint| start_epoch <= checkpoint.epoch && checkpoint.epoch < end_epoch)
+            .cloned()
+            .collect();
+        prop_assert_eq!(actual, expected);
+
+        for checkpoint in &checkpoints {
+            prop_assert_eq!(
+                db.ledger_store.get_epoch_checkpoint(checkpoint.epoch).unwrap().as_ref(),
+                Some(checkpoint)
+            );
+        }
+    }
+
     #[test]
     fn test_get_epoch(
         (ledger_infos_with_sigs, version) in arb_ledger_infos_with_sigs()
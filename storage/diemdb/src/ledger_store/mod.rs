@@ -8,8 +8,8 @@ use crate::{
     change_set::ChangeSet,
     errors::DiemDbError,
     schema::{
-        epoch_by_version::EpochByVersionSchema, ledger_info::LedgerInfoSchema,
-        transaction_accumulator::TransactionAccumulatorSchema,
+        epoch_by_version::EpochByVersionSchema, epoch_checkpoint::EpochCheckpointSchema,
+        ledger_info::LedgerInfoSchema, transaction_accumulator::TransactionAccumulatorSchema,
         transaction_info::TransactionInfoSchema,
     },
 };
@@ -21,6 +21,7 @@ use diem_crypto::{
     HashValue,
 };
 use diem_types::{
+    epoch_checkpoint::EpochCheckpoint,
     epoch_state::EpochState,
     ledger_info::LedgerInfoWithSignatures,
     proof::{
@@ -47,16 +48,8 @@ pub(crate) struct LedgerStore {
 impl LedgerStore {
     pub fn new(db: Arc<DB>) -> Self {
         // Upon restart, read the latest ledger info and signatures and cache them in memory.
-        let ledger_info = {
-            let mut iter = db
-                .iter::<LedgerInfoSchema>(ReadOptions::default())
-                .expect("Constructing iterator should work.");
-            iter.seek_to_last();
-            iter.next()
-                .transpose()
-                .expect("Reading latest ledger info from DB should work.")
-                .map(|kv| kv.1)
-        };
+        let ledger_info = Self::read_latest_ledger_info(&db)
+            .expect("Reading latest ledger info from DB should work.");
 
         Self {
             db,
@@ -64,6 +57,20 @@ impl LedgerStore {
         }
     }
 
+    fn read_latest_ledger_info(db: &DB) -> Result<Option<LedgerInfoWithSignatures>> {
+        let mut iter = db.iter::<LedgerInfoSchema>(ReadOptions::default())?;
+        iter.seek_to_last();
+        Ok(iter.next().transpose()?.map(|kv| kv.1))
+    }
+
+    /// Refreshes the cached latest ledger info from the DB, which a secondary instance needs after
+    /// catching up with the primary.
+    pub fn reload_latest_ledger_info(&self) -> Result<()> {
+        self.latest_ledger_info
+            .store(Arc::new(Self::read_latest_ledger_info(&self.db)?));
+        Ok(())
+    }
+
     pub fn get_epoch(&self, version: Version) -> Result<u64> {
         let mut iter = self
             .db
@@ -173,6 +180,27 @@ impl LedgerStore {
         Accumulator::get_frozen_subtree_hashes(self, num_transactions)
     }
 
+    /// Gets the latest LedgerInfo of the epoch of `version`, which must be at `version`, with the
+    /// TransactionInfo at `version` and the frozen subtree roots of the accumulator before it.
+    pub fn get_bootstrap_ledger(
+        &self,
+        version: Version,
+    ) -> Result<(LedgerInfoWithSignatures, TransactionInfo, Vec<HashValue>)> {
+        let epoch = self.get_epoch(version)?;
+        let ledger_info = self.get_latest_ledger_info_in_epoch(epoch)?;
+        ensure!(
+            ledger_info.ledger_info().version() == version,
+            "No LedgerInfo at version {}, the last one of epoch {} is at version {}.",
+            version,
+            epoch,
+            ledger_info.ledger_info().version(),
+        );
+        let txn_info = self.get_transaction_info(version)?;
+        let frozen_subtrees = self.get_frozen_subtree_hashes(version)?;
+
+        Ok((ledger_info, txn_info, frozen_subtrees))
+    }
+
     pub fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
         // Get the latest ledger info. Return None if not bootstrapped.
         let latest_ledger_info = match self.get_latest_ledger_info_option() {
@@ -269,6 +297,51 @@ impl LedgerStore {
         })
     }
 
+    /// Gets the ledger infos ending the epochs whose last version is from `start_version` to the
+    /// one before `end_version`.
+    pub fn get_epoch_ending_ledger_infos_in_versions(
+        &self,
+        start_version: Version,
+        end_version: Version,
+    ) -> Result<Vec<LedgerInfoWithSignatures>> {
+        let mut iter = self
+            .db
+            .iter::<EpochByVersionSchema>(ReadOptions::default())?;
+        iter.seek(&start_version)?;
+        let mut ledger_infos = Vec::new();
+        for res in iter {
+            let (version, epoch) = res?;
+            if version >= end_version {
+                break;
+            }
+            ledger_infos.push(self.db.get::<LedgerInfoSchema>(&epoch)?.ok_or_else(|| {
+                DiemDbError::NotFound(format!("LedgerInfo for epoch {}.", epoch))
+            })?);
+        }
+        Ok(ledger_infos)
+    }
+
+    /// Gets the checkpoint of `epoch`, if the epoch ended after the checkpoints were written.
+    pub fn get_epoch_checkpoint(&self, epoch: u64) -> Result<Option<EpochCheckpoint>> {
+        self.db.get::<EpochCheckpointSchema>(&epoch)
+    }
+
+    /// Gets an iterator that yields the checkpoints of the epochs from `start_epoch` to the one
+    /// before `end_epoch`, skipping the epochs without checkpoint.
+    pub fn get_epoch_checkpoint_iter(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<impl Iterator<Item = Result<EpochCheckpoint>> + '_> {
+        let mut iter = self
+            .db
+            .iter::<EpochCheckpointSchema>(ReadOptions::default())?;
+        iter.seek(&start_epoch)?;
+        Ok(iter
+            .take_while(move |res| res.as_ref().map_or(true, |(epoch, _)| *epoch < end_epoch))
+            .map(|res| res.map(|(_epoch, checkpoint)| checkpoint)))
+    }
+
     /// Get transaction info at `version` with proof towards root of ledger at `ledger_version`.
     pub fn get_transaction_info_with_proof(
         &self,
@@ -365,6 +438,16 @@ impl LedgerStore {
             .put::<LedgerInfoSchema>(&ledger_info.epoch(), ledger_info_with_sigs)
     }
 
+    /// Write `checkpoint` to `cs`.
+    pub fn put_epoch_checkpoint(
+        &self,
+        checkpoint: &EpochCheckpoint,
+        cs: &mut ChangeSet,
+    ) -> Result<()> {
+        cs.batch
+            .put::<EpochCheckpointSchema>(&checkpoint.epoch, checkpoint)
+    }
+
     pub fn get_root_hash(&self, version: Version) -> Result<HashValue> {
         Accumulator::get_root_hash(self, version + 1)
     }
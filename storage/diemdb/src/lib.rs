@@ -10,6 +10,8 @@
 //! It relays read/write operations on the physical storage via [`schemadb`] to the underlying
 //! Key-Value storage system, and implements diem data structures on top of it.
 
+#[cfg(any(test, feature = "db-checker"))]
+pub mod db_checker;
 #[cfg(any(feature = "diemsum"))]
 pub mod diemsum;
 // Used in this and other crates for testing.
@@ -21,7 +23,9 @@ pub mod errors;
 pub mod metrics;
 pub mod schema;
 
+mod archive;
 mod change_set;
+mod commit_writer;
 mod event_store;
 mod ledger_counters;
 mod ledger_store;
@@ -36,10 +40,13 @@ mod diemdb_test;
 
 #[cfg(feature = "fuzzing")]
 pub use diemdb_test::test_save_blocks_impl;
+pub use pruner::PruneWindows;
 
 use crate::{
+    archive::{Archive, Archiver},
     backup::{backup_handler::BackupHandler, restore_handler::RestoreHandler},
     change_set::{ChangeSet, SealedChangeSet},
+    commit_writer::CommitWriter,
     errors::DiemDbError,
     event_store::EventStore,
     ledger_counters::LedgerCounters,
@@ -50,15 +57,16 @@ use crate::{
         DIEM_STORAGE_NEXT_BLOCK_EPOCH, DIEM_STORAGE_OTHER_TIMERS_SECONDS,
         DIEM_STORAGE_ROCKSDB_PROPERTIES,
     },
-    pruner::Pruner,
-    schema::*,
+    pruner::{PrunedData, Pruner, PrunerProgress},
+    schema::{transaction_accumulator::TransactionAccumulatorSchema, *},
     state_store::StateStore,
     system_store::SystemStore,
     transaction_store::TransactionStore,
 };
 use anyhow::{ensure, format_err, Result};
-use diem_config::config::RocksdbConfig;
+use diem_config::config::{RocksdbCompactionStyle, RocksdbCompressionType, RocksdbConfig};
 use diem_crypto::hash::{CryptoHash, HashValue, SPARSE_MERKLE_PLACEHOLDER_HASH};
+use diem_jellyfish_merkle::iterator::JellyfishMerkleIterator;
 use diem_logger::prelude::*;
 use diem_types::{
     account_address::AccountAddress,
@@ -66,11 +74,12 @@ use diem_types::{
     account_state_blob::{AccountStateBlob, AccountStateWithProof},
     contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
     epoch_change::EpochChangeProof,
+    epoch_checkpoint::EpochCheckpoint,
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     proof::{
-        AccountStateProof, AccumulatorConsistencyProof, EventProof, SparseMerkleProof,
-        TransactionListProof,
+        definition::LeafCount, position::FrozenSubTreeIterator, AccountStateProof,
+        AccumulatorConsistencyProof, EventProof, SparseMerkleProof, TransactionListProof,
     },
     state_proof::StateProof,
     transaction::{
@@ -84,7 +93,11 @@ use move_core_types::{
     resolver::{ModuleResolver, ResourceResolver},
 };
 use once_cell::sync::Lazy;
-use schemadb::{ColumnFamilyName, Options, DB, DEFAULT_CF_NAME};
+use schemadb::{
+    migration::{SchemaVersionRegistry, SCHEMA_VERSION_CF_NAME},
+    BlockBasedOptions, Cache, ColumnFamilyName, DBCompactionStyle, DBCompressionType, Options, DB,
+    DEFAULT_CF_NAME,
+};
 use std::{
     collections::HashMap,
     convert::TryFrom,
@@ -94,10 +107,16 @@ use std::{
     thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
-use storage_interface::{DbReader, DbWriter, MoveDbReader, Order, StartupInfo, TreeState};
+use storage_interface::{
+    AccountStateChunkWithProof, DbReader, DbWriter, EventPage, MoveDbReader, Order, StartupInfo,
+    StateSnapshotReceiver, TreeState,
+};
 
 const MAX_LIMIT: u64 = 1000;
 
+/// The dir of the archive of the old transactions and events, next to the RocksDB dir.
+const ARCHIVE_DIR_NAME: &str = "archive";
+
 // TODO: Either implement an iteration API to allow a very old client to loop through a long history
 // or guarantee that there is always a recent enough waypoint and client knows to boot from there.
 const MAX_NUM_EPOCH_ENDING_LEDGER_INFO: usize = 100;
@@ -146,9 +165,56 @@ fn gen_rocksdb_options(config: &RocksdbConfig) -> Options {
     let mut db_opts = Options::default();
     db_opts.set_max_open_files(config.max_open_files);
     db_opts.set_max_total_wal_size(config.max_total_wal_size);
+    db_opts.set_wal_ttl_seconds(config.wal_ttl_seconds);
+    db_opts.set_wal_size_limit_mb(config.wal_size_limit_mb);
     db_opts
 }
 
+/// Options of each column family, the ones with a block cache sharing it.
+fn gen_cf_options(
+    config: &RocksdbConfig,
+    column_families: Vec<ColumnFamilyName>,
+) -> Result<Vec<(ColumnFamilyName, Options)>> {
+    // A misspelled column family would silently keep the default options
+    for cf_name in config.column_families.keys() {
+        ensure!(
+            column_families.contains(&cf_name.as_str()),
+            "Unknown column family {} in the RocksDB config, expected one of {:?}",
+            cf_name,
+            column_families,
+        );
+    }
+    let block_cache = config
+        .block_cache_size
+        .map(Cache::new_lru_cache)
+        .transpose()?;
+
+    Ok(column_families
+        .into_iter()
+        .map(|cf_name| {
+            let mut cf_opts = Options::default();
+            cf_opts.set_compression_type(match config.compression(cf_name) {
+                RocksdbCompressionType::None => DBCompressionType::None,
+                RocksdbCompressionType::Lz4 => DBCompressionType::Lz4,
+                RocksdbCompressionType::Lz4hc => DBCompressionType::Lz4hc,
+            });
+            cf_opts.set_compaction_style(match config.compaction_style(cf_name) {
+                RocksdbCompactionStyle::Level => DBCompactionStyle::Level,
+                RocksdbCompactionStyle::Universal => DBCompactionStyle::Universal,
+            });
+            if let Some(write_buffer_size) = config.write_buffer_size(cf_name) {
+                cf_opts.set_write_buffer_size(write_buffer_size);
+            }
+            if let Some(block_cache) = &block_cache {
+                let mut table_opts = BlockBasedOptions::default();
+                table_opts.set_block_cache(block_cache);
+                cf_opts.set_block_based_table_factory(&table_opts);
+            }
+            (cf_name, cf_opts)
+        })
+        .collect())
+}
+
 fn update_rocksdb_properties(db: &DB) -> Result<()> {
     let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
         .with_label_values(&["update_rocksdb_properties"])
@@ -217,6 +283,11 @@ pub struct DiemDB {
     system_store: SystemStore,
     rocksdb_property_reporter: RocksdbPropertyReporter,
     pruner: Option<Pruner>,
+    pruner_progress: Arc<PrunerProgress>,
+    /// Syncs the commits in the background if the commit is pipelined.
+    commit_writer: Option<CommitWriter>,
+    archive: Arc<Archive>,
+    archiver: Option<Archiver>,
 }
 
 impl DiemDB {
@@ -224,12 +295,14 @@ impl DiemDB {
         vec![
             /* LedgerInfo CF = */ DEFAULT_CF_NAME,
             EPOCH_BY_VERSION_CF_NAME,
+            EPOCH_CHECKPOINT_CF_NAME,
             EVENT_ACCUMULATOR_CF_NAME,
             EVENT_BY_KEY_CF_NAME,
             EVENT_BY_VERSION_CF_NAME,
             EVENT_CF_NAME,
             JELLYFISH_MERKLE_NODE_CF_NAME,
             LEDGER_COUNTERS_CF_NAME,
+            SCHEMA_VERSION_CF_NAME,
             STALE_NODE_INDEX_CF_NAME,
             TRANSACTION_CF_NAME,
             TRANSACTION_ACCUMULATOR_CF_NAME,
@@ -238,30 +311,121 @@ impl DiemDB {
         ]
     }
 
-    fn new_with_db(db: DB, prune_window: Option<u64>) -> Self {
+    /// The column families of `column_families` the DB at `path` has. A DB opened readonly or as
+    /// secondary can't create the column families added since it was written (e.g., the one of
+    /// the schema versions), so it's opened without them.
+    fn existing_column_families(
+        path: &Path,
+        rocksdb_opts: &Options,
+    ) -> Result<Vec<ColumnFamilyName>> {
+        let existing = DB::list_cf(rocksdb_opts, path)?;
+        Ok(Self::column_families()
+            .into_iter()
+            .filter(|cf_name| {
+                existing
+                    .iter()
+                    .any(|existing_name| existing_name == cf_name)
+            })
+            .collect())
+    }
+
+    /// The schema version of every column family, with the migrations of the data written by the
+    /// older versions. Bump the version of a column family and add the migration from the previous
+    /// one whenever the format of its keys or values changes.
+    fn schema_versions() -> SchemaVersionRegistry {
+        let mut registry = SchemaVersionRegistry::new();
+        for cf_name in Self::column_families() {
+            if cf_name != SCHEMA_VERSION_CF_NAME {
+                registry.register(cf_name, 0);
+            }
+        }
+        registry
+    }
+
+    fn new_with_db(
+        db: DB,
+        archive: Archive,
+        prune_windows: PruneWindows,
+        pipelined_commit: bool,
+    ) -> Self {
         let db = Arc::new(db);
+        let archive = Arc::new(archive);
+        let pruner_progress = Arc::new(PrunerProgress::default());
 
         DiemDB {
             db: Arc::clone(&db),
-            event_store: Arc::new(EventStore::new(Arc::clone(&db))),
+            event_store: Arc::new(EventStore::new_with_archive(
+                Arc::clone(&db),
+                Arc::clone(&archive),
+            )),
             ledger_store: Arc::new(LedgerStore::new(Arc::clone(&db))),
             state_store: Arc::new(StateStore::new(Arc::clone(&db))),
-            transaction_store: Arc::new(TransactionStore::new(Arc::clone(&db))),
+            transaction_store: Arc::new(TransactionStore::new_with_archive(
+                Arc::clone(&db),
+                Arc::clone(&archive),
+            )),
             system_store: SystemStore::new(Arc::clone(&db)),
             rocksdb_property_reporter: RocksdbPropertyReporter::new(Arc::clone(&db)),
-            pruner: prune_window.map(|n| Pruner::new(Arc::clone(&db), n)),
+            pruner: if prune_windows.is_pruning() {
+                Some(Pruner::new(
+                    Arc::clone(&db),
+                    prune_windows,
+                    Arc::clone(&pruner_progress),
+                ))
+            } else {
+                None
+            },
+            pruner_progress,
+            commit_writer: if pipelined_commit {
+                Some(CommitWriter::new(Arc::clone(&db)))
+            } else {
+                None
+            },
+            archiver: prune_windows.archive.map(|horizon| {
+                Archiver::new(
+                    Arc::clone(&db),
+                    Arc::clone(&archive),
+                    horizon,
+                    Archiver::VERSIONS_PER_CHUNK,
+                )
+            }),
+            archive,
         }
     }
 
+    /// Opens the DB, pruning only the state if `prune_window` is set.
     pub fn open<P: AsRef<Path> + Clone>(
         db_root_path: P,
         readonly: bool,
         prune_window: Option<u64>,
         rocksdb_config: RocksdbConfig,
     ) -> Result<Self> {
+        Self::open_with_prune_windows(
+            db_root_path,
+            readonly,
+            PruneWindows {
+                state: prune_window,
+                ..PruneWindows::default()
+            },
+            rocksdb_config,
+        )
+    }
+
+    /// Opens the DB, pruning the data of the versions out of their windows in the background.
+    pub fn open_with_prune_windows<P: AsRef<Path> + Clone>(
+        db_root_path: P,
+        readonly: bool,
+        prune_windows: PruneWindows,
+        rocksdb_config: RocksdbConfig,
+    ) -> Result<Self> {
+        ensure!(
+            !(prune_windows.is_pruning() || prune_windows.archive.is_some()) || !readonly,
+            "Do not set prune windows when opening readonly.",
+        );
         ensure!(
-            prune_window.is_none() || !readonly,
-            "Do not set prune_window when opening readonly.",
+            prune_windows.archive.is_none()
+                || (prune_windows.transactions.is_none() && prune_windows.events.is_none()),
+            "Do not prune the transactions or the events when archiving them.",
         );
 
         let path = db_root_path.as_ref().join("diemdb");
@@ -270,24 +434,33 @@ impl DiemDB {
         let mut rocksdb_opts = gen_rocksdb_options(&rocksdb_config);
 
         let db = if readonly {
-            DB::open_readonly(
+            let db = DB::open_readonly(
                 path.clone(),
                 "diemdb_ro",
-                Self::column_families(),
+                Self::existing_column_families(&path, &rocksdb_opts)?,
                 &rocksdb_opts,
-            )?
+            )?;
+            Self::schema_versions().check(&db)?;
+            db
         } else {
             rocksdb_opts.create_if_missing(true);
             rocksdb_opts.create_missing_column_families(true);
-            DB::open(
+            let db = DB::open_with_cf_opts(
                 path.clone(),
                 "diemdb",
-                Self::column_families(),
+                gen_cf_options(&rocksdb_config, Self::column_families())?,
                 &rocksdb_opts,
-            )?
+            )?;
+            Self::schema_versions().migrate(&db)?;
+            db
         };
 
-        let ret = Self::new_with_db(db, prune_window);
+        let ret = Self::new_with_db(
+            db,
+            Archive::open(db_root_path.as_ref().join(ARCHIVE_DIR_NAME))?,
+            prune_windows,
+            rocksdb_config.pipelined_commit && !readonly,
+        );
         info!(
             path = path,
             time_ms = %instant.elapsed().as_millis(),
@@ -296,7 +469,11 @@ impl DiemDB {
         Ok(ret)
     }
 
-    pub fn open_as_secondary<P: AsRef<Path> + Clone>(
+    /// Opens the DB as a RocksDB secondary instance, from another process than the one that has it
+    /// open (e.g. a node), to serve the heavy reads there. `secondary_path` keeps the info logs of
+    /// the instance. The instance sees the writes of the primary up to the opening, later ones are
+    /// caught up with by `try_catch_up_with_primary`.
+    pub fn open_secondary<P: AsRef<Path> + Clone>(
         db_root_path: P,
         secondary_path: P,
         mut rocksdb_config: RocksdbConfig,
@@ -307,16 +484,35 @@ impl DiemDB {
         rocksdb_config.max_open_files = -1;
         let rocksdb_opts = gen_rocksdb_options(&rocksdb_config);
 
-        Ok(Self::new_with_db(
-            DB::open_as_secondary(
-                primary_path,
-                secondary_path,
-                "diemdb_sec",
-                Self::column_families(),
-                &rocksdb_opts,
-            )?,
-            None, // prune_window
-        ))
+        let db = DB::open_as_secondary(
+            primary_path.clone(),
+            secondary_path,
+            "diemdb_sec",
+            Self::existing_column_families(&primary_path, &rocksdb_opts)?,
+            &rocksdb_opts,
+        )?;
+        Self::schema_versions().check(&db)?;
+
+        let ret = Self::new_with_db(
+            db,
+            Archive::open(db_root_path.as_ref().join(ARCHIVE_DIR_NAME))?,
+            PruneWindows::default(),
+            false, /* pipelined_commit */
+        );
+        info!(path = primary_path, "Opened DiemDB as secondary.");
+        Ok(ret)
+    }
+
+    /// Catches up a DB opened by `open_secondary` with the writes of the primary since the opening
+    /// or the last catch-up. The data of the versions the primary pruned meanwhile may be gone.
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
+            .with_label_values(&["try_catch_up_with_primary"])
+            .start_timer();
+        // The primary deletes the archived data from RocksDB after writing the archive.
+        self.archive.refresh()?;
+        self.db.try_catch_up_with_primary()?;
+        self.ledger_store.reload_latest_ledger_info()
     }
 
     /// This opens db in non-readonly mode, without the pruner.
@@ -433,9 +629,98 @@ impl DiemDB {
             Arc::clone(&self.transaction_store),
             Arc::clone(&self.state_store),
             Arc::clone(&self.event_store),
+            Arc::clone(&self.pruner_progress),
         )
     }
 
+    /// Confirms the frozen subtree roots of the transaction accumulator with `num_leaves` leaves,
+    /// from right to left, match those already in the DB, and saves the missing ones.
+    pub(crate) fn confirm_or_save_frozen_subtrees(
+        &self,
+        num_leaves: LeafCount,
+        frozen_subtrees: &[HashValue],
+    ) -> Result<()> {
+        let mut cs = ChangeSet::new();
+        let positions: Vec<_> = FrozenSubTreeIterator::new(num_leaves).collect();
+
+        ensure!(
+            positions.len() == frozen_subtrees.len(),
+            "Number of frozen subtree roots not expected. Expected: {}, actual: {}",
+            positions.len(),
+            frozen_subtrees.len(),
+        );
+
+        positions
+            .iter()
+            .zip(frozen_subtrees.iter().rev())
+            .map(|(p, h)| {
+                if let Some(_h) = self.db.get::<TransactionAccumulatorSchema>(p)? {
+                    ensure!(
+                        h == &_h,
+                        "Frozen subtree root does not match that already in DB. Provided: {}, in db: {}.",
+                        h,
+                        _h,
+                    );
+                } else {
+                    cs.batch.put::<TransactionAccumulatorSchema>(p, h)?;
+                }
+                Ok(())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.db.write_schemas(cs.batch)
+    }
+
+    /// Makes the state at the version of `ledger_info`, which must be in the DB already, the
+    /// latest state of the DB, given the TransactionInfo at that version and the frozen subtree
+    /// roots of the transaction accumulator before it, from left to right. Both are checked
+    /// against `ledger_info`, which is trusted. If `ledger_info` ends an epoch, its checkpoint is
+    /// saved too.
+    pub(crate) fn save_ledger_at_state(
+        &self,
+        ledger_info: &LedgerInfoWithSignatures,
+        txn_info: &TransactionInfo,
+        frozen_subtrees: &[HashValue],
+    ) -> Result<()> {
+        let version = ledger_info.ledger_info().version();
+        let state_root_hash = self.state_store.get_root_hash(version)?;
+        ensure!(
+            state_root_hash == txn_info.state_root_hash(),
+            "State root hash at version {} doesn't match. In DB: {}, in TransactionInfo: {}",
+            version,
+            state_root_hash,
+            txn_info.state_root_hash(),
+        );
+
+        let left_siblings: Vec<_> = frozen_subtrees.iter().rev().cloned().collect();
+        self.confirm_or_save_frozen_subtrees(version, &left_siblings)?;
+        let mut cs = ChangeSet::new();
+        let root_hash = self.ledger_store.put_transaction_infos(
+            version,
+            std::slice::from_ref(txn_info),
+            &mut cs,
+        )?;
+        ensure!(
+            root_hash == ledger_info.ledger_info().transaction_accumulator_hash(),
+            "Transaction accumulator root hash at version {} doesn't match. Computed: {}, \
+            in LedgerInfo: {}",
+            version,
+            root_hash,
+            ledger_info.ledger_info().transaction_accumulator_hash(),
+        );
+        self.ledger_store.put_ledger_info(ledger_info, &mut cs)?;
+        if ledger_info.ledger_info().ends_epoch() {
+            let checkpoint =
+                EpochCheckpoint::new(ledger_info.ledger_info(), txn_info.state_root_hash())?;
+            self.ledger_store
+                .put_epoch_checkpoint(&checkpoint, &mut cs)?;
+        }
+        self.db.write_schemas(cs.batch)?;
+        self.ledger_store
+            .set_latest_ledger_info(ledger_info.clone());
+
+        Ok(())
+    }
+
     // ================================== Private APIs ==================================
     fn get_events_with_proof_by_event_key(
         &self,
@@ -538,7 +823,7 @@ impl DiemDB {
         txns_to_commit: &[TransactionToCommit],
         first_version: u64,
         cs: &mut ChangeSet,
-    ) -> Result<HashValue> {
+    ) -> Result<(HashValue, Option<HashValue>)> {
         let last_version = first_version + txns_to_commit.len() as u64 - 1;
 
         // Account state updates. Gather account state root hashes
@@ -590,17 +875,29 @@ impl DiemDB {
         let new_root_hash =
             self.ledger_store
                 .put_transaction_infos(first_version, &txn_infos, cs)?;
+        let last_state_root_hash = txn_infos.last().map(TransactionInfo::state_root_hash);
 
-        Ok(new_root_hash)
+        Ok((new_root_hash, last_state_root_hash))
     }
 
     /// Write the whole schema batch including all data necessary to mutate the ledger
     /// state of some transaction by leveraging rocksdb atomicity support. Also committed are the
     /// LedgerCounters.
-    fn commit(&self, sealed_cs: SealedChangeSet) -> Result<()> {
-        self.db.write_schemas(sealed_cs.batch)?;
+    ///
+    /// If the commit is pipelined, the batch is written in the background once the write of the
+    /// previous commit is done, the reads waiting for it in the meantime.
+    fn commit(&self, sealed_cs: SealedChangeSet, last_version: Version) -> Result<()> {
+        match &self.commit_writer {
+            Some(commit_writer) => commit_writer.write_in_background(sealed_cs.batch, last_version),
+            None => self.db.write_schemas(sealed_cs.batch),
+        }
+    }
 
-        Ok(())
+    /// Waits for the commits to be durable, if the commit is pipelined.
+    pub fn wait_for_commit_sync(&self) -> Result<()> {
+        self.commit_writer
+            .as_ref()
+            .map_or(Ok(()), CommitWriter::wait_for_write)
     }
 
     fn wake_pruner(&self, latest_version: Version) {
@@ -608,6 +905,12 @@ impl DiemDB {
             pruner.wake(latest_version)
         }
     }
+
+    fn wake_archiver(&self, latest_version: Version) {
+        if let Some(archiver) = self.archiver.as_ref() {
+            archiver.wake(latest_version)
+        }
+    }
 }
 
 impl DbReader for DiemDB {
@@ -689,6 +992,35 @@ impl DbReader for DiemDB {
         })
     }
 
+    fn get_latest_account_transactions(
+        &self,
+        address: AccountAddress,
+        seq_num: Option<u64>,
+        limit: u64,
+        include_events: bool,
+        ledger_version: Version,
+    ) -> Result<AccountTransactionsWithProof> {
+        gauged_api("get_latest_account_transactions", || {
+            error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+            let txns_with_proofs = self
+                .transaction_store
+                .get_account_transaction_version_rev_iter(
+                    address,
+                    seq_num.unwrap_or_else(u64::max_value),
+                    limit,
+                    ledger_version,
+                )?
+                .map(|result| {
+                    let (_seq_num, txn_version) = result?;
+                    self.get_transaction_with_proof(txn_version, ledger_version, include_events)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            Ok(AccountTransactionsWithProof::new(txns_with_proofs))
+        })
+    }
+
     // ======================= State Synchronizer Internal APIs ===================================
     /// Gets a batch of transactions for the purpose of synchronizing state to another node.
     ///
@@ -779,6 +1111,56 @@ impl DbReader for DiemDB {
         })
     }
 
+    fn get_events_page(
+        &self,
+        event_key: &EventKey,
+        start: Option<u64>,
+        order: Order,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<EventPage> {
+        gauged_api("get_events_page", || {
+            ensure!(limit > 0, "limit should > 0, got {}", limit);
+            error_if_too_many_requested(limit, MAX_LIMIT)?;
+            let start_seq_num = start.unwrap_or(match order {
+                Order::Ascending => 0,
+                Order::Descending => u64::max_value(),
+            });
+
+            let event_indices = self.event_store.lookup_events_by_key_range(
+                event_key,
+                start_seq_num,
+                order,
+                limit,
+                ledger_version,
+            )?;
+            let next_cursor = match (order, event_indices.last()) {
+                (Order::Ascending, Some((seq, _, _))) => Some(seq + 1),
+                (Order::Ascending, None) => Some(start_seq_num),
+                (Order::Descending, Some((seq, _, _))) => seq.checked_sub(1),
+                (Order::Descending, None) => None,
+            };
+            let events = event_indices
+                .into_iter()
+                .map(|(seq, ver, idx)| {
+                    let event = self.event_store.get_event_by_version_and_index(ver, idx)?;
+                    ensure!(
+                        seq == event.sequence_number(),
+                        "Index broken, expected seq:{}, actual:{}",
+                        seq,
+                        event.sequence_number()
+                    );
+                    Ok((ver, event))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            Ok(EventPage {
+                events,
+                next_cursor,
+            })
+        })
+    }
+
     /// Gets ledger info at specified version and ensures it's an epoch ending.
     fn get_epoch_ending_ledger_info(&self, version: u64) -> Result<LedgerInfoWithSignatures> {
         gauged_api("get_epoch_ending_ledger_info", || {
@@ -786,6 +1168,58 @@ impl DbReader for DiemDB {
         })
     }
 
+    fn get_epoch_checkpoint(&self, epoch: u64) -> Result<Option<EpochCheckpoint>> {
+        gauged_api("get_epoch_checkpoint", || {
+            self.ledger_store.get_epoch_checkpoint(epoch)
+        })
+    }
+
+    fn get_account_chunk_with_proof(
+        &self,
+        version: Version,
+        last_key: Option<HashValue>,
+        chunk_size: usize,
+    ) -> Result<AccountStateChunkWithProof> {
+        gauged_api("get_account_chunk_with_proof", || {
+            ensure!(chunk_size > 0, "Chunk size must be positive.");
+            let _pin = self.pruner_progress.pin(version, &[PrunedData::State])?;
+
+            // Reads one more account to tell whether the chunk is the last one.
+            let mut account_blobs = JellyfishMerkleIterator::new(
+                Arc::clone(&self.state_store),
+                version,
+                last_key.unwrap_or_else(HashValue::zero),
+            )?
+            .filter(|res| !matches!(res, Ok((key, _blob)) if Some(*key) == last_key))
+            .take(chunk_size + 1)
+            .collect::<Result<Vec<_>>>()?;
+            let is_last_chunk = account_blobs.len() <= chunk_size;
+            account_blobs.truncate(chunk_size);
+
+            let (rightmost_key, _blob) = account_blobs.last().ok_or_else(|| {
+                format_err!("No account after {:?} at version {}.", last_key, version)
+            })?;
+            let proof = self
+                .state_store
+                .get_account_state_range_proof(*rightmost_key, version)?;
+
+            Ok(AccountStateChunkWithProof {
+                account_blobs,
+                proof,
+                is_last_chunk,
+            })
+        })
+    }
+
+    fn get_bootstrap_ledger(
+        &self,
+        version: Version,
+    ) -> Result<(LedgerInfoWithSignatures, TransactionInfo, Vec<HashValue>)> {
+        gauged_api("get_bootstrap_ledger", || {
+            self.ledger_store.get_bootstrap_ledger(version)
+        })
+    }
+
     fn get_state_proof_with_ledger_info(
         &self,
         known_version: u64,
@@ -882,6 +1316,49 @@ impl DbReader for DiemDB {
         })
     }
 
+    fn get_state_values_with_proofs(
+        &self,
+        addresses: &[AccountAddress],
+        version: Version,
+        ledger_version: Version,
+    ) -> Result<Vec<AccountStateWithProof>> {
+        gauged_api("get_state_values_with_proofs", || {
+            error_if_too_many_requested(addresses.len() as u64, MAX_LIMIT)?;
+            ensure!(
+                version <= ledger_version,
+                "The queried version {} should be equal to or older than ledger version {}.",
+                version,
+                ledger_version
+            );
+            {
+                let latest_version = self.get_latest_version()?;
+                ensure!(
+                    ledger_version <= latest_version,
+                    "ledger_version specified {} is greater than committed version {}.",
+                    ledger_version,
+                    latest_version
+                );
+            }
+
+            let txn_info_with_proof = self
+                .ledger_store
+                .get_transaction_info_with_proof(version, ledger_version)?;
+            let account_states_with_proofs = self
+                .state_store
+                .get_account_states_with_proofs_by_version(addresses, version)?
+                .into_iter()
+                .map(|(account_state_blob, sparse_merkle_proof)| {
+                    AccountStateWithProof::new(
+                        version,
+                        account_state_blob,
+                        AccountStateProof::new(txn_info_with_proof.clone(), sparse_merkle_proof),
+                    )
+                })
+                .collect();
+            Ok(account_states_with_proofs)
+        })
+    }
+
     fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
         gauged_api("get_startup_info", || self.ledger_store.get_startup_info())
     }
@@ -1034,6 +1511,12 @@ impl DbReader for DiemDB {
         })
     }
 
+    fn get_first_txn_version(&self) -> Result<Option<Version>> {
+        gauged_api("get_first_txn_version", || {
+            self.transaction_store.get_first_txn_version()
+        })
+    }
+
     fn get_latest_transaction_info_option(&self) -> Result<Option<(Version, TransactionInfo)>> {
         gauged_api("get_latest_transaction_info_option", || {
             self.ledger_store.get_latest_transaction_info_option()
@@ -1127,7 +1610,7 @@ impl DbWriter for DiemDB {
             // Gather db mutations to `batch`.
             let mut cs = ChangeSet::new();
 
-            let new_root_hash =
+            let (new_root_hash, last_state_root_hash) =
                 self.save_transactions_impl(txns_to_commit, first_version, &mut cs)?;
 
             // If expected ledger info is provided, verify result root hash and save the ledger info.
@@ -1141,6 +1624,20 @@ impl DbWriter for DiemDB {
                 );
 
                 self.ledger_store.put_ledger_info(x, &mut cs)?;
+
+                // The checkpoint is committed along with the ledger info ending the epoch.
+                if x.ledger_info().ends_epoch() {
+                    let state_root_hash = match last_state_root_hash {
+                        Some(state_root_hash) => state_root_hash,
+                        None => self
+                            .ledger_store
+                            .get_transaction_info(x.ledger_info().version())?
+                            .state_root_hash(),
+                    };
+                    let checkpoint = EpochCheckpoint::new(x.ledger_info(), state_root_hash)?;
+                    self.ledger_store
+                        .put_epoch_checkpoint(&checkpoint, &mut cs)?;
+                }
             }
 
             // Persist.
@@ -1149,7 +1646,8 @@ impl DbWriter for DiemDB {
                 let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
                     .with_label_values(&["save_transactions_commit"])
                     .start_timer();
-                self.commit(sealed_cs)?;
+                // `ledger_info_with_sigs` is at `first_version - 1` if there are no transactions.
+                self.commit(sealed_cs, first_version + num_txns - 1)?;
             }
 
             // Once everything is successfully persisted, update the latest in-memory ledger info.
@@ -1171,11 +1669,64 @@ impl DbWriter for DiemDB {
                     .bump_op_counters();
 
                 self.wake_pruner(last_version);
+                self.wake_archiver(last_version);
             }
 
             Ok(())
         })
     }
+
+    fn get_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>> {
+        gauged_api("get_state_snapshot_receiver", || {
+            Ok(Box::new(
+                self.state_store
+                    .get_snapshot_receiver(version, expected_root_hash)?,
+            ) as Box<dyn StateSnapshotReceiver>)
+        })
+    }
+
+    /// Nothing is written for the transactions skipped before the snapshot, so they can't be read
+    /// nor served to other nodes afterwards.
+    fn finalize_state_snapshot(
+        &self,
+        epoch_endings: &[LedgerInfoWithSignatures],
+        ledger_info: &LedgerInfoWithSignatures,
+        txn_info: &TransactionInfo,
+        frozen_subtrees: &[HashValue],
+    ) -> Result<()> {
+        gauged_api("finalize_state_snapshot", || {
+            let version = ledger_info.ledger_info().version();
+            if let Some((latest_version, _txn_info)) = self.get_latest_transaction_info_option()? {
+                ensure!(
+                    latest_version < version,
+                    "State snapshot at version {} is not ahead of the latest transaction at \
+                    version {}.",
+                    version,
+                    latest_version,
+                );
+            }
+            let root_hash = self.state_store.get_root_hash_option(version)?;
+            ensure!(
+                root_hash == Some(txn_info.state_root_hash()),
+                "State snapshot at version {} doesn't have the state root hash {} of its \
+                transaction, found {:?}.",
+                version,
+                txn_info.state_root_hash(),
+                root_hash,
+            );
+
+            let mut cs = ChangeSet::new();
+            for li in epoch_endings {
+                self.ledger_store.put_ledger_info(li, &mut cs)?;
+            }
+            self.db.write_schemas(cs.batch)?;
+            self.save_ledger_at_state(ledger_info, txn_info, frozen_subtrees)
+        })
+    }
 }
 
 // Convert requested range and order to a range in ascending order.
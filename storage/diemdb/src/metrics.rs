@@ -43,6 +43,22 @@ pub static DIEM_STORAGE_LEDGER_VERSION: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static DIEM_STORAGE_ARCHIVED_VERSION: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_storage_archived_version",
+        "The version following the last one with its transactions and events archived."
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_SYNCED_VERSION: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_storage_synced_version",
+        "Last version of the commits known durable, with the pipelined commit on."
+    )
+    .unwrap()
+});
+
 pub static DIEM_STORAGE_NEXT_BLOCK_EPOCH: Lazy<IntGauge> = Lazy::new(|| {
     register_int_gauge!(
         "diem_storage_next_block_epoch",
@@ -63,6 +79,30 @@ pub static DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION: Lazy<IntGauge> = La
     .unwrap()
 });
 
+pub static DIEM_STORAGE_PRUNER_LEAST_READABLE_VERSION: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        // metric name
+        "diem_storage_pruner_least_readable_version",
+        // metric description
+        "Diem storage pruner least readable version, by pruned data",
+        // metric labels (dimensions)
+        &["data"]
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_PRUNER_TARGET_VERSION: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        // metric name
+        "diem_storage_pruner_target_version",
+        // metric description
+        "Diem storage pruner target least readable version, by pruned data",
+        // metric labels (dimensions)
+        &["data"]
+    )
+    .unwrap()
+});
+
 pub static DIEM_STORAGE_API_LATENCY_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         // metric name
@@ -0,0 +1,122 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Pruning of the transactions and the events of old versions.
+//!
+//! The transaction infos and the transaction accumulator are kept: they're small and the proofs of
+//! the later transactions are built from them.
+
+use crate::{
+    metrics::DIEM_STORAGE_OTHER_TIMERS_SECONDS,
+    schema::{
+        event::EventSchema, event_accumulator::EventAccumulatorSchema,
+        event_by_key::EventByKeySchema, event_by_version::EventByVersionSchema,
+        transaction::TransactionSchema, transaction_by_account::TransactionByAccountSchema,
+    },
+};
+use anyhow::Result;
+use diem_types::{
+    proof::position::Position,
+    transaction::{Transaction, Version},
+};
+use schemadb::{ReadOptions, SchemaBatch, DB};
+use std::cmp::min;
+
+/// Exclusive end of the versions pruned by the next batch.
+fn batch_end(
+    least_readable_version: Version,
+    target_least_readable_version: Version,
+    max_versions: usize,
+) -> Version {
+    min(
+        target_least_readable_version,
+        least_readable_version.saturating_add(max_versions as u64),
+    )
+}
+
+/// Deletes the transactions of at most `max_versions` versions from `least_readable_version`
+/// towards `target_least_readable_version`, along with their index by account.
+///
+/// Returns the new least readable version.
+pub fn prune_transactions(
+    db: &DB,
+    least_readable_version: Version,
+    target_least_readable_version: Version,
+    max_versions: usize,
+) -> Result<Version> {
+    let end = batch_end(
+        least_readable_version,
+        target_least_readable_version,
+        max_versions,
+    );
+    if end <= least_readable_version {
+        return Ok(least_readable_version);
+    }
+
+    let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
+        .with_label_values(&["pruner_commit_transactions"])
+        .start_timer();
+    let mut batch = SchemaBatch::new();
+    let mut iter = db.iter::<TransactionSchema>(ReadOptions::default())?;
+    iter.seek(&least_readable_version)?;
+    for res in iter {
+        let (version, txn) = res?;
+        if version >= end {
+            break;
+        }
+        if let Transaction::UserTransaction(txn) = txn {
+            batch.delete::<TransactionByAccountSchema>(&(txn.sender(), txn.sequence_number()))?;
+        }
+        batch.delete::<TransactionSchema>(&version)?;
+    }
+    db.write_schemas(batch)?;
+    Ok(end)
+}
+
+/// Deletes the events of at most `max_versions` versions from `least_readable_version` towards
+/// `target_least_readable_version`, along with their indexes and accumulators.
+///
+/// Returns the new least readable version.
+pub fn prune_events(
+    db: &DB,
+    least_readable_version: Version,
+    target_least_readable_version: Version,
+    max_versions: usize,
+) -> Result<Version> {
+    let end = batch_end(
+        least_readable_version,
+        target_least_readable_version,
+        max_versions,
+    );
+    if end <= least_readable_version {
+        return Ok(least_readable_version);
+    }
+
+    let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
+        .with_label_values(&["pruner_commit_events"])
+        .start_timer();
+    let mut batch = SchemaBatch::new();
+    let mut iter = db.iter::<EventSchema>(ReadOptions::default())?;
+    iter.seek(&least_readable_version)?;
+    for res in iter {
+        let ((version, index), event) = res?;
+        if version >= end {
+            break;
+        }
+        batch.delete::<EventByKeySchema>(&(*event.key(), event.sequence_number()))?;
+        batch.delete::<EventByVersionSchema>(&(*event.key(), version, event.sequence_number()))?;
+        batch.delete::<EventSchema>(&(version, index))?;
+    }
+
+    let mut iter = db.iter::<EventAccumulatorSchema>(ReadOptions::default())?;
+    iter.seek(&(least_readable_version, Position::from_inorder_index(0)))?;
+    for res in iter {
+        let ((version, position), _hash) = res?;
+        if version >= end {
+            break;
+        }
+        batch.delete::<EventAccumulatorSchema>(&(version, position))?;
+    }
+    db.write_schemas(batch)?;
+    Ok(end)
+}
@@ -3,23 +3,33 @@
 
 //! This module provides `Pruner` which manages a thread pruning old data in the background and is
 //! meant to be triggered by other threads as they commit new data to the DB.
+//!
+//! The state (stale nodes of the Jellyfish Merkle tree), the transactions and the events are
+//! pruned independently, each one keeping its own window of historical versions.
+
+mod ledger;
+
+pub use ledger::{prune_events, prune_transactions};
 
 use crate::{
     metrics::{
         DIEM_STORAGE_OTHER_TIMERS_SECONDS, DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION,
+        DIEM_STORAGE_PRUNER_LEAST_READABLE_VERSION, DIEM_STORAGE_PRUNER_TARGET_VERSION,
         DIEM_STORAGE_PRUNE_WINDOW,
     },
     schema::{
-        jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
+        event::EventSchema, jellyfish_merkle_node::JellyfishMerkleNodeSchema,
+        stale_node_index::StaleNodeIndexSchema, transaction::TransactionSchema,
     },
 };
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use diem_infallible::Mutex;
 use diem_jellyfish_merkle::StaleNodeIndex;
 use diem_logger::prelude::*;
 use diem_types::transaction::Version;
 use schemadb::{ReadOptions, SchemaBatch, SchemaIterator, DB};
 use std::{
+    collections::BTreeMap,
     iter::Peekable,
     sync::{
         atomic::{AtomicU64, Ordering},
@@ -30,6 +40,147 @@ use std::{
     time::{Duration, Instant},
 };
 
+/// How many historical versions of each kind of data to keep readable, other than the latest
+/// version. For example, a window being 0 means keep only the latest version. None disables the
+/// pruning of the data.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PruneWindows {
+    /// Window of the state, i.e. of the stale nodes of the Jellyfish Merkle tree.
+    pub state: Option<u64>,
+    /// Window of the transactions, along with their index by account.
+    pub transactions: Option<u64>,
+    /// Window of the events, along with their indexes and accumulators.
+    pub events: Option<u64>,
+    /// Window of the transactions and the events kept in RocksDB, the older ones being moved into
+    /// the archive where they're still readable, though slower. It's not a pruning window, the
+    /// indexes and the accumulators stay in RocksDB. None disables the archiving.
+    pub archive: Option<u64>,
+}
+
+impl PruneWindows {
+    fn get(&self, data: PrunedData) -> Option<u64> {
+        match data {
+            PrunedData::State => self.state,
+            PrunedData::Transactions => self.transactions,
+            PrunedData::Events => self.events,
+        }
+    }
+
+    /// Whether any data is pruned.
+    pub fn is_pruning(&self) -> bool {
+        PrunedData::ALL.iter().any(|data| self.get(*data).is_some())
+    }
+
+    /// The least readable version of the data once `latest_version` is committed, if it's pruned.
+    fn target_least_readable_version(
+        &self,
+        data: PrunedData,
+        latest_version: Version,
+    ) -> Option<Version> {
+        self.get(data)
+            .map(|window| latest_version.saturating_sub(window))
+    }
+}
+
+/// The kinds of data pruned independently.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum PrunedData {
+    State,
+    Transactions,
+    Events,
+}
+
+impl PrunedData {
+    pub const ALL: [PrunedData; 3] = [
+        PrunedData::State,
+        PrunedData::Transactions,
+        PrunedData::Events,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PrunedData::State => "state",
+            PrunedData::Transactions => "transactions",
+            PrunedData::Events => "events",
+        }
+    }
+}
+
+/// The pruning progress, shared between the worker thread and the readers which can't have the
+/// data they read disappear midway, e.g. backups.
+///
+/// A reader pins the least version it reads, which stays readable along with the later versions
+/// until the pin is dropped: the worker never prunes past the least pinned version.
+#[derive(Debug, Default)]
+pub(crate) struct PrunerProgress {
+    /// For each kind of data, all versions before this one can no longer be accessed.
+    least_readable_versions: [AtomicU64; 3],
+    /// Pinned versions with the number of pins on them. The worker holds the lock while pruning a
+    /// batch, so a version can't be pinned while it's being pruned.
+    pins: Mutex<BTreeMap<Version, usize>>,
+}
+
+impl PrunerProgress {
+    pub fn least_readable_version(&self, data: PrunedData) -> Version {
+        self.least_readable_versions[data as usize].load(Ordering::Relaxed)
+    }
+
+    fn record(&self, data: PrunedData, least_readable_version: Version) {
+        self.least_readable_versions[data as usize]
+            .store(least_readable_version, Ordering::Relaxed);
+        DIEM_STORAGE_PRUNER_LEAST_READABLE_VERSION
+            .with_label_values(&[data.as_str()])
+            .set(least_readable_version as i64);
+        if data == PrunedData::State {
+            DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION.set(least_readable_version as i64);
+        }
+    }
+
+    /// Keeps `version` and the later versions readable until the returned pin is dropped.
+    /// Fails if any of `data` is already pruned at `version`.
+    pub fn pin(self: &Arc<Self>, version: Version, data: &[PrunedData]) -> Result<VersionPin> {
+        let mut pins = self.pins.lock();
+        for data in data {
+            let least_readable_version = self.least_readable_version(*data);
+            ensure!(
+                version >= least_readable_version,
+                "The {} of version {} are pruned, the least readable version is {}.",
+                data.as_str(),
+                version,
+                least_readable_version,
+            );
+        }
+        *pins.entry(version).or_default() += 1;
+        Ok(VersionPin {
+            progress: Arc::clone(self),
+            version,
+        })
+    }
+
+    fn least_pinned_version(pins: &BTreeMap<Version, usize>) -> Option<Version> {
+        pins.keys().next().copied()
+    }
+}
+
+/// Keeps a version readable while it's held, see `PrunerProgress::pin()`.
+#[derive(Debug)]
+pub(crate) struct VersionPin {
+    progress: Arc<PrunerProgress>,
+    version: Version,
+}
+
+impl Drop for VersionPin {
+    fn drop(&mut self) {
+        let mut pins = self.progress.pins.lock();
+        if let Some(count) = pins.get_mut(&self.version) {
+            *count -= 1;
+            if *count == 0 {
+                pins.remove(&self.version);
+            }
+        }
+    }
+}
+
 /// The `Pruner` is meant to be part of a `DiemDB` instance and runs in the background to prune old
 /// data.
 ///
@@ -37,76 +188,78 @@ use std::{
 /// quits the worker thread eagerly without waiting for all pending work to be done.
 #[derive(Debug)]
 pub(crate) struct Pruner {
-    /// Other than the latest version, how many historical versions to keep being readable. For
-    /// example, this being 0 means keep only the latest version.
-    historical_versions_to_keep: u64,
+    /// Other than the latest version, how many historical versions of each kind of data to keep
+    /// being readable.
+    prune_windows: PruneWindows,
     /// The worker thread handle, created upon Pruner instance construction and joined upon its
     /// destruction. It only becomes `None` after joined in `drop()`.
     worker_thread: Option<JoinHandle<()>>,
     /// The sender side of the channel talking to the worker thread.
     command_sender: Mutex<Sender<Command>>,
-    /// (For tests) A way for the worker thread to inform the `Pruner` the pruning progress. If it
-    /// sets this atomic value to `V`, all versions before `V` can no longer be accessed.
-    #[allow(dead_code)]
-    worker_progress: Arc<AtomicU64>,
+    /// The worker thread informs the `Pruner` and the readers of the pruning progress through it.
+    progress: Arc<PrunerProgress>,
 }
 
 impl Pruner {
     /// Creates a worker thread that waits on a channel for pruning commands.
-    pub fn new(db: Arc<DB>, historical_versions_to_keep: u64) -> Self {
+    pub fn new(db: Arc<DB>, prune_windows: PruneWindows, progress: Arc<PrunerProgress>) -> Self {
         let (command_sender, command_receiver) = channel();
 
-        let worker_progress = Arc::new(AtomicU64::new(0));
-        let worker_progress_clone = Arc::clone(&worker_progress);
-
-        DIEM_STORAGE_PRUNE_WINDOW.set(historical_versions_to_keep as i64);
+        let worker_progress = Arc::clone(&progress);
+        if let Some(window) = prune_windows.state {
+            DIEM_STORAGE_PRUNE_WINDOW.set(window as i64);
+        }
         let worker_thread = std::thread::Builder::new()
             .name("diemdb_pruner".into())
-            .spawn(move || Worker::new(db, command_receiver, worker_progress_clone).work())
+            .spawn(move || Worker::new(db, prune_windows, command_receiver, worker_progress).work())
             .expect("Creating pruner thread should succeed.");
 
         Self {
-            historical_versions_to_keep,
+            prune_windows,
             worker_thread: Some(worker_thread),
             command_sender: Mutex::new(command_sender),
-            worker_progress,
+            progress,
         }
     }
 
     /// Sends pruning command to the worker thread when necessary.
     pub fn wake(&self, latest_version: Version) {
-        if latest_version > self.historical_versions_to_keep {
-            let least_readable_version = latest_version - self.historical_versions_to_keep;
+        let has_work = PrunedData::ALL.iter().any(|data| {
+            self.prune_windows
+                .target_least_readable_version(*data, latest_version)
+                .map_or(false, |target| target > 0)
+        });
+        if has_work {
             self.command_sender
                 .lock()
-                .send(Command::Prune {
-                    least_readable_version,
-                })
+                .send(Command::Prune { latest_version })
                 .expect("Receiver should not destruct prematurely.");
         }
     }
 
     /// (For tests only.) Notifies the worker thread and waits for it to finish its job by polling
-    /// an internal counter.
+    /// the progress.
     #[cfg(test)]
     pub fn wake_and_wait(&self, latest_version: Version) -> Result<()> {
         self.wake(latest_version);
 
-        if latest_version > self.historical_versions_to_keep {
-            let least_readable_version = latest_version - self.historical_versions_to_keep;
-            // Assuming no big pruning chunks will be issued by a test.
-            const TIMEOUT: Duration = Duration::from_secs(10);
-            let end = Instant::now() + TIMEOUT;
-
-            while Instant::now() < end {
-                if self.worker_progress.load(Ordering::Relaxed) >= least_readable_version {
-                    return Ok(());
-                }
-                sleep(Duration::from_millis(1));
+        // Assuming no big pruning chunks will be issued by a test.
+        const TIMEOUT: Duration = Duration::from_secs(10);
+        let end = Instant::now() + TIMEOUT;
+
+        while Instant::now() < end {
+            if PrunedData::ALL.iter().all(|data| {
+                self.prune_windows
+                    .target_least_readable_version(*data, latest_version)
+                    .map_or(true, |target| {
+                        self.progress.least_readable_version(*data) >= target
+                    })
+            }) {
+                return Ok(());
             }
-            anyhow::bail!("Timeout waiting for pruner worker.");
+            sleep(Duration::from_millis(1));
         }
-        Ok(())
+        anyhow::bail!("Timeout waiting for pruner worker.");
     }
 }
 
@@ -126,17 +279,19 @@ impl Drop for Pruner {
 
 enum Command {
     Quit,
-    Prune { least_readable_version: Version },
+    Prune { latest_version: Version },
 }
 
 struct Worker {
     db: Arc<DB>,
+    prune_windows: PruneWindows,
     command_receiver: Receiver<Command>,
-    target_least_readable_version: Version,
-    /// Keeps a record of the pruning progress. If this equals to version `V`, we know versions
-    /// smaller than `V` are no longer readable.
-    /// This being an atomic value is to communicate the info with the Pruner thread (for tests).
-    least_readable_version: Arc<AtomicU64>,
+    /// The latest version committed, the targets of the pruning are derived from it.
+    latest_version: Version,
+    /// Keeps a record of the pruning progress. If the least readable version of some data is `V`,
+    /// we know versions smaller than `V` of it are no longer readable.
+    /// It's shared with the `Pruner` and the readers pinning the versions they read.
+    progress: Arc<PrunerProgress>,
     /// Indicates if there's NOT any pending work to do currently, to hint
     /// `Self::receive_commands()` to `recv()` blocking-ly.
     blocking_recv: bool,
@@ -149,14 +304,16 @@ impl Worker {
 
     fn new(
         db: Arc<DB>,
+        prune_windows: PruneWindows,
         command_receiver: Receiver<Command>,
-        least_readable_version: Arc<AtomicU64>,
+        progress: Arc<PrunerProgress>,
     ) -> Self {
         Self {
             db,
+            prune_windows,
             command_receiver,
-            least_readable_version,
-            target_least_readable_version: 0,
+            latest_version: 0,
+            progress,
             blocking_recv: true,
             index_min_nonpurged_version: 0,
             index_purged_at: Instant::now(),
@@ -169,85 +326,146 @@ impl Worker {
         while self.receive_commands() {
             // Process a reasonably small batch of work before trying to receive commands again,
             // in case `Command::Quit` is received (that's when we should quit.)
-            let least_readable_version = self.least_readable_version.load(Ordering::Relaxed);
-            match prune_state(
-                Arc::clone(&self.db),
-                least_readable_version,
-                self.target_least_readable_version,
-                Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
-            ) {
-                Ok(new_least_readable_version) => {
-                    self.record_progress(new_least_readable_version);
-
-                    // Make next recv() blocking if nothing left to do.
-                    self.blocking_recv = new_least_readable_version == least_readable_version // did nothing
-                        || new_least_readable_version == self.target_least_readable_version; // did all
+            let mut did_something = false;
+            let mut did_all = true;
+            {
+                // Holding the pins prevents the readers from pinning what's being pruned.
+                let pins = self.progress.pins.lock();
+                let least_pinned_version = PrunerProgress::least_pinned_version(&pins);
+                for data in PrunedData::ALL.iter().copied() {
+                    let target = match self
+                        .prune_windows
+                        .target_least_readable_version(data, self.latest_version)
+                    {
+                        Some(target) => target,
+                        None => continue,
+                    };
+                    DIEM_STORAGE_PRUNER_TARGET_VERSION
+                        .with_label_values(&[data.as_str()])
+                        .set(target as i64);
+                    let target = least_pinned_version.map_or(target, |pinned| target.min(pinned));
+                    let least_readable_version = self.progress.least_readable_version(data);
+                    if target <= least_readable_version {
+                        continue;
+                    }
 
-                    // Try to purge the log.
-                    if let Err(e) = self.maybe_purge_index() {
-                        warn!(
-                            error = ?e,
-                            "Failed purging state node index, ignored.",
-                        );
+                    match self.prune(data, least_readable_version, target) {
+                        Ok(new_least_readable_version) => {
+                            self.progress.record(data, new_least_readable_version);
+                            did_something |= new_least_readable_version != least_readable_version;
+                            did_all &= new_least_readable_version == target;
+                        }
+                        Err(e) => {
+                            error!(
+                                error = ?e,
+                                data = data.as_str(),
+                                "Error pruning.",
+                            );
+                        }
                     }
                 }
-                Err(e) => {
-                    error!(
+            }
+            // Make next recv() blocking if nothing left to do. On error, stop retrying vigorously.
+            self.blocking_recv = !did_something || did_all;
+
+            // Try to purge the log.
+            if self.prune_windows.state.is_some() {
+                if let Err(e) = self.maybe_purge_index() {
+                    warn!(
                         error = ?e,
-                        "Error pruning stale state nodes.",
+                        "Failed purging state node index, ignored.",
                     );
-                    // On error, stop retrying vigorously by making next recv() blocking.
-                    self.blocking_recv = true;
                 }
             }
         }
     }
 
-    /// Find out the first undeleted item in the stale node index.
+    fn prune(
+        &self,
+        data: PrunedData,
+        least_readable_version: Version,
+        target_least_readable_version: Version,
+    ) -> Result<Version> {
+        match data {
+            PrunedData::State => prune_state(
+                Arc::clone(&self.db),
+                least_readable_version,
+                target_least_readable_version,
+                Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
+            ),
+            PrunedData::Transactions => prune_transactions(
+                &self.db,
+                least_readable_version,
+                target_least_readable_version,
+                Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
+            ),
+            PrunedData::Events => prune_events(
+                &self.db,
+                least_readable_version,
+                target_least_readable_version,
+                Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
+            ),
+        }
+    }
+
+    /// Find out the first undeleted item of each kind of data.
     ///
     /// Seeking from the beginning (version 0) is potentially costly, we do it once upon worker
     /// thread start, record the progress and seek from that position afterwards.
     fn initialize(&mut self) {
-        loop {
-            match self.get_least_readable_version() {
-                Ok(least_readable_version) => {
-                    info!(
-                        least_readable_version = least_readable_version,
-                        "[state pruner worker] initialized."
-                    );
-                    self.target_least_readable_version = least_readable_version;
-                    self.record_progress(least_readable_version);
-                    return;
-                }
-                Err(e) => {
-                    error!(
-                        error = ?e,
-                        "[state pruner worker] Error on first seek. Retrying in 1 second.",
-                    );
-                    sleep(Duration::from_secs(1));
+        for data in PrunedData::ALL.iter().copied() {
+            loop {
+                match self.get_least_readable_version(data) {
+                    Ok(least_readable_version) => {
+                        info!(
+                            data = data.as_str(),
+                            least_readable_version = least_readable_version,
+                            "[pruner worker] initialized."
+                        );
+                        self.progress.record(data, least_readable_version);
+                        break;
+                    }
+                    Err(e) => {
+                        error!(
+                            error = ?e,
+                            data = data.as_str(),
+                            "[pruner worker] Error on first seek. Retrying in 1 second.",
+                        );
+                        sleep(Duration::from_secs(1));
+                    }
                 }
             }
         }
     }
 
-    fn get_least_readable_version(&self) -> Result<Version> {
-        let mut iter = self
-            .db
-            .iter::<StaleNodeIndexSchema>(ReadOptions::default())?;
-        iter.seek_to_first();
-        Ok(iter.next().transpose()?.map_or(0, |(index, _)| {
-            index
-                .stale_since_version
-                .checked_sub(1)
-                .expect("Nothing is stale since version 0.")
-        }))
-    }
-
-    /// Log the progress.
-    fn record_progress(&mut self, least_readable_version: Version) {
-        self.least_readable_version
-            .store(least_readable_version, Ordering::Relaxed);
-        DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION.set(least_readable_version as i64);
+    fn get_least_readable_version(&self, data: PrunedData) -> Result<Version> {
+        match data {
+            PrunedData::State => {
+                let mut iter = self
+                    .db
+                    .iter::<StaleNodeIndexSchema>(ReadOptions::default())?;
+                iter.seek_to_first();
+                Ok(iter.next().transpose()?.map_or(0, |(index, _)| {
+                    index
+                        .stale_since_version
+                        .checked_sub(1)
+                        .expect("Nothing is stale since version 0.")
+                }))
+            }
+            PrunedData::Transactions => {
+                let mut iter = self.db.iter::<TransactionSchema>(ReadOptions::default())?;
+                iter.seek_to_first();
+                Ok(iter.next().transpose()?.map_or(0, |(version, _)| version))
+            }
+            PrunedData::Events => {
+                let mut iter = self.db.iter::<EventSchema>(ReadOptions::default())?;
+                iter.seek_to_first();
+                Ok(iter
+                    .next()
+                    .transpose()?
+                    .map_or(0, |((version, _index), _)| version))
+            }
+        }
     }
 
     /// Tries to receive all pending commands, blocking waits for the next command if no work needs
@@ -275,11 +493,9 @@ impl Worker {
             match command {
                 // On `Command::Quit` inform the outer loop to quit by returning `false`.
                 Command::Quit => return false,
-                Command::Prune {
-                    least_readable_version,
-                } => {
-                    if least_readable_version > self.target_least_readable_version {
-                        self.target_least_readable_version = least_readable_version;
+                Command::Prune { latest_version } => {
+                    if latest_version > self.latest_version {
+                        self.latest_version = latest_version;
                         // Switch to non-blocking to allow some work to be done after the
                         // channel has drained.
                         self.blocking_recv = false;
@@ -303,7 +519,7 @@ impl Worker {
         // this imposes at most one minute of work in vain after restarting.)
         let now = Instant::now();
         if now - self.index_purged_at > MIN_INTERVAL {
-            let least_readable_version = self.least_readable_version.load(Ordering::Relaxed);
+            let least_readable_version = self.progress.least_readable_version(PrunedData::State);
 
             if least_readable_version - self.index_min_nonpurged_version + 1 > MIN_VERSIONS {
                 let new_min_non_purged_version = least_readable_version + 1;
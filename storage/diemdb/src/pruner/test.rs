@@ -2,10 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
-use crate::{change_set::ChangeSet, state_store::StateStore, DiemDB};
-use diem_crypto::HashValue;
+use crate::{
+    change_set::ChangeSet, event_store::EventStore, state_store::StateStore,
+    transaction_store::TransactionStore, DiemDB,
+};
+use diem_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, Uniform};
 use diem_temppath::TempPath;
-use diem_types::{account_address::AccountAddress, account_state_blob::AccountStateBlob};
+use diem_types::{
+    account_address::AccountAddress, account_state_blob::AccountStateBlob,
+    contract_event::ContractEvent, event::EventKey,
+    test_helpers::transaction_test_helpers::get_test_signed_txn, transaction::Transaction,
+};
+use move_core_types::language_storage::TypeTag;
 use std::collections::HashMap;
 
 fn put_account_state_set(
@@ -50,7 +58,14 @@ fn test_pruner() {
     let tmp_dir = TempPath::new();
     let db = DiemDB::new_for_test(&tmp_dir).db;
     let state_store = &StateStore::new(Arc::clone(&db));
-    let pruner = Pruner::new(Arc::clone(&db), 0 /* historical_versions_to_keep */);
+    let pruner = Pruner::new(
+        Arc::clone(&db),
+        PruneWindows {
+            state: Some(0), /* historical_versions_to_keep */
+            ..PruneWindows::default()
+        },
+        Arc::new(PrunerProgress::default()),
+    );
 
     let _root0 = put_account_state_set(
         &db,
@@ -135,18 +150,18 @@ fn test_worker_quit_eagerly() {
         let (command_sender, command_receiver) = channel();
         let worker = Worker::new(
             Arc::clone(&db),
+            PruneWindows {
+                state: Some(0),
+                ..PruneWindows::default()
+            },
             command_receiver,
-            Arc::new(AtomicU64::new(0)), /* progress */
+            Arc::new(PrunerProgress::default()),
         );
         command_sender
-            .send(Command::Prune {
-                least_readable_version: 1,
-            })
+            .send(Command::Prune { latest_version: 1 })
             .unwrap();
         command_sender
-            .send(Command::Prune {
-                least_readable_version: 2,
-            })
+            .send(Command::Prune { latest_version: 2 })
             .unwrap();
         command_sender.send(Command::Quit).unwrap();
         // Worker quits immediately although `Command::Quit` is not the first command sent.
@@ -156,3 +171,126 @@ fn test_worker_quit_eagerly() {
         verify_state_in_store(state_store, address, Some(&value2), 2);
     }
 }
+
+/// Puts a transaction of `sender` emitting an event for each of the versions.
+fn put_ledger(db: &Arc<DB>, sender: AccountAddress, event_key: EventKey, num_versions: u64) {
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+    let transaction_store = TransactionStore::new(Arc::clone(db));
+    let event_store = EventStore::new(Arc::clone(db));
+    let mut cs = ChangeSet::new();
+    for version in 0..num_versions {
+        let txn = get_test_signed_txn(
+            sender,
+            version,
+            &private_key,
+            private_key.public_key(),
+            None,
+        );
+        transaction_store
+            .put_transaction(version, &Transaction::UserTransaction(txn), &mut cs)
+            .unwrap();
+        let event = ContractEvent::new(event_key, version, TypeTag::Bool, vec![]);
+        event_store.put_events(version, &[event], &mut cs).unwrap();
+    }
+    db.write_schemas(cs.batch).unwrap();
+}
+
+fn wait_until(condition: impl Fn() -> bool) {
+    let end = Instant::now() + Duration::from_secs(10);
+    while !condition() {
+        assert!(Instant::now() < end, "Timeout waiting for pruner worker.");
+        sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn test_ledger_pruner() {
+    let sender = AccountAddress::new([1u8; AccountAddress::LENGTH]);
+    let event_key = EventKey::new_from_address(&sender, 0);
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir).db;
+    put_ledger(&db, sender, event_key, 10);
+    let transaction_store = TransactionStore::new(Arc::clone(&db));
+    let event_store = EventStore::new(Arc::clone(&db));
+    let progress = Arc::new(PrunerProgress::default());
+    let pruner = Pruner::new(
+        Arc::clone(&db),
+        PruneWindows {
+            transactions: Some(5),
+            events: Some(2),
+            ..PruneWindows::default()
+        },
+        Arc::clone(&progress),
+    );
+
+    pruner.wake_and_wait(9 /* latest_version */).unwrap();
+    assert_eq!(progress.least_readable_version(PrunedData::Transactions), 4);
+    assert_eq!(progress.least_readable_version(PrunedData::Events), 7);
+    // the state isn't pruned
+    assert_eq!(progress.least_readable_version(PrunedData::State), 0);
+
+    // the transactions are gone along with their index by account
+    assert!(transaction_store.get_transaction(3).is_err());
+    assert!(transaction_store.get_transaction(4).is_ok());
+    assert_eq!(
+        transaction_store
+            .get_account_transaction_version(sender, 3, 9)
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        transaction_store
+            .get_account_transaction_version(sender, 4, 9)
+            .unwrap(),
+        Some(4)
+    );
+
+    // so are the events
+    assert!(event_store.get_events_by_version(6).unwrap().is_empty());
+    assert_eq!(event_store.get_events_by_version(7).unwrap().len(), 1);
+    assert!(event_store
+        .lookup_events_by_key(&event_key, 6, 1, 9)
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        event_store
+            .lookup_events_by_key(&event_key, 7, 1, 9)
+            .unwrap(),
+        vec![(7, 7, 0)]
+    );
+}
+
+#[test]
+fn test_pruner_leaves_pinned_version() {
+    let sender = AccountAddress::new([1u8; AccountAddress::LENGTH]);
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir).db;
+    put_ledger(&db, sender, EventKey::new_from_address(&sender, 0), 11);
+    let transaction_store = TransactionStore::new(Arc::clone(&db));
+    let progress = Arc::new(PrunerProgress::default());
+    let pruner = Pruner::new(
+        Arc::clone(&db),
+        PruneWindows {
+            transactions: Some(0),
+            ..PruneWindows::default()
+        },
+        Arc::clone(&progress),
+    );
+
+    // a reader pins version 2, the pruner stops there
+    let pin = progress.pin(2, &[PrunedData::Transactions]).unwrap();
+    pruner.wake(9 /* latest_version */);
+    wait_until(|| progress.least_readable_version(PrunedData::Transactions) == 2);
+    assert!(transaction_store.get_transaction(2).is_ok());
+
+    // once the pin is dropped the pruner catches up
+    drop(pin);
+    pruner.wake_and_wait(10 /* latest_version */).unwrap();
+    assert!(transaction_store.get_transaction(9).is_err());
+
+    // pruned versions can't be pinned
+    assert!(progress.pin(9, &[PrunedData::Transactions]).is_err());
+    assert!(progress
+        .pin(9, &[PrunedData::State, PrunedData::Events])
+        .is_ok());
+}
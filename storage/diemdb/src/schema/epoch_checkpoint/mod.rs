@@ -0,0 +1,54 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the checkpoints the executor writes at every
+//! epoch boundary, identified by the epoch they end.
+//!
+//! ```text
+//! |<--key-->|<-----value------>|
+//! |  epoch  | epoch checkpoint |
+//! ```
+//!
+//! `epoch` is serialized in big endian so that records in RocksDB will be in order of their
+//! numeric value.
+
+use crate::schema::{ensure_slice_len_eq, EPOCH_CHECKPOINT_CF_NAME};
+use anyhow::Result;
+use byteorder::{BigEndian, ReadBytesExt};
+use diem_types::epoch_checkpoint::EpochCheckpoint;
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::mem::size_of;
+
+define_schema!(
+    EpochCheckpointSchema,
+    u64, /* epoch num */
+    EpochCheckpoint,
+    EPOCH_CHECKPOINT_CF_NAME
+);
+
+impl KeyCodec<EpochCheckpointSchema> for u64 {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(self.to_be_bytes().to_vec())
+    }
+
+    fn decode_key(mut data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<Self>())?;
+        Ok(data.read_u64::<BigEndian>()?)
+    }
+}
+
+impl ValueCodec<EpochCheckpointSchema> for EpochCheckpoint {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        bcs::to_bytes(self).map_err(Into::into)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        bcs::from_bytes(data).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,16 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use proptest::prelude::*;
+use schemadb::schema::assert_encode_decode;
+
+proptest! {
+    #[test]
+    fn test_encode_decode(
+        epoch in any::<u64>(),
+        checkpoint in any::<EpochCheckpoint>(),
+    ) {
+        assert_encode_decode::<EpochCheckpointSchema>(&epoch, &checkpoint);
+    }
+}
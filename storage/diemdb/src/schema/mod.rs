@@ -7,6 +7,7 @@
 //! All schemas are `pub(crate)` so not shown in rustdoc, refer to the source code to see details.
 
 pub(crate) mod epoch_by_version;
+pub(crate) mod epoch_checkpoint;
 pub(crate) mod event;
 pub(crate) mod event_accumulator;
 pub(crate) mod event_by_key;
@@ -24,6 +25,7 @@ use anyhow::{ensure, Result};
 use schemadb::ColumnFamilyName;
 
 pub const EPOCH_BY_VERSION_CF_NAME: ColumnFamilyName = "epoch_by_version";
+pub const EPOCH_CHECKPOINT_CF_NAME: ColumnFamilyName = "epoch_checkpoint";
 pub const EVENT_ACCUMULATOR_CF_NAME: ColumnFamilyName = "event_accumulator";
 pub const EVENT_BY_KEY_CF_NAME: ColumnFamilyName = "event_by_key";
 pub const EVENT_BY_VERSION_CF_NAME: ColumnFamilyName = "event_by_version";
@@ -71,6 +73,7 @@ pub mod fuzzing {
         #[allow(unused_must_use)]
         {
             decode_key_value!(super::epoch_by_version::EpochByVersionSchema, data);
+            decode_key_value!(super::epoch_checkpoint::EpochCheckpointSchema, data);
             decode_key_value!(super::event::EventSchema, data);
             decode_key_value!(super::event_accumulator::EventAccumulatorSchema, data);
             decode_key_value!(super::event_by_key::EventByKeySchema, data);
@@ -13,9 +13,12 @@ use crate::{
         jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
     },
 };
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use diem_crypto::HashValue;
-use diem_jellyfish_merkle::{node_type::NodeKey, JellyfishMerkleTree, TreeReader, TreeWriter};
+use diem_jellyfish_merkle::{
+    node_type::NodeKey, restore::JellyfishMerkleRestore, JellyfishMerkleTree, TreeReader,
+    TreeWriter,
+};
 use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_state_blob::AccountStateBlob,
@@ -25,6 +28,7 @@ use diem_types::{
 };
 use schemadb::{SchemaBatch, DB};
 use std::{collections::HashMap, sync::Arc};
+use storage_interface::StateSnapshotReceiver;
 
 type LeafNode = diem_jellyfish_merkle::node_type::LeafNode<AccountStateBlob>;
 type Node = diem_jellyfish_merkle::node_type::Node<AccountStateBlob>;
@@ -40,6 +44,19 @@ impl StateStore {
         Self { db }
     }
 
+    /// Gets the receiver writing the accounts of the state snapshot at `version` into the store.
+    pub fn get_snapshot_receiver(
+        self: &Arc<Self>,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<StateSnapshotRestore> {
+        Ok(StateSnapshotRestore(JellyfishMerkleRestore::new_overwrite(
+            Arc::clone(self),
+            version,
+            expected_root_hash,
+        )?))
+    }
+
     /// Get the account state blob given account address and root hash of state Merkle tree
     pub fn get_account_state_with_proof_by_version(
         &self,
@@ -52,6 +69,22 @@ impl StateStore {
         JellyfishMerkleTree::new(self).get_with_proof(address.hash(), version)
     }
 
+    /// Gets the account state blobs of a batch of addresses at `version` with their proofs,
+    /// reading the tree nodes the proofs have in common only once.
+    pub fn get_account_states_with_proofs_by_version(
+        &self,
+        addresses: &[AccountAddress],
+        version: Version,
+    ) -> Result<
+        Vec<(
+            Option<AccountStateBlob>,
+            SparseMerkleProof<AccountStateBlob>,
+        )>,
+    > {
+        let keys: Vec<_> = addresses.iter().map(|address| address.hash()).collect();
+        JellyfishMerkleTree::new(self).get_with_proofs(&keys, version)
+    }
+
     /// Gets the proof that proves a range of accounts.
     pub fn get_account_state_range_proof(
         &self,
@@ -61,6 +94,34 @@ impl StateStore {
         JellyfishMerkleTree::new(self).get_range_proof(rightmost_key, version)
     }
 
+    /// Writes the tree of `version` made of the tree of `base_version` with the changed accounts
+    /// in `delta`, making sure it adds up to `expected_root_hash`.
+    pub fn put_account_state_delta(
+        &self,
+        base_version: Version,
+        version: Version,
+        expected_root_hash: HashValue,
+        delta: Vec<(HashValue, AccountStateBlob)>,
+    ) -> Result<()> {
+        let (root_hash, tree_update_batch) =
+            JellyfishMerkleTree::new(self).put_value_set_on_base(delta, base_version, version)?;
+        ensure!(
+            root_hash == expected_root_hash,
+            "Root hash mismatch after applying the state delta. root hash: {}, expected: {}",
+            root_hash,
+            expected_root_hash,
+        );
+
+        let mut batch = SchemaBatch::new();
+        add_node_batch(&mut batch, &tree_update_batch.node_batch)?;
+        tree_update_batch
+            .stale_node_index_batch
+            .iter()
+            .map(|row| batch.put::<StaleNodeIndexSchema>(row, &()))
+            .collect::<Result<Vec<()>>>()?;
+        self.db.write_schemas(batch)
+    }
+
     /// Put the results generated by `account_state_sets` to `batch` and return the result root
     /// hashes for each write set.
     pub fn put_account_state_sets(
@@ -225,3 +286,20 @@ fn add_node_batch(batch: &mut SchemaBatch, node_batch: &NodeBatch) -> Result<()>
         .collect::<Result<Vec<_>>>()?;
     Ok(())
 }
+
+/// Restores a state snapshot received chunk by chunk, see `StateStore::get_snapshot_receiver()`.
+pub(crate) struct StateSnapshotRestore(JellyfishMerkleRestore<AccountStateBlob>);
+
+impl StateSnapshotReceiver for StateSnapshotRestore {
+    fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()> {
+        self.0.add_chunk(chunk, proof)
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.0.finish()
+    }
+}
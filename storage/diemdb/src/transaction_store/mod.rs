@@ -4,6 +4,7 @@
 //! This file defines transaction store APIs that are related to committed signed transactions.
 
 use crate::{
+    archive::Archive,
     change_set::ChangeSet,
     errors::DiemDbError,
     schema::{transaction::TransactionSchema, transaction_by_account::TransactionByAccountSchema},
@@ -20,11 +21,27 @@ use std::sync::Arc;
 #[derive(Debug)]
 pub(crate) struct TransactionStore {
     db: Arc<DB>,
+    /// The transactions moved out of `db` once old enough, if archiving.
+    archive: Option<Arc<Archive>>,
 }
 
 impl TransactionStore {
     pub fn new(db: Arc<DB>) -> Self {
-        Self { db }
+        Self { db, archive: None }
+    }
+
+    /// Like `new`, but falls back on `archive` to read the transactions missing from `db`.
+    pub fn new_with_archive(db: Arc<DB>, archive: Arc<Archive>) -> Self {
+        Self {
+            db,
+            archive: Some(archive),
+        }
+    }
+
+    fn get_archived_transaction(&self, version: Version) -> Result<Option<Transaction>> {
+        self.archive
+            .as_ref()
+            .map_or(Ok(None), |archive| archive.get_transaction(version))
     }
 
     /// Gets the version of a transaction by the sender `address` and `sequence_number`.
@@ -75,10 +92,54 @@ impl TransactionStore {
         })
     }
 
+    /// Gets an iterator that yields `(sequence_number, version)` for each
+    /// transaction sent by an account with `version <= ledger_version`, newest
+    /// first from `start_seq_num` down, or from the latest such transaction if
+    /// `start_seq_num` is beyond it, and returning at most `num_versions` results.
+    ///
+    /// Guarantees that the returned sequence numbers are sequential, i.e.,
+    /// `seq_num_{i} = seq_num_{i+1} + 1`.
+    pub fn get_account_transaction_version_rev_iter(
+        &self,
+        address: AccountAddress,
+        start_seq_num: u64,
+        num_versions: u64,
+        ledger_version: Version,
+    ) -> Result<AccountTransactionVersionRevIter> {
+        let mut iter = self
+            .db
+            .rev_iter::<TransactionByAccountSchema>(ReadOptions::default())?;
+        iter.seek_for_prev(&(address, start_seq_num))?;
+        Ok(AccountTransactionVersionRevIter {
+            inner: iter,
+            address,
+            expected_next_seq_num: None,
+            num_remaining: num_versions,
+            prev_version: None,
+            ledger_version,
+        })
+    }
+
     /// Get signed transaction given `version`
+    /// Gets the version of the first transaction, in the archive if any or else in the DB.
+    pub fn get_first_txn_version(&self) -> Result<Option<Version>> {
+        if let Some(range) = self
+            .archive
+            .as_ref()
+            .and_then(|archive| archive.version_range())
+        {
+            return Ok(Some(range.start));
+        }
+        let mut iter = self.db.iter::<TransactionSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        Ok(iter.next().transpose()?.map(|(version, _)| version))
+    }
+
     pub fn get_transaction(&self, version: Version) -> Result<Transaction> {
-        self.db
-            .get::<TransactionSchema>(&version)?
+        if let Some(txn) = self.db.get::<TransactionSchema>(&version)? {
+            return Ok(txn);
+        }
+        self.get_archived_transaction(version)?
             .ok_or_else(|| DiemDbError::NotFound(format!("Txn {}", version)).into())
     }
 
@@ -92,6 +153,7 @@ impl TransactionStore {
         iter.seek(&start_version)?;
         Ok(TransactionIter {
             inner: iter,
+            archive: self.archive.as_deref(),
             expected_next_version: start_version,
             end_version: start_version
                 .checked_add(num_transactions as u64)
@@ -112,6 +174,7 @@ impl TransactionStore {
         // each block.
         let mut iter = self.db.rev_iter::<TransactionSchema>(Default::default())?;
         iter.seek(&version)?;
+        let mut num_searched = 0;
         for res in iter.take(MAX_VERSIONS_TO_SEARCH) {
             let (v, txn) = res?;
             if let Transaction::BlockMetadata(block_meta) = txn {
@@ -119,6 +182,23 @@ impl TransactionStore {
             } else if v == 0 {
                 return Ok(None);
             }
+            num_searched += 1;
+        }
+
+        // The search goes on in the archive, which has the transactions before the ones in `db`.
+        if let Some(archive) = &self.archive {
+            let mut v = archive.end_version().min(version.saturating_add(1));
+            while v > 0 && num_searched < MAX_VERSIONS_TO_SEARCH {
+                v -= 1;
+                match archive.get_transaction(v)? {
+                    Some(Transaction::BlockMetadata(block_meta)) => {
+                        return Ok(Some((v, block_meta)))
+                    }
+                    Some(_) if v == 0 => return Ok(None),
+                    Some(_) => num_searched += 1,
+                    None => break,
+                }
+            }
         }
 
         Err(DiemDbError::NotFound(format!("BlockMetadata preceding version {}", version)).into())
@@ -145,6 +225,7 @@ impl TransactionStore {
 
 pub struct TransactionIter<'a> {
     inner: SchemaIterator<'a, TransactionSchema>,
+    archive: Option<&'a Archive>,
     expected_next_version: Version,
     end_version: Version,
 }
@@ -155,7 +236,20 @@ impl<'a> TransactionIter<'a> {
             return Ok(None);
         }
 
-        let ret = match self.inner.next().transpose()? {
+        if let Some(archive) = self.archive {
+            if let Some(transaction) = archive.get_transaction(self.expected_next_version)? {
+                self.expected_next_version += 1;
+                return Ok(Some(transaction));
+            }
+        }
+
+        // Skips the transactions read from the archive, if the iterator still sees them.
+        let mut next = self.inner.next().transpose()?;
+        while matches!(next, Some((version, _)) if version < self.expected_next_version) {
+            next = self.inner.next().transpose()?;
+        }
+
+        let ret = match next {
             Some((version, transaction)) => {
                 ensure!(
                     version == self.expected_next_version,
@@ -260,3 +354,76 @@ impl<'a> Iterator for AccountTransactionVersionIter<'a> {
 
 #[cfg(test)]
 mod test;
+
+pub struct AccountTransactionVersionRevIter<'a> {
+    inner: SchemaIterator<'a, TransactionByAccountSchema>,
+    address: AccountAddress,
+    expected_next_seq_num: Option<u64>,
+    num_remaining: u64,
+    prev_version: Option<Version>,
+    ledger_version: Version,
+}
+
+impl<'a> AccountTransactionVersionRevIter<'a> {
+    fn next_impl(&mut self) -> Result<Option<(u64, Version)>> {
+        while self.num_remaining > 0 {
+            let ((address, seq_num), version) = match self.inner.next().transpose()? {
+                Some(item) => item,
+                None => return Ok(None),
+            };
+            // No more transactions sent by this account.
+            if address != self.address {
+                return Ok(None);
+            }
+
+            // Ensure version_{i+1} < version_{i}
+            if let Some(prev_version) = self.prev_version {
+                ensure!(
+                    version < prev_version,
+                    "DB corruption: account transaction versions are not strictly decreasing: \
+                     previous version: {}, current version: {}",
+                    prev_version,
+                    version,
+                );
+            }
+
+            // Not in this view of the ledger yet. Since versions grow with sequence numbers, only
+            // the latest transactions can be skipped.
+            if version > self.ledger_version {
+                continue;
+            }
+
+            // Ensure seq_num_{i+1} + 1 == seq_num_{i}
+            if let Some(expected_next_seq_num) = self.expected_next_seq_num {
+                ensure!(
+                    seq_num == expected_next_seq_num,
+                    "DB corruption: account transactions sequence numbers are not contiguous: \
+                     actual: {}, expected: {}",
+                    seq_num,
+                    expected_next_seq_num,
+                );
+            }
+
+            self.prev_version = Some(version);
+            match seq_num.checked_sub(1) {
+                Some(next_seq_num) => {
+                    self.expected_next_seq_num = Some(next_seq_num);
+                    self.num_remaining -= 1;
+                }
+                // The first transaction of the account.
+                None => self.num_remaining = 0,
+            }
+            return Ok(Some((seq_num, version)));
+        }
+
+        Ok(None)
+    }
+}
+
+impl<'a> Iterator for AccountTransactionVersionRevIter<'a> {
+    type Item = Result<(u64, Version)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_impl().transpose()
+    }
+}
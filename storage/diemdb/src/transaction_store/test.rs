@@ -234,6 +234,60 @@ proptest! {
 
         prop_assert_eq!(&actual_scan, &expected_scan);
     }
+
+    #[test]
+    fn test_get_account_transaction_version_rev_iter(
+        universe in any_with::<AccountInfoUniverse>(5),
+        gens in vec(
+            (any::<Index>(), any::<SignatureCheckedTransactionGen>()),
+            1..=50,
+        ),
+        start_seq_num in 0_u64..=20,
+        ledger_version in 0_u64..50,
+        num_versions in 0_u64..=50,
+    ) {
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::new_for_test(&tmp_dir);
+        let store = &db.transaction_store;
+        let txns = init_store(universe, gens, store);
+
+        // what does the expected view look like
+        let mut expected_scan = BTreeMap::<AccountAddress, Vec<(u64, Version)>>::new();
+        for (version, txn) in txns.iter().enumerate().rev() {
+            let version = version as u64;
+            let txn = txn.as_signed_user_txn().unwrap();
+            let seq_num = txn.sequence_number();
+            if version <= ledger_version && seq_num <= start_seq_num {
+                let txn_metadatas = expected_scan.entry(txn.sender()).or_default();
+                if (txn_metadatas.len() as u64) < num_versions {
+                    txn_metadatas.push((seq_num, version));
+                }
+            }
+        }
+
+        // throw in a non-existent account; make sure we don't return anything for it
+        expected_scan.entry(AccountAddress::from_hex_literal("0x1234").unwrap()).or_default();
+
+        // scan the db
+        let actual_scan = expected_scan
+            .keys()
+            .map(|address| {
+                let txn_metadatas = store
+                    .get_account_transaction_version_rev_iter(
+                        *address,
+                        start_seq_num,
+                        num_versions,
+                        ledger_version,
+                    )
+                    .unwrap()
+                    .collect::<Result<Vec<_>>>()
+                    .unwrap();
+                (*address, txn_metadatas)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        prop_assert_eq!(&actual_scan, &expected_scan);
+    }
 }
 
 fn init_store(
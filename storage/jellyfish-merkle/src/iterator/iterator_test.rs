@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    iterator::JellyfishMerkleIterator,
+    iterator::{JellyfishMerkleDeltaIterator, JellyfishMerkleIterator},
     mock_tree_store::MockTreeStore,
     test_helper::{plus_one, ValueBlob},
     JellyfishMerkleTree,
@@ -25,6 +25,61 @@ fn test_iterator_multiple_versions() {
     test_n_leaves_multiple_versions(50);
 }
 
+#[test]
+fn test_delta_iterator() {
+    let db = Arc::new(MockTreeStore::default());
+    let tree = JellyfishMerkleTree::new(&*db);
+
+    let mut rng = StdRng::from_seed([1; 32]);
+
+    let mut btree = BTreeMap::new();
+    for i in 0..50usize {
+        let key = HashValue::random_with_rng(&mut rng);
+        btree.insert(key, ValueBlob::from(i.to_be_bytes().to_vec()));
+    }
+    let (_root_hash, batch) = tree
+        .put_value_set(btree.clone().into_iter().collect(), 0 /* version */)
+        .unwrap();
+    db.write_tree_update_batch(batch).unwrap();
+
+    // update some of the keys and add new ones in the following versions
+    let keys: Vec<_> = btree.keys().cloned().collect();
+    let mut changed = BTreeMap::new();
+    for version in 1..=5usize {
+        let value = ValueBlob::from(version.to_be_bytes().to_vec());
+        let value_set = vec![
+            (keys[version * 7], value.clone()),
+            (HashValue::random_with_rng(&mut rng), value),
+        ];
+        let (_root_hash, batch) = tree
+            .put_value_set(value_set.clone(), version as Version)
+            .unwrap();
+        db.write_tree_update_batch(batch).unwrap();
+        btree.extend(value_set.clone());
+        changed.extend(value_set);
+    }
+
+    let delta = JellyfishMerkleDeltaIterator::new(Arc::clone(&db), 0, 5)
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    // sorted by key, with the latest values, and covering every change
+    assert!(delta.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    for (key, value) in &delta {
+        assert_eq!(btree.get(key), Some(value));
+    }
+    for key in changed.keys() {
+        assert!(delta.iter().any(|(delta_key, _)| delta_key == key));
+    }
+    // the keys untouched since version 0 are mostly left out
+    assert!(delta.len() < btree.len());
+
+    let delta = JellyfishMerkleDeltaIterator::new(Arc::clone(&db), 4, 5)
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert!(delta.iter().any(|(key, _)| *key == keys[35]));
+    assert!(delta.len() < changed.len());
+}
+
 fn test_n_leaves_same_version(n: usize) {
     let db = Arc::new(MockTreeStore::default());
     let tree = JellyfishMerkleTree::new(&*db);
@@ -5,6 +5,9 @@
 //! iterator generates all the key-value pairs in this version of the tree, starting from the
 //! smallest key that is greater or equal to the given key, by performing a depth first traversal
 //! on the tree.
+//!
+//! It also implements `JellyfishMerkleDeltaIterator`, which generates the key-value pairs of a
+//! version of the tree written after a base version, skipping the subtrees left untouched since.
 
 #[cfg(test)]
 mod iterator_test;
@@ -265,3 +268,70 @@ where
         }
     }
 }
+
+/// Iterates over the leaves of the tree of `version` written after `base_version`, in key order:
+/// the keys whose values changed in between, along with some keys whose leaves only moved in the
+/// tree. Only the subtrees written after `base_version` are visited, so the memory used doesn't
+/// depend on the size of the tree or of the delta.
+pub struct JellyfishMerkleDeltaIterator<R, V> {
+    /// The storage engine from which we can read nodes using node keys.
+    reader: Arc<R>,
+
+    /// The version of the tree after which the leaves are generated.
+    base_version: Version,
+
+    /// The nodes left to visit, the next one on top. At most 15 siblings are pending on each
+    /// level of the tree.
+    stack: Vec<NodeKey>,
+
+    phantom_value: PhantomData<V>,
+}
+
+impl<R, V> JellyfishMerkleDeltaIterator<R, V>
+where
+    R: TreeReader<V>,
+    V: crate::Value,
+{
+    pub fn new(reader: Arc<R>, base_version: Version, version: Version) -> Self {
+        Self {
+            reader,
+            base_version,
+            stack: vec![NodeKey::new_empty_path(version)],
+            phantom_value: PhantomData,
+        }
+    }
+}
+
+impl<R, V> Iterator for JellyfishMerkleDeltaIterator<R, V>
+where
+    R: TreeReader<V>,
+    V: crate::Value,
+{
+    type Item = Result<(HashValue, V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node_key) = self.stack.pop() {
+            match self.reader.get_node(&node_key) {
+                Ok(Node::Internal(internal_node)) => {
+                    // the rightmost child is pushed first, so that the leftmost is visited first
+                    for child_index in (0..16u8).rev().map(Nibble::from) {
+                        if let Some(child) = internal_node.child(child_index) {
+                            if child.version > self.base_version {
+                                self.stack
+                                    .push(node_key.gen_child_node_key(child.version, child_index));
+                            }
+                        }
+                    }
+                }
+                Ok(Node::Leaf(leaf_node)) => {
+                    if node_key.version() > self.base_version {
+                        return Some(Ok((leaf_node.account_key(), leaf_node.value().clone())));
+                    }
+                }
+                Ok(Node::Null) => (),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        None
+    }
+}
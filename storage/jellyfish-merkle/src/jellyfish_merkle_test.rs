@@ -12,7 +12,7 @@ use diem_types::{nibble::Nibble, transaction::PRE_GENESIS_VERSION};
 use mock_tree_store::MockTreeStore;
 use proptest::prelude::*;
 use rand::{rngs::StdRng, Rng, SeedableRng};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 fn update_nibble(original_key: &HashValue, n: usize, nibble: u8) -> HashValue {
     assert!(nibble < 16);
@@ -570,6 +570,59 @@ fn test_put_value_sets() {
     }
 }
 
+#[test]
+fn test_put_value_set_on_base() {
+    let keys: Vec<_> = (0..20).map(|_| HashValue::random()).collect();
+    let value_sets: Vec<Vec<_>> = (0..10)
+        .map(|version| {
+            // every version updates two of the first keys and adds a new one
+            (0..3)
+                .map(|i| {
+                    let key = if i < 2 {
+                        keys[(version + i) % 10]
+                    } else {
+                        keys[10 + version]
+                    };
+                    (key, ValueBlob::from(HashValue::random().to_vec()))
+                })
+                .collect()
+        })
+        .collect();
+
+    let db = MockTreeStore::default();
+    let tree = JellyfishMerkleTree::new(&db);
+    let (root_hashes, batch) = tree
+        .batch_put_value_sets(value_sets.clone(), None, 0 /* version */)
+        .unwrap();
+    db.write_tree_update_batch(batch).unwrap();
+
+    // the latest values of the keys changed after version 0
+    let delta: BTreeMap<_, _> = value_sets[1..].iter().flatten().cloned().collect();
+    let db_on_base = MockTreeStore::default();
+    let tree_on_base = JellyfishMerkleTree::new(&db_on_base);
+    let (_root_hash, batch) = tree_on_base
+        .put_value_set(value_sets[0].clone(), 0 /* version */)
+        .unwrap();
+    db_on_base.write_tree_update_batch(batch).unwrap();
+    let (root_hash, batch) = tree_on_base
+        .put_value_set_on_base(delta.into_iter().collect(), 0 /* base_version */, 9)
+        .unwrap();
+    assert_eq!(root_hash, root_hashes[9]);
+    db_on_base.write_tree_update_batch(batch).unwrap();
+    assert_eq!(tree_on_base.get_root_hash(9).unwrap(), root_hashes[9]);
+    for key in &keys {
+        assert_eq!(
+            tree_on_base.get(*key, 9).unwrap(),
+            tree.get(*key, 9).unwrap()
+        );
+    }
+
+    // the nodes of the base replaced by the delta are stale
+    db_on_base.purge_stale_nodes(9).unwrap();
+    assert!(tree_on_base.get_root_hash_option(0).unwrap().is_none());
+    assert_eq!(tree_on_base.get_root_hash(9).unwrap(), root_hashes[9]);
+}
+
 fn many_keys_get_proof_and_verify_tree_root(seed: &[u8], num_keys: usize) {
     assert!(seed.len() < 32);
     let mut actual_seed = [0u8; 32];
@@ -604,6 +657,49 @@ fn test_1000_keys() {
     many_keys_get_proof_and_verify_tree_root(seed, 1000);
 }
 
+#[test]
+fn test_get_with_proofs() {
+    let seed: &[_] = &[1, 2, 3, 4];
+    let mut actual_seed = [0u8; 32];
+    actual_seed[..seed.len()].copy_from_slice(seed);
+    let mut rng: StdRng = StdRng::from_seed(actual_seed);
+
+    let db = MockTreeStore::default();
+    let tree = JellyfishMerkleTree::new(&db);
+
+    let mut kvs = vec![];
+    for _i in 0..100 {
+        let key = HashValue::random_with_rng(&mut rng);
+        let value = ValueBlob::from(HashValue::random_with_rng(&mut rng).to_vec());
+        kvs.push((key, value));
+    }
+    let (roots, batch) = tree
+        .batch_put_value_sets(vec![kvs.clone()], None, 0 /* version */)
+        .unwrap();
+    db.write_tree_update_batch(batch).unwrap();
+
+    // existent keys, a nonexistent key and a duplicate, in no particular order
+    let mut keys: Vec<_> = kvs.iter().map(|(k, _)| *k).rev().collect();
+    keys.push(HashValue::random_with_rng(&mut rng));
+    keys.push(kvs[0].0);
+
+    let values_with_proofs = tree.get_with_proofs(&keys, 0).unwrap();
+    assert_eq!(values_with_proofs.len(), keys.len());
+    for (key, (value, proof)) in keys.iter().zip(values_with_proofs) {
+        let (expected_value, expected_proof) = tree.get_with_proof(*key, 0).unwrap();
+        assert_eq!(value, expected_value);
+        assert_eq!(proof, expected_proof);
+        assert!(proof.verify(roots[0], *key, value.as_ref()).is_ok());
+    }
+
+    // the root of a version that doesn't exist is still reported as missing
+    assert!(tree
+        .get_with_proofs(&keys, 1)
+        .unwrap_err()
+        .downcast::<MissingRootError>()
+        .is_ok());
+}
+
 fn many_versions_get_proof_and_verify_tree_root(seed: &[u8], num_versions: usize) {
     assert!(seed.len() < 32);
     let mut actual_seed = [0u8; 32];
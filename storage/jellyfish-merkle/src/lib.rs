@@ -97,6 +97,7 @@ use proptest::arbitrary::Arbitrary;
 use proptest_derive::Arbitrary;
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
+    cell::RefCell,
     collections::{BTreeMap, BTreeSet, HashMap},
     marker::PhantomData,
 };
@@ -306,6 +307,44 @@ where
         Ok(tree_cache.into())
     }
 
+    /// Like [`batch_put_value_sets`](struct.JellyfishMerkleTree.html#method.batch_put_value_sets)
+    /// with a single `value_set`, but builds the tree of `version` on top of the tree of
+    /// `base_version` instead of that of `version - 1`, skipping the versions in between. Used to
+    /// apply the accounts changed between two versions at once, e.g. when restoring a state delta.
+    pub fn put_value_set_on_base(
+        &self,
+        value_set: Vec<(HashValue, V)>,
+        base_version: Version,
+        version: Version,
+    ) -> Result<(HashValue, TreeUpdateBatch<V>)> {
+        ensure!(
+            base_version != version,
+            "Can't put values on top of the tree of the same version {}.",
+            version,
+        );
+        ensure!(!value_set.is_empty(), "No values to put.");
+        let mut tree_cache = TreeCache::new_on_base(self.reader, base_version, version);
+        let deduped_and_sorted_kvs = value_set
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let root_node_key = tree_cache.get_root_node_key().clone();
+        let (new_root_node_key, _) = Self::batch_insert_at(
+            root_node_key,
+            version,
+            deduped_and_sorted_kvs.as_slice(),
+            0,
+            &None,
+            &mut tree_cache,
+        )?;
+        tree_cache.set_root_node_key(new_root_node_key);
+        tree_cache.freeze();
+
+        let (root_hashes, tree_update_batch) = tree_cache.into();
+        Ok((root_hashes[0], tree_update_batch))
+    }
+
     fn batch_insert_at(
         mut node_key: NodeKey,
         version: Version,
@@ -921,6 +960,21 @@ where
         bail!("Jellyfish Merkle tree has cyclic graph inside.");
     }
 
+    /// Returns the values and proofs of `keys` at `version`, in the order of `keys`. The internal
+    /// nodes read while walking down the tree are shared among the keys, so the nodes close to the
+    /// root, where the paths of the keys overlap, are only read once for the whole batch.
+    pub fn get_with_proofs(
+        &self,
+        keys: &[HashValue],
+        version: Version,
+    ) -> Result<Vec<(Option<V>, SparseMerkleProof<V>)>> {
+        let reader = InternalNodeCachingReader::new(self.reader);
+        let tree = JellyfishMerkleTree::new(&reader);
+        keys.iter()
+            .map(|key| tree.get_with_proof(*key, version))
+            .collect()
+    }
+
     /// Gets the proof that shows a list of keys up to `rightmost_key_to_prove` exist at `version`.
     pub fn get_range_proof(
         &self,
@@ -967,6 +1021,45 @@ where
     }
 }
 
+/// A [`TreeReader`] remembering the internal nodes it has read, so that walking down the tree for
+/// several keys doesn't read the nodes the paths have in common more than once.
+struct InternalNodeCachingReader<'a, R, V> {
+    reader: &'a R,
+    internal_nodes: RefCell<HashMap<NodeKey, Node<V>>>,
+}
+
+impl<'a, R, V> InternalNodeCachingReader<'a, R, V> {
+    fn new(reader: &'a R) -> Self {
+        Self {
+            reader,
+            internal_nodes: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+impl<'a, R, V> TreeReader<V> for InternalNodeCachingReader<'a, R, V>
+where
+    R: 'a + TreeReader<V>,
+    V: Value,
+{
+    fn get_node_option(&self, node_key: &NodeKey) -> Result<Option<Node<V>>> {
+        if let Some(node) = self.internal_nodes.borrow().get(node_key) {
+            return Ok(Some(node.clone()));
+        }
+        let node_opt = self.reader.get_node_option(node_key)?;
+        if let Some(node @ Node::Internal(_)) = &node_opt {
+            self.internal_nodes
+                .borrow_mut()
+                .insert(node_key.clone(), node.clone());
+        }
+        Ok(node_opt)
+    }
+
+    fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode<V>)>> {
+        self.reader.get_rightmost_leaf()
+    }
+}
+
 trait NibbleExt {
     fn get_nibble(&self, index: usize) -> Nibble;
     fn common_prefix_nibbles_len(&self, other: HashValue) -> usize;
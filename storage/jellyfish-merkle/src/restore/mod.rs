@@ -107,7 +107,7 @@ where
 
 pub struct JellyfishMerkleRestore<V> {
     /// The underlying storage.
-    store: Arc<dyn TreeWriter<V>>,
+    store: Arc<dyn TreeWriter<V> + Send + Sync>,
 
     /// The version of the tree we are restoring.
     version: Version,
@@ -162,7 +162,7 @@ impl<V> JellyfishMerkleRestore<V>
 where
     V: crate::Value,
 {
-    pub fn new<D: 'static + TreeReader<V> + TreeWriter<V>>(
+    pub fn new<D: 'static + TreeReader<V> + TreeWriter<V> + Send + Sync>(
         store: Arc<D>,
         version: Version,
         expected_root_hash: HashValue,
@@ -195,7 +195,7 @@ where
         })
     }
 
-    pub fn new_overwrite<D: 'static + TreeWriter<V>>(
+    pub fn new_overwrite<D: 'static + TreeWriter<V> + Send + Sync>(
         store: Arc<D>,
         version: Version,
         expected_root_hash: HashValue,
@@ -280,7 +280,8 @@ where
 
     /// Restores a chunk of accounts. This function will verify that the given chunk is correct
     /// using the proof and root hash, then write things to storage. If the chunk is invalid, an
-    /// error will be returned and nothing will be written to storage.
+    /// error will be returned, nothing will be written to storage and the restore is left as it
+    /// was before the chunk, so that a valid chunk can be added instead.
     pub fn add_chunk(
         &mut self,
         chunk: Vec<(HashValue, V)>,
@@ -288,6 +289,30 @@ where
     ) -> Result<()> {
         ensure!(!chunk.is_empty(), "Should not add empty chunks.");
 
+        let partial_nodes = self.partial_nodes.clone();
+        let frozen_nodes = self.frozen_nodes.clone();
+        let previous_leaf = self.previous_leaf.clone();
+        let num_keys_received = self.num_keys_received;
+        if let Err(error) = self.add_and_verify_chunk(chunk, proof) {
+            self.partial_nodes = partial_nodes;
+            self.frozen_nodes = frozen_nodes;
+            self.previous_leaf = previous_leaf;
+            self.num_keys_received = num_keys_received;
+            return Err(error);
+        }
+
+        // Write the frozen nodes to storage.
+        self.store.write_node_batch(&self.frozen_nodes)?;
+        self.frozen_nodes.clear();
+
+        Ok(())
+    }
+
+    fn add_and_verify_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, V)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()> {
         for (key, value) in chunk {
             if let Some(ref prev_leaf) = self.previous_leaf {
                 ensure!(
@@ -301,13 +326,7 @@ where
         }
 
         // Verify what we have added so far is all correct.
-        self.verify(proof)?;
-
-        // Write the frozen nodes to storage.
-        self.store.write_node_batch(&self.frozen_nodes)?;
-        self.frozen_nodes.clear();
-
-        Ok(())
+        self.verify(proof)
     }
 
     /// Restores one account.
@@ -649,32 +668,47 @@ where
 
     /// Finishes the restoration process. This tells the code that there is no more account,
     /// otherwise we can not freeze the rightmost leaf and its ancestors.
+    ///
+    /// The root node is only written if its hash is the expected root hash: a chunk proven with
+    /// accounts on its right can't be passed off as the last one.
     pub fn finish(mut self) -> Result<()> {
         // Deal with the special case when the entire tree has a single leaf.
+        let mut single_leaf = None;
         if self.partial_nodes.len() == 1 {
             let mut num_children = 0;
-            let mut leaf = None;
             for i in 0..16 {
                 if let Some(ref child_info) = self.partial_nodes[0].children[i] {
                     num_children += 1;
                     if let ChildInfo::Leaf { node } = child_info {
-                        leaf = Some(node.clone());
+                        single_leaf = Some(node.clone());
                     }
                 }
             }
+            if num_children != 1 {
+                single_leaf = None;
+            }
+        }
 
-            if num_children == 1 {
-                if let Some(node) = leaf {
-                    let node_key = NodeKey::new_empty_path(self.version);
-                    assert!(self.frozen_nodes.is_empty());
-                    self.frozen_nodes.insert(node_key, node.into());
-                    self.store.write_node_batch(&self.frozen_nodes)?;
-                    return Ok(());
-                }
+        let root_key = NodeKey::new_empty_path(self.version);
+        match single_leaf {
+            Some(node) => {
+                assert!(self.frozen_nodes.is_empty());
+                self.frozen_nodes.insert(root_key.clone(), node.into());
             }
+            None => self.freeze(0),
         }
 
-        self.freeze(0);
+        let root_hash = self
+            .frozen_nodes
+            .get(&root_key)
+            .ok_or_else(|| format_err!("The root node must be frozen."))?
+            .hash();
+        ensure!(
+            root_hash == self.expected_root_hash,
+            "Root hashes do not match. Actual root hash: {:x}. Expected root hash: {:x}.",
+            root_hash,
+            self.expected_root_hash,
+        );
         self.store.write_node_batch(&self.frozen_nodes)
     }
 }
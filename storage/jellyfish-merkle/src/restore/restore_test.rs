@@ -78,6 +78,61 @@ proptest! {
         assert_success(&restore_db, expected_root_hash, &all, version);
     }
 
+    #[test]
+    fn test_restore_after_invalid_chunk(
+        btree in btree_map(any::<HashValue>(), any::<ValueBlob>(), 2..1000),
+        target_version in 0u64..2000,
+    ) {
+        let (db, source_version) = init_mock_db(&btree.clone().into_iter().collect());
+        let tree = JellyfishMerkleTree::new(&db);
+        let expected_root_hash = tree.get_root_hash(source_version).unwrap();
+
+        let restore_db = Arc::new(MockTreeStore::default());
+        let mut restore =
+            JellyfishMerkleRestore::new(Arc::clone(&restore_db), target_version, expected_root_hash).unwrap();
+        let keys: Vec<_> = btree.keys().cloned().collect();
+        for (index, (key, value)) in btree.iter().enumerate() {
+            // A chunk with the proof of another account is rejected, the valid chunk is then added.
+            let wrong_key = keys[(index + 1) % keys.len()];
+            let wrong_proof = tree.get_range_proof(wrong_key, source_version).unwrap();
+            prop_assert!(restore.add_chunk(vec![(*key, value.clone())], wrong_proof).is_err());
+
+            let proof = tree.get_range_proof(*key, source_version).unwrap();
+            restore.add_chunk(vec![(*key, value.clone())], proof).unwrap();
+        }
+        restore.finish().unwrap();
+
+        assert_success(&restore_db, expected_root_hash, &btree, target_version);
+    }
+
+    #[test]
+    fn test_finish_truncated_restore(
+        (all, batch1_size) in btree_map(any::<HashValue>(), any::<ValueBlob>(), 2..1000)
+            .prop_flat_map(|btree| {
+                let len = btree.len();
+                (Just(btree), 1..len)
+            })
+    ) {
+        let (db, version) = init_mock_db(&all.clone().into_iter().collect());
+        let tree = JellyfishMerkleTree::new(&db);
+        let expected_root_hash = tree.get_root_hash(version).unwrap();
+        let batch1: Vec<_> = all.into_iter().take(batch1_size).collect();
+
+        // The first accounts verify against their proof, but they aren't the whole tree.
+        let restore_db = Arc::new(MockTreeStore::default());
+        let mut restore =
+            JellyfishMerkleRestore::new(Arc::clone(&restore_db), version, expected_root_hash).unwrap();
+        let proof = tree
+            .get_range_proof(batch1.last().map(|(key, _value)| *key).unwrap(), version)
+            .unwrap();
+        restore.add_chunk(batch1, proof).unwrap();
+        prop_assert!(restore.finish().is_err());
+        prop_assert_eq!(
+            JellyfishMerkleTree::new(&*restore_db).get_root_hash_option(version).unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn test_overwrite(
         btree1 in btree_map(any::<HashValue>(), any::<ValueBlob>(), 1..1000),
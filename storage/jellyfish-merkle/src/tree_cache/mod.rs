@@ -177,6 +177,21 @@ where
         })
     }
 
+    /// Constructs a new `TreeCache` instance building the tree of `next_version` on top of the
+    /// tree of `base_version`, which doesn't have to be `next_version - 1`.
+    pub fn new_on_base(reader: &'a R, base_version: Version, next_version: Version) -> Self {
+        Self {
+            node_cache: HashMap::new(),
+            stale_node_index_cache: HashSet::new(),
+            frozen_cache: FrozenTreeCache::new(),
+            root_node_key: NodeKey::new_empty_path(base_version),
+            next_version,
+            reader,
+            num_stale_leaves: 0,
+            num_new_leaves: 0,
+        }
+    }
+
     /// Gets a node with given node key. If it doesn't exist in node cache, read from `reader`.
     pub fn get_node(&self, node_key: &NodeKey) -> Result<Node<V>> {
         Ok(if let Some(node) = self.node_cache.get(node_key) {
@@ -16,6 +16,7 @@
 mod metrics;
 #[macro_use]
 pub mod schema;
+pub mod migration;
 
 use crate::{
     metrics::{
@@ -32,6 +33,10 @@ use std::{
     iter::Iterator,
     marker::PhantomData,
     path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Condvar, Mutex,
+    },
 };
 
 /// Type alias to `rocksdb::ReadOptions`. See [`rocksdb doc`](https://github.com/pingcap/rust-rocksdb/blob/master/src/rocksdb_options.rs)
@@ -40,6 +45,14 @@ pub type ReadOptions = rocksdb::ReadOptions;
 /// Type alias to `rocksdb::Options`.
 pub type Options = rocksdb::Options;
 
+/// Type alias to `rocksdb::BlockBasedOptions`, the options of the SST files of a column family.
+pub type BlockBasedOptions = rocksdb::BlockBasedOptions;
+
+/// Type alias to `rocksdb::Cache`, a block cache that can be shared by column families.
+pub type Cache = rocksdb::Cache;
+
+pub use rocksdb::{DBCompactionStyle, DBCompressionType};
+
 /// Type alias to improve readability.
 pub type ColumnFamilyName = &'static str;
 
@@ -195,6 +208,43 @@ pub struct DB {
     name: &'static str, // for logging
     inner: rocksdb::DB,
     column_families: Vec<ColumnFamilyName>,
+    deferred_writes: DeferredWrites,
+}
+
+/// Counts the writes announced by [`DB::defer_write`] and the ones done by [`DB::write_deferred`],
+/// so that the reads and the other writes can wait for the ones announced before them.
+#[derive(Debug, Default)]
+struct DeferredWrites {
+    num_deferred: AtomicU64,
+    num_done: AtomicU64,
+    lock: Mutex<()>,
+    done: Condvar,
+}
+
+impl DeferredWrites {
+    fn defer(&self) {
+        self.num_deferred.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Waits for the writes deferred so far to be done.
+    fn wait(&self) {
+        let num_deferred = self.num_deferred.load(Ordering::SeqCst);
+        if self.num_done.load(Ordering::SeqCst) >= num_deferred {
+            return;
+        }
+        let mut guard = self.lock.lock().expect("Lock should not be poisoned.");
+        while self.num_done.load(Ordering::SeqCst) < num_deferred {
+            guard = self.done.wait(guard).expect("Lock should not be poisoned.");
+        }
+    }
+
+    fn finish(&self) {
+        {
+            let _guard = self.lock.lock().expect("Lock should not be poisoned.");
+            self.num_done.fetch_add(1, Ordering::SeqCst);
+        }
+        self.done.notify_all();
+    }
 }
 
 impl DB {
@@ -205,9 +255,27 @@ impl DB {
         name: &'static str,
         column_families: Vec<ColumnFamilyName>,
         db_opts: &rocksdb::Options,
+    ) -> Result<Self> {
+        Self::open_with_cf_opts(
+            path,
+            name,
+            column_families
+                .into_iter()
+                .map(|cf_name| (cf_name, default_cf_options()))
+                .collect(),
+            db_opts,
+        )
+    }
+
+    /// Like `open`, but with the options of each column family instead of `default_cf_options`.
+    pub fn open_with_cf_opts(
+        path: impl AsRef<Path>,
+        name: &'static str,
+        column_families: Vec<(ColumnFamilyName, rocksdb::Options)>,
+        db_opts: &rocksdb::Options,
     ) -> Result<Self> {
         {
-            let cfs_set: HashSet<_> = column_families.iter().collect();
+            let cfs_set: HashSet<_> = column_families.iter().map(|(cf_name, _)| cf_name).collect();
             ensure!(
                 cfs_set.contains(&DEFAULT_CF_NAME),
                 "No \"default\" column family name is provided.",
@@ -248,22 +316,42 @@ impl DB {
         DB::open_cf_as_secondary(db_opts, primary_path, secondary_path, name, column_families)
     }
 
+    /// Lists the column families of the DB at `path`, e.g. for a DB opened readonly, which can't
+    /// create the missing ones, to only open those it has.
+    pub fn list_cf(db_opts: &rocksdb::Options, path: impl AsRef<Path>) -> Result<Vec<String>> {
+        Ok(rocksdb::DB::list_cf(db_opts, path)?)
+    }
+
+    /// Whether the DB was opened with the column family.
+    pub fn has_cf(&self, cf_name: &str) -> bool {
+        self.inner.cf_handle(cf_name).is_some()
+    }
+
+    /// Catches up a DB opened by `open_as_secondary` with the primary, replaying the WAL and the
+    /// new SST files of the primary written since the last catch-up.
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.inner.try_catch_up_with_primary()?;
+        Ok(())
+    }
+
     fn open_cf(
         db_opts: &rocksdb::Options,
         path: impl AsRef<Path>,
         name: &'static str,
-        column_families: Vec<ColumnFamilyName>,
+        column_families: Vec<(ColumnFamilyName, rocksdb::Options)>,
     ) -> Result<DB> {
+        let cf_names = column_families
+            .iter()
+            .map(|(cf_name, _)| *cf_name)
+            .collect();
         let inner = rocksdb::DB::open_cf_descriptors(
             db_opts,
             path,
-            column_families.iter().map(|cf_name| {
-                let mut cf_opts = rocksdb::Options::default();
-                cf_opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
-                rocksdb::ColumnFamilyDescriptor::new((*cf_name).to_string(), cf_opts)
+            column_families.into_iter().map(|(cf_name, cf_opts)| {
+                rocksdb::ColumnFamilyDescriptor::new(cf_name.to_string(), cf_opts)
             }),
         )?;
-        Ok(Self::log_construct(name, column_families, inner))
+        Ok(Self::log_construct(name, cf_names, inner))
     }
 
     fn open_cf_readonly(
@@ -310,6 +398,7 @@ impl DB {
             name,
             inner,
             column_families,
+            deferred_writes: DeferredWrites::default(),
         }
     }
 
@@ -322,6 +411,7 @@ impl DB {
         let k = <S::Key as KeyCodec<S>>::encode_key(schema_key)?;
         let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
 
+        self.deferred_writes.wait();
         let result = self.inner.get_cf(cf_handle, &k)?;
         DIEM_SCHEMADB_GET_BYTES
             .with_label_values(&[S::COLUMN_FAMILY_NAME])
@@ -354,6 +444,7 @@ impl DB {
         let raw_end = end.encode_seek_key()?;
         let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
 
+        self.deferred_writes.wait();
         self.inner
             .delete_range_cf(cf_handle, &raw_begin, &raw_end)?;
         Ok(())
@@ -365,6 +456,7 @@ impl DB {
         direction: ScanDirection,
     ) -> Result<SchemaIterator<S>> {
         let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
+        self.deferred_writes.wait();
         Ok(SchemaIterator::new(
             self.inner.raw_iterator_cf_opt(cf_handle, opts),
             direction,
@@ -383,6 +475,27 @@ impl DB {
 
     /// Writes a group of records wrapped in a [`SchemaBatch`].
     pub fn write_schemas(&self, batch: SchemaBatch) -> Result<()> {
+        self.deferred_writes.wait();
+        self.write_schemas_impl(batch)
+    }
+
+    /// Announces a write to be done later, likely on another thread, by [`DB::write_deferred`].
+    /// Until it's done, the reads and the other writes wait for it, so that the records look
+    /// written from now on.
+    pub fn defer_write(&self) {
+        self.deferred_writes.defer()
+    }
+
+    /// Does the write announced by an earlier [`DB::defer_write`], releasing the reads and writes
+    /// waiting for it even if it fails. The deferred writes are done in the order they were
+    /// announced.
+    pub fn write_deferred(&self, batch: SchemaBatch) -> Result<()> {
+        let res = self.write_schemas_impl(batch);
+        self.deferred_writes.finish();
+        res
+    }
+
+    fn write_schemas_impl(&self, batch: SchemaBatch) -> Result<()> {
         let _timer = DIEM_SCHEMADB_BATCH_COMMIT_LATENCY_SECONDS
             .with_label_values(&[self.name])
             .start_timer();
@@ -453,6 +566,23 @@ impl DB {
                 )
             })
     }
+
+    /// Returns whether the column family has no record at all.
+    pub fn is_cf_empty(&self, cf_name: &str) -> Result<bool> {
+        let cf_handle = self.get_cf_handle(cf_name)?;
+        Ok(self
+            .inner
+            .iterator_cf(cf_handle, rocksdb::IteratorMode::Start)
+            .next()
+            .is_none())
+    }
+}
+
+/// Options of the column families opened by `DB::open`.
+pub fn default_cf_options() -> rocksdb::Options {
+    let mut cf_opts = rocksdb::Options::default();
+    cf_opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
+    cf_opts
 }
 
 /// For now we always use synchronous writes. This makes sure that once the operation returns
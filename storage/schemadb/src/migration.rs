@@ -0,0 +1,266 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module keeps the version of the format of the data in each column family, and migrates
+//! the data of a column family from an older version to the one the code expects on open.
+//!
+//! The versions are stored in the column family named [`SCHEMA_VERSION_CF_NAME`], which must be
+//! opened along with the others for write. A DB written before the versions were kept doesn't have
+//! it, so a readonly DB may be opened without it. A migration is run in steps, each written atomically along with
+//! a checkpoint to resume from, so that a large migration interrupted by a crash or a shutdown
+//! resumes on the next open instead of starting over.
+
+use crate::{
+    schema::{KeyCodec, ValueCodec},
+    ColumnFamilyName, SchemaBatch, DB,
+};
+use anyhow::{bail, ensure, format_err, Result};
+use diem_logger::prelude::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryInto,
+    mem::size_of,
+};
+
+/// The column family keeping the schema version of every other column family.
+pub const SCHEMA_VERSION_CF_NAME: ColumnFamilyName = "schema_version";
+
+define_schema!(
+    SchemaVersionSchema,
+    String,
+    SchemaVersionRecord,
+    SCHEMA_VERSION_CF_NAME
+);
+
+/// The schema version of a column family, with the checkpoint of the migration from it to the
+/// next version if that one is ongoing.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct SchemaVersionRecord {
+    version: u64,
+    checkpoint: Option<Vec<u8>>,
+}
+
+impl KeyCodec<SchemaVersionSchema> for String {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(self.as_bytes().to_vec())
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        Ok(String::from_utf8(data.to_vec())?)
+    }
+}
+
+impl ValueCodec<SchemaVersionSchema> for SchemaVersionRecord {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        let mut encoded = self.version.to_be_bytes().to_vec();
+        if let Some(checkpoint) = &self.checkpoint {
+            encoded.push(1);
+            encoded.extend_from_slice(checkpoint);
+        } else {
+            encoded.push(0);
+        }
+        Ok(encoded)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        ensure!(
+            data.len() > size_of::<u64>(),
+            "Unexpected data len {}, expected to be greater than {}.",
+            data.len(),
+            size_of::<u64>(),
+        );
+        let version = u64::from_be_bytes(data[..size_of::<u64>()].try_into()?);
+        let checkpoint = match data[size_of::<u64>()] {
+            0 => None,
+            1 => Some(data[size_of::<u64>() + 1..].to_vec()),
+            flag => bail!("Unexpected checkpoint flag {}.", flag),
+        };
+        Ok(Self {
+            version,
+            checkpoint,
+        })
+    }
+}
+
+/// Migrates the data of a column family from `from_version()` to `from_version() + 1`.
+pub trait Migration {
+    /// The column family migrated.
+    fn cf_name(&self) -> ColumnFamilyName;
+
+    /// The schema version migrated from.
+    fn from_version(&self) -> u64;
+
+    /// Puts the writes migrating the next part of the data into `batch`, resuming from
+    /// `checkpoint`, which is `None` at the start of the migration.
+    ///
+    /// Returns the checkpoint to resume the next step from, or `None` if the migration is done
+    /// with this step. The batch is written atomically along with the returned checkpoint, so a
+    /// step must keep the batch small enough to be written at once.
+    fn migrate_step(
+        &self,
+        db: &DB,
+        checkpoint: Option<&[u8]>,
+        batch: &mut SchemaBatch,
+    ) -> Result<Option<Vec<u8>>>;
+}
+
+/// The schema versions the code expects, by column family, along with the migrations bringing
+/// the data of older versions up to them.
+#[derive(Default)]
+pub struct SchemaVersionRegistry {
+    versions: BTreeMap<ColumnFamilyName, u64>,
+    migrations: HashMap<(ColumnFamilyName, u64), Box<dyn Migration>>,
+}
+
+impl SchemaVersionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expects the column family to be at `version`. A column family never registered is not
+    /// versioned.
+    pub fn register(&mut self, cf_name: ColumnFamilyName, version: u64) -> &mut Self {
+        self.versions.insert(cf_name, version);
+        self
+    }
+
+    /// Adds a migration for a registered column family, from a version lower than the registered
+    /// one.
+    pub fn add_migration(&mut self, migration: Box<dyn Migration>) -> Result<&mut Self> {
+        let cf_name = migration.cf_name();
+        let from_version = migration.from_version();
+        let version = *self
+            .versions
+            .get(cf_name)
+            .ok_or_else(|| format_err!("Column family {} is not registered.", cf_name))?;
+        ensure!(
+            from_version < version,
+            "Migration of column family {} from version {} is beyond registered version {}.",
+            cf_name,
+            from_version,
+            version,
+        );
+        ensure!(
+            !self.migrations.contains_key(&(cf_name, from_version)),
+            "Duplicate migration of column family {} from version {}.",
+            cf_name,
+            from_version,
+        );
+        self.migrations.insert((cf_name, from_version), migration);
+        Ok(self)
+    }
+
+    /// Migrates every registered column family up to its registered version, resuming the ongoing
+    /// migrations from their checkpoints.
+    ///
+    /// An empty column family without a stored version is taken as being at the registered
+    /// version, a non-empty one as being at version 0, i.e. written before it was versioned.
+    pub fn migrate(&self, db: &DB) -> Result<()> {
+        for (&cf_name, &version) in &self.versions {
+            let mut record = self.stored_record(db, cf_name, version)?;
+            let mut resumed = record.checkpoint.is_some();
+            while record.version < version {
+                let migration = self.migration(cf_name, record.version)?;
+                if record.checkpoint.is_none() || resumed {
+                    info!(
+                        cf_name = cf_name,
+                        from_version = record.version,
+                        resumed = resumed,
+                        "Migrating column family.",
+                    );
+                    resumed = false;
+                }
+
+                let mut batch = SchemaBatch::new();
+                let checkpoint =
+                    migration.migrate_step(db, record.checkpoint.as_deref(), &mut batch)?;
+                record = match checkpoint {
+                    Some(_) => SchemaVersionRecord {
+                        version: record.version,
+                        checkpoint,
+                    },
+                    None => {
+                        info!(
+                            cf_name = cf_name,
+                            version = record.version + 1,
+                            "Migrated column family.",
+                        );
+                        SchemaVersionRecord {
+                            version: record.version + 1,
+                            checkpoint: None,
+                        }
+                    }
+                };
+                batch.put::<SchemaVersionSchema>(&cf_name.to_string(), &record)?;
+                db.write_schemas(batch)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every registered column family is at its registered version, for a DB opened
+    /// readonly, which can't be migrated. The DB may be opened without the column family of the
+    /// versions, or without some registered ones, which are then taken as empty.
+    pub fn check(&self, db: &DB) -> Result<()> {
+        for (&cf_name, &version) in &self.versions {
+            let record = if db.has_cf(SCHEMA_VERSION_CF_NAME) {
+                db.get::<SchemaVersionSchema>(&cf_name.to_string())?
+            } else {
+                None
+            };
+            let stored_version = match record {
+                Some(record) => record.version,
+                None if !db.has_cf(cf_name) || db.is_cf_empty(cf_name)? => version,
+                None => 0,
+            };
+            ensure!(
+                stored_version == version,
+                "Column family {} is at version {}, expected version {}. Open the DB for write \
+                to migrate it.",
+                cf_name,
+                stored_version,
+                version,
+            );
+        }
+        Ok(())
+    }
+
+    fn migration(&self, cf_name: ColumnFamilyName, from_version: u64) -> Result<&dyn Migration> {
+        self.migrations
+            .get(&(cf_name, from_version))
+            .map(|migration| &**migration)
+            .ok_or_else(|| {
+                format_err!(
+                    "No migration of column family {} from version {}.",
+                    cf_name,
+                    from_version,
+                )
+            })
+    }
+
+    /// Gets the stored version of the column family, storing it first if it's not stored yet.
+    fn stored_record(
+        &self,
+        db: &DB,
+        cf_name: ColumnFamilyName,
+        version: u64,
+    ) -> Result<SchemaVersionRecord> {
+        if let Some(record) = db.get::<SchemaVersionSchema>(&cf_name.to_string())? {
+            ensure!(
+                record.version <= version,
+                "Column family {} is at version {}, newer than expected version {}.",
+                cf_name,
+                record.version,
+                version,
+            );
+            return Ok(record);
+        }
+
+        let record = SchemaVersionRecord {
+            version: if db.is_cf_empty(cf_name)? { version } else { 0 },
+            checkpoint: None,
+        };
+        db.put::<SchemaVersionSchema>(&cf_name.to_string(), &record)?;
+        Ok(record)
+    }
+}
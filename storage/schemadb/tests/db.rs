@@ -9,6 +9,7 @@ use schemadb::{
     schema::{KeyCodec, Schema, ValueCodec},
     ColumnFamilyName, SchemaBatch, DB, DEFAULT_CF_NAME,
 };
+use std::{sync::Arc, thread, time::Duration};
 
 // Creating two schemas that share exactly the same structure but are stored in different column
 // families. Also note that the key and value are of the same type `TestField`. By implementing
@@ -319,6 +320,31 @@ fn test_reopen() {
     }
 }
 
+#[test]
+fn test_deferred_write() {
+    let tmpdir = diem_temppath::TempPath::new();
+    let db = Arc::new(open_db(&tmpdir));
+    db.defer_write();
+
+    let writer = {
+        let db = Arc::clone(&db);
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            let mut db_batch = SchemaBatch::new();
+            db_batch
+                .put::<TestSchema1>(&TestField(0), &TestField(0))
+                .unwrap();
+            db.write_deferred(db_batch).unwrap();
+        })
+    };
+    // The read waits for the deferred write.
+    assert_eq!(
+        db.get::<TestSchema1>(&TestField(0)).unwrap(),
+        Some(TestField(0)),
+    );
+    writer.join().unwrap();
+}
+
 #[test]
 fn test_open_read_only() {
     let tmpdir = diem_temppath::TempPath::new();
@@ -0,0 +1,311 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{bail, Result};
+use byteorder::{BigEndian, ReadBytesExt};
+use schemadb::{
+    define_schema,
+    migration::{Migration, SchemaVersionRegistry, SCHEMA_VERSION_CF_NAME},
+    schema::{KeyCodec, Schema, ValueCodec},
+    ColumnFamilyName, ReadOptions, SchemaBatch, DB, DEFAULT_CF_NAME,
+};
+use std::{
+    convert::TryInto,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+define_schema!(TestSchema, TestField, TestField, "TestCF");
+
+#[derive(Debug, Eq, PartialEq)]
+struct TestField(u32);
+
+impl TestField {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+
+    fn from_bytes(data: &[u8]) -> Result<Self> {
+        let mut reader = std::io::Cursor::new(data);
+        Ok(TestField(reader.read_u32::<BigEndian>()?))
+    }
+}
+
+impl KeyCodec<TestSchema> for TestField {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(self.to_bytes())
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        Self::from_bytes(data)
+    }
+}
+
+impl ValueCodec<TestSchema> for TestField {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(self.to_bytes())
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Self::from_bytes(data)
+    }
+}
+
+const NUM_KEYS: u32 = 5;
+const KEYS_PER_STEP: usize = 2;
+
+/// Migrates `TestCF` from version 0 to 1 by doubling the values, `KEYS_PER_STEP` keys at a time,
+/// failing after `fail_after_steps` steps if set.
+struct DoubleValues {
+    num_steps: Arc<AtomicUsize>,
+    fail_after_steps: Option<usize>,
+}
+
+impl Migration for DoubleValues {
+    fn cf_name(&self) -> ColumnFamilyName {
+        TestSchema::COLUMN_FAMILY_NAME
+    }
+
+    fn from_version(&self) -> u64 {
+        0
+    }
+
+    fn migrate_step(
+        &self,
+        db: &DB,
+        checkpoint: Option<&[u8]>,
+        batch: &mut SchemaBatch,
+    ) -> Result<Option<Vec<u8>>> {
+        let num_steps = self.num_steps.fetch_add(1, Ordering::SeqCst);
+        if self.fail_after_steps == Some(num_steps) {
+            bail!("Injected failure.");
+        }
+
+        let mut iter = db.iter::<TestSchema>(ReadOptions::default())?;
+        match checkpoint {
+            Some(key) => iter.seek(&TestField(u32::from_be_bytes(key.try_into()?)))?,
+            None => iter.seek_to_first(),
+        }
+        for res in iter.by_ref().take(KEYS_PER_STEP) {
+            let (key, value) = res?;
+            batch.put::<TestSchema>(&key, &TestField(value.0 * 2))?;
+        }
+        Ok(match iter.next().transpose()? {
+            Some((key, _)) => Some(key.to_bytes()),
+            None => None,
+        })
+    }
+}
+
+/// Migrates `TestCF` from version 1 to 2 by incrementing the values, all at once.
+struct IncrementValues;
+
+impl Migration for IncrementValues {
+    fn cf_name(&self) -> ColumnFamilyName {
+        TestSchema::COLUMN_FAMILY_NAME
+    }
+
+    fn from_version(&self) -> u64 {
+        1
+    }
+
+    fn migrate_step(
+        &self,
+        db: &DB,
+        _checkpoint: Option<&[u8]>,
+        batch: &mut SchemaBatch,
+    ) -> Result<Option<Vec<u8>>> {
+        let mut iter = db.iter::<TestSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        for res in iter {
+            let (key, value) = res?;
+            batch.put::<TestSchema>(&key, &TestField(value.0 + 1))?;
+        }
+        Ok(None)
+    }
+}
+
+fn open_db(dir: &diem_temppath::TempPath) -> DB {
+    let mut db_opts = rocksdb::Options::default();
+    db_opts.create_if_missing(true);
+    db_opts.create_missing_column_families(true);
+    DB::open(
+        &dir.path(),
+        "test",
+        vec![
+            DEFAULT_CF_NAME,
+            SCHEMA_VERSION_CF_NAME,
+            TestSchema::COLUMN_FAMILY_NAME,
+        ],
+        &db_opts,
+    )
+    .expect("Failed to open DB.")
+}
+
+fn put_values(db: &DB) {
+    for i in 0..NUM_KEYS {
+        db.put::<TestSchema>(&TestField(i), &TestField(i)).unwrap();
+    }
+}
+
+fn get_values(db: &DB) -> Vec<u32> {
+    (0..NUM_KEYS)
+        .map(|i| db.get::<TestSchema>(&TestField(i)).unwrap().unwrap().0)
+        .collect()
+}
+
+fn test_registry(
+    version: u64,
+    fail_after_steps: Option<usize>,
+) -> (SchemaVersionRegistry, Arc<AtomicUsize>) {
+    let num_steps = Arc::new(AtomicUsize::new(0));
+    let mut registry = SchemaVersionRegistry::new();
+    registry.register(TestSchema::COLUMN_FAMILY_NAME, version);
+    if version > 0 {
+        registry
+            .add_migration(Box::new(DoubleValues {
+                num_steps: Arc::clone(&num_steps),
+                fail_after_steps,
+            }))
+            .unwrap();
+    }
+    if version > 1 {
+        registry.add_migration(Box::new(IncrementValues)).unwrap();
+    }
+    (registry, num_steps)
+}
+
+#[test]
+fn test_new_cf_starts_at_registered_version() {
+    let tmpdir = diem_temppath::TempPath::new();
+    let db = open_db(&tmpdir);
+
+    let (registry, num_steps) = test_registry(2, None);
+    assert!(registry.check(&db).is_ok());
+    registry.migrate(&db).unwrap();
+    assert_eq!(num_steps.load(Ordering::SeqCst), 0);
+
+    // The version is stored, the data written from now on doesn't make it version 0.
+    put_values(&db);
+    assert!(registry.check(&db).is_ok());
+    registry.migrate(&db).unwrap();
+    assert_eq!(get_values(&db), vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn test_migrate_unversioned_cf() {
+    let tmpdir = diem_temppath::TempPath::new();
+    let db = open_db(&tmpdir);
+    put_values(&db);
+
+    let (registry, num_steps) = test_registry(2, None);
+    assert!(registry.check(&db).is_err());
+    registry.migrate(&db).unwrap();
+    assert_eq!(num_steps.load(Ordering::SeqCst), 3);
+    assert_eq!(get_values(&db), vec![1, 3, 5, 7, 9]);
+    assert!(registry.check(&db).is_ok());
+
+    // Migrating again is a no-op.
+    registry.migrate(&db).unwrap();
+    assert_eq!(get_values(&db), vec![1, 3, 5, 7, 9]);
+}
+
+#[test]
+fn test_resume_interrupted_migration() {
+    let tmpdir = diem_temppath::TempPath::new();
+    {
+        let db = open_db(&tmpdir);
+        put_values(&db);
+        let (registry, _) = test_registry(1, Some(1));
+        assert!(registry.migrate(&db).is_err());
+        // Only the first step is written.
+        assert_eq!(get_values(&db), vec![0, 2, 2, 3, 4]);
+    }
+    {
+        let db = open_db(&tmpdir);
+        let (registry, num_steps) = test_registry(1, None);
+        assert!(registry.check(&db).is_err());
+        registry.migrate(&db).unwrap();
+        // The migration resumed from the checkpoint, every value is doubled exactly once.
+        assert_eq!(num_steps.load(Ordering::SeqCst), 2);
+        assert_eq!(get_values(&db), vec![0, 2, 4, 6, 8]);
+        assert!(registry.check(&db).is_ok());
+
+        // Further versions are migrated to from there.
+        let (registry, _) = test_registry(2, None);
+        registry.migrate(&db).unwrap();
+        assert_eq!(get_values(&db), vec![1, 3, 5, 7, 9]);
+    }
+}
+
+#[test]
+fn test_migration_errors() {
+    let tmpdir = diem_temppath::TempPath::new();
+    let db = open_db(&tmpdir);
+    put_values(&db);
+
+    // No migration from version 0.
+    let mut registry = SchemaVersionRegistry::new();
+    registry.register(TestSchema::COLUMN_FAMILY_NAME, 1);
+    assert!(registry.migrate(&db).is_err());
+
+    // Migrations must be of registered column families, to lower versions, and unique.
+    let mut registry = SchemaVersionRegistry::new();
+    assert!(registry.add_migration(Box::new(IncrementValues)).is_err());
+    registry.register(TestSchema::COLUMN_FAMILY_NAME, 1);
+    assert!(registry.add_migration(Box::new(IncrementValues)).is_err());
+    registry.register(TestSchema::COLUMN_FAMILY_NAME, 2);
+    assert!(registry.add_migration(Box::new(IncrementValues)).is_ok());
+    assert!(registry.add_migration(Box::new(IncrementValues)).is_err());
+
+    // The data is newer than the code.
+    let (registry, _) = test_registry(2, None);
+    registry.migrate(&db).unwrap();
+    let (registry, _) = test_registry(1, None);
+    assert!(registry.migrate(&db).is_err());
+    assert!(registry.check(&db).is_err());
+}
+
+#[test]
+fn test_check_readonly_db_without_versions() {
+    let tmpdir = diem_temppath::TempPath::new();
+    {
+        // A DB written before the versions were kept
+        let mut db_opts = rocksdb::Options::default();
+        db_opts.create_if_missing(true);
+        db_opts.create_missing_column_families(true);
+        let db = DB::open(
+            &tmpdir.path(),
+            "test",
+            vec![DEFAULT_CF_NAME, TestSchema::COLUMN_FAMILY_NAME],
+            &db_opts,
+        )
+        .unwrap();
+        put_values(&db);
+    }
+
+    let db_opts = rocksdb::Options::default();
+    let cf_names = DB::list_cf(&db_opts, &tmpdir.path()).unwrap();
+    assert!(!cf_names
+        .iter()
+        .any(|cf_name| cf_name == SCHEMA_VERSION_CF_NAME));
+    let db = DB::open_readonly(
+        &tmpdir.path(),
+        "test_ro",
+        vec![DEFAULT_CF_NAME, TestSchema::COLUMN_FAMILY_NAME],
+        &db_opts,
+    )
+    .unwrap();
+    assert!(!db.has_cf(SCHEMA_VERSION_CF_NAME));
+
+    // The data is at version 0, and a registered column family the DB doesn't have is empty
+    let (registry, _) = test_registry(0, None);
+    assert!(registry.check(&db).is_ok());
+    let (registry, _) = test_registry(1, None);
+    assert!(registry.check(&db).is_err());
+    let mut registry = SchemaVersionRegistry::new();
+    registry.register("MissingCF", 1);
+    assert!(registry.check(&db).is_ok());
+}
@@ -10,13 +10,14 @@ use diem_types::{
     account_state_blob::{AccountStateBlob, AccountStateWithProof},
     contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
     epoch_change::EpochChangeProof,
+    epoch_checkpoint::EpochCheckpoint,
     epoch_state::EpochState,
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     move_resource::MoveStorage,
     proof::{
         definition::LeafCount, AccumulatorConsistencyProof, SparseMerkleProof,
-        TransactionAccumulatorSummary,
+        SparseMerkleRangeProof, TransactionAccumulatorSummary,
     },
     state_proof::StateProof,
     transaction::{
@@ -166,6 +167,49 @@ pub enum Order {
     Descending,
 }
 
+/// A page of the events of an event key, see [`DbReader::get_events_page`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EventPage {
+    /// The events with the versions of their transactions, in the requested order.
+    pub events: Vec<(Version, ContractEvent)>,
+    /// The sequence number the next page starts from, `None` once a descending read reached the
+    /// first event.
+    pub next_cursor: Option<u64>,
+}
+
+/// A chunk of the accounts of the state at a version, see
+/// [`DbReader::get_account_chunk_with_proof`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AccountStateChunkWithProof {
+    /// The accounts keyed by the hashes of their addresses, in increasing order of keys.
+    pub account_blobs: Vec<(HashValue, AccountStateBlob)>,
+    /// Proves the accounts, with all the accounts before them, against the state root.
+    pub proof: SparseMerkleRangeProof,
+    /// Whether the chunk ends with the last account of the state.
+    pub is_last_chunk: bool,
+}
+
+impl AccountStateChunkWithProof {
+    /// The key of the last account of the chunk, the next chunk starts after it.
+    pub fn last_key(&self) -> Option<HashValue> {
+        self.account_blobs.last().map(|(key, _blob)| *key)
+    }
+}
+
+/// Saves the chunks of accounts of a state snapshot, given in increasing order of keys, after
+/// checking them against the expected state root, see [`DbWriter::get_state_snapshot_receiver`].
+pub trait StateSnapshotReceiver: Send {
+    fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()>;
+
+    /// Saves the rest of the state tree once all the accounts are added, if its root hash is the
+    /// expected one.
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
 /// Trait that is implemented by a DB that supports certain public (to client) read APIs
 /// expected of a Diem DB
 pub trait DbReader: Send + Sync {
@@ -209,6 +253,21 @@ pub trait DbReader: Send + Sync {
         known_version: Option<u64>,
     ) -> Result<Vec<EventWithProof>>;
 
+    /// Returns at most `limit` events of `event_key` as of `ledger_version`, from sequence number
+    /// `start` on in `order`, reading the event index by key without building any proof. `None`
+    /// starts from the first event ascending and from the latest one descending, like a `start`
+    /// beyond the latest event does.
+    fn get_events_page(
+        &self,
+        _event_key: &EventKey,
+        _start: Option<u64>,
+        _order: Order,
+        _limit: u64,
+        _ledger_version: Version,
+    ) -> Result<EventPage> {
+        unimplemented!()
+    }
+
     /// See [`DiemDB::get_block_timestamp`].
     ///
     /// [`DiemDB::get_block_timestamp`]:
@@ -287,6 +346,22 @@ pub trait DbReader: Send + Sync {
         ledger_version: Version,
     ) -> Result<AccountTransactionsWithProof>;
 
+    /// Returns the latest transactions sent by an account with `address`, newest
+    /// first, from sequence number `seq_num` down or from the latest transaction
+    /// if `seq_num` is `None` or beyond it. Will return no more than `limit`
+    /// transactions. Will ignore transactions with `txn.version > ledger_version`.
+    /// Optionally fetch events for each transaction when `include_events` is `true`.
+    fn get_latest_account_transactions(
+        &self,
+        _address: AccountAddress,
+        _seq_num: Option<u64>,
+        _limit: u64,
+        _include_events: bool,
+        _ledger_version: Version,
+    ) -> Result<AccountTransactionsWithProof> {
+        unimplemented!()
+    }
+
     /// Returns proof of new state for a given ledger info with signatures relative to version known
     /// to client
     fn get_state_proof_with_ledger_info(
@@ -307,6 +382,22 @@ pub trait DbReader: Send + Sync {
         ledger_version: Version,
     ) -> Result<AccountStateWithProof>;
 
+    /// Returns the account states of `addresses` at `version` with proofs based on
+    /// `ledger_version`, in the order of `addresses`. Unlike calling
+    /// [`DbReader::get_account_state_with_proof`] once per address, the transaction info proof
+    /// and the state tree nodes the proofs have in common are only read once.
+    fn get_state_values_with_proofs(
+        &self,
+        addresses: &[AccountAddress],
+        version: Version,
+        ledger_version: Version,
+    ) -> Result<Vec<AccountStateWithProof>> {
+        addresses
+            .iter()
+            .map(|address| self.get_account_state_with_proof(*address, version, ledger_version))
+            .collect()
+    }
+
     // Gets an account state by account address, out of the ledger state indicated by the state
     // Merkle tree root with a sparse merkle proof proving state tree root.
     // See [`DiemDB::get_account_state_with_proof_by_version`].
@@ -337,6 +428,39 @@ pub trait DbReader: Send + Sync {
     /// Get the ledger info of the epoch that `known_version` belongs to.
     fn get_epoch_ending_ledger_info(&self, known_version: u64) -> Result<LedgerInfoWithSignatures>;
 
+    /// Gets the checkpoint saved at the end of `epoch`, `None` if the epoch is still open or ended
+    /// before the checkpoints were written.
+    fn get_epoch_checkpoint(&self, _epoch: u64) -> Result<Option<EpochCheckpoint>> {
+        unimplemented!()
+    }
+
+    /// Gets at most `chunk_size` accounts of the state at `version`, starting from the account
+    /// after `last_key`, or from the first account if `None`, with the proof of the chunk.
+    fn get_account_chunk_with_proof(
+        &self,
+        _version: Version,
+        _last_key: Option<HashValue>,
+        _chunk_size: usize,
+    ) -> Result<AccountStateChunkWithProof> {
+        unimplemented!()
+    }
+
+    /// Gets what a node needs on top of the state at `version` to continue from it: the latest
+    /// ledger info of the epoch of `version`, which must be at `version`, the TransactionInfo at
+    /// `version` and the frozen subtree roots of the transaction accumulator before it.
+    fn get_bootstrap_ledger(
+        &self,
+        _version: Version,
+    ) -> Result<(LedgerInfoWithSignatures, TransactionInfo, Vec<HashValue>)> {
+        unimplemented!()
+    }
+
+    /// Gets the version of the first transaction stored, if any. The transactions before it are
+    /// missing, e.g. on a node synced from a state snapshot.
+    fn get_first_txn_version(&self) -> Result<Option<Version>> {
+        unimplemented!()
+    }
+
     /// Gets the latest transaction info.
     /// N.B. Unlike get_startup_info(), even if the db is not bootstrapped, this can return `Some`
     /// -- those from a db-restore run.
@@ -464,6 +588,35 @@ pub trait DbWriter: Send + Sync {
         first_version: Version,
         ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
     ) -> Result<()>;
+
+    /// Gets the receiver saving the state snapshot at `version`, whose root hash must be
+    /// `expected_root_hash`. Called by state sync when syncing from a state snapshot.
+    fn get_state_snapshot_receiver(
+        &self,
+        _version: Version,
+        _expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>> {
+        unimplemented!()
+    }
+
+    /// Makes the state snapshot saved at the version of `ledger_info` the latest state, skipping
+    /// the transactions between the latest one and it. `epoch_endings` are the ledger infos ending
+    /// the epochs in between, `txn_info` the TransactionInfo at the version and `frozen_subtrees`
+    /// the frozen subtree roots of the transaction accumulator before it, all verified by the
+    /// caller.
+    /// See [`DiemDB::finalize_state_snapshot`].
+    ///
+    /// [`DiemDB::finalize_state_snapshot`]:
+    /// ../diemdb/struct.DiemDB.html#method.finalize_state_snapshot
+    fn finalize_state_snapshot(
+        &self,
+        _epoch_endings: &[LedgerInfoWithSignatures],
+        _ledger_info: &LedgerInfoWithSignatures,
+        _txn_info: &TransactionInfo,
+        _frozen_subtrees: &[HashValue],
+    ) -> Result<()> {
+        unimplemented!()
+    }
 }
 
 pub trait MoveDbReader:
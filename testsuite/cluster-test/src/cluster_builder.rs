@@ -397,6 +397,7 @@ impl ClusterBuilder {
                     None,
                     None,
                     true,
+                    false,
                     None,
                     None,
                 ))),
@@ -8,6 +8,7 @@ use std::{collections::BTreeMap, env};
 
 // List fuzz target modules here.
 mod consensus;
+mod crypto;
 mod executor;
 mod json_rpc_service;
 mod mempool;
@@ -26,6 +27,10 @@ static ALL_TARGETS: Lazy<BTreeMap<&'static str, Box<dyn FuzzTargetImpl>>> = Lazy
     let targets: Vec<Box<dyn FuzzTargetImpl>> = vec![
         // Consensus
         Box::new(consensus::ConsensusProposal::default()),
+        // Crypto
+        Box::new(crypto::BLS12381SignatureVerification::default()),
+        Box::new(crypto::BLS12381AggregateSignatureVerification::default()),
+        Box::new(crypto::VRFProofVerification::default()),
         // Executor
         Box::new(executor::ExecuteAndCommitBlocks::default()),
         Box::new(executor::ExecuteAndCommitChunk::default()),
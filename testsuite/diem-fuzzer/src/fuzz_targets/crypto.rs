@@ -0,0 +1,111 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::FuzzTargetImpl;
+use diem_crypto::{
+    bls12381::{keypair_strategy, BLS12381ProofOfPossession, BLS12381PublicKey, BLS12381Signature},
+    ecvrf::{self, VRFOutput, VRFProof, VRFPublicKey},
+    traits::{Signature, SigningKey},
+};
+use diem_proptest_helpers::ValueGenerator;
+use proptest::{collection::vec, prelude::*};
+
+#[derive(Clone, Debug, Default)]
+pub struct BLS12381SignatureVerification;
+
+impl FuzzTargetImpl for BLS12381SignatureVerification {
+    fn description(&self) -> &'static str {
+        "Crypto: BLS12-381 public key and signature deserialization and verification"
+    }
+
+    fn generate(&self, _idx: usize, gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        let (keypair, message) = gen.generate((keypair_strategy(), vec(any::<u8>(), 0..256)));
+        let signature = keypair.private_key.sign_arbitrary_message(&message);
+        Some(bcs::to_bytes(&(keypair.public_key, message, signature)).unwrap())
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        if let Ok((public_key, message, signature)) =
+            bcs::from_bytes::<(BLS12381PublicKey, Vec<u8>, BLS12381Signature)>(data)
+        {
+            let _ = signature.verify_arbitrary_msg(&message, &public_key);
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct BLS12381AggregateSignatureVerification;
+
+impl FuzzTargetImpl for BLS12381AggregateSignatureVerification {
+    fn description(&self) -> &'static str {
+        "Crypto: BLS12-381 proofs of possession and aggregate signature verification"
+    }
+
+    fn generate(&self, _idx: usize, gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        let (keypairs, message) =
+            gen.generate((vec(keypair_strategy(), 1..8), vec(any::<u8>(), 0..256)));
+        let public_keys: Vec<_> = keypairs
+            .iter()
+            .map(|keypair| keypair.public_key.clone())
+            .collect();
+        let proofs: Vec<_> = keypairs
+            .iter()
+            .map(|keypair| BLS12381ProofOfPossession::create(&keypair.private_key))
+            .collect();
+        let signatures: Vec<_> = keypairs
+            .iter()
+            .map(|keypair| keypair.private_key.sign_arbitrary_message(&message))
+            .collect();
+        let signature =
+            BLS12381Signature::aggregate(&signatures.iter().collect::<Vec<_>>()).unwrap();
+        Some(bcs::to_bytes(&(public_keys, proofs, message, signature)).unwrap())
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        let (public_keys, proofs, message, signature) = match bcs::from_bytes::<(
+            Vec<BLS12381PublicKey>,
+            Vec<BLS12381ProofOfPossession>,
+            Vec<u8>,
+            BLS12381Signature,
+        )>(data)
+        {
+            Ok(input) => input,
+            Err(_) => return,
+        };
+        if public_keys.len() != proofs.len()
+            || public_keys
+                .iter()
+                .zip(&proofs)
+                .any(|(public_key, proof)| proof.verify(public_key).is_err())
+        {
+            return;
+        }
+        let _ = signature
+            .verify_aggregate_arbitrary_msg(&message, &public_keys.iter().collect::<Vec<_>>());
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct VRFProofVerification;
+
+impl FuzzTargetImpl for VRFProofVerification {
+    fn description(&self) -> &'static str {
+        "Crypto: ECVRF public key and proof deserialization and verification"
+    }
+
+    fn generate(&self, _idx: usize, gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        let (keypair, alpha) = gen.generate((ecvrf::keypair_strategy(), vec(any::<u8>(), 0..256)));
+        let proof = keypair.private_key.prove(&alpha);
+        Some(bcs::to_bytes(&(keypair.public_key, alpha, proof)).unwrap())
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        if let Ok((public_key, alpha, proof)) =
+            bcs::from_bytes::<(VRFPublicKey, Vec<u8>, VRFProof)>(data)
+        {
+            if public_key.verify(&proof, &alpha).is_ok() {
+                let _ = VRFOutput::from(&proof);
+            }
+        }
+    }
+}
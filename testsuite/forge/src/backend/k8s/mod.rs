@@ -58,6 +58,7 @@ impl K8sFactory {
             Some(vault_cacert_contents),
             None,
             false,
+            false,
             None,
             None,
         );
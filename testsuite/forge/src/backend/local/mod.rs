@@ -13,6 +13,7 @@ use std::{
 
 mod cargo;
 mod node;
+mod partition;
 mod swarm;
 pub use node::LocalNode;
 pub use swarm::{LocalSwarm, LocalSwarmBuilder, SwarmDirectory};
@@ -124,6 +125,12 @@ impl LocalFactory {
         Self::with_revision_and_workspace(&merge_base)
     }
 
+    /// A builder of swarms of the versions of the factory, to configure the swarm further than
+    /// `new_swarm` does. The swarm it builds must then be launched.
+    pub fn swarm_builder(&self) -> LocalSwarmBuilder {
+        LocalSwarm::builder(self.versions.clone())
+    }
+
     pub fn new_swarm<R>(&self, rng: R, number_of_validators: NonZeroUsize) -> Result<LocalSwarm>
     where
         R: ::rand::RngCore + ::rand::CryptoRng,
@@ -135,6 +135,22 @@ impl LocalNode {
         &mut self.config
     }
 
+    /// Applies `update` to the config of the node and saves it. A running node is restarted for
+    /// the update to apply.
+    pub fn update_config<F>(&mut self, update: F) -> Result<()>
+    where
+        F: FnOnce(&mut NodeConfig),
+    {
+        update(&mut self.config);
+        let config_path = self.config_path();
+        self.config.save(config_path)?;
+        if self.process.is_some() {
+            self.stop();
+            self.start()?;
+        }
+        Ok(())
+    }
+
     pub fn upgrade(&mut self, version: LocalVersion) -> Result<()> {
         self.stop();
         self.version = version;
@@ -0,0 +1,362 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A SOCKS5 proxy through which the nodes of a `LocalSwarm` dial each other, so that the swarm can
+//! partition their network. Each node authenticates with its peer id as username, which tells the
+//! proxy who dials, and the port dialed tells it who is dialed. While the network is partitioned,
+//! the proxy refuses the connections between nodes on different sides, and cuts those already
+//! established. A validator and its fullnode share their peer id, so they are always on the same
+//! side.
+
+use crate::LocalNode;
+use anyhow::Result;
+use diem_config::config::{NodeConfig, Socks5ProxyConfig};
+use diem_logger::{debug, warn};
+use diem_sdk::types::{network_address::parse_tcp, PeerId};
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+    net::{
+        IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs,
+    },
+    sync::{Arc, Mutex},
+    thread,
+};
+
+const VERSION: u8 = 0x05;
+const USERNAME_PASSWORD: u8 = 0x02;
+const NO_ACCEPTABLE_METHODS: u8 = 0xFF;
+const USERNAME_PASSWORD_VERSION: u8 = 0x01;
+const CONNECT: u8 = 0x01;
+const IPV4: u8 = 0x01;
+const DOMAIN_NAME: u8 = 0x03;
+const IPV6: u8 = 0x04;
+
+// Replies
+const SUCCEEDED: u8 = 0x00;
+const GENERAL_FAILURE: u8 = 0x01;
+const NOT_ALLOWED: u8 = 0x02;
+const CONNECTION_REFUSED: u8 = 0x05;
+const COMMAND_NOT_SUPPORTED: u8 = 0x07;
+
+/// The proxy doesn't check passwords, but the nodes need one to send their username
+const PASSWORD: &str = "partition";
+
+#[derive(Debug, Default)]
+struct ProxyState {
+    /// Peer id of the node listening on each port
+    listeners: HashMap<u16, PeerId>,
+    /// Side of the partition of each node, if the network is partitioned
+    sides: Option<HashMap<PeerId, usize>>,
+    tunnels: HashMap<u64, Tunnel>,
+    next_tunnel_id: u64,
+    shutdown: bool,
+}
+
+impl ProxyState {
+    fn connected(&self, source: PeerId, destination: PeerId) -> bool {
+        if source == destination {
+            return true;
+        }
+        match &self.sides {
+            None => true,
+            Some(sides) => matches!(
+                (sides.get(&source), sides.get(&destination)),
+                (Some(source_side), Some(destination_side)) if source_side == destination_side
+            ),
+        }
+    }
+}
+
+/// A connection relayed between two nodes
+#[derive(Debug)]
+struct Tunnel {
+    source: PeerId,
+    destination: PeerId,
+    streams: [TcpStream; 2],
+}
+
+impl Tunnel {
+    fn cut(&self) {
+        for stream in &self.streams {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct PartitionProxy {
+    address: SocketAddr,
+    state: Arc<Mutex<ProxyState>>,
+}
+
+impl PartitionProxy {
+    pub fn start() -> Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(ProxyState::default()));
+
+        let accept_state = state.clone();
+        thread::Builder::new()
+            .name("partition-proxy".into())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    if accept_state.lock().unwrap().shutdown {
+                        break;
+                    }
+                    match stream {
+                        Ok(stream) => {
+                            let state = accept_state.clone();
+                            thread::spawn(move || {
+                                if let Err(error) = relay(stream, &state) {
+                                    debug!("Partition proxy dropped a connection: {}", error);
+                                }
+                            });
+                        }
+                        Err(error) => warn!("Partition proxy failed to accept: {}", error),
+                    }
+                }
+            })?;
+
+        Ok(Self { address, state })
+    }
+
+    /// Routes the outbound connections of `node` through the proxy, and tells the proxy the ports
+    /// `node` listens on. Saves the config of `node`, whose networks may have changed since, so
+    /// the node must be (re)started for it to apply.
+    pub fn route(&self, node: &mut LocalNode) -> Result<()> {
+        let peer_id = node.peer_id();
+        let config_path = node.config_path();
+        let config = node.config_mut();
+        self.route_config(peer_id, config);
+        config.save(config_path)?;
+        Ok(())
+    }
+
+    fn route_config(&self, peer_id: PeerId, config: &mut NodeConfig) {
+        let mut state = self.state.lock().unwrap();
+        let networks = config
+            .validator_network
+            .iter_mut()
+            .chain(config.full_node_networks.iter_mut());
+        for network in networks {
+            if let Some(((_host, port), _)) = parse_tcp(network.listen_address.as_slice()) {
+                state.listeners.insert(port, peer_id);
+            }
+            network.socks5_proxy_config = Some(Socks5ProxyConfig {
+                address: self.address.to_string(),
+                username: Some(peer_id.to_hex()),
+                password: Some(PASSWORD.to_string()),
+                enabled: true,
+            });
+        }
+    }
+
+    /// Partitions the network into `groups`, cutting the connections across them. The nodes in no
+    /// group are cut off from every other node.
+    pub fn partition(&self, groups: &[Vec<PeerId>]) {
+        let mut state = self.state.lock().unwrap();
+        state.sides = Some(
+            groups
+                .iter()
+                .enumerate()
+                .flat_map(|(side, group)| group.iter().map(move |peer_id| (*peer_id, side)))
+                .collect(),
+        );
+
+        let cut: Vec<_> = state
+            .tunnels
+            .iter()
+            .filter(|(_, tunnel)| !state.connected(tunnel.source, tunnel.destination))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in cut {
+            if let Some(tunnel) = state.tunnels.remove(&id) {
+                tunnel.cut();
+            }
+        }
+    }
+
+    /// Lets all the nodes connect to each other again.
+    pub fn heal(&self) {
+        self.state.lock().unwrap().sides = None;
+    }
+}
+
+impl Drop for PartitionProxy {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.shutdown = true;
+        for (_, tunnel) in state.tunnels.drain() {
+            tunnel.cut();
+        }
+        drop(state);
+        // Wake up the accepting thread, for it to see the shutdown
+        let _ = TcpStream::connect(self.address);
+    }
+}
+
+/// Runs the SOCKS5 handshake with the dialing node, then relays the traffic between it and the
+/// node it dials until either closes the connection or the partition cuts it.
+fn relay(mut client: TcpStream, state: &Mutex<ProxyState>) -> io::Result<()> {
+    let source = authenticate(&mut client)?;
+
+    let mut request = [0u8; 4];
+    client.read_exact(&mut request)?;
+    check_version(request[0])?;
+    let target = read_target(&mut client, request[3])?;
+    if request[1] != CONNECT {
+        reply(&mut client, COMMAND_NOT_SUPPORTED)?;
+        return Err(invalid_data(format!("unsupported command {}", request[1])));
+    }
+
+    let destination = state.lock().unwrap().listeners.get(&target.port()).copied();
+    if let Some(destination) = destination {
+        if !state.lock().unwrap().connected(source, destination) {
+            reply(&mut client, NOT_ALLOWED)?;
+            return Ok(());
+        }
+    }
+    let server = match TcpStream::connect(target) {
+        Ok(server) => server,
+        Err(error) => {
+            let code = if error.kind() == io::ErrorKind::ConnectionRefused {
+                CONNECTION_REFUSED
+            } else {
+                GENERAL_FAILURE
+            };
+            reply(&mut client, code)?;
+            return Err(error);
+        }
+    };
+    reply(&mut client, SUCCEEDED)?;
+
+    // The connections to unknown destinations can't be cut, as they don't belong to any side
+    let tunnel_id = match destination {
+        Some(destination) => {
+            let mut state = state.lock().unwrap();
+            let tunnel = Tunnel {
+                source,
+                destination,
+                streams: [client.try_clone()?, server.try_clone()?],
+            };
+            // The network may have been partitioned since the dial was allowed
+            if !state.connected(source, destination) {
+                tunnel.cut();
+                return Ok(());
+            }
+            let id = state.next_tunnel_id;
+            state.next_tunnel_id += 1;
+            state.tunnels.insert(id, tunnel);
+            Some(id)
+        }
+        None => None,
+    };
+
+    let (mut client_reader, mut server_writer) = (client.try_clone()?, server.try_clone()?);
+    let upstream = thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut server_writer);
+        let _ = server_writer.shutdown(Shutdown::Write);
+    });
+    let (mut server_reader, mut client_writer) = (server, client);
+    let _ = io::copy(&mut server_reader, &mut client_writer);
+    let _ = client_writer.shutdown(Shutdown::Write);
+    let _ = upstream.join();
+
+    if let Some(id) = tunnel_id {
+        state.lock().unwrap().tunnels.remove(&id);
+    }
+    Ok(())
+}
+
+/// Negotiates the username/password authentication, and returns the peer id of the username.
+fn authenticate(client: &mut TcpStream) -> io::Result<PeerId> {
+    let mut greeting = [0u8; 2];
+    client.read_exact(&mut greeting)?;
+    check_version(greeting[0])?;
+    let mut methods = vec![0u8; greeting[1] as usize];
+    client.read_exact(&mut methods)?;
+    if !methods.contains(&USERNAME_PASSWORD) {
+        client.write_all(&[VERSION, NO_ACCEPTABLE_METHODS])?;
+        return Err(invalid_data("the node didn't offer its peer id".into()));
+    }
+    client.write_all(&[VERSION, USERNAME_PASSWORD])?;
+
+    let mut version = [0u8; 1];
+    client.read_exact(&mut version)?;
+    let username = read_with_len(client)?;
+    let _password = read_with_len(client)?;
+    match PeerId::from_hex(&username) {
+        Ok(peer_id) => {
+            client.write_all(&[USERNAME_PASSWORD_VERSION, SUCCEEDED])?;
+            Ok(peer_id)
+        }
+        Err(_) => {
+            client.write_all(&[USERNAME_PASSWORD_VERSION, GENERAL_FAILURE])?;
+            Err(invalid_data("the username isn't a peer id".into()))
+        }
+    }
+}
+
+fn read_target(client: &mut TcpStream, address_type: u8) -> io::Result<SocketAddr> {
+    let ip = match address_type {
+        IPV4 => {
+            let mut octets = [0u8; 4];
+            client.read_exact(&mut octets)?;
+            IpAddr::V4(Ipv4Addr::from(octets))
+        }
+        IPV6 => {
+            let mut octets = [0u8; 16];
+            client.read_exact(&mut octets)?;
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        DOMAIN_NAME => {
+            let dns_name = String::from_utf8_lossy(&read_with_len(client)?).into_owned();
+            let port = read_port(client)?;
+            return (dns_name.as_str(), port)
+                .to_socket_addrs()?
+                .next()
+                .ok_or_else(|| invalid_data(format!("{} doesn't resolve", dns_name)));
+        }
+        address_type => {
+            return Err(invalid_data(format!(
+                "unknown address type {}",
+                address_type
+            )))
+        }
+    };
+    Ok(SocketAddr::new(ip, read_port(client)?))
+}
+
+fn read_port(client: &mut TcpStream) -> io::Result<u16> {
+    let mut port = [0u8; 2];
+    client.read_exact(&mut port)?;
+    Ok(u16::from_be_bytes(port))
+}
+
+fn read_with_len(client: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let mut len = [0u8; 1];
+    client.read_exact(&mut len)?;
+    let mut field = vec![0u8; len[0] as usize];
+    client.read_exact(&mut field)?;
+    Ok(field)
+}
+
+fn reply(client: &mut TcpStream, code: u8) -> io::Result<()> {
+    // The address the proxy bound is of no use to the nodes
+    client.write_all(&[VERSION, code, 0x00, IPV4, 0, 0, 0, 0, 0, 0])
+}
+
+fn check_version(version: u8) -> io::Result<()> {
+    if version != VERSION {
+        return Err(invalid_data(format!(
+            "unsupported SOCKS version {}",
+            version
+        )));
+    }
+    Ok(())
+}
+
+fn invalid_data(error: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
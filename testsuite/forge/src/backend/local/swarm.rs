@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use super::partition::PartitionProxy;
 use crate::{
     ChainInfo, FullNode, HealthCheckError, LocalNode, LocalVersion, Node, NodeExt, Swarm, SwarmExt,
     Validator, Version,
@@ -84,6 +85,9 @@ pub struct LocalSwarmBuilder {
     initial_version: Option<Version>,
     template: NodeConfig,
     number_of_validators: NonZeroUsize,
+    fullnode_template: NodeConfig,
+    number_of_fullnodes: usize,
+    partitionable: bool,
     dir: Option<PathBuf>,
 }
 
@@ -94,6 +98,9 @@ impl LocalSwarmBuilder {
             initial_version: None,
             template: NodeConfig::default_for_validator(),
             number_of_validators: NonZeroUsize::new(1).unwrap(),
+            fullnode_template: NodeConfig::default_for_public_full_node(),
+            number_of_fullnodes: 0,
+            partitionable: false,
             dir: None,
         }
     }
@@ -113,6 +120,24 @@ impl LocalSwarmBuilder {
         self
     }
 
+    pub fn fullnode_template(mut self, fullnode_template: NodeConfig) -> Self {
+        self.fullnode_template = fullnode_template;
+        self
+    }
+
+    /// Public fullnodes launched along with the validators
+    pub fn number_of_fullnodes(mut self, number_of_fullnodes: usize) -> Self {
+        self.number_of_fullnodes = number_of_fullnodes;
+        self
+    }
+
+    /// Routes the connections between the nodes through a proxy of the swarm, for
+    /// `LocalSwarm::partition` to be able to cut them.
+    pub fn partitionable(mut self, partitionable: bool) -> Self {
+        self.partitionable = partitionable;
+        self
+    }
+
     pub fn dir<T: AsRef<Path>>(mut self, dir: T) -> Self {
         self.dir = Some(dir.as_ref().into());
         self
@@ -153,10 +178,18 @@ impl LocalSwarmBuilder {
         });
         let version = versions.get(&initial_version).unwrap();
 
+        let partition_proxy = if self.partitionable {
+            Some(PartitionProxy::start()?)
+        } else {
+            None
+        };
         let validators = validators
             .into_iter()
             .map(|v| {
-                let node = LocalNode::new(version.to_owned(), v.name, v.directory)?;
+                let mut node = LocalNode::new(version.to_owned(), v.name, v.directory)?;
+                if let Some(partition_proxy) = &partition_proxy {
+                    partition_proxy.route(&mut node)?;
+                }
                 Ok((node.peer_id(), node))
             })
             .collect::<Result<HashMap<_, _>>>()?;
@@ -188,7 +221,7 @@ impl LocalSwarmBuilder {
             0,
         );
 
-        Ok(LocalSwarm {
+        let mut swarm = LocalSwarm {
             node_name_counter: validators.len() as u64,
             genesis,
             genesis_waypoint,
@@ -201,7 +234,13 @@ impl LocalSwarmBuilder {
             treasury_compliance_account,
             designated_dealer_account,
             chain_id: ChainId::test(),
-        })
+            partition_proxy,
+        };
+        for _ in 0..self.number_of_fullnodes {
+            swarm.create_fullnode(&initial_version, self.fullnode_template.clone())?;
+        }
+
+        Ok(swarm)
     }
 }
 
@@ -219,6 +258,8 @@ pub struct LocalSwarm {
     treasury_compliance_account: LocalAccount,
     designated_dealer_account: LocalAccount,
     chain_id: ChainId,
+    /// Relays the connections between the nodes, if the swarm is partitionable
+    partition_proxy: Option<PartitionProxy>,
 }
 
 impl LocalSwarm {
@@ -227,9 +268,13 @@ impl LocalSwarm {
     }
 
     pub fn launch(&mut self) -> Result<()> {
-        // Start all the validators
-        for validator in self.validators.values_mut() {
-            validator.start()?;
+        // Start all the nodes
+        for node in self
+            .validators
+            .values_mut()
+            .chain(self.fullnodes.values_mut())
+        {
+            node.start()?;
         }
 
         // Wait for all of them to startup
@@ -243,10 +288,14 @@ impl LocalSwarm {
 
     fn wait_for_startup(&mut self) -> Result<()> {
         let num_attempts = 10;
-        let mut done = vec![false; self.validators.len()];
+        let mut done = vec![false; self.validators.len() + self.fullnodes.len()];
         for i in 0..num_attempts {
             println!("Wait for startup attempt: {} of {}", i, num_attempts);
-            for (node, done) in self.validators.values_mut().zip(done.iter_mut()) {
+            let nodes = self
+                .validators
+                .values_mut()
+                .chain(self.fullnodes.values_mut());
+            for (node, done) in nodes.zip(done.iter_mut()) {
                 if *done {
                     continue;
                 }
@@ -307,6 +356,9 @@ impl LocalSwarm {
         // Since the validator's config has changed we need to save it
         validator_config.save(validator.config_path())?;
         *validator.config_mut() = validator_config;
+        if let Some(partition_proxy) = &self.partition_proxy {
+            partition_proxy.route(validator)?;
+        }
         validator.restart()?;
 
         let version = self.versions.get(version).unwrap();
@@ -315,6 +367,9 @@ impl LocalSwarm {
             fullnode_config.name,
             fullnode_config.directory,
         )?;
+        if let Some(partition_proxy) = &self.partition_proxy {
+            partition_proxy.route(&mut fullnode)?;
+        }
 
         let peer_id = fullnode.peer_id();
         assert_eq!(peer_id, validator_peer_id);
@@ -326,6 +381,14 @@ impl LocalSwarm {
     }
 
     fn add_fullnode(&mut self, version: &Version, template: NodeConfig) -> Result<PeerId> {
+        let peer_id = self.create_fullnode(version, template)?;
+        self.fullnodes.get_mut(&peer_id).unwrap().start()?;
+
+        Ok(peer_id)
+    }
+
+    /// Configures a public fullnode, without starting it.
+    fn create_fullnode(&mut self, version: &Version, template: NodeConfig) -> Result<PeerId> {
         let name = self.node_name_counter.to_string();
         self.node_name_counter += 1;
         let fullnode_config = FullnodeConfig::public_fullnode(
@@ -343,14 +406,50 @@ impl LocalSwarm {
             fullnode_config.directory,
         )?;
 
-        let peer_id = fullnode.peer_id();
-        fullnode.start()?;
+        if let Some(partition_proxy) = &self.partition_proxy {
+            partition_proxy.route(&mut fullnode)?;
+        }
 
+        let peer_id = fullnode.peer_id();
         self.fullnodes.insert(peer_id, fullnode);
 
         Ok(peer_id)
     }
 
+    /// Partitions the network of the swarm into `groups` of peer ids: the nodes of different
+    /// groups can't connect to each other anymore, and their connections are cut. A validator
+    /// fullnode is on the side of its validator. The nodes in no group are cut off from all the
+    /// others. The swarm must have been built `partitionable`.
+    pub fn partition(&self, groups: &[Vec<PeerId>]) -> Result<()> {
+        self.partition_proxy()?.partition(groups);
+        Ok(())
+    }
+
+    /// Cuts the node `peer_id` off from all the others.
+    pub fn isolate(&self, peer_id: PeerId) -> Result<()> {
+        let others = self
+            .validators
+            .keys()
+            .chain(self.fullnodes.keys())
+            .filter(|other| **other != peer_id)
+            .copied()
+            .collect();
+        self.partition(&[vec![peer_id], others])
+    }
+
+    /// Lets all the nodes connect to each other again. They reconnect as they would after a
+    /// network outage.
+    pub fn heal(&self) -> Result<()> {
+        self.partition_proxy()?.heal();
+        Ok(())
+    }
+
+    fn partition_proxy(&self) -> Result<&PartitionProxy> {
+        self.partition_proxy
+            .as_ref()
+            .ok_or_else(|| anyhow!("the swarm isn't partitionable"))
+    }
+
     pub fn chain_id(&self) -> ChainId {
         self.chain_id
     }
@@ -2,8 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diem_crypto::{
+    ecvrf::VRFPrivateKey,
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
     multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature},
+    secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey},
     traits::{SigningKey, Uniform},
 };
 use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
@@ -35,6 +37,14 @@ fn trace_crypto_values(tracer: &mut Tracer, samples: &mut Samples) -> Result<()>
     tracer.trace_value(samples, &signature)?;
     tracer.trace_value::<MultiEd25519PublicKey>(samples, &public_key.into())?;
     tracer.trace_value::<MultiEd25519Signature>(samples, &signature.into())?;
+
+    let secp256k1_private_key = Secp256k1PrivateKey::generate(&mut rng);
+    let secp256k1_public_key: Secp256k1PublicKey = (&secp256k1_private_key).into();
+    tracer.trace_value(samples, &secp256k1_public_key)?;
+    tracer.trace_value(samples, &secp256k1_private_key.sign(&message))?;
+
+    let vrf_private_key = VRFPrivateKey::generate(&mut rng);
+    tracer.trace_value(samples, &vrf_private_key.prove(b"Hello, World"))?;
     Ok(())
 }
 
@@ -5,6 +5,7 @@ use diem_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
     hash::{CryptoHasher as _, TestOnlyHasher},
     multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature},
+    secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey},
     traits::{SigningKey, Uniform},
 };
 use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
@@ -41,6 +42,11 @@ fn trace_crypto_values(tracer: &mut Tracer, samples: &mut Samples) -> Result<()>
     tracer.trace_value::<MultiEd25519PublicKey>(samples, &public_key.into())?;
     tracer.trace_value(samples, &signature)?;
     tracer.trace_value::<MultiEd25519Signature>(samples, &signature.into())?;
+
+    let secp256k1_private_key = Secp256k1PrivateKey::generate(&mut rng);
+    let secp256k1_public_key: Secp256k1PublicKey = (&secp256k1_private_key).into();
+    tracer.trace_value(samples, &secp256k1_public_key)?;
+    tracer.trace_value(samples, &secp256k1_private_key.sign(&message))?;
     Ok(())
 }
 
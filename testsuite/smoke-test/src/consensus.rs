@@ -5,7 +5,7 @@ use crate::{
     operational_tooling::{
         launch_swarm_with_op_tool_and_backend, wait_for_transaction_on_all_nodes,
     },
-    smoke_test_environment::new_local_swarm,
+    smoke_test_environment::{new_local_swarm, new_local_swarm_with},
     test_utils::{check_create_mint_transfer, diem_swarm_utils::load_validators_backend_storage},
 };
 use diem_config::config::SecureBackend;
@@ -17,8 +17,13 @@ use diem_types::{
     account_address::AccountAddress, network_address::NetworkAddress,
     on_chain_config::ConsensusConfigV1,
 };
-use forge::{LocalSwarm, Node, NodeExt, Swarm};
-use std::{convert::TryInto, str::FromStr};
+use forge::{LocalSwarm, Node, NodeExt, Swarm, SwarmExt};
+use std::{
+    convert::TryInto,
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
 
 #[test]
 fn test_consensus_observer_mode_storage_error() {
@@ -137,6 +142,35 @@ fn test_2chain_upgrade() {
     check_create_mint_transfer(&mut swarm);
 }
 
+#[test]
+fn test_network_partition() {
+    let mut swarm = new_local_swarm_with(4, |builder| {
+        builder.number_of_fullnodes(1).partitionable(true)
+    });
+    check_create_mint_transfer(&mut swarm);
+
+    // Neither half of the validators is a quorum, so no block commits while they are split
+    let peer_ids: Vec<_> = swarm
+        .validators()
+        .map(|validator| validator.peer_id())
+        .collect();
+    swarm
+        .partition(&[peer_ids[..2].to_vec(), peer_ids[2..].to_vec()])
+        .unwrap();
+    let client = swarm.validators().next().unwrap().json_rpc_client();
+    // Let the blocks in flight commit
+    thread::sleep(Duration::from_secs(5));
+    let version = client.get_metadata().unwrap().into_inner().version;
+    thread::sleep(Duration::from_secs(5));
+    assert_eq!(client.get_metadata().unwrap().into_inner().version, version);
+
+    swarm.heal().unwrap();
+    swarm
+        .wait_for_connectivity(Instant::now() + Duration::from_secs(60))
+        .unwrap();
+    check_create_mint_transfer(&mut swarm);
+}
+
 fn rotate_operator_and_consensus_key(swarm: LocalSwarm) {
     let validator = swarm.validators().next().unwrap();
     let json_rpc_endpoint = validator.json_rpc_endpoint().to_string();
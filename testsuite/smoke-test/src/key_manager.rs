@@ -49,6 +49,8 @@ fn test_key_manager_consensus_rotation() {
         1000, // Large sleep period to force a single rotation
         1000,
         ChainId::test(),
+        false,
+        false,
     );
 
     // Add some time padding to ensure the libra timestamp increases on-chain
@@ -788,6 +788,35 @@ fn test_operator_key_rotation_recovery() {
     );
 }
 
+#[test]
+fn test_owner_key_rotation() {
+    let (_env, op_tool, backend, storage) = launch_swarm_with_op_tool_and_backend(1);
+
+    // Rotate the owner key and verify the rotation took effect on-chain
+    let result = op_tool.rotate_owner_key(&backend, false).unwrap();
+    assert_eq!(
+        VMStatusView::Executed,
+        result.transaction_context.execution_result.unwrap()
+    );
+    let verification = result.verification.unwrap();
+    assert!(verification.authentication_key_matches);
+    assert!(verification.validator_config_matches);
+
+    // Verify that the owner key in storage is the new key registered on-chain
+    let owner_account = storage.get::<AccountAddress>(OWNER_ACCOUNT).unwrap().value;
+    assert_eq!(owner_account, result.account);
+    assert_eq!(
+        result.new_key,
+        storage.get_public_key(OWNER_KEY).unwrap().public_key
+    );
+    let account_resource = op_tool.account_resource(owner_account).unwrap();
+    let on_chain_owner_key = hex::decode(account_resource.authentication_key).unwrap();
+    assert_eq!(
+        AuthenticationKey::ed25519(&result.new_key),
+        AuthenticationKey::try_from(on_chain_owner_key).unwrap()
+    );
+}
+
 #[test]
 fn test_print_account() {
     let (_env, op_tool, backend, storage) = launch_swarm_with_op_tool_and_backend(1);
@@ -1,17 +1,32 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use forge::{LocalFactory, LocalSwarm};
+use forge::{LocalFactory, LocalSwarm, LocalSwarmBuilder};
 use once_cell::sync::Lazy;
 use rand::rngs::OsRng;
 use std::num::NonZeroUsize;
 
-pub fn new_local_swarm(num_validators: usize) -> LocalSwarm {
-    static FACTORY: Lazy<LocalFactory> = Lazy::new(|| LocalFactory::from_workspace().unwrap());
+static FACTORY: Lazy<LocalFactory> = Lazy::new(|| LocalFactory::from_workspace().unwrap());
 
+pub fn new_local_swarm(num_validators: usize) -> LocalSwarm {
     ::diem_logger::Logger::new().init();
 
     FACTORY
         .new_swarm(OsRng, NonZeroUsize::new(num_validators).unwrap())
         .unwrap()
 }
+
+/// Launches a swarm of `num_validators`, configured further by `configure`.
+pub fn new_local_swarm_with<F>(num_validators: usize, configure: F) -> LocalSwarm
+where
+    F: FnOnce(LocalSwarmBuilder) -> LocalSwarmBuilder,
+{
+    ::diem_logger::Logger::new().init();
+
+    let builder = FACTORY
+        .swarm_builder()
+        .number_of_validators(NonZeroUsize::new(num_validators).unwrap());
+    let mut swarm = configure(builder).build(OsRng).unwrap();
+    swarm.launch().unwrap();
+    swarm
+}
@@ -0,0 +1,79 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{epoch_state::EpochState, ledger_info::LedgerInfo, transaction::Version};
+use anyhow::{ensure, format_err, Result};
+use diem_crypto::HashValue;
+#[cfg(any(test, feature = "fuzzing"))]
+use proptest_derive::Arbitrary;
+use serde::{Deserialize, Serialize};
+
+/// EpochCheckpoint is the compact manifest of the ledger at the end of an epoch: its version, the
+/// roots of the ledger and of the state at that version, and the validators of the next epoch.
+/// The storage writes one along with every ledger info ending an epoch, whether committed,
+/// restored from a backup or synced from a state snapshot, for the backups and the nodes
+/// bootstrapping from a state snapshot to find the epoch boundaries without scanning the ledger
+/// infos.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct EpochCheckpoint {
+    /// The epoch ending at the checkpoint
+    pub epoch: u64,
+    /// The last version of the epoch
+    pub version: Version,
+    pub timestamp_usecs: u64,
+    pub transaction_accumulator_root_hash: HashValue,
+    /// The root hash of the account state tree at `version`
+    pub state_root_hash: HashValue,
+    pub next_epoch_state: EpochState,
+}
+
+impl EpochCheckpoint {
+    /// The checkpoint of the epoch `ledger_info` ends, whose state root at its version is
+    /// `state_root_hash`
+    pub fn new(ledger_info: &LedgerInfo, state_root_hash: HashValue) -> Result<Self> {
+        let next_epoch_state = ledger_info.next_epoch_state().cloned().ok_or_else(|| {
+            format_err!(
+                "LedgerInfo at version {} doesn't end epoch {}.",
+                ledger_info.version(),
+                ledger_info.epoch(),
+            )
+        })?;
+        Ok(Self {
+            epoch: ledger_info.epoch(),
+            version: ledger_info.version(),
+            timestamp_usecs: ledger_info.timestamp_usecs(),
+            transaction_accumulator_root_hash: ledger_info.transaction_accumulator_hash(),
+            state_root_hash,
+            next_epoch_state,
+        })
+    }
+
+    /// Checks the checkpoint is that of the epoch ending `ledger_info`, which the caller trusts,
+    /// e.g. after verifying it against a waypoint. The state root is only trusted once proven by
+    /// the TransactionInfo at `version`.
+    pub fn verify(&self, ledger_info: &LedgerInfo) -> Result<()> {
+        ensure!(
+            self.epoch == ledger_info.epoch() && self.version == ledger_info.version(),
+            "Checkpoint of epoch {} at version {} doesn't match LedgerInfo of epoch {} at version {}.",
+            self.epoch,
+            self.version,
+            ledger_info.epoch(),
+            ledger_info.version(),
+        );
+        ensure!(
+            self.timestamp_usecs == ledger_info.timestamp_usecs()
+                && self.transaction_accumulator_root_hash
+                    == ledger_info.transaction_accumulator_hash(),
+            "Checkpoint of epoch {} doesn't match the LedgerInfo.",
+            self.epoch,
+        );
+        ensure!(
+            Some(&self.next_epoch_state) == ledger_info.next_epoch_state(),
+            "Checkpoint of epoch {} has unexpected next epoch state {}.",
+            self.epoch,
+            self.next_epoch_state,
+        );
+        Ok(())
+    }
+}
@@ -15,6 +15,7 @@ pub mod contract_event;
 pub mod diem_id_identifier;
 pub mod diem_timestamp;
 pub mod epoch_change;
+pub mod epoch_checkpoint;
 pub mod epoch_state;
 pub mod event;
 pub mod ledger_info;
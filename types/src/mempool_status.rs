@@ -8,6 +8,7 @@ use anyhow::Result;
 use proptest::prelude::*;
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
+use serde::{Deserialize, Serialize};
 use std::{convert::TryFrom, fmt};
 
 /// A `MempoolStatus` is represented as a required status code that is semantic coupled with an optional sub status and message.
@@ -36,7 +37,7 @@ impl MempoolStatus {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize)]
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
 #[repr(u64)]
 pub enum MempoolStatusCode {
@@ -53,6 +54,9 @@ pub enum MempoolStatusCode {
     // transaction didn't pass vm_validation
     VmError = 5,
     UnknownStatus = 6,
+    // Replacement of a transaction with the same sequence number doesn't increase the gas price by
+    // the required percentage
+    UnderpricedReplacement = 7,
 }
 
 impl TryFrom<u64> for MempoolStatusCode {
@@ -67,6 +71,7 @@ impl TryFrom<u64> for MempoolStatusCode {
             4 => Ok(MempoolStatusCode::InvalidUpdate),
             5 => Ok(MempoolStatusCode::VmError),
             6 => Ok(MempoolStatusCode::UnknownStatus),
+            7 => Ok(MempoolStatusCode::UnderpricedReplacement),
             _ => Err("invalid StatusCode"),
         }
     }
@@ -2,19 +2,78 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::on_chain_config::OnChainConfig;
-use anyhow::{format_err, Result};
+use anyhow::{ensure, format_err, Result};
 use serde::{Deserialize, Serialize};
 
 /// The on-chain consensus config, in order to be able to add fields, we use enum to wrap the actual struct.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum OnChainConsensusConfig {
     V1(ConsensusConfigV1),
+    V2(ConsensusConfigV2),
+    V3(ConsensusConfigV3),
+    V4(ConsensusConfigV4),
 }
 
 impl OnChainConsensusConfig {
     pub fn two_chain(&self) -> bool {
         match &self {
             OnChainConsensusConfig::V1(config) => config.two_chain,
+            OnChainConsensusConfig::V2(config) => config.two_chain,
+            OnChainConsensusConfig::V3(config) => config.two_chain,
+            OnChainConsensusConfig::V4(config) => config.two_chain,
+        }
+    }
+
+    /// The proposer election policy set on-chain, None means the node local config decides.
+    pub fn proposer_election_type(&self) -> Option<&ProposerElectionType> {
+        match &self {
+            OnChainConsensusConfig::V1(_) => None,
+            OnChainConsensusConfig::V2(config) => Some(&config.proposer_election_type),
+            OnChainConsensusConfig::V3(config) => Some(&config.proposer_election_type),
+            OnChainConsensusConfig::V4(config) => Some(&config.proposer_election_type),
+        }
+    }
+
+    /// The max number of bytes of the proposed blocks set on-chain, None means the node local
+    /// config decides.
+    pub fn max_block_bytes(&self) -> Option<u64> {
+        match &self {
+            OnChainConsensusConfig::V1(_) | OnChainConsensusConfig::V2(_) => None,
+            OnChainConsensusConfig::V3(config) => Some(config.max_block_bytes),
+            OnChainConsensusConfig::V4(config) => Some(config.max_block_bytes),
+        }
+    }
+
+    /// The max estimated gas of the proposed blocks set on-chain, None means the node local
+    /// config decides.
+    pub fn max_block_gas(&self) -> Option<u64> {
+        match &self {
+            OnChainConsensusConfig::V1(_) | OnChainConsensusConfig::V2(_) => None,
+            OnChainConsensusConfig::V3(config) => Some(config.max_block_gas),
+            OnChainConsensusConfig::V4(config) => Some(config.max_block_gas),
+        }
+    }
+
+    /// Checks that the payload limits set on-chain, if any, leave room for transactions in the
+    /// proposed blocks.
+    pub fn validate_payload_limits(&self) -> Result<()> {
+        if let Some(max_block_bytes) = self.max_block_bytes() {
+            ensure!(max_block_bytes > 0, "max_block_bytes must be positive");
+        }
+        if let Some(max_block_gas) = self.max_block_gas() {
+            ensure!(max_block_gas > 0, "max_block_gas must be positive");
+        }
+        Ok(())
+    }
+
+    /// The validators the votes are sent to set on-chain, None means the node local config
+    /// decides. All the validators must agree on it, or the votes miss their aggregators.
+    pub fn vote_recipients(&self) -> Option<&VoteRecipients> {
+        match &self {
+            OnChainConsensusConfig::V1(_)
+            | OnChainConsensusConfig::V2(_)
+            | OnChainConsensusConfig::V3(_) => None,
+            OnChainConsensusConfig::V4(config) => Some(&config.vote_recipients),
         }
     }
 }
@@ -37,6 +96,108 @@ impl Default for ConsensusConfigV1 {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ConsensusConfigV2 {
+    pub two_chain: bool,
+    pub proposer_election_type: ProposerElectionType,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ConsensusConfigV3 {
+    pub two_chain: bool,
+    pub proposer_election_type: ProposerElectionType,
+    // Max number of bytes of the transactions of a proposed block
+    pub max_block_bytes: u64,
+    // Max sum of the max gas amounts of the transactions of a proposed block
+    pub max_block_gas: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ConsensusConfigV4 {
+    pub two_chain: bool,
+    pub proposer_election_type: ProposerElectionType,
+    // Max number of bytes of the transactions of a proposed block
+    pub max_block_bytes: u64,
+    // Max sum of the max gas amounts of the transactions of a proposed block
+    pub max_block_gas: u64,
+    pub vote_recipients: VoteRecipients,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum VoteRecipients {
+    // Only the proposer of the next round aggregates the QC
+    NextProposer,
+    // The proposers of the given number of next rounds, so that a slow proposer doesn't delay the
+    // QC
+    NextProposers(u64),
+    // All the validators
+    Broadcast,
+}
+
+impl VoteRecipients {
+    /// Checks that the votes are sent to at least one validator.
+    pub fn validate(&self) -> Result<()> {
+        if let VoteRecipients::NextProposers(count) = self {
+            ensure!(
+                *count > 0,
+                "the votes must be sent to at least one next proposer"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ProposerElectionType {
+    // Choose the smallest PeerId as the proposer, active for the given number of contiguous rounds
+    FixedProposer(u32),
+    // Round robin rotation of proposers, each one active for the given number of contiguous rounds
+    RotatingProposer(u32),
+    // Committed history based proposer election
+    LeaderReputation(LeaderReputationType),
+}
+
+impl ProposerElectionType {
+    /// Checks that the election chooses a proposer for every round: each proposer is active for at
+    /// least one round, and the weights of the leader reputation can't all be zero.
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            ProposerElectionType::FixedProposer(contiguous_rounds)
+            | ProposerElectionType::RotatingProposer(contiguous_rounds) => {
+                ensure!(*contiguous_rounds > 0, "contiguous_rounds must be positive");
+            }
+            ProposerElectionType::LeaderReputation(leader_reputation_type) => {
+                ensure!(
+                    leader_reputation_type.active_weight > 0
+                        && leader_reputation_type.inactive_weight > 0,
+                    "active_weight and inactive_weight must be positive"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LeaderReputationType {
+    pub heuristic: LeaderReputationHeuristic,
+    pub active_weight: u64,
+    pub inactive_weight: u64,
+    // Number of most recent rounds excluded from the committed history window
+    pub exclude_round: u64,
+    // Leader reputation is only used starting from this epoch, earlier epochs rotate the proposers
+    // each round
+    pub enable_from_epoch: u64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum LeaderReputationHeuristic {
+    // Any committed proposal or vote in the window makes a validator active
+    ActiveInactive,
+    // Weights grow with the number of committed proposals and votes in the window
+    ProposerAndVoter,
+}
+
 impl OnChainConfig for OnChainConsensusConfig {
     const IDENTIFIER: &'static str = "DiemConsensusConfig";
 
@@ -25,9 +25,14 @@ mod vm_config;
 mod vm_publishing_option;
 
 pub use self::{
-    consensus_config::{ConsensusConfigV1, OnChainConsensusConfig},
+    consensus_config::{
+        ConsensusConfigV1, ConsensusConfigV2, ConsensusConfigV3, ConsensusConfigV4,
+        LeaderReputationHeuristic, LeaderReputationType, OnChainConsensusConfig,
+        ProposerElectionType, VoteRecipients,
+    },
     diem_version::{
         DiemVersion, DIEM_MAX_KNOWN_VERSION, DIEM_VERSION_2, DIEM_VERSION_3, DIEM_VERSION_4,
+        DIEM_VERSION_5,
     },
     registered_currencies::RegisteredCurrencies,
     validator_set::ValidatorSet,
@@ -107,6 +107,20 @@ fn test_accumulator_append() {
 }
 
 proptest! {
+    #[test]
+    fn test_accumulator_from_leaves(hashes in vec(any::<HashValue>(), 0..100)) {
+        // Hashing the tree one level at a time gives the same accumulator as appending the leaves
+        // one at a time.
+        let accumulator = InMemoryAccumulator::<TestOnlyHasher>::from_leaves(&hashes);
+        let mut expected = InMemoryAccumulator::<TestOnlyHasher>::default();
+        for leaf in &hashes {
+            expected = expected.append(&[*leaf]);
+        }
+        prop_assert_eq!(accumulator.frozen_subtree_roots(), expected.frozen_subtree_roots());
+        prop_assert_eq!(accumulator.num_leaves(), hashes.len() as LeafCount);
+        prop_assert_eq!(accumulator.root_hash(), compute_root_hash_naive(&hashes));
+    }
+
     #[test]
     fn test_accumulator_append_subtrees(
         hashes1 in vec(any::<HashValue>(), 0..100),
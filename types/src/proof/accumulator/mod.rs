@@ -82,7 +82,21 @@ where
 
     /// Constructs a new accumulator with given leaves.
     pub fn from_leaves(leaves: &[HashValue]) -> Self {
-        Self::default().append(leaves)
+        // The tree is built one level at a time, so that the nodes of a level are hashed in a
+        // batch. A level with an odd number of nodes leaves its last node as the root of a frozen
+        // subtree, smaller than the ones found on the levels above.
+        let mut frozen_subtree_roots = Vec::new();
+        let mut nodes = leaves.to_vec();
+        while !nodes.is_empty() {
+            if nodes.len() % 2 == 1 {
+                frozen_subtree_roots.extend(nodes.pop());
+            }
+            nodes = MerkleTreeInternalNode::<H>::hash_pairs(&nodes);
+        }
+        frozen_subtree_roots.reverse();
+
+        Self::new(frozen_subtree_roots, leaves.len() as LeafCount)
+            .expect("The frozen subtrees of valid leaves should form a valid accumulator.")
     }
 
     /// Appends a list of new leaves to an existing accumulator. Since the accumulator is
@@ -76,6 +76,23 @@ impl<H: CryptoHasher> MerkleTreeInternalNode<H> {
             hasher: PhantomData,
         }
     }
+
+    /// Computes the hashes of the parents of `nodes`, taken two by two, all at once so that the
+    /// SHA3 backend can hash several of them in parallel. `nodes` must have an even length.
+    pub fn hash_pairs(nodes: &[HashValue]) -> Vec<HashValue> {
+        assert_eq!(nodes.len() % 2, 0, "The nodes should come in pairs.");
+        let children: Vec<[u8; 2 * HashValue::LENGTH]> = nodes
+            .chunks(2)
+            .map(|pair| {
+                let mut children = [0u8; 2 * HashValue::LENGTH];
+                children[..HashValue::LENGTH].copy_from_slice(pair[0].as_ref());
+                children[HashValue::LENGTH..].copy_from_slice(pair[1].as_ref());
+                children
+            })
+            .collect();
+        let inputs: Vec<&[u8]> = children.iter().map(|children| &children[..]).collect();
+        H::hash_all_batch(&inputs)
+    }
 }
 
 impl<H: CryptoHasher> CryptoHash for MerkleTreeInternalNode<H> {
@@ -10,6 +10,7 @@ use diem_crypto::{
     ed25519::{Ed25519PublicKey, Ed25519Signature},
     hash::CryptoHash,
     multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature},
+    secp256k1::{Secp256k1PublicKey, Secp256k1Signature},
     traits::Signature,
     validatable::Validatable,
     CryptoMaterialError, HashValue, ValidCryptoMaterial, ValidCryptoMaterialStringExt,
@@ -58,6 +59,11 @@ pub enum TransactionAuthenticator {
         secondary_signer_addresses: Vec<AccountAddress>,
         secondary_signers: Vec<AccountAuthenticator>,
     },
+    /// Single secp256k1 ECDSA signature
+    Secp256k1Ecdsa {
+        public_key: Secp256k1PublicKey,
+        signature: Secp256k1Signature,
+    },
 }
 
 impl TransactionAuthenticator {
@@ -80,6 +86,21 @@ impl TransactionAuthenticator {
         }
     }
 
+    /// Create a single-signature secp256k1 ECDSA authenticator
+    pub fn secp256k1_ecdsa(public_key: Secp256k1PublicKey, signature: Secp256k1Signature) -> Self {
+        Self::Secp256k1Ecdsa {
+            public_key,
+            signature,
+        }
+    }
+
+    /// Return true if the sender or any secondary signer signs with a secp256k1 ECDSA key
+    pub fn uses_secp256k1_ecdsa(&self) -> bool {
+        std::iter::once(self.sender())
+            .chain(self.secondary_signers())
+            .any(|signer| matches!(signer.scheme(), Scheme::Secp256k1Ecdsa))
+    }
+
     /// Create a multi-agent authenticator
     pub fn multi_agent(
         sender: AccountAuthenticator,
@@ -113,6 +134,10 @@ impl TransactionAuthenticator {
                 public_key,
                 signature,
             } => signature.verify(raw_txn, public_key),
+            Self::Secp256k1Ecdsa {
+                public_key,
+                signature,
+            } => signature.verify(raw_txn, public_key),
             Self::MultiAgent {
                 sender,
                 secondary_signer_addresses,
@@ -144,6 +169,10 @@ impl TransactionAuthenticator {
                 public_key,
                 signature,
             } => AccountAuthenticator::multi_ed25519(public_key.clone(), signature.clone()),
+            Self::Secp256k1Ecdsa {
+                public_key,
+                signature,
+            } => AccountAuthenticator::secp256k1_ecdsa(public_key.clone(), signature.clone()),
             Self::MultiAgent { sender, .. } => sender.clone(),
         }
     }
@@ -154,7 +183,8 @@ impl TransactionAuthenticator {
             | Self::MultiEd25519 {
                 public_key: _,
                 signature: _,
-            } => vec![],
+            }
+            | Self::Secp256k1Ecdsa { .. } => vec![],
             Self::MultiAgent {
                 sender: _,
                 secondary_signer_addresses,
@@ -169,7 +199,8 @@ impl TransactionAuthenticator {
             | Self::MultiEd25519 {
                 public_key: _,
                 signature: _,
-            } => vec![],
+            }
+            | Self::Secp256k1Ecdsa { .. } => vec![],
             Self::MultiAgent {
                 sender: _,
                 secondary_signer_addresses: _,
@@ -202,6 +233,13 @@ impl fmt::Display for TransactionAuthenticator {
                     self.sender()
                 )
             }
+            Self::Secp256k1Ecdsa { .. } => {
+                write!(
+                    f,
+                    "TransactionAuthenticator[scheme: Secp256k1Ecdsa, sender: {}]",
+                    self.sender()
+                )
+            }
             Self::MultiAgent {
                 sender,
                 secondary_signer_addresses,
@@ -242,6 +280,7 @@ impl fmt::Display for TransactionAuthenticator {
 pub enum Scheme {
     Ed25519 = 0,
     MultiEd25519 = 1,
+    Secp256k1Ecdsa = 2,
     // ... add more schemes here
 }
 
@@ -250,6 +289,7 @@ impl fmt::Display for Scheme {
         let display = match self {
             Scheme::Ed25519 => "Ed25519",
             Scheme::MultiEd25519 => "MultiEd25519",
+            Scheme::Secp256k1Ecdsa => "Secp256k1Ecdsa",
         };
         write!(f, "Scheme::{}", display)
     }
@@ -267,6 +307,11 @@ pub enum AccountAuthenticator {
         public_key: MultiEd25519PublicKey,
         signature: MultiEd25519Signature,
     },
+    /// Single secp256k1 ECDSA signature
+    Secp256k1Ecdsa {
+        public_key: Secp256k1PublicKey,
+        signature: Secp256k1Signature,
+    },
     // ... add more schemes here
 }
 
@@ -276,6 +321,7 @@ impl AccountAuthenticator {
         match self {
             Self::Ed25519 { .. } => Scheme::Ed25519,
             Self::MultiEd25519 { .. } => Scheme::MultiEd25519,
+            Self::Secp256k1Ecdsa { .. } => Scheme::Secp256k1Ecdsa,
         }
     }
 
@@ -298,6 +344,14 @@ impl AccountAuthenticator {
         }
     }
 
+    /// Create a single-signature secp256k1 ECDSA authenticator
+    pub fn secp256k1_ecdsa(public_key: Secp256k1PublicKey, signature: Secp256k1Signature) -> Self {
+        Self::Secp256k1Ecdsa {
+            public_key,
+            signature,
+        }
+    }
+
     /// Return Ok if the authenticator's public key matches its signature, Err otherwise
     pub fn verify<T: Serialize + CryptoHash>(&self, message: &T) -> Result<()> {
         match self {
@@ -309,6 +363,10 @@ impl AccountAuthenticator {
                 public_key,
                 signature,
             } => signature.verify(message, public_key),
+            Self::Secp256k1Ecdsa {
+                public_key,
+                signature,
+            } => signature.verify(message, public_key),
         }
     }
 
@@ -317,6 +375,7 @@ impl AccountAuthenticator {
         match self {
             Self::Ed25519 { public_key, .. } => public_key.unvalidated().to_bytes().to_vec(),
             Self::MultiEd25519 { public_key, .. } => public_key.to_bytes().to_vec(),
+            Self::Secp256k1Ecdsa { public_key, .. } => public_key.to_bytes().to_vec(),
         }
     }
 
@@ -325,6 +384,7 @@ impl AccountAuthenticator {
         match self {
             Self::Ed25519 { signature, .. } => signature.to_bytes().to_vec(),
             Self::MultiEd25519 { signature, .. } => signature.to_bytes().to_vec(),
+            Self::Secp256k1Ecdsa { signature, .. } => signature.to_bytes().to_vec(),
         }
     }
 
@@ -341,7 +401,7 @@ impl AccountAuthenticator {
     /// Return the number of signatures included in this account authenticator.
     pub fn number_of_signatures(&self) -> usize {
         match self {
-            Self::Ed25519 { .. } => 1,
+            Self::Ed25519 { .. } | Self::Secp256k1Ecdsa { .. } => 1,
             Self::MultiEd25519 { signature, .. } => signature.signatures().len(),
         }
     }
@@ -395,6 +455,11 @@ impl AuthenticationKey {
         Self::from_preimage(&AuthenticationKeyPreimage::multi_ed25519(public_key))
     }
 
+    /// Create an authentication key from a secp256k1 ECDSA public key
+    pub fn secp256k1_ecdsa(public_key: &Secp256k1PublicKey) -> Self {
+        Self::from_preimage(&AuthenticationKeyPreimage::secp256k1_ecdsa(public_key))
+    }
+
     /// Return an address derived from the last `AccountAddress::LENGTH` bytes of this
     /// authentication key.
     pub fn derived_address(&self) -> AccountAddress {
@@ -450,6 +515,11 @@ impl AuthenticationKeyPreimage {
         Self::new(public_key.to_bytes(), Scheme::MultiEd25519)
     }
 
+    /// Construct a preimage from a secp256k1 ECDSA public key, in its uncompressed encoding
+    pub fn secp256k1_ecdsa(public_key: &Secp256k1PublicKey) -> AuthenticationKeyPreimage {
+        Self::new(public_key.to_bytes().to_vec(), Scheme::Secp256k1Ecdsa)
+    }
+
     /// Construct a vector from this authentication key
     pub fn into_vec(self) -> Vec<u8> {
         self.0
@@ -524,11 +594,47 @@ impl fmt::Display for AuthenticationKey {
 
 #[cfg(test)]
 mod tests {
-    use crate::transaction::authenticator::AuthenticationKey;
+    use crate::transaction::authenticator::{
+        AccountAuthenticator, AuthenticationKey, AuthenticationKeyPreimage, Scheme,
+    };
+    use diem_crypto::{
+        secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey},
+        test_utils::TestDiemCrypto,
+        HashValue, SigningKey, Uniform,
+    };
     use std::str::FromStr;
 
     #[test]
     fn test_from_str_should_not_panic_by_given_empty_string() {
         assert!(AuthenticationKey::from_str("").is_err());
     }
+
+    #[test]
+    fn test_secp256k1_ecdsa_authenticator() {
+        let private_key = Secp256k1PrivateKey::generate_for_testing();
+        let public_key = Secp256k1PublicKey::from(&private_key);
+        let message = TestDiemCrypto("Hello, World".to_string());
+        let authenticator =
+            AccountAuthenticator::secp256k1_ecdsa(public_key.clone(), private_key.sign(&message));
+        assert!(authenticator.verify(&message).is_ok());
+        assert!(authenticator
+            .verify(&TestDiemCrypto("Hello, World!".to_string()))
+            .is_err());
+
+        // The authentication key is the hash of the uncompressed public key and the scheme id
+        let mut preimage = public_key.to_bytes().to_vec();
+        preimage.push(Scheme::Secp256k1Ecdsa as u8);
+        assert_eq!(
+            AuthenticationKeyPreimage::secp256k1_ecdsa(&public_key).into_vec(),
+            preimage
+        );
+        assert_eq!(
+            authenticator.authentication_key(),
+            AuthenticationKey::new(*HashValue::sha3_256_of(&preimage).as_ref())
+        );
+        assert_eq!(
+            authenticator.authentication_key(),
+            AuthenticationKey::secp256k1_ecdsa(&public_key)
+        );
+    }
 }
@@ -20,6 +20,7 @@ use diem_crypto::{
     ed25519::*,
     hash::{CryptoHash, EventAccumulatorHasher},
     multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature},
+    secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey, Secp256k1Signature},
     traits::SigningKey,
     HashValue,
 };
@@ -270,6 +271,23 @@ impl RawTransaction {
         )))
     }
 
+    /// Signs the given `RawTransaction` with a secp256k1 ECDSA private key, e.g. the key of an
+    /// Ethereum wallet. Note that this consumes the `RawTransaction` and turns it into a
+    /// `SignatureCheckedTransaction`.
+    pub fn sign_secp256k1_ecdsa(
+        self,
+        private_key: &Secp256k1PrivateKey,
+    ) -> Result<SignatureCheckedTransaction> {
+        let signature = private_key.sign(&self);
+        Ok(SignatureCheckedTransaction(
+            SignedTransaction::new_secp256k1_ecdsa(
+                self,
+                Secp256k1PublicKey::from(private_key),
+                signature,
+            ),
+        ))
+    }
+
     /// Signs the given multi-agent `RawTransaction`, which is a transaction with secondary
     /// signers in addition to a sender. The private keys of the sender and the
     /// secondary signers are used to sign the transaction.
@@ -282,35 +300,15 @@ impl RawTransaction {
         secondary_signers: Vec<AccountAddress>,
         secondary_private_keys: Vec<&Ed25519PrivateKey>,
     ) -> Result<SignatureCheckedTransaction> {
-        let message =
-            RawTransactionWithData::new_multi_agent(self.clone(), secondary_signers.clone());
-        let sender_signature = sender_private_key.sign(&message);
-        let sender_authenticator = AccountAuthenticator::ed25519(
-            Ed25519PublicKey::from(sender_private_key),
-            sender_signature,
-        );
-
-        if secondary_private_keys.len() != secondary_signers.len() {
-            return Err(format_err!(
-                "number of secondary private keys and number of secondary signers don't match"
-            ));
-        }
-        let mut secondary_authenticators = vec![];
-        for priv_key in secondary_private_keys {
-            let signature = priv_key.sign(&message);
-            secondary_authenticators.push(AccountAuthenticator::ed25519(
-                Ed25519PublicKey::from(priv_key),
-                signature,
-            ));
-        }
+        let message = RawTransactionWithData::new_multi_agent(self, secondary_signers);
+        let sender_authenticator = message.sign(sender_private_key);
+        let secondary_authenticators = secondary_private_keys
+            .into_iter()
+            .map(|private_key| message.sign(private_key))
+            .collect();
 
         Ok(SignatureCheckedTransaction(
-            SignedTransaction::new_multi_agent(
-                self,
-                sender_authenticator,
-                secondary_signers,
-                secondary_authenticators,
-            ),
+            message.into_signed_transaction(sender_authenticator, secondary_authenticators)?,
         ))
     }
 
@@ -399,6 +397,61 @@ impl RawTransactionWithData {
             secondary_signer_addresses,
         }
     }
+
+    pub fn raw_txn(&self) -> &RawTransaction {
+        match self {
+            Self::MultiAgent { raw_txn, .. } => raw_txn,
+        }
+    }
+
+    pub fn secondary_signer_addresses(&self) -> &[AccountAddress] {
+        match self {
+            Self::MultiAgent {
+                secondary_signer_addresses,
+                ..
+            } => secondary_signer_addresses,
+        }
+    }
+
+    /// Signs the message on behalf of one of the signers of the transaction, either its sender or
+    /// one of its secondary signers. Each signer can sign on its own, e.g. within the custody
+    /// service holding its keys, before the signatures are put together with
+    /// `into_signed_transaction`.
+    pub fn sign(&self, private_key: &Ed25519PrivateKey) -> AccountAuthenticator {
+        AccountAuthenticator::ed25519(Ed25519PublicKey::from(private_key), private_key.sign(self))
+    }
+
+    /// Signs the message with a secp256k1 ECDSA key, like `sign` does with an Ed25519 key.
+    pub fn sign_secp256k1_ecdsa(&self, private_key: &Secp256k1PrivateKey) -> AccountAuthenticator {
+        AccountAuthenticator::secp256k1_ecdsa(
+            Secp256k1PublicKey::from(private_key),
+            private_key.sign(self),
+        )
+    }
+
+    /// Puts together the signed transaction from the authenticators of its sender and of its
+    /// secondary signers, which have to be in the order of `secondary_signer_addresses`.
+    /// The signatures are only checked later on, e.g. by `SignedTransaction::check_signature`.
+    pub fn into_signed_transaction(
+        self,
+        sender_authenticator: AccountAuthenticator,
+        secondary_authenticators: Vec<AccountAuthenticator>,
+    ) -> Result<SignedTransaction> {
+        let Self::MultiAgent {
+            raw_txn,
+            secondary_signer_addresses,
+        } = self;
+        ensure!(
+            secondary_authenticators.len() == secondary_signer_addresses.len(),
+            "number of secondary authenticators and number of secondary signers don't match"
+        );
+        Ok(SignedTransaction::new_multi_agent(
+            raw_txn,
+            sender_authenticator,
+            secondary_signer_addresses,
+            secondary_authenticators,
+        ))
+    }
 }
 
 /// Different kinds of transactions.
@@ -534,6 +587,18 @@ impl SignedTransaction {
         }
     }
 
+    pub fn new_secp256k1_ecdsa(
+        raw_txn: RawTransaction,
+        public_key: Secp256k1PublicKey,
+        signature: Secp256k1Signature,
+    ) -> SignedTransaction {
+        let authenticator = TransactionAuthenticator::secp256k1_ecdsa(public_key, signature);
+        SignedTransaction {
+            raw_txn,
+            authenticator,
+        }
+    }
+
     pub fn new_multi_agent(
         raw_txn: RawTransaction,
         sender: AccountAuthenticator,
@@ -627,6 +692,10 @@ impl SignedTransaction {
             TransactionAuthenticator::MultiAgent { .. }
         )
     }
+
+    pub fn uses_secp256k1_ecdsa(&self) -> bool {
+        self.authenticator.uses_secp256k1_ecdsa()
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -1142,6 +1211,105 @@ impl TransactionListWithProof {
     }
 }
 
+/// A list of transactions with their outputs, and the proof of the transactions. Like
+/// `TransactionListWithProof`, both the list and `first_transaction_output_version` are empty or
+/// neither is.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct TransactionOutputListWithProof {
+    pub transactions_and_outputs: Vec<(Transaction, TransactionOutput)>,
+    pub first_transaction_output_version: Option<Version>,
+    pub proof: TransactionListProof,
+}
+
+impl TransactionOutputListWithProof {
+    pub fn new(
+        transactions_and_outputs: Vec<(Transaction, TransactionOutput)>,
+        first_transaction_output_version: Option<Version>,
+        proof: TransactionListProof,
+    ) -> Self {
+        Self {
+            transactions_and_outputs,
+            first_transaction_output_version,
+            proof,
+        }
+    }
+
+    pub fn new_empty() -> Self {
+        Self::new(vec![], None, TransactionListProof::new_empty())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions_and_outputs.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions_and_outputs.len()
+    }
+
+    /// Verifies the transactions against `ledger_info` with the proof, then the events, the gas
+    /// used and the status of every output against the `TransactionInfo` of its transaction.
+    /// The `TransactionInfo` doesn't carry the hash of the write set: the write sets are only
+    /// proven once applied, by the state root hash of the `TransactionInfo` of the last output,
+    /// which the caller must check.
+    pub fn verify(
+        &self,
+        ledger_info: &LedgerInfo,
+        first_transaction_output_version: Option<Version>,
+    ) -> Result<()> {
+        ensure!(
+            self.first_transaction_output_version == first_transaction_output_version,
+            "First transaction output version ({:?}) not expected ({:?}).",
+            self.first_transaction_output_version,
+            first_transaction_output_version,
+        );
+
+        let txn_hashes: Vec<_> = self
+            .transactions_and_outputs
+            .iter()
+            .map(|(txn, _output)| CryptoHash::hash(txn))
+            .collect();
+        self.proof.verify(
+            ledger_info,
+            self.first_transaction_output_version,
+            &txn_hashes,
+        )?;
+
+        itertools::zip_eq(
+            &self.transactions_and_outputs,
+            self.proof.transaction_infos(),
+        )
+        .map(|((_txn, output), txn_info)| {
+            let event_hashes: Vec<_> = output.events().iter().map(ContractEvent::hash).collect();
+            let event_root_hash =
+                InMemoryAccumulator::<EventAccumulatorHasher>::from_leaves(&event_hashes)
+                    .root_hash();
+            ensure!(
+                event_root_hash == txn_info.event_root_hash(),
+                "The event root hash of an output doesn't match that carried on the \
+                     transaction info.",
+            );
+            ensure!(
+                output.gas_used() == txn_info.gas_used(),
+                "The gas used by an output ({}) doesn't match that carried on the \
+                     transaction info ({}).",
+                output.gas_used(),
+                txn_info.gas_used(),
+            );
+            ensure!(
+                output.status() == &TransactionStatus::Keep(txn_info.status().clone()),
+                "The status of an output ({:?}) doesn't match that carried on the \
+                     transaction info ({:?}).",
+                output.status(),
+                txn_info.status(),
+            );
+            Ok(())
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+        Ok(())
+    }
+}
+
 /// A list of transactions under an account that are contiguous by sequence number
 /// and include proofs.
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
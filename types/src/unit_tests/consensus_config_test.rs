@@ -0,0 +1,33 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::on_chain_config::{
+    LeaderReputationHeuristic, LeaderReputationType, ProposerElectionType,
+};
+
+fn leader_reputation(active_weight: u64, inactive_weight: u64) -> ProposerElectionType {
+    ProposerElectionType::LeaderReputation(LeaderReputationType {
+        heuristic: LeaderReputationHeuristic::ProposerAndVoter,
+        active_weight,
+        inactive_weight,
+        exclude_round: 20,
+        enable_from_epoch: 0,
+    })
+}
+
+#[test]
+fn test_validate_proposer_election_type() {
+    assert!(ProposerElectionType::RotatingProposer(1).validate().is_ok());
+    assert!(ProposerElectionType::FixedProposer(2).validate().is_ok());
+    assert!(leader_reputation(99, 1).validate().is_ok());
+
+    // no proposer would ever be active
+    assert!(ProposerElectionType::RotatingProposer(0)
+        .validate()
+        .is_err());
+    assert!(ProposerElectionType::FixedProposer(0).validate().is_err());
+    // the weights of all the proposers could be zero
+    assert!(leader_reputation(0, 0).validate().is_err());
+    assert!(leader_reputation(99, 0).validate().is_err());
+    assert!(leader_reputation(0, 1).validate().is_err());
+}
@@ -6,9 +6,9 @@ use crate::{
     account_config::XUS_NAME,
     chain_id::ChainId,
     transaction::{
-        metadata, AccountTransactionsWithProof, GovernanceRole, RawTransaction, Script,
-        SignedTransaction, Transaction, TransactionInfo, TransactionListWithProof,
-        TransactionPayload, TransactionWithProof,
+        metadata, AccountTransactionsWithProof, GovernanceRole, RawTransaction,
+        RawTransactionWithData, Script, SignedTransaction, Transaction, TransactionInfo,
+        TransactionListWithProof, TransactionPayload, TransactionWithProof,
     },
 };
 use bcs::test_helpers::assert_canonical_encode_decode;
@@ -90,6 +90,39 @@ proptest! {
         assert!(signed_txn.check_signature().is_ok());
     }
 
+    #[test]
+    fn test_sign_multi_agent_separately(
+        raw_txn in any::<RawTransaction>(),
+        secondary_signer in any::<AccountAddress>(),
+        sender_keypair in ed25519::keypair_strategy(),
+        secondary_keypair in ed25519::keypair_strategy(),
+    ) {
+        // The signers sign the same message on their own, then the signatures are put together
+        let message = RawTransactionWithData::new_multi_agent(raw_txn.clone(), vec![secondary_signer]);
+        let sender_authenticator = message.sign(&sender_keypair.private_key);
+        let secondary_authenticator = message.sign(&secondary_keypair.private_key);
+        let signed_txn = message
+            .clone()
+            .into_signed_transaction(sender_authenticator.clone(), vec![secondary_authenticator])
+            .unwrap();
+        prop_assert!(signed_txn.is_multi_agent());
+        prop_assert!(signed_txn.check_signature().is_ok());
+
+        // Signing all at once gives the same transaction
+        let expected_txn = raw_txn
+            .sign_multi_agent(
+                &sender_keypair.private_key,
+                vec![secondary_signer],
+                vec![&secondary_keypair.private_key],
+            )
+            .unwrap()
+            .into_inner();
+        prop_assert_eq!(&signed_txn, &expected_txn);
+
+        // Every secondary signer has to sign
+        prop_assert!(message.into_signed_transaction(sender_authenticator, vec![]).is_err());
+    }
+
     #[test]
     fn transaction_payload_bcs_roundtrip(txn_payload in any::<TransactionPayload>()) {
         assert_canonical_encode_decode(txn_payload);
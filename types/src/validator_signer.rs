@@ -3,6 +3,7 @@
 
 use crate::account_address::AccountAddress;
 use diem_crypto::{
+    ecvrf::{VRFPrivateKey, VRFProof},
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
     hash::CryptoHash,
     test_utils::TEST_SEED,
@@ -35,6 +36,12 @@ impl ValidatorSigner {
         self.private_key.sign(message)
     }
 
+    /// Proves the VRF output of `private_key` on `alpha`, the VRF key sharing the secret of the
+    /// signing key.
+    pub fn prove_vrf(&self, alpha: &[u8]) -> VRFProof {
+        VRFPrivateKey::from(&self.private_key).prove(alpha)
+    }
+
     /// Returns the author associated with this signer.
     pub fn author(&self) -> AccountAddress {
         self.author
@@ -47,7 +47,9 @@ impl TransactionValidation for MockVMValidator {
         let seq_number_too_old_test_add = AccountAddress::new([4_u8; AccountAddress::LENGTH]);
         let txn_expiration_time_test_add = AccountAddress::new([5_u8; AccountAddress::LENGTH]);
         let invalid_auth_key_test_add = AccountAddress::new([6_u8; AccountAddress::LENGTH]);
-        let ret = if sender == account_dne_test_add {
+        let ret = if txn.contains_duplicate_signers() {
+            Some(StatusCode::SIGNERS_CONTAIN_DUPLICATES)
+        } else if sender == account_dne_test_add {
             Some(StatusCode::SENDING_ACCOUNT_DOES_NOT_EXIST)
         } else if sender == invalid_sig_test_add {
             Some(StatusCode::INVALID_SIGNATURE)
@@ -1,7 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::vm_validator::{TransactionValidation, VMValidator};
+use crate::vm_validator::{
+    get_account_sequence_number, TransactionValidation, VMValidator,
+    GAS_ESTIMATE_SAFETY_MARGIN_PERCENT, MAX_SIMULATION_GAS_AMOUNT,
+};
 use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
 use diem_transaction_builder::stdlib::encode_peer_to_peer_with_metadata_script;
 use diem_types::{
@@ -90,6 +93,47 @@ fn test_validate_transaction() {
     assert_eq!(ret.status(), None);
 }
 
+#[test]
+fn test_validate_multi_agent_transaction() {
+    let vm_validator = TestValidator::new();
+
+    // Diem root and treasury compliance share the genesis key in tests
+    let transaction = transaction_test_helpers::get_test_unchecked_multi_agent_txn(
+        account_config::diem_root_address(),
+        vec![account_config::treasury_compliance_account_address()],
+        1,
+        &vm_genesis::GENESIS_KEYPAIR.0,
+        vm_genesis::GENESIS_KEYPAIR.1.clone(),
+        vec![&vm_genesis::GENESIS_KEYPAIR.0],
+        vec![vm_genesis::GENESIS_KEYPAIR.1.clone()],
+        None,
+    );
+    let ret = vm_validator.validate_transaction(transaction).unwrap();
+    assert_eq!(ret.status(), None);
+}
+
+#[test]
+fn test_validate_multi_agent_duplicate_signers() {
+    let vm_validator = TestValidator::new();
+
+    let address = account_config::diem_root_address();
+    let transaction = transaction_test_helpers::get_test_unchecked_multi_agent_txn(
+        address,
+        vec![address],
+        1,
+        &vm_genesis::GENESIS_KEYPAIR.0,
+        vm_genesis::GENESIS_KEYPAIR.1.clone(),
+        vec![&vm_genesis::GENESIS_KEYPAIR.0],
+        vec![vm_genesis::GENESIS_KEYPAIR.1.clone()],
+        None,
+    );
+    let ret = vm_validator.validate_transaction(transaction).unwrap();
+    assert_eq!(
+        ret.status().unwrap(),
+        StatusCode::SIGNERS_CONTAIN_DUPLICATES
+    );
+}
+
 #[test]
 fn test_validate_invalid_signature() {
     let vm_validator = TestValidator::new();
@@ -500,3 +544,55 @@ fn test_validate_gas_currency_code() {
         StatusCode::CURRENCY_INFO_DOES_NOT_EXIST
     );
 }
+
+#[test]
+fn test_estimate_gas() {
+    let vm_validator = TestValidator::new();
+
+    let address = account_config::diem_root_address();
+    let sequence_number = get_account_sequence_number(&*vm_validator.db_reader, address)
+        .unwrap()
+        .min_seq();
+    let program = encode_peer_to_peer_with_metadata_script(xus_tag(), address, 100, vec![], vec![]);
+    // The max gas amount of the transaction is too low to execute it, but doesn't limit the
+    // estimate
+    let transaction = transaction_test_helpers::get_test_signed_transaction(
+        address,
+        sequence_number,
+        &vm_genesis::GENESIS_KEYPAIR.0,
+        vm_genesis::GENESIS_KEYPAIR.1.clone(),
+        Some(TransactionPayload::Script(program)),
+        u64::MAX, /* expiration_time */
+        0,        /* gas_unit_price */
+        XUS_NAME.to_owned(),
+        Some(1), /* max_gas_amount */
+    );
+    let estimate = vm_validator.estimate_gas(transaction).unwrap();
+    assert!(estimate.gas_used > 1);
+    assert!(
+        estimate.suggested_max_gas_amount
+            >= estimate.gas_used * (100 + GAS_ESTIMATE_SAFETY_MARGIN_PERCENT) / 100
+    );
+    assert!(
+        estimate.suggested_max_gas_amount
+            <= GasConstants::default()
+                .maximum_number_of_gas_units
+                .get()
+                .min(MAX_SIMULATION_GAS_AMOUNT)
+    );
+}
+
+#[test]
+fn test_estimate_gas_of_discarded_transaction() {
+    let vm_validator = TestValidator::new();
+
+    let address = account_config::diem_root_address();
+    let transaction = transaction_test_helpers::get_test_txn_with_chain_id(
+        address,
+        0, /* sequence_number */
+        &vm_genesis::GENESIS_KEYPAIR.0,
+        vm_genesis::GENESIS_KEYPAIR.1.clone(),
+        ChainId::new(ChainId::test().id() + 1),
+    );
+    assert!(vm_validator.estimate_gas(transaction).is_err());
+}
@@ -3,16 +3,21 @@
 
 use anyhow::Result;
 use diem_scratchpad::SparseMerkleTree;
-use diem_state_view::StateViewId;
+use diem_state_view::{StateView, StateViewId};
 use diem_types::{
     account_address::AccountAddress,
     account_config::{AccountResource, AccountSequenceInfo},
     account_state::AccountState,
     on_chain_config::{DiemVersion, OnChainConfigPayload, VMConfig, VMPublishingOption},
-    transaction::{SignedTransaction, VMValidatorResult},
+    transaction::{
+        authenticator::TransactionAuthenticator, RawTransaction, SignedTransaction,
+        TransactionOutput, TransactionStatus, VMValidatorResult,
+    },
+    vm_status::{KeptVMStatus, StatusCode, VMStatus},
 };
-use diem_vm::DiemVM;
+use diem_vm::{logging::AdapterLogSchema, DiemVM};
 use fail::fail_point;
+use move_core_types::gas_schedule::GasAlgebra;
 use std::{convert::TryFrom, sync::Arc};
 use storage_interface::{state_view::VerifiedStateView, DbReader};
 
@@ -53,6 +58,154 @@ impl VMValidator {
     }
 }
 
+/// Margin of the suggested max gas amount of a transaction over the gas it used when measured, in
+/// percent, for the changes of the state until it executes
+pub const GAS_ESTIMATE_SAFETY_MARGIN_PERCENT: u64 = 20;
+
+/// The largest max gas amount of the transactions simulated for clients, which bounds the time a
+/// simulation takes
+pub const MAX_SIMULATION_GAS_AMOUNT: u64 = 1_000_000;
+
+/// The gas a transaction uses, measured against the state
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GasEstimate {
+    pub gas_used: u64,
+    /// The gas used with a safety margin, capped by the max gas amount of a transaction
+    pub suggested_max_gas_amount: u64,
+}
+
+impl GasEstimate {
+    fn new(gas_used: u64, max_gas_amount: u64) -> Self {
+        let margin = (gas_used * GAS_ESTIMATE_SAFETY_MARGIN_PERCENT + 99) / 100;
+        Self {
+            gas_used,
+            suggested_max_gas_amount: gas_used.saturating_add(margin).min(max_gas_amount),
+        }
+    }
+}
+
+impl VMValidator {
+    /// Measures the gas the transaction uses against the latest state, see `estimate_gas`
+    pub fn estimate_gas(&self, txn: SignedTransaction) -> Result<GasEstimate> {
+        let (version, state_root) = self.db_reader.get_latest_state_root()?;
+        let smt = SparseMerkleTree::new(state_root);
+        let state_view = VerifiedStateView::new(
+            StateViewId::TransactionValidation {
+                base_version: version,
+            },
+            Arc::clone(&self.db_reader),
+            Some(version),
+            state_root,
+            &smt,
+        );
+        Ok(estimate_gas(txn, &state_view)?)
+    }
+}
+
+/// The max gas amount and the gas unit price transactions are measured with: the max gas amount of
+/// a transaction, capped by `MAX_SIMULATION_GAS_AMOUNT`, and the min price of a gas unit
+fn measure_gas_parameters<S: StateView>(state_view: &S) -> Result<(u64, u64), VMStatus> {
+    let vm = DiemVM::new(state_view);
+    let log_context = AdapterLogSchema::new(state_view.id(), 0);
+    let gas_constants = &vm.internals().gas_schedule(&log_context)?.gas_constants;
+    Ok((
+        gas_constants
+            .maximum_number_of_gas_units
+            .get()
+            .min(MAX_SIMULATION_GAS_AMOUNT),
+        gas_constants.min_price_per_gas_unit.get(),
+    ))
+}
+
+/// Measures the gas the transaction uses against `state_view`. The transaction is executed with
+/// the gas parameters of `measure_gas_parameters`, so its own max gas amount doesn't limit the
+/// measure, and without checking its signature, which the new gas parameters invalidate. Returns
+/// the status of the transactions the VM discards.
+pub fn estimate_gas<S: StateView>(
+    txn: SignedTransaction,
+    state_view: &S,
+) -> Result<GasEstimate, VMStatus> {
+    let (max_gas_amount, gas_unit_price) = measure_gas_parameters(state_view)?;
+    let txn = with_gas_parameters(txn, max_gas_amount, gas_unit_price)?;
+    let (_vm_status, output) = DiemVM::simulate_signed_transaction(txn, true, state_view);
+    match output.status() {
+        TransactionStatus::Keep(_) => Ok(GasEstimate::new(output.gas_used(), max_gas_amount)),
+        TransactionStatus::Discard(status_code) => Err(VMStatus::Error(*status_code)),
+        TransactionStatus::Retry => Err(VMStatus::Error(StatusCode::UNKNOWN_STATUS)),
+    }
+}
+
+/// Simulates the transaction against `state_view`, and measures the gas it uses. The gas a
+/// transaction uses doesn't depend on its own gas parameters unless they stop it, so it is only
+/// measured again, with `estimate_gas`, when the simulation runs out of gas or is discarded.
+pub fn simulate_and_estimate_gas<S: StateView>(
+    txn: SignedTransaction,
+    skip_signature_check: bool,
+    state_view: &S,
+) -> (TransactionOutput, Option<GasEstimate>) {
+    let (_vm_status, output) =
+        DiemVM::simulate_signed_transaction(txn.clone(), skip_signature_check, state_view);
+    let estimate = match output.status() {
+        TransactionStatus::Keep(KeptVMStatus::OutOfGas) | TransactionStatus::Discard(_) => {
+            estimate_gas(txn, state_view).ok()
+        }
+        TransactionStatus::Keep(_) => measure_gas_parameters(state_view)
+            .ok()
+            .map(|(max_gas_amount, _)| GasEstimate::new(output.gas_used(), max_gas_amount)),
+        TransactionStatus::Retry => None,
+    };
+    (output, estimate)
+}
+
+/// Replaces the gas parameters of the transaction, which invalidates its signature: the
+/// transaction may only be simulated without checking it afterwards.
+fn with_gas_parameters(
+    txn: SignedTransaction,
+    max_gas_amount: u64,
+    gas_unit_price: u64,
+) -> Result<SignedTransaction, VMStatus> {
+    let raw_txn = RawTransaction::new(
+        txn.sender(),
+        txn.sequence_number(),
+        txn.payload().clone(),
+        max_gas_amount,
+        gas_unit_price,
+        txn.gas_currency_code().to_owned(),
+        txn.expiration_timestamp_secs(),
+        txn.chain_id(),
+    );
+    Ok(match txn.authenticator() {
+        TransactionAuthenticator::Ed25519 {
+            public_key,
+            signature,
+        } => {
+            let public_key = public_key
+                .validate()
+                .map_err(|_| VMStatus::Error(StatusCode::INVALID_SIGNATURE))?
+                .clone();
+            SignedTransaction::new(raw_txn, public_key, signature)
+        }
+        TransactionAuthenticator::MultiEd25519 {
+            public_key,
+            signature,
+        } => SignedTransaction::new_multisig(raw_txn, public_key, signature),
+        TransactionAuthenticator::MultiAgent {
+            sender,
+            secondary_signer_addresses,
+            secondary_signers,
+        } => SignedTransaction::new_multi_agent(
+            raw_txn,
+            sender,
+            secondary_signer_addresses,
+            secondary_signers,
+        ),
+        TransactionAuthenticator::Secp256k1Ecdsa {
+            public_key,
+            signature,
+        } => SignedTransaction::new_secp256k1_ecdsa(raw_txn, public_key, signature),
+    })
+}
+
 impl TransactionValidation for VMValidator {
     type ValidationInstance = DiemVM;
 
@@ -117,3 +270,20 @@ pub fn get_account_sequence_number(
         None => Ok(AccountSequenceInfo::Sequential(0)),
     }
 }
+
+/// Returns the balance of the account in the given currency, 0 if the account doesn't exist or
+/// doesn't hold the currency.
+pub fn get_account_balance(
+    storage: &dyn DbReader,
+    address: AccountAddress,
+    currency_code: &str,
+) -> Result<u64> {
+    match storage.get_latest_account_state(address)? {
+        Some(blob) => Ok(AccountState::try_from(&blob)?
+            .get_balance_resources()?
+            .iter()
+            .find(|(code, _)| code.as_str() == currency_code)
+            .map_or(0, |(_, balance)| balance.coin())),
+        None => Ok(0),
+    }
+}